@@ -6,6 +6,9 @@
 
 use serde::{Deserialize, Serialize};
 
+mod test_spec;
+pub use test_spec::*;
+
 /// Represents the response from the server after a compilation request.
 /// It can be in one of three states:
 /// 1. InProgress: The compilation is still ongoing.
@@ -17,6 +20,9 @@ pub enum CompilationResponse {
     InProgress {
         /// Unique identifier for the compilation status request.
         id: String,
+        /// Place in the queue of the server from 1, 0 once the request is being built.
+        #[serde(default)]
+        position: usize,
     },
     /// The compilation has completed successfully.
     Done {
@@ -26,41 +32,186 @@ pub enum CompilationResponse {
 
         /// Disassembly data
         disassembler: String,
+
+        /// ELF file with the debug info, empty when the server does not provide it.
+        #[serde(default, with = "serde_bytes")]
+        elf: Vec<u8>,
+
+        /// Linker map, if asked for by `Artifacts::map` and made by the build.
+        #[serde(default)]
+        map: String,
+
+        /// ELF symbol table sorted by address, if asked for by `Artifacts::symbols`.
+        #[serde(default)]
+        symbols: Vec<ElfSymbol>,
     },
     /// An error occurred during the compilation process.
     Error { message: String },
 }
 
+/// Optional artifacts of a build, returned on top of the uf2, the disassembly and
+/// the ELF file.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Artifacts {
+    /// The map written by the linker, the pico-sdk and MicroPython builds have one.
+    pub map: bool,
+    /// The defined symbols of the ELF file.
+    pub symbols: bool,
+}
+
+/// Kind of an ELF symbol, from its type.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ElfSymbolKind {
+    Function,
+    Object,
+    Other,
+}
+
+/// A symbol of the ELF symbol table.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ElfSymbol {
+    pub name: String,
+    /// Address of the symbol, without the Thumb bit of the Arm functions.
+    pub address: u32,
+    /// Size in bytes, 0 when the symbol has none.
+    pub size: u32,
+    pub kind: ElfSymbolKind,
+}
+
+impl ElfSymbol {
+    /// Whether an address is inside the symbol, or is the symbol when it has no size
+    pub fn contains(&self, address: u32) -> bool {
+        match self.size {
+            0 => address == self.address,
+            size => address.wrapping_sub(self.address) < size,
+        }
+    }
+}
+
+/// Messages streamed by the server while it compiles a request, as the JSON data of
+/// the server-sent events of `/api/events/{id}`. The stream ends after `Finished`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CompilationEvent {
+    /// A line printed by a step of the build, on its stdout or stderr.
+    Log { line: String },
+    /// The outcome of the compilation, `Done` or `Error`.
+    Finished(CompilationResponse),
+}
+
 /// Supported programming languages for compilation.
-/// Currently, only C is supported.
+/// C, C++ and assembly are built with the pico-sdk, Rust with cargo and
+/// MicroPython scripts are frozen into the MicroPython firmware.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Language {
     #[serde(rename = "c")]
     C,
+    #[serde(rename = "cpp")]
+    Cpp,
+    #[serde(rename = "rust")]
+    Rust,
+    /// Assembly of the target, with a `main` label called by the pico-sdk runtime
+    #[serde(rename = "asm")]
+    Assembly,
+    #[serde(rename = "micropython")]
+    MicroPython,
 }
 
-/// Supported compilation target architectures.
-/// Currently, only RISC-V is supported.
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Language::C,
+        Language::Cpp,
+        Language::Rust,
+        Language::Assembly,
+        Language::MicroPython,
+    ];
+
+    /// Name of the single source file of a program
+    pub fn filename(self) -> &'static str {
+        match self {
+            Language::C => "main.c",
+            Language::Cpp => "main.cpp",
+            Language::Rust => "main.rs",
+            Language::Assembly => "main.S",
+            Language::MicroPython => "main.py",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::C => "C",
+            Language::Cpp => "C++",
+            Language::Rust => "Rust",
+            Language::Assembly => "Assembly",
+            Language::MicroPython => "MicroPython",
+        }
+    }
+}
+
+/// Supported compilation target architectures, the two cores of the RP2350.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Target {
+    /// Hazard3
     #[serde(rename = "riscv")]
     RiscV,
+    /// Cortex-M33, in the secure state
+    #[serde(rename = "arm")]
+    Arm,
+}
+
+impl Target {
+    pub const ALL: [Target; 2] = [Target::RiscV, Target::Arm];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::RiscV => "RISC-V",
+            Target::Arm => "Arm",
+        }
+    }
 }
 
 /// Represents the source code to be compiled.
 /// It includes the filename and the actual code content.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The filename is a path relative to the project, e.g. `drivers/led.h`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SourceCode {
     pub filename: String,
     pub code: String,
 }
 
+impl SourceCode {
+    /// Check that the filename stays in the project: relative, without `.` or `..`
+    /// and made of letters, digits, `_`, `-` and `.` only
+    pub fn is_valid_path(&self) -> bool {
+        self.filename.split('/').all(|part| {
+            !part.is_empty()
+                && part != "."
+                && part != ".."
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        })
+    }
+
+    /// Extension of the file, empty if it has none
+    pub fn extension(&self) -> &str {
+        let name = self.filename.rsplit('/').next().unwrap_or_default();
+        name.rsplit_once('.')
+            .map(|(_, ext)| ext)
+            .unwrap_or_default()
+    }
+}
+
 /// Represents a request to compile source code.
 /// It includes the programming language, source code, target architecture,
 /// and optional compiler options.
-/// The source code is provided as a vector of `SourceCode` structs.
+/// The source code is provided as a vector of `SourceCode` structs, the files of
+/// the project. One of them is the entry point named by `Language::filename`,
+/// unless the project brings its own CMakeLists.txt.
 /// The `compiler_options` field is optional and can be used to specify
 /// additional compilation flags or settings.
+/// The `libraries` are linked to the pico-sdk projects, the server tells the
+/// ones it allows through `/api/libraries`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompilationRequest {
     /// The programming language of the source code.
@@ -71,6 +222,12 @@ pub struct CompilationRequest {
     pub target: Target,
     /// Optional compiler options.
     pub compiler_options: Option<String>,
+    /// Libraries linked on top of the default ones, e.g. `hardware_adc` or `FreeRTOS`.
+    #[serde(default)]
+    pub libraries: Vec<String>,
+    /// Artifacts returned on top of the default ones.
+    #[serde(default)]
+    pub artifacts: Artifacts,
 }
 
 /// Represents a request to check the status of a compilation.
@@ -81,3 +238,26 @@ pub struct CompilationStatusRequest {
     /// Unique identifier for the compilation request.
     pub id: String,
 }
+
+/// A workspace saved on the server to be shared by a link, through `/api/project`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedProject {
+    pub language: Language,
+    pub target: Target,
+    /// Files of the project, as in `CompilationRequest::source`.
+    pub files: Vec<SourceCode>,
+    #[serde(default)]
+    pub libraries: Vec<String>,
+    /// Components and wires of the breadboard, as saved by the web app.
+    #[serde(default)]
+    pub board: String,
+}
+
+/// Represents the response from the server after a project is saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProjectResponse {
+    /// The project is stored under a short ID, loaded from `/api/project/{id}`.
+    Saved { id: String },
+    /// The project could not be saved, e.g. it is too large.
+    Error { message: String },
+}
@@ -0,0 +1,254 @@
+//! pico2
+//! Author: Nguyen Le Duy
+//! Date: 15/10/2026
+//! Description: Test specification of a project, the debugging setup and the
+//! acceptance checks stored next to the code, shared by the web UI and the CLI runner.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Version of the files written by this build, older versions are still read.
+pub const TEST_SPEC_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum TestSpecError {
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for TestSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(why) => write!(f, "Invalid test spec: {why}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported test spec version {version}, the latest is {TEST_SPEC_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TestSpecError {}
+
+impl From<serde_json::Error> for TestSpecError {
+    fn from(why: serde_json::Error) -> Self {
+        Self::Json(why)
+    }
+}
+
+/// What happens when the execution reaches a breakpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakpointAction {
+    #[default]
+    Pause,
+    Snapshot,
+    SnapshotAndPause,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub address: u32,
+    #[serde(default)]
+    pub action: BreakpointAction,
+}
+
+/// Text the firmware has to print, or must never print, on the UARTs and the USB serial.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UartAssertions {
+    #[serde(default)]
+    pub expect: Vec<String>,
+    #[serde(default)]
+    pub fail_on: Vec<String>,
+}
+
+/// Level of a pin at a point of the simulated time, or at the end of the run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpioAssertion {
+    pub pin: u8,
+    pub high: bool,
+    #[serde(default)]
+    pub at_us: Option<u64>,
+}
+
+/// The most cycles a single execution of a region may take.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CycleBudgetSpec {
+    /// `<start>..<end>` in hex, or the name of a function
+    pub region: String,
+    pub max_cycles: u64,
+}
+
+/// A word written to a peripheral register, or to the memory, by its absolute address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterWrite {
+    pub address: u32,
+    pub value: u32,
+}
+
+/// A register write which is not `ADDRESS=VALUE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRegisterWrite(pub String);
+
+impl fmt::Display for InvalidRegisterWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(text) = self;
+        write!(f, "Invalid register write `{text}`, expected ADDRESS=VALUE")
+    }
+}
+
+impl std::error::Error for InvalidRegisterWrite {}
+
+impl FromStr for RegisterWrite {
+    type Err = InvalidRegisterWrite;
+
+    /// `ADDRESS=VALUE`, the numbers are in hex with a `0x` prefix and decimal otherwise
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let number = |text: &str| {
+            let text = text.trim().replace('_', "");
+            match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => text.parse().ok(),
+            }
+        };
+
+        text.split_once('=')
+            .and_then(|(address, value)| {
+                Some(Self {
+                    address: number(address)?,
+                    value: number(value)?,
+                })
+            })
+            .ok_or_else(|| InvalidRegisterWrite(text.to_string()))
+    }
+}
+
+impl fmt::Display for RegisterWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#010x}={:#010x}", self.address, self.value)
+    }
+}
+
+/// Named list of register writes applied at once, to try a peripheral out without
+/// any firmware, like "PWM slice 0 at 1kHz 50%".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterPreset {
+    pub name: String,
+    /// Applied in order
+    pub writes: Vec<RegisterWrite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestSpec {
+    pub version: u32,
+    #[serde(default)]
+    pub breakpoints: Vec<Breakpoint>,
+    /// Names of the watched variables
+    #[serde(default)]
+    pub watches: Vec<String>,
+    #[serde(default)]
+    pub uart: UartAssertions,
+    #[serde(default)]
+    pub gpio: Vec<GpioAssertion>,
+    #[serde(default)]
+    pub budgets: Vec<CycleBudgetSpec>,
+    #[serde(default)]
+    pub presets: Vec<RegisterPreset>,
+}
+
+impl Default for TestSpec {
+    fn default() -> Self {
+        Self {
+            version: TEST_SPEC_VERSION,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            uart: UartAssertions::default(),
+            gpio: Vec::new(),
+            budgets: Vec::new(),
+            presets: Vec::new(),
+        }
+    }
+}
+
+impl TestSpec {
+    pub fn from_json(text: &str) -> Result<Self, TestSpecError> {
+        let spec: Self = serde_json::from_str(text)?;
+
+        if spec.version == 0 || spec.version > TEST_SPEC_VERSION {
+            return Err(TestSpecError::UnsupportedVersion(spec.version));
+        }
+
+        Ok(Self {
+            version: TEST_SPEC_VERSION,
+            ..spec
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a test spec is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let spec = TestSpec {
+            breakpoints: vec![Breakpoint {
+                address: 0x1000_0100,
+                action: BreakpointAction::SnapshotAndPause,
+            }],
+            watches: vec!["counter".to_string()],
+            uart: UartAssertions {
+                expect: vec!["PASS".to_string()],
+                fail_on: vec!["panic".to_string()],
+            },
+            gpio: vec![GpioAssertion {
+                pin: 25,
+                high: true,
+                at_us: Some(1000),
+            }],
+            budgets: vec![CycleBudgetSpec {
+                region: "isr_handler".to_string(),
+                max_cycles: 500,
+            }],
+            presets: vec![RegisterPreset {
+                name: "PWM 0 at 1kHz".to_string(),
+                writes: vec![RegisterWrite {
+                    address: 0x400a_8000,
+                    value: 1,
+                }],
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(TestSpec::from_json(&spec.to_json()).unwrap(), spec);
+    }
+
+    #[test]
+    fn test_version() {
+        let spec = TestSpec::from_json(r#"{ "version": 1, "watches": ["ticks"] }"#).unwrap();
+        assert_eq!(spec.watches, ["ticks"]);
+        assert!(spec.breakpoints.is_empty());
+
+        assert!(matches!(
+            TestSpec::from_json(r#"{ "version": 2 }"#),
+            Err(TestSpecError::UnsupportedVersion(2))
+        ));
+        assert!(TestSpec::from_json(r#"{ "watches": [] }"#).is_err());
+    }
+
+    #[test]
+    fn test_register_write() {
+        let write: RegisterWrite = "0x400a_8010 = 1000".parse().unwrap();
+        assert_eq!(write.address, 0x400a_8010);
+        assert_eq!(write.value, 1000);
+        assert_eq!(write.to_string().parse(), Ok(write));
+
+        assert!("0x400a8010".parse::<RegisterWrite>().is_err());
+        assert!("0x400a8010=0xg".parse::<RegisterWrite>().is_err());
+    }
+}
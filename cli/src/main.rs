@@ -0,0 +1,353 @@
+/**
+ * @file main.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Headless runner of the simulator, for a single run or a matrix of configurations
+ */
+use anyhow::{Context, bail};
+use api_types::TestSpec;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::rc::Rc;
+
+mod matrix;
+mod run;
+mod soak;
+
+use rp2350::Inspector;
+use rp2350::peripherals::UartLink;
+use rp2350::processor::InterruptLatency;
+use rp2350::script::{self, Harness, UartCapture};
+use run::{Architecture, RunConfig, Status};
+use soak::SoakOptions;
+
+const USAGE: &str = "\
+Usage:
+    pico2-cli run <firmware> [options]
+    pico2-cli matrix <matrix.json> [--jobs N] [--json FILE] [--csv FILE]
+    pico2-cli soak <firmware> [options of run] [options of soak]
+    pico2-cli gdb <firmware> [options of run] [--port N]
+    pico2-cli script <test.rhai> <firmware> [options of run]
+
+Options of run:
+    --clk-sys-mhz N     Frequency of the system clock, 150MHz by default
+    --seed N            Fill the SRAM with random content from the seed
+    --stimulus FILE     Drive the inputs of the chip from a stimulus file
+    --arch riscv|arm    Architecture of the cores
+    --skip-bootrom      Jump straight into the flashed program
+    --skip-busy-waits   Fast-forward the delay loops of the program
+    --skip-idle         Fast-forward the time both cores sleep through
+    --lax-csrs          Read 0 instead of trapping on the CSR accesses the spec forbids
+    --irq-latency E,X   Extra cycles of the interrupt entry and exit of the Hazard3
+    --uart-link MODE    Wire the UARTs to each other, null-modem or loopback
+    --symbols FILE      ELF file of the firmware, for the budgets naming a function
+    --budget SPEC       Fail when a run of START..END or a function takes more cycles than
+                        allowed, as `START..END:CYCLES` or `SYMBOL:CYCLES`, repeatable
+    --max-cycles N      Stop after N cycles, 100000000 by default
+    --expect TEXT       Pass once the UART output contains TEXT, repeatable
+    --fail-on TEXT      Fail as soon as the UART output contains TEXT, repeatable
+    --spec FILE         Add the UART, GPIO and cycle budget checks of a project test spec
+    --preset NAME       Apply a register preset of the test spec before the first cycle, repeatable
+    --json              Print the result as a single line of JSON
+    --json-summary FILE Write the counters of the run, the result of each pattern and budget
+
+Options of soak, which runs 1000000000 cycles by default and stops on the first broken invariant:
+    --check-interval N  Cycles between the checks of the stack pointers and the watchdog
+    --guard START..END  Fail on any store into the region, addresses in hex or symbols, repeatable
+    --allow-trap N      Cause of a trap the firmware takes on purpose, besides the system calls
+    --no-watchdog       Do not fail when the enabled watchdog is not fed in time
+    --trace-window N    Instructions kept before the failure, 256 by default
+    --report DIR        Write the report, the registers, the trace window and the SRAM on a failure
+The stacks of both cores are checked against the pico-sdk symbols when --symbols is given
+
+Options of gdb, which waits for `target remote :3333` of riscv32-unknown-elf-gdb:
+    --port N            Port of the local host to listen on, 3333 by default
+
+A script drives the board with set_pin, read_pin, expect_pin, advance_us, advance_ms,
+advance_cycles, time_us, expect_uart, uart_output, read_register and write_register,
+and fails on the first broken expectation or `throw`";
+
+/// Command line arguments, consumed from the front
+struct Args(std::vec::IntoIter<String>);
+
+impl Args {
+    fn value(&mut self, flag: &str) -> anyhow::Result<String> {
+        self.0
+            .next()
+            .with_context(|| format!("Missing value for {flag}"))
+    }
+
+    fn number(&mut self, flag: &str) -> anyhow::Result<u64> {
+        let value = self.value(flag)?;
+        value
+            .parse()
+            .with_context(|| format!("Invalid number `{value}` for {flag}"))
+    }
+}
+
+/// Options of a run, the ones `extra` takes are left to the command
+fn parse_run(
+    mut args: Args,
+    max_cycles: u64,
+    mut extra: impl FnMut(&str, &mut Args) -> anyhow::Result<bool>,
+) -> anyhow::Result<(RunConfig, bool, Option<PathBuf>)> {
+    let firmware = args.value("the firmware")?;
+    let mut config = RunConfig::new(PathBuf::from(firmware));
+    config.max_cycles = max_cycles;
+    let mut json = false;
+    let mut summary = None;
+
+    while let Some(flag) = args.0.next() {
+        match flag.as_str() {
+            "--clk-sys-mhz" => config.clk_sys_mhz = args.number(&flag)?,
+            "--seed" => config.seed = Some(args.number(&flag)?),
+            "--stimulus" => config.stimulus = Some(PathBuf::from(args.value(&flag)?)),
+            "--arch" => {
+                config.architecture = Some(match args.value(&flag)?.as_str() {
+                    "riscv" => Architecture::Riscv,
+                    "arm" => Architecture::Arm,
+                    other => bail!("Unknown architecture `{other}`"),
+                })
+            }
+            "--skip-bootrom" => config.skip_bootrom = true,
+            "--skip-busy-waits" => config.skip_busy_waits = true,
+            "--skip-idle" => config.skip_idle = true,
+            "--lax-csrs" => config.lax_csrs = true,
+            "--irq-latency" => {
+                let value = args.value(&flag)?;
+                let latency = value
+                    .split_once(',')
+                    .and_then(|(entry, exit)| Some((entry.parse().ok()?, exit.parse().ok()?)));
+
+                let Some((entry, exit)) = latency else {
+                    bail!("Invalid latency `{value}` for {flag}, expected ENTRY,EXIT");
+                };
+
+                config.interrupt_latency = InterruptLatency { entry, exit };
+            }
+            "--uart-link" => {
+                config.uart_link = match args.value(&flag)?.as_str() {
+                    "null-modem" => UartLink::NullModem,
+                    "loopback" => UartLink::Loopback,
+                    other => bail!("Unknown UART link `{other}`"),
+                }
+            }
+            "--symbols" => config.symbols = Some(PathBuf::from(args.value(&flag)?)),
+            "--budget" => config.budgets.push(args.value(&flag)?),
+            "--max-cycles" => config.max_cycles = args.number(&flag)?,
+            "--expect" => config.expect.push(args.value(&flag)?),
+            "--fail-on" => config.fail_on.push(args.value(&flag)?),
+            "--spec" => {
+                let path = PathBuf::from(args.value(&flag)?);
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Cannot read {}", path.display()))?;
+                let spec = TestSpec::from_json(&text)
+                    .with_context(|| format!("Invalid test spec {}", path.display()))?;
+                config.apply_spec(&spec);
+            }
+            "--preset" => config.apply_presets.push(args.value(&flag)?),
+            "--json" => json = true,
+            "--json-summary" => summary = Some(PathBuf::from(args.value(&flag)?)),
+            _ => {
+                if !extra(&flag, &mut args)? {
+                    bail!("Unknown option `{flag}`");
+                }
+            }
+        }
+    }
+
+    Ok((config, json, summary))
+}
+
+fn run_command(args: Args) -> anyhow::Result<bool> {
+    let (config, json, summary) = parse_run(args, run::DEFAULT_MAX_CYCLES, |_, _| Ok(false))?;
+    let run = run::run(&config);
+    let result = &run.result;
+
+    if let Some(path) = summary {
+        let summary = run::Summary::new(&config, &run);
+        std::fs::write(&path, serde_json::to_string_pretty(&summary)?)
+            .with_context(|| format!("Cannot write {}", path.display()))?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&result)?);
+    } else {
+        print!("{}", String::from_utf8_lossy(&run.output));
+        println!();
+        println!(
+            "{:?} after {} cycles in {} ms ({:.2} MHz)",
+            result.status, result.cycles, result.wall_time_ms, result.simulated_mhz
+        );
+
+        if let Some(message) = &result.message {
+            println!("{message}");
+        }
+    }
+
+    Ok(result.status == Status::Pass)
+}
+
+fn matrix_command(mut args: Args) -> anyhow::Result<bool> {
+    let path = PathBuf::from(args.value("the matrix file")?);
+    let mut jobs = std::thread::available_parallelism().map_or(1, |jobs| jobs.get());
+    let mut json = None;
+    let mut csv = None;
+
+    while let Some(flag) = args.0.next() {
+        match flag.as_str() {
+            "--jobs" => jobs = args.number(&flag)?.max(1) as usize,
+            "--json" => json = Some(PathBuf::from(args.value(&flag)?)),
+            "--csv" => csv = Some(PathBuf::from(args.value(&flag)?)),
+            _ => bail!("Unknown option `{flag}`"),
+        }
+    }
+
+    let matrix = matrix::Matrix::load(&path)?;
+    let report = matrix.run(jobs)?;
+
+    if let Some(json) = json {
+        std::fs::write(&json, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Cannot write {}", json.display()))?;
+    }
+
+    if let Some(csv) = csv {
+        std::fs::write(&csv, report.to_csv())
+            .with_context(|| format!("Cannot write {}", csv.display()))?;
+    }
+
+    println!(
+        "{} runs: {} passed, {} failed, {} timed out, {} errors in {} ms",
+        report.results.len(),
+        report.passed,
+        report.failed,
+        report.timed_out,
+        report.errors,
+        report.wall_time_ms
+    );
+
+    Ok(report.passed == report.results.len())
+}
+
+fn soak_command(args: Args) -> anyhow::Result<bool> {
+    let mut options = SoakOptions::default();
+
+    let (config, json, summary) = parse_run(args, soak::DEFAULT_SOAK_CYCLES, |flag, args| {
+        match flag {
+            "--check-interval" => options.check_interval = args.number(flag)?,
+            "--guard" => options.guards.push(args.value(flag)?),
+            "--allow-trap" => options.allowed_traps.push(args.number(flag)? as u32),
+            "--no-watchdog" => options.watchdog = false,
+            "--trace-window" => options.trace_window = args.number(flag)? as usize,
+            "--report" => options.report = Some(PathBuf::from(args.value(flag)?)),
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    })?;
+
+    if summary.is_some() {
+        bail!("--json-summary only applies to run, use --report");
+    }
+
+    let report = soak::soak(&config, &options)?;
+    let result = &report.result;
+
+    if json {
+        println!("{}", serde_json::to_string(&result)?);
+    } else {
+        println!(
+            "{} after {} cycles in {} ms ({:.2} MHz)",
+            if result.passed { "Pass" } else { "Fail" },
+            result.cycles,
+            result.wall_time_ms,
+            result.simulated_mhz
+        );
+
+        if let Some(violation) = &result.violation {
+            println!("{violation}");
+        }
+
+        for entry in report.trace.iter().rev().take(8).rev() {
+            println!(
+                "    core {} {:#010x}: {:08x} {}",
+                entry.core, entry.address, entry.instruction, entry.name
+            );
+        }
+    }
+
+    Ok(result.passed)
+}
+
+fn gdb_command(args: Args) -> anyhow::Result<bool> {
+    let mut port = debug::DEFAULT_PORT;
+
+    let (config, _, _) = parse_run(args, run::DEFAULT_MAX_CYCLES, |flag, args| {
+        match flag {
+            "--port" => port = u16::try_from(args.number(flag)?).context("Invalid port")?,
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    })?;
+
+    let mcu = run::build(&config, Vec::new())?;
+    let mut target = debug::GdbTarget::new(mcu);
+
+    eprintln!("Waiting for GDB on port {port}");
+    let stream = debug::listen(port).with_context(|| format!("Cannot listen on port {port}"))?;
+    debug::serve(stream, &mut target).context("Connection to GDB lost")?;
+
+    Ok(true)
+}
+
+fn script_command(mut args: Args) -> anyhow::Result<bool> {
+    let path = PathBuf::from(args.value("the script")?);
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    let (config, _, _) = parse_run(args, run::DEFAULT_MAX_CYCLES, |_, _| Ok(false))?;
+
+    let output = Rc::new(UartCapture::default());
+    let mcu = run::build(&config, vec![Rc::clone(&output) as Rc<dyn Inspector>])?;
+    let harness = Rc::new(RefCell::new(Harness::new(mcu, output)));
+
+    let result = script::run_script(&harness, &text);
+    let harness = harness.borrow();
+
+    match &result {
+        Ok(()) => println!("Pass after {} us", harness.time_us()),
+        Err(error) => {
+            print!("{}", harness.uart_output());
+            println!();
+            println!("Fail after {} us: {error}", harness.time_us());
+        }
+    }
+
+    Ok(result.is_ok())
+}
+
+fn main() -> ExitCode {
+    let mut args = Args(std::env::args().skip(1).collect::<Vec<_>>().into_iter());
+
+    let result = match args.0.next().as_deref() {
+        Some("run") => run_command(args),
+        Some("matrix") => matrix_command(args),
+        Some("soak") => soak_command(args),
+        Some("gdb") => gdb_command(args),
+        Some("script") => script_command(args),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,286 @@
+/**
+ * @file matrix.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Sweep a firmware over a matrix of configurations, each run in its own process
+ */
+use crate::run::{Architecture, DEFAULT_MAX_CYCLES, RunResult, Status};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Description of the sweep, every combination of the lists is run once
+///
+/// ```json
+/// {
+///     "firmware": "blink.uf2",
+///     "clk_sys_mhz": [48, 150],
+///     "seeds": [1, 2, 3],
+///     "stimulus": [null, "button.txt"],
+///     "expect": ["PASS"],
+///     "fail_on": ["FAIL"]
+/// }
+/// ```
+///
+/// Relative paths are resolved from the directory of the matrix file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Matrix {
+    pub firmware: PathBuf,
+    #[serde(default = "default_clk_sys")]
+    pub clk_sys_mhz: Vec<u64>,
+    /// Seeds of the SRAM content, an empty list keeps the SRAM zeroed
+    #[serde(default)]
+    pub seeds: Vec<u64>,
+    /// `null` runs without any stimulus
+    #[serde(default)]
+    pub stimulus: Vec<Option<PathBuf>>,
+    pub architecture: Option<Architecture>,
+    #[serde(default)]
+    pub skip_bootrom: bool,
+    #[serde(default = "default_max_cycles")]
+    pub max_cycles: u64,
+    #[serde(default)]
+    pub expect: Vec<String>,
+    #[serde(default)]
+    pub fail_on: Vec<String>,
+}
+
+fn default_clk_sys() -> Vec<u64> {
+    vec![150]
+}
+
+fn default_max_cycles() -> u64 {
+    DEFAULT_MAX_CYCLES
+}
+
+/// One combination of the matrix
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub clk_sys_mhz: u64,
+    pub seed: Option<u64>,
+    pub stimulus: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    #[serde(flatten)]
+    pub job: Job,
+    #[serde(flatten)]
+    pub result: RunResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub firmware: PathBuf,
+    pub passed: usize,
+    pub failed: usize,
+    pub timed_out: usize,
+    pub errors: usize,
+    pub total_cycles: u64,
+    pub wall_time_ms: u64,
+    pub results: Vec<Entry>,
+}
+
+impl Matrix {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        let mut matrix: Self = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid matrix {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        matrix.firmware = base.join(&matrix.firmware);
+
+        for stimulus in matrix.stimulus.iter_mut().flatten() {
+            *stimulus = base.join(&*stimulus);
+        }
+
+        Ok(matrix)
+    }
+
+    pub fn jobs(&self) -> Vec<Job> {
+        let seeds: Vec<Option<u64>> = match self.seeds.is_empty() {
+            true => vec![None],
+            false => self.seeds.iter().copied().map(Some).collect(),
+        };
+
+        let stimulus = match self.stimulus.is_empty() {
+            true => vec![None],
+            false => self.stimulus.clone(),
+        };
+
+        let mut jobs = Vec::new();
+
+        for &clk_sys_mhz in self.clk_sys_mhz.iter() {
+            for &seed in seeds.iter() {
+                for stimulus in stimulus.iter() {
+                    jobs.push(Job {
+                        clk_sys_mhz,
+                        seed,
+                        stimulus: stimulus.clone(),
+                    });
+                }
+            }
+        }
+
+        jobs
+    }
+
+    /// Run the job in a child process, so a crash of the simulator only fails that job
+    fn run_job(&self, executable: &Path, job: &Job) -> RunResult {
+        let mut command = Command::new(executable);
+        command
+            .arg("run")
+            .arg(&self.firmware)
+            .args(["--clk-sys-mhz", &job.clk_sys_mhz.to_string()])
+            .args(["--max-cycles", &self.max_cycles.to_string()])
+            .arg("--json");
+
+        if let Some(seed) = job.seed {
+            command.args(["--seed", &seed.to_string()]);
+        }
+
+        if let Some(stimulus) = &job.stimulus {
+            command.arg("--stimulus").arg(stimulus);
+        }
+
+        if let Some(architecture) = self.architecture {
+            command.args([
+                "--arch",
+                match architecture {
+                    Architecture::Riscv => "riscv",
+                    Architecture::Arm => "arm",
+                },
+            ]);
+        }
+
+        if self.skip_bootrom {
+            command.arg("--skip-bootrom");
+        }
+
+        for pattern in self.expect.iter() {
+            command.args(["--expect", pattern]);
+        }
+
+        for pattern in self.fail_on.iter() {
+            command.args(["--fail-on", pattern]);
+        }
+
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(error) => return RunResult::error(format!("Cannot start the run: {error}")),
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        match stdout.lines().last().map(serde_json::from_str::<RunResult>) {
+            Some(Ok(result)) => result,
+            _ => RunResult::error(format!(
+                "The run exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    pub fn run(&self, parallel: usize) -> anyhow::Result<Report> {
+        let executable = std::env::current_exe().context("Cannot find the runner executable")?;
+        let jobs = self.jobs();
+        let start = Instant::now();
+
+        let next = Mutex::new(0);
+        let results = Mutex::new(vec![None; jobs.len()]);
+
+        std::thread::scope(|scope| {
+            for _ in 0..parallel.min(jobs.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let index = {
+                            let mut next = next.lock().unwrap();
+                            *next += 1;
+                            *next - 1
+                        };
+
+                        let Some(job) = jobs.get(index) else {
+                            break;
+                        };
+
+                        let result = self.run_job(&executable, job);
+                        results.lock().unwrap()[index] = Some(result);
+                    }
+                });
+            }
+        });
+
+        let results = results.into_inner().unwrap();
+        let results: Vec<Entry> = jobs
+            .into_iter()
+            .zip(results)
+            .map(|(job, result)| Entry {
+                job,
+                result: result.unwrap_or_else(|| RunResult::error("Not run".to_string())),
+            })
+            .collect();
+
+        let count = |status: Status| {
+            results
+                .iter()
+                .filter(|entry| entry.result.status == status)
+                .count()
+        };
+
+        Ok(Report {
+            firmware: self.firmware.clone(),
+            passed: count(Status::Pass),
+            failed: count(Status::Fail),
+            timed_out: count(Status::Timeout),
+            errors: count(Status::Error),
+            total_cycles: results.iter().map(|entry| entry.result.cycles).sum(),
+            wall_time_ms: start.elapsed().as_millis() as u64,
+            results,
+        })
+    }
+}
+
+/// Quote a field only when it needs to
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+impl Report {
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "clk_sys_mhz,seed,stimulus,status,cycles,wall_time_ms,simulated_mhz,uart_bytes,message\n",
+        );
+
+        for Entry { job, result } in self.results.iter() {
+            let status = serde_json::to_value(result.status).unwrap_or_default();
+            let stimulus = job
+                .stimulus
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{:.3},{},{}\n",
+                job.clk_sys_mhz,
+                job.seed.map(|seed| seed.to_string()).unwrap_or_default(),
+                csv_field(&stimulus),
+                status.as_str().unwrap_or_default(),
+                result.cycles,
+                result.wall_time_ms,
+                result.simulated_mhz,
+                result.uart_bytes,
+                csv_field(result.message.as_deref().unwrap_or_default()),
+            ));
+        }
+
+        csv
+    }
+}
@@ -0,0 +1,580 @@
+/**
+ * @file run.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief A single headless run of a firmware until it passes, fails or runs out of cycles
+ */
+use anyhow::Context;
+use api_types::{GpioAssertion, RegisterPreset, TestSpec};
+use rp2350::budget::{BudgetChecker, BudgetReport, CycleBudget};
+use rp2350::common::{ArchitectureType, MHZ};
+use rp2350::device::Stimulus;
+use rp2350::elf::Elf;
+use rp2350::memory::InitPattern;
+use rp2350::peripherals::UartLink;
+use rp2350::processor::InterruptLatency;
+use rp2350::summary::{RunSummary, SummaryCollector};
+use rp2350::{InspectionEvent, Inspector, Rp2350};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+
+pub const DEFAULT_MAX_CYCLES: u64 = 100_000_000;
+
+/// The patterns are searched in the output every so often rather than on each cycle
+const CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Architecture {
+    Riscv,
+    Arm,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub firmware: PathBuf,
+    pub clk_sys_mhz: u64,
+    pub seed: Option<u64>,
+    pub stimulus: Option<PathBuf>,
+    pub architecture: Option<Architecture>,
+    pub skip_bootrom: bool,
+    pub skip_busy_waits: bool,
+    pub skip_idle: bool,
+    /// Read 0 from the CSRs the spec makes an illegal instruction, like older versions
+    pub lax_csrs: bool,
+    pub interrupt_latency: InterruptLatency,
+    pub uart_link: UartLink,
+    /// ELF file of the firmware, for the budgets naming a symbol
+    pub symbols: Option<PathBuf>,
+    /// `<start>..<end>:<cycles>` or `<symbol>:<cycles>`
+    pub budgets: Vec<String>,
+    pub max_cycles: u64,
+    pub expect: Vec<String>,
+    pub fail_on: Vec<String>,
+    pub gpio: Vec<GpioAssertion>,
+    /// Register presets of the test spec
+    pub presets: Vec<RegisterPreset>,
+    /// Names of the presets applied before the first cycle, in order
+    pub apply_presets: Vec<String>,
+}
+
+impl RunConfig {
+    pub fn new(firmware: PathBuf) -> Self {
+        Self {
+            firmware,
+            clk_sys_mhz: 150,
+            seed: None,
+            stimulus: None,
+            architecture: None,
+            skip_bootrom: false,
+            skip_busy_waits: false,
+            skip_idle: false,
+            lax_csrs: false,
+            interrupt_latency: InterruptLatency::default(),
+            uart_link: UartLink::None,
+            symbols: None,
+            budgets: Vec::new(),
+            max_cycles: DEFAULT_MAX_CYCLES,
+            expect: Vec::new(),
+            fail_on: Vec::new(),
+            gpio: Vec::new(),
+            presets: Vec::new(),
+            apply_presets: Vec::new(),
+        }
+    }
+
+    /// Add the checks of a test spec, the breakpoints and watches only matter to the web UI
+    pub fn apply_spec(&mut self, spec: &TestSpec) {
+        self.expect.extend(spec.uart.expect.iter().cloned());
+        self.fail_on.extend(spec.uart.fail_on.iter().cloned());
+        self.gpio.extend(spec.gpio.iter().cloned());
+        self.budgets.extend(
+            spec.budgets
+                .iter()
+                .map(|budget| format!("{}:{}", budget.region, budget.max_cycles)),
+        );
+        self.presets.extend(spec.presets.iter().cloned());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Every expected pattern was printed, or the cycles ran out without any expectation
+    Pass,
+    /// A failure pattern was printed before the expected ones, a pin had the wrong level
+    /// or a cycle budget was exceeded
+    Fail,
+    /// The cycles ran out before the expected patterns were printed
+    Timeout,
+    /// The run could not start, e.g. the firmware is missing
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    pub status: Status,
+    pub cycles: u64,
+    pub wall_time_ms: u64,
+    /// Simulated cycles per second of wall time, in MHz
+    pub simulated_mhz: f64,
+    pub uart_bytes: usize,
+    pub message: Option<String>,
+}
+
+impl RunResult {
+    pub fn error(message: String) -> Self {
+        Self {
+            status: Status::Error,
+            cycles: 0,
+            wall_time_ms: 0,
+            simulated_mhz: 0.0,
+            uart_bytes: 0,
+            message: Some(message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssertionKind {
+    Expect,
+    FailOn,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Assertion {
+    pub kind: AssertionKind,
+    pub pattern: String,
+    /// The pattern was printed by the firmware
+    pub found: bool,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetViolationReport {
+    pub core: u8,
+    pub entered_at: u64,
+    pub cycles: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetSummary {
+    pub name: String,
+    pub start: u32,
+    pub end: u32,
+    pub max_cycles: u64,
+    pub invocations: u64,
+    pub worst_cycles: u64,
+    pub violations: u64,
+    pub first_violations: Vec<BudgetViolationReport>,
+    pub passed: bool,
+}
+
+impl From<&BudgetReport> for BudgetSummary {
+    fn from(report: &BudgetReport) -> Self {
+        Self {
+            name: report.budget.name.clone(),
+            start: report.budget.start,
+            end: report.budget.end,
+            max_cycles: report.budget.max_cycles,
+            invocations: report.invocations,
+            worst_cycles: report.worst_cycles,
+            violations: report.violations,
+            first_violations: report
+                .first_violations
+                .iter()
+                .map(|violation| BudgetViolationReport {
+                    core: violation.core,
+                    entered_at: violation.entered_at,
+                    cycles: violation.cycles,
+                })
+                .collect(),
+            passed: report.passed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpioReport {
+    pub pin: u8,
+    pub high: bool,
+    pub at_us: Option<u64>,
+    /// Level of the pin when it was checked, none if the run ended before
+    pub level: Option<bool>,
+    pub passed: bool,
+}
+
+impl GpioReport {
+    fn new(assertion: &GpioAssertion, level: Option<bool>) -> Self {
+        Self {
+            pin: assertion.pin,
+            high: assertion.high,
+            at_us: assertion.at_us,
+            level,
+            passed: level == Some(assertion.high),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreReport {
+    pub core: usize,
+    pub instret: u64,
+    pub interrupts: u64,
+    pub branches: u64,
+    pub mispredicted_branches: u64,
+}
+
+/// Everything known about a run once it ended, the document of `--json-summary`
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    #[serde(flatten)]
+    pub result: RunResult,
+    pub cores: Vec<CoreReport>,
+    /// Bytes sent by UART0 and UART1
+    pub uart_tx: [u64; 2],
+    pub usb_tx: u64,
+    pub unimplemented_accesses: u64,
+    pub bus_errors: u64,
+    pub assertions: Vec<Assertion>,
+    pub gpio: Vec<GpioReport>,
+    pub budgets: Vec<BudgetSummary>,
+}
+
+impl Summary {
+    pub fn new(config: &RunConfig, run: &RunOutput) -> Self {
+        let counters = &run.counters;
+        let output = String::from_utf8_lossy(&run.output);
+
+        let expect = config
+            .expect
+            .iter()
+            .map(|pattern| (AssertionKind::Expect, pattern));
+        let fail_on = config
+            .fail_on
+            .iter()
+            .map(|pattern| (AssertionKind::FailOn, pattern));
+
+        let assertions = expect
+            .chain(fail_on)
+            .map(|(kind, pattern)| {
+                let found = output.contains(pattern.as_str());
+
+                Assertion {
+                    kind,
+                    pattern: pattern.clone(),
+                    found,
+                    passed: found == (kind == AssertionKind::Expect),
+                }
+            })
+            .collect();
+
+        let cores = counters
+            .cores
+            .iter()
+            .enumerate()
+            .map(|(core, counters)| CoreReport {
+                core,
+                instret: counters.instret,
+                interrupts: counters.interrupts,
+                branches: counters.branches,
+                mispredicted_branches: counters.mispredicted_branches,
+            })
+            .collect();
+
+        Self {
+            result: run.result.clone(),
+            cores,
+            uart_tx: counters.uart_tx,
+            usb_tx: counters.usb_tx,
+            unimplemented_accesses: counters.unimplemented_accesses,
+            bus_errors: counters.bus_errors,
+            assertions,
+            gpio: run.gpio.clone(),
+            budgets: run.budgets.iter().map(BudgetSummary::from).collect(),
+        }
+    }
+}
+
+/// Everything a run produced
+pub struct RunOutput {
+    pub result: RunResult,
+    /// What the firmware printed
+    pub output: Vec<u8>,
+    pub counters: RunSummary,
+    pub gpio: Vec<GpioReport>,
+    pub budgets: Vec<BudgetReport>,
+}
+
+impl RunOutput {
+    fn error(message: String) -> Self {
+        Self {
+            result: RunResult::error(message),
+            output: Vec::new(),
+            counters: RunSummary::default(),
+            gpio: Vec::new(),
+            budgets: Vec::new(),
+        }
+    }
+}
+
+/// Collect what the firmware prints on the UARTs and the USB serial
+#[derive(Default)]
+pub struct OutputCollector(RefCell<Vec<u8>>);
+
+impl OutputCollector {
+    pub fn take(&self) -> Vec<u8> {
+        self.0.take()
+    }
+}
+
+impl Inspector for OutputCollector {
+    fn handle_event(&self, event: InspectionEvent) {
+        match event {
+            InspectionEvent::UartTx { value, .. } => self.0.borrow_mut().push(value),
+            InspectionEvent::UsbCdcTx { data } => self.0.borrow_mut().extend(data),
+            _ => {}
+        }
+    }
+}
+
+/// Build the chip of the configuration, the inspectors see every event of the run
+pub fn build(config: &RunConfig, inspectors: Vec<Rc<dyn Inspector>>) -> anyhow::Result<Rp2350> {
+    let firmware = std::fs::read(&config.firmware)
+        .with_context(|| format!("Cannot read {}", config.firmware.display()))?;
+
+    let mut builder = Rp2350::builder()
+        .clk_sys(config.clk_sys_mhz * MHZ)
+        .skip_bootrom(config.skip_bootrom)
+        .skip_busy_waits(config.skip_busy_waits)
+        .skip_idle(config.skip_idle)
+        .strict_csrs(!config.lax_csrs)
+        .interrupt_latency(config.interrupt_latency)
+        .uart_link(config.uart_link);
+
+    for inspector in inspectors {
+        builder = builder.inspector(inspector);
+    }
+
+    builder = match extension(&config.firmware) {
+        "uf2" => builder.uf2(firmware),
+        "hex" | "ihex" => builder.hex(String::from_utf8_lossy(&firmware).into_owned()),
+        _ => builder.bin(firmware),
+    };
+
+    if let Some(seed) = config.seed {
+        builder = builder.sram_init(InitPattern::Random(seed));
+    }
+
+    if let Some(architecture) = config.architecture {
+        builder = builder.architecture(match architecture {
+            Architecture::Riscv => ArchitectureType::Hazard3,
+            Architecture::Arm => ArchitectureType::CortexM33,
+        });
+    }
+
+    if let Some(path) = &config.stimulus {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        let stimulus = Stimulus::parse(&text)
+            .with_context(|| format!("Invalid stimulus {}", path.display()))?;
+        builder = builder.device(stimulus);
+    }
+
+    let mut mcu = builder.build()?;
+
+    for name in &config.apply_presets {
+        let preset = config
+            .presets
+            .iter()
+            .find(|preset| &preset.name == name)
+            .with_context(|| format!("Unknown register preset `{name}`"))?;
+
+        let writes: Vec<_> = preset
+            .writes
+            .iter()
+            .map(|write| (write.address, write.value))
+            .collect();
+
+        mcu.apply_register_writes(&writes)
+            .with_context(|| format!("Cannot apply the register preset `{name}`"))?;
+    }
+
+    Ok(mcu)
+}
+
+/// The ELF file given for the symbols, if any
+pub fn symbols(config: &RunConfig) -> anyhow::Result<Option<Elf>> {
+    let Some(path) = &config.symbols else {
+        return Ok(None);
+    };
+
+    let data = std::fs::read(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let elf = Elf::parse(&data).with_context(|| format!("Invalid ELF {}", path.display()))?;
+    Ok(Some(elf))
+}
+
+fn budgets(config: &RunConfig) -> anyhow::Result<Vec<CycleBudget>> {
+    let elf = symbols(config)?;
+
+    let budgets = config
+        .budgets
+        .iter()
+        .map(|spec| CycleBudget::parse(spec, elf.as_ref()))
+        .collect::<Result<_, _>>()?;
+
+    Ok(budgets)
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+}
+
+/// Search the output for the patterns, returns the status once it is decided
+fn check(config: &RunConfig, output: &[u8]) -> Option<Status> {
+    let output = String::from_utf8_lossy(output);
+
+    if config
+        .fail_on
+        .iter()
+        .any(|pattern| output.contains(pattern))
+    {
+        return Some(Status::Fail);
+    }
+
+    let passed =
+        !config.expect.is_empty() && config.expect.iter().all(|pattern| output.contains(pattern));
+
+    passed.then_some(Status::Pass)
+}
+
+fn pin_level(mcu: &Rp2350, pin: u8) -> bool {
+    mcu.gpio.borrow().pad_level(pin)
+}
+
+/// Run the firmware until it passes, fails or runs out of cycles
+pub fn run(config: &RunConfig) -> RunOutput {
+    let output = Rc::new(OutputCollector::default());
+    let counters = Rc::new(SummaryCollector::new());
+
+    let mcu = budgets(config).and_then(|budgets| {
+        let budgets = Rc::new(BudgetChecker::new(budgets));
+        let mut inspectors: Vec<Rc<dyn Inspector>> = vec![output.clone(), counters.clone()];
+
+        if !budgets.is_empty() {
+            inspectors.push(budgets.clone());
+        }
+
+        let mcu = build(config, inspectors)?;
+        Ok((mcu, budgets))
+    });
+
+    let (mut mcu, budgets) = match mcu {
+        Ok(built) => built,
+        Err(error) => return RunOutput::error(format!("{error:#}")),
+    };
+
+    let start = Instant::now();
+    let start_ticks = *mcu.clock.ticks.borrow();
+    let mut steps = 0u64;
+    let mut cycles = 0;
+    let mut checked = 0;
+    let mut status = None;
+    let mut levels = vec![None; config.gpio.len()];
+
+    while cycles < config.max_cycles && status.is_none() {
+        mcu.tick();
+        steps += 1;
+        // a skipped delay loop or sleep moves the clock further than a single tick
+        cycles = (cycles + 1).max(*mcu.clock.ticks.borrow() - start_ticks);
+
+        for (assertion, level) in config.gpio.iter().zip(levels.iter_mut()) {
+            let due = assertion
+                .at_us
+                .is_some_and(|at_us| cycles >= at_us * config.clk_sys_mhz);
+
+            if level.is_none() && due {
+                *level = Some(pin_level(&mcu, assertion.pin));
+            }
+        }
+
+        if steps.is_multiple_of(CHECK_INTERVAL) {
+            let output = output.0.borrow();
+
+            if output.len() != checked {
+                checked = output.len();
+                status = check(config, &output);
+            }
+        }
+    }
+
+    let output = output.take();
+    let status =
+        status
+            .or_else(|| check(config, &output))
+            .unwrap_or(match config.expect.is_empty() {
+                true => Status::Pass,
+                false => Status::Timeout,
+            });
+
+    // the pins without a time are checked at the end of the run
+    let gpio: Vec<GpioReport> = config
+        .gpio
+        .iter()
+        .zip(levels)
+        .map(|(assertion, level)| match assertion.at_us {
+            None => GpioReport::new(assertion, Some(pin_level(&mcu, assertion.pin))),
+            Some(_) => GpioReport::new(assertion, level),
+        })
+        .collect();
+
+    let reports = budgets.reports();
+    let exceeded = reports.iter().find(|report| !report.passed());
+    let wrong_level = gpio.iter().find(|report| !report.passed);
+
+    let (status, message) = match (exceeded, wrong_level) {
+        (Some(report), _) if status == Status::Pass => (
+            Status::Fail,
+            Some(format!(
+                "`{}` took up to {} cycles, over its budget of {}",
+                report.budget.name, report.worst_cycles, report.budget.max_cycles
+            )),
+        ),
+        (_, Some(report)) if status == Status::Pass => (
+            Status::Fail,
+            Some(match report.level {
+                Some(_) => format!(
+                    "GPIO{} was {}",
+                    report.pin,
+                    if report.high { "low" } else { "high" }
+                ),
+                None => format!("GPIO{} was not checked before the end", report.pin),
+            }),
+        ),
+        _ => (status, None),
+    };
+
+    let elapsed = start.elapsed();
+    let result = RunResult {
+        status,
+        cycles,
+        wall_time_ms: elapsed.as_millis() as u64,
+        simulated_mhz: cycles as f64 / elapsed.as_secs_f64().max(1e-9) / MHZ as f64,
+        uart_bytes: output.len(),
+        message,
+    };
+
+    RunOutput {
+        result,
+        output,
+        counters: counters.summary(),
+        gpio,
+        budgets: reports,
+    }
+}
@@ -0,0 +1,239 @@
+/**
+ * @file soak.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Soak test, a long run of a firmware checking its invariants until one breaks
+ */
+use crate::run::{self, RunConfig};
+use anyhow::{Context, bail};
+use rp2350::common::MHZ;
+use rp2350::elf::Elf;
+use rp2350::soak::{SoakConfig, SoakFailure, SoakMonitor, StackBounds};
+use rp2350::{Inspector, ProgramSnapshot};
+use serde::Serialize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Instant;
+
+pub const DEFAULT_SOAK_CYCLES: u64 = 1_000_000_000;
+pub const DEFAULT_CHECK_INTERVAL: u64 = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct SoakOptions {
+    pub check_interval: u64,
+    /// `<start>..<end>`, each bound an address in hex or a symbol
+    pub guards: Vec<String>,
+    pub allowed_traps: Vec<u32>,
+    pub watchdog: bool,
+    pub trace_window: usize,
+    /// Directory the report and the SRAM are written to when an invariant breaks
+    pub report: Option<PathBuf>,
+}
+
+impl Default for SoakOptions {
+    fn default() -> Self {
+        let monitor = SoakConfig::default();
+
+        Self {
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            guards: Vec::new(),
+            allowed_traps: Vec::new(),
+            watchdog: monitor.watchdog,
+            trace_window: monitor.trace_window,
+            report: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakResult {
+    pub passed: bool,
+    pub cycles: u64,
+    pub wall_time_ms: u64,
+    /// Simulated cycles per second of wall time, in MHz
+    pub simulated_mhz: f64,
+    pub violation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreState {
+    pub core: usize,
+    pub pc: Option<u32>,
+    /// Only captured on the Hazard3
+    pub registers: Option<[u32; 32]>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceLine {
+    pub core: u8,
+    pub address: u32,
+    pub instruction: u32,
+    pub name: &'static str,
+}
+
+/// What is written to `report.json` when an invariant breaks, next to `sram.bin`
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    #[serde(flatten)]
+    pub result: SoakResult,
+    pub ticks: u64,
+    pub cores: Vec<CoreState>,
+    pub trace: Vec<TraceLine>,
+}
+
+impl SoakReport {
+    fn new(result: SoakResult, failure: &SoakFailure, snapshot: &ProgramSnapshot) -> Self {
+        let cores = (0..2)
+            .map(|core| CoreState {
+                core,
+                pc: snapshot.pc(core),
+                registers: snapshot.registers(core),
+            })
+            .collect();
+
+        let trace = failure
+            .trace
+            .iter()
+            .map(|entry| TraceLine {
+                core: entry.core,
+                address: entry.address,
+                instruction: entry.instruction,
+                name: entry.name,
+            })
+            .collect();
+
+        Self {
+            result,
+            ticks: snapshot.ticks,
+            cores,
+            trace,
+        }
+    }
+}
+
+/// Address in hex with an optional `0x`, or the address of a symbol
+fn address(text: &str, elf: Option<&Elf>) -> anyhow::Result<u32> {
+    let text = text.trim();
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+
+    if let Ok(address) = u32::from_str_radix(digits, 16) {
+        return Ok(address);
+    }
+
+    let Some(elf) = elf else {
+        bail!("`{text}` names a symbol, the ELF file of the program is needed");
+    };
+
+    let symbol = elf
+        .symbol(text)
+        .with_context(|| format!("Unknown symbol `{text}`"))?;
+    Ok(symbol.address)
+}
+
+fn guard(spec: &str, elf: Option<&Elf>) -> anyhow::Result<Range<u32>> {
+    let Some((start, end)) = spec.split_once("..") else {
+        bail!("Invalid guard `{spec}`, expected `<start>..<end>`");
+    };
+
+    Ok(address(start, elf)?..address(end, elf)?)
+}
+
+/// Stack of a core from the symbols of the pico-sdk linker scripts
+fn stack(elf: &Elf, bottom: &str, top: &str) -> Option<StackBounds> {
+    Some(StackBounds {
+        bottom: elf.symbol(bottom)?.address,
+        top: elf.symbol(top)?.address,
+    })
+}
+
+fn monitor(config: &RunConfig, options: &SoakOptions) -> anyhow::Result<SoakMonitor> {
+    let elf = run::symbols(config)?;
+    let mut monitor = SoakConfig::default();
+
+    monitor.allowed_traps.extend(&options.allowed_traps);
+    monitor.watchdog = options.watchdog;
+    monitor.trace_window = options.trace_window;
+    monitor.guards = options
+        .guards
+        .iter()
+        .map(|spec| guard(spec, elf.as_ref()))
+        .collect::<anyhow::Result<_>>()?;
+
+    if let Some(elf) = &elf {
+        monitor.stacks = [
+            stack(elf, "__StackBottom", "__StackTop"),
+            stack(elf, "__StackOneBottom", "__StackOneTop"),
+        ];
+    }
+
+    Ok(SoakMonitor::new(monitor))
+}
+
+fn write_report(dir: &Path, report: &SoakReport, snapshot: &ProgramSnapshot) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+
+    let path = dir.join("report.json");
+    std::fs::write(&path, serde_json::to_string_pretty(report)?)
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+
+    let path = dir.join("sram.bin");
+    std::fs::write(&path, snapshot.sram())
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Run the firmware until an invariant breaks or the cycles run out
+pub fn soak(config: &RunConfig, options: &SoakOptions) -> anyhow::Result<SoakReport> {
+    let monitor = Rc::new(monitor(config, options)?);
+    let mut mcu = run::build(config, vec![Rc::clone(&monitor) as Rc<dyn Inspector>])?;
+
+    let start = Instant::now();
+    let start_ticks = *mcu.clock.ticks.borrow();
+    let interval = options.check_interval.max(1);
+    let mut next_check = interval;
+    let mut cycles = 0;
+
+    while cycles < config.max_cycles && !monitor.has_failed() {
+        mcu.tick();
+        cycles = (cycles + 1).max(*mcu.clock.ticks.borrow() - start_ticks);
+
+        if cycles >= next_check {
+            monitor.check(&mcu);
+            next_check = cycles + interval;
+        }
+    }
+
+    let elapsed = start.elapsed();
+    let failure = monitor.failure();
+    let violation = failure
+        .as_ref()
+        .map(|failure| failure.violation.to_string());
+    let result = SoakResult {
+        passed: failure.is_none(),
+        cycles,
+        wall_time_ms: elapsed.as_millis() as u64,
+        simulated_mhz: cycles as f64 / elapsed.as_secs_f64().max(1e-9) / MHZ as f64,
+        violation,
+    };
+
+    let Some(failure) = failure else {
+        return Ok(SoakReport {
+            result,
+            ticks: *mcu.clock.ticks.borrow(),
+            cores: Vec::new(),
+            trace: Vec::new(),
+        });
+    };
+
+    // the run stopped on the cycle of the violation
+    let snapshot = mcu.snapshot();
+    let report = SoakReport::new(result, &failure, &snapshot);
+
+    if let Some(dir) = &options.report {
+        write_report(dir, &report, &snapshot)?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,12 @@
+/**
+ * @file lib.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Debugging the simulator with GDB over its remote serial protocol
+ */
+pub mod packet;
+pub mod server;
+pub mod target;
+
+pub use server::{DEFAULT_PORT, listen, serve};
+pub use target::{GdbTarget, Stop};
@@ -0,0 +1,181 @@
+/**
+ * @file packet.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Framing of the packets of the GDB remote serial protocol
+ */
+/// What came from GDB, one byte at a time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Packet(String),
+    Ack,
+    Nack,
+    /// Ctrl-C, GDB asks to stop the target while it runs
+    Interrupt,
+    /// A packet with the wrong checksum, GDB sends it again after a nack
+    Corrupted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Data,
+    Escape,
+    Checksum(Option<u8>),
+}
+
+/// Decoder of `$data#checksum` packets
+#[derive(Debug)]
+pub struct Decoder {
+    state: State,
+    data: Vec<u8>,
+    checksum: u8,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self {
+            state: State::Idle,
+            data: Vec::new(),
+            checksum: 0,
+        }
+    }
+}
+
+impl Decoder {
+    pub fn feed(&mut self, byte: u8) -> Option<Event> {
+        match self.state {
+            State::Idle => match byte {
+                b'$' => {
+                    self.state = State::Data;
+                    self.data.clear();
+                    self.checksum = 0;
+                    None
+                }
+                b'+' => Some(Event::Ack),
+                b'-' => Some(Event::Nack),
+                0x03 => Some(Event::Interrupt),
+                _ => None,
+            },
+
+            State::Data => {
+                match byte {
+                    b'#' => self.state = State::Checksum(None),
+                    b'}' => self.state = State::Escape,
+                    _ => self.data.push(byte),
+                }
+
+                if byte != b'#' {
+                    self.checksum = self.checksum.wrapping_add(byte);
+                }
+
+                None
+            }
+
+            State::Escape => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                self.data.push(byte ^ 0x20);
+                self.state = State::Data;
+                None
+            }
+
+            State::Checksum(None) => {
+                self.state = State::Checksum(Some(byte));
+                None
+            }
+
+            State::Checksum(Some(high)) => {
+                self.state = State::Idle;
+
+                let expected = std::str::from_utf8(&[high, byte])
+                    .ok()
+                    .and_then(|text| u8::from_str_radix(text, 16).ok());
+
+                match expected == Some(self.checksum) {
+                    true => Some(Event::Packet(
+                        String::from_utf8_lossy(&self.data).into_owned(),
+                    )),
+                    false => Some(Event::Corrupted),
+                }
+            }
+        }
+    }
+}
+
+/// Frame a reply, escaping the characters the protocol reserves
+pub fn encode(data: &str) -> Vec<u8> {
+    let mut packet = vec![b'$'];
+    let mut checksum = 0u8;
+
+    for &byte in data.as_bytes() {
+        let escaped: &[u8] = match byte {
+            b'$' | b'#' | b'}' | b'*' => &[b'}', byte ^ 0x20],
+            _ => &[byte],
+        };
+
+        for &byte in escaped {
+            checksum = checksum.wrapping_add(byte);
+            packet.push(byte);
+        }
+    }
+
+    packet.extend(format!("#{checksum:02x}").bytes());
+    packet
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(bytes: &[u8]) -> Vec<Event> {
+        let mut decoder = Decoder::default();
+        bytes
+            .iter()
+            .filter_map(|&byte| decoder.feed(byte))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode(b"+$g#67-\x03"),
+            [
+                Event::Ack,
+                Event::Packet("g".to_string()),
+                Event::Nack,
+                Event::Interrupt
+            ]
+        );
+
+        assert_eq!(decode(b"$g#00"), [Event::Corrupted]);
+
+        // `}]` is an escaped `}`
+        let mut packet = encode("M0,1:}");
+        assert_eq!(packet, b"$M0,1:}]#ee");
+        packet.insert(0, b'+');
+        assert_eq!(decode(&packet)[1], Event::Packet("M0,1:}".to_string()));
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x12, 0xab]), "12ab");
+        assert_eq!(parse_hex("12ab"), Some(vec![0x12, 0xab]));
+        assert_eq!(parse_hex("12a"), None);
+        assert_eq!(parse_hex("zz"), None);
+    }
+}
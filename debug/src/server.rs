@@ -0,0 +1,132 @@
+/**
+ * @file server.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief TCP server GDB connects to with `target remote`
+ */
+use crate::packet::{Decoder, Event, encode};
+use crate::target::{Action, GdbTarget};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+pub const DEFAULT_PORT: u16 = 3333;
+
+/// Wait for GDB on the port of the local host
+pub fn listen(port: u16) -> io::Result<TcpStream> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    log::info!("Waiting for GDB on port {port}");
+
+    let (stream, address) = listener.accept()?;
+    log::info!("GDB connected from {address}");
+
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+struct Connection {
+    stream: TcpStream,
+    decoder: Decoder,
+    /// Set by `QStartNoAckMode`, neither side acknowledges the packets anymore
+    no_ack: bool,
+    last_reply: Vec<u8>,
+}
+
+impl Connection {
+    fn send(&mut self, reply: &str) -> io::Result<()> {
+        self.last_reply = encode(reply);
+        self.stream.write_all(&self.last_reply)
+    }
+
+    fn ack(&mut self, byte: u8) -> io::Result<()> {
+        match self.no_ack {
+            true => Ok(()),
+            false => self.stream.write_all(&[byte]),
+        }
+    }
+
+    /// Next packet of GDB, `None` once it hung up
+    fn receive(&mut self) -> io::Result<Option<String>> {
+        let mut byte = [0];
+
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+
+            match self.decoder.feed(byte[0]) {
+                Some(Event::Packet(packet)) => {
+                    self.ack(b'+')?;
+                    return Ok(Some(packet));
+                }
+                Some(Event::Corrupted) => self.ack(b'-')?,
+                Some(Event::Nack) => {
+                    let reply = self.last_reply.clone();
+                    self.stream.write_all(&reply)?;
+                }
+                // nothing is running to interrupt
+                Some(Event::Ack) | Some(Event::Interrupt) | None => {}
+            }
+        }
+    }
+
+    /// Whether GDB sent a Ctrl-C while the chip runs, without waiting for it
+    fn interrupted(&mut self) -> bool {
+        let mut bytes = [0; 64];
+
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+
+        let interrupted = match self.stream.read(&mut bytes) {
+            // GDB hung up, the next receive notices it
+            Ok(0) => true,
+            // every byte goes through the decoder, even after an interrupt
+            Ok(len) => {
+                bytes[..len]
+                    .iter()
+                    .filter_map(|&byte| self.decoder.feed(byte))
+                    .filter(|event| *event == Event::Interrupt)
+                    .count()
+                    > 0
+            }
+            Err(error) => error.kind() != ErrorKind::WouldBlock,
+        };
+
+        let _ = self.stream.set_nonblocking(false);
+        interrupted
+    }
+}
+
+/// Answer GDB until it detaches, kills the target or hangs up
+pub fn serve(stream: TcpStream, target: &mut GdbTarget) -> io::Result<()> {
+    let mut connection = Connection {
+        stream,
+        decoder: Decoder::default(),
+        no_ack: false,
+        last_reply: Vec::new(),
+    };
+
+    while let Some(packet) = connection.receive()? {
+        if packet == "QStartNoAckMode" {
+            connection.send("OK")?;
+            connection.no_ack = true;
+            continue;
+        }
+
+        match target.handle(&packet) {
+            Action::Reply(reply) => connection.send(&reply)?,
+            Action::Resume { step } => {
+                let stop = target.resume(step, || connection.interrupted());
+                connection.send(&stop.reply())?;
+            }
+            Action::Detach(reply) => {
+                connection.send(&reply)?;
+                break;
+            }
+            Action::Kill => break,
+        }
+    }
+
+    log::info!("GDB disconnected");
+    Ok(())
+}
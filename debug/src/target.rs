@@ -0,0 +1,636 @@
+/**
+ * @file target.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief The commands of GDB against a simulated chip, the cores as its threads
+ */
+use crate::packet::{hex, parse_hex};
+use rp2350::Rp2350;
+use rp2350::processor::{Hazard3, NUM_TRIGGERS, Rp2350Core, Trigger};
+use rp2350::rp2350::STEP_TIMEOUT;
+use std::collections::BTreeSet;
+
+/// Ticks run between two checks for a Ctrl-C of GDB
+pub const POLL_INTERVAL: u64 = 4096;
+
+/// Bytes read at most by a single `m` packet, within the packet size announced to GDB
+pub const MAX_READ: usize = 0x800;
+
+/// x0 to x31 then the pc, the register layout GDB expects from an RV32 target
+const NUM_REGISTERS: usize = 33;
+const PC: usize = 32;
+
+/// Why the chip stopped, the cores are numbered from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// A breakpoint, or the end of a single step
+    Trap { core: usize },
+    Watch {
+        core: usize,
+        kind: WatchKind,
+        address: u32,
+    },
+    /// GDB sent a Ctrl-C
+    Interrupted { core: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Write,
+    Read,
+    Access,
+}
+
+impl Stop {
+    /// The stop reply packet
+    pub fn reply(&self) -> String {
+        match *self {
+            Self::Trap { core } => format!("T05thread:{};", core + 1),
+            Self::Interrupted { core } => format!("T02thread:{};", core + 1),
+            Self::Watch {
+                core,
+                kind,
+                address,
+            } => {
+                let name = match kind {
+                    WatchKind::Write => "watch",
+                    WatchKind::Read => "rwatch",
+                    WatchKind::Access => "awatch",
+                };
+
+                format!("T05{name}:{address:x};thread:{};", core + 1)
+            }
+        }
+    }
+}
+
+/// What the server does with a packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Reply(String),
+    /// Run until a stop reason, the given core single steps
+    Resume {
+        step: Option<usize>,
+    },
+    /// Reply then close the connection
+    Detach(String),
+    Kill,
+}
+
+/// A debugger trigger set on the Hazard3 cores, for `Z1` to `Z4`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HardwareBreakpoint {
+    kind: Option<WatchKind>,
+    address: u32,
+}
+
+impl HardwareBreakpoint {
+    fn trigger(&self) -> Trigger {
+        match self.kind {
+            None => Trigger::breakpoint(self.address),
+            Some(WatchKind::Write) => Trigger::watchpoint(self.address, false, true),
+            Some(WatchKind::Read) => Trigger::watchpoint(self.address, true, false),
+            Some(WatchKind::Access) => Trigger::watchpoint(self.address, true, true),
+        }
+    }
+}
+
+/// The chip as GDB sees it. The two cores are the threads 1 and 2, all of the chip
+/// stops whenever one of them does.
+///
+/// Software breakpoints are kept here and checked between the cycles, the chip is
+/// stopped once a core arrives on one, before it executes the instruction there. The
+/// hardware breakpoints and watchpoints go into the trigger slots of the Hazard3, the
+/// core halts in Debug Mode on them and is resumed right away, so it stays at the
+/// instruction which hit. Only the registers of the Hazard3 are readable, the cores
+/// in Arm mode can still be run, stepped and have their memory read.
+pub struct GdbTarget {
+    pub mcu: Rp2350,
+    breakpoints: BTreeSet<u32>,
+    triggers: [Option<HardwareBreakpoint>; NUM_TRIGGERS],
+    /// Core of the register and memory accesses, set by `Hg`
+    general: usize,
+    /// Core stepped by `s`, set by `Hc`
+    stepping: Option<usize>,
+    last_stop: Stop,
+}
+
+impl GdbTarget {
+    pub fn new(mcu: Rp2350) -> Self {
+        Self {
+            mcu,
+            breakpoints: BTreeSet::new(),
+            triggers: [None; NUM_TRIGGERS],
+            general: 0,
+            stepping: None,
+            last_stop: Stop::Trap { core: 0 },
+        }
+    }
+
+    pub fn handle(&mut self, packet: &str) -> Action {
+        let reply = |text: &str| Action::Reply(text.to_string());
+        let (command, args) = split_first(packet);
+
+        match command {
+            "?" => Action::Reply(self.last_stop.reply()),
+            "g" => Action::Reply(value(self.read_registers())),
+            "G" => Action::Reply(status(self.write_registers(args))),
+            "p" => Action::Reply(value(self.read_register(args))),
+            "P" => Action::Reply(status(self.write_register(args))),
+            "m" => Action::Reply(value(self.read_memory(args))),
+            "M" => Action::Reply(status(self.write_memory(args))),
+            "Z" => Action::Reply(self.breakpoint(args, true)),
+            "z" => Action::Reply(self.breakpoint(args, false)),
+            "H" => Action::Reply(status(self.select_thread(args))),
+            "T" => match thread(args) {
+                Some(Some(_)) => reply("OK"),
+                _ => reply("E01"),
+            },
+            "c" | "C" => Action::Resume { step: None },
+            "s" | "S" => Action::Resume {
+                step: Some(self.stepping.unwrap_or(self.general)),
+            },
+            "D" => Action::Detach("OK".to_string()),
+            "k" => Action::Kill,
+            "q" => Action::Reply(self.query(args)),
+            "v" => self.v_command(args),
+            _ => reply(""),
+        }
+    }
+
+    fn query(&self, query: &str) -> String {
+        let (name, args) = query.split_once([':', ',']).unwrap_or((query, ""));
+
+        match name {
+            "Supported" => "PacketSize=1000;QStartNoAckMode+;vContSupported+".to_string(),
+            "Attached" => "1".to_string(),
+            "C" => format!("QC{}", self.general + 1),
+            "fThreadInfo" => "m1,2".to_string(),
+            "sThreadInfo" => "l".to_string(),
+            "ThreadExtraInfo" => {
+                let info = match thread(args) {
+                    Some(Some(core)) => self.thread_info(core),
+                    _ => return "E01".to_string(),
+                };
+
+                hex(info.as_bytes())
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn thread_info(&self, core: usize) -> String {
+        let architecture = match &self.mcu.processor[core] {
+            Rp2350Core::RiscV(_) => "Hazard3",
+            Rp2350Core::Arm(_) => "Cortex-M33",
+        };
+
+        format!("core {core} ({architecture})")
+    }
+
+    fn v_command(&mut self, command: &str) -> Action {
+        let Some(actions) = command.strip_prefix("Cont") else {
+            return Action::Reply(String::new());
+        };
+
+        if actions == "?" {
+            return Action::Reply("vCont;c;C;s;S".to_string());
+        }
+
+        // the actions apply to the first thread they match, only a step matters here
+        for action in actions.split(';').skip(1) {
+            let (action, target) = action.split_once(':').unwrap_or((action, "-1"));
+
+            if action.starts_with(['s', 'S']) {
+                let core = match thread(target) {
+                    Some(Some(core)) => core,
+                    Some(None) => self.general,
+                    None => return Action::Reply("E01".to_string()),
+                };
+
+                return Action::Resume { step: Some(core) };
+            }
+        }
+
+        Action::Resume { step: None }
+    }
+
+    fn select_thread(&mut self, args: &str) -> Option<()> {
+        let (operation, id) = split_first(args);
+        let core = thread(id)?;
+
+        match operation {
+            "g" => self.general = core.unwrap_or(0),
+            "c" => self.stepping = core,
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn core(&self) -> Option<&Hazard3> {
+        match &self.mcu.processor[self.general] {
+            Rp2350Core::RiscV(core) => Some(core),
+            Rp2350Core::Arm(_) => None,
+        }
+    }
+
+    fn register(&self, index: usize) -> Option<u32> {
+        let core = self.core()?;
+
+        match index {
+            PC => Some(core.pc),
+            _ if index < PC => Some(core.registers.read(index as u8)),
+            _ => None,
+        }
+    }
+
+    fn set_register(&mut self, index: usize, value: u32) -> Option<()> {
+        self.core()?;
+
+        match index {
+            PC => self.mcu.processor[self.general].set_pc(value),
+            _ if index < PC => self.mcu.processor[self.general].set_register(index as u8, value),
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn read_registers(&self) -> Option<String> {
+        (0..NUM_REGISTERS)
+            .map(|index| Some(hex(&self.register(index)?.to_le_bytes())))
+            .collect()
+    }
+
+    fn write_registers(&mut self, args: &str) -> Option<()> {
+        let bytes = parse_hex(args)?;
+
+        for (index, value) in bytes.chunks_exact(4).take(NUM_REGISTERS).enumerate() {
+            let value = u32::from_le_bytes(value.try_into().ok()?);
+            self.set_register(index, value)?;
+        }
+
+        Some(())
+    }
+
+    fn read_register(&self, args: &str) -> Option<String> {
+        let index = usize::from_str_radix(args, 16).ok()?;
+        Some(hex(&self.register(index)?.to_le_bytes()))
+    }
+
+    fn write_register(&mut self, args: &str) -> Option<()> {
+        let (index, value) = args.split_once('=')?;
+        let index = usize::from_str_radix(index, 16).ok()?;
+        let value = u32::from_le_bytes(parse_hex(value)?.try_into().ok()?);
+        self.set_register(index, value)
+    }
+
+    /// Reads what it can, GDB only takes an error when not a single byte is readable
+    fn read_memory(&mut self, args: &str) -> Option<String> {
+        let (address, length) = address_length(args)?;
+        let mut bytes = Vec::new();
+
+        for address in (address..).take(length.min(MAX_READ)) {
+            let Ok(word) = self.mcu.bus.debug_read(address) else {
+                break;
+            };
+
+            bytes.push(word.to_le_bytes()[address as usize & 0b11]);
+        }
+
+        match bytes.is_empty() && length != 0 {
+            true => None,
+            false => Some(hex(&bytes)),
+        }
+    }
+
+    fn write_memory(&mut self, args: &str) -> Option<()> {
+        let (range, data) = args.split_once(':')?;
+        let (address, length) = address_length(range)?;
+        let data = parse_hex(data)?;
+
+        if data.len() != length {
+            return None;
+        }
+
+        for (address, byte) in (address..).zip(data) {
+            self.mcu.bus.debug_write_u8(address, byte).ok()?;
+        }
+
+        Some(())
+    }
+
+    /// `Z` and `z`, software breakpoints are `0`, the others take a trigger slot
+    fn breakpoint(&mut self, args: &str, insert: bool) -> String {
+        let mut fields = args.split(',');
+        let kind = fields.next();
+        let address = fields
+            .next()
+            .and_then(|text| u32::from_str_radix(text, 16).ok());
+
+        let Some(address) = address else {
+            return "E01".to_string();
+        };
+
+        let kind = match kind {
+            Some("0") => {
+                match insert {
+                    true => self.breakpoints.insert(address),
+                    false => self.breakpoints.remove(&address),
+                };
+
+                return "OK".to_string();
+            }
+            Some("1") => None,
+            Some("2") => Some(WatchKind::Write),
+            Some("3") => Some(WatchKind::Read),
+            Some("4") => Some(WatchKind::Access),
+            _ => return String::new(),
+        };
+
+        let breakpoint = HardwareBreakpoint { kind, address };
+        let slot = match insert {
+            true => self.triggers.iter().position(Option::is_none),
+            false => self.triggers.iter().position(|t| *t == Some(breakpoint)),
+        };
+
+        let Some(slot) = slot else {
+            return match insert {
+                true => "E01".to_string(),
+                false => "OK".to_string(),
+            };
+        };
+
+        self.triggers[slot] = insert.then_some(breakpoint);
+        self.arm_trigger(slot);
+        "OK".to_string()
+    }
+
+    /// Write a slot to the cores, which also clears its hit bit
+    fn arm_trigger(&mut self, slot: usize) {
+        let trigger = self.triggers[slot].map(|breakpoint| breakpoint.trigger());
+
+        for core in &mut self.mcu.processor {
+            core.set_trigger(slot, trigger);
+        }
+    }
+
+    /// Run the chip until it stops. `interrupted` is polled every [`POLL_INTERVAL`]
+    /// ticks for a Ctrl-C of GDB
+    pub fn resume(&mut self, step: Option<usize>, mut interrupted: impl FnMut() -> bool) -> Stop {
+        let stop = match step {
+            Some(core) => self.step(core),
+            None => self.run(&mut interrupted),
+        };
+
+        self.settle();
+        self.last_stop = stop;
+        stop
+    }
+
+    fn step(&mut self, core: usize) -> Stop {
+        self.mcu.step_instruction(core);
+        self.halted().unwrap_or(Stop::Trap { core })
+    }
+
+    fn run(&mut self, interrupted: &mut impl FnMut() -> bool) -> Stop {
+        // leave the breakpoints the cores stopped on
+        for (core, (pc, _)) in self.progress().into_iter().enumerate() {
+            if self.breakpoints.contains(&pc) {
+                self.mcu.step_instruction(core);
+
+                if let Some(stop) = self.halted() {
+                    return stop;
+                }
+            }
+        }
+
+        let mut last = self.progress();
+        let mut ticks = 0u64;
+
+        loop {
+            self.mcu.tick();
+
+            if let Some(stop) = self.halted() {
+                return stop;
+            }
+
+            // the pc changes as an instruction executes, the next one waits for a later cycle
+            let progress = self.progress();
+            let moved = progress.iter().zip(&last).enumerate();
+
+            for (core, (&(pc, retired), &before)) in moved {
+                if (pc, retired) != before && self.breakpoints.contains(&pc) {
+                    return Stop::Trap { core };
+                }
+            }
+
+            last = progress;
+            ticks += 1;
+
+            if ticks.is_multiple_of(POLL_INTERVAL) && interrupted() {
+                return Stop::Interrupted { core: self.general };
+            }
+        }
+    }
+
+    /// The pc and the instructions retired of each core, a core at the same pc moved on
+    /// when it executed a jump to itself
+    fn progress(&self) -> [(u32, u64); 2] {
+        let core = |i: usize| {
+            let core = &self.mcu.processor[i];
+            (core.get_pc(), core.retired())
+        };
+
+        [core(0), core(1)]
+    }
+
+    /// A core halted by a trigger, it is taken out of Debug Mode back at the instruction
+    /// which hit, the trigger stays quiet for that instruction once resumed
+    fn halted(&mut self) -> Option<Stop> {
+        let core = (0..2).find(|&core| self.mcu.processor[core].is_halted())?;
+
+        let hit = match &self.mcu.processor[core] {
+            Rp2350Core::RiscV(hazard3) => hazard3.csrs.triggers.iter().position(Trigger::has_hit),
+            Rp2350Core::Arm(_) => None,
+        };
+
+        self.mcu.processor[core].resume();
+
+        let breakpoint = hit.and_then(|slot| {
+            self.arm_trigger(slot);
+            self.triggers[slot]
+        });
+
+        Some(match breakpoint {
+            Some(HardwareBreakpoint {
+                kind: Some(kind),
+                address,
+            }) => Stop::Watch {
+                core,
+                kind,
+                address,
+            },
+            _ => Stop::Trap { core },
+        })
+    }
+
+    /// Let both cores finish their instruction in flight without starting another, so
+    /// GDB reads the registers with every result written
+    fn settle(&mut self) {
+        for core in &mut self.mcu.processor {
+            core.set_hold(true);
+        }
+
+        for _ in 0..STEP_TIMEOUT {
+            if !self.mcu.processor.iter().any(Rp2350Core::is_busy) {
+                break;
+            }
+
+            self.mcu.tick();
+        }
+
+        for core in &mut self.mcu.processor {
+            core.set_hold(false);
+            core.flush_bypass();
+        }
+    }
+}
+
+fn split_first(text: &str) -> (&str, &str) {
+    text.split_at(text.char_indices().nth(1).map_or(text.len(), |(i, _)| i))
+}
+
+fn value(reply: Option<String>) -> String {
+    reply.unwrap_or_else(|| "E01".to_string())
+}
+
+fn status(result: Option<()>) -> String {
+    match result {
+        Some(()) => "OK".to_string(),
+        None => "E01".to_string(),
+    }
+}
+
+/// A thread id of GDB, `None` inside for all of them or any of them
+fn thread(id: &str) -> Option<Option<usize>> {
+    match id {
+        "-1" | "0" => Some(None),
+        _ => match usize::from_str_radix(id, 16).ok()? {
+            id @ 1..=2 => Some(Some(id - 1)),
+            _ => None,
+        },
+    }
+}
+
+fn address_length(args: &str) -> Option<(u32, usize)> {
+    let (address, length) = args.split_once(',')?;
+    Some((
+        u32::from_str_radix(address, 16).ok()?,
+        usize::from_str_radix(length, 16).ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rp2350::bus::Bus;
+    use rp2350::testing::setup;
+
+    const START: u32 = Bus::SRAM;
+
+    fn target() -> GdbTarget {
+        let program = [
+            0x0000_0617u32, // auipc a2, 0
+            0x3e80_0513,    // li a0, 1000
+            0x0006_2583,    // lw a1, 0(a2)
+            0x0015_0513,    // addi a0, a0, 1
+            0x0000_006f,    // j .
+        ];
+        GdbTarget::new(setup(&program))
+    }
+
+    fn reply(target: &mut GdbTarget, packet: &str) -> String {
+        match target.handle(packet) {
+            Action::Reply(reply) => reply,
+            action => panic!("{packet} did not reply: {action:?}"),
+        }
+    }
+
+    /// Run the chip like the server does on a resume
+    fn resume(target: &mut GdbTarget, packet: &str) -> Stop {
+        match target.handle(packet) {
+            Action::Resume { step } => target.resume(step, || false),
+            action => panic!("{packet} did not resume: {action:?}"),
+        }
+    }
+
+    #[test]
+    fn test_registers_and_memory() {
+        let mut target = target();
+
+        assert_eq!(reply(&mut target, "p20"), "00000020");
+        assert_eq!(reply(&mut target, "Pa=78563412"), "OK");
+        assert_eq!(reply(&mut target, "pa"), "78563412");
+        assert_eq!(reply(&mut target, "g").len(), NUM_REGISTERS * 8);
+
+        assert_eq!(reply(&mut target, "m20000004,4"), "1305803e");
+        assert_eq!(reply(&mut target, "M20000100,2:abcd"), "OK");
+        assert_eq!(reply(&mut target, "m20000100,2"), "abcd");
+        assert_eq!(reply(&mut target, "m4fff0000,4"), "E01");
+
+        assert_eq!(reply(&mut target, "Hg2"), "OK");
+        assert_eq!(reply(&mut target, "qC"), "QC2");
+        assert_eq!(reply(&mut target, "Hg3"), "E01");
+        assert_eq!(reply(&mut target, "qfThreadInfo"), "m1,2");
+    }
+
+    #[test]
+    fn test_step_and_breakpoints() {
+        let mut target = target();
+
+        assert_eq!(resume(&mut target, "vCont;s:1"), Stop::Trap { core: 0 });
+        assert_eq!(target.mcu.processor[0].get_pc(), START + 4);
+
+        // stops before the addi, with the load before it done
+        assert_eq!(reply(&mut target, "Z0,2000000c,4"), "OK");
+        assert_eq!(resume(&mut target, "c"), Stop::Trap { core: 0 });
+        assert_eq!(target.mcu.processor[0].get_pc(), START + 12);
+        assert_eq!(reply(&mut target, "pa"), hex(&1000u32.to_le_bytes()));
+        assert_eq!(reply(&mut target, "pb"), "17060000");
+
+        // continuing steps off the breakpoint, the hardware one stops on the jump
+        assert_eq!(reply(&mut target, "Z1,20000010,4"), "OK");
+        assert_eq!(resume(&mut target, "c"), Stop::Trap { core: 0 });
+        assert_eq!(target.mcu.processor[0].get_pc(), START + 16);
+        assert!(!target.mcu.processor[0].is_halted());
+        assert_eq!(reply(&mut target, "pa"), hex(&1001u32.to_le_bytes()));
+
+        assert_eq!(reply(&mut target, "z1,20000010,4"), "OK");
+        assert_eq!(reply(&mut target, "z0,2000000c,4"), "OK");
+        assert_eq!(target.resume(None, || true), Stop::Interrupted { core: 0 });
+        assert_eq!(reply(&mut target, "?"), "T02thread:1;");
+    }
+
+    #[test]
+    fn test_watchpoint() {
+        let mut target = target();
+
+        assert_eq!(reply(&mut target, "Z3,20000000,4"), "OK");
+        let stop = resume(&mut target, "c");
+        assert_eq!(
+            stop,
+            Stop::Watch {
+                core: 0,
+                kind: WatchKind::Read,
+                address: START
+            }
+        );
+        assert_eq!(stop.reply(), "T05rwatch:20000000;thread:1;");
+
+        // halted before the load
+        assert_eq!(target.mcu.processor[0].get_pc(), START + 8);
+        assert_eq!(reply(&mut target, "pb"), "00000000");
+    }
+}
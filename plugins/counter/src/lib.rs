@@ -0,0 +1,134 @@
+//! @file lib.rs
+//! @author Nguyen Le Duy
+//! @date 14/10/2026
+//! @brief Sample peripheral plugin, a counter of cycles with a compare interrupt
+
+#![no_std]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// Free slot of the APB peripherals
+const BASE: u32 = 0x4011_0000;
+/// SPAREIRQ_IRQ_0
+const IRQ: i32 = 46;
+
+/// Bit 0 enables the counter, bit 1 toggles OUTPUT_PIN on every match
+const CTRL: i32 = 0x00;
+const COUNT: i32 = 0x04;
+const COMPARE: i32 = 0x08;
+/// Bit 0 is set on a match and raises the IRQ, write 1 to clear it
+const STATUS: i32 = 0x0c;
+
+const CTRL_ENABLE: u32 = 1 << 0;
+const CTRL_TOGGLE: u32 = 1 << 1;
+const OUTPUT_PIN: i32 = 15;
+
+static CONTROL: AtomicU32 = AtomicU32::new(0);
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+static COMPARE_VALUE: AtomicU32 = AtomicU32::new(u32::MAX);
+static MATCHED: AtomicU32 = AtomicU32::new(0);
+static OUTPUT: AtomicU32 = AtomicU32::new(0);
+
+#[link(wasm_import_module = "pico2")]
+extern "C" {
+    fn set_irq(level: i32);
+    fn gpio_set(pin: i32, level: i32);
+    fn log(ptr: *const u8, len: usize);
+}
+
+fn say(message: &str) {
+    unsafe { log(message.as_ptr(), message.len()) }
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_abi_version() -> i32 {
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_base() -> i32 {
+    BASE as i32
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_size() -> i32 {
+    0x10
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_irq() -> i32 {
+    IRQ
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_read(offset: i32) -> i32 {
+    let value = match offset {
+        CTRL => CONTROL.load(Ordering::Relaxed),
+        COUNT => COUNTER.load(Ordering::Relaxed),
+        COMPARE => COMPARE_VALUE.load(Ordering::Relaxed),
+        STATUS => MATCHED.load(Ordering::Relaxed),
+        _ => 0,
+    };
+
+    value as i32
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_write(offset: i32, value: i32) {
+    let value = value as u32;
+
+    match offset {
+        CTRL => CONTROL.store(value & (CTRL_ENABLE | CTRL_TOGGLE), Ordering::Relaxed),
+        COUNT => COUNTER.store(value, Ordering::Relaxed),
+        COMPARE => COMPARE_VALUE.store(value, Ordering::Relaxed),
+        STATUS if value & 1 != 0 => {
+            MATCHED.store(0, Ordering::Relaxed);
+            unsafe { set_irq(0) };
+        }
+        _ => {}
+    }
+}
+
+/// Called every few cycles with the cycles elapsed since the last call
+#[no_mangle]
+pub extern "C" fn pico2_tick(cycles: i32) {
+    let control = CONTROL.load(Ordering::Relaxed);
+
+    if control & CTRL_ENABLE == 0 {
+        return;
+    }
+
+    let from = COUNTER.load(Ordering::Relaxed);
+    let to = from.wrapping_add(cycles as u32);
+    COUNTER.store(to, Ordering::Relaxed);
+
+    // the compare value was passed during the elapsed cycles
+    let compare = COMPARE_VALUE.load(Ordering::Relaxed);
+    if compare.wrapping_sub(from) >= cycles as u32 || MATCHED.load(Ordering::Relaxed) != 0 {
+        return;
+    }
+
+    MATCHED.store(1, Ordering::Relaxed);
+    unsafe { set_irq(1) };
+
+    if control & CTRL_TOGGLE != 0 {
+        let output = OUTPUT.fetch_xor(1, Ordering::Relaxed) ^ 1;
+        unsafe { gpio_set(OUTPUT_PIN, output as i32) };
+    }
+
+    say("compare value reached");
+}
+
+#[no_mangle]
+pub extern "C" fn pico2_reset() {
+    CONTROL.store(0, Ordering::Relaxed);
+    COUNTER.store(0, Ordering::Relaxed);
+    COMPARE_VALUE.store(u32::MAX, Ordering::Relaxed);
+    MATCHED.store(0, Ordering::Relaxed);
+    OUTPUT.store(0, Ordering::Relaxed);
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
@@ -0,0 +1,332 @@
+/**
+ * @file budget.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Cycle budgets of code regions, every execution of a region is measured against its budget
+ */
+use crate::elf::Elf;
+use crate::inspector::{InspectionEvent, Inspector};
+use std::cell::RefCell;
+use thiserror::Error;
+
+// Only the first violations of a region are kept, the rest are counted
+pub const MAX_RECORDED_VIOLATIONS: usize = 16;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum BudgetError {
+    #[error("Invalid budget `{0}`, expected `<start>..<end>:<cycles>` or `<symbol>:<cycles>`")]
+    Malformed(String),
+
+    #[error("Unknown symbol `{0}`")]
+    UnknownSymbol(String),
+
+    #[error("Budget `{0}` names a symbol, the ELF file of the program is needed")]
+    MissingSymbols(String),
+}
+
+/// The most cycles a single execution of a region of code may take
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleBudget {
+    pub name: String,
+    /// Address of the first instruction of the region
+    pub start: u32,
+    /// Address right after the region
+    pub end: u32,
+    pub max_cycles: u64,
+}
+
+impl CycleBudget {
+    pub fn new(name: impl Into<String>, start: u32, end: u32, max_cycles: u64) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            end,
+            max_cycles,
+        }
+    }
+
+    /// Budget of a whole function, its bounds come from the symbol table
+    pub fn for_symbol(elf: &Elf, name: &str, max_cycles: u64) -> Result<Self, BudgetError> {
+        let symbol = elf
+            .symbol(name)
+            .ok_or_else(|| BudgetError::UnknownSymbol(name.to_string()))?;
+
+        // the lowest bit marks the Thumb functions
+        let start = symbol.address & !1;
+        let end = start + symbol.size.max(1);
+
+        Ok(Self::new(name, start, end, max_cycles))
+    }
+
+    /// Parses `<start>..<end>:<cycles>`, the addresses in hex with an optional `0x`,
+    /// or `<symbol>:<cycles>` which needs the ELF file of the program
+    pub fn parse(spec: &str, elf: Option<&Elf>) -> Result<Self, BudgetError> {
+        let malformed = || BudgetError::Malformed(spec.to_string());
+
+        let (region, cycles) = spec.rsplit_once(':').ok_or_else(malformed)?;
+        let max_cycles = cycles.trim().parse::<u64>().map_err(|_| malformed())?;
+        let region = region.trim();
+
+        if let Some((start, end)) = region.split_once("..") {
+            let address = |text: &str| {
+                let text = text.trim();
+                let digits = text.strip_prefix("0x").unwrap_or(text);
+                u32::from_str_radix(digits, 16).map_err(|_| malformed())
+            };
+
+            let (start, end) = (address(start)?, address(end)?);
+
+            if start >= end {
+                return Err(malformed());
+            }
+
+            return Ok(Self::new(region, start, end, max_cycles));
+        }
+
+        if region.is_empty() {
+            return Err(malformed());
+        }
+
+        match elf {
+            Some(elf) => Self::for_symbol(elf, region, max_cycles),
+            None => Err(BudgetError::MissingSymbols(spec.to_string())),
+        }
+    }
+
+    pub fn contains(&self, address: u32) -> bool {
+        (self.start..self.end).contains(&address)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetViolation {
+    pub core: u8,
+    /// Cycle of the core at which the region was entered
+    pub entered_at: u64,
+    pub cycles: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetReport {
+    pub budget: CycleBudget,
+    /// Completed executions of the region
+    pub invocations: u64,
+    pub worst_cycles: u64,
+    pub violations: u64,
+    /// Up to [`MAX_RECORDED_VIOLATIONS`], the earliest first
+    pub first_violations: Vec<BudgetViolation>,
+}
+
+impl BudgetReport {
+    fn new(budget: CycleBudget) -> Self {
+        Self {
+            budget,
+            invocations: 0,
+            worst_cycles: 0,
+            violations: 0,
+            first_violations: Vec::new(),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.violations == 0
+    }
+
+    fn record(&mut self, core: u8, entered_at: u64, cycles: u64) {
+        self.invocations += 1;
+        self.worst_cycles = self.worst_cycles.max(cycles);
+
+        if cycles <= self.budget.max_cycles {
+            return;
+        }
+
+        self.violations += 1;
+        log::warn!(
+            "Core {core}: `{}` took {cycles} cycles, over its budget of {}",
+            self.budget.name,
+            self.budget.max_cycles
+        );
+
+        if self.first_violations.len() < MAX_RECORDED_VIOLATIONS {
+            self.first_violations.push(BudgetViolation {
+                core,
+                entered_at,
+                cycles,
+            });
+        }
+    }
+}
+
+struct Region {
+    report: BudgetReport,
+    /// Cycle at which each core entered the region, if it is inside
+    entered: [Option<u64>; 2],
+}
+
+#[derive(Default)]
+struct State {
+    cycles: [u64; 2],
+    regions: Vec<Region>,
+}
+
+/// Inspector timing the regions from the instructions the cores execute.
+///
+/// An execution lasts from the first instruction inside the region to the first
+/// one outside of it, so the functions it calls are only counted when they are
+/// part of the region. An interrupt taken in the middle ends the execution early.
+#[derive(Default)]
+pub struct BudgetChecker(RefCell<State>);
+
+impl BudgetChecker {
+    pub fn new(budgets: impl IntoIterator<Item = CycleBudget>) -> Self {
+        let regions = budgets
+            .into_iter()
+            .map(|budget| Region {
+                report: BudgetReport::new(budget),
+                entered: [None; 2],
+            })
+            .collect();
+
+        Self(RefCell::new(State {
+            cycles: [0; 2],
+            regions,
+        }))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().regions.is_empty()
+    }
+
+    pub fn reports(&self) -> Vec<BudgetReport> {
+        let state = self.0.borrow();
+        state.regions.iter().map(|r| r.report.clone()).collect()
+    }
+
+    pub fn has_violations(&self) -> bool {
+        let state = self.0.borrow();
+        state.regions.iter().any(|r| !r.report.passed())
+    }
+}
+
+impl Inspector for BudgetChecker {
+    fn handle_event(&self, event: InspectionEvent) {
+        let mut state = self.0.borrow_mut();
+        let state = &mut *state;
+
+        match event {
+            InspectionEvent::TickCore(core) => {
+                state.cycles[core as usize] += 1;
+            }
+            // the whole chip moved forward, not only the waiting core
+            InspectionEvent::BusyWaitSkipped { cycles, .. }
+            | InspectionEvent::IdleSkipped { cycles } => {
+                state.cycles.iter_mut().for_each(|count| *count += cycles);
+            }
+            InspectionEvent::ExecutedInstruction { core, address, .. } => {
+                let now = state.cycles[core as usize];
+
+                for region in state.regions.iter_mut() {
+                    let entered = &mut region.entered[core as usize];
+
+                    match (*entered, region.report.budget.contains(address)) {
+                        (None, true) => *entered = Some(now),
+                        (Some(start), false) => {
+                            *entered = None;
+                            region.report.record(core, start, now - start);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            InspectionEvent::FlashedBinary => {
+                state.cycles = [0; 2];
+
+                for region in state.regions.iter_mut() {
+                    let budget = region.report.budget.clone();
+                    region.report = BudgetReport::new(budget);
+                    region.entered = [None; 2];
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute(checker: &BudgetChecker, core: u8, address: u32, cycles: u64) {
+        for _ in 0..cycles {
+            checker.handle_event(InspectionEvent::TickCore(core));
+        }
+
+        checker.handle_event(InspectionEvent::ExecutedInstruction {
+            core,
+            instruction: 0,
+            address,
+            name: "",
+            operands: Vec::new(),
+        });
+    }
+
+    #[test]
+    fn test_parse() {
+        let budget = CycleBudget::parse("0x10000100..10000180:500", None).unwrap();
+        assert_eq!(budget.start, 0x1000_0100);
+        assert_eq!(budget.end, 0x1000_0180);
+        assert_eq!(budget.max_cycles, 500);
+
+        assert!(matches!(
+            CycleBudget::parse("isr_handler:500", None),
+            Err(BudgetError::MissingSymbols(_))
+        ));
+        assert!(CycleBudget::parse("0x200..0x100:5", None).is_err());
+        assert!(CycleBudget::parse("0x100..0x200", None).is_err());
+    }
+
+    #[test]
+    fn test_invocations() {
+        let checker = BudgetChecker::new([CycleBudget::new("isr", 0x100, 0x110, 10)]);
+
+        // 5 cycles inside, then leave
+        execute(&checker, 0, 0x100, 1);
+        execute(&checker, 0, 0x104, 2);
+        execute(&checker, 0, 0x108, 2);
+        execute(&checker, 0, 0x200, 1);
+
+        // 12 cycles inside, over the budget
+        execute(&checker, 0, 0x100, 1);
+        execute(&checker, 0, 0x10c, 4);
+        execute(&checker, 0, 0x300, 8);
+
+        let report = &checker.reports()[0];
+        assert_eq!(report.invocations, 2);
+        assert_eq!(report.worst_cycles, 12);
+        assert_eq!(report.violations, 1);
+        assert_eq!(report.first_violations[0].cycles, 12);
+        assert!(checker.has_violations());
+    }
+
+    #[test]
+    fn test_cores_are_separate() {
+        let checker = BudgetChecker::new([CycleBudget::new("isr", 0x100, 0x110, 10)]);
+
+        execute(&checker, 0, 0x100, 1);
+        execute(&checker, 1, 0x100, 1);
+        execute(&checker, 1, 0x200, 3);
+        checker.handle_event(InspectionEvent::BusyWaitSkipped {
+            core: 1,
+            address: 0x200,
+            iterations: 10,
+            instructions: 20,
+            cycles: 20,
+        });
+        execute(&checker, 0, 0x200, 1);
+
+        let report = &checker.reports()[0];
+        assert_eq!(report.invocations, 2);
+        assert_eq!(report.worst_cycles, 21);
+        assert_eq!(report.first_violations[0].core, 0);
+    }
+}
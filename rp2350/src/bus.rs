@@ -9,23 +9,39 @@ use crate::common::*;
 use crate::gpio::GpioController;
 use crate::interrupts::Interrupts;
 use crate::memory::*;
+use crate::processor::hazard3::PrivilegeMode;
+use crate::peripherals::busctrl::{PerformanceEventSource, PerformanceEventType};
 use crate::peripherals::*;
 use crate::utils::*;
 use crate::InspectionEvent;
 use crate::InspectorRef;
 use std::cell::RefCell;
+use std::ops::Range;
 use std::rc::Rc;
 
-// TODO - counter
+pub mod pmp;
+pub mod store_log;
+
+pub use pmp::{Pmp, PmpAccess};
+pub use store_log::{StoreLog, StoreRecord, StoreSource};
 
 pub const XIP_ADDRESS_MASK: u32 = 0x00FF_FFFF;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
 pub enum BusError {
+    #[error("bus fault")]
     BusFault,
+    #[error("concurrent access")]
     ConcurrentAccess,
+    #[error("load failed")]
     LoadError,
+    #[error("store failed")]
     StoreError,
+    #[error("denied by the PMP")]
+    PmpFault,
+    #[error("peripheral error: {0}")]
+    Peripheral(#[from] PeripheralError),
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -34,6 +50,8 @@ pub enum BusError {
 /// As well as how it should be handled
 pub struct BusAccessContext {
     pub secure: bool,
+    /// Privileged software, the handler mode or CONTROL.nPRIV clear on Arm, M-mode on Hazard3
+    pub privileged: bool,
     pub requestor: Requestor,
     pub size: DataSize,
     pub signed: bool,
@@ -95,11 +113,190 @@ enum StatusType {
     Store(u32, Rc<RefCell<StoreStatus>>),
 }
 
+/// A load from a SRAM location which was never written since reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UninitializedRead {
+    pub requestor: Requestor,
+    pub address: u32,
+    pub size: DataSize,
+    pub pc: Option<u32>, // only known for the processors
+}
+
+/// Addresses watched for stores, see [`Bus::watch_region`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchRegion {
+    pub range: Range<u32>,
+    pub id: u32,
+}
+
+impl WatchRegion {
+    fn overlaps(&self, address: u32, size: DataSize) -> bool {
+        self.range.start < address + size as u32 && address < self.range.end
+    }
+}
+
+/// Accesses a memory breakpoint stops on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BreakOn {
+    Read,
+    Write,
+    #[default]
+    Access,
+}
+
+impl BreakOn {
+    fn matches(self, write: bool) -> bool {
+        match self {
+            Self::Read => !write,
+            Self::Write => write,
+            Self::Access => true,
+        }
+    }
+}
+
+/// Addresses the simulation pauses on, see [`Bus::add_memory_breakpoint`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryBreakpoint {
+    pub range: Range<u32>,
+    pub on: BreakOn,
+    pub id: u32,
+}
+
+/// Access which hit a memory breakpoint, whichever master made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakpointHit {
+    pub id: u32,
+    pub requestor: Requestor,
+    pub address: u32,
+    pub size: DataSize,
+    pub write: bool,
+}
+
+// Stop recording after this many findings, the first ones are the interesting ones anyway
+const MAX_UNINITIALIZED_READS: usize = 1024;
+
+pub const LATENCY_HISTOGRAM_SIZE: usize = 16;
+
+/// SRAM0-7 of 64KB each and the two 4KB scratch banks SRAM8 and SRAM9
+pub const SRAM_BANKS: usize = 10;
+
+/// Bank of the SRAM holding an address. SRAM0-3 are striped word by word over the first
+/// 256KB and SRAM4-7 over the next 256KB, so sequential accesses spread over the banks,
+/// while the scratch banks are mapped directly after them
+pub fn sram_bank(address: u32) -> Option<usize> {
+    let stripe = ((address >> 2) & 0b11) as usize;
+
+    match address {
+        0x2000_0000..=0x2003_FFFF => Some(stripe),
+        0x2004_0000..=0x2007_FFFF => Some(4 + stripe),
+        0x2008_0000..=0x2008_0FFF => Some(8),
+        0x2008_1000..=0x2008_1FFF => Some(9),
+        _ => None,
+    }
+}
+
+/// Transaction statistics of a single requestor
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RequestorStatistics {
+    pub loads: u64,
+    pub stores: u64,
+    pub errors: u64,
+    /// Bytes moved by successful transactions
+    pub bytes: u64,
+    /// Cycles spent waiting for transactions to complete
+    pub wait_cycles: u64,
+    /// Cycles spent waiting for a port of the bus fabric another master was using
+    pub contention_stalls: u64,
+    /// Number of transactions by latency in cycles,
+    /// index 0 is 1 cycle and the last one collects everything longer
+    pub latency_histogram: [u64; LATENCY_HISTOGRAM_SIZE],
+}
+
+impl RequestorStatistics {
+    pub fn transactions(&self) -> u64 {
+        self.loads + self.stores
+    }
+
+    pub fn average_latency(&self) -> f64 {
+        match self.transactions() {
+            0 => 0.0,
+            n => self.wait_cycles as f64 / n as f64,
+        }
+    }
+
+    fn record(&mut self, is_load: bool, size: DataSize, latency: u32, success: bool) {
+        if is_load {
+            self.loads += 1;
+        } else {
+            self.stores += 1;
+        }
+
+        if success {
+            self.bytes += size as u64;
+        } else {
+            self.errors += 1;
+        }
+
+        self.wait_cycles += latency as u64;
+        let bucket = (latency.max(1) as usize - 1).min(LATENCY_HISTOGRAM_SIZE - 1);
+        self.latency_histogram[bucket] += 1;
+    }
+}
+
+/// Loads and stores served by a bank of the SRAM
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BankStatistics {
+    pub accesses: u64,
+    /// Cycles a master waited for the bank while it served another one
+    pub contention_stalls: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BusStatistics {
+    /// Indexed by `Requestor`
+    pub requestors: [RequestorStatistics; 4],
+    /// Indexed by the number of the bank, see [`sram_bank`]
+    pub sram_banks: [BankStatistics; SRAM_BANKS],
+    /// Bus ticks since the statistics were reset
+    pub ticks: u64,
+}
+
+impl BusStatistics {
+    pub fn requestor(&self, requestor: Requestor) -> &RequestorStatistics {
+        &self.requestors[requestor as usize]
+    }
+
+    /// Bytes actually moved by the DMA, counted on the write side
+    pub fn dma_bytes(&self) -> u64 {
+        self.requestor(Requestor::DmaW).bytes
+    }
+
+    /// DMA throughput in bytes per millisecond of simulated time,
+    /// `clk_sys` is the frequency the bus is ticked with
+    pub fn dma_bytes_per_ms(&self, clk_sys: u64) -> f64 {
+        if self.ticks == 0 {
+            return 0.0;
+        }
+
+        let ticks_per_ms = clk_sys as f64 / 1000.0;
+        self.dma_bytes() as f64 * ticks_per_ms / self.ticks as f64
+    }
+}
+
 struct Status {
     address: u32,
+    /// Downstream port of the bus fabric the access goes through
+    port: PerformanceEventSource,
+    /// The port is serving the access, the others on it wait for it to complete
+    granted: bool,
+    /// Lost the arbitration of the port at least once
+    contested: bool,
     wait_cycles: u8,
+    elapsed: u32,
     ctx: BusAccessContext,
     status: StatusType,
+    /// Who issued a store, only known while the stores are recorded
+    source: Option<StoreSource>,
 }
 
 pub struct Bus {
@@ -110,6 +307,23 @@ pub struct Bus {
 
     pub peripherals: Peripherals,
 
+    /// Content of the SRAM after reset
+    pub sram_init: InitPattern,
+    /// Which bytes of the SRAM have been written, only when tracking is enabled
+    sram_shadow: Option<InitializedMap>,
+    pub uninitialized_reads: Vec<UninitializedRead>,
+    core_pc: [u32; 2],
+    dma_channel: u8,
+    watches: Vec<WatchRegion>,
+    breakpoints: Vec<MemoryBreakpoint>,
+    breakpoint_hit: Option<BreakpointHit>,
+    store_log: Option<StoreLog>,
+    /// Published by the Hazard3 cores, none until they run
+    pmp: [Option<Pmp>; 2],
+
+    pub statistics: BusStatistics,
+    peripheral_accesses: u64,
+
     // Internal states
     dma_read_access: Option<Status>,
     dma_write_access: Option<Status>,
@@ -118,6 +332,8 @@ pub struct Bus {
 
     core0_exclusive: Option<u32>, // address
     core1_exclusive: Option<u32>, // address
+    /// Master last granted each port, the others come first on the next contention
+    last_granted: [Requestor; PerformanceEventSource::COUNT],
 }
 
 impl Default for Bus {
@@ -127,12 +343,25 @@ impl Default for Bus {
             rom: GenericMemory::default(),
             flash: GenericMemory::default(),
             peripherals: Peripherals::default(),
+            sram_init: InitPattern::default(),
+            sram_shadow: None,
+            uninitialized_reads: Vec::new(),
+            core_pc: [0; 2],
+            dma_channel: 0,
+            watches: Vec::new(),
+            breakpoints: Vec::new(),
+            breakpoint_hit: None,
+            store_log: None,
+            pmp: [None; 2],
+            statistics: BusStatistics::default(),
+            peripheral_accesses: 0,
             dma_write_access: None,
             dma_read_access: None,
             core0_access: None,
             core1_access: None,
             core0_exclusive: None,
             core1_exclusive: None,
+            last_granted: [Requestor::DmaW; PerformanceEventSource::COUNT],
         };
 
         res.set_rom(*include_bytes!("../bootrom-combined.bin"));
@@ -146,6 +375,7 @@ impl Bus {
     pub const XIP: u32 = 0x1000_0000;
     pub const SRAM: u32 = 0x2000_0000;
     pub const ABP: u32 = 0x4000_0000;
+    pub const BOOTRAM: u32 = 0x400e_0000;
     pub const AHB: u32 = 0x5000_0000;
     pub const SIO: u32 = 0xd000_0000;
     pub const CORTEX_M33_PRIVATE_REGISTERS: u32 = 0xe0000000;
@@ -164,6 +394,17 @@ impl Bus {
 
     pub fn reset(&mut self) {
         self.sram = GenericMemory::default();
+        self.sram_init.fill(self.sram.as_mut());
+        if let Some(shadow) = self.sram_shadow.as_mut() {
+            shadow.clear();
+        }
+        self.uninitialized_reads.clear();
+        if let Some(log) = self.store_log.as_mut() {
+            log.clear();
+        }
+        self.statistics = BusStatistics::default();
+        self.breakpoint_hit = None;
+        self.pmp = [None; 2];
         self.peripherals.reset();
         self.dma_write_access = None;
         self.dma_read_access = None;
@@ -171,12 +412,432 @@ impl Bus {
         self.core1_access = None;
         self.core0_exclusive = None;
         self.core1_exclusive = None;
+        self.last_granted = [Requestor::DmaW; PerformanceEventSource::COUNT];
+    }
+
+    /// Reset everything except the content of the SRAM, which is retained over a chip reset
+    pub fn reset_retain_sram(&mut self) {
+        let sram = core::mem::take(&mut self.sram);
+        let shadow = self.sram_shadow.take();
+        let store_log = self.store_log.take();
+        self.reset();
+        self.sram = sram;
+        self.sram_shadow = shadow;
+        self.store_log = store_log;
+    }
+
+    /// Set the content of the SRAM after reset, applied immediately
+    pub fn set_sram_init(&mut self, pattern: InitPattern) {
+        self.sram_init = pattern;
+        self.sram_init.fill(self.sram.as_mut());
+    }
+
+    /// Track stores into the SRAM to report loads from never written addresses.
+    /// Everything written before enabling it is considered as uninitialized.
+    pub fn track_uninitialized_reads(&mut self, enable: bool) {
+        self.sram_shadow = enable.then(|| InitializedMap::new(520 * KB));
+        self.uninitialized_reads.clear();
+    }
+
+    pub fn is_tracking_uninitialized_reads(&self) -> bool {
+        self.sram_shadow.is_some()
+    }
+
+    /// PC of the instruction a core is about to execute,
+    /// used to tell which instruction is responsible for a finding
+    pub(crate) fn set_core_pc(&mut self, core: usize, pc: u32) {
+        self.core_pc[core] = pc;
+    }
+
+    /// PMP configuration of a Hazard3 core, applied to its following accesses
+    pub(crate) fn set_pmp(&mut self, core: usize, pmp: Pmp) {
+        self.pmp[core] = Some(pmp);
+    }
+
+    /// Only the Hazard3 cores have a PMP, for them `secure` means running in M-mode
+    fn pmp_allows(&self, address: u32, access: PmpAccess, ctx: &BusAccessContext) -> bool {
+        let core = match ctx.requestor {
+            Requestor::Proc0 => 0,
+            Requestor::Proc1 => 1,
+            Requestor::DmaR | Requestor::DmaW => return true,
+        };
+
+        let Some(pmp) = self.pmp[core].as_ref() else {
+            return true;
+        };
+
+        if ctx.architecture != ArchitectureType::Hazard3 {
+            return true;
+        }
+
+        let privilege = match ctx.secure {
+            true => PrivilegeMode::Machine,
+            false => PrivilegeMode::User,
+        };
+
+        pmp.check(address, ctx.size as u32, access, privilege)
+    }
+
+    fn pmp_fault(&self, address: u32, ctx: &BusAccessContext) -> BusError {
+        self.inspector().emit(InspectionEvent::BusError {
+            error: BusError::PmpFault,
+            requestor: ctx.requestor,
+            size: ctx.size,
+            address,
+        });
+
+        BusError::PmpFault
+    }
+
+    /// ACCESSCTRL filters the memories when the access is issued
+    fn memory_allowed(&mut self, address: u32, ctx: &BusAccessContext) -> bool {
+        match address & 0xF000_0000 {
+            Self::ROM | Self::SRAM | Self::XIP => self.peripherals.accessctrl.check(
+                address,
+                ctx.requestor,
+                ctx.secure,
+                ctx.privileged,
+            ),
+            _ => true,
+        }
+    }
+
+    /// ACCESSCTRL filters the peripherals when the access reaches them, a peripheral
+    /// held in reset faults as well
+    fn peripheral_allowed(&mut self, address: u32, ctx: &BusAccessContext) -> bool {
+        let accessctrl = &mut self.peripherals.accessctrl;
+
+        accessctrl.check(address, ctx.requestor, ctx.secure, ctx.privileged)
+            && !self.peripherals.in_reset(address)
+    }
+
+    /// Channel whose write the DMA is about to issue
+    pub(crate) fn set_dma_channel(&mut self, channel: u8) {
+        self.dma_channel = channel;
+    }
+
+    /// Record every completed store with the instruction or DMA channel behind it,
+    /// to answer [`Bus::last_writer`]. Disabling it drops the recorded stores
+    pub fn record_stores(&mut self, enable: bool) {
+        self.store_log = enable.then(StoreLog::new);
+    }
+
+    pub fn is_recording_stores(&self) -> bool {
+        self.store_log.is_some()
+    }
+
+    pub fn store_log(&self) -> Option<&StoreLog> {
+        self.store_log.as_ref()
+    }
+
+    /// The most recent recorded store to the byte at `address` at or before `tick`
+    pub fn last_writer(&self, address: u32, tick: u64) -> Option<&StoreRecord> {
+        self.store_log.as_ref()?.last_writer(address, tick)
+    }
+
+    fn store_source(&self, requestor: Requestor) -> StoreSource {
+        match requestor {
+            Requestor::Proc0 => StoreSource::Core {
+                core: 0,
+                pc: self.core_pc[0],
+            },
+            Requestor::Proc1 => StoreSource::Core {
+                core: 1,
+                pc: self.core_pc[1],
+            },
+            Requestor::DmaR | Requestor::DmaW => StoreSource::Dma {
+                channel: self.dma_channel,
+            },
+        }
+    }
+
+    fn mark_sram_initialized(&mut self, address: u32, len: usize) {
+        if let Some(shadow) = self.sram_shadow.as_mut() {
+            shadow.mark(address - Self::SRAM, len);
+        }
+    }
+
+    fn check_sram_initialized(&mut self, address: u32, ctx: &BusAccessContext) {
+        let Some(shadow) = self.sram_shadow.as_ref() else {
+            return;
+        };
+
+        if address & 0xF000_0000 != Self::SRAM
+            || shadow.is_initialized(address - Self::SRAM, ctx.size as usize)
+        {
+            return;
+        }
+
+        let pc = match ctx.requestor {
+            Requestor::Proc0 => Some(self.core_pc[0]),
+            Requestor::Proc1 => Some(self.core_pc[1]),
+            Requestor::DmaR | Requestor::DmaW => None,
+        };
+
+        self.inspector().emit(InspectionEvent::UninitializedRead {
+            requestor: ctx.requestor,
+            size: ctx.size,
+            address,
+            pc,
+        });
+
+        if self.uninitialized_reads.len() < MAX_UNINITIALIZED_READS {
+            self.uninitialized_reads.push(UninitializedRead {
+                requestor: ctx.requestor,
+                address,
+                size: ctx.size,
+                pc,
+            });
+        }
+    }
+
+    /// Drop the pending transactions and exclusive monitors of both cores.
+    /// DMA transactions are kept, the DMA would wait forever on a cancelled one
+    pub fn cancel_core_transactions(&mut self) {
+        self.core0_access = None;
+        self.core1_access = None;
+        self.core0_exclusive = None;
+        self.core1_exclusive = None;
+    }
+
+    /// Emit [`InspectionEvent::WatchedWrite`] with the old and new value on every store
+    /// that touches the range, the `id` is handed back in the events.
+    /// Only the SRAM and the flash can be watched, the watches survive the resets
+    pub fn watch_region(&mut self, range: Range<u32>, id: u32) {
+        self.watches.push(WatchRegion { range, id });
+    }
+
+    pub fn unwatch_region(&mut self, id: u32) {
+        self.watches.retain(|watch| watch.id != id);
+    }
+
+    pub fn watched_regions(&self) -> &[WatchRegion] {
+        &self.watches
+    }
+
+    /// Stop on the loads, the stores or both touching the range, of the cores and the DMA
+    /// alike. The access still happens, the hit is held until [`Bus::take_breakpoint_hit`]
+    /// for the simulation loop to pause. The breakpoints survive the resets
+    pub fn add_memory_breakpoint(&mut self, range: Range<u32>, on: BreakOn, id: u32) {
+        self.breakpoints.push(MemoryBreakpoint { range, on, id });
+    }
+
+    pub fn remove_memory_breakpoint(&mut self, id: u32) {
+        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+    }
+
+    pub fn memory_breakpoints(&self) -> &[MemoryBreakpoint] {
+        &self.breakpoints
+    }
+
+    /// First access which hit a memory breakpoint since the last call
+    pub fn take_breakpoint_hit(&mut self) -> Option<BreakpointHit> {
+        self.breakpoint_hit.take()
+    }
+
+    fn check_breakpoints(&mut self, address: u32, write: bool, ctx: &BusAccessContext) {
+        let Some(breakpoint) = self.breakpoints.iter().find(|breakpoint| {
+            breakpoint.on.matches(write)
+                && breakpoint.range.start < address + ctx.size as u32
+                && address < breakpoint.range.end
+        }) else {
+            return;
+        };
+
+        let hit = BreakpointHit {
+            id: breakpoint.id,
+            requestor: ctx.requestor,
+            address,
+            size: ctx.size,
+            write,
+        };
+
+        self.inspector().emit(InspectionEvent::MemoryBreakpoint {
+            id: hit.id,
+            requestor: hit.requestor,
+            size: hit.size,
+            address,
+            write,
+        });
+
+        self.breakpoint_hit.get_or_insert(hit);
+    }
+
+    /// Read a memory without going through a transaction, the peripherals are not peeked
+    /// as reading them may have side effects
+    pub fn peek_slice(&self, address: u32, len: usize) -> Option<&[u8]> {
+        let (memory, offset): (&[u8], u32) = match address & 0xF000_0000 {
+            Self::SRAM => (self.sram.as_ref(), address - Self::SRAM),
+            Self::XIP => (self.flash.as_ref(), address & XIP_ADDRESS_MASK),
+            _ => return None,
+        };
+
+        let offset = offset as usize;
+        memory.get(offset..offset.checked_add(len)?)
+    }
+
+    fn peek(&self, address: u32, size: DataSize) -> Option<u32> {
+        let bytes = self.peek_slice(address, size as usize)?;
+        Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0, |acc, &byte| (acc << 8) | byte as u32),
+        )
+    }
+
+    /// Value before a store, only when the store touches a watched region
+    fn watched_value(&self, address: u32, size: DataSize) -> Option<u32> {
+        // nothing to do for the full speed runs
+        if self.watches.is_empty() {
+            return None;
+        }
+
+        self.watches
+            .iter()
+            .any(|watch| watch.overlaps(address, size))
+            .then(|| self.peek(address, size))
+            .flatten()
+    }
+
+    fn notify_watches(&self, address: u32, old: u32, ctx: &BusAccessContext) {
+        let Some(new) = self.peek(address, ctx.size) else {
+            return;
+        };
+
+        for watch in self.watches.iter() {
+            if watch.overlaps(address, ctx.size) {
+                self.inspector().emit(InspectionEvent::WatchedWrite {
+                    id: watch.id,
+                    requestor: ctx.requestor,
+                    size: ctx.size,
+                    address,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+
+    /// Context of the accesses of a debugger, a secure privileged access of core 0
+    fn debug_context() -> BusAccessContext {
+        BusAccessContext {
+            secure: true,
+            privileged: true,
+            ..Default::default()
+        }
+    }
+
+    /// Write a word outside of the transactions of the requestors, like a debugger does.
+    /// It passes the access control as a secure access of core 0, and counts as a
+    /// peripheral access like the ones of the cores
+    pub fn debug_write(&mut self, address: u32, value: u32) -> BusResult<()> {
+        let ctx = Self::debug_context();
+        self.count_peripheral_access(address, &ctx);
+        self.write_u32(address & !0b11, value, ctx)
+    }
+
+    /// Byte write of a debugger, see [`Bus::debug_write`]
+    pub fn debug_write_u8(&mut self, address: u32, value: u8) -> BusResult<()> {
+        let ctx = Self::debug_context();
+        self.count_peripheral_access(address, &ctx);
+        self.write_u8(address, value as u32, ctx)
+    }
+
+    /// Read a word like a debugger does, the reads of a peripheral keep their side effects
+    pub fn debug_read(&mut self, address: u32) -> BusResult<u32> {
+        let ctx = Self::debug_context();
+        self.count_peripheral_access(address, &ctx);
+        self.read_u32(address & !0b11, ctx)
     }
 
     fn inspector(&self) -> &InspectorRef {
         &self.peripherals.inspector
     }
 
+    /// Write a word, of which only the bytes in `mask` are meant to be written when it comes
+    /// from a narrower store. Peripherals are given the mask, the memories ignore it
+    fn write_u32_masked(
+        &mut self,
+        address: u32,
+        value: u32,
+        mask: Option<u32>,
+        ctx: BusAccessContext,
+    ) -> BusResult<()> {
+        if !self.is_address_free(address, &ctx) {
+            return Err(BusError::ConcurrentAccess);
+        }
+
+        // Exclusive write will unlock the address of that requestor
+        // normal write will not unlock the address even if exclusive is set for that address
+        if ctx.exclusive {
+            match ctx.requestor {
+                Requestor::Proc0 => self.core0_exclusive = None,
+                Requestor::Proc1 => self.core1_exclusive = None,
+                Requestor::DmaR | Requestor::DmaW => unreachable!(),
+            }
+        }
+
+        match address & 0xF000_0000 {
+            Self::ROM => (),
+            Self::SRAM => {
+                self.sram.write_u32(address - Self::SRAM, value)?;
+                self.mark_sram_initialized(address, 4);
+            }
+            Self::XIP => self.flash.write_u32(address & XIP_ADDRESS_MASK, value)?,
+            _ => {
+                if !self.peripheral_allowed(address, &ctx) {
+                    return Err(BusError::BusFault);
+                }
+
+                let mut peri_ctx = self
+                    .peripherals
+                    .get_context(address, ctx.requestor, ctx.secure);
+                peri_ctx.write_mask = mask;
+
+                self.peripherals
+                    .find_mut(address, ctx.requestor)
+                    .ok_or(BusError::BusFault)?
+                    .write(address as u16, value, &peri_ctx)?;
+
+                self.serve_flash(address);
+                self.derive_clocks(address);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The clock domains follow the oscillators, the PLLs and the clock generators as the
+    /// firmware programs them
+    fn derive_clocks(&self, address: u32) {
+        match address & 0xFFFF_C000 {
+            // CLOCKS, XOSC, PLL_SYS, PLL_USB and ROSC
+            0x4001_0000 | 0x4004_8000 | 0x4005_0000 | 0x4005_8000 | 0x400E_8000 => {
+                self.peripherals.update_frequencies()
+            }
+            _ => {}
+        }
+    }
+
+    /// The flash is only reachable through the direct mode of the QMI and the stream of
+    /// XIP_CTRL, they are handed the memory once a write may have started a transfer
+    fn serve_flash(&mut self, address: u32) {
+        match address & 0xFFFF_C000 {
+            0x400C_8000 => self
+                .peripherals
+                .xip_ctrl
+                .borrow_mut()
+                .fill_stream(&self.flash),
+            0x400D_0000 => self
+                .peripherals
+                .xip_qmi
+                .borrow_mut()
+                .transfer(self.flash.as_mut()),
+            _ => {}
+        }
+    }
+
     pub fn set_rom(&mut self, data: [u8; 32 * KB]) {
         self.rom = GenericMemory::new(&data);
     }
@@ -186,39 +847,157 @@ impl Bus {
             data = &data[..(520 * KB)]; // truncate to 520KB
         }
 
+        self.mark_sram_initialized(Self::SRAM, data.len());
+
         if let Err(why) = self.sram.write_slice(0, data) {
             log::error!("Failed to write SRAM: {why:?}");
         }
     }
 
+    pub fn reset_statistics(&mut self) {
+        self.statistics = BusStatistics::default();
+    }
+
     pub fn tick(&mut self) {
-        let mut core0_access = self.core0_access.take();
-        let mut core1_access = self.core1_access.take();
-        let mut dma_read_access = self.dma_read_access.take();
-        let mut dma_write_access = self.dma_write_access.take();
-
-        self.update_status(&mut core0_access);
-        self.update_status(&mut core1_access);
-        self.update_status(&mut dma_read_access);
-        self.update_status(&mut dma_write_access);
+        self.statistics.ticks += 1;
+
+        let mut accesses = [
+            self.core0_access.take(),
+            self.core1_access.take(),
+            self.dma_read_access.take(),
+            self.dma_write_access.take(),
+        ];
+
+        self.arbitrate(&mut accesses);
+
+        if self.peripherals.busctrl.is_counting() {
+            self.count_stalls(&accesses);
+        }
+
+        for access in accesses.iter_mut() {
+            self.update_status(access);
+        }
+
+        let [core0_access, core1_access, dma_read_access, dma_write_access] = accesses;
         self.core0_access = core0_access;
         self.core1_access = core1_access;
         self.dma_read_access = dma_read_access;
         self.dma_write_access = dma_write_access;
     }
 
+    /// Whether `master` wins a port over `other`, the masters with their BUSCTRL priority
+    /// bit set first, then in turn starting after the last one granted the port
+    fn wins(&self, master: Requestor, other: Requestor, port: PerformanceEventSource) -> bool {
+        let busctrl = &self.peripherals.busctrl;
+        let last = self.last_granted[port as usize] as usize;
+        let rank = |requestor: Requestor| {
+            let turn = (requestor as usize + 3 - last) % 4;
+            (!busctrl.has_priority(requestor), turn)
+        };
+
+        rank(master) < rank(other)
+    }
+
+    /// Grant the ports of the bus fabric to the pending accesses, a port serves one access
+    /// until it completes and the others on it stall
+    fn arbitrate(&mut self, accesses: &mut [Option<Status>; 4]) {
+        for index in 0..accesses.len() {
+            let Some(status) = &accesses[index] else {
+                continue;
+            };
+
+            if status.granted {
+                continue;
+            }
+
+            let (port, master) = (status.port, status.ctx.requestor);
+            // the accesses no performance counter covers, like the DMA on the XIP, are not
+            // arbitrated
+            let shared = port != PerformanceEventSource::Reserved;
+
+            let contends = |(other, access): (usize, &Option<Status>)| match access {
+                Some(access) if other != index && access.port == port => {
+                    access.granted || self.wins(access.ctx.requestor, master, port)
+                }
+                _ => false,
+            };
+
+            let blocked = shared && accesses.iter().enumerate().any(contends);
+
+            let Some(status) = accesses[index].as_mut() else {
+                continue;
+            };
+
+            if blocked {
+                status.contested = true;
+                self.statistics.requestors[master as usize].contention_stalls += 1;
+
+                if let Some(bank) = sram_bank(status.address) {
+                    self.statistics.sram_banks[bank].contention_stalls += 1;
+                }
+            } else {
+                status.granted = true;
+                self.last_granted[port as usize] = master;
+            }
+        }
+    }
+
+    /// Count the stalls of the ports in the BUSCTRL performance counters, once per cycle
+    /// for each port: upstream for any master waiting, downstream for a port waiting on
+    /// the memory or the peripheral behind it
+    fn count_stalls(&mut self, accesses: &[Option<Status>; 4]) {
+        let mut counted = 0u32;
+
+        for status in accesses.iter().flatten() {
+            let downstream = status.granted && status.wait_cycles > 1;
+            let bit = 1 << status.port as u32;
+
+            if (!status.granted || downstream) && counted & bit == 0 {
+                counted |= bit;
+                let busctrl = &mut self.peripherals.busctrl;
+                busctrl.count_source(status.port, PerformanceEventType::StallUpstream);
+            }
+
+            if downstream {
+                let busctrl = &mut self.peripherals.busctrl;
+                busctrl.count_source(status.port, PerformanceEventType::StallDownstream);
+            }
+        }
+    }
+
+    /// Count a completed access in the statistics and the BUSCTRL performance counters
+    fn count_access(&mut self, status: &Status) {
+        if let Some(bank) = sram_bank(status.address) {
+            self.statistics.sram_banks[bank].accesses += 1;
+        }
+
+        let busctrl = &mut self.peripherals.busctrl;
+        busctrl.count_source(status.port, PerformanceEventType::Access);
+
+        if status.contested {
+            busctrl.count_source(status.port, PerformanceEventType::AccessContested);
+        }
+    }
+
     fn update_status(&mut self, target_status: &mut Option<Status>) {
         let Some(mut status) = target_status.take() else {
             return;
         };
 
+        status.elapsed += 1;
+
+        if !status.granted {
+            *target_status = Some(status);
+            return;
+        }
+
         if status.wait_cycles > 1 {
             status.wait_cycles -= 1;
             *target_status = Some(status);
             return;
         }
 
-        match status.status {
+        match &status.status {
             StatusType::Load(load_status) => {
                 let result = match status.ctx.size {
                     DataSize::Byte => self.read_u8(status.address, status.ctx).map(|v| {
@@ -239,6 +1018,16 @@ impl Bus {
                     DataSize::Word => self.read_u32(status.address, status.ctx),
                 };
 
+                if result != Err(BusError::ConcurrentAccess) {
+                    self.count_access(&status);
+                    self.statistics.requestors[status.ctx.requestor as usize].record(
+                        true,
+                        status.ctx.size,
+                        status.elapsed,
+                        result.is_ok(),
+                    );
+                }
+
                 *load_status.borrow_mut() = match result {
                     Ok(v) if status.ctx.exclusive => LoadStatus::ExclusiveDone(v),
                     Ok(v) => LoadStatus::Done(v),
@@ -256,11 +1045,41 @@ impl Bus {
             }
 
             StatusType::Store(value, store_status) => {
+                let value = *value;
+                let old = self.watched_value(status.address, status.ctx.size);
                 let result = match status.ctx.size {
                     DataSize::Byte => self.write_u8(status.address, value, status.ctx),
                     DataSize::HalfWord => self.write_u16(status.address, value, status.ctx),
                     DataSize::Word => self.write_u32(status.address, value, status.ctx),
                 };
+
+                if result != Err(BusError::ConcurrentAccess) {
+                    self.count_access(&status);
+                    self.statistics.requestors[status.ctx.requestor as usize].record(
+                        false,
+                        status.ctx.size,
+                        status.elapsed,
+                        result.is_ok(),
+                    );
+                }
+
+                if let (Ok(_), Some(old)) = (&result, old) {
+                    self.notify_watches(status.address, old, &status.ctx);
+                }
+
+                if let (Ok(_), Some(source)) = (&result, status.source) {
+                    let tick = *self.peripherals.clock().ticks.borrow();
+                    if let Some(log) = self.store_log.as_mut() {
+                        log.record(StoreRecord {
+                            tick,
+                            address: status.address,
+                            size: status.ctx.size,
+                            value,
+                            source,
+                        });
+                    }
+                }
+
                 *store_status.borrow_mut() = match result {
                     Ok(_) if status.ctx.exclusive => StoreStatus::ExclusiveDone,
                     Ok(_) => StoreStatus::Done,
@@ -279,16 +1098,18 @@ impl Bus {
         }
     }
 
-    pub fn fetch(&mut self, address: u32) -> BusResult<u32> {
+    pub fn fetch(&mut self, address: u32, ctx: BusAccessContext) -> BusResult<u32> {
+        if !self.pmp_allows(address, PmpAccess::Execute, &ctx) {
+            return Err(self.pmp_fault(address, &ctx));
+        }
+
         let base_address = address & 0xF000_0000;
+        let executable = matches!(base_address, Self::ROM | Self::SRAM | Self::XIP);
 
-        if (base_address != Self::ROM)
-            && (base_address != Self::SRAM)
-            && (base_address != Self::XIP)
-        {
+        if !executable || !self.memory_allowed(address, &ctx) {
             self.inspector().emit(InspectionEvent::BusError {
                 error: BusError::BusFault,
-                requestor: Requestor::Proc0,
+                requestor: ctx.requestor,
                 size: DataSize::Word,
                 address,
             });
@@ -305,7 +1126,7 @@ impl Bus {
         result.map_err(|_| {
             self.inspector().emit(InspectionEvent::BusError {
                 error: BusError::BusFault,
-                requestor: Requestor::Proc0,
+                requestor: ctx.requestor,
                 size: DataSize::Word,
                 address,
             });
@@ -319,15 +1140,18 @@ impl Bus {
         address: u32,
         ctx: BusAccessContext,
     ) -> BusResult<Rc<RefCell<LoadStatus>>> {
-        // TODO: counter
         self.inspector().emit(InspectionEvent::BusLoad {
             requestor: ctx.requestor,
             size: ctx.size,
             address,
         });
 
+        if !self.pmp_allows(address, PmpAccess::Read, &ctx) {
+            return Err(self.pmp_fault(address, &ctx));
+        }
+
         // check for address correctness
-        if !self.is_valid_address(address, &ctx) {
+        if !self.is_valid_address(address, &ctx) || !self.memory_allowed(address, &ctx) {
             self.inspector().emit(InspectionEvent::BusError {
                 error: BusError::BusFault,
                 requestor: ctx.requestor,
@@ -338,13 +1162,22 @@ impl Bus {
             return Err(BusError::BusFault);
         }
 
+        self.check_sram_initialized(address, &ctx);
+        self.check_breakpoints(address, false, &ctx);
+        self.count_peripheral_access(address, &ctx);
+
         let load_status = Rc::new(RefCell::new(LoadStatus::Waiting));
 
         let status = Status {
             ctx,
             address,
+            port: PerformanceEventSource::from_address(address, ctx.requestor),
+            granted: false,
+            contested: false,
             wait_cycles: self.address_cycle(address).0,
+            elapsed: 0,
             status: StatusType::Load(Rc::clone(&load_status)),
+            source: None,
         };
 
         match ctx.requestor {
@@ -357,6 +1190,20 @@ impl Bus {
         Ok(load_status)
     }
 
+    /// Loads and stores issued to the peripherals so far. Any access of the DMA counts,
+    /// the memories it fills are not the work of the cores
+    pub fn peripheral_accesses(&self) -> u64 {
+        self.peripheral_accesses
+    }
+
+    fn count_peripheral_access(&mut self, address: u32, ctx: &BusAccessContext) {
+        let memory = matches!(address & 0xF000_0000, Self::ROM | Self::SRAM | Self::XIP);
+
+        if !memory || matches!(ctx.requestor, Requestor::DmaR | Requestor::DmaW) {
+            self.peripheral_accesses += 1;
+        }
+    }
+
     /// Call by a store instruction
     pub fn store(
         &mut self,
@@ -364,7 +1211,6 @@ impl Bus {
         value: u32,
         ctx: BusAccessContext,
     ) -> BusResult<Rc<RefCell<StoreStatus>>> {
-        // TODO: counter
         self.inspector().emit(InspectionEvent::BusStore {
             requestor: ctx.requestor,
             size: ctx.size,
@@ -372,8 +1218,12 @@ impl Bus {
             value,
         });
 
+        if !self.pmp_allows(address, PmpAccess::Write, &ctx) {
+            return Err(self.pmp_fault(address, &ctx));
+        }
+
         // check for address correctness
-        if !self.is_valid_address(address, &ctx) {
+        if !self.is_valid_address(address, &ctx) || !self.memory_allowed(address, &ctx) {
             self.inspector().emit(InspectionEvent::BusError {
                 error: BusError::BusFault,
                 requestor: ctx.requestor,
@@ -383,13 +1233,24 @@ impl Bus {
             return Err(BusError::BusFault);
         }
 
+        self.check_breakpoints(address, true, &ctx);
+        self.count_peripheral_access(address, &ctx);
+
         let store_status = Rc::new(RefCell::new(StoreStatus::Waiting));
 
         let status = Status {
             ctx,
             address,
+            port: PerformanceEventSource::from_address(address, ctx.requestor),
+            granted: false,
+            contested: false,
             wait_cycles: self.address_cycle(address).1,
+            elapsed: 0,
             status: StatusType::Store(value, Rc::clone(&store_status)),
+            source: self
+                .store_log
+                .is_some()
+                .then(|| self.store_source(ctx.requestor)),
         };
 
         match ctx.requestor {
@@ -421,6 +1282,9 @@ impl Bus {
 
         match address & 0xF000_0000 {
             Self::ROM | Self::SRAM | Self::XIP => true,
+            // the Private Peripheral Bus is inside each Cortex-M33, the cores decode it
+            // before the bus and the other masters cannot reach it
+            Self::CORTEX_M33_PRIVATE_REGISTERS => false,
             _ => self.peripherals.find(address, ctx.requestor).is_some(),
         }
     }
@@ -466,6 +1330,10 @@ impl Bus {
             Self::SRAM => Ok(self.sram.read_u32(address - Self::SRAM)?),
             Self::XIP => Ok(self.flash.read_u32(address & XIP_ADDRESS_MASK)?),
             _ => {
+                if !self.peripheral_allowed(address, &ctx) {
+                    return Err(BusError::BusFault);
+                }
+
                 let peri_ctx = self
                     .peripherals
                     .get_context(address, ctx.requestor, ctx.secure);
@@ -475,44 +1343,13 @@ impl Bus {
                     .ok_or(BusError::BusFault)?
                     .read((address as u16) & 0xFFF, &peri_ctx)
                     .inspect_err(|e| log::error!("Peripherals Error at 0x{:X}: {:?}", address, e))
-                    .map_err(|_| BusError::BusFault)
+                    .map_err(BusError::from)
             }
         }
     }
 
     fn write_u32(&mut self, address: u32, value: u32, ctx: BusAccessContext) -> BusResult<()> {
-        if !self.is_address_free(address, &ctx) {
-            return Err(BusError::ConcurrentAccess);
-        }
-
-        // Exclusive write will unlock the address of that requestor
-        // normal write will not unlock the address even if exclusive is set for that address
-        if ctx.exclusive {
-            match ctx.requestor {
-                Requestor::Proc0 => self.core0_exclusive = None,
-                Requestor::Proc1 => self.core1_exclusive = None,
-                Requestor::DmaR | Requestor::DmaW => unreachable!(),
-            }
-        }
-
-        match address & 0xF000_0000 {
-            Self::ROM => (),
-            Self::SRAM => self.sram.write_u32(address - Self::SRAM, value)?,
-            Self::XIP => self.flash.write_u32(address & XIP_ADDRESS_MASK, value)?,
-            _ => {
-                let peri_ctx = self
-                    .peripherals
-                    .get_context(address, ctx.requestor, ctx.secure);
-
-                self.peripherals
-                    .find_mut(address, ctx.requestor)
-                    .ok_or(BusError::BusFault)?
-                    .write(address as u16, value, &peri_ctx)
-                    .map_err(|_| BusError::BusFault)?
-            }
-        }
-
-        Ok(())
+        self.write_u32_masked(address, value, None, ctx)
     }
 
     fn read_u16(&mut self, address: u32, ctx: BusAccessContext) -> BusResult<u16> {
@@ -534,16 +1371,16 @@ impl Bus {
     fn write_u16(&mut self, address: u32, value: u32, ctx: BusAccessContext) -> BusResult<()> {
         match address & 0xF000_0000 {
             Self::ROM => (),
-            Self::SRAM => self.sram.write_u16(address - Self::SRAM, value as u16)?,
+            Self::SRAM => {
+                self.sram.write_u16(address - Self::SRAM, value as u16)?;
+                self.mark_sram_initialized(address, 2);
+            }
             Self::XIP => self.flash.write_u16(address & 0x00FF_FFFF, value as u16)?,
             _ => {
-                let value = if (address & 0b11) == 0 {
-                    value & 0x0000_FFFF
-                } else {
-                    (value as u32) << 16
-                };
+                let shift = (address & 0b10) * 8;
+                let value = (value & 0x0000_FFFF) << shift;
 
-                self.write_u32(address & !0b11, value, ctx)?
+                self.write_u32_masked(address & !0b11, value, Some(0xFFFF << shift), ctx)?
             }
         }
 
@@ -566,21 +1403,18 @@ impl Bus {
     fn write_u8(&mut self, address: u32, value: u32, ctx: BusAccessContext) -> BusResult<()> {
         match address & 0xF000_0000 {
             Self::ROM => (),
-            Self::SRAM => self.sram.write_u8(address - Self::SRAM, value as u8)?,
+            Self::SRAM => {
+                self.sram.write_u8(address - Self::SRAM, value as u8)?;
+                self.mark_sram_initialized(address, 1);
+            }
             Self::XIP => self
                 .flash
                 .write_u8(address & XIP_ADDRESS_MASK, value as u8)?,
             _ => {
-                let value = value & 0xFF;
-                let value = match address & 0b11 {
-                    0 => value << 0,
-                    1 => value << 8,
-                    2 => value << 16,
-                    3 => value << 24,
-                    _ => unreachable!(),
-                };
+                let shift = (address & 0b11) * 8;
+                let value = (value & 0xFF) << shift;
 
-                self.write_u32(address & !0b11, value, ctx)?
+                self.write_u32_masked(address & !0b11, value, Some(0xFF << shift), ctx)?
             }
         }
 
@@ -610,14 +1444,82 @@ mod tests {
         let value = 0x1234_5678;
         bus.write_u32(address, value, Default::default()).unwrap();
 
-        assert_eq!(bus.fetch(address), Ok(value));
+        assert_eq!(bus.fetch(address, Default::default()), Ok(value));
+    }
+
+    #[test]
+    fn debug_write() {
+        setup!(bus);
+        let ctx = BusAccessContext {
+            secure: true,
+            ..Default::default()
+        };
+
+        // TOP of the PWM slice 0
+        bus.debug_write(0x400a_8010, 999).unwrap();
+        assert_eq!(bus.read_u32(0x400a_8010, ctx), Ok(999));
+
+        bus.debug_write(Bus::SRAM + 2, 0x1234_5678).unwrap();
+        assert_eq!(bus.read_u32(Bus::SRAM, ctx), Ok(0x1234_5678));
+        assert_eq!(bus.debug_write(0x4fff_0000, 0), Err(BusError::BusFault));
+    }
+
+    #[test]
+    fn held_in_reset() {
+        setup!(bus);
+        let ctx = BusAccessContext {
+            secure: true,
+            privileged: true,
+            ..Default::default()
+        };
+
+        // hold the PWM in reset, then release it
+        bus.write_u32(0x4002_2000, 1 << 16, ctx).unwrap();
+        assert_eq!(bus.read_u32(0x4002_0008, ctx), Ok(0x1fff_ffff & !(1 << 16)));
+        assert_eq!(bus.read_u32(0x400a_8010, ctx), Err(BusError::BusFault));
+        assert!(bus.write_u32(0x400a_8010, 999, ctx).is_err());
+
+        bus.write_u32(0x4002_3000, 1 << 16, ctx).unwrap();
+        bus.write_u32(0x400a_8010, 999, ctx).unwrap();
+        assert_eq!(bus.read_u32(0x400a_8010, ctx), Ok(999));
+    }
+
+    #[test]
+    fn access_control() {
+        setup!(bus);
+        let secure = BusAccessContext {
+            secure: true,
+            privileged: true,
+            ..Default::default()
+        };
+        let user = BusAccessContext {
+            secure: true,
+            ..Default::default()
+        };
+
+        // RESETS is for Secure privileged code only
+        assert!(bus.read_u32(0x4002_0000, secure).is_ok());
+        assert_eq!(bus.read_u32(0x4002_0000, user), Err(BusError::BusFault));
+
+        // SRAM0 closed to the Non-secure accesses, the memories are filtered when issued
+        bus.write_u32(0x4006_001c, accessctrl::PASSWORD | 0xfc, secure)
+            .unwrap();
+        assert_eq!(
+            bus.load(Bus::SRAM, BusAccessContext::default()),
+            Err(BusError::BusFault)
+        );
+        assert!(bus.load(Bus::SRAM, user).is_ok());
+
+        let violation = bus.peripherals.accessctrl.last_violation.unwrap();
+        assert_eq!(violation.address, Bus::SRAM);
+        assert_eq!(violation.master, accessctrl::AccessMaster::Core0NonSecure);
     }
 
     #[test]
     fn fetch_error() {
         setup!(bus);
         let address = 0x4000_0000;
-        assert_eq!(bus.fetch(address), Err(BusError::BusFault));
+        assert_eq!(bus.fetch(address, Default::default()), Err(BusError::BusFault));
     }
 
     #[test]
@@ -633,4 +1535,253 @@ mod tests {
         bus.tick();
         assert_eq!(*status.borrow(), LoadStatus::Done(value));
     }
+
+    #[derive(Default)]
+    struct Events(RefCell<Vec<InspectionEvent>>);
+
+    impl crate::Inspector for Events {
+        fn handle_event(&self, event: InspectionEvent) {
+            self.0.borrow_mut().push(event);
+        }
+    }
+
+    #[test]
+    fn watch_region() {
+        let events = Rc::new(Events::default());
+        let mut inspector = InspectorRef::default();
+        inspector.set_inspector(events.clone());
+
+        let mut bus = Bus::new(
+            Rc::new(RefCell::new(GpioController::default())),
+            Rc::new(RefCell::new(Interrupts::default())),
+            Rc::new(Clock::new()),
+            inspector,
+        );
+        let ctx = BusAccessContext {
+            size: DataSize::HalfWord,
+            ..Default::default()
+        };
+
+        bus.watch_region(Bus::SRAM + 0x10..Bus::SRAM + 0x14, 7);
+
+        bus.store(Bus::SRAM + 0x12, 0xbeef, ctx).unwrap();
+        bus.tick();
+        bus.store(Bus::SRAM + 0x14, 0x1234, ctx).unwrap();
+        bus.tick();
+
+        let watched: Vec<_> = events
+            .0
+            .borrow()
+            .iter()
+            .filter_map(|event| match *event {
+                InspectionEvent::WatchedWrite {
+                    id,
+                    address,
+                    old,
+                    new,
+                    ..
+                } => Some((id, address, old, new)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(watched, [(7, Bus::SRAM + 0x12, 0, 0xbeef)]);
+
+        bus.unwatch_region(7);
+        assert!(bus.watched_regions().is_empty());
+    }
+
+    #[test]
+    fn memory_breakpoint() {
+        setup!(bus);
+        let dma = BusAccessContext {
+            requestor: Requestor::DmaW,
+            size: DataSize::HalfWord,
+            ..Default::default()
+        };
+
+        bus.add_memory_breakpoint(Bus::SRAM + 0x100..Bus::SRAM + 0x108, BreakOn::Write, 3);
+
+        bus.load(Bus::SRAM + 0x104, Default::default()).unwrap();
+        bus.store(Bus::SRAM + 0x108, 0x1234, dma).unwrap();
+        assert_eq!(bus.take_breakpoint_hit(), None);
+
+        bus.store(Bus::SRAM + 0x106, 0x1234, dma).unwrap();
+        assert_eq!(
+            bus.take_breakpoint_hit(),
+            Some(BreakpointHit {
+                id: 3,
+                requestor: Requestor::DmaW,
+                address: Bus::SRAM + 0x106,
+                size: DataSize::HalfWord,
+                write: true,
+            })
+        );
+        assert_eq!(bus.take_breakpoint_hit(), None);
+
+        bus.remove_memory_breakpoint(3);
+        assert!(bus.memory_breakpoints().is_empty());
+    }
+
+    #[test]
+    fn last_writer() {
+        let clock = Rc::new(Clock::new());
+        let mut bus = Bus::new(
+            Rc::new(RefCell::new(GpioController::default())),
+            Rc::new(RefCell::new(Interrupts::default())),
+            Rc::clone(&clock),
+            InspectorRef::default(),
+        );
+        bus.record_stores(true);
+
+        bus.set_core_pc(1, 0x1000_0100);
+        let ctx = BusAccessContext {
+            requestor: Requestor::Proc1,
+            ..Default::default()
+        };
+        bus.store(Bus::SRAM + 0x20, 0x1234_5678, ctx).unwrap();
+        clock.tick();
+        bus.tick();
+
+        bus.set_dma_channel(3);
+        let ctx = BusAccessContext {
+            requestor: Requestor::DmaW,
+            size: DataSize::Byte,
+            ..Default::default()
+        };
+        bus.store(Bus::SRAM + 0x21, 0xab, ctx).unwrap();
+        clock.tick();
+        bus.tick();
+
+        let source = |address, tick| bus.last_writer(address, tick).map(|r| r.source);
+        let core = StoreSource::Core {
+            core: 1,
+            pc: 0x1000_0100,
+        };
+
+        assert_eq!(source(Bus::SRAM + 0x21, 2), Some(StoreSource::Dma { channel: 3 }));
+        assert_eq!(source(Bus::SRAM + 0x21, 1), Some(core));
+        assert_eq!(source(Bus::SRAM + 0x20, 2), Some(core));
+        assert_eq!(source(Bus::SRAM + 0x24, 2), None);
+
+        bus.record_stores(false);
+        assert!(bus.last_writer(Bus::SRAM + 0x20, 2).is_none());
+    }
+
+    #[test]
+    fn statistics() {
+        setup!(bus);
+        let ctx = BusAccessContext {
+            requestor: Requestor::DmaW,
+            ..Default::default()
+        };
+
+        let status = bus.store(Bus::SRAM, 0x1234_5678, ctx).unwrap();
+        bus.tick();
+        assert_eq!(*status.borrow(), StoreStatus::Done);
+
+        let stats = bus.statistics.requestor(Requestor::DmaW);
+        assert_eq!(stats.stores, 1);
+        assert_eq!(stats.bytes, 4);
+        assert_eq!(stats.latency_histogram[0], 1);
+        assert_eq!(bus.statistics.ticks, 1);
+        assert_eq!(bus.statistics.dma_bytes_per_ms(1_000_000), 4000.0);
+
+        bus.reset_statistics();
+        assert_eq!(bus.statistics, BusStatistics::default());
+    }
+
+    #[test]
+    fn banks() {
+        assert_eq!(sram_bank(Bus::SRAM), Some(0));
+        assert_eq!(sram_bank(Bus::SRAM + 0x1c), Some(3));
+        assert_eq!(sram_bank(0x2004_0008), Some(6));
+        assert_eq!(sram_bank(0x2008_0ffc), Some(8));
+        assert_eq!(sram_bank(0x2008_1000), Some(9));
+        assert_eq!(sram_bank(0x2008_2000), None);
+    }
+
+    #[test]
+    fn arbitration() {
+        setup!(bus);
+        let core0 = BusAccessContext::default();
+        let core1 = BusAccessContext {
+            requestor: Requestor::Proc1,
+            ..Default::default()
+        };
+
+        // SRAM0 counters, the contested accesses and the stalled cycles
+        let ctx = PeripheralAccessContext::default();
+        let busctrl = &mut bus.peripherals.busctrl;
+        busctrl.write(0x08, 1, &ctx).unwrap();
+        busctrl.write(0x10, 13 << 2 | 2, &ctx).unwrap();
+        busctrl.write(0x18, 13 << 2, &ctx).unwrap();
+
+        // both in SRAM0, core 0 first then each in turn
+        let first = bus.load(Bus::SRAM, core0).unwrap();
+        let second = bus.load(Bus::SRAM + 0x10, core1).unwrap();
+        bus.tick();
+        assert!(first.borrow().is_done());
+        assert_eq!(*second.borrow(), LoadStatus::Waiting);
+        bus.tick();
+        assert!(second.borrow().is_done());
+
+        let first = bus.load(Bus::SRAM, core1).unwrap();
+        let second = bus.load(Bus::SRAM, core0).unwrap();
+        bus.tick();
+        assert_eq!(*first.borrow(), LoadStatus::Waiting);
+        assert!(second.borrow().is_done());
+        bus.tick();
+
+        // the priority of core 1 wins over the turn of core 0
+        let priority = BusCtrl::PRIORITY_PROC1;
+        bus.peripherals.busctrl.write(0, priority, &ctx).unwrap();
+        let first = bus.load(Bus::SRAM, core0).unwrap();
+        let second = bus.load(Bus::SRAM, core1).unwrap();
+        bus.tick();
+        assert_eq!(*first.borrow(), LoadStatus::Waiting);
+        assert!(second.borrow().is_done());
+        bus.tick();
+
+        // different banks are served at once
+        let first = bus.load(Bus::SRAM, core0).unwrap();
+        let second = bus.load(Bus::SRAM + 4, core1).unwrap();
+        bus.tick();
+        assert!(first.borrow().is_done() && second.borrow().is_done());
+        assert_eq!(bus.statistics.sram_banks[0].accesses, 7);
+        assert_eq!(bus.statistics.sram_banks[0].contention_stalls, 3);
+        assert_eq!(bus.statistics.sram_banks[1].accesses, 1);
+
+        assert_eq!(bus.peripherals.busctrl.read(0x0c, &ctx), Ok(3));
+        assert_eq!(bus.peripherals.busctrl.read(0x14, &ctx), Ok(3));
+        let stats = &bus.statistics;
+        assert_eq!(stats.requestor(Requestor::Proc0).contention_stalls, 1);
+        assert_eq!(stats.requestor(Requestor::Proc1).contention_stalls, 2);
+    }
+
+    #[test]
+    fn pmp() {
+        setup!(bus);
+        let user = BusAccessContext::default();
+        let machine = BusAccessContext {
+            secure: true,
+            ..Default::default()
+        };
+
+        // no PMP published yet
+        assert!(bus.load(Bus::SRAM, user).is_ok());
+
+        bus.set_pmp(0, Pmp::default());
+        assert_eq!(bus.load(Bus::SRAM, user), Err(BusError::PmpFault));
+        assert_eq!(bus.store(Bus::SRAM, 0, user), Err(BusError::PmpFault));
+        assert_eq!(bus.fetch(Bus::SRAM, user), Err(BusError::PmpFault));
+        assert!(bus.fetch(Bus::SRAM, machine).is_ok());
+
+        // the PMP of core 1 is not published
+        let core1 = BusAccessContext {
+            requestor: Requestor::Proc1,
+            ..Default::default()
+        };
+        assert!(bus.load(Bus::SRAM, core1).is_ok());
+    }
 }
@@ -0,0 +1,208 @@
+/**
+ * @file bus/pmp.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Physical memory protection of the Hazard3 cores, checked by the bus on every access
+ */
+use crate::processor::hazard3::PrivilegeMode;
+
+pub const NUM_PMP_REGIONS: usize = 16;
+
+// Fields of a region in PMPCFGx
+pub const PMPCFG_R: u8 = 1 << 0;
+pub const PMPCFG_W: u8 = 1 << 1;
+pub const PMPCFG_X: u8 = 1 << 2;
+pub const PMPCFG_L: u8 = 1 << 7;
+pub const PMPCFG_A_OFF: u8 = 0b00 << 3;
+pub const PMPCFG_A_TOR: u8 = 0b01 << 3;
+pub const PMPCFG_A_NA4: u8 = 0b10 << 3;
+pub const PMPCFG_A_NAPOT: u8 = 0b11 << 3;
+const PMPCFG_A_MASK: u8 = 0b11 << 3;
+
+/// PMPCFG2 is hardwired: regions 8 to 10 are NAPOT regions over the ROM, the peripherals
+/// and the SIO, open to U-mode, where ACCESSCTRL filters the accesses instead
+const HARDWIRED_CFG2: u32 = 0x001f_1f1f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PmpAccess {
+    Read,
+    Write,
+    Execute,
+}
+
+impl PmpAccess {
+    fn permission(self) -> u8 {
+        match self {
+            Self::Read => PMPCFG_R,
+            Self::Write => PMPCFG_W,
+            Self::Execute => PMPCFG_X,
+        }
+    }
+}
+
+/// Content of the PMP CSRs of a core
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pmp {
+    /// PMPCFG0..3, a byte per region
+    pub cfg: [u32; 4],
+    /// PMPADDR0..15, bits 33:2 of the addresses
+    pub addr: [u32; NUM_PMP_REGIONS],
+    /// PMPCFGM0, the regions also enforced in M-mode without being locked
+    pub m0: u32,
+}
+
+impl Default for Pmp {
+    fn default() -> Self {
+        let mut addr = [0; NUM_PMP_REGIONS];
+        addr[8] = 0x01ff_ffff; // 0x00000000..0x10000000
+        addr[9] = 0x13ff_ffff; // 0x40000000..0x60000000
+        addr[10] = 0x35ff_ffff; // 0xd0000000..0xe0000000
+
+        Self {
+            cfg: [0, 0, HARDWIRED_CFG2, 0],
+            addr,
+            m0: 0,
+        }
+    }
+}
+
+impl Pmp {
+    pub fn config(&self, region: usize) -> u8 {
+        (self.cfg[region / 4] >> (8 * (region % 4))) as u8
+    }
+
+    /// Byte range covered by a region, the end is exclusive
+    pub fn bounds(&self, region: usize) -> Option<(u64, u64)> {
+        let addr = self.addr[region] as u64;
+
+        match self.config(region) & PMPCFG_A_MASK {
+            PMPCFG_A_TOR => {
+                let lower = match region {
+                    0 => 0,
+                    _ => (self.addr[region - 1] as u64) << 2,
+                };
+
+                // an empty range matches nothing
+                (lower < addr << 2).then_some((lower, addr << 2))
+            }
+            PMPCFG_A_NA4 => Some((addr << 2, (addr << 2) + 4)),
+            PMPCFG_A_NAPOT => {
+                // the number of trailing ones gives the size, from 8 bytes
+                let ones = addr.trailing_ones();
+                let base = (addr & !((1 << ones) - 1)) << 2;
+                Some((base, base + (1 << (ones + 3))))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether an access of `size` bytes is allowed. The lowest numbered region
+    /// covering the access decides, an access only partially inside of it fails.
+    /// M-mode only obeys the locked regions and the ones selected in PMPCFGM0,
+    /// it is allowed when no region matches while U-mode is denied
+    pub fn check(
+        &self,
+        address: u32,
+        size: u32,
+        access: PmpAccess,
+        privilege: PrivilegeMode,
+    ) -> bool {
+        let start = address as u64;
+        let end = start + size.max(1) as u64;
+
+        for region in 0..NUM_PMP_REGIONS {
+            let Some((base, top)) = self.bounds(region) else {
+                continue;
+            };
+
+            if end <= base || start >= top {
+                continue;
+            }
+
+            if start < base || end > top {
+                return false;
+            }
+
+            let cfg = self.config(region);
+            let enforced = privilege != PrivilegeMode::Machine
+                || cfg & PMPCFG_L != 0
+                || self.m0 >> region & 1 != 0;
+
+            return !enforced || cfg & access.permission() != 0;
+        }
+
+        privilege == PrivilegeMode::Machine
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_config(pmp: &mut Pmp, region: usize, cfg: u8) {
+        let shift = 8 * (region % 4);
+        pmp.cfg[region / 4] &= !(0xff << shift);
+        pmp.cfg[region / 4] |= (cfg as u32) << shift;
+    }
+
+    #[test]
+    fn test_napot() {
+        let mut pmp = Pmp::default();
+
+        // 4 KB of SRAM at 0x20001000, read and execute only
+        pmp.addr[0] = (0x2000_1000 >> 2) | ((4096 >> 3) - 1);
+        set_config(&mut pmp, 0, PMPCFG_A_NAPOT | PMPCFG_R | PMPCFG_X);
+        assert_eq!(pmp.bounds(0), Some((0x2000_1000, 0x2000_2000)));
+
+        let user = PrivilegeMode::User;
+        assert!(pmp.check(0x2000_1000, 4, PmpAccess::Read, user));
+        assert!(pmp.check(0x2000_1ffc, 4, PmpAccess::Execute, user));
+        assert!(!pmp.check(0x2000_1000, 4, PmpAccess::Write, user));
+        assert!(!pmp.check(0x2000_2000, 4, PmpAccess::Read, user));
+        // straddles the end of the region
+        assert!(!pmp.check(0x2000_1ffe, 4, PmpAccess::Read, user));
+
+        // M-mode ignores the region until it is locked or selected in PMPCFGM0
+        let machine = PrivilegeMode::Machine;
+        assert!(pmp.check(0x2000_1000, 4, PmpAccess::Write, machine));
+        pmp.m0 = 1;
+        assert!(!pmp.check(0x2000_1000, 4, PmpAccess::Write, machine));
+        pmp.m0 = 0;
+        set_config(&mut pmp, 0, PMPCFG_A_NAPOT | PMPCFG_R | PMPCFG_L);
+        assert!(!pmp.check(0x2000_1000, 4, PmpAccess::Execute, machine));
+        assert!(pmp.check(0x2000_3000, 4, PmpAccess::Write, machine));
+    }
+
+    #[test]
+    fn test_tor() {
+        let mut pmp = Pmp::default();
+
+        // region 1 covers pmpaddr0..pmpaddr1
+        pmp.addr[0] = 0x1000_0000 >> 2;
+        pmp.addr[1] = 0x1000_8000 >> 2;
+        set_config(&mut pmp, 1, PMPCFG_A_TOR | PMPCFG_R | PMPCFG_X);
+        // region 2 shadows the start of region 3
+        pmp.addr[2] = 0x2000_0000 >> 2;
+        set_config(&mut pmp, 2, PMPCFG_A_NA4 | PMPCFG_R);
+        pmp.addr[3] = 0x2000_0100 >> 2;
+        set_config(&mut pmp, 3, PMPCFG_A_TOR | PMPCFG_R | PMPCFG_W);
+
+        let user = PrivilegeMode::User;
+        assert_eq!(pmp.bounds(1), Some((0x1000_0000, 0x1000_8000)));
+        assert!(pmp.check(0x1000_0000, 2, PmpAccess::Execute, user));
+        assert!(!pmp.check(0x1000_8000, 2, PmpAccess::Execute, user));
+        assert!(!pmp.check(0x2000_0000, 4, PmpAccess::Write, user));
+        assert!(pmp.check(0x2000_0004, 4, PmpAccess::Write, user));
+        assert!(!pmp.check(0x2000_0100, 1, PmpAccess::Read, user));
+
+        // the hardwired regions open the ROM, the peripherals and the SIO to U-mode
+        assert_eq!(pmp.bounds(8), Some((0x0000_0000, 0x1000_0000)));
+        assert_eq!(pmp.bounds(9), Some((0x4000_0000, 0x6000_0000)));
+        assert_eq!(pmp.bounds(10), Some((0xd000_0000, 0xe000_0000)));
+        assert!(pmp.check(0x0fff_fffc, 4, PmpAccess::Execute, user));
+        assert!(pmp.check(0x4001_4000, 4, PmpAccess::Write, user));
+        assert!(pmp.check(0xd000_0000, 4, PmpAccess::Read, user));
+        // and nothing past them
+        assert!(!pmp.check(0x6000_0000, 4, PmpAccess::Read, user));
+    }
+}
@@ -0,0 +1,136 @@
+/**
+ * @file bus/store_log.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Indexed record of the completed stores, to find who wrote an address
+ */
+use crate::common::DataSize;
+use std::collections::{HashMap, VecDeque};
+
+// Older writes of a word are forgotten past this, it bounds the memory of long runs
+pub const MAX_WRITES_PER_WORD: usize = 256;
+
+/// What issued a store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreSource {
+    /// `pc` is the address of the store instruction
+    Core { core: u8, pc: u32 },
+    Dma { channel: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoreRecord {
+    /// Tick of the clock the store completed at
+    pub tick: u64,
+    pub address: u32,
+    pub size: DataSize,
+    pub value: u32,
+    pub source: StoreSource,
+}
+
+impl StoreRecord {
+    fn covers(&self, address: u32) -> bool {
+        (self.address..self.address + self.size as u32).contains(&address)
+    }
+}
+
+/// Stores indexed by the word they touch, in the order they completed
+#[derive(Default)]
+pub struct StoreLog {
+    words: HashMap<u32, VecDeque<StoreRecord>>,
+}
+
+impl StoreLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, record: StoreRecord) {
+        let writes = self.words.entry(record.address & !0b11).or_default();
+
+        if writes.len() >= MAX_WRITES_PER_WORD {
+            writes.pop_front();
+        }
+
+        writes.push_back(record);
+    }
+
+    /// The most recent store to the byte at `address` which completed at or before `tick`
+    pub fn last_writer(&self, address: u32, tick: u64) -> Option<&StoreRecord> {
+        let writes = self.words.get(&(address & !0b11))?;
+        // the records are sorted by tick, skip the ones after the point in time
+        let end = writes.partition_point(|record| record.tick <= tick);
+
+        writes
+            .range(..end)
+            .rev()
+            .find(|record| record.covers(address))
+    }
+
+    /// Every recorded store to the byte at `address`, the oldest first
+    pub fn writers(&self, address: u32) -> impl Iterator<Item = &StoreRecord> {
+        self.words
+            .get(&(address & !0b11))
+            .into_iter()
+            .flatten()
+            .filter(move |record| record.covers(address))
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(tick: u64, address: u32, size: DataSize, pc: u32) -> StoreRecord {
+        StoreRecord {
+            tick,
+            address,
+            size,
+            value: 0,
+            source: StoreSource::Core { core: 0, pc },
+        }
+    }
+
+    #[test]
+    fn test_last_writer_in_time() {
+        let mut log = StoreLog::new();
+        log.record(store(10, 0x2000_0000, DataSize::Word, 0x100));
+        log.record(store(20, 0x2000_0002, DataSize::Byte, 0x200));
+        log.record(store(30, 0x2000_0000, DataSize::HalfWord, 0x300));
+
+        let pc = |address, tick| match log.last_writer(address, tick)?.source {
+            StoreSource::Core { pc, .. } => Some(pc),
+            StoreSource::Dma { .. } => None,
+        };
+
+        assert_eq!(pc(0x2000_0002, 100), Some(0x200));
+        assert_eq!(pc(0x2000_0003, 100), Some(0x100));
+        assert_eq!(pc(0x2000_0001, 100), Some(0x300));
+        assert_eq!(pc(0x2000_0001, 29), Some(0x100));
+        assert_eq!(pc(0x2000_0002, 19), Some(0x100));
+        assert_eq!(pc(0x2000_0000, 9), None);
+        assert_eq!(pc(0x2000_0004, 100), None);
+        assert_eq!(log.writers(0x2000_0002).count(), 2);
+    }
+
+    #[test]
+    fn test_bounded_history() {
+        let mut log = StoreLog::new();
+
+        for tick in 0..MAX_WRITES_PER_WORD as u64 + 10 {
+            log.record(store(tick, 0x2000_0000, DataSize::Word, 0));
+        }
+
+        assert_eq!(log.writers(0x2000_0000).count(), MAX_WRITES_PER_WORD);
+        assert!(log.last_writer(0x2000_0000, 5).is_none());
+        assert_eq!(log.last_writer(0x2000_0000, 20).map(|r| r.tick), Some(20));
+    }
+}
@@ -4,7 +4,7 @@
  * @date 02/01/2025
  * @brief Clock module for the Rp2350 simulator to handle the clock and events.
  */
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::BTreeSet;
 
 use crate::common::MHZ;
@@ -15,10 +15,51 @@ pub mod tick;
 pub use event::{Event, EventFn, EventType};
 pub use tick::*;
 
-#[derive(Default)]
+/// Frequencies in Hz of the oscillators, the PLLs and the clock domains they feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frequencies {
+    pub xosc: u64,
+    pub pll_sys: u64,
+    pub pll_usb: u64,
+    pub clk_ref: u64,
+    pub clk_sys: u64,
+    pub clk_peri: u64,
+    pub clk_usb: u64,
+    pub clk_adc: u64,
+    pub clk_hstx: u64,
+}
+
+impl Default for Frequencies {
+    /// The clocks as the SDK sets them up, clk_ref from the crystal, clk_sys, clk_peri and
+    /// clk_hstx from PLL_SYS, clk_usb and clk_adc from PLL_USB
+    fn default() -> Self {
+        Self {
+            xosc: 12 * MHZ,
+            pll_sys: 150 * MHZ,
+            pll_usb: 48 * MHZ,
+            clk_ref: 12 * MHZ,
+            clk_sys: 150 * MHZ,
+            clk_peri: 150 * MHZ,
+            clk_usb: 48 * MHZ,
+            clk_adc: 48 * MHZ,
+            clk_hstx: 150 * MHZ,
+        }
+    }
+}
+
 pub struct Clock {
     pub ticks: RefCell<u64>,
     pub events: RefCell<BTreeSet<Event>>,
+    /// One tick of the simulation is one cycle of clk_sys
+    frequencies: Cell<Frequencies>,
+    /// Output of PLL_SYS forced by the simulator, whatever the firmware programs in it
+    pll_sys_override: Cell<Option<u64>>,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Clock {
@@ -26,9 +67,43 @@ impl Clock {
         Self {
             ticks: RefCell::new(0),
             events: RefCell::new(BTreeSet::new()),
+            frequencies: Cell::new(Frequencies::default()),
+            pll_sys_override: Cell::new(None),
         }
     }
 
+    /// Run PLL_SYS at `frequency` whatever its dividers are, the clock generators still
+    /// divide it. With the clocks as the SDK sets them up it is the frequency of clk_sys
+    pub fn set_clk_sys(&self, frequency: u64) {
+        let frequency = frequency.max(1);
+        self.pll_sys_override.set(Some(frequency));
+
+        self.set_frequencies(Frequencies {
+            pll_sys: frequency,
+            clk_sys: frequency,
+            clk_peri: frequency,
+            clk_hstx: frequency,
+            ..self.frequencies()
+        });
+    }
+
+    pub fn pll_sys_override(&self) -> Option<u64> {
+        self.pll_sys_override.get()
+    }
+
+    pub fn frequencies(&self) -> Frequencies {
+        self.frequencies.get()
+    }
+
+    /// Change the frequencies of the clock domains, the events already scheduled keep their
+    /// activation time. clk_sys never stops, the simulation is ticked by it
+    pub fn set_frequencies(&self, frequencies: Frequencies) {
+        self.frequencies.set(Frequencies {
+            clk_sys: frequencies.clk_sys.max(1),
+            ..frequencies
+        });
+    }
+
     pub fn tick(&self) {
         let ticks = {
             let mut tmp = self.ticks.borrow_mut();
@@ -65,7 +140,7 @@ impl Clock {
         typ: EventType,
         event_fn: F,
     ) -> u64 {
-        let ticks = ticks.into().into_ticks_number();
+        let ticks = ticks.into().into_ticks_number(self.clk_sys());
         let activation_time = *self.ticks.borrow() + ticks;
         self.events
             .borrow_mut()
@@ -74,6 +149,41 @@ impl Clock {
         activation_time
     }
 
+    /// Jump `ticks` ahead, the events in between are activated in order at their own
+    /// time. Stop right after an event when `stop` returns true.
+    /// Return the number of ticks actually skipped.
+    pub fn advance(&self, ticks: u64, mut stop: impl FnMut() -> bool) -> u64 {
+        let start = *self.ticks.borrow();
+        let end = start.saturating_add(ticks);
+
+        loop {
+            let next = self
+                .events
+                .borrow()
+                .first()
+                .map(|event| event.activation_time);
+            let now = *self.ticks.borrow();
+
+            match next {
+                Some(time) if time <= end && now < end => {
+                    // the tick itself activates the events which are due
+                    *self.ticks.borrow_mut() = time.max(now + 1) - 1;
+                    self.tick();
+
+                    if stop() {
+                        break;
+                    }
+                }
+                _ => {
+                    *self.ticks.borrow_mut() = end;
+                    break;
+                }
+            }
+        }
+
+        *self.ticks.borrow() - start
+    }
+
     pub fn is_scheduled(&self, typ: EventType) -> bool {
         self.events.borrow().iter().any(|event| event.typ == typ)
     }
@@ -90,26 +200,26 @@ impl Clock {
     }
 
     pub fn clk_sys(&self) -> u64 {
-        150 * MHZ
+        self.frequencies().clk_sys
     }
 
     pub fn clk_ref(&self) -> u64 {
-        12 * MHZ
+        self.frequencies().clk_ref
     }
 
     pub fn clk_peri(&self) -> u64 {
-        150 * MHZ
+        self.frequencies().clk_peri
     }
 
     pub fn clk_usb(&self) -> u64 {
-        48 * MHZ
+        self.frequencies().clk_usb
     }
 
     pub fn clk_adc(&self) -> u64 {
-        48 * MHZ
+        self.frequencies().clk_adc
     }
 
     pub fn clk_hstx(&self) -> u64 {
-        150 * MHZ
+        self.frequencies().clk_hstx
     }
 }
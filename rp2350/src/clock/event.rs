@@ -15,6 +15,7 @@ pub enum EventType {
     UartRx(usize),
     Timer(usize),
     Sha256,
+    Adc,
 }
 
 impl fmt::Display for EventType {
@@ -23,6 +24,7 @@ impl fmt::Display for EventType {
             EventType::DmaChannelTimer(ch) => write!(f, "DMA Channel {}", ch),
             EventType::RiscVTimer => write!(f, "RISC-V Timer"),
             EventType::Sha256 => write!(f, "SHA256"),
+            EventType::Adc => write!(f, "ADC"),
             EventType::UartTx(ch) => write!(f, "UART Tx {}", ch),
             EventType::UartRx(ch) => write!(f, "UART Rx {}", ch),
             EventType::Pwm(ch) => write!(f, "PWM {}", ch),
@@ -6,8 +6,6 @@
  */
 use std::time::Duration;
 
-use crate::common::MHZ;
-
 #[derive(Clone, Debug)]
 pub enum Ticks {
     Duration(Duration),
@@ -15,15 +13,15 @@ pub enum Ticks {
 }
 
 impl Ticks {
-    pub const _1MHZ: Self = Ticks::Exact(150);
+    pub const _1MHZ: Self = Ticks::Duration(Duration::from_micros(1));
     pub const CKL_SYS: Self = Ticks::Exact(1);
 
-    pub fn into_ticks_number(self) -> u64 {
+    /// Number of cycles of a clock running at `clk_sys` Hz, rounded up
+    pub fn into_ticks_number(self, clk_sys: u64) -> u64 {
         match self {
             Ticks::Duration(dur) => {
-                let base = 1.0 / (150 * MHZ) as f64;
-                let base = Duration::from_secs_f64(base);
-                dur.div_duration_f64(base).ceil() as u64
+                let ticks = (dur.as_nanos() * clk_sys as u128).div_ceil(1_000_000_000);
+                ticks as u64
             }
             Ticks::Exact(tick) => tick,
         }
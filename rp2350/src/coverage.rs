@@ -0,0 +1,183 @@
+/**
+ * @file coverage.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Instructions executed by the firmware, mapped to its source lines and exported as lcov
+ */
+use crate::elf::LineTable;
+use crate::inspector::{InspectionEvent, Inspector};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Executions of every line of a source file, 0 for the lines with code never executed
+pub type LineHits = BTreeMap<u32, u64>;
+
+/// Counts the executions of every instruction address of both cores.
+///
+/// A line is covered when any of its instructions ran, its count is the one of its
+/// instruction executed the most. The lines come from the line table of the program,
+/// so it has to be built with `-g`.
+#[derive(Default)]
+pub struct Coverage(RefCell<BTreeMap<u32, u64>>);
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    /// Number of distinct instructions executed
+    pub fn executed(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn hits(&self, address: u32) -> u64 {
+        self.0.borrow().get(&address).copied().unwrap_or(0)
+    }
+
+    fn lines_where(
+        &self,
+        table: &LineTable,
+        include: impl Fn(&str) -> bool,
+    ) -> BTreeMap<usize, LineHits> {
+        let addresses = self.0.borrow();
+        let mut files: BTreeMap<usize, LineHits> = BTreeMap::new();
+
+        for (range, file, line) in table.ranges() {
+            if !include(&table.files[file]) {
+                continue;
+            }
+
+            let hits = addresses.range(range).map(|(_, &hits)| hits).max();
+            let entry = files.entry(file).or_default().entry(line).or_default();
+            *entry = (*entry).max(hits.unwrap_or(0));
+        }
+
+        files
+    }
+
+    /// Executions of the lines of every source file, by the path in the line table
+    pub fn lines(&self, table: &LineTable) -> BTreeMap<String, LineHits> {
+        self.lines_where(table, |_| true)
+            .into_iter()
+            .map(|(file, lines)| (table.files[file].clone(), lines))
+            .collect()
+    }
+
+    /// Executions of the lines of a file, found by the end of its path like `main.c`
+    pub fn file_lines(&self, table: &LineTable, name: &str) -> LineHits {
+        let matches = |path: &str| {
+            path == name
+                || path
+                    .strip_suffix(name)
+                    .is_some_and(|dir| dir.ends_with('/'))
+        };
+
+        let mut lines = LineHits::new();
+        for (_, hits) in self.lines_where(table, matches) {
+            for (line, count) in hits {
+                let entry = lines.entry(line).or_default();
+                *entry = (*entry).max(count);
+            }
+        }
+
+        lines
+    }
+
+    /// The tracefile of `lcov`, with a record of the lines of every source file
+    pub fn to_lcov(&self, table: &LineTable) -> String {
+        let mut lcov = String::new();
+
+        for (path, lines) in self.lines(table) {
+            let _ = writeln!(lcov, "TN:\nSF:{path}");
+
+            for (line, hits) in lines.iter() {
+                let _ = writeln!(lcov, "DA:{line},{hits}");
+            }
+
+            let hit = lines.values().filter(|&&hits| hits > 0).count();
+            let _ = writeln!(lcov, "LF:{}\nLH:{hit}\nend_of_record", lines.len());
+        }
+
+        lcov
+    }
+}
+
+impl Inspector for Coverage {
+    fn handle_event(&self, event: InspectionEvent) {
+        match event {
+            InspectionEvent::ExecutedInstruction { address, .. } => {
+                *self.0.borrow_mut().entry(address).or_default() += 1;
+            }
+            InspectionEvent::FlashedBinary => self.clear(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::LineRow;
+
+    fn executed(address: u32) -> InspectionEvent {
+        InspectionEvent::ExecutedInstruction {
+            core: 0,
+            instruction: 0x0000_0013,
+            address,
+            name: "addi",
+            operands: Vec::new(),
+        }
+    }
+
+    fn table() -> LineTable {
+        let row = |address, file, line, end_sequence| LineRow {
+            address,
+            file,
+            line,
+            end_sequence,
+        };
+
+        LineTable {
+            files: vec!["/build/src/main.c".to_string(), "lib.c".to_string()],
+            rows: vec![
+                row(0x100, 0, 3, false),
+                row(0x104, 0, 4, false),
+                row(0x108, 0, 3, false),
+                row(0x10c, 1, 7, false),
+                row(0x110, 1, 0, true),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_lines() {
+        let coverage = Coverage::new();
+
+        for address in [0x100, 0x100, 0x102, 0x10c, 0x200] {
+            coverage.handle_event(executed(address));
+        }
+
+        assert_eq!(coverage.executed(), 4);
+        assert_eq!(coverage.hits(0x100), 2);
+
+        let table = table();
+        let main = coverage.file_lines(&table, "main.c");
+        assert_eq!(main, LineHits::from([(3, 2), (4, 0)]));
+        assert_eq!(coverage.file_lines(&table, "src/main.c"), main);
+        assert!(coverage.file_lines(&table, "ain.c").is_empty());
+
+        assert_eq!(
+            coverage.to_lcov(&table),
+            "TN:\nSF:/build/src/main.c\nDA:3,2\nDA:4,0\nLF:2\nLH:1\nend_of_record\n\
+             TN:\nSF:lib.c\nDA:7,1\nLF:1\nLH:1\nend_of_record\n"
+        );
+
+        coverage.handle_event(InspectionEvent::FlashedBinary);
+        assert_eq!(coverage.executed(), 0);
+    }
+}
@@ -0,0 +1,106 @@
+/**
+ * @file device.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Virtual devices living outside of the chip, wired to its pins
+ */
+pub mod breadboard;
+pub mod circuit;
+pub mod dht;
+pub mod eeprom;
+pub mod encoder_knob;
+pub mod hc_sr04;
+pub mod hub75;
+pub mod ir_nec;
+pub mod keypad;
+pub mod max7219;
+pub mod motor;
+pub mod mpu6050;
+pub mod rotary_encoder;
+pub mod sd_card;
+pub mod serial;
+pub mod seven_segment;
+pub mod spi_flash;
+pub mod ssd1306;
+pub mod stepper;
+pub mod stimulus;
+pub mod ws2812;
+
+use crate::gpio::{OutputState, PinState};
+use crate::Rp2350;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub use breadboard::{
+    Breadboard, Button, Buzzer, Component, ComponentFactory, ComponentRegistry, Led,
+    PlacedComponent, Resistor,
+};
+pub use circuit::{Branch, Fault};
+pub use dht::{Dht, DhtKind};
+pub use eeprom::Eeprom;
+pub use encoder_knob::EncoderKnob;
+pub use hc_sr04::{HcSr04, HcSr04Pins};
+pub use hub75::{Hub75, Hub75Pins};
+pub use ir_nec::{IrReceiver, IrRemote, NecCode, NecEvent};
+pub use keypad::{Keypad, KEYPAD_COLUMNS, KEYPAD_LABELS, KEYPAD_ROWS};
+pub use max7219::{Max7219, Max7219Pins};
+pub use motor::{DcMotor, Servo};
+pub use mpu6050::Mpu6050;
+pub use rotary_encoder::{Bounce, RotaryEncoder, RotaryEncoderPins};
+pub use sd_card::SdCard;
+pub use serial::SerialTerminal;
+pub use seven_segment::{SevenSegment, Tm1637};
+pub use spi_flash::SpiFlash;
+pub use ssd1306::Ssd1306;
+pub use stepper::StepperDriver;
+pub use stimulus::{Stimulus, StimulusAction, StimulusError, StimulusStep};
+pub use ws2812::Ws2812;
+
+/// A device outside of the chip, e.g. a button or a sensor wired to the GPIOs.
+/// It is ticked by the machine it is attached to and survives the resets of the chip.
+pub trait VirtualDevice {
+    /// Called once when the device is attached to a machine
+    fn attach(&mut self, _mcu: &mut Rp2350) {}
+
+    /// Called on every tick of the machine, before the chip runs its cycle
+    fn tick(&mut self, mcu: &mut Rp2350);
+}
+
+/// Shared devices, so the state can still be inspected after attaching them
+impl<T: VirtualDevice> VirtualDevice for Rc<RefCell<T>> {
+    fn attach(&mut self, mcu: &mut Rp2350) {
+        self.borrow_mut().attach(mcu);
+    }
+
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        self.borrow_mut().tick(mcu);
+    }
+}
+
+/// Level driven by the chip on a GPIO, an input or a non existing pin reads as low
+pub(crate) fn is_pin_high(mcu: &Rp2350, pin: u8) -> bool {
+    pin < 30 && mcu.gpio.borrow().pin_state(pin).is_high()
+}
+
+/// Whether the chip actively pulls a GPIO low, an open drain line is high otherwise
+pub(crate) fn is_pin_low(mcu: &Rp2350, pin: u8) -> bool {
+    pin < 30
+        && matches!(
+            mcu.gpio.borrow().pin_state(pin),
+            PinState::Output(OutputState::Low, _)
+        )
+}
+
+/// Drive a GPIO from the outside, only the changes are applied so the edge interrupts
+/// are not raised again on every tick
+pub(crate) fn drive_pin(mcu: &Rp2350, pin: u8, level: bool) {
+    let current = mcu
+        .gpio
+        .borrow()
+        .get_pin(pin)
+        .map(|pin| pin.raw_input_value);
+
+    if current.is_some_and(|current| current != level) {
+        let _ = mcu.set_gpio_pin_input(pin, level);
+    }
+}
@@ -0,0 +1,646 @@
+/**
+ * @file device/breadboard.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Breadboard of components wired to the GPIOs, with the built-in LEDs, buttons,
+ * resistors and buzzers
+ */
+use super::circuit::{self, Branch, Fault, MAX_PIN_CURRENT, PULL_OHMS, VIH, VIL};
+use super::{
+    drive_pin, DcMotor, EncoderKnob, Keypad, Servo, SevenSegment, StepperDriver, Tm1637,
+    VirtualDevice, Ws2812,
+};
+use crate::gpio::{InputState, OutputState, PinState};
+use crate::Rp2350;
+use std::any::Any;
+
+/// A buzzer which saw no rising edge for that long is a steady level, 50ms
+const SILENCE_DIVIDER: u64 = 20;
+/// Red LED, with the resistance of its junction
+const LED_FORWARD_VOLTS: f64 = 2.0;
+const LED_OHMS: f64 = 20.0;
+/// Current from which an LED visibly lights
+const LED_LIT_CURRENT: f64 = 0.0005;
+/// Piezo buzzer behind its transistor, about 3mA
+const BUZZER_OHMS: f64 = 1_000.0;
+
+/// A part of the breadboard. Its pins are wired to the GPIOs by the board, which solves
+/// the net of every GPIO with what the chip and the components put on it, then tells
+/// them about the level, the voltage and the current of their pins.
+pub trait Component: Any {
+    /// Name in the palette, also the one it is registered and saved with
+    fn name(&self) -> &'static str;
+
+    /// Labels of its pins, each can be wired to a GPIO
+    fn pins(&self) -> &'static [&'static str];
+
+    /// The GPIO wired to one of its pins changed level, at the given tick
+    fn notify_pin_change(&mut self, _pin: usize, _level: bool, _now: u64, _clk_sys: u64) {}
+
+    /// Called on every tick of the machine, after the pin changes
+    fn tick(&mut self, _now: u64, _clk_sys: u64) {}
+
+    /// What it puts on the net of one of its pins, `None` if it leaves the pin alone
+    fn branch(&self, _pin: usize) -> Option<Branch> {
+        None
+    }
+
+    /// Voltage of the net of one of its pins and the current flowing from the net into
+    /// it, on every tick
+    fn notify_current(&mut self, _pin: usize, _volts: f64, _amps: f64) {}
+
+    /// Short state shown next to it, e.g. the position of a motor
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    /// Settings saved with the layout of the board
+    fn save(&self) -> String {
+        String::new()
+    }
+
+    /// Restore the settings from `save`, a malformed text is ignored
+    fn load(&mut self, _state: &str) {}
+}
+
+/// LED from the GPIO to GND, lit while a current flows through it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Led {
+    pub color: [u8; 3],
+    lit: bool,
+}
+
+impl Led {
+    pub fn new(color: [u8; 3]) -> Self {
+        Self { color, lit: false }
+    }
+
+    pub fn is_lit(&self) -> bool {
+        self.lit
+    }
+}
+
+impl Default for Led {
+    fn default() -> Self {
+        Self::new([255, 40, 40])
+    }
+}
+
+impl Component for Led {
+    fn name(&self) -> &'static str {
+        "LED"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["A"]
+    }
+
+    fn branch(&self, _pin: usize) -> Option<Branch> {
+        Some(Branch::Diode {
+            forward_volts: LED_FORWARD_VOLTS,
+            ohms: LED_OHMS,
+        })
+    }
+
+    fn notify_current(&mut self, _pin: usize, _volts: f64, amps: f64) {
+        self.lit = amps > LED_LIT_CURRENT;
+    }
+
+    fn save(&self) -> String {
+        let [r, g, b] = self.color;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    fn load(&mut self, state: &str) {
+        let Some(Ok(color)) = state
+            .strip_prefix('#')
+            .map(|hex| u32::from_str_radix(hex, 16))
+        else {
+            return;
+        };
+
+        let [_, r, g, b] = color.to_be_bytes();
+        self.color = [r, g, b];
+    }
+}
+
+/// Push button from the GPIO to 3V3 or to GND, the pin follows its pulls while released
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Button {
+    pub to_vcc: bool,
+    pub pressed: bool,
+}
+
+impl Component for Button {
+    fn name(&self) -> &'static str {
+        "Button"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["IO"]
+    }
+
+    fn branch(&self, _pin: usize) -> Option<Branch> {
+        self.pressed.then(|| Branch::source(self.to_vcc, 0.0))
+    }
+
+    fn save(&self) -> String {
+        match self.to_vcc {
+            true => "vcc".to_string(),
+            false => "gnd".to_string(),
+        }
+    }
+
+    fn load(&mut self, state: &str) {
+        self.to_vcc = state == "vcc";
+    }
+}
+
+/// External pull from the GPIO to 3V3 or to GND
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resistor {
+    pub ohms: u32,
+    pub to_vcc: bool,
+}
+
+impl Default for Resistor {
+    /// The usual pull up
+    fn default() -> Self {
+        Self {
+            ohms: 10_000,
+            to_vcc: true,
+        }
+    }
+}
+
+impl Component for Resistor {
+    fn name(&self) -> &'static str {
+        "Resistor"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["IO"]
+    }
+
+    fn branch(&self, _pin: usize) -> Option<Branch> {
+        Some(Branch::source(self.to_vcc, self.ohms as f64))
+    }
+
+    fn save(&self) -> String {
+        let rail = if self.to_vcc { "vcc" } else { "gnd" };
+        format!("{},{rail}", self.ohms)
+    }
+
+    fn load(&mut self, state: &str) {
+        let Some((ohms, rail)) = state.split_once(',') else {
+            return;
+        };
+
+        if let Ok(ohms) = ohms.parse() {
+            self.ohms = ohms;
+            self.to_vcc = rail == "vcc";
+        }
+    }
+}
+
+/// Buzzer from the GPIO to GND. It buzzes while the pin is high, or at the frequency the
+/// pin toggles at like a passive one.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Buzzer {
+    level: bool,
+    last_rise: Option<u64>,
+    frequency: f64,
+}
+
+impl Buzzer {
+    /// Frequency of the tone in Hz, 0 while the pin does not toggle
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    pub fn is_buzzing(&self) -> bool {
+        self.level || self.frequency > 0.0
+    }
+}
+
+impl Component for Buzzer {
+    fn name(&self) -> &'static str {
+        "Buzzer"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["+"]
+    }
+
+    fn branch(&self, _pin: usize) -> Option<Branch> {
+        Some(Branch::source(false, BUZZER_OHMS))
+    }
+
+    fn notify_pin_change(&mut self, _pin: usize, level: bool, now: u64, clk_sys: u64) {
+        self.level = level;
+
+        if !level {
+            return;
+        }
+
+        if let Some(last_rise) = self.last_rise.filter(|last| now > *last) {
+            self.frequency = clk_sys as f64 / (now - last_rise) as f64;
+        }
+
+        self.last_rise = Some(now);
+    }
+
+    /// A tone stops once the pin stayed at the same level for a while
+    fn tick(&mut self, now: u64, clk_sys: u64) {
+        let timeout = clk_sys / SILENCE_DIVIDER;
+
+        if self
+            .last_rise
+            .is_some_and(|last| now.saturating_sub(last) > timeout)
+        {
+            self.last_rise = None;
+            self.frequency = 0.0;
+        }
+    }
+}
+
+/// Creates a component with its default settings
+pub type ComponentFactory = fn() -> Box<dyn Component>;
+
+/// Components which can be placed by their name, the built-in ones and the ones of the
+/// users. The names are the ones the layouts of the boards are saved with.
+#[derive(Clone)]
+pub struct ComponentRegistry {
+    factories: Vec<(&'static str, ComponentFactory)>,
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            factories: Vec::new(),
+        };
+
+        registry.register("LED", || Box::new(Led::default()));
+        registry.register("Button", || Box::new(Button::default()));
+        registry.register("Resistor", || Box::new(Resistor::default()));
+        registry.register("Buzzer", || Box::new(Buzzer::default()));
+        registry.register("Rotary encoder", || Box::new(EncoderKnob::default()));
+        registry.register("Keypad 4x4", || Box::new(Keypad::default()));
+        registry.register("Servo", || Box::new(Servo::default()));
+        registry.register("DC motor", || Box::new(DcMotor::default()));
+        registry.register("Stepper driver", || Box::new(StepperDriver::default()));
+        registry.register("WS2812 strip", || Box::new(Ws2812::default()));
+        registry.register("7-segment", || Box::new(SevenSegment::default()));
+        registry.register("TM1637", || Box::new(Tm1637::default()));
+        registry
+    }
+}
+
+impl ComponentRegistry {
+    /// The built-in components
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a component, or replace the one registered with the same name
+    pub fn register(&mut self, name: &'static str, factory: ComponentFactory) {
+        match self.factories.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = factory,
+            None => self.factories.push((name, factory)),
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.factories.iter().map(|(name, _)| *name)
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn Component>> {
+        self.factories
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, factory)| factory())
+    }
+}
+
+/// A component on the board with the wires of its pins
+pub struct PlacedComponent {
+    pub component: Box<dyn Component>,
+    /// Hole of its first pin, the column then the row. The others follow on the right.
+    pub position: (i32, i32),
+    /// GPIO wired to each of its pins
+    pub wires: Vec<Option<u8>>,
+    levels: Vec<bool>,
+    volts: Vec<Option<f64>>,
+    amps: Vec<f64>,
+}
+
+impl PlacedComponent {
+    pub fn new(component: Box<dyn Component>, position: (i32, i32)) -> Self {
+        let pins = component.pins().len();
+
+        Self {
+            component,
+            position,
+            wires: vec![None; pins],
+            levels: vec![false; pins],
+            volts: vec![None; pins],
+            amps: vec![0.0; pins],
+        }
+    }
+
+    /// Level of the GPIO wired to a pin, as last seen
+    pub fn level(&self, pin: usize) -> bool {
+        self.levels.get(pin).copied().unwrap_or_default()
+    }
+
+    /// Voltage of the net of a pin, `None` while it floats or is not wired
+    pub fn volts(&self, pin: usize) -> Option<f64> {
+        self.volts.get(pin).copied().flatten()
+    }
+
+    /// Current flowing from the net into a pin, in amps
+    pub fn amps(&self, pin: usize) -> f64 {
+        self.amps.get(pin).copied().unwrap_or_default()
+    }
+
+    /// The component if it is of the given type
+    pub fn downcast_ref<T: Component>(&self) -> Option<&T> {
+        (self.component.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Component>(&mut self) -> Option<&mut T> {
+        (self.component.as_mut() as &mut dyn Any).downcast_mut()
+    }
+}
+
+/// Components on a breadboard with their wires to the GPIOs. Each GPIO is a net, solved
+/// on every tick with the output or the pulls of its pad and the branches of the
+/// components; the input of the pad follows the voltage of the net.
+#[derive(Default)]
+pub struct Breadboard {
+    pub components: Vec<PlacedComponent>,
+    faults: Vec<Fault>,
+}
+
+impl Breadboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put a component on the board, unwired, and return its index
+    pub fn place(&mut self, component: Box<dyn Component>, position: (i32, i32)) -> usize {
+        self.components
+            .push(PlacedComponent::new(component, position));
+        self.components.len() - 1
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<PlacedComponent> {
+        (index < self.components.len()).then(|| self.components.remove(index))
+    }
+
+    /// Wiring which would damage a real board, as of the last tick
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    /// What the chip puts on a GPIO, its output or else the pulls of its pad
+    fn chip_branch(mcu: &Rp2350, gpio: u8) -> Option<Branch> {
+        if gpio >= 30 {
+            return None;
+        }
+
+        let pins = mcu.gpio.borrow();
+        let pad = pins.get_pin(gpio)?;
+
+        match pins.pin_state(gpio) {
+            PinState::Output(value, _) => Some(Branch::output(
+                matches!(value, OutputState::High),
+                pad.drive_strength(),
+            )),
+            PinState::Input(InputState::PullUp) => Some(Branch::source(true, PULL_OHMS)),
+            PinState::Input(InputState::PullDown) => Some(Branch::source(false, PULL_OHMS)),
+            PinState::Input(InputState::BusKeeper) => {
+                Some(Branch::source(pad.raw_input_value, PULL_OHMS))
+            }
+            PinState::Input(InputState::Floating) => None,
+        }
+    }
+
+    /// Solve the net of a GPIO, drive the input of its pad and tell its components
+    fn update_net(&mut self, mcu: &Rp2350, gpio: u8, now: u64, clk_sys: u64) {
+        let mut branches = Vec::new();
+        let mut owners = Vec::new();
+
+        for (index, placed) in self.components.iter().enumerate() {
+            for (pin, wire) in placed.wires.iter().enumerate() {
+                if *wire != Some(gpio) {
+                    continue;
+                }
+
+                if let Some(branch) = placed.component.branch(pin) {
+                    branches.push(branch);
+                    owners.push((index, pin));
+                }
+            }
+        }
+
+        let chip = Self::chip_branch(mcu, gpio);
+        let is_output =
+            gpio < 30 && matches!(mcu.gpio.borrow().pin_state(gpio), PinState::Output(..));
+        branches.extend(chip);
+
+        let solution = circuit::solve(&branches);
+
+        if solution.is_short() {
+            self.faults.push(Fault::Short { gpio });
+        } else if let Some(amps) = chip
+            .and(solution.currents.last().copied())
+            .filter(|amps| amps.abs() > MAX_PIN_CURRENT)
+        {
+            self.faults.push(Fault::Overcurrent { gpio, amps });
+        }
+
+        let input = mcu
+            .gpio
+            .borrow()
+            .get_pin(gpio)
+            .map(|pad| pad.raw_input_value);
+        let level = |previous: bool| match solution.volts {
+            Some(volts) if volts >= VIH => true,
+            Some(volts) if volts <= VIL => false,
+            Some(_) => previous,
+            None => false,
+        };
+
+        if let Some(input) = input.filter(|_| !is_output && solution.volts.is_some()) {
+            drive_pin(mcu, gpio, level(input));
+        }
+
+        for (index, placed) in self.components.iter_mut().enumerate() {
+            for pin in 0..placed.wires.len() {
+                if placed.wires[pin] != Some(gpio) {
+                    continue;
+                }
+
+                let amps = owners
+                    .iter()
+                    .position(|owner| *owner == (index, pin))
+                    .map(|branch| solution.currents[branch])
+                    .unwrap_or_default();
+
+                placed.volts[pin] = solution.volts;
+                placed.amps[pin] = amps;
+                placed
+                    .component
+                    .notify_current(pin, solution.volts.unwrap_or_default(), amps);
+
+                let level = level(placed.levels[pin]);
+                if level != placed.levels[pin] {
+                    placed.levels[pin] = level;
+                    placed.component.notify_pin_change(pin, level, now, clk_sys);
+                }
+            }
+        }
+    }
+}
+
+impl VirtualDevice for Breadboard {
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let now = *mcu.clock.ticks.borrow();
+        let clk_sys = mcu.clock.clk_sys();
+
+        let mut gpios: Vec<u8> = self
+            .components
+            .iter()
+            .flat_map(|placed| placed.wires.iter().flatten().copied())
+            .collect();
+        gpios.sort_unstable();
+        gpios.dedup();
+
+        self.faults.clear();
+        for gpio in gpios {
+            self.update_net(mcu, gpio, now, clk_sys);
+        }
+
+        for placed in self.components.iter_mut() {
+            placed.component.tick(now, clk_sys);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIO: u32 = 5;
+
+    #[test]
+    fn test_led_and_button() {
+        let mut mcu = Rp2350::new();
+        let mut board = Breadboard::new();
+        let registry = ComponentRegistry::new();
+
+        let led = board.place(registry.create("LED").unwrap(), (0, 0));
+        let button = board.place(registry.create("Button").unwrap(), (4, 0));
+        let pull_up = board.place(registry.create("Resistor").unwrap(), (8, 0));
+        board.components[led].wires[0] = Some(2);
+        board.components[button].wires[0] = Some(3);
+        board.components[pull_up].wires[0] = Some(3);
+        mcu.gpio.borrow_mut().pins[2].ctrl = SIO;
+
+        mcu.gpio.borrow_mut().update_sio(1 << 2, 1 << 2);
+        board.tick(&mut mcu);
+        assert!(board.components[led]
+            .downcast_ref::<Led>()
+            .unwrap()
+            .is_lit());
+        assert!(mcu.gpio.borrow().get_pin(3).unwrap().raw_input_value);
+
+        // the pressed button wins over the pull up
+        board.components[button]
+            .downcast_mut::<Button>()
+            .unwrap()
+            .pressed = true;
+        mcu.gpio.borrow_mut().update_sio(1 << 2, 0);
+        board.tick(&mut mcu);
+        assert!(!board.components[led]
+            .downcast_ref::<Led>()
+            .unwrap()
+            .is_lit());
+        assert!(!mcu.gpio.borrow().get_pin(3).unwrap().raw_input_value);
+        assert!(!board.components[button].level(0));
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut mcu = Rp2350::new();
+        let mut board = Breadboard::new();
+
+        let to_gnd = board.place(Box::new(Button::default()), (0, 0));
+        board.components[to_gnd].wires[0] = Some(4);
+        board.components[to_gnd]
+            .downcast_mut::<Button>()
+            .unwrap()
+            .pressed = true;
+
+        // a high output at 12mA straight into GND
+        mcu.gpio.borrow_mut().pins[4].ctrl = SIO;
+        mcu.gpio.borrow_mut().pins[4].pad |= 0b11 << 4;
+        mcu.gpio.borrow_mut().update_sio(1 << 4, 1 << 4);
+        board.tick(&mut mcu);
+        assert!(matches!(
+            board.faults(),
+            [Fault::Overcurrent { gpio: 4, .. }]
+        ));
+
+        // the same at 2mA stays in the limits, the net sits low
+        mcu.gpio.borrow_mut().pins[4].pad &= !(0b11 << 4);
+        board.tick(&mut mcu);
+        assert!(board.faults().is_empty());
+        assert!(board.components[to_gnd].volts(0).unwrap() < VIL);
+
+        let to_vcc = board.place(
+            Box::new(Button {
+                to_vcc: true,
+                pressed: true,
+            }),
+            (4, 0),
+        );
+        board.components[to_vcc].wires[0] = Some(4);
+        board.tick(&mut mcu);
+        assert_eq!(board.faults(), [Fault::Short { gpio: 4 }]);
+    }
+
+    #[test]
+    fn test_buzzer_tone() {
+        let mut buzzer = Buzzer::default();
+        let clk_sys = 150_000_000;
+
+        // toggling at 1kHz
+        for period in 0..4 {
+            buzzer.notify_pin_change(0, true, period * 150_000, clk_sys);
+            buzzer.notify_pin_change(0, false, period * 150_000 + 75_000, clk_sys);
+        }
+
+        assert_eq!(buzzer.frequency(), 1000.0);
+        assert!(buzzer.is_buzzing());
+
+        buzzer.tick(4 * 150_000 + clk_sys, clk_sys);
+        assert!(!buzzer.is_buzzing());
+    }
+
+    #[test]
+    fn test_saved_settings() {
+        let registry = ComponentRegistry::new();
+        let mut resistor = registry.create("Resistor").unwrap();
+        resistor.load("4700,gnd");
+        assert_eq!(resistor.save(), "4700,gnd");
+        assert_eq!(resistor.branch(0), Some(Branch::source(false, 4700.0)));
+
+        let mut led = Led::default();
+        led.load("#00ff80");
+        assert_eq!(led.color, [0x00, 0xFF, 0x80]);
+        assert!(registry.create("Stepper driver").is_some());
+        assert!(registry.create("Solenoid").is_none());
+    }
+}
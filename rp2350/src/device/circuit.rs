@@ -0,0 +1,233 @@
+/**
+ * @file device/circuit.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Nodal analysis of the nets of the breadboard, their voltage and the current
+ * through every branch
+ */
+use crate::gpio::DriveStrength;
+use std::fmt;
+
+/// Supply of the pads
+pub const VDD: f64 = 3.3;
+/// The pad reads high above it
+pub const VIH: f64 = 2.0;
+/// The pad reads low below it, the level is kept in between
+pub const VIL: f64 = 0.8;
+/// Resistance of a wire or a closed switch, a short still has a finite current
+pub const WIRE_OHMS: f64 = 0.1;
+/// Resistance of the pull up and pull down of the pads
+pub const PULL_OHMS: f64 = 50_000.0;
+/// Current a pad bears before it is damaged
+pub const MAX_PIN_CURRENT: f64 = 0.05;
+/// Current beyond which the net is a short between the rails
+const SHORT_CURRENT: f64 = 1.0;
+
+/// Something connected to a net, with the other end on a rail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Branch {
+    /// Voltage source behind a resistance, e.g. a driven output, a pull or a closed switch
+    Source { volts: f64, ohms: f64 },
+    /// Diode to GND, e.g. an LED. It only conducts once the net is above its forward
+    /// voltage, then acts like a source of that voltage behind its resistance.
+    Diode { forward_volts: f64, ohms: f64 },
+}
+
+impl Branch {
+    pub fn source(high: bool, ohms: f64) -> Self {
+        Self::Source {
+            volts: if high { VDD } else { 0.0 },
+            ohms: ohms.max(WIRE_OHMS),
+        }
+    }
+
+    /// Output of a pad, the voltage drops by about 0.4V at its drive strength
+    pub fn output(high: bool, strength: DriveStrength) -> Self {
+        let milliamps = match strength {
+            DriveStrength::_2mA => 2.0,
+            DriveStrength::_4mA => 4.0,
+            DriveStrength::_8mA => 8.0,
+            DriveStrength::_12mA => 12.0,
+        };
+
+        Self::source(high, 0.4 / (milliamps / 1000.0))
+    }
+
+    fn volts(&self) -> f64 {
+        match *self {
+            Self::Source { volts, .. } => volts,
+            Self::Diode { forward_volts, .. } => forward_volts,
+        }
+    }
+
+    fn ohms(&self) -> f64 {
+        match *self {
+            Self::Source { ohms, .. } | Self::Diode { ohms, .. } => ohms.max(WIRE_OHMS),
+        }
+    }
+}
+
+/// State of a net once solved
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// Voltage of the net, `None` when nothing drives it
+    pub volts: Option<f64>,
+    /// Current flowing from the net into each branch, in amps. A source driving the
+    /// net high has a negative one.
+    pub currents: Vec<f64>,
+}
+
+impl Solution {
+    /// Whether the net joins the rails through almost nothing
+    pub fn is_short(&self) -> bool {
+        self.currents
+            .iter()
+            .any(|current| current.abs() > SHORT_CURRENT)
+    }
+}
+
+/// Resolve the voltage of a net by Kirchhoff's current law, the sum of the currents of
+/// the branches is null. Diodes which do not conduct at the found voltage are taken out
+/// and the net is solved again.
+pub fn solve(branches: &[Branch]) -> Solution {
+    let mut conducting = vec![true; branches.len()];
+    let mut volts = None;
+
+    if branches
+        .iter()
+        .any(|branch| matches!(branch, Branch::Source { .. }))
+    {
+        // every pass takes out at least one diode, or it is settled
+        for _ in 0..=branches.len() {
+            let (conductance, current) = branches
+                .iter()
+                .zip(conducting.iter())
+                .filter(|(_, conducting)| **conducting)
+                .fold((0.0, 0.0), |(g, i), (branch, _)| {
+                    (g + 1.0 / branch.ohms(), i + branch.volts() / branch.ohms())
+                });
+
+            let voltage = current / conductance;
+            volts = Some(voltage);
+
+            let mut settled = true;
+            for (branch, conducting) in branches.iter().zip(conducting.iter_mut()) {
+                let cut_off = match branch {
+                    Branch::Diode { forward_volts, .. } => *conducting && voltage < *forward_volts,
+                    _ => false,
+                };
+
+                if cut_off {
+                    *conducting = false;
+                    settled = false;
+                }
+            }
+
+            if settled {
+                break;
+            }
+        }
+    }
+
+    let currents = branches
+        .iter()
+        .zip(conducting.iter())
+        .map(|(branch, conducting)| match (volts, conducting) {
+            (Some(volts), true) => (volts - branch.volts()) / branch.ohms(),
+            _ => 0.0,
+        })
+        .collect();
+
+    Solution { volts, currents }
+}
+
+/// Wiring that would damage a real board
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    /// The net joins 3V3 and GND, e.g. two buttons to both rails pressed together
+    Short { gpio: u8 },
+    /// The pad drives more current than it bears, e.g. a high output shorted to GND
+    Overcurrent { gpio: u8, amps: f64 },
+}
+
+impl Fault {
+    pub fn gpio(&self) -> u8 {
+        match *self {
+            Self::Short { gpio } | Self::Overcurrent { gpio, .. } => gpio,
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Short { gpio } => write!(f, "GPIO{gpio}: short between 3V3 and GND"),
+            Self::Overcurrent { gpio, amps } => write!(
+                f,
+                "GPIO{gpio}: the pad drives {:.0}mA, over its {:.0}mA limit",
+                amps.abs() * 1000.0,
+                MAX_PIN_CURRENT * 1000.0
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divider_and_diode() {
+        // 10k pull up against the 50k pull down of the pad
+        let solution = solve(&[
+            Branch::source(true, 10_000.0),
+            Branch::source(false, PULL_OHMS),
+        ]);
+        let volts = solution.volts.unwrap();
+        assert!((volts - 2.75).abs() < 1e-9);
+        assert!((solution.currents[0] + solution.currents[1]).abs() < 1e-12);
+
+        // the LED clamps the net to its forward voltage, plus its resistance
+        let solution = solve(&[
+            Branch::output(true, DriveStrength::_4mA),
+            Branch::Diode {
+                forward_volts: 2.0,
+                ohms: 20.0,
+            },
+        ]);
+        let volts = solution.volts.unwrap();
+        assert!(volts > 2.0 && volts < 2.3);
+        assert!(solution.currents[1] > 0.01);
+
+        // below its forward voltage it is out of the net
+        let solution = solve(&[
+            Branch::output(false, DriveStrength::_4mA),
+            Branch::Diode {
+                forward_volts: 2.0,
+                ohms: 20.0,
+            },
+        ]);
+        assert_eq!(solution.volts, Some(0.0));
+        assert_eq!(solution.currents[1], 0.0);
+    }
+
+    #[test]
+    fn test_short_and_floating() {
+        let solution = solve(&[Branch::source(true, 0.0), Branch::source(false, 0.0)]);
+        assert!(solution.is_short());
+
+        // a high output of 12mA pulled to GND by a switch
+        let solution = solve(&[
+            Branch::output(true, DriveStrength::_12mA),
+            Branch::source(false, 0.0),
+        ]);
+        assert!(!solution.is_short());
+        assert!(solution.currents[0].abs() > MAX_PIN_CURRENT);
+
+        let solution = solve(&[Branch::Diode {
+            forward_volts: 2.0,
+            ohms: 20.0,
+        }]);
+        assert_eq!(solution.volts, None);
+    }
+}
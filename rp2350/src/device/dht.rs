@@ -0,0 +1,301 @@
+/**
+ * @file device/dht.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief DHT11 and DHT22 temperature and humidity sensors on their single wire bus
+ */
+use super::{drive_pin, is_pin_low, VirtualDevice};
+use crate::Rp2350;
+use std::collections::VecDeque;
+
+/// Delay between the release of the line by the host and the response of the sensor
+pub const RESPONSE_DELAY_US: u64 = 30;
+/// The sensor pulls the line low then high for that long before the data
+pub const RESPONSE_US: u64 = 80;
+/// Every bit starts with the line low for that long
+pub const BIT_LOW_US: u64 = 50;
+pub const BIT_ZERO_HIGH_US: u64 = 27;
+pub const BIT_ONE_HIGH_US: u64 = 70;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DhtKind {
+    /// Integral values only, 0 to 50°C and 20 to 90%
+    #[default]
+    Dht11,
+    /// Tenths of a unit, -40 to 80°C and 0 to 100%
+    Dht22,
+}
+
+impl DhtKind {
+    /// Shortest low pulse of the host which wakes the sensor up
+    pub fn start_us(self) -> u64 {
+        match self {
+            Self::Dht11 => 18_000,
+            Self::Dht22 => 1_000,
+        }
+    }
+}
+
+/// The sensor answers each start signal of the host, a low pulse on the data line, with
+/// 40 bits: the humidity, the temperature then a checksum. The line is open drain, it is
+/// low whenever the host or the sensor pulls it low.
+#[derive(Debug, Clone)]
+pub struct Dht {
+    pub pin: u8,
+    pub kind: DhtKind,
+    /// In °C
+    pub temperature: f64,
+    /// Relative humidity in %
+    pub humidity: f64,
+    /// Up to this many microseconds are randomly added to or removed from each pulse
+    pub jitter_us: u64,
+    /// Levels driven by the sensor and until which tick, the line is released afterward
+    pulses: VecDeque<(bool, u64)>,
+    low_since: Option<u64>,
+    readings: u64,
+    seed: u32,
+}
+
+impl Dht {
+    pub fn new(pin: u8, kind: DhtKind) -> Self {
+        Self {
+            pin,
+            kind,
+            temperature: 25.0,
+            humidity: 50.0,
+            jitter_us: 0,
+            pulses: VecDeque::new(),
+            low_since: None,
+            readings: 0,
+            seed: 0x2545_f491,
+        }
+    }
+
+    /// Number of readings answered since the sensor was attached
+    pub fn readings(&self) -> u64 {
+        self.readings
+    }
+
+    pub fn is_responding(&self) -> bool {
+        !self.pulses.is_empty()
+    }
+
+    /// The 5 bytes sent for the current values, the last one being the checksum
+    pub fn frame(&self) -> [u8; 5] {
+        let [h0, h1, t0, t1] = match self.kind {
+            DhtKind::Dht11 => {
+                let humidity = self.humidity.clamp(0.0, 100.0);
+                let temperature = self.temperature.clamp(0.0, 50.0);
+
+                [
+                    humidity.trunc() as u8,
+                    (humidity.fract() * 10.0) as u8,
+                    temperature.trunc() as u8,
+                    (temperature.fract() * 10.0) as u8,
+                ]
+            }
+            DhtKind::Dht22 => {
+                let humidity = (self.humidity.clamp(0.0, 100.0) * 10.0).round() as u16;
+                let temperature = (self.temperature.clamp(-40.0, 80.0) * 10.0).round() as i16;
+
+                // sign and magnitude, not two's complement
+                let temperature = match temperature < 0 {
+                    true => 0x8000 | temperature.unsigned_abs(),
+                    false => temperature as u16,
+                };
+
+                let [h0, h1] = humidity.to_be_bytes();
+                let [t0, t1] = temperature.to_be_bytes();
+                [h0, h1, t0, t1]
+            }
+        };
+
+        let checksum = [h0, h1, t0, t1]
+            .into_iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(byte));
+
+        [h0, h1, t0, t1, checksum]
+    }
+
+    fn jitter(&mut self) -> i64 {
+        if self.jitter_us == 0 {
+            return 0;
+        }
+
+        // xorshift32
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+
+        let range = self.jitter_us * 2 + 1;
+        (self.seed as u64 % range) as i64 - self.jitter_us as i64
+    }
+
+    fn respond(&mut self, mcu: &Rp2350, now: u64) {
+        let mut durations = vec![
+            (true, RESPONSE_DELAY_US),
+            (false, RESPONSE_US),
+            (true, RESPONSE_US),
+        ];
+
+        for byte in self.frame() {
+            for bit in (0..8).rev() {
+                let high = match byte & (1 << bit) != 0 {
+                    true => BIT_ONE_HIGH_US,
+                    false => BIT_ZERO_HIGH_US,
+                };
+
+                durations.push((false, BIT_LOW_US));
+                durations.push((true, high));
+            }
+        }
+
+        // the end of the transmission
+        durations.push((false, BIT_LOW_US));
+
+        let cycles_per_us = mcu.clock.clk_sys() as f64 / 1_000_000.0;
+        let mut until = now;
+
+        for (level, duration) in durations {
+            let duration = (duration as i64 + self.jitter()).max(1);
+            until += (duration as f64 * cycles_per_us).round() as u64;
+            self.pulses.push_back((level, until));
+        }
+    }
+}
+
+impl VirtualDevice for Dht {
+    fn attach(&mut self, mcu: &mut Rp2350) {
+        // idle high through the pull up resistor
+        drive_pin(mcu, self.pin, true);
+    }
+
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let now = *mcu.clock.ticks.borrow();
+        let host_low = is_pin_low(mcu, self.pin);
+
+        while self.pulses.front().is_some_and(|(_, until)| now >= *until) {
+            self.pulses.pop_front();
+
+            if self.pulses.is_empty() {
+                self.readings += 1;
+            }
+        }
+
+        // the host only starts a reading while the sensor is quiet
+        if self.pulses.is_empty() {
+            match (host_low, self.low_since) {
+                (true, None) => self.low_since = Some(now),
+                (false, Some(since)) => {
+                    self.low_since = None;
+
+                    let start = self.kind.start_us() * mcu.clock.clk_sys() / 1_000_000;
+                    if now - since >= start {
+                        self.respond(mcu, now);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let sensor_level = self.pulses.front().is_none_or(|(level, _)| *level);
+        drive_pin(mcu, self.pin, sensor_level && !host_low);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIO: u32 = 5;
+    const PIN: u8 = 4;
+
+    fn run(mcu: &mut Rp2350, dht: &mut Dht, cycles: u64) {
+        for _ in 0..cycles {
+            dht.tick(mcu);
+            mcu.clock.tick();
+        }
+    }
+
+    /// Send the start signal, then decode the response by the width of the high pulses
+    fn read(mcu: &mut Rp2350, dht: &mut Dht) -> Vec<u8> {
+        let cycles_per_us = mcu.clock.clk_sys() / 1_000_000;
+
+        mcu.gpio.borrow_mut().update_sio(1 << PIN, 0);
+        run(mcu, dht, dht.kind.start_us() * cycles_per_us);
+        mcu.gpio.borrow_mut().update_sio(0, 0);
+
+        let level = |mcu: &Rp2350| mcu.gpio.borrow().get_pin(PIN).unwrap().raw_input_value;
+        let mut highs = Vec::new();
+        let mut width = 0;
+        let mut last = true;
+
+        while dht.is_responding() || highs.is_empty() {
+            run(mcu, dht, 1);
+
+            let current = level(mcu);
+            match (last, current) {
+                (true, true) => width += 1,
+                (true, false) => highs.push(width),
+                (false, true) => width = 1,
+                (false, false) => {}
+            }
+
+            last = current;
+        }
+
+        // the delay before the response, then the response itself
+        assert_eq!(highs.len(), 2 + 40);
+
+        let bits: Vec<bool> = highs[2..]
+            .iter()
+            .map(|width| *width > 50 * cycles_per_us)
+            .collect();
+
+        bits.chunks(8)
+            .map(|bits| bits.iter().fold(0, |byte, bit| (byte << 1) | *bit as u8))
+            .collect()
+    }
+
+    fn setup(kind: DhtKind) -> (Rp2350, Dht) {
+        let mut mcu = Rp2350::new();
+        let mut dht = Dht::new(PIN, kind);
+        mcu.gpio.borrow_mut().pins[PIN as usize].ctrl = SIO;
+        dht.attach(&mut mcu);
+        (mcu, dht)
+    }
+
+    #[test]
+    fn test_dht22() {
+        let (mut mcu, mut dht) = setup(DhtKind::Dht22);
+        dht.temperature = -10.1;
+        dht.humidity = 65.2;
+
+        let frame = [0x02, 0x8c, 0x80, 0x65, 0x73];
+        assert_eq!(dht.frame(), frame);
+        assert_eq!(read(&mut mcu, &mut dht), frame);
+        assert_eq!(dht.readings(), 1);
+    }
+
+    #[test]
+    fn test_jitter() {
+        let (mut mcu, mut dht) = setup(DhtKind::Dht11);
+        dht.temperature = 23.0;
+        dht.humidity = 41.0;
+        dht.jitter_us = 5;
+
+        assert_eq!(read(&mut mcu, &mut dht), [41, 0, 23, 0, 64]);
+    }
+
+    #[test]
+    fn test_short_start() {
+        let (mut mcu, mut dht) = setup(DhtKind::Dht11);
+
+        mcu.gpio.borrow_mut().update_sio(1 << PIN, 0);
+        run(&mut mcu, &mut dht, 1_000 * 150);
+        mcu.gpio.borrow_mut().update_sio(0, 0);
+        run(&mut mcu, &mut dht, 10);
+
+        assert!(!dht.is_responding());
+    }
+}
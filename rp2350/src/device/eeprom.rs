@@ -0,0 +1,111 @@
+/**
+ * @file device/eeprom.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief 24xx serial EEPROM on the I2C bus, like the 24LC256
+ */
+use crate::peripherals::I2cDevice;
+
+/// Usual address of the 24xx EEPROMs with their A0-A2 pins tied low
+pub const EEPROM_ADDRESS: u8 = 0x50;
+
+/// EEPROM addressed with a 16 bit word address sent after the device address. A write
+/// sets the address then stores the following bytes, wrapping within the page. A read
+/// goes on from the current address, wrapping at the end of the memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eeprom {
+    pub memory: Vec<u8>,
+    page_size: usize,
+    address: usize,
+    /// Bytes of the word address still expected in the write
+    address_bytes: u8,
+}
+
+impl Eeprom {
+    /// The memory is erased, all its bytes read as 0xFF
+    pub fn new(size: usize, page_size: usize) -> Self {
+        Self {
+            memory: vec![0xFF; size.max(1)],
+            page_size: page_size.max(1),
+            address: 0,
+            address_bytes: 0,
+        }
+    }
+
+    /// 32 KiB with pages of 64 bytes
+    pub fn new_24lc256() -> Self {
+        Self::new(32 * 1024, 64)
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Address of the next byte read or written
+    pub fn address(&self) -> usize {
+        self.address
+    }
+}
+
+impl I2cDevice for Eeprom {
+    fn start(&mut self, read: bool) {
+        self.address_bytes = if read { 0 } else { 2 };
+    }
+
+    fn write(&mut self, data: u8) -> bool {
+        match self.address_bytes {
+            2 => self.address = (data as usize) << 8,
+            1 => self.address = (self.address | data as usize) % self.memory.len(),
+            _ => {
+                self.memory[self.address] = data;
+                let page = self.address - self.address % self.page_size;
+                self.address = page + (self.address + 1 - page) % self.page_size;
+                self.address %= self.memory.len();
+                return true;
+            }
+        }
+
+        self.address_bytes -= 1;
+        true
+    }
+
+    fn read(&mut self) -> u8 {
+        let data = self.memory[self.address];
+        self.address = (self.address + 1) % self.memory.len();
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_write_and_read() {
+        let mut eeprom = Eeprom::new_24lc256();
+
+        // the write wraps to the beginning of the page
+        eeprom.start(false);
+        for data in [0x00, 0x3E, 1, 2, 3, 4] {
+            assert!(eeprom.write(data));
+        }
+        eeprom.stop();
+
+        assert_eq!(&eeprom.memory[0x3E..0x40], &[1, 2]);
+        assert_eq!(&eeprom.memory[0x00..0x02], &[3, 4]);
+
+        // random read, a dummy write sets the address
+        eeprom.start(false);
+        eeprom.write(0x00);
+        eeprom.write(0x3F);
+        eeprom.start(true);
+        assert_eq!(eeprom.read(), 2);
+        assert_eq!(eeprom.read(), 0xFF);
+        eeprom.stop();
+
+        // the current address read goes on from there
+        eeprom.start(true);
+        assert_eq!(eeprom.read(), 0xFF);
+        assert_eq!(eeprom.address(), 0x42);
+    }
+}
@@ -0,0 +1,148 @@
+/**
+ * @file device/encoder_knob.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Rotary encoder module (KY-040) placed on the breadboard
+ */
+use super::circuit::{Branch, WIRE_OHMS};
+use super::rotary_encoder::QUADRATURE;
+use super::Component;
+
+const A: usize = 0;
+const B: usize = 1;
+const SW: usize = 2;
+
+/// Pull ups of the module on A and B
+const PULL_UP_OHMS: f64 = 10_000.0;
+/// A quadrature step every millisecond, a brisk turn of the knob
+const STEPS_PER_SECOND: u64 = 1000;
+
+/// Rotary encoder on a KY-040 module. A and B are contacts to GND with the pull ups of
+/// the module, high on the resting detent; turning the knob plays the quadrature steps
+/// back at the pace of a hand, clockwise with A leading B. SW is the push switch to GND.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncoderKnob {
+    pub steps_per_detent: u8,
+    pub pressed: bool,
+    position: i64,
+    target: i64,
+    last_step: u64,
+}
+
+impl Default for EncoderKnob {
+    fn default() -> Self {
+        Self {
+            steps_per_detent: 4,
+            pressed: false,
+            position: 0,
+            target: 0,
+            last_step: 0,
+        }
+    }
+}
+
+impl EncoderKnob {
+    /// Turn the knob by a number of detents, positive is clockwise
+    pub fn rotate(&mut self, detents: i32) {
+        self.target += detents as i64 * self.steps_per_detent as i64;
+    }
+
+    /// Detents already played back on A and B
+    pub fn position(&self) -> i64 {
+        self.position / self.steps_per_detent.max(1) as i64
+    }
+
+    /// Angle of the knob, in degrees, a detent is 18° like on the common 20 detents ones
+    pub fn angle(&self) -> f64 {
+        self.position as f64 * 18.0 / self.steps_per_detent.max(1) as f64
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.position == self.target
+    }
+
+    fn levels(&self) -> (bool, bool) {
+        QUADRATURE[self.position.rem_euclid(4) as usize]
+    }
+}
+
+impl Component for EncoderKnob {
+    fn name(&self) -> &'static str {
+        "Rotary encoder"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["A", "B", "SW"]
+    }
+
+    fn tick(&mut self, now: u64, clk_sys: u64) {
+        let step = (clk_sys / STEPS_PER_SECOND).max(1);
+
+        if self.position != self.target && now >= self.last_step + step {
+            self.position += (self.target - self.position).signum();
+            self.last_step = now;
+        }
+    }
+
+    fn branch(&self, pin: usize) -> Option<Branch> {
+        let (a, b) = self.levels();
+
+        match pin {
+            A if a => Some(Branch::source(true, PULL_UP_OHMS)),
+            B if b => Some(Branch::source(true, PULL_UP_OHMS)),
+            A | B => Some(Branch::source(false, WIRE_OHMS)),
+            SW => self.pressed.then(|| Branch::source(false, WIRE_OHMS)),
+            _ => None,
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{} detents", self.position()))
+    }
+
+    fn save(&self) -> String {
+        self.steps_per_detent.to_string()
+    }
+
+    fn load(&mut self, state: &str) {
+        if let Ok(steps) = state.parse() {
+            self.steps_per_detent = steps;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether a contact is open, its line left to the pull up of the module
+    fn high(knob: &EncoderKnob, pin: usize) -> bool {
+        matches!(knob.branch(pin), Some(Branch::Source { volts, .. }) if volts > 0.0)
+    }
+
+    #[test]
+    fn test_quadrature_timing() {
+        let clk_sys = 150_000;
+        let mut knob = EncoderKnob::default();
+
+        knob.rotate(1);
+        let mut changes = vec![(high(&knob, A), high(&knob, B))];
+
+        for now in 1..=10 * clk_sys {
+            knob.tick(now, clk_sys);
+
+            let levels = (high(&knob, A), high(&knob, B));
+            if changes.last() != Some(&levels) {
+                changes.push(levels);
+            }
+        }
+
+        // one step per millisecond, A leads B and it rests on both high
+        let mut expected = QUADRATURE.to_vec();
+        expected.push(QUADRATURE[0]);
+        assert_eq!(changes, expected);
+        assert!(knob.is_idle());
+        assert_eq!(knob.position(), 1);
+        assert_eq!(knob.angle(), 18.0);
+    }
+}
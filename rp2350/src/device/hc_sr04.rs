@@ -0,0 +1,225 @@
+/**
+ * @file device/hc_sr04.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief HC-SR04 ultrasonic distance sensor, answering a trigger pulse with an echo pulse
+ */
+use super::{drive_pin, is_pin_high, VirtualDevice};
+use crate::Rp2350;
+
+/// Width of the echo for each centimeter, the round trip of the sound
+pub const ECHO_US_PER_CM: f64 = 58.0;
+
+/// Shortest trigger pulse the sensor reacts to
+pub const MIN_TRIGGER_US: u64 = 10;
+
+/// Time to send the burst of 8 cycles at 40kHz before the echo is raised
+pub const BURST_US: u64 = 200;
+
+/// Echo width when nothing reflects the burst back
+pub const NO_ECHO_US: u64 = 38_000;
+
+/// The sensor does not see anything further than that
+pub const MAX_DISTANCE_CM: f64 = 400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HcSr04Pins {
+    pub trigger: u8,
+    pub echo: u8,
+}
+
+impl Default for HcSr04Pins {
+    fn default() -> Self {
+        Self {
+            trigger: 20,
+            echo: 21,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    /// TRIG is held high since the given tick
+    Triggered(u64),
+    /// Sending the burst, the echo rises at the given tick
+    Burst(u64),
+    /// ECHO is high until the given tick
+    Echo(u64),
+}
+
+/// The distance to the obstacle is set from the outside, `None` when there is nothing in
+/// front of the sensor. Falling TRIG after a pulse of at least 10µs starts a measurement,
+/// other triggers are ignored until ECHO falls again.
+#[derive(Debug, Clone)]
+pub struct HcSr04 {
+    pub pins: HcSr04Pins,
+    pub distance_cm: Option<f64>,
+    state: State,
+    /// Width of the last echo, in microseconds
+    last_echo_us: Option<f64>,
+    measurements: u64,
+}
+
+impl Default for HcSr04 {
+    fn default() -> Self {
+        Self::new(HcSr04Pins::default())
+    }
+}
+
+impl HcSr04 {
+    pub fn new(pins: HcSr04Pins) -> Self {
+        Self {
+            pins,
+            distance_cm: Some(100.0),
+            state: State::Idle,
+            last_echo_us: None,
+            measurements: 0,
+        }
+    }
+
+    /// Width of the echo pulse for the current distance
+    pub fn echo_us(&self) -> f64 {
+        match self.distance_cm {
+            Some(distance) if (0.0..=MAX_DISTANCE_CM).contains(&distance) => {
+                distance * ECHO_US_PER_CM
+            }
+            _ => NO_ECHO_US as f64,
+        }
+    }
+
+    pub fn last_echo_us(&self) -> Option<f64> {
+        self.last_echo_us
+    }
+
+    /// Number of measurements answered since the sensor was attached
+    pub fn measurements(&self) -> u64 {
+        self.measurements
+    }
+
+    pub fn is_measuring(&self) -> bool {
+        matches!(self.state, State::Burst(_) | State::Echo(_))
+    }
+}
+
+fn us_to_ticks(mcu: &Rp2350, us: f64) -> u64 {
+    (us * mcu.clock.clk_sys() as f64 / 1_000_000.0).round() as u64
+}
+
+impl VirtualDevice for HcSr04 {
+    fn attach(&mut self, mcu: &mut Rp2350) {
+        drive_pin(mcu, self.pins.echo, false);
+    }
+
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let now = *mcu.clock.ticks.borrow();
+        let trigger = is_pin_high(mcu, self.pins.trigger);
+
+        self.state = match self.state {
+            State::Idle if trigger => State::Triggered(now),
+            State::Triggered(_) if trigger => self.state,
+            State::Triggered(since) => {
+                match now - since >= us_to_ticks(mcu, MIN_TRIGGER_US as f64) {
+                    true => State::Burst(now + us_to_ticks(mcu, BURST_US as f64)),
+                    false => State::Idle,
+                }
+            }
+            State::Burst(until) if now >= until => {
+                let echo_us = self.echo_us();
+                self.last_echo_us = Some(echo_us);
+                State::Echo(now + us_to_ticks(mcu, echo_us).max(1))
+            }
+            State::Echo(until) if now >= until => {
+                self.measurements += 1;
+                State::Idle
+            }
+            state => state,
+        };
+
+        drive_pin(mcu, self.pins.echo, matches!(self.state, State::Echo(_)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIO: u32 = 5;
+
+    /// Tick the machine and the sensor for the given number of cycles
+    fn run(mcu: &mut Rp2350, sensor: &mut HcSr04, cycles: u64) {
+        for _ in 0..cycles {
+            sensor.tick(mcu);
+            mcu.clock.tick();
+        }
+    }
+
+    fn set_trigger(mcu: &mut Rp2350, level: bool) {
+        let pin = HcSr04Pins::default().trigger;
+        mcu.gpio
+            .borrow_mut()
+            .update_sio(1 << pin, (level as u32) << pin);
+    }
+
+    /// Measure the width of the echo in cycles
+    fn measure(mcu: &mut Rp2350, sensor: &mut HcSr04) -> u64 {
+        let pin = sensor.pins.echo;
+        let echo = |mcu: &Rp2350| mcu.gpio.borrow().get_pin(pin).unwrap().raw_input_value;
+
+        while !echo(mcu) {
+            run(mcu, sensor, 1);
+        }
+
+        let mut width = 0;
+        while echo(mcu) {
+            run(mcu, sensor, 1);
+            width += 1;
+        }
+
+        width
+    }
+
+    fn setup() -> (Rp2350, HcSr04) {
+        let mut mcu = Rp2350::new();
+        let mut sensor = HcSr04::default();
+        mcu.gpio.borrow_mut().pins[sensor.pins.trigger as usize].ctrl = SIO;
+        sensor.attach(&mut mcu);
+        (mcu, sensor)
+    }
+
+    #[test]
+    fn test_echo_width() {
+        let (mut mcu, mut sensor) = setup();
+        let cycles_per_us = mcu.clock.clk_sys() / 1_000_000;
+        sensor.distance_cm = Some(10.0);
+
+        set_trigger(&mut mcu, true);
+        run(&mut mcu, &mut sensor, 10 * cycles_per_us);
+        set_trigger(&mut mcu, false);
+
+        assert_eq!(measure(&mut mcu, &mut sensor), 580 * cycles_per_us);
+        assert_eq!(sensor.measurements(), 1);
+
+        // nothing in front of the sensor
+        sensor.distance_cm = None;
+        set_trigger(&mut mcu, true);
+        run(&mut mcu, &mut sensor, 10 * cycles_per_us);
+        set_trigger(&mut mcu, false);
+
+        assert_eq!(measure(&mut mcu, &mut sensor), NO_ECHO_US * cycles_per_us);
+    }
+
+    #[test]
+    fn test_short_trigger() {
+        let (mut mcu, mut sensor) = setup();
+        let cycles_per_us = mcu.clock.clk_sys() / 1_000_000;
+
+        set_trigger(&mut mcu, true);
+        run(&mut mcu, &mut sensor, 5 * cycles_per_us);
+        set_trigger(&mut mcu, false);
+        run(&mut mcu, &mut sensor, 1_000 * cycles_per_us);
+
+        assert!(!sensor.is_measuring());
+        assert_eq!(sensor.last_echo_us(), None);
+    }
+}
@@ -0,0 +1,248 @@
+/**
+ * @file device/hub75.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief HUB75 RGB LED panel, scanned two rows at a time
+ */
+use super::{is_pin_high, VirtualDevice};
+use crate::Rp2350;
+
+/// GPIOs wired to the HUB75 connector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hub75Pins {
+    /// R1, G1, B1 of the upper half then R2, G2, B2 of the lower half
+    pub rgb: [u8; 6],
+    /// Row select lines, starting from A
+    pub address: Vec<u8>,
+    pub clk: u8,
+    pub lat: u8,
+    /// Output enable, active low
+    pub oe: u8,
+}
+
+impl Default for Hub75Pins {
+    // wiring of the PIO HUB75 example of the Pico SDK
+    fn default() -> Self {
+        Self {
+            rgb: [0, 1, 2, 3, 4, 5],
+            address: vec![6, 7, 8, 9, 10],
+            clk: 11,
+            lat: 12,
+            oe: 13,
+        }
+    }
+}
+
+/// RGB bits of the upper and the lower pixel clocked in together
+type PixelPair = [bool; 6];
+
+/// A panel of `width` x `height` pixels.
+/// Each row is lit for as long as OE is held low, so the brightness of a pixel is the
+/// time it was lit relative to the time its row was enabled. This resolves the bit
+/// planes of a binary coded modulation into 8 bit colors.
+#[derive(Debug, Clone)]
+pub struct Hub75 {
+    pub pins: Hub75Pins,
+    width: usize,
+    height: usize,
+    shift: Vec<PixelPair>,
+    latched: Vec<PixelPair>,
+    /// Time each channel of the selected row pair was lit
+    lit_time: Vec<[u64; 6]>,
+    enabled_time: u64,
+    /// Colors of every pixel, row by row
+    frame: Vec<[u8; 3]>,
+    row: usize,
+    oe_since: Option<u64>,
+    ticks: u64,
+    last_clk: bool,
+    last_lat: bool,
+}
+
+impl Hub75 {
+    pub fn new(pins: Hub75Pins, width: usize, height: usize) -> Self {
+        let width = width.max(1);
+        let height = height.max(2) & !1;
+
+        Self {
+            pins,
+            width,
+            height,
+            shift: vec![Default::default(); width],
+            latched: vec![Default::default(); width],
+            lit_time: vec![Default::default(); width],
+            enabled_time: 0,
+            frame: vec![Default::default(); width * height],
+            row: 0,
+            oe_since: None,
+            ticks: 0,
+            last_clk: false,
+            last_lat: false,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Color of a pixel, from the last time its row was scanned
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        if x >= self.width {
+            return Default::default();
+        }
+
+        self.frame
+            .get(y * self.width + x)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn selected_row(&self, mcu: &Rp2350) -> usize {
+        let row = self
+            .pins
+            .address
+            .iter()
+            .enumerate()
+            .filter(|(_, pin)| is_pin_high(mcu, **pin))
+            .fold(0, |row, (bit, _)| row | (1 << bit));
+
+        row % (self.height / 2)
+    }
+
+    /// Account the time the latched pixels were shown since the last call
+    fn account_lit_time(&mut self) {
+        let Some(since) = self.oe_since.replace(self.ticks) else {
+            return;
+        };
+
+        let duration = self.ticks - since;
+        self.enabled_time += duration;
+
+        for (time, pixels) in self.lit_time.iter_mut().zip(self.latched.iter()) {
+            for (time, lit) in time.iter_mut().zip(pixels) {
+                if *lit {
+                    *time += duration;
+                }
+            }
+        }
+    }
+
+    /// Resolve the colors of the selected row pair once the panel moves to another row
+    fn finish_row(&mut self) {
+        let half = self.height / 2;
+
+        for (x, time) in self.lit_time.iter_mut().enumerate() {
+            for (half_index, y) in [self.row, self.row + half].into_iter().enumerate() {
+                let pixel = &mut self.frame[y * self.width + x];
+
+                for (channel, value) in pixel.iter_mut().enumerate() {
+                    *value = match self.enabled_time {
+                        0 => 0,
+                        total => (time[half_index * 3 + channel] * 255 / total) as u8,
+                    };
+                }
+            }
+
+            *time = Default::default();
+        }
+
+        self.enabled_time = 0;
+    }
+}
+
+impl VirtualDevice for Hub75 {
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        self.ticks += 1;
+
+        let clk = is_pin_high(mcu, self.pins.clk);
+        let lat = is_pin_high(mcu, self.pins.lat);
+        let enabled = !is_pin_high(mcu, self.pins.oe);
+        let row = self.selected_row(mcu);
+
+        if clk && !self.last_clk {
+            let mut pixels = PixelPair::default();
+
+            for (bit, pin) in pixels.iter_mut().zip(self.pins.rgb) {
+                *bit = is_pin_high(mcu, pin);
+            }
+
+            // the first pixel shifted in ends up in the leftmost column
+            self.shift.remove(0);
+            self.shift.push(pixels);
+        }
+
+        if row != self.row || (lat && !self.last_lat) || !enabled {
+            self.account_lit_time();
+        }
+
+        if row != self.row {
+            self.finish_row();
+            self.row = row;
+        }
+
+        if lat && !self.last_lat {
+            self.latched.copy_from_slice(&self.shift);
+        }
+
+        match enabled {
+            true => {
+                self.oe_since.get_or_insert(self.ticks);
+            }
+            false => self.oe_since = None,
+        }
+
+        self.last_clk = clk;
+        self.last_lat = lat;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIO: u32 = 5;
+
+    fn drive(mcu: &mut Rp2350, panel: &mut Hub75, pins: u32) {
+        mcu.gpio.borrow_mut().update_sio(u32::MAX, pins);
+        panel.tick(mcu);
+    }
+
+    #[test]
+    fn test_bit_planes() {
+        let mut mcu = Rp2350::new();
+        let mut panel = Hub75::new(Hub75Pins::default(), 2, 4);
+        let oe_high = 1 << 13;
+
+        for pin in 0..14 {
+            mcu.gpio.borrow_mut().pins[pin].ctrl = SIO;
+        }
+
+        // row 0: red on the upper left pixel for the MSB plane only, full blue on the
+        // lower right one
+        for (plane, duration) in [(false, 1), (true, 3)] {
+            for pixel in [plane as u32, 1 << 5] {
+                drive(&mut mcu, &mut panel, oe_high | pixel);
+                drive(&mut mcu, &mut panel, oe_high | pixel | (1 << 11));
+            }
+
+            drive(&mut mcu, &mut panel, oe_high | (1 << 12));
+            drive(&mut mcu, &mut panel, oe_high);
+
+            for _ in 0..duration {
+                drive(&mut mcu, &mut panel, 0);
+            }
+        }
+
+        // move on to row 1
+        drive(&mut mcu, &mut panel, oe_high | (1 << 6));
+
+        assert_eq!(panel.pixel(0, 0), [191, 0, 0]);
+        assert_eq!(panel.pixel(1, 2), [0, 0, 255]);
+        assert_eq!(panel.pixel(1, 0), [0, 0, 0]);
+        assert_eq!(panel.pixel(0, 2), [0, 0, 0]);
+    }
+}
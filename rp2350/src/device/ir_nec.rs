@@ -0,0 +1,442 @@
+/**
+ * @file device/ir_nec.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Infrared remote and receiver speaking the NEC protocol
+ */
+use super::{drive_pin, is_pin_high, VirtualDevice};
+use crate::Rp2350;
+use std::collections::VecDeque;
+
+pub const LEADER_MARK_US: f64 = 9_000.0;
+pub const LEADER_SPACE_US: f64 = 4_500.0;
+/// Space after the leader of a repeat code, sent while the button is held
+pub const REPEAT_SPACE_US: f64 = 2_250.0;
+pub const BIT_MARK_US: f64 = 562.5;
+pub const ZERO_SPACE_US: f64 = 562.5;
+pub const ONE_SPACE_US: f64 = 1_687.5;
+/// Period of the frames and the repeat codes, from the start of one to the next
+pub const REPEAT_PERIOD_US: f64 = 108_000.0;
+
+/// Relative error on the width of a pulse accepted by the decoder
+pub const TOLERANCE: f64 = 0.25;
+
+/// The receiver treats its input as one mark until it stays low for that long, the
+/// carrier of 38kHz toggles about every 13µs
+pub const CARRIER_HOLD_US: f64 = 40.0;
+
+/// Address and command of a frame. An address up to 0xff is sent with its inverse, a
+/// larger one is an extended address taking both bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NecCode {
+    pub address: u16,
+    pub command: u8,
+}
+
+impl NecCode {
+    pub fn new(address: u8, command: u8) -> Self {
+        Self {
+            address: address as u16,
+            command,
+        }
+    }
+
+    /// The 32 bits of the frame, sent from the least significant one
+    pub fn bits(self) -> u32 {
+        let address = match self.address {
+            0..=0xff => self.address as u32 | ((!self.address as u32 & 0xff) << 8),
+            _ => self.address as u32,
+        };
+
+        address | ((self.command as u32) << 16) | ((!self.command as u32) << 24)
+    }
+
+    /// `None` if the command does not match its inverse
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        let [address_low, address_high, command, inverse] = bits.to_le_bytes();
+
+        if command != !inverse {
+            return None;
+        }
+
+        let address = match address_low == !address_high {
+            true => address_low as u16,
+            false => u16::from_le_bytes([address_low, address_high]),
+        };
+
+        Some(Self { address, command })
+    }
+
+    /// Marks and spaces of the frame with their width in microseconds
+    pub fn pulses(self) -> Vec<(bool, f64)> {
+        let bits = self.bits();
+        let mut pulses = vec![(true, LEADER_MARK_US), (false, LEADER_SPACE_US)];
+
+        for bit in 0..32 {
+            let space = match bits & (1 << bit) != 0 {
+                true => ONE_SPACE_US,
+                false => ZERO_SPACE_US,
+            };
+
+            pulses.push((true, BIT_MARK_US));
+            pulses.push((false, space));
+        }
+
+        pulses.push((true, BIT_MARK_US));
+        pulses
+    }
+}
+
+/// Marks and spaces of a repeat code
+pub fn repeat_pulses() -> Vec<(bool, f64)> {
+    vec![
+        (true, LEADER_MARK_US),
+        (false, REPEAT_SPACE_US),
+        (true, BIT_MARK_US),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NecEvent {
+    Code(NecCode),
+    Repeat,
+    /// The timing is right but the command does not match its inverse
+    Invalid(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum DecoderState {
+    #[default]
+    Idle,
+    Leader,
+    /// Received bits and their number, waiting for the mark of the next bit
+    BitMark(u32, u32),
+    BitSpace(u32, u32),
+    RepeatMark,
+}
+
+/// Decode the marks and spaces of the NEC protocol, pulse by pulse
+#[derive(Debug, Clone, Default)]
+pub struct NecDecoder {
+    state: DecoderState,
+    errors: u64,
+}
+
+fn matches_width(width_us: f64, expected_us: f64) -> bool {
+    (width_us - expected_us).abs() <= expected_us * TOLERANCE
+}
+
+impl NecDecoder {
+    /// Number of pulses which did not fit in a frame
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Feed a mark or a space with its width, a frame is returned on its last mark
+    pub fn pulse(&mut self, mark: bool, width_us: f64) -> Option<NecEvent> {
+        use DecoderState::*;
+
+        let (state, event) = match (self.state, mark) {
+            (Idle, true) if matches_width(width_us, LEADER_MARK_US) => (Leader, None),
+            (Idle, _) => (Idle, None),
+            (Leader, false) if matches_width(width_us, LEADER_SPACE_US) => (BitMark(0, 0), None),
+            (Leader, false) if matches_width(width_us, REPEAT_SPACE_US) => (RepeatMark, None),
+            (BitMark(bits, 32), true) if matches_width(width_us, BIT_MARK_US) => {
+                let event = match NecCode::from_bits(bits) {
+                    Some(code) => NecEvent::Code(code),
+                    None => NecEvent::Invalid(bits),
+                };
+
+                (Idle, Some(event))
+            }
+            (BitMark(bits, count), true) if matches_width(width_us, BIT_MARK_US) => {
+                (BitSpace(bits, count), None)
+            }
+            (BitSpace(bits, count), false) if matches_width(width_us, ZERO_SPACE_US) => {
+                (BitMark(bits, count + 1), None)
+            }
+            (BitSpace(bits, count), false) if matches_width(width_us, ONE_SPACE_US) => {
+                (BitMark(bits | (1 << count), count + 1), None)
+            }
+            (RepeatMark, true) if matches_width(width_us, BIT_MARK_US) => {
+                (Idle, Some(NecEvent::Repeat))
+            }
+            _ => {
+                self.errors += 1;
+                self.state = Idle;
+
+                // the pulse may still be the leader of the next frame
+                return self.pulse(mark, width_us);
+            }
+        };
+
+        self.state = state;
+        event
+    }
+}
+
+fn us_to_ticks(mcu: &Rp2350, us: f64) -> u64 {
+    (us * mcu.clock.clk_sys() as f64 / 1_000_000.0).round() as u64
+}
+
+/// A remote seen through a demodulating IR receiver module, the output is high while idle
+/// and low during the marks. Each press sends one frame, repeat codes follow for as long
+/// as the button is held.
+#[derive(Debug, Clone)]
+pub struct IrRemote {
+    pub pin: u8,
+    /// Whether the last pressed button is still held down
+    pub held: bool,
+    pending: VecDeque<NecCode>,
+    /// Marks and spaces being sent and until which tick
+    pulses: VecDeque<(bool, u64)>,
+    last_code: Option<NecCode>,
+    /// Start of the last frame or repeat code
+    last_start: u64,
+    frames_sent: u64,
+}
+
+impl IrRemote {
+    pub fn new(pin: u8) -> Self {
+        Self {
+            pin,
+            held: false,
+            pending: VecDeque::new(),
+            pulses: VecDeque::new(),
+            last_code: None,
+            last_start: 0,
+            frames_sent: 0,
+        }
+    }
+
+    /// Queue a frame, it is sent once the previous ones are done
+    pub fn press(&mut self, code: NecCode) {
+        self.pending.push_back(code);
+    }
+
+    pub fn is_transmitting(&self) -> bool {
+        !self.pulses.is_empty()
+    }
+
+    pub fn last_code(&self) -> Option<NecCode> {
+        self.last_code
+    }
+
+    /// Number of frames sent, without the repeat codes
+    pub fn frames_sent(&self) -> u64 {
+        self.frames_sent
+    }
+
+    fn send(&mut self, mcu: &Rp2350, now: u64, pulses: Vec<(bool, f64)>) {
+        let mut elapsed = 0.0;
+        self.last_start = now;
+
+        for (mark, width) in pulses {
+            elapsed += width;
+            self.pulses
+                .push_back((mark, now + us_to_ticks(mcu, elapsed)));
+        }
+    }
+}
+
+impl VirtualDevice for IrRemote {
+    fn attach(&mut self, mcu: &mut Rp2350) {
+        drive_pin(mcu, self.pin, true);
+    }
+
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let now = *mcu.clock.ticks.borrow();
+
+        while self.pulses.front().is_some_and(|(_, until)| now >= *until) {
+            self.pulses.pop_front();
+        }
+
+        // the frames do not start closer than their period
+        let next_start = self.last_start + us_to_ticks(mcu, REPEAT_PERIOD_US);
+        let ready = self.pulses.is_empty() && (self.frames_sent == 0 || now >= next_start);
+
+        if ready {
+            if let Some(code) = self.pending.pop_front() {
+                self.send(mcu, now, code.pulses());
+                self.last_code = Some(code);
+                self.frames_sent += 1;
+            } else if self.held && self.last_code.is_some() {
+                self.send(mcu, now, repeat_pulses());
+            }
+        }
+
+        let mark = self.pulses.front().is_some_and(|(mark, _)| *mark);
+        drive_pin(mcu, self.pin, !mark);
+    }
+}
+
+/// Decode what the firmware sends to an IR LED, the pin is high during the marks and may
+/// be modulated by a carrier
+#[derive(Debug, Clone)]
+pub struct IrReceiver {
+    pub pin: u8,
+    decoder: NecDecoder,
+    events: Vec<NecEvent>,
+    mark_start: Option<u64>,
+    /// End of the last mark, the next mark ends the space started there
+    mark_end: Option<u64>,
+    last_high: u64,
+}
+
+impl IrReceiver {
+    pub fn new(pin: u8) -> Self {
+        Self {
+            pin,
+            decoder: NecDecoder::default(),
+            events: Vec::new(),
+            mark_start: None,
+            mark_end: None,
+            last_high: 0,
+        }
+    }
+
+    pub fn events(&self) -> &[NecEvent] {
+        &self.events
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Number of pulses with a timing out of the protocol
+    pub fn errors(&self) -> u64 {
+        self.decoder.errors()
+    }
+
+    fn pulse(&mut self, mcu: &Rp2350, mark: bool, ticks: u64) {
+        let width_us = ticks as f64 * 1_000_000.0 / mcu.clock.clk_sys() as f64;
+
+        if let Some(event) = self.decoder.pulse(mark, width_us) {
+            self.events.push(event);
+        }
+    }
+}
+
+impl VirtualDevice for IrReceiver {
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let now = *mcu.clock.ticks.borrow();
+
+        if is_pin_high(mcu, self.pin) {
+            if self.mark_start.is_none() {
+                if let Some(end) = self.mark_end {
+                    self.pulse(mcu, false, now - end);
+                }
+
+                self.mark_start = Some(now);
+            }
+
+            self.last_high = now;
+            return;
+        }
+
+        let Some(start) = self.mark_start else {
+            return;
+        };
+
+        // the mark lasts until the last high level of the carrier
+        if now - self.last_high > us_to_ticks(mcu, CARRIER_HOLD_US) {
+            let end = self.last_high + 1;
+            self.pulse(mcu, true, end - start);
+            self.mark_start = None;
+            self.mark_end = Some(end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIO: u32 = 5;
+
+    fn width_us(mcu: &Rp2350, ticks: u64) -> f64 {
+        ticks as f64 * 1_000_000.0 / mcu.clock.clk_sys() as f64
+    }
+
+    #[test]
+    fn test_code_bits() {
+        let code = NecCode::new(0x04, 0x08);
+        assert_eq!(code.bits(), 0xf708_fb04);
+        assert_eq!(NecCode::from_bits(code.bits()), Some(code));
+
+        let extended = NecCode {
+            address: 0x1234,
+            command: 0x56,
+        };
+        assert_eq!(NecCode::from_bits(extended.bits()), Some(extended));
+        assert_eq!(NecCode::from_bits(0x0008_fb04), None);
+    }
+
+    #[test]
+    fn test_remote() {
+        let mut mcu = Rp2350::new();
+        let mut remote = IrRemote::new(3);
+        let mut decoder = NecDecoder::default();
+        let mut events = Vec::new();
+
+        remote.attach(&mut mcu);
+        remote.press(NecCode::new(0x10, 0x42));
+        remote.held = true;
+
+        // the frame then the first repeat code
+        let mut last = true;
+        let mut since = 0;
+        let cycles = us_to_ticks(&mcu, REPEAT_PERIOD_US + 15_000.0);
+
+        for _ in 0..cycles {
+            remote.tick(&mut mcu);
+            mcu.clock.tick();
+
+            let now = *mcu.clock.ticks.borrow();
+            let level = mcu.gpio.borrow().get_pin(3).unwrap().raw_input_value;
+
+            if level != last {
+                // the output of the receiver module is low during a mark
+                events.extend(decoder.pulse(!last, width_us(&mcu, now - since)));
+                since = now;
+                last = level;
+            }
+        }
+
+        assert_eq!(
+            events,
+            [NecEvent::Code(NecCode::new(0x10, 0x42)), NecEvent::Repeat]
+        );
+        assert_eq!(decoder.errors(), 0);
+        assert_eq!(remote.frames_sent(), 1);
+    }
+
+    #[test]
+    fn test_receiver_with_carrier() {
+        let mut mcu = Rp2350::new();
+        let mut receiver = IrReceiver::new(7);
+        mcu.gpio.borrow_mut().pins[7].ctrl = SIO;
+        receiver.attach(&mut mcu);
+
+        let carrier_half_period = us_to_ticks(&mcu, 13.0);
+        let code = NecCode::new(0x00, 0x45);
+
+        let mut pulses = code.pulses();
+        pulses.push((false, 40_000.0));
+        pulses.extend(repeat_pulses());
+        pulses.push((false, 1_000.0));
+
+        for (mark, width) in pulses {
+            for tick in 0..us_to_ticks(&mcu, width) {
+                let high = mark && (tick / carrier_half_period) & 1 == 0;
+                mcu.gpio.borrow_mut().update_sio(1 << 7, (high as u32) << 7);
+
+                receiver.tick(&mut mcu);
+                mcu.clock.tick();
+            }
+        }
+
+        assert_eq!(receiver.events(), [NecEvent::Code(code), NecEvent::Repeat]);
+        assert_eq!(receiver.errors(), 0);
+    }
+}
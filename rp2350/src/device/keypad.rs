@@ -0,0 +1,152 @@
+/**
+ * @file device/keypad.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief 4x4 membrane keypad scanned through its rows and columns, placed on the breadboard
+ */
+use super::circuit::Branch;
+use super::Component;
+
+pub const KEYPAD_ROWS: usize = 4;
+pub const KEYPAD_COLUMNS: usize = 4;
+
+/// Labels of the keys, row by row
+pub const KEYPAD_LABELS: [[char; KEYPAD_COLUMNS]; KEYPAD_ROWS] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// Resistance standing for a closed key. The level of a line crosses to the other one
+/// through it, without fighting the pad which drives that line.
+const KEY_OHMS: f64 = 1_000.0;
+
+/// Membrane keypad, a pressed key joins its row to its column. The firmware drives the
+/// rows one at a time and reads the columns with their pulls, or the other way around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Keypad {
+    pub pressed: [[bool; KEYPAD_COLUMNS]; KEYPAD_ROWS],
+    /// Levels of R1..R4 then C1..C4, as last seen
+    levels: [bool; KEYPAD_ROWS + KEYPAD_COLUMNS],
+}
+
+impl Keypad {
+    /// Labels of the keys held down
+    pub fn pressed_keys(&self) -> impl Iterator<Item = char> + '_ {
+        (0..KEYPAD_ROWS).flat_map(move |row| {
+            (0..KEYPAD_COLUMNS)
+                .filter(move |column| self.pressed[row][*column])
+                .map(move |column| KEYPAD_LABELS[row][column])
+        })
+    }
+
+    /// Line on the other side of the keys held down on a line, rows then columns
+    fn joined(&self, pin: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..KEYPAD_ROWS * KEYPAD_COLUMNS)
+            .map(|key| (key / KEYPAD_COLUMNS, key % KEYPAD_COLUMNS))
+            .filter(|(row, column)| self.pressed[*row][*column])
+            .filter_map(move |(row, column)| {
+                if pin == row {
+                    Some(KEYPAD_ROWS + column)
+                } else if pin == KEYPAD_ROWS + column {
+                    Some(row)
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+impl Component for Keypad {
+    fn name(&self) -> &'static str {
+        "Keypad 4x4"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["R1", "R2", "R3", "R4", "C1", "C2", "C3", "C4"]
+    }
+
+    fn notify_pin_change(&mut self, pin: usize, level: bool, _now: u64, _clk_sys: u64) {
+        if let Some(current) = self.levels.get_mut(pin) {
+            *current = level;
+        }
+    }
+
+    /// A line takes the level of the line joined to it by a key, a low one wins like
+    /// on the scanned column
+    fn branch(&self, pin: usize) -> Option<Branch> {
+        self.joined(pin)
+            .map(|other| self.levels[other])
+            .reduce(|a, b| a && b)
+            .map(|level| Branch::source(level, KEY_OHMS))
+    }
+
+    fn status(&self) -> Option<String> {
+        let keys: String = self.pressed_keys().collect();
+        (!keys.is_empty()).then_some(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{Breadboard, VirtualDevice};
+    use crate::Rp2350;
+
+    const SIO: u32 = 5;
+
+    /// Rows on GPIO0..3 driven by the SIO, columns on GPIO4..7 with their pull ups
+    fn scan(mcu: &mut Rp2350, board: &mut Breadboard) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+
+        for row in 0..KEYPAD_ROWS {
+            // only the scanned row is low, the others are high
+            let low = 1 << row;
+            mcu.gpio.borrow_mut().update_sio(0b1111, 0b1111 & !low);
+
+            for _ in 0..2 {
+                board.tick(mcu);
+            }
+
+            for column in 0..KEYPAD_COLUMNS {
+                let gpio = mcu.gpio.borrow();
+                if !gpio.get_pin(4 + column as u8).unwrap().raw_input_value {
+                    found.push((row, column));
+                }
+            }
+        }
+
+        found
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut mcu = Rp2350::new();
+        let mut board = Breadboard::new();
+        let keypad = board.place(Box::new(Keypad::default()), (0, 0));
+
+        for pin in 0..8 {
+            board.components[keypad].wires[pin] = Some(pin as u8);
+        }
+
+        {
+            let mut gpio = mcu.gpio.borrow_mut();
+            for pin in 0..4 {
+                gpio.pins[pin].ctrl = SIO;
+                // the column pads pull up, not down
+                gpio.pins[4 + pin].pad = (gpio.pins[4 + pin].pad & !0b1100) | 0b1000;
+            }
+        }
+
+        assert!(scan(&mut mcu, &mut board).is_empty());
+
+        let pad = board.components[keypad].downcast_mut::<Keypad>().unwrap();
+        pad.pressed[1][2] = true;
+        pad.pressed[3][0] = true;
+        assert_eq!(pad.pressed_keys().collect::<String>(), "6*");
+
+        assert_eq!(scan(&mut mcu, &mut board), [(1, 2), (3, 0)]);
+        assert!(board.faults().is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+/**
+ * @file device/max7219.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief MAX7219 8x8 LED matrix driver, daisy chained over SPI
+ */
+use super::{is_pin_high, VirtualDevice};
+use crate::peripherals::SpiDevice;
+use crate::Rp2350;
+
+pub const REG_NOOP: u8 = 0x0;
+pub const REG_DIGIT0: u8 = 0x1; // up to DIGIT7 at 0x8
+pub const REG_DECODE_MODE: u8 = 0x9;
+pub const REG_INTENSITY: u8 = 0xA;
+pub const REG_SCAN_LIMIT: u8 = 0xB;
+pub const REG_SHUTDOWN: u8 = 0xC;
+pub const REG_DISPLAY_TEST: u8 = 0xF;
+
+/// GPIOs wired to the chain, the SPI TX, SCK and CSn of the chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Max7219Pins {
+    pub din: u8,
+    pub clk: u8,
+    pub cs: u8,
+}
+
+impl Default for Max7219Pins {
+    // default SPI0 pins of the Pico SDK
+    fn default() -> Self {
+        Self {
+            din: 19,
+            clk: 18,
+            cs: 17,
+        }
+    }
+}
+
+/// One driver of the chain with its 8x8 matrix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Max7219Module {
+    /// One byte per row, the MSB is the leftmost column
+    pub digits: [u8; 8],
+    pub decode_mode: u8,
+    pub intensity: u8,
+    pub scan_limit: u8,
+    pub display_test: bool,
+    /// The display is blanked until SHUTDOWN is written with 1
+    pub normal_operation: bool,
+    shift: u16,
+}
+
+impl Max7219Module {
+    fn latch(&mut self) {
+        let address = ((self.shift >> 8) & 0xF) as u8;
+        let data = self.shift as u8;
+
+        match address {
+            REG_NOOP => {}
+            REG_DIGIT0..=0x8 => self.digits[(address - REG_DIGIT0) as usize] = data,
+            REG_DECODE_MODE => self.decode_mode = data,
+            REG_INTENSITY => self.intensity = data & 0xF,
+            REG_SCAN_LIMIT => self.scan_limit = data & 0x7,
+            REG_SHUTDOWN => self.normal_operation = data & 1 != 0,
+            REG_DISPLAY_TEST => self.display_test = data & 1 != 0,
+            _ => log::warn!("MAX7219: write to unknown register {address:#X}"),
+        }
+    }
+
+    pub fn is_lit(&self, row: usize, column: usize) -> bool {
+        if self.display_test {
+            return true;
+        }
+
+        if !self.normal_operation || row > self.scan_limit as usize {
+            return false;
+        }
+
+        self.digits[row] & (0x80 >> column) != 0
+    }
+
+    /// Brightness of the lit LEDs from 0.0 to 1.0, following the 16 duty cycle steps
+    pub fn brightness(&self) -> f32 {
+        match self.display_test {
+            true => 1.0,
+            false => (self.intensity as f32 * 2.0 + 1.0) / 32.0,
+        }
+    }
+}
+
+/// A chain of MAX7219 drivers, each shifting its 16 bit register into the next one.
+/// The pins are sampled on every tick so both the SPI and a bit banged bus are decoded.
+#[derive(Debug, Clone)]
+pub struct Max7219 {
+    pub pins: Max7219Pins,
+    /// The first module is the one wired to the chip
+    pub modules: Vec<Max7219Module>,
+    last_clk: bool,
+    last_cs: bool,
+}
+
+impl Max7219 {
+    pub fn new(pins: Max7219Pins, nof_modules: usize) -> Self {
+        Self {
+            pins,
+            modules: vec![Max7219Module::default(); nof_modules.max(1)],
+            last_clk: false,
+            last_cs: true,
+        }
+    }
+
+    pub fn is_lit(&self, module: usize, row: usize, column: usize) -> bool {
+        self.modules
+            .get(module)
+            .is_some_and(|m| m.is_lit(row, column))
+    }
+
+    /// Shift a bit into the chain, the one out of DOUT of the last module is returned
+    fn shift_in(&mut self, din: bool) -> bool {
+        let mut carry = din as u16;
+
+        for module in self.modules.iter_mut() {
+            let out = module.shift >> 15;
+            module.shift = (module.shift << 1) | carry;
+            carry = out;
+        }
+
+        carry != 0
+    }
+}
+
+impl VirtualDevice for Max7219 {
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let clk = is_pin_high(mcu, self.pins.clk);
+        let cs = is_pin_high(mcu, self.pins.cs);
+
+        // data is shifted in on the rising edge of the clock while CS is low
+        if !cs && clk && !self.last_clk {
+            let din = is_pin_high(mcu, self.pins.din);
+            self.shift_in(din);
+        }
+
+        // and latched into the registers on the rising edge of CS
+        if cs && !self.last_cs {
+            self.modules.iter_mut().for_each(Max7219Module::latch);
+        }
+
+        self.last_clk = clk;
+        self.last_cs = cs;
+    }
+}
+
+/// The chain on the bus of an SPI block, the pins are then left alone. DOUT of the last
+/// module is looped back as MISO.
+impl SpiDevice for Max7219 {
+    fn transfer(&mut self, data: u8) -> u8 {
+        (0..8).rev().fold(0, |out, bit| {
+            (out << 1) | self.shift_in((data >> bit) & 1 != 0) as u8
+        })
+    }
+
+    fn deselect(&mut self) {
+        self.modules.iter_mut().for_each(Max7219Module::latch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIO: u32 = 5;
+
+    fn drive(mcu: &mut Rp2350, device: &mut Max7219, pins: u32) {
+        mcu.gpio.borrow_mut().update_sio(u32::MAX, pins);
+        device.tick(mcu);
+    }
+
+    fn send(mcu: &mut Rp2350, device: &mut Max7219, words: &[u16]) {
+        let Max7219Pins { din, clk, cs } = device.pins;
+        drive(mcu, device, 1 << cs);
+        drive(mcu, device, 0);
+
+        for word in words {
+            for bit in (0..16).rev() {
+                let data = (((word >> bit) & 1) as u32) << din;
+                drive(mcu, device, data);
+                drive(mcu, device, data | (1 << clk));
+            }
+        }
+
+        drive(mcu, device, 1 << cs);
+    }
+
+    #[test]
+    fn test_daisy_chain() {
+        let mut mcu = Rp2350::new();
+        let mut device = Max7219::new(Max7219Pins::default(), 2);
+
+        for pin in [17, 18, 19] {
+            mcu.gpio.borrow_mut().pins[pin].ctrl = SIO;
+        }
+
+        // the last word shifted goes to the module wired to the chip
+        send(&mut mcu, &mut device, &[0x0C01, 0x0C01]);
+        send(&mut mcu, &mut device, &[0x0B07, 0x0B07]);
+        send(&mut mcu, &mut device, &[0x0181, 0x0000]);
+        send(&mut mcu, &mut device, &[0x0000, 0x0842]);
+
+        assert!(device.is_lit(1, 0, 0));
+        assert!(device.is_lit(1, 0, 7));
+        assert!(!device.is_lit(1, 0, 1));
+        assert!(!device.is_lit(0, 0, 0));
+        assert!(device.is_lit(0, 7, 1));
+        assert!(device.is_lit(0, 7, 6));
+        assert!(!device.is_lit(1, 7, 1));
+    }
+
+    #[test]
+    fn test_spi_bus() {
+        let mut device = Max7219::new(Max7219Pins::default(), 1);
+
+        for word in [0x0C01u16, 0x0B07, 0x0418] {
+            device.select();
+            device.transfer((word >> 8) as u8);
+            device.transfer(word as u8);
+            device.deselect();
+        }
+
+        assert!(device.is_lit(0, 3, 3));
+        assert!(device.is_lit(0, 3, 4));
+        assert!(!device.is_lit(0, 3, 5));
+    }
+
+    #[test]
+    fn test_shutdown_blanks_display() {
+        let mut module = Max7219Module {
+            digits: [0xFF; 8],
+            scan_limit: 7,
+            ..Default::default()
+        };
+        assert!(!module.is_lit(0, 0));
+
+        module.display_test = true;
+        assert!(module.is_lit(0, 0));
+    }
+}
@@ -0,0 +1,261 @@
+/**
+ * @file device/motor.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Hobby servo and DC motor driven by PWM, placed on the breadboard
+ */
+use super::Component;
+
+/// Pulse widths of a hobby servo for 0° and 180°
+const SERVO_MIN_US: f64 = 500.0;
+const SERVO_MAX_US: f64 = 2500.0;
+/// Speed of an SG90, 0.1s per 60°
+const SERVO_DEGREES_PER_SECOND: f64 = 600.0;
+/// Time constant of the speed of the motor
+const MOTOR_INERTIA_SECONDS: f64 = 0.1;
+/// The animation is stepped once per millisecond
+const STEPS_PER_SECOND: u64 = 1000;
+
+/// Measures the pulses on a pin: the width of the last high pulse and the period
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct PulseMeter {
+    level: bool,
+    last_rise: Option<u64>,
+    last_edge: u64,
+    high: u64,
+    period: u64,
+}
+
+impl PulseMeter {
+    fn edge(&mut self, level: bool, now: u64) {
+        match (level, self.last_rise) {
+            (true, Some(rise)) => self.period = now - rise,
+            (false, Some(rise)) => self.high = now - rise,
+            _ => {}
+        }
+
+        if level {
+            self.last_rise = Some(now);
+        }
+
+        self.level = level;
+        self.last_edge = now;
+    }
+
+    /// Part of the period spent high, a line which stopped toggling is 0 or 1
+    fn duty(&self, now: u64) -> f64 {
+        let steady = self.period == 0 || now - self.last_edge > 2 * self.period;
+
+        match steady {
+            true => self.level as u8 as f64,
+            false => (self.high as f64 / self.period as f64).min(1.0),
+        }
+    }
+}
+
+/// Hobby servo, its angle follows the width of the pulses on its signal pin, 0.5ms for
+/// 0° up to 2.5ms for 180°, at the speed of the real one
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Servo {
+    /// Angle of the horn, in degrees
+    pub angle: f64,
+    target: f64,
+    meter: PulseMeter,
+    last_step: u64,
+}
+
+impl Servo {
+    /// Angle it is heading to, from the last pulse
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+}
+
+impl Component for Servo {
+    fn name(&self) -> &'static str {
+        "Servo"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["SIG"]
+    }
+
+    fn notify_pin_change(&mut self, _pin: usize, level: bool, now: u64, clk_sys: u64) {
+        self.meter.edge(level, now);
+
+        if level {
+            return;
+        }
+
+        // out of range pulses are ignored like glitches
+        let width_us = self.meter.high as f64 * 1e6 / clk_sys.max(1) as f64;
+        if (SERVO_MIN_US * 0.8..=SERVO_MAX_US * 1.2).contains(&width_us) {
+            let span = SERVO_MAX_US - SERVO_MIN_US;
+            self.target = ((width_us - SERVO_MIN_US) / span * 180.0).clamp(0.0, 180.0);
+        }
+    }
+
+    fn tick(&mut self, now: u64, clk_sys: u64) {
+        let step = (clk_sys / STEPS_PER_SECOND).max(1);
+        if now < self.last_step + step {
+            return;
+        }
+
+        let seconds = (now - self.last_step) as f64 / clk_sys.max(1) as f64;
+        let max_move = SERVO_DEGREES_PER_SECOND * seconds;
+        self.angle += (self.target - self.angle).clamp(-max_move, max_move);
+        self.last_step = now;
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{:.0}°", self.angle))
+    }
+}
+
+/// DC motor behind an H-bridge, e.g. a DRV8833. The speed follows the duty cycle of IN1
+/// forward and of IN2 backward, with the inertia of the rotor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DcMotor {
+    /// Speed at full duty cycle, in RPM
+    pub max_rpm: f64,
+    /// Current speed, in RPM, negative backward
+    pub rpm: f64,
+    /// Angle of the shaft, in degrees
+    pub angle: f64,
+    inputs: [PulseMeter; 2],
+    last_step: u64,
+}
+
+impl Default for DcMotor {
+    /// The small yellow gear motors
+    fn default() -> Self {
+        Self {
+            max_rpm: 200.0,
+            rpm: 0.0,
+            angle: 0.0,
+            inputs: Default::default(),
+            last_step: 0,
+        }
+    }
+}
+
+impl DcMotor {
+    /// Speed it is heading to, between -1 and 1 of its top speed
+    pub fn throttle(&self, now: u64) -> f64 {
+        self.inputs[0].duty(now) - self.inputs[1].duty(now)
+    }
+}
+
+impl Component for DcMotor {
+    fn name(&self) -> &'static str {
+        "DC motor"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["IN1", "IN2"]
+    }
+
+    fn notify_pin_change(&mut self, pin: usize, level: bool, now: u64, _clk_sys: u64) {
+        if let Some(input) = self.inputs.get_mut(pin) {
+            input.edge(level, now);
+        }
+    }
+
+    fn tick(&mut self, now: u64, clk_sys: u64) {
+        let step = (clk_sys / STEPS_PER_SECOND).max(1);
+        if now < self.last_step + step {
+            return;
+        }
+
+        let seconds = (now - self.last_step) as f64 / clk_sys.max(1) as f64;
+        let target = self.throttle(now) * self.max_rpm;
+        let blend = (seconds / MOTOR_INERTIA_SECONDS).min(1.0);
+
+        self.rpm += (target - self.rpm) * blend;
+        self.angle = (self.angle + self.rpm * 6.0 * seconds).rem_euclid(360.0);
+        self.last_step = now;
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{:.0} RPM", self.rpm))
+    }
+
+    fn save(&self) -> String {
+        self.max_rpm.to_string()
+    }
+
+    fn load(&mut self, state: &str) {
+        if let Ok(rpm) = state.parse() {
+            self.max_rpm = rpm;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLK_SYS: u64 = 150_000_000;
+
+    /// Run a PWM on a pin for some time, in ticks
+    fn pwm(
+        component: &mut dyn Component,
+        pin: usize,
+        period: u64,
+        high: u64,
+        now: &mut u64,
+        duration: u64,
+    ) {
+        let end = *now + duration;
+
+        while *now < end {
+            component.notify_pin_change(pin, true, *now, CLK_SYS);
+            component.tick(*now, CLK_SYS);
+            component.notify_pin_change(pin, false, *now + high, CLK_SYS);
+            *now += period;
+            component.tick(*now, CLK_SYS);
+        }
+    }
+
+    #[test]
+    fn test_servo() {
+        let mut servo = Servo::default();
+        let mut now = 0;
+        let period = CLK_SYS / 50;
+
+        // 1.5ms pulses every 20ms, the middle
+        pwm(
+            &mut servo,
+            0,
+            period,
+            period * 3 / 40,
+            &mut now,
+            CLK_SYS / 2,
+        );
+        assert_eq!(servo.target(), 90.0);
+        assert!((servo.angle - 90.0).abs() < 1e-9);
+
+        // it takes its time to turn to 180°
+        pwm(&mut servo, 0, period, period / 8, &mut now, CLK_SYS / 20);
+        assert_eq!(servo.target(), 180.0);
+        assert!(servo.angle > 90.0 && servo.angle < 180.0);
+    }
+
+    #[test]
+    fn test_dc_motor() {
+        let mut motor = DcMotor::default();
+        let mut now = 0;
+
+        // 75% forward at 20kHz for a second
+        pwm(&mut motor, 0, 7_500, 5_625, &mut now, CLK_SYS);
+        assert!((motor.throttle(now) - 0.75).abs() < 1e-9);
+        assert!((motor.rpm - 150.0).abs() < 1.0);
+
+        // IN1 low and IN2 held high, full speed backward
+        motor.notify_pin_change(1, true, now, CLK_SYS);
+        now += CLK_SYS;
+        motor.tick(now, CLK_SYS);
+        assert_eq!(motor.throttle(now), -1.0);
+        assert!(motor.rpm < -150.0);
+    }
+}
@@ -0,0 +1,186 @@
+/**
+ * @file device/mpu6050.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief MPU6050 accelerometer and gyroscope on the I2C bus
+ */
+use crate::peripherals::I2cDevice;
+
+/// Address with the AD0 pin low, 0x69 when it is high
+pub const MPU6050_ADDRESS: u8 = 0x68;
+
+pub const REG_SMPLRT_DIV: u8 = 0x19;
+pub const REG_CONFIG: u8 = 0x1A;
+pub const REG_GYRO_CONFIG: u8 = 0x1B;
+pub const REG_ACCEL_CONFIG: u8 = 0x1C;
+pub const REG_ACCEL_XOUT_H: u8 = 0x3B;
+pub const REG_TEMP_OUT_H: u8 = 0x41;
+pub const REG_GYRO_XOUT_H: u8 = 0x43;
+pub const REG_GYRO_ZOUT_L: u8 = 0x48;
+pub const REG_PWR_MGMT_1: u8 = 0x6B;
+pub const REG_WHO_AM_I: u8 = 0x75;
+
+const PWR_MGMT_1_DEVICE_RESET: u8 = 1 << 7;
+const PWR_MGMT_1_SLEEP: u8 = 1 << 6;
+
+/// The motion is set from the outside, the acceleration in g, the rotation in degrees
+/// per second and the temperature in °C. The chip starts asleep, the measurement
+/// registers only follow the motion once PWR_MGMT_1.SLEEP is cleared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mpu6050 {
+    pub accel: [f64; 3],
+    pub gyro: [f64; 3],
+    pub temperature: f64,
+    registers: [u8; 128],
+    pointer: u8,
+    /// The first byte of a write is the register address
+    pointer_set: bool,
+}
+
+impl Default for Mpu6050 {
+    /// Lying flat and still
+    fn default() -> Self {
+        let mut mpu = Self {
+            accel: [0.0, 0.0, 1.0],
+            gyro: [0.0; 3],
+            temperature: 25.0,
+            registers: [0; 128],
+            pointer: 0,
+            pointer_set: false,
+        };
+
+        mpu.reset();
+        mpu
+    }
+}
+
+impl Mpu6050 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn reset(&mut self) {
+        self.registers = [0; 128];
+        self.registers[REG_PWR_MGMT_1 as usize] = PWR_MGMT_1_SLEEP;
+        self.registers[REG_WHO_AM_I as usize] = MPU6050_ADDRESS;
+    }
+
+    pub fn register(&self, register: u8) -> u8 {
+        self.registers[(register & 0x7F) as usize]
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.register(REG_PWR_MGMT_1) & PWR_MGMT_1_SLEEP != 0
+    }
+
+    /// LSB per g of the selected full scale range, ±2g up to ±16g
+    pub fn accel_sensitivity(&self) -> f64 {
+        16384.0 / (1 << ((self.register(REG_ACCEL_CONFIG) >> 3) & 0b11)) as f64
+    }
+
+    /// LSB per °/s of the selected full scale range, ±250°/s up to ±2000°/s
+    pub fn gyro_sensitivity(&self) -> f64 {
+        131.0 / (1 << ((self.register(REG_GYRO_CONFIG) >> 3) & 0b11)) as f64
+    }
+
+    /// Convert the motion into the measurement registers, all at once so a burst read
+    /// of them is consistent
+    fn sample(&mut self) {
+        if self.is_sleeping() {
+            return;
+        }
+
+        let to_raw = |value: f64| value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+        let (accel, gyro) = (self.accel_sensitivity(), self.gyro_sensitivity());
+
+        let values = [
+            to_raw(self.accel[0] * accel),
+            to_raw(self.accel[1] * accel),
+            to_raw(self.accel[2] * accel),
+            to_raw((self.temperature - 36.53) * 340.0),
+            to_raw(self.gyro[0] * gyro),
+            to_raw(self.gyro[1] * gyro),
+            to_raw(self.gyro[2] * gyro),
+        ];
+
+        for (index, value) in values.into_iter().enumerate() {
+            let register = REG_ACCEL_XOUT_H as usize + index * 2;
+            self.registers[register..register + 2].copy_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    fn store(&mut self, register: u8, data: u8) {
+        match register {
+            REG_ACCEL_XOUT_H..=REG_GYRO_ZOUT_L | REG_WHO_AM_I => {}
+            REG_PWR_MGMT_1 if data & PWR_MGMT_1_DEVICE_RESET != 0 => self.reset(),
+            register => self.registers[register as usize] = data,
+        }
+    }
+}
+
+impl I2cDevice for Mpu6050 {
+    fn start(&mut self, read: bool) {
+        self.pointer_set = read;
+        self.sample();
+    }
+
+    fn write(&mut self, data: u8) -> bool {
+        if !self.pointer_set {
+            self.pointer = data & 0x7F;
+            self.pointer_set = true;
+            return true;
+        }
+
+        self.store(self.pointer, data);
+        self.pointer = (self.pointer + 1) & 0x7F;
+        true
+    }
+
+    fn read(&mut self) -> u8 {
+        let data = self.registers[self.pointer as usize];
+        self.pointer = (self.pointer + 1) & 0x7F;
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_registers(mpu: &mut Mpu6050, register: u8, count: usize) -> Vec<u8> {
+        mpu.start(false);
+        mpu.write(register);
+        mpu.start(true);
+        let data = (0..count).map(|_| mpu.read()).collect();
+        mpu.stop();
+        data
+    }
+
+    #[test]
+    fn test_measurements() {
+        let mut mpu = Mpu6050::new();
+        assert_eq!(read_registers(&mut mpu, REG_WHO_AM_I, 1), [0x68]);
+
+        // asleep after the power on
+        assert_eq!(read_registers(&mut mpu, REG_ACCEL_XOUT_H, 2), [0, 0]);
+
+        // wake up and select ±4g
+        mpu.start(false);
+        mpu.write(REG_PWR_MGMT_1);
+        mpu.write(0);
+        mpu.stop();
+        mpu.start(false);
+        mpu.write(REG_ACCEL_CONFIG);
+        mpu.write(1 << 3);
+        mpu.stop();
+
+        mpu.gyro = [0.0, -250.0, 0.0];
+        let data = read_registers(&mut mpu, REG_ACCEL_XOUT_H, 14);
+        let word = |index: usize| i16::from_be_bytes([data[index * 2], data[index * 2 + 1]]);
+
+        assert_eq!(word(0), 0);
+        assert_eq!(word(2), 8192);
+        assert_eq!(word(3), ((25.0 - 36.53) * 340.0f64).round() as i16);
+        assert_eq!(word(5), -32750);
+    }
+}
@@ -0,0 +1,278 @@
+/**
+ * @file device/rotary_encoder.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Mechanical rotary encoder with quadrature outputs and contact bounce
+ */
+use super::{drive_pin, VirtualDevice};
+use crate::Rp2350;
+
+/// Levels of A and B for each quadrature step, both are high on the resting detent
+pub(crate) const QUADRATURE: [(bool, bool); 4] =
+    [(true, true), (false, true), (false, false), (true, false)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotaryEncoderPins {
+    pub a: u8,
+    pub b: u8,
+    /// Push switch to ground, if wired
+    pub button: Option<u8>,
+}
+
+impl Default for RotaryEncoderPins {
+    fn default() -> Self {
+        Self {
+            a: 14,
+            b: 15,
+            button: Some(16),
+        }
+    }
+}
+
+/// Contact bounce after each edge of A and B
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bounce {
+    /// Number of glitches back to the previous level, 0 for clean edges
+    pub glitches: u8,
+    /// Upper bound of each glitch and of the gap between them, in ticks
+    pub max_duration: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Chatter {
+    pin: u8,
+    level: bool,
+    toggles_left: u8,
+    wait: u64,
+}
+
+/// Turning the knob queues detents, which are then played back one quadrature step every
+/// `step_interval` ticks. Clockwise rotation makes A lead B.
+#[derive(Debug, Clone)]
+pub struct RotaryEncoder {
+    pub pins: RotaryEncoderPins,
+    pub steps_per_detent: u8,
+    pub step_interval: u64,
+    pub bounce: Bounce,
+    position: i64,
+    target: i64,
+    wait: u64,
+    chatter: Option<Chatter>,
+    pressed: bool,
+    seed: u32,
+}
+
+impl Default for RotaryEncoder {
+    fn default() -> Self {
+        Self::new(RotaryEncoderPins::default())
+    }
+}
+
+impl RotaryEncoder {
+    pub fn new(pins: RotaryEncoderPins) -> Self {
+        Self {
+            pins,
+            steps_per_detent: 4,
+            // 1ms per step at 150MHz
+            step_interval: 150_000,
+            bounce: Bounce::default(),
+            position: 0,
+            target: 0,
+            wait: 0,
+            chatter: None,
+            pressed: false,
+            seed: 0x1234_5678,
+        }
+    }
+
+    /// Turn the knob by a number of detents, positive is clockwise
+    pub fn rotate(&mut self, detents: i32) {
+        self.target += detents as i64 * self.steps_per_detent as i64;
+    }
+
+    pub fn set_pressed(&mut self, pressed: bool) {
+        self.pressed = pressed;
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Detents already played back to the chip
+    pub fn position(&self) -> i64 {
+        self.position / self.steps_per_detent.max(1) as i64
+    }
+
+    /// Whether every queued detent has been played back and the contacts settled
+    pub fn is_idle(&self) -> bool {
+        self.position == self.target && self.chatter.is_none()
+    }
+
+    fn levels(&self) -> (bool, bool) {
+        QUADRATURE[self.position.rem_euclid(4) as usize]
+    }
+
+    fn random_duration(&mut self) -> u64 {
+        // xorshift32, the bounce only needs to look irregular
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        1 + self.seed as u64 % self.bounce.max_duration.max(1)
+    }
+
+    fn step(&mut self) {
+        let before = self.levels();
+        self.position += (self.target - self.position).signum();
+        let after = self.levels();
+
+        self.wait = self.step_interval;
+
+        if self.bounce.glitches == 0 {
+            return;
+        }
+
+        let (pin, level) = match before.0 != after.0 {
+            true => (self.pins.a, after.0),
+            false => (self.pins.b, after.1),
+        };
+
+        let wait = self.random_duration();
+        self.chatter = Some(Chatter {
+            pin,
+            level,
+            toggles_left: self.bounce.glitches * 2,
+            wait,
+        });
+    }
+
+    fn update_chatter(&mut self) {
+        let Some(mut chatter) = self.chatter.take() else {
+            return;
+        };
+
+        if chatter.wait > 0 {
+            chatter.wait -= 1;
+            self.chatter = Some(chatter);
+            return;
+        }
+
+        chatter.level = !chatter.level;
+        chatter.toggles_left -= 1;
+
+        // an even number of toggles, so it settles on the new level
+        if chatter.toggles_left > 0 {
+            chatter.wait = self.random_duration();
+            self.chatter = Some(chatter);
+        }
+    }
+}
+
+impl VirtualDevice for RotaryEncoder {
+    fn attach(&mut self, mcu: &mut Rp2350) {
+        self.tick(mcu);
+    }
+
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        self.wait = self.wait.saturating_sub(1);
+
+        // the next edge waits for the contacts of the previous one to settle
+        match self.chatter.is_some() {
+            true => self.update_chatter(),
+            false if self.wait == 0 && self.position != self.target => self.step(),
+            false => {}
+        }
+
+        let (mut a, mut b) = self.levels();
+
+        if let Some(chatter) = self.chatter {
+            match chatter.pin == self.pins.a {
+                true => a = chatter.level,
+                false => b = chatter.level,
+            }
+        }
+
+        drive_pin(mcu, self.pins.a, a);
+        drive_pin(mcu, self.pins.b, b);
+
+        if let Some(button) = self.pins.button {
+            drive_pin(mcu, button, !self.pressed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(mcu: &Rp2350, pins: RotaryEncoderPins) -> (bool, bool) {
+        let gpio = mcu.gpio.borrow();
+        let level = |pin| gpio.get_pin(pin).unwrap().raw_input_value;
+        (level(pins.a), level(pins.b))
+    }
+
+    /// Record every change of A and B until the encoder settles
+    fn record(mcu: &mut Rp2350, encoder: &mut RotaryEncoder) -> Vec<(bool, bool)> {
+        let mut changes = vec![levels(mcu, encoder.pins)];
+
+        while !encoder.is_idle() {
+            encoder.tick(mcu);
+
+            let current = levels(mcu, encoder.pins);
+            if changes.last() != Some(&current) {
+                changes.push(current);
+            }
+        }
+
+        changes
+    }
+
+    #[test]
+    fn test_quadrature() {
+        let mut mcu = Rp2350::new();
+        let mut encoder = RotaryEncoder {
+            step_interval: 10,
+            ..Default::default()
+        };
+        encoder.attach(&mut mcu);
+
+        encoder.rotate(1);
+        assert_eq!(
+            record(&mut mcu, &mut encoder),
+            QUADRATURE
+                .iter()
+                .cycle()
+                .take(5)
+                .copied()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(encoder.position(), 1);
+
+        encoder.rotate(-1);
+        let mut expected = QUADRATURE.to_vec();
+        expected.reverse();
+        expected.insert(0, QUADRATURE[0]);
+        assert_eq!(record(&mut mcu, &mut encoder), expected);
+        assert_eq!(encoder.position(), 0);
+    }
+
+    #[test]
+    fn test_bounce() {
+        let mut mcu = Rp2350::new();
+        let mut encoder = RotaryEncoder {
+            step_interval: 10,
+            bounce: Bounce {
+                glitches: 2,
+                max_duration: 5,
+            },
+            ..Default::default()
+        };
+        encoder.attach(&mut mcu);
+
+        encoder.rotate(1);
+        let changes = record(&mut mcu, &mut encoder);
+
+        // each of the 4 edges glitches back twice before settling
+        assert_eq!(changes.len(), 1 + 4 * 5);
+        assert_eq!(changes.last(), Some(&QUADRATURE[0]));
+    }
+}
@@ -0,0 +1,412 @@
+/**
+ * @file device/sd_card.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief SD card in SPI mode, backed by a disk image
+ */
+use crate::peripherals::SpiDevice;
+use std::collections::VecDeque;
+
+pub const BLOCK_SIZE: usize = 512;
+/// Granularity of the capacity in a version 2.0 CSD
+pub const CAPACITY_UNIT: usize = 512 * 1024;
+
+const R1_IDLE: u8 = 1 << 0;
+const R1_ILLEGAL_COMMAND: u8 = 1 << 2;
+const R1_ADDRESS_ERROR: u8 = 1 << 5;
+const R1_PARAMETER_ERROR: u8 = 1 << 6;
+
+const TOKEN_START_BLOCK: u8 = 0xFE;
+const TOKEN_START_MULTIPLE: u8 = 0xFC;
+const TOKEN_STOP_TRANSMISSION: u8 = 0xFD;
+const DATA_ACCEPTED: u8 = 0x05;
+
+/// Powered up, high capacity
+const OCR_BUSY: u32 = 1 << 31;
+const OCR_CCS: u32 = 1 << 30;
+/// 2.7V to 3.6V
+const OCR_VOLTAGES: u32 = 0x00FF_8000;
+
+const CID: [u8; 15] = [
+    0x1D, b'T', b'M', b'P', b'I', b'C', b'O', b'2', 0x10, 0x12, 0x34, 0x56, 0x78, 0x01, 0xAA,
+];
+const SCR: [u8; 8] = [0x02, 0x35, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+/// CRC of the commands, the CID and the CSD
+pub fn crc7(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+
+    for byte in data {
+        for bit in (0..8).rev() {
+            let feedback = ((byte >> bit) ^ (crc >> 6)) & 1;
+            crc = (crc << 1) & 0x7F;
+
+            if feedback != 0 {
+                crc ^= 0x09;
+            }
+        }
+    }
+
+    crc
+}
+
+/// CRC of the data blocks, the CCITT polynomial
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ (*byte as u16) << 8, |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x1021,
+        })
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    None,
+    /// CMD18, the blocks are sent one after the other until CMD12
+    Reading {
+        block: usize,
+    },
+    /// CMD24 or CMD25, waiting for the start tokens
+    Writing {
+        block: usize,
+        multiple: bool,
+    },
+}
+
+/// High capacity card with block addressing, answering after one byte like the fast
+/// cards. The CRCs of the commands and of the written blocks are not checked.
+#[derive(Debug, Clone)]
+pub struct SdCard {
+    pub image: Vec<u8>,
+    /// Blocks read and written since it was inserted
+    pub reads: u64,
+    pub writes: u64,
+    idle: bool,
+    app_command: bool,
+    command: Vec<u8>,
+    response: VecDeque<u8>,
+    transfer: Transfer,
+    /// Block received after a start token, followed by its CRC
+    data: Option<Vec<u8>>,
+}
+
+impl SdCard {
+    /// The image is padded with zeros to the next multiple of 512 KiB, the size a
+    /// version 2.0 CSD can describe
+    pub fn new(mut image: Vec<u8>) -> Self {
+        let size = image.len().div_ceil(CAPACITY_UNIT).max(1) * CAPACITY_UNIT;
+        image.resize(size, 0);
+
+        Self {
+            image,
+            reads: 0,
+            writes: 0,
+            idle: true,
+            app_command: false,
+            command: Vec::new(),
+            response: VecDeque::new(),
+            transfer: Transfer::None,
+            data: None,
+        }
+    }
+
+    /// Unformatted card of the given size
+    pub fn blank(size: usize) -> Self {
+        Self::new(vec![0; size])
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.image.len() / BLOCK_SIZE
+    }
+
+    /// Left the idle state, ACMD41 was accepted
+    pub fn is_initialized(&self) -> bool {
+        !self.idle
+    }
+
+    fn csd(&self) -> [u8; 16] {
+        let size = (self.image.len() / CAPACITY_UNIT - 1) as u32;
+        let mut csd = [
+            0x40, 0x0E, 0x00, 0x32, 0x5B, 0x59, 0x00, 0x00, 0x00, 0x00, 0x7F, 0x80, 0x0A, 0x40,
+            0x00, 0x00,
+        ];
+
+        csd[7] = (size >> 16) as u8 & 0x3F;
+        csd[8] = (size >> 8) as u8;
+        csd[9] = size as u8;
+        csd[15] = (crc7(&csd[..15]) << 1) | 1;
+        csd
+    }
+
+    fn cid(&self) -> [u8; 16] {
+        let mut cid = [0; 16];
+        cid[..15].copy_from_slice(&CID);
+        cid[15] = (crc7(&CID) << 1) | 1;
+        cid
+    }
+
+    fn ocr(&self) -> u32 {
+        match self.idle {
+            true => OCR_VOLTAGES | OCR_CCS,
+            false => OCR_VOLTAGES | OCR_CCS | OCR_BUSY,
+        }
+    }
+
+    /// R1 after one byte, then the rest of the response
+    fn respond(&mut self, flags: u8, rest: &[u8]) {
+        let idle = if self.idle { R1_IDLE } else { 0 };
+        self.response.extend([0xFF, flags | idle]);
+        self.response.extend(rest);
+    }
+
+    fn send_data(&mut self, data: &[u8]) {
+        self.response.extend([0xFF, TOKEN_START_BLOCK]);
+        self.response.extend(data);
+        self.response.extend(crc16(data).to_be_bytes());
+    }
+
+    fn send_block(&mut self, block: usize) {
+        let start = block * BLOCK_SIZE;
+        let data = self.image[start..start + BLOCK_SIZE].to_vec();
+        self.send_data(&data);
+        self.reads += 1;
+    }
+
+    fn execute(&mut self, index: u8, argument: u32) {
+        let app = std::mem::take(&mut self.app_command);
+
+        // the initialization commands are the only ones accepted in the idle state
+        let initializing = matches!((app, index), (true, 41) | (_, 0 | 1 | 8 | 55 | 58 | 59));
+        if self.idle && !initializing {
+            self.respond(R1_ILLEGAL_COMMAND, &[]);
+            return;
+        }
+
+        let block = argument as usize;
+
+        match (app, index) {
+            // SET_WR_BLK_ERASE_COUNT, nothing is pre-erased
+            (true, 23) => self.respond(0, &[]),
+            (true, 41) => {
+                self.idle = false;
+                self.respond(0, &[]);
+            }
+            (true, 51) => {
+                self.respond(0, &[]);
+                self.send_data(&SCR);
+            }
+            (_, 0) => {
+                self.idle = true;
+                self.transfer = Transfer::None;
+                self.respond(0, &[]);
+            }
+            (_, 1) => {
+                self.idle = false;
+                self.respond(0, &[]);
+            }
+            // the voltage and the check pattern are echoed back
+            (_, 8) => self.respond(0, &[0, 0, (argument >> 8) as u8 & 0xF, argument as u8]),
+            (_, 9) => {
+                self.respond(0, &[]);
+                self.send_data(&self.csd());
+            }
+            (_, 10) => {
+                self.respond(0, &[]);
+                self.send_data(&self.cid());
+            }
+            (_, 12) => {
+                self.transfer = Transfer::None;
+                self.response.clear();
+                self.respond(0, &[]);
+            }
+            (_, 13) => self.respond(0, &[0]),
+            // the blocks of the high capacity cards are always 512 bytes
+            (_, 16) if argument as usize == BLOCK_SIZE => self.respond(0, &[]),
+            (_, 16) => self.respond(R1_PARAMETER_ERROR, &[]),
+            (_, 17 | 18 | 24 | 25) if block >= self.blocks() => self.respond(R1_ADDRESS_ERROR, &[]),
+            (_, 17) => {
+                self.respond(0, &[]);
+                self.send_block(block);
+            }
+            (_, 18) => {
+                self.respond(0, &[]);
+                self.transfer = Transfer::Reading { block };
+            }
+            (_, 24 | 25) => {
+                self.respond(0, &[]);
+                self.transfer = Transfer::Writing {
+                    block,
+                    multiple: index == 25,
+                };
+            }
+            (_, 55) => {
+                self.app_command = true;
+                self.respond(0, &[]);
+            }
+            (_, 58) => self.respond(0, &self.ocr().to_be_bytes()),
+            // CRC_ON_OFF, they are never checked anyway
+            (_, 59) => self.respond(0, &[]),
+            _ => self.respond(R1_ILLEGAL_COMMAND, &[]),
+        }
+    }
+
+    fn store_block(&mut self, data: Vec<u8>) {
+        let Transfer::Writing { block, multiple } = self.transfer else {
+            return;
+        };
+
+        if block < self.blocks() {
+            let start = block * BLOCK_SIZE;
+            self.image[start..start + BLOCK_SIZE].copy_from_slice(&data[..BLOCK_SIZE]);
+            self.writes += 1;
+        }
+
+        // accepted, then busy for a byte while it is programmed
+        self.response.extend([DATA_ACCEPTED, 0x00]);
+        self.transfer = match multiple {
+            true => Transfer::Writing {
+                block: block + 1,
+                multiple,
+            },
+            false => Transfer::None,
+        };
+    }
+
+    fn receive(&mut self, byte: u8) {
+        if let Some(mut data) = self.data.take() {
+            data.push(byte);
+
+            match data.len() == BLOCK_SIZE + 2 {
+                true => self.store_block(data),
+                false => self.data = Some(data),
+            }
+
+            return;
+        }
+
+        if let Transfer::Writing { multiple, .. } = self.transfer {
+            match byte {
+                TOKEN_START_BLOCK if !multiple => self.data = Some(Vec::new()),
+                TOKEN_START_MULTIPLE if multiple => self.data = Some(Vec::new()),
+                TOKEN_STOP_TRANSMISSION if multiple => {
+                    self.transfer = Transfer::None;
+                    self.response.extend([0xFF, 0x00]);
+                }
+                _ => {}
+            }
+        }
+
+        // a command starts with the bits 01, the filling bytes are all ones
+        if self.command.is_empty() && byte & 0xC0 != 0x40 {
+            return;
+        }
+
+        self.command.push(byte);
+
+        if self.command.len() == 6 {
+            let command = std::mem::take(&mut self.command);
+            let argument = u32::from_be_bytes([command[1], command[2], command[3], command[4]]);
+            self.execute(command[0] & 0x3F, argument);
+        }
+    }
+}
+
+impl SpiDevice for SdCard {
+    fn transfer(&mut self, data: u8) -> u8 {
+        let out = self.response.pop_front().unwrap_or(0xFF);
+        self.receive(data);
+
+        match self.transfer {
+            Transfer::Reading { block } if self.response.is_empty() => {
+                self.send_block(block);
+                self.transfer = match block + 1 < self.blocks() {
+                    true => Transfer::Reading { block: block + 1 },
+                    false => Transfer::None,
+                };
+            }
+            _ => (),
+        }
+
+        out
+    }
+
+    /// A command cut by the chip select is dropped
+    fn deselect(&mut self) {
+        self.command.clear();
+        self.response.clear();
+        self.data = None;
+        self.transfer = Transfer::None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// R1 and the given number of bytes following it
+    fn command(card: &mut SdCard, index: u8, argument: u32, extra: usize) -> Vec<u8> {
+        let mut bytes = vec![0x40 | index];
+        bytes.extend(argument.to_be_bytes());
+        bytes.push((crc7(&bytes) << 1) | 1);
+        bytes.iter().for_each(|byte| {
+            card.transfer(*byte);
+        });
+
+        let r1 = (0..8)
+            .map(|_| card.transfer(0xFF))
+            .find(|byte| *byte != 0xFF);
+        let mut response = vec![r1.expect("no response")];
+        response.extend((0..extra).map(|_| card.transfer(0xFF)));
+        response
+    }
+
+    /// A data block with its CRC
+    fn read_data(card: &mut SdCard, len: usize) -> Vec<u8> {
+        while card.transfer(0xFF) != TOKEN_START_BLOCK {}
+        (0..len + 2).map(|_| card.transfer(0xFF)).collect()
+    }
+
+    #[test]
+    fn test_crc() {
+        assert_eq!(crc7(&[0x40, 0, 0, 0, 0]), 0x4A);
+        assert_eq!(crc16(&[0xFF; BLOCK_SIZE]), 0x7FA1);
+    }
+
+    #[test]
+    fn test_initialization_and_blocks() {
+        let mut card = SdCard::blank(1024 * 1024);
+        card.select();
+
+        assert_eq!(command(&mut card, 17, 0, 0), [R1_IDLE | R1_ILLEGAL_COMMAND]);
+        assert_eq!(command(&mut card, 0, 0, 0), [R1_IDLE]);
+        assert_eq!(command(&mut card, 8, 0x1AA, 4), [R1_IDLE, 0, 0, 0x01, 0xAA]);
+        assert_eq!(command(&mut card, 55, 0, 0), [R1_IDLE]);
+        assert_eq!(command(&mut card, 41, 1 << 30, 0), [0]);
+        assert_eq!(command(&mut card, 58, 0, 4), [0, 0xC0, 0xFF, 0x80, 0x00]);
+        assert!(card.is_initialized());
+
+        // write the second block
+        assert_eq!(command(&mut card, 24, 1, 0), [0]);
+        card.transfer(TOKEN_START_BLOCK);
+        (0..BLOCK_SIZE).for_each(|i| {
+            card.transfer(i as u8);
+        });
+        card.transfer(0xFF);
+        card.transfer(0xFF);
+        assert_eq!(card.transfer(0xFF) & 0x1F, DATA_ACCEPTED);
+        assert_eq!(card.image[BLOCK_SIZE + 3], 3);
+
+        // and read it back with its CRC
+        assert_eq!(command(&mut card, 17, 1, 0), [0]);
+        let data = read_data(&mut card, BLOCK_SIZE);
+        assert_eq!(data[..4], [0, 1, 2, 3]);
+        assert_eq!(data[BLOCK_SIZE..], crc16(&data[..BLOCK_SIZE]).to_be_bytes());
+
+        // the capacity in the CSD, (C_SIZE + 1) * 512 KiB
+        assert_eq!(command(&mut card, 9, 0, 0), [0]);
+        let csd = read_data(&mut card, 16);
+        assert_eq!(u16::from_be_bytes([csd[8], csd[9]]), 1);
+        assert_eq!(command(&mut card, 17, 2048, 0), [R1_ADDRESS_ERROR]);
+    }
+}
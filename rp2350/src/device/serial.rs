@@ -0,0 +1,149 @@
+/**
+ * @file device/serial.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Terminal typing into a UART, one character per frame time of the line
+ */
+use super::VirtualDevice;
+use crate::clock::Clock;
+use crate::peripherals::Uart;
+use crate::Rp2350;
+use std::collections::VecDeque;
+
+/// Characters typed on a terminal wired to the RX pin of a UART. They reach the receive
+/// FIFO one after the other, each once a whole frame passed at the baud rate and with the
+/// format the UART is configured with, like on a real line. The characters wait while the
+/// receiver of the UART is disabled.
+#[derive(Debug, Clone)]
+pub struct SerialTerminal {
+    pub uart: u8,
+    pending: VecDeque<u8>,
+    /// Tick at which the character on the line reaches the FIFO
+    arrival: Option<u64>,
+    delivered: u64,
+}
+
+/// Ticks of a frame, `None` while the receiver is disabled
+fn frame_ticks<const IDX: usize>(uart: &Uart<IDX>, clock: &Clock) -> Option<u64> {
+    if !uart.is_enabled() || !uart.is_receive_enabled() {
+        return None;
+    }
+
+    let baudrate = (uart.get_baudrate(clock.clk_peri()) as u64).max(1);
+    Some((uart.frame_bits() as u64 * clock.clk_sys()).div_ceil(baudrate))
+}
+
+impl SerialTerminal {
+    pub fn new(uart: u8) -> Self {
+        Self {
+            uart,
+            pending: VecDeque::new(),
+            arrival: None,
+            delivered: 0,
+        }
+    }
+
+    pub fn send(&mut self, data: &[u8]) {
+        self.pending.extend(data);
+    }
+
+    /// Characters not yet in the FIFO, the one on the line included
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn delivered(&self) -> u64 {
+        self.delivered
+    }
+
+    /// Drop the characters not sent yet
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.arrival = None;
+    }
+
+    fn frame_ticks(&self, mcu: &Rp2350) -> Option<u64> {
+        let peripherals = &mcu.bus.peripherals;
+
+        match self.uart {
+            0 => frame_ticks(&peripherals.uart0.borrow(), &mcu.clock),
+            1 => frame_ticks(&peripherals.uart1.borrow(), &mcu.clock),
+            _ => None,
+        }
+    }
+}
+
+impl VirtualDevice for SerialTerminal {
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let Some(&data) = self.pending.front() else {
+            return;
+        };
+
+        let now = *mcu.clock.ticks.borrow();
+
+        match self.arrival {
+            None => {
+                // the next frame starts right after the previous one
+                self.arrival = self.frame_ticks(mcu).map(|ticks| now + ticks);
+            }
+            Some(arrival) if now >= arrival => {
+                self.arrival = None;
+
+                if mcu.uart_receive(self.uart, data) {
+                    self.pending.pop_front();
+                    self.delivered += 1;
+                }
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UART0: u32 = 0x4007_0000;
+
+    #[test]
+    fn test_baud_timing() {
+        let mut mcu = Rp2350::new();
+        let mut terminal = SerialTerminal::new(0);
+        terminal.send(b"hi");
+
+        // nothing arrives while the UART is off
+        for _ in 0..1000 {
+            terminal.tick(&mut mcu);
+            mcu.clock.tick();
+        }
+        assert_eq!(terminal.pending(), 2);
+
+        // 115200 baud from the 150MHz clock, 8N1
+        mcu.write_register(UART0 + 0x24, 81).unwrap();
+        mcu.write_register(UART0 + 0x28, 25).unwrap();
+        mcu.write_register(UART0 + 0x2c, 0b11 << 5).unwrap();
+        mcu.write_register(UART0 + 0x30, 1 | (1 << 9)).unwrap();
+
+        let frame = 10 * 150_000_000 / 115_200;
+        let start = *mcu.clock.ticks.borrow();
+        let mut arrivals = Vec::new();
+
+        while terminal.pending() > 0 {
+            let pending = terminal.pending();
+            terminal.tick(&mut mcu);
+            mcu.clock.tick();
+
+            if terminal.pending() < pending {
+                arrivals.push(*mcu.clock.ticks.borrow() - start);
+            }
+        }
+
+        // the divisors give 115185 baud
+        assert_eq!(arrivals.len(), 2);
+        assert!(arrivals[0].abs_diff(frame) <= frame / 100);
+        assert!((arrivals[1] - arrivals[0]).abs_diff(frame) <= frame / 100);
+
+        assert_eq!(mcu.read_register(UART0).unwrap(), b'h' as u32);
+        assert_eq!(mcu.read_register(UART0).unwrap(), b'i' as u32);
+    }
+}
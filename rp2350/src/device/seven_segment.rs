@@ -0,0 +1,377 @@
+/**
+ * @file device/seven_segment.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Seven-segment displays placed on the breadboard, multiplexed on the GPIOs or
+ * behind a TM1637
+ */
+use super::circuit::Branch;
+use super::Component;
+
+/// A digit not selected for that long is dark, 20ms is below the flicker of the eye
+const PERSISTENCE_DIVIDER: u64 = 50;
+const SEGMENT_PINS: usize = 8;
+const DIGIT_PINS: usize = 4;
+
+/// Pull ups of the TM1637 modules on CLK and DIO
+const TM1637_PULL_UP_OHMS: f64 = 10_000.0;
+/// Open drain output of the TM1637 on DIO, for the acknowledge
+const TM1637_ACK_OHMS: f64 = 100.0;
+const TM1637_DIGITS: usize = 6;
+
+/// Multiplexed display of up to 4 digits, e.g. a 5641AS. A digit shows the segments
+/// driven while its common is active, as long as it is refreshed often enough; a common
+/// left unwired is tied to its rail and always active. The segments are a bitmap, bit 0
+/// is A up to bit 6 for G and bit 7 for the dot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SevenSegment {
+    /// Common anode, the segments light while low and a digit is selected by a high common
+    pub common_anode: bool,
+    pub digits: usize,
+    levels: [bool; SEGMENT_PINS + DIGIT_PINS],
+    wired: [bool; SEGMENT_PINS + DIGIT_PINS],
+    segments: [u8; DIGIT_PINS],
+    last_lit: [Option<u64>; DIGIT_PINS],
+    now: u64,
+    clk_sys: u64,
+}
+
+impl Default for SevenSegment {
+    fn default() -> Self {
+        Self {
+            common_anode: false,
+            digits: DIGIT_PINS,
+            levels: [false; SEGMENT_PINS + DIGIT_PINS],
+            wired: [false; SEGMENT_PINS + DIGIT_PINS],
+            segments: [0; DIGIT_PINS],
+            last_lit: [None; DIGIT_PINS],
+            now: 0,
+            clk_sys: 1,
+        }
+    }
+}
+
+impl SevenSegment {
+    /// Segments shown by each digit, the ones not refreshed lately are dark
+    pub fn segments(&self) -> Vec<u8> {
+        let persistence = self.clk_sys / PERSISTENCE_DIVIDER;
+
+        (0..self.digits.min(DIGIT_PINS))
+            .map(|digit| match self.last_lit[digit] {
+                Some(lit) if self.now.saturating_sub(lit) <= persistence => self.segments[digit],
+                _ => 0,
+            })
+            .collect()
+    }
+
+    fn is_selected(&self, digit: usize) -> bool {
+        let pin = SEGMENT_PINS + digit;
+        !self.wired[pin] || self.levels[pin] == self.common_anode
+    }
+}
+
+impl Component for SevenSegment {
+    fn name(&self) -> &'static str {
+        "7-segment"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &[
+            "A", "B", "C", "D", "E", "F", "G", "DP", "D1", "D2", "D3", "D4",
+        ]
+    }
+
+    fn notify_pin_change(&mut self, pin: usize, level: bool, _now: u64, _clk_sys: u64) {
+        if let Some(current) = self.levels.get_mut(pin) {
+            *current = level;
+        }
+    }
+
+    /// Called for the wired pins only, on every tick
+    fn notify_current(&mut self, pin: usize, _volts: f64, _amps: f64) {
+        if let Some(wired) = self.wired.get_mut(pin) {
+            *wired = true;
+        }
+    }
+
+    fn tick(&mut self, now: u64, clk_sys: u64) {
+        let lit = (0..SEGMENT_PINS)
+            .filter(|pin| self.wired[*pin] && self.levels[*pin] != self.common_anode)
+            .fold(0u8, |segments, pin| segments | 1 << pin);
+
+        for digit in 0..DIGIT_PINS {
+            if self.is_selected(digit) {
+                self.segments[digit] = lit;
+                self.last_lit[digit] = Some(now);
+            }
+        }
+
+        self.now = now;
+        self.clk_sys = clk_sys;
+        self.wired = [false; SEGMENT_PINS + DIGIT_PINS];
+    }
+
+    fn save(&self) -> String {
+        let common = match self.common_anode {
+            true => "anode",
+            false => "cathode",
+        };
+        format!("{},{common}", self.digits)
+    }
+
+    fn load(&mut self, state: &str) {
+        let Some((digits, common)) = state.split_once(',') else {
+            return;
+        };
+
+        if let Ok(digits) = digits.parse() {
+            self.digits = usize::clamp(digits, 1, DIGIT_PINS);
+            self.common_anode = common == "anode";
+        }
+    }
+}
+
+/// TM1637 display driver, on the 4 digits modules with a colon. The bytes are sent LSB
+/// first between a start and a stop like on I2C, and acknowledged by pulling DIO low
+/// during the 9th clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tm1637 {
+    pub digits: usize,
+    /// Segments of each grid, bit 7 of the second one is the colon
+    pub segments: [u8; TM1637_DIGITS],
+    pub display_on: bool,
+    /// Pulse width of the grids, 0 to 7
+    pub brightness: u8,
+    clk: bool,
+    dio: bool,
+    receiving: bool,
+    bits: u8,
+    byte: u8,
+    bytes: usize,
+    ack: bool,
+    address: usize,
+    auto_increment: bool,
+}
+
+impl Default for Tm1637 {
+    fn default() -> Self {
+        Self {
+            digits: 4,
+            segments: [0; TM1637_DIGITS],
+            display_on: false,
+            brightness: 7,
+            clk: true,
+            dio: true,
+            receiving: false,
+            bits: 0,
+            byte: 0,
+            bytes: 0,
+            ack: false,
+            address: 0,
+            auto_increment: true,
+        }
+    }
+}
+
+impl Tm1637 {
+    /// Segments shown by each digit, dark while the display is off
+    pub fn shown(&self) -> &[u8] {
+        match self.display_on {
+            true => &self.segments[..self.digits.min(TM1637_DIGITS)],
+            false => &[],
+        }
+    }
+
+    fn receive(&mut self, byte: u8) {
+        let first = self.bytes == 0;
+        self.bytes += 1;
+
+        match byte & 0xC0 {
+            // data command, only the writes are modelled
+            0x40 if first => self.auto_increment = byte & 0x04 == 0,
+            0x80 if first => {
+                self.display_on = byte & 0x08 != 0;
+                self.brightness = byte & 0x07;
+            }
+            0xC0 if first => self.address = (byte & 0x07) as usize,
+            _ if !first => {
+                if let Some(segments) = self.segments.get_mut(self.address) {
+                    *segments = byte;
+                }
+
+                if self.auto_increment {
+                    self.address += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn clock(&mut self, rising: bool) {
+        match (rising, self.bits) {
+            (true, 0..=7) => {
+                self.byte |= (self.dio as u8) << self.bits;
+                self.bits += 1;
+            }
+            (false, 8) => self.ack = true,
+            (true, 8) => {
+                self.bits = 9;
+                let byte = self.byte;
+                self.receive(byte);
+            }
+            (false, 9) => {
+                self.ack = false;
+                self.bits = 0;
+                self.byte = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Component for Tm1637 {
+    fn name(&self) -> &'static str {
+        "TM1637"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["CLK", "DIO"]
+    }
+
+    fn notify_pin_change(&mut self, pin: usize, level: bool, _now: u64, _clk_sys: u64) {
+        match pin {
+            0 => {
+                self.clk = level;
+
+                if self.receiving {
+                    self.clock(level);
+                }
+            }
+            1 => {
+                self.dio = level;
+
+                // start and stop, DIO changing while CLK is high
+                if self.clk && !self.ack {
+                    self.receiving = !level;
+                    self.bits = 0;
+                    self.byte = 0;
+
+                    if !level {
+                        self.bytes = 0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn branch(&self, pin: usize) -> Option<Branch> {
+        match pin {
+            1 if self.ack => Some(Branch::source(false, TM1637_ACK_OHMS)),
+            0 | 1 => Some(Branch::source(true, TM1637_PULL_UP_OHMS)),
+            _ => None,
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        match self.display_on {
+            true => Some(format!("brightness {}", self.brightness)),
+            false => Some("off".to_string()),
+        }
+    }
+
+    fn save(&self) -> String {
+        self.digits.to_string()
+    }
+
+    fn load(&mut self, state: &str) {
+        if let Ok(digits) = state.parse() {
+            self.digits = usize::clamp(digits, 1, TM1637_DIGITS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiplexed() {
+        let mut display = SevenSegment::default();
+        let clk_sys = 1000;
+
+        // "1" on the first digit then "7." on the second, common cathode
+        let frames = [(0, 0b0000_0110u8), (1, 0b1000_0111)];
+
+        for (now, (digit, segments)) in frames.iter().enumerate() {
+            for pin in 0..SEGMENT_PINS + DIGIT_PINS {
+                let level = match pin {
+                    0..=7 => segments >> pin & 1 == 1,
+                    _ => pin - SEGMENT_PINS != *digit,
+                };
+
+                display.notify_current(pin, 0.0, 0.0);
+                display.notify_pin_change(pin, level, now as u64, clk_sys);
+            }
+
+            display.tick(now as u64, clk_sys);
+        }
+
+        assert_eq!(display.segments(), [0b0000_0110, 0b1000_0111, 0, 0]);
+
+        // without refresh the digits fade
+        display.tick(100, clk_sys);
+        assert_eq!(display.segments(), [0; 4]);
+    }
+
+    /// Bit-bang a transaction, the pins go through the component like on the board
+    fn transfer(tm: &mut Tm1637, bytes: &[u8]) -> Vec<bool> {
+        let mut acks = Vec::new();
+
+        tm.notify_pin_change(1, false, 0, 1);
+        tm.notify_pin_change(0, false, 0, 1);
+
+        for byte in bytes {
+            for bit in 0..8 {
+                tm.notify_pin_change(1, byte >> bit & 1 == 1, 0, 1);
+                tm.notify_pin_change(0, true, 0, 1);
+                tm.notify_pin_change(0, false, 0, 1);
+            }
+
+            // DIO released to the pull up, the TM1637 holds it low
+            tm.notify_pin_change(1, true, 0, 1);
+            acks.push(matches!(
+                tm.branch(1),
+                Some(Branch::Source { volts, .. }) if volts == 0.0
+            ));
+            tm.notify_pin_change(0, true, 0, 1);
+            tm.notify_pin_change(0, false, 0, 1);
+        }
+
+        tm.notify_pin_change(1, false, 0, 1);
+        tm.notify_pin_change(0, true, 0, 1);
+        tm.notify_pin_change(1, true, 0, 1);
+        acks
+    }
+
+    #[test]
+    fn test_tm1637() {
+        let mut tm = Tm1637::default();
+
+        assert_eq!(transfer(&mut tm, &[0x40]), [true]);
+        assert_eq!(
+            transfer(&mut tm, &[0xC0, 0x3F, 0x86, 0x5B, 0x4F]),
+            [true; 5]
+        );
+        assert!(tm.shown().is_empty());
+
+        transfer(&mut tm, &[0x8A]);
+        assert_eq!(tm.shown(), [0x3F, 0x86, 0x5B, 0x4F]);
+        assert_eq!(tm.brightness, 2);
+
+        // a fixed address write
+        transfer(&mut tm, &[0x44]);
+        transfer(&mut tm, &[0xC2, 0x06, 0x07]);
+        assert_eq!(tm.shown(), [0x3F, 0x86, 0x07, 0x4F]);
+    }
+}
@@ -0,0 +1,76 @@
+/**
+ * @file device/spi_flash.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief External SPI NOR flash, with the W25Q command set of the flash on the Pico 2
+ */
+use crate::peripherals::qmi::SerialFlash;
+use crate::peripherals::SpiDevice;
+
+/// A W25Q flash wired to an SPI block instead of the QMI. The size in the JEDEC id
+/// follows the memory, which is always a power of two.
+#[derive(Debug, Clone)]
+pub struct SpiFlash {
+    pub flash: SerialFlash,
+    pub memory: Vec<u8>,
+}
+
+impl SpiFlash {
+    /// Erased flash of at least `size` bytes
+    pub fn new(size: usize) -> Self {
+        Self {
+            flash: SerialFlash::default(),
+            memory: vec![0xFF; size.max(4 * 1024).next_power_of_two()],
+        }
+    }
+
+    /// W25Q16, 2 MiB
+    pub fn new_w25q16() -> Self {
+        Self::new(2 * 1024 * 1024)
+    }
+}
+
+impl SpiDevice for SpiFlash {
+    fn select(&mut self) {
+        self.flash.select();
+    }
+
+    fn transfer(&mut self, data: u8) -> u8 {
+        self.flash.transfer(data, &mut self.memory)
+    }
+
+    fn deselect(&mut self) {
+        self.flash.deselect(&mut self.memory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::qmi::serial_flash::{JEDEC_ID, PAGE_PROGRAM, READ, WRITE_ENABLE};
+
+    fn command(flash: &mut SpiFlash, bytes: &[u8]) -> Vec<u8> {
+        flash.select();
+        let data = bytes.iter().map(|byte| flash.transfer(*byte)).collect();
+        flash.deselect();
+        data
+    }
+
+    #[test]
+    fn test_program_and_read() {
+        let mut flash = SpiFlash::new_w25q16();
+        assert_eq!(
+            command(&mut flash, &[JEDEC_ID, 0, 0, 0])[1..],
+            [0xEF, 0x40, 21]
+        );
+
+        // the program is ignored until the write is enabled
+        command(&mut flash, &[PAGE_PROGRAM, 0x00, 0x01, 0x00, 0x12]);
+        command(&mut flash, &[WRITE_ENABLE]);
+        command(&mut flash, &[PAGE_PROGRAM, 0x00, 0x01, 0x00, 0x34, 0x56]);
+
+        let data = command(&mut flash, &[READ, 0x00, 0x01, 0x00, 0, 0, 0]);
+        assert_eq!(data[4..], [0x34, 0x56, 0xFF]);
+        assert_eq!(flash.flash.programs(), 1);
+    }
+}
@@ -0,0 +1,280 @@
+/**
+ * @file device/ssd1306.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief SSD1306 monochrome OLED controller on the I2C bus, with its display RAM
+ */
+use crate::peripherals::I2cDevice;
+
+/// Address with the D/C# pin low, 0x3D when it is high
+pub const SSD1306_ADDRESS: u8 = 0x3C;
+
+pub const WIDTH: usize = 128;
+const PAGES: usize = 8;
+
+/// The control byte following the address tells whether commands or data come next
+const CONTROL_CONTINUATION: u8 = 1 << 7;
+const CONTROL_DATA: u8 = 1 << 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressingMode {
+    Horizontal,
+    Vertical,
+    Page,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Control,
+    /// One byte, then another control byte
+    Single {
+        data: bool,
+    },
+    /// Bytes of the same kind until the STOP
+    Stream {
+        data: bool,
+    },
+}
+
+/// Bytes of a command, the opcode included
+fn command_len(opcode: u8) -> usize {
+    match opcode {
+        0x81 | 0x20 | 0xA8 | 0xD3 | 0xD5 | 0xD9 | 0xDA | 0xDB | 0x8D => 2,
+        0x21 | 0x22 | 0xA3 => 3,
+        // horizontal scroll setup
+        0x26 | 0x27 => 7,
+        // vertical and horizontal scroll setup
+        0x29 | 0x2A => 6,
+        _ => 1,
+    }
+}
+
+/// 128 columns of 8 pages of 8 rows, 64 or 32 of the rows are on the panel. The scrolling
+/// commands are accepted without moving the picture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ssd1306 {
+    height: usize,
+    /// One byte per column and page, the LSB is the top row of the page
+    pub ram: [[u8; WIDTH]; PAGES],
+    pub display_on: bool,
+    pub contrast: u8,
+    pub inverse: bool,
+    /// All the pixels lit whatever the RAM holds
+    pub entire_on: bool,
+    pub segment_remap: bool,
+    pub com_remap: bool,
+    pub start_line: u8,
+    pub addressing_mode: AddressingMode,
+    columns: (u8, u8),
+    pages: (u8, u8),
+    column: u8,
+    page: u8,
+    expect: Expect,
+    command: Vec<u8>,
+}
+
+impl Ssd1306 {
+    /// Panel of 128x64 or 128x32 pixels
+    pub fn new(height: usize) -> Self {
+        Self {
+            height: if height <= 32 { 32 } else { 64 },
+            ram: [[0; WIDTH]; PAGES],
+            display_on: false,
+            contrast: 0x7F,
+            inverse: false,
+            entire_on: false,
+            segment_remap: false,
+            com_remap: false,
+            start_line: 0,
+            addressing_mode: AddressingMode::Page,
+            columns: (0, WIDTH as u8 - 1),
+            pages: (0, PAGES as u8 - 1),
+            column: 0,
+            page: 0,
+            expect: Expect::Control,
+            command: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the pixel is lit, drawn as on the common modules whose panel is mounted
+    /// rotated: the picture is upright with the segments and the COM scan remapped
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        if !self.display_on || x >= WIDTH || y >= self.height {
+            return false;
+        }
+
+        if self.entire_on {
+            return true;
+        }
+
+        let column = if self.segment_remap { x } else { WIDTH - 1 - x };
+        let row = if self.com_remap {
+            y
+        } else {
+            self.height - 1 - y
+        };
+        let line = (row + self.start_line as usize) % (PAGES * 8);
+        let lit = self.ram[line / 8][column] >> (line % 8) & 1 == 1;
+
+        lit != self.inverse
+    }
+
+    fn execute(&mut self) {
+        let command = std::mem::take(&mut self.command);
+
+        match command[..] {
+            [0xAE] => self.display_on = false,
+            [0xAF] => self.display_on = true,
+            [0x81, contrast] => self.contrast = contrast,
+            [0xA4] => self.entire_on = false,
+            [0xA5] => self.entire_on = true,
+            [0xA6] => self.inverse = false,
+            [0xA7] => self.inverse = true,
+            [0xA0] => self.segment_remap = false,
+            [0xA1] => self.segment_remap = true,
+            [0xC0] => self.com_remap = false,
+            [0xC8] => self.com_remap = true,
+            [0x20, mode] => {
+                self.addressing_mode = match mode & 0b11 {
+                    0 => AddressingMode::Horizontal,
+                    1 => AddressingMode::Vertical,
+                    _ => AddressingMode::Page,
+                }
+            }
+            [0x21, start, end] => {
+                self.columns = (start & 0x7F, end & 0x7F);
+                self.column = self.columns.0;
+            }
+            [0x22, start, end] => {
+                self.pages = (start & 0x7, end & 0x7);
+                self.page = self.pages.0;
+            }
+            [page @ 0xB0..=0xB7] => self.page = page & 0x7,
+            [low @ 0x00..=0x0F] => self.column = (self.column & 0xF0) | low,
+            [high @ 0x10..=0x1F] => self.column = ((high & 0x7) << 4) | (self.column & 0x0F),
+            [line @ 0x40..=0x7F] => self.start_line = line & 0x3F,
+            // the timing, the power and the scrolling do not change the picture here
+            _ => {}
+        }
+    }
+
+    fn command_byte(&mut self, byte: u8) {
+        self.command.push(byte);
+
+        if self.command.len() >= command_len(self.command[0]) {
+            self.execute();
+        }
+    }
+
+    fn receive(&mut self, data: bool, byte: u8) {
+        match data {
+            true => self.data_byte(byte),
+            false => self.command_byte(byte),
+        }
+    }
+
+    fn data_byte(&mut self, byte: u8) {
+        self.ram[self.page as usize][self.column as usize & (WIDTH - 1)] = byte;
+
+        let (columns, pages) = (self.columns, self.pages);
+
+        match self.addressing_mode {
+            AddressingMode::Horizontal => {
+                if self.column >= columns.1 {
+                    self.column = columns.0;
+                    self.page = if self.page >= pages.1 {
+                        pages.0
+                    } else {
+                        self.page + 1
+                    };
+                } else {
+                    self.column += 1;
+                }
+            }
+            AddressingMode::Vertical => {
+                if self.page >= pages.1 {
+                    self.page = pages.0;
+                    self.column = if self.column >= columns.1 {
+                        columns.0
+                    } else {
+                        self.column + 1
+                    };
+                } else {
+                    self.page += 1;
+                }
+            }
+            AddressingMode::Page => self.column = (self.column + 1) % WIDTH as u8,
+        }
+    }
+}
+
+impl I2cDevice for Ssd1306 {
+    fn start(&mut self, _read: bool) {
+        self.expect = Expect::Control;
+    }
+
+    fn write(&mut self, byte: u8) -> bool {
+        match self.expect {
+            Expect::Control => {
+                let data = byte & CONTROL_DATA != 0;
+                self.expect = match byte & CONTROL_CONTINUATION != 0 {
+                    true => Expect::Single { data },
+                    false => Expect::Stream { data },
+                };
+            }
+            Expect::Single { data } => {
+                self.expect = Expect::Control;
+                self.receive(data, byte);
+            }
+            Expect::Stream { data } => self.receive(data, byte),
+        }
+
+        true
+    }
+
+    /// The status byte, bit 6 is set while the display is off
+    fn read(&mut self) -> u8 {
+        (!self.display_on as u8) << 6
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(display: &mut Ssd1306, bytes: &[u8]) {
+        display.start(false);
+        bytes.iter().for_each(|byte| assert!(display.write(*byte)));
+        display.stop();
+    }
+
+    #[test]
+    fn test_horizontal_addressing() {
+        let mut display = Ssd1306::new(64);
+
+        // the usual initialization: horizontal mode, remapped segments and COM scan
+        send(&mut display, &[0x00, 0x20, 0x00, 0xA1, 0xC8, 0xAF]);
+        send(&mut display, &[0x00, 0x21, 126, 127, 0x22, 0, 7]);
+        send(&mut display, &[0x40, 0x01, 0x80, 0xFF]);
+
+        assert!(display.pixel(126, 0));
+        assert!(!display.pixel(126, 1));
+        assert!(display.pixel(127, 7));
+        // the third byte wrapped to the next page
+        assert!((8..16).all(|y| display.pixel(126, y)));
+
+        // a command and a data byte each after its own control byte
+        send(&mut display, &[0x80, 0xA7, 0xC0, 0x00]);
+        assert!(display.inverse);
+        assert!(!display.pixel(126, 8));
+        assert!(display.pixel(0, 0));
+    }
+}
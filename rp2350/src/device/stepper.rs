@@ -0,0 +1,110 @@
+/**
+ * @file device/stepper.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief A4988 style stepper motor driver, placed on the breadboard
+ */
+use super::Component;
+
+const STEP: usize = 0;
+const DIR: usize = 1;
+const EN: usize = 2;
+
+/// Stepper motor behind a STEP/DIR driver. The motor moves one step on each rising edge of
+/// STEP, clockwise while DIR is high, as long as the active low EN is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepperDriver {
+    /// Full steps per revolution of the motor
+    pub steps_per_revolution: u32,
+    /// Current position, in steps from where it started
+    pub position: i64,
+    direction: bool,
+    enabled: bool,
+}
+
+impl Default for StepperDriver {
+    /// The 1.8° motors
+    fn default() -> Self {
+        Self {
+            steps_per_revolution: 200,
+            position: 0,
+            direction: false,
+            // EN is pulled down on the boards
+            enabled: true,
+        }
+    }
+}
+
+impl StepperDriver {
+    /// Angle of the shaft in degrees, between 0 and 360
+    pub fn angle(&self) -> f64 {
+        let steps = self.steps_per_revolution.max(1) as i64;
+        self.position.rem_euclid(steps) as f64 * 360.0 / steps as f64
+    }
+}
+
+impl Component for StepperDriver {
+    fn name(&self) -> &'static str {
+        "Stepper driver"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["STEP", "DIR", "EN"]
+    }
+
+    fn notify_pin_change(&mut self, pin: usize, level: bool, _now: u64, _clk_sys: u64) {
+        match pin {
+            STEP if level && self.enabled => match self.direction {
+                true => self.position += 1,
+                false => self.position -= 1,
+            },
+            DIR => self.direction = level,
+            EN => self.enabled = !level,
+            _ => {}
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{} steps, {:.1}°", self.position, self.angle()))
+    }
+
+    fn save(&self) -> String {
+        self.steps_per_revolution.to_string()
+    }
+
+    fn load(&mut self, state: &str) {
+        if let Ok(steps) = state.parse() {
+            self.steps_per_revolution = steps;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps() {
+        let mut driver = StepperDriver::default();
+
+        driver.notify_pin_change(DIR, true, 0, 1);
+        for _ in 0..50 {
+            driver.notify_pin_change(STEP, true, 0, 1);
+            driver.notify_pin_change(STEP, false, 0, 1);
+        }
+
+        assert_eq!(driver.position, 50);
+        assert_eq!(driver.angle(), 90.0);
+
+        // disabled, the pulses are ignored
+        driver.notify_pin_change(EN, true, 0, 1);
+        driver.notify_pin_change(STEP, true, 0, 1);
+        assert_eq!(driver.position, 50);
+
+        driver.notify_pin_change(EN, false, 0, 1);
+        driver.notify_pin_change(DIR, false, 0, 1);
+        driver.notify_pin_change(STEP, false, 0, 1);
+        driver.notify_pin_change(STEP, true, 0, 1);
+        assert_eq!(driver.position, 49);
+    }
+}
@@ -0,0 +1,300 @@
+/**
+ * @file device/stimulus.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Scripted stimulus, driving the inputs of the chip at given points in time
+ */
+use super::{drive_pin, VirtualDevice};
+use crate::gpio::NUM_GPIO;
+use crate::Rp2350;
+use thiserror::Error;
+
+/// Lines are counted from 1
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum StimulusError {
+    #[error("Line {0}: expected `<time_us> <command> <arguments>`")]
+    Malformed(usize),
+
+    #[error("Line {line}: unknown command `{command}`")]
+    UnknownCommand { line: usize, command: String },
+
+    #[error("Line {line}: invalid argument `{argument}`")]
+    InvalidArgument { line: usize, argument: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StimulusAction {
+    Gpio { pin: u8, level: bool },
+    Adc { channel: usize, voltage: f64 },
+    Temperature(f64),
+    Bootsel(bool),
+    Write { address: u32, value: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StimulusStep {
+    /// Simulated time since the stimulus was attached
+    pub time_us: u64,
+    pub action: StimulusAction,
+}
+
+/// Steps applied once their time is reached, in the order of their time.
+///
+/// The text format has one step per line, `#` starts a comment:
+///
+/// ```text
+/// 1000 gpio 15 low      # press the button on GPIO15 after 1ms
+/// 51000 gpio 15 high
+/// 2000 adc 0 1.65
+/// 0 temperature 45.0
+/// 0 bootsel released
+/// 3000 write 0x400a8010 999
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Stimulus {
+    steps: Vec<StimulusStep>,
+    next: usize,
+    /// Tick at which the device was attached
+    start: u64,
+}
+
+impl Stimulus {
+    pub fn new(mut steps: Vec<StimulusStep>) -> Self {
+        // stable, the steps at the same time keep their order
+        steps.sort_by_key(|step| step.time_us);
+
+        Self {
+            steps,
+            next: 0,
+            start: 0,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, StimulusError> {
+        let mut steps = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            steps.push(parse_step(line_no, line)?);
+        }
+
+        Ok(Self::new(steps))
+    }
+
+    pub fn steps(&self) -> &[StimulusStep] {
+        &self.steps
+    }
+
+    /// Number of steps already applied
+    pub fn applied(&self) -> usize {
+        self.next
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.steps.len()
+    }
+
+    fn apply(mcu: &mut Rp2350, action: StimulusAction) {
+        match action {
+            StimulusAction::Gpio { pin, level } => drive_pin(mcu, pin, level),
+            // the channel is checked while parsing
+            StimulusAction::Adc { channel, voltage } => {
+                let _ = mcu.set_adc_input(channel, voltage);
+            }
+            StimulusAction::Temperature(temperature) => mcu.set_ambient_temperature(temperature),
+            StimulusAction::Bootsel(pressed) => mcu.set_bootsel(pressed),
+            StimulusAction::Write { address, value } => {
+                if let Err(why) = mcu.write_register(address, value) {
+                    log::warn!("Stimulus write failed: {why}");
+                }
+            }
+        }
+    }
+}
+
+fn parse_step(line: usize, text: &str) -> Result<StimulusStep, StimulusError> {
+    let mut words = text.split_whitespace();
+    let (Some(time), Some(command)) = (words.next(), words.next()) else {
+        return Err(StimulusError::Malformed(line));
+    };
+
+    let arguments: Vec<&str> = words.collect();
+    let invalid = |argument: &str| StimulusError::InvalidArgument {
+        line,
+        argument: argument.to_string(),
+    };
+
+    let time_us = time.parse::<u64>().map_err(|_| invalid(time))?;
+
+    let action = match (command, arguments.as_slice()) {
+        ("gpio", [pin, level]) => StimulusAction::Gpio {
+            pin: pin
+                .parse::<u8>()
+                .ok()
+                .filter(|pin| (*pin as usize) < NUM_GPIO)
+                .ok_or_else(|| invalid(pin))?,
+            level: parse_level(level).ok_or_else(|| invalid(level))?,
+        },
+        ("adc", [channel, voltage]) => StimulusAction::Adc {
+            channel: channel
+                .parse::<usize>()
+                .ok()
+                .filter(|channel| *channel < 4)
+                .ok_or_else(|| invalid(channel))?,
+            voltage: voltage.parse::<f64>().map_err(|_| invalid(voltage))?,
+        },
+        ("temperature", [celsius]) => {
+            StimulusAction::Temperature(celsius.parse::<f64>().map_err(|_| invalid(celsius))?)
+        }
+        ("bootsel", [state]) => StimulusAction::Bootsel(match *state {
+            "pressed" | "1" => true,
+            "released" | "0" => false,
+            _ => return Err(invalid(state)),
+        }),
+        ("write", [address, value]) => StimulusAction::Write {
+            address: parse_number(address).ok_or_else(|| invalid(address))?,
+            value: parse_number(value).ok_or_else(|| invalid(value))?,
+        },
+        ("gpio" | "adc" | "temperature" | "bootsel" | "write", _) => {
+            return Err(StimulusError::Malformed(line))
+        }
+        _ => {
+            return Err(StimulusError::UnknownCommand {
+                line,
+                command: command.to_string(),
+            })
+        }
+    };
+
+    Ok(StimulusStep { time_us, action })
+}
+
+/// Hex with a `0x` prefix, decimal otherwise
+fn parse_number(number: &str) -> Option<u32> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
+}
+
+fn parse_level(level: &str) -> Option<bool> {
+    match level {
+        "high" | "1" => Some(true),
+        "low" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+impl VirtualDevice for Stimulus {
+    fn attach(&mut self, mcu: &mut Rp2350) {
+        self.start = *mcu.clock.ticks.borrow();
+        self.next = 0;
+    }
+
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        if self.is_done() {
+            return;
+        }
+
+        let elapsed = *mcu.clock.ticks.borrow() - self.start;
+        let now_us = (elapsed as u128 * 1_000_000 / mcu.clock.clk_sys() as u128) as u64;
+
+        while let Some(step) = self
+            .steps
+            .get(self.next)
+            .filter(|step| step.time_us <= now_us)
+        {
+            Self::apply(mcu, step.action);
+            self.next += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let stimulus = Stimulus::parse(
+            "# button\n\
+             500 gpio 3 low\n\
+             100 gpio 3 high   # released first\n\
+             \n\
+             200 adc 1 2.5\n\
+             0 bootsel pressed\n\
+             700 write 0x400a8010 999\n",
+        )
+        .unwrap();
+
+        let times: Vec<u64> = stimulus.steps().iter().map(|step| step.time_us).collect();
+        assert_eq!(times, [0, 100, 200, 500, 700]);
+        assert_eq!(
+            stimulus.steps()[4].action,
+            StimulusAction::Write {
+                address: 0x400a_8010,
+                value: 999
+            }
+        );
+        assert_eq!(
+            stimulus.steps()[2].action,
+            StimulusAction::Adc {
+                channel: 1,
+                voltage: 2.5
+            }
+        );
+
+        assert_eq!(
+            Stimulus::parse("10 gpio 48 high").unwrap_err(),
+            StimulusError::InvalidArgument {
+                line: 1,
+                argument: "48".to_string()
+            }
+        );
+        assert_eq!(
+            Stimulus::parse("\n10 uart 0 hello").unwrap_err(),
+            StimulusError::UnknownCommand {
+                line: 2,
+                command: "uart".to_string()
+            }
+        );
+        assert_eq!(
+            Stimulus::parse("10 gpio 3").unwrap_err(),
+            StimulusError::Malformed(1)
+        );
+    }
+
+    #[test]
+    fn test_apply_in_time() {
+        let mut mcu = Rp2350::new();
+        let mut stimulus = Stimulus::parse("2 gpio 4 high\n5 adc 0 1.0").unwrap();
+        let cycles_per_us = mcu.clock.clk_sys() / 1_000_000;
+        let input = |mcu: &Rp2350| mcu.gpio.borrow().get_pin(4).unwrap().raw_input_value;
+
+        stimulus.attach(&mut mcu);
+
+        for _ in 0..2 * cycles_per_us {
+            stimulus.tick(&mut mcu);
+            mcu.clock.tick();
+        }
+
+        assert!(!input(&mcu));
+        stimulus.tick(&mut mcu);
+        assert!(input(&mcu));
+        assert_eq!(stimulus.applied(), 1);
+
+        for _ in 0..3 * cycles_per_us + 1 {
+            stimulus.tick(&mut mcu);
+            mcu.clock.tick();
+        }
+
+        assert!(stimulus.is_done());
+        assert_eq!(mcu.bus.peripherals.adc.borrow().inputs[0], 1.0);
+    }
+}
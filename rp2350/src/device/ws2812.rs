@@ -0,0 +1,194 @@
+/**
+ * @file device/ws2812.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief WS2812 (NeoPixel) LED strip, decoded from the timings of its data line
+ */
+use super::Component;
+
+/// A high pulse longer than that is a 1, 0.4us for a 0 and 0.8us for a 1
+const BIT_THRESHOLD_NS: u64 = 625;
+/// The line held low for that long latches the colors shifted in
+const RESET_NS: u64 = 50_000;
+const BITS_PER_LED: u32 = 24;
+
+/// Strip of WS2812 LEDs chained on one data line. Each LED keeps the first 24 bits it
+/// receives, in the GRB order, and passes the rest down the strip; the colors show once
+/// the line is held low for the reset time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ws2812 {
+    /// Colors shown by the LEDs, in RGB
+    pub pixels: Vec<[u8; 3]>,
+    /// Colors shifted in since the last reset
+    pending: Vec<[u8; 3]>,
+    shift: u32,
+    bits: u32,
+    last_rise: Option<u64>,
+    last_fall: Option<u64>,
+    /// Frames latched since the start
+    pub frames: u64,
+}
+
+impl Default for Ws2812 {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl Ws2812 {
+    pub fn new(length: usize) -> Self {
+        Self {
+            pixels: vec![[0; 3]; length],
+            pending: Vec::new(),
+            shift: 0,
+            bits: 0,
+            last_rise: None,
+            last_fall: None,
+            frames: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Change the number of LEDs of the strip, the new ones are off
+    pub fn set_len(&mut self, length: usize) {
+        self.pixels.resize(length, [0; 3]);
+    }
+
+    fn latch(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        for (pixel, color) in self.pixels.iter_mut().zip(self.pending.drain(..)) {
+            *pixel = color;
+        }
+
+        self.shift = 0;
+        self.bits = 0;
+        self.frames += 1;
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        self.shift = (self.shift << 1) | bit as u32;
+        self.bits += 1;
+
+        if self.bits == BITS_PER_LED {
+            let [_, g, r, b] = self.shift.to_be_bytes();
+            self.pending.push([r, g, b]);
+            self.shift = 0;
+            self.bits = 0;
+        }
+    }
+}
+
+fn nanos(ticks: u64, clk_sys: u64) -> u64 {
+    ticks.saturating_mul(1_000_000_000) / clk_sys.max(1)
+}
+
+impl Component for Ws2812 {
+    fn name(&self) -> &'static str {
+        "WS2812 strip"
+    }
+
+    fn pins(&self) -> &'static [&'static str] {
+        &["DIN"]
+    }
+
+    fn notify_pin_change(&mut self, _pin: usize, level: bool, now: u64, clk_sys: u64) {
+        if level {
+            if self
+                .last_fall
+                .is_some_and(|fall| nanos(now.saturating_sub(fall), clk_sys) >= RESET_NS)
+            {
+                self.latch();
+                self.shift = 0;
+                self.bits = 0;
+            }
+
+            self.last_rise = Some(now);
+            return;
+        }
+
+        self.last_fall = Some(now);
+
+        if let Some(rise) = self.last_rise.take() {
+            let high = nanos(now.saturating_sub(rise), clk_sys);
+            self.push_bit(high > BIT_THRESHOLD_NS);
+        }
+    }
+
+    fn tick(&mut self, now: u64, clk_sys: u64) {
+        let idle = self
+            .last_fall
+            .is_some_and(|fall| nanos(now.saturating_sub(fall), clk_sys) >= RESET_NS);
+
+        if idle && self.last_rise.is_none() {
+            self.latch();
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{} LEDs, {} frames", self.len(), self.frames))
+    }
+
+    fn save(&self) -> String {
+        self.len().to_string()
+    }
+
+    fn load(&mut self, state: &str) {
+        if let Ok(length) = state.parse() {
+            self.set_len(length);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLK_SYS: u64 = 150_000_000;
+
+    /// Send the colors in GRB at 800kHz, 150 ticks per 1us
+    fn send(strip: &mut Ws2812, now: &mut u64, colors: &[[u8; 3]]) {
+        for [r, g, b] in colors {
+            let word = u32::from_be_bytes([0, *g, *r, *b]);
+
+            for bit in (0..24).rev() {
+                let high = if word >> bit & 1 == 1 { 120 } else { 60 };
+                strip.notify_pin_change(0, true, *now, CLK_SYS);
+                strip.notify_pin_change(0, false, *now + high, CLK_SYS);
+                *now += 188;
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut strip = Ws2812::new(3);
+        let mut now = 1_000;
+
+        send(&mut strip, &mut now, &[[255, 0, 0], [0, 128, 0], [1, 2, 3]]);
+
+        // nothing shows before the reset
+        strip.tick(now, CLK_SYS);
+        assert_eq!(strip.pixels, [[0; 3]; 3]);
+
+        now += CLK_SYS / 10_000;
+        strip.tick(now, CLK_SYS);
+        assert_eq!(strip.pixels, [[255, 0, 0], [0, 128, 0], [1, 2, 3]]);
+        assert_eq!(strip.frames, 1);
+
+        // a shorter frame only changes the first LEDs
+        send(&mut strip, &mut now, &[[9, 9, 9]]);
+        now += CLK_SYS / 10_000;
+        strip.tick(now, CLK_SYS);
+        assert_eq!(strip.pixels, [[9, 9, 9], [0, 128, 0], [1, 2, 3]]);
+    }
+}
@@ -0,0 +1,436 @@
+/**
+ * @file elf.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Reader of 32 bit little endian ELF files, limited to the sections and the symbols
+ */
+pub mod dwarf;
+pub mod lines;
+
+use thiserror::Error;
+
+pub use dwarf::{DebugInfo, Encoding, Field, Member, Type, TypeRef, Variable};
+pub use lines::{LineRow, LineTable};
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_NOBITS: u32 = 8;
+
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+const SECTION_HEADER_SIZE: usize = 40;
+const SYMBOL_SIZE: usize = 16;
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    #[error("Not an ELF file")]
+    NotElf,
+
+    #[error("Only 32 bit little endian ELF files are supported")]
+    Unsupported,
+
+    #[error("Truncated or malformed {0}")]
+    Malformed(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub address: u32,
+    pub size: u32,
+    /// The section takes no space in the file, e.g. `.bss`
+    pub no_bits: bool,
+    offset: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Object,
+    Function,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u32,
+    pub size: u32,
+    pub kind: SymbolKind,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Elf {
+    data: Vec<u8>,
+    pub entry: u32,
+    pub sections: Vec<Section>,
+    pub symbols: Vec<Symbol>,
+}
+
+struct RawSection {
+    name: u32,
+    kind: u32,
+    address: u32,
+    offset: u32,
+    size: u32,
+    link: u32,
+}
+
+impl Elf {
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        if !data.starts_with(b"\x7fELF") {
+            return Err(ElfError::NotElf);
+        }
+
+        // ELFCLASS32 and ELFDATA2LSB
+        if data.get(4) != Some(&1) || data.get(5) != Some(&1) {
+            return Err(ElfError::Unsupported);
+        }
+
+        let header = ElfError::Malformed("header");
+
+        let mut reader = Reader::at(data, 0x18);
+        let entry = reader.u32().ok_or(header)?;
+        let _program_headers = reader.u32().ok_or(header)?;
+        let section_headers = reader.u32().ok_or(header)? as usize;
+
+        let mut reader = Reader::at(data, 0x2E);
+        let entry_size = reader.u16().ok_or(header)? as usize;
+        let count = reader.u16().ok_or(header)? as usize;
+        let names_index = reader.u16().ok_or(header)? as usize;
+
+        if count != 0 && entry_size < SECTION_HEADER_SIZE {
+            return Err(header);
+        }
+
+        let mut raw = Vec::with_capacity(count);
+
+        for index in 0..count {
+            let mut reader = Reader::at(data, section_headers + index * entry_size);
+            let mut next = || reader.u32().ok_or(ElfError::Malformed("section header"));
+
+            let name = next()?;
+            let kind = next()?;
+            let _flags = next()?;
+            let address = next()?;
+            let offset = next()?;
+            let size = next()?;
+            let link = next()?;
+
+            raw.push(RawSection {
+                name,
+                kind,
+                address,
+                offset,
+                size,
+                link,
+            });
+        }
+
+        let contents = |section: &RawSection| match section.kind {
+            SHT_NOBITS => Some(&data[..0]),
+            _ => data.get(
+                section.offset as usize
+                    ..(section.offset as usize).checked_add(section.size as usize)?,
+            ),
+        };
+
+        let names = raw.get(names_index).and_then(contents).unwrap_or_default();
+
+        let sections = raw
+            .iter()
+            .map(|section| Section {
+                name: cstr_at(names, section.name as usize)
+                    .unwrap_or_default()
+                    .to_string(),
+                address: section.address,
+                size: section.size,
+                no_bits: section.kind == SHT_NOBITS,
+                offset: section.offset,
+            })
+            .collect();
+
+        let mut symbols = Vec::new();
+
+        for table in raw.iter().filter(|section| section.kind == SHT_SYMTAB) {
+            let entries = contents(table).ok_or(ElfError::Malformed("symbol table"))?;
+            let strings = raw
+                .get(table.link as usize)
+                .and_then(contents)
+                .unwrap_or_default();
+
+            for entry in entries.chunks_exact(SYMBOL_SIZE) {
+                let mut reader = Reader::new(entry);
+                let name = reader.u32().unwrap_or_default();
+                let address = reader.u32().unwrap_or_default();
+                let size = reader.u32().unwrap_or_default();
+                let info = reader.u8().unwrap_or_default();
+
+                let Some(name) = cstr_at(strings, name as usize).filter(|name| !name.is_empty())
+                else {
+                    continue;
+                };
+
+                let kind = match info & 0xf {
+                    STT_OBJECT => SymbolKind::Object,
+                    STT_FUNC => SymbolKind::Function,
+                    _ => SymbolKind::Other,
+                };
+
+                symbols.push(Symbol {
+                    name: name.to_string(),
+                    address,
+                    size,
+                    kind,
+                });
+            }
+        }
+
+        Ok(Self {
+            data: data.to_vec(),
+            entry,
+            sections,
+            symbols,
+        })
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Content of a section in the file, `None` for the sections without any
+    pub fn section_data(&self, name: &str) -> Option<&[u8]> {
+        let section = self.section(name).filter(|section| !section.no_bits)?;
+        let start = section.offset as usize;
+        self.data
+            .get(start..start.checked_add(section.size as usize)?)
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+
+    /// Variables and types described by the DWARF sections, the program has to be
+    /// compiled with `-g`
+    pub fn debug_info(&self) -> Result<DebugInfo, ElfError> {
+        DebugInfo::parse(self)
+    }
+
+    /// Source file and line of the instructions, from the `.debug_line` section
+    pub fn line_table(&self) -> Result<LineTable, ElfError> {
+        LineTable::parse(self)
+    }
+}
+
+/// Little endian cursor over a byte slice, every read returns `None` past the end
+#[derive(Clone)]
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn at(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.bytes(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// Unsigned value of 1 to 8 bytes
+    fn sized(&mut self, size: u8) -> Option<u64> {
+        let bytes = self.bytes(size as usize).filter(|_| size <= 8)?;
+        Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0, |acc, &byte| (acc << 8) | byte as u64),
+        )
+    }
+
+    fn uleb(&mut self) -> Option<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.u8()?;
+
+            if shift < 64 {
+                result |= ((byte & 0x7f) as u64) << shift;
+            }
+
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+    }
+
+    fn sleb(&mut self) -> Option<i64> {
+        let mut result = 0i64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.u8()?;
+
+            if shift < 64 {
+                result |= ((byte & 0x7f) as i64) << shift;
+            }
+
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1 << shift;
+                }
+
+                return Some(result);
+            }
+        }
+    }
+
+    fn cstr(&mut self) -> Option<&'a str> {
+        let rest = self.data.get(self.pos..)?;
+        let end = rest.iter().position(|&byte| byte == 0)?;
+        self.pos += end + 1;
+        core::str::from_utf8(&rest[..end]).ok()
+    }
+}
+
+fn cstr_at(data: &[u8], offset: usize) -> Option<&str> {
+    Reader::at(data, offset).cstr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn build_elf() -> Vec<u8> {
+        let names = b"\0.symtab\0.strtab\0.shstrtab\0.data\0.bss\0";
+        let strings = b"\0counter\0main\0";
+
+        let mut symbols = vec![0; SYMBOL_SIZE];
+        symbols.extend(words(&[1, 0x2000_0000, 4]));
+        symbols.extend([0x11, 0, 4, 0]);
+        symbols.extend(words(&[9, 0x1000_0100, 32]));
+        symbols.extend([0x12, 0, 1, 0]);
+
+        let data = [1, 2, 3, 4];
+
+        let names_offset = 52;
+        let strings_offset = names_offset + names.len();
+        let symbols_offset = strings_offset + strings.len();
+        let data_offset = symbols_offset + symbols.len();
+        let headers_offset = data_offset + data.len();
+
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1];
+        elf.resize(16, 0);
+        elf.extend([2, 0, 0xf3, 0]);
+        elf.extend(words(&[1, 0x1000_0100, 0, headers_offset as u32, 0]));
+        elf.extend([52, 0, 0, 0, 0, 0, 40, 0, 6, 0, 1, 0]);
+
+        elf.extend(names);
+        elf.extend(strings);
+        elf.extend(&symbols);
+        elf.extend(data);
+
+        let section =
+            |name: u32, kind: u32, address: u32, offset: usize, size: usize, link: u32| {
+                words(&[
+                    name,
+                    kind,
+                    0,
+                    address,
+                    offset as u32,
+                    size as u32,
+                    link,
+                    0,
+                    4,
+                    0,
+                ])
+            };
+
+        elf.extend([0; SECTION_HEADER_SIZE]);
+        elf.extend(section(17, 3, 0, names_offset, names.len(), 0));
+        elf.extend(section(9, 3, 0, strings_offset, strings.len(), 0));
+        elf.extend(section(1, SHT_SYMTAB, 0, symbols_offset, symbols.len(), 2));
+        elf.extend(section(27, 1, 0x2000_0000, data_offset, data.len(), 0));
+        elf.extend(section(33, SHT_NOBITS, 0x2000_0004, 0, 64, 0));
+
+        elf
+    }
+
+    #[test]
+    fn test_parse() {
+        let elf = Elf::parse(&build_elf()).unwrap();
+
+        assert_eq!(elf.entry, 0x1000_0100);
+        assert_eq!(elf.section_data(".data"), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(elf.section(".bss").map(|section| section.size), Some(64));
+        assert_eq!(elf.section_data(".bss"), None);
+
+        let counter = elf.symbol("counter").unwrap();
+        assert_eq!(counter.address, 0x2000_0000);
+        assert_eq!(counter.size, 4);
+        assert_eq!(counter.kind, SymbolKind::Object);
+        assert_eq!(elf.symbol("main").unwrap().kind, SymbolKind::Function);
+        assert_eq!(elf.symbols.len(), 2);
+
+        assert!(matches!(Elf::parse(b"not an elf"), Err(ElfError::NotElf)));
+
+        let mut big_endian = build_elf();
+        big_endian[5] = 2;
+        assert!(matches!(
+            Elf::parse(&big_endian),
+            Err(ElfError::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn test_leb128() {
+        let mut reader = Reader::new(&[0xe5, 0x8e, 0x26, 0x7f, 0x80, 0x7f]);
+
+        assert_eq!(reader.uleb(), Some(624485));
+        assert_eq!(reader.sleb(), Some(-1));
+        assert_eq!(reader.sleb(), Some(-128));
+        assert!(reader.is_empty());
+        assert_eq!(reader.uleb(), None);
+    }
+}
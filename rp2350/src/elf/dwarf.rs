@@ -0,0 +1,1295 @@
+/**
+ * @file elf/dwarf.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Subset of DWARF 2 to 5, the global variables and their C types
+ */
+use super::{cstr_at, Elf, ElfError, LineTable, Reader};
+use std::collections::hash_map::Entry::{Occupied, Vacant};
+use std::collections::HashMap;
+
+const DW_TAG_ARRAY_TYPE: u64 = 0x01;
+const DW_TAG_ENUMERATION_TYPE: u64 = 0x04;
+const DW_TAG_MEMBER: u64 = 0x0d;
+const DW_TAG_POINTER_TYPE: u64 = 0x0f;
+const DW_TAG_STRUCTURE_TYPE: u64 = 0x13;
+const DW_TAG_SUBROUTINE_TYPE: u64 = 0x15;
+const DW_TAG_TYPEDEF: u64 = 0x16;
+const DW_TAG_UNION_TYPE: u64 = 0x17;
+const DW_TAG_SUBRANGE_TYPE: u64 = 0x21;
+const DW_TAG_BASE_TYPE: u64 = 0x24;
+const DW_TAG_CONST_TYPE: u64 = 0x26;
+const DW_TAG_ENUMERATOR: u64 = 0x28;
+const DW_TAG_VARIABLE: u64 = 0x34;
+const DW_TAG_VOLATILE_TYPE: u64 = 0x35;
+const DW_TAG_RESTRICT_TYPE: u64 = 0x37;
+const DW_TAG_ATOMIC_TYPE: u64 = 0x47;
+
+const DW_AT_LOCATION: u64 = 0x02;
+const DW_AT_NAME: u64 = 0x03;
+const DW_AT_BYTE_SIZE: u64 = 0x0b;
+const DW_AT_CONST_VALUE: u64 = 0x1c;
+const DW_AT_UPPER_BOUND: u64 = 0x2f;
+const DW_AT_COUNT: u64 = 0x37;
+const DW_AT_DATA_MEMBER_LOCATION: u64 = 0x38;
+const DW_AT_ENCODING: u64 = 0x3e;
+const DW_AT_SPECIFICATION: u64 = 0x47;
+const DW_AT_TYPE: u64 = 0x49;
+
+const DW_FORM_ADDR: u64 = 0x01;
+const DW_FORM_BLOCK2: u64 = 0x03;
+const DW_FORM_BLOCK4: u64 = 0x04;
+const DW_FORM_DATA2: u64 = 0x05;
+const DW_FORM_DATA4: u64 = 0x06;
+const DW_FORM_DATA8: u64 = 0x07;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_BLOCK: u64 = 0x09;
+const DW_FORM_BLOCK1: u64 = 0x0a;
+const DW_FORM_DATA1: u64 = 0x0b;
+const DW_FORM_FLAG: u64 = 0x0c;
+const DW_FORM_SDATA: u64 = 0x0d;
+const DW_FORM_STRP: u64 = 0x0e;
+const DW_FORM_UDATA: u64 = 0x0f;
+const DW_FORM_REF_ADDR: u64 = 0x10;
+const DW_FORM_REF1: u64 = 0x11;
+const DW_FORM_REF2: u64 = 0x12;
+const DW_FORM_REF4: u64 = 0x13;
+const DW_FORM_REF8: u64 = 0x14;
+const DW_FORM_REF_UDATA: u64 = 0x15;
+const DW_FORM_INDIRECT: u64 = 0x16;
+const DW_FORM_SEC_OFFSET: u64 = 0x17;
+const DW_FORM_EXPRLOC: u64 = 0x18;
+const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+const DW_FORM_STRX: u64 = 0x1a;
+const DW_FORM_ADDRX: u64 = 0x1b;
+const DW_FORM_REF_SUP4: u64 = 0x1c;
+const DW_FORM_STRP_SUP: u64 = 0x1d;
+const DW_FORM_DATA16: u64 = 0x1e;
+const DW_FORM_LINE_STRP: u64 = 0x1f;
+const DW_FORM_REF_SIG8: u64 = 0x20;
+const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+const DW_FORM_LOCLISTX: u64 = 0x22;
+const DW_FORM_RNGLISTX: u64 = 0x23;
+const DW_FORM_REF_SUP8: u64 = 0x24;
+const DW_FORM_STRX1: u64 = 0x25;
+const DW_FORM_STRX2: u64 = 0x26;
+const DW_FORM_STRX3: u64 = 0x27;
+const DW_FORM_STRX4: u64 = 0x28;
+const DW_FORM_ADDRX1: u64 = 0x29;
+const DW_FORM_ADDRX2: u64 = 0x2a;
+const DW_FORM_ADDRX3: u64 = 0x2b;
+const DW_FORM_ADDRX4: u64 = 0x2c;
+
+const DW_ATE_ADDRESS: u64 = 0x01;
+const DW_ATE_BOOLEAN: u64 = 0x02;
+const DW_ATE_FLOAT: u64 = 0x04;
+const DW_ATE_SIGNED: u64 = 0x05;
+const DW_ATE_SIGNED_CHAR: u64 = 0x06;
+const DW_ATE_UNSIGNED_CHAR: u64 = 0x08;
+const DW_ATE_UTF: u64 = 0x10;
+
+const DW_UT_COMPILE: u8 = 0x01;
+const DW_UT_PARTIAL: u8 = 0x03;
+const DW_UT_SKELETON: u8 = 0x04;
+const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+
+const DW_OP_ADDR: u8 = 0x03;
+const DW_OP_PLUS_UCONST: u8 = 0x23;
+
+/// Guard against cycles in a malformed type graph
+const MAX_DEPTH: usize = 16;
+
+/// Longer arrays are cut in the rendered value
+pub const MAX_ELEMENTS: usize = 16;
+
+/// Offset of the describing entry in `.debug_info`
+pub type TypeRef = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Signed,
+    Unsigned,
+    SignedChar,
+    UnsignedChar,
+    Float,
+    Boolean,
+    Address,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: Option<String>,
+    pub offset: u32,
+    pub ty: Option<TypeRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Base {
+        name: String,
+        encoding: Encoding,
+        size: u32,
+    },
+    /// `target` is `None` for `void *`
+    Pointer {
+        size: u32,
+        target: Option<TypeRef>,
+    },
+    Struct {
+        name: Option<String>,
+        size: u32,
+        members: Vec<Member>,
+    },
+    Union {
+        name: Option<String>,
+        size: u32,
+        members: Vec<Member>,
+    },
+    /// One count per dimension, the outermost first, 0 if unknown
+    Array {
+        element: Option<TypeRef>,
+        counts: Vec<u32>,
+    },
+    Enum {
+        name: Option<String>,
+        size: u32,
+        enumerators: Vec<(String, i64)>,
+    },
+    Typedef {
+        name: String,
+        target: Option<TypeRef>,
+    },
+    /// `const`, `volatile` and the like, the representation is the one of the target
+    Qualified {
+        qualifier: &'static str,
+        target: Option<TypeRef>,
+    },
+    Function,
+}
+
+/// Variable with a static address, globals and static locals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    pub address: u32,
+    pub ty: Option<TypeRef>,
+}
+
+/// Part of a value which can be shown on its own, a struct member or an array element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub offset: u32,
+    pub ty: Option<TypeRef>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DebugInfo {
+    types: HashMap<TypeRef, Type>,
+    pub variables: Vec<Variable>,
+    /// Source lines of the instructions, empty if the line table cannot be read
+    pub lines: LineTable,
+}
+
+struct Abbreviation {
+    tag: u64,
+    has_children: bool,
+    /// Name, form and the value of the implicit constants
+    attributes: Vec<(u64, u64, i64)>,
+}
+
+#[derive(Clone, Copy)]
+pub(super) enum Value<'a> {
+    Unsigned(u64),
+    Signed(i64),
+    Block(&'a [u8]),
+    Str(&'a str),
+    Reference(usize),
+    /// Forms which are skipped, e.g. the indexes into the split DWARF sections
+    Unknown,
+}
+
+impl Value<'_> {
+    pub(super) fn unsigned(self) -> Option<u64> {
+        match self {
+            Value::Unsigned(value) => Some(value),
+            Value::Signed(value) => u64::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    fn signed(self) -> Option<i64> {
+        match self {
+            Value::Unsigned(value) => Some(value as i64),
+            Value::Signed(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+pub(super) struct Sections<'a> {
+    pub(super) info: &'a [u8],
+    pub(super) abbrev: &'a [u8],
+    pub(super) str: &'a [u8],
+    pub(super) line_str: &'a [u8],
+}
+
+pub(super) struct Unit {
+    pub(super) offset: usize,
+    pub(super) version: u16,
+    pub(super) address_size: u8,
+}
+
+#[derive(Default)]
+struct Entry<'a> {
+    name: Option<&'a str>,
+    ty: Option<TypeRef>,
+    byte_size: Option<u64>,
+    encoding: Option<u64>,
+    location: Option<&'a [u8]>,
+    upper_bound: Option<i64>,
+    count: Option<u64>,
+    member_location: Option<Value<'a>>,
+    specification: Option<usize>,
+    const_value: Option<i64>,
+}
+
+impl DebugInfo {
+    pub fn parse(elf: &Elf) -> Result<Self, ElfError> {
+        let sections = Sections {
+            info: elf.section_data(".debug_info").unwrap_or_default(),
+            abbrev: elf.section_data(".debug_abbrev").unwrap_or_default(),
+            str: elf.section_data(".debug_str").unwrap_or_default(),
+            line_str: elf.section_data(".debug_line_str").unwrap_or_default(),
+        };
+
+        let mut result = Self::from_sections(&sections)?;
+        result.lines = LineTable::parse(elf).unwrap_or_default();
+        Ok(result)
+    }
+
+    fn from_sections(sections: &Sections) -> Result<Self, ElfError> {
+        let mut result = Self::default();
+        // name and type of every variable entry, for the definitions pointing to their declaration
+        let mut declarations = HashMap::new();
+        let mut definitions = Vec::new();
+        let mut abbreviations = HashMap::new();
+
+        let mut reader = Reader::new(sections.info);
+
+        while !reader.is_empty() {
+            let malformed = ElfError::Malformed("debug info");
+
+            let offset = reader.pos;
+            let length = reader.u32().ok_or(malformed)?;
+
+            if length == 0xffff_ffff {
+                // 64 bit DWARF
+                return Err(ElfError::Unsupported);
+            }
+
+            let end = reader.pos + length as usize;
+            let data = sections.info.get(..end).ok_or(malformed)?;
+            let version = reader.u16().ok_or(malformed)?;
+
+            let (abbrev_offset, address_size) = match version {
+                2..=4 => {
+                    let abbrev_offset = reader.u32().ok_or(malformed)?;
+                    (abbrev_offset, reader.u8().ok_or(malformed)?)
+                }
+                5 => {
+                    let unit_type = reader.u8().ok_or(malformed)?;
+                    let address_size = reader.u8().ok_or(malformed)?;
+                    let abbrev_offset = reader.u32().ok_or(malformed)?;
+
+                    match unit_type {
+                        DW_UT_COMPILE | DW_UT_PARTIAL => {}
+                        DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => {
+                            reader.u64().ok_or(malformed)?;
+                        }
+                        // type units are only referenced through signatures
+                        _ => {
+                            reader = Reader::at(sections.info, end);
+                            continue;
+                        }
+                    }
+
+                    (abbrev_offset, address_size)
+                }
+                _ => return Err(ElfError::Unsupported),
+            };
+
+            // the units of a file usually share the same table
+            let table = match abbreviations.entry(abbrev_offset) {
+                Occupied(table) => table.into_mut(),
+                Vacant(table) => table.insert(
+                    parse_abbreviations(sections.abbrev, abbrev_offset as usize)
+                        .ok_or(ElfError::Malformed("abbreviations"))?,
+                ),
+            };
+
+            let unit = Unit {
+                offset,
+                version,
+                address_size,
+            };
+
+            let mut entries = Reader::at(data, reader.pos);
+            result
+                .parse_unit(
+                    &mut entries,
+                    &unit,
+                    table,
+                    sections,
+                    &mut declarations,
+                    &mut definitions,
+                )
+                .ok_or(malformed)?;
+
+            reader = Reader::at(sections.info, end);
+        }
+
+        for (address, specification, name, ty) in definitions {
+            let declaration = declarations.get(&specification);
+
+            let Some(name) = name.or_else(|| declaration.and_then(|(name, _)| *name)) else {
+                continue;
+            };
+
+            result.variables.push(Variable {
+                name: name.to_string(),
+                address,
+                ty: ty.or_else(|| declaration.and_then(|(_, ty)| *ty)),
+            });
+        }
+
+        result.variables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(result)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_unit<'a>(
+        &mut self,
+        reader: &mut Reader<'a>,
+        unit: &Unit,
+        abbreviations: &HashMap<u64, Abbreviation>,
+        sections: &Sections<'a>,
+        declarations: &mut HashMap<usize, (Option<&'a str>, Option<TypeRef>)>,
+        definitions: &mut Vec<(u32, usize, Option<&'a str>, Option<TypeRef>)>,
+    ) -> Option<()> {
+        // entries owning the children being read, only the types keep track of them
+        let mut parents: Vec<usize> = Vec::new();
+
+        while !reader.is_empty() {
+            let offset = reader.pos;
+            let code = reader.uleb()?;
+
+            if code == 0 {
+                parents.pop();
+                continue;
+            }
+
+            let abbreviation = abbreviations.get(&code)?;
+            let mut entry = Entry::default();
+
+            for &(name, form, implicit) in abbreviation.attributes.iter() {
+                let value = read_value(reader, form, implicit, unit, sections)?;
+
+                match (name, value) {
+                    (DW_AT_NAME, Value::Str(value)) => entry.name = Some(value),
+                    (DW_AT_TYPE, Value::Reference(value)) => entry.ty = Some(value),
+                    (DW_AT_SPECIFICATION, Value::Reference(value)) => {
+                        entry.specification = Some(value)
+                    }
+                    (DW_AT_LOCATION, Value::Block(value)) => entry.location = Some(value),
+                    (DW_AT_BYTE_SIZE, value) => entry.byte_size = value.unsigned(),
+                    (DW_AT_ENCODING, value) => entry.encoding = value.unsigned(),
+                    (DW_AT_UPPER_BOUND, value) => entry.upper_bound = value.signed(),
+                    (DW_AT_COUNT, value) => entry.count = value.unsigned(),
+                    (DW_AT_CONST_VALUE, value) => entry.const_value = value.signed(),
+                    (DW_AT_DATA_MEMBER_LOCATION, value) => entry.member_location = Some(value),
+                    _ => {}
+                }
+            }
+
+            self.add_entry(
+                offset,
+                abbreviation.tag,
+                entry,
+                parents.last().copied(),
+                unit,
+                declarations,
+                definitions,
+            );
+
+            if abbreviation.has_children {
+                parents.push(offset);
+            }
+        }
+
+        Some(())
+    }
+
+    #[allow(clippy::too_many_arguments, clippy::type_complexity)]
+    fn add_entry<'a>(
+        &mut self,
+        offset: usize,
+        tag: u64,
+        entry: Entry<'a>,
+        parent: Option<usize>,
+        unit: &Unit,
+        declarations: &mut HashMap<usize, (Option<&'a str>, Option<TypeRef>)>,
+        definitions: &mut Vec<(u32, usize, Option<&'a str>, Option<TypeRef>)>,
+    ) {
+        let name = entry.name.map(String::from);
+        let size = entry.byte_size.unwrap_or_default() as u32;
+        let parent = parent.and_then(|parent| self.types.get_mut(&parent));
+
+        let ty = match tag {
+            DW_TAG_BASE_TYPE => Type::Base {
+                name: name.unwrap_or_default(),
+                encoding: match entry.encoding.unwrap_or_default() {
+                    DW_ATE_ADDRESS => Encoding::Address,
+                    DW_ATE_BOOLEAN => Encoding::Boolean,
+                    DW_ATE_FLOAT => Encoding::Float,
+                    DW_ATE_SIGNED => Encoding::Signed,
+                    DW_ATE_SIGNED_CHAR => Encoding::SignedChar,
+                    DW_ATE_UNSIGNED_CHAR | DW_ATE_UTF => Encoding::UnsignedChar,
+                    _ => Encoding::Unsigned,
+                },
+                size,
+            },
+            DW_TAG_POINTER_TYPE => Type::Pointer {
+                size: entry
+                    .byte_size
+                    .map_or(unit.address_size as u32, |size| size as u32),
+                target: entry.ty,
+            },
+            DW_TAG_STRUCTURE_TYPE => Type::Struct {
+                name,
+                size,
+                members: Vec::new(),
+            },
+            DW_TAG_UNION_TYPE => Type::Union {
+                name,
+                size,
+                members: Vec::new(),
+            },
+            DW_TAG_ARRAY_TYPE => Type::Array {
+                element: entry.ty,
+                counts: Vec::new(),
+            },
+            DW_TAG_ENUMERATION_TYPE => Type::Enum {
+                name,
+                size,
+                enumerators: Vec::new(),
+            },
+            DW_TAG_TYPEDEF => Type::Typedef {
+                name: name.unwrap_or_default(),
+                target: entry.ty,
+            },
+            DW_TAG_CONST_TYPE => Type::Qualified {
+                qualifier: "const",
+                target: entry.ty,
+            },
+            DW_TAG_VOLATILE_TYPE => Type::Qualified {
+                qualifier: "volatile",
+                target: entry.ty,
+            },
+            DW_TAG_RESTRICT_TYPE => Type::Qualified {
+                qualifier: "restrict",
+                target: entry.ty,
+            },
+            DW_TAG_ATOMIC_TYPE => Type::Qualified {
+                qualifier: "_Atomic",
+                target: entry.ty,
+            },
+            DW_TAG_SUBROUTINE_TYPE => Type::Function,
+
+            DW_TAG_MEMBER => {
+                let offset = match entry.member_location {
+                    Some(Value::Block([DW_OP_PLUS_UCONST, rest @ ..])) => {
+                        Reader::new(rest).uleb().unwrap_or_default()
+                    }
+                    Some(value) => value.unsigned().unwrap_or_default(),
+                    // members of an union
+                    None => 0,
+                };
+
+                if let Some(Type::Struct { members, .. } | Type::Union { members, .. }) = parent {
+                    members.push(Member {
+                        name,
+                        offset: offset as u32,
+                        ty: entry.ty,
+                    });
+                }
+
+                return;
+            }
+            DW_TAG_SUBRANGE_TYPE => {
+                let count = entry
+                    .count
+                    .or_else(|| entry.upper_bound.map(|bound| (bound + 1).max(0) as u64))
+                    .unwrap_or_default();
+
+                if let Some(Type::Array { counts, .. }) = parent {
+                    counts.push(count as u32);
+                }
+
+                return;
+            }
+            DW_TAG_ENUMERATOR => {
+                if let (Some(Type::Enum { enumerators, .. }), Some(name)) = (parent, name) {
+                    enumerators.push((name, entry.const_value.unwrap_or_default()));
+                }
+
+                return;
+            }
+            DW_TAG_VARIABLE => {
+                declarations.insert(offset, (entry.name, entry.ty));
+
+                let address = match entry.location {
+                    Some([DW_OP_ADDR, address @ ..])
+                        if address.len() == unit.address_size as usize =>
+                    {
+                        Reader::new(address).sized(unit.address_size)
+                    }
+                    _ => None,
+                };
+
+                if let Some(address) = address {
+                    let specification = entry.specification.unwrap_or(offset);
+                    definitions.push((address as u32, specification, entry.name, entry.ty));
+                }
+
+                return;
+            }
+            _ => return,
+        };
+
+        self.types.insert(offset, ty);
+    }
+
+    pub fn get(&self, ty: TypeRef) -> Option<&Type> {
+        self.types.get(&ty)
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Variable> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+
+    /// The type behind the typedefs and the qualifiers, `None` for `void`
+    pub fn resolve(&self, ty: Option<TypeRef>) -> Option<&Type> {
+        let mut current = ty;
+
+        for _ in 0..MAX_DEPTH {
+            match self.types.get(&current?)? {
+                Type::Typedef { target, .. } | Type::Qualified { target, .. } => current = *target,
+                ty => return Some(ty),
+            }
+        }
+
+        None
+    }
+
+    pub fn size_of(&self, ty: Option<TypeRef>) -> Option<u32> {
+        match self.resolve(ty)? {
+            Type::Base { size, .. }
+            | Type::Pointer { size, .. }
+            | Type::Struct { size, .. }
+            | Type::Union { size, .. }
+            | Type::Enum { size, .. } => Some(*size),
+            Type::Array { element, counts } => self.array_size(*element, counts),
+            _ => None,
+        }
+    }
+
+    fn array_size(&self, element: Option<TypeRef>, counts: &[u32]) -> Option<u32> {
+        counts
+            .iter()
+            .try_fold(self.size_of(element)?, |size, &count| {
+                size.checked_mul(count)
+            })
+    }
+
+    /// C spelling of the type
+    pub fn type_name(&self, ty: Option<TypeRef>) -> String {
+        self.type_name_at(ty, 0)
+    }
+
+    fn type_name_at(&self, ty: Option<TypeRef>, depth: usize) -> String {
+        let Some(ty) = ty.and_then(|ty| self.types.get(&ty)) else {
+            return String::from("void");
+        };
+
+        if depth > MAX_DEPTH {
+            return String::from("...");
+        }
+
+        let tag = |kind: &str, name: &Option<String>| {
+            format!("{} {}", kind, name.as_deref().unwrap_or("<anonymous>"))
+        };
+
+        match ty {
+            Type::Base { name, .. } | Type::Typedef { name, .. } => name.clone(),
+            Type::Pointer { target, .. } => format!("{} *", self.type_name_at(*target, depth + 1)),
+            Type::Struct { name, .. } => tag("struct", name),
+            Type::Union { name, .. } => tag("union", name),
+            Type::Enum { name, .. } => tag("enum", name),
+            Type::Array { element, counts } => {
+                let dimensions: String = counts.iter().map(|count| format!("[{count}]")).collect();
+                format!("{}{}", self.type_name_at(*element, depth + 1), dimensions)
+            }
+            Type::Qualified { qualifier, target } => {
+                format!("{} {}", qualifier, self.type_name_at(*target, depth + 1))
+            }
+            Type::Function => String::from("function"),
+        }
+    }
+
+    /// Render a value of the type from its bytes in memory
+    pub fn format(&self, ty: Option<TypeRef>, bytes: &[u8]) -> String {
+        self.format_at(ty, bytes, 0)
+    }
+
+    fn format_at(&self, ty: Option<TypeRef>, bytes: &[u8], depth: usize) -> String {
+        if depth > MAX_DEPTH {
+            return String::from("...");
+        }
+
+        let Some(resolved) = self.resolve(ty) else {
+            return String::from("?");
+        };
+
+        match resolved {
+            Type::Base { encoding, size, .. } => format_base(*encoding, bytes, *size),
+            Type::Pointer { size, .. } => match le_value(bytes, *size) {
+                Some(value) => format!("{value:#010x}"),
+                None => String::from("?"),
+            },
+            Type::Enum {
+                size, enumerators, ..
+            } => {
+                let Some(value) = le_value(bytes, *size).map(|value| sign_extend(value, *size))
+                else {
+                    return String::from("?");
+                };
+
+                enumerators
+                    .iter()
+                    .find(|(_, enumerator)| *enumerator == value)
+                    .map_or_else(|| value.to_string(), |(name, _)| name.clone())
+            }
+            Type::Struct { members, .. } | Type::Union { members, .. } => {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|member| {
+                        let bytes = bytes.get(member.offset as usize..).unwrap_or_default();
+                        format!(
+                            "{} = {}",
+                            member.name.as_deref().unwrap_or("<anonymous>"),
+                            self.format_at(member.ty, bytes, depth + 1)
+                        )
+                    })
+                    .collect();
+
+                format!("{{ {} }}", members.join(", "))
+            }
+            Type::Array { element, counts } => self.format_array(*element, counts, bytes, depth),
+            Type::Function => String::from("<function>"),
+            Type::Typedef { .. } | Type::Qualified { .. } => String::from("?"),
+        }
+    }
+
+    fn format_array(
+        &self,
+        element: Option<TypeRef>,
+        counts: &[u32],
+        bytes: &[u8],
+        depth: usize,
+    ) -> String {
+        let Some((&count, inner)) = counts.split_first() else {
+            return self.format_at(element, bytes, depth + 1);
+        };
+
+        if inner.is_empty() && self.is_char(element) {
+            let bytes = &bytes[..bytes.len().min(count as usize)];
+            let end = bytes
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(bytes.len());
+            return format!("{:?}", String::from_utf8_lossy(&bytes[..end]));
+        }
+
+        let stride = self.array_size(element, inner).unwrap_or_default() as usize;
+        let shown = (count as usize).min(MAX_ELEMENTS);
+
+        let mut elements: Vec<String> = (0..shown)
+            .map(|index| {
+                let bytes = bytes.get(index * stride..).unwrap_or_default();
+                self.format_array(element, inner, bytes, depth + 1)
+            })
+            .collect();
+
+        if shown < count as usize {
+            elements.push(String::from("..."));
+        }
+
+        format!("[{}]", elements.join(", "))
+    }
+
+    fn is_char(&self, ty: Option<TypeRef>) -> bool {
+        matches!(
+            self.resolve(ty),
+            Some(Type::Base {
+                encoding: Encoding::SignedChar | Encoding::UnsignedChar,
+                size: 1,
+                ..
+            })
+        )
+    }
+
+    /// Whether [`Self::encode`] can write a value of the type
+    pub fn is_editable(&self, ty: Option<TypeRef>) -> bool {
+        match self.resolve(ty) {
+            Some(
+                Type::Base { size, .. } | Type::Pointer { size, .. } | Type::Enum { size, .. },
+            ) => (1..=8).contains(size),
+            Some(Type::Array { element, counts }) => counts.len() == 1 && self.is_char(*element),
+            _ => false,
+        }
+    }
+
+    /// Bytes of a value typed by the user, only the scalars and the char arrays can be written
+    pub fn encode(&self, ty: Option<TypeRef>, text: &str) -> Option<Vec<u8>> {
+        let text = text.trim();
+
+        let (value, size) = match self.resolve(ty)? {
+            Type::Base {
+                encoding: Encoding::Float,
+                size: 4,
+                ..
+            } => (text.parse::<f32>().ok()?.to_bits() as u64, 4),
+            Type::Base {
+                encoding: Encoding::Float,
+                size: 8,
+                ..
+            } => (text.parse::<f64>().ok()?.to_bits(), 8),
+            Type::Base {
+                encoding: Encoding::Boolean,
+                size,
+                ..
+            } => match text {
+                "true" | "1" => (1, *size),
+                "false" | "0" => (0, *size),
+                _ => return None,
+            },
+            Type::Base { size, .. } | Type::Pointer { size, .. } => (parse_integer(text)?, *size),
+            Type::Enum {
+                size, enumerators, ..
+            } => {
+                let value = enumerators
+                    .iter()
+                    .find(|(name, _)| name == text)
+                    .map(|(_, value)| *value as u64)
+                    .or_else(|| parse_integer(text))?;
+
+                (value, *size)
+            }
+            Type::Array { element, counts } if counts.len() == 1 && self.is_char(*element) => {
+                let text = text
+                    .strip_prefix('"')
+                    .and_then(|text| text.strip_suffix('"'))
+                    .unwrap_or(text);
+
+                let mut bytes = text.as_bytes().to_vec();
+                // keep the terminating null
+                bytes.truncate((counts[0] as usize).saturating_sub(1));
+                bytes.resize(counts[0] as usize, 0);
+                return Some(bytes);
+            }
+            _ => return None,
+        };
+
+        (1..=8)
+            .contains(&size)
+            .then(|| value.to_le_bytes()[..size as usize].to_vec())
+    }
+
+    /// Members of a struct or the elements of an one dimension array, to be shown on their own
+    pub fn fields(&self, ty: Option<TypeRef>) -> Vec<Field> {
+        match self.resolve(ty) {
+            Some(Type::Struct { members, .. } | Type::Union { members, .. }) => members
+                .iter()
+                .map(|member| Field {
+                    name: member
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| String::from("<anonymous>")),
+                    offset: member.offset,
+                    ty: member.ty,
+                })
+                .collect(),
+            Some(Type::Array { element, counts })
+                if counts.len() == 1 && !self.is_char(*element) =>
+            {
+                let stride = self.size_of(*element).unwrap_or_default();
+
+                (0..counts[0].min(MAX_ELEMENTS as u32))
+                    .map(|index| Field {
+                        name: format!("[{index}]"),
+                        offset: index * stride,
+                        ty: *element,
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn parse_abbreviations(data: &[u8], offset: usize) -> Option<HashMap<u64, Abbreviation>> {
+    let mut reader = Reader::at(data, offset);
+    let mut table = HashMap::new();
+
+    loop {
+        let code = reader.uleb()?;
+
+        if code == 0 {
+            return Some(table);
+        }
+
+        let tag = reader.uleb()?;
+        let has_children = reader.u8()? != 0;
+        let mut attributes = Vec::new();
+
+        loop {
+            let name = reader.uleb()?;
+            let form = reader.uleb()?;
+
+            if name == 0 && form == 0 {
+                break;
+            }
+
+            let implicit = match form {
+                DW_FORM_IMPLICIT_CONST => reader.sleb()?,
+                _ => 0,
+            };
+
+            attributes.push((name, form, implicit));
+        }
+
+        table.insert(
+            code,
+            Abbreviation {
+                tag,
+                has_children,
+                attributes,
+            },
+        );
+    }
+}
+
+pub(super) fn read_value<'a>(
+    reader: &mut Reader<'a>,
+    form: u64,
+    implicit: i64,
+    unit: &Unit,
+    sections: &Sections<'a>,
+) -> Option<Value<'a>> {
+    let string = |section: &'a [u8], offset: u32| {
+        cstr_at(section, offset as usize).map_or(Value::Unknown, Value::Str)
+    };
+
+    let value = match form {
+        DW_FORM_ADDR => Value::Unsigned(reader.sized(unit.address_size)?),
+        DW_FORM_DATA1 => Value::Unsigned(reader.u8()? as u64),
+        DW_FORM_DATA2 => Value::Unsigned(reader.u16()? as u64),
+        DW_FORM_DATA4 => Value::Unsigned(reader.u32()? as u64),
+        DW_FORM_DATA8 => Value::Unsigned(reader.u64()?),
+        DW_FORM_UDATA => Value::Unsigned(reader.uleb()?),
+        DW_FORM_SDATA => Value::Signed(reader.sleb()?),
+        DW_FORM_IMPLICIT_CONST => Value::Signed(implicit),
+        DW_FORM_FLAG => Value::Unsigned(reader.u8()? as u64),
+        DW_FORM_FLAG_PRESENT => Value::Unsigned(1),
+
+        DW_FORM_BLOCK1 => {
+            let len = reader.u8()? as usize;
+            Value::Block(reader.bytes(len)?)
+        }
+        DW_FORM_BLOCK2 => {
+            let len = reader.u16()? as usize;
+            Value::Block(reader.bytes(len)?)
+        }
+        DW_FORM_BLOCK4 => {
+            let len = reader.u32()? as usize;
+            Value::Block(reader.bytes(len)?)
+        }
+        DW_FORM_BLOCK | DW_FORM_EXPRLOC => {
+            let len = reader.uleb()? as usize;
+            Value::Block(reader.bytes(len)?)
+        }
+
+        DW_FORM_STRING => Value::Str(reader.cstr()?),
+        DW_FORM_STRP => string(sections.str, reader.u32()?),
+        DW_FORM_LINE_STRP => string(sections.line_str, reader.u32()?),
+
+        DW_FORM_REF1 => Value::Reference(unit.offset + reader.u8()? as usize),
+        DW_FORM_REF2 => Value::Reference(unit.offset + reader.u16()? as usize),
+        DW_FORM_REF4 => Value::Reference(unit.offset + reader.u32()? as usize),
+        DW_FORM_REF8 => Value::Reference(unit.offset + reader.u64()? as usize),
+        DW_FORM_REF_UDATA => Value::Reference(unit.offset + reader.uleb()? as usize),
+        DW_FORM_REF_ADDR => match unit.version {
+            2 => Value::Reference(reader.sized(unit.address_size)? as usize),
+            _ => Value::Reference(reader.u32()? as usize),
+        },
+
+        DW_FORM_INDIRECT => {
+            let form = reader.uleb()?;
+            return read_value(reader, form, implicit, unit, sections);
+        }
+
+        DW_FORM_STRX1 | DW_FORM_ADDRX1 => {
+            reader.u8()?;
+            Value::Unknown
+        }
+        DW_FORM_STRX2 | DW_FORM_ADDRX2 => {
+            reader.u16()?;
+            Value::Unknown
+        }
+        DW_FORM_STRX3 | DW_FORM_ADDRX3 => {
+            reader.bytes(3)?;
+            Value::Unknown
+        }
+        DW_FORM_STRX4 | DW_FORM_ADDRX4 | DW_FORM_SEC_OFFSET | DW_FORM_STRP_SUP
+        | DW_FORM_REF_SUP4 => {
+            reader.u32()?;
+            Value::Unknown
+        }
+        DW_FORM_REF_SIG8 | DW_FORM_REF_SUP8 => {
+            reader.u64()?;
+            Value::Unknown
+        }
+        DW_FORM_DATA16 => {
+            reader.bytes(16)?;
+            Value::Unknown
+        }
+        DW_FORM_STRX | DW_FORM_ADDRX | DW_FORM_LOCLISTX | DW_FORM_RNGLISTX => {
+            reader.uleb()?;
+            Value::Unknown
+        }
+
+        // the size of an unknown form is unknown, the rest of the unit cannot be read
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// Little endian value of 1 to 8 bytes
+fn le_value(bytes: &[u8], size: u32) -> Option<u64> {
+    if !(1..=8).contains(&size) {
+        return None;
+    }
+
+    Reader::new(bytes).sized(size as u8)
+}
+
+fn sign_extend(value: u64, size: u32) -> i64 {
+    let shift = 64 - size * 8;
+    ((value << shift) as i64) >> shift
+}
+
+fn format_base(encoding: Encoding, bytes: &[u8], size: u32) -> String {
+    let Some(value) = le_value(bytes, size) else {
+        return String::from("?");
+    };
+
+    match encoding {
+        Encoding::Boolean => (value != 0).to_string(),
+        Encoding::Float if size == 4 => f32::from_bits(value as u32).to_string(),
+        Encoding::Float if size == 8 => f64::from_bits(value).to_string(),
+        Encoding::Float => format!("{value:#x}"),
+        Encoding::Signed => sign_extend(value, size).to_string(),
+        Encoding::Unsigned => value.to_string(),
+        Encoding::Address => format!("{value:#010x}"),
+        Encoding::SignedChar | Encoding::UnsignedChar => {
+            let number = match encoding {
+                Encoding::SignedChar => sign_extend(value, size).to_string(),
+                _ => value.to_string(),
+            };
+
+            match value as u8 {
+                byte if size == 1 && (byte.is_ascii_graphic() || byte == b' ') => {
+                    format!("{} '{}'", number, byte as char)
+                }
+                _ => number,
+            }
+        }
+    }
+}
+
+fn parse_integer(text: &str) -> Option<u64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return u64::from_str_radix(hex, 16).ok();
+    }
+
+    if let Some(negative) = text.strip_prefix('-') {
+        return negative
+            .parse::<i64>()
+            .ok()
+            .map(|value| value.wrapping_neg() as u64);
+    }
+
+    text.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+    const DW_CHILDREN_YES: u8 = 1;
+    const DW_CHILDREN_NO: u8 = 0;
+
+    /// Abbreviation codes of the test unit
+    const CU: u8 = 1;
+    const BASE: u8 = 2;
+    const VARIABLE: u8 = 3;
+    const STRUCT: u8 = 4;
+    const MEMBER: u8 = 5;
+    const ARRAY: u8 = 6;
+    const SUBRANGE: u8 = 7;
+    const DECLARATION: u8 = 8;
+    const DEFINITION: u8 = 9;
+
+    fn abbreviations() -> Vec<u8> {
+        let entry = |code: u8, tag: u64, children: u8, attributes: &[(u64, u64)]| {
+            let mut bytes = vec![code, tag as u8, children];
+            for &(name, form) in attributes {
+                bytes.extend([name as u8, form as u8]);
+            }
+            bytes.extend([0, 0]);
+            bytes
+        };
+
+        let name = (DW_AT_NAME, DW_FORM_STRING);
+        let ty = (DW_AT_TYPE, DW_FORM_REF4);
+        let location = (DW_AT_LOCATION, DW_FORM_EXPRLOC);
+        let size = (DW_AT_BYTE_SIZE, DW_FORM_DATA1);
+
+        let mut bytes = Vec::new();
+        bytes.extend(entry(
+            CU,
+            DW_TAG_COMPILE_UNIT as u64,
+            DW_CHILDREN_YES,
+            &[name],
+        ));
+        bytes.extend(entry(
+            BASE,
+            DW_TAG_BASE_TYPE,
+            DW_CHILDREN_NO,
+            &[name, (DW_AT_ENCODING, DW_FORM_DATA1), size],
+        ));
+        bytes.extend(entry(
+            VARIABLE,
+            DW_TAG_VARIABLE,
+            DW_CHILDREN_NO,
+            &[name, ty, location],
+        ));
+        bytes.extend(entry(
+            STRUCT,
+            DW_TAG_STRUCTURE_TYPE,
+            DW_CHILDREN_YES,
+            &[name, size],
+        ));
+        bytes.extend(entry(
+            MEMBER,
+            DW_TAG_MEMBER,
+            DW_CHILDREN_NO,
+            &[name, ty, (DW_AT_DATA_MEMBER_LOCATION, DW_FORM_DATA1)],
+        ));
+        bytes.extend(entry(ARRAY, DW_TAG_ARRAY_TYPE, DW_CHILDREN_YES, &[ty]));
+        bytes.extend(entry(
+            SUBRANGE,
+            DW_TAG_SUBRANGE_TYPE,
+            DW_CHILDREN_NO,
+            &[(DW_AT_UPPER_BOUND, DW_FORM_DATA1)],
+        ));
+        bytes.extend(entry(
+            DECLARATION,
+            DW_TAG_VARIABLE,
+            DW_CHILDREN_NO,
+            &[(DW_AT_NAME, DW_FORM_STRP), ty],
+        ));
+        bytes.extend(entry(
+            DEFINITION,
+            DW_TAG_VARIABLE,
+            DW_CHILDREN_NO,
+            &[(DW_AT_SPECIFICATION, DW_FORM_REF4), location],
+        ));
+        bytes.push(0);
+        bytes
+    }
+
+    fn reference(info: &mut Vec<u8>, offset: usize) {
+        info.extend((offset as u32).to_le_bytes());
+    }
+
+    fn location(info: &mut Vec<u8>, address: u32) {
+        info.extend([5, DW_OP_ADDR]);
+        info.extend(address.to_le_bytes());
+    }
+
+    /// A DWARF 4 unit of
+    ///
+    /// ```c
+    /// int counter;
+    /// struct point { int x; int y; } origin;
+    /// char name[8];
+    /// extern char flag;
+    /// char flag;
+    /// ```
+    fn debug_info() -> Vec<u8> {
+        let mut info = vec![0; 4];
+        info.extend(4u16.to_le_bytes());
+        info.extend(0u32.to_le_bytes());
+        info.push(4);
+
+        info.push(CU);
+        info.extend(b"test.c\0");
+
+        let int = info.len();
+        info.push(BASE);
+        info.extend(b"int\0");
+        info.extend([DW_ATE_SIGNED as u8, 4]);
+
+        let byte = info.len();
+        info.push(BASE);
+        info.extend(b"char\0");
+        info.extend([DW_ATE_UNSIGNED_CHAR as u8, 1]);
+
+        let point = info.len();
+        info.push(STRUCT);
+        info.extend(b"point\0");
+        info.push(8);
+        for (name, offset) in [(b"x\0", 0), (b"y\0", 4)] {
+            info.push(MEMBER);
+            info.extend(name);
+            reference(&mut info, int);
+            info.push(offset);
+        }
+        info.push(0);
+
+        let name = info.len();
+        info.push(ARRAY);
+        reference(&mut info, byte);
+        info.extend([SUBRANGE, 7]);
+        info.push(0);
+
+        for (variable, ty, address) in [
+            (&b"counter\0"[..], int, 0x2000_0000),
+            (&b"origin\0"[..], point, 0x2000_0010),
+            (&b"name\0"[..], name, 0x2000_0020),
+        ] {
+            info.push(VARIABLE);
+            info.extend(variable);
+            reference(&mut info, ty);
+            location(&mut info, address);
+        }
+
+        let declaration = info.len();
+        info.push(DECLARATION);
+        info.extend(0u32.to_le_bytes());
+        reference(&mut info, byte);
+
+        info.push(DEFINITION);
+        reference(&mut info, declaration);
+        location(&mut info, 0x2000_0030);
+
+        info.push(0);
+
+        let length = (info.len() - 4) as u32;
+        info[..4].copy_from_slice(&length.to_le_bytes());
+        info
+    }
+
+    fn parse() -> DebugInfo {
+        let info = debug_info();
+        let abbrev = abbreviations();
+
+        DebugInfo::from_sections(&Sections {
+            info: &info,
+            abbrev: &abbrev,
+            str: b"flag\0",
+            line_str: &[],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_variables() {
+        let debug_info = parse();
+
+        let names: Vec<&str> = debug_info
+            .variables
+            .iter()
+            .map(|variable| variable.name.as_str())
+            .collect();
+        assert_eq!(names, ["counter", "flag", "name", "origin"]);
+
+        let origin = debug_info.variable("origin").unwrap();
+        assert_eq!(origin.address, 0x2000_0010);
+        assert_eq!(debug_info.size_of(origin.ty), Some(8));
+        assert_eq!(debug_info.type_name(origin.ty), "struct point");
+
+        let name = debug_info.variable("name").unwrap();
+        assert_eq!(debug_info.size_of(name.ty), Some(8));
+        assert_eq!(debug_info.type_name(name.ty), "char[8]");
+
+        let flag = debug_info.variable("flag").unwrap();
+        assert_eq!(flag.address, 0x2000_0030);
+        assert_eq!(debug_info.type_name(flag.ty), "char");
+
+        let fields = debug_info.fields(origin.ty);
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].name, "y");
+        assert_eq!(fields[1].offset, 4);
+        assert!(debug_info.fields(name.ty).is_empty());
+    }
+
+    #[test]
+    fn test_format_and_encode() {
+        let debug_info = parse();
+        let ty = |name: &str| debug_info.variable(name).unwrap().ty;
+
+        assert_eq!(
+            debug_info.format(ty("counter"), &(-5i32).to_le_bytes()),
+            "-5"
+        );
+        assert_eq!(
+            debug_info.format(ty("origin"), &[1, 0, 0, 0, 2, 0, 0, 0]),
+            "{ x = 1, y = 2 }"
+        );
+        assert_eq!(debug_info.format(ty("name"), b"hi\0garbage"), "\"hi\"");
+        assert_eq!(debug_info.format(ty("flag"), b"A"), "65 'A'");
+        assert_eq!(debug_info.format(ty("counter"), &[1, 2]), "?");
+
+        assert_eq!(
+            debug_info.encode(ty("counter"), "-2"),
+            Some(vec![0xfe, 0xff, 0xff, 0xff])
+        );
+        assert_eq!(
+            debug_info.encode(ty("counter"), "0x10"),
+            Some(vec![0x10, 0, 0, 0])
+        );
+        assert_eq!(
+            debug_info.encode(ty("name"), "\"a long name\""),
+            Some(b"a long \0".to_vec())
+        );
+        assert!(debug_info.is_editable(ty("name")));
+        assert!(!debug_info.is_editable(ty("origin")));
+        assert_eq!(debug_info.encode(ty("origin"), "1"), None);
+        assert_eq!(debug_info.encode(ty("counter"), "abc"), None);
+    }
+}
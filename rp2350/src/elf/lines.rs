@@ -0,0 +1,409 @@
+/**
+ * @file elf/lines.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Line number programs of DWARF 2 to 5, the source line of every instruction
+ */
+use super::dwarf::{read_value, Sections, Unit, Value};
+use super::{Elf, ElfError, Reader};
+use std::collections::HashMap;
+use std::ops::Range;
+
+const DW_LNS_COPY: u8 = 0x01;
+const DW_LNS_ADVANCE_PC: u8 = 0x02;
+const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+const DW_LNS_SET_FILE: u8 = 0x04;
+const DW_LNS_CONST_ADD_PC: u8 = 0x08;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 0x09;
+
+const DW_LNE_END_SEQUENCE: u8 = 0x01;
+const DW_LNE_SET_ADDRESS: u8 = 0x02;
+
+const DW_LNCT_PATH: u64 = 0x1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 0x2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u32,
+    /// Index in [`LineTable::files`]
+    pub file: usize,
+    pub line: u32,
+    /// First address after a sequence of instructions, it has no line
+    pub end_sequence: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineTable {
+    /// Paths of the source files, joined with their directory when it is known
+    pub files: Vec<String>,
+    /// Sorted by address
+    pub rows: Vec<LineRow>,
+}
+
+/// Directory and file entries of a DWARF 5 header, each described by its own format
+fn entries<'a>(
+    reader: &mut Reader<'a>,
+    unit: &Unit,
+    sections: &Sections<'a>,
+) -> Option<Vec<(Option<&'a str>, u64)>> {
+    let formats: Vec<(u64, u64)> = (0..reader.u8()?)
+        .map(|_| Some((reader.uleb()?, reader.uleb()?)))
+        .collect::<Option<_>>()?;
+
+    (0..reader.uleb()?)
+        .map(|_| {
+            let mut entry = (None, 0);
+
+            for &(content, form) in formats.iter() {
+                match (content, read_value(reader, form, 0, unit, sections)?) {
+                    (DW_LNCT_PATH, Value::Str(path)) => entry.0 = Some(path),
+                    (DW_LNCT_DIRECTORY_INDEX, value) => entry.1 = value.unsigned()?,
+                    _ => {}
+                }
+            }
+
+            Some(entry)
+        })
+        .collect()
+}
+
+fn join(directory: Option<&str>, name: &str) -> String {
+    match directory {
+        Some(directory) if !directory.is_empty() && !name.starts_with('/') => {
+            format!("{}/{name}", directory.trim_end_matches('/'))
+        }
+        _ => name.to_string(),
+    }
+}
+
+impl LineTable {
+    pub fn parse(elf: &Elf) -> Result<Self, ElfError> {
+        let sections = Sections {
+            info: &[],
+            abbrev: &[],
+            str: elf.section_data(".debug_str").unwrap_or_default(),
+            line_str: elf.section_data(".debug_line_str").unwrap_or_default(),
+        };
+
+        Self::from_sections(
+            elf.section_data(".debug_line").unwrap_or_default(),
+            &sections,
+        )
+    }
+
+    fn from_sections<'a>(data: &'a [u8], sections: &Sections<'a>) -> Result<Self, ElfError> {
+        let mut table = Self::default();
+        // the headers repeat the same files, they are kept once
+        let mut paths = HashMap::new();
+        let mut reader = Reader::new(data);
+
+        while !reader.is_empty() {
+            let length = reader.u32().ok_or(ElfError::Malformed("line table"))?;
+
+            if length == 0xffff_ffff {
+                // 64 bit DWARF
+                return Err(ElfError::Unsupported);
+            }
+
+            let end = reader.pos + length as usize;
+            let unit = Reader::at(data.get(..end).unwrap_or_default(), reader.pos);
+
+            table
+                .parse_unit(unit, sections, &mut paths)
+                .ok_or(ElfError::Malformed("line table"))?;
+
+            reader = Reader::at(data, end);
+        }
+
+        // an end of sequence before the start of another one at the same address
+        table
+            .rows
+            .sort_by_key(|row| (row.address, !row.end_sequence));
+        Ok(table)
+    }
+
+    fn parse_unit<'a>(
+        &mut self,
+        mut reader: Reader<'a>,
+        sections: &Sections<'a>,
+        paths: &mut HashMap<String, usize>,
+    ) -> Option<()> {
+        let version = reader.u16()?;
+
+        let address_size = match version {
+            2..=4 => 4,
+            5 => {
+                let address_size = reader.u8()?;
+                reader.u8()?; // segment selector size
+                address_size
+            }
+            _ => return None,
+        };
+
+        let header_length = reader.u32()? as usize;
+        let program = reader.pos + header_length;
+
+        let min_instruction_length = reader.u8()? as u32;
+        if version >= 4 {
+            reader.u8()?; // maximum operations per instruction, 1 without VLIW
+        }
+        reader.u8()?; // default of is_stmt, every row is kept
+        let line_base = reader.u8()? as i8 as i64;
+        let line_range = reader.u8()?;
+        let opcode_base = reader.u8()?;
+        let lengths = reader.bytes(opcode_base.checked_sub(1)? as usize)?;
+
+        if line_range == 0 {
+            return None;
+        }
+
+        let unit = Unit {
+            offset: 0,
+            version,
+            address_size,
+        };
+
+        // indices of the files of the header in `self.files`
+        let files: Vec<usize> = match version {
+            5 => {
+                let directories = entries(&mut reader, &unit, sections)?;
+                let directory = |index: u64| directories.get(index as usize).and_then(|d| d.0);
+                let base = directory(0);
+
+                entries(&mut reader, &unit, sections)?
+                    .into_iter()
+                    .map(|(name, index)| {
+                        // the other directories are relative to the one of the compilation
+                        let directory = match index {
+                            0 => base.map(str::to_string),
+                            _ => directory(index).map(|directory| join(base, directory)),
+                        };
+
+                        let path = join(directory.as_deref(), name.unwrap_or_default());
+                        self.file(path, paths)
+                    })
+                    .collect()
+            }
+            _ => {
+                let mut directories = Vec::new();
+                while let Some(directory) = reader.cstr().filter(|d| !d.is_empty()) {
+                    directories.push(directory);
+                }
+
+                let mut files = Vec::new();
+                while let Some(name) = reader.cstr().filter(|name| !name.is_empty()) {
+                    let index = reader.uleb()? as usize;
+                    reader.uleb()?; // modification time
+                    reader.uleb()?; // length
+
+                    // 0 is the directory of the compilation, which is not in the header
+                    let directory = index.checked_sub(1).and_then(|i| directories.get(i));
+                    files.push(self.file(join(directory.copied(), name), paths));
+                }
+
+                files
+            }
+        };
+
+        // the files are counted from 1 before DWARF 5
+        let first_file = if version >= 5 { 0 } else { 1 };
+        let reset = (0u64, 1usize, 1i64);
+        let (mut address, mut file, mut line) = reset;
+        reader.pos = program;
+
+        let push = |rows: &mut Vec<LineRow>, address: u64, file: usize, line: i64, end: bool| {
+            let Some(&file) = files.get(file.wrapping_sub(first_file)) else {
+                return;
+            };
+
+            if line > 0 || end {
+                rows.push(LineRow {
+                    address: address as u32,
+                    file,
+                    line: line.max(0) as u32,
+                    end_sequence: end,
+                });
+            }
+        };
+
+        while !reader.is_empty() {
+            let opcode = reader.u8()?;
+
+            match opcode {
+                _ if opcode >= opcode_base => {
+                    let adjusted = opcode - opcode_base;
+                    address += (adjusted / line_range) as u64 * min_instruction_length as u64;
+                    line += line_base + (adjusted % line_range) as i64;
+                    push(&mut self.rows, address, file, line, false);
+                }
+                0 => {
+                    let length = reader.uleb()? as usize;
+                    let next = reader.pos + length;
+
+                    match reader.u8()? {
+                        DW_LNE_END_SEQUENCE => {
+                            push(&mut self.rows, address, file, line, true);
+                            (address, file, line) = reset;
+                        }
+                        DW_LNE_SET_ADDRESS => {
+                            address = reader.sized(length.checked_sub(1)? as u8)?;
+                        }
+                        // discriminators and files defined in the program
+                        _ => {}
+                    }
+
+                    reader.pos = next;
+                }
+                DW_LNS_COPY => push(&mut self.rows, address, file, line, false),
+                DW_LNS_ADVANCE_PC => {
+                    let advance = reader.uleb()?.wrapping_mul(min_instruction_length as u64);
+                    address = address.wrapping_add(advance);
+                }
+                DW_LNS_ADVANCE_LINE => line = line.wrapping_add(reader.sleb()?),
+                DW_LNS_SET_FILE => file = reader.uleb()? as usize,
+                DW_LNS_CONST_ADD_PC => {
+                    let adjusted = 255 - opcode_base;
+                    address += (adjusted / line_range) as u64 * min_instruction_length as u64;
+                }
+                DW_LNS_FIXED_ADVANCE_PC => address += reader.u16()? as u64,
+                // the columns, the flags and the ISA do not change the line
+                _ => {
+                    for _ in 0..lengths[opcode as usize - 1] {
+                        reader.uleb()?;
+                    }
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    fn file(&mut self, path: String, paths: &mut HashMap<String, usize>) -> usize {
+        *paths.entry(path).or_insert_with_key(|path| {
+            self.files.push(path.clone());
+            self.files.len() - 1
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Source file and line of the instruction at an address
+    pub fn locate(&self, address: u32) -> Option<(&str, u32)> {
+        let index = self.rows.partition_point(|row| row.address <= address);
+        let row = self.rows.get(index.checked_sub(1)?)?;
+
+        match row.end_sequence {
+            true => None,
+            false => Some((self.files[row.file].as_str(), row.line)),
+        }
+    }
+
+    /// Addresses of every row until the next one, with their file and line
+    pub fn ranges(&self) -> impl Iterator<Item = (Range<u32>, usize, u32)> + '_ {
+        self.rows.windows(2).filter_map(|rows| match rows[0] {
+            row if row.end_sequence || row.address == rows[1].address => None,
+            row => Some((row.address..rows[1].address, row.file, row.line)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPCODE_BASE: u8 = 13;
+    const STANDARD_LENGTHS: [u8; 12] = [0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1];
+
+    /// Unit length and header length filled in
+    fn unit(version: u16, header: &[u8], program: &[u8]) -> Vec<u8> {
+        let mut unit = vec![0; 4];
+        unit.extend_from_slice(&version.to_le_bytes());
+
+        if version >= 5 {
+            unit.extend_from_slice(&[4, 0]);
+        }
+
+        let mut fields = vec![2, 1, 1, (-5i8) as u8, 14, OPCODE_BASE];
+        fields.extend_from_slice(&STANDARD_LENGTHS);
+        fields.extend_from_slice(header);
+
+        unit.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+        unit.extend_from_slice(&fields);
+        unit.extend_from_slice(program);
+
+        let length = (unit.len() - 4) as u32;
+        unit[..4].copy_from_slice(&length.to_le_bytes());
+        unit
+    }
+
+    fn set_address(address: u32) -> Vec<u8> {
+        let mut bytes = vec![0, 5, DW_LNE_SET_ADDRESS];
+        bytes.extend_from_slice(&address.to_le_bytes());
+        bytes
+    }
+
+    fn parse(data: &[u8], line_str: &[u8]) -> LineTable {
+        let sections = Sections {
+            info: &[],
+            abbrev: &[],
+            str: &[],
+            line_str,
+        };
+
+        LineTable::from_sections(data, &sections).unwrap()
+    }
+
+    #[test]
+    fn test_dwarf4() {
+        let header = b"src\0\0main.c\0\x01\0\0\0";
+
+        let mut program = set_address(0x1000_0000);
+        program.extend_from_slice(&[DW_LNS_ADVANCE_LINE, 9, DW_LNS_COPY]);
+        // 4 bytes and a line further
+        program.push(OPCODE_BASE + 6 + 14 * 2);
+        program.extend_from_slice(&[DW_LNS_ADVANCE_PC, 4, 0, 1, DW_LNE_END_SEQUENCE]);
+
+        let table = parse(&unit(4, header, &program), &[]);
+
+        assert_eq!(table.files, ["src/main.c"]);
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.locate(0x1000_0002), Some(("src/main.c", 10)));
+        assert_eq!(table.locate(0x1000_0006), Some(("src/main.c", 11)));
+        assert_eq!(table.locate(0x1000_000c), None);
+        assert_eq!(table.locate(0x0fff_fffe), None);
+
+        let ranges: Vec<_> = table.ranges().collect();
+        assert_eq!(
+            ranges,
+            [
+                (0x1000_0000..0x1000_0004, 0, 10),
+                (0x1000_0004..0x1000_000c, 0, 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dwarf5() {
+        const DW_FORM_STRING: u8 = 0x08;
+        const DW_FORM_UDATA: u8 = 0x0f;
+        const DW_FORM_LINE_STRP: u8 = 0x1f;
+
+        let mut header = vec![1, DW_LNCT_PATH as u8, DW_FORM_LINE_STRP, 2];
+        header.extend_from_slice(&[0, 0, 0, 0, 7, 0, 0, 0]);
+        header.extend_from_slice(&[2, DW_LNCT_PATH as u8, DW_FORM_STRING]);
+        header.extend_from_slice(&[DW_LNCT_DIRECTORY_INDEX as u8, DW_FORM_UDATA, 2]);
+        header.extend_from_slice(b"main.c\0\x00util.c\0\x01");
+
+        let mut program = set_address(0x2000_0000);
+        program.extend_from_slice(&[DW_LNS_SET_FILE, 1, DW_LNS_ADVANCE_LINE, 4, DW_LNS_COPY]);
+        program.extend_from_slice(&[DW_LNS_ADVANCE_PC, 2, 0, 1, DW_LNE_END_SEQUENCE]);
+
+        let table = parse(&unit(5, &header, &program), b"/build\0lib\0");
+
+        assert_eq!(table.files, ["/build/main.c", "/build/lib/util.c"]);
+        assert_eq!(table.locate(0x2000_0002), Some(("/build/lib/util.c", 5)));
+        assert_eq!(table.locate(0x2000_0004), None);
+    }
+}
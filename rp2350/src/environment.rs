@@ -0,0 +1,194 @@
+/**
+ * @file environment.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Ambient conditions around the chip, scriptable as scenarios over the simulated time
+ */
+use crate::clock::Clock;
+use crate::common::MHZ;
+
+/// Die temperature the characterisation data of the datasheet refers to
+pub const REFERENCE_TEMPERATURE: f64 = 27.0;
+
+/// Typical ROSC frequency at the reference temperature with the reset settings
+pub const ROSC_NOMINAL_FREQUENCY: f64 = 11.0 * MHZ as f64;
+
+/// Relative change of the ROSC frequency per degree Celsius,
+/// the inverter chain gets slower as the die warms up
+pub const ROSC_TEMPERATURE_COEFFICIENT: f64 = -0.0015;
+
+/// Voltage of the temperature sensor at the reference temperature
+pub const TEMPERATURE_SENSOR_VBE: f64 = 0.706;
+
+/// Slope of the temperature sensor in V/°C
+pub const TEMPERATURE_SENSOR_SLOPE: f64 = -0.001721;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment {
+    duration_us: u64,
+    temperature: f64,
+}
+
+/// Piecewise linear temperature profile, built from ramps and holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalScenario {
+    start: f64,
+    segments: Vec<Segment>,
+}
+
+impl ThermalScenario {
+    pub fn new(start_temperature: f64) -> Self {
+        Self {
+            start: start_temperature,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Change the temperature linearly over the given time
+    pub fn ramp_to(mut self, temperature: f64, duration_us: u64) -> Self {
+        self.segments.push(Segment {
+            duration_us,
+            temperature,
+        });
+        self
+    }
+
+    /// Keep the current temperature for the given time
+    pub fn hold(self, duration_us: u64) -> Self {
+        let temperature = self.end_temperature();
+        self.ramp_to(temperature, duration_us)
+    }
+
+    /// Jump to a temperature immediately
+    pub fn step_to(self, temperature: f64) -> Self {
+        self.ramp_to(temperature, 0)
+    }
+
+    pub fn duration_us(&self) -> u64 {
+        self.segments.iter().map(|v| v.duration_us).sum()
+    }
+
+    pub fn end_temperature(&self) -> f64 {
+        self.segments.last().map_or(self.start, |v| v.temperature)
+    }
+
+    /// Temperature at the given time since the start of the scenario,
+    /// the last temperature is kept once the scenario is over
+    pub fn temperature_at(&self, time_us: u64) -> f64 {
+        let mut from = self.start;
+        let mut elapsed = time_us;
+
+        for segment in self.segments.iter() {
+            if elapsed < segment.duration_us {
+                let progress = elapsed as f64 / segment.duration_us as f64;
+                return from + (segment.temperature - from) * progress;
+            }
+
+            elapsed -= segment.duration_us;
+            from = segment.temperature;
+        }
+
+        from
+    }
+}
+
+/// Conditions the chip is running in, shared by the peripherals depending on them
+#[derive(Debug, Clone)]
+pub struct Environment {
+    /// Temperature used while no scenario is running
+    pub ambient_temperature: f64,
+    /// The running scenario with the tick it was started at
+    scenario: Option<(u64, ThermalScenario)>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            ambient_temperature: REFERENCE_TEMPERATURE,
+            scenario: None,
+        }
+    }
+}
+
+impl Environment {
+    /// Start a scenario from the current simulated time, replacing the running one
+    pub fn run_scenario(&mut self, scenario: ThermalScenario, clock: &Clock) {
+        self.scenario = Some((*clock.ticks.borrow(), scenario));
+    }
+
+    /// Stop the running scenario, the temperature goes back to the ambient temperature
+    pub fn stop_scenario(&mut self) {
+        self.scenario = None;
+    }
+
+    pub fn scenario(&self) -> Option<&ThermalScenario> {
+        self.scenario.as_ref().map(|(_, scenario)| scenario)
+    }
+
+    /// Time in microseconds since the running scenario was started
+    pub fn scenario_elapsed_us(&self, clock: &Clock) -> Option<u64> {
+        let (started_at, _) = self.scenario.as_ref()?;
+        let elapsed = clock.ticks.borrow().saturating_sub(*started_at);
+        Some(elapsed * 1_000_000 / clock.clk_sys())
+    }
+
+    /// Die temperature in °C at the current simulated time
+    pub fn temperature(&self, clock: &Clock) -> f64 {
+        match (&self.scenario, self.scenario_elapsed_us(clock)) {
+            (Some((_, scenario)), Some(elapsed)) => scenario.temperature_at(elapsed),
+            _ => self.ambient_temperature,
+        }
+    }
+
+    /// Output voltage of the on-chip temperature sensor
+    pub fn temperature_sensor_voltage(&self, clock: &Clock) -> f64 {
+        let delta = self.temperature(clock) - REFERENCE_TEMPERATURE;
+        TEMPERATURE_SENSOR_VBE + delta * TEMPERATURE_SENSOR_SLOPE
+    }
+
+    /// Frequency of the ring oscillator in Hz, it drifts with the temperature
+    pub fn rosc_frequency(&self, clock: &Clock) -> f64 {
+        let delta = self.temperature(clock) - REFERENCE_TEMPERATURE;
+        ROSC_NOMINAL_FREQUENCY * (1.0 + delta * ROSC_TEMPERATURE_COEFFICIENT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thermal_scenario() {
+        // Heat up from 25°C to 85°C in 10ms, stay there for 5ms, then drop back instantly
+        let scenario = ThermalScenario::new(25.0)
+            .ramp_to(85.0, 10_000)
+            .hold(5_000)
+            .step_to(25.0);
+
+        assert_eq!(scenario.duration_us(), 15_000);
+        assert_eq!(scenario.temperature_at(0), 25.0);
+        assert_eq!(scenario.temperature_at(5_000), 55.0);
+        assert_eq!(scenario.temperature_at(12_000), 85.0);
+        assert_eq!(scenario.temperature_at(20_000), 25.0);
+    }
+
+    #[test]
+    fn test_scenario_follows_the_clock() {
+        let clock = Clock::new();
+        let mut environment = Environment::default();
+        assert_eq!(environment.temperature(&clock), REFERENCE_TEMPERATURE);
+
+        environment.run_scenario(ThermalScenario::new(27.0).ramp_to(127.0, 100), &clock);
+
+        // 150 ticks of clk_sys per microsecond
+        for _ in 0..150 * 50 {
+            clock.tick();
+        }
+
+        assert_eq!(environment.temperature(&clock), 77.0);
+        assert!(environment.rosc_frequency(&clock) < ROSC_NOMINAL_FREQUENCY);
+
+        environment.stop_scenario();
+        assert_eq!(environment.temperature(&clock), REFERENCE_TEMPERATURE);
+    }
+}
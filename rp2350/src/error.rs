@@ -4,19 +4,115 @@
  * @date 02/01/2025
  * @brief Error handling module
  */
+use crate::bus::BusError;
+use crate::common::Requestor;
+use crate::ihex::IhexError;
+use crate::peripherals::PeripheralError;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, Error)]
+/// Errors returned by the public API of the simulator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
 pub enum Error {
-    #[error("The file is too large for the operation")]
-    FileTooLarge,
+    #[error("Bus error at {address:#010X} from {requestor:?}: {source}")]
+    Bus {
+        address: u32,
+        requestor: Requestor,
+        source: BusError,
+    },
 
-    #[error("Invalid address: {0:#X}")]
+    #[error("Peripheral error at {address:#010X} from {requestor:?}: {source}")]
+    Peripheral {
+        address: u32,
+        requestor: Requestor,
+        source: PeripheralError,
+    },
+
+    #[error(transparent)]
+    LoadImage(#[from] LoadImageError),
+
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error("The peripherals were accessed since the snapshot, it only holds the program")]
+    StaleSnapshot,
+}
+
+/// Failures while putting a program image into the memory of the chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum LoadImageError {
+    #[error("The image of {size} bytes does not fit in the {capacity} bytes of flash")]
+    TooLarge { size: usize, capacity: usize },
+
+    #[error("There is no loadable memory at {0:#010X}")]
     InvalidAddress(u32),
 
-    #[error("Invalid target address")]
-    MemoryError(#[from] crate::memory::MemoryOutOfBoundsError),
+    #[error("{size} bytes at {address:#010X} go past the end of the memory")]
+    OutOfBounds { address: u32, size: usize },
+
+    #[error("Invalid UF2 file: {0}")]
+    Uf2(#[from] uf2::Error),
+
+    #[error("Rejected UF2 file: {0}")]
+    Uf2Rejected(uf2::Diagnostic),
+
+    #[error("Invalid Intel HEX file: {0}")]
+    IntelHex(#[from] IhexError),
+}
+
+/// Invalid settings given to the simulator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    #[error("GPIO{0} does not exist, there are {count} GPIO pins", count = crate::gpio::NUM_GPIO)]
+    InvalidGpioPin(u8),
+
+    #[error("ADC channel {0} does not exist, there are 4 external ADC inputs")]
+    InvalidAdcChannel(usize),
+
+    #[error("I2C{0} does not exist, there are 2 I2C blocks")]
+    InvalidI2c(u8),
+
+    #[error("{0:#04X} is a reserved I2C address")]
+    InvalidI2cAddress(u8),
+
+    #[error("A device is already at the I2C address {0:#04X}")]
+    I2cAddressInUse(u8),
+
+    #[error("SPI{0} does not exist, there are 2 SPI blocks")]
+    InvalidSpi(u8),
+
+    #[error("A device already has GPIO{0} as its SPI chip select")]
+    SpiChipSelectInUse(u8),
+}
+
+impl Error {
+    /// Error of a bus access, a peripheral failing keeps its own cause
+    pub fn from_bus(address: u32, requestor: Requestor, error: BusError) -> Self {
+        match error {
+            BusError::Peripheral(source) => Self::Peripheral {
+                address,
+                requestor,
+                source,
+            },
+            source => Self::Bus {
+                address,
+                requestor,
+                source,
+            },
+        }
+    }
+}
+
+impl From<uf2::Error> for Error {
+    fn from(error: uf2::Error) -> Self {
+        Self::LoadImage(error.into())
+    }
+}
 
-    #[error("Invalid UF2 file")]
-    UF2Error(#[from] uf2::Error),
+impl From<IhexError> for Error {
+    fn from(error: IhexError) -> Self {
+        Self::LoadImage(error.into())
+    }
 }
@@ -7,8 +7,10 @@
 //
 pub mod drive_strength;
 pub mod function_select;
+pub mod lint;
 pub mod r#override;
 pub mod pin;
+pub mod spi_bus;
 pub mod state;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -16,18 +18,31 @@ use std::rc::Rc;
 
 use crate::clock::Clock;
 use crate::interrupts::Interrupts;
-use crate::peripherals::Pwm;
+use crate::peripherals::{Pwm, UartLink};
 use crate::utils::extract_bit;
 use crate::InspectorRef;
 
 pub use drive_strength::*;
 pub use function_select::*;
+pub use lint::*;
 pub use pin::*;
 pub use r#override::*;
+pub use spi_bus::SpiBusDevice;
 pub use state::*;
 
 type PinIndex = u8;
 
+/// Bank 0 GPIOs of the QFN-80 package, the QFN-60 one only bonds out the first 30
+pub const NUM_GPIO: usize = 48;
+
+// Pins outside of bank 0 in the GPIO_HI registers of the SIO, above GPIO32..47
+pub const GPIO_HI_USB_DP: u32 = 24;
+pub const GPIO_HI_USB_DM: u32 = 25;
+pub const GPIO_HI_QSPI_SCK: u32 = 26;
+pub const GPIO_HI_QSPI_CSN: u32 = 27;
+pub const GPIO_HI_QSPI_SD0: u32 = 28;
+const GPIO_HI_BANK0_MASK: u32 = 0xFFFF;
+
 const IRQ_LEVEL_LOW: u8 = 1 << 0;
 const IRQ_LEVEL_HIGH: u8 = 1 << 1;
 const IRQ_EDGE_LOW: u8 = 1 << 2;
@@ -42,21 +57,31 @@ pub struct GpioPinOutputOption {
 #[derive(Debug, Clone, Default)]
 pub struct GpioPinOutputs {
     pub outputs: HashMap<FunctionSelect, GpioPinOutputOption>,
-    pub sio_output_enable: u32,
-    pub sio_output_value: u32,
+    /// GPIO0..31 in the lower half, the layout of the GPIO_HI registers in the upper one
+    pub sio_output_enable: u64,
+    pub sio_output_value: u64,
+    /// Output enables of the PIO blocks, indexed by the GPIO number
+    pub pio_output_enable: [u32; 3],
+    /// Output values of the PIO blocks, indexed by the GPIO number
+    pub pio_output_value: [u32; 3],
 }
 
 pub struct GpioController {
-    pub pins: [GpioPin; 30],
+    pub pins: [GpioPin; NUM_GPIO],
     interrupts: Rc<RefCell<Interrupts>>,
     outputs: GpioPinOutputs,
-    // pub qspi: [GpioPin; 4],
+    /// BOOTSEL button of the board, it is outside of the chip so it survives a reset
+    bootsel: bool,
+    /// Board wiring between the UARTs, it survives a reset as well
+    uart_link: UartLink,
+    /// Devices on the SPI buses, outside of the chip too
+    spi_devices: Vec<SpiBusDevice>,
 }
 
 impl Default for GpioController {
     fn default() -> Self {
         let outputs = GpioPinOutputs::default();
-        let pins: [GpioPin; 30] = (0u8..30)
+        let pins: [GpioPin; NUM_GPIO] = (0u8..NUM_GPIO as u8)
             .map(|i| GpioPin::new(i))
             .collect::<Vec<GpioPin>>()
             .try_into()
@@ -66,6 +91,9 @@ impl Default for GpioController {
             pins,
             outputs,
             interrupts: Default::default(),
+            bootsel: false,
+            uart_link: UartLink::None,
+            spi_devices: Vec::new(),
         }
     }
 }
@@ -79,8 +107,50 @@ impl GpioController {
     }
 
     pub fn reset(&mut self) {
-        let Self { interrupts, .. } = core::mem::take(self);
+        let Self {
+            interrupts,
+            bootsel,
+            uart_link,
+            spi_devices,
+            ..
+        } = core::mem::take(self);
         self.interrupts = interrupts;
+        self.bootsel = bootsel;
+        self.uart_link = uart_link;
+        self.spi_devices = spi_devices;
+        // the chip selects are released along with the pins
+        self.update_chip_selects();
+    }
+
+    /// Press or release the BOOTSEL button, which pulls the QSPI chip select low
+    pub fn set_bootsel(&mut self, pressed: bool) {
+        self.bootsel = pressed;
+    }
+
+    pub fn is_bootsel_pressed(&self) -> bool {
+        self.bootsel
+    }
+
+    /// Input value of the QSPI chip select, pulled up by the flash unless BOOTSEL is held
+    pub fn qspi_csn_input(&self) -> bool {
+        !self.bootsel
+    }
+
+    pub fn set_uart_link(&mut self, link: UartLink) {
+        self.uart_link = link;
+    }
+
+    pub fn uart_link(&self) -> UartLink {
+        self.uart_link
+    }
+
+    /// Output of a peripheral before the function select, whichever pin it is muxed on
+    pub fn peripheral_output(&self, funcsel: FunctionSelect) -> GpioPinOutputOption {
+        self.outputs
+            .outputs
+            .get(&funcsel)
+            .copied()
+            .unwrap_or_default()
     }
 
     pub fn get_pin(&self, index: u8) -> Option<&GpioPin> {
@@ -97,20 +167,34 @@ impl GpioController {
 
     fn raw_output(&self, funcsel: FunctionSelect, index: PinIndex) -> GpioPinOutputOption {
         if funcsel == FunctionSelect::SIO {
-            let enable = extract_bit(self.outputs.sio_output_enable, index as _) != 0;
-            let value = extract_bit(self.outputs.sio_output_value, index as _) != 0;
+            let enable = self.outputs.sio_output_enable >> index & 1 != 0;
+            let value = self.outputs.sio_output_value >> index & 1 != 0;
             return GpioPinOutputOption { enable, value };
         }
 
-        self.outputs
-            .outputs
-            .get(&funcsel)
-            .copied()
-            .unwrap_or_default()
+        if index >= 32 {
+            // TODO PIO GPIOBASE, the PIO outputs only cover GPIO0..31 for now
+            return self.peripheral_output(funcsel);
+        }
+
+        let pio = match funcsel {
+            FunctionSelect::PIO_0 => Some(0),
+            FunctionSelect::PIO_1 => Some(1),
+            FunctionSelect::PIO_2 => Some(2),
+            _ => None,
+        };
+
+        if let Some(pio) = pio {
+            let enable = extract_bit(self.outputs.pio_output_enable[pio], index as _) != 0;
+            let value = extract_bit(self.outputs.pio_output_value[pio], index as _) != 0;
+            return GpioPinOutputOption { enable, value };
+        }
+
+        self.peripheral_output(funcsel)
     }
 
     pub fn pin_status(&self, index: PinIndex) -> u32 {
-        assert!(index < 30);
+        assert!((index as usize) < NUM_GPIO);
         let ref pin = self.pins[index as usize];
         let funcsel = pin.func_sel();
         let raw_output = self.raw_output(funcsel, index);
@@ -119,8 +203,8 @@ impl GpioController {
 
         let irq_to_proc = if pin.interrupting() { 1 } else { 0 };
         let irq_from_pad = if pin.interrupt_status() != 0 { 1 } else { 0 };
-        let in_to_peripheral = pin.input_value() as u32;
-        let in_from_pad = pin.raw_input_value as u32;
+        let in_to_peripheral = self.input_value(index) as u32;
+        let in_from_pad = self.pad_level(index) as u32;
         let oe_to_pad = output_enable as u32;
         let oe_from_peripheral = raw_output.enable as u32;
         let out_to_pad = output_value as u32;
@@ -137,7 +221,7 @@ impl GpioController {
     }
 
     pub fn pin_state(&self, index: PinIndex) -> PinState {
-        assert!(index < 30);
+        assert!((index as usize) < NUM_GPIO);
         let ref pin = self.pins[index as usize];
         let funcsel = pin.func_sel();
         let raw_output = self.raw_output(funcsel, index);
@@ -163,16 +247,46 @@ impl GpioController {
         }
     }
 
+    /// Level on the pad, what the chip drives on it or else what comes from the outside
+    pub fn pad_level(&self, index: PinIndex) -> bool {
+        match self.pin_state(index) {
+            PinState::Output(value, _) => matches!(value, OutputState::High),
+            PinState::Input(_) => self.pins[index as usize].raw_input_value,
+        }
+    }
+
+    /// Input value of a pin as seen by the peripherals, after the input override
+    pub fn input_value(&self, index: PinIndex) -> bool {
+        self.get_pin(index)
+            .is_some_and(|pin| pin.in_override().apply_bool(self.pad_level(index)))
+    }
+
+    /// Feed the levels driven by the chip back to the inputs of the pads, to be read
+    /// back by GPIO_IN and to trigger the edge and level interrupts
+    fn update_pad_levels(&mut self) {
+        for index in 0..NUM_GPIO as PinIndex {
+            let level = self.pad_level(index);
+            let pin = &mut self.pins[index as usize];
+
+            if level != pin.previous_value {
+                pin.update_level(level);
+            }
+        }
+
+        self.update_chip_selects();
+        self.update_interrupt();
+    }
+
     pub fn set_pin_output(&mut self, funcsel: FunctionSelect, value: bool) {
         let entry = self.outputs.outputs.entry(funcsel).or_default();
         entry.value = value;
-        self.update_interrupt();
+        self.update_pad_levels();
     }
 
     pub fn set_pin_output_enable(&mut self, funcsel: FunctionSelect, value: bool) {
         let entry = self.outputs.outputs.entry(funcsel).or_default();
         entry.enable = value;
-        self.update_interrupt();
+        self.update_pad_levels();
     }
 
     pub fn update_pin_ctrl(&mut self, index: u8, value: u32) {
@@ -180,7 +294,7 @@ impl GpioController {
             pin.ctrl = value;
         }
 
-        self.update_interrupt();
+        self.update_pad_levels();
     }
 
     pub fn update_pin_pads(&mut self, index: u8, value: u32) {
@@ -188,7 +302,7 @@ impl GpioController {
             pin.pad = value;
         }
 
-        self.update_interrupt();
+        self.update_pad_levels();
     }
 
     pub fn update_pin_irq(&mut self, index: u8, value: u8) {
@@ -199,9 +313,50 @@ impl GpioController {
         self.update_interrupt();
     }
 
+    /// Outputs of the SIO for GPIO0..31
     pub fn update_sio(&mut self, enable: u32, value: u32) {
-        self.outputs.sio_output_enable = enable;
-        self.outputs.sio_output_value = value;
+        let high = 0xFFFF_FFFF_0000_0000;
+        self.outputs.sio_output_enable = (self.outputs.sio_output_enable & high) | enable as u64;
+        self.outputs.sio_output_value = (self.outputs.sio_output_value & high) | value as u64;
+        self.update_pad_levels();
+    }
+
+    /// Outputs of the SIO in the layout of the GPIO_HI registers
+    pub fn update_sio_hi(&mut self, enable: u32, value: u32) {
+        let low = 0x0000_0000_FFFF_FFFF;
+        self.outputs.sio_output_enable =
+            (self.outputs.sio_output_enable & low) | (enable as u64) << 32;
+        self.outputs.sio_output_value =
+            (self.outputs.sio_output_value & low) | (value as u64) << 32;
+        self.update_pad_levels();
+    }
+
+    /// Input value of GPIO0..31
+    pub fn sio_input(&self) -> u32 {
+        (0..32)
+            .rev()
+            .fold(0, |acc, index| (acc << 1) | self.input_value(index) as u32)
+    }
+
+    /// Input value in the layout of the GPIO_HI registers
+    pub fn sio_hi_input(&self) -> u32 {
+        let bank0 = (32..NUM_GPIO as PinIndex)
+            .rev()
+            .fold(0, |acc, index| (acc << 1) | self.input_value(index) as u32);
+
+        // The QSPI and USB pins read back what the SIO drives on them, nothing else
+        // drives them in the simulation beside the flash pulling up the chip select
+        let enable = (self.outputs.sio_output_enable >> 32) as u32 & !GPIO_HI_BANK0_MASK;
+        let value = (self.outputs.sio_output_value >> 32) as u32 & enable;
+        let idle = (self.qspi_csn_input() as u32) << GPIO_HI_QSPI_CSN;
+
+        bank0 | value | (idle & !enable)
+    }
+
+    pub fn update_pio(&mut self, pio: usize, enable: u32, value: u32) {
+        self.outputs.pio_output_enable[pio] = enable;
+        self.outputs.pio_output_value[pio] = value;
+        self.update_pad_levels();
     }
 
     pub fn update_interrupt(&self) {
@@ -226,12 +381,15 @@ pub(crate) fn update_pwm_b_pin(
         return;
     }
 
-    if pin_index > 15 {
-        // pwm channels is repeated for each 16 pins
-        pin_index -= 16;
-    }
-
-    let channel_idx = pin_index / 2;
+    let channel_idx = match pin_index {
+        // slices 8 to 11 of the QFN-80 package, repeated for each 8 pins
+        32.. => 8 + (pin_index - 32) % 8 / 2,
+        _ => {
+            // pwm channels is repeated for each 16 pins
+            pin_index %= 16;
+            pin_index / 2
+        }
+    };
 
     crate::peripherals::pwm::channel_b_update(
         pwm,
@@ -243,3 +401,53 @@ pub(crate) fn update_pwm_b_pin(
         inspector,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sio_hi_bank() {
+        let mut gpio = GpioController::default();
+        gpio.pins[40].ctrl = 5; // SIO
+        gpio.update_sio_hi(1 << 8 | 1 << GPIO_HI_USB_DP, 1 << 8 | 1 << GPIO_HI_USB_DP);
+
+        assert!(gpio.pin_state(40).is_high());
+        assert!(!gpio.pin_state(8).is_high());
+
+        // the low bank is left untouched
+        gpio.pins[8].ctrl = 5;
+        gpio.update_sio(1 << 8, 1 << 8);
+        assert!(gpio.pin_state(8).is_high());
+        assert!(gpio.pin_state(40).is_high());
+
+        // GPIO40 reads back the level it drives
+        gpio.pins[33].set_input(true);
+        assert_eq!(
+            gpio.sio_hi_input(),
+            1 << 1 | 1 << 8 | 1 << GPIO_HI_USB_DP | 1 << GPIO_HI_QSPI_CSN
+        );
+
+        gpio.set_bootsel(true);
+        assert_eq!(gpio.sio_hi_input() >> GPIO_HI_QSPI_CSN & 1, 0);
+    }
+
+    #[test]
+    fn test_sio_output_reaches_pad() {
+        let mut gpio = GpioController::default();
+        gpio.update_pin_ctrl(25, 5); // SIO
+        gpio.update_pin_pads(25, 1 << 6); // input enabled
+        gpio.pins[25].interrupt_mask = IRQ_EDGE_HIGH;
+
+        gpio.update_sio(1 << 25, 1 << 25);
+        assert!(gpio.pin_state(25).is_high());
+        assert_eq!(gpio.sio_input(), 1 << 25);
+        assert_eq!(gpio.pin_status(25) >> 17 & 1, 1); // in from pad
+        assert!(gpio.pins[25].interrupting());
+
+        // output disabled by the pad
+        gpio.update_pin_pads(25, 1 << 6 | 1 << 7);
+        assert!(!gpio.pin_state(25).is_high());
+        assert_eq!(gpio.sio_input(), 0);
+    }
+}
@@ -46,6 +46,14 @@ pub enum FunctionSelect {
     PWM6_B,
     PWM7_A,
     PWM7_B,
+    PWM8_A,
+    PWM8_B,
+    PWM9_A,
+    PWM9_B,
+    PWM10_A,
+    PWM10_B,
+    PWM11_A,
+    PWM11_B,
     SIO,
     PIO_0,
     PIO_1,
@@ -72,8 +80,9 @@ use FunctionSelect::*;
 
 /// GPIO functions for each pin
 /// As in the datasheet for the RP2350 section 1.2.3.
+/// GPIO 30 to 47 are only bonded out on the QFN-80 package (RP2350B)
 #[rustfmt::skip]
-pub const FUNCTION_SELECTS: [[FunctionSelect; 12]; 30] = [
+pub const FUNCTION_SELECTS: [[FunctionSelect; 12]; 48] = [
 /* GPIO 00 */ [None, SPI0_RX,  UART0_TX,  I2C0_SDA, PWM0_A, SIO, PIO_0, PIO_1, PIO_2, QMI_CS1n,     USB_OVCUR_DET, None],
 /* GPIO 01 */ [None, SPI0_CSn, UART0_RX,  I2C0_SCL, PWM0_B, SIO, PIO_0, PIO_1, PIO_2, TRACECKL,     USB_VBUS_DET,  None],
 /* GPIO 02 */ [None, SPI0_SCK, UART0_CTS, I2C1_SDA, PWM1_A, SIO, PIO_0, PIO_1, PIO_2, TRACEDATA0,   USB_VBUS_EN,   UART0_TX],
@@ -104,4 +113,22 @@ pub const FUNCTION_SELECTS: [[FunctionSelect; 12]; 30] = [
 /* GPIO 27 */ [None, SPI1_TX,  UART1_RTS, I2C1_SCL, PWM5_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, UART1_RX],
 /* GPIO 28 */ [None, SPI1_RX,  UART0_TX,  I2C0_SDA, PWM6_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  None],
 /* GPIO 29 */ [None, SPI1_CSn, UART0_RX,  I2C0_SCL, PWM6_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_EN,   None],
+/* GPIO 30 */ [None, SPI1_SCK, UART0_CTS, I2C1_SDA, PWM7_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, UART0_TX],
+/* GPIO 31 */ [None, SPI1_TX,  UART0_RTS, I2C1_SCL, PWM7_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  UART0_RX],
+/* GPIO 32 */ [None, SPI0_RX,  UART0_TX,  I2C0_SDA, PWM8_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_EN,   None],
+/* GPIO 33 */ [None, SPI0_CSn, UART0_RX,  I2C0_SCL, PWM8_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, None],
+/* GPIO 34 */ [None, SPI0_SCK, UART0_CTS, I2C1_SDA, PWM9_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  UART0_TX],
+/* GPIO 35 */ [None, SPI0_TX,  UART0_RTS, I2C1_SCL, PWM9_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_EN,   UART0_RX],
+/* GPIO 36 */ [None, SPI0_RX,  UART1_TX,  I2C0_SDA, PWM10_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, None],
+/* GPIO 37 */ [None, SPI0_CSn, UART1_RX,  I2C0_SCL, PWM10_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  None],
+/* GPIO 38 */ [None, SPI0_SCK, UART1_CTS, I2C1_SDA, PWM11_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_EN,   UART1_TX],
+/* GPIO 39 */ [None, SPI0_TX,  UART1_RTS, I2C1_SCL, PWM11_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, UART1_RX],
+/* GPIO 40 */ [None, SPI1_RX,  UART1_TX,  I2C0_SDA, PWM8_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  None],
+/* GPIO 41 */ [None, SPI1_CSn, UART1_RX,  I2C0_SCL, PWM8_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_EN,   None],
+/* GPIO 42 */ [None, SPI1_SCK, UART1_CTS, I2C1_SDA, PWM9_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, UART1_TX],
+/* GPIO 43 */ [None, SPI1_TX,  UART1_RTS, I2C1_SCL, PWM9_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  UART1_RX],
+/* GPIO 44 */ [None, SPI1_RX,  UART0_TX,  I2C0_SDA, PWM10_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_EN,   None],
+/* GPIO 45 */ [None, SPI1_CSn, UART0_RX,  I2C0_SCL, PWM10_B, SIO, PIO_0, PIO_1, PIO_2, None,         USB_OVCUR_DET, None],
+/* GPIO 46 */ [None, SPI1_SCK, UART0_CTS, I2C1_SDA, PWM11_A, SIO, PIO_0, PIO_1, PIO_2, None,         USB_VBUS_DET,  UART0_TX],
+/* GPIO 47 */ [None, SPI1_TX,  UART0_RTS, I2C1_SCL, PWM11_B, SIO, PIO_0, PIO_1, PIO_2, QMI_CS1n,     USB_VBUS_EN,   UART0_RX],
 ];
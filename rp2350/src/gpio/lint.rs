@@ -0,0 +1,207 @@
+/**
+ * @file gpio/lint.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Checks of the IO_BANK0 and pads configuration for conflicting or unusable pins
+ */
+use super::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PinIssue {
+    /// The same peripheral input is muxed onto several pins, it sees the OR of them
+    SharedInput {
+        function: FunctionSelect,
+        pins: Vec<PinIndex>,
+    },
+    /// The SIO drives a pin muxed onto another function, the output never reaches the pad
+    SioNotSelected {
+        pin: PinIndex,
+        function: FunctionSelect,
+    },
+    /// The function drives the pin while its pad has the output disabled
+    OutputDisabled {
+        pin: PinIndex,
+        function: FunctionSelect,
+    },
+    /// The function reads the pin while its pad has the input disabled, it only sees low
+    InputDisabled {
+        pin: PinIndex,
+        function: FunctionSelect,
+    },
+    /// Both pulls are enabled, the pad acts as a bus keeper
+    PullUpAndDown { pin: PinIndex },
+}
+
+impl PinIssue {
+    /// Pins involved in the issue
+    pub fn pins(&self) -> Vec<PinIndex> {
+        match self {
+            Self::SharedInput { pins, .. } => pins.clone(),
+            Self::SioNotSelected { pin, .. }
+            | Self::OutputDisabled { pin, .. }
+            | Self::InputDisabled { pin, .. }
+            | Self::PullUpAndDown { pin } => vec![*pin],
+        }
+    }
+}
+
+impl fmt::Display for PinIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SharedInput { function, pins } => {
+                let pins: Vec<_> = pins.iter().map(|pin| format!("GPIO{pin}")).collect();
+                write!(f, "{function:?} is selected on {}", pins.join(", "))
+            }
+            Self::SioNotSelected { pin, function } => write!(
+                f,
+                "GPIO{pin}: the SIO output is enabled but the pin is muxed to {function:?}"
+            ),
+            Self::OutputDisabled { pin, function } => write!(
+                f,
+                "GPIO{pin}: {function:?} drives the pin but the pad output is disabled"
+            ),
+            Self::InputDisabled { pin, function } => write!(
+                f,
+                "GPIO{pin}: {function:?} reads the pin but the pad input is disabled"
+            ),
+            Self::PullUpAndDown { pin } => write!(
+                f,
+                "GPIO{pin}: both pull-up and pull-down are enabled, the pad is a bus keeper"
+            ),
+        }
+    }
+}
+
+/// Functions which only ever read their pin
+fn is_input_function(function: FunctionSelect) -> bool {
+    use FunctionSelect::*;
+
+    matches!(
+        function,
+        SPI0_RX
+            | SPI1_RX
+            | UART0_RX
+            | UART0_CTS
+            | UART1_RX
+            | UART1_CTS
+            | CLOCK_GPINO
+            | CLOCK_GPIN1
+            | USB_OVCUR_DET
+            | USB_VBUS_DET
+    )
+}
+
+impl GpioController {
+    /// Everything suspicious in the current configuration of the pins, by pin number
+    pub fn lint(&self) -> Vec<PinIssue> {
+        let mut issues = Vec::new();
+        let mut inputs: Vec<(FunctionSelect, Vec<PinIndex>)> = Vec::new();
+
+        for pin in self.pins.iter() {
+            let index = pin.index;
+            let function = pin.func_sel();
+            let output = self.raw_output(function, index);
+            let output_enable = pin.oe_override().apply_bool(output.enable);
+
+            if function != FunctionSelect::SIO && self.outputs.sio_output_enable >> index & 1 != 0
+            {
+                issues.push(PinIssue::SioNotSelected { pin: index, function });
+            }
+
+            if function != FunctionSelect::None && output_enable && pin.output_disable() {
+                issues.push(PinIssue::OutputDisabled { pin: index, function });
+            }
+
+            if is_input_function(function) {
+                if !pin.input_enable() {
+                    issues.push(PinIssue::InputDisabled { pin: index, function });
+                }
+
+                match inputs.iter_mut().find(|(selected, _)| *selected == function) {
+                    Some((_, pins)) => pins.push(index),
+                    None => inputs.push((function, vec![index])),
+                }
+            }
+
+            if pin.pull_up_enable() && pin.pull_down_enable() {
+                issues.push(PinIssue::PullUpAndDown { pin: index });
+            }
+        }
+
+        issues.extend(
+            inputs
+                .into_iter()
+                .filter(|(_, pins)| pins.len() > 1)
+                .map(|(function, pins)| PinIssue::SharedInput { function, pins }),
+        );
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(gpio: &mut GpioController, index: PinIndex, funcsel: u32) {
+        gpio.pins[index as usize].ctrl = funcsel;
+    }
+
+    #[test]
+    fn test_reset_is_clean() {
+        let gpio = GpioController::default();
+        assert!(gpio.lint().is_empty());
+    }
+
+    #[test]
+    fn test_issues() {
+        let mut gpio = GpioController::default();
+
+        // UART0 RX on GPIO1 and GPIO13, the pad of GPIO13 still has its input disabled
+        select(&mut gpio, 1, 2);
+        select(&mut gpio, 13, 2);
+        gpio.pins[1].pad |= 1 << 6;
+
+        // SIO output enabled on GPIO4 muxed to UART1 TX
+        select(&mut gpio, 4, 2);
+        gpio.outputs.sio_output_enable = 1 << 4;
+
+        // both pulls on GPIO7
+        gpio.pins[7].pad |= 1 << 3;
+
+        let issues = gpio.lint();
+        assert!(issues.contains(&PinIssue::InputDisabled {
+            pin: 13,
+            function: FunctionSelect::UART0_RX
+        }));
+        assert!(issues.contains(&PinIssue::SioNotSelected {
+            pin: 4,
+            function: FunctionSelect::UART1_TX
+        }));
+        assert!(issues.contains(&PinIssue::PullUpAndDown { pin: 7 }));
+        assert!(issues.contains(&PinIssue::SharedInput {
+            function: FunctionSelect::UART0_RX,
+            pins: vec![1, 13]
+        }));
+        assert_eq!(issues.len(), 4);
+    }
+
+    #[test]
+    fn test_output_disabled() {
+        let mut gpio = GpioController::default();
+
+        // SIO drives GPIO25 with the pad output disabled
+        select(&mut gpio, 25, 5);
+        gpio.outputs.sio_output_enable = 1 << 25;
+        gpio.pins[25].pad |= 1 << 7;
+
+        assert_eq!(
+            gpio.lint(),
+            [PinIssue::OutputDisabled {
+                pin: 25,
+                function: FunctionSelect::SIO
+            }]
+        );
+    }
+}
@@ -19,6 +19,7 @@ pub struct GpioPin {
     pub interrupt_raw: u8,
     pub interrupt_mask: u8,
     pub interrupt_force: u8,
+    /// Level of the pad seen by the interrupt logic at the last update
     pub previous_value: bool,
 }
 
@@ -75,8 +76,9 @@ impl GpioPin {
         DriveStrength::from(extract_bits(self.pad, 4..=5))
     }
 
+    // from pad
     pub fn input_enable(&self) -> bool {
-        extract_bit(self.ctrl, 6) == 1
+        extract_bit(self.pad, 6) == 1
     }
 
     pub fn pad_isolation_control(&self) -> bool {
@@ -120,6 +122,12 @@ impl GpioPin {
 
     pub fn set_input(&mut self, value: bool) -> InterruptCheck {
         self.raw_input_value = value;
+        self.update_level(value)
+    }
+
+    /// The pad changed level, either from the outside or from an output of the chip
+    pub fn update_level(&mut self, value: bool) -> InterruptCheck {
+        self.previous_value = value;
         let last_irq = self.interrupt_status();
 
         if value && self.input_enable() {
@@ -0,0 +1,103 @@
+/**
+ * @file gpio/spi_bus.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Devices on the SPI buses, selected by the GPIO wired to their chip select
+ */
+use super::{GpioController, OutputState, PinIndex, PinState, NUM_GPIO};
+use crate::error::ConfigError;
+use crate::peripherals::SpiDeviceRef;
+
+/// A device on the bus of SPI0 or SPI1, with the GPIO wired to its chip select
+#[derive(Clone)]
+pub struct SpiBusDevice {
+    pub spi: u8,
+    pub cs: PinIndex,
+    pub device: SpiDeviceRef,
+    selected: bool,
+}
+
+impl SpiBusDevice {
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+}
+
+impl GpioController {
+    /// Wire a device to the bus of an SPI block. Its chip select can be driven by the SIO
+    /// like most drivers do, or by the CSn function of the block.
+    pub fn attach_spi_device(
+        &mut self,
+        spi: u8,
+        cs: PinIndex,
+        device: SpiDeviceRef,
+    ) -> Result<(), ConfigError> {
+        if spi > 1 {
+            return Err(ConfigError::InvalidSpi(spi));
+        }
+
+        if cs as usize >= NUM_GPIO {
+            return Err(ConfigError::InvalidGpioPin(cs));
+        }
+
+        if self.spi_devices.iter().any(|d| d.spi == spi && d.cs == cs) {
+            return Err(ConfigError::SpiChipSelectInUse(cs));
+        }
+
+        self.spi_devices.push(SpiBusDevice {
+            spi,
+            cs,
+            device,
+            selected: false,
+        });
+
+        self.update_chip_selects();
+        Ok(())
+    }
+
+    pub fn detach_spi_device(&mut self, spi: u8, cs: PinIndex) -> Option<SpiDeviceRef> {
+        let index = self
+            .spi_devices
+            .iter()
+            .position(|d| d.spi == spi && d.cs == cs)?;
+
+        Some(self.spi_devices.remove(index).device)
+    }
+
+    pub fn spi_devices(&self) -> &[SpiBusDevice] {
+        &self.spi_devices
+    }
+
+    /// A device is selected while the chip actively drives its chip select low
+    fn is_chip_selected(&self, cs: PinIndex) -> bool {
+        matches!(self.pin_state(cs), PinState::Output(OutputState::Low, _))
+    }
+
+    /// Tell the devices whose chip select changed, called whenever the pads may change
+    pub(crate) fn update_chip_selects(&mut self) {
+        for index in 0..self.spi_devices.len() {
+            let selected = self.is_chip_selected(self.spi_devices[index].cs);
+            let device = &mut self.spi_devices[index];
+
+            if selected == device.selected {
+                continue;
+            }
+
+            device.selected = selected;
+
+            match selected {
+                true => device.device.borrow_mut().select(),
+                false => device.device.borrow_mut().deselect(),
+            }
+        }
+    }
+
+    /// Exchange a byte with the selected devices of a bus. Their outputs are wired
+    /// together, a line nobody drives is pulled high.
+    pub(crate) fn spi_transfer(&mut self, spi: u8, data: u8) -> u8 {
+        self.spi_devices
+            .iter()
+            .filter(|d| d.spi == spi && d.selected)
+            .fold(0xFF, |miso, d| miso & d.device.borrow_mut().transfer(data))
+    }
+}
@@ -0,0 +1,166 @@
+/**
+ * @file ihex.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Parser for Intel HEX images
+ */
+use thiserror::Error;
+
+const RECORD_DATA: u8 = 0x00;
+const RECORD_EOF: u8 = 0x01;
+const RECORD_EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const RECORD_START_SEGMENT_ADDRESS: u8 = 0x03;
+const RECORD_EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const RECORD_START_LINEAR_ADDRESS: u8 = 0x05;
+
+/// Lines are counted from 1
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum IhexError {
+    #[error("Line {0}: malformed record")]
+    Malformed(usize),
+
+    #[error("Line {0}: checksum mismatch")]
+    Checksum(usize),
+
+    #[error("Line {line}: unknown record type {kind:#04x}")]
+    UnknownRecord { line: usize, kind: u8 },
+
+    #[error("Missing end of file record")]
+    MissingEof,
+}
+
+/// Continuous block of data at an absolute address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub address: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntelHex {
+    pub segments: Vec<Segment>,
+    /// From the start linear address record, if any
+    pub entry: Option<u32>,
+}
+
+impl IntelHex {
+    pub fn parse(text: &str) -> Result<Self, IhexError> {
+        let mut result = Self::default();
+        let mut base = 0u32;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let record = decode_record(line).ok_or(IhexError::Malformed(line_no))?;
+
+            if record.iter().fold(0u8, |acc, v| acc.wrapping_add(*v)) != 0 {
+                return Err(IhexError::Checksum(line_no));
+            }
+
+            let len = record[0] as usize;
+            if record.len() != len + 5 {
+                return Err(IhexError::Malformed(line_no));
+            }
+
+            let offset = u16::from_be_bytes([record[1], record[2]]) as u32;
+            let kind = record[3];
+            let data = &record[4..4 + len];
+
+            match kind {
+                RECORD_DATA => result.push(base.wrapping_add(offset), data),
+                RECORD_EOF => return Ok(result),
+                RECORD_EXTENDED_SEGMENT_ADDRESS | RECORD_EXTENDED_LINEAR_ADDRESS => {
+                    let [hi, lo] = data else {
+                        return Err(IhexError::Malformed(line_no));
+                    };
+
+                    let value = u16::from_be_bytes([*hi, *lo]) as u32;
+                    base = if kind == RECORD_EXTENDED_LINEAR_ADDRESS {
+                        value << 16
+                    } else {
+                        value << 4
+                    };
+                }
+                RECORD_START_SEGMENT_ADDRESS => {}
+                RECORD_START_LINEAR_ADDRESS => {
+                    let entry: [u8; 4] =
+                        data.try_into().map_err(|_| IhexError::Malformed(line_no))?;
+                    result.entry = Some(u32::from_be_bytes(entry));
+                }
+                _ => return Err(IhexError::UnknownRecord { line: line_no, kind }),
+            }
+        }
+
+        Err(IhexError::MissingEof)
+    }
+
+    fn push(&mut self, address: u32, data: &[u8]) {
+        // Records are usually sequential, merge them to reduce the number of writes
+        if let Some(last) = self
+            .segments
+            .last_mut()
+            .filter(|last| last.address.wrapping_add(last.data.len() as u32) == address)
+        {
+            last.data.extend_from_slice(data);
+            return;
+        }
+
+        self.segments.push(Segment {
+            address,
+            data: data.to_vec(),
+        });
+    }
+}
+
+fn decode_record(line: &str) -> Option<Vec<u8>> {
+    let hex = line.strip_prefix(':')?;
+
+    if hex.len() % 2 != 0 || hex.len() < 10 {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let text = "\
+:020000041000EA
+:0400000001020304F2
+:0400040005060708DE
+:0400000510000000E7
+:00000001FF
+";
+        let hex = IntelHex::parse(text).unwrap();
+        assert_eq!(
+            hex.segments,
+            vec![Segment {
+                address: 0x1000_0000,
+                data: vec![1, 2, 3, 4, 5, 6, 7, 8],
+            }]
+        );
+        assert_eq!(hex.entry, Some(0x1000_0000));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            IntelHex::parse(":0400000001020304F3\n:00000001FF"),
+            Err(IhexError::Checksum(1))
+        );
+        assert_eq!(IntelHex::parse("0400000001020304F2"), Err(IhexError::Malformed(1)));
+        assert_eq!(IntelHex::parse(":0400000001020304F2"), Err(IhexError::MissingEof));
+    }
+}
@@ -27,6 +27,25 @@ pub enum InspectionEvent {
         core: u8,
         exception: u32,
     },
+    /// A Hazard3 core halted in Debug Mode, `cause` is the value of DCSR.CAUSE
+    DebugHalt {
+        core: u8,
+        address: u32,
+        cause: u8,
+    },
+    /// An interrupt entered by a core, the IRQ number on RISC-V and the exception
+    /// number on Arm, where the external interrupts start at 16
+    InterruptTaken {
+        core: u8,
+        number: u32,
+    },
+    /// A conditional branch of a Hazard3 core, checked against its branch predictor
+    BranchPredicted {
+        core: u8,
+        address: u32,
+        taken: bool,
+        mispredicted: bool,
+    },
 
     BusStore {
         requestor: Requestor,
@@ -48,6 +67,60 @@ pub enum InspectionEvent {
         address: u32,
     },
 
+    UninitializedRead {
+        requestor: Requestor,
+        size: DataSize,
+        address: u32,
+        pc: Option<u32>,
+    },
+
+    /// An access to a peripheral the simulator does not model, it reads as zero
+    UnimplementedAccess {
+        requestor: Requestor,
+        address: u32,
+        write: bool,
+    },
+
+    /// A store into a region watched with [`crate::bus::Bus::watch_region`]
+    WatchedWrite {
+        id: u32,
+        requestor: Requestor,
+        size: DataSize,
+        address: u32,
+        old: u32,
+        new: u32,
+    },
+
+    /// An access hit a breakpoint added with [`crate::bus::Bus::add_memory_breakpoint`]
+    MemoryBreakpoint {
+        id: u32,
+        requestor: Requestor,
+        size: DataSize,
+        address: u32,
+        write: bool,
+    },
+
+    /// A DMA channel halted on a bus fault, the address is the one the channel stopped at
+    DmaError {
+        channel: u8,
+        address: u32,
+        write: bool,
+    },
+
+    /// A delay loop fast-forwarded by [`crate::rp2350::Rp2350::skip_busy_wait`]
+    BusyWaitSkipped {
+        core: u8,
+        address: u32,
+        iterations: u64,
+        instructions: u64,
+        cycles: u64,
+    },
+
+    /// Ticks both cores slept through, fast-forwarded by [`crate::rp2350::Rp2350::skip_idle`]
+    IdleSkipped {
+        cycles: u64,
+    },
+
     TickCore(u8),
     WakeCore(u8),
     FlashedBinary,
@@ -60,6 +133,15 @@ pub enum InspectionEvent {
         uart_index: u8,
         value: u16,
     },
+
+    /// Bytes sent by the firmware through its USB CDC ACM interface
+    UsbCdcTx {
+        data: Vec<u8>,
+    },
+    /// Bytes received by the firmware on its USB CDC ACM interface
+    UsbCdcRx {
+        data: Vec<u8>,
+    },
 }
 
 pub trait Inspector {
@@ -85,11 +167,47 @@ impl Inspector for InspectorRef {
     }
 }
 
+/// Forward every event to several inspectors, in the order they were added
+#[derive(Default, Clone)]
+pub struct InspectorGroup {
+    inspectors: Vec<Rc<dyn Inspector>>,
+}
+
+impl InspectorGroup {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, inspector: Rc<dyn Inspector>) {
+        self.inspectors.push(inspector);
+    }
+}
+
+impl FromIterator<Rc<dyn Inspector>> for InspectorGroup {
+    fn from_iter<I: IntoIterator<Item = Rc<dyn Inspector>>>(iter: I) -> Self {
+        Self {
+            inspectors: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Inspector for InspectorGroup {
+    fn handle_event(&self, event: InspectionEvent) {
+        for inspector in self.inspectors.iter() {
+            inspector.handle_event(event.clone());
+        }
+    }
+}
+
 impl InspectorRef {
     pub fn set_inspector(&mut self, inspector: Rc<dyn Inspector>) {
         self.inspector = inspector;
     }
 
+    pub fn inspector(&self) -> Rc<dyn Inspector> {
+        Rc::clone(&self.inspector)
+    }
+
     pub fn emit(&self, event: InspectionEvent) {
         self.inspector.handle_event(event);
     }
@@ -117,6 +235,29 @@ impl Inspector for LoggerInspector {
                 log::info!("Core {core}: Exception: {exception:#010x}");
             }
 
+            InspectionEvent::DebugHalt {
+                core,
+                address,
+                cause,
+            } => {
+                log::info!("Core {core}: Halted in debug mode at {address:#010x}, cause {cause}");
+            }
+
+            InspectionEvent::InterruptTaken { core, number } => {
+                log::trace!("Core {core}: Interrupt {number} taken");
+            }
+
+            InspectionEvent::BranchPredicted {
+                core,
+                address,
+                taken,
+                mispredicted,
+            } => {
+                log::trace!(
+                    "Core {core}: Branch at {address:#010x} taken: {taken}, mispredicted: {mispredicted}"
+                );
+            }
+
             InspectionEvent::ExecutedInstruction {
                 core,
                 instruction,
@@ -136,6 +277,28 @@ impl Inspector for LoggerInspector {
             InspectionEvent::WakeCore(core) => {
                 log::info!("Core {core}: Wake event");
             }
+            InspectionEvent::DmaError {
+                channel,
+                address,
+                write,
+            } => {
+                let side = if write { "write" } else { "read" };
+                log::warn!("DMA channel {channel}: Bus error on the {side} side, stopped at {address:#010x}");
+            }
+
+            InspectionEvent::BusyWaitSkipped {
+                core,
+                address,
+                iterations,
+                cycles,
+                ..
+            } => {
+                log::info!("Core {core}: Skipped {iterations} iterations ({cycles} cycles) of the delay loop at {address:#010x}");
+            }
+
+            InspectionEvent::IdleSkipped { cycles } => {
+                log::info!("Skipped {cycles} cycles while both cores were asleep");
+            }
 
             InspectionEvent::UartTx { uart_index, value } => {
                 log::info!("UART TX event on UART {uart_index}: {value}");
@@ -145,6 +308,14 @@ impl Inspector for LoggerInspector {
                 log::info!("UART RX event on UART {uart_index}: {value}");
             }
 
+            InspectionEvent::UsbCdcTx { data } => {
+                log::info!("USB CDC TX event: {data:02x?}");
+            }
+
+            InspectionEvent::UsbCdcRx { data } => {
+                log::info!("USB CDC RX event: {data:02x?}");
+            }
+
             InspectionEvent::BusStore {
                 requestor,
                 size,
@@ -175,6 +346,46 @@ impl Inspector for LoggerInspector {
                 // Detailing about error message
                 log::error!("Bus Error: {error:?} {requestor:?} {size:?} address: {address:#010x}");
             }
+
+            InspectionEvent::UninitializedRead {
+                requestor,
+                size,
+                address,
+                pc,
+            } => {
+                log::warn!("Uninitialized read: {requestor:?} {size:?} address: {address:#010x} pc: {pc:x?}");
+            }
+
+            InspectionEvent::UnimplementedAccess {
+                requestor,
+                address,
+                write,
+            } => {
+                let access = if write { "write" } else { "read" };
+                log::trace!("Unimplemented {access}: {requestor:?} address: {address:#010x}");
+            }
+
+            InspectionEvent::WatchedWrite {
+                id,
+                requestor,
+                address,
+                old,
+                new,
+                ..
+            } => {
+                log::info!("Watch {id}: {requestor:?} wrote {address:#010x}: {old:#010x} -> {new:#010x}");
+            }
+
+            InspectionEvent::MemoryBreakpoint {
+                id,
+                requestor,
+                size,
+                address,
+                write,
+            } => {
+                let access = if write { "write" } else { "read" };
+                log::info!("Breakpoint {id}: {requestor:?} {size:?} {access} at {address:#010x}");
+            }
         }
     }
 }
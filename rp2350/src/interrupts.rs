@@ -6,6 +6,10 @@
  */
 pub type Interrupt = u8;
 
+/// Number of interrupt lines of the RP2350, the spare ones included
+pub const NUM_IRQS: usize = 52;
+const IRQS_MASK: u64 = (1 << NUM_IRQS) - 1;
+
 pub struct InterruptIter(u64);
 
 impl Iterator for InterruptIter {
@@ -22,10 +26,58 @@ impl Iterator for InterruptIter {
     }
 }
 
+/// What the interrupt controller of a core lets through, published by the core itself:
+/// the Xh3irq arrays on Hazard3, the NVIC on the Cortex-M33
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoreRouting {
+    pub enabled: u64,
+    /// Pending state forced by software, on top of the lines
+    pub forced: u64,
+    /// 4 bit priorities in the encoding of the controller,
+    /// higher is more urgent on Hazard3 while lower is more urgent on Arm
+    pub priority: [u8; NUM_IRQS],
+}
+
+impl Default for CoreRouting {
+    fn default() -> Self {
+        Self {
+            enabled: 0,
+            forced: 0,
+            priority: [0; NUM_IRQS],
+        }
+    }
+}
+
+/// Where an interrupt goes, for core 0 and core 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrqRoute {
+    pub irq: Interrupt,
+    /// Raised by its peripheral, the core local ones differ between the cores
+    pub asserted: [bool; 2],
+    pub forced: [bool; 2],
+    pub enabled: [bool; 2],
+    pub priority: [u8; 2],
+}
+
+impl IrqRoute {
+    /// The interrupt is pending and enabled on the core
+    pub fn is_delivered(&self, core: u8) -> bool {
+        let core = core as usize & 1;
+        (self.asserted[core] || self.forced[core]) && self.enabled[core]
+    }
+}
+
 #[derive(Default)]
 pub struct Interrupts {
     global: u64,
     core1: u64,
+    /// Standard RISC-V timer line of each core (MIP.MTIP), driven by the SIO platform timer
+    machine_timer: [bool; 2],
+    /// Standard RISC-V software interrupt of each core (MIP.MSIP), driven by SIO RISCV_SOFTIRQ
+    machine_software: [bool; 2],
+    routing: [CoreRouting; 2],
+    /// Cores sleeping in WFI or WFE, the scheduler skips them until one of their lines rises
+    asleep: [bool; 2],
 }
 
 impl Interrupts {
@@ -90,11 +142,28 @@ impl Interrupts {
     pub fn reset(&mut self) {
         self.global = 0;
         self.core1 = 0;
+        self.machine_timer = [false; 2];
+        self.machine_software = [false; 2];
+        self.routing = Default::default();
+        self.asleep = [false; 2];
+    }
+
+    pub fn is_core_local(irq: Interrupt) -> bool {
+        Self::CORE_LOCAL_IRQS_MASK >> irq & 1 == 1
     }
 
     /// Enable the IRQ for the given core
     pub fn set_irq(&mut self, irq: Interrupt, value: bool) {
         if value {
+            if self.global >> irq & 1 == 0 {
+                // the core local lines of the global set are the ones of core 0
+                self.wake(0);
+
+                if !Self::is_core_local(irq) {
+                    self.wake(1);
+                }
+            }
+
             self.global |= 1 << irq;
         } else {
             self.clear_irq(irq);
@@ -103,10 +172,11 @@ impl Interrupts {
 
     pub fn set_core_local_irq(&mut self, core: u8, irq: Interrupt, value: bool) {
         if value {
-            if core == 0 {
-                self.global |= 1 << irq;
-            } else {
-                self.core1 |= 1 << irq;
+            let lines = if core == 0 { &mut self.global } else { &mut self.core1 };
+
+            if *lines >> irq & 1 == 0 {
+                *lines |= 1 << irq;
+                self.wake(core);
             }
         } else {
             self.clear_core_local_irq(core, irq);
@@ -125,6 +195,32 @@ impl Interrupts {
         }
     }
 
+    pub fn set_machine_timer(&mut self, core: u8, value: bool) {
+        if value && !self.machine_timer[core as usize & 1] {
+            self.wake(core);
+        }
+
+        self.machine_timer[core as usize & 1] = value;
+    }
+
+    /// Whether the machine timer interrupt (MIP.MTIP) is asserted on the given core
+    pub fn machine_timer(&self, core: u8) -> bool {
+        self.machine_timer[core as usize & 1]
+    }
+
+    pub fn set_machine_software(&mut self, core: u8, value: bool) {
+        if value && !self.machine_software[core as usize & 1] {
+            self.wake(core);
+        }
+
+        self.machine_software[core as usize & 1] = value;
+    }
+
+    /// Whether the machine software interrupt (MIP.MSIP) is asserted on the given core
+    pub fn machine_software(&self, core: u8) -> bool {
+        self.machine_software[core as usize & 1]
+    }
+
     pub fn iter(&self, core: u8) -> InterruptIter {
         if core == 0 {
             InterruptIter(self.global)
@@ -137,6 +233,64 @@ impl Interrupts {
         }
     }
 
+    /// Bit mask of the interrupts pending for the given core
+    pub fn pending(&self, core: u8) -> u64 {
+        self.iter(core).0
+    }
+
+    /// Publish the configuration of the interrupt controller of a core
+    pub fn set_routing(&mut self, core: u8, routing: CoreRouting) {
+        self.routing[core as usize & 1] = routing;
+    }
+
+    pub fn routing(&self, core: u8) -> &CoreRouting {
+        &self.routing[core as usize & 1]
+    }
+
+    /// Bit mask of the interrupts pending and enabled on the given core
+    pub fn delivered(&self, core: u8) -> u64 {
+        let routing = self.routing(core);
+        (self.pending(core) | routing.forced) & routing.enabled & IRQS_MASK
+    }
+
+    pub fn route(&self, irq: Interrupt) -> IrqRoute {
+        let bit = |mask: u64| mask >> irq & 1 == 1;
+        let priority = |core: u8| {
+            let priority = &self.routing(core).priority;
+            priority.get(irq as usize).copied().unwrap_or(0)
+        };
+
+        IrqRoute {
+            irq,
+            asserted: [bit(self.pending(0)), bit(self.pending(1))],
+            forced: [bit(self.routing[0].forced), bit(self.routing[1].forced)],
+            enabled: [bit(self.routing[0].enabled), bit(self.routing[1].enabled)],
+            priority: [priority(0), priority(1)],
+        }
+    }
+
+    /// Current routing of every interrupt line
+    pub fn routes(&self) -> impl Iterator<Item = IrqRoute> + '_ {
+        (0..NUM_IRQS as Interrupt).map(|irq| self.route(irq))
+    }
+
+    /// The core waits for an interrupt, it stays asleep until one of its lines rises.
+    /// The lines already high did not wake it, the core is the only one which can
+    /// change what it is waiting for
+    pub fn sleep_until_interrupt(&mut self, core: u8) {
+        self.asleep[core as usize & 1] = true;
+    }
+
+    /// Wake the core up, it checks again whether what it waits for happened.
+    /// Done on every rising line of the core, and by the other core through events
+    pub fn wake(&mut self, core: u8) {
+        self.asleep[core as usize & 1] = false;
+    }
+
+    pub fn is_asleep(&self, core: u8) -> bool {
+        self.asleep[core as usize & 1]
+    }
+
     pub fn update(&mut self) {
         // do nothing for now...
     }
@@ -163,4 +317,65 @@ mod tests {
 
         assert!(interrupts.iter(0).next().is_none());
     }
+
+    #[test]
+    fn test_sleep() {
+        let mut interrupts = Interrupts::default();
+        interrupts.set_irq(Interrupts::UART0_IRQ, true);
+        interrupts.sleep_until_interrupt(0);
+        interrupts.sleep_until_interrupt(1);
+
+        // a line already high does not wake the cores up again
+        interrupts.set_irq(Interrupts::UART0_IRQ, true);
+        assert!(interrupts.is_asleep(0) && interrupts.is_asleep(1));
+
+        // the core local lines only wake their own core
+        interrupts.set_core_local_irq(1, Interrupts::SIO_IRQ_FIFO, true);
+        assert!(interrupts.is_asleep(0));
+        assert!(!interrupts.is_asleep(1));
+
+        interrupts.set_machine_timer(0, true);
+        assert!(!interrupts.is_asleep(0));
+    }
+
+    #[test]
+    fn test_routing() {
+        let mut interrupts = Interrupts::default();
+
+        interrupts.set_irq(Interrupts::UART0_IRQ, true);
+        interrupts.set_core_local_irq(1, Interrupts::SIO_IRQ_FIFO, true);
+        assert_eq!(interrupts.delivered(0), 0);
+        assert_eq!(interrupts.delivered(1), 0);
+
+        interrupts.set_routing(
+            1,
+            CoreRouting {
+                enabled: 1 << Interrupts::UART0_IRQ | 1 << Interrupts::SIO_IRQ_FIFO,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(interrupts.delivered(0), 0);
+        assert_eq!(
+            interrupts.delivered(1),
+            1 << Interrupts::UART0_IRQ | 1 << Interrupts::SIO_IRQ_FIFO
+        );
+
+        let route = interrupts.route(Interrupts::SIO_IRQ_FIFO);
+        assert_eq!(route.asserted, [false, true]);
+        assert!(!route.is_delivered(0));
+        assert!(route.is_delivered(1));
+
+        // software forced on core 0 only
+        interrupts.set_routing(
+            0,
+            CoreRouting {
+                enabled: 1 << Interrupts::DMA_IRQ_0,
+                forced: 1 << Interrupts::DMA_IRQ_0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(interrupts.delivered(0), 1 << Interrupts::DMA_IRQ_0);
+        assert_eq!(interrupts.routes().filter(|r| r.is_delivered(0)).count(), 1);
+    }
 }
@@ -4,24 +4,41 @@
  * @date 02/01/2025
  * @brief Rp2350 simulator library
  */
+pub mod budget;
 pub mod bus;
 pub mod clock;
 pub mod common;
+pub mod coverage;
+pub mod device;
+pub mod elf;
+pub mod environment;
 pub mod error;
 pub mod gpio;
+pub mod ihex;
 pub mod inspector;
 pub mod interrupts;
 pub mod memory;
 pub mod peripherals;
 pub mod processor;
+pub mod profiler;
+pub mod rewind;
 pub mod rp2350;
+pub mod script;
 pub mod simulator;
+pub mod snapshot;
+pub mod soak;
+pub mod summary;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod trace;
 
 mod utils;
 
 pub type Time = u64;
 
-pub use error::Error as SimulatorError;
-pub use inspector::{InspectionEvent, Inspector, InspectorRef};
-pub use rp2350::Rp2350;
+pub use device::VirtualDevice;
+pub use error::{ConfigError, Error as SimulatorError, LoadImageError};
+pub use inspector::{InspectionEvent, Inspector, InspectorGroup, InspectorRef};
+pub use rp2350::{Rp2350, Rp2350Builder};
+pub use snapshot::ProgramSnapshot;
 pub type Result<T> = core::result::Result<T, SimulatorError>;
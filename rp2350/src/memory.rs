@@ -13,6 +13,7 @@ pub struct MemoryOutOfBoundsError;
 
 type MemoryResult<T> = Result<T, MemoryOutOfBoundsError>;
 
+#[derive(Clone)]
 pub struct GenericMemory<const N: usize> {
     data: Vec<u8>,
 }
@@ -140,6 +141,76 @@ impl<const N: usize> GenericMemory<N> {
     }
 }
 
+/// Content of a memory after reset
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InitPattern {
+    #[default]
+    Zero,
+    /// Every byte is set to the given value, e.g. 0xCC
+    Byte(u8),
+    /// Pseudo random content, the same seed always produce the same content
+    Random(u64),
+}
+
+impl InitPattern {
+    pub fn fill(&self, data: &mut [u8]) {
+        match *self {
+            InitPattern::Zero => data.fill(0),
+            InitPattern::Byte(value) => data.fill(value),
+            InitPattern::Random(seed) => {
+                // xorshift64*, the state must never be zero. Every seed gives its own
+                // sequence, forcing a bit would make two seeds share one
+                let mut state = match seed ^ 0x9e37_79b9_7f4a_7c15 {
+                    0 => 1,
+                    state => state,
+                };
+                for chunk in data.chunks_mut(8) {
+                    state ^= state >> 12;
+                    state ^= state << 25;
+                    state ^= state >> 27;
+                    let value = state.wrapping_mul(0x2545_f491_4f6c_dd1d).to_le_bytes();
+                    chunk.copy_from_slice(&value[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
+
+/// Shadow bitmap of a memory, one bit per byte telling if it has been written since reset
+#[derive(Clone)]
+pub struct InitializedMap {
+    bits: Vec<u64>,
+}
+
+impl InitializedMap {
+    pub fn new(size: usize) -> Self {
+        Self {
+            bits: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+
+    pub fn mark(&mut self, address: u32, len: usize) {
+        for byte in address as usize..(address as usize + len) {
+            if let Some(word) = self.bits.get_mut(byte / 64) {
+                *word |= 1 << (byte % 64);
+            }
+        }
+    }
+
+    /// Return true only if all bytes in the range have been written
+    pub fn is_initialized(&self, address: u32, len: usize) -> bool {
+        (address as usize..(address as usize + len)).all(|byte| {
+            self.bits
+                .get(byte / 64)
+                .is_some_and(|word| word & (1 << (byte % 64)) != 0)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,4 +251,36 @@ mod tests {
             MemoryOutOfBoundsError
         );
     }
+
+    #[test]
+    fn test_init_pattern() {
+        let mut a = [0u8; 37];
+        let mut b = [0u8; 37];
+
+        InitPattern::Byte(0xCC).fill(&mut a);
+        assert!(a.iter().all(|&v| v == 0xCC));
+
+        InitPattern::Random(42).fill(&mut a);
+        InitPattern::Random(42).fill(&mut b);
+        assert_eq!(a, b);
+
+        InitPattern::Random(43).fill(&mut b);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_initialized_map() {
+        let mut map = InitializedMap::new(1024);
+
+        assert!(!map.is_initialized(0, 4));
+        map.mark(0, 2);
+        assert!(map.is_initialized(0, 2));
+        assert!(!map.is_initialized(0, 4));
+
+        map.mark(63, 2); // across the word boundary
+        assert!(map.is_initialized(63, 2));
+
+        map.clear();
+        assert!(!map.is_initialized(0, 1));
+    }
 }
@@ -5,60 +5,90 @@
  * @brief Peripheral module for the RP2350
  */
 use crate::clock::Clock;
+use crate::environment::Environment;
 use crate::gpio::GpioController;
 use crate::interrupts::Interrupts;
-use crate::{common::*, InspectorRef};
+use crate::{common::*, InspectionEvent, InspectorRef};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+pub mod accessctrl;
+pub mod adc;
 pub mod bootram;
 pub mod busctrl;
 pub mod clocks;
+pub mod coresight;
 pub mod dma;
+pub mod glitch_detector;
 pub mod i2c;
 pub mod io;
 pub mod otp;
 pub mod pads;
+pub mod pio;
 pub mod pll;
+pub mod plugin;
+pub mod powman;
+pub mod psm;
 pub mod pwm;
+pub mod qmi;
 pub mod reset;
+pub mod rosc;
 pub mod sha256;
 pub mod sio;
-// pub mod spi;
+pub mod sysinfo;
+pub mod tbman;
+pub mod spi;
 pub mod ticks;
 pub mod timer;
 pub mod trng;
 pub mod uart;
+pub mod usb;
 pub mod watchdog;
+pub mod xip_ctrl;
 pub mod xosc;
 
+pub use accessctrl::AccessCtrl;
+pub use adc::Adc;
 pub use bootram::BootRam;
 pub use busctrl::BusCtrl;
 pub use clocks::Clocks;
+pub use coresight::{AtbFunnel, TimestampGenerator, Tpiu};
 pub use dma::Dma;
-pub use i2c::I2c;
+pub use glitch_detector::GlitchDetector;
+pub use i2c::{I2c, I2cDevice, I2cDeviceRef};
 pub use io::IoBank0;
-pub use otp::Otp;
+pub use otp::{Otp, OtpData, WhiteLabel};
 pub use pads::PadsBank0;
+pub use plugin::{Plugin, PluginDevice, PluginError, PluginInfo, PluginModule};
+pub use pio::Pio;
 pub use pll::Pll;
+pub use powman::Powman;
+pub use psm::Psm;
 pub use pwm::Pwm;
+pub use qmi::Qmi;
 pub use reset::Reset;
+pub use rosc::Rosc;
 pub use sha256::Sha256;
 pub use sio::Sio;
+pub use spi::{Spi, SpiDevice, SpiDeviceRef};
+pub use sysinfo::SysInfo;
+pub use tbman::Tbman;
 pub use ticks::Ticks;
 pub use timer::Timer;
 pub use trng::Trng;
-pub use uart::Uart;
+pub use uart::{Uart, UartLink};
+pub use usb::UsbCtrl;
 pub use watchdog::WatchDog;
+pub use xip_ctrl::XipCtrl;
 pub use xosc::Xosc;
 
 #[derive(Default)]
 pub struct Peripherals {
     // APB peripherals
-    pub sysinfo: UnimplementedPeripheral,
+    pub sysinfo: SysInfo,
     pub syscfg: UnimplementedPeripheral,
     pub clocks: Rc<RefCell<Clocks>>,
-    pub psm: UnimplementedPeripheral,
+    pub psm: Psm,
     pub resets: Reset,
     pub io_bank0: IoBank0,
     pub io_qspi: UnimplementedPeripheral,
@@ -67,50 +97,48 @@ pub struct Peripherals {
     pub xosc: Xosc,
     pub pll_sys: Pll<0>,
     pub pll_usb: Pll<1>,
-    pub accessctrl: UnimplementedPeripheral,
+    pub accessctrl: AccessCtrl,
     pub busctrl: BusCtrl,
     pub uart0: Rc<RefCell<Uart<0>>>,
     pub uart1: Rc<RefCell<Uart<1>>>,
-    pub spi0: UnimplementedPeripheral,
-    pub spi1: UnimplementedPeripheral,
+    pub spi0: Rc<RefCell<Spi<0>>>,
+    pub spi1: Rc<RefCell<Spi<1>>>,
     pub i2c0: Rc<RefCell<I2c<0>>>,
     pub i2c1: Rc<RefCell<I2c<1>>>,
-    pub adc: UnimplementedPeripheral,
+    pub adc: Rc<RefCell<Adc>>,
     pub pwm: Rc<RefCell<Pwm>>,
     pub timer0: Rc<RefCell<Timer<0>>>,
     pub timer1: Rc<RefCell<Timer<1>>>,
     pub hstx_ctrl: UnimplementedPeripheral,
-    pub xip_ctrl: UnimplementedPeripheral,
-    pub xip_qmi: UnimplementedPeripheral,
+    pub xip_ctrl: Rc<RefCell<XipCtrl>>,
+    pub xip_qmi: Rc<RefCell<Qmi>>,
     pub watch_dog: WatchDog,
     pub bootram: BootRam, // only allow secure access
-    pub rosc: UnimplementedPeripheral,
+    pub rosc: Rc<RefCell<Rosc>>,
     pub trng: Trng,
     pub sha256: Rc<RefCell<Sha256>>,
-    pub powman: UnimplementedPeripheral,
+    pub powman: Rc<RefCell<Powman>>,
     pub ticks: Ticks,
     pub otp: Otp,
-    pub otp_data: UnimplementedPeripheral,
-    pub otp_data_raw: UnimplementedPeripheral,
-    pub otp_data_guarded: UnimplementedPeripheral,
-    pub otp_data_raw_guarded: UnimplementedPeripheral,
+    /// The rows behind the 4 aliases of OTP_DATA
+    pub otp_data: OtpData,
     pub coresight_periph: UnimplementedPeripheral,
     pub coresight_romtable: UnimplementedPeripheral,
     pub coresight_ahb_ap: [UnimplementedPeripheral; 2],
-    pub coresight_timestamp_gen: UnimplementedPeripheral,
-    pub coresight_atb_funnel: UnimplementedPeripheral,
-    pub coresight_tpiu: UnimplementedPeripheral,
+    pub coresight_timestamp_gen: Rc<RefCell<TimestampGenerator>>,
+    pub coresight_atb_funnel: Rc<RefCell<AtbFunnel>>,
+    pub coresight_tpiu: Rc<RefCell<Tpiu>>,
     pub coresight_cti: UnimplementedPeripheral,
     pub coresight_apb_ap_riscv: UnimplementedPeripheral,
-    pub glitch_detector: UnimplementedPeripheral,
-    pub tbman: UnimplementedPeripheral,
+    pub glitch_detector: GlitchDetector,
+    pub tbman: Tbman,
 
     // AHB peripherals
     pub dma: Rc<RefCell<Dma>>,
-    pub usbctrl: UnimplementedPeripheral,
-    pub usbctrl_dpram: UnimplementedPeripheral,
-    pub usbctrl_regs: UnimplementedPeripheral,
-    pub pio: [UnimplementedPeripheral; 3],
+    pub usbctrl: Rc<RefCell<UsbCtrl>>,
+    pub pio0: Rc<RefCell<Pio<0>>>,
+    pub pio1: Rc<RefCell<Pio<1>>>,
+    pub pio2: Rc<RefCell<Pio<2>>>,
     pub xip_aux: UnimplementedPeripheral,
     pub hstx_fifo: UnimplementedPeripheral,
     pub coresight_trace: UnimplementedPeripheral,
@@ -118,9 +146,13 @@ pub struct Peripherals {
     // Core local
     pub sio: Sio,
 
+    /// Devices of third party modules, mapped into the free slots
+    pub plugins: Vec<Rc<RefCell<Plugin>>>,
+
     clock: Rc<Clock>,
     interrupts: Rc<RefCell<Interrupts>>,
     gpio: Rc<RefCell<GpioController>>,
+    pub environment: Rc<RefCell<Environment>>,
     pub(crate) inspector: InspectorRef,
 }
 
@@ -174,10 +206,17 @@ impl Peripherals {
             interrupts: Rc::clone(&self.interrupts),
             clock: Rc::clone(&self.clock),
             dma: Rc::clone(&self.dma),
+            environment: Rc::clone(&self.environment),
+            rosc: Rc::clone(&self.rosc),
             inspector: self.inspector.clone(),
+            write_mask: None,
         }
     }
 
+    pub(crate) fn clock(&self) -> &Rc<Clock> {
+        &self.clock
+    }
+
     pub fn reset(&mut self) {
         let Self {
             watch_dog,
@@ -185,6 +224,19 @@ impl Peripherals {
             gpio,
             interrupts,
             inspector,
+            otp,
+            otp_data,
+            glitch_detector,
+            environment,
+            powman,
+            plugins,
+            sysinfo,
+            tbman,
+            coresight_timestamp_gen,
+            coresight_atb_funnel,
+            coresight_tpiu,
+            i2c0,
+            i2c1,
             ..
         } = core::mem::take(self);
 
@@ -193,7 +245,38 @@ impl Peripherals {
         self.gpio = gpio;
         self.interrupts = interrupts;
         self.inspector = inspector;
+        // The surroundings of the chip are not affected by a reset
+        self.environment = environment;
+        // POWMAN is in the always-on domain, its timer and scratch registers are kept
+        self.powman = powman;
+        // The plugins are outside of the chip, they only get notified
+        self.plugins = plugins;
+        self.plugins
+            .iter()
+            .for_each(|plugin| plugin.borrow_mut().reset());
+        // ARCHSEL survives the warm reset, it is what selects the architecture of the cores
+        self.otp.archsel = otp.archsel;
+        // the fuses are programmed for good
+        self.otp_data = otp_data;
+        self.latch_otp();
+        // which detectors caused the reset is only lost on a power cycle
+        self.glitch_detector.trig_status = glitch_detector.trig_status;
+        // the metadata of the chip and the platform are configuration of the simulator
+        self.sysinfo = sysinfo;
+        self.tbman = tbman;
+        // the debug components are in the debug domain, a trace can follow the chip through
+        // its resets
+        self.coresight_timestamp_gen = coresight_timestamp_gen;
+        self.coresight_atb_funnel = coresight_atb_funnel;
+        self.coresight_tpiu = coresight_tpiu;
+        // the devices on the I2C buses are outside of the chip
+        let devices = i2c0.borrow_mut().take_devices();
+        self.i2c0.borrow_mut().set_devices(devices);
+        let devices = i2c1.borrow_mut().take_devices();
+        self.i2c1.borrow_mut().set_devices(devices);
         self.watch_dog.reset();
+        // the clock generators and the PLLs are back to their reset values
+        self.update_frequencies();
 
         timer::reschedule_timer_tick(
             self.timer0.clone(),
@@ -214,12 +297,86 @@ impl Peripherals {
         );
     }
 
+    /// Derive the frequencies of the clock domains from the oscillators, the PLLs and the
+    /// clock generators, after the firmware programmed one of them
+    pub fn update_frequencies(&self) {
+        let xosc = self.xosc.frequency();
+        let rosc = self
+            .rosc
+            .borrow()
+            .frequency(&self.environment.borrow(), &self.clock) as u64;
+        let pll_sys = self
+            .clock
+            .pll_sys_override()
+            .unwrap_or_else(|| self.pll_sys.frequency(xosc));
+        let pll_usb = self.pll_usb.frequency(xosc);
+
+        let frequencies = self
+            .clocks
+            .borrow()
+            .frequencies(xosc, rosc, pll_sys, pll_usb);
+        self.clock.set_frequencies(frequencies);
+    }
+
+    /// Read the critical flags out of the fuses, as the chip does on reset
+    pub fn latch_otp(&mut self) {
+        self.otp.critical = self.otp_data.critical();
+        self.glitch_detector.armed_by_otp =
+            self.otp.critical & otp::CRITICAL_GLITCH_DETECTOR_ENABLE != 0;
+    }
+
+    /// Advance the peripherals clocked by clk_sys by one cycle
+    pub fn tick(&mut self) {
+        self.pio0
+            .borrow_mut()
+            .tick(&self.gpio, &self.interrupts, &self.dma, &self.clock);
+        self.pio1
+            .borrow_mut()
+            .tick(&self.gpio, &self.interrupts, &self.dma, &self.clock);
+        self.pio2
+            .borrow_mut()
+            .tick(&self.gpio, &self.interrupts, &self.dma, &self.clock);
+        self.usbctrl
+            .borrow_mut()
+            .tick(&self.interrupts, &self.inspector);
+        self.powman.borrow_mut().tick(&self.clock, &self.interrupts);
+    }
+
+    /// Map a plugin into the slot it asked for, which must not be used by the chip or
+    /// another plugin
+    pub fn add_plugin(
+        &mut self,
+        info: PluginInfo,
+        module: Box<dyn PluginModule>,
+    ) -> Result<Rc<RefCell<Plugin>>, PluginError> {
+        let (base, size) = (info.base, info.size);
+        let plugin = Plugin::new(info, module)?;
+
+        if self.find(base, Requestor::Proc0).is_some() {
+            return Err(PluginError::InvalidRegion { base, size });
+        }
+
+        let plugin = Rc::new(RefCell::new(plugin));
+        self.plugins.push(Rc::clone(&plugin));
+        Ok(plugin)
+    }
+
+    /// None of the peripherals ticked on every cycle has work to do, skipping time
+    /// only needs the events of the clock
+    pub fn is_idle(&self) -> bool {
+        let powman = self.powman.borrow();
+        let alarm = powman.timer & (powman::TIMER_ALARM_ENAB | powman::TIMER_ALARM);
+        let powman_waiting = powman.is_timer_running() && alarm == powman::TIMER_ALARM_ENAB;
+
+        self.pio0.borrow().enabled == 0
+            && self.pio1.borrow().enabled == 0
+            && self.pio2.borrow().enabled == 0
+            && self.usbctrl.borrow().sie_status & usb::SIE_STATUS_CONNECTED == 0
+            && !powman_waiting
+    }
+
     pub fn find_mut(&mut self, address: u32, requestor: Requestor) -> Option<&mut dyn Peripheral> {
-        // TODO don't know if this address mask correct or not...
-        // All I know for now is that it will not work correctly with
-        // the Coresight peripherals, which are not implemented yet.
-        //
-        // Also missing the Cortex exclusive peripherals.
+        // TODO missing the Cortex exclusive peripherals.
         let result = match address & 0xFFFF_C000 {
             0x4000_0000 => &mut self.sysinfo as &mut dyn Peripheral,
             0x4000_8000 => &mut self.syscfg as &mut dyn Peripheral,
@@ -256,41 +413,53 @@ impl Peripherals {
             0x4010_0000 => &mut self.powman as &mut dyn Peripheral,
             0x4010_8000 => &mut self.ticks as &mut dyn Peripheral,
             0x4012_0000 => &mut self.otp as &mut dyn Peripheral,
-            0x4013_0000 => &mut self.otp_data as &mut dyn Peripheral,
-            0x4013_4000 => &mut self.otp_data_raw as &mut dyn Peripheral,
-            0x4013_8000 => &mut self.otp_data_guarded as &mut dyn Peripheral,
-            0x4013_C000 => &mut self.otp_data_raw_guarded as &mut dyn Peripheral,
-            0x4014_0000 => &mut self.coresight_periph as &mut dyn Peripheral,
-            // 0x4014_0000 => Some(&mut self.coresight_romtable as &mut dyn Peripheral,
-            0x4014_2000 => &mut self.coresight_ahb_ap[0] as &mut dyn Peripheral,
-            0x4014_4000 => &mut self.coresight_ahb_ap[1] as &mut dyn Peripheral,
-            0x4014_6000 => &mut self.coresight_timestamp_gen as &mut dyn Peripheral,
-            0x4014_7000 => &mut self.coresight_atb_funnel as &mut dyn Peripheral,
-            0x4014_8000 => &mut self.coresight_tpiu as &mut dyn Peripheral,
-            0x4014_9000 => &mut self.coresight_cti as &mut dyn Peripheral,
-            0x4014_A000 => &mut self.coresight_apb_ap_riscv as &mut dyn Peripheral,
+            0x4013_0000 | 0x4013_4000 | 0x4013_8000 | 0x4013_C000 => {
+                &mut self.otp_data as &mut dyn Peripheral
+            }
+            // the CoreSight components are 4 KB apart
+            0x4014_0000 | 0x4014_4000 | 0x4014_8000 => match address & 0xFFFF_F000 {
+                0x4014_0000 | 0x4014_1000 => &mut self.coresight_periph as &mut dyn Peripheral,
+                0x4014_2000 | 0x4014_3000 => &mut self.coresight_ahb_ap[0] as &mut dyn Peripheral,
+                0x4014_4000 | 0x4014_5000 => &mut self.coresight_ahb_ap[1] as &mut dyn Peripheral,
+                0x4014_6000 => &mut self.coresight_timestamp_gen as &mut dyn Peripheral,
+                0x4014_7000 => &mut self.coresight_atb_funnel as &mut dyn Peripheral,
+                0x4014_8000 => &mut self.coresight_tpiu as &mut dyn Peripheral,
+                0x4014_9000 => &mut self.coresight_cti as &mut dyn Peripheral,
+                0x4014_A000 => &mut self.coresight_apb_ap_riscv as &mut dyn Peripheral,
+                _ => return None,
+            },
             0x4015_8000 => &mut self.glitch_detector as &mut dyn Peripheral,
             0x4016_0000 => &mut self.tbman as &mut dyn Peripheral,
 
             // AHB
             0x5000_0000 => &mut self.dma as &mut dyn Peripheral,
-            0x5010_0000 => &mut self.usbctrl as &mut dyn Peripheral,
-            // 0x5010_0000 => Some(&mut self.usbctrl_dpram as &mut dyn Peripheral,
-            0x5011_0000 => &mut self.usbctrl_regs as &mut dyn Peripheral,
-            0x5020_0000 => &mut self.pio[0] as &mut dyn Peripheral,
-            0x5030_8000 => &mut self.pio[1] as &mut dyn Peripheral,
-            0x5040_0000 => &mut self.pio[2] as &mut dyn Peripheral,
+            // the DPRAM and the registers
+            0x5010_0000 | 0x5011_0000 => &mut self.usbctrl as &mut dyn Peripheral,
+            0x5020_0000 => &mut self.pio0 as &mut dyn Peripheral,
+            0x5030_0000 => &mut self.pio1 as &mut dyn Peripheral,
+            0x5040_0000 => &mut self.pio2 as &mut dyn Peripheral,
             0x5050_0000 => &mut self.xip_aux as &mut dyn Peripheral,
             0x5060_0000 => &mut self.hstx_fifo as &mut dyn Peripheral,
             0x5070_0000 => &mut self.coresight_trace as &mut dyn Peripheral,
 
             0xd0000000 | 0xd0020000 if requestor.is_proc() => &mut self.sio as &mut dyn Peripheral,
-            _ => return None,
+            slot => {
+                return self
+                    .plugins
+                    .iter_mut()
+                    .find(|plugin| plugin.borrow().info.base == slot)
+                    .map(|plugin| plugin as &mut dyn Peripheral)
+            }
         };
 
         Some(result)
     }
 
+    /// Whether the block at `address` is held in reset by the RESETS, it faults on the bus
+    pub fn in_reset(&self, address: u32) -> bool {
+        reset::block_of(address).is_some_and(|block| self.resets.is_held(block))
+    }
+
     pub fn find(&self, address: u32, requestor: Requestor) -> Option<&dyn Peripheral> {
         let result = match address & 0xFFFF_C000 {
             0x4000_0000 => &self.sysinfo as &dyn Peripheral,
@@ -328,47 +497,59 @@ impl Peripherals {
             0x4010_0000 => &self.powman as &dyn Peripheral,
             0x4010_8000 => &self.ticks as &dyn Peripheral,
             0x4012_0000 => &self.otp as &dyn Peripheral,
-            0x4013_0000 => &self.otp_data as &dyn Peripheral,
-            0x4013_4000 => &self.otp_data_raw as &dyn Peripheral,
-            0x4013_8000 => &self.otp_data_guarded as &dyn Peripheral,
-            0x4013_C000 => &self.otp_data_raw_guarded as &dyn Peripheral,
-            0x4014_0000 => &self.coresight_periph as &dyn Peripheral,
-            // 0x4014_0000 => Some(&self.coresight_romtable as &dyn Peripheral,
-            0x4014_2000 => &self.coresight_ahb_ap[0] as &dyn Peripheral,
-            0x4014_4000 => &self.coresight_ahb_ap[1] as &dyn Peripheral,
-            0x4014_6000 => &self.coresight_timestamp_gen as &dyn Peripheral,
-            0x4014_7000 => &self.coresight_atb_funnel as &dyn Peripheral,
-            0x4014_8000 => &self.coresight_tpiu as &dyn Peripheral,
-            0x4014_9000 => &self.coresight_cti as &dyn Peripheral,
-            0x4014_A000 => &self.coresight_apb_ap_riscv as &dyn Peripheral,
+            0x4013_0000 | 0x4013_4000 | 0x4013_8000 | 0x4013_C000 => {
+                &self.otp_data as &dyn Peripheral
+            }
+            // the CoreSight components are 4 KB apart
+            0x4014_0000 | 0x4014_4000 | 0x4014_8000 => match address & 0xFFFF_F000 {
+                0x4014_0000 | 0x4014_1000 => &self.coresight_periph as &dyn Peripheral,
+                0x4014_2000 | 0x4014_3000 => &self.coresight_ahb_ap[0] as &dyn Peripheral,
+                0x4014_4000 | 0x4014_5000 => &self.coresight_ahb_ap[1] as &dyn Peripheral,
+                0x4014_6000 => &self.coresight_timestamp_gen as &dyn Peripheral,
+                0x4014_7000 => &self.coresight_atb_funnel as &dyn Peripheral,
+                0x4014_8000 => &self.coresight_tpiu as &dyn Peripheral,
+                0x4014_9000 => &self.coresight_cti as &dyn Peripheral,
+                0x4014_A000 => &self.coresight_apb_ap_riscv as &dyn Peripheral,
+                _ => return None,
+            },
             0x4015_8000 => &self.glitch_detector as &dyn Peripheral,
             0x4016_0000 => &self.tbman as &dyn Peripheral,
 
             // AHB
             0x5000_0000 => &self.dma as &dyn Peripheral,
-            0x5010_0000 => &self.usbctrl as &dyn Peripheral,
-            // 0x5010_0000 => Some(&self.usbctrl_dpram as &dyn Peripheral,
-            0x5011_0000 => &self.usbctrl_regs as &dyn Peripheral,
-            0x5020_0000 => &self.pio[0] as &dyn Peripheral,
-            0x5030_8000 => &self.pio[1] as &dyn Peripheral,
-            0x5040_0000 => &self.pio[2] as &dyn Peripheral,
+            0x5010_0000 | 0x5011_0000 => &self.usbctrl as &dyn Peripheral,
+            0x5020_0000 => &self.pio0 as &dyn Peripheral,
+            0x5030_0000 => &self.pio1 as &dyn Peripheral,
+            0x5040_0000 => &self.pio2 as &dyn Peripheral,
             0x5050_0000 => &self.xip_aux as &dyn Peripheral,
             0x5060_0000 => &self.hstx_fifo as &dyn Peripheral,
             0x5070_0000 => &self.coresight_trace as &dyn Peripheral,
 
             0xd0000000 | 0xd0020000 if requestor.is_proc() => &self.sio as &dyn Peripheral,
-            _ => return None,
+            slot => {
+                return self
+                    .plugins
+                    .iter()
+                    .find(|plugin| plugin.borrow().info.base == slot)
+                    .map(|plugin| plugin as &dyn Peripheral)
+            }
         };
 
         Some(result)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
 pub enum PeripheralError {
+    #[error("no register at this offset")]
     OutOfBounds,
+    #[error("missing permission")]
     MissingPermission,
+    #[error("reserved register")]
     Reserved,
+    #[error("uncorrectable ECC error")]
+    EccError,
 }
 
 pub type PeripheralResult<T> = std::result::Result<T, PeripheralError>;
@@ -382,7 +563,11 @@ pub struct PeripheralAccessContext {
     pub interrupts: Rc<RefCell<Interrupts>>,
     pub clock: Rc<Clock>,
     pub dma: Rc<RefCell<Dma>>,
+    pub environment: Rc<RefCell<Environment>>,
+    pub rosc: Rc<RefCell<Rosc>>,
     pub inspector: InspectorRef,
+    /// Byte lanes written by a byte or halfword store, `None` for a word store
+    pub write_mask: Option<u32>,
 }
 
 // Purpose: Define the Peripheral trait and a default implementation for unimplemented peripherals.
@@ -439,6 +624,11 @@ impl Peripheral for UnimplementedPeripheral {
             "Unimplemented peripheral read at address {:#X}",
             ctx.address
         );
+        ctx.inspector.emit(InspectionEvent::UnimplementedAccess {
+            requestor: ctx.requestor,
+            address: ctx.address,
+            write: false,
+        });
         Ok(0)
     }
 
@@ -453,6 +643,11 @@ impl Peripheral for UnimplementedPeripheral {
             ctx.address,
             value
         );
+        ctx.inspector.emit(InspectionEvent::UnimplementedAccess {
+            requestor: ctx.requestor,
+            address: ctx.address,
+            write: true,
+        });
         Ok(())
     }
 }
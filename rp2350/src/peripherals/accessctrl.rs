@@ -0,0 +1,497 @@
+/**
+ * @file peripherals/accessctrl.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Access control (ACCESSCTRL) peripheral implementation
+ * @todo GPIO_NSMASK is stored but not yet applied to the SIO / IO banks
+ */
+use super::*;
+use crate::utils::extract_bit;
+
+pub const LOCK: u16 = 0x00; // Lock ACCESSCTRL configuration per bus master
+pub const FORCE_CORE_NS: u16 = 0x04; // Force core 1's bus accesses to be Non-secure
+pub const CFGRESET: u16 = 0x08; // Reset all ACCESSCTRL configuration to the reset value
+pub const GPIO_NSMASK0: u16 = 0x0c; // Non-secure GPIO mask for GPIO 0..31
+pub const GPIO_NSMASK1: u16 = 0x10; // Non-secure GPIO mask for GPIO 32..47 and QSPI/USB
+pub const PERMISSION_START: u16 = 0x14; // ROM, the first per-peripheral register
+pub const PERMISSION_END: u16 = 0xe8; // XIP_AUX, the last per-peripheral register
+
+// Writes to ACCESSCTRL must have this value in the upper half-word
+pub const PASSWORD: u32 = 0xacce_0000;
+pub const PASSWORD_MASK: u32 = 0xffff_0000;
+
+// Bits of each per-peripheral register
+pub const NSU: u32 = 1 << 0; // Non-secure, unprivileged (only with NSP)
+pub const NSP: u32 = 1 << 1; // Non-secure, privileged
+pub const SU: u32 = 1 << 2; // Secure, unprivileged (only with SP)
+pub const SP: u32 = 1 << 3; // Secure, privileged
+pub const CORE0: u32 = 1 << 4;
+pub const CORE1: u32 = 1 << 5;
+pub const DMA: u32 = 1 << 6;
+pub const DBG: u32 = 1 << 7;
+
+const LOCK_RESET: u32 = 0x4; // DMA is locked out by default
+const LOCK_MASK: u32 = 0xf;
+
+/// Name and reset value of every peripheral controlled by ACCESSCTRL,
+/// in register order starting at `PERMISSION_START`
+pub const ACCESS_CONTROLLED: [(&str, u32); 54] = [
+    ("ROM", 0xff),
+    ("XIP_MAIN", 0xff),
+    ("SRAM0", 0xff),
+    ("SRAM1", 0xff),
+    ("SRAM2", 0xff),
+    ("SRAM3", 0xff),
+    ("SRAM4", 0xff),
+    ("SRAM5", 0xff),
+    ("SRAM6", 0xff),
+    ("SRAM7", 0xff),
+    ("SRAM8", 0xff),
+    ("SRAM9", 0xff),
+    ("DMA", 0xfc),
+    ("USBCTRL", 0xfc),
+    ("PIO0", 0xfc),
+    ("PIO1", 0xfc),
+    ("PIO2", 0xfc),
+    ("CORESIGHT_TRACE", 0xb8),
+    ("CORESIGHT_PERIPH", 0xb8),
+    ("SYSINFO", 0xff),
+    ("RESETS", 0xb8),
+    ("IO_BANK0", 0xb8),
+    ("IO_BANK1", 0xb8),
+    ("PADS_BANK0", 0xb8),
+    ("PADS_QSPI", 0xb8),
+    ("BUSCTRL", 0xb8),
+    ("ADC0", 0xfc),
+    ("HSTX", 0xfc),
+    ("I2C0", 0xfc),
+    ("I2C1", 0xfc),
+    ("PWM", 0xfc),
+    ("SPI0", 0xfc),
+    ("SPI1", 0xfc),
+    ("TIMER0", 0xfc),
+    ("TIMER1", 0xfc),
+    ("UART0", 0xfc),
+    ("UART1", 0xfc),
+    ("OTP", 0xfc),
+    ("TBMAN", 0xfc),
+    ("POWMAN", 0xb8),
+    ("TRNG", 0xb8),
+    ("SHA256", 0xf8),
+    ("SYSCFG", 0xb8),
+    ("CLOCKS", 0xb8),
+    ("XOSC", 0xb8),
+    ("ROSC", 0xb8),
+    ("PLL_SYS", 0xb8),
+    ("PLL_USB", 0xb8),
+    ("TICKS", 0xb8),
+    ("WATCHDOG", 0xb8),
+    ("PSM", 0xb8),
+    ("XIP_CTRL", 0xfc),
+    ("XIP_QMI", 0xb8),
+    ("XIP_AUX", 0xfc),
+];
+
+pub const NOF_ACCESS_CONTROLLED: usize = ACCESS_CONTROLLED.len();
+
+/// Bus master as seen by ACCESSCTRL, split by security state for the cores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessMaster {
+    Core0Secure,
+    Core0NonSecure,
+    Core1Secure,
+    Core1NonSecure,
+    Dma,
+}
+
+impl AccessMaster {
+    pub const ALL: [AccessMaster; 5] = [
+        AccessMaster::Core0Secure,
+        AccessMaster::Core0NonSecure,
+        AccessMaster::Core1Secure,
+        AccessMaster::Core1NonSecure,
+        AccessMaster::Dma,
+    ];
+
+    pub fn new(requestor: Requestor, secure: bool) -> Self {
+        match (requestor, secure) {
+            (Requestor::Proc0, true) => AccessMaster::Core0Secure,
+            (Requestor::Proc0, false) => AccessMaster::Core0NonSecure,
+            (Requestor::Proc1, true) => AccessMaster::Core1Secure,
+            (Requestor::Proc1, false) => AccessMaster::Core1NonSecure,
+            (Requestor::DmaR | Requestor::DmaW, _) => AccessMaster::Dma,
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+
+    fn master_mask(&self) -> u32 {
+        match self {
+            AccessMaster::Core0Secure | AccessMaster::Core0NonSecure => CORE0,
+            AccessMaster::Core1Secure | AccessMaster::Core1NonSecure => CORE1,
+            AccessMaster::Dma => DMA,
+        }
+    }
+}
+
+/// Map a bus address to its index in `ACCESS_CONTROLLED`.
+/// Returns None for addresses that are not filtered by ACCESSCTRL (SIO, BOOTRAM, ACCESSCTRL itself...)
+pub fn controlled_index(address: u32) -> Option<usize> {
+    let index = match address {
+        0x0000_0000..=0x0000_7fff => 0,
+        0x1000_0000..=0x1fff_ffff => 1,
+        0x2000_0000..=0x2003_ffff => 2 + ((address >> 2) & 0b11) as usize, // striped
+        0x2004_0000..=0x2007_ffff => 6 + ((address >> 2) & 0b11) as usize, // striped
+        0x2008_0000..=0x2008_0fff => 10,
+        0x2008_1000..=0x2008_1fff => 11,
+        0x4000_0000..=0x4000_7fff => 19,
+        0x4000_8000..=0x4000_ffff => 42,
+        0x4001_0000..=0x4001_7fff => 43,
+        0x4001_8000..=0x4001_ffff => 50,
+        0x4002_0000..=0x4002_7fff => 20,
+        0x4002_8000..=0x4002_ffff => 21,
+        0x4003_0000..=0x4003_7fff => 22,
+        0x4003_8000..=0x4003_ffff => 23,
+        0x4004_0000..=0x4004_7fff => 24,
+        0x4004_8000..=0x4004_ffff => 44,
+        0x4005_0000..=0x4005_7fff => 46,
+        0x4005_8000..=0x4005_ffff => 47,
+        0x4006_8000..=0x4006_ffff => 25,
+        0x4007_0000..=0x4007_7fff => 35,
+        0x4007_8000..=0x4007_ffff => 36,
+        0x4008_0000..=0x4008_7fff => 31,
+        0x4008_8000..=0x4008_ffff => 32,
+        0x4009_0000..=0x4009_7fff => 28,
+        0x4009_8000..=0x4009_ffff => 29,
+        0x400a_0000..=0x400a_7fff => 26,
+        0x400a_8000..=0x400a_ffff => 30,
+        0x400b_0000..=0x400b_7fff => 33,
+        0x400b_8000..=0x400b_ffff => 34,
+        0x400c_0000..=0x400c_7fff => 27,
+        0x400c_8000..=0x400c_ffff => 51,
+        0x400d_0000..=0x400d_7fff => 52,
+        0x400d_8000..=0x400d_ffff => 49,
+        0x400e_8000..=0x400e_ffff => 45,
+        0x400f_0000..=0x400f_7fff => 40,
+        0x400f_8000..=0x400f_ffff => 41,
+        0x4010_0000..=0x4010_7fff => 39,
+        0x4010_8000..=0x4010_ffff => 48,
+        0x4012_0000..=0x4013_ffff => 37,
+        0x4014_0000..=0x4014_ffff => 18,
+        0x4016_0000..=0x4016_7fff => 38,
+        0x5000_0000..=0x5000_ffff => 12,
+        0x5010_0000..=0x5011_ffff => 13,
+        0x5020_0000..=0x5020_ffff => 14,
+        0x5030_0000..=0x5030_ffff => 15,
+        0x5040_0000..=0x5040_ffff => 16,
+        0x5050_0000..=0x5050_ffff => 53,
+        0x5060_0000..=0x5060_ffff => 27,
+        0x5070_0000..=0x5070_ffff => 17,
+        _ => return None,
+    };
+
+    Some(index)
+}
+
+/// An access blocked by ACCESSCTRL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub address: u32,
+    pub master: AccessMaster,
+    pub privileged: bool,
+}
+
+pub struct AccessCtrl {
+    pub lock: u32,
+    pub force_core_ns: u32,
+    pub gpio_nsmask: [u32; 2],
+    pub permissions: [u32; NOF_ACCESS_CONTROLLED],
+    /// Per peripheral bitmask of `AccessMaster` which had an access blocked
+    pub blocked: [u8; NOF_ACCESS_CONTROLLED],
+    /// The chip keeps no record of the blocked accesses, only the bus fault of the core
+    /// tells about them. The simulator remembers the last one for the debugger
+    pub last_violation: Option<Violation>,
+}
+
+impl Default for AccessCtrl {
+    fn default() -> Self {
+        Self {
+            lock: LOCK_RESET,
+            force_core_ns: 0,
+            gpio_nsmask: [0; 2],
+            permissions: ACCESS_CONTROLLED.map(|(_, reset)| reset),
+            blocked: [0; NOF_ACCESS_CONTROLLED],
+            last_violation: None,
+        }
+    }
+}
+
+impl AccessCtrl {
+    /// Resolve the effective security of an access, core 1 can be forced to Non-secure
+    pub fn effective_secure(&self, requestor: Requestor, secure: bool) -> bool {
+        match requestor {
+            Requestor::Proc1 if extract_bit(self.force_core_ns, 1) != 0 => false,
+            _ => secure,
+        }
+    }
+
+    /// Check whether a master is allowed to access the peripheral at `index`.
+    /// The unprivileged accesses also need the bit of the privileged ones
+    pub fn is_allowed(
+        &self,
+        index: usize,
+        master: AccessMaster,
+        secure: bool,
+        privileged: bool,
+    ) -> bool {
+        let Some(&permission) = self.permissions.get(index) else {
+            return true;
+        };
+
+        if permission & master.master_mask() == 0 {
+            return false;
+        }
+
+        let needed = match (secure, privileged) {
+            (true, true) => SP,
+            (true, false) => SP | SU,
+            (false, true) => NSP,
+            (false, false) => NSP | NSU,
+        };
+
+        permission & needed == needed
+    }
+
+    /// Check an access and remember it if it is blocked
+    pub fn check(
+        &mut self,
+        address: u32,
+        requestor: Requestor,
+        secure: bool,
+        privileged: bool,
+    ) -> bool {
+        let Some(index) = controlled_index(address) else {
+            return true;
+        };
+
+        let secure = self.effective_secure(requestor, secure);
+        let master = AccessMaster::new(requestor, secure);
+
+        if self.is_allowed(index, master, secure, privileged) {
+            return true;
+        }
+
+        log::warn!(
+            "ACCESSCTRL blocked {:?} access to {} at {:#010x}",
+            master,
+            ACCESS_CONTROLLED[index].0,
+            address
+        );
+
+        self.blocked[index] |= master.bit();
+        self.last_violation = Some(Violation {
+            address,
+            master,
+            privileged,
+        });
+        false
+    }
+
+    pub fn was_blocked(&self, index: usize, master: AccessMaster) -> bool {
+        self.blocked
+            .get(index)
+            .is_some_and(|blocked| blocked & master.bit() != 0)
+    }
+
+    pub fn clear_blocked(&mut self) {
+        self.blocked = [0; NOF_ACCESS_CONTROLLED];
+        self.last_violation = None;
+    }
+
+    fn is_locked(&self, requestor: Requestor) -> bool {
+        let bit = match requestor {
+            Requestor::Proc0 => 0,
+            Requestor::Proc1 => 1,
+            Requestor::DmaR | Requestor::DmaW => 2,
+        };
+
+        extract_bit(self.lock, bit) != 0
+    }
+
+    fn config_reset(&mut self) {
+        self.force_core_ns = 0;
+        self.gpio_nsmask = [0; 2];
+        self.permissions = ACCESS_CONTROLLED.map(|(_, reset)| reset);
+    }
+}
+
+impl Peripheral for AccessCtrl {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let value = match address {
+            LOCK => self.lock,
+            FORCE_CORE_NS => self.force_core_ns,
+            CFGRESET => 0,
+            GPIO_NSMASK0 => self.gpio_nsmask[0],
+            GPIO_NSMASK1 => self.gpio_nsmask[1],
+            PERMISSION_START..=PERMISSION_END if address & 3 == 0 => {
+                self.permissions[((address - PERMISSION_START) / 4) as usize]
+            }
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        // ACCESSCTRL itself is only writable from Secure privileged code
+        if !ctx.secure {
+            return Err(PeripheralError::MissingPermission);
+        }
+
+        // Writes without the password are ignored
+        if value & PASSWORD_MASK != PASSWORD {
+            log::warn!("ACCESSCTRL write without password at {:#X}", address);
+            return Ok(());
+        }
+
+        if self.is_locked(ctx.requestor) {
+            return Ok(());
+        }
+
+        let value = value & !PASSWORD_MASK;
+
+        match address {
+            // lock bits can only be set, cleared by a reset
+            LOCK => self.lock |= value & LOCK_MASK,
+            FORCE_CORE_NS => self.force_core_ns = value & 0b10,
+            CFGRESET => {
+                if value & 1 != 0 {
+                    self.config_reset();
+                }
+            }
+            GPIO_NSMASK0 => self.gpio_nsmask[0] = value,
+            GPIO_NSMASK1 => self.gpio_nsmask[1] = value,
+            PERMISSION_START..=PERMISSION_END if address & 3 == 0 => {
+                self.permissions[((address - PERMISSION_START) / 4) as usize] = value & 0xff;
+            }
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secure_ctx() -> PeripheralAccessContext {
+        PeripheralAccessContext {
+            secure: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_permissions() {
+        let mut ctrl = AccessCtrl::default();
+
+        // UART0 is accessible from both cores in secure state, and by DMA
+        assert!(ctrl.check(0x4007_0000, Requestor::Proc0, true, true));
+        assert!(ctrl.check(0x4007_0000, Requestor::Proc1, true, true));
+        assert!(ctrl.check(0x4007_0000, Requestor::DmaW, true, true));
+
+        // but not from Non-secure
+        assert!(!ctrl.check(0x4007_0000, Requestor::Proc0, false, false));
+        assert!(ctrl.was_blocked(35, AccessMaster::Core0NonSecure));
+        assert!(!ctrl.was_blocked(35, AccessMaster::Core0Secure));
+
+        // SRAM is open to everyone
+        assert!(ctrl.check(0x2000_0000, Requestor::Proc1, false, false));
+
+        // SIO is not filtered by ACCESSCTRL
+        assert!(ctrl.check(0xd000_0000, Requestor::Proc0, false, false));
+
+        ctrl.clear_blocked();
+        assert!(!ctrl.was_blocked(35, AccessMaster::Core0NonSecure));
+    }
+
+    #[test]
+    fn password_protected_write() {
+        let mut ctrl = AccessCtrl::default();
+        let ctx = secure_ctx();
+        let uart0 = PERMISSION_START + 35 * 4;
+
+        // ignored without password
+        ctrl.write(uart0, 0xff, &ctx).unwrap();
+        assert_eq!(ctrl.read(uart0, &ctx), Ok(0xfc));
+
+        ctrl.write(uart0, PASSWORD | 0xff, &ctx).unwrap();
+        assert_eq!(ctrl.read(uart0, &ctx), Ok(0xff));
+        assert!(ctrl.check(0x4007_0000, Requestor::Proc0, false, false));
+
+        // Non-secure cannot touch ACCESSCTRL at all
+        assert_eq!(
+            ctrl.write(uart0, PASSWORD, &Default::default()),
+            Err(PeripheralError::MissingPermission)
+        );
+
+        ctrl.write(CFGRESET, PASSWORD | 1, &ctx).unwrap();
+        assert_eq!(ctrl.read(uart0, &ctx), Ok(0xfc));
+    }
+
+    #[test]
+    fn force_core1_non_secure() {
+        let mut ctrl = AccessCtrl::default();
+        ctrl.write(FORCE_CORE_NS, PASSWORD | 0b10, &secure_ctx())
+            .unwrap();
+
+        assert!(ctrl.check(0x4007_0000, Requestor::Proc0, true, true));
+        assert!(!ctrl.check(0x4007_0000, Requestor::Proc1, true, true));
+        assert!(ctrl.was_blocked(35, AccessMaster::Core1NonSecure));
+    }
+
+    #[test]
+    fn privilege() {
+        let mut ctrl = AccessCtrl::default();
+        let resets = PERMISSION_START + 20 * 4;
+
+        // RESETS only lets the Secure privileged code in
+        assert!(ctrl.check(0x4002_0000, Requestor::Proc0, true, true));
+        assert!(!ctrl.check(0x4002_0000, Requestor::Proc0, true, false));
+        assert_eq!(
+            ctrl.last_violation,
+            Some(Violation {
+                address: 0x4002_0000,
+                master: AccessMaster::Core0Secure,
+                privileged: false,
+            })
+        );
+
+        // the unprivileged bit needs the privileged one
+        ctrl.write(resets, PASSWORD | CORE0 | SU, &secure_ctx())
+            .unwrap();
+        assert!(!ctrl.check(0x4002_0000, Requestor::Proc0, true, false));
+        ctrl.write(resets, PASSWORD | CORE0 | SU | SP, &secure_ctx())
+            .unwrap();
+        assert!(ctrl.check(0x4002_0000, Requestor::Proc0, true, false));
+    }
+
+    #[test]
+    fn lock() {
+        let mut ctrl = AccessCtrl::default();
+        let ctx = secure_ctx();
+        let uart0 = PERMISSION_START + 35 * 4;
+
+        ctrl.write(LOCK, PASSWORD | 0b1, &ctx).unwrap();
+        ctrl.write(uart0, PASSWORD | 0xff, &ctx).unwrap();
+        assert_eq!(ctrl.read(uart0, &ctx), Ok(0xfc));
+    }
+}
@@ -0,0 +1,360 @@
+/**
+ * @file peripherals/adc.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief ADC peripheral implementation, with the temperature sensor driven by the environment
+ * @note The conversions complete immediately for START_ONCE, the DREQ to the DMA is not connected
+ */
+use super::*;
+use crate::clock::EventType;
+use crate::environment::Environment;
+use crate::interrupts::Interrupts;
+use std::collections::VecDeque;
+
+pub const CS: u16 = 0x00; // ADC Control and Status
+pub const RESULT: u16 = 0x04; // Result of most recent ADC conversion
+pub const FCS: u16 = 0x08; // FIFO control and status
+pub const FIFO: u16 = 0x0c; // Conversion result FIFO
+pub const DIV: u16 = 0x10; // Clock divider. If non-zero, CS_START_MANY will start conversions at regular intervals
+pub const INTR: u16 = 0x14; // Raw Interrupts
+pub const INTE: u16 = 0x18; // Interrupt Enable
+pub const INTF: u16 = 0x1c; // Interrupt Force
+pub const INTS: u16 = 0x20; // Interrupt status after masking & forcing
+
+pub const CS_EN: u32 = 1 << 0;
+pub const CS_TS_EN: u32 = 1 << 1;
+pub const CS_START_ONCE: u32 = 1 << 2;
+pub const CS_START_MANY: u32 = 1 << 3;
+pub const CS_READY: u32 = 1 << 8;
+pub const CS_ERR_STICKY: u32 = 1 << 10;
+
+pub const FCS_EN: u32 = 1 << 0;
+pub const FCS_SHIFT: u32 = 1 << 1;
+pub const FCS_ERR: u32 = 1 << 2;
+pub const FCS_DREQ_EN: u32 = 1 << 3;
+pub const FCS_UNDER: u32 = 1 << 10;
+pub const FCS_OVER: u32 = 1 << 11;
+
+pub const FIFO_DEPTH: usize = 4;
+
+/// AINSEL of the temperature sensor on the QFN-60 package (RP2350A), as on the Pico 2
+pub const TEMPERATURE_SENSOR_CHANNEL: u8 = 4;
+
+/// Reference voltage of the ADC
+pub const ADC_VREF: f64 = 3.3;
+
+/// A conversion takes 96 cycles of clk_adc
+const CONVERSION_CYCLES: u64 = 96;
+
+pub struct Adc {
+    /// Voltages on the ADC pins, GPIO26 to GPIO29
+    pub inputs: [f64; 4],
+
+    pub enabled: bool,
+    pub temperature_sensor_enabled: bool,
+    pub start_many: bool,
+    pub ainsel: u8,
+    pub round_robin: u8,
+    pub err_sticky: bool,
+    pub result: u16,
+
+    pub fifo_enabled: bool,
+    pub fifo_shift: bool,
+    pub fifo_err: bool,
+    pub dreq_enabled: bool,
+    pub fifo_threshold: u8,
+    pub fifo: VecDeque<u16>,
+    pub underflow: bool,
+    pub overflow: bool,
+
+    pub div: u32,
+    pub inte: bool,
+    pub intf: bool,
+}
+
+impl Default for Adc {
+    fn default() -> Self {
+        Self {
+            inputs: [0.0; 4],
+            enabled: false,
+            temperature_sensor_enabled: false,
+            start_many: false,
+            ainsel: 0,
+            round_robin: 0,
+            err_sticky: false,
+            result: 0,
+            fifo_enabled: false,
+            fifo_shift: false,
+            fifo_err: false,
+            dreq_enabled: false,
+            fifo_threshold: 0,
+            fifo: VecDeque::with_capacity(FIFO_DEPTH),
+            underflow: false,
+            overflow: false,
+            div: 0,
+            inte: false,
+            intf: false,
+        }
+    }
+}
+
+impl Adc {
+    fn voltage(&self, channel: u8, environment: &Environment, clock: &Clock) -> f64 {
+        match channel {
+            0..=3 => self.inputs[channel as usize],
+            TEMPERATURE_SENSOR_CHANNEL if self.temperature_sensor_enabled => {
+                environment.temperature_sensor_voltage(clock)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Do a conversion on the selected channel and move on to the next one of the round robin
+    fn convert(&mut self, environment: &Environment, clock: &Clock) {
+        let voltage = self.voltage(self.ainsel, environment, clock);
+        let value = (voltage / ADC_VREF * 4096.0).clamp(0.0, 4095.0) as u16;
+        self.result = value;
+
+        if self.fifo_enabled {
+            if self.fifo.len() < FIFO_DEPTH {
+                let value = if self.fifo_shift { value >> 4 } else { value };
+                self.fifo.push_back(value);
+            } else {
+                self.overflow = true;
+            }
+        }
+
+        if self.round_robin != 0 {
+            self.ainsel = (1..=5)
+                .map(|offset| (self.ainsel + offset) % 5)
+                .find(|channel| self.round_robin & (1 << channel) != 0)
+                .unwrap_or(self.ainsel);
+        }
+    }
+
+    fn intr(&self) -> bool {
+        self.fifo_enabled && self.fifo.len() >= (self.fifo_threshold.max(1) as usize)
+    }
+
+    fn update_interrupt(&self, interrupts: &RefCell<Interrupts>) {
+        let irq = (self.intr() && self.inte) || self.intf;
+        interrupts
+            .borrow_mut()
+            .set_irq(Interrupts::ADC_IRQ_FIFO, irq);
+    }
+
+    /// Time between two conversions of the free running mode, in ticks of clk_sys
+    fn sample_period(&self, clock: &Clock) -> u64 {
+        let int = ((self.div >> 8) & 0xFFFF) as u64;
+        let frac = (self.div & 0xFF) as u64;
+
+        // The sample rate can not be faster than a conversion
+        let adc_cycles_x256 = if int == 0 {
+            CONVERSION_CYCLES * 256
+        } else {
+            ((1 + int) * 256 + frac).max(CONVERSION_CYCLES * 256)
+        };
+
+        (adc_cycles_x256 * clock.clk_sys() / clock.clk_adc().max(1) / 256).max(1)
+    }
+}
+
+fn schedule_conversion(
+    adc: Rc<RefCell<Adc>>,
+    clock: Rc<Clock>,
+    interrupts: Rc<RefCell<Interrupts>>,
+    environment: Rc<RefCell<Environment>>,
+) {
+    let period = adc.borrow().sample_period(&clock);
+    let clock_clone = Rc::clone(&clock);
+
+    clock.schedule(period, EventType::Adc, move || {
+        let mut inner = adc.borrow_mut();
+        if !inner.start_many || !inner.enabled {
+            return;
+        }
+
+        inner.convert(&environment.borrow(), &clock_clone);
+        inner.update_interrupt(&interrupts);
+        drop(inner);
+
+        schedule_conversion(adc, clock_clone, interrupts, environment);
+    });
+}
+
+impl Peripheral for Rc<RefCell<Adc>> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let mut inner = self.borrow_mut();
+
+        let value = match address {
+            CS => {
+                (inner.enabled as u32)
+                    | (inner.temperature_sensor_enabled as u32) << 1
+                    | (inner.start_many as u32) << 3
+                    | (inner.enabled as u32) << 8 // READY
+                    | (inner.err_sticky as u32) << 10
+                    | (inner.ainsel as u32) << 12
+                    | (inner.round_robin as u32) << 16
+            }
+            RESULT => inner.result as u32,
+            FCS => {
+                (inner.fifo_enabled as u32)
+                    | (inner.fifo_shift as u32) << 1
+                    | (inner.fifo_err as u32) << 2
+                    | (inner.dreq_enabled as u32) << 3
+                    | (inner.fifo.is_empty() as u32) << 8
+                    | ((inner.fifo.len() == FIFO_DEPTH) as u32) << 9
+                    | (inner.underflow as u32) << 10
+                    | (inner.overflow as u32) << 11
+                    | (inner.fifo.len() as u32) << 16
+                    | (inner.fifo_threshold as u32) << 24
+            }
+            FIFO => {
+                let value = match inner.fifo.pop_front() {
+                    Some(value) => value as u32,
+                    None => {
+                        inner.underflow = true;
+                        0
+                    }
+                };
+
+                inner.update_interrupt(&ctx.interrupts);
+                value
+            }
+            DIV => inner.div,
+            INTR => inner.intr() as u32,
+            INTE => inner.inte as u32,
+            INTF => inner.intf as u32,
+            INTS => ((inner.intr() && inner.inte) || inner.intf) as u32,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut inner = self.borrow_mut();
+
+        match address {
+            CS => {
+                let was_running = inner.start_many && inner.enabled;
+
+                inner.enabled = value & CS_EN != 0;
+                inner.temperature_sensor_enabled = value & CS_TS_EN != 0;
+                inner.start_many = value & CS_START_MANY != 0;
+                inner.ainsel = ((value >> 12) & 0xF) as u8;
+                inner.round_robin = ((value >> 16) & 0x1F) as u8;
+
+                if value & CS_ERR_STICKY != 0 {
+                    inner.err_sticky = false;
+                }
+
+                if inner.enabled && value & CS_START_ONCE != 0 {
+                    inner.convert(&ctx.environment.borrow(), &ctx.clock);
+                }
+
+                inner.update_interrupt(&ctx.interrupts);
+
+                if inner.enabled && inner.start_many && !was_running {
+                    drop(inner);
+                    ctx.clock.cancel(EventType::Adc);
+                    schedule_conversion(
+                        Rc::clone(self),
+                        Rc::clone(&ctx.clock),
+                        Rc::clone(&ctx.interrupts),
+                        Rc::clone(&ctx.environment),
+                    );
+                }
+            }
+            FCS => {
+                inner.fifo_enabled = value & FCS_EN != 0;
+                inner.fifo_shift = value & FCS_SHIFT != 0;
+                inner.fifo_err = value & FCS_ERR != 0;
+                inner.dreq_enabled = value & FCS_DREQ_EN != 0;
+                inner.fifo_threshold = ((value >> 24) & 0xF) as u8;
+
+                if value & FCS_UNDER != 0 {
+                    inner.underflow = false;
+                }
+
+                if value & FCS_OVER != 0 {
+                    inner.overflow = false;
+                }
+
+                inner.update_interrupt(&ctx.interrupts);
+            }
+            DIV => inner.div = value & 0xFF_FFFF,
+            INTE => {
+                inner.inte = value & 1 != 0;
+                inner.update_interrupt(&ctx.interrupts);
+            }
+            INTF => {
+                inner.intf = value & 1 != 0;
+                inner.update_interrupt(&ctx.interrupts);
+            }
+            RESULT | FIFO | INTR | INTS => { /* Read only */ }
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::ThermalScenario;
+
+    #[test]
+    fn test_temperature_sensor_follows_the_scenario() {
+        let ctx = PeripheralAccessContext::default();
+        let mut adc = Rc::new(RefCell::new(Adc::default()));
+        let select_sensor = CS_EN | CS_TS_EN | (TEMPERATURE_SENSOR_CHANNEL as u32) << 12;
+
+        adc.write(CS, select_sensor | CS_START_ONCE, &ctx).unwrap();
+        let cold = adc.read(RESULT, &ctx).unwrap();
+
+        // Convert the reading back the way the SDK examples do
+        let voltage = cold as f64 * ADC_VREF / 4096.0;
+        let temperature = 27.0 - (voltage - 0.706) / 0.001721;
+        assert!((temperature - 27.0).abs() < 1.0);
+
+        ctx.environment.borrow_mut().ambient_temperature = 85.0;
+        adc.write(CS, select_sensor | CS_START_ONCE, &ctx).unwrap();
+        let hot = adc.read(RESULT, &ctx).unwrap();
+
+        // The sensor voltage drops as the die heats up
+        assert!(hot < cold);
+
+        ctx.environment
+            .borrow_mut()
+            .run_scenario(ThermalScenario::new(-20.0), &ctx.clock);
+        adc.write(CS, select_sensor | CS_START_ONCE, &ctx).unwrap();
+        assert!(adc.read(RESULT, &ctx).unwrap() > cold);
+    }
+
+    #[test]
+    fn test_free_running_fills_fifo() {
+        let ctx = PeripheralAccessContext::default();
+        let mut adc = Rc::new(RefCell::new(Adc::default()));
+        adc.borrow_mut().inputs[0] = ADC_VREF / 2.0;
+
+        adc.write(FCS, FCS_EN | 2 << 24, &ctx).unwrap();
+        adc.write(INTE, 1, &ctx).unwrap();
+        adc.write(CS, CS_EN | CS_START_MANY, &ctx).unwrap();
+
+        let period = adc.borrow().sample_period(&ctx.clock);
+        for _ in 0..period * 2 {
+            ctx.clock.tick();
+        }
+
+        assert_eq!(adc.read(FCS, &ctx).unwrap() >> 16 & 0xF, 2);
+        assert_eq!(adc.read(INTS, &ctx).unwrap(), 1);
+        assert_eq!(adc.read(FIFO, &ctx).unwrap(), 2048);
+    }
+}
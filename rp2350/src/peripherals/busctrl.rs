@@ -23,7 +23,8 @@ pub enum PerformanceEventType {
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 // default to 0x1f => SRAM6 Access
-enum PerformanceEventSource {
+// also the downstream ports of the bus fabric, each one serving a single access at a time
+pub(crate) enum PerformanceEventSource {
     SiobProc1,
     SiobProc0,
     Apb,
@@ -61,7 +62,9 @@ impl From<PerformanceEvent> for u32 {
 
 // Map address to the performance event source
 impl PerformanceEventSource {
-    fn from_address(address: u32, master: Requestor) -> PerformanceEventSource {
+    pub(crate) const COUNT: usize = PerformanceEventSource::Reserved as usize + 1;
+
+    pub(crate) fn from_address(address: u32, master: Requestor) -> PerformanceEventSource {
         let base_address = address & 0xF000_0000;
         match base_address {
             Bus::ROM => PerformanceEventSource::Rom,
@@ -72,17 +75,17 @@ impl PerformanceEventSource {
             },
             Bus::ABP => PerformanceEventSource::Apb,
             Bus::AHB => PerformanceEventSource::Fastperi,
-            Bus::SRAM => match (address, (address >> 2) & 0b11) {
-                (0x2000_0000..=0x2003_FFFF, 0) => PerformanceEventSource::Sram0,
-                (0x2000_0000..=0x2003_FFFF, 1) => PerformanceEventSource::Sram1,
-                (0x2000_0000..=0x2003_FFFF, 2) => PerformanceEventSource::Sram2,
-                (0x2000_0000..=0x2003_FFFF, 3) => PerformanceEventSource::Sram3,
-                (0x2004_0000..=0x2007_FFFF, 0) => PerformanceEventSource::Sram4,
-                (0x2004_0000..=0x2007_FFFF, 1) => PerformanceEventSource::Sram5,
-                (0x2004_0000..=0x2007_FFFF, 2) => PerformanceEventSource::Sram6,
-                (0x2004_0000..=0x2007_FFFF, 3) => PerformanceEventSource::Sram7,
-                (0x2008_0000, _) => PerformanceEventSource::Sram8,
-                (0x2008_1000, _) => PerformanceEventSource::Sram9,
+            Bus::SRAM => match sram_bank(address) {
+                Some(0) => PerformanceEventSource::Sram0,
+                Some(1) => PerformanceEventSource::Sram1,
+                Some(2) => PerformanceEventSource::Sram2,
+                Some(3) => PerformanceEventSource::Sram3,
+                Some(4) => PerformanceEventSource::Sram4,
+                Some(5) => PerformanceEventSource::Sram5,
+                Some(6) => PerformanceEventSource::Sram6,
+                Some(7) => PerformanceEventSource::Sram7,
+                Some(8) => PerformanceEventSource::Sram8,
+                Some(9) => PerformanceEventSource::Sram9,
                 _ => PerformanceEventSource::Reserved,
             },
             Bus::XIP => match master {
@@ -143,6 +146,7 @@ impl BusCtrl {
     pub const PRIORITY_PROC1: u32 = 1 << 4;
     pub const PRIORITY_DMA_R: u32 = 1 << 8;
     pub const PRIORITY_DMA_W: u32 = 1 << 12;
+    const PRIORITY_MASK: u32 = 0x1111;
 
     pub fn has_priority(&self, master: Requestor) -> bool {
         match master {
@@ -153,16 +157,26 @@ impl BusCtrl {
         }
     }
 
+    pub fn is_counting(&self) -> bool {
+        self.perfctr_en
+    }
+
     pub fn count(&mut self, address: u32, master: Requestor, event: PerformanceEventType) {
+        let source = PerformanceEventSource::from_address(address, master);
+        self.count_source(source, event);
+    }
+
+    pub(crate) fn count_source(
+        &mut self,
+        source: PerformanceEventSource,
+        event: PerformanceEventType,
+    ) {
         // Disable by default
         if !self.perfctr_en {
             return;
         }
 
-        let event = PerformanceEvent {
-            source: PerformanceEventSource::from_address(address, master),
-            event,
-        };
+        let event = PerformanceEvent { source, event };
 
         for i in 0..4 {
             if self.perfsel[i] == event {
@@ -200,7 +214,7 @@ impl Peripheral for BusCtrl {
         _ctx: &PeripheralAccessContext,
     ) -> PeripheralResult<()> {
         match addr & 0xFFF {
-            0x00 => self.priority = value,
+            0x00 => self.priority = value & Self::PRIORITY_MASK,
             0x04 => (), /* Priority Ack, ignore */
             0x08 => self.perfctr_en = value & 1 == 1,
             0x0C => self.perfctr[0] = 0, // Reset counter
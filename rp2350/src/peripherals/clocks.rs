@@ -3,9 +3,9 @@
  * @author Nguyen Le Duy
  * @date 06/03/2025
  * @brief Clock peripheral implementation
- * @todo Handle the clock source selection and frequency counting
  */
 use super::*;
+use crate::clock::Frequencies;
 
 pub const CLK_GPOUT0_CTRL: u16 = 0x00; // Clock control, can be changed on-the-fly (except for auxsrc)
 pub const CLK_GPOUT0_DIV: u16 = 0x04; // Clock control, can be changed on-the-fly (except for auxsrc)
@@ -61,6 +61,26 @@ pub const INTE: u16 = 0xC8; // Interrupt Enable
 pub const INTF: u16 = 0xCC; // Interrupt Force
 pub const INTS: u16 = 0xD0; // Interrupt status after masking & forcing
 
+pub const FC0_STATUS_PASS: u32 = 1 << 0;
+pub const FC0_STATUS_DONE: u32 = 1 << 4;
+pub const FC0_STATUS_FAIL: u32 = 1 << 16;
+pub const FC0_STATUS_SLOW: u32 = 1 << 20;
+pub const FC0_STATUS_FAST: u32 = 1 << 24;
+pub const FC0_STATUS_DIED: u32 = 1 << 28;
+
+pub const FC0_SRC_ROSC_CLKSRC: u8 = 0x03;
+pub const FC0_SRC_ROSC_CLKSRC_PH: u8 = 0x04;
+
+/// Starts the clocks with an ENABLE bit, every clock but clk_ref and clk_sys
+pub const CTRL_ENABLE: u32 = 1 << 11;
+
+pub const CLK_REF_SRC_ROSC: u32 = 0x0;
+pub const CLK_REF_SRC_AUX: u32 = 0x1;
+pub const CLK_REF_SRC_XOSC: u32 = 0x2;
+pub const CLK_SYS_SRC_AUX: u32 = 0x1;
+
+const LPOSC_FREQUENCY: u64 = 32_768;
+
 pub struct ClockState<const DIV_MASK: u32> {
     ctrl: u32,
     div: u32,
@@ -68,15 +88,17 @@ pub struct ClockState<const DIV_MASK: u32> {
 
 impl<const DIV_MASK: u32> Default for ClockState<DIV_MASK> {
     fn default() -> Self {
-        Self {
-            ctrl: 0,
-            div: 1 << 16,
-        }
+        Self::with_ctrl(0)
     }
 }
 
 impl<const DIV_MASK: u32> ClockState<DIV_MASK> {
     const CLK_ENABLE_MASK: u32 = 1 << 28;
+
+    fn with_ctrl(ctrl: u32) -> Self {
+        Self { ctrl, div: 1 << 16 }
+    }
+
     pub fn is_enabled(&self) -> bool {
         // 28th bit is the enabled
         (self.ctrl & Self::CLK_ENABLE_MASK) != 0
@@ -98,6 +120,32 @@ impl<const DIV_MASK: u32> ClockState<DIV_MASK> {
         self.div & (DIV_MASK & 0xFFFF)
     }
 
+    pub fn src(&self) -> u32 {
+        self.ctrl & 0b11
+    }
+
+    pub fn auxsrc(&self) -> u32 {
+        (self.ctrl >> 5) & 0b111
+    }
+
+    /// Frequency out of the divider, an integer part of 0 divides by its largest value plus one
+    pub fn divide(&self, frequency: u64) -> u64 {
+        let int = match self.clk_div_int() {
+            0 => (DIV_MASK >> 16) as u64 + 1,
+            int => int as u64,
+        };
+
+        frequency * (1 << 16) / ((int << 16) | self.clk_div_frac() as u64)
+    }
+
+    /// Divided output of the clocks with an ENABLE bit, stopped while it is clear
+    fn gated(&self, frequency: u64) -> u64 {
+        match self.ctrl & CTRL_ENABLE {
+            0 => 0,
+            _ => self.divide(frequency),
+        }
+    }
+
     fn write_ctrl(&mut self, value: u32) {
         // clear all bits except the 28th
         self.ctrl &= Self::CLK_ENABLE_MASK;
@@ -139,10 +187,6 @@ pub struct Clocks {
     pub clock_en_wake: [u32; 2],
     pub clock_en_sleep: [u32; 2],
 
-    // Dirty workaround for the simulator
-    clock_sys_selected: u8,
-    clock_ref_selected: u8,
-
     interrupt_enabled: bool,
     interrupt_force: bool,
     // TODO
@@ -156,18 +200,124 @@ impl Clocks {
     pub fn mode(&self) -> ClockMode {
         self.mode
     }
+
+    /// Frequency in Hz of a source of the frequency counter
+    fn fc0_source_frequency(&self, src: u8, ctx: &PeripheralAccessContext) -> f64 {
+        let clock = &ctx.clock;
+        let frequency = match src {
+            0x01 | 0x09 => clock.clk_sys(), // pll_sys_clksrc_primary, clk_sys
+            0x02 | 0x0b => clock.clk_usb(), // pll_usb_clksrc_primary, clk_usb
+            0x0c => clock.clk_adc(),
+            FC0_SRC_ROSC_CLKSRC | FC0_SRC_ROSC_CLKSRC_PH => {
+                let rosc = ctx.rosc.borrow();
+                return rosc.frequency(&ctx.environment.borrow(), clock);
+            }
+            0x05 | 0x08 => clock.clk_ref(), // xosc_clksrc, clk_ref
+            0x0a => clock.clk_peri(),
+            0x0d => clock.clk_hstx(),
+            0x0e => LPOSC_FREQUENCY, // lposc_clksrc
+            _ => 0,
+        };
+
+        frequency as f64
+    }
+
+    /// Derive the clock domains from the outputs of the oscillators and the PLLs, the GPIN
+    /// inputs are not driven
+    pub fn frequencies(&self, xosc: u64, rosc: u64, pll_sys: u64, pll_usb: u64) -> Frequencies {
+        let clk_ref = self.clk_ref.divide(match self.clk_ref.src() {
+            CLK_REF_SRC_ROSC => rosc,
+            CLK_REF_SRC_AUX if self.clk_ref.auxsrc() == 0 => pll_usb,
+            CLK_REF_SRC_AUX => 0,
+            CLK_REF_SRC_XOSC => xosc,
+            _ => LPOSC_FREQUENCY,
+        });
+
+        let clk_sys = match self.clk_sys.src() & CLK_SYS_SRC_AUX {
+            0 => clk_ref,
+            _ => match self.clk_sys.auxsrc() {
+                0 => pll_sys,
+                1 => pll_usb,
+                2 => rosc,
+                3 => xosc,
+                _ => 0,
+            },
+        };
+        let clk_sys = self.clk_sys.divide(clk_sys);
+
+        let clk_peri = self.clk_peri.gated(match self.clk_peri.auxsrc() {
+            0 => clk_sys,
+            1 => pll_sys,
+            2 => pll_usb,
+            3 => rosc,
+            4 => xosc,
+            _ => 0,
+        });
+
+        // clk_usb and clk_adc have the same sources
+        let usb_source = |auxsrc| match auxsrc {
+            0 => pll_usb,
+            1 => pll_sys,
+            2 => rosc,
+            3 => xosc,
+            _ => 0,
+        };
+
+        let clk_hstx = self.clk_hstx.gated(match self.clk_hstx.auxsrc() {
+            0 => clk_sys,
+            1 => pll_sys,
+            2 => pll_usb,
+            _ => 0,
+        });
+
+        Frequencies {
+            xosc,
+            pll_sys,
+            pll_usb,
+            clk_ref,
+            clk_sys,
+            clk_peri,
+            clk_usb: self.clk_usb.gated(usb_source(self.clk_usb.auxsrc())),
+            clk_adc: self.clk_adc.gated(usb_source(self.clk_adc.auxsrc())),
+            clk_hstx,
+        }
+    }
+
+    /// The measurement completes right away, the test interval is not simulated
+    fn measure_frequency(&mut self, ctx: &PeripheralAccessContext) {
+        if self.fc0_src == 0 {
+            self.fc0_status = 0;
+            return;
+        }
+
+        let khz = self.fc0_source_frequency(self.fc0_src, ctx) / 1000.0;
+        let whole = khz as u32;
+
+        self.fc0_result = (whole & 0x1FF_FFFF) << 5 | ((khz.fract() * 32.0) as u32 & 0x1F);
+        self.fc0_status = if whole == 0 {
+            FC0_STATUS_DONE | FC0_STATUS_DIED | FC0_STATUS_FAIL
+        } else if whole < self.fc0_min_khz {
+            FC0_STATUS_DONE | FC0_STATUS_SLOW | FC0_STATUS_FAIL
+        } else if whole > self.fc0_max_khz {
+            FC0_STATUS_DONE | FC0_STATUS_FAST | FC0_STATUS_FAIL
+        } else {
+            FC0_STATUS_DONE | FC0_STATUS_PASS
+        };
+    }
 }
 
 impl Default for Clocks {
+    /// The clocks as the SDK sets them up, the simulator does not start from the ring
+    /// oscillator like the chip
     fn default() -> Self {
         Self {
             gp_outs: Default::default(),
-            clk_ref: Default::default(),
-            clk_sys: Default::default(),
-            clk_peri: Default::default(),
-            clk_hstx: Default::default(),
-            clk_usb: Default::default(),
-            clk_adc: Default::default(),
+            clk_ref: ClockState::with_ctrl(CLK_REF_SRC_XOSC),
+            clk_sys: ClockState::with_ctrl(CLK_SYS_SRC_AUX),
+            clk_peri: ClockState::with_ctrl(CTRL_ENABLE),
+            clk_hstx: ClockState::with_ctrl(CTRL_ENABLE),
+            clk_usb: ClockState::with_ctrl(CTRL_ENABLE),
+            clk_adc: ClockState::with_ctrl(CTRL_ENABLE),
             dftclk_xosc_ctrl: 0,
             dftclk_rosc_ctrl: 0,
             dftclk_losc_ctrl: 0,
@@ -186,15 +336,13 @@ impl Default for Clocks {
             interrupt_enabled: false,
             interrupt_force: false,
             clk_sys_resus_status: false,
-            clock_sys_selected: 1,
-            clock_ref_selected: 1,
         }
     }
 }
 
 impl Peripheral for Rc<RefCell<Clocks>> {
     fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
-        let mut clocks = self.borrow_mut();
+        let clocks = self.borrow();
         let value = match address {
             CLK_GPOUT0_CTRL => clocks.gp_outs[0].ctrl,
             CLK_GPOUT0_DIV => clocks.gp_outs[0].div,
@@ -206,27 +354,11 @@ impl Peripheral for Rc<RefCell<Clocks>> {
             CLK_GPOUT3_DIV => clocks.gp_outs[3].div,
             CLK_REF_CTRL => clocks.clk_ref.ctrl,
             CLK_REF_DIV => clocks.clk_ref.div,
-            CLK_REF_SELECTED => {
-                // dirty hack for the simulator
-                clocks.clock_ref_selected <<= 1;
-                clocks.clock_ref_selected &= 0b111;
-                if clocks.clock_ref_selected == 0 {
-                    clocks.clock_ref_selected = 1;
-                }
-
-                clocks.clock_ref_selected as u32
-            }
+            // the glitchless muxes switch right away
+            CLK_REF_SELECTED => 1 << clocks.clk_ref.src(),
             CLK_SYS_CTRL => clocks.clk_sys.ctrl,
             CLK_SYS_DIV => clocks.clk_sys.div,
-            CLK_SYS_SELECTED => {
-                // dirty hack for the simulator
-                clocks.clock_sys_selected <<= 1;
-                clocks.clock_sys_selected &= 0b111;
-                if clocks.clock_sys_selected == 0 {
-                    clocks.clock_sys_selected = 1;
-                }
-                clocks.clock_sys_selected as u32
-            }
+            CLK_SYS_SELECTED => 1 << (clocks.clk_sys.src() & CLK_SYS_SRC_AUX),
             CLK_PERI_CTRL => clocks.clk_peri.ctrl,
             CLK_PERI_DIV => clocks.clk_peri.div,
             CLK_HSTX_CTRL => clocks.clk_hstx.ctrl,
@@ -283,11 +415,29 @@ impl Peripheral for Rc<RefCell<Clocks>> {
         Ok(value)
     }
 
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
     fn write_raw(
         &mut self,
         address: u16,
         value: u32,
-        _ctx: &PeripheralAccessContext,
+        ctx: &PeripheralAccessContext,
     ) -> PeripheralResult<()> {
         let mut clocks = self.borrow_mut();
         match address {
@@ -319,7 +469,10 @@ impl Peripheral for Rc<RefCell<Clocks>> {
             FC0_MAX_KHZ => clocks.fc0_max_khz = value,
             FC0_DELAY => clocks.fc0_delay = value as u8,
             FC0_INTERVAL => clocks.fc0_interval = value as u8,
-            FC0_SRC => clocks.fc0_src = value as u8,
+            FC0_SRC => {
+                clocks.fc0_src = value as u8;
+                clocks.measure_frequency(ctx);
+            }
             WAKE_EN0 => clocks.clock_en_wake[0] = value,
             WAKE_EN1 => clocks.clock_en_wake[1] = value,
             SLEEP_EN0 => clocks.clock_en_sleep[0] = value,
@@ -352,3 +505,46 @@ impl Peripheral for Rc<RefCell<Clocks>> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEAR: u16 = 0x3000;
+
+    fn derive(clocks: &Rc<RefCell<Clocks>>) -> Frequencies {
+        clocks
+            .borrow()
+            .frequencies(12 * MHZ, 11 * MHZ, 150 * MHZ, 48 * MHZ)
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let ctx = PeripheralAccessContext::default();
+        let mut clocks = Rc::new(RefCell::new(Clocks::default()));
+        assert_eq!(derive(&clocks), Frequencies::default());
+
+        clocks.write(CLK_SYS_DIV, 2 << 16, &ctx).unwrap();
+        let frequencies = derive(&clocks);
+        assert_eq!(frequencies.clk_sys, 75 * MHZ);
+        assert_eq!(frequencies.clk_peri, 75 * MHZ);
+        assert_eq!(frequencies.clk_usb, 48 * MHZ);
+
+        // back to clk_ref, the crystal
+        clocks
+            .write(CLEAR | CLK_SYS_CTRL, CLK_SYS_SRC_AUX, &ctx)
+            .unwrap();
+        assert_eq!(clocks.read(CLK_SYS_SELECTED, &ctx), Ok(1));
+        assert_eq!(derive(&clocks).clk_sys, 6 * MHZ);
+
+        clocks
+            .write(CLK_PERI_CTRL, CTRL_ENABLE | (2 << 5), &ctx)
+            .unwrap();
+        assert_eq!(derive(&clocks).clk_peri, 48 * MHZ);
+
+        clocks
+            .write(CLEAR | CLK_PERI_CTRL, CTRL_ENABLE, &ctx)
+            .unwrap();
+        assert_eq!(derive(&clocks).clk_peri, 0);
+    }
+}
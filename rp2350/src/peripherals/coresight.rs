@@ -0,0 +1,77 @@
+/**
+ * @file peripherals/coresight.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief CoreSight debug components, the timestamp generator and the trace path to the TPIU
+ */
+use super::*;
+
+pub mod funnel;
+pub mod timestamp;
+pub mod tpiu;
+
+pub use funnel::AtbFunnel;
+pub use timestamp::TimestampGenerator;
+pub use tpiu::Tpiu;
+
+// Management registers at the end of the 4 KB block of every component
+pub const CLAIMSET: u16 = 0xFA0;
+pub const CLAIMCLR: u16 = 0xFA4;
+pub const LAR: u16 = 0xFB0;
+pub const LSR: u16 = 0xFB4;
+pub const AUTHSTATUS: u16 = 0xFB8;
+pub const DEVID: u16 = 0xFC8;
+pub const DEVTYPE: u16 = 0xFCC;
+pub const PIDR4: u16 = 0xFD0;
+pub const PIDR0: u16 = 0xFE0;
+pub const PIDR1: u16 = 0xFE4;
+pub const PIDR2: u16 = 0xFE8;
+pub const PIDR3: u16 = 0xFEC;
+pub const CIDR0: u16 = 0xFF0;
+pub const CIDR1: u16 = 0xFF4;
+pub const CIDR2: u16 = 0xFF8;
+pub const CIDR3: u16 = 0xFFC;
+
+/// JEP106 identity code of Arm
+const JEP106_ARM: u32 = 0x3B;
+
+/// What a debugger reads to discover a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentId {
+    pub part: u16,
+    pub revision: u8,
+    /// 0x9 for the CoreSight components, 0xF for the generic IPs
+    pub class: u8,
+    pub devtype: u8,
+    pub devid: u32,
+}
+
+impl ComponentId {
+    /// The identification and management registers, `None` for the other offsets.
+    /// The lock and the claim tags are not implemented
+    pub fn read(&self, address: u16) -> Option<u32> {
+        let value = match address {
+            CLAIMSET | CLAIMCLR | LAR | LSR | AUTHSTATUS => 0,
+            DEVID => self.devid,
+            DEVTYPE => self.devtype as u32,
+            // a single 4 KB block, continuation code of Arm
+            PIDR4 => 0x04,
+            PIDR0 => self.part as u32 & 0xff,
+            PIDR1 => (self.part as u32 >> 8) & 0xf | (JEP106_ARM & 0xf) << 4,
+            PIDR2 => (self.revision as u32) << 4 | 1 << 3 | JEP106_ARM >> 4,
+            PIDR3 => 0,
+            CIDR0 => 0x0D,
+            CIDR1 => (self.class as u32) << 4,
+            CIDR2 => 0x05,
+            CIDR3 => 0xB1,
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    /// The writes to the management registers are ignored, `None` for the other offsets
+    pub fn write(&self, address: u16) -> Option<()> {
+        (address >= CLAIMSET).then_some(())
+    }
+}
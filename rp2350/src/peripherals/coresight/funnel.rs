@@ -0,0 +1,78 @@
+/**
+ * @file peripherals/coresight/funnel.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief CoreSight ATB funnel, merges the trace of both cores into the stream sent to the TPIU
+ */
+use super::*;
+
+pub const CTRL: u16 = 0x000;
+pub const PRIORITY: u16 = 0x004;
+
+/// One slave port per core, port N carries the trace of core N
+pub const NUM_PORTS: usize = 2;
+
+const CTRL_ENABLE: u32 = (1 << NUM_PORTS) - 1;
+const CTRL_HOLD_TIME: u32 = 0xf << 8;
+const PRIORITY_MASK: u32 = (1 << (3 * NUM_PORTS)) - 1;
+
+const ID: ComponentId = ComponentId {
+    part: 0x908,
+    revision: 1,
+    class: 0x9,
+    devtype: 0x12,
+    devid: NUM_PORTS as u32,
+};
+
+#[derive(Debug)]
+pub struct AtbFunnel {
+    ctrl: u32,
+    priority: u32,
+}
+
+impl Default for AtbFunnel {
+    fn default() -> Self {
+        Self {
+            // every port disabled, hold time of 4 transactions
+            ctrl: 0x3 << 8,
+            priority: 0,
+        }
+    }
+}
+
+impl AtbFunnel {
+    pub fn is_port_enabled(&self, port: u8) -> bool {
+        (port as usize) < NUM_PORTS && self.ctrl >> port & 1 != 0
+    }
+}
+
+impl Peripheral for Rc<RefCell<AtbFunnel>> {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let funnel = self.borrow();
+
+        let value = match address {
+            CTRL => funnel.ctrl,
+            PRIORITY => funnel.priority,
+            _ => ID.read(address).ok_or(PeripheralError::OutOfBounds)?,
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut funnel = self.borrow_mut();
+
+        match address {
+            CTRL => funnel.ctrl = value & (CTRL_ENABLE | CTRL_HOLD_TIME),
+            PRIORITY => funnel.priority = value & PRIORITY_MASK,
+            _ => ID.write(address).ok_or(PeripheralError::OutOfBounds)?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+/**
+ * @file peripherals/coresight/timestamp.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief CoreSight timestamp generator, the 64 bit counter the trace sources stamp their packets with
+ */
+use super::*;
+
+pub const CNTCR: u16 = 0x000;
+pub const CNTSR: u16 = 0x004;
+pub const CNTCVL: u16 = 0x008;
+pub const CNTCVU: u16 = 0x00C;
+pub const CNTFID0: u16 = 0x020;
+
+pub const CNTCR_EN: u32 = 1 << 0;
+/// Halt on debug, the counter never stops in the simulation
+pub const CNTCR_HDBG: u32 = 1 << 1;
+
+const ID: ComponentId = ComponentId {
+    part: 0x101,
+    revision: 0,
+    class: 0xF,
+    devtype: 0,
+    devid: 0,
+};
+
+/// Counts the cycles of clk_sys while enabled, the value is derived from the ticks of the
+/// clock when read
+#[derive(Debug, Default)]
+pub struct TimestampGenerator {
+    ctrl: u32,
+    /// Value of the counter at the tick `since`
+    base: u64,
+    since: u64,
+    /// CNTFID0, the frequency of the counter as told to the software
+    pub frequency: u32,
+}
+
+impl TimestampGenerator {
+    pub fn is_enabled(&self) -> bool {
+        self.ctrl & CNTCR_EN != 0
+    }
+
+    /// Value of the counter at the tick `now` of the clock
+    pub fn value(&self, now: u64) -> u64 {
+        match self.is_enabled() {
+            true => self.base.wrapping_add(now.saturating_sub(self.since)),
+            false => self.base,
+        }
+    }
+
+    pub fn set_value(&mut self, value: u64, now: u64) {
+        self.base = value;
+        self.since = now;
+    }
+
+    pub fn set_ctrl(&mut self, value: u32, now: u64) {
+        // keep the count up to now before it starts or stops
+        self.set_value(self.value(now), now);
+        self.ctrl = value & (CNTCR_EN | CNTCR_HDBG);
+    }
+}
+
+impl Peripheral for Rc<RefCell<TimestampGenerator>> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let tsgen = self.borrow();
+        let now = *ctx.clock.ticks.borrow();
+
+        let value = match address {
+            CNTCR => tsgen.ctrl,
+            CNTSR => 0,
+            CNTCVL => tsgen.value(now) as u32,
+            CNTCVU => (tsgen.value(now) >> 32) as u32,
+            CNTFID0 => tsgen.frequency,
+            _ => ID.read(address).ok_or(PeripheralError::OutOfBounds)?,
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut tsgen = self.borrow_mut();
+        let now = *ctx.clock.ticks.borrow();
+
+        match address {
+            CNTCR => tsgen.set_ctrl(value, now),
+            CNTSR => { /* read-only */ }
+            CNTCVL => {
+                let upper = tsgen.value(now) & !0xffff_ffff;
+                tsgen.set_value(upper | value as u64, now);
+            }
+            CNTCVU => {
+                let lower = tsgen.value(now) & 0xffff_ffff;
+                tsgen.set_value((value as u64) << 32 | lower, now);
+            }
+            CNTFID0 => tsgen.frequency = value,
+            _ => ID.write(address).ok_or(PeripheralError::OutOfBounds)?,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter() {
+        let ctx = PeripheralAccessContext::default();
+        let mut tsgen = Rc::new(RefCell::new(TimestampGenerator::default()));
+
+        // stopped out of reset
+        *ctx.clock.ticks.borrow_mut() = 100;
+        assert_eq!(tsgen.read(CNTCVL, &ctx), Ok(0));
+
+        tsgen.write(CNTCVU, 1, &ctx).unwrap();
+        tsgen.write(CNTCR, CNTCR_EN, &ctx).unwrap();
+        *ctx.clock.ticks.borrow_mut() = 150;
+        assert_eq!(tsgen.read(CNTCVL, &ctx), Ok(50));
+        assert_eq!(tsgen.read(CNTCVU, &ctx), Ok(1));
+
+        tsgen.write(CNTCR, 0, &ctx).unwrap();
+        *ctx.clock.ticks.borrow_mut() = 200;
+        assert_eq!(tsgen.borrow().value(200), 1 << 32 | 50);
+
+        assert_eq!(tsgen.read(PIDR0, &ctx), Ok(0x01));
+        assert_eq!(tsgen.read(PIDR1, &ctx), Ok(0xB1));
+        assert_eq!(tsgen.read(CIDR1, &ctx), Ok(0xF0));
+    }
+}
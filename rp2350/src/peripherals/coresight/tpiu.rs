@@ -0,0 +1,110 @@
+/**
+ * @file peripherals/coresight/tpiu.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief CoreSight TPIU, formats the trace stream for the trace port
+ */
+use super::*;
+
+pub const SSPSR: u16 = 0x000;
+pub const CSPSR: u16 = 0x004;
+pub const STMR: u16 = 0x100;
+pub const TCVR: u16 = 0x104;
+pub const TMR: u16 = 0x108;
+pub const FFSR: u16 = 0x300;
+pub const FFCR: u16 = 0x304;
+pub const FSCR: u16 = 0x308;
+
+/// Trace port of 1, 2 or 4 bits
+pub const SUPPORTED_PORT_SIZES: u32 = 0b1011;
+
+pub const FFSR_FT_STOPPED: u32 = 1 << 1;
+
+pub const FFCR_EN_FTC: u32 = 1 << 0;
+pub const FFCR_EN_FCONT: u32 = 1 << 1;
+pub const FFCR_FON_MAN: u32 = 1 << 6;
+const FFCR_MASK: u32 = 0x3173;
+
+const ID: ComponentId = ComponentId {
+    part: 0x912,
+    revision: 4,
+    class: 0x9,
+    devtype: 0x11,
+    devid: 0xCA0,
+};
+
+#[derive(Debug)]
+pub struct Tpiu {
+    /// One-hot, bit N for a port of N + 1 bits
+    pub port_size: u32,
+    trigger_counter: u32,
+    trigger_multiplier: u32,
+    pub formatter_ctrl: u32,
+    sync_counter: u32,
+}
+
+impl Default for Tpiu {
+    fn default() -> Self {
+        Self {
+            port_size: 1,
+            trigger_counter: 0,
+            trigger_multiplier: 0,
+            formatter_ctrl: 0,
+            sync_counter: 0x40,
+        }
+    }
+}
+
+impl Tpiu {
+    /// The formatter runs, the trace goes out of the port
+    pub fn is_formatting(&self) -> bool {
+        self.formatter_ctrl & (FFCR_EN_FTC | FFCR_EN_FCONT) != 0
+    }
+}
+
+impl Peripheral for Rc<RefCell<Tpiu>> {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let tpiu = self.borrow();
+
+        let value = match address {
+            SSPSR => SUPPORTED_PORT_SIZES,
+            CSPSR => tpiu.port_size,
+            STMR => 0,
+            TCVR => tpiu.trigger_counter,
+            TMR => tpiu.trigger_multiplier,
+            FFSR if tpiu.is_formatting() => 0,
+            FFSR => FFSR_FT_STOPPED,
+            FFCR => tpiu.formatter_ctrl,
+            FSCR => tpiu.sync_counter,
+            _ => ID.read(address).ok_or(PeripheralError::OutOfBounds)?,
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut tpiu = self.borrow_mut();
+
+        match address {
+            // a single size at a time, among the supported ones
+            CSPSR if value.count_ones() == 1 && value & SUPPORTED_PORT_SIZES != 0 => {
+                tpiu.port_size = value
+            }
+            CSPSR => log::warn!("TPIU: unsupported trace port size {value:#x}"),
+            SSPSR | STMR | FFSR => { /* read-only */ }
+            TCVR => tpiu.trigger_counter = value & 0xff,
+            TMR => tpiu.trigger_multiplier = value & 0x1f,
+            // the manual flush completes right away
+            FFCR => tpiu.formatter_ctrl = value & FFCR_MASK & !FFCR_FON_MAN,
+            FSCR => tpiu.sync_counter = value & 0xfff,
+            _ => ID.write(address).ok_or(PeripheralError::OutOfBounds)?,
+        }
+
+        Ok(())
+    }
+}
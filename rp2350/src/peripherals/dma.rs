@@ -10,6 +10,7 @@ use self::channel::TransferMode;
 use super::*;
 use crate::bus::{Bus, BusAccessContext, LoadStatus, StoreStatus};
 use crate::clock::EventType;
+use crate::inspector::InspectionEvent;
 use crate::interrupts::{Interrupt, Interrupts};
 use crate::utils::{clear_bits, w1c, Fifo};
 use std::cell::RefCell;
@@ -17,9 +18,11 @@ use std::ops::DerefMut;
 use std::rc::Rc;
 
 mod channel;
+mod sniffer;
 mod timer;
 
 use channel::{Channel, TreqSel};
+use sniffer::Sniffer;
 use timer::Timer;
 
 const NOF_CHANNEL: usize = 16;
@@ -60,6 +63,8 @@ pub const N_CHANNELS: u16 = 0x468;
 
 // 16 channels
 pub const SECCFG_CHN: u16 = 0x480;
+pub const SECCFG_P: u8 = 1 << 0; // the transfers of the channel are privileged
+pub const SECCFG_S: u8 = 1 << 1; // the transfers of the channel are Secure
 
 // 4 interrupt channels
 pub const SECCFG_IRQN: u16 = 0x4c0;
@@ -76,6 +81,10 @@ pub const MPU_LARN: u16 = 0x508;
 pub const CHN_DBG_CTDREQ: u16 = 0x800;
 pub const CHN_DBG_TCR: u16 = 0x804;
 
+// EN up to SNIFF_EN, the upper bits are the status of the channel
+const CTRL_WRITABLE_MASK: u32 = 0x03ff_ffff;
+const TRANSFER_COUNT_MASK: u32 = 0x0fff_ffff;
+
 // Offset between each register
 pub const CHANNEL_REGISTER_OFFSET: u16 = 0x040;
 pub const INT_REGISTER_OFFSET: u16 = 0x010;
@@ -98,10 +107,12 @@ pub struct Dma {
     pub interrupt_force: [u16; 4],
     pub interrupt_secure: [u8; 4],
     pub seccfg: u16,
+    pub sniffer: Sniffer,
     pub fifo: Fifo<FifoValue, NOF_CHANNEL>,
     pub channel_round_robin: Fifo<usize, NOF_CHANNEL>,
     current_read: Option<Rc<RefCell<LoadStatus>>>,
-    current_write: Option<Rc<RefCell<StoreStatus>>>,
+    /// The store in flight and the channel doing it
+    current_write: Option<(usize, Rc<RefCell<StoreStatus>>)>,
 }
 
 impl Default for Dma {
@@ -133,6 +144,7 @@ impl Default for Dma {
             interrupt_force: [0; 4],
             interrupt_secure: [0; 4],
             seccfg: 0,
+            sniffer: Sniffer::default(),
             fifo: Fifo::default(),
             current_read: None,
             current_write: None,
@@ -172,11 +184,11 @@ impl Dma {
     }
 
     fn read(&mut self, bus: &mut Bus) {
+        // one read in flight at a time
         if self
             .current_read
             .as_ref()
-            .filter(|v| v.borrow().is_done())
-            .is_none()
+            .is_some_and(|v| *v.borrow() == LoadStatus::Waiting)
         {
             return;
         }
@@ -184,7 +196,7 @@ impl Dma {
         self.current_read = None;
 
         let mut channel_idx = None;
-        for _ in 0..self.fifo.len() {
+        for _ in 0..self.channel_round_robin.len() {
             let Some(idx) = self.channel_round_robin.pop() else {
                 return;
             };
@@ -192,7 +204,9 @@ impl Dma {
             if self.channels[idx].is_enabled() && self.channels[idx].busy() {
                 self.add_channel_to_round_robin(idx);
 
-                if *self.channels[idx].ready_to_transfer.borrow() {
+                if *self.channels[idx].ready_to_transfer.borrow()
+                    && self.has_pending_read(idx)
+                {
                     channel_idx = Some(idx);
                     break;
                 }
@@ -205,14 +219,11 @@ impl Dma {
 
         let ref mut channel = self.channels[channel_idx];
 
-        if channel.transfer_count == 0 {
-            return;
-        }
-
         let load_status = bus.load(
             channel.read_addr,
             BusAccessContext {
-                secure: channel.secure != 0,
+                secure: channel.secure & SECCFG_S != 0,
+                privileged: channel.secure & SECCFG_P != 0,
                 requestor: Requestor::DmaR,
                 size: channel.datasize(),
                 signed: false,
@@ -233,23 +244,40 @@ impl Dma {
             }
 
             Err(_why) => {
-                // TODO
+                let address = channel.read_addr;
+                self.halt_on_error(channel_idx, address, false, bus);
             }
         }
     }
 
     fn write(&mut self, bus: &mut Bus) {
-        if self
+        let store = self
             .current_write
             .as_ref()
-            .filter(|v| v.borrow().is_done())
-            .is_none()
-        {
-            return;
+            .map(|(channel_idx, status)| (*channel_idx, *status.borrow()));
+
+        match store {
+            Some((_, StoreStatus::Waiting)) => return,
+            Some((channel_idx, StoreStatus::Error(_))) => {
+                // like on the hardware, the address reported is past the faulting one
+                let address = self.channels[channel_idx].write_addr;
+                self.halt_on_error(channel_idx, address, true, bus);
+            }
+            _ => {}
         }
 
         self.current_write = None;
 
+        // the data at the front of the FIFO is not read yet
+        if self
+            .fifo
+            .iter()
+            .next()
+            .is_some_and(|front| *front.value.borrow() == LoadStatus::Waiting)
+        {
+            return;
+        }
+
         let Some(fifo_value) = self.fifo.pop() else {
             return;
         };
@@ -262,6 +290,9 @@ impl Dma {
 
         let data_size = channel.datasize();
         let Some(mut value) = fifo_value.value.borrow_mut().value() else {
+            // the load of this data faulted
+            let address = channel.read_addr;
+            self.halt_on_error(fifo_value.channel, address, false, bus);
             return;
         };
 
@@ -276,11 +307,13 @@ impl Dma {
             }
         }
 
+        bus.set_dma_channel(fifo_value.channel as u8);
         let store_status = bus.store(
             channel.write_addr,
             value,
             BusAccessContext {
-                secure: channel.secure != 0,
+                secure: channel.secure & SECCFG_S != 0,
+                privileged: channel.secure & SECCFG_P != 0,
                 requestor: Requestor::DmaW,
                 size: channel.datasize(),
                 signed: false,
@@ -289,16 +322,25 @@ impl Dma {
             },
         );
 
+        if self.sniffer.is_enabled()
+            && self.sniffer.channel() == fifo_value.channel
+            && channel.sniff_en()
+        {
+            self.sniffer.feed(value, data_size);
+        }
+
         match store_status {
             Ok(status) => {
-                self.current_write = Some(status);
+                self.current_write = Some((fifo_value.channel, status));
                 channel.update_write_address();
 
                 if channel.transfer_mode() == TransferMode::Endless {
                     return;
                 }
 
-                if channel.transfer_count == 0 {
+                channel.transfer_count -= 1;
+
+                if channel.transfer_count & TRANSFER_COUNT_MASK == 0 {
                     channel.set_busy(false);
                     let chain_to = channel.chain_to() as usize;
                     let clock = Rc::clone(&bus.peripherals.clock);
@@ -322,14 +364,31 @@ impl Dma {
             }
 
             Err(_why) => {
-                // TODO
+                let address = channel.write_addr;
+                self.halt_on_error(fifo_value.channel, address, true, bus);
             }
         }
     }
 
+    /// A bus fault stops the channel with its error flag set, it raises its IRQ even when
+    /// IRQ_QUIET is set since the transfer will never complete
+    fn halt_on_error(&mut self, channel_idx: usize, address: u32, write: bool, bus: &Bus) {
+        let channel = &mut self.channels[channel_idx];
+        channel.set_error(write);
+        channel.set_busy(false);
+
+        self.interrupt_raw |= 1 << channel_idx;
+        self.update_irq(bus.peripherals.interrupts.borrow_mut().deref_mut());
+
+        bus.peripherals.inspector.emit(InspectionEvent::DmaError {
+            channel: channel_idx as u8,
+            address,
+            write,
+        });
+    }
+
     fn start_channel(&mut self, channel_idx: usize, clock: Rc<Clock>) {
         let ref mut channel = self.channels[channel_idx];
-        let transfer_count = channel.transfer_count;
 
         if !channel.is_enabled() || channel.busy() {
             return;
@@ -337,13 +396,33 @@ impl Dma {
 
         channel.set_busy(true);
         channel.transfer_count = channel.transfer_counter_reload;
+        let has_transfers = channel.transfer_count & TRANSFER_COUNT_MASK > 0
+            || channel.transfer_mode() == TransferMode::Endless;
+
         self.add_channel_to_round_robin(channel_idx);
 
-        if transfer_count > 0 {
+        if has_transfers {
             self.schedule_transfer(channel_idx, Rc::clone(&clock));
         }
     }
 
+    /// Whether the channel still has data to read, besides the one already in the FIFO
+    fn has_pending_read(&self, channel_idx: usize) -> bool {
+        let channel = &self.channels[channel_idx];
+
+        if channel.transfer_mode() == TransferMode::Endless {
+            return true;
+        }
+
+        let in_flight = self
+            .fifo
+            .iter()
+            .filter(|value| value.channel == channel_idx)
+            .count() as u32;
+
+        channel.transfer_count & TRANSFER_COUNT_MASK > in_flight
+    }
+
     fn abort_channel(&mut self, channel_idx: usize) {
         self.channels[channel_idx].set_busy(false);
     }
@@ -385,6 +464,10 @@ impl Dma {
         }
     }
 
+    pub(crate) fn clear_dreg(&mut self, dreg_channel: usize) {
+        self.dreg[dreg_channel] = false;
+    }
+
     fn has_dreg(&self, treq_sel: TreqSel) -> bool {
         match treq_sel {
             TreqSel::Dreg(val) => self.dreg[val as usize],
@@ -487,8 +570,8 @@ impl Peripheral for Rc<RefCell<Dma>> {
             DmaOffset::Default => match addr {
                 INTR => dma.interrupt_raw as u32,
                 MULTI_CHAN_TRIGGER => 0,
-                SNIFF_CTRL => 0, // not implemented
-                SNIFF_DATA => 0,
+                SNIFF_CTRL => dma.sniffer.ctrl,
+                SNIFF_DATA => dma.sniffer.result(),
                 FIFO_LEVELS => 0, // TODO
                 CHAN_ABORT => 0,
                 N_CHANNELS => NOF_CHANNEL as u32,
@@ -542,9 +625,10 @@ impl Peripheral for Rc<RefCell<Dma>> {
                         let ref mut ctrl = channel.ctrl;
                         w1c(ctrl, value, 0b11 << 29);
 
-                        let rw_mask = 0b111111 << 26;
-                        clear_bits(ctrl, !rw_mask);
+                        let rw_mask = CTRL_WRITABLE_MASK;
+                        clear_bits(ctrl, rw_mask);
                         *ctrl |= value & rw_mask;
+                        channel.update_ahb_err();
 
                         if channel.is_enabled(){
                             if channel.busy() {
@@ -622,7 +706,8 @@ impl Peripheral for Rc<RefCell<Dma>> {
                         }
                     }
                 }
-                SNIFF_CTRL | SNIFF_DATA => { /* Not implemented */ }
+                SNIFF_CTRL => dma.sniffer.ctrl = value & sniffer::CTRL_MASK,
+                SNIFF_DATA => dma.sniffer.data = value,
                 FIFO_LEVELS => { /* read only */ }
                 CHAN_ABORT => {
                     for index in 0..NOF_CHANNEL {
@@ -691,3 +776,142 @@ fn parse_offset(offset: u16) -> DmaOffset {
         _ => DmaOffset::Default,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::GpioController;
+    use crate::inspector::InspectorRef;
+
+    fn setup() -> (Rc<Clock>, Bus, Rc<RefCell<Dma>>, PeripheralAccessContext) {
+        let clock = Rc::new(Clock::new());
+        let bus = Bus::new(
+            Rc::new(RefCell::new(GpioController::default())),
+            Rc::new(RefCell::new(Interrupts::default())),
+            Rc::clone(&clock),
+            InspectorRef::default(),
+        );
+        let dma = Rc::clone(&bus.peripherals.dma);
+        let ctx = PeripheralAccessContext {
+            clock: Rc::clone(&clock),
+            ..Default::default()
+        };
+
+        (clock, bus, dma, ctx)
+    }
+
+    fn run(clock: &Clock, bus: &mut Bus, dma: &RefCell<Dma>, ticks: usize) {
+        for _ in 0..ticks {
+            clock.tick();
+            bus.tick();
+            dma.borrow_mut().tick(bus);
+        }
+    }
+
+    #[test]
+    fn test_sniff_transfer() {
+        let (clock, mut bus, mut dma, ctx) = setup();
+
+        bus.sram.write_u32(0, 0x1111_1111).unwrap();
+        bus.sram.write_u32(4, 0x2222_2222).unwrap();
+
+        // checksum mode on channel 0
+        dma.write(SNIFF_CTRL, 1 | (0xf << 5), &ctx).unwrap();
+        dma.write(SNIFF_DATA, 5, &ctx).unwrap();
+
+        dma.write(CHN_READ_ADDR, Bus::SRAM, &ctx).unwrap();
+        dma.write(CHN_WRITE_ADDR, Bus::SRAM + 0x100, &ctx).unwrap();
+        dma.write(CHN_TRANSFER_COUNT, 2, &ctx).unwrap();
+
+        // word transfers, both addresses incremented, unpaced and sniffed
+        let ctrl = 1 | (2 << 2) | (1 << 4) | (1 << 6) | (0x3f << 17) | (1 << 25);
+        dma.write(CHN_CTRL_TRIG, ctrl, &ctx).unwrap();
+
+        run(&clock, &mut bus, &dma, 20);
+
+        assert_eq!(bus.sram.read_u32(0x100), Ok(0x1111_1111));
+        assert_eq!(bus.sram.read_u32(0x104), Ok(0x2222_2222));
+        assert_eq!(dma.read(SNIFF_DATA, &ctx), Ok(0x3333_3338));
+        assert_eq!(dma.read(CHN_TRANSFER_COUNT, &ctx), Ok(0));
+        assert!(!dma.borrow().channels[0].busy());
+        assert_eq!(dma.borrow().interrupt_raw, 1);
+    }
+
+    #[test]
+    fn test_ring_bswap_transfer() {
+        let (clock, mut bus, mut dma, ctx) = setup();
+
+        bus.sram.write_u32(0, 0x1122_3344).unwrap();
+        bus.sram.write_u32(4, 0x5566_7788).unwrap();
+
+        dma.write(CHN_READ_ADDR, Bus::SRAM, &ctx).unwrap();
+        dma.write(CHN_WRITE_ADDR, Bus::SRAM + 0x100, &ctx).unwrap();
+        dma.write(CHN_TRANSFER_COUNT, 4, &ctx).unwrap();
+
+        // word transfers from an 8 byte ring on the read side, byte swapped
+        let ctrl = 1 | (2 << 2) | (1 << 4) | (1 << 6) | (3 << 8) | (0x3f << 17) | (1 << 24);
+        dma.write(CHN_CTRL_TRIG, ctrl, &ctx).unwrap();
+
+        run(&clock, &mut bus, &dma, 40);
+
+        assert_eq!(bus.sram.read_u32(0x100), Ok(0x4433_2211));
+        assert_eq!(bus.sram.read_u32(0x104), Ok(0x8877_6655));
+        assert_eq!(bus.sram.read_u32(0x108), Ok(0x4433_2211));
+        assert_eq!(bus.sram.read_u32(0x10c), Ok(0x8877_6655));
+        assert_eq!(dma.read(CHN_READ_ADDR, &ctx), Ok(Bus::SRAM));
+        assert_eq!(dma.read(CHN_WRITE_ADDR, &ctx), Ok(Bus::SRAM + 0x110));
+
+        // halfwords only swap their two bytes
+        dma.write(CHN_READ_ADDR, Bus::SRAM, &ctx).unwrap();
+        dma.write(CHN_WRITE_ADDR, Bus::SRAM + 0x200, &ctx).unwrap();
+        dma.write(CHN_TRANSFER_COUNT, 2, &ctx).unwrap();
+
+        let ctrl = 1 | (1 << 2) | (1 << 4) | (1 << 6) | (0x3f << 17) | (1 << 24);
+        dma.write(CHN_CTRL_TRIG, ctrl, &ctx).unwrap();
+
+        run(&clock, &mut bus, &dma, 20);
+
+        assert_eq!(bus.sram.read_u32(0x200), Ok(0x2211_4433));
+    }
+
+    #[test]
+    fn test_bus_errors() {
+        let (clock, mut bus, mut dma, ctx) = setup();
+
+        // word transfers, both addresses incremented, unpaced and quiet
+        let ctrl = 1 | (2 << 2) | (1 << 4) | (1 << 6) | (0x3f << 17) | (1 << 23);
+
+        // nothing is mapped there, the load is refused right away
+        dma.write(CHN_READ_ADDR, 0x3000_0000, &ctx).unwrap();
+        dma.write(CHN_WRITE_ADDR, Bus::SRAM + 0x100, &ctx).unwrap();
+        dma.write(CHN_TRANSFER_COUNT, 4, &ctx).unwrap();
+        dma.write(CHN_CTRL_TRIG, ctrl, &ctx).unwrap();
+
+        run(&clock, &mut bus, &dma, 20);
+
+        let status = dma.read(CHN_AL1_CTRL, &ctx).unwrap();
+        assert_eq!(status >> 29, 0b110);
+        assert!(!dma.borrow().channels[0].busy());
+        assert_eq!(dma.borrow().interrupt_raw, 1);
+        assert_eq!(dma.read(CHN_READ_ADDR, &ctx), Ok(0x3000_0000));
+
+        // the error flags are write 1 to clear
+        dma.write(CHN_AL1_CTRL, ctrl | (1 << 30), &ctx).unwrap();
+        assert_eq!(dma.read(CHN_AL1_CTRL, &ctx).unwrap() >> 29, 0);
+
+        // a register the DMA does not have, the fault comes once the store is done
+        dma.write(INTR, 0, &ctx).unwrap();
+        dma.write(CHN_READ_ADDR, Bus::SRAM, &ctx).unwrap();
+        dma.write(CHN_WRITE_ADDR, 0x5000_0700, &ctx).unwrap();
+        dma.write(CHN_TRANSFER_COUNT, 4, &ctx).unwrap();
+        dma.write(CHN_CTRL_TRIG, ctrl, &ctx).unwrap();
+
+        run(&clock, &mut bus, &dma, 20);
+
+        let status = dma.read(CHN_AL1_CTRL, &ctx).unwrap();
+        assert_eq!(status >> 29, 0b101);
+        assert!(!dma.borrow().channels[0].busy());
+        assert_eq!(dma.borrow().interrupt_raw, 1);
+        assert_ne!(dma.read(CHN_TRANSFER_COUNT, &ctx), Ok(0));
+    }
+}
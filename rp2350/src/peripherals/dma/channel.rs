@@ -28,7 +28,7 @@ pub enum TransferMode {
     Endless,
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Channel {
     pub read_addr: u32,
     pub write_addr: u32,
@@ -40,6 +40,21 @@ pub struct Channel {
     pub ready_to_transfer: Rc<RefCell<bool>>,
 }
 
+impl Default for Channel {
+    fn default() -> Self {
+        Self {
+            read_addr: 0,
+            write_addr: 0,
+            transfer_count: 0,
+            ctrl: 0,
+            dreq_counter: 0,
+            secure: 0b11, // Secure and privileged after reset
+            transfer_counter_reload: 0,
+            ready_to_transfer: Rc::new(RefCell::new(false)),
+        }
+    }
+}
+
 impl Channel {
     pub fn transfer_mode(&self) -> TransferMode {
         match self.transfer_count >> 28 {
@@ -136,6 +151,21 @@ impl Channel {
         extract_bit(self.ctrl, 31) != 0
     }
 
+    /// Flag a bus error of the read or the write side, AHB_ERROR is the OR of both
+    pub fn set_error(&mut self, write: bool) {
+        match write {
+            true => set_bit_state(&mut self.ctrl, 29, true),
+            false => set_bit_state(&mut self.ctrl, 30, true),
+        }
+
+        self.update_ahb_err();
+    }
+
+    pub fn update_ahb_err(&mut self) {
+        let error = self.read_err() || self.write_err();
+        set_bit_state(&mut self.ctrl, 31, error);
+    }
+
     fn ring_mask(&self) -> u32 {
         let ring_size = self.ring_size();
 
@@ -159,39 +189,152 @@ impl Channel {
         upper_addr | addr
     }
 
-    pub fn update_read_address(&mut self) {
+    /// Address of the next transfer on one side of the channel.
+    /// Reversed without increment is the otherwise unused combination stepping over
+    /// every other element, with the ring only the lower RING_SIZE bits change
+    fn next_address(&self, address: u32, incr: bool, rev: bool, ring: bool) -> u32 {
         let data_size = self.datasize() as u32;
 
-        let mut addr = match (self.incr_read(), self.incr_read_rev()) {
-            (true, true) => self.read_addr.wrapping_sub(data_size),
-            (true, false) => self.read_addr.wrapping_add(data_size),
-            (false, true) => self.read_addr.wrapping_add(data_size * 2),
-            (false, false) => self.read_addr,
+        let next = match (incr, rev) {
+            (true, true) => address.wrapping_sub(data_size),
+            (true, false) => address.wrapping_add(data_size),
+            (false, true) => address.wrapping_add(data_size * 2),
+            (false, false) => address,
         };
 
-        // read are wrapped on ring_self == 0
-        if !self.ring_sel() {
-            addr = self.addr_wrap(self.read_addr, addr);
+        match ring {
+            true => self.addr_wrap(address, next),
+            false => next,
         }
+    }
 
-        self.read_addr = addr;
+    pub fn update_read_address(&mut self) {
+        // reads are wrapped on ring_sel == 0
+        self.read_addr = self.next_address(
+            self.read_addr,
+            self.incr_read(),
+            self.incr_read_rev(),
+            !self.ring_sel(),
+        );
     }
 
     pub fn update_write_address(&mut self) {
-        let data_size = self.datasize() as u32;
-
-        let mut addr = match (self.incr_write(), self.incr_write_rev()) {
-            (true, true) => self.write_addr.wrapping_sub(data_size),
-            (true, false) => self.write_addr.wrapping_add(data_size),
-            (false, true) => self.write_addr.wrapping_add(data_size * 2),
-            (false, false) => self.write_addr,
-        };
+        // writes are wrapped on ring_sel == 1
+        self.write_addr = self.next_address(
+            self.write_addr,
+            self.incr_write(),
+            self.incr_write_rev(),
+            self.ring_sel(),
+        );
+    }
+}
 
-        // write are wrapped on ring_self == 1
-        if self.ring_sel() {
-            addr = self.addr_wrap(self.write_addr, addr);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INCR_READ: u32 = 1 << 4;
+    const INCR_READ_REV: u32 = 1 << 5;
+    const INCR_WRITE: u32 = 1 << 6;
+    const INCR_WRITE_REV: u32 = 1 << 7;
+    const RING_SEL: u32 = 1 << 12;
+    const WORD: u32 = 2 << 2;
+    const HALFWORD: u32 = 1 << 2;
+
+    fn channel(ctrl: u32) -> Channel {
+        Channel {
+            read_addr: 0x2000_0100,
+            write_addr: 0x2000_0200,
+            ctrl,
+            ..Default::default()
         }
+    }
+
+    fn read_addresses(channel: &mut Channel, count: usize) -> Vec<u32> {
+        (0..count)
+            .map(|_| {
+                channel.update_read_address();
+                channel.read_addr
+            })
+            .collect()
+    }
+
+    fn write_addresses(channel: &mut Channel, count: usize) -> Vec<u32> {
+        (0..count)
+            .map(|_| {
+                channel.update_write_address();
+                channel.write_addr
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_increment() {
+        let mut fixed = channel(WORD);
+        assert_eq!(read_addresses(&mut fixed, 2), [0x2000_0100, 0x2000_0100]);
+
+        let mut incr = channel(WORD | INCR_READ | INCR_WRITE);
+        assert_eq!(read_addresses(&mut incr, 2), [0x2000_0104, 0x2000_0108]);
+        assert_eq!(write_addresses(&mut incr, 2), [0x2000_0204, 0x2000_0208]);
+
+        let mut halfword = channel(HALFWORD | INCR_READ);
+        assert_eq!(read_addresses(&mut halfword, 2), [0x2000_0102, 0x2000_0104]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        // decrementing, e.g. to reverse the order of a buffer
+        let mut rev = channel(WORD | INCR_READ | INCR_READ_REV | INCR_WRITE);
+        assert_eq!(read_addresses(&mut rev, 2), [0x2000_00fc, 0x2000_00f8]);
+        assert_eq!(write_addresses(&mut rev, 2), [0x2000_0204, 0x2000_0208]);
+
+        // REV without INCR skips every other element, e.g. one channel of stereo samples
+        let mut skip = channel(HALFWORD | INCR_WRITE_REV);
+        assert_eq!(write_addresses(&mut skip, 2), [0x2000_0204, 0x2000_0208]);
+        assert_eq!(read_addresses(&mut skip, 1), [0x2000_0100]);
+    }
 
-        self.write_addr = addr;
+    #[test]
+    fn test_ring() {
+        // 16 byte ring on the read side, a 4 word table sent in a loop
+        let mut read_ring = channel(WORD | INCR_READ | INCR_WRITE | (4 << 8));
+        assert_eq!(
+            read_addresses(&mut read_ring, 5),
+            [
+                0x2000_0104,
+                0x2000_0108,
+                0x2000_010c,
+                0x2000_0100,
+                0x2000_0104
+            ]
+        );
+        // the other side is not wrapped
+        assert_eq!(
+            write_addresses(&mut read_ring, 5),
+            [
+                0x2000_0204,
+                0x2000_0208,
+                0x2000_020c,
+                0x2000_0210,
+                0x2000_0214
+            ]
+        );
+
+        // 8 byte ring on the write side, a circular buffer of 4 halfwords
+        let mut write_ring = channel(HALFWORD | INCR_WRITE | RING_SEL | (3 << 8));
+        assert_eq!(
+            write_addresses(&mut write_ring, 5),
+            [
+                0x2000_0202,
+                0x2000_0204,
+                0x2000_0206,
+                0x2000_0200,
+                0x2000_0202
+            ]
+        );
+
+        // the ring also holds when decrementing
+        let mut rev_ring = channel(WORD | INCR_READ | INCR_READ_REV | (4 << 8));
+        assert_eq!(read_addresses(&mut rev_ring, 2), [0x2000_010c, 0x2000_0108]);
     }
 }
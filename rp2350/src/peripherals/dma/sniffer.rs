@@ -0,0 +1,223 @@
+/**
+ * @file peripherals/dma/sniffer.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief DMA sniffer, computing a checksum over the data of a channel
+ */
+use crate::common::DataSize;
+use crate::utils::{extract_bit, extract_bits};
+
+pub const CTRL_MASK: u32 = 0xfff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Calculation {
+    /// IEEE802.3 polynomial, the data is fed MSB first
+    Crc32,
+    /// IEEE802.3 polynomial with the bits of the data reversed
+    Crc32BitReversed,
+    /// CRC-16-CCITT polynomial
+    Crc16,
+    Crc16BitReversed,
+    /// XOR reduction, bit 0 is toggled on each data with an odd number of ones
+    Parity,
+    /// 32 bit sum of the data
+    Sum,
+    Reserved,
+}
+
+const CRC32_POLYNOMIAL: u32 = 0x04c1_1db7;
+const CRC16_POLYNOMIAL: u32 = 0x1021;
+
+/// The data seen by the sniffer is the one written by the channel, after its own byte swap
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sniffer {
+    pub ctrl: u32,
+    /// Running value, written to seed the calculation
+    pub data: u32,
+}
+
+impl Sniffer {
+    pub fn is_enabled(&self) -> bool {
+        extract_bit(self.ctrl, 0) != 0
+    }
+
+    pub fn channel(&self) -> usize {
+        extract_bits(self.ctrl, 1..=4) as usize
+    }
+
+    pub fn calculation(&self) -> Calculation {
+        match extract_bits(self.ctrl, 5..=8) {
+            0x0 => Calculation::Crc32,
+            0x1 => Calculation::Crc32BitReversed,
+            0x2 => Calculation::Crc16,
+            0x3 => Calculation::Crc16BitReversed,
+            0xe => Calculation::Parity,
+            0xf => Calculation::Sum,
+            _ => Calculation::Reserved,
+        }
+    }
+
+    pub fn bswap(&self) -> bool {
+        extract_bit(self.ctrl, 9) != 0
+    }
+
+    pub fn out_rev(&self) -> bool {
+        extract_bit(self.ctrl, 10) != 0
+    }
+
+    pub fn out_inv(&self) -> bool {
+        extract_bit(self.ctrl, 11) != 0
+    }
+
+    /// Value of SNIFF_DATA as seen by the bus, the output options do not change the
+    /// running value itself
+    pub fn result(&self) -> u32 {
+        let mut result = self.data;
+
+        if self.out_rev() {
+            result = result.reverse_bits();
+        }
+
+        if self.out_inv() {
+            result = !result;
+        }
+
+        result
+    }
+
+    /// Feed the data of one transfer
+    pub fn feed(&mut self, value: u32, size: DataSize) {
+        let bits = match size {
+            DataSize::Byte => 8,
+            DataSize::HalfWord => 16,
+            DataSize::Word => 32,
+        };
+
+        let mut value = match size {
+            DataSize::Byte => value & 0xff,
+            DataSize::HalfWord => value & 0xffff,
+            DataSize::Word => value,
+        };
+
+        if self.bswap() {
+            value = match size {
+                DataSize::Byte => value,
+                DataSize::HalfWord => (value as u16).swap_bytes() as u32,
+                DataSize::Word => value.swap_bytes(),
+            };
+        }
+
+        let reversed = value.reverse_bits() >> (32 - bits);
+
+        self.data = match self.calculation() {
+            Calculation::Crc32 => crc32(self.data, value, bits),
+            Calculation::Crc32BitReversed => crc32(self.data, reversed, bits),
+            Calculation::Crc16 => crc16(self.data, value, bits),
+            Calculation::Crc16BitReversed => crc16(self.data, reversed, bits),
+            Calculation::Parity => self.data ^ (value.count_ones() & 1),
+            Calculation::Sum => self.data.wrapping_add(value),
+            Calculation::Reserved => self.data,
+        };
+    }
+}
+
+fn crc32(mut crc: u32, data: u32, bits: u32) -> u32 {
+    for bit in (0..bits).rev() {
+        let feedback = (crc >> 31) ^ ((data >> bit) & 1);
+        crc <<= 1;
+
+        if feedback != 0 {
+            crc ^= CRC32_POLYNOMIAL;
+        }
+    }
+
+    crc
+}
+
+fn crc16(crc: u32, data: u32, bits: u32) -> u32 {
+    let mut crc = crc & 0xffff;
+
+    for bit in (0..bits).rev() {
+        let feedback = (crc >> 15) ^ ((data >> bit) & 1);
+        crc = (crc << 1) & 0xffff;
+
+        if feedback != 0 {
+            crc ^= CRC16_POLYNOMIAL;
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    fn sniff(ctrl: u32, seed: u32) -> u32 {
+        let mut sniffer = Sniffer { ctrl, data: seed };
+
+        for &byte in CHECK {
+            sniffer.feed(byte as u32, DataSize::Byte);
+        }
+
+        sniffer.result()
+    }
+
+    #[test]
+    fn test_crc() {
+        const OUT_REV: u32 = 1 << 10;
+        const OUT_INV: u32 = 1 << 11;
+
+        // CRC-32/MPEG-2
+        assert_eq!(sniff(0, 0xffff_ffff), 0x0376_e6e7);
+        // the usual CRC-32, reflected with the output inverted
+        assert_eq!(
+            sniff((0x1 << 5) | OUT_REV | OUT_INV, 0xffff_ffff),
+            0xcbf4_3926
+        );
+        // CRC-16/CCITT-FALSE and XMODEM
+        assert_eq!(sniff(0x2 << 5, 0xffff), 0x29b1);
+        assert_eq!(sniff(0x2 << 5, 0), 0x31c3);
+    }
+
+    #[test]
+    fn test_word_transfers() {
+        // a word with the bits reversed is the same as its bytes in little endian order
+        let mut bytes = Sniffer {
+            ctrl: 0x1 << 5,
+            data: 0xffff_ffff,
+        };
+        let mut words = bytes;
+
+        for chunk in b"12345678".chunks(4) {
+            bytes.feed(chunk[0] as u32, DataSize::Byte);
+            bytes.feed(chunk[1] as u32, DataSize::Byte);
+            bytes.feed(chunk[2] as u32, DataSize::Byte);
+            bytes.feed(chunk[3] as u32, DataSize::Byte);
+            words.feed(
+                u32::from_le_bytes(chunk.try_into().unwrap()),
+                DataSize::Word,
+            );
+        }
+
+        assert_eq!(bytes.data, words.data);
+
+        let mut sum = Sniffer {
+            ctrl: (0xf << 5) | (1 << 9),
+            data: 1,
+        };
+        sum.feed(0x1234, DataSize::HalfWord);
+        assert_eq!(sum.result(), 0x3413);
+
+        let mut parity = Sniffer {
+            ctrl: 0xe << 5,
+            data: 0,
+        };
+        parity.feed(0b111, DataSize::Word);
+        parity.feed(0b1, DataSize::Word);
+        parity.feed(0b11, DataSize::Word);
+        assert_eq!(parity.result(), 0);
+    }
+}
@@ -0,0 +1,180 @@
+/**
+ * @file peripherals/glitch_detector.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Glitch detectors, resetting the chip when a glitch on the core supply or clock is seen
+ */
+use super::*;
+
+pub const ARM: u16 = 0x00; // Forcibly arm the glitch detectors, if they are not already armed by OTP
+pub const DISARM: u16 = 0x04; // Forcibly disarm the glitch detectors, even if armed by OTP
+pub const SENSITIVITY: u16 = 0x08; // Adjust the sensitivity of the glitch detectors
+pub const LOCK: u16 = 0x0c; // Nonzero disables the writes to ARM, DISARM, SENSITIVITY and LOCK
+pub const TRIG_STATUS: u16 = 0x10; // Detectors which caused the last reset, write 1 to clear
+pub const TRIG_FORCE: u16 = 0x14; // Simulate the firing of the detectors
+
+/// ARM reads this while the detectors are not forcibly armed
+pub const ARM_NO: u32 = 0x5bad;
+/// Written to DISARM to force the detectors off
+pub const DISARM_YES: u32 = 0xdcaf;
+
+/// One bit per detector
+pub const DETECTORS: u32 = 0xf;
+
+/// There is no glitch to detect in the simulator, the detectors only fire through
+/// TRIG_FORCE or [`GlitchDetector::trigger`]. Firing while armed resets the chip like
+/// the real ones, the machine takes the reset at the end of the tick. TRIG_STATUS is
+/// only cleared by a power cycle.
+pub struct GlitchDetector {
+    pub arm: u32,
+    pub disarm: u32,
+    pub sensitivity: u32,
+    pub lock: u32,
+    pub trig_status: u32,
+    /// GLITCH_DETECTOR_ENABLE of the OTP critical flags, latched on reset
+    pub armed_by_otp: bool,
+    reset_pending: bool,
+}
+
+impl Default for GlitchDetector {
+    fn default() -> Self {
+        Self {
+            arm: ARM_NO,
+            disarm: 0,
+            sensitivity: 0,
+            lock: 0,
+            trig_status: 0,
+            armed_by_otp: false,
+            reset_pending: false,
+        }
+    }
+}
+
+impl GlitchDetector {
+    pub fn is_armed(&self) -> bool {
+        (self.armed_by_otp || self.arm != ARM_NO) && self.disarm != DISARM_YES
+    }
+
+    /// Fire the given detectors, resetting the chip if they are armed
+    pub fn trigger(&mut self, detectors: u32) {
+        let detectors = detectors & DETECTORS;
+
+        if detectors == 0 || !self.is_armed() {
+            return;
+        }
+
+        log::warn!(
+            "Glitch detectors {:#x} fired, resetting the chip",
+            detectors
+        );
+        self.trig_status |= detectors;
+        self.reset_pending = true;
+    }
+
+    /// Whether the detectors requested a reset since the last call
+    pub fn take_reset(&mut self) -> bool {
+        core::mem::take(&mut self.reset_pending)
+    }
+}
+
+impl Peripheral for GlitchDetector {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let value = match address {
+            ARM => self.arm,
+            DISARM => self.disarm,
+            SENSITIVITY => self.sensitivity,
+            LOCK => self.lock,
+            TRIG_STATUS => self.trig_status,
+            TRIG_FORCE => 0,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let locked = self.lock != 0;
+
+        match address {
+            ARM | DISARM | SENSITIVITY | LOCK if locked => {
+                log::warn!("Write to the locked glitch detector at {:#x}", address);
+            }
+            ARM => self.arm = value & 0xffff,
+            DISARM => self.disarm = value & 0xffff,
+            SENSITIVITY => self.sensitivity = value,
+            LOCK => self.lock = value & 0xff,
+            TRIG_STATUS => self.trig_status &= !value,
+            TRIG_FORCE => self.trigger(value),
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm() {
+        let mut detector = GlitchDetector::default();
+        let ctx = PeripheralAccessContext::default();
+
+        // not armed, forcing does nothing
+        detector.write(TRIG_FORCE, 0b1, &ctx).unwrap();
+        assert!(!detector.take_reset());
+        assert_eq!(detector.read(TRIG_STATUS, &ctx), Ok(0));
+
+        detector.write(ARM, 0, &ctx).unwrap();
+        detector.write(TRIG_FORCE, 0b101, &ctx).unwrap();
+        assert!(detector.take_reset());
+        assert!(!detector.take_reset());
+        assert_eq!(detector.read(TRIG_STATUS, &ctx), Ok(0b101));
+
+        detector.write(TRIG_STATUS, 0b1, &ctx).unwrap();
+        assert_eq!(detector.read(TRIG_STATUS, &ctx), Ok(0b100));
+
+        // DISARM wins over the OTP
+        detector.armed_by_otp = true;
+        detector.write(DISARM, DISARM_YES, &ctx).unwrap();
+        assert!(!detector.is_armed());
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut detector = GlitchDetector::default();
+        let ctx = PeripheralAccessContext::default();
+
+        detector.write(ARM, 0, &ctx).unwrap();
+        detector.write(LOCK, 1, &ctx).unwrap();
+        detector.write(DISARM, DISARM_YES, &ctx).unwrap();
+        detector.write(LOCK, 0, &ctx).unwrap();
+
+        assert!(detector.is_armed());
+        assert_eq!(detector.read(LOCK, &ctx), Ok(1));
+    }
+}
@@ -3,13 +3,15 @@
  * @author Nguyen Le Duy
  * @date 04/05/2025
  * @brief I2C peripheral implementation
- * @todo actually implement the I2C peripheral
+ * @todo the slave mode and the timing of the bus
  */
+use crate::error::ConfigError;
 use crate::interrupts::{Interrupt, Interrupts};
 use crate::utils::{extract_bit, set_bit_state, Fifo};
 
 use super::{Peripheral, PeripheralAccessContext, PeripheralError, PeripheralResult};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 pub const IC_CON: u16 = 0x00; // I2C Control Register
@@ -55,6 +57,58 @@ pub const IC_COMP_PARAM_1: u16 = 0xF4; // Component Parameter Register 1
 pub const IC_COMP_VERSION: u16 = 0xF8; // I2C Component Version Register
 pub const IC_COMP_TYPE: u16 = 0xFC; // I2C Component Type Register
 
+const CON_MASTER_MODE: u32 = 1 << 0;
+
+const DATA_CMD_READ: u32 = 1 << 8;
+const DATA_CMD_STOP: u32 = 1 << 9;
+const DATA_CMD_RESTART: u32 = 1 << 10;
+
+const ENABLE_ENABLE: u32 = 1 << 0;
+const ENABLE_ABORT: u32 = 1 << 1;
+
+pub const INTR_RX_UNDER: u32 = 1 << 0;
+pub const INTR_RX_OVER: u32 = 1 << 1;
+pub const INTR_RX_FULL: u32 = 1 << 2;
+pub const INTR_TX_OVER: u32 = 1 << 3;
+pub const INTR_TX_EMPTY: u32 = 1 << 4;
+pub const INTR_RD_REQ: u32 = 1 << 5;
+pub const INTR_TX_ABRT: u32 = 1 << 6;
+pub const INTR_RX_DONE: u32 = 1 << 7;
+pub const INTR_ACTIVITY: u32 = 1 << 8;
+pub const INTR_STOP_DET: u32 = 1 << 9;
+pub const INTR_START_DET: u32 = 1 << 10;
+pub const INTR_GEN_CALL: u32 = 1 << 11;
+pub const INTR_RESTART_DET: u32 = 1 << 12;
+
+pub const ABRT_7B_ADDR_NOACK: u32 = 1 << 0;
+pub const ABRT_TXDATA_NOACK: u32 = 1 << 3;
+pub const ABRT_USER_ABRT: u32 = 1 << 16;
+
+/// A target on the bus of an I2C block, answering the transfers mastered by the chip.
+/// The device is addressed with its 7 bit address, the chip handles the address byte.
+pub trait I2cDevice {
+    /// A START or a repeated START addressed the device, `read` is the direction of the transfer
+    fn start(&mut self, _read: bool) {}
+
+    /// A byte written by the chip, returns whether the device acknowledges it
+    fn write(&mut self, data: u8) -> bool;
+
+    /// A byte read by the chip
+    fn read(&mut self) -> u8;
+
+    /// The STOP ending the transfer
+    fn stop(&mut self) {}
+}
+
+pub type I2cDeviceRef = Rc<RefCell<dyn I2cDevice>>;
+
+/// Transfer in progress on the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transfer {
+    address: u8,
+    read: bool,
+}
+
 pub struct I2c<const IDX: usize> {
     pub ctrl: u32,
     pub ic_enable: u8,
@@ -66,17 +120,24 @@ pub struct I2c<const IDX: usize> {
     pub ssclk_hcnt: u16,
     pub ssclk_lcnt: u16,
     pub sda_setup: u8,
+    pub sda_hold: u32,
     pub ack_general_call: bool,
     pub ic_fs_spklen: u8,
     pub receive_data_level: u8,
     pub transmit_data_level: u8,
+    pub rx_threshold: u8,
+    pub tx_threshold: u8,
     pub dma_ctrl: u8,
     pub generate_nack: bool,
     pub tx_fifo: Fifo<u32, 16>,
     pub rx_fifo: Fifo<u32, 16>,
+    pub tx_abort_source: u32,
 
     interrupt_raw: u32,
     interrupt_mask: u32,
+    transfer: Option<Transfer>,
+    /// Devices on the bus by their 7 bit address, they are outside of the chip
+    devices: BTreeMap<u8, I2cDeviceRef>,
 }
 
 impl<const IDX: usize> Default for I2c<IDX> {
@@ -90,19 +151,25 @@ impl<const IDX: usize> Default for I2c<IDX> {
             ssclk_hcnt: 0x0028,
             ssclk_lcnt: 0x002f,
             sda_setup: 0x64,
+            sda_hold: 0x1,
             ack_general_call: true,
             ic_fs_spklen: 0x07,
             receive_data_level: 0,
             transmit_data_level: 0,
+            rx_threshold: 0,
+            tx_threshold: 0,
             dma_ctrl: 0,
             generate_nack: false,
             ic_enable: 0,
             ic_status: 0b110,
             tx_fifo: Fifo::default(),
             rx_fifo: Fifo::default(),
+            tx_abort_source: 0,
 
             interrupt_raw: 0,
             interrupt_mask: 0,
+            transfer: None,
+            devices: BTreeMap::new(),
         }
     }
 }
@@ -112,6 +179,10 @@ impl<const IDX: usize> I2c<IDX> {
         extract_bit(self.ic_enable, 0) == 1
     }
 
+    pub fn is_master_mode(&self) -> bool {
+        self.ctrl & CON_MASTER_MODE != 0
+    }
+
     pub fn is_slave_active(&self) -> bool {
         extract_bit(self.ic_status, 6) == 1
     }
@@ -128,26 +199,86 @@ impl<const IDX: usize> I2c<IDX> {
         extract_bit(self.dma_ctrl, 1) == 1
     }
 
+    /// Put a device on the bus, it stays there through the resets of the chip
+    pub fn attach_device(&mut self, address: u8, device: I2cDeviceRef) -> Result<(), ConfigError> {
+        // 0x00-0x07 and 0x78-0x7F are reserved by the I2C specification
+        if !(0x08..0x78).contains(&address) {
+            return Err(ConfigError::InvalidI2cAddress(address));
+        }
+
+        if self.devices.contains_key(&address) {
+            return Err(ConfigError::I2cAddressInUse(address));
+        }
+
+        self.devices.insert(address, device);
+        Ok(())
+    }
+
+    pub fn detach_device(&mut self, address: u8) -> Option<I2cDeviceRef> {
+        if self
+            .transfer
+            .is_some_and(|transfer| transfer.address == address)
+        {
+            self.transfer = None;
+        }
+
+        self.devices.remove(&address)
+    }
+
+    pub fn device(&self, address: u8) -> Option<&I2cDeviceRef> {
+        self.devices.get(&address)
+    }
+
+    /// Addresses of the devices on the bus
+    pub fn device_addresses(&self) -> impl Iterator<Item = u8> + '_ {
+        self.devices.keys().copied()
+    }
+
+    /// Move the devices over to the block after a reset
+    pub(crate) fn take_devices(&mut self) -> BTreeMap<u8, I2cDeviceRef> {
+        self.transfer = None;
+        core::mem::take(&mut self.devices)
+    }
+
+    pub(crate) fn set_devices(&mut self, devices: BTreeMap<u8, I2cDeviceRef>) {
+        self.devices = devices;
+    }
+
+    /// Raw interrupts, with the ones following the level of the FIFOs
+    pub fn raw_interrupt(&self) -> u32 {
+        let mut raw = self.interrupt_raw;
+
+        if self.rx_fifo.len() > self.rx_threshold as usize {
+            raw |= INTR_RX_FULL;
+        }
+
+        // the commands are carried out as soon as they are written, the TX FIFO is
+        // always empty while the block is enabled
+        if self.is_enabled() && self.tx_fifo.len() <= self.tx_threshold as usize {
+            raw |= INTR_TX_EMPTY;
+        }
+
+        raw
+    }
+
     pub fn interrupt(&self) -> u32 {
-        self.interrupt_raw & self.interrupt_mask
+        self.raw_interrupt() & self.interrupt_mask
     }
 
     pub fn update_status(&mut self) {
-        // TODO Master/slave active (FSM not in idle state)
-        // enable / activity status
-        //
         let mut status = self.ic_status as u32;
+        let active = self.transfer.is_some();
 
+        set_bit_state(&mut status, 5, active);
         set_bit_state(&mut status, 4, self.rx_fifo.is_full());
         set_bit_state(&mut status, 3, !self.rx_fifo.is_empty());
-        set_bit_state(&mut status, 2, self.tx_fifo.is_full());
-        set_bit_state(&mut status, 1, !self.tx_fifo.is_empty());
+        set_bit_state(&mut status, 2, self.tx_fifo.is_empty());
+        set_bit_state(&mut status, 1, !self.tx_fifo.is_full());
+        set_bit_state(&mut status, 0, active);
         self.ic_status = status as u8;
     }
 
     pub fn update_interrupt(&mut self, interrupts: Rc<RefCell<Interrupts>>) {
-        // TODO update
-
         let irq = self.interrupt();
 
         interrupts
@@ -162,58 +293,180 @@ impl<const IDX: usize> I2c<IDX> {
             _ => unreachable!(),
         }
     }
+
+    /// Carry out a command written to IC_DATA_CMD on the bus
+    fn command(&mut self, value: u32) {
+        if !self.is_enabled() || !self.is_master_mode() {
+            return;
+        }
+
+        // the TX FIFO is flushed and held until the abort is cleared
+        if self.interrupt_raw & INTR_TX_ABRT != 0 {
+            return;
+        }
+
+        let read = value & DATA_CMD_READ != 0;
+        let address = (self.target_address & 0x7F) as u8;
+
+        // a change of direction needs a repeated START too
+        let restart = value & DATA_CMD_RESTART != 0
+            || self.transfer.is_some_and(|transfer| transfer.read != read);
+
+        if self.transfer.is_none() || restart {
+            self.interrupt_raw |= INTR_START_DET | INTR_ACTIVITY;
+
+            let Some(device) = self.devices.get(&address) else {
+                self.abort(ABRT_7B_ADDR_NOACK);
+                return;
+            };
+
+            device.borrow_mut().start(read);
+            self.transfer = Some(Transfer { address, read });
+        }
+
+        let device = Rc::clone(&self.devices[&address]);
+
+        if read {
+            let data = device.borrow_mut().read();
+
+            if self.rx_fifo.push(data as u32).is_err() {
+                self.interrupt_raw |= INTR_RX_OVER;
+            }
+        } else if !device.borrow_mut().write(value as u8) {
+            self.abort(ABRT_TXDATA_NOACK);
+            return;
+        }
+
+        if value & DATA_CMD_STOP != 0 {
+            self.stop();
+        }
+    }
+
+    /// The master gives up the transfer and releases the bus with a STOP
+    fn abort(&mut self, source: u32) {
+        self.tx_abort_source |= source;
+        self.interrupt_raw |= INTR_TX_ABRT;
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        let device = self
+            .transfer
+            .take()
+            .and_then(|transfer| self.devices.get(&transfer.address));
+
+        if let Some(device) = device {
+            device.borrow_mut().stop();
+        }
+
+        self.interrupt_raw |= INTR_STOP_DET;
+    }
+
+    /// Read of a clear register, returns the interrupts it cleared
+    fn clear_interrupt(&mut self, interrupts: u32) -> u32 {
+        let cleared = self.interrupt_raw & interrupts;
+        self.interrupt_raw &= !interrupts;
+
+        if cleared & INTR_TX_ABRT != 0 {
+            self.tx_abort_source = 0;
+        }
+
+        (cleared != 0) as u32
+    }
+
+    fn set_enable(&mut self, value: u32) {
+        if value & ENABLE_ABORT != 0 && self.is_enabled() {
+            self.abort(ABRT_USER_ABRT);
+        }
+
+        self.ic_enable = (value & 0b101) as u8;
+
+        if value & ENABLE_ENABLE == 0 {
+            // disabling flushes the FIFOs, the transfer on the bus is ended
+            if self.transfer.is_some() {
+                self.stop();
+            }
+
+            self.tx_fifo = Fifo::default();
+            self.rx_fifo = Fifo::default();
+        }
+    }
 }
 
 impl<const IDX: usize> Peripheral for Rc<RefCell<I2c<IDX>>> {
-    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
         let mut i2c = self.borrow_mut();
 
         let value = match address {
-            IC_CON => 0x00,      // I2C Control Register
-            IC_TAR => 0x04,      // I2C Target Address
-            IC_SAR => 0x08,      // I2C Slave Address
-            IC_DATA_CMD => 0x10, // I2C Rx/Tx Data Buffer and Command Register
+            IC_CON => i2c.ctrl,
+            IC_TAR => i2c.target_address,
+            IC_SAR => i2c.slave_address,
+            IC_DATA_CMD => match i2c.rx_fifo.pop() {
+                Some(data) => data & 0xFF,
+                None => {
+                    i2c.interrupt_raw |= INTR_RX_UNDER;
+                    0
+                }
+            },
             IC_SS_SCL_HCNT => i2c.ssclk_hcnt as u32,
             IC_SS_SCL_LCNT => i2c.ssclk_lcnt as u32,
             IC_FS_SCL_HCNT => i2c.fsclk_hcnt as u32,
             IC_FS_SCL_LCNT => i2c.fsclk_lcnt as u32,
-            IC_INTR_STAT => i2c.interrupt() as u32,
-            IC_INTR_MASK => i2c.interrupt_mask as u32,
-            IC_RAW_INTR_STAT => i2c.interrupt_raw as u32,
-            IC_RX_TL => 0x38,              // I2C Receive FIFO Threshold Register
-            IC_TX_TL => 0x3C,              // I2C Transmit FIFO Threshold Register
-            IC_CLR_INTR => 0x40,           // Clear Combined and Individual Interrupt Register
-            IC_CLR_RX_UNDER => 0x44,       // Clear RX_UNDER Interrupt Register
-            IC_CLR_RX_OVER => 0x48,        // Clear RX_OVER Interrupt Register
-            IC_CLR_TX_OVER => 0x4C,        // Clear TX_OVER Interrupt Register
-            IC_CLR_RD_REQ => 0x50,         // Clear RD_REQ Interrupt Register
-            IC_CLR_TX_ABRT => 0x54,        // Clear TX_ABRT Interrupt Register
-            IC_CLR_RX_DONE => 0x58,        // Clear RX_DONE Interrupt Register
-            IC_CLR_ACTIVITY => 0x5C,       // Clear ACTIVITY Interrupt Register
-            IC_CLR_STOP_DET => 0x60,       // Clear STOP_DET Interrupt Register
-            IC_CLR_START_DET => 0x64,      // Clear START_DET Interrupt Register
-            IC_CLR_GEN_CALL => 0x68,       // Clear GEN_CALL Interrupt Register
-            IC_ENABLE => 0x6C,             // I2C ENABLE Register
-            IC_STATUS => 0x70,             // I2C STATUS Register
-            IC_TXFLR => 0x74,              // I2C Transmit FIFO Level Register
-            IC_RXFLR => 0x78,              // I2C Receive FIFO Level Register
-            IC_SDA_HOLD => 0x7C,           // I2C SDA Hold Time Length Register
-            IC_TX_ABRT_SOURCE => 0x80,     // I2C Transmit Abort Source Register
-            IC_SLV_DATA_NACK_ONLY => 0x84, // Generate Slave Data NACK Register
-            IC_DMA_CR => 0x88,             // DMA Control Register
+            IC_INTR_STAT => i2c.interrupt(),
+            IC_INTR_MASK => i2c.interrupt_mask,
+            IC_RAW_INTR_STAT => i2c.raw_interrupt(),
+            IC_RX_TL => i2c.rx_threshold as u32,
+            IC_TX_TL => i2c.tx_threshold as u32,
+            IC_CLR_INTR => i2c.clear_interrupt(
+                INTR_RX_UNDER
+                    | INTR_RX_OVER
+                    | INTR_TX_OVER
+                    | INTR_RD_REQ
+                    | INTR_TX_ABRT
+                    | INTR_RX_DONE
+                    | INTR_ACTIVITY
+                    | INTR_STOP_DET
+                    | INTR_START_DET
+                    | INTR_GEN_CALL
+                    | INTR_RESTART_DET,
+            ),
+            IC_CLR_RX_UNDER => i2c.clear_interrupt(INTR_RX_UNDER),
+            IC_CLR_RX_OVER => i2c.clear_interrupt(INTR_RX_OVER),
+            IC_CLR_TX_OVER => i2c.clear_interrupt(INTR_TX_OVER),
+            IC_CLR_RD_REQ => i2c.clear_interrupt(INTR_RD_REQ),
+            IC_CLR_TX_ABRT => i2c.clear_interrupt(INTR_TX_ABRT),
+            IC_CLR_RX_DONE => i2c.clear_interrupt(INTR_RX_DONE),
+            IC_CLR_ACTIVITY => i2c.clear_interrupt(INTR_ACTIVITY),
+            IC_CLR_STOP_DET => i2c.clear_interrupt(INTR_STOP_DET),
+            IC_CLR_START_DET => i2c.clear_interrupt(INTR_START_DET),
+            IC_CLR_GEN_CALL => i2c.clear_interrupt(INTR_GEN_CALL),
+            IC_ENABLE => i2c.ic_enable as u32,
+            IC_STATUS => {
+                i2c.update_status();
+                i2c.ic_status as u32
+            }
+            IC_TXFLR => i2c.tx_fifo.len() as u32,
+            IC_RXFLR => i2c.rx_fifo.len() as u32,
+            IC_SDA_HOLD => i2c.sda_hold,
+            IC_TX_ABRT_SOURCE => i2c.tx_abort_source,
+            IC_SLV_DATA_NACK_ONLY => i2c.generate_nack as u32,
+            IC_DMA_CR => i2c.dma_ctrl as u32,
             IC_DMA_TDLR => i2c.transmit_data_level as u32,
             IC_DMA_RDLR => i2c.receive_data_level as u32,
-            IC_SDA_SETUP => 0x94, // I2C SDA Setup Register
+            IC_SDA_SETUP => i2c.sda_setup as u32,
             IC_ACK_GENERAL_CALL => i2c.ack_general_call as u32,
-            IC_ENABLE_STATUS => 0x9C, // I2C Enable Status Register
+            IC_ENABLE_STATUS => i2c.is_enabled() as u32,
             IC_FS_SPKLEN => i2c.ic_fs_spklen as u32,
-            IC_CLR_RESTART_DET => 0xA8, // Clear RESTART_DET Interrupt Register
+            IC_CLR_RESTART_DET => i2c.clear_interrupt(INTR_RESTART_DET),
             IC_COMP_PARAM_1 => 0,
             IC_COMP_VERSION => 0x3230312a,
             IC_COMP_TYPE => 0x44570140,
             _ => return Err(PeripheralError::OutOfBounds),
         };
 
+        i2c.update_status();
+        i2c.update_interrupt(Rc::clone(&ctx.interrupts));
+
         Ok(value)
     }
 
@@ -221,27 +474,25 @@ impl<const IDX: usize> Peripheral for Rc<RefCell<I2c<IDX>>> {
         &mut self,
         address: u16,
         value: u32,
-        _ctx: &PeripheralAccessContext,
+        ctx: &PeripheralAccessContext,
     ) -> PeripheralResult<()> {
         let mut i2c = self.borrow_mut();
         match address {
-            IC_CON => {}
-            IC_TAR => {}
-            IC_SAR => {}
-            IC_DATA_CMD => {}
+            // the configuration can only be changed while the block is disabled
+            IC_CON if !i2c.is_enabled() => i2c.ctrl = value & 0x7FF,
+            IC_TAR if !i2c.is_enabled() => i2c.target_address = value & 0xFFF,
+            IC_SAR if !i2c.is_enabled() => i2c.slave_address = value & 0x3FF,
+            IC_CON | IC_TAR | IC_SAR => {}
+            IC_DATA_CMD => i2c.command(value),
             IC_SS_SCL_HCNT => i2c.ssclk_hcnt = value as u16,
             IC_SS_SCL_LCNT => i2c.ssclk_lcnt = value as u16,
             IC_FS_SCL_HCNT => i2c.fsclk_hcnt = value as u16,
             IC_FS_SCL_LCNT => i2c.fsclk_lcnt = value as u16,
-            IC_INTR_MASK => i2c.interrupt_mask = value,
-            IC_RX_TL => {}
-            IC_TX_TL => {}
-            IC_ENABLE => {
-                let value = value & 0b111;
-                i2c.ic_enable = value as u8;
-                // TODO
-            }
-            IC_SDA_HOLD => {}
+            IC_INTR_MASK => i2c.interrupt_mask = value & 0x1FFF,
+            IC_RX_TL => i2c.rx_threshold = value.min(15) as u8,
+            IC_TX_TL => i2c.tx_threshold = value.min(15) as u8,
+            IC_ENABLE => i2c.set_enable(value),
+            IC_SDA_HOLD => i2c.sda_hold = value & 0xFF_FFFF,
             IC_SLV_DATA_NACK_ONLY => {
                 if i2c.is_enabled() && !i2c.is_slave_active() {
                     i2c.generate_nack = (value & 1) == 1;
@@ -250,7 +501,7 @@ impl<const IDX: usize> Peripheral for Rc<RefCell<I2c<IDX>>> {
             IC_DMA_CR => i2c.dma_ctrl = (value & 0b11) as u8,
             IC_DMA_TDLR => i2c.transmit_data_level = value as u8,
             IC_DMA_RDLR => i2c.receive_data_level = value as u8,
-            IC_SDA_SETUP => {}
+            IC_SDA_SETUP => i2c.sda_setup = value as u8,
             IC_ACK_GENERAL_CALL => i2c.ack_general_call = (value & 1) == 1,
             IC_FS_SPKLEN => {
                 if i2c.is_enabled() {
@@ -266,6 +517,74 @@ impl<const IDX: usize> Peripheral for Rc<RefCell<I2c<IDX>>> {
             _ => return Err(PeripheralError::OutOfBounds),
         }
 
+        i2c.update_status();
+        i2c.update_interrupt(Rc::clone(&ctx.interrupts));
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::Eeprom;
+
+    fn enable(i2c: &mut Rc<RefCell<I2c<0>>>, target: u8, ctx: &PeripheralAccessContext) {
+        i2c.write(IC_ENABLE, 0, ctx).unwrap();
+        i2c.write(IC_TAR, target as u32, ctx).unwrap();
+        i2c.write(IC_ENABLE, 1, ctx).unwrap();
+    }
+
+    #[test]
+    fn test_eeprom_transfers() {
+        let ctx = PeripheralAccessContext::default();
+        let mut i2c = Rc::new(RefCell::new(I2c::<0>::default()));
+        let eeprom = Rc::new(RefCell::new(Eeprom::new_24lc256()));
+        i2c.borrow_mut()
+            .attach_device(0x50, eeprom.clone())
+            .unwrap();
+
+        enable(&mut i2c, 0x50, &ctx);
+        for value in [0x01, 0x00, 0xAB, 0xCD | DATA_CMD_STOP] {
+            i2c.write(IC_DATA_CMD, value, &ctx).unwrap();
+        }
+
+        assert_ne!(i2c.read(IC_RAW_INTR_STAT, &ctx).unwrap() & INTR_STOP_DET, 0);
+        assert_eq!(i2c.read(IC_CLR_STOP_DET, &ctx), Ok(1));
+        assert_eq!(&eeprom.borrow().memory[0x100..0x102], &[0xAB, 0xCD]);
+
+        // the change of direction restarts the transfer
+        i2c.write(IC_DATA_CMD, 0x01, &ctx).unwrap();
+        i2c.write(IC_DATA_CMD, 0x01, &ctx).unwrap();
+        i2c.write(IC_DATA_CMD, DATA_CMD_READ | DATA_CMD_STOP, &ctx)
+            .unwrap();
+
+        assert_eq!(i2c.read(IC_RXFLR, &ctx), Ok(1));
+        assert_eq!(i2c.read(IC_DATA_CMD, &ctx), Ok(0xCD));
+        assert_eq!(i2c.read(IC_TX_ABRT_SOURCE, &ctx), Ok(0));
+    }
+
+    #[test]
+    fn test_address_nack() {
+        let ctx = PeripheralAccessContext::default();
+        let mut i2c = Rc::new(RefCell::new(I2c::<0>::default()));
+
+        enable(&mut i2c, 0x3C, &ctx);
+        i2c.write(IC_DATA_CMD, 0x00, &ctx).unwrap();
+        i2c.write(IC_DATA_CMD, 0xAF | DATA_CMD_STOP, &ctx).unwrap();
+
+        let raw = i2c.read(IC_RAW_INTR_STAT, &ctx).unwrap();
+        assert_ne!(raw & INTR_TX_ABRT, 0);
+        assert_ne!(raw & INTR_STOP_DET, 0);
+        assert_eq!(i2c.read(IC_TX_ABRT_SOURCE, &ctx), Ok(ABRT_7B_ADDR_NOACK));
+
+        // reading the clear register releases the TX FIFO
+        assert_eq!(i2c.read(IC_CLR_TX_ABRT, &ctx), Ok(1));
+        assert_eq!(i2c.read(IC_TX_ABRT_SOURCE, &ctx), Ok(0));
+        assert_eq!(
+            i2c.borrow_mut()
+                .attach_device(0x78, Rc::new(RefCell::new(Eeprom::new(16, 8)))),
+            Err(ConfigError::InvalidI2cAddress(0x78))
+        );
+    }
+}
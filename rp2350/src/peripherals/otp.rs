@@ -5,6 +5,10 @@
  * @brief One Time Programmable (OTP) peripheral implementation
  * @todo need real hardware to implement this
  */
+pub mod data;
+
+pub use data::{OtpData, WhiteLabel};
+
 use super::*;
 
 pub const CRIT0: u16 = 0x038; // Page 0 critical boot flags (RBIT-8)
@@ -15,20 +19,68 @@ pub const BOOT_FLAGS0: u16 = 0x048; // Disable/Enable boot paths/features in the
 pub const BOOT_FLAGS0_R2: u16 = 0x04a; // Copied
 pub const BOOT_FLAGS1: u16 = 0x04b; // Disable/Enable boot paths/features in the RP2350 mask ROM.
 pub const BOOT_FLAGS1_R2: u16 = 0x04d; // Copied
+pub const CRITICAL: u16 = 0x154; // Critical flags of the OTP, latched on reset
+pub const ARCHSEL: u16 = 0x158; // Architecture select (Arm/RISC-V), applied on the next warm reset
+pub const ARCHSEL_STATUS: u16 = 0x15c; // Architecture of each core at the last warm reset
+
+/// Bit set means the core boots as RISC-V
+pub const ARCHSEL_RISCV: u32 = 0b1;
 
-pub struct Otp {}
+pub const CRITICAL_SECURE_BOOT_ENABLE: u32 = 1 << 0;
+pub const CRITICAL_SECURE_DEBUG_DISABLE: u32 = 1 << 1;
+pub const CRITICAL_DEBUG_DISABLE: u32 = 1 << 2;
+pub const CRITICAL_DEFAULT_ARCHSEL: u32 = 1 << 3;
+pub const CRITICAL_GLITCH_DETECTOR_ENABLE: u32 = 1 << 4;
+pub const CRITICAL_GLITCH_DETECTOR_SENS: u32 = 0b11 << 5;
+pub const CRITICAL_ARM_DISABLE: u32 = 1 << 16;
+pub const CRITICAL_RISCV_DISABLE: u32 = 1 << 17;
+
+pub struct Otp {
+    pub archsel: u32,
+    pub archsel_status: u32,
+    /// CRIT0 and CRIT1 of [`OtpData::critical`] as of the last reset
+    pub critical: u32,
+}
 
 impl Default for Otp {
     fn default() -> Self {
-        Otp {}
+        Otp {
+            archsel: 0b11,
+            archsel_status: 0b11,
+            critical: 0,
+        }
+    }
+}
+
+impl Otp {
+    /// Architecture of a core after the next reset, the one left when the other is
+    /// disabled by the critical flags
+    pub fn is_riscv(&self, core: u8) -> bool {
+        match self.critical & (CRITICAL_ARM_DISABLE | CRITICAL_RISCV_DISABLE) {
+            CRITICAL_ARM_DISABLE => true,
+            CRITICAL_RISCV_DISABLE => false,
+            _ => (self.archsel >> core) & ARCHSEL_RISCV != 0,
+        }
+    }
+
+    /// The bootrom checks the signature of the images before running them
+    pub fn is_secure_boot(&self) -> bool {
+        self.critical & CRITICAL_SECURE_BOOT_ENABLE != 0
     }
 }
 
 impl Peripheral for Otp {
     fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
         let value = match address {
-            CRIT0..=CRIT0_R7 => 0b01,     // Disable ARM core for now,
-            CRIT1..=CRIT1_R7 => 0b001000, // Bootarch RISC-V
+            // Disable the ARM core when booting as RISC-V
+            CRIT0..=CRIT0_R7 if self.is_riscv(0) => 0b01,
+            CRIT0..=CRIT0_R7 => 0b00,
+            // Bootarch RISC-V
+            CRIT1..=CRIT1_R7 if self.is_riscv(0) => 0b001000,
+            CRIT1..=CRIT1_R7 => 0b000000,
+            CRITICAL => self.critical,
+            ARCHSEL => self.archsel,
+            ARCHSEL_STATUS => self.archsel_status,
             BOOT_FLAGS0..=BOOT_FLAGS0_R2 => 0,
             BOOT_FLAGS1..=BOOT_FLAGS1_R2 => 0,
 
@@ -43,11 +95,15 @@ impl Peripheral for Otp {
 
     fn write_raw(
         &mut self,
-        _address: u16,
-        _value: u32,
+        address: u16,
+        value: u32,
         _ctx: &PeripheralAccessContext,
     ) -> PeripheralResult<()> {
-        // One Time Programable, so everything here are read only
+        // One Time Programable, so everything else here is read only
+        if address == ARCHSEL {
+            self.archsel = value & 0b11;
+        }
+
         Ok(())
     }
 }
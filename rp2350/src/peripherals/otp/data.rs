@@ -0,0 +1,362 @@
+/**
+ * @file peripherals/otp/data.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Rows of the OTP, read through the ECC and raw aliases of OTP_DATA
+ * @todo the page locks are not applied, every row can be read
+ */
+use super::CRITICAL_SECURE_BOOT_ENABLE;
+use crate::peripherals::{Peripheral, PeripheralAccessContext, PeripheralError, PeripheralResult};
+
+pub const ROWS: usize = 4096;
+/// 24 bits per row, 16 bits of data and the ECC bits
+pub const ROW_MASK: u32 = 0x00ff_ffff;
+
+pub const CHIPID0: usize = 0x000; // Bits 15:0 of the public device ID, CHIPID1..3 follow
+pub const RANDID0: usize = 0x004; // Bits 15:0 of the private random number, RANDID1..7 follow
+pub const CRIT0: usize = 0x038; // Page 0 critical boot flags (RBIT-8)
+pub const CRIT1: usize = 0x040; // Page 1 critical boot flags (RBIT-8)
+pub const BOOT_FLAGS0: usize = 0x048; // Disable/Enable boot paths/features in the mask ROM (RBIT-3)
+pub const BOOT_FLAGS1: usize = 0x04b; // Boot keys and their validity (RBIT-3)
+pub const USB_BOOT_FLAGS: usize = 0x059; // USB boot specific feature flags (RBIT-3)
+pub const USB_WHITE_LABEL_ADDR: usize = 0x05c; // Row of the white label structure
+pub const BOOTKEY0_0: usize = 0x080; // Hash of the boot key 0, the 4 keys are 16 rows each
+
+/// Bits of CRIT0 and CRIT1 as they show in the CRITICAL register of OTP
+const CRIT0_MASK: u32 = 0x03;
+const CRIT0_SHIFT: u32 = 16;
+const CRIT1_MASK: u32 = 0x7f;
+/// Copies of each critical row, a bit is set when 3 of them have it
+const CRIT_COPIES: usize = 8;
+const CRIT_VOTES: usize = 3;
+
+pub const BOOT_KEYS: usize = 4;
+const BOOT_KEY_ROWS: usize = 16;
+const BOOT_FLAGS1_KEY_VALID: u32 = 0x1;
+
+/// Valid bits of the white label entries in USB_BOOT_FLAGS, one per entry
+const USB_BOOT_FLAGS_WHITE_LABEL_ADDR_VALID: u32 = 1 << 22;
+
+/// Row the white label structure is written at, in the user area after the boot keys
+pub const WHITE_LABEL_ROW: usize = 0x0c0;
+const WHITE_LABEL_ENTRIES: usize = 16;
+
+/// ID the default content gives the chip
+const DEFAULT_CHIP_ID: u64 = 0x5e1f_2350_0000_0001;
+
+/// Offsets of the aliases in the OTP_DATA window
+const RAW_ALIAS: u32 = 0x4000;
+const GUARDED_ALIAS: u32 = 0x8000;
+
+/// Entries of the white label the bootrom shows on its USB device and drive.
+/// The missing ones keep the defaults of the bootrom
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WhiteLabel {
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+    pub volume_label: Option<String>,
+    /// Board-ID of INFO_UF2.TXT
+    pub board_id: Option<String>,
+}
+
+/// The fuses of the OTP, kept through the resets and the power cycles like the flash.
+///
+/// The rows read through the ECC alias return their 16 bits of data. The ECC bits are not
+/// computed, a row programmed with ECC has them clear, and a row programmed raw reads as
+/// an uncorrectable error through the ECC alias unless it is blank. The guarded aliases
+/// fault on that error where the others return the data bits as they are.
+#[derive(Clone, PartialEq, Eq)]
+pub struct OtpData {
+    rows: Vec<u32>,
+    /// Whether each row was programmed with ECC
+    ecc: Vec<bool>,
+}
+
+impl Default for OtpData {
+    fn default() -> Self {
+        let mut otp = Self::blank();
+        otp.set_chip_id(DEFAULT_CHIP_ID);
+        otp
+    }
+}
+
+impl OtpData {
+    /// Every row unprogrammed, not even the chip ID
+    pub fn blank() -> Self {
+        Self {
+            rows: vec![0; ROWS],
+            ecc: vec![false; ROWS],
+        }
+    }
+
+    /// Raw content of a row, 24 bits
+    pub fn row(&self, row: usize) -> Option<u32> {
+        self.rows.get(row).copied()
+    }
+
+    pub fn is_ecc(&self, row: usize) -> bool {
+        self.ecc.get(row).copied().unwrap_or(false)
+    }
+
+    /// Program the 16 bits of a row protected by ECC
+    pub fn program_ecc(&mut self, row: usize, value: u16) {
+        if row < ROWS {
+            self.rows[row] = value as u32;
+            self.ecc[row] = true;
+        }
+    }
+
+    /// Program the 24 bits of a row, without ECC
+    pub fn program_raw(&mut self, row: usize, value: u32) {
+        if row < ROWS {
+            self.rows[row] = value & ROW_MASK;
+            self.ecc[row] = false;
+        }
+    }
+
+    /// Program a flag row and its copies, the bootrom takes a majority vote
+    fn program_redundant(&mut self, row: usize, copies: usize, value: u32) {
+        for copy in row..row + copies {
+            self.program_raw(copy, value);
+        }
+    }
+
+    /// Public 64 bits ID of the chip, read by the bootrom and `pico_get_unique_board_id`
+    pub fn chip_id(&self) -> u64 {
+        (0..4).fold(0, |id, i| {
+            id | (self.rows[CHIPID0 + i] as u64 & 0xffff) << (16 * i)
+        })
+    }
+
+    pub fn set_chip_id(&mut self, id: u64) {
+        for i in 0..4 {
+            self.program_ecc(CHIPID0 + i, (id >> (16 * i)) as u16);
+        }
+    }
+
+    /// Random ID of the chip, 128 bits in RANDID0..7
+    pub fn set_random_id(&mut self, id: u128) {
+        for i in 0..8 {
+            self.program_ecc(RANDID0 + i, (id >> (16 * i)) as u16);
+        }
+    }
+
+    /// The critical flags in the layout of the CRITICAL register, CRIT1 in the low bits
+    /// and CRIT0 from bit 16, after the vote of their 8 copies
+    pub fn critical(&self) -> u32 {
+        let vote = |row: usize| {
+            (0..24)
+                .filter(|bit| {
+                    let copies = &self.rows[row..row + CRIT_COPIES];
+                    copies.iter().filter(|copy| *copy >> bit & 1 != 0).count() >= CRIT_VOTES
+                })
+                .fold(0, |flags, bit| flags | 1 << bit)
+        };
+
+        (vote(CRIT0) & CRIT0_MASK) << CRIT0_SHIFT | vote(CRIT1) & CRIT1_MASK
+    }
+
+    /// Program the critical flags in the layout of the CRITICAL register, in their 8 copies
+    pub fn set_critical(&mut self, critical: u32) {
+        self.program_redundant(CRIT0, CRIT_COPIES, critical >> CRIT0_SHIFT & CRIT0_MASK);
+        self.program_redundant(CRIT1, CRIT_COPIES, critical & CRIT1_MASK);
+    }
+
+    /// Turn on the secure boot, the bootrom then only runs the images signed by one of
+    /// the boot keys
+    pub fn enable_secure_boot(&mut self) {
+        self.set_critical(self.critical() | CRITICAL_SECURE_BOOT_ENABLE);
+    }
+
+    /// Boot flags of the bootrom, 24 bits each, in their 3 copies
+    pub fn set_boot_flags(&mut self, flags0: u32, flags1: u32) {
+        self.program_redundant(BOOT_FLAGS0, 3, flags0);
+        self.program_redundant(BOOT_FLAGS1, 3, flags1);
+    }
+
+    /// SHA-256 hash of a public key the secure boot accepts, marked valid in BOOT_FLAGS1
+    pub fn set_boot_key(&mut self, index: usize, hash: &[u8; 32]) {
+        if index >= BOOT_KEYS {
+            return;
+        }
+
+        let base = BOOTKEY0_0 + index * BOOT_KEY_ROWS;
+        for (i, half) in hash.chunks_exact(2).enumerate() {
+            self.program_ecc(base + i, u16::from_le_bytes([half[0], half[1]]));
+        }
+
+        let flags1 = self.rows[BOOT_FLAGS1] | BOOT_FLAGS1_KEY_VALID << index;
+        self.program_redundant(BOOT_FLAGS1, 3, flags1);
+    }
+
+    /// Write the white label structure at [`WHITE_LABEL_ROW`], with its strings after the
+    /// entries, and point USB_WHITE_LABEL_ADDR to it
+    pub fn set_white_label(&mut self, label: &WhiteLabel) {
+        let base = WHITE_LABEL_ROW;
+        let mut next_string = WHITE_LABEL_ENTRIES;
+        let mut valid = USB_BOOT_FLAGS_WHITE_LABEL_ADDR_VALID;
+
+        let values = [(0, label.vendor_id), (1, label.product_id)];
+        for (entry, value) in values {
+            if let Some(value) = value {
+                self.program_ecc(base + entry, value);
+                valid |= 1 << entry;
+            }
+        }
+
+        let strings = [
+            (4, &label.manufacturer),
+            (5, &label.product),
+            (6, &label.serial_number),
+            (8, &label.volume_label),
+            (15, &label.board_id),
+        ];
+
+        for (entry, string) in strings {
+            let Some(string) = string else {
+                continue;
+            };
+
+            // ASCII, 2 characters per row, the length and the row offset in the entry
+            let bytes: Vec<u8> = string.bytes().filter(u8::is_ascii).take(0x7f).collect();
+            self.program_ecc(base + entry, (next_string as u16) << 8 | bytes.len() as u16);
+
+            for pair in bytes.chunks(2) {
+                let high = pair.get(1).copied().unwrap_or(0);
+                self.program_ecc(base + next_string, u16::from_le_bytes([pair[0], high]));
+                next_string += 1;
+            }
+
+            valid |= 1 << entry;
+        }
+
+        self.program_ecc(USB_WHITE_LABEL_ADDR, base as u16);
+        self.program_redundant(USB_BOOT_FLAGS, 3, valid);
+    }
+
+    /// Data bits of a row read through the ECC alias, None on an uncorrectable error
+    fn read_ecc(&self, row: usize) -> Option<u32> {
+        let value = *self.rows.get(row)?;
+
+        match self.ecc[row] || value == 0 {
+            true => Some(value & 0xffff),
+            false => None,
+        }
+    }
+}
+
+impl Peripheral for OtpData {
+    /// The 4 aliases share the rows, the alias is taken from the whole address
+    fn read(&self, _address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let offset = ctx.address & 0x3fff;
+        let guarded = ctx.address & GUARDED_ALIAS != 0;
+
+        // raw, a row per word
+        if ctx.address & RAW_ALIAS != 0 {
+            return Ok(self.rows[(offset / 4) as usize]);
+        }
+
+        // ECC, 2 rows per word
+        let row = (offset / 4) as usize * 2;
+        if row >= ROWS {
+            return Err(PeripheralError::OutOfBounds);
+        }
+
+        let rows = (self.read_ecc(row), self.read_ecc(row + 1));
+
+        match rows {
+            (Some(low), Some(high)) => Ok(high << 16 | low),
+            _ if guarded => Err(PeripheralError::EccError),
+            _ => Ok((self.rows[row + 1] & 0xffff) << 16 | self.rows[row] & 0xffff),
+        }
+    }
+
+    fn write_raw(
+        &mut self,
+        _address: u16,
+        _value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        // programmed through the bootrom, the aliases are read only
+        log::warn!("Write to the read only OTP data at {:#X}", ctx.address);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::otp::CRITICAL_RISCV_DISABLE;
+
+    fn read(otp: &OtpData, address: u32) -> PeripheralResult<u32> {
+        let ctx = PeripheralAccessContext {
+            address,
+            ..Default::default()
+        };
+
+        otp.read(address as u16 & 0xfff, &ctx)
+    }
+
+    #[test]
+    fn test_aliases() {
+        let mut otp = OtpData::blank();
+        otp.set_chip_id(0x1122_3344_5566_7788);
+        otp.program_raw(0x802, 0xab_cdef);
+
+        assert_eq!(otp.chip_id(), 0x1122_3344_5566_7788);
+        assert_eq!(read(&otp, 0x4013_0000), Ok(0x5566_7788));
+        assert_eq!(read(&otp, 0x4013_8004), Ok(0x1122_3344));
+        assert_eq!(read(&otp, 0x4013_4004), Ok(0x5566));
+
+        // a raw row through the ECC aliases
+        assert_eq!(read(&otp, 0x4013_6008), Ok(0xab_cdef));
+        assert_eq!(read(&otp, 0x4013_1004), Ok(0xcdef));
+        assert_eq!(read(&otp, 0x4013_9004), Err(PeripheralError::EccError));
+        assert_eq!(read(&otp, 0x4013_e008), Ok(0xab_cdef));
+    }
+
+    #[test]
+    fn test_critical() {
+        let mut otp = OtpData::blank();
+        otp.enable_secure_boot();
+        otp.set_critical(otp.critical() | CRITICAL_RISCV_DISABLE);
+
+        assert_eq!(otp.row(CRIT1 + 7), Some(1));
+        assert_eq!(otp.row(CRIT0 + 7), Some(0b10));
+        assert_eq!(otp.critical(), 1 << 17 | 1);
+
+        // 2 copies out of 8 are not enough
+        otp.program_raw(CRIT1, 0b100);
+        otp.program_raw(CRIT1 + 1, 0b100);
+        assert_eq!(otp.critical() & 0b100, 0);
+        otp.program_raw(CRIT1 + 2, 0b100);
+        assert_eq!(otp.critical() & 0b100, 0b100);
+    }
+
+    #[test]
+    fn test_white_label() {
+        let mut otp = OtpData::blank();
+        otp.set_white_label(&WhiteLabel {
+            vendor_id: Some(0x2e8a),
+            product: Some("Pico".to_string()),
+            ..Default::default()
+        });
+
+        let base = WHITE_LABEL_ROW;
+        assert_eq!(otp.row(USB_WHITE_LABEL_ADDR), Some(base as u32));
+        assert_eq!(otp.row(USB_BOOT_FLAGS + 2), Some(1 << 22 | 1 << 5 | 1));
+        assert_eq!(otp.row(base), Some(0x2e8a));
+        assert_eq!(otp.row(base + 5), Some(0x10 << 8 | 4));
+        assert_eq!(
+            otp.row(base + 0x10),
+            Some(u16::from_le_bytes(*b"Pi") as u32)
+        );
+        assert_eq!(
+            otp.row(base + 0x11),
+            Some(u16::from_le_bytes(*b"co") as u32)
+        );
+    }
+}
@@ -0,0 +1,536 @@
+/**
+ * @file peripherals/pio.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Programmable I/O block with its four state machines
+ */
+use super::*;
+use crate::clock::Clock;
+use crate::gpio::GpioController;
+use crate::interrupts::{Interrupt, Interrupts};
+use crate::utils::{extract_bits, w1c};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub mod instruction;
+pub mod state_machine;
+
+pub use instruction::Instruction;
+use state_machine::*;
+pub use state_machine::{FIFO_DEPTH, INSTRUCTION_MEMORY_SIZE, StateMachine};
+
+pub const CTRL: u16 = 0x000; // PIO control register
+pub const FSTAT: u16 = 0x004; // FIFO status register
+pub const FDEBUG: u16 = 0x008; // FIFO debug register
+pub const FLEVEL: u16 = 0x00c; // FIFO levels
+pub const TXF0: u16 = 0x010; // Direct write access to the TX FIFO for this state machine
+pub const TXF3: u16 = 0x01c;
+pub const RXF0: u16 = 0x020; // Direct read access to the RX FIFO for this state machine
+pub const RXF3: u16 = 0x02c;
+pub const IRQ: u16 = 0x030; // State machine IRQ flags register, write 1 to clear
+pub const IRQ_FORCE: u16 = 0x034; // Writing a 1 to each of these bits will forcibly assert the corresponding IRQ
+pub const INPUT_SYNC_BYPASS: u16 = 0x038; // There is a 2-flipflop synchronizer on each GPIO input
+pub const DBG_PADOUT: u16 = 0x03c; // Read to sample the pad output values PIO is currently driving to the GPIOs
+pub const DBG_PADOE: u16 = 0x040; // Read to sample the pad output enables (direction) PIO is currently driving to the GPIOs
+pub const DBG_CFGINFO: u16 = 0x044; // The PIO hardware has some free parameters that may vary between chip products
+pub const INSTR_MEM0: u16 = 0x048; // Write-only access to instruction memory location 0
+pub const INSTR_MEM31: u16 = 0x0c4;
+pub const SM0_CLKDIV: u16 = 0x0c8; // Clock divisor register for state machine 0
+pub const SM0_EXECCTRL: u16 = 0x0cc; // Execution/behavioural settings for state machine 0
+pub const SM0_SHIFTCTRL: u16 = 0x0d0; // Control behaviour of the input/output shift registers for state machine 0
+pub const SM0_ADDR: u16 = 0x0d4; // Current instruction address of state machine 0
+pub const SM0_INSTR: u16 = 0x0d8; // Read to see the instruction currently addressed by state machine 0's program counter
+pub const SM0_PINCTRL: u16 = 0x0dc; // State machine pin control
+pub const SM3_PINCTRL: u16 = 0x124;
+pub const RXF0_PUTGET0: u16 = 0x128; // Direct read/write access to entry 0 of SM0's RX FIFO, if SHIFTCTRL_FJOIN_RX_PUT xor SHIFTCTRL_FJOIN_RX_GET is set
+pub const RXF3_PUTGET3: u16 = 0x164;
+pub const GPIOBASE: u16 = 0x168; // Relocate GPIO 0 (from PIO's point of view) in the system GPIO numbering
+pub const INTR: u16 = 0x16c; // Raw Interrupts
+pub const IRQ0_INTE: u16 = 0x170; // Interrupt Enable for irq0
+pub const IRQ0_INTF: u16 = 0x174; // Interrupt Force for irq0
+pub const IRQ0_INTS: u16 = 0x178; // Interrupt status after masking & forcing for irq0
+pub const IRQ1_INTE: u16 = 0x17c; // Interrupt Enable for irq1
+pub const IRQ1_INTF: u16 = 0x180; // Interrupt Force for irq1
+pub const IRQ1_INTS: u16 = 0x184; // Interrupt status after masking & forcing for irq1
+
+const SM_OFFSET: u16 = 0x018; // Offset to the registers of the next state machine
+const RXF_PUTGET_OFFSET: u16 = 0x010; // Offset to the RX registers of the next state machine
+pub const NOF_STATE_MACHINE: usize = 4;
+
+// version 1, 32 instructions, 4 state machines, FIFOs of 4 entries
+const CFGINFO: u32 = (1 << 28) | (32 << 16) | (4 << 8) | 4;
+
+pub struct Pio<const IDX: usize> {
+    pub state_machines: [StateMachine; NOF_STATE_MACHINE],
+    pub instruction_memory: [u16; INSTRUCTION_MEMORY_SIZE],
+    pub enabled: u8,
+    pub irq: u8,
+    pub fdebug: u32,
+    pub input_sync_bypass: u32,
+    pub gpio_base: u32,
+    pub interrupt_enable: [u16; 2],
+    pub interrupt_force: [u16; 2],
+    /// Output levels driven by the state machines, relative to GPIOBASE
+    pub pad_out: u32,
+    /// Output enables driven by the state machines, relative to GPIOBASE
+    pub pad_oe: u32,
+
+    // last state given to the rest of the chip, to only update them on change
+    last_pads: Option<(u32, u32)>,
+    last_dreq: u8,
+    last_irq: [bool; 2],
+}
+
+impl<const IDX: usize> Default for Pio<IDX> {
+    fn default() -> Self {
+        Self {
+            state_machines: Default::default(),
+            instruction_memory: [0; INSTRUCTION_MEMORY_SIZE],
+            enabled: 0,
+            irq: 0,
+            fdebug: 0,
+            input_sync_bypass: 0,
+            gpio_base: 0,
+            interrupt_enable: [0; 2],
+            interrupt_force: [0; 2],
+            pad_out: 0,
+            pad_oe: 0,
+            last_pads: None,
+            last_dreq: 0,
+            last_irq: [false; 2],
+        }
+    }
+}
+
+impl<const IDX: usize> Pio<IDX> {
+    const IRQ: [Interrupt; 2] = [
+        Interrupts::PIO0_IRQ_0 + 2 * IDX as Interrupt,
+        Interrupts::PIO0_IRQ_1 + 2 * IDX as Interrupt,
+    ];
+
+    /// DREQ of the TX FIFO of SM0, the RX FIFO of SM0 is 4 above
+    const DREQ_BASE: usize = IDX * 8;
+
+    fn fstat(&self) -> u32 {
+        let mut result = 0;
+
+        for (i, sm) in self.state_machines.iter().enumerate() {
+            result |= (sm.is_rx_full() as u32) << i;
+            result |= (sm.rx_fifo.is_empty() as u32) << (i + 8);
+            result |= (sm.is_tx_full() as u32) << (i + 16);
+            result |= (sm.tx_fifo.is_empty() as u32) << (i + 24);
+        }
+
+        result
+    }
+
+    fn flevel(&self) -> u32 {
+        let mut result = 0;
+
+        for (i, sm) in self.state_machines.iter().enumerate() {
+            let level = (sm.tx_fifo.len() as u32 & 0xf) | ((sm.rx_fifo.len() as u32 & 0xf) << 4);
+            result |= level << (i * 8);
+        }
+
+        result
+    }
+
+    fn interrupt_raw(&self) -> u16 {
+        let mut result = (self.irq as u16) << 8;
+
+        for (i, sm) in self.state_machines.iter().enumerate() {
+            result |= (!sm.rx_fifo.is_empty() as u16) << i;
+            result |= (!sm.is_tx_full() as u16) << (i + 4);
+        }
+
+        result
+    }
+
+    fn interrupt_status(&self, line: usize) -> u16 {
+        (self.interrupt_raw() | self.interrupt_force[line]) & self.interrupt_enable[line]
+    }
+
+    /// Level of the DREQ signals, TX not full in the lower nibble and RX not empty in the upper one
+    fn dreq(&self) -> u8 {
+        let mut result = 0;
+
+        for (i, sm) in self.state_machines.iter().enumerate() {
+            result |= (!sm.is_tx_full() as u8) << i;
+            result |= (!sm.rx_fifo.is_empty() as u8) << (i + 4);
+        }
+
+        result
+    }
+
+    /// GPIO inputs as seen by the state machines
+    fn inputs(&self, gpio: &GpioController) -> u32 {
+        let mut result = 0;
+
+        for i in 0..32 {
+            if gpio.input_value((i + self.gpio_base) as u8) {
+                result |= 1 << i;
+            }
+        }
+
+        result
+    }
+
+    fn write_ctrl(&mut self, value: u32) {
+        self.enabled = extract_bits(value, 0..=3) as u8;
+
+        for (i, sm) in self.state_machines.iter_mut().enumerate() {
+            if extract_bits(value, 4..=7) & (1 << i) != 0 {
+                sm.restart();
+            }
+
+            if extract_bits(value, 8..=11) & (1 << i) != 0 {
+                sm.restart_clock_divider();
+            }
+        }
+    }
+
+    /// Advance the state machines by one clk_sys cycle
+    pub(crate) fn tick(
+        &mut self,
+        gpio: &RefCell<GpioController>,
+        interrupts: &RefCell<Interrupts>,
+        dma: &RefCell<Dma>,
+        clock: &Rc<Clock>,
+    ) {
+        let has_pending = self
+            .state_machines
+            .iter()
+            .any(|sm| sm.pending_exec.is_some());
+
+        if self.enabled != 0 || has_pending {
+            self.run_state_machines(gpio);
+        }
+
+        let pads = (self.pad_oe, self.pad_out);
+        if self.last_pads != Some(pads) {
+            self.last_pads = Some(pads);
+            gpio.borrow_mut().update_pio(
+                IDX,
+                self.pad_oe << self.gpio_base,
+                self.pad_out << self.gpio_base,
+            );
+        }
+
+        let dreq = self.dreq();
+        let changed = dreq ^ self.last_dreq;
+        if changed != 0 {
+            let mut dma = dma.borrow_mut();
+
+            for i in (0..8).filter(|i| changed & (1 << i) != 0) {
+                match dreq & (1 << i) != 0 {
+                    true => dma.set_dreg(Self::DREQ_BASE + i, Rc::clone(clock)),
+                    false => dma.clear_dreg(Self::DREQ_BASE + i),
+                }
+            }
+
+            self.last_dreq = dreq;
+        }
+
+        for line in 0..2 {
+            let irq = self.interrupt_status(line) != 0;
+            if self.last_irq[line] != irq {
+                self.last_irq[line] = irq;
+                interrupts.borrow_mut().set_irq(Self::IRQ[line], irq);
+            }
+        }
+    }
+
+    fn run_state_machines(&mut self, gpio: &RefCell<GpioController>) {
+        let inputs = self.inputs(&gpio.borrow());
+        let enabled = self.enabled;
+        let mut signals = PioSignals {
+            instruction_memory: &self.instruction_memory,
+            inputs,
+            irq: &mut self.irq,
+            pad_out: &mut self.pad_out,
+            pad_oe: &mut self.pad_oe,
+            fdebug: &mut self.fdebug,
+        };
+
+        // Higher numbered state machines take priority on the pins, by running last
+        for (i, sm) in self.state_machines.iter_mut().enumerate() {
+            let enabled = enabled & (1 << i) != 0;
+
+            if enabled && sm.clock_enable() {
+                sm.step(i, &mut signals);
+            } else if !enabled && sm.pending_exec.is_some() {
+                // SM_INSTR also executes on a disabled state machine
+                sm.step(i, &mut signals);
+            }
+        }
+    }
+
+    fn state_machine_register(&self, address: u16) -> PeripheralResult<u32> {
+        let index = ((address - SM0_CLKDIV) / SM_OFFSET) as usize;
+        let sm = &self.state_machines[index];
+
+        let value = match (address - SM0_CLKDIV) % SM_OFFSET + SM0_CLKDIV {
+            SM0_CLKDIV => sm.clkdiv,
+            SM0_EXECCTRL => {
+                let stalled = sm.stalled && sm.pending_exec.is_some();
+                (sm.execctrl & !EXECCTRL_EXEC_STALLED) | ((stalled as u32) << 31)
+            }
+            SM0_SHIFTCTRL => sm.shiftctrl,
+            SM0_ADDR => sm.pc as u32,
+            SM0_INSTR => sm.current_instruction(&self.instruction_memory) as u32,
+            SM0_PINCTRL => sm.pinctrl,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_state_machine_register(&mut self, address: u16, value: u32) -> PeripheralResult<()> {
+        let index = ((address - SM0_CLKDIV) / SM_OFFSET) as usize;
+        let sm = &mut self.state_machines[index];
+
+        match (address - SM0_CLKDIV) % SM_OFFSET + SM0_CLKDIV {
+            SM0_CLKDIV => sm.clkdiv = value & 0xffff_ff00,
+            SM0_EXECCTRL => sm.execctrl = value & !EXECCTRL_EXEC_STALLED,
+            SM0_SHIFTCTRL => sm.set_shiftctrl(value),
+            SM0_ADDR => { /* read-only */ }
+            SM0_INSTR => sm.pending_exec = Some(value as u16),
+            SM0_PINCTRL => sm.pinctrl = value,
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+impl<const IDX: usize> Peripheral for Rc<RefCell<Pio<IDX>>> {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let mut pio = self.borrow_mut();
+
+        let value = match address {
+            CTRL => pio.enabled as u32,
+            FSTAT => pio.fstat(),
+            FDEBUG => pio.fdebug,
+            FLEVEL => pio.flevel(),
+            TXF0..=TXF3 => 0, // write-only
+            RXF0..=RXF3 => {
+                let index = ((address - RXF0) / 4) as usize;
+                match pio.state_machines[index].rx_fifo.pop_front() {
+                    Some(value) => value,
+                    None => {
+                        pio.fdebug |= 1 << (FDEBUG_RXUNDER + index as u32);
+                        0
+                    }
+                }
+            }
+            IRQ => pio.irq as u32,
+            IRQ_FORCE => 0,
+            INPUT_SYNC_BYPASS => pio.input_sync_bypass,
+            DBG_PADOUT => pio.pad_out,
+            DBG_PADOE => pio.pad_oe,
+            DBG_CFGINFO => CFGINFO,
+            INSTR_MEM0..=INSTR_MEM31 => 0, // write-only
+            SM0_CLKDIV..=SM3_PINCTRL => pio.state_machine_register(address)?,
+            RXF0_PUTGET0..=RXF3_PUTGET3 => {
+                let index = ((address - RXF0_PUTGET0) / RXF_PUTGET_OFFSET) as usize;
+                let entry = ((address - RXF0_PUTGET0) % RXF_PUTGET_OFFSET / 4) as usize;
+                pio.state_machines[index].rx_registers[entry]
+            }
+            GPIOBASE => pio.gpio_base,
+            INTR => pio.interrupt_raw() as u32,
+            IRQ0_INTE => pio.interrupt_enable[0] as u32,
+            IRQ0_INTF => pio.interrupt_force[0] as u32,
+            IRQ0_INTS => pio.interrupt_status(0) as u32,
+            IRQ1_INTE => pio.interrupt_enable[1] as u32,
+            IRQ1_INTF => pio.interrupt_force[1] as u32,
+            IRQ1_INTS => pio.interrupt_status(1) as u32,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut pio = self.borrow_mut();
+
+        match address {
+            CTRL => pio.write_ctrl(value),
+            FSTAT | FLEVEL => { /* read-only */ }
+            FDEBUG => w1c(&mut pio.fdebug, value, 0x0f0f_0f0f),
+            TXF0..=TXF3 => {
+                let index = ((address - TXF0) / 4) as usize;
+
+                if pio.state_machines[index].is_tx_full() {
+                    pio.fdebug |= 1 << (FDEBUG_TXOVER + index as u32);
+                } else {
+                    pio.state_machines[index].tx_fifo.push_back(value);
+                }
+            }
+            RXF0..=RXF3 => { /* read-only */ }
+            IRQ => pio.irq &= !(value as u8),
+            IRQ_FORCE => pio.irq |= value as u8,
+            INPUT_SYNC_BYPASS => pio.input_sync_bypass = value,
+            DBG_PADOUT | DBG_PADOE | DBG_CFGINFO => { /* read-only */ }
+            INSTR_MEM0..=INSTR_MEM31 => {
+                let index = ((address - INSTR_MEM0) / 4) as usize;
+                pio.instruction_memory[index] = value as u16;
+            }
+            SM0_CLKDIV..=SM3_PINCTRL => pio.write_state_machine_register(address, value)?,
+            RXF0_PUTGET0..=RXF3_PUTGET3 => {
+                let index = ((address - RXF0_PUTGET0) / RXF_PUTGET_OFFSET) as usize;
+                let entry = ((address - RXF0_PUTGET0) % RXF_PUTGET_OFFSET / 4) as usize;
+                pio.state_machines[index].rx_registers[entry] = value;
+            }
+            // Only GPIO 0 and 16 are valid bases
+            GPIOBASE => pio.gpio_base = value & (1 << 4),
+            INTR | IRQ0_INTS | IRQ1_INTS => { /* read-only */ }
+            IRQ0_INTE => pio.interrupt_enable[0] = value as u16,
+            IRQ0_INTF => pio.interrupt_force[0] = value as u16,
+            IRQ1_INTE => pio.interrupt_enable[1] = value as u16,
+            IRQ1_INTF => pio.interrupt_force[1] = value as u16,
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::FunctionSelect;
+
+    const PIO0_FUNCSEL: u32 = 6;
+
+    struct Harness {
+        pio: Rc<RefCell<Pio<0>>>,
+        gpio: Rc<RefCell<GpioController>>,
+        interrupts: Rc<RefCell<Interrupts>>,
+        dma: Rc<RefCell<Dma>>,
+        ctx: PeripheralAccessContext,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let ctx = PeripheralAccessContext::default();
+            Self {
+                pio: Default::default(),
+                gpio: Rc::clone(&ctx.gpio),
+                interrupts: Rc::clone(&ctx.interrupts),
+                dma: Rc::clone(&ctx.dma),
+                ctx,
+            }
+        }
+
+        fn write(&mut self, address: u16, value: u32) {
+            self.pio.write_raw(address, value, &self.ctx).unwrap();
+        }
+
+        fn read(&self, address: u16) -> u32 {
+            self.pio.read(address, &self.ctx).unwrap()
+        }
+
+        fn load(&mut self, program: &[u16]) {
+            for (i, instruction) in program.iter().enumerate() {
+                self.write(INSTR_MEM0 + i as u16 * 4, *instruction as u32);
+            }
+        }
+
+        fn tick(&mut self, cycles: usize) {
+            for _ in 0..cycles {
+                self.pio.borrow_mut().tick(
+                    &self.gpio,
+                    &self.interrupts,
+                    &self.dma,
+                    &self.ctx.clock,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_and_side_set_drive_the_pins() {
+        let mut h = Harness::new();
+        h.gpio.borrow_mut().pins[2].ctrl = PIO0_FUNCSEL;
+        h.gpio.borrow_mut().pins[3].ctrl = PIO0_FUNCSEL;
+
+        // set pindirs, 1 side 0
+        // set pins, 1    side 1 [1]
+        h.load(&[0xe081, 0xf101]);
+        // SET_BASE = 2, SET_COUNT = 1, SIDESET_BASE = 3, SIDESET_COUNT = 1
+        h.write(SM0_PINCTRL, (1 << 29) | (1 << 26) | (3 << 10) | (2 << 5));
+        // wrap at the second instruction
+        h.write(SM0_EXECCTRL, 1 << 12);
+        h.write(CTRL, 1);
+
+        h.tick(2);
+        assert_eq!(h.read(DBG_PADOE), 1 << 2);
+        assert_eq!(h.read(DBG_PADOUT), (1 << 2) | (1 << 3));
+        assert!(h.gpio.borrow().pin_state(2).is_high());
+        assert_eq!(h.gpio.borrow().pins[2].func_sel(), FunctionSelect::PIO_0);
+
+        // the delay of the second instruction postpones the wrap to the first one
+        h.tick(1);
+        assert_eq!(h.read(DBG_PADOUT), (1 << 2) | (1 << 3));
+        h.tick(1);
+        assert_eq!(h.read(DBG_PADOUT), 1 << 2);
+    }
+
+    #[test]
+    fn test_autopull_and_autopush() {
+        let mut h = Harness::new();
+
+        // out x, 8
+        // in x, 8
+        h.load(&[0x6028, 0x4028]);
+        // autopush and autopull with a threshold of 16
+        h.write(SM0_SHIFTCTRL, (16 << 25) | (16 << 20) | (0b1111 << 16));
+        h.write(SM0_EXECCTRL, 1 << 12);
+        h.write(TXF0, 0xabcd);
+        h.write(CTRL, 1);
+
+        h.tick(4);
+        assert_eq!(h.read(FLEVEL) & 0xff, 1 << 4);
+        assert_eq!(h.read(RXF0), 0xabcd << 16);
+
+        // stalls on an empty TX FIFO
+        h.tick(2);
+        assert_eq!(h.read(SM0_ADDR), 0);
+        assert_ne!(h.read(FDEBUG) & (1 << FDEBUG_TXSTALL), 0);
+        assert_eq!(h.read(INTR) & 0xf, 0);
+    }
+
+    #[test]
+    fn test_irq_flags_raise_the_interrupt() {
+        let mut h = Harness::new();
+
+        // irq 1
+        h.load(&[0xc001]);
+        h.write(IRQ0_INTE, 1 << 9);
+        h.write(SM0_INSTR, 0xc001);
+        h.tick(1);
+
+        assert_eq!(h.read(IRQ), 1 << 1);
+        assert_eq!(h.read(IRQ0_INTS), 1 << 9);
+        assert!(
+            h.interrupts
+                .borrow()
+                .iter(0)
+                .any(|v| v == Interrupts::PIO0_IRQ_0)
+        );
+
+        h.write(IRQ, 1 << 1);
+        h.tick(1);
+        assert!(
+            !h.interrupts
+                .borrow()
+                .iter(0)
+                .any(|v| v == Interrupts::PIO0_IRQ_0)
+        );
+    }
+}
@@ -0,0 +1,390 @@
+/**
+ * @file peripherals/pio/instruction.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Decoder of the PIO instruction set
+ */
+use crate::utils::extract_bits;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JmpCondition {
+    Always,
+    XZero,
+    XPostDec,
+    YZero,
+    YPostDec,
+    XNotEqualY,
+    Pin,
+    OsrNotEmpty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitSource {
+    Gpio,
+    Pin,
+    Irq,
+    JmpPin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InSource {
+    Pins,
+    X,
+    Y,
+    Null,
+    Isr,
+    Osr,
+    Reserved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutDestination {
+    Pins,
+    X,
+    Y,
+    Null,
+    PinDirs,
+    Pc,
+    Isr,
+    Exec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovDestination {
+    Pins,
+    X,
+    Y,
+    PinDirs,
+    Exec,
+    Pc,
+    Isr,
+    Osr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovSource {
+    Pins,
+    X,
+    Y,
+    Null,
+    Status,
+    Isr,
+    Osr,
+    Reserved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovOperation {
+    None,
+    Invert,
+    BitReverse,
+    Reserved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetDestination {
+    Pins,
+    X,
+    Y,
+    PinDirs,
+    Reserved,
+}
+
+/// How the index of an IRQ flag is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqIndexMode {
+    /// The flag of this PIO block
+    Direct,
+    /// The flag of the previous PIO block
+    Prev,
+    /// The state machine number is added to the lower two bits of the index
+    Relative,
+    /// The flag of the next PIO block
+    Next,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Jmp {
+        condition: JmpCondition,
+        address: u8,
+    },
+    Wait {
+        polarity: bool,
+        source: WaitSource,
+        index: u8,
+        /// Only meaningful for the IRQ source
+        mode: IrqIndexMode,
+    },
+    In {
+        source: InSource,
+        bit_count: u8,
+    },
+    Out {
+        destination: OutDestination,
+        bit_count: u8,
+    },
+    Push {
+        if_full: bool,
+        block: bool,
+    },
+    Pull {
+        if_empty: bool,
+        block: bool,
+    },
+    /// `mov rxfifo[], isr`, only available with FJOIN_RX_PUT
+    MovToRx {
+        index: Option<u8>,
+    },
+    /// `mov osr, rxfifo[]`, only available with FJOIN_RX_GET
+    MovFromRx {
+        index: Option<u8>,
+    },
+    Mov {
+        destination: MovDestination,
+        operation: MovOperation,
+        source: MovSource,
+    },
+    Irq {
+        clear: bool,
+        wait: bool,
+        index: u8,
+        mode: IrqIndexMode,
+    },
+    Set {
+        destination: SetDestination,
+        data: u8,
+    },
+}
+
+/// Bit count of IN and OUT, where 0 encodes 32
+fn bit_count(instruction: u16) -> u8 {
+    match extract_bits(instruction, 0..=4) as u8 {
+        0 => 32,
+        count => count,
+    }
+}
+
+fn irq_index_mode(instruction: u16) -> IrqIndexMode {
+    match extract_bits(instruction, 3..=4) {
+        0 => IrqIndexMode::Direct,
+        1 => IrqIndexMode::Prev,
+        2 => IrqIndexMode::Relative,
+        _ => IrqIndexMode::Next,
+    }
+}
+
+/// Index of `mov rxfifo[]`, `None` when it is taken from the Y register
+fn rx_index(instruction: u16) -> Option<u8> {
+    match extract_bits(instruction, 3..=3) {
+        0 => None,
+        _ => Some(extract_bits(instruction, 0..=1) as u8),
+    }
+}
+
+impl Instruction {
+    pub fn decode(instruction: u16) -> Self {
+        let arg1 = extract_bits(instruction, 5..=7);
+        let arg2 = extract_bits(instruction, 0..=4) as u8;
+
+        match extract_bits(instruction, 13..=15) {
+            0b000 => Self::Jmp {
+                condition: match arg1 {
+                    0 => JmpCondition::Always,
+                    1 => JmpCondition::XZero,
+                    2 => JmpCondition::XPostDec,
+                    3 => JmpCondition::YZero,
+                    4 => JmpCondition::YPostDec,
+                    5 => JmpCondition::XNotEqualY,
+                    6 => JmpCondition::Pin,
+                    _ => JmpCondition::OsrNotEmpty,
+                },
+                address: arg2,
+            },
+
+            0b001 => {
+                let source = match extract_bits(instruction, 5..=6) {
+                    0 => WaitSource::Gpio,
+                    1 => WaitSource::Pin,
+                    2 => WaitSource::Irq,
+                    _ => WaitSource::JmpPin,
+                };
+
+                let (index, mode) = match source {
+                    WaitSource::Irq => (arg2 & 0b111, irq_index_mode(instruction)),
+                    _ => (arg2, IrqIndexMode::Direct),
+                };
+
+                Self::Wait {
+                    polarity: extract_bits(instruction, 7..=7) != 0,
+                    source,
+                    index,
+                    mode,
+                }
+            }
+
+            0b010 => Self::In {
+                source: match arg1 {
+                    0 => InSource::Pins,
+                    1 => InSource::X,
+                    2 => InSource::Y,
+                    3 => InSource::Null,
+                    6 => InSource::Isr,
+                    7 => InSource::Osr,
+                    _ => InSource::Reserved,
+                },
+                bit_count: bit_count(instruction),
+            },
+
+            0b011 => Self::Out {
+                destination: match arg1 {
+                    0 => OutDestination::Pins,
+                    1 => OutDestination::X,
+                    2 => OutDestination::Y,
+                    3 => OutDestination::Null,
+                    4 => OutDestination::PinDirs,
+                    5 => OutDestination::Pc,
+                    6 => OutDestination::Isr,
+                    _ => OutDestination::Exec,
+                },
+                bit_count: bit_count(instruction),
+            },
+
+            0b100 => {
+                let is_pull = extract_bits(instruction, 7..=7) != 0;
+                let is_mov = extract_bits(instruction, 4..=4) != 0;
+                let flag = extract_bits(instruction, 6..=6) != 0;
+                let block = extract_bits(instruction, 5..=5) != 0;
+
+                match (is_pull, is_mov) {
+                    (false, false) => Self::Push {
+                        if_full: flag,
+                        block,
+                    },
+                    (true, false) => Self::Pull {
+                        if_empty: flag,
+                        block,
+                    },
+                    (false, true) => Self::MovToRx {
+                        index: rx_index(instruction),
+                    },
+                    (true, true) => Self::MovFromRx {
+                        index: rx_index(instruction),
+                    },
+                }
+            }
+
+            0b101 => Self::Mov {
+                destination: match arg1 {
+                    0 => MovDestination::Pins,
+                    1 => MovDestination::X,
+                    2 => MovDestination::Y,
+                    3 => MovDestination::PinDirs,
+                    4 => MovDestination::Exec,
+                    5 => MovDestination::Pc,
+                    6 => MovDestination::Isr,
+                    _ => MovDestination::Osr,
+                },
+                operation: match extract_bits(instruction, 3..=4) {
+                    0 => MovOperation::None,
+                    1 => MovOperation::Invert,
+                    2 => MovOperation::BitReverse,
+                    _ => MovOperation::Reserved,
+                },
+                source: match extract_bits(instruction, 0..=2) {
+                    0 => MovSource::Pins,
+                    1 => MovSource::X,
+                    2 => MovSource::Y,
+                    3 => MovSource::Null,
+                    5 => MovSource::Status,
+                    6 => MovSource::Isr,
+                    7 => MovSource::Osr,
+                    _ => MovSource::Reserved,
+                },
+            },
+
+            0b110 => Self::Irq {
+                clear: extract_bits(instruction, 6..=6) != 0,
+                wait: extract_bits(instruction, 5..=5) != 0,
+                index: arg2 & 0b111,
+                mode: irq_index_mode(instruction),
+            },
+
+            _ => Self::Set {
+                destination: match arg1 {
+                    0 => SetDestination::Pins,
+                    1 => SetDestination::X,
+                    2 => SetDestination::Y,
+                    4 => SetDestination::PinDirs,
+                    _ => SetDestination::Reserved,
+                },
+                data: arg2,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // jmp x-- 3
+        assert_eq!(
+            Instruction::decode(0x0043),
+            Instruction::Jmp {
+                condition: JmpCondition::XPostDec,
+                address: 3
+            }
+        );
+
+        // out pins, 1 [2]
+        assert_eq!(
+            Instruction::decode(0x6201),
+            Instruction::Out {
+                destination: OutDestination::Pins,
+                bit_count: 1
+            }
+        );
+
+        // pull block
+        assert_eq!(
+            Instruction::decode(0x80a0),
+            Instruction::Pull {
+                if_empty: false,
+                block: true
+            }
+        );
+
+        // mov x, !y
+        assert_eq!(
+            Instruction::decode(0xa02a),
+            Instruction::Mov {
+                destination: MovDestination::X,
+                operation: MovOperation::Invert,
+                source: MovSource::Y
+            }
+        );
+
+        // irq wait 0 rel
+        assert_eq!(
+            Instruction::decode(0xc030),
+            Instruction::Irq {
+                clear: false,
+                wait: true,
+                index: 0,
+                mode: IrqIndexMode::Relative
+            }
+        );
+
+        // set pindirs, 1
+        assert_eq!(
+            Instruction::decode(0xe081),
+            Instruction::Set {
+                destination: SetDestination::PinDirs,
+                data: 1
+            }
+        );
+    }
+}
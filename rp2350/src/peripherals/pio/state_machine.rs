@@ -0,0 +1,743 @@
+/**
+ * @file peripherals/pio/state_machine.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief A single PIO state machine, its shift registers, FIFOs and execution
+ */
+use super::instruction::*;
+use crate::utils::{extract_bit, extract_bits, set_bit_state};
+use std::collections::VecDeque;
+
+pub const FIFO_DEPTH: usize = 4;
+pub const INSTRUCTION_MEMORY_SIZE: usize = 32;
+
+const CLKDIV_RESET: u32 = 0x0001_0000;
+const EXECCTRL_RESET: u32 = 0x0001_f000;
+const SHIFTCTRL_RESET: u32 = 0x000c_0000;
+const PINCTRL_RESET: u32 = 0x1400_0000;
+
+// EXECCTRL bits
+pub const EXECCTRL_EXEC_STALLED: u32 = 1 << 31;
+const EXECCTRL_SIDE_EN: u32 = 1 << 30;
+const EXECCTRL_SIDE_PINDIR: u32 = 1 << 29;
+
+// SHIFTCTRL bits
+const SHIFTCTRL_FJOIN_RX: u32 = 1 << 31;
+const SHIFTCTRL_FJOIN_TX: u32 = 1 << 30;
+const SHIFTCTRL_OUT_SHIFTDIR: u32 = 1 << 19;
+const SHIFTCTRL_IN_SHIFTDIR: u32 = 1 << 18;
+const SHIFTCTRL_AUTOPULL: u32 = 1 << 17;
+const SHIFTCTRL_AUTOPUSH: u32 = 1 << 16;
+const SHIFTCTRL_FJOIN_RX_PUT: u32 = 1 << 15;
+const SHIFTCTRL_FJOIN_RX_GET: u32 = 1 << 14;
+const SHIFTCTRL_FJOIN_MASK: u32 =
+    SHIFTCTRL_FJOIN_RX | SHIFTCTRL_FJOIN_TX | SHIFTCTRL_FJOIN_RX_PUT | SHIFTCTRL_FJOIN_RX_GET;
+
+// FDEBUG fields, shifted by the state machine number
+pub const FDEBUG_RXSTALL: u32 = 0;
+pub const FDEBUG_RXUNDER: u32 = 8;
+pub const FDEBUG_TXOVER: u32 = 16;
+pub const FDEBUG_TXSTALL: u32 = 24;
+
+/// Everything shared between the state machines of a block
+pub(super) struct PioSignals<'a> {
+    pub instruction_memory: &'a [u16; INSTRUCTION_MEMORY_SIZE],
+    /// GPIO inputs, already relative to GPIOBASE
+    pub inputs: u32,
+    pub irq: &'a mut u8,
+    pub pad_out: &'a mut u32,
+    pub pad_oe: &'a mut u32,
+    pub fdebug: &'a mut u32,
+}
+
+enum Flow {
+    Next,
+    Jump(u8),
+    Stall,
+    /// Execute the instruction on the next cycle, the delay of the current one is ignored
+    Exec(u16),
+}
+
+pub struct StateMachine {
+    pub clkdiv: u32,
+    pub execctrl: u32,
+    pub shiftctrl: u32,
+    pub pinctrl: u32,
+
+    pub pc: u8,
+    pub x: u32,
+    pub y: u32,
+    pub isr: u32,
+    pub osr: u32,
+    /// Number of bits shifted into the ISR
+    pub isr_count: u8,
+    /// Number of bits shifted out of the OSR, 32 means it is empty
+    pub osr_count: u8,
+
+    pub tx_fifo: VecDeque<u32>,
+    pub rx_fifo: VecDeque<u32>,
+    /// The RX FIFO as random access registers when FJOIN_RX_PUT or FJOIN_RX_GET is set
+    pub rx_registers: [u32; FIFO_DEPTH],
+
+    /// Instruction written to SM_INSTR or by an OUT/MOV EXEC
+    pub(super) pending_exec: Option<u16>,
+    pub(super) stalled: bool,
+    delay: u8,
+    /// Set once an `irq wait` has raised its flag and waits for it to be cleared
+    irq_waiting: bool,
+    /// Progress of the clock divider in 1/256 of a clk_sys cycle
+    divider_counter: u32,
+}
+
+impl Default for StateMachine {
+    fn default() -> Self {
+        Self {
+            clkdiv: CLKDIV_RESET,
+            execctrl: EXECCTRL_RESET,
+            shiftctrl: SHIFTCTRL_RESET,
+            pinctrl: PINCTRL_RESET,
+            pc: 0,
+            x: 0,
+            y: 0,
+            isr: 0,
+            osr: 0,
+            isr_count: 0,
+            osr_count: 32,
+            tx_fifo: VecDeque::with_capacity(FIFO_DEPTH * 2),
+            rx_fifo: VecDeque::with_capacity(FIFO_DEPTH * 2),
+            rx_registers: [0; FIFO_DEPTH],
+            pending_exec: None,
+            stalled: false,
+            delay: 0,
+            irq_waiting: false,
+            divider_counter: 0,
+        }
+    }
+}
+
+/// Write `count` bits of `value` to the pins starting at `base`, wrapping around at 32
+fn write_pins(target: &mut u32, base: u32, count: u32, value: u32) {
+    for i in 0..count.min(32) {
+        set_bit_state(target, (base + i) % 32, extract_bit(value, i) != 0);
+    }
+}
+
+fn mask(bit_count: u8) -> u32 {
+    match bit_count {
+        32.. => u32::MAX,
+        n => (1 << n) - 1,
+    }
+}
+
+impl StateMachine {
+    fn wrap_top(&self) -> u8 {
+        extract_bits(self.execctrl, 12..=16) as u8
+    }
+
+    fn wrap_bottom(&self) -> u8 {
+        extract_bits(self.execctrl, 7..=11) as u8
+    }
+
+    fn jmp_pin(&self) -> u32 {
+        extract_bits(self.execctrl, 24..=28)
+    }
+
+    fn status_sel(&self) -> u32 {
+        extract_bits(self.execctrl, 5..=6)
+    }
+
+    fn status_n(&self) -> u32 {
+        extract_bits(self.execctrl, 0..=4)
+    }
+
+    fn push_threshold(&self) -> u8 {
+        match extract_bits(self.shiftctrl, 20..=24) as u8 {
+            0 => 32,
+            n => n,
+        }
+    }
+
+    fn pull_threshold(&self) -> u8 {
+        match extract_bits(self.shiftctrl, 25..=29) as u8 {
+            0 => 32,
+            n => n,
+        }
+    }
+
+    fn in_count(&self) -> u8 {
+        match extract_bits(self.shiftctrl, 0..=4) as u8 {
+            0 => 32,
+            n => n,
+        }
+    }
+
+    fn out_base(&self) -> u32 {
+        extract_bits(self.pinctrl, 0..=4)
+    }
+
+    fn set_base(&self) -> u32 {
+        extract_bits(self.pinctrl, 5..=9)
+    }
+
+    fn sideset_base(&self) -> u32 {
+        extract_bits(self.pinctrl, 10..=14)
+    }
+
+    fn in_base(&self) -> u32 {
+        extract_bits(self.pinctrl, 15..=19)
+    }
+
+    fn out_count(&self) -> u32 {
+        extract_bits(self.pinctrl, 20..=25)
+    }
+
+    fn set_count(&self) -> u32 {
+        extract_bits(self.pinctrl, 26..=28)
+    }
+
+    fn sideset_count(&self) -> u32 {
+        extract_bits(self.pinctrl, 29..=31)
+    }
+
+    fn autopush(&self) -> bool {
+        self.shiftctrl & SHIFTCTRL_AUTOPUSH != 0
+    }
+
+    fn autopull(&self) -> bool {
+        self.shiftctrl & SHIFTCTRL_AUTOPULL != 0
+    }
+
+    fn rx_as_registers(&self) -> bool {
+        self.shiftctrl & (SHIFTCTRL_FJOIN_RX_PUT | SHIFTCTRL_FJOIN_RX_GET) != 0
+    }
+
+    pub fn tx_capacity(&self) -> usize {
+        match (
+            self.shiftctrl & SHIFTCTRL_FJOIN_TX != 0,
+            self.shiftctrl & SHIFTCTRL_FJOIN_RX != 0,
+        ) {
+            (true, false) => FIFO_DEPTH * 2,
+            (false, true) => 0,
+            _ => FIFO_DEPTH,
+        }
+    }
+
+    pub fn rx_capacity(&self) -> usize {
+        if self.rx_as_registers() {
+            return 0;
+        }
+
+        match (
+            self.shiftctrl & SHIFTCTRL_FJOIN_TX != 0,
+            self.shiftctrl & SHIFTCTRL_FJOIN_RX != 0,
+        ) {
+            (false, true) => FIFO_DEPTH * 2,
+            (true, false) => 0,
+            _ => FIFO_DEPTH,
+        }
+    }
+
+    pub fn is_tx_full(&self) -> bool {
+        self.tx_fifo.len() >= self.tx_capacity()
+    }
+
+    pub fn is_rx_full(&self) -> bool {
+        self.rx_fifo.len() >= self.rx_capacity()
+    }
+
+    pub fn set_shiftctrl(&mut self, value: u32) {
+        // Changing the FIFO joins flushes both FIFOs
+        if (self.shiftctrl ^ value) & SHIFTCTRL_FJOIN_MASK != 0 {
+            self.tx_fifo.clear();
+            self.rx_fifo.clear();
+        }
+
+        self.shiftctrl = value;
+    }
+
+    /// Current instruction, as reported by SM_INSTR
+    pub fn current_instruction(&self, instruction_memory: &[u16; INSTRUCTION_MEMORY_SIZE]) -> u16 {
+        self.pending_exec
+            .unwrap_or(instruction_memory[self.pc as usize % INSTRUCTION_MEMORY_SIZE])
+    }
+
+    /// SM_RESTART, the program counter, X, Y and the configuration are kept
+    pub fn restart(&mut self) {
+        self.isr = 0;
+        self.isr_count = 0;
+        self.osr_count = 32;
+        self.delay = 0;
+        self.stalled = false;
+        self.irq_waiting = false;
+        self.pending_exec = None;
+    }
+
+    pub fn restart_clock_divider(&mut self) {
+        self.divider_counter = 0;
+    }
+
+    /// Advance the clock divider by one clk_sys cycle, returns whether the state machine runs on it
+    pub(super) fn clock_enable(&mut self) -> bool {
+        let int = match extract_bits(self.clkdiv, 16..=31) {
+            0 => 0x1_0000,
+            n => n,
+        };
+
+        let divider = int * 256 + extract_bits(self.clkdiv, 8..=15);
+        self.divider_counter += 256;
+
+        if self.divider_counter < divider {
+            return false;
+        }
+
+        self.divider_counter -= divider;
+        true
+    }
+
+    fn next_pc(&self) -> u8 {
+        match self.pc == self.wrap_top() {
+            true => self.wrap_bottom(),
+            false => (self.pc + 1) % INSTRUCTION_MEMORY_SIZE as u8,
+        }
+    }
+
+    /// Run the state machine for one of its cycles
+    pub(super) fn step(&mut self, index: usize, signals: &mut PioSignals) {
+        if self.delay > 0 && self.pending_exec.is_none() {
+            self.delay -= 1;
+            return;
+        }
+
+        let (instruction, is_exec) = match self.pending_exec {
+            Some(instruction) => (instruction, true),
+            None => (signals.instruction_memory[self.pc as usize], false),
+        };
+
+        let delay_side_set = extract_bits(instruction, 8..=12) as u32;
+        let flow = self.execute(Instruction::decode(instruction), index, signals);
+        let delay = self.apply_side_set(delay_side_set, signals);
+
+        self.stalled = matches!(flow, Flow::Stall);
+
+        match flow {
+            Flow::Stall => return,
+            Flow::Next if !is_exec => self.pc = self.next_pc(),
+            Flow::Next => {}
+            Flow::Jump(address) => self.pc = address % INSTRUCTION_MEMORY_SIZE as u8,
+            Flow::Exec(next) => {
+                if !is_exec {
+                    self.pc = self.next_pc();
+                }
+
+                self.pending_exec = Some(next);
+                return;
+            }
+        }
+
+        self.pending_exec = None;
+        self.delay = delay;
+    }
+
+    /// Drive the side-set pins, returns the delay encoded in the instruction
+    fn apply_side_set(&mut self, field: u32, signals: &mut PioSignals) -> u8 {
+        let count = self.sideset_count().min(5);
+        let delay = field & ((1 << (5 - count)) - 1);
+
+        if count == 0 {
+            return delay as u8;
+        }
+
+        let mut value_bits = count;
+        let mut value = field >> (5 - count);
+
+        if self.execctrl & EXECCTRL_SIDE_EN != 0 {
+            value_bits -= 1;
+
+            // The MSB of the side-set field tells whether this instruction side-sets or not
+            if extract_bit(value, count - 1) == 0 {
+                return delay as u8;
+            }
+
+            value &= (1 << value_bits) - 1;
+        }
+
+        let target = match self.execctrl & EXECCTRL_SIDE_PINDIR != 0 {
+            true => &mut *signals.pad_oe,
+            false => &mut *signals.pad_out,
+        };
+
+        write_pins(target, self.sideset_base(), value_bits, value);
+        delay as u8
+    }
+
+    fn irq_index(&self, index: u8, mode: IrqIndexMode, sm: usize) -> Option<u8> {
+        match mode {
+            IrqIndexMode::Direct => Some(index),
+            IrqIndexMode::Relative => Some((index & 0b100) | ((index + sm as u8) & 0b11)),
+            IrqIndexMode::Prev | IrqIndexMode::Next => {
+                log::warn!("PIO: IRQ flags of the neighbouring PIO blocks are not supported");
+                None
+            }
+        }
+    }
+
+    fn status(&self, irq: u8) -> u32 {
+        let status = match self.status_sel() {
+            0 => (self.tx_fifo.len() as u32) < self.status_n(),
+            1 => (self.rx_fifo.len() as u32) < self.status_n(),
+            _ => extract_bit(irq as u32, self.status_n() & 0b111) != 0,
+        };
+
+        match status {
+            true => u32::MAX,
+            false => 0,
+        }
+    }
+
+    fn pull(&mut self) -> bool {
+        match self.tx_fifo.pop_front() {
+            Some(value) => {
+                self.osr = value;
+                self.osr_count = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn push(&mut self) -> bool {
+        if self.is_rx_full() {
+            return false;
+        }
+
+        self.rx_fifo.push_back(self.isr);
+        self.isr = 0;
+        self.isr_count = 0;
+        true
+    }
+
+    fn shift_in(&mut self, data: u32, bit_count: u8) {
+        let data = data & mask(bit_count);
+
+        self.isr = match (bit_count, self.shiftctrl & SHIFTCTRL_IN_SHIFTDIR != 0) {
+            (32, _) => data,
+            (n, true) => (self.isr >> n) | (data << (32 - n)),
+            (n, false) => (self.isr << n) | data,
+        };
+
+        self.isr_count = (self.isr_count + bit_count).min(32);
+    }
+
+    fn shift_out(&mut self, bit_count: u8) -> u32 {
+        let (data, osr) = match (bit_count, self.shiftctrl & SHIFTCTRL_OUT_SHIFTDIR != 0) {
+            (32, _) => (self.osr, 0),
+            (n, true) => (self.osr & mask(n), self.osr >> n),
+            (n, false) => (self.osr >> (32 - n), self.osr << n),
+        };
+
+        self.osr = osr;
+        self.osr_count = (self.osr_count + bit_count).min(32);
+        data
+    }
+
+    fn execute(&mut self, instruction: Instruction, sm: usize, signals: &mut PioSignals) -> Flow {
+        match instruction {
+            Instruction::Jmp { condition, address } => {
+                let jump = match condition {
+                    JmpCondition::Always => true,
+                    JmpCondition::XZero => self.x == 0,
+                    JmpCondition::XPostDec => {
+                        let jump = self.x != 0;
+                        self.x = self.x.wrapping_sub(1);
+                        jump
+                    }
+                    JmpCondition::YZero => self.y == 0,
+                    JmpCondition::YPostDec => {
+                        let jump = self.y != 0;
+                        self.y = self.y.wrapping_sub(1);
+                        jump
+                    }
+                    JmpCondition::XNotEqualY => self.x != self.y,
+                    JmpCondition::Pin => extract_bit(signals.inputs, self.jmp_pin()) != 0,
+                    JmpCondition::OsrNotEmpty => self.osr_count < self.pull_threshold(),
+                };
+
+                match jump {
+                    true => Flow::Jump(address),
+                    false => Flow::Next,
+                }
+            }
+
+            Instruction::Wait {
+                polarity,
+                source,
+                index,
+                mode,
+            } => {
+                let index = index as u32;
+                let value = match source {
+                    WaitSource::Gpio => extract_bit(signals.inputs, index) != 0,
+                    WaitSource::Pin => {
+                        extract_bit(signals.inputs, (self.in_base() + index) % 32) != 0
+                    }
+                    WaitSource::JmpPin => {
+                        extract_bit(signals.inputs, (self.jmp_pin() + (index & 0b11)) % 32) != 0
+                    }
+                    WaitSource::Irq => {
+                        let Some(flag) = self.irq_index(index as u8, mode, sm) else {
+                            return Flow::Next;
+                        };
+
+                        let value = extract_bit(*signals.irq, flag) != 0;
+
+                        // Waiting for a set flag also clears it
+                        if value && polarity {
+                            *signals.irq &= !(1 << flag);
+                        }
+
+                        value
+                    }
+                };
+
+                match value == polarity {
+                    true => Flow::Next,
+                    false => Flow::Stall,
+                }
+            }
+
+            Instruction::In { source, bit_count } => {
+                let data = match source {
+                    InSource::Pins => signals.inputs.rotate_right(self.in_base()),
+                    InSource::X => self.x,
+                    InSource::Y => self.y,
+                    InSource::Isr => self.isr,
+                    InSource::Osr => self.osr,
+                    InSource::Null | InSource::Reserved => 0,
+                };
+
+                let threshold = self.push_threshold();
+                let reach_threshold = self.isr_count + bit_count >= threshold;
+
+                if self.autopush() && reach_threshold && self.is_rx_full() {
+                    *signals.fdebug |= 1 << (FDEBUG_RXSTALL + sm as u32);
+                    return Flow::Stall;
+                }
+
+                self.shift_in(data, bit_count);
+
+                if self.autopush() && reach_threshold {
+                    self.push();
+                }
+
+                Flow::Next
+            }
+
+            Instruction::Out {
+                destination,
+                bit_count,
+            } => {
+                if self.autopull() && self.osr_count >= self.pull_threshold() && !self.pull() {
+                    *signals.fdebug |= 1 << (FDEBUG_TXSTALL + sm as u32);
+                    return Flow::Stall;
+                }
+
+                let data = self.shift_out(bit_count);
+
+                let flow = match destination {
+                    OutDestination::Pins => {
+                        write_pins(signals.pad_out, self.out_base(), self.out_count(), data);
+                        Flow::Next
+                    }
+                    OutDestination::PinDirs => {
+                        write_pins(signals.pad_oe, self.out_base(), self.out_count(), data);
+                        Flow::Next
+                    }
+                    OutDestination::X => {
+                        self.x = data;
+                        Flow::Next
+                    }
+                    OutDestination::Y => {
+                        self.y = data;
+                        Flow::Next
+                    }
+                    OutDestination::Null => Flow::Next,
+                    OutDestination::Pc => Flow::Jump(data as u8),
+                    OutDestination::Isr => {
+                        self.isr = data;
+                        self.isr_count = bit_count;
+                        Flow::Next
+                    }
+                    OutDestination::Exec => Flow::Exec(data as u16),
+                };
+
+                if self.autopull() && self.osr_count >= self.pull_threshold() {
+                    self.pull();
+                }
+
+                flow
+            }
+
+            Instruction::Push { if_full, block } => {
+                if if_full && self.isr_count < self.push_threshold() {
+                    return Flow::Next;
+                }
+
+                if self.push() {
+                    return Flow::Next;
+                }
+
+                if block {
+                    *signals.fdebug |= 1 << (FDEBUG_RXSTALL + sm as u32);
+                    return Flow::Stall;
+                }
+
+                // A non-blocking push to a full FIFO drops the data
+                self.isr = 0;
+                self.isr_count = 0;
+                Flow::Next
+            }
+
+            Instruction::Pull { if_empty, block } => {
+                let is_full = self.osr_count < self.pull_threshold();
+
+                // With autopull, a pull of a full OSR does nothing
+                if (if_empty || self.autopull()) && is_full {
+                    return Flow::Next;
+                }
+
+                if self.pull() {
+                    return Flow::Next;
+                }
+
+                if block {
+                    *signals.fdebug |= 1 << (FDEBUG_TXSTALL + sm as u32);
+                    return Flow::Stall;
+                }
+
+                // A non-blocking pull from an empty FIFO copies X to the OSR
+                self.osr = self.x;
+                self.osr_count = 0;
+                Flow::Next
+            }
+
+            Instruction::MovToRx { index } => {
+                if self.shiftctrl & SHIFTCTRL_FJOIN_RX_PUT != 0 {
+                    let index = index.map_or(self.y & 0b11, |v| v as u32) as usize;
+                    self.rx_registers[index] = self.isr;
+                }
+
+                Flow::Next
+            }
+
+            Instruction::MovFromRx { index } => {
+                if self.shiftctrl & SHIFTCTRL_FJOIN_RX_GET != 0 {
+                    let index = index.map_or(self.y & 0b11, |v| v as u32) as usize;
+                    self.osr = self.rx_registers[index];
+                    self.osr_count = 0;
+                }
+
+                Flow::Next
+            }
+
+            Instruction::Mov {
+                destination,
+                operation,
+                source,
+            } => {
+                let data = match source {
+                    MovSource::Pins => {
+                        signals.inputs.rotate_right(self.in_base()) & mask(self.in_count())
+                    }
+                    MovSource::X => self.x,
+                    MovSource::Y => self.y,
+                    MovSource::Status => self.status(*signals.irq),
+                    MovSource::Isr => self.isr,
+                    MovSource::Osr => self.osr,
+                    MovSource::Null | MovSource::Reserved => 0,
+                };
+
+                let data = match operation {
+                    MovOperation::None | MovOperation::Reserved => data,
+                    MovOperation::Invert => !data,
+                    MovOperation::BitReverse => data.reverse_bits(),
+                };
+
+                match destination {
+                    MovDestination::Pins => {
+                        write_pins(signals.pad_out, self.out_base(), self.out_count(), data)
+                    }
+                    MovDestination::PinDirs => {
+                        write_pins(signals.pad_oe, self.out_base(), self.out_count(), data)
+                    }
+                    MovDestination::X => self.x = data,
+                    MovDestination::Y => self.y = data,
+                    MovDestination::Exec => return Flow::Exec(data as u16),
+                    MovDestination::Pc => return Flow::Jump(data as u8),
+                    MovDestination::Isr => {
+                        self.isr = data;
+                        self.isr_count = 0;
+                    }
+                    MovDestination::Osr => {
+                        self.osr = data;
+                        self.osr_count = 0;
+                    }
+                }
+
+                Flow::Next
+            }
+
+            Instruction::Irq {
+                clear,
+                wait,
+                index,
+                mode,
+            } => {
+                let Some(flag) = self.irq_index(index, mode, sm) else {
+                    return Flow::Next;
+                };
+
+                if self.irq_waiting {
+                    return match extract_bit(*signals.irq, flag) != 0 {
+                        true => Flow::Stall,
+                        false => {
+                            self.irq_waiting = false;
+                            Flow::Next
+                        }
+                    };
+                }
+
+                if clear {
+                    *signals.irq &= !(1 << flag);
+                    return Flow::Next;
+                }
+
+                *signals.irq |= 1 << flag;
+
+                if wait {
+                    self.irq_waiting = true;
+                    return Flow::Stall;
+                }
+
+                Flow::Next
+            }
+
+            Instruction::Set { destination, data } => {
+                let data = data as u32;
+
+                match destination {
+                    SetDestination::Pins => {
+                        write_pins(signals.pad_out, self.set_base(), self.set_count(), data)
+                    }
+                    SetDestination::PinDirs => {
+                        write_pins(signals.pad_oe, self.set_base(), self.set_count(), data)
+                    }
+                    SetDestination::X => self.x = data,
+                    SetDestination::Y => self.y = data,
+                    SetDestination::Reserved => {}
+                }
+
+                Flow::Next
+            }
+        }
+    }
+}
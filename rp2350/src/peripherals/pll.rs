@@ -4,8 +4,7 @@ use crate::utils::extract_bit;
  * @file peripherals/pll.rs
  * @author Nguyen Le Duy
  * @date 06/05/2025
- * @brief PLL peripheral implementation
- * @todo actually implement the PLL peripheral, this is just a hotfix to get the simulator running
+ * @brief PLL peripheral implementation, it locks right away
  * @todo this actually generates interrupts, but we don't have a way to handle them yet
  */
 use super::*;
@@ -19,6 +18,12 @@ pub const INTE: u16 = 0x14;
 pub const INTF: u16 = 0x18;
 pub const INTS: u16 = 0x1c;
 
+pub const CS_BYPASS: u32 = 1 << 8;
+pub const PWR_PD: u32 = 1 << 0;
+pub const PWR_DSMPD: u32 = 1 << 2;
+pub const PWR_POSTDIVPD: u32 = 1 << 3;
+pub const PWR_VCOPD: u32 = 1 << 5;
+
 #[derive(Debug)]
 // IDX 0 for PLL_SYS, 1 for PLL_USB
 pub struct Pll<const IDX: usize> {
@@ -34,12 +39,16 @@ pub struct Pll<const IDX: usize> {
 }
 
 impl<const IDX: usize> Default for Pll<IDX> {
+    /// Locked as the SDK leaves it, PLL_SYS at 150MHz and PLL_USB at 48MHz from the 12MHz
+    /// crystal
     fn default() -> Self {
+        let (fbdiv_int, postdiv1, postdiv2) = if IDX == 0 { (125, 5, 2) } else { (120, 6, 5) };
+
         Self {
             cs: 1 | 1 << 31,
-            pwr: 0b101101,
-            fbdiv_int: 0,
-            prim: (0x7 << 12) | (0x7 << 16),
+            pwr: PWR_DSMPD,
+            fbdiv_int,
+            prim: (postdiv1 << 16) | (postdiv2 << 12),
 
             interrupt_raw: false,
             interrupt_enabled: false,
@@ -49,6 +58,23 @@ impl<const IDX: usize> Default for Pll<IDX> {
 }
 
 impl<const IDX: usize> Pll<IDX> {
+    /// Output in Hz from the `reference` of the crystal, none while it is powered down
+    pub fn frequency(&self, reference: u64) -> u64 {
+        if self.cs & CS_BYPASS != 0 {
+            return reference;
+        }
+
+        if self.pwr & (PWR_PD | PWR_POSTDIVPD | PWR_VCOPD) != 0 {
+            return 0;
+        }
+
+        let refdiv = (self.cs & 0x3f).max(1) as u64;
+        let postdiv1 = ((self.prim >> 16) & 0x7).max(1) as u64;
+        let postdiv2 = ((self.prim >> 12) & 0x7).max(1) as u64;
+
+        reference / refdiv * self.fbdiv_int as u64 / (postdiv1 * postdiv2)
+    }
+
     fn interrupt_status(&self) -> bool {
         (self.interrupt_raw && self.interrupt_enabled) || self.interrupt_force
     }
@@ -81,6 +107,24 @@ impl<const IDX: usize> Peripheral for Pll<IDX> {
         Ok(value)
     }
 
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
     fn write_raw(
         &mut self,
         address: u16,
@@ -97,7 +141,7 @@ impl<const IDX: usize> Peripheral for Pll<IDX> {
                 self.cs = value | (1 << 31); // lock
             }
             PWR => self.pwr = value & 0b101101,
-            FBDIV_INT => self.pwr = value & 0xFFF,
+            FBDIV_INT => self.fbdiv_int = value & 0xFFF,
             PRIM => self.prim = value & ((0b111 << 16) | (0b111 << 12)),
             INTR => {
                 if extract_bit(value, 0) == 1 {
@@ -121,3 +165,23 @@ impl<const IDX: usize> Peripheral for Pll<IDX> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frequency() {
+        let ctx = PeripheralAccessContext::default();
+        let mut pll = Pll::<0>::default();
+        assert_eq!(pll.frequency(12 * MHZ), 150 * MHZ);
+        assert_eq!(Pll::<1>::default().frequency(12 * MHZ), 48 * MHZ);
+
+        // 12MHz * 100 / (5 * 2)
+        pll.write(FBDIV_INT, 100, &ctx).unwrap();
+        assert_eq!(pll.frequency(12 * MHZ), 120 * MHZ);
+
+        pll.write(0x2000 | PWR, PWR_PD, &ctx).unwrap();
+        assert_eq!(pll.frequency(12 * MHZ), 0);
+    }
+}
@@ -0,0 +1,417 @@
+/**
+ * @file peripherals/plugin.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Memory mapped devices provided by third party modules, e.g. WebAssembly plugins
+ */
+use super::*;
+use crate::device;
+use crate::interrupts::Interrupt;
+use crate::Rp2350;
+use crate::VirtualDevice;
+
+/// Version of the interface between the simulator and the modules, a module reports
+/// the one it was written for
+pub const ABI_VERSION: u32 = 1;
+
+/// A plugin gets the same register window as a builtin peripheral, the atomic aliases
+/// above it never reach the module
+pub const MAX_SIZE: u32 = 0x1000;
+
+/// Cycles between two ticks of a plugin, calling into a runtime on every cycle is too slow
+pub const TICK_INTERVAL: u32 = 64;
+
+/// Interrupts a plugin may raise, the spare IRQs are not wired to anything on the chip
+pub const IRQS: core::ops::RangeInclusive<Interrupt> =
+    Interrupts::_SPAREIRQ_IRQ_0..=Interrupts::_SPAREIRQ_IRQ_5;
+
+/// Functions a WebAssembly module exports, the ones marked optional may be missing
+pub mod export {
+    /// `() -> i32`, the version of the interface
+    pub const ABI_VERSION: &str = "pico2_abi_version";
+    /// `() -> i32`, address of the registers, aligned to 16KB
+    pub const BASE: &str = "pico2_base";
+    /// `() -> i32`, size of the registers in bytes
+    pub const SIZE: &str = "pico2_size";
+    /// `() -> i32`, the spare IRQ wired to the device or -1, optional
+    pub const IRQ: &str = "pico2_irq";
+    /// `(offset: i32) -> i32`
+    pub const READ: &str = "pico2_read";
+    /// `(offset: i32, value: i32)`
+    pub const WRITE: &str = "pico2_write";
+    /// `(cycles: i32)`, cycles elapsed since the last tick, optional
+    pub const TICK: &str = "pico2_tick";
+    /// `()`, the chip is reset, optional
+    pub const RESET: &str = "pico2_reset";
+    /// The memory holding the messages of `log`
+    pub const MEMORY: &str = "memory";
+}
+
+/// Functions a WebAssembly module may import from the `pico2` module
+pub mod import {
+    pub const MODULE: &str = "pico2";
+    /// `(level: i32)`, drive the IRQ line of the device
+    pub const SET_IRQ: &str = "set_irq";
+    /// `(pin: i32) -> i32`, level of a GPIO as driven by the chip
+    pub const GPIO_GET: &str = "gpio_get";
+    /// `(pin: i32, level: i32)`, drive an input of the chip
+    pub const GPIO_SET: &str = "gpio_set";
+    /// `() -> i64`, cycles since the chip was powered
+    pub const TICKS: &str = "ticks";
+    /// `(ptr: i32, len: i32)`, an UTF-8 message in the memory of the module
+    pub const LOG: &str = "log";
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PluginError {
+    #[error("written for the version {0} of the interface, {ABI_VERSION} is supported")]
+    UnsupportedVersion(u32),
+    #[error("{size:#x} bytes at {base:#010x} do not fit in a free peripheral slot")]
+    InvalidRegion { base: u32, size: u32 },
+    #[error("IRQ {0} is not one of the spare interrupts")]
+    InvalidIrq(u32),
+    #[error("missing the export `{0}`")]
+    MissingExport(String),
+    #[error("runtime error: {0}")]
+    Runtime(String),
+}
+
+/// What a module tells about itself when it is loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: u32,
+    pub base: u32,
+    pub size: u32,
+    pub irq: Option<Interrupt>,
+}
+
+impl PluginInfo {
+    fn validate(&self) -> Result<(), PluginError> {
+        if self.version != ABI_VERSION {
+            return Err(PluginError::UnsupportedVersion(self.version));
+        }
+
+        let in_peripherals = (0x4000_0000..0x6000_0000).contains(&self.base);
+
+        if !in_peripherals || self.base & 0x3FFF != 0 || self.size == 0 || self.size > MAX_SIZE {
+            return Err(PluginError::InvalidRegion {
+                base: self.base,
+                size: self.size,
+            });
+        }
+
+        match self.irq {
+            Some(irq) if !IRQS.contains(&irq) => Err(PluginError::InvalidIrq(irq as u32)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The chip as seen by a module during one call, its requests are applied once the
+/// call returned
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PluginIo {
+    /// Levels of the GPIOs driven by the chip
+    pub pins: u32,
+    pub ticks: u64,
+    /// New level of the IRQ line
+    pub irq: Option<bool>,
+    /// Inputs of the chip to drive
+    pub outputs: Vec<(u8, bool)>,
+}
+
+impl PluginIo {
+    pub fn gpio_get(&self, pin: u8) -> bool {
+        pin < 32 && (self.pins >> pin) & 1 == 1
+    }
+
+    pub fn gpio_set(&mut self, pin: u8, level: bool) {
+        self.outputs.retain(|(output, _)| *output != pin);
+        self.outputs.push((pin, level));
+    }
+
+    pub fn set_irq(&mut self, level: bool) {
+        self.irq = Some(level);
+    }
+}
+
+/// An instance of a module, implemented by the runtime hosting it, so far only the one
+/// of the browser in the web app. The offsets are relative to the base of the registers
+/// and already checked against their size.
+pub trait PluginModule {
+    fn read(&mut self, offset: u16, io: &mut PluginIo) -> Result<u32, PluginError>;
+    fn write(&mut self, offset: u16, value: u32, io: &mut PluginIo) -> Result<(), PluginError>;
+
+    fn tick(&mut self, _cycles: u32, _io: &mut PluginIo) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+}
+
+/// A loaded module mapped into the peripherals
+pub struct Plugin {
+    pub info: PluginInfo,
+    /// The first failure of the module, it is not called anymore afterward
+    pub error: Option<PluginError>,
+    module: Box<dyn PluginModule>,
+    /// GPIOs driven during a register access, they need the whole chip
+    pending: Vec<(u8, bool)>,
+    elapsed: u32,
+}
+
+impl Plugin {
+    pub fn new(info: PluginInfo, module: Box<dyn PluginModule>) -> Result<Self, PluginError> {
+        info.validate()?;
+
+        Ok(Self {
+            info,
+            error: None,
+            module,
+            pending: Vec::new(),
+            elapsed: 0,
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.elapsed = 0;
+
+        if self.error.is_none() {
+            let result = self.module.reset();
+            self.check(result);
+        }
+    }
+
+    fn check<T>(&mut self, result: Result<T, PluginError>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(why) => {
+                log::error!("Plugin {} stopped: {}", self.info.name, why);
+                self.error = Some(why);
+                None
+            }
+        }
+    }
+
+    fn call<T>(
+        &mut self,
+        gpio: &RefCell<GpioController>,
+        clock: &Clock,
+        interrupts: &RefCell<Interrupts>,
+        f: impl FnOnce(&mut dyn PluginModule, &mut PluginIo) -> Result<T, PluginError>,
+    ) -> Option<T> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        let pins = {
+            let gpio = gpio.borrow();
+            (0..30).fold(0, |pins, pin| {
+                pins | ((gpio.pin_state(pin).is_high() as u32) << pin)
+            })
+        };
+
+        let mut io = PluginIo {
+            pins,
+            ticks: *clock.ticks.borrow(),
+            ..Default::default()
+        };
+
+        let result = f(self.module.as_mut(), &mut io);
+        let value = self.check(result)?;
+
+        if let (Some(irq), Some(level)) = (self.info.irq, io.irq) {
+            interrupts.borrow_mut().set_irq(irq, level);
+        }
+
+        self.pending.extend(io.outputs);
+        Some(value)
+    }
+
+    fn offset(&self, address: u16) -> PeripheralResult<u16> {
+        let offset = address & 0x0FFF;
+
+        match (offset as u32) < self.info.size {
+            true => Ok(offset),
+            false => Err(PeripheralError::OutOfBounds),
+        }
+    }
+}
+
+impl Peripheral for Rc<RefCell<Plugin>> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let mut plugin = self.borrow_mut();
+        let offset = plugin.offset(address)?;
+
+        plugin
+            .call(&ctx.gpio, &ctx.clock, &ctx.interrupts, |module, io| {
+                module.read(offset, io)
+            })
+            .ok_or(PeripheralError::OutOfBounds)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut plugin = self.borrow_mut();
+        let offset = plugin.offset(address)?;
+
+        plugin
+            .call(&ctx.gpio, &ctx.clock, &ctx.interrupts, |module, io| {
+                module.write(offset, value, io)
+            })
+            .ok_or(PeripheralError::OutOfBounds)
+    }
+}
+
+/// Ticks a plugin and drives the GPIOs it asked for
+pub struct PluginDevice(pub Rc<RefCell<Plugin>>);
+
+impl VirtualDevice for PluginDevice {
+    fn tick(&mut self, mcu: &mut Rp2350) {
+        let mut plugin = self.0.borrow_mut();
+        plugin.elapsed += 1;
+
+        if plugin.elapsed >= TICK_INTERVAL {
+            let cycles = core::mem::take(&mut plugin.elapsed);
+
+            plugin.call(&mcu.gpio, &mcu.clock, &mcu.interrupts, |module, io| {
+                module.tick(cycles, io)
+            });
+        }
+
+        for (pin, level) in plugin.pending.drain(..) {
+            device::drive_pin(mcu, pin, level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: u32 = 0x4011_0000;
+
+    /// Two registers, writing the second one raises the IRQ and drives GPIO2 on the
+    /// next tick
+    #[derive(Default)]
+    struct Registers {
+        values: [u32; 2],
+        ticks: u32,
+    }
+
+    impl PluginModule for Rc<RefCell<Registers>> {
+        fn read(&mut self, offset: u16, _io: &mut PluginIo) -> Result<u32, PluginError> {
+            match offset {
+                0 | 4 => Ok(self.borrow().values[offset as usize / 4]),
+                _ => Err(PluginError::Runtime("unreachable".into())),
+            }
+        }
+
+        fn write(&mut self, offset: u16, value: u32, io: &mut PluginIo) -> Result<(), PluginError> {
+            self.borrow_mut().values[offset as usize / 4] = value;
+
+            if offset == 4 {
+                io.set_irq(value != 0);
+            }
+
+            Ok(())
+        }
+
+        fn tick(&mut self, cycles: u32, io: &mut PluginIo) -> Result<(), PluginError> {
+            let mut registers = self.borrow_mut();
+            registers.ticks += cycles;
+            io.gpio_set(2, registers.values[1] != 0);
+            Ok(())
+        }
+    }
+
+    fn info(base: u32) -> PluginInfo {
+        PluginInfo {
+            name: String::from("registers"),
+            version: ABI_VERSION,
+            base,
+            size: 8,
+            irq: Some(Interrupts::_SPAREIRQ_IRQ_0),
+        }
+    }
+
+    #[test]
+    fn test_plugin_region() {
+        let mut mcu = Rp2350::builder().build().unwrap();
+        let registers = Rc::new(RefCell::new(Registers::default()));
+
+        let mut load = |info| mcu.load_plugin(info, Box::new(Rc::clone(&registers)));
+
+        // UART0
+        assert!(matches!(
+            load(info(0x4007_0000)),
+            Err(PluginError::InvalidRegion { .. })
+        ));
+        assert!(matches!(
+            load(info(BASE + 0x100)),
+            Err(PluginError::InvalidRegion { .. })
+        ));
+        assert!(matches!(
+            load(PluginInfo {
+                irq: Some(1),
+                ..info(BASE)
+            }),
+            Err(PluginError::InvalidIrq(1))
+        ));
+        assert!(matches!(
+            load(PluginInfo {
+                version: 2,
+                ..info(BASE)
+            }),
+            Err(PluginError::UnsupportedVersion(2))
+        ));
+
+        assert!(load(info(BASE)).is_ok());
+        assert!(matches!(
+            load(info(BASE)),
+            Err(PluginError::InvalidRegion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_plugin_access() {
+        let mut mcu = Rp2350::builder().build().unwrap();
+        let registers = Rc::new(RefCell::new(Registers::default()));
+        mcu.load_plugin(info(BASE), Box::new(Rc::clone(&registers)))
+            .unwrap();
+
+        let ctx = mcu
+            .bus
+            .peripherals
+            .get_context(BASE, Requestor::Proc0, true);
+
+        let plugin = mcu
+            .bus
+            .peripherals
+            .find_mut(BASE, Requestor::Proc0)
+            .unwrap();
+
+        plugin.write(0, 0x1234, &ctx).unwrap();
+        assert_eq!(plugin.read(0, &ctx), Ok(0x1234));
+        assert_eq!(plugin.read(8, &ctx), Err(PeripheralError::OutOfBounds));
+
+        plugin.write(4, 1, &ctx).unwrap();
+        let irq = 1 << Interrupts::_SPAREIRQ_IRQ_0;
+        assert_eq!(mcu.interrupts.borrow().pending(0) & irq, irq);
+
+        for _ in 0..TICK_INTERVAL {
+            mcu.tick();
+        }
+
+        assert_eq!(registers.borrow().ticks, TICK_INTERVAL);
+        assert!(mcu.gpio.borrow().get_pin(2).unwrap().raw_input_value);
+    }
+}
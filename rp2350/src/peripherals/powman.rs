@@ -1,21 +1,460 @@
+/**
+ * @file peripherals/powman.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Power manager, with the always-on timer and the power states of the domains
+ */
 use super::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub const BADPASSWD: u16 = 0x00; // Indicates a bad password has been used
+pub const VREG_CTRL: u16 = 0x04; // Voltage Regulator Control
+pub const VREG_STS: u16 = 0x08; // Voltage Regulator Status
+pub const VREG: u16 = 0x0c; // Voltage Regulator Settings
+pub const VREG_LP_ENTRY: u16 = 0x10; // Voltage Regulator Low Power Entry Settings
+pub const VREG_LP_EXIT: u16 = 0x14; // Voltage Regulator Low Power Exit Settings
+pub const BOD_CTRL: u16 = 0x18; // Brown-out Detection Control
+pub const BOD: u16 = 0x1c; // Brown-out Detection Settings
+pub const BOD_LP_ENTRY: u16 = 0x20; // Brown-out Detection Low Power Entry Settings
+pub const BOD_LP_EXIT: u16 = 0x24; // Brown-out Detection Low Power Exit Settings
+pub const LPOSC: u16 = 0x28; // Low power oscillator control register
+pub const CHIP_RESET: u16 = 0x2c; // Chip reset control and status
+pub const WDSEL: u16 = 0x30; // Allows a watchdog reset to reset the internal state of powman
+pub const SEQ_CFG: u16 = 0x34; // For configuration of the power sequencer
+pub const STATE: u16 = 0x38; // Power state of the domains
+pub const POW_FASTDIV: u16 = 0x3c; // Divider of the powman clock for the fast sequencer
+pub const POW_DELAY: u16 = 0x40; // Power state machine delays
+pub const EXT_CTRL0: u16 = 0x44; // Configures a GPIO as a power mode aware control output
+pub const EXT_CTRL1: u16 = 0x48; // Configures a GPIO as a power mode aware control output
+pub const EXT_TIME_REF: u16 = 0x4c; // Select a GPIO to use as a time reference
+pub const LPOSC_FREQ_KHZ_INT: u16 = 0x50; // Integer part of the LPOSC frequency in kHz
+pub const LPOSC_FREQ_KHZ_FRAC: u16 = 0x54; // Fractional part of the LPOSC frequency in kHz
+pub const XOSC_FREQ_KHZ_INT: u16 = 0x58; // Integer part of the XOSC frequency in kHz
+pub const XOSC_FREQ_KHZ_FRAC: u16 = 0x5c; // Fractional part of the XOSC frequency in kHz
+pub const SET_TIME_63TO48: u16 = 0x60; // Set the time of the always-on timer
+pub const SET_TIME_47TO32: u16 = 0x64;
+pub const SET_TIME_31TO16: u16 = 0x68;
+pub const SET_TIME_15TO0: u16 = 0x6c;
+pub const READ_TIME_UPPER: u16 = 0x70; // Read the upper 32 bits of the always-on timer
+pub const READ_TIME_LOWER: u16 = 0x74; // Read the lower 32 bits of the always-on timer
+pub const ALARM_TIME_63TO48: u16 = 0x78; // Alarm time of the always-on timer
+pub const ALARM_TIME_47TO32: u16 = 0x7c;
+pub const ALARM_TIME_31TO16: u16 = 0x80;
+pub const ALARM_TIME_15TO0: u16 = 0x84;
+pub const TIMER: u16 = 0x88; // Control of the always-on timer
+pub const PWRUP0: u16 = 0x8c; // GPIO power up sources, up to 4
+pub const PWRUP3: u16 = 0x98;
+pub const CURRENT_PWRUP_REQ: u16 = 0x9c; // Indicates current powerup request state
+pub const LAST_SWCORE_PWRUP: u16 = 0xa0; // Indicates which pwrup source triggered the last switched-core power up
+pub const DBG_PWRCFG: u16 = 0xa4; // Debug power configuration
+pub const BOOTDIS: u16 = 0xa8; // Disables the boot of the cores from the watchdog or powman paths
+pub const DBGCONFIG: u16 = 0xac; // Configuration of the debug port
+pub const SCRATCH0: u16 = 0xb0; // Scratch registers, they persist in low power modes
+pub const SCRATCH7: u16 = 0xcc;
+pub const BOOT0: u16 = 0xd0; // Boot vector of the next switched-core power up
+pub const BOOT3: u16 = 0xdc;
+pub const INTR: u16 = 0xe0; // Raw Interrupts
+pub const INTE: u16 = 0xe4; // Interrupt Enable
+pub const INTF: u16 = 0xe8; // Interrupt Force
+pub const INTS: u16 = 0xec; // Interrupt status after masking & forcing
+
+/// Every write, but the ones to the scratch and boot registers, needs it in its upper half
+pub const PASSWORD: u32 = 0x5afe;
+
+pub const STATE_CHANGING: u32 = 1 << 13;
+pub const STATE_WAITING: u32 = 1 << 12;
+pub const STATE_BAD_HW_REQ: u32 = 1 << 11;
+pub const STATE_BAD_SW_REQ: u32 = 1 << 10;
+pub const STATE_PWRUP_WHILE_WAITING: u32 = 1 << 9;
+pub const STATE_REQ_IGNORED: u32 = 1 << 8;
+const STATE_W1C: u32 = 0x0f00;
+
+/// Domains of the power states, a bit is set in STATE.CURRENT while the domain is powered
+pub const DOMAIN_SRAM_BANK1: u32 = 1 << 0;
+pub const DOMAIN_SRAM_BANK0: u32 = 1 << 1;
+pub const DOMAIN_XIP_CACHE: u32 = 1 << 2;
+pub const DOMAIN_SWITCHED_CORE: u32 = 1 << 3;
+
+/// The only writable bit of CHIP_RESET, the others record the cause of the last reset
+pub const CHIP_RESET_DOUBLE_TAP: u32 = 1 << 0;
+pub const CHIP_RESET_HAD_GLITCH_DETECT: u32 = 1 << 26;
+
+pub const TIMER_NONSEC_WRITE: u32 = 1 << 0;
+pub const TIMER_RUN: u32 = 1 << 1;
+pub const TIMER_CLEAR: u32 = 1 << 2;
+pub const TIMER_ALARM_ENAB: u32 = 1 << 4;
+pub const TIMER_PWRUP_ON_ALARM: u32 = 1 << 5;
+pub const TIMER_ALARM: u32 = 1 << 6;
+pub const TIMER_USE_LPOSC: u32 = 1 << 8;
+pub const TIMER_USE_XOSC: u32 = 1 << 9;
+pub const TIMER_USING_XOSC: u32 = 1 << 16;
+pub const TIMER_USING_LPOSC: u32 = 1 << 17;
+
+pub const INTR_VREG_OUTPUT_LOW: u32 = 1 << 0;
+pub const INTR_TIMER: u32 = 1 << 1;
+pub const INTR_STATE_REQ_IGNORED: u32 = 1 << 2;
+pub const INTR_PWRUP_WHILE_WAITING: u32 = 1 << 3;
+
 pub struct Powman {
-    // TODO
+    pub vreg_ctrl: u32,
+    pub vreg: u32,
+    pub vreg_lp_entry: u32,
+    pub vreg_lp_exit: u32,
+    pub bod_ctrl: u32,
+    pub bod: u32,
+    pub bod_lp_entry: u32,
+    pub bod_lp_exit: u32,
+    pub lposc: u32,
+    pub chip_reset: u32,
+    pub wdsel: u32,
+    pub seq_cfg: u32,
+    /// Powered domains, as in STATE.CURRENT
+    pub power_state: u32,
+    /// Flags of STATE other than the current power state
+    pub state_flags: u32,
+    pub pow_fastdiv: u32,
+    pub pow_delay: u32,
+    pub ext_ctrl: [u32; 2],
+    pub ext_time_ref: u32,
+    pub lposc_freq_khz: [u32; 2],
+    pub xosc_freq_khz: [u32; 2],
+    pub alarm: u64,
+    pub timer: u32,
+    pub pwrup: [u32; 4],
+    pub dbg_pwrcfg: u32,
+    pub bootdis: u32,
+    pub dbgconfig: u32,
+    pub scratch: [u32; 8],
+    pub boot: [u32; 4],
+    pub interrupt_enable: u32,
+    pub interrupt_force: u32,
+    pub bad_password: bool,
+
+    /// Time of the always-on timer in ms when it was last stopped or started
+    time_base: u64,
+    /// Tick the timer was started at, if it is running
+    running_since: Option<u64>,
 }
 
 impl Default for Powman {
     fn default() -> Self {
-        Self {}
+        Self {
+            vreg_ctrl: 0x8050,
+            vreg: 0x00b0, // 1.10V
+            vreg_lp_entry: 0x00b4,
+            vreg_lp_exit: 0x00b0,
+            bod_ctrl: 0,
+            bod: 0x00b1,
+            bod_lp_entry: 0x00b0,
+            bod_lp_exit: 0x00b1,
+            lposc: 0x0203,
+            chip_reset: 0,
+            wdsel: 0,
+            seq_cfg: 0x0010_11f0,
+            power_state: 0xf, // everything is powered
+            state_flags: 0,
+            pow_fastdiv: 0x40,
+            pow_delay: 0x2011,
+            ext_ctrl: [0x0000_003f; 2],
+            ext_time_ref: 0,
+            lposc_freq_khz: [0x20, 0xc49c], // 32.768kHz
+            xosc_freq_khz: [0x2ee0, 0],     // 12MHz
+            alarm: 0,
+            timer: 0,
+            pwrup: [0x0000_003f; 4],
+            dbg_pwrcfg: 0,
+            bootdis: 0,
+            dbgconfig: 0,
+            scratch: [0; 8],
+            boot: [0; 4],
+            interrupt_enable: 0,
+            interrupt_force: 0,
+            bad_password: false,
+            time_base: 0,
+            running_since: None,
+        }
     }
 }
 
-impl Peripheral for Powman {
+impl Powman {
+    pub fn is_timer_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Time of the always-on timer in ms.
+    /// It counts the simulated time, whatever its clock source is set to.
+    pub fn time(&self, clock: &Clock) -> u64 {
+        let Some(since) = self.running_since else {
+            return self.time_base;
+        };
+
+        let elapsed = clock.ticks.borrow().saturating_sub(since);
+        self.time_base + elapsed * 1000 / clock.clk_sys()
+    }
+
+    fn set_time(&mut self, time: u64, clock: &Clock) {
+        self.time_base = time;
+
+        if self.running_since.is_some() {
+            self.running_since = Some(*clock.ticks.borrow());
+        }
+    }
+
+    pub fn intr(&self) -> u32 {
+        let mut intr = 0;
+
+        if self.timer & TIMER_ALARM != 0 {
+            intr |= INTR_TIMER;
+        }
+
+        if self.state_flags & STATE_REQ_IGNORED != 0 {
+            intr |= INTR_STATE_REQ_IGNORED;
+        }
+
+        if self.state_flags & STATE_PWRUP_WHILE_WAITING != 0 {
+            intr |= INTR_PWRUP_WHILE_WAITING;
+        }
+
+        intr
+    }
+
+    pub fn ints(&self) -> u32 {
+        (self.intr() & self.interrupt_enable) | self.interrupt_force
+    }
+
+    fn update_interrupt(&self, interrupts: &RefCell<Interrupts>) {
+        let ints = self.ints();
+        let mut interrupts = interrupts.borrow_mut();
+        interrupts.set_irq(Interrupts::POWMAN_IRQ_TIMER, ints & INTR_TIMER != 0);
+        interrupts.set_irq(Interrupts::POWMAN_IRQ_POW, ints & !INTR_TIMER != 0);
+    }
+
+    /// Raise the alarm once the timer reaches it
+    pub(crate) fn tick(&mut self, clock: &Clock, interrupts: &RefCell<Interrupts>) {
+        let armed = self.timer & (TIMER_ALARM_ENAB | TIMER_ALARM) == TIMER_ALARM_ENAB;
+
+        if !armed || !self.is_timer_running() || self.time(clock) < self.alarm {
+            return;
+        }
+
+        self.timer |= TIMER_ALARM;
+        self.update_interrupt(interrupts);
+    }
+
+    fn write_timer(&mut self, value: u32, clock: &Clock) {
+        if value & TIMER_ALARM != 0 {
+            self.timer &= !TIMER_ALARM;
+        }
+
+        if value & TIMER_CLEAR != 0 {
+            self.set_time(0, clock);
+        }
+
+        match (value & TIMER_RUN != 0, self.running_since) {
+            (true, None) => self.running_since = Some(*clock.ticks.borrow()),
+            (false, Some(_)) => {
+                self.time_base = self.time(clock);
+                self.running_since = None;
+            }
+            _ => {}
+        }
+
+        let mut timer = value & 0x3733 & !TIMER_CLEAR;
+        if timer & TIMER_RUN != 0 {
+            timer |= match value & TIMER_USE_XOSC != 0 {
+                true => TIMER_USING_XOSC,
+                false => TIMER_USING_LPOSC,
+            };
+        }
+
+        self.timer = timer | (self.timer & TIMER_ALARM);
+    }
+
+    /// Request the power state of STATE.REQ, a set bit powers the domain down.
+    /// The transition completes straight away, powering the switched core down is not
+    /// simulated and the cores keep running.
+    fn request_power_state(&mut self, request: u32) {
+        let next = !request & 0xf;
+
+        if next == self.power_state {
+            return;
+        }
+
+        if next & DOMAIN_SWITCHED_CORE == 0 {
+            log::warn!("POWMAN: the switched core domain is not powered down in the simulator");
+        }
+
+        self.power_state = next;
+    }
+
+    /// Replace a chunk of 16 bits of a 64 bits value
+    fn set_chunk(value: u64, chunk: u32, bits: u32) -> u64 {
+        let shift = chunk * 16;
+        (value & !(0xffff << shift)) | ((bits as u64 & 0xffff) << shift)
+    }
+
+    fn is_password_protected(address: u16) -> bool {
+        !(SCRATCH0..=BOOT3).contains(&address)
+    }
+
+    /// Bits cleared by writing a 1, through any alias
+    fn w1c_mask(address: u16) -> u32 {
+        match address {
+            BADPASSWD => 1,
+            STATE => STATE_W1C,
+            TIMER => TIMER_ALARM,
+            _ => 0,
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u32, clock: &Clock) -> PeripheralResult<()> {
+        match address {
+            BADPASSWD if value & 1 != 0 => self.bad_password = false,
+            BADPASSWD => {}
+            VREG_CTRL => self.vreg_ctrl = value & 0xf077,
+            VREG_STS => { /* read-only */ }
+            VREG => self.vreg = value & 0x01f2,
+            VREG_LP_ENTRY => self.vreg_lp_entry = value & 0x01f6,
+            VREG_LP_EXIT => self.vreg_lp_exit = value & 0x01f6,
+            BOD_CTRL => self.bod_ctrl = value & 0x1000,
+            BOD => self.bod = value & 0x01f1,
+            BOD_LP_ENTRY => self.bod_lp_entry = value & 0x01f1,
+            BOD_LP_EXIT => self.bod_lp_exit = value & 0x01f1,
+            LPOSC => self.lposc = value & 0x03f3,
+            CHIP_RESET => {
+                self.chip_reset =
+                    self.chip_reset & !CHIP_RESET_DOUBLE_TAP | value & CHIP_RESET_DOUBLE_TAP;
+            }
+            WDSEL => self.wdsel = value & 0x1111,
+            SEQ_CFG => self.seq_cfg = value & 0x0013_11f3,
+            STATE => {
+                self.state_flags &= !(value & STATE_W1C);
+                self.request_power_state((value >> 4) & 0xf);
+            }
+            POW_FASTDIV => self.pow_fastdiv = value & 0x07ff,
+            POW_DELAY => self.pow_delay = value & 0xffff,
+            EXT_CTRL0 => self.ext_ctrl[0] = value & 0x713f,
+            EXT_CTRL1 => self.ext_ctrl[1] = value & 0x713f,
+            EXT_TIME_REF => self.ext_time_ref = value & 0x13,
+            LPOSC_FREQ_KHZ_INT => self.lposc_freq_khz[0] = value & 0x3f,
+            LPOSC_FREQ_KHZ_FRAC => self.lposc_freq_khz[1] = value & 0xffff,
+            XOSC_FREQ_KHZ_INT => self.xosc_freq_khz[0] = value & 0xffff,
+            XOSC_FREQ_KHZ_FRAC => self.xosc_freq_khz[1] = value & 0xffff,
+            SET_TIME_63TO48..=SET_TIME_15TO0 => {
+                // the time can only be set while the timer is stopped
+                if !self.is_timer_running() {
+                    let chunk = 3 - (address - SET_TIME_63TO48) as u32 / 4;
+                    let time = Self::set_chunk(self.time(clock), chunk, value);
+                    self.set_time(time, clock);
+                }
+            }
+            READ_TIME_UPPER | READ_TIME_LOWER => { /* read-only */ }
+            ALARM_TIME_63TO48..=ALARM_TIME_15TO0 => {
+                let chunk = 3 - (address - ALARM_TIME_63TO48) as u32 / 4;
+                self.alarm = Self::set_chunk(self.alarm, chunk, value);
+            }
+            TIMER => self.write_timer(value, clock),
+            PWRUP0..=PWRUP3 => self.pwrup[(address - PWRUP0) as usize / 4] = value & 0x07ff,
+            CURRENT_PWRUP_REQ | LAST_SWCORE_PWRUP => { /* read-only */ }
+            DBG_PWRCFG => self.dbg_pwrcfg = value & 0x1,
+            BOOTDIS => self.bootdis = value & 0x3,
+            DBGCONFIG => self.dbgconfig = value & 0xf,
+            SCRATCH0..=SCRATCH7 => self.scratch[(address - SCRATCH0) as usize / 4] = value,
+            BOOT0..=BOOT3 => self.boot[(address - BOOT0) as usize / 4] = value,
+            INTR | INTS => { /* read-only */ }
+            INTE => self.interrupt_enable = value & 0xf,
+            INTF => self.interrupt_force = value & 0xf,
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+impl Peripheral for Rc<RefCell<Powman>> {
     fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
-        log::warn!(
-            "Unimplemented peripheral read at address {:#X}",
-            ctx.address
-        );
-        Ok(0)
+        let powman = self.borrow();
+
+        let value = match address {
+            BADPASSWD => powman.bad_password as u32,
+            VREG_CTRL => powman.vreg_ctrl,
+            VREG_STS => 0x10, // VOUT_OK, the regulator is never busy
+            VREG => powman.vreg,
+            VREG_LP_ENTRY => powman.vreg_lp_entry,
+            VREG_LP_EXIT => powman.vreg_lp_exit,
+            BOD_CTRL => powman.bod_ctrl,
+            BOD => powman.bod,
+            BOD_LP_ENTRY => powman.bod_lp_entry,
+            BOD_LP_EXIT => powman.bod_lp_exit,
+            LPOSC => powman.lposc,
+            CHIP_RESET => powman.chip_reset,
+            WDSEL => powman.wdsel,
+            SEQ_CFG => powman.seq_cfg,
+            STATE => powman.state_flags | ((!powman.power_state & 0xf) << 4) | powman.power_state,
+            POW_FASTDIV => powman.pow_fastdiv,
+            POW_DELAY => powman.pow_delay,
+            EXT_CTRL0 => powman.ext_ctrl[0],
+            EXT_CTRL1 => powman.ext_ctrl[1],
+            EXT_TIME_REF => powman.ext_time_ref,
+            LPOSC_FREQ_KHZ_INT => powman.lposc_freq_khz[0],
+            LPOSC_FREQ_KHZ_FRAC => powman.lposc_freq_khz[1],
+            XOSC_FREQ_KHZ_INT => powman.xosc_freq_khz[0],
+            XOSC_FREQ_KHZ_FRAC => powman.xosc_freq_khz[1],
+            SET_TIME_63TO48..=SET_TIME_15TO0 => 0, // write-only
+            READ_TIME_UPPER => (powman.time(&ctx.clock) >> 32) as u32,
+            READ_TIME_LOWER => powman.time(&ctx.clock) as u32,
+            ALARM_TIME_63TO48..=ALARM_TIME_15TO0 => {
+                let chunk = 3 - (address - ALARM_TIME_63TO48) as u32 / 4;
+                (powman.alarm >> (chunk * 16)) as u32 & 0xffff
+            }
+            TIMER => powman.timer,
+            PWRUP0..=PWRUP3 => powman.pwrup[(address - PWRUP0) as usize / 4],
+            CURRENT_PWRUP_REQ => 0,
+            LAST_SWCORE_PWRUP => 0,
+            DBG_PWRCFG => powman.dbg_pwrcfg,
+            BOOTDIS => powman.bootdis,
+            DBGCONFIG => powman.dbgconfig,
+            SCRATCH0..=SCRATCH7 => powman.scratch[(address - SCRATCH0) as usize / 4],
+            BOOT0..=BOOT3 => powman.boot[(address - BOOT0) as usize / 4],
+            INTR => powman.intr(),
+            INTE => powman.interrupt_enable,
+            INTF => powman.interrupt_force,
+            INTS => powman.ints(),
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        let value = match Powman::is_password_protected(offset) {
+            true if value >> 16 != PASSWORD => {
+                let mut powman = self.borrow_mut();
+                powman.bad_password = true;
+                return Ok(());
+            }
+            true => value & 0xffff,
+            false => value,
+        };
+
+        let aliased = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        // the written ones clear these bits, whatever the alias is
+        let w1c = Powman::w1c_mask(offset);
+        self.write_raw(offset, (aliased & !w1c) | (value & w1c), ctx)
     }
 
     fn write_raw(
@@ -24,11 +463,101 @@ impl Peripheral for Powman {
         value: u32,
         ctx: &PeripheralAccessContext,
     ) -> PeripheralResult<()> {
-        log::warn!(
-            "Unimplemented peripheral write at address {:#X} with value {:#X}",
-            ctx.address,
-            value
-        );
+        let mut powman = self.borrow_mut();
+        powman.write_register(address, value, &ctx.clock)?;
+        powman.update_interrupt(&ctx.interrupts);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: u16 = 0x2000;
+
+    fn setup() -> (Rc<RefCell<Powman>>, PeripheralAccessContext) {
+        (Default::default(), Default::default())
+    }
+
+    fn tick_ms(powman: &Rc<RefCell<Powman>>, ctx: &PeripheralAccessContext, ms: u64) {
+        for _ in 0..ms * ctx.clock.clk_sys() / 1000 {
+            ctx.clock.tick();
+            powman.borrow_mut().tick(&ctx.clock, &ctx.interrupts);
+        }
+    }
+
+    fn timer_irq(ctx: &PeripheralAccessContext) -> bool {
+        let interrupts = ctx.interrupts.borrow();
+        interrupts
+            .iter(0)
+            .any(|irq| irq == Interrupts::POWMAN_IRQ_TIMER)
+    }
+
+    #[test]
+    fn test_password() {
+        let (mut powman, ctx) = setup();
+
+        powman.write(SCRATCH0, 0xdead_beef, &ctx).unwrap();
+        assert_eq!(powman.read(SCRATCH0, &ctx), Ok(0xdead_beef));
+
+        powman.write(VREG, 0x00c0, &ctx).unwrap();
+        assert_eq!(powman.read(VREG, &ctx), Ok(0x00b0));
+        assert_eq!(powman.read(BADPASSWD, &ctx), Ok(1));
+
+        powman.write(VREG, (PASSWORD << 16) | 0x00c0, &ctx).unwrap();
+        assert_eq!(powman.read(VREG, &ctx), Ok(0x00c0));
+
+        powman.write(BADPASSWD, (PASSWORD << 16) | 1, &ctx).unwrap();
+        assert_eq!(powman.read(BADPASSWD, &ctx), Ok(0));
+    }
+
+    #[test]
+    fn test_aon_timer_alarm() {
+        let (mut powman, ctx) = setup();
+        let write = |powman: &mut Rc<RefCell<Powman>>, address, value| {
+            powman
+                .write(address, (PASSWORD << 16) | value, &ctx)
+                .unwrap();
+        };
+
+        write(&mut powman, SET_TIME_15TO0, 1000);
+        write(&mut powman, ALARM_TIME_15TO0, 1003);
+        write(&mut powman, INTE, INTR_TIMER);
+        write(&mut powman, TIMER, TIMER_RUN | TIMER_ALARM_ENAB);
+
+        tick_ms(&powman, &ctx, 2);
+        assert_eq!(powman.read(READ_TIME_LOWER, &ctx), Ok(1002));
+        assert_eq!(powman.read(TIMER, &ctx).unwrap() & TIMER_ALARM, 0);
+
+        tick_ms(&powman, &ctx, 1);
+        assert_ne!(powman.read(TIMER, &ctx).unwrap() & TIMER_ALARM, 0);
+        assert!(timer_irq(&ctx));
+
+        // the set alias does not leave the alarm set
+        write(&mut powman, SET | TIMER, TIMER_ALARM);
+        assert_eq!(powman.read(TIMER, &ctx).unwrap() & TIMER_ALARM, 0);
+        assert!(!timer_irq(&ctx));
+
+        // the time is frozen while stopped
+        write(&mut powman, TIMER, 0);
+        tick_ms(&powman, &ctx, 1);
+        assert_eq!(powman.read(READ_TIME_LOWER, &ctx), Ok(1003));
+    }
+
+    #[test]
+    fn test_power_state() {
+        let (mut powman, ctx) = setup();
+        assert_eq!(powman.read(STATE, &ctx).unwrap() & 0xf, 0xf);
+
+        // power down both SRAM banks
+        let request = DOMAIN_SRAM_BANK0 | DOMAIN_SRAM_BANK1;
+        powman
+            .write(STATE, (PASSWORD << 16) | (request << 4), &ctx)
+            .unwrap();
+        assert_eq!(
+            powman.read(STATE, &ctx).unwrap() & 0xf,
+            DOMAIN_XIP_CACHE | DOMAIN_SWITCHED_CORE
+        );
+    }
+}
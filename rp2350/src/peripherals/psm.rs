@@ -0,0 +1,110 @@
+/**
+ * @file peripherals/psm.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Power-on state machine, sequences the power domains and the cores out of reset
+ */
+use super::*;
+
+pub const FRCE_ON: u16 = 0x0; // Force block out of reset (i.e. power it on)
+pub const FRCE_OFF: u16 = 0x4; // Force into reset (i.e. power it off)
+pub const WDSEL: u16 = 0x8; // Set to 1 if the Watchdog should reset this
+pub const DONE: u16 = 0xC; // Is the subsystem ready?
+
+pub const PROC_COLD: u32 = 1 << 0;
+pub const OTP: u32 = 1 << 1;
+pub const ROSC: u32 = 1 << 2;
+pub const XOSC: u32 = 1 << 3;
+pub const RESETS: u32 = 1 << 4;
+pub const CLOCKS: u32 = 1 << 5;
+pub const PSM_READY: u32 = 1 << 6;
+pub const BUSFABRIC: u32 = 1 << 7;
+pub const ROM: u32 = 1 << 8;
+pub const BOOTRAM: u32 = 1 << 9;
+pub const SRAM0: u32 = 1 << 10;
+pub const XIP: u32 = 1 << 20;
+pub const SIO: u32 = 1 << 21;
+pub const ACCESSCTRL: u32 = 1 << 22;
+pub const PROC0: u32 = 1 << 23;
+pub const PROC1: u32 = 1 << 24;
+
+const MASK: u32 = 0x01ff_ffff;
+
+/// The power-on sequence is over by the time the cores run, every domain not forced off
+/// is done. Forcing a domain off only shows in DONE, nothing is powered down.
+#[derive(Default)]
+pub struct Psm {
+    pub frce_on: u32,
+    pub frce_off: u32,
+    pub wdsel: u32,
+}
+
+impl Peripheral for Psm {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let value = match address {
+            FRCE_ON => self.frce_on,
+            FRCE_OFF => self.frce_off,
+            WDSEL => self.wdsel,
+            DONE => !self.frce_off & MASK,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        match address {
+            FRCE_ON => self.frce_on = value & MASK,
+            FRCE_OFF => self.frce_off = value & MASK,
+            WDSEL => self.wdsel = value & MASK,
+            DONE => { /* Read only */ }
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_force_off() {
+        let mut psm = Psm::default();
+        let ctx = PeripheralAccessContext::default();
+
+        assert_eq!(psm.read(DONE, &ctx), Ok(MASK));
+
+        // how the SDK resets core 1
+        psm.write(0x2000 | FRCE_OFF, PROC1, &ctx).unwrap();
+        assert_eq!(psm.read(FRCE_OFF, &ctx), Ok(PROC1));
+        assert_eq!(psm.read(DONE, &ctx), Ok(MASK & !PROC1));
+
+        psm.write(0x3000 | FRCE_OFF, PROC1, &ctx).unwrap();
+        assert_eq!(psm.read(DONE, &ctx), Ok(MASK));
+    }
+}
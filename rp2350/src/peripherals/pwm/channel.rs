@@ -198,6 +198,10 @@ pub(super) fn channel_as_function_select(index: u8) -> (FunctionSelect, Function
         5 => (FunctionSelect::PWM5_A, FunctionSelect::PWM5_B),
         6 => (FunctionSelect::PWM6_A, FunctionSelect::PWM6_B),
         7 => (FunctionSelect::PWM7_A, FunctionSelect::PWM7_B),
+        8 => (FunctionSelect::PWM8_A, FunctionSelect::PWM8_B),
+        9 => (FunctionSelect::PWM9_A, FunctionSelect::PWM9_B),
+        10 => (FunctionSelect::PWM10_A, FunctionSelect::PWM10_B),
+        11 => (FunctionSelect::PWM11_A, FunctionSelect::PWM11_B),
         _ => unreachable!(),
     }
 }
@@ -0,0 +1,322 @@
+/**
+ * @file peripherals/qmi.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief QSPI memory interface, its direct mode drives the serial flash behind the XIP window
+ */
+pub mod serial_flash;
+
+pub use serial_flash::SerialFlash;
+
+use super::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+pub const DIRECT_CSR: u16 = 0x00; // Control and status for direct serial mode
+pub const DIRECT_TX: u16 = 0x04; // Transmit FIFO for direct mode
+pub const DIRECT_RX: u16 = 0x08; // Receive FIFO for direct mode
+pub const M0_TIMING: u16 = 0x0c; // Timing configuration register for memory address window 0
+pub const M0_RFMT: u16 = 0x10; // Read transfer format configuration for memory address window 0
+pub const M0_RCMD: u16 = 0x14; // Command constants used for reads from memory address window 0
+pub const M0_WFMT: u16 = 0x18; // Write transfer format configuration for memory address window 0
+pub const M0_WCMD: u16 = 0x1c; // Command constants used for writes to memory address window 0
+pub const M1_TIMING: u16 = 0x20; // Timing configuration register for memory address window 1
+pub const M1_RFMT: u16 = 0x24; // Read transfer format configuration for memory address window 1
+pub const M1_RCMD: u16 = 0x28; // Command constants used for reads from memory address window 1
+pub const M1_WFMT: u16 = 0x2c; // Write transfer format configuration for memory address window 1
+pub const M1_WCMD: u16 = 0x30; // Command constants used for writes to memory address window 1
+pub const ATRANS0: u16 = 0x34; // Configure address translation for XIP virtual addresses, 8 of them
+pub const ATRANS7: u16 = 0x50;
+
+pub const DIRECT_CSR_EN: u32 = 1 << 0;
+pub const DIRECT_CSR_BUSY: u32 = 1 << 1;
+pub const DIRECT_CSR_ASSERT_CS0N: u32 = 1 << 2;
+pub const DIRECT_CSR_ASSERT_CS1N: u32 = 1 << 3;
+pub const DIRECT_CSR_AUTO_CS0N: u32 = 1 << 6;
+pub const DIRECT_CSR_AUTO_CS1N: u32 = 1 << 7;
+pub const DIRECT_CSR_TXFULL: u32 = 1 << 10;
+pub const DIRECT_CSR_TXEMPTY: u32 = 1 << 11;
+pub const DIRECT_CSR_RXEMPTY: u32 = 1 << 16;
+pub const DIRECT_CSR_RXFULL: u32 = 1 << 17;
+/// EN, the chip selects, CLKDIV and RXDELAY
+const DIRECT_CSR_WRITABLE: u32 = 0xffc0_00cd;
+
+pub const DIRECT_TX_DWIDTH: u32 = 1 << 18;
+pub const DIRECT_TX_OE: u32 = 1 << 19;
+pub const DIRECT_TX_NOPUSH: u32 = 1 << 20;
+
+/// Depth of the direct mode FIFOs
+pub const FIFO_DEPTH: usize = 4;
+
+/// Transfer configuration of one of the two memory windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryWindow {
+    pub timing: u32,
+    pub rfmt: u32,
+    pub rcmd: u32,
+    pub wfmt: u32,
+    pub wcmd: u32,
+}
+
+impl Default for MemoryWindow {
+    fn default() -> Self {
+        Self {
+            timing: 0x4000_0004,
+            rfmt: 0x0000_1000,
+            rcmd: 0x0000_a003,
+            wfmt: 0x0000_1000,
+            wcmd: 0x0000_a002,
+        }
+    }
+}
+
+/// The direct mode shifts its bytes through the flash as soon as the memory is handed over
+/// by the bus, a transfer is never seen busy. With the automatic chip select, every burst of
+/// writes to the TX FIFO is one transaction.
+pub struct Qmi {
+    pub direct_csr: u32,
+    pub windows: [MemoryWindow; 2],
+    pub atrans: [u32; 8],
+    /// The flash on CS0, nothing is wired to CS1
+    pub flash: SerialFlash,
+    tx: VecDeque<u32>,
+    rx: RefCell<VecDeque<u16>>,
+}
+
+impl Default for Qmi {
+    fn default() -> Self {
+        Self {
+            direct_csr: 0x0180_0000,
+            windows: Default::default(),
+            // 4MB windows, one after the other
+            atrans: core::array::from_fn(|i| 0x0400_0000 | (i as u32 * 0x400)),
+            flash: SerialFlash::default(),
+            tx: VecDeque::new(),
+            rx: RefCell::new(VecDeque::new()),
+        }
+    }
+}
+
+impl Qmi {
+    pub fn is_direct_mode(&self) -> bool {
+        self.direct_csr & DIRECT_CSR_EN != 0
+    }
+
+    fn is_cs0_asserted(&self) -> bool {
+        let assert = DIRECT_CSR_ASSERT_CS0N | DIRECT_CSR_AUTO_CS0N;
+        self.is_direct_mode() && self.direct_csr & assert != 0
+    }
+
+    fn direct_csr(&self) -> u32 {
+        let rx = self.rx.borrow();
+        let mut csr = self.direct_csr | DIRECT_CSR_TXEMPTY | ((rx.len() as u32 & 0x7) << 18);
+
+        if rx.is_empty() {
+            csr |= DIRECT_CSR_RXEMPTY;
+        }
+
+        if rx.len() >= FIFO_DEPTH {
+            csr |= DIRECT_CSR_RXFULL;
+        }
+
+        csr
+    }
+
+    /// Shift the TX FIFO through the flash and update its chip select, called by the bus
+    /// after each write to the registers
+    pub(crate) fn transfer(&mut self, memory: &mut [u8]) {
+        let auto = self.direct_csr & DIRECT_CSR_AUTO_CS0N != 0;
+        let assert = self.direct_csr & DIRECT_CSR_ASSERT_CS0N != 0;
+
+        if self.is_cs0_asserted() && !self.flash.is_selected() && (assert || !self.tx.is_empty()) {
+            self.flash.select();
+        }
+
+        while let Some(entry) = self.tx.pop_front() {
+            let data = entry as u16;
+            let bytes = match entry & DIRECT_TX_DWIDTH != 0 {
+                true => data.to_be_bytes().to_vec(),
+                false => vec![data as u8],
+            };
+
+            let received = bytes.into_iter().fold(0u16, |received, byte| {
+                (received << 8) | self.flash.transfer(byte, memory) as u16
+            });
+
+            let mut rx = self.rx.borrow_mut();
+            if entry & DIRECT_TX_NOPUSH == 0 && rx.len() < FIFO_DEPTH {
+                rx.push_back(received);
+            }
+        }
+
+        // the automatic chip select is released once the FIFO is empty
+        if !self.is_cs0_asserted() || (auto && !assert) {
+            self.flash.deselect(memory);
+        }
+    }
+}
+
+impl Peripheral for Rc<RefCell<Qmi>> {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let qmi = self.borrow();
+
+        let value = match address {
+            DIRECT_CSR => qmi.direct_csr(),
+            DIRECT_TX => 0, // write-only
+            DIRECT_RX => qmi.rx.borrow_mut().pop_front().unwrap_or_default() as u32,
+            M0_TIMING => qmi.windows[0].timing,
+            M0_RFMT => qmi.windows[0].rfmt,
+            M0_RCMD => qmi.windows[0].rcmd,
+            M0_WFMT => qmi.windows[0].wfmt,
+            M0_WCMD => qmi.windows[0].wcmd,
+            M1_TIMING => qmi.windows[1].timing,
+            M1_RFMT => qmi.windows[1].rfmt,
+            M1_RCMD => qmi.windows[1].rcmd,
+            M1_WFMT => qmi.windows[1].wfmt,
+            M1_WCMD => qmi.windows[1].wcmd,
+            ATRANS0..=ATRANS7 => qmi.atrans[(address - ATRANS0) as usize / 4],
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        // a push to the FIFO, not a register to modify
+        if matches!(offset, DIRECT_TX | DIRECT_RX) {
+            return self.write_raw(offset, value, ctx);
+        }
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut qmi = self.borrow_mut();
+
+        match address {
+            DIRECT_CSR => qmi.direct_csr = value & DIRECT_CSR_WRITABLE,
+            DIRECT_TX if qmi.tx.len() < FIFO_DEPTH => qmi.tx.push_back(value & 0x001f_ffff),
+            DIRECT_TX => log::warn!("QMI: write to the full TX FIFO is dropped"),
+            DIRECT_RX => { /* read-only */ }
+            M0_TIMING => qmi.windows[0].timing = value & 0xf3ff_f7ff,
+            M0_RFMT => qmi.windows[0].rfmt = value & 0x1007_37ff,
+            M0_RCMD => qmi.windows[0].rcmd = value & 0xffff,
+            M0_WFMT => qmi.windows[0].wfmt = value & 0x1007_37ff,
+            M0_WCMD => qmi.windows[0].wcmd = value & 0xffff,
+            M1_TIMING => qmi.windows[1].timing = value & 0xf3ff_f7ff,
+            M1_RFMT => qmi.windows[1].rfmt = value & 0x1007_37ff,
+            M1_RCMD => qmi.windows[1].rcmd = value & 0xffff,
+            M1_WFMT => qmi.windows[1].wfmt = value & 0x1007_37ff,
+            M1_WCMD => qmi.windows[1].wcmd = value & 0xffff,
+            ATRANS0..=ATRANS7 => qmi.atrans[(address - ATRANS0) as usize / 4] = value & 0x07ff_0fff,
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::serial_flash::*;
+    use super::*;
+
+    const SET: u16 = 0x2000;
+    const CLEAR: u16 = 0x3000;
+
+    /// Send a command with the chip select held, like the flash functions of the bootrom
+    fn command(
+        qmi: &mut Rc<RefCell<Qmi>>,
+        memory: &mut [u8],
+        ctx: &PeripheralAccessContext,
+        bytes: &[u8],
+    ) -> Vec<u8> {
+        qmi.write(SET | DIRECT_CSR, DIRECT_CSR_ASSERT_CS0N, ctx)
+            .unwrap();
+        qmi.borrow_mut().transfer(memory);
+
+        let mut response = Vec::new();
+        for byte in bytes {
+            qmi.write(DIRECT_TX, *byte as u32, ctx).unwrap();
+            qmi.borrow_mut().transfer(memory);
+            response.push(qmi.read(DIRECT_RX, ctx).unwrap() as u8);
+        }
+
+        qmi.write(CLEAR | DIRECT_CSR, DIRECT_CSR_ASSERT_CS0N, ctx)
+            .unwrap();
+        qmi.borrow_mut().transfer(memory);
+        response
+    }
+
+    #[test]
+    fn test_direct_mode_program() {
+        let mut qmi: Rc<RefCell<Qmi>> = Default::default();
+        let ctx = PeripheralAccessContext::default();
+        let mut memory = vec![0xff; 4 * 1024 * 1024];
+
+        qmi.write(SET | DIRECT_CSR, DIRECT_CSR_EN, &ctx).unwrap();
+        assert!(qmi.borrow().is_direct_mode());
+
+        command(&mut qmi, &mut memory, &ctx, &[WRITE_ENABLE]);
+        command(
+            &mut qmi,
+            &mut memory,
+            &ctx,
+            &[PAGE_PROGRAM, 0x01, 0x00, 0x00, 0xde, 0xad],
+        );
+        assert_eq!(memory[0x10000..0x10002], [0xde, 0xad]);
+
+        let status = command(&mut qmi, &mut memory, &ctx, &[READ_STATUS1, 0]);
+        assert_eq!(status[1] & STATUS1_WEL, 0);
+
+        let data = command(&mut qmi, &mut memory, &ctx, &[READ, 0x01, 0x00, 0x00, 0, 0]);
+        assert_eq!(data[4..], [0xde, 0xad]);
+    }
+
+    #[test]
+    fn test_auto_chip_select() {
+        let mut qmi: Rc<RefCell<Qmi>> = Default::default();
+        let ctx = PeripheralAccessContext::default();
+        let mut memory = vec![0xff; 4 * 1024 * 1024];
+
+        let csr = DIRECT_CSR_EN | DIRECT_CSR_AUTO_CS0N;
+        qmi.write(DIRECT_CSR, csr, &ctx).unwrap();
+
+        // the whole command in one burst, read back as 16 bits
+        qmi.write(DIRECT_TX, JEDEC_ID as u32 | DIRECT_TX_NOPUSH, &ctx)
+            .unwrap();
+        qmi.write(DIRECT_TX, DIRECT_TX_DWIDTH, &ctx).unwrap();
+        qmi.borrow_mut().transfer(&mut memory);
+
+        let csr = qmi.read(DIRECT_CSR, &ctx).unwrap();
+        assert_eq!(csr & DIRECT_CSR_RXEMPTY, 0);
+        assert_eq!((csr >> 18) & 0x7, 1);
+
+        let id = qmi.read(DIRECT_RX, &ctx).unwrap();
+        assert_eq!(
+            id,
+            ((MANUFACTURER_WINBOND as u32) << 8) | MEMORY_TYPE as u32
+        );
+        assert!(!qmi.borrow().flash.is_selected());
+    }
+}
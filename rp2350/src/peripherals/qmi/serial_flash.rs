@@ -0,0 +1,355 @@
+/**
+ * @file peripherals/qmi/serial_flash.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Command set of a W25Q serial NOR flash, the one on the Pico 2
+ */
+pub const WRITE_STATUS1: u8 = 0x01;
+pub const PAGE_PROGRAM: u8 = 0x02;
+pub const READ: u8 = 0x03;
+pub const WRITE_DISABLE: u8 = 0x04;
+pub const READ_STATUS1: u8 = 0x05;
+pub const WRITE_ENABLE: u8 = 0x06;
+pub const FAST_READ: u8 = 0x0b;
+pub const WRITE_STATUS3: u8 = 0x11;
+pub const READ_STATUS3: u8 = 0x15;
+pub const SECTOR_ERASE: u8 = 0x20;
+pub const WRITE_STATUS2: u8 = 0x31;
+pub const QUAD_PAGE_PROGRAM: u8 = 0x32;
+pub const READ_STATUS2: u8 = 0x35;
+pub const DUAL_OUTPUT_READ: u8 = 0x3b;
+pub const UNIQUE_ID: u8 = 0x4b;
+pub const VOLATILE_WRITE_ENABLE: u8 = 0x50;
+pub const BLOCK_ERASE_32K: u8 = 0x52;
+pub const READ_SFDP: u8 = 0x5a;
+pub const CHIP_ERASE: u8 = 0x60;
+pub const ENABLE_RESET: u8 = 0x66;
+pub const QUAD_OUTPUT_READ: u8 = 0x6b;
+pub const MANUFACTURER_ID: u8 = 0x90;
+pub const RESET: u8 = 0x99;
+pub const JEDEC_ID: u8 = 0x9f;
+pub const RELEASE_POWER_DOWN: u8 = 0xab;
+pub const POWER_DOWN: u8 = 0xb9;
+pub const DUAL_IO_READ: u8 = 0xbb;
+pub const CHIP_ERASE_ALT: u8 = 0xc7;
+pub const BLOCK_ERASE_64K: u8 = 0xd8;
+pub const QUAD_IO_READ: u8 = 0xeb;
+
+pub const STATUS1_BUSY: u8 = 1 << 0;
+pub const STATUS1_WEL: u8 = 1 << 1;
+pub const STATUS2_QE: u8 = 1 << 1;
+
+pub const MANUFACTURER_WINBOND: u8 = 0xef;
+pub const MEMORY_TYPE: u8 = 0x40;
+pub const DEVICE_ID: u8 = 0x15;
+
+pub const PAGE_SIZE: usize = 256;
+
+/// What is shifted out while the flash does not drive its output
+const FLOATING: u8 = 0xff;
+
+/// The flash on its chip select, fed byte by byte while it is selected.
+/// Programming and erasing complete as soon as the chip is deselected, the busy bit is
+/// never seen.
+#[derive(Debug, Clone)]
+pub struct SerialFlash {
+    pub status: [u8; 3],
+    pub unique_id: [u8; 8],
+    powered_down: bool,
+    selected: bool,
+    /// Command of the current transaction
+    command: Option<u8>,
+    /// Bytes received after the command
+    position: usize,
+    address: u32,
+    write_enable_volatile: bool,
+    /// Number of page programs and erases done since power on
+    programs: u64,
+    erases: u64,
+}
+
+impl Default for SerialFlash {
+    fn default() -> Self {
+        Self {
+            status: [0x00, STATUS2_QE, 0x60],
+            unique_id: *b"RP2350SM",
+            powered_down: false,
+            selected: false,
+            command: None,
+            position: 0,
+            address: 0,
+            write_enable_volatile: false,
+            programs: 0,
+            erases: 0,
+        }
+    }
+}
+
+impl SerialFlash {
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    pub fn is_write_enabled(&self) -> bool {
+        self.status[0] & STATUS1_WEL != 0
+    }
+
+    pub fn programs(&self) -> u64 {
+        self.programs
+    }
+
+    pub fn erases(&self) -> u64 {
+        self.erases
+    }
+
+    /// Chip select asserted, the next byte is a command
+    pub fn select(&mut self) {
+        self.selected = true;
+        self.command = None;
+        self.position = 0;
+        self.address = 0;
+    }
+
+    /// Chip select released, it ends the command and starts the erases
+    pub fn deselect(&mut self, memory: &mut [u8]) {
+        if !self.selected {
+            return;
+        }
+
+        self.selected = false;
+
+        let Some(command) = self.command.take() else {
+            return;
+        };
+
+        let has_address = self.position >= 3;
+
+        match command {
+            SECTOR_ERASE if has_address => self.erase(memory, 4 * 1024),
+            BLOCK_ERASE_32K if has_address => self.erase(memory, 32 * 1024),
+            BLOCK_ERASE_64K if has_address => self.erase(memory, 64 * 1024),
+            CHIP_ERASE | CHIP_ERASE_ALT => self.erase(memory, memory.len()),
+            PAGE_PROGRAM | QUAD_PAGE_PROGRAM if self.position > 3 && self.is_write_enabled() => {
+                self.programs += 1;
+                self.status[0] &= !STATUS1_WEL;
+            }
+            WRITE_STATUS1 | WRITE_STATUS2 | WRITE_STATUS3 if self.position > 0 => {
+                self.status[0] &= !STATUS1_WEL;
+                self.write_enable_volatile = false;
+            }
+            _ => {}
+        }
+    }
+
+    /// Shift a byte in, and the byte the flash drives at the same time out
+    pub fn transfer(&mut self, byte: u8, memory: &mut [u8]) -> u8 {
+        if !self.selected {
+            return FLOATING;
+        }
+
+        let Some(command) = self.command else {
+            self.start(byte);
+            return FLOATING;
+        };
+
+        let position = self.position;
+        self.position += 1;
+
+        if position < 3 && Self::has_address(command) {
+            self.address = (self.address << 8) | byte as u32;
+            return FLOATING;
+        }
+
+        match command {
+            READ => self.read(memory, position - 3),
+            // a dummy byte, or the mode byte of the dual I/O read, before the data
+            FAST_READ | DUAL_OUTPUT_READ | QUAD_OUTPUT_READ | DUAL_IO_READ if position > 3 => {
+                self.read(memory, position - 4)
+            }
+            // the mode byte then two dummy bytes
+            QUAD_IO_READ if position > 5 => self.read(memory, position - 6),
+            PAGE_PROGRAM | QUAD_PAGE_PROGRAM => {
+                self.program(memory, position - 3, byte);
+                FLOATING
+            }
+            READ_STATUS1 => self.status[0],
+            READ_STATUS2 => self.status[1],
+            READ_STATUS3 => self.status[2],
+            WRITE_STATUS1 | WRITE_STATUS2 | WRITE_STATUS3 => {
+                self.write_status(command, position, byte);
+                FLOATING
+            }
+            JEDEC_ID => match position {
+                0 => MANUFACTURER_WINBOND,
+                1 => MEMORY_TYPE,
+                2 => memory.len().trailing_zeros() as u8,
+                _ => FLOATING,
+            },
+            MANUFACTURER_ID if (position - 3) % 2 == 1 => DEVICE_ID,
+            MANUFACTURER_ID => MANUFACTURER_WINBOND,
+            // four dummy bytes before the id
+            UNIQUE_ID if (4..12).contains(&position) => self.unique_id[position - 4],
+            RELEASE_POWER_DOWN if position >= 3 => DEVICE_ID,
+            _ => FLOATING,
+        }
+    }
+
+    fn start(&mut self, command: u8) {
+        // only a release wakes the flash up from the deep power down
+        if self.powered_down && command != RELEASE_POWER_DOWN {
+            return;
+        }
+
+        self.command = Some(command);
+
+        match command {
+            WRITE_ENABLE => self.status[0] |= STATUS1_WEL,
+            WRITE_DISABLE => self.status[0] &= !STATUS1_WEL,
+            VOLATILE_WRITE_ENABLE => self.write_enable_volatile = true,
+            POWER_DOWN => self.powered_down = true,
+            RELEASE_POWER_DOWN => self.powered_down = false,
+            RESET => {
+                self.status[0] &= !STATUS1_WEL;
+                self.write_enable_volatile = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn has_address(command: u8) -> bool {
+        matches!(
+            command,
+            READ | FAST_READ
+                | DUAL_OUTPUT_READ
+                | QUAD_OUTPUT_READ
+                | DUAL_IO_READ
+                | QUAD_IO_READ
+                | READ_SFDP
+                | PAGE_PROGRAM
+                | QUAD_PAGE_PROGRAM
+                | SECTOR_ERASE
+                | BLOCK_ERASE_32K
+                | BLOCK_ERASE_64K
+                | MANUFACTURER_ID
+        )
+    }
+
+    /// The address keeps increasing while reading and wraps around the end of the memory
+    fn read(&self, memory: &[u8], offset: usize) -> u8 {
+        let address = (self.address as usize + offset) % memory.len();
+        memory[address]
+    }
+
+    /// Programming can only clear bits, the address wraps around the page
+    fn program(&mut self, memory: &mut [u8], offset: usize, byte: u8) {
+        if !self.is_write_enabled() {
+            return;
+        }
+
+        let page = self.address as usize & !(PAGE_SIZE - 1);
+        let address = (page + (self.address as usize + offset) % PAGE_SIZE) % memory.len();
+        memory[address] &= byte;
+    }
+
+    fn erase(&mut self, memory: &mut [u8], size: usize) {
+        if !self.is_write_enabled() {
+            return;
+        }
+
+        let start = (self.address as usize & !(size - 1)) % memory.len();
+        let end = (start + size).min(memory.len());
+        memory[start..end].fill(0xff);
+
+        self.erases += 1;
+        self.status[0] &= !STATUS1_WEL;
+    }
+
+    fn write_status(&mut self, command: u8, position: usize, byte: u8) {
+        if !self.is_write_enabled() && !self.write_enable_volatile {
+            return;
+        }
+
+        // the second byte of WRITE_STATUS1 goes to the next register
+        let register = match (command, position) {
+            (WRITE_STATUS1, 0) => 0,
+            (WRITE_STATUS1, 1) | (WRITE_STATUS2, 0) => 1,
+            (WRITE_STATUS3, 0) => 2,
+            _ => return,
+        };
+
+        // BUSY and WEL are not writable
+        let mask = match register {
+            0 => !(STATUS1_BUSY | STATUS1_WEL),
+            _ => 0xff,
+        };
+
+        self.status[register] = (self.status[register] & !mask) | (byte & mask);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(flash: &mut SerialFlash, memory: &mut [u8], bytes: &[u8]) -> Vec<u8> {
+        flash.select();
+        let response = bytes
+            .iter()
+            .map(|byte| flash.transfer(*byte, memory))
+            .collect();
+        flash.deselect(memory);
+        response
+    }
+
+    #[test]
+    fn test_program_and_erase() {
+        let mut memory = vec![0xff; 64 * 1024];
+        let mut flash = SerialFlash::default();
+
+        // without the write enable latch, nothing is written
+        command(
+            &mut flash,
+            &mut memory,
+            &[PAGE_PROGRAM, 0, 0x01, 0x00, 0x12],
+        );
+        assert_eq!(memory[0x100], 0xff);
+
+        command(&mut flash, &mut memory, &[WRITE_ENABLE]);
+        let status = command(&mut flash, &mut memory, &[READ_STATUS1, 0]);
+        assert_eq!(status[1], STATUS1_WEL);
+
+        // the address wraps around the page
+        command(
+            &mut flash,
+            &mut memory,
+            &[PAGE_PROGRAM, 0, 0x01, 0xff, 0x12, 0x34],
+        );
+        assert_eq!((memory[0x1ff], memory[0x100]), (0x12, 0x34));
+        assert!(!flash.is_write_enabled());
+
+        let data = command(&mut flash, &mut memory, &[READ, 0, 0x01, 0xff, 0, 0]);
+        assert_eq!(data[4..], [0x12, 0xff]);
+
+        command(&mut flash, &mut memory, &[WRITE_ENABLE]);
+        command(&mut flash, &mut memory, &[SECTOR_ERASE, 0, 0x0f, 0xff]);
+        assert_eq!((memory[0x1ff], memory[0x100]), (0xff, 0xff));
+        assert_eq!(flash.erases(), 1);
+    }
+
+    #[test]
+    fn test_identification() {
+        let mut memory = vec![0xff; 4 * 1024 * 1024];
+        let mut flash = SerialFlash::default();
+
+        let id = command(&mut flash, &mut memory, &[JEDEC_ID, 0, 0, 0]);
+        assert_eq!(id[1..], [MANUFACTURER_WINBOND, MEMORY_TYPE, 0x16]);
+
+        // nothing answers but the release while powered down
+        command(&mut flash, &mut memory, &[POWER_DOWN]);
+        let id = command(&mut flash, &mut memory, &[JEDEC_ID, 0]);
+        assert_eq!(id[1], FLOATING);
+
+        let id = command(&mut flash, &mut memory, &[RELEASE_POWER_DOWN, 0, 0, 0, 0]);
+        assert_eq!(id[4], DEVICE_ID);
+    }
+}
@@ -2,58 +2,130 @@
  * @file peripherals/reset.rs
  * @author Nguyen Le Duy
  * @date 06/05/2025
- * @brief Reset peripheral implementation
- * @todo actually implement the Reset peripheral, this is just a hotfix to get the simulator
- * running
+ * @brief Resets of the peripherals, a block held in reset faults on the bus
  */
 use super::*;
 
-pub const FRCE_ON: u16 = 0x0; // Force block out of reset (i.e. power it on)
-pub const FRCE_OFF: u16 = 0x4; // Force into reset (i.e. power it off)
-pub const WDSEL: u16 = 0x8; // Set to 1 if the Watchdog should reset this
-pub const DONE: u16 = 0xC; // Is the subsystem ready?
+pub const RESET: u16 = 0x0; // Reset control, 1 holds the block in reset
+pub const WDSEL: u16 = 0x4; // Set to 1 if the Watchdog should reset this
+pub const RESET_DONE: u16 = 0x8; // The block is out of reset and ready
 
+pub const ADC: u32 = 1 << 0;
+pub const BUSCTRL: u32 = 1 << 1;
+pub const DMA: u32 = 1 << 2;
+pub const HSTX: u32 = 1 << 3;
+pub const I2C0: u32 = 1 << 4;
+pub const I2C1: u32 = 1 << 5;
+pub const IO_BANK0: u32 = 1 << 6;
+pub const IO_QSPI: u32 = 1 << 7;
+pub const JTAG: u32 = 1 << 8;
+pub const PADS_BANK0: u32 = 1 << 9;
+pub const PADS_QSPI: u32 = 1 << 10;
+pub const PIO0: u32 = 1 << 11;
+pub const PIO1: u32 = 1 << 12;
+pub const PIO2: u32 = 1 << 13;
+pub const PLL_SYS: u32 = 1 << 14;
+pub const PLL_USB: u32 = 1 << 15;
+pub const PWM: u32 = 1 << 16;
+pub const SHA256: u32 = 1 << 17;
+pub const SPI0: u32 = 1 << 18;
+pub const SPI1: u32 = 1 << 19;
+pub const SYSCFG: u32 = 1 << 20;
+pub const SYSINFO: u32 = 1 << 21;
+pub const TBMAN: u32 = 1 << 22;
+pub const TIMER0: u32 = 1 << 23;
+pub const TIMER1: u32 = 1 << 24;
+pub const TRNG: u32 = 1 << 25;
+pub const UART0: u32 = 1 << 26;
+pub const UART1: u32 = 1 << 27;
+pub const USBCTRL: u32 = 1 << 28;
+
+const MASK: u32 = 0x1fff_ffff;
+
+/// Reset bit of the block mapped at `address`, for the blocks the RESETS controls
+pub fn block_of(address: u32) -> Option<u32> {
+    let bit = match address & 0xFFFF_C000 {
+        0x4000_0000 => SYSINFO,
+        0x4000_8000 => SYSCFG,
+        0x4002_8000 => IO_BANK0,
+        0x4003_0000 => IO_QSPI,
+        0x4003_8000 => PADS_BANK0,
+        0x4004_0000 => PADS_QSPI,
+        0x4005_0000 => PLL_SYS,
+        0x4005_8000 => PLL_USB,
+        0x4006_8000 => BUSCTRL,
+        0x4007_0000 => UART0,
+        0x4007_8000 => UART1,
+        0x4008_0000 => SPI0,
+        0x4008_8000 => SPI1,
+        0x4009_0000 => I2C0,
+        0x4009_8000 => I2C1,
+        0x400A_0000 => ADC,
+        0x400A_8000 => PWM,
+        0x400B_0000 => TIMER0,
+        0x400B_8000 => TIMER1,
+        0x400C_0000 | 0x5060_0000 => HSTX,
+        0x400F_0000 => TRNG,
+        0x400F_8000 => SHA256,
+        0x4016_0000 => TBMAN,
+        0x5000_0000 => DMA,
+        0x5010_0000 | 0x5011_0000 => USBCTRL,
+        0x5020_0000 => PIO0,
+        0x5030_0000 => PIO1,
+        0x5040_0000 => PIO2,
+        _ => return None,
+    };
+
+    Some(bit)
+}
+
+/// The blocks come out of reset as soon as they are released, RESET_DONE follows RESET.
+/// Unlike the chip, which boots with everything in reset until the bootrom and the
+/// runtime release them, they start released so the bare programs can use them directly.
+/// A block held in reset keeps its state, only its registers are out of reach.
+#[derive(Default)]
 pub struct Reset {
-    frce_on: u32,
-    frce_off: u32,
-    wdsel: u32,
+    pub reset: u32,
+    pub wdsel: u32,
 }
 
-impl Default for Reset {
-    fn default() -> Self {
-        Self {
-            frce_on: 0,
-            frce_off: 0,
-            wdsel: 0,
-        }
+impl Reset {
+    /// Whether the block of this reset bit is held in reset
+    pub fn is_held(&self, block: u32) -> bool {
+        self.reset & block != 0
     }
 }
 
 impl Peripheral for Reset {
     fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
         let value = match address {
-            FRCE_ON => self.frce_on,
-            FRCE_OFF => self.frce_off,
-            WDSEL => {
-                if self.wdsel == 0 {
-                    0x1fff_ffff // In our simulator, this is always ready
-                } else {
-                    self.wdsel
-                }
-            }
-            DONE => {
-                if self.frce_on == 0 {
-                    0x1fff_ffff // In our simulator, this is always ready
-                } else {
-                    0
-                }
-            }
+            RESET => self.reset,
+            WDSEL => self.wdsel,
+            RESET_DONE => !self.reset & MASK,
             _ => return Err(PeripheralError::OutOfBounds),
         };
 
         Ok(value)
     }
 
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
     fn write_raw(
         &mut self,
         address: u16,
@@ -61,13 +133,44 @@ impl Peripheral for Reset {
         _ctx: &PeripheralAccessContext,
     ) -> PeripheralResult<()> {
         match address {
-            FRCE_ON => self.frce_on = value & 0x1fff_ffff,
-            FRCE_OFF => self.frce_off = value & 0x1fff_ffff,
-            WDSEL => self.wdsel = value & 0x1fff_ffff,
-            DONE => { /* Read only */ }
+            RESET => self.reset = value & MASK,
+            WDSEL => self.wdsel = value & MASK,
+            RESET_DONE => { /* Read only */ }
             _ => return Err(PeripheralError::OutOfBounds),
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: u16 = 0x2000;
+    const CLEAR: u16 = 0x3000;
+
+    #[test]
+    fn test_reset_done() {
+        let mut resets = Reset::default();
+        let ctx = PeripheralAccessContext::default();
+
+        assert_eq!(resets.read(RESET_DONE, &ctx), Ok(MASK));
+
+        resets.write(SET | RESET, UART0 | PIO1, &ctx).unwrap();
+        assert!(resets.is_held(UART0));
+        assert_eq!(resets.read(RESET_DONE, &ctx), Ok(MASK & !(UART0 | PIO1)));
+
+        resets.write(CLEAR | RESET, UART0, &ctx).unwrap();
+        assert!(!resets.is_held(UART0));
+        assert_eq!(resets.read(RESET, &ctx), Ok(PIO1));
+    }
+
+    #[test]
+    fn test_block_of() {
+        assert_eq!(block_of(0x4007_0018), Some(UART0));
+        assert_eq!(block_of(0x4007_2018), Some(UART0));
+        assert_eq!(block_of(0x5030_00d4), Some(PIO1));
+        assert_eq!(block_of(0x4002_0000), None);
+    }
+}
@@ -0,0 +1,315 @@
+/**
+ * @file peripherals/rosc.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Ring oscillator, its frequency follows the settings and the temperature of the die
+ */
+use super::*;
+use crate::environment::Environment;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub const CTRL: u16 = 0x00; // Ring Oscillator control
+pub const FREQA: u16 = 0x04; // Drive strengths of the stages 0 to 3
+pub const FREQB: u16 = 0x08; // Drive strengths of the stages 4 to 7
+pub const RANDOM: u16 = 0x0c; // Seed of the LFSR randomising the drive strengths
+pub const DORMANT: u16 = 0x10; // Ring Oscillator pause control
+pub const DIV: u16 = 0x14; // Controls the output divider
+pub const PHASE: u16 = 0x18; // Controls the phase shifted output
+pub const STATUS: u16 = 0x1c; // Ring Oscillator Status
+pub const RANDOMBIT: u16 = 0x20; // A random bit sampled from the oscillator
+pub const COUNT: u16 = 0x24; // A down counter running at the ROSC frequency which counts to zero and stops
+
+const CTRL_ENABLE: u32 = 0xfab;
+const CTRL_DISABLE: u32 = 0xd1e;
+
+const FREQ_RANGE_LOW: u32 = 0xfa4;
+const FREQ_RANGE_MEDIUM: u32 = 0xfa5;
+const FREQ_RANGE_HIGH: u32 = 0xfa7;
+const FREQ_RANGE_TOOHIGH: u32 = 0xfa6;
+/// The reset value of FREQ_RANGE, it works like LOW
+const FREQ_RANGE_RESET: u32 = 0xaa0;
+
+const FREQ_PASSWORD: u32 = 0x9696;
+const DIV_PASSWORD: u32 = 0xaa00;
+const PHASE_PASSWORD: u32 = 0xaa;
+
+const DORMANT_VAL: u32 = 0x636f6d61;
+const WAKE: u32 = 0x77616b65;
+
+const STATUS_STABLE: u32 = 1 << 31;
+const STATUS_BADWRITE: u32 = 1 << 24;
+const STATUS_DIV_RUNNING: u32 = 1 << 16;
+const STATUS_ENABLED: u32 = 1 << 12;
+
+/// Divider of the reset value of DIV, what the nominal frequency of the environment is
+/// measured with
+const RESET_DIV: u32 = 8;
+
+/// Relative increase of the frequency for each step of drive strength of every stage
+const DRIVE_STRENGTH_STEP: f64 = 0.02;
+
+pub struct Rosc {
+    pub ctrl: u32,
+    pub freqa: u32,
+    pub freqb: u32,
+    pub random: u32,
+    pub div: u32,
+    pub phase: u32,
+    bad_write: bool,
+    /// Value written to COUNT and the tick it was written at
+    count: (u16, u64),
+}
+
+impl Default for Rosc {
+    fn default() -> Self {
+        Self {
+            ctrl: (CTRL_ENABLE << 12) | FREQ_RANGE_RESET,
+            freqa: 0,
+            freqb: 0,
+            random: 0x3f04_b16d,
+            div: DIV_PASSWORD | RESET_DIV,
+            phase: 0x0008,
+            bad_write: false,
+            count: (0, 0),
+        }
+    }
+}
+
+impl Rosc {
+    pub fn is_enabled(&self) -> bool {
+        (self.ctrl >> 12) & 0xfff != CTRL_DISABLE
+    }
+
+    /// Number of inverters in the ring, the fewer the faster
+    pub fn stages(&self) -> u32 {
+        match self.ctrl & 0xfff {
+            FREQ_RANGE_MEDIUM => 6,
+            FREQ_RANGE_HIGH => 4,
+            FREQ_RANGE_TOOHIGH => 2,
+            _ => 8,
+        }
+    }
+
+    pub fn divider(&self) -> u32 {
+        match self.div & 0xff {
+            0 => 128,
+            div => div.min(128),
+        }
+    }
+
+    /// Average drive strength of the stages in the ring, from 0 to 3 as each one has up to
+    /// 3 drivers
+    fn drive_strength(&self) -> f64 {
+        let strengths = ((self.freqb as u64) << 16) | (self.freqa as u64 & 0xffff);
+        let stages = self.stages();
+
+        // the ring is cut short from the last stages
+        let total: u32 = (0..stages)
+            .map(|stage| ((strengths >> (stage * 4)) & 0b111).count_ones())
+            .sum();
+
+        total as f64 / stages as f64
+    }
+
+    /// Output frequency in Hz, 0 when the oscillator is stopped
+    pub fn frequency(&self, environment: &Environment, clock: &Clock) -> f64 {
+        if !self.is_enabled() {
+            return 0.0;
+        }
+
+        let ring = 8.0 / self.stages() as f64;
+        let drive = 1.0 + self.drive_strength() * DRIVE_STRENGTH_STEP;
+        let divider = RESET_DIV as f64 / self.divider() as f64;
+
+        environment.rosc_frequency(clock) * ring * drive * divider
+    }
+
+    fn count(&self, ctx: &PeripheralAccessContext) -> u32 {
+        let (value, written_at) = self.count;
+        let elapsed = ctx.clock.ticks.borrow().saturating_sub(written_at);
+
+        let frequency = self.frequency(&ctx.environment.borrow(), &ctx.clock);
+        let counted = elapsed as f64 * frequency / ctx.clock.clk_sys() as f64;
+
+        (value as f64 - counted).max(0.0) as u32
+    }
+
+    fn write_freq(&mut self, value: u32) -> u32 {
+        match value >> 16 {
+            FREQ_PASSWORD => value & 0xffff,
+            _ => {
+                // any other password resets the drive strengths
+                self.bad_write = true;
+                0
+            }
+        }
+    }
+}
+
+impl Peripheral for Rc<RefCell<Rosc>> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let rosc = self.borrow();
+
+        let value = match address {
+            CTRL => rosc.ctrl,
+            FREQA => rosc.freqa,
+            FREQB => rosc.freqb,
+            RANDOM => rosc.random,
+            DORMANT => WAKE, // wakes up straight away in our simulator
+            DIV => rosc.div,
+            PHASE => rosc.phase,
+            STATUS => {
+                let mut status = STATUS_DIV_RUNNING;
+
+                if rosc.is_enabled() {
+                    status |= STATUS_STABLE | STATUS_ENABLED;
+                }
+
+                if rosc.bad_write {
+                    status |= STATUS_BADWRITE;
+                }
+
+                status
+            }
+            RANDOMBIT => getrandom::u32().unwrap_or_default() & 1,
+            COUNT => rosc.count(ctx),
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut rosc = self.borrow_mut();
+
+        match address {
+            CTRL => {
+                let enable = (value >> 12) & 0xfff;
+                let range = value & 0xfff;
+
+                let valid_enable = matches!(enable, CTRL_ENABLE | CTRL_DISABLE);
+                let valid_range = matches!(
+                    range,
+                    FREQ_RANGE_LOW | FREQ_RANGE_MEDIUM | FREQ_RANGE_HIGH | FREQ_RANGE_TOOHIGH
+                );
+
+                if !valid_enable || !valid_range {
+                    rosc.bad_write = true;
+                }
+
+                rosc.ctrl = value & 0x00ff_ffff;
+            }
+            FREQA => rosc.freqa = rosc.write_freq(value),
+            FREQB => rosc.freqb = rosc.write_freq(value),
+            RANDOM => rosc.random = value,
+            DORMANT if value == DORMANT_VAL => {
+                log::warn!("ROSC: dormant mode is not supported, the oscillator keeps running");
+            }
+            DORMANT if value != WAKE => rosc.bad_write = true,
+            DORMANT => {}
+            DIV => {
+                // any other password divides by 128
+                if value & 0xff00 != DIV_PASSWORD {
+                    rosc.bad_write = true;
+                    rosc.div = DIV_PASSWORD;
+                } else {
+                    rosc.div = value & 0xffff;
+                }
+            }
+            PHASE => {
+                if (value >> 4) & 0xff != PHASE_PASSWORD {
+                    rosc.bad_write = true;
+                }
+
+                rosc.phase = value & 0xfff;
+            }
+            STATUS if value & STATUS_BADWRITE != 0 => rosc.bad_write = false,
+            STATUS => {}
+            RANDOMBIT => { /* read-only */ }
+            COUNT => rosc.count = (value as u16, *ctx.clock.ticks.borrow()),
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Rc<RefCell<Rosc>>, PeripheralAccessContext) {
+        (Default::default(), Default::default())
+    }
+
+    fn frequency(rosc: &Rc<RefCell<Rosc>>, ctx: &PeripheralAccessContext) -> f64 {
+        rosc.borrow()
+            .frequency(&ctx.environment.borrow(), &ctx.clock)
+    }
+
+    #[test]
+    fn test_frequency_range() {
+        let (mut rosc, ctx) = setup();
+        let nominal = frequency(&rosc, &ctx);
+        assert_eq!(nominal, ctx.environment.borrow().rosc_frequency(&ctx.clock));
+
+        rosc.write(CTRL, (CTRL_ENABLE << 12) | FREQ_RANGE_HIGH, &ctx)
+            .unwrap();
+        assert_eq!(frequency(&rosc, &ctx), nominal * 2.0);
+
+        // half the divider, twice the frequency
+        rosc.write(DIV, DIV_PASSWORD | 4, &ctx).unwrap();
+        assert_eq!(frequency(&rosc, &ctx), nominal * 4.0);
+
+        // stronger drivers make it faster
+        rosc.write(FREQA, (FREQ_PASSWORD << 16) | 0x7777, &ctx)
+            .unwrap();
+        assert!(frequency(&rosc, &ctx) > nominal * 4.0);
+
+        rosc.write(CTRL, (CTRL_DISABLE << 12) | FREQ_RANGE_HIGH, &ctx)
+            .unwrap();
+        assert_eq!(frequency(&rosc, &ctx), 0.0);
+        assert_eq!(rosc.read(STATUS, &ctx).unwrap() & STATUS_ENABLED, 0);
+    }
+
+    #[test]
+    fn test_bad_write() {
+        let (mut rosc, ctx) = setup();
+        assert_eq!(rosc.read(STATUS, &ctx).unwrap() & STATUS_BADWRITE, 0);
+
+        // wrong password, the drive strengths are reset
+        rosc.write(FREQA, 0x1234_7777, &ctx).unwrap();
+        assert_eq!(rosc.read(FREQA, &ctx), Ok(0));
+        assert_ne!(rosc.read(STATUS, &ctx).unwrap() & STATUS_BADWRITE, 0);
+
+        rosc.write(STATUS, STATUS_BADWRITE, &ctx).unwrap();
+        assert_eq!(rosc.read(STATUS, &ctx).unwrap() & STATUS_BADWRITE, 0);
+    }
+
+    #[test]
+    fn test_count() {
+        let (mut rosc, ctx) = setup();
+        rosc.write(COUNT, 1000, &ctx).unwrap();
+        assert_eq!(rosc.read(COUNT, &ctx), Ok(1000));
+
+        // 11MHz against 150MHz of clk_sys, about 11 counts every 150 ticks
+        for _ in 0..150 {
+            ctx.clock.tick();
+        }
+
+        let count = rosc.read(COUNT, &ctx).unwrap();
+        assert!((988..=990).contains(&count), "{count}");
+
+        for _ in 0..150 * 100 {
+            ctx.clock.tick();
+        }
+
+        assert_eq!(rosc.read(COUNT, &ctx), Ok(0));
+    }
+}
@@ -35,7 +35,9 @@ pub struct Sio {
     pub tmds: [TmdsEncoder; 2],
 
     gpio_value: u32,
-    gpio_output_enable: u32
+    gpio_output_enable: u32,
+    gpio_hi_value: u32,
+    gpio_hi_output_enable: u32,
 }
 
 impl Sio {
@@ -49,6 +51,8 @@ impl Sio {
             tmds: [TmdsEncoder::default(), TmdsEncoder::default()],
             gpio_value: 0,
             gpio_output_enable: 0,
+            gpio_hi_value: 0,
+            gpio_hi_output_enable: 0,
         }
     }
 
@@ -63,12 +67,24 @@ impl Sio {
 
         gpio.borrow_mut().update_sio(self.gpio_output_enable, self.gpio_value);
     }
+
+    fn update_gpio_hi(&self, gpio: Rc<RefCell<GpioController>>, old_gpio_hi_value: u32, old_gpio_hi_output_enable: u32) {
+        let updated_pins = (self.gpio_hi_value ^ old_gpio_hi_value)
+            | (self.gpio_hi_output_enable ^ old_gpio_hi_output_enable);
+
+        if updated_pins == 0 {
+            return
+        }
+
+        gpio.borrow_mut().update_sio_hi(self.gpio_hi_output_enable, self.gpio_hi_value);
+    }
 }
 
 
 pub const CPUID: u16 = 0x000; // Processor core identifier
 pub const GPIO_IN: u16 = 0x004; // Input value for GPIO0..31
 pub const GPIO_HILIN: u16 = 0x008; // Input value on GPIO32..47, QSPI IOs and USB pins
+pub const GPIO_HI_MASK: u32 = 0xFF00_FFFF; // GPIO32..47, USB DP/DM and the QSPI IOs
 pub const GPIO_OUT: u16 = 0x010; // GPIO0..31 output value
 pub const GPIO_HILOUT: u16 = 0x014; // Output value for GPIO32..47, QSPI IOs and USB pins
 pub const GPIO_OUT_SET: u16 = 0x018; // GPIO0..31 output value set
@@ -129,6 +145,10 @@ pub const DOORBELL_IN_SET: u16 = 0x188; // Write 1s to trigger doorbell interrup
 pub const DOORBELL_IN_CLR: u16 = 0x18C; // Check and acknowledge doorbells posted to this core
 pub const PERI_NONSEC: u16 = 0x190; // Detach certain core-local peripherals from Secure SIO
 pub const RISCV_SOFTIRQ: u16 = 0x1A0; // Control the assertion of the standard software interrupt (MIP.MSIP) on the RISC-V cores
+pub const RISCV_SOFTIRQ_CORE0_SET: u32 = 1 << 0;
+pub const RISCV_SOFTIRQ_CORE1_SET: u32 = 1 << 1;
+pub const RISCV_SOFTIRQ_CORE0_CLR: u32 = 1 << 8;
+pub const RISCV_SOFTIRQ_CORE1_CLR: u32 = 1 << 9;
 pub const MTIME_CTRL: u16 = 0x1A4; // Control register for the RISC-V 64-bit Machine-mode timer
 pub const MTIME: u16 = 0x1B0; // Read/write access to the high half of RISC-V Machine-mode timer
 pub const MTIMEH: u16 = 0x1B4; // Read/write access to the high half of RISC-V Machine-mode timer
@@ -158,19 +178,12 @@ impl Peripheral for Sio {
                 _ => return Err(PeripheralError::OutOfBounds),
             }
 
-            GPIO_IN => {
-                let gpio = ctx.gpio.as_ref().borrow();
-                gpio.pins
-                    .iter()
-                    .map(|pin| pin.input_value() as u32)
-                    .rev()
-                    .fold(0, |acc, value| (acc << 1) | value)
-            }
-            GPIO_HILIN => 0, // TODO QSPI USB GPIO32..47
+            GPIO_IN => ctx.gpio.as_ref().borrow().sio_input(),
+            GPIO_HILIN => ctx.gpio.as_ref().borrow().sio_hi_input(),
             GPIO_OUT => self.gpio_value,
-            GPIO_HILOUT => 0, // TODO QSPI USB GPIO32..47
+            GPIO_HILOUT => self.gpio_hi_value,
             GPIO_OE => self.gpio_output_enable,
-            GPIO_HI_OE => 0, // TODO QSPI USB GPIO32..47
+            GPIO_HI_OE => self.gpio_hi_output_enable,
 
             FIFO_ST => self.mailboxes.borrow_mut().state(ctx.requestor),
             FIFO_RD => self.mailboxes.borrow_mut().read(ctx.requestor),
@@ -238,15 +251,22 @@ impl Peripheral for Sio {
             INTERP1_ACCUM1_ADD => interpolator1.sm_result[1],
             // INTERP1_BASE_1AND0
 
+            RISCV_SOFTIRQ => {
+                let interrupts = ctx.interrupts.borrow();
+                // Both the SET and CLR fields read back the state of the flag
+                let flags = (interrupts.machine_software(0) as u32)
+                    | (interrupts.machine_software(1) as u32) << 1;
+                flags | flags << 8
+            }
+
             MTIME_CTRL => timer.ctrl as u32,
             MTIME => timer.counter as u32,
             MTIMEH => (timer.counter >> 32) as u32,
-            MTIMECMP => timer.cmp as u32,
-            MTIMECMPH => (timer.cmp >> 32) as u32,
+            MTIMECMP => timer.cmp[ctx.requestor as usize] as u32,
+            MTIMECMPH => (timer.cmp[ctx.requestor as usize] >> 32) as u32,
 
 
             PERI_NONSEC  // TODO
-            | RISCV_SOFTIRQ
             | TMDS_CTRL
             | TMDS_WDATA
             | TMDS_PEEK_SINGLE
@@ -288,55 +308,57 @@ impl Peripheral for Sio {
 
         let old_gpio_value = self.gpio_value;
         let old_gpio_output_enable = self.gpio_output_enable;
+        let old_gpio_hi_value = self.gpio_hi_value;
+        let old_gpio_hi_output_enable = self.gpio_hi_output_enable;
 
         match address {
             GPIO_OUT => {
                 self.gpio_value = value;
             }
             GPIO_HILOUT => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_value = value & GPIO_HI_MASK;
             }
             GPIO_OUT_SET => {
                 self.gpio_value |= value;
             }
             GPIO_HILOUT_SET => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_value |= value & GPIO_HI_MASK;
             }
             GPIO_OUT_CLR => {
                 self.gpio_value &= !value;
             }
             GPIO_HILOUT_CLR => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_value &= !value & GPIO_HI_MASK;
             }
             GPIO_OUT_XOR => {
                 self.gpio_value ^= value;
             }
             GPIO_HLOUT_XOR => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_value ^= value & GPIO_HI_MASK;
             }
             GPIO_OE => {
                 self.gpio_output_enable = value;
             }
             GPIO_HI_OE => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_output_enable = value & GPIO_HI_MASK;
             }
             GPIO_OE_SET => {
                 self.gpio_output_enable |= value;
             }
             GPIO_HI_OE_SET => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_output_enable |= value & GPIO_HI_MASK;
             }
             GPIO_OE_CLR => {
                 self.gpio_output_enable &= !value;
             }
             GPIO_HI_OE_CLR => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_output_enable &= !value & GPIO_HI_MASK;
             }
             GPIO_OE_XOR => {
                 self.gpio_output_enable ^= value;
             }
             GPIO_HI_OE_XOR => {
-                // TODO QSPI USB GPIO32..47
+                self.gpio_hi_output_enable ^= value & GPIO_HI_MASK;
             }
             FIFO_ST => {
                 if value & (1 << 2) != 0 {
@@ -433,9 +455,24 @@ impl Peripheral for Sio {
             INTERP1_BASE_1AND0 => {
                 interpolator1.set_base01(value);
             }
+            RISCV_SOFTIRQ => {
+                let mut interrupts = ctx.interrupts.borrow_mut();
+
+                for (core, set, clr) in [
+                    (0, RISCV_SOFTIRQ_CORE0_SET, RISCV_SOFTIRQ_CORE0_CLR),
+                    (1, RISCV_SOFTIRQ_CORE1_SET, RISCV_SOFTIRQ_CORE1_CLR),
+                ] {
+                    // Setting wins when both are written at once
+                    if value & set != 0 {
+                        interrupts.set_machine_software(core, true);
+                    } else if value & clr != 0 {
+                        interrupts.set_machine_software(core, false);
+                    }
+                }
+            }
             MTIME_CTRL => {
                 drop(timer);
-                update_timer_ctrl(self.timer.clone(), value as u8, ctx);
+                update_timer_ctrl(self.timer.clone(), (value & 0xF) as u8, ctx);
             }
             MTIME => {
                 timer.counter = (timer.counter & 0xFFFF_FFFF_0000_0000) | value as u64;
@@ -446,16 +483,17 @@ impl Peripheral for Sio {
                 timer.update_interrupt(ctx.interrupts.clone());
             }
             MTIMECMP => {
-                timer.cmp = (timer.cmp & 0xFFFF_FFFF_0000_0000) | value as u64;
+                let cmp = &mut timer.cmp[ctx.requestor as usize];
+                *cmp = (*cmp & 0xFFFF_FFFF_0000_0000) | value as u64;
                 timer.update_interrupt(ctx.interrupts.clone());
             }
             MTIMECMPH => {
-                timer.cmp = (timer.cmp & 0x0000_0000_FFFF_FFFF) | ((value as u64) << 32);
+                let cmp = &mut timer.cmp[ctx.requestor as usize];
+                *cmp = (*cmp & 0x0000_0000_FFFF_FFFF) | ((value as u64) << 32);
                 timer.update_interrupt(ctx.interrupts.clone());
             }
 
             PERI_NONSEC // TODO
-            | RISCV_SOFTIRQ
             | TMDS_CTRL
             | TMDS_WDATA
             | TMDS_PEEK_SINGLE
@@ -487,6 +525,7 @@ impl Peripheral for Sio {
         }
 
         self.update_gpio(Rc::clone(&ctx.gpio), old_gpio_value, old_gpio_output_enable);
+        self.update_gpio_hi(Rc::clone(&ctx.gpio), old_gpio_hi_value, old_gpio_hi_output_enable);
 
         Ok(())
     }
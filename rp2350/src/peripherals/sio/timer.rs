@@ -12,10 +12,14 @@ use crate::interrupts::Interrupts;
 use crate::peripherals::PeripheralAccessContext;
 use crate::utils::extract_bit;
 
+pub const CTRL_EN: u8 = 1 << 0;
+pub const CTRL_FULLSPEED: u8 = 1 << 1;
+
 pub struct RiscVPlatformTimer {
     pub ctrl: u8,
     pub counter: u64,
-    pub cmp: u64,
+    /// MTIMECMP is core local, each core compares the shared counter with its own value
+    pub cmp: [u64; 2],
 }
 
 impl Default for RiscVPlatformTimer {
@@ -23,7 +27,7 @@ impl Default for RiscVPlatformTimer {
         Self {
             ctrl: 0b1101,
             counter: 0,
-            cmp: 0xFFFF_FFFF_FFFF_FFFF,
+            cmp: [0xFFFF_FFFF_FFFF_FFFF; 2],
         }
     }
 }
@@ -37,11 +41,23 @@ impl RiscVPlatformTimer {
         }
     }
 
+    pub fn is_enabled(&self) -> bool {
+        self.ctrl & CTRL_EN != 0
+    }
+
+    /// The comparator of a core fires as long as the counter is at or past its value
+    pub fn is_pending(&self, core: u8) -> bool {
+        self.counter >= self.cmp[core as usize & 1]
+    }
+
     pub fn update_interrupt(&self, interrupts: Rc<RefCell<Interrupts>>) {
-        let irq = self.cmp == self.counter;
-        interrupts
-            .borrow_mut()
-            .set_irq(Interrupts::SIO_IRQ_MTIMECMP, irq);
+        let mut interrupts = interrupts.borrow_mut();
+
+        for core in 0..2 {
+            let irq = self.is_pending(core);
+            interrupts.set_core_local_irq(core, Interrupts::SIO_IRQ_MTIMECMP, irq);
+            interrupts.set_machine_timer(core, irq);
+        }
     }
 }
 
@@ -55,17 +71,15 @@ pub fn update_timer_ctrl(
     timer.ctrl = ctrl;
     drop(timer);
 
-    if extract_bit(ctrl, 0) == 0 {
+    if ctrl & CTRL_EN == 0 {
         ctx.clock.cancel(EventType::RiscVTimer);
-    } else {
-        start_timer(timer_ref.clone(), ctx.clock.clone(), ctx.interrupts.clone());
-    }
-
-    // Reconfigurated speed => reschedule timer
-    // 1 = full speed
-    // 0 = 1 per microsecond
-    if extract_bit(ctrl, 1) != extract_bit(last_ctrl, 1) {
+    } else if (ctrl ^ last_ctrl) & CTRL_FULLSPEED != 0 {
+        // Reconfigurated speed => reschedule timer
+        // 1 = full speed
+        // 0 = 1 per microsecond
         reschedule_timer(timer_ref, ctx.clock.clone(), ctx.interrupts.clone());
+    } else {
+        start_timer(timer_ref, ctx.clock.clone(), ctx.interrupts.clone());
     }
 }
 
@@ -83,7 +97,7 @@ pub fn start_timer(
     clock: Rc<Clock>,
     interrupt: Rc<RefCell<Interrupts>>,
 ) {
-    if clock.is_scheduled(EventType::RiscVTimer) {
+    if clock.is_scheduled(EventType::RiscVTimer) || !timer.borrow().is_enabled() {
         return;
     }
 
@@ -95,10 +109,85 @@ pub fn start_timer(
     clock.schedule(tick, EventType::RiscVTimer, move || {
         {
             let mut timer = timer.borrow_mut();
-            timer.counter += 1;
+            timer.counter = timer.counter.wrapping_add(1);
             timer.update_interrupt(interrupt.clone());
         }
 
         start_timer(timer, clock_ref, interrupt_ref);
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Rc<RefCell<RiscVPlatformTimer>>, Rc<Clock>, Rc<RefCell<Interrupts>>) {
+        let timer = Rc::new(RefCell::new(RiscVPlatformTimer::default()));
+        let clock = Rc::new(Clock::new());
+        let interrupts = Rc::new(RefCell::new(Interrupts::default()));
+        start_timer(timer.clone(), clock.clone(), interrupts.clone());
+        (timer, clock, interrupts)
+    }
+
+    #[test]
+    fn test_counts_microseconds() {
+        let (timer, clock, _interrupts) = setup();
+
+        for _ in 0..150 * 3 {
+            clock.tick();
+        }
+
+        assert_eq!(timer.borrow().counter, 3);
+    }
+
+    #[test]
+    fn test_per_core_comparator() {
+        let (timer, clock, interrupts) = setup();
+        timer.borrow_mut().cmp = [2, 5];
+
+        for _ in 0..150 * 2 {
+            clock.tick();
+        }
+
+        let irqs = interrupts.borrow();
+        assert!(irqs.machine_timer(0));
+        assert!(!irqs.machine_timer(1));
+        assert_eq!(irqs.pending(0) & (1 << Interrupts::SIO_IRQ_MTIMECMP), 1 << 29);
+        assert_eq!(irqs.pending(1) & (1 << Interrupts::SIO_IRQ_MTIMECMP), 0);
+        drop(irqs);
+
+        // stays asserted past the compare value until MTIMECMP is moved
+        for _ in 0..150 * 3 {
+            clock.tick();
+        }
+
+        assert!(interrupts.borrow().machine_timer(0));
+        assert!(interrupts.borrow().machine_timer(1));
+
+        timer.borrow_mut().cmp[0] = u64::MAX;
+        timer.borrow().update_interrupt(interrupts.clone());
+        assert!(!interrupts.borrow().machine_timer(0));
+    }
+
+    #[test]
+    fn test_disable() {
+        let (timer, clock, interrupts) = setup();
+        let ctx = PeripheralAccessContext {
+            clock: clock.clone(),
+            interrupts,
+            ..Default::default()
+        };
+
+        update_timer_ctrl(timer.clone(), 0, &ctx);
+        for _ in 0..150 * 2 {
+            clock.tick();
+        }
+        assert_eq!(timer.borrow().counter, 0);
+
+        update_timer_ctrl(timer.clone(), CTRL_EN | CTRL_FULLSPEED, &ctx);
+        for _ in 0..10 {
+            clock.tick();
+        }
+        assert_eq!(timer.borrow().counter, 10);
+    }
+}
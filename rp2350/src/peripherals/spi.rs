@@ -0,0 +1,342 @@
+/**
+ * @file peripherals/spi.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief PL022 SPI controller, exchanging its frames with the devices wired to the bus
+ * @todo the slave mode, the DMA requests and the timing of the frames
+ */
+use crate::gpio::{FunctionSelect, GpioController};
+use crate::interrupts::{Interrupt, Interrupts};
+use crate::utils::Fifo;
+
+use super::{Peripheral, PeripheralAccessContext, PeripheralError, PeripheralResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub const SSPCR0: u16 = 0x000; // Control register 0
+pub const SSPCR1: u16 = 0x004; // Control register 1
+pub const SSPDR: u16 = 0x008; // Data register
+pub const SSPSR: u16 = 0x00C; // Status register
+pub const SSPCPSR: u16 = 0x010; // Clock prescale register
+pub const SSPIMSC: u16 = 0x014; // Interrupt mask set or clear register
+pub const SSPRIS: u16 = 0x018; // Raw interrupt status register
+pub const SSPMIS: u16 = 0x01C; // Masked interrupt status register
+pub const SSPICR: u16 = 0x020; // Interrupt clear register
+pub const SSPDMACR: u16 = 0x024; // DMA control register
+pub const SSPPERIPHID0: u16 = 0xFE0; // Peripheral identification registers
+pub const SSPPERIPHID1: u16 = 0xFE4;
+pub const SSPPERIPHID2: u16 = 0xFE8;
+pub const SSPPERIPHID3: u16 = 0xFEC;
+pub const SSPPCELLID0: u16 = 0xFF0; // PrimeCell identification registers
+pub const SSPPCELLID1: u16 = 0xFF4;
+pub const SSPPCELLID2: u16 = 0xFF8;
+pub const SSPPCELLID3: u16 = 0xFFC;
+
+const CR1_LBM: u8 = 1 << 0;
+const CR1_SSE: u8 = 1 << 1;
+const CR1_MS: u8 = 1 << 2;
+
+const SR_TFE: u32 = 1 << 0;
+const SR_TNF: u32 = 1 << 1;
+const SR_RNE: u32 = 1 << 2;
+const SR_RFF: u32 = 1 << 3;
+
+pub const INTR_ROR: u8 = 1 << 0;
+pub const INTR_RT: u8 = 1 << 1;
+pub const INTR_RX: u8 = 1 << 2;
+pub const INTR_TX: u8 = 1 << 3;
+
+const FIFO_DEPTH: usize = 8;
+
+/// A device on an SPI bus, selected while the chip drives its chip select low. The frames
+/// are shifted as bytes, the MSB first, the ones of more than 8 bits as two bytes.
+pub trait SpiDevice {
+    /// The chip select went low
+    fn select(&mut self) {}
+
+    /// Shift a byte in, and the byte the device drives at the same time out
+    fn transfer(&mut self, data: u8) -> u8;
+
+    /// The chip select went high, it ends the command of most devices
+    fn deselect(&mut self) {}
+}
+
+pub type SpiDeviceRef = Rc<RefCell<dyn SpiDevice>>;
+
+#[derive(Default)]
+pub struct Spi<const IDX: usize> {
+    pub ctrl0: u16,
+    pub ctrl1: u8,
+    pub prescale: u8,
+    pub interrupt_mask: u8,
+    pub dma_ctrl: u8,
+    pub rx_fifo: Fifo<u16, FIFO_DEPTH>,
+    /// Frames exchanged on the bus
+    pub frames: u64,
+
+    interrupt_raw: u8,
+}
+
+impl<const IDX: usize> Spi<IDX> {
+    pub fn is_enabled(&self) -> bool {
+        self.ctrl1 & CR1_SSE != 0
+    }
+
+    pub fn is_master(&self) -> bool {
+        self.ctrl1 & CR1_MS == 0
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.ctrl1 & CR1_LBM != 0
+    }
+
+    /// Bits of a frame, from 4 to 16
+    pub fn data_bits(&self) -> u8 {
+        (self.ctrl0 & 0xF).max(3) as u8 + 1
+    }
+
+    /// 0 for the Motorola SPI, 1 for TI synchronous serial, 2 for National Microwire
+    pub fn frame_format(&self) -> u8 {
+        ((self.ctrl0 >> 4) & 0b11) as u8
+    }
+
+    pub fn clock_polarity(&self) -> bool {
+        self.ctrl0 & (1 << 6) != 0
+    }
+
+    pub fn clock_phase(&self) -> bool {
+        self.ctrl0 & (1 << 7) != 0
+    }
+
+    /// Frequency of SCK from the peripheral clock
+    pub fn baudrate(&self, clk_peri: u64) -> u64 {
+        let scr = (self.ctrl0 >> 8) as u64;
+        clk_peri / (self.prescale.max(2) as u64 * (1 + scr))
+    }
+
+    /// Raw interrupts, with the ones following the level of the FIFOs. The frames are
+    /// exchanged as soon as they are written, the TX FIFO is always empty.
+    pub fn raw_interrupt(&self) -> u8 {
+        let mut raw = self.interrupt_raw | INTR_TX;
+
+        if self.rx_fifo.len() >= FIFO_DEPTH / 2 {
+            raw |= INTR_RX;
+        }
+
+        raw
+    }
+
+    pub fn interrupt(&self) -> u8 {
+        self.raw_interrupt() & self.interrupt_mask
+    }
+
+    pub fn status(&self) -> u32 {
+        let mut status = SR_TFE | SR_TNF;
+
+        if !self.rx_fifo.is_empty() {
+            status |= SR_RNE;
+        }
+
+        if self.rx_fifo.is_full() {
+            status |= SR_RFF;
+        }
+
+        status
+    }
+
+    pub fn update_interrupt(&self, interrupts: &RefCell<Interrupts>) {
+        interrupts
+            .borrow_mut()
+            .set_irq(Self::num_interrupt(), self.interrupt() != 0);
+    }
+
+    fn num_interrupt() -> Interrupt {
+        match IDX {
+            0 => Interrupts::SPI0_IRQ,
+            1 => Interrupts::SPI1_IRQ,
+            _ => unreachable!(),
+        }
+    }
+
+    fn csn_function() -> FunctionSelect {
+        match IDX {
+            0 => FunctionSelect::SPI0_CSn,
+            1 => FunctionSelect::SPI1_CSn,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The chip select function idles high while the controller masters the bus
+    fn update_csn(&self, gpio: &mut GpioController) {
+        let driven = self.is_enabled() && self.is_master();
+        gpio.set_pin_output(Self::csn_function(), true);
+        gpio.set_pin_output_enable(Self::csn_function(), driven);
+    }
+
+    /// Shift a frame written to SSPDR out and the one the devices answer with in
+    fn exchange(&mut self, data: u16, gpio: &mut GpioController) {
+        if !self.is_enabled() || !self.is_master() {
+            return;
+        }
+
+        let mask = ((1u32 << self.data_bits()) - 1) as u16;
+        let data = data & mask;
+
+        let received = match self.is_loopback() {
+            true => data,
+            false => {
+                // the chip select function is pulsed low around each frame
+                gpio.set_pin_output(Self::csn_function(), false);
+
+                let received = match self.data_bits() > 8 {
+                    true => {
+                        let high = gpio.spi_transfer(IDX as u8, (data >> 8) as u8) as u16;
+                        (high << 8) | gpio.spi_transfer(IDX as u8, data as u8) as u16
+                    }
+                    false => gpio.spi_transfer(IDX as u8, data as u8) as u16,
+                };
+
+                gpio.set_pin_output(Self::csn_function(), true);
+                received & mask
+            }
+        };
+
+        self.frames += 1;
+
+        if self.rx_fifo.push(received).is_err() {
+            self.interrupt_raw |= INTR_ROR;
+        }
+    }
+}
+
+impl<const IDX: usize> Peripheral for Rc<RefCell<Spi<IDX>>> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let mut spi = self.borrow_mut();
+
+        let value = match address {
+            SSPCR0 => spi.ctrl0 as u32,
+            SSPCR1 => spi.ctrl1 as u32,
+            SSPDR => spi.rx_fifo.pop().unwrap_or_default() as u32,
+            SSPSR => spi.status(),
+            SSPCPSR => spi.prescale as u32,
+            SSPIMSC => spi.interrupt_mask as u32,
+            SSPRIS => spi.raw_interrupt() as u32,
+            SSPMIS => spi.interrupt() as u32,
+            SSPDMACR => spi.dma_ctrl as u32,
+            SSPPERIPHID0 => 0x22,
+            SSPPERIPHID1 => 0x10,
+            SSPPERIPHID2 => 0x34,
+            SSPPERIPHID3 => 0x00,
+            SSPPCELLID0 => 0x0D,
+            SSPPCELLID1 => 0xF0,
+            SSPPCELLID2 => 0x05,
+            SSPPCELLID3 => 0xB1,
+            SSPICR => 0,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        spi.update_interrupt(&ctx.interrupts);
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut spi = self.borrow_mut();
+
+        match address {
+            SSPCR0 => spi.ctrl0 = value as u16,
+            SSPCR1 => {
+                spi.ctrl1 = (value & 0xF) as u8;
+                spi.update_csn(&mut ctx.gpio.borrow_mut());
+            }
+            SSPDR => spi.exchange(value as u16, &mut ctx.gpio.borrow_mut()),
+            // only even values, the LSB always reads as 0
+            SSPCPSR => spi.prescale = (value & 0xFE) as u8,
+            SSPIMSC => spi.interrupt_mask = (value & 0xF) as u8,
+            SSPICR => spi.interrupt_raw &= !(value as u8 & (INTR_ROR | INTR_RT)),
+            SSPDMACR => spi.dma_ctrl = (value & 0b11) as u8,
+            SSPSR | SSPRIS | SSPMIS | SSPPERIPHID0 | SSPPERIPHID1 | SSPPERIPHID2 | SSPPERIPHID3
+            | SSPPCELLID0 | SSPPCELLID1 | SSPPCELLID2 | SSPPCELLID3 => { /* Read-only registers */ }
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        spi.update_interrupt(&ctx.interrupts);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{Max7219, Max7219Pins};
+    use crate::error::ConfigError;
+
+    const SIO: u32 = 5;
+    const CS: u8 = 17;
+
+    fn enable(spi: &mut Rc<RefCell<Spi<0>>>, ctrl1: u32, ctx: &PeripheralAccessContext) {
+        // 16 bit frames, SCK at clk_peri / 8
+        spi.write(SSPCR0, 0xF, ctx).unwrap();
+        spi.write(SSPCPSR, 8, ctx).unwrap();
+        spi.write(SSPCR1, ctrl1 | CR1_SSE as u32, ctx).unwrap();
+    }
+
+    #[test]
+    fn test_loopback() {
+        let ctx = PeripheralAccessContext::default();
+        let mut spi = Rc::new(RefCell::new(Spi::<0>::default()));
+        enable(&mut spi, CR1_LBM as u32, &ctx);
+
+        for frame in 0..9 {
+            spi.write(SSPDR, 0x1234 + frame, &ctx).unwrap();
+        }
+
+        // the ninth frame overran the RX FIFO
+        assert_eq!(
+            spi.read(SSPRIS, &ctx),
+            Ok((INTR_ROR | INTR_RX | INTR_TX) as u32)
+        );
+        assert_eq!(spi.read(SSPSR, &ctx), Ok(SR_TFE | SR_TNF | SR_RNE | SR_RFF));
+        assert_eq!(spi.read(SSPDR, &ctx), Ok(0x1234));
+        spi.write(SSPICR, INTR_ROR as u32, &ctx).unwrap();
+        assert_eq!(spi.read(SSPRIS, &ctx).unwrap() & INTR_ROR as u32, 0);
+    }
+
+    #[test]
+    fn test_chip_select() {
+        let ctx = PeripheralAccessContext::default();
+        let mut spi = Rc::new(RefCell::new(Spi::<0>::default()));
+        let matrix = Rc::new(RefCell::new(Max7219::new(Max7219Pins::default(), 1)));
+
+        {
+            let mut gpio = ctx.gpio.borrow_mut();
+            gpio.attach_spi_device(0, CS, matrix.clone()).unwrap();
+            assert_eq!(
+                gpio.attach_spi_device(0, CS, matrix.clone()),
+                Err(ConfigError::SpiChipSelectInUse(CS))
+            );
+            gpio.pins[CS as usize].ctrl = SIO;
+            gpio.update_sio(1 << CS, 1 << CS);
+        }
+
+        enable(&mut spi, 0, &ctx);
+
+        // not selected while CS is high
+        spi.write(SSPDR, 0x0C01, &ctx).unwrap();
+        assert_eq!(spi.read(SSPDR, &ctx), Ok(0xFFFF));
+
+        for word in [0x0C01, 0x0B07, 0x0181] {
+            ctx.gpio.borrow_mut().update_sio(1 << CS, 0);
+            spi.write(SSPDR, word, &ctx).unwrap();
+            ctx.gpio.borrow_mut().update_sio(1 << CS, 1 << CS);
+        }
+
+        assert!(matrix.borrow().is_lit(0, 0, 0));
+        assert!(matrix.borrow().is_lit(0, 0, 7));
+        assert_eq!(spi.borrow().frames, 4);
+    }
+}
@@ -0,0 +1,131 @@
+/**
+ * @file peripherals/sysinfo.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief SYSINFO peripheral, the identity of the chip and the platform it runs on
+ */
+use super::*;
+
+pub const CHIP_ID: u16 = 0x00;
+pub const PACKAGE_SEL: u16 = 0x04;
+pub const PLATFORM: u16 = 0x08;
+pub const GITREF_RP2350: u16 = 0x14;
+
+const MANUFACTURER: u32 = 0x927;
+const PART: u32 = 0x0004;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Package {
+    /// RP2350A, the one of the Pico 2
+    #[default]
+    Qfn60,
+    /// RP2350B
+    Qfn80,
+}
+
+/// What the chip is, as reported by PLATFORM
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Platform {
+    #[default]
+    Asic,
+    Fpga,
+    /// RTL simulation, lets the firmware tell it is not running on silicon
+    HdlSim,
+    BatchSim,
+    GateSim,
+}
+
+impl Platform {
+    fn bits(self) -> u32 {
+        match self {
+            Self::Fpga => 1 << 0,
+            Self::Asic => 1 << 1,
+            Self::HdlSim => 1 << 2,
+            Self::BatchSim => 1 << 3,
+            Self::GateSim => 1 << 4,
+        }
+    }
+}
+
+/// Chip metadata read by the firmware, kept over resets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SysInfo {
+    /// Silicon revision, 2 for A2
+    pub revision: u8,
+    pub package: Package,
+    pub platform: Platform,
+    /// Git hash of the chip source
+    pub gitref: u32,
+}
+
+impl Default for SysInfo {
+    fn default() -> Self {
+        Self {
+            revision: 2,
+            package: Package::default(),
+            platform: Platform::default(),
+            gitref: 0,
+        }
+    }
+}
+
+impl SysInfo {
+    pub fn chip_id(&self) -> u32 {
+        (self.revision as u32 & 0xf) << 28 | PART << 12 | MANUFACTURER
+    }
+}
+
+impl Peripheral for SysInfo {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let value = match address {
+            CHIP_ID => self.chip_id(),
+            // PACKAGE_SEL is read as 1 on the QFN-60 package
+            PACKAGE_SEL => (self.package == Package::Qfn60) as u32,
+            PLATFORM => self.platform.bits(),
+            GITREF_RP2350 => self.gitref,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        _value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        match address {
+            CHIP_ID | PACKAGE_SEL | PLATFORM | GITREF_RP2350 => Ok(()), // read-only
+            _ => Err(PeripheralError::OutOfBounds),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let ctx = PeripheralAccessContext::default();
+        let mut sysinfo = SysInfo::default();
+        assert_eq!(sysinfo.read(CHIP_ID, &ctx), Ok(0x2000_4927));
+        assert_eq!(sysinfo.read(PACKAGE_SEL, &ctx), Ok(1));
+        assert_eq!(sysinfo.read(PLATFORM, &ctx), Ok(0b10));
+
+        sysinfo.write(PLATFORM, 0, &ctx).unwrap();
+        assert_eq!(sysinfo.read(PLATFORM, &ctx), Ok(0b10));
+
+        let sysinfo = SysInfo {
+            revision: 1,
+            package: Package::Qfn80,
+            platform: Platform::HdlSim,
+            gitref: 0x1234_5678,
+        };
+        assert_eq!(sysinfo.read(CHIP_ID, &ctx), Ok(0x1000_4927));
+        assert_eq!(sysinfo.read(PACKAGE_SEL, &ctx), Ok(0));
+        assert_eq!(sysinfo.read(PLATFORM, &ctx), Ok(0b100));
+        assert_eq!(sysinfo.read(GITREF_RP2350, &ctx), Ok(0x1234_5678));
+    }
+}
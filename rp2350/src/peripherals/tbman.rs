@@ -0,0 +1,81 @@
+/**
+ * @file peripherals/tbman.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief TBMAN peripheral, the testbench manager telling the firmware where it runs
+ */
+use super::*;
+
+pub const PLATFORM: u16 = 0x00;
+
+pub const PLATFORM_ASIC: u32 = 1 << 0;
+pub const PLATFORM_FPGA: u32 = 1 << 1;
+pub const PLATFORM_HDLSIM: u32 = 1 << 2;
+
+/// Testbench manager.
+///
+/// On silicon PLATFORM only has ASIC set. The simulator follows the convention of
+/// the chip testbenches and also sets HDLSIM, which is what `running_in_sim()` of
+/// the pico-sdk checks, so a program can stub the behaviour only real hardware has.
+/// SYSINFO keeps reporting the configured [`super::sysinfo::Platform`], code which
+/// only looks there sees the chip as on silicon.
+///
+/// Set `report_simulation` to false to read exactly what silicon reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tbman {
+    pub report_simulation: bool,
+}
+
+impl Default for Tbman {
+    fn default() -> Self {
+        Self {
+            report_simulation: true,
+        }
+    }
+}
+
+impl Tbman {
+    pub fn platform(&self) -> u32 {
+        match self.report_simulation {
+            true => PLATFORM_ASIC | PLATFORM_HDLSIM,
+            false => PLATFORM_ASIC,
+        }
+    }
+}
+
+impl Peripheral for Tbman {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        match address {
+            PLATFORM => Ok(self.platform()),
+            _ => Err(PeripheralError::OutOfBounds),
+        }
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        _value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        match address {
+            PLATFORM => Ok(()), // read-only
+            _ => Err(PeripheralError::OutOfBounds),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform() {
+        let ctx = PeripheralAccessContext::default();
+        let mut tbman = Tbman::default();
+        assert_eq!(tbman.read(PLATFORM, &ctx), Ok(PLATFORM_ASIC | PLATFORM_HDLSIM));
+
+        tbman.report_simulation = false;
+        assert_eq!(tbman.read(PLATFORM, &ctx), Ok(PLATFORM_ASIC));
+        assert!(tbman.read(0x04, &ctx).is_err());
+    }
+}
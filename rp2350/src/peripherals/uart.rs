@@ -11,8 +11,8 @@
  */
 use super::*;
 use crate::utils::{extract_bit, extract_bits, w1c, Fifo};
+use crate::clock::Clock;
 use std::cell::RefCell;
-use std::time::Duration;
 
 mod receive;
 mod transmit;
@@ -20,6 +20,8 @@ mod transmit;
 use receive::*;
 use transmit::*;
 
+pub(crate) use receive::receive_character;
+
 pub const UARTDR: u16 = 0x000; // Data Register, UARTDR
 pub const UARTRSR: u16 = 0x004; // Receive Status Register/Error Clear Register, UARTRSR/UARTECR
 pub const UARTFR: u16 = 0x018; // Flag Register, UARTFR
@@ -84,6 +86,18 @@ const LINE_CTRL_SPS: u8 = 0x1 << 7;
 const IRQ_UARTRXINTR: u16 = 0x1 << 4;
 const IRQ_UARTTXINTR: u16 = 0x1 << 5;
 
+/// Wiring of the two UARTs to each other, on top of their pins
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UartLink {
+    /// The UARTs only talk through the pins they are muxed on
+    #[default]
+    None,
+    /// TX0 drives RX1 and TX1 drives RX0
+    NullModem,
+    /// Each UART receives its own TX, as if UARTCR.LBE were set
+    Loopback,
+}
+
 pub struct Uart<const IDX: usize> {
     // receive are 12 bit wide
     rx_fifo: Fifo<u16, FIFO_DEPTH>,
@@ -145,9 +159,25 @@ impl<const IDX: usize> Uart<IDX> {
         }
     }
 
+    /// Divisor of clk_peri in 1/64, from the integer and the fractional baud rate registers
+    fn divisor(&self) -> u64 {
+        let mut baud_ibrd = self.baud_divint as u64;
+        let mut baud_fbrd = self.baud_divfrac as u64;
+
+        if baud_ibrd == 0 {
+            baud_ibrd = 1;
+            baud_fbrd = 0;
+        } else if baud_ibrd >= 65535 {
+            baud_ibrd = 65535;
+            baud_fbrd = 0;
+        }
+
+        64 * baud_ibrd + baud_fbrd
+    }
+
     // Inspired by the implementation in the pico-sdk
     // https://github.com/raspberrypi/pico-sdk/blob/ee68c78d0afae2b69c03ae1a72bf5cc267a2d94c/src/rp2_common/hardware_uart/uart.c#L155
-    pub fn get_baudrate(&self) -> u32 {
+    pub fn get_baudrate(&self, clk_peri: u64) -> u32 {
         //     uint32_t baud_rate_div = (8 * uart_clock_get_hz(uart) / baudrate) + 1;
         //     uint32_t baud_ibrd = baud_rate_div >> 7;
         //     uint32_t baud_fbrd;
@@ -171,30 +201,13 @@ impl<const IDX: usize> Uart<IDX> {
         //
         //     // See datasheet
         //     return (4 * uart_clock_get_hz(uart)) / (64 * baud_ibrd + baud_fbrd);
-        let mut baud_ibrd = self.baud_divint as u64;
-        let mut baud_fbrd = self.baud_divfrac as u64;
-
-        if baud_ibrd == 0 {
-            baud_ibrd = 1;
-            baud_fbrd = 0;
-        } else if baud_ibrd >= 65535 {
-            baud_ibrd = 65535;
-            baud_fbrd = 0;
-        }
-
-        let clock = 150 * MHZ;
-        let baudrate = (4 * clock) / (64 * baud_ibrd + baud_fbrd);
-        baudrate as u32
+        ((4 * clk_peri) / self.divisor()) as u32
     }
 
-    fn get_bit_time(&self) -> Duration {
-        let baudrate = self.get_baudrate();
-        let base_duration = Duration::from_secs_f64(1. / (150 * MHZ) as f64);
-        if baudrate == 0 {
-            return base_duration;
-        }
-
-        base_duration / (16 * self.get_baudrate())
+    /// Cycles of clk_sys a bit stays on the line, the UART is clocked by clk_peri
+    fn bit_ticks(&self, clock: &Clock) -> u64 {
+        let ticks = (clock.clk_sys() * self.divisor()).div_ceil(4 * clock.clk_peri().max(1));
+        ticks.max(1)
     }
 
     pub fn fifo_level(&self, level: u8) -> u8 {
@@ -240,6 +253,10 @@ impl<const IDX: usize> Uart<IDX> {
         extract_bit(self.ctrl, 9) != 0
     }
 
+    pub fn is_loopback_enabled(&self) -> bool {
+        self.ctrl & CTRL_LBE != 0
+    }
+
     pub fn is_fifo_enabled(&self) -> bool {
         extract_bit(self.ctrl, 4) != 0
     }
@@ -270,6 +287,13 @@ impl<const IDX: usize> Uart<IDX> {
         extract_bit(self.line_ctrl, 7) != 0
     }
 
+    /// Bits on the line for one character: the start bit, the data, the parity and the stop bits
+    pub fn frame_bits(&self) -> u32 {
+        let parity = self.is_parity_enabled() as u32;
+        let stop = 1 + self.two_stop_bits() as u32;
+        1 + self.word_len() as u32 + parity + stop
+    }
+
     fn update_interrupt(&mut self, interrupts: Rc<RefCell<Interrupts>>) {
         if self.is_fifo_enabled() {
             if self.tx_fifo.len() as u8 >= self.transmit_interrupt_fifo_level() {
@@ -503,3 +527,67 @@ pub(self) fn get_odd_parity(value: u8, word_len: u8) -> u8 {
 
     parity
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enable<const IDX: usize>(
+        uart: &mut Rc<RefCell<Uart<IDX>>>,
+        ctrl: u16,
+        ctx: &PeripheralAccessContext,
+    ) {
+        uart.write(UARTLCR_H, LINE_CTRL_WLEN as u32, ctx).unwrap();
+        uart.write(UARTCR, (CTRL_UARTEN | ctrl) as u32, ctx).unwrap();
+    }
+
+    /// Divisors of 0 shift a bit every 16 cycles of clk_peri, a frame is 10 bits
+    const FRAME: usize = 12 * 16;
+
+    fn run(ctx: &PeripheralAccessContext, ticks: usize) {
+        for _ in 0..ticks {
+            ctx.clock.tick();
+        }
+    }
+
+    #[test]
+    fn test_loopback() {
+        let ctx = PeripheralAccessContext::default();
+        let mut uart = Rc::new(RefCell::new(Uart::<0>::default()));
+        enable(&mut uart, CTRL_TXE | CTRL_RXE | CTRL_LBE, &ctx);
+
+        for value in [0x5A, 0xA5] {
+            uart.write(UARTDR, value, &ctx).unwrap();
+            run(&ctx, FRAME);
+
+            assert_eq!(uart.read(UARTFR, &ctx).unwrap() & FLAG_RXFE, 0);
+            assert_eq!(uart.read(UARTDR, &ctx), Ok(value));
+        }
+
+        assert_ne!(uart.read(UARTFR, &ctx).unwrap() & FLAG_RXFE, 0);
+    }
+
+    #[test]
+    fn test_null_modem() {
+        let ctx = PeripheralAccessContext::default();
+        let mut uart0 = Rc::new(RefCell::new(Uart::<0>::default()));
+        let mut uart1 = Rc::new(RefCell::new(Uart::<1>::default()));
+        enable(&mut uart0, CTRL_TXE | CTRL_RXE, &ctx);
+        enable(&mut uart1, CTRL_TXE | CTRL_RXE, &ctx);
+
+        // nothing is wired to the pins
+        uart0.write(UARTDR, b'A' as u32, &ctx).unwrap();
+        run(&ctx, FRAME);
+        assert_ne!(uart1.read(UARTFR, &ctx).unwrap() & FLAG_RXFE, 0);
+
+        ctx.gpio.borrow_mut().set_uart_link(UartLink::NullModem);
+        run(&ctx, 2 * 16); // let the receivers see the idle lines
+        uart0.write(UARTDR, b'B' as u32, &ctx).unwrap();
+        run(&ctx, FRAME);
+        uart1.write(UARTDR, b'C' as u32, &ctx).unwrap();
+        run(&ctx, FRAME);
+
+        assert_eq!(uart1.read(UARTDR, &ctx), Ok(b'B' as u32));
+        assert_eq!(uart0.read(UARTDR, &ctx), Ok(b'C' as u32));
+    }
+}
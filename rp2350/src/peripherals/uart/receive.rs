@@ -10,7 +10,7 @@ use crate::gpio::FunctionSelect;
 use crate::gpio::GpioController;
 use crate::inspector::InspectorRef;
 use crate::interrupts::Interrupts;
-use crate::peripherals::uart::Uart;
+use crate::peripherals::uart::{Uart, UartLink};
 use crate::peripherals::PeripheralAccessContext;
 use crate::InspectionEvent;
 use std::cell::RefCell;
@@ -18,6 +18,7 @@ use std::rc::Rc;
 
 use super::get_even_parity;
 use super::get_odd_parity;
+use super::transmit::tx_function;
 use super::FRAME_ERROR;
 use super::OVERRUN_ERROR;
 use super::PARITY_ERROR;
@@ -68,14 +69,31 @@ fn receive<const IDX: usize>(
     inspector: InspectorRef,
 ) {
     let mut uart = uart_ref.borrow_mut();
-    let bit_time = uart.get_bit_time();
+    let bit_time = uart.bit_ticks(&clock);
 
     let mut next_state: ReceiveState = state;
     let mut gpio = gpio_ref.borrow_mut();
 
-    if let Some(gpio_pin) = gpio.select(rx_gpio_func::<IDX>()) {
-        let bit = gpio_pin.input_value() as u8;
+    // A UART looped back or linked to the other one listens to a TX line instead of its pin
+    let tx_line = match (uart.is_loopback_enabled(), gpio.uart_link()) {
+        (true, _) | (false, UartLink::Loopback) => Some(tx_function(IDX)),
+        (false, UartLink::NullModem) => Some(tx_function(1 - IDX)),
+        (false, UartLink::None) => None,
+    };
+
+    let line = match tx_line {
+        Some(tx) => {
+            // the line idles high while the transmitter does not drive it
+            let output = gpio.peripheral_output(tx);
+            Some(!output.enable || output.value)
+        }
+        None => {
+            let pin = gpio.select(rx_gpio_func::<IDX>()).map(|gpio_pin| gpio_pin.index);
+            pin.map(|index| gpio.input_value(index))
+        }
+    };
 
+    if let Some(bit) = line.map(u8::from) {
         match state {
             ReceiveState::StartReceiving => {
                 next_state = ReceiveState::Idle { last_bit: bit };
@@ -84,6 +102,7 @@ fn receive<const IDX: usize>(
             ReceiveState::Idle { last_bit } => {
                 if last_bit == 1 && bit == 0 {
                     // Start bit detected
+                    data = 0;
                     next_state = ReceiveState::DataBit { index: 0 };
                 } else {
                     // No start bit detected, continue waiting
@@ -122,21 +141,10 @@ fn receive<const IDX: usize>(
                     uart.error |= FRAME_ERROR;
                     uart.update_interrupt(interrupts.clone());
                     next_state = ReceiveState::Idle { last_bit: bit };
-                } else if uart.two_stop_bits() && index == 1 {
+                } else if !uart.two_stop_bits() || index == 1 {
                     // All stop bits received, move to idle state
                     next_state = ReceiveState::Idle { last_bit: bit };
-                    uart.update_interrupt(interrupts.clone());
-
-                    let data = data as u16 | (uart.error as u16) << 8;
-                    inspector.emit(InspectionEvent::UartRx {
-                        uart_index: IDX as u8,
-                        value: data,
-                    });
-
-                    if let Err(_why) = uart.rx_fifo.push(data) {
-                        uart.error |= OVERRUN_ERROR;
-                        uart.update_interrupt(interrupts.clone());
-                    }
+                    store(&mut uart, data, &interrupts, &inspector);
                 } else {
                     // Move to next stop bit
                     next_state = ReceiveState::StopBit { index: index + 1 };
@@ -162,6 +170,45 @@ fn receive<const IDX: usize>(
     });
 }
 
+/// Store a character once its stop bit passed, with the errors of its frame
+fn store<const IDX: usize>(
+    uart: &mut Uart<IDX>,
+    data: u8,
+    interrupts: &Rc<RefCell<Interrupts>>,
+    inspector: &InspectorRef,
+) {
+    let data = data as u16 | (uart.error as u16) << 8;
+    inspector.emit(InspectionEvent::UartRx {
+        uart_index: IDX as u8,
+        value: data,
+    });
+
+    if let Err(_why) = uart.rx_fifo.push(data) {
+        uart.error |= OVERRUN_ERROR;
+    }
+
+    uart.check_rx_fifo();
+    uart.update_interrupt(interrupts.clone());
+}
+
+/// A character which came through the line without sampling the RX pin, see
+/// [`crate::Rp2350::uart_receive`]. It is lost while the receiver is disabled
+pub(crate) fn receive_character<const IDX: usize>(
+    uart: &Rc<RefCell<Uart<IDX>>>,
+    data: u8,
+    interrupts: &Rc<RefCell<Interrupts>>,
+    inspector: &InspectorRef,
+) -> bool {
+    let mut uart = uart.borrow_mut();
+
+    if !uart.is_enabled() || !uart.is_receive_enabled() {
+        return false;
+    }
+
+    store(&mut uart, data, interrupts, inspector);
+    true
+}
+
 const fn rx_gpio_func<const IDX: usize>() -> FunctionSelect {
     match IDX {
         0 => FunctionSelect::UART0_RX,
@@ -31,24 +31,31 @@ pub(super) fn start_transmitting<const IDX: usize>(
         return;
     }
 
-    ctx.gpio
-        .borrow_mut()
-        .set_pin_output_enable(tx_gpio_func::<IDX>(), true);
+    // The line idles high
+    let mut gpio = ctx.gpio.borrow_mut();
+    gpio.set_pin_output(tx_gpio_func::<IDX>(), true);
+    gpio.set_pin_output_enable(tx_gpio_func::<IDX>(), true);
+    drop(gpio);
 
     let clock = ctx.clock.clone();
     let interrupts = ctx.interrupts.clone();
     let inspector = ctx.inspector.clone();
     let gpio = ctx.gpio.clone();
 
-    transmit(
-        uart_ref.clone(),
-        0,
-        TransmitState::Idle,
-        clock,
-        interrupts,
-        gpio,
-        inspector,
-    );
+    // The data is picked up on the next bit period, so that the receivers sampling
+    // the line see the start bit for a whole period
+    ctx.clock
+        .schedule(0, EventType::UartTx(IDX), move || {
+            transmit(
+                uart_ref,
+                0,
+                TransmitState::Idle,
+                clock,
+                interrupts,
+                gpio,
+                inspector,
+            );
+        });
 }
 
 fn transmit<const IDX: usize>(
@@ -61,7 +68,7 @@ fn transmit<const IDX: usize>(
     inspector: InspectorRef,
 ) {
     let mut uart = uart_ref.borrow_mut();
-    let bit_time = uart.get_bit_time();
+    let bit_time = uart.bit_ticks(&clock);
 
     if !uart.is_enabled() || !uart.is_transmit_enabled() {
         gpio_ref
@@ -155,7 +162,11 @@ fn transmit<const IDX: usize>(
 }
 
 const fn tx_gpio_func<const IDX: usize>() -> FunctionSelect {
-    match IDX {
+    tx_function(IDX)
+}
+
+pub(super) const fn tx_function(index: usize) -> FunctionSelect {
+    match index {
         0 => FunctionSelect::UART0_TX,
         1 => FunctionSelect::UART1_TX,
         _ => unreachable!(),
@@ -0,0 +1,660 @@
+/**
+ * @file peripherals/usb.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief USB controller in device mode, with its dual port RAM
+ */
+use super::*;
+use crate::interrupts::Interrupts;
+use crate::utils::{extract_bits, w1c};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub mod host;
+
+pub use host::{CdcInterface, HostState, LineCoding, UsbHost};
+
+pub const DPRAM_BASE: u32 = 0x5010_0000;
+pub const DPRAM_SIZE: usize = 4096;
+
+// Layout of the DPRAM in device mode
+pub const DPRAM_SETUP_PACKET: usize = 0x000; // The 8 bytes of the last SETUP packet
+pub const DPRAM_EP_CONTROL: usize = 0x008; // EP1_IN_CONTROL, EP1_OUT_CONTROL up to EP15_OUT_CONTROL
+pub const DPRAM_EP_BUFFER_CONTROL: usize = 0x080; // EP0_IN_BUFFER_CONTROL, EP0_OUT_BUFFER_CONTROL up to EP15
+pub const DPRAM_EP0_BUFFER: usize = 0x100; // Buffer shared by both directions of EP0
+
+pub const ADDR_ENDP: u16 = 0x000; // Device address and endpoint control
+pub const MAIN_CTRL: u16 = 0x040; // Main control register
+pub const SOF_WR: u16 = 0x044; // Set the SOF (Start of Frame) frame number in the host controller
+pub const SOF_RD: u16 = 0x048; // Read the last SOF (Start of Frame) frame number seen
+pub const SIE_CTRL: u16 = 0x04c; // SIE control register
+pub const SIE_STATUS: u16 = 0x050; // SIE status register
+pub const INT_EP_CTRL: u16 = 0x054; // Interrupt endpoint control register
+pub const BUFF_STATUS: u16 = 0x058; // Buffer status register, a bit for each endpoint direction
+pub const BUFF_CPU_SHOULD_HANDLE: u16 = 0x05c; // Which of the double buffers should be handled
+pub const EP_ABORT: u16 = 0x060; // Device only: abort the next transaction on an endpoint
+pub const EP_ABORT_DONE: u16 = 0x064; // Device only: the endpoint is idle so the buffer can be modified
+pub const EP_STALL_ARM: u16 = 0x068; // Device: this bit must be set with the STALL bit of EP0
+pub const NAK_POLL: u16 = 0x06c; // Used by the host controller
+pub const EP_STATUS_STALL_NAK: u16 = 0x070; // Device: a bit for each endpoint which sent a NAK or STALL
+pub const USB_MUXING: u16 = 0x074; // Where to connect the USB controller
+pub const USB_PWR: u16 = 0x078; // Overrides for the power signals
+pub const USBPHY_DIRECT: u16 = 0x07c; // Direct control of the USB PHY
+pub const USBPHY_DIRECT_OVERRIDE: u16 = 0x080; // Override enable for each control in USBPHY_DIRECT
+pub const USBPHY_TRIM: u16 = 0x084; // Trim of the pull resistors
+pub const LINESTATE_TUNING: u16 = 0x088; // Tuning of the line state sampling
+pub const INTR: u16 = 0x08c; // Raw interrupts
+pub const INTE: u16 = 0x090; // Interrupt enable
+pub const INTF: u16 = 0x094; // Interrupt force
+pub const INTS: u16 = 0x098; // Interrupt status after masking & forcing
+pub const SOF_TIMESTAMP_RAW: u16 = 0x100; // Device only: time of the last SOF
+pub const SOF_TIMESTAMP_LAST: u16 = 0x104; // Device only: time of the last SOF, with the PHY delay
+pub const SM_STATE: u16 = 0x108; // State of the controller
+pub const EP_TX_ERROR: u16 = 0x10c; // TX error count for each endpoint
+pub const EP_RX_ERROR: u16 = 0x110; // RX error count for each endpoint
+pub const DEV_SM_WATCHDOG: u16 = 0x114; // Watchdog of the device state machine
+
+pub const MAIN_CTRL_CONTROLLER_EN: u32 = 1 << 0;
+pub const MAIN_CTRL_HOST_NDEVICE: u32 = 1 << 1;
+
+pub const SIE_CTRL_PULLUP_EN: u32 = 1 << 16;
+pub const SIE_CTRL_EP0_INT_1BUF: u32 = 1 << 29;
+
+pub const SIE_STATUS_VBUS_DETECTED: u32 = 1 << 0;
+pub const SIE_STATUS_SUSPENDED: u32 = 1 << 4;
+pub const SIE_STATUS_RESUME: u32 = 1 << 11;
+pub const SIE_STATUS_CONNECTED: u32 = 1 << 16;
+pub const SIE_STATUS_SETUP_REC: u32 = 1 << 17;
+pub const SIE_STATUS_TRANS_COMPLETE: u32 = 1 << 18;
+pub const SIE_STATUS_BUS_RESET: u32 = 1 << 19;
+pub const SIE_STATUS_CRC_ERROR: u32 = 1 << 24;
+pub const SIE_STATUS_BIT_STUFF_ERROR: u32 = 1 << 25;
+pub const SIE_STATUS_RX_OVERFLOW: u32 = 1 << 26;
+pub const SIE_STATUS_RX_TIMEOUT: u32 = 1 << 27;
+pub const SIE_STATUS_STALL_REC: u32 = 1 << 29;
+pub const SIE_STATUS_DATA_SEQ_ERROR: u32 = 1 << 31;
+const SIE_STATUS_W1C: u32 = 0xff0e_0810;
+
+pub const INTR_TRANS_COMPLETE: u32 = 1 << 3;
+pub const INTR_BUFF_STATUS: u32 = 1 << 4;
+pub const INTR_ERROR_DATA_SEQ: u32 = 1 << 5;
+pub const INTR_ERROR_RX_TIMEOUT: u32 = 1 << 6;
+pub const INTR_ERROR_RX_OVERFLOW: u32 = 1 << 7;
+pub const INTR_ERROR_BIT_STUFF: u32 = 1 << 8;
+pub const INTR_ERROR_CRC: u32 = 1 << 9;
+pub const INTR_STALL: u32 = 1 << 10;
+pub const INTR_VBUS_DETECT: u32 = 1 << 11;
+pub const INTR_BUS_RESET: u32 = 1 << 12;
+pub const INTR_DEV_CONN_DIS: u32 = 1 << 13;
+pub const INTR_DEV_SUSPEND: u32 = 1 << 14;
+pub const INTR_DEV_RESUME_FROM_HOST: u32 = 1 << 15;
+pub const INTR_SETUP_REQ: u32 = 1 << 16;
+pub const INTR_DEV_SOF: u32 = 1 << 17;
+pub const INTR_ABORT_DONE: u32 = 1 << 18;
+pub const INTR_EP_STALL_NAK: u32 = 1 << 19;
+
+pub const EP_CTRL_ENABLE: u32 = 1 << 31;
+pub const EP_CTRL_BUFFER_ADDRESS: u32 = 0xffc0;
+
+pub const BUF_CTRL_FULL: u32 = 1 << 15;
+pub const BUF_CTRL_LAST: u32 = 1 << 14;
+pub const BUF_CTRL_DATA1_PID: u32 = 1 << 13;
+pub const BUF_CTRL_STALL: u32 = 1 << 11;
+pub const BUF_CTRL_AVAILABLE: u32 = 1 << 10;
+pub const BUF_CTRL_LENGTH: u32 = 0x3ff;
+
+/// Frames are 1ms apart, at the default clk_sys of 150MHz
+const FRAME_TICKS: u64 = 150_000;
+
+pub const NOF_ENDPOINTS: u8 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the device to the host
+    In,
+    /// From the host to the device
+    Out,
+}
+
+/// Answer of the device to a transaction of the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Handshake {
+    /// Data sent by the device to an IN token, or the number of bytes accepted after an OUT
+    Ack(Vec<u8>),
+    Nak,
+    Stall,
+}
+
+/// USB controller in device mode, connected to a virtual host.
+/// Only the first buffer of each endpoint is used, double buffering is not modelled.
+pub struct UsbCtrl {
+    pub dpram: Vec<u8>,
+    pub addr_endp: u32,
+    pub main_ctrl: u32,
+    pub sof_wr: u32,
+    pub frame: u32,
+    pub sie_ctrl: u32,
+    pub sie_status: u32,
+    pub int_ep_ctrl: u32,
+    pub buff_status: u32,
+    pub ep_abort: u32,
+    pub ep_abort_done: u32,
+    pub ep_stall_arm: u32,
+    pub nak_poll: u32,
+    pub ep_status_stall_nak: u32,
+    pub usb_muxing: u32,
+    pub usb_pwr: u32,
+    pub usbphy_direct: u32,
+    pub usbphy_direct_override: u32,
+    pub usbphy_trim: u32,
+    pub linestate_tuning: u32,
+    pub interrupt_enable: u32,
+    pub interrupt_force: u32,
+    pub host: UsbHost,
+
+    // latched until cleared through SIE_STATUS or SOF_RD
+    connection_changed: bool,
+    sof_received: bool,
+    // ticks since the last frame
+    ticks: u64,
+}
+
+impl Default for UsbCtrl {
+    fn default() -> Self {
+        Self {
+            dpram: vec![0; DPRAM_SIZE],
+            addr_endp: 0,
+            main_ctrl: 0,
+            sof_wr: 0,
+            frame: 0,
+            sie_ctrl: 0,
+            sie_status: 0,
+            int_ep_ctrl: 0,
+            buff_status: 0,
+            ep_abort: 0,
+            ep_abort_done: 0,
+            ep_stall_arm: 0,
+            nak_poll: 0x0010_0010,
+            ep_status_stall_nak: 0,
+            usb_muxing: 0x0000_0001,
+            usb_pwr: 0,
+            usbphy_direct: 0,
+            usbphy_direct_override: 0,
+            usbphy_trim: 0x0000_1f1f,
+            linestate_tuning: 0x0000_00f8,
+            interrupt_enable: 0,
+            interrupt_force: 0,
+            host: UsbHost::default(),
+            connection_changed: false,
+            sof_received: false,
+            ticks: 0,
+        }
+    }
+}
+
+impl UsbCtrl {
+    pub fn is_enabled(&self) -> bool {
+        self.main_ctrl & MAIN_CTRL_CONTROLLER_EN != 0
+            && self.main_ctrl & MAIN_CTRL_HOST_NDEVICE == 0
+    }
+
+    /// Whether the device is visible to the host, it pulls up D+ once ready
+    pub fn is_attached(&self) -> bool {
+        self.is_enabled() && self.sie_ctrl & SIE_CTRL_PULLUP_EN != 0
+    }
+
+    pub fn device_address(&self) -> u8 {
+        extract_bits(self.addr_endp, 0..=6) as u8
+    }
+
+    pub fn intr(&self) -> u32 {
+        let status = self.sie_status;
+        let sources = [
+            (status & SIE_STATUS_TRANS_COMPLETE != 0, INTR_TRANS_COMPLETE),
+            (self.buff_status != 0, INTR_BUFF_STATUS),
+            (status & SIE_STATUS_DATA_SEQ_ERROR != 0, INTR_ERROR_DATA_SEQ),
+            (status & SIE_STATUS_RX_TIMEOUT != 0, INTR_ERROR_RX_TIMEOUT),
+            (status & SIE_STATUS_RX_OVERFLOW != 0, INTR_ERROR_RX_OVERFLOW),
+            (
+                status & SIE_STATUS_BIT_STUFF_ERROR != 0,
+                INTR_ERROR_BIT_STUFF,
+            ),
+            (status & SIE_STATUS_CRC_ERROR != 0, INTR_ERROR_CRC),
+            (status & SIE_STATUS_STALL_REC != 0, INTR_STALL),
+            (status & SIE_STATUS_VBUS_DETECTED != 0, INTR_VBUS_DETECT),
+            (status & SIE_STATUS_BUS_RESET != 0, INTR_BUS_RESET),
+            (self.connection_changed, INTR_DEV_CONN_DIS),
+            (status & SIE_STATUS_SUSPENDED != 0, INTR_DEV_SUSPEND),
+            (status & SIE_STATUS_RESUME != 0, INTR_DEV_RESUME_FROM_HOST),
+            (status & SIE_STATUS_SETUP_REC != 0, INTR_SETUP_REQ),
+            (self.sof_received, INTR_DEV_SOF),
+            (self.ep_abort_done != 0, INTR_ABORT_DONE),
+            (self.ep_status_stall_nak != 0, INTR_EP_STALL_NAK),
+        ];
+
+        sources
+            .into_iter()
+            .filter(|(raised, _)| *raised)
+            .fold(0, |intr, (_, bit)| intr | bit)
+    }
+
+    pub fn ints(&self) -> u32 {
+        (self.intr() & self.interrupt_enable) | self.interrupt_force
+    }
+
+    fn update_interrupt(&self, interrupts: &RefCell<Interrupts>) {
+        interrupts
+            .borrow_mut()
+            .set_irq(Interrupts::USBCTRL_IRQ, self.ints() != 0);
+    }
+
+    pub fn read_dpram(&self, offset: usize) -> u32 {
+        let bytes = &self.dpram[offset & !0b11..][..4];
+        u32::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    pub fn write_dpram(&mut self, offset: usize, value: u32) {
+        self.dpram[offset & !0b11..][..4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn direction_offset(direction: Direction) -> usize {
+        match direction {
+            Direction::In => 0,
+            Direction::Out => 4,
+        }
+    }
+
+    pub fn buffer_control(&self, endpoint: u8, direction: Direction) -> u32 {
+        let offset = DPRAM_EP_BUFFER_CONTROL + endpoint as usize * 8;
+        self.read_dpram(offset + Self::direction_offset(direction))
+    }
+
+    fn set_buffer_control(&mut self, endpoint: u8, direction: Direction, value: u32) {
+        let offset = DPRAM_EP_BUFFER_CONTROL + endpoint as usize * 8;
+        self.write_dpram(offset + Self::direction_offset(direction), value);
+    }
+
+    /// Offset of the first buffer of an endpoint in the DPRAM, `None` if it is disabled
+    pub fn buffer_address(&self, endpoint: u8, direction: Direction) -> Option<usize> {
+        if endpoint == 0 {
+            return Some(DPRAM_EP0_BUFFER);
+        }
+
+        let offset = DPRAM_EP_CONTROL + (endpoint as usize - 1) * 8;
+        let control = self.read_dpram(offset + Self::direction_offset(direction));
+
+        match control & EP_CTRL_ENABLE != 0 {
+            true => Some((control & EP_CTRL_BUFFER_ADDRESS) as usize),
+            false => None,
+        }
+    }
+
+    fn is_stalled(&self, endpoint: u8, direction: Direction, control: u32) -> bool {
+        if control & BUF_CTRL_STALL == 0 {
+            return false;
+        }
+
+        // EP0 only stalls if it was armed to, the arm is dropped by the next SETUP
+        match (endpoint, direction) {
+            (0, Direction::In) => self.ep_stall_arm & 0b01 != 0,
+            (0, Direction::Out) => self.ep_stall_arm & 0b10 != 0,
+            _ => true,
+        }
+    }
+
+    /// Bit of an endpoint direction in BUFF_STATUS and the other per endpoint registers
+    fn endpoint_bit(endpoint: u8, direction: Direction) -> u32 {
+        let bit = endpoint as u32 * 2 + (direction == Direction::Out) as u32;
+        1 << bit
+    }
+
+    fn buffer_done(&mut self, endpoint: u8, direction: Direction, control: u32) {
+        self.buff_status |= Self::endpoint_bit(endpoint, direction);
+
+        if control & BUF_CTRL_LAST != 0 {
+            self.sie_status |= SIE_STATUS_TRANS_COMPLETE;
+        }
+    }
+
+    fn nak(&mut self, endpoint: u8, direction: Direction) -> Handshake {
+        let bit = Self::endpoint_bit(endpoint, direction);
+        if self.int_ep_ctrl & bit != 0 {
+            self.ep_status_stall_nak |= bit;
+        }
+
+        Handshake::Nak
+    }
+
+    /// The host sends a SETUP packet to EP0
+    pub fn receive_setup(&mut self, setup: &[u8; 8]) {
+        self.dpram[DPRAM_SETUP_PACKET..][..8].copy_from_slice(setup);
+        self.sie_status |= SIE_STATUS_SETUP_REC;
+        self.ep_stall_arm = 0;
+    }
+
+    /// The host sends an IN token, the device answers with its buffer if it is available
+    pub fn transfer_in(&mut self, endpoint: u8) -> Handshake {
+        let control = self.buffer_control(endpoint, Direction::In);
+
+        if self.is_stalled(endpoint, Direction::In, control) {
+            return Handshake::Stall;
+        }
+
+        let ready = BUF_CTRL_AVAILABLE | BUF_CTRL_FULL;
+        let address = self.buffer_address(endpoint, Direction::In);
+
+        let Some(address) = address.filter(|_| control & ready == ready) else {
+            return self.nak(endpoint, Direction::In);
+        };
+
+        let length = (control & BUF_CTRL_LENGTH) as usize;
+        let data = self.dpram[address..][..length.min(DPRAM_SIZE - address)].to_vec();
+
+        self.set_buffer_control(endpoint, Direction::In, control & !ready);
+        self.buffer_done(endpoint, Direction::In, control);
+
+        Handshake::Ack(data)
+    }
+
+    /// The host sends an OUT packet, it is only accepted into an available buffer.
+    /// On success the bytes which fit into the buffer are returned.
+    pub fn transfer_out(&mut self, endpoint: u8, data: &[u8]) -> Handshake {
+        let control = self.buffer_control(endpoint, Direction::Out);
+
+        if self.is_stalled(endpoint, Direction::Out, control) {
+            return Handshake::Stall;
+        }
+
+        let address = self.buffer_address(endpoint, Direction::Out);
+        let Some(address) = address.filter(|_| control & BUF_CTRL_AVAILABLE != 0) else {
+            return self.nak(endpoint, Direction::Out);
+        };
+
+        let length = data
+            .len()
+            .min((control & BUF_CTRL_LENGTH) as usize)
+            .min(DPRAM_SIZE - address);
+
+        self.dpram[address..][..length].copy_from_slice(&data[..length]);
+
+        let control = (control & !(BUF_CTRL_AVAILABLE | BUF_CTRL_LENGTH)) | BUF_CTRL_FULL;
+        self.set_buffer_control(endpoint, Direction::Out, control | length as u32);
+        self.buffer_done(endpoint, Direction::Out, control);
+
+        Handshake::Ack(data[..length].to_vec())
+    }
+
+    /// The host resets the bus, the device starts over from the address 0
+    pub fn bus_reset(&mut self) {
+        self.sie_status |= SIE_STATUS_BUS_RESET | SIE_STATUS_CONNECTED;
+        self.addr_endp = 0;
+        self.connection_changed = true;
+    }
+
+    pub fn disconnect(&mut self) {
+        if self.sie_status & SIE_STATUS_CONNECTED != 0 {
+            self.sie_status &= !SIE_STATUS_CONNECTED;
+            self.connection_changed = true;
+        }
+    }
+
+    /// Queue bytes to be sent by the host to the CDC ACM interface of the device
+    pub fn cdc_send(&mut self, data: &[u8]) {
+        self.host.send(data);
+    }
+
+    pub(crate) fn tick(&mut self, interrupts: &RefCell<Interrupts>, inspector: &InspectorRef) {
+        self.ticks += 1;
+
+        // the host keeps sending SOFs once it reset the device
+        if self.ticks == FRAME_TICKS {
+            self.ticks = 0;
+
+            if self.sie_status & SIE_STATUS_CONNECTED != 0 {
+                self.frame = (self.frame + 1) & 0x7ff;
+                self.sof_received = true;
+            }
+        }
+
+        let mut host = core::mem::take(&mut self.host);
+        host.tick(self, inspector);
+        self.host = host;
+
+        self.update_interrupt(interrupts);
+    }
+
+    fn write_register(&mut self, address: u16, value: u32) -> PeripheralResult<()> {
+        match address {
+            ADDR_ENDP => self.addr_endp = value & 0x000f_007f,
+            MAIN_CTRL => self.main_ctrl = value & 0x8000_0007,
+            SOF_WR => self.sof_wr = value & 0x7ff,
+            SOF_RD => { /* read-only */ }
+            SIE_CTRL => self.sie_ctrl = value,
+            SIE_STATUS => {
+                if value & SIE_STATUS_CONNECTED != 0 {
+                    self.connection_changed = false;
+                }
+
+                w1c(&mut self.sie_status, value, SIE_STATUS_W1C);
+            }
+            INT_EP_CTRL => self.int_ep_ctrl = value & 0xfffe,
+            BUFF_STATUS => w1c(&mut self.buff_status, value, u32::MAX),
+            BUFF_CPU_SHOULD_HANDLE => { /* read-only */ }
+            EP_ABORT => {
+                self.ep_abort = value;
+                // nothing is ever in flight, the endpoints are idle straight away
+                self.ep_abort_done |= value;
+            }
+            EP_ABORT_DONE => w1c(&mut self.ep_abort_done, value, u32::MAX),
+            EP_STALL_ARM => self.ep_stall_arm = value & 0b11,
+            NAK_POLL => self.nak_poll = value,
+            EP_STATUS_STALL_NAK => w1c(&mut self.ep_status_stall_nak, value, u32::MAX),
+            USB_MUXING => self.usb_muxing = value,
+            USB_PWR => self.usb_pwr = value,
+            USBPHY_DIRECT => self.usbphy_direct = value,
+            USBPHY_DIRECT_OVERRIDE => self.usbphy_direct_override = value,
+            USBPHY_TRIM => self.usbphy_trim = value,
+            LINESTATE_TUNING => self.linestate_tuning = value,
+            INTR | INTS => { /* read-only */ }
+            INTE => self.interrupt_enable = value & 0x007f_ffff,
+            INTF => self.interrupt_force = value & 0x007f_ffff,
+            SOF_TIMESTAMP_RAW | SOF_TIMESTAMP_LAST | SM_STATE => { /* read-only */ }
+            EP_TX_ERROR | EP_RX_ERROR | DEV_SM_WATCHDOG => { /* not modelled */ }
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+
+    fn is_w1c_register(address: u16) -> bool {
+        matches!(
+            address,
+            SIE_STATUS | BUFF_STATUS | EP_ABORT_DONE | EP_STATUS_STALL_NAK
+        )
+    }
+}
+
+fn is_dpram(ctx: &PeripheralAccessContext) -> bool {
+    ctx.address & 0xffff_0000 == DPRAM_BASE
+}
+
+impl Peripheral for Rc<RefCell<UsbCtrl>> {
+    fn read(&self, address: u16, ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let mut usb = self.borrow_mut();
+
+        if is_dpram(ctx) {
+            return Ok(usb.read_dpram(address as usize & (DPRAM_SIZE - 1)));
+        }
+
+        let value = match address {
+            ADDR_ENDP => usb.addr_endp,
+            MAIN_CTRL => usb.main_ctrl,
+            SOF_WR => 0, // write-only
+            SOF_RD => {
+                usb.sof_received = false;
+                usb.frame
+            }
+            SIE_CTRL => usb.sie_ctrl,
+            SIE_STATUS => {
+                // VBUS is always present, the board is powered by the host
+                usb.sie_status | SIE_STATUS_VBUS_DETECTED
+            }
+            INT_EP_CTRL => usb.int_ep_ctrl,
+            BUFF_STATUS => usb.buff_status,
+            BUFF_CPU_SHOULD_HANDLE => 0,
+            EP_ABORT => usb.ep_abort,
+            EP_ABORT_DONE => usb.ep_abort_done,
+            EP_STALL_ARM => usb.ep_stall_arm,
+            NAK_POLL => usb.nak_poll,
+            EP_STATUS_STALL_NAK => usb.ep_status_stall_nak,
+            USB_MUXING => usb.usb_muxing,
+            USB_PWR => usb.usb_pwr,
+            USBPHY_DIRECT => usb.usbphy_direct,
+            USBPHY_DIRECT_OVERRIDE => usb.usbphy_direct_override,
+            USBPHY_TRIM => usb.usbphy_trim,
+            LINESTATE_TUNING => usb.linestate_tuning,
+            INTR => usb.intr(),
+            INTE => usb.interrupt_enable,
+            INTF => usb.interrupt_force,
+            INTS => usb.ints(),
+            SOF_TIMESTAMP_RAW | SOF_TIMESTAMP_LAST => 0,
+            SM_STATE => 0,
+            EP_TX_ERROR | EP_RX_ERROR | DEV_SM_WATCHDOG => 0,
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let offset = address & 0x0fff;
+
+        if is_dpram(ctx) {
+            // a plain memory, it does not have the atomic aliases
+            if address > 0x0fff {
+                return Err(PeripheralError::OutOfBounds);
+            }
+
+            return self.write_raw(offset, value, ctx);
+        }
+
+        // writing 1 clears the bits of these registers, whatever the alias is
+        if UsbCtrl::is_w1c_register(offset) {
+            return self.write_raw(offset, value, ctx);
+        }
+
+        let value = match (address >> 12) & 0x3 {
+            0x0 => value,
+            0x1 => self.read(offset, ctx)? ^ value,
+            0x2 => self.read(offset, ctx)? | value,
+            _ => self.read(offset, ctx)? & !value,
+        };
+
+        self.write_raw(offset, value, ctx)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut usb = self.borrow_mut();
+
+        if is_dpram(ctx) {
+            let offset = address as usize & (DPRAM_SIZE - 1);
+            let mask = ctx.write_mask.unwrap_or(u32::MAX);
+            let value = (usb.read_dpram(offset) & !mask) | (value & mask);
+            usb.write_dpram(offset, value);
+            return Ok(());
+        }
+
+        usb.write_register(address, value)?;
+        usb.update_interrupt(&ctx.interrupts);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGS_BASE: u32 = 0x5011_0000;
+
+    fn setup() -> (Rc<RefCell<UsbCtrl>>, PeripheralAccessContext) {
+        let usb = Rc::new(RefCell::new(UsbCtrl::default()));
+        let ctx = PeripheralAccessContext {
+            address: REGS_BASE,
+            ..Default::default()
+        };
+
+        (usb, ctx)
+    }
+
+    #[test]
+    fn test_atomic_aliases() {
+        let (mut usb, ctx) = setup();
+
+        usb.write(SIE_CTRL, SIE_CTRL_EP0_INT_1BUF, &ctx).unwrap();
+        usb.write(0x2000 | SIE_CTRL, SIE_CTRL_PULLUP_EN, &ctx)
+            .unwrap();
+        assert_eq!(
+            usb.read(SIE_CTRL, &ctx),
+            Ok(SIE_CTRL_EP0_INT_1BUF | SIE_CTRL_PULLUP_EN)
+        );
+
+        // the clear alias of a write-1-to-clear register only clears the given bits
+        usb.borrow_mut().sie_status = SIE_STATUS_SETUP_REC | SIE_STATUS_BUS_RESET;
+        usb.write(0x3000 | SIE_STATUS, SIE_STATUS_SETUP_REC, &ctx)
+            .unwrap();
+        assert_eq!(usb.borrow().sie_status, SIE_STATUS_BUS_RESET);
+    }
+
+    #[test]
+    fn test_dpram_byte_write() {
+        let (mut usb, _) = setup();
+        let mut ctx = PeripheralAccessContext {
+            address: DPRAM_BASE + 0x100,
+            ..Default::default()
+        };
+
+        usb.write(0x100, 0x1122_3344, &ctx).unwrap();
+
+        ctx.write_mask = Some(0x00ff_0000);
+        usb.write(0x100, 0x00aa_0000, &ctx).unwrap();
+        assert_eq!(usb.read(0x100, &ctx), Ok(0x11aa_3344));
+    }
+
+    #[test]
+    fn test_transfers() {
+        let (usb, _) = setup();
+        let mut usb = usb.borrow_mut();
+
+        // nothing armed yet
+        assert_eq!(usb.transfer_in(0), Handshake::Nak);
+
+        usb.dpram[DPRAM_EP0_BUFFER..][..3].copy_from_slice(b"abc");
+        let control = BUF_CTRL_AVAILABLE | BUF_CTRL_FULL | BUF_CTRL_LAST | 3;
+        usb.set_buffer_control(0, Direction::In, control);
+
+        assert_eq!(usb.transfer_in(0), Handshake::Ack(b"abc".to_vec()));
+        assert_eq!(usb.buff_status, 0b01);
+        assert_ne!(usb.sie_status & SIE_STATUS_TRANS_COMPLETE, 0);
+        assert_eq!(usb.transfer_in(0), Handshake::Nak);
+
+        // EP1 OUT with its buffer at 0x180, ready for 2 bytes
+        usb.write_dpram(DPRAM_EP_CONTROL + 4, EP_CTRL_ENABLE | 0x180);
+        usb.set_buffer_control(1, Direction::Out, BUF_CTRL_AVAILABLE | 2);
+
+        assert_eq!(usb.transfer_out(1, b"xyz"), Handshake::Ack(b"xy".to_vec()));
+        assert_eq!(&usb.dpram[0x180..0x182], b"xy");
+        assert_eq!(usb.buffer_control(1, Direction::Out), BUF_CTRL_FULL | 2);
+        assert_eq!(usb.buff_status, 0b1001);
+    }
+}
@@ -0,0 +1,508 @@
+/**
+ * @file peripherals/usb/host.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Virtual USB host enumerating the device and talking to its CDC ACM interface
+ */
+use super::{Handshake, UsbCtrl};
+use crate::inspector::{InspectionEvent, InspectorRef};
+use std::collections::VecDeque;
+
+pub const MAX_PACKET_SIZE: usize = 64;
+
+/// The host only runs a transaction every few ticks
+const POLL_INTERVAL: u64 = 64;
+/// Delay between the pull up of the device and the bus reset, way shorter than on a real host
+const CONNECT_DELAY: u64 = 15_000;
+const RESET_DURATION: u64 = 1_500;
+
+/// Address given to the device by SET_ADDRESS
+const DEVICE_ADDRESS: u8 = 1;
+
+const CDC_CLASS_COMMUNICATION: u8 = 0x02;
+const CDC_CLASS_DATA: u8 = 0x0a;
+
+const DESCRIPTOR_INTERFACE: u8 = 0x04;
+const DESCRIPTOR_ENDPOINT: u8 = 0x05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HostState {
+    /// The device is not pulling up D+
+    #[default]
+    Detached,
+    Connecting,
+    Resetting,
+    Enumerating,
+    Configured,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Request {
+    GetDeviceDescriptor,
+    SetAddress(u8),
+    GetConfigDescriptor,
+    SetConfiguration(u8),
+    SetLineCoding,
+    SetControlLineState(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Setup,
+    DataIn,
+    DataOut { sent: usize },
+    StatusIn,
+    StatusOut,
+}
+
+#[derive(Debug, Clone)]
+struct ControlTransfer {
+    request: Request,
+    setup: [u8; 8],
+    stage: Stage,
+    /// Data received from the device, or to be sent to it
+    data: Vec<u8>,
+}
+
+impl ControlTransfer {
+    fn new(request: Request, host: &UsbHost) -> Self {
+        let interface = host.cdc.map(|cdc| cdc.interface).unwrap_or_default();
+        let mut data = Vec::new();
+
+        let setup = match request {
+            Request::GetDeviceDescriptor => [0x80, 0x06, 0x00, 0x01, 0, 0, 0x40, 0x00],
+            Request::SetAddress(address) => [0x00, 0x05, address, 0, 0, 0, 0, 0],
+            Request::GetConfigDescriptor => [0x80, 0x06, 0x00, 0x02, 0, 0, 0x00, 0x02],
+            Request::SetConfiguration(value) => [0x00, 0x09, value, 0, 0, 0, 0, 0],
+            Request::SetLineCoding => {
+                data = host.line_coding.to_bytes().to_vec();
+                [0x21, 0x20, 0, 0, interface, 0, data.len() as u8, 0]
+            }
+            Request::SetControlLineState(value) => {
+                let [low, high] = value.to_le_bytes();
+                [0x21, 0x22, low, high, interface, 0, 0, 0]
+            }
+        };
+
+        Self {
+            request,
+            setup,
+            stage: Stage::Setup,
+            data,
+        }
+    }
+
+    fn length(&self) -> usize {
+        u16::from_le_bytes([self.setup[6], self.setup[7]]) as usize
+    }
+
+    fn is_device_to_host(&self) -> bool {
+        self.setup[0] & 0x80 != 0
+    }
+}
+
+/// Line coding given to the device, as set by a terminal opening the port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCoding {
+    pub baudrate: u32,
+    /// 0 for 1 stop bit, 1 for 1.5 and 2 for 2 stop bits
+    pub stop_bits: u8,
+    /// 0 none, 1 odd, 2 even, 3 mark, 4 space
+    pub parity: u8,
+    pub data_bits: u8,
+}
+
+impl Default for LineCoding {
+    fn default() -> Self {
+        Self {
+            baudrate: 115200,
+            stop_bits: 0,
+            parity: 0,
+            data_bits: 8,
+        }
+    }
+}
+
+impl LineCoding {
+    fn to_bytes(self) -> [u8; 7] {
+        let [b0, b1, b2, b3] = self.baudrate.to_le_bytes();
+        [b0, b1, b2, b3, self.stop_bits, self.parity, self.data_bits]
+    }
+}
+
+/// The CDC ACM interface found in the configuration descriptor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CdcInterface {
+    /// Number of the communication interface, the target of the class requests
+    pub interface: u8,
+    pub data_in: u8,
+    pub data_out: u8,
+}
+
+/// Find the first CDC ACM interface with its bulk endpoints
+fn find_cdc_interface(config: &[u8]) -> Option<CdcInterface> {
+    let mut interface = None;
+    let mut class = 0;
+    let mut data_in = None;
+    let mut data_out = None;
+
+    let mut descriptors = config;
+    while let [length, kind, ..] = descriptors {
+        let (length, kind) = (*length as usize, *kind);
+        if length < 2 || length > descriptors.len() {
+            break;
+        }
+
+        let descriptor = &descriptors[..length];
+        descriptors = &descriptors[length..];
+
+        match (kind, descriptor) {
+            (DESCRIPTOR_INTERFACE, [_, _, number, _, _, interface_class, ..]) => {
+                class = *interface_class;
+                if class == CDC_CLASS_COMMUNICATION && interface.is_none() {
+                    interface = Some(*number);
+                }
+            }
+            (DESCRIPTOR_ENDPOINT, [_, _, address, attributes, ..])
+                if class == CDC_CLASS_DATA && attributes & 0b11 == 0b10 =>
+            {
+                match address & 0x80 != 0 {
+                    true => data_in = data_in.or(Some(address & 0x0f)),
+                    false => data_out = data_out.or(Some(address & 0x0f)),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(CdcInterface {
+        interface: interface?,
+        data_in: data_in?,
+        data_out: data_out?,
+    })
+}
+
+/// A host on the other end of the cable, like the computer running a serial terminal.
+/// It enumerates the device once it is attached, then opens its CDC ACM port if it has one.
+#[derive(Debug, Clone, Default)]
+pub struct UsbHost {
+    pub state: HostState,
+    pub device_descriptor: Vec<u8>,
+    pub config_descriptor: Vec<u8>,
+    pub cdc: Option<CdcInterface>,
+    pub line_coding: LineCoding,
+    requests: VecDeque<Request>,
+    transfer: Option<ControlTransfer>,
+    address: u8,
+    wait: u64,
+    poll: u64,
+    /// Bytes waiting to be sent to the device
+    tx: VecDeque<u8>,
+}
+
+impl UsbHost {
+    /// Queue bytes to be sent to the CDC ACM interface of the device
+    pub fn send(&mut self, data: &[u8]) {
+        self.tx.extend(data);
+    }
+
+    pub fn pending_tx(&self) -> usize {
+        self.tx.len()
+    }
+
+    pub(super) fn tick(&mut self, usb: &mut UsbCtrl, inspector: &InspectorRef) {
+        if !usb.is_attached() {
+            if self.state != HostState::Detached {
+                usb.disconnect();
+
+                // the data queued for the device stays, like in the terminal of the host
+                *self = Self {
+                    line_coding: self.line_coding,
+                    tx: core::mem::take(&mut self.tx),
+                    ..Default::default()
+                };
+            }
+
+            return;
+        }
+
+        self.wait = self.wait.saturating_sub(1);
+        if self.wait > 0 {
+            return;
+        }
+
+        match self.state {
+            HostState::Detached => {
+                self.state = HostState::Connecting;
+                self.wait = CONNECT_DELAY;
+            }
+            HostState::Connecting => {
+                usb.bus_reset();
+                self.state = HostState::Resetting;
+                self.wait = RESET_DURATION;
+            }
+            HostState::Resetting => {
+                self.address = 0;
+                self.requests = VecDeque::from([
+                    Request::GetDeviceDescriptor,
+                    Request::SetAddress(DEVICE_ADDRESS),
+                    Request::GetConfigDescriptor,
+                    Request::SetConfiguration(1),
+                ]);
+                self.state = HostState::Enumerating;
+            }
+            HostState::Enumerating | HostState::Configured => {
+                self.poll += 1;
+                if self.poll < POLL_INTERVAL {
+                    return;
+                }
+                self.poll = 0;
+
+                // the device only answers once it took the address given to it
+                if usb.device_address() != self.address {
+                    return;
+                }
+
+                self.control(usb);
+
+                if self.state == HostState::Configured {
+                    self.cdc_transfer(usb, inspector);
+                }
+            }
+        }
+    }
+
+    /// Run the next transaction of the current control transfer on EP0
+    fn control(&mut self, usb: &mut UsbCtrl) {
+        let mut transfer = match self.transfer.take() {
+            Some(transfer) => transfer,
+            None => match self.requests.pop_front() {
+                Some(request) => ControlTransfer::new(request, self),
+                None => {
+                    self.state = HostState::Configured;
+                    return;
+                }
+            },
+        };
+
+        let handshake = match transfer.stage {
+            Stage::Setup => {
+                usb.receive_setup(&transfer.setup);
+
+                transfer.stage = match (transfer.length(), transfer.is_device_to_host()) {
+                    (0, _) => Stage::StatusIn,
+                    (_, true) => Stage::DataIn,
+                    (_, false) => Stage::DataOut { sent: 0 },
+                };
+
+                self.transfer = Some(transfer);
+                return;
+            }
+            Stage::DataIn => usb.transfer_in(0),
+            Stage::DataOut { sent } => {
+                let end = transfer.data.len().min(sent + MAX_PACKET_SIZE);
+                usb.transfer_out(0, &transfer.data[sent..end])
+            }
+            Stage::StatusIn => usb.transfer_in(0),
+            Stage::StatusOut => usb.transfer_out(0, &[]),
+        };
+
+        let packet = match handshake {
+            Handshake::Ack(packet) => packet,
+            Handshake::Nak => {
+                self.transfer = Some(transfer);
+                return;
+            }
+            Handshake::Stall => {
+                log::warn!("USB: the device stalled {:?}", transfer.request);
+                return;
+            }
+        };
+
+        match transfer.stage {
+            Stage::DataIn => {
+                transfer.data.extend_from_slice(&packet);
+
+                // a short packet ends the data stage
+                if packet.len() < MAX_PACKET_SIZE || transfer.data.len() >= transfer.length() {
+                    transfer.stage = Stage::StatusOut;
+                }
+            }
+            Stage::DataOut { sent } => {
+                let sent = sent + packet.len();
+                transfer.stage = match sent >= transfer.data.len() {
+                    true => Stage::StatusIn,
+                    false => Stage::DataOut { sent },
+                };
+            }
+            Stage::Setup | Stage::StatusIn | Stage::StatusOut => {
+                self.finish(transfer);
+                return;
+            }
+        }
+
+        self.transfer = Some(transfer);
+    }
+
+    fn finish(&mut self, transfer: ControlTransfer) {
+        match transfer.request {
+            Request::GetDeviceDescriptor => self.device_descriptor = transfer.data,
+            Request::SetAddress(address) => self.address = address,
+            Request::GetConfigDescriptor => {
+                self.cdc = find_cdc_interface(&transfer.data);
+                self.config_descriptor = transfer.data;
+
+                // open the port, with DTR and RTS like a terminal does
+                if self.cdc.is_some() {
+                    self.requests.push_back(Request::SetLineCoding);
+                    self.requests.push_back(Request::SetControlLineState(0b11));
+                }
+            }
+            Request::SetConfiguration(_)
+            | Request::SetLineCoding
+            | Request::SetControlLineState(_) => {}
+        }
+    }
+
+    fn cdc_transfer(&mut self, usb: &mut UsbCtrl, inspector: &InspectorRef) {
+        let Some(cdc) = self.cdc else {
+            return;
+        };
+
+        match usb.transfer_in(cdc.data_in) {
+            Handshake::Ack(data) if !data.is_empty() => {
+                inspector.emit(InspectionEvent::UsbCdcTx { data });
+            }
+            _ => {}
+        }
+
+        if self.tx.is_empty() {
+            return;
+        }
+
+        let packet: Vec<u8> = self.tx.iter().take(MAX_PACKET_SIZE).copied().collect();
+        if let Handshake::Ack(data) = usb.transfer_out(cdc.data_out, &packet) {
+            self.tx.drain(..data.len());
+            inspector.emit(InspectionEvent::UsbCdcRx { data });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    /// Configuration of a CDC ACM device, as given by TinyUSB
+    const CONFIG: [u8; 75] = [
+        0x09, 0x02, 0x4b, 0x00, 0x02, 0x01, 0x00, 0x80, 0xfa, // configuration
+        0x08, 0x0b, 0x00, 0x02, 0x02, 0x02, 0x00, 0x00, // interface association
+        0x09, 0x04, 0x00, 0x00, 0x01, 0x02, 0x02, 0x00, 0x04, // communication interface
+        0x05, 0x24, 0x00, 0x20, 0x01, // header
+        0x05, 0x24, 0x01, 0x00, 0x01, // call management
+        0x04, 0x24, 0x02, 0x02, // abstract control management
+        0x05, 0x24, 0x06, 0x00, 0x01, // union
+        0x07, 0x05, 0x81, 0x03, 0x08, 0x00, 0x10, // notification endpoint
+        0x09, 0x04, 0x01, 0x00, 0x02, 0x0a, 0x00, 0x00, 0x00, // data interface
+        0x07, 0x05, 0x02, 0x02, 0x40, 0x00, 0x00, // bulk OUT
+        0x07, 0x05, 0x82, 0x02, 0x40, 0x00, 0x00, // bulk IN
+    ];
+
+    #[test]
+    fn test_find_cdc_interface() {
+        assert_eq!(
+            find_cdc_interface(&CONFIG),
+            Some(CdcInterface {
+                interface: 0,
+                data_in: 2,
+                data_out: 2,
+            })
+        );
+
+        assert_eq!(find_cdc_interface(&CONFIG[..52]), None);
+    }
+
+    fn tick_until(usb: &mut UsbCtrl, inspector: &InspectorRef, done: impl Fn(&UsbCtrl) -> bool) {
+        for _ in 0..100_000 {
+            if done(usb) {
+                return;
+            }
+
+            let mut host = core::mem::take(&mut usb.host);
+            host.tick(usb, inspector);
+            usb.host = host;
+        }
+
+        panic!("the host got stuck in {:?}", usb.host.state);
+    }
+
+    /// Answer the control requests on EP0 like a device stack would
+    fn answer_setup(usb: &mut UsbCtrl, response: &[u8]) {
+        usb.sie_status &= !SIE_STATUS_SETUP_REC;
+        usb.dpram[DPRAM_EP0_BUFFER..][..response.len()].copy_from_slice(response);
+
+        let control = BUF_CTRL_AVAILABLE | BUF_CTRL_FULL | response.len() as u32;
+        usb.set_buffer_control(0, Direction::In, control);
+        usb.set_buffer_control(0, Direction::Out, BUF_CTRL_AVAILABLE | 64);
+    }
+
+    #[test]
+    fn test_enumeration() {
+        let mut usb = UsbCtrl::default();
+        let inspector = InspectorRef::default();
+        usb.main_ctrl = MAIN_CTRL_CONTROLLER_EN;
+        usb.sie_ctrl = SIE_CTRL_PULLUP_EN;
+
+        tick_until(&mut usb, &inspector, |usb| {
+            usb.sie_status & SIE_STATUS_BUS_RESET != 0
+        });
+
+        let mut requests = Vec::new();
+        while requests.len() < 6 {
+            tick_until(&mut usb, &inspector, |usb| {
+                usb.sie_status & SIE_STATUS_SETUP_REC != 0
+            });
+
+            let setup: [u8; 8] = usb.dpram[..8].try_into().unwrap();
+            requests.push((setup[0], setup[1]));
+
+            match (setup[1], setup[3]) {
+                (0x06, 0x01) => answer_setup(&mut usb, &[0x12, 0x01]),
+                (0x06, 0x02) => answer_setup(&mut usb, &CONFIG[..64]),
+                _ => answer_setup(&mut usb, &[]),
+            }
+
+            if setup[1] == 0x06 && setup[3] == 0x02 {
+                // the rest of the configuration goes in a second packet
+                tick_until(&mut usb, &inspector, |usb| {
+                    usb.host.transfer.as_ref().unwrap().data.len() == 64
+                });
+                answer_setup(&mut usb, &CONFIG[64..]);
+            }
+
+            if setup[1] == 0x05 {
+                // the address is taken once the status stage is done
+                tick_until(&mut usb, &inspector, |usb| usb.host.address == setup[2]);
+                usb.addr_endp = setup[2] as u32;
+            }
+        }
+
+        assert_eq!(
+            requests,
+            [
+                (0x80, 0x06),
+                (0x00, 0x05),
+                (0x80, 0x06),
+                (0x00, 0x09),
+                (0x21, 0x20),
+                (0x21, 0x22)
+            ]
+        );
+
+        tick_until(&mut usb, &inspector, |usb| {
+            usb.host.state == HostState::Configured
+        });
+        assert_eq!(usb.host.config_descriptor, CONFIG);
+        assert_eq!(usb.host.cdc.map(|cdc| cdc.data_in), Some(2));
+    }
+}
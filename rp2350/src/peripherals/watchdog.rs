@@ -113,7 +113,7 @@ impl Peripheral for WatchDog {
                 self.enable = extract_bit(value, 30) != 0;
                 self.pause_jtag = extract_bit(value, 24) != 0;
                 self.pause_dbg0 = extract_bit(value, 25) != 0;
-                self.enable = extract_bit(value, 26) != 0;
+                self.pause_dbg1 = extract_bit(value, 26) != 0;
             }
             LOAD => self.timer = value,
             REASON => { /* read only */ }
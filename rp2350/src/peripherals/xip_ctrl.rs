@@ -0,0 +1,153 @@
+/**
+ * @file peripherals/xip_ctrl.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief XIP cache control and the streaming of the flash content to the FIFO
+ */
+use super::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+pub const CTRL: u16 = 0x00; // Cache control register
+pub const STAT: u16 = 0x08; // Status of the streaming FIFO
+pub const CTR_HIT: u16 = 0x0c; // Cache Hit counter
+pub const CTR_ACC: u16 = 0x10; // Cache Access counter
+pub const STREAM_ADDR: u16 = 0x14; // FIFO stream address
+pub const STREAM_CTR: u16 = 0x18; // FIFO stream control
+pub const STREAM_FIFO: u16 = 0x1c; // FIFO stream data
+
+pub const CTRL_EN_SECURE: u32 = 1 << 0;
+pub const CTRL_EN_NONSECURE: u32 = 1 << 1;
+pub const CTRL_POWER_DOWN: u32 = 1 << 3;
+pub const CTRL_WRITABLE_M0: u32 = 1 << 11;
+pub const CTRL_WRITABLE_M1: u32 = 1 << 12;
+
+pub const STAT_FIFO_EMPTY: u32 = 1 << 1;
+pub const STAT_FIFO_FULL: u32 = 1 << 2;
+
+/// Depth of the streaming FIFO
+pub const FIFO_DEPTH: usize = 2;
+
+/// There is no cache in the simulator, the counters stay at zero. The stream is read at
+/// once when it is started, so the FIFO never runs dry before the count is reached.
+#[derive(Debug)]
+pub struct XipCtrl {
+    pub ctrl: u32,
+    pub stream_addr: u32,
+    stream: RefCell<VecDeque<u32>>,
+    /// Words to read from the flash, handed over by the bus
+    requested: u32,
+}
+
+impl Default for XipCtrl {
+    fn default() -> Self {
+        Self {
+            ctrl: 0x0000_0083,
+            stream_addr: 0,
+            stream: RefCell::new(VecDeque::new()),
+            requested: 0,
+        }
+    }
+}
+
+impl XipCtrl {
+    /// Read the requested words of the stream, called by the bus after each write to the
+    /// registers
+    pub(crate) fn fill_stream(&mut self, memory: &[u8]) {
+        if self.requested == 0 {
+            return;
+        }
+
+        let mut stream = self.stream.borrow_mut();
+        let start = (self.stream_addr & 0x00ff_fffc) as usize;
+
+        for word in 0..self.requested as usize {
+            let address = (start + word * 4) % memory.len();
+            let bytes = memory
+                .get(address..address + 4)
+                .and_then(|bytes| bytes.try_into().ok())
+                .unwrap_or_default();
+            stream.push_back(u32::from_le_bytes(bytes));
+        }
+
+        self.stream_addr = self.stream_addr.wrapping_add(self.requested * 4);
+        self.requested = 0;
+    }
+
+    fn stat(&self) -> u32 {
+        let stream = self.stream.borrow();
+
+        match stream.len() {
+            0 => STAT_FIFO_EMPTY,
+            len if len >= FIFO_DEPTH => STAT_FIFO_FULL,
+            _ => 0,
+        }
+    }
+}
+
+impl Peripheral for Rc<RefCell<XipCtrl>> {
+    fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
+        let xip = self.borrow();
+
+        let value = match address {
+            CTRL => xip.ctrl,
+            STAT => xip.stat(),
+            CTR_HIT | CTR_ACC => 0,
+            STREAM_ADDR => xip.stream_addr,
+            // the words left in the stream
+            STREAM_CTR => xip.stream.borrow().len() as u32 + xip.requested,
+            STREAM_FIFO => xip.stream.borrow_mut().pop_front().unwrap_or_default(),
+            _ => return Err(PeripheralError::OutOfBounds),
+        };
+
+        Ok(value)
+    }
+
+    fn write_raw(
+        &mut self,
+        address: u16,
+        value: u32,
+        _ctx: &PeripheralAccessContext,
+    ) -> PeripheralResult<()> {
+        let mut xip = self.borrow_mut();
+
+        match address {
+            CTRL => xip.ctrl = value & 0x1ffb,
+            STAT => { /* read-only */ }
+            CTR_HIT | CTR_ACC => { /* cleared on write, always zero */ }
+            STREAM_ADDR => xip.stream_addr = value & !0x3,
+            STREAM_CTR => {
+                // writing zero stops the stream in progress
+                xip.stream.borrow_mut().clear();
+                xip.requested = value & 0x003f_ffff;
+            }
+            STREAM_FIFO => { /* read-only */ }
+            _ => return Err(PeripheralError::OutOfBounds),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream() {
+        let mut xip: Rc<RefCell<XipCtrl>> = Default::default();
+        let ctx = PeripheralAccessContext::default();
+        let memory: Vec<u8> = (0..=255).collect();
+
+        xip.write(STREAM_ADDR, 0x1000_0010, &ctx).unwrap();
+        xip.write(STREAM_CTR, 2, &ctx).unwrap();
+        xip.borrow_mut().fill_stream(&memory);
+
+        assert_eq!(xip.read(STAT, &ctx), Ok(STAT_FIFO_FULL));
+        assert_eq!(xip.read(STREAM_FIFO, &ctx), Ok(0x1312_1110));
+        assert_eq!(xip.read(STREAM_CTR, &ctx), Ok(1));
+        assert_eq!(xip.read(STREAM_FIFO, &ctx), Ok(0x1716_1514));
+        assert_eq!(xip.read(STAT, &ctx), Ok(STAT_FIFO_EMPTY));
+    }
+}
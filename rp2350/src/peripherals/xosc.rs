@@ -18,7 +18,11 @@ const DORMANT_VAL: u32 = 0x636f6d61;
 const WAKE: u32 = 0x77616b65;
 
 const CTRL_ENABLE: u32 = 0xfab << 12;
-const _CTRL_DISABLE: u32 = 0xd1e << 12;
+const CTRL_DISABLE: u32 = 0xd1e << 12;
+const CTRL_ENABLE_MASK: u32 = 0xfff << 12;
+
+/// The crystal of the Pico 2
+pub const XOSC_FREQUENCY: u64 = 12 * MHZ;
 
 pub struct Xosc {
     ctrl: u32,
@@ -38,6 +42,16 @@ impl Default for Xosc {
     }
 }
 
+impl Xosc {
+    /// Output in Hz, none once it is disabled
+    pub fn frequency(&self) -> u64 {
+        match self.ctrl & CTRL_ENABLE_MASK {
+            CTRL_DISABLE => 0,
+            _ => XOSC_FREQUENCY,
+        }
+    }
+}
+
 impl Peripheral for Xosc {
     fn read(&self, address: u16, _ctx: &PeripheralAccessContext) -> PeripheralResult<u32> {
         let value = match address {
@@ -8,10 +8,12 @@ pub mod cortex_m33;
 pub mod hazard3;
 
 use crate::bus::Bus;
+use crate::common::ArchitectureType;
 use crate::interrupts::Interrupts;
 use crate::InspectorRef;
-pub use cortex_m33::CortexM33;
-pub use hazard3::Hazard3;
+pub use cortex_m33::{CortexM33, CortexM33Snapshot};
+pub use hazard3::trigger::NUM_TRIGGERS;
+pub use hazard3::{Hazard3, Hazard3Snapshot, InterruptLatency, Trigger};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -26,6 +28,7 @@ pub trait CpuArchitecture {
     fn set_core_id(&mut self, core_id: u8);
     fn get_pc(&self) -> u32;
     fn set_pc(&mut self, value: u32);
+    fn get_sp(&self) -> u32;
     fn set_sp(&mut self, value: u32);
     fn tick(&mut self, ctx: &mut ProcessorContext);
     fn sleep(&mut self);
@@ -37,11 +40,57 @@ pub enum Rp2350Core {
     RiscV(Hazard3),
 }
 
+#[derive(Clone)]
+pub enum CoreSnapshot {
+    Arm(CortexM33Snapshot),
+    RiscV(Hazard3Snapshot),
+}
+
+impl CoreSnapshot {
+    pub fn pc(&self) -> Option<u32> {
+        match self {
+            Self::RiscV(snapshot) => Some(snapshot.pc),
+            Self::Arm(snapshot) => Some(snapshot.pc),
+        }
+    }
+
+    pub fn registers(&self) -> Option<[u32; 32]> {
+        match self {
+            Self::RiscV(snapshot) => Some(core::array::from_fn(|i| {
+                snapshot.registers.read(i as u8)
+            })),
+            Self::Arm(_) => None,
+        }
+    }
+
+    /// Instructions the core had executed when the snapshot was taken
+    pub fn retired(&self) -> u64 {
+        match self {
+            Self::RiscV(snapshot) => snapshot.retired,
+            Self::Arm(snapshot) => snapshot.retired,
+        }
+    }
+}
+
 impl Rp2350Core {
     pub fn new() -> Self {
         Self::RiscV(Hazard3::new())
     }
 
+    pub fn with_architecture(architecture: ArchitectureType) -> Self {
+        match architecture {
+            ArchitectureType::Hazard3 => Self::RiscV(Hazard3::new()),
+            ArchitectureType::CortexM33 => Self::Arm(CortexM33::new()),
+        }
+    }
+
+    pub fn architecture(&self) -> ArchitectureType {
+        match self {
+            Self::Arm(_) => ArchitectureType::CortexM33,
+            Self::RiscV(_) => ArchitectureType::Hazard3,
+        }
+    }
+
     pub fn set_core_id(&mut self, core_id: u8) {
         match self {
             Self::Arm(core) => core.set_core_id(core_id),
@@ -70,6 +119,15 @@ impl Rp2350Core {
         }
     }
 
+    /// The core was asleep for `cycles` while the scheduler skipped it, the Hazard3
+    /// counts them and the SysTick of the Cortex-M33 counts down through them
+    pub fn slept(&mut self, cycles: u64) {
+        match self {
+            Self::Arm(core) => core.slept(cycles),
+            Self::RiscV(core) => core.slept(cycles),
+        }
+    }
+
     pub fn set_pc(&mut self, value: u32) {
         match self {
             Self::Arm(core) => core.set_pc(value),
@@ -84,6 +142,13 @@ impl Rp2350Core {
         }
     }
 
+    pub fn get_sp(&self) -> u32 {
+        match self {
+            Self::Arm(core) => core.get_sp(),
+            Self::RiscV(core) => core.get_sp(),
+        }
+    }
+
     pub fn set_sp(&mut self, value: u32) {
         match self {
             Self::Arm(core) => core.set_sp(value),
@@ -91,10 +156,116 @@ impl Rp2350Core {
         }
     }
 
+    pub fn snapshot(&self) -> CoreSnapshot {
+        match self {
+            Self::Arm(core) => CoreSnapshot::Arm(core.snapshot()),
+            Self::RiscV(core) => CoreSnapshot::RiscV(core.snapshot()),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &CoreSnapshot) {
+        match (self, snapshot) {
+            (Self::RiscV(core), CoreSnapshot::RiscV(snapshot)) => core.restore(snapshot),
+            (Self::Arm(core), CoreSnapshot::Arm(snapshot)) => core.restore(snapshot),
+            _ => log::warn!("Snapshot was taken with a different core architecture"),
+        }
+    }
+
     pub fn set_register(&mut self, reg: u8, value: u32) {
         match self {
             Self::RiscV(core) => core.registers.write(reg, value),
-            _ => {} // TODO
+            // The PC is not part of the register file of the Cortex-M33
+            Self::Arm(core) if reg < 15 => core.registers.write(reg, value),
+            Self::Arm(_) => {}
+        }
+    }
+
+    /// The general purpose registers, x0-x31 on RISC-V and r0-r14 on Arm where the rest
+    /// reads as zero
+    pub fn registers(&self) -> [u32; 32] {
+        match self {
+            Self::RiscV(core) => core::array::from_fn(|i| core.registers.read(i as u8)),
+            Self::Arm(core) => core::array::from_fn(|i| match i {
+                0..=14 => core.registers.read(i as u8),
+                _ => 0,
+            }),
+        }
+    }
+
+    /// Whether the core is halted by its debug module, only the Hazard3 has one
+    pub fn is_halted(&self) -> bool {
+        match self {
+            Self::RiscV(core) => core.is_halted(),
+            Self::Arm(_) => false,
+        }
+    }
+
+    /// Instructions executed by the core since it was created
+    pub fn retired(&self) -> u64 {
+        match self {
+            Self::Arm(core) => core.retired(),
+            Self::RiscV(core) => core.retired,
+        }
+    }
+
+    /// Whether the core is still working on its last instruction
+    pub fn is_busy(&self) -> bool {
+        match self {
+            Self::Arm(core) => core.is_busy(),
+            Self::RiscV(core) => core.is_busy(),
+        }
+    }
+
+    pub fn set_hold(&mut self, hold: bool) {
+        match self {
+            Self::Arm(core) => core.hold = hold,
+            Self::RiscV(core) => core.hold = hold,
+        }
+    }
+
+    /// Write back the result of the last instruction still in the pipeline, only the
+    /// Hazard3 holds one
+    pub fn flush_bypass(&mut self) {
+        if let Self::RiscV(core) = self {
+            core.flush_bypass();
+        }
+    }
+
+    pub fn halt(&mut self) {
+        if let Self::RiscV(core) = self {
+            core.halt();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Self::RiscV(core) = self {
+            core.resume();
+        }
+    }
+
+    /// Whether the CSRs raise the exceptions mandated by the spec, see [`hazard3::csrs::Csrs::strict`]
+    pub fn set_strict_csrs(&mut self, strict: bool) {
+        if let Self::RiscV(core) = self {
+            core.csrs.strict = strict;
+        }
+    }
+
+    /// Cycles of the interrupt entry and exit, only modeled on the Hazard3
+    pub fn set_interrupt_latency(&mut self, latency: InterruptLatency) {
+        if let Self::RiscV(core) = self {
+            core.interrupt_latency = latency;
+        }
+    }
+
+    /// Hardware breakpoint or watchpoint in one of the trigger slots,
+    /// `None` clears the slot. Returns false when the core has no such slot
+    pub fn set_trigger(&mut self, index: usize, trigger: Option<Trigger>) -> bool {
+        match self {
+            Self::RiscV(core) if index < NUM_TRIGGERS => {
+                core.csrs.triggers[index] = trigger.unwrap_or_default();
+                true
+            }
+            _ => false,
         }
     }
 }
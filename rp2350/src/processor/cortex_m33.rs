@@ -3,42 +3,1069 @@
  * @author Nguyen Le Duy
  * @date 02/01/2025
  * @brief Cortex-M33 processor architecture implementation
- * @todo just a placeholder for now, need to implement the Cortex-M33 architecture
+ * @note Only the Secure state is modelled, the FPU and the coprocessors are not implemented
  */
-use super::CpuArchitecture;
-use super::ProcessorContext;
+mod exec;
+pub mod nvic;
+pub mod registers;
+pub mod systick;
+
+use super::{CpuArchitecture, ProcessorContext};
+use crate::InspectionEvent;
+use crate::bus::{BusAccessContext, LoadStatus, StoreStatus};
+use crate::common::*;
+use core::mem;
+use exec::*;
+use nvic::*;
+pub use registers::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use systick::SysTick;
+
+/// System Control Space inside the Private Peripheral Bus
+const SCS_START: u32 = 0xE000_E000;
+const SCS_END: u32 = 0xE000_F000;
+const PPB_START: u32 = 0xE000_0000;
+const PPB_END: u32 = 0xE010_0000;
+/// SysTick registers inside the System Control Space
+const SYSTICK_START: u32 = 0xE000_E010;
+const SYSTICK_END: u32 = 0xE000_E020;
+
+/// Debug components of the Private Peripheral Bus, they are not modelled and read as zero.
+/// The rest of the PPB holds nothing and faults
+const PPB_DEBUG: [(u32, u32); 4] = [
+    (0xE000_0000, 0xE000_3000), // ITM, DWT and FPB
+    (0xE002_E000, 0xE002_F000), // Non-secure alias of the System Control Space
+    (0xE004_0000, 0xE004_3000), // TPIU, ETM and CTI
+    (0xE00F_F000, PPB_END),     // ROM table
+];
+
+fn is_ppb_debug(address: u32) -> bool {
+    PPB_DEBUG
+        .iter()
+        .any(|(start, end)| (*start..*end).contains(&address))
+}
+
+/// Value for the PC when the core locks up
+const LOCKUP_ADDRESS: u32 = 0xEFFF_FFFE;
+
+const EXC_RETURN_HANDLER: u32 = 0xFFFF_FFF1;
+const EXC_RETURN_THREAD_MSP: u32 = 0xFFFF_FFF9;
+const EXC_RETURN_THREAD_PSP: u32 = 0xFFFF_FFFD;
+
+/// Where the value of a load ends up once the bus returns it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadTarget {
+    Register(Register),
+    /// Interworking branch, which may also be an exception return
+    Pc,
+    /// The return address while unstacking an exception frame
+    ReturnAddress,
+    /// The xPSR while unstacking an exception frame
+    Xpsr,
+    /// TBB/TBH, holds the PC the table offset is relative to
+    TableBranch(u32),
+}
+
+/// Single bus access of an instruction or of the exception entry and return sequences
+#[derive(Debug, Clone, Copy)]
+pub(super) enum MicroOp {
+    Load {
+        address: u32,
+        size: DataSize,
+        signed: bool,
+        exclusive: bool,
+        target: LoadTarget,
+    },
+    Store {
+        address: u32,
+        value: u32,
+        size: DataSize,
+        /// STREX, the status is written to the register
+        exclusive: Option<Register>,
+    },
+}
 
 #[derive(Default)]
+pub enum State {
+    /// Fetch the initial stack pointer and the reset vector from the vector table
+    Reset,
+    Stall(u8),
+    BusWaitLoad(LoadTarget, Rc<RefCell<LoadStatus>>),
+    BusWaitStore(Option<Register>, Rc<RefCell<StoreStatus>>),
+    Wfi,
+    Wfe,
+    /// A fault happened while it could not be handled, only a reset gets out of here
+    Lockup,
+    Sleep(Box<State>),
+
+    #[default]
+    Normal,
+}
+
+impl PartialEq for State {
+    // Just need to compare the variant, not the contents
+    fn eq(&self, other: &Self) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+}
+
+impl Eq for State {}
+
 pub struct CortexM33 {
-    // TODO
+    pub pc: u32,
+    pub state: State,
+    pub registers: Registers,
+    pub nvic: Nvic,
+    pub systick: SysTick,
+    pub core_id: u8,
+
+    // for LDREX/STREX
+    pub(super) local_monitor: bool,
+    // for WFE/SEV
+    pub(super) event_register: bool,
+
+    pub(super) micro_ops: VecDeque<MicroOp>,
+    /// Set while the pending micro ops are stacking or unstacking an exception frame
+    frame_in_progress: bool,
+    /// Address of the last executed instruction, the return address of a precise fault
+    instruction_pc: u32,
+    /// Instructions executed since the core was created
+    retired: u64,
+    /// Kept from starting the next instruction, the one in flight still completes
+    pub hold: bool,
+}
+
+/// Architectural state of the core, pipeline state is not included
+#[derive(Clone)]
+pub struct CortexM33Snapshot {
+    pub pc: u32,
+    pub registers: Registers,
+    pub nvic: Nvic,
+    pub systick: SysTick,
+    pub sleeping: bool,
+    pub wfi: bool,
+    pub retired: u64,
+}
+
+impl Default for CortexM33 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CortexM33 {
+    pub fn new() -> Self {
+        Self {
+            pc: 0,
+            state: State::Reset,
+            registers: Registers::default(),
+            nvic: Nvic::default(),
+            systick: SysTick::default(),
+            core_id: 0,
+            local_monitor: false,
+            event_register: false,
+            micro_ops: VecDeque::new(),
+            frame_in_progress: false,
+            instruction_pc: 0,
+            retired: 0,
+            hold: false,
+        }
+    }
+
+    /// Restart the core from the vector table at `vtor`, the bootrom uses the one at address 0
+    pub fn boot_from(&mut self, vtor: u32) {
+        self.nvic.vtor = vtor;
+        self.micro_ops.clear();
+        self.state = State::Reset;
+    }
+
+    pub fn is_locked_up(&self) -> bool {
+        self.state == State::Lockup
+    }
+
+    pub fn retired(&self) -> u64 {
+        self.retired
+    }
+
+    /// The core was asleep for `cycles` without being ticked, the SysTick counted on
+    pub fn slept(&mut self, cycles: u64) {
+        if self.systick.advance(cycles) {
+            self.nvic.set_pending(SYS_TICK, true);
+        }
+    }
+
+    /// Still working on the last instruction, stalled or waiting on the bus
+    pub fn is_busy(&self) -> bool {
+        let busy = matches!(
+            self.state,
+            State::Stall(_) | State::BusWaitLoad(..) | State::BusWaitStore(..)
+        );
+
+        busy || !self.micro_ops.is_empty()
+    }
+
+    pub fn snapshot(&self) -> CortexM33Snapshot {
+        let inner_state = match &self.state {
+            State::Sleep(state) => state.as_ref(),
+            state => state,
+        };
+
+        CortexM33Snapshot {
+            pc: self.pc,
+            registers: self.registers.clone(),
+            nvic: self.nvic.clone(),
+            systick: self.systick.clone(),
+            sleeping: matches!(self.state, State::Sleep(_)),
+            wfi: *inner_state == State::Wfi,
+            retired: self.retired,
+        }
+    }
+
+    /// Restore the state from a snapshot.
+    /// Any in-flight bus transaction is dropped, the core continues at the instruction boundary of the snapshot
+    pub fn restore(&mut self, snapshot: &CortexM33Snapshot) {
+        self.pc = snapshot.pc;
+        self.registers = snapshot.registers.clone();
+        self.nvic = snapshot.nvic.clone();
+        self.systick = snapshot.systick.clone();
+        self.retired = snapshot.retired;
+        self.local_monitor = false;
+        self.micro_ops.clear();
+        self.frame_in_progress = false;
+        self.state = if snapshot.wfi {
+            State::Wfi
+        } else {
+            State::Normal
+        };
+
+        if snapshot.sleeping {
+            self.sleep();
+        }
+    }
 }
 
 impl CpuArchitecture for CortexM33 {
-    fn set_core_id(&mut self, _core_id: u8) {
-        todo!()
+    fn set_core_id(&mut self, core_id: u8) {
+        self.core_id = core_id;
     }
 
     fn get_pc(&self) -> u32 {
-        todo!()
+        self.pc
     }
 
-    fn set_pc(&mut self, _value: u32) {
-        todo!()
+    fn set_pc(&mut self, value: u32) {
+        self.pc = value & !1;
+
+        if self.state == State::Reset {
+            self.state = State::Normal;
+        }
     }
 
-    fn tick(&mut self, _ctx: &mut ProcessorContext) {
-        todo!()
+    fn get_sp(&self) -> u32 {
+        self.registers.read(SP)
+    }
+
+    fn set_sp(&mut self, value: u32) {
+        self.registers.write(SP, value);
+    }
+
+    fn tick(&mut self, ctx: &mut ProcessorContext) {
+        match self.state {
+            State::Sleep(_) | State::Lockup => return,
+            State::Reset => return self.boot(ctx),
+            _ => {}
+        }
+
+        if self.systick.advance(1) {
+            self.nvic.set_pending(SYS_TICK, true);
+        }
+
+        self.update_state(ctx);
+
+        if self.state != State::Normal {
+            // The processor is in a state where it is waiting for something
+            return;
+        }
+
+        if let Some(op) = self.micro_ops.pop_front() {
+            return self.issue(op, ctx);
+        }
+
+        self.frame_in_progress = false;
+
+        if self.hold {
+            return;
+        }
+
+        ctx.interrupts
+            .borrow_mut()
+            .set_routing(self.core_id, self.nvic.routing());
+
+        // Pending exceptions are taken between instructions
+        let pending = self
+            .nvic
+            .highest_pending(self.core_id, &ctx.interrupts.borrow());
+
+        let preempts = pending.filter(|&exception| {
+            self.nvic.exception_priority(exception) < self.execution_priority()
+        });
+
+        if let Some(exception) = preempts {
+            ctx.inspector.emit(InspectionEvent::InterruptTaken {
+                core: self.core_id,
+                number: exception as u32,
+            });
+
+            return self.exception_entry(exception, self.pc, ctx);
+        }
+
+        self.step(ctx);
     }
 
     fn sleep(&mut self) {
-        todo!()
+        let last_state = mem::take(&mut self.state);
+        self.state = State::Sleep(Box::new(last_state));
     }
 
     fn wake(&mut self) {
-        todo!()
+        match mem::take(&mut self.state) {
+            State::Sleep(state) => self.state = *state,
+            State::Wfe => self.state = State::Normal,
+            state => {
+                // SEV from the other core while this one is busy, the next WFE falls through
+                self.state = state;
+                self.event_register = true;
+            }
+        }
+    }
+}
+
+impl CortexM33 {
+    fn boot(&mut self, ctx: &mut ProcessorContext) {
+        let vtor = self.nvic.vtor;
+
+        let bus_ctx = self.bus_context(DataSize::Word, false, false);
+
+        match (ctx.bus.fetch(vtor, bus_ctx), ctx.bus.fetch(vtor + 4, bus_ctx)) {
+            (Ok(sp), Ok(reset_vector)) => {
+                self.registers.msp = sp & !0b11;
+                self.registers.lr = 0xFFFF_FFFF;
+                self.pc = reset_vector & !1;
+                self.state = State::Normal;
+            }
+            _ => self.lockup(),
+        }
+    }
+
+    fn lockup(&mut self) {
+        log::error!(
+            "Core {}: Cortex-M33 locked up at {:#010x}",
+            self.core_id,
+            self.instruction_pc
+        );
+        self.micro_ops.clear();
+        self.pc = LOCKUP_ADDRESS;
+        self.state = State::Lockup;
     }
 
-    fn set_sp(&mut self, _value: u32) {
-        todo!()
+    fn bus_context(&self, size: DataSize, signed: bool, exclusive: bool) -> BusAccessContext {
+        BusAccessContext {
+            size,
+            signed,
+            exclusive,
+            secure: true,
+            privileged: self.registers.is_privileged(),
+            architecture: ArchitectureType::CortexM33,
+            requestor: match self.core_id {
+                0 => Requestor::Proc0,
+                1 => Requestor::Proc1,
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    /// Execution priority, taking the masking registers into account
+    fn execution_priority(&self) -> i16 {
+        let mut priority = self.nvic.active_priority();
+
+        if self.registers.basepri != 0 {
+            priority = priority.min(self.registers.basepri as i16);
+        }
+
+        if self.registers.primask {
+            priority = priority.min(0);
+        }
+
+        if self.registers.faultmask {
+            priority = priority.min(-1);
+        }
+
+        priority
+    }
+
+    /// Pending exception which would preempt the current execution, ignoring PRIMASK.
+    /// It wakes the core from WFI and WFE even if it is not taken right away.
+    fn has_wakeup_event(&self, ctx: &ProcessorContext) -> bool {
+        self.nvic
+            .highest_pending(self.core_id, &ctx.interrupts.borrow())
+            .is_some_and(|v| self.nvic.exception_priority(v) < self.nvic.active_priority())
+    }
+
+    /// Nothing to wake up for, the core is skipped until an interrupt line rises
+    /// or the other core sends an event
+    fn sleep_until_event(&self, ctx: &ProcessorContext) {
+        // the SysTick is inside the core, it only wakes it up if the core keeps ticking
+        if self.systick.is_interrupting() {
+            return;
+        }
+
+        ctx.interrupts
+            .borrow_mut()
+            .sleep_until_interrupt(self.core_id);
+    }
+
+    fn step(&mut self, ctx: &mut ProcessorContext) {
+        self.instruction_pc = self.pc;
+
+        let bus_ctx = self.bus_context(DataSize::Word, false, false);
+        let Ok(code) = ctx.bus.fetch(self.pc, bus_ctx) else {
+            return self.fault(BUS_FAULT, CFSR_IBUSERR, ctx);
+        };
+
+        let pc = self.pc;
+        let in_it_block = self.registers.in_it_block();
+
+        let mut exec_ctx = ExecContext::new(self, pc);
+        exec_instruction(code, &mut exec_ctx);
+
+        let ExecContext {
+            next_pc,
+            cycles,
+            fault,
+            size,
+            instruction_name,
+            wake_opposite_core,
+            ..
+        } = exec_ctx;
+
+        ctx.inspector.emit(InspectionEvent::ExecutedInstruction {
+            core: self.core_id,
+            instruction: if size == 2 { code & 0xFFFF } else { code },
+            address: pc,
+            name: instruction_name,
+            operands: Vec::new(), // TODO
+        });
+
+        self.retired += 1;
+
+        if let Some(fault) = fault {
+            return self.raise(fault, next_pc, ctx);
+        }
+
+        if in_it_block {
+            self.registers.advance_it();
+        }
+
+        self.pc = next_pc;
+        ctx.wake_opposite_core |= wake_opposite_core;
+
+        if self.state != State::Normal {
+            // WFI or WFE
+            return;
+        }
+
+        if cycles > 1 {
+            self.state = State::Stall(cycles - 1);
+        } else if let Some(op) = self.micro_ops.pop_front() {
+            // The first bus access starts in the same cycle
+            self.issue(op, ctx);
+        }
+    }
+
+    fn update_state(&mut self, ctx: &mut ProcessorContext) {
+        match mem::take(&mut self.state) {
+            State::Stall(cycles) => {
+                if cycles > 1 {
+                    self.state = State::Stall(cycles - 1);
+                }
+            }
+            State::BusWaitLoad(target, load_status) => match *load_status.clone().borrow() {
+                LoadStatus::Waiting => self.state = State::BusWaitLoad(target, load_status),
+                LoadStatus::Done(value) => self.complete_load(target, value, ctx),
+                LoadStatus::ExclusiveDone(value) => {
+                    self.local_monitor = true;
+                    self.complete_load(target, value, ctx);
+                }
+                LoadStatus::Error(_e) => self.fault(BUS_FAULT, CFSR_PRECISERR, ctx),
+            },
+            State::BusWaitStore(status_register, store_status) => {
+                match *store_status.clone().borrow() {
+                    StoreStatus::Waiting => {
+                        self.state = State::BusWaitStore(status_register, store_status)
+                    }
+                    StoreStatus::Done => {}
+                    StoreStatus::ExclusiveDone => {
+                        self.local_monitor = false;
+                        if let Some(rd) = status_register {
+                            self.registers.write(rd, 0);
+                        }
+                    }
+                    StoreStatus::Error(_e) => self.fault(BUS_FAULT, CFSR_PRECISERR, ctx),
+                }
+            }
+            State::Wfi => {
+                if !self.has_wakeup_event(ctx) {
+                    self.state = State::Wfi;
+                    self.sleep_until_event(ctx);
+                }
+            }
+            State::Wfe => {
+                if self.event_register {
+                    self.event_register = false;
+                } else if !self.has_wakeup_event(ctx) {
+                    self.state = State::Wfe;
+                    self.sleep_until_event(ctx);
+                }
+            }
+            state => self.state = state,
+        }
+    }
+
+    fn issue(&mut self, op: MicroOp, ctx: &mut ProcessorContext) {
+        match op {
+            MicroOp::Load {
+                address, target, ..
+            } if (PPB_START..PPB_END).contains(&address) => match self.ppb_read(address, ctx) {
+                Some(value) => self.complete_load(target, value, ctx),
+                None => self.data_bus_fault(address, ctx),
+            },
+
+            MicroOp::Store {
+                address,
+                value,
+                size,
+                exclusive,
+            } if (PPB_START..PPB_END).contains(&address) => {
+                if !self.ppb_write(address, value, size, ctx) {
+                    return self.data_bus_fault(address, ctx);
+                }
+
+                if let Some(rd) = exclusive {
+                    self.registers.write(rd, 0);
+                }
+            }
+
+            MicroOp::Load {
+                address,
+                size,
+                signed,
+                exclusive,
+                target,
+            } => match ctx
+                .bus
+                .load(address, self.bus_context(size, signed, exclusive))
+            {
+                Ok(status) => self.state = State::BusWaitLoad(target, status),
+                Err(_e) => self.data_bus_fault(address, ctx),
+            },
+
+            MicroOp::Store {
+                exclusive: Some(rd),
+                ..
+            } if !self.local_monitor => {
+                // The exclusive access was lost, the store does not happen
+                self.registers.write(rd, 1);
+            }
+
+            MicroOp::Store {
+                address,
+                value,
+                size,
+                exclusive,
+            } => {
+                let bus_ctx = self.bus_context(size, false, exclusive.is_some());
+                match ctx.bus.store(address, value, bus_ctx) {
+                    Ok(status) => self.state = State::BusWaitStore(exclusive, status),
+                    Err(_e) => self.data_bus_fault(address, ctx),
+                }
+            }
+        }
+    }
+
+    fn complete_load(&mut self, target: LoadTarget, value: u32, ctx: &mut ProcessorContext) {
+        match target {
+            LoadTarget::Register(rd) => self.registers.write(rd, value),
+            LoadTarget::Pc => {
+                if self.registers.is_handler_mode() && value >> 24 == 0xFF {
+                    self.exception_return(value);
+                } else if value & 1 == 0 {
+                    self.fault(USAGE_FAULT, CFSR_INVSTATE, ctx);
+                } else {
+                    self.pc = value & !1;
+                }
+            }
+            LoadTarget::ReturnAddress => self.pc = value & !1,
+            LoadTarget::Xpsr => {
+                self.registers.set_xpsr(value);
+
+                // The stack was realigned to 8 bytes on the exception entry
+                if value & (1 << 9) != 0 {
+                    let sp = self.registers.read(SP);
+                    self.registers.write(SP, sp + 4);
+                }
+            }
+            LoadTarget::TableBranch(base) => self.pc = base.wrapping_add(value * 2),
+        }
+    }
+
+    /// None on the addresses of the PPB holding nothing, they fault
+    fn ppb_read(&mut self, address: u32, ctx: &ProcessorContext) -> Option<u32> {
+        let shift = (address & 0b11) * 8;
+
+        let word = match address & !0b11 {
+            SYSTICK_START..SYSTICK_END => self.systick.read(address & !0b11 & 0xFFF),
+            SCS_START..SCS_END => {
+                let offset = (address - SCS_START) & !0b11;
+                self.nvic
+                    .read(offset, self.core_id, &ctx.interrupts.borrow())
+            }
+            _ if is_ppb_debug(address) => {
+                log::warn!(
+                    "Unimplemented Private Peripheral Bus read at {:#X}",
+                    address
+                );
+                0
+            }
+            _ => return None,
+        };
+
+        Some(word >> shift)
+    }
+
+    /// False on the addresses of the PPB holding nothing, they fault
+    fn ppb_write(
+        &mut self,
+        address: u32,
+        value: u32,
+        size: DataSize,
+        ctx: &ProcessorContext,
+    ) -> bool {
+        match address {
+            SYSTICK_START..SYSTICK_END => self.systick.write(address & !0b11 & 0xFFF, value),
+            SCS_START..SCS_END => {
+                let offset = address - SCS_START;
+                let aligned = offset & !0b11;
+                let shift = (offset & 0b11) * 8;
+
+                // Byte and halfword accesses, mostly on the priority registers
+                let value = match size {
+                    DataSize::Word => value,
+                    _ => {
+                        let mask = (((1u64 << (size as u32 * 8)) - 1) as u32) << shift;
+                        let current =
+                            self.nvic
+                                .read(aligned, self.core_id, &ctx.interrupts.borrow());
+                        (current & !mask) | ((value << shift) & mask)
+                    }
+                };
+
+                self.nvic.write(aligned, value);
+            }
+            _ if is_ppb_debug(address) => log::warn!(
+                "Unimplemented Private Peripheral Bus write at {:#X}",
+                address
+            ),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn data_bus_fault(&mut self, address: u32, ctx: &mut ProcessorContext) {
+        self.nvic.bfar = address;
+        self.fault(BUS_FAULT, CFSR_PRECISERR | CFSR_BFARVALID, ctx);
+    }
+
+    /// The exception is reported with its number once it is taken, in `exception_entry`
+    fn raise(&mut self, fault: Fault, next_pc: u32, ctx: &mut ProcessorContext) {
+        match fault {
+            Fault::Undefined => self.fault(USAGE_FAULT, CFSR_UNDEFINSTR, ctx),
+            Fault::NoCoprocessor => self.fault(USAGE_FAULT, CFSR_NOCP, ctx),
+            Fault::InvalidState => self.fault(USAGE_FAULT, CFSR_INVSTATE, ctx),
+            Fault::Unaligned => self.fault(USAGE_FAULT, CFSR_UNALIGNED, ctx),
+            Fault::DivideByZero => self.fault(USAGE_FAULT, CFSR_DIVBYZERO, ctx),
+            Fault::Breakpoint => {
+                // No debugger is attached to catch it
+                self.nvic.hfsr |= 1 << 31;
+                self.fault(HARD_FAULT, 0, ctx);
+            }
+            Fault::SupervisorCall => {
+                if self.nvic.exception_priority(SV_CALL) < self.execution_priority() {
+                    self.exception_entry(SV_CALL, next_pc, ctx);
+                } else {
+                    self.nvic.hfsr |= HFSR_FORCED;
+                    self.escalate(ctx);
+                }
+            }
+        }
+    }
+
+    /// Synchronous fault of the current instruction
+    fn fault(&mut self, exception: ExceptionNumber, status: u32, ctx: &mut ProcessorContext) {
+        self.nvic.cfsr |= status;
+        self.micro_ops.clear();
+
+        if self.frame_in_progress {
+            // Faults while stacking or unstacking are not recoverable here
+            return self.lockup();
+        }
+
+        let can_be_taken = self.nvic.is_fault_enabled(exception)
+            && self.nvic.exception_priority(exception) < self.execution_priority();
+
+        if exception != HARD_FAULT && !can_be_taken {
+            self.nvic.hfsr |= HFSR_FORCED;
+            return self.escalate(ctx);
+        }
+
+        if exception == HARD_FAULT {
+            return self.escalate(ctx);
+        }
+
+        self.exception_entry(exception, self.instruction_pc, ctx);
+    }
+
+    fn escalate(&mut self, ctx: &mut ProcessorContext) {
+        if self.execution_priority() <= self.nvic.exception_priority(HARD_FAULT) {
+            // Already in HardFault or NMI
+            return self.lockup();
+        }
+
+        self.exception_entry(HARD_FAULT, self.instruction_pc, ctx);
+    }
+
+    fn exception_entry(
+        &mut self,
+        exception: ExceptionNumber,
+        return_address: u32,
+        ctx: &mut ProcessorContext,
+    ) {
+        let address = self.nvic.vtor + 4 * exception as u32;
+        let bus_ctx = self.bus_context(DataSize::Word, false, false);
+        let Ok(vector) = ctx.bus.fetch(address, bus_ctx) else {
+            return self.lockup();
+        };
+
+        ctx.inspector.emit(InspectionEvent::Exception {
+            core: self.core_id,
+            exception: exception as u32,
+        });
+
+        let sp = self.registers.read(SP);
+        let realign = sp & 0b100 != 0;
+        let frame = sp.wrapping_sub(0x20) & !0b111;
+
+        let values = [
+            self.registers.read(0),
+            self.registers.read(1),
+            self.registers.read(2),
+            self.registers.read(3),
+            self.registers.read(12),
+            self.registers.lr,
+            return_address,
+            self.registers.xpsr() | (realign as u32) << 9,
+        ];
+
+        self.micro_ops.clear();
+        for (i, value) in values.into_iter().enumerate() {
+            self.micro_ops.push_back(MicroOp::Store {
+                address: frame + 4 * i as u32,
+                value,
+                size: DataSize::Word,
+                exclusive: None,
+            });
+        }
+
+        // written before the mode changes to update the right stack pointer
+        self.registers.write(SP, frame);
+
+        self.registers.lr = if self.registers.is_handler_mode() {
+            EXC_RETURN_HANDLER
+        } else if self.registers.is_using_psp() {
+            EXC_RETURN_THREAD_PSP
+        } else {
+            EXC_RETURN_THREAD_MSP
+        };
+
+        self.registers.ipsr = exception;
+        self.registers.it_state = 0;
+        self.nvic.set_pending(exception, false);
+        self.nvic.set_active(exception, true);
+        self.local_monitor = false;
+        self.frame_in_progress = true;
+
+        self.pc = vector & !1;
+        self.state = State::Normal;
+    }
+
+    pub(super) fn exception_return(&mut self, exc_return: u32) {
+        let exception = self.registers.ipsr;
+        self.nvic.set_active(exception, false);
+
+        if exc_return & 0b1000 != 0 {
+            self.registers.ipsr = 0;
+            if exc_return & 0b100 != 0 {
+                self.registers.control |= CONTROL_SPSEL;
+            } else {
+                self.registers.control &= !CONTROL_SPSEL;
+            }
+        } else {
+            // The real number is restored together with the xPSR,
+            // it only has to select the main stack until then
+            self.registers.ipsr = HARD_FAULT;
+        }
+
+        let frame = self.registers.read(SP);
+        let targets = [
+            LoadTarget::Register(0),
+            LoadTarget::Register(1),
+            LoadTarget::Register(2),
+            LoadTarget::Register(3),
+            LoadTarget::Register(12),
+            LoadTarget::Register(LR),
+            LoadTarget::ReturnAddress,
+            LoadTarget::Xpsr,
+        ];
+
+        self.micro_ops.clear();
+        for (i, target) in targets.into_iter().enumerate() {
+            self.micro_ops.push_back(MicroOp::Load {
+                address: frame + 4 * i as u32,
+                size: DataSize::Word,
+                signed: false,
+                exclusive: false,
+                target,
+            });
+        }
+
+        self.registers.write(SP, frame + 0x20);
+        self.local_monitor = false;
+        self.frame_in_progress = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::inspector::*;
+    use crate::interrupts::Interrupts;
+    use crate::processor::ProcessorContext;
+
+    const SRAM: u32 = 0x2000_0000;
+
+    macro_rules! setup {
+        ($cpu:tt, $ctx:tt) => {
+            let mut $cpu = CortexM33::new();
+            let mut bus = Bus::default();
+
+            $cpu.set_pc(SRAM);
+            $cpu.registers.msp = SRAM + 0x1000;
+
+            let mut $ctx = ProcessorContext {
+                bus: &mut bus,
+                wake_opposite_core: false,
+                interrupts: Default::default(),
+                inspector: InspectorRef::default(),
+            };
+        };
+    }
+
+    fn write_code(ctx: &mut ProcessorContext, code: &[u16]) {
+        for (i, hw) in code.iter().enumerate() {
+            ctx.bus.sram.write_u16(i as u32 * 2, *hw).unwrap();
+        }
+    }
+
+    fn run(cpu: &mut CortexM33, ctx: &mut ProcessorContext, ticks: usize) {
+        for _ in 0..ticks {
+            ctx.bus.tick();
+            cpu.tick(ctx);
+        }
+    }
+
+    #[test]
+    fn test_boot_from_vector_table() {
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0x100, 0x2000_4000).unwrap();
+        ctx.bus.sram.write_u32(0x104, 0x2000_0201).unwrap();
+
+        cpu.boot_from(SRAM + 0x100);
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.registers.msp, 0x2000_4000);
+        assert_eq!(cpu.pc, 0x2000_0200);
+        assert!(cpu.state == State::Normal);
+    }
+
+    #[test]
+    fn test_data_processing_and_flags() {
+        setup!(cpu, ctx);
+        write_code(
+            &mut ctx,
+            &[
+                0x2005, // movs r0, #5
+                0x2107, // movs r1, #7
+                0x1a42, // subs r2, r0, r1
+                0xf240, 0x1334, // movw r3, #0x134
+                0xf2c1, 0x2300, // movt r3, #0x1200
+            ],
+        );
+
+        run(&mut cpu, &mut ctx, 5);
+        assert_eq!(cpu.registers.read(2), -2i32 as u32);
+        assert!(cpu.registers.flags.n);
+        assert!(!cpu.registers.flags.c);
+        assert_eq!(cpu.registers.read(3), 0x1200_0134);
+    }
+
+    #[test]
+    fn test_push_pop_and_call() {
+        setup!(cpu, ctx);
+        write_code(
+            &mut ctx,
+            &[
+                0x2101, // movs r1, #1
+                0xf000, 0xf801, // bl +2
+                0xe7fe, // b .
+                0xb502, // push {r1, lr}
+                0x2102, // movs r1, #2
+                0xbd02, // pop {r1, pc}
+            ],
+        );
+
+        run(&mut cpu, &mut ctx, 20);
+        assert_eq!(cpu.pc, SRAM + 6);
+        assert_eq!(cpu.registers.read(1), 1);
+        assert_eq!(cpu.registers.read(SP), SRAM + 0x1000);
+    }
+
+    #[test]
+    fn test_it_block() {
+        setup!(cpu, ctx);
+        write_code(
+            &mut ctx,
+            &[
+                0x2800, // cmp r0, #0
+                0xbf0c, // ite eq
+                0x2101, // moveq r1, #1
+                0x2102, // movne r1, #2
+            ],
+        );
+
+        run(&mut cpu, &mut ctx, 4);
+        assert_eq!(cpu.registers.read(1), 1);
+        assert!(!cpu.registers.in_it_block());
+    }
+
+    #[test]
+    fn test_interrupt_entry_and_return() {
+        setup!(cpu, ctx);
+        // vector table at the start of the SRAM, the code after it
+        cpu.nvic.vtor = SRAM;
+        let irq = Interrupts::TIMER0_IRQ_0;
+        let handler = SRAM + 0x200;
+        ctx.bus
+            .sram
+            .write_u32(4 * (IRQ_BASE as u32 + irq as u32), handler | 1)
+            .unwrap();
+
+        ctx.bus.sram.write_u16(0x100, 0xe7fe).unwrap(); // b .
+        ctx.bus.sram.write_u16(0x200, 0x2442).unwrap(); // movs r4, #0x42
+        ctx.bus.sram.write_u16(0x202, 0x4770).unwrap(); // bx lr
+        cpu.set_pc(SRAM + 0x100);
+
+        cpu.nvic.write(NVIC_ISER0, 1 << irq);
+        ctx.interrupts.borrow_mut().set_irq(irq, true);
+        run(&mut cpu, &mut ctx, 1);
+        assert_eq!(cpu.pc, handler);
+        assert_eq!(cpu.registers.ipsr, IRQ_BASE + irq as u16);
+        assert_eq!(cpu.registers.lr, EXC_RETURN_THREAD_MSP);
+
+        ctx.interrupts.borrow_mut().set_irq(irq, false);
+        run(&mut cpu, &mut ctx, 60);
+        // r4 is not part of the frame, r0 to r3 come back from the stack
+        assert_eq!(cpu.registers.read(4), 0x42);
+        assert_eq!(cpu.registers.ipsr, 0);
+        assert_eq!(cpu.pc, SRAM + 0x100);
+        assert_eq!(cpu.registers.read(SP), SRAM + 0x1000);
+        assert!(!cpu.nvic.is_active(IRQ_BASE + irq as u16));
+    }
+
+    #[test]
+    fn test_undefined_instruction_escalates_to_hard_fault() {
+        setup!(cpu, ctx);
+        cpu.nvic.vtor = SRAM;
+        ctx.bus
+            .sram
+            .write_u32(4 * HARD_FAULT as u32, SRAM + 0x201)
+            .unwrap();
+        ctx.bus.sram.write_u16(0x100, 0xde00).unwrap(); // udf #0
+        cpu.set_pc(SRAM + 0x100);
+
+        run(&mut cpu, &mut ctx, 1);
+        assert_eq!(cpu.pc, SRAM + 0x200);
+        assert_eq!(cpu.registers.ipsr, HARD_FAULT);
+        assert_ne!(cpu.nvic.cfsr & CFSR_UNDEFINSTR, 0);
+        assert_ne!(cpu.nvic.hfsr & HFSR_FORCED, 0);
+    }
+
+    #[test]
+    fn test_systick_exception() {
+        setup!(cpu, ctx);
+        cpu.nvic.vtor = SRAM;
+        ctx.bus
+            .sram
+            .write_u32(4 * SYS_TICK as u32, SRAM + 0x201)
+            .unwrap();
+        ctx.bus.sram.write_u16(0x100, 0xe7fe).unwrap(); // b .
+        ctx.bus.sram.write_u16(0x200, 0xe7fe).unwrap(); // b .
+        cpu.set_pc(SRAM + 0x100);
+
+        // loads 10 on the first cycle, then wraps 10 cycles later
+        cpu.systick.write(systick::SYST_RVR, 10);
+        cpu.systick.write(systick::SYST_CSR, 0b111);
+        run(&mut cpu, &mut ctx, 9);
+        assert_eq!(cpu.registers.ipsr, 0);
+        run(&mut cpu, &mut ctx, 4);
+        assert_eq!(cpu.registers.ipsr, SYS_TICK);
+        assert_eq!(cpu.pc, SRAM + 0x200);
+    }
+
+    #[test]
+    fn test_ppb_decode() {
+        setup!(cpu, ctx);
+        cpu.nvic.vtor = SRAM;
+        ctx.bus
+            .sram
+            .write_u32(4 * HARD_FAULT as u32, SRAM + 0x201)
+            .unwrap();
+        ctx.bus.sram.write_u16(0x100, 0x6808).unwrap(); // ldr r0, [r1]
+        ctx.bus.sram.write_u16(0x102, 0x6813).unwrap(); // ldr r3, [r2]
+        ctx.bus.sram.write_u16(0x104, 0xe7fe).unwrap(); // b .
+        ctx.bus.sram.write_u16(0x200, 0xe7fe).unwrap(); // b .
+        cpu.set_pc(SRAM + 0x100);
+
+        // the DWT is not modelled and reads as zero, nothing is at 0xE000_5000
+        cpu.registers.write(0, 0x1234);
+        cpu.registers.write(1, 0xE000_1004);
+        cpu.registers.write(2, 0xE000_5000);
+        run(&mut cpu, &mut ctx, 20);
+        assert_eq!(cpu.registers.read(0), 0);
+        assert_eq!(cpu.registers.ipsr, HARD_FAULT);
+        assert_eq!(cpu.pc, SRAM + 0x200);
+        assert_eq!(cpu.nvic.bfar, 0xE000_5000);
+        assert_ne!(cpu.nvic.cfsr & CFSR_BFARVALID, 0);
     }
 }
@@ -0,0 +1,1706 @@
+/**
+ * @file processor/cortex_m33/exec.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Thumb and Thumb-2 execution unit of the Cortex-M33
+ */
+use super::nvic::CCR_DIV_0_TRP;
+use super::registers::*;
+use super::{CortexM33, LoadTarget, MicroOp, State};
+use crate::common::DataSize;
+use crate::utils::{extract_bit, extract_bits, sign_extend};
+
+/// Synchronous exceptions caused by an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Fault {
+    Undefined,
+    NoCoprocessor,
+    InvalidState,
+    Unaligned,
+    DivideByZero,
+    Breakpoint,
+    SupervisorCall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shift {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+    Rrx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AluOp {
+    And,
+    Bic,
+    Orr,
+    Orn,
+    Eor,
+    Add,
+    Adc,
+    Sbc,
+    Sub,
+    Rsb,
+    Mov,
+    Mvn,
+    Tst,
+    Teq,
+    Cmp,
+    Cmn,
+}
+
+impl AluOp {
+    fn name(&self) -> &'static str {
+        match self {
+            AluOp::And => "AND",
+            AluOp::Bic => "BIC",
+            AluOp::Orr => "ORR",
+            AluOp::Orn => "ORN",
+            AluOp::Eor => "EOR",
+            AluOp::Add => "ADD",
+            AluOp::Adc => "ADC",
+            AluOp::Sbc => "SBC",
+            AluOp::Sub => "SUB",
+            AluOp::Rsb => "RSB",
+            AluOp::Mov => "MOV",
+            AluOp::Mvn => "MVN",
+            AluOp::Tst => "TST",
+            AluOp::Teq => "TEQ",
+            AluOp::Cmp => "CMP",
+            AluOp::Cmn => "CMN",
+        }
+    }
+
+    /// Decode the operation of the 32 bit data processing instructions,
+    /// `rn` and `rd` select the MOV/MVN and the compare forms
+    fn decode(op: u32, rn: u8, rd: u8, setflags: bool) -> Option<Self> {
+        let compare = rd == PC && setflags;
+
+        let op = match op {
+            0b0000 if compare => AluOp::Tst,
+            0b0000 => AluOp::And,
+            0b0001 => AluOp::Bic,
+            0b0010 if rn == PC => AluOp::Mov,
+            0b0010 => AluOp::Orr,
+            0b0011 if rn == PC => AluOp::Mvn,
+            0b0011 => AluOp::Orn,
+            0b0100 if compare => AluOp::Teq,
+            0b0100 => AluOp::Eor,
+            0b1000 if compare => AluOp::Cmn,
+            0b1000 => AluOp::Add,
+            0b1010 => AluOp::Adc,
+            0b1011 => AluOp::Sbc,
+            0b1101 if compare => AluOp::Cmp,
+            0b1101 => AluOp::Sub,
+            0b1110 => AluOp::Rsb,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+}
+
+fn add_with_carry(x: u32, y: u32, carry_in: bool) -> (u32, bool, bool) {
+    let unsigned = x as u64 + y as u64 + carry_in as u64;
+    let signed = x as i32 as i64 + y as i32 as i64 + carry_in as i64;
+    let result = unsigned as u32;
+    (result, unsigned >> 32 != 0, result as i32 as i64 != signed)
+}
+
+fn decode_imm_shift(ty: u32, imm5: u32) -> (Shift, u32) {
+    match ty {
+        0b00 => (Shift::Lsl, imm5),
+        0b01 => (Shift::Lsr, if imm5 == 0 { 32 } else { imm5 }),
+        0b10 => (Shift::Asr, if imm5 == 0 { 32 } else { imm5 }),
+        _ if imm5 == 0 => (Shift::Rrx, 1),
+        _ => (Shift::Ror, imm5),
+    }
+}
+
+fn register_shift(ty: u32) -> Shift {
+    match ty {
+        0b00 => Shift::Lsl,
+        0b01 => Shift::Lsr,
+        0b10 => Shift::Asr,
+        _ => Shift::Ror,
+    }
+}
+
+fn shift_c(value: u32, shift: Shift, amount: u32, carry_in: bool) -> (u32, bool) {
+    if amount == 0 && shift != Shift::Rrx {
+        return (value, carry_in);
+    }
+
+    match shift {
+        Shift::Lsl if amount < 32 => (value << amount, (value >> (32 - amount)) & 1 == 1),
+        Shift::Lsl if amount == 32 => (0, value & 1 == 1),
+        Shift::Lsr if amount < 32 => (value >> amount, (value >> (amount - 1)) & 1 == 1),
+        Shift::Lsr if amount == 32 => (0, value >> 31 == 1),
+        Shift::Lsl | Shift::Lsr => (0, false),
+        Shift::Asr if amount < 32 => (
+            ((value as i32) >> amount) as u32,
+            (value >> (amount - 1)) & 1 == 1,
+        ),
+        Shift::Asr => {
+            let result = ((value as i32) >> 31) as u32;
+            (result, result & 1 == 1)
+        }
+        Shift::Ror => {
+            let result = value.rotate_right(amount % 32);
+            (result, result >> 31 == 1)
+        }
+        Shift::Rrx => ((carry_in as u32) << 31 | value >> 1, value & 1 == 1),
+    }
+}
+
+fn thumb_expand_imm_c(imm12: u32, carry_in: bool) -> (u32, bool) {
+    if imm12 >> 10 == 0 {
+        let imm8 = imm12 & 0xFF;
+        let value = match (imm12 >> 8) & 0b11 {
+            0b00 => imm8,
+            0b01 => imm8 << 16 | imm8,
+            0b10 => imm8 << 24 | imm8 << 8,
+            _ => imm8 * 0x0101_0101,
+        };
+
+        (value, carry_in)
+    } else {
+        let value = (0x80 | (imm12 & 0x7F)).rotate_right(imm12 >> 7);
+        (value, value >> 31 == 1)
+    }
+}
+
+fn signed_saturate(value: i64, bits: u32) -> (u32, bool) {
+    let max = (1i64 << (bits - 1)) - 1;
+    let min = -(1i64 << (bits - 1));
+    let result = value.clamp(min, max);
+    (result as u32, result != value)
+}
+
+fn unsigned_saturate(value: i64, bits: u32) -> (u32, bool) {
+    let max = (1i64 << bits) - 1;
+    let result = value.clamp(0, max);
+    (result as u32, result != value)
+}
+
+const fn align(value: u32, alignment: u32) -> u32 {
+    value & !(alignment - 1)
+}
+
+pub(super) struct ExecContext<'a> {
+    pub(super) core: &'a mut CortexM33,
+    /// Address of the executing instruction
+    pub(super) pc: u32,
+    pub(super) next_pc: u32,
+    pub(super) size: u32,
+    pub(super) cycles: u8,
+    pub(super) fault: Option<Fault>,
+    pub(super) instruction_name: &'static str,
+    pub(super) wake_opposite_core: bool,
+}
+
+impl<'a> ExecContext<'a> {
+    pub fn new(core: &'a mut CortexM33, pc: u32) -> Self {
+        Self {
+            core,
+            pc,
+            next_pc: pc,
+            size: 2,
+            cycles: 1,
+            fault: None,
+            instruction_name: "Unknown",
+            wake_opposite_core: false,
+        }
+    }
+
+    /// Reading the PC gives the address of the instruction plus 4
+    fn read_register(&self, reg: Register) -> u32 {
+        if reg == PC {
+            self.pc.wrapping_add(4)
+        } else {
+            self.core.registers.read(reg)
+        }
+    }
+
+    fn write_register(&mut self, reg: Register, value: u32) {
+        if reg == PC {
+            self.branch(value & !1);
+        } else {
+            self.core.registers.write(reg, value);
+        }
+    }
+
+    fn branch(&mut self, target: u32) {
+        self.next_pc = target;
+        self.cycles = 2;
+    }
+
+    /// BX, BLX and the loads into the PC, this is also how exceptions return
+    fn branch_exchange(&mut self, target: u32) {
+        if self.core.registers.is_handler_mode() && target >> 24 == 0xFF {
+            self.core.exception_return(target);
+            self.cycles = 2;
+        } else if target & 1 == 0 {
+            // There is no Arm state on M profile cores
+            self.fault = Some(Fault::InvalidState);
+        } else {
+            self.branch(target & !1);
+        }
+    }
+
+    /// The 16 bit instructions only update the flags outside of an IT block
+    fn sets_flags(&self) -> bool {
+        !self.core.registers.in_it_block()
+    }
+
+    fn set_nz(&mut self, result: u32) {
+        self.core.registers.flags.n = result >> 31 == 1;
+        self.core.registers.flags.z = result == 0;
+    }
+
+    fn carry(&self) -> bool {
+        self.core.registers.flags.c
+    }
+
+    fn alu(&mut self, op: AluOp, rd: Register, n: u32, m: u32, carry: bool, setflags: bool) {
+        let c = self.carry();
+
+        let (result, carry, overflow) = match op {
+            AluOp::And | AluOp::Tst => (n & m, carry, None),
+            AluOp::Bic => (n & !m, carry, None),
+            AluOp::Orr => (n | m, carry, None),
+            AluOp::Orn => (n | !m, carry, None),
+            AluOp::Eor | AluOp::Teq => (n ^ m, carry, None),
+            AluOp::Mov => (m, carry, None),
+            AluOp::Mvn => (!m, carry, None),
+            AluOp::Add | AluOp::Cmn => {
+                let (r, c, v) = add_with_carry(n, m, false);
+                (r, c, Some(v))
+            }
+            AluOp::Adc => {
+                let (r, c, v) = add_with_carry(n, m, c);
+                (r, c, Some(v))
+            }
+            AluOp::Sbc => {
+                let (r, c, v) = add_with_carry(n, !m, c);
+                (r, c, Some(v))
+            }
+            AluOp::Sub | AluOp::Cmp => {
+                let (r, c, v) = add_with_carry(n, !m, true);
+                (r, c, Some(v))
+            }
+            AluOp::Rsb => {
+                let (r, c, v) = add_with_carry(!n, m, true);
+                (r, c, Some(v))
+            }
+        };
+
+        self.instruction_name = op.name();
+
+        if !matches!(op, AluOp::Tst | AluOp::Teq | AluOp::Cmp | AluOp::Cmn) {
+            self.write_register(rd, result);
+        }
+
+        if setflags {
+            self.set_nz(result);
+            self.core.registers.flags.c = carry;
+            if let Some(overflow) = overflow {
+                self.core.registers.flags.v = overflow;
+            }
+        }
+    }
+
+    fn load(&mut self, address: u32, size: DataSize, signed: bool, rt: Register) {
+        let target = if rt == PC {
+            LoadTarget::Pc
+        } else {
+            LoadTarget::Register(rt)
+        };
+
+        self.core.micro_ops.push_back(MicroOp::Load {
+            address,
+            size,
+            signed,
+            exclusive: false,
+            target,
+        });
+    }
+
+    fn store(&mut self, address: u32, value: u32, size: DataSize) {
+        self.core.micro_ops.push_back(MicroOp::Store {
+            address,
+            value,
+            size,
+            exclusive: None,
+        });
+    }
+
+    fn load_exclusive(&mut self, address: u32, size: DataSize, rt: Register) {
+        self.core.micro_ops.push_back(MicroOp::Load {
+            address,
+            size,
+            signed: false,
+            exclusive: true,
+            target: LoadTarget::Register(rt),
+        });
+    }
+
+    fn store_exclusive(&mut self, address: u32, value: u32, size: DataSize, rd: Register) {
+        self.core.micro_ops.push_back(MicroOp::Store {
+            address,
+            value,
+            size,
+            exclusive: Some(rd),
+        });
+    }
+
+    /// Loads for LDM and POP, in ascending register order from `address`
+    fn load_multiple(&mut self, address: u32, list: u32) {
+        if address & 0b11 != 0 {
+            self.fault = Some(Fault::Unaligned);
+            return;
+        }
+
+        let registers = (0..16).filter(|v| list >> v & 1 == 1);
+        for (i, reg) in registers.enumerate() {
+            self.load(
+                address + 4 * i as u32,
+                DataSize::Word,
+                false,
+                reg as Register,
+            );
+        }
+    }
+
+    /// Stores for STM and PUSH, in ascending register order from `address`
+    fn store_multiple(&mut self, address: u32, list: u32) {
+        if address & 0b11 != 0 {
+            self.fault = Some(Fault::Unaligned);
+            return;
+        }
+
+        let registers = (0..15).filter(|v| list >> v & 1 == 1);
+        for (i, reg) in registers.enumerate() {
+            let value = self.read_register(reg as Register);
+            self.store(address + 4 * i as u32, value, DataSize::Word);
+        }
+    }
+
+    fn exec16(&mut self, code: u32) {
+        let low = |lsb: u32| (code >> lsb & 0b111) as Register;
+        let setflags = self.sets_flags();
+        let c = self.carry();
+
+        match code >> 10 {
+            // Shift (immediate), add, subtract, move, and compare
+            0b000000..=0b001111 => {
+                let opcode = extract_bits(code, 9..=13);
+                let rd = low(0);
+                let rn = low(3);
+                let imm8 = code & 0xFF;
+                let rdn = low(8);
+
+                match opcode >> 2 {
+                    0b000..=0b010 => {
+                        let imm5 = extract_bits(code, 6..=10);
+                        let (shift, amount) = decode_imm_shift(opcode >> 2, imm5);
+                        let (result, carry) = shift_c(self.read_register(rn), shift, amount, c);
+                        self.alu(AluOp::Mov, rd, 0, result, carry, setflags);
+                        self.instruction_name = ["LSL", "LSR", "ASR"][(opcode >> 2) as usize];
+                    }
+                    0b011 => {
+                        let n = self.read_register(rn);
+                        let m = if opcode & 0b10 == 0 {
+                            self.read_register(low(6))
+                        } else {
+                            extract_bits(code, 6..=8)
+                        };
+
+                        let op = if opcode & 1 == 0 {
+                            AluOp::Add
+                        } else {
+                            AluOp::Sub
+                        };
+                        self.alu(op, rd, n, m, c, setflags);
+                    }
+                    0b100 => self.alu(AluOp::Mov, rdn, 0, imm8, c, setflags),
+                    0b101 => {
+                        let n = self.read_register(rdn);
+                        self.alu(AluOp::Cmp, rdn, n, imm8, c, true);
+                    }
+                    0b110 => {
+                        let n = self.read_register(rdn);
+                        self.alu(AluOp::Add, rdn, n, imm8, c, setflags);
+                    }
+                    _ => {
+                        let n = self.read_register(rdn);
+                        self.alu(AluOp::Sub, rdn, n, imm8, c, setflags);
+                    }
+                }
+            }
+
+            // Data processing
+            0b010000 => {
+                let rdn = low(0);
+                let rm = low(3);
+                let n = self.read_register(rdn);
+                let m = self.read_register(rm);
+
+                match extract_bits(code, 6..=9) {
+                    0b0000 => self.alu(AluOp::And, rdn, n, m, c, setflags),
+                    0b0001 => self.alu(AluOp::Eor, rdn, n, m, c, setflags),
+                    op @ (0b0010 | 0b0011 | 0b0100 | 0b0111) => {
+                        let shift = match op {
+                            0b0010 => Shift::Lsl,
+                            0b0011 => Shift::Lsr,
+                            0b0100 => Shift::Asr,
+                            _ => Shift::Ror,
+                        };
+
+                        let (result, carry) = shift_c(n, shift, m & 0xFF, c);
+                        self.alu(AluOp::Mov, rdn, 0, result, carry, setflags);
+                        self.instruction_name = match shift {
+                            Shift::Lsl => "LSL",
+                            Shift::Lsr => "LSR",
+                            Shift::Asr => "ASR",
+                            _ => "ROR",
+                        };
+                    }
+                    0b0101 => self.alu(AluOp::Adc, rdn, n, m, c, setflags),
+                    0b0110 => self.alu(AluOp::Sbc, rdn, n, m, c, setflags),
+                    0b1000 => self.alu(AluOp::Tst, rdn, n, m, c, true),
+                    0b1001 => self.alu(AluOp::Rsb, rdn, m, 0, c, setflags),
+                    0b1010 => self.alu(AluOp::Cmp, rdn, n, m, c, true),
+                    0b1011 => self.alu(AluOp::Cmn, rdn, n, m, c, true),
+                    0b1100 => self.alu(AluOp::Orr, rdn, n, m, c, setflags),
+                    0b1101 => {
+                        let result = n.wrapping_mul(m);
+                        self.write_register(rdn, result);
+                        if setflags {
+                            self.set_nz(result);
+                        }
+                        self.instruction_name = "MUL";
+                    }
+                    0b1110 => self.alu(AluOp::Bic, rdn, n, m, c, setflags),
+                    _ => self.alu(AluOp::Mvn, rdn, n, m, c, setflags),
+                }
+            }
+
+            // Special data instructions and branch and exchange
+            0b010001 => {
+                let rdn = (extract_bit(code, 7) << 3 | code & 0b111) as Register;
+                let rm = extract_bits(code, 3..=6) as Register;
+                let m = self.read_register(rm);
+
+                match extract_bits(code, 8..=9) {
+                    0b00 => {
+                        let n = self.read_register(rdn);
+                        self.alu(AluOp::Add, rdn, n, m, c, false);
+                    }
+                    0b01 => {
+                        let n = self.read_register(rdn);
+                        self.alu(AluOp::Cmp, rdn, n, m, c, true);
+                    }
+                    0b10 => self.alu(AluOp::Mov, rdn, 0, m, c, false),
+                    _ => {
+                        if extract_bit(code, 7) == 1 {
+                            self.core.registers.lr = self.pc.wrapping_add(2) | 1;
+                            self.instruction_name = "BLX";
+                        } else {
+                            self.instruction_name = "BX";
+                        }
+
+                        self.branch_exchange(m);
+                    }
+                }
+            }
+
+            // LDR (literal)
+            0b010010 | 0b010011 => {
+                let address = align(self.read_register(PC), 4) + (code & 0xFF) * 4;
+                self.load(address, DataSize::Word, false, low(8));
+                self.instruction_name = "LDR";
+            }
+
+            // Load/store single data item
+            0b010100..=0b100111 => self.load_store16(code),
+
+            // ADR
+            0b101000 | 0b101001 => {
+                let address = align(self.read_register(PC), 4) + (code & 0xFF) * 4;
+                self.write_register(low(8), address);
+                self.instruction_name = "ADR";
+            }
+
+            // ADD (SP plus immediate)
+            0b101010 | 0b101011 => {
+                let sp = self.read_register(SP);
+                self.write_register(low(8), sp.wrapping_add((code & 0xFF) * 4));
+                self.instruction_name = "ADD";
+            }
+
+            // Miscellaneous 16-bit instructions
+            0b101100..=0b101111 => self.misc16(code),
+
+            // STM, LDM
+            0b110000..=0b110011 => {
+                let rn = low(8);
+                let list = code & 0xFF;
+                let address = self.read_register(rn);
+                let count = list.count_ones();
+
+                if extract_bit(code, 11) == 0 {
+                    self.store_multiple(address, list);
+                    self.write_register(rn, address + 4 * count);
+                    self.instruction_name = "STM";
+                } else {
+                    if list >> rn & 1 == 0 {
+                        self.write_register(rn, address + 4 * count);
+                    }
+                    self.load_multiple(address, list);
+                    self.instruction_name = "LDM";
+                }
+            }
+
+            // Conditional branch and supervisor call
+            0b110100..=0b110111 => match extract_bits(code, 8..=11) {
+                0b1110 => {
+                    self.fault = Some(Fault::Undefined);
+                    self.instruction_name = "UDF";
+                }
+                0b1111 => {
+                    self.fault = Some(Fault::SupervisorCall);
+                    self.instruction_name = "SVC";
+                }
+                cond => {
+                    if self.core.registers.condition_passed(cond) {
+                        let offset = sign_extend((code & 0xFF) << 1, 8);
+                        self.branch(self.read_register(PC).wrapping_add(offset));
+                    }
+                    self.instruction_name = "B";
+                }
+            },
+
+            // Unconditional branch
+            0b111000 | 0b111001 => {
+                let offset = sign_extend((code & 0x7FF) << 1, 11);
+                self.branch(self.read_register(PC).wrapping_add(offset));
+                self.instruction_name = "B";
+            }
+
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn load_store16(&mut self, code: u32) {
+        let rt = (code & 0b111) as Register;
+        let rn = (code >> 3 & 0b111) as Register;
+        let imm5 = extract_bits(code, 6..=10);
+        let n = self.read_register(rn);
+
+        match code >> 12 {
+            0b0101 => {
+                let address = n.wrapping_add(self.read_register((code >> 6 & 0b111) as Register));
+                let value = self.read_register(rt);
+
+                match extract_bits(code, 9..=11) {
+                    0b000 => self.store(address, value, DataSize::Word),
+                    0b001 => self.store(address, value, DataSize::HalfWord),
+                    0b010 => self.store(address, value, DataSize::Byte),
+                    0b011 => self.load(address, DataSize::Byte, true, rt),
+                    0b100 => self.load(address, DataSize::Word, false, rt),
+                    0b101 => self.load(address, DataSize::HalfWord, false, rt),
+                    0b110 => self.load(address, DataSize::Byte, false, rt),
+                    _ => self.load(address, DataSize::HalfWord, true, rt),
+                }
+            }
+            0b0110..=0b1000 => {
+                let size = match code >> 12 {
+                    0b0110 => DataSize::Word,
+                    0b0111 => DataSize::Byte,
+                    _ => DataSize::HalfWord,
+                };
+
+                let address = n.wrapping_add(imm5 * size as u32);
+
+                if extract_bit(code, 11) == 1 {
+                    self.load(address, size, false, rt);
+                } else {
+                    let value = self.read_register(rt);
+                    self.store(address, value, size);
+                }
+            }
+            _ => {
+                // SP relative
+                let rt = (code >> 8 & 0b111) as Register;
+                let address = self.read_register(SP).wrapping_add((code & 0xFF) * 4);
+
+                if extract_bit(code, 11) == 1 {
+                    self.load(address, DataSize::Word, false, rt);
+                } else {
+                    let value = self.read_register(rt);
+                    self.store(address, value, DataSize::Word);
+                }
+            }
+        }
+
+        self.instruction_name = match self.core.micro_ops.back() {
+            Some(MicroOp::Load { .. }) => "LDR",
+            _ => "STR",
+        };
+    }
+
+    fn misc16(&mut self, code: u32) {
+        let rd = (code & 0b111) as Register;
+        let m = self.read_register((code >> 3 & 0b111) as Register);
+
+        match code {
+            // ADD, SUB (SP plus immediate)
+            _ if code & 0xFF00 == 0xB000 => {
+                let sp = self.read_register(SP);
+                let imm = (code & 0x7F) * 4;
+
+                if extract_bit(code, 7) == 0 {
+                    self.write_register(SP, sp.wrapping_add(imm));
+                    self.instruction_name = "ADD";
+                } else {
+                    self.write_register(SP, sp.wrapping_sub(imm));
+                    self.instruction_name = "SUB";
+                }
+            }
+
+            // CBZ, CBNZ
+            _ if code & 0xF500 == 0xB100 => {
+                let offset = extract_bit(code, 9) << 6 | extract_bits(code, 3..=7) << 1;
+                let is_zero = self.read_register(rd) == 0;
+                let nonzero = extract_bit(code, 11) == 1;
+
+                if is_zero != nonzero {
+                    self.branch(self.read_register(PC).wrapping_add(offset));
+                }
+
+                self.instruction_name = if nonzero { "CBNZ" } else { "CBZ" };
+            }
+
+            // SXTH, SXTB, UXTH, UXTB
+            _ if code & 0xFF00 == 0xB200 => {
+                let (result, name) = match extract_bits(code, 6..=7) {
+                    0b00 => (sign_extend(m & 0xFFFF, 15), "SXTH"),
+                    0b01 => (sign_extend(m & 0xFF, 7), "SXTB"),
+                    0b10 => (m & 0xFFFF, "UXTH"),
+                    _ => (m & 0xFF, "UXTB"),
+                };
+
+                self.write_register(rd, result);
+                self.instruction_name = name;
+            }
+
+            // PUSH
+            _ if code & 0xFE00 == 0xB400 => {
+                let list = (code & 0xFF) | extract_bit(code, 8) << LR;
+                let address = self.read_register(SP).wrapping_sub(4 * list.count_ones());
+                self.store_multiple(address, list);
+                self.write_register(SP, address);
+                self.instruction_name = "PUSH";
+            }
+
+            // CPS
+            _ if code & 0xFFEC == 0xB660 => {
+                let disable = extract_bit(code, 4) == 1;
+
+                if self.core.registers.is_privileged() {
+                    if extract_bit(code, 1) == 1 {
+                        self.core.registers.primask = disable;
+                    }
+                    if extract_bit(code, 0) == 1 {
+                        self.core.registers.faultmask = disable;
+                    }
+                }
+
+                self.instruction_name = if disable { "CPSID" } else { "CPSIE" };
+            }
+
+            // REV, REV16, REVSH
+            _ if code & 0xFF00 == 0xBA00 => {
+                let (result, name) = match extract_bits(code, 6..=7) {
+                    0b00 => (m.swap_bytes(), "REV"),
+                    0b01 => (rev16(m), "REV16"),
+                    0b11 => (sign_extend((m as u16).swap_bytes() as u32, 15), "REVSH"),
+                    _ => {
+                        // HLT, halting debug is not available
+                        self.fault = Some(Fault::Breakpoint);
+                        self.instruction_name = "HLT";
+                        return;
+                    }
+                };
+
+                self.write_register(rd, result);
+                self.instruction_name = name;
+            }
+
+            // POP
+            _ if code & 0xFE00 == 0xBC00 => {
+                let list = (code & 0xFF) | extract_bit(code, 8) << PC;
+                let address = self.read_register(SP);
+                self.write_register(SP, address + 4 * list.count_ones());
+                self.load_multiple(address, list);
+                self.instruction_name = "POP";
+            }
+
+            // BKPT
+            _ if code & 0xFF00 == 0xBE00 => {
+                self.fault = Some(Fault::Breakpoint);
+                self.instruction_name = "BKPT";
+            }
+
+            // IT
+            _ if code & 0xFF00 == 0xBF00 && code & 0xF != 0 => {
+                self.core.registers.it_state = (code & 0xFF) as u8;
+                self.instruction_name = "IT";
+            }
+
+            // Hints
+            _ if code & 0xFF00 == 0xBF00 => self.hint(extract_bits(code, 4..=7)),
+
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn hint(&mut self, op: u32) {
+        match op {
+            0b0010 => {
+                if self.core.event_register {
+                    self.core.event_register = false;
+                } else {
+                    self.core.state = State::Wfe;
+                }
+                self.instruction_name = "WFE";
+            }
+            0b0011 => {
+                self.core.state = State::Wfi;
+                self.instruction_name = "WFI";
+            }
+            0b0100 => {
+                self.core.event_register = true;
+                self.wake_opposite_core = true;
+                self.instruction_name = "SEV";
+            }
+            0b0001 => self.instruction_name = "YIELD",
+            _ => self.instruction_name = "NOP",
+        }
+    }
+
+    fn exec32(&mut self, hw1: u32, hw2: u32) {
+        // SG, there is nothing to change without the Non-secure state
+        if hw1 == 0xE97F && hw2 == 0xE97F {
+            self.instruction_name = "SG";
+            return;
+        }
+
+        let op1 = extract_bits(hw1, 11..=12);
+        let op2 = extract_bits(hw1, 4..=10);
+
+        match op1 {
+            0b01 if op2 & 0b1100100 == 0b0000000 => self.load_store_multiple(hw1, hw2),
+            0b01 if op2 & 0b1100100 == 0b0000100 => self.load_store_dual(hw1, hw2),
+            0b01 if op2 & 0b1100000 == 0b0100000 => self.data_processing_shifted(hw1, hw2),
+            0b10 if extract_bit(hw2, 15) == 1 => self.branch_misc(hw1, hw2),
+            0b10 if op2 & 0b0100000 == 0 => self.data_processing_modified_imm(hw1, hw2),
+            0b10 => self.data_processing_plain_imm(hw1, hw2),
+            0b11 if op2 & 0b1110001 == 0b0000000 => self.store_single(hw1, hw2),
+            0b11 if op2 & 0b1100001 == 0b0000001 && op2 & 0b110 != 0b110 => {
+                self.load_single(hw1, hw2)
+            }
+            0b11 if op2 & 0b1110000 == 0b0100000 => self.data_processing_register(hw1, hw2),
+            0b11 if op2 & 0b1111000 == 0b0110000 => self.multiply(hw1, hw2),
+            0b11 if op2 & 0b1111000 == 0b0111000 => self.long_multiply(hw1, hw2),
+            _ if op2 & 0b1000000 != 0 => {
+                // Coprocessor, floating point and DCP instructions
+                self.fault = Some(Fault::NoCoprocessor);
+                self.instruction_name = "CDP";
+            }
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn load_store_multiple(&mut self, hw1: u32, hw2: u32) {
+        let rn = (hw1 & 0xF) as Register;
+        let writeback = extract_bit(hw1, 5) == 1;
+        let load = extract_bit(hw1, 4) == 1;
+        let list = hw2 & 0xFFFF;
+        let n = self.read_register(rn);
+        let size = 4 * list.count_ones();
+
+        let (address, writeback_value) = match extract_bits(hw1, 7..=8) {
+            0b01 => (n, n.wrapping_add(size)),
+            0b10 => (n.wrapping_sub(size), n.wrapping_sub(size)),
+            _ => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+        };
+
+        if load {
+            if writeback && list >> rn & 1 == 0 {
+                self.write_register(rn, writeback_value);
+            }
+            self.load_multiple(address, list);
+        } else {
+            self.store_multiple(address, list);
+            if writeback {
+                self.write_register(rn, writeback_value);
+            }
+        }
+
+        let is_stack = rn == SP && writeback;
+        self.instruction_name = match (load, is_stack) {
+            (true, true) => "POP",
+            (true, false) => "LDM",
+            (false, true) => "PUSH",
+            (false, false) => "STM",
+        };
+    }
+
+    fn load_store_dual(&mut self, hw1: u32, hw2: u32) {
+        let rn = (hw1 & 0xF) as Register;
+        let rt = (hw2 >> 12) as Register;
+        let rt2 = (hw2 >> 8 & 0xF) as Register;
+        let load = extract_bit(hw1, 4) == 1;
+        let n = if rn == PC {
+            align(self.read_register(PC), 4)
+        } else {
+            self.read_register(rn)
+        };
+
+        let p = extract_bit(hw1, 8) == 1;
+        let u = extract_bit(hw1, 7) == 1;
+        let w = extract_bit(hw1, 5) == 1;
+
+        if p || w {
+            // LDRD, STRD
+            let offset = (hw2 & 0xFF) * 4;
+            let offset_address = if u {
+                n.wrapping_add(offset)
+            } else {
+                n.wrapping_sub(offset)
+            };
+            let address = if p { offset_address } else { n };
+
+            if address & 0b11 != 0 {
+                self.fault = Some(Fault::Unaligned);
+                return;
+            }
+
+            if load {
+                self.load(address, DataSize::Word, false, rt);
+                self.load(address + 4, DataSize::Word, false, rt2);
+                self.instruction_name = "LDRD";
+            } else {
+                let (first, second) = (self.read_register(rt), self.read_register(rt2));
+                self.store(address, first, DataSize::Word);
+                self.store(address + 4, second, DataSize::Word);
+                self.instruction_name = "STRD";
+            }
+
+            if w {
+                self.write_register(rn, offset_address);
+            }
+
+            return;
+        }
+
+        if !u {
+            let address = n.wrapping_add((hw2 & 0xFF) * 4);
+
+            if load {
+                self.load_exclusive(address, DataSize::Word, rt);
+                self.instruction_name = "LDREX";
+            } else if rt == PC {
+                // TT, no SAU or MPU regions are configured
+                self.write_register(rt2, 0);
+                self.instruction_name = "TT";
+            } else {
+                let value = self.read_register(rt);
+                self.store_exclusive(address, value, DataSize::Word, rt2);
+                self.instruction_name = "STREX";
+            }
+
+            return;
+        }
+
+        let op3 = extract_bits(hw2, 4..=7);
+        let rd = (hw2 & 0xF) as Register;
+        let size = match op3 & 0b11 {
+            0b00 => DataSize::Byte,
+            0b01 => DataSize::HalfWord,
+            _ => DataSize::Word,
+        };
+
+        match (load, op3) {
+            (true, 0b0000 | 0b0001) => {
+                let m = self.read_register(rd);
+                let (address, size) = if op3 == 0 {
+                    (n.wrapping_add(m), DataSize::Byte)
+                } else {
+                    (n.wrapping_add(m << 1), DataSize::HalfWord)
+                };
+
+                self.core.micro_ops.push_back(MicroOp::Load {
+                    address,
+                    size,
+                    signed: false,
+                    exclusive: false,
+                    target: LoadTarget::TableBranch(self.read_register(PC)),
+                });
+
+                self.cycles = 2;
+                self.instruction_name = if op3 == 0 { "TBB" } else { "TBH" };
+            }
+            // LDREXB, LDREXH, LDAEXB, LDAEXH, LDAEX
+            (true, 0b0100 | 0b0101 | 0b1100..=0b1110) => {
+                self.load_exclusive(n, size, rt);
+                self.instruction_name = "LDREX";
+            }
+            // LDAB, LDAH, LDA
+            (true, 0b1000..=0b1010) => {
+                self.load(n, size, false, rt);
+                self.instruction_name = "LDA";
+            }
+            // STREXB, STREXH, STLEXB, STLEXH, STLEX
+            (false, 0b0100 | 0b0101 | 0b1100..=0b1110) => {
+                let value = self.read_register(rt);
+                self.store_exclusive(n, value, size, rd);
+                self.instruction_name = "STREX";
+            }
+            // STLB, STLH, STL
+            (false, 0b1000..=0b1010) => {
+                let value = self.read_register(rt);
+                self.store(n, value, size);
+                self.instruction_name = "STL";
+            }
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn data_processing_shifted(&mut self, hw1: u32, hw2: u32) {
+        let setflags = extract_bit(hw1, 4) == 1;
+        let rn = (hw1 & 0xF) as Register;
+        let rd = (hw2 >> 8 & 0xF) as Register;
+        let rm = (hw2 & 0xF) as Register;
+        let ty = extract_bits(hw2, 4..=5);
+        let imm5 = extract_bits(hw2, 12..=14) << 2 | extract_bits(hw2, 6..=7);
+        let op = extract_bits(hw1, 5..=8);
+
+        let (shift, amount) = decode_imm_shift(ty, imm5);
+        let (m, carry) = shift_c(self.read_register(rm), shift, amount, self.carry());
+        let n = self.read_register(rn);
+
+        if op == 0b0110 {
+            // PKHBT, PKHTB
+            let result = if extract_bit(hw2, 5) == 1 {
+                (n & 0xFFFF_0000) | (m & 0xFFFF)
+            } else {
+                (m & 0xFFFF_0000) | (n & 0xFFFF)
+            };
+
+            self.write_register(rd, result);
+            self.instruction_name = "PKH";
+            return;
+        }
+
+        match AluOp::decode(op, rn, rd, setflags) {
+            Some(op) => self.alu(op, rd, n, m, carry, setflags),
+            None => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn data_processing_modified_imm(&mut self, hw1: u32, hw2: u32) {
+        let setflags = extract_bit(hw1, 4) == 1;
+        let rn = (hw1 & 0xF) as Register;
+        let rd = (hw2 >> 8 & 0xF) as Register;
+        let imm12 = extract_bit(hw1, 10) << 11 | extract_bits(hw2, 12..=14) << 8 | hw2 & 0xFF;
+
+        let (imm, carry) = thumb_expand_imm_c(imm12, self.carry());
+        let n = self.read_register(rn);
+
+        match AluOp::decode(extract_bits(hw1, 5..=8), rn, rd, setflags) {
+            Some(op) => self.alu(op, rd, n, imm, carry, setflags),
+            None => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn data_processing_plain_imm(&mut self, hw1: u32, hw2: u32) {
+        let rn = (hw1 & 0xF) as Register;
+        let rd = (hw2 >> 8 & 0xF) as Register;
+        let imm12 = extract_bit(hw1, 10) << 11 | extract_bits(hw2, 12..=14) << 8 | hw2 & 0xFF;
+        let imm16 = (hw1 & 0xF) << 12 | imm12;
+        let lsb = extract_bits(hw2, 12..=14) << 2 | extract_bits(hw2, 6..=7);
+        let bits = hw2 & 0x1F;
+        let n = if rn == PC {
+            align(self.read_register(PC), 4)
+        } else {
+            self.read_register(rn)
+        };
+
+        match extract_bits(hw1, 4..=8) {
+            0b00000 => {
+                self.write_register(rd, n.wrapping_add(imm12));
+                self.instruction_name = if rn == PC { "ADR" } else { "ADDW" };
+            }
+            0b01010 => {
+                self.write_register(rd, n.wrapping_sub(imm12));
+                self.instruction_name = if rn == PC { "ADR" } else { "SUBW" };
+            }
+            0b00100 => {
+                self.write_register(rd, imm16);
+                self.instruction_name = "MOVW";
+            }
+            0b01100 => {
+                let value = self.read_register(rd) & 0xFFFF | imm16 << 16;
+                self.write_register(rd, value);
+                self.instruction_name = "MOVT";
+            }
+            op @ (0b10000 | 0b10010 | 0b11000 | 0b11010) => {
+                // SSAT, USAT
+                let shift = if extract_bit(hw1, 5) == 1 {
+                    Shift::Asr
+                } else {
+                    Shift::Lsl
+                };
+                let (operand, _) = shift_c(n, shift, lsb, false);
+                let (result, saturated) = if op & 0b01000 == 0 {
+                    self.instruction_name = "SSAT";
+                    signed_saturate(operand as i32 as i64, bits + 1)
+                } else {
+                    self.instruction_name = "USAT";
+                    unsigned_saturate(operand as i32 as i64, bits)
+                };
+
+                self.write_register(rd, result);
+                if saturated {
+                    self.core.registers.flags.q = true;
+                }
+            }
+            0b10100 | 0b11100 => {
+                // SBFX, UBFX
+                let width = bits + 1;
+                if lsb + width > 32 {
+                    self.fault = Some(Fault::Undefined);
+                    return;
+                }
+
+                let field = extract_bits(n, lsb..=lsb + width - 1);
+                let signed = extract_bit(hw1, 7) == 0;
+                let result = if signed {
+                    sign_extend(field, width - 1)
+                } else {
+                    field
+                };
+
+                self.write_register(rd, result);
+                self.instruction_name = if signed { "SBFX" } else { "UBFX" };
+            }
+            0b10110 => {
+                // BFI, BFC
+                let msb = bits;
+                if msb < lsb {
+                    self.fault = Some(Fault::Undefined);
+                    return;
+                }
+
+                let mask = (u32::MAX >> (31 - (msb - lsb))) << lsb;
+                let source = if rn == PC { 0 } else { n << lsb };
+                let value = (self.read_register(rd) & !mask) | (source & mask);
+
+                self.write_register(rd, value);
+                self.instruction_name = if rn == PC { "BFC" } else { "BFI" };
+            }
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn branch_misc(&mut self, hw1: u32, hw2: u32) {
+        let op1 = extract_bits(hw2, 12..=14);
+        let op = extract_bits(hw1, 4..=10);
+        let s = extract_bit(hw1, 10);
+        let j1 = extract_bit(hw2, 13);
+        let j2 = extract_bit(hw2, 11);
+
+        match op1 & 0b101 {
+            0b000 if op & 0b0111000 != 0b0111000 => {
+                // Conditional branch
+                let cond = extract_bits(hw1, 6..=9);
+                let imm = s << 20 | j2 << 19 | j1 << 18 | (hw1 & 0x3F) << 12 | (hw2 & 0x7FF) << 1;
+
+                if self.core.registers.condition_passed(cond) {
+                    self.branch(self.read_register(PC).wrapping_add(sign_extend(imm, 20)));
+                }
+                self.instruction_name = "B";
+            }
+            0b000 => self.misc_control(op, op1, hw1, hw2),
+            0b001 | 0b101 => {
+                let i1 = !(j1 ^ s) & 1;
+                let i2 = !(j2 ^ s) & 1;
+                let imm = s << 24 | i1 << 23 | i2 << 22 | (hw1 & 0x3FF) << 12 | (hw2 & 0x7FF) << 1;
+                let target = self.read_register(PC).wrapping_add(sign_extend(imm, 24));
+
+                if op1 & 0b100 != 0 {
+                    self.core.registers.lr = self.pc.wrapping_add(4) | 1;
+                    self.instruction_name = "BL";
+                } else {
+                    self.instruction_name = "B";
+                }
+
+                self.branch(target);
+            }
+            // BLX (immediate) switches to the Arm state
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn misc_control(&mut self, op: u32, op1: u32, hw1: u32, hw2: u32) {
+        let rn = (hw1 & 0xF) as Register;
+        let rd = (hw2 >> 8 & 0xF) as Register;
+        let sysm = hw2 & 0xFF;
+
+        match op {
+            0b0111000 | 0b0111001 => {
+                let value = self.read_register(rn);
+                let mask = extract_bits(hw2, 10..=11);
+                self.write_special(sysm, mask, value);
+                self.instruction_name = "MSR";
+            }
+            0b0111010 => self.hint(hw2 & 0xFF),
+            0b0111011 => match extract_bits(hw2, 4..=7) {
+                0b0010 => {
+                    self.core.local_monitor = false;
+                    self.instruction_name = "CLREX";
+                }
+                0b0100 => self.instruction_name = "DSB",
+                0b0101 => self.instruction_name = "DMB",
+                0b0110 => self.instruction_name = "ISB",
+                _ => self.fault = Some(Fault::Undefined),
+            },
+            0b0111110 | 0b0111111 => {
+                let value = self.read_special(sysm);
+                self.write_register(rd, value);
+                self.instruction_name = "MRS";
+            }
+            0b1111111 if op1 == 0b010 => {
+                self.fault = Some(Fault::Undefined);
+                self.instruction_name = "UDF";
+            }
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn read_special(&self, sysm: u32) -> u32 {
+        let registers = &self.core.registers;
+        let privileged = registers.is_privileged();
+
+        match sysm {
+            0..=7 => {
+                let mut value = 0;
+                if sysm & 1 != 0 {
+                    value |= registers.ipsr as u32;
+                }
+                if sysm & 0b100 == 0 {
+                    value |= registers.apsr();
+                }
+                value
+            }
+            20 => registers.control,
+            _ if !privileged => 0,
+            8 => registers.msp,
+            9 => registers.psp,
+            10 => registers.msplim,
+            11 => registers.psplim,
+            16 => registers.primask as u32,
+            17 | 18 => registers.basepri as u32,
+            19 => registers.faultmask as u32,
+            _ => 0, // Non-secure aliases
+        }
+    }
+
+    fn write_special(&mut self, sysm: u32, mask: u32, value: u32) {
+        let registers = &mut self.core.registers;
+
+        if sysm <= 7 {
+            if sysm & 0b100 == 0 {
+                registers.set_apsr(value, mask & 0b10 != 0, mask & 0b01 != 0);
+            }
+            return;
+        }
+
+        if !registers.is_privileged() {
+            return;
+        }
+
+        match sysm {
+            8 => registers.msp = value & !0b11,
+            9 => registers.psp = value & !0b11,
+            10 => registers.msplim = value & !0b111,
+            11 => registers.psplim = value & !0b111,
+            16 => registers.primask = value & 1 != 0,
+            17 => registers.basepri = value as u8 & 0xF0,
+            18 => {
+                let value = value as u8 & 0xF0;
+                if value != 0 && (value < registers.basepri || registers.basepri == 0) {
+                    registers.basepri = value;
+                }
+            }
+            19 => registers.faultmask = value & 1 != 0,
+            20 => {
+                let mut control = registers.control & !CONTROL_NPRIV | value & CONTROL_NPRIV;
+                if !registers.is_handler_mode() {
+                    control = control & !CONTROL_SPSEL | value & CONTROL_SPSEL;
+                }
+                registers.control = control;
+            }
+            _ => {}
+        }
+    }
+
+    /// Address of the immediate and register offset forms of the single loads and stores
+    fn single_address(&mut self, hw1: u32, hw2: u32) -> Option<u32> {
+        let rn = (hw1 & 0xF) as Register;
+        let n = self.read_register(rn);
+
+        if extract_bit(hw1, 7) == 1 {
+            return Some(n.wrapping_add(hw2 & 0xFFF));
+        }
+
+        if extract_bit(hw2, 11) == 1 {
+            let imm8 = hw2 & 0xFF;
+            let p = extract_bit(hw2, 10) == 1;
+            let u = extract_bit(hw2, 9) == 1;
+            let w = extract_bit(hw2, 8) == 1;
+
+            let offset_address = if u {
+                n.wrapping_add(imm8)
+            } else {
+                n.wrapping_sub(imm8)
+            };
+
+            if w {
+                self.write_register(rn, offset_address);
+            }
+
+            return Some(if p { offset_address } else { n });
+        }
+
+        if extract_bits(hw2, 6..=11) == 0 {
+            let m = self.read_register((hw2 & 0xF) as Register);
+            return Some(n.wrapping_add(m << extract_bits(hw2, 4..=5)));
+        }
+
+        None
+    }
+
+    fn store_single(&mut self, hw1: u32, hw2: u32) {
+        let rt = (hw2 >> 12) as Register;
+        let size = match extract_bits(hw1, 5..=6) {
+            _ if hw1 & 0xF == 0xF => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+            0b00 => DataSize::Byte,
+            0b01 => DataSize::HalfWord,
+            0b10 => DataSize::Word,
+            _ => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+        };
+
+        // read before a possible writeback of the base register
+        let value = self.read_register(rt);
+
+        match self.single_address(hw1, hw2) {
+            Some(address) => {
+                self.store(address, value, size);
+                self.instruction_name = "STR";
+            }
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    fn load_single(&mut self, hw1: u32, hw2: u32) {
+        let rt = (hw2 >> 12) as Register;
+        let signed = extract_bit(hw1, 8) == 1;
+        let size = match extract_bits(hw1, 5..=6) {
+            0b00 => DataSize::Byte,
+            0b01 => DataSize::HalfWord,
+            _ => DataSize::Word,
+        };
+
+        let address = if hw1 & 0xF == 0xF {
+            // Literal
+            let base = align(self.read_register(PC), 4);
+            let imm12 = hw2 & 0xFFF;
+
+            Some(if extract_bit(hw1, 7) == 1 {
+                base.wrapping_add(imm12)
+            } else {
+                base.wrapping_sub(imm12)
+            })
+        } else {
+            self.single_address(hw1, hw2)
+        };
+
+        let Some(address) = address else {
+            self.fault = Some(Fault::Undefined);
+            return;
+        };
+
+        if rt == PC && size != DataSize::Word {
+            // PLD, PLI
+            self.instruction_name = "PLD";
+            return;
+        }
+
+        self.load(address, size, signed, rt);
+        self.instruction_name = "LDR";
+    }
+
+    fn data_processing_register(&mut self, hw1: u32, hw2: u32) {
+        let op1 = extract_bits(hw1, 4..=7);
+        let op2 = extract_bits(hw2, 4..=7);
+        let rn = (hw1 & 0xF) as Register;
+        let rd = (hw2 >> 8 & 0xF) as Register;
+        let rm = (hw2 & 0xF) as Register;
+        let n = self.read_register(rn);
+        let m = self.read_register(rm);
+
+        match (op1, op2) {
+            // LSL, LSR, ASR, ROR (register)
+            (0b0000..=0b0111, 0b0000) => {
+                let setflags = extract_bit(hw1, 4) == 1;
+                let shift = register_shift(extract_bits(hw1, 5..=6));
+                let (result, carry) = shift_c(n, shift, m & 0xFF, self.carry());
+                self.alu(AluOp::Mov, rd, 0, result, carry, setflags);
+                self.instruction_name =
+                    ["LSL", "LSR", "ASR", "ROR"][extract_bits(hw1, 5..=6) as usize];
+            }
+
+            // Extend and add
+            (0b0000..=0b0101, 0b1000..=0b1111) => {
+                let rotated = m.rotate_right(extract_bits(hw2, 4..=5) * 8);
+                let add = if rn == PC { 0 } else { n };
+
+                let (result, name) = match op1 {
+                    0b0000 => (add.wrapping_add(sign_extend(rotated & 0xFFFF, 15)), "SXTAH"),
+                    0b0001 => (add.wrapping_add(rotated & 0xFFFF), "UXTAH"),
+                    0b0010 | 0b0011 => {
+                        let signed = op1 == 0b0010;
+                        let extend = |v: u32| {
+                            if signed {
+                                sign_extend(v & 0xFF, 7)
+                            } else {
+                                v & 0xFF
+                            }
+                        };
+
+                        let low = (add & 0xFFFF).wrapping_add(extend(rotated)) & 0xFFFF;
+                        let high = (add >> 16).wrapping_add(extend(rotated >> 16)) & 0xFFFF;
+                        (high << 16 | low, if signed { "SXTAB16" } else { "UXTAB16" })
+                    }
+                    0b0100 => (add.wrapping_add(sign_extend(rotated & 0xFF, 7)), "SXTAB"),
+                    _ => (add.wrapping_add(rotated & 0xFF), "UXTAB"),
+                };
+
+                self.write_register(rd, result);
+                self.instruction_name = name;
+            }
+
+            // Parallel addition and subtraction
+            (0b1000..=0b1111, 0b0000..=0b0111) => self.parallel_add_sub(op1 & 0b111, op2, rd, n, m),
+
+            // Miscellaneous operations
+            (0b1000..=0b1011, 0b1000..=0b1011) => {
+                let (result, name) = match (op1 & 0b11, op2 & 0b11) {
+                    (0b00, op) => {
+                        // QADD, QDADD, QSUB, QDSUB
+                        let (double, saturated) = if op & 1 == 1 {
+                            signed_saturate(n as i32 as i64 * 2, 32)
+                        } else {
+                            (n, false)
+                        };
+
+                        let value = if op & 0b10 == 0 {
+                            m as i32 as i64 + double as i32 as i64
+                        } else {
+                            m as i32 as i64 - double as i32 as i64
+                        };
+
+                        let (result, overflow) = signed_saturate(value, 32);
+                        if saturated || overflow {
+                            self.core.registers.flags.q = true;
+                        }
+
+                        (result, ["QADD", "QDADD", "QSUB", "QDSUB"][op as usize])
+                    }
+                    (0b01, 0b00) => (m.swap_bytes(), "REV"),
+                    (0b01, 0b01) => (rev16(m), "REV16"),
+                    (0b01, 0b10) => (m.reverse_bits(), "RBIT"),
+                    (0b01, 0b11) => (sign_extend((m as u16).swap_bytes() as u32, 15), "REVSH"),
+                    (0b10, 0b00) => {
+                        let ge = self.core.registers.flags.ge;
+                        let result = (0..4).fold(0, |acc, i| {
+                            let source = if ge >> i & 1 == 1 { n } else { m };
+                            acc | source & (0xFF << (i * 8))
+                        });
+                        (result, "SEL")
+                    }
+                    (0b11, 0b00) => (m.leading_zeros(), "CLZ"),
+                    _ => {
+                        self.fault = Some(Fault::Undefined);
+                        return;
+                    }
+                };
+
+                self.write_register(rd, result);
+                self.instruction_name = name;
+            }
+
+            _ => self.fault = Some(Fault::Undefined),
+        }
+    }
+
+    /// Only the modulo forms setting the GE flags are supported,
+    /// the saturating and halving forms are undefined here
+    fn parallel_add_sub(&mut self, op: u32, prefix: u32, rd: Register, n: u32, m: u32) {
+        let signed = match prefix {
+            0b000 => true,
+            0b100 => false,
+            _ => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+        };
+
+        let (lanes, width, subtract, name) = match op {
+            0b001 => (2, 16, false, if signed { "SADD16" } else { "UADD16" }),
+            0b101 => (2, 16, true, if signed { "SSUB16" } else { "USUB16" }),
+            0b000 => (4, 8, false, if signed { "SADD8" } else { "UADD8" }),
+            0b100 => (4, 8, true, if signed { "SSUB8" } else { "USUB8" }),
+            _ => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+        };
+
+        let mask = (1u32 << width) - 1;
+        let mut result = 0;
+        let mut ge = 0u8;
+
+        for lane in 0..lanes {
+            let shift = lane * width;
+            let (a, b) = if signed {
+                (
+                    sign_extend(n >> shift & mask, width - 1) as i32 as i64,
+                    sign_extend(m >> shift & mask, width - 1) as i32 as i64,
+                )
+            } else {
+                ((n >> shift & mask) as i64, (m >> shift & mask) as i64)
+            };
+
+            let value = if subtract { a - b } else { a + b };
+            result |= (value as u32 & mask) << shift;
+
+            let set = match (signed, subtract) {
+                (true, _) => value >= 0,
+                (false, false) => value >= 1 << width,
+                (false, true) => value >= 0,
+            };
+
+            if set {
+                let lane_bits = if width == 16 { 0b11 } else { 0b1 };
+                ge |= lane_bits << (lane * (width / 8));
+            }
+        }
+
+        self.core.registers.flags.ge = ge;
+        self.write_register(rd, result);
+        self.instruction_name = name;
+    }
+
+    fn multiply(&mut self, hw1: u32, hw2: u32) {
+        let op1 = extract_bits(hw1, 4..=6);
+        let op2 = extract_bits(hw2, 4..=5);
+        let rn = (hw1 & 0xF) as Register;
+        let ra = (hw2 >> 12) as Register;
+        let rd = (hw2 >> 8 & 0xF) as Register;
+        let rm = (hw2 & 0xF) as Register;
+        let n = self.read_register(rn);
+        let m = self.read_register(rm);
+        let a = if ra == PC { 0 } else { self.read_register(ra) };
+
+        let half = |value: u32, top: bool| {
+            let value = if top { value >> 16 } else { value & 0xFFFF };
+            sign_extend(value, 15) as i32 as i64
+        };
+
+        let (result, name) = match (op1, op2) {
+            (0b000, 0b00) => {
+                let name = if ra == PC { "MUL" } else { "MLA" };
+                (a.wrapping_add(n.wrapping_mul(m)), name)
+            }
+            (0b000, 0b01) => (a.wrapping_sub(n.wrapping_mul(m)), "MLS"),
+            (0b001, _) => {
+                // SMULxy, SMLAxy
+                let product = half(n, op2 & 0b10 != 0) * half(m, op2 & 0b01 != 0);
+                let value = product + a as i32 as i64;
+                if value != value as i32 as i64 {
+                    self.core.registers.flags.q = true;
+                }
+                (value as u32, if ra == PC { "SMULxy" } else { "SMLAxy" })
+            }
+            (0b011, 0b00 | 0b01) => {
+                // SMULWy, SMLAWy
+                let product = (n as i32 as i64 * half(m, op2 & 0b01 != 0)) >> 16;
+                let value = product + a as i32 as i64;
+                if value != value as i32 as i64 {
+                    self.core.registers.flags.q = true;
+                }
+                (value as u32, if ra == PC { "SMULWy" } else { "SMLAWy" })
+            }
+            _ => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+        };
+
+        self.write_register(rd, result);
+        self.instruction_name = name;
+    }
+
+    fn long_multiply(&mut self, hw1: u32, hw2: u32) {
+        let op1 = extract_bits(hw1, 4..=6);
+        let op2 = extract_bits(hw2, 4..=7);
+        let rn = (hw1 & 0xF) as Register;
+        let rdlo = (hw2 >> 12) as Register;
+        let rdhi = (hw2 >> 8 & 0xF) as Register;
+        let rm = (hw2 & 0xF) as Register;
+        let n = self.read_register(rn);
+        let m = self.read_register(rm);
+
+        if op2 == 0b1111 && (op1 == 0b001 || op1 == 0b011) {
+            // SDIV, UDIV
+            if m == 0 && self.core.nvic.ccr & CCR_DIV_0_TRP != 0 {
+                self.fault = Some(Fault::DivideByZero);
+                return;
+            }
+
+            let quotient = match (m, op1) {
+                (0, _) => 0,
+                (_, 0b001) => (n as i32).wrapping_div(m as i32) as u32,
+                _ => n / m,
+            };
+
+            // The divider takes 2 to 11 cycles depending on the size of the quotient
+            self.cycles = 2 + ((32 - quotient.leading_zeros()) / 4) as u8;
+            self.write_register(rdhi, quotient);
+            self.instruction_name = if op1 == 0b001 { "SDIV" } else { "UDIV" };
+            return;
+        }
+
+        let accumulate = (self.read_register(rdhi) as u64) << 32 | self.read_register(rdlo) as u64;
+
+        let (result, name) = match (op1, op2) {
+            (0b000, 0b0000) => ((n as i32 as i64 * m as i32 as i64) as u64, "SMULL"),
+            (0b010, 0b0000) => (n as u64 * m as u64, "UMULL"),
+            (0b100, 0b0000) => (
+                (n as i32 as i64 * m as i32 as i64).wrapping_add(accumulate as i64) as u64,
+                "SMLAL",
+            ),
+            (0b110, 0b0000) => ((n as u64 * m as u64).wrapping_add(accumulate), "UMLAL"),
+            (0b110, 0b0110) => {
+                let low = self.read_register(rdlo) as u64;
+                let high = self.read_register(rdhi) as u64;
+                (n as u64 * m as u64 + low + high, "UMAAL")
+            }
+            _ => {
+                self.fault = Some(Fault::Undefined);
+                return;
+            }
+        };
+
+        self.write_register(rdlo, result as u32);
+        self.write_register(rdhi, (result >> 32) as u32);
+        self.instruction_name = name;
+    }
+}
+
+fn rev16(value: u32) -> u32 {
+    (value & 0xFF00_FF00) >> 8 | (value & 0x00FF_00FF) << 8
+}
+
+pub(super) fn exec_instruction(code: u32, ctx: &mut ExecContext) {
+    let hw1 = code & 0xFFFF;
+    let hw2 = code >> 16;
+    let is_32bit = matches!(hw1 >> 11, 0b11101..=0b11111);
+
+    ctx.size = if is_32bit { 4 } else { 2 };
+    ctx.next_pc = ctx.pc.wrapping_add(ctx.size);
+
+    let registers = &ctx.core.registers;
+    if registers.in_it_block() && !registers.condition_passed((registers.it_state >> 4) as u32) {
+        // Skipped by the IT block
+        ctx.instruction_name = "NOP (IT)";
+        return;
+    }
+
+    if is_32bit {
+        ctx.exec32(hw1, hw2);
+    } else {
+        ctx.exec16(hw1);
+    }
+
+    if ctx.fault.is_some() {
+        ctx.core.micro_ops.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumb_expand_imm() {
+        assert_eq!(thumb_expand_imm_c(0x0AB, false), (0xAB, false));
+        assert_eq!(thumb_expand_imm_c(0x1AB, false), (0x00AB_00AB, false));
+        assert_eq!(thumb_expand_imm_c(0x2AB, false), (0xAB00_AB00, false));
+        assert_eq!(thumb_expand_imm_c(0x3AB, false), (0xABAB_ABAB, false));
+        // 0b0100_0010_1010 -> 0xAA rotated right by 8
+        assert_eq!(thumb_expand_imm_c(0x42A, false), (0xAA00_0000, true));
+    }
+
+    #[test]
+    fn test_shift_c() {
+        assert_eq!(shift_c(0x8000_0001, Shift::Lsl, 1, false), (2, true));
+        assert_eq!(shift_c(0x8000_0001, Shift::Lsr, 32, false), (0, true));
+        assert_eq!(
+            shift_c(0x8000_0000, Shift::Asr, 40, false),
+            (u32::MAX, true)
+        );
+        assert_eq!(
+            shift_c(0x0000_0001, Shift::Rrx, 1, true),
+            (0x8000_0000, true)
+        );
+        assert_eq!(shift_c(0x1234, Shift::Ror, 0, true), (0x1234, true));
+    }
+
+    #[test]
+    fn test_add_with_carry() {
+        assert_eq!(add_with_carry(u32::MAX, 1, false), (0, true, false));
+        assert_eq!(
+            add_with_carry(0x7FFF_FFFF, 1, false),
+            (0x8000_0000, false, true)
+        );
+        // 5 - 7
+        assert_eq!(add_with_carry(5, !7, true), (-2i32 as u32, false, false));
+    }
+}
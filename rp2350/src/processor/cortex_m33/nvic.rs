@@ -0,0 +1,440 @@
+/**
+ * @file processor/cortex_m33/nvic.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Nested Vectored Interrupt Controller and System Control Block of the Cortex-M33
+ */
+use crate::interrupts::{CoreRouting, Interrupts};
+
+pub type ExceptionNumber = u16;
+
+pub const RESET: ExceptionNumber = 1;
+pub const NMI: ExceptionNumber = 2;
+pub const HARD_FAULT: ExceptionNumber = 3;
+pub const MEM_MANAGE: ExceptionNumber = 4;
+pub const BUS_FAULT: ExceptionNumber = 5;
+pub const USAGE_FAULT: ExceptionNumber = 6;
+pub const SECURE_FAULT: ExceptionNumber = 7;
+pub const SV_CALL: ExceptionNumber = 11;
+pub const DEBUG_MONITOR: ExceptionNumber = 12;
+pub const PEND_SV: ExceptionNumber = 14;
+pub const SYS_TICK: ExceptionNumber = 15;
+/// Exception number of the first external interrupt
+pub const IRQ_BASE: ExceptionNumber = 16;
+
+/// Number of external interrupts wired to the NVIC on the RP2350
+pub const NUM_IRQS: usize = 52;
+
+// Offsets from the start of the System Control Space (0xE000_E000)
+pub const ICTR: u32 = 0x004; // Interrupt Controller Type Register
+pub const ACTLR: u32 = 0x008; // Auxiliary Control Register
+pub const NVIC_ISER0: u32 = 0x100; // Interrupt Set Enable Registers
+pub const NVIC_ICER0: u32 = 0x180; // Interrupt Clear Enable Registers
+pub const NVIC_ISPR0: u32 = 0x200; // Interrupt Set Pending Registers
+pub const NVIC_ICPR0: u32 = 0x280; // Interrupt Clear Pending Registers
+pub const NVIC_IABR0: u32 = 0x300; // Interrupt Active Bit Registers
+pub const NVIC_ITNS0: u32 = 0x380; // Interrupt Target Non-secure Registers
+pub const NVIC_IPR0: u32 = 0x400; // Interrupt Priority Registers
+pub const CPUID: u32 = 0xD00; // CPUID Base Register
+pub const ICSR: u32 = 0xD04; // Interrupt Control and State Register
+pub const VTOR: u32 = 0xD08; // Vector Table Offset Register
+pub const AIRCR: u32 = 0xD0C; // Application Interrupt and Reset Control Register
+pub const SCR: u32 = 0xD10; // System Control Register
+pub const CCR: u32 = 0xD14; // Configuration and Control Register
+pub const SHPR1: u32 = 0xD18; // System Handler Priority Register 1
+pub const SHPR2: u32 = 0xD1C; // System Handler Priority Register 2
+pub const SHPR3: u32 = 0xD20; // System Handler Priority Register 3
+pub const SHCSR: u32 = 0xD24; // System Handler Control and State Register
+pub const CFSR: u32 = 0xD28; // Configurable Fault Status Register
+pub const HFSR: u32 = 0xD2C; // HardFault Status Register
+pub const MMFAR: u32 = 0xD34; // MemManage Fault Address Register
+pub const BFAR: u32 = 0xD38; // BusFault Address Register
+pub const CPACR: u32 = 0xD88; // Coprocessor Access Control Register
+pub const STIR: u32 = 0xF00; // Software Triggered Interrupt Register
+
+/// Cortex-M33 revision r1p0
+const CPUID_VALUE: u32 = 0x411F_D210;
+
+// Only the upper 4 bits of the priorities are implemented on the RP2350
+const PRIORITY_MASK: u8 = 0xF0;
+
+const ICSR_PENDSTCLR: u32 = 1 << 25;
+const ICSR_PENDSTSET: u32 = 1 << 26;
+const ICSR_PENDSVCLR: u32 = 1 << 27;
+const ICSR_PENDSVSET: u32 = 1 << 28;
+const ICSR_PENDNMISET: u32 = 1 << 31;
+
+const AIRCR_VECTKEY: u32 = 0x05FA;
+const AIRCR_VECTKEYSTAT: u32 = 0xFA05;
+const AIRCR_SYSRESETREQ: u32 = 1 << 2;
+
+pub const SCR_SLEEPONEXIT: u32 = 1 << 1;
+pub const SCR_SEVONPEND: u32 = 1 << 4;
+
+pub const CCR_UNALIGN_TRP: u32 = 1 << 3;
+pub const CCR_DIV_0_TRP: u32 = 1 << 4;
+
+const SHCSR_MEMFAULTENA: u32 = 1 << 16;
+const SHCSR_BUSFAULTENA: u32 = 1 << 17;
+const SHCSR_USGFAULTENA: u32 = 1 << 18;
+
+// Fault status bits in the CFSR
+pub const CFSR_IBUSERR: u32 = 1 << 8;
+pub const CFSR_PRECISERR: u32 = 1 << 9;
+pub const CFSR_BFARVALID: u32 = 1 << 15;
+pub const CFSR_UNDEFINSTR: u32 = 1 << 16;
+pub const CFSR_INVSTATE: u32 = 1 << 17;
+pub const CFSR_NOCP: u32 = 1 << 19;
+pub const CFSR_UNALIGNED: u32 = 1 << 24;
+pub const CFSR_DIVBYZERO: u32 = 1 << 25;
+
+pub const HFSR_FORCED: u32 = 1 << 30;
+
+#[derive(Debug, Clone)]
+pub struct Nvic {
+    pub enabled: u64,
+    /// Software pending state, the interrupt lines are level sensitive and added on top
+    pub pending: u64,
+    pub active: u64,
+    pub priority: [u8; NUM_IRQS],
+
+    /// Pending and active state of the system exceptions, indexed by the exception number
+    pub system_pending: u16,
+    pub system_active: u16,
+    /// Priorities of the exceptions 4 to 15
+    pub system_priority: [u8; 12],
+
+    pub vtor: u32,
+    pub scr: u32,
+    pub ccr: u32,
+    pub aircr: u32,
+    pub shcsr: u32,
+    pub cfsr: u32,
+    pub hfsr: u32,
+    pub mmfar: u32,
+    pub bfar: u32,
+    pub cpacr: u32,
+    pub actlr: u32,
+
+    /// Set by AIRCR.SYSRESETREQ, the chip level reset is left to the owner of the core
+    pub reset_requested: bool,
+}
+
+impl Default for Nvic {
+    fn default() -> Self {
+        Self {
+            enabled: 0,
+            pending: 0,
+            active: 0,
+            priority: [0; NUM_IRQS],
+            system_pending: 0,
+            system_active: 0,
+            system_priority: [0; 12],
+            vtor: 0,
+            scr: 0,
+            ccr: 1 << 9, // STKALIGN
+            aircr: 0,
+            shcsr: 0,
+            cfsr: 0,
+            hfsr: 0,
+            mmfar: 0,
+            bfar: 0,
+            cpacr: 0,
+            actlr: 0,
+            reset_requested: false,
+        }
+    }
+}
+
+impl Nvic {
+    /// Priority of an exception, lower value means higher priority
+    pub fn exception_priority(&self, exception: ExceptionNumber) -> i16 {
+        match exception {
+            RESET => -4,
+            NMI => -2,
+            HARD_FAULT => -1,
+            4..=15 => self.system_priority[exception as usize - 4] as i16,
+            _ => self.priority[(exception - IRQ_BASE) as usize] as i16,
+        }
+    }
+
+    /// The priority of the currently active exceptions, without the masking registers
+    pub fn active_priority(&self) -> i16 {
+        let system = (1..16)
+            .filter(|v| self.system_active >> v & 1 == 1)
+            .map(|v| self.exception_priority(v));
+
+        let irqs = (0..NUM_IRQS as u16)
+            .filter(|v| self.active >> v & 1 == 1)
+            .map(|v| self.exception_priority(v + IRQ_BASE));
+
+        system.chain(irqs).min().unwrap_or(256)
+    }
+
+    pub fn is_active(&self, exception: ExceptionNumber) -> bool {
+        if exception >= IRQ_BASE {
+            self.active >> (exception - IRQ_BASE) & 1 == 1
+        } else {
+            self.system_active >> exception & 1 == 1
+        }
+    }
+
+    pub fn set_active(&mut self, exception: ExceptionNumber, active: bool) {
+        if exception >= IRQ_BASE {
+            let bit = 1 << (exception - IRQ_BASE);
+            if active {
+                self.active |= bit;
+            } else {
+                self.active &= !bit;
+            }
+        } else if active {
+            self.system_active |= 1 << exception;
+        } else {
+            self.system_active &= !(1 << exception);
+        }
+    }
+
+    pub fn set_pending(&mut self, exception: ExceptionNumber, pending: bool) {
+        if exception >= IRQ_BASE {
+            let bit = 1 << (exception - IRQ_BASE);
+            if pending {
+                self.pending |= bit;
+            } else {
+                self.pending &= !bit;
+            }
+        } else if pending {
+            self.system_pending |= 1 << exception;
+        } else {
+            self.system_pending &= !(1 << exception);
+        }
+    }
+
+    /// Pending state of the external interrupts, combining the hardware lines of the core
+    pub fn irq_pending(&self, core_id: u8, interrupts: &Interrupts) -> u64 {
+        let lines = interrupts
+            .iter(core_id)
+            .filter(|irq| (*irq as usize) < NUM_IRQS)
+            .fold(0u64, |acc, irq| acc | 1 << irq);
+
+        self.pending | lines
+    }
+
+    /// The enables and priorities of the external interrupts, as published to the routing matrix
+    pub fn routing(&self) -> CoreRouting {
+        let mut routing = CoreRouting {
+            enabled: self.enabled,
+            forced: self.pending,
+            ..Default::default()
+        };
+
+        for (dst, src) in routing.priority.iter_mut().zip(self.priority) {
+            *dst = src >> 4;
+        }
+
+        routing
+    }
+
+    /// A configurable fault escalates to HardFault when its handler is disabled
+    pub fn is_fault_enabled(&self, exception: ExceptionNumber) -> bool {
+        match exception {
+            MEM_MANAGE => self.shcsr & SHCSR_MEMFAULTENA != 0,
+            BUS_FAULT => self.shcsr & SHCSR_BUSFAULTENA != 0,
+            USAGE_FAULT => self.shcsr & SHCSR_USGFAULTENA != 0,
+            _ => true,
+        }
+    }
+
+    /// Highest priority pending and enabled exception, ties go to the lowest exception number
+    pub fn highest_pending(&self, core_id: u8, interrupts: &Interrupts) -> Option<ExceptionNumber> {
+        let system = (1..16).filter(|v| self.system_pending >> v & 1 == 1);
+
+        let irq_pending = self.irq_pending(core_id, interrupts) & self.enabled & !self.active;
+        let irqs = (0..NUM_IRQS as u16)
+            .filter(move |v| irq_pending >> v & 1 == 1)
+            .map(|v| v + IRQ_BASE);
+
+        system
+            .chain(irqs)
+            .min_by_key(|v| (self.exception_priority(*v), *v))
+    }
+
+    pub fn read(&self, offset: u32, core_id: u8, interrupts: &Interrupts) -> u32 {
+        // index of the 32 bit bank for the NVIC registers
+        let bank = ((offset & 0x7F) >> 2) as u64;
+        let nvic_bits = |bits: u64| match bank {
+            0 => bits as u32,
+            1 => (bits >> 32) as u32,
+            _ => 0,
+        };
+
+        match offset {
+            ICTR => (NUM_IRQS as u32).div_ceil(32) - 1,
+            ACTLR => self.actlr,
+            NVIC_ISER0..=0x13C => nvic_bits(self.enabled),
+            NVIC_ICER0..=0x1BC => nvic_bits(self.enabled),
+            NVIC_ISPR0..=0x23C => nvic_bits(self.irq_pending(core_id, interrupts)),
+            NVIC_ICPR0..=0x2BC => nvic_bits(self.irq_pending(core_id, interrupts)),
+            NVIC_IABR0..=0x33C => nvic_bits(self.active),
+            NVIC_ITNS0..=0x3BC => 0, // everything targets the Secure state
+            NVIC_IPR0..=0x5EC => {
+                let index = (offset - NVIC_IPR0) as usize;
+                (0..4).fold(0, |acc, i| {
+                    let priority = self.priority.get(index + i).copied().unwrap_or(0);
+                    acc | (priority as u32) << (i * 8)
+                })
+            }
+            CPUID => CPUID_VALUE,
+            ICSR => self.icsr(core_id, interrupts),
+            VTOR => self.vtor,
+            AIRCR => AIRCR_VECTKEYSTAT << 16 | self.aircr,
+            SCR => self.scr,
+            CCR => self.ccr,
+            SHPR1..=SHPR3 => {
+                let index = (offset - SHPR1) as usize;
+                u32::from_le_bytes(self.system_priority[index..index + 4].try_into().unwrap())
+            }
+            SHCSR => {
+                let active = (self.system_active >> MEM_MANAGE) as u32 & 0b111;
+                self.shcsr | active & 0b11 | (active & 0b100) << 1
+            }
+            CFSR => self.cfsr,
+            HFSR => self.hfsr,
+            MMFAR => self.mmfar,
+            BFAR => self.bfar,
+            CPACR => self.cpacr,
+            _ => {
+                log::warn!("Unimplemented System Control Space read at {:#X}", offset);
+                0
+            }
+        }
+    }
+
+    pub fn write(&mut self, offset: u32, value: u32) {
+        let bank = (offset & 0x7F) >> 2;
+        let bits = match bank {
+            0 => value as u64,
+            1 => (value as u64) << 32,
+            _ => 0,
+        } & ((1 << NUM_IRQS) - 1);
+
+        match offset {
+            ACTLR => self.actlr = value,
+            NVIC_ISER0..=0x13C => self.enabled |= bits,
+            NVIC_ICER0..=0x1BC => self.enabled &= !bits,
+            NVIC_ISPR0..=0x23C => self.pending |= bits,
+            NVIC_ICPR0..=0x2BC => self.pending &= !bits,
+            NVIC_IABR0..=0x33C | NVIC_ITNS0..=0x3BC => {}
+            NVIC_IPR0..=0x5EC => {
+                let index = (offset - NVIC_IPR0) as usize;
+                for (i, byte) in value.to_le_bytes().into_iter().enumerate() {
+                    if let Some(priority) = self.priority.get_mut(index + i) {
+                        *priority = byte & PRIORITY_MASK;
+                    }
+                }
+            }
+            ICSR => {
+                if value & ICSR_PENDNMISET != 0 {
+                    self.set_pending(NMI, true);
+                }
+                if value & ICSR_PENDSVSET != 0 {
+                    self.set_pending(PEND_SV, true);
+                }
+                if value & ICSR_PENDSVCLR != 0 {
+                    self.set_pending(PEND_SV, false);
+                }
+                if value & ICSR_PENDSTSET != 0 {
+                    self.set_pending(SYS_TICK, true);
+                }
+                if value & ICSR_PENDSTCLR != 0 {
+                    self.set_pending(SYS_TICK, false);
+                }
+            }
+            VTOR => self.vtor = value & !0x7F,
+            AIRCR => {
+                if value >> 16 != AIRCR_VECTKEY {
+                    return;
+                }
+
+                self.aircr = value & 0x700; // PRIGROUP
+                if value & AIRCR_SYSRESETREQ != 0 {
+                    self.reset_requested = true;
+                }
+            }
+            SCR => self.scr = value & 0b11110,
+            CCR => self.ccr = value,
+            SHPR1..=SHPR3 => {
+                let index = (offset - SHPR1) as usize;
+                for (i, byte) in value.to_le_bytes().into_iter().enumerate() {
+                    self.system_priority[index + i] = byte & PRIORITY_MASK;
+                }
+            }
+            SHCSR => {
+                self.shcsr = value & (SHCSR_MEMFAULTENA | SHCSR_BUSFAULTENA | SHCSR_USGFAULTENA)
+            }
+            CFSR => self.cfsr &= !value, // write 1 to clear
+            HFSR => self.hfsr &= !value,
+            MMFAR => self.mmfar = value,
+            BFAR => self.bfar = value,
+            CPACR => self.cpacr = value,
+            STIR => {
+                let irq = value & 0x1FF;
+                if (irq as usize) < NUM_IRQS {
+                    self.pending |= 1 << irq;
+                }
+            }
+            _ => log::warn!("Unimplemented System Control Space write at {:#X}", offset),
+        }
+    }
+
+    fn icsr(&self, core_id: u8, interrupts: &Interrupts) -> u32 {
+        let pending = self.highest_pending(core_id, interrupts).unwrap_or(0) as u32;
+        let irq_pending = self.irq_pending(core_id, interrupts) & self.enabled != 0;
+
+        let mut value = pending << 12 | (irq_pending as u32) << 22;
+
+        if self.system_pending >> NMI & 1 == 1 {
+            value |= ICSR_PENDNMISET;
+        }
+        if self.system_pending >> PEND_SV & 1 == 1 {
+            value |= ICSR_PENDSVSET;
+        }
+        if self.system_pending >> SYS_TICK & 1 == 1 {
+            value |= ICSR_PENDSTSET;
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_pending() {
+        let mut nvic = Nvic::default();
+        let mut interrupts = Interrupts::default();
+
+        interrupts.set_irq(Interrupts::UART0_IRQ, true);
+        interrupts.set_irq(Interrupts::TIMER0_IRQ_0, true);
+        assert_eq!(nvic.highest_pending(0, &interrupts), None);
+
+        nvic.write(NVIC_ISER0, 1 << Interrupts::TIMER0_IRQ_0);
+        nvic.write(NVIC_ISER0 + 4, 1 << (Interrupts::UART0_IRQ - 32));
+        assert_eq!(
+            nvic.highest_pending(0, &interrupts),
+            Some(IRQ_BASE + Interrupts::TIMER0_IRQ_0 as u16)
+        );
+
+        // Lower the priority of the timer below UART0
+        nvic.write(NVIC_IPR0, 0x40);
+        assert_eq!(
+            nvic.highest_pending(0, &interrupts),
+            Some(IRQ_BASE + Interrupts::UART0_IRQ as u16)
+        );
+
+        // Same priority, the lower exception number wins
+        nvic.write(ICSR, ICSR_PENDSVSET);
+        assert_eq!(nvic.highest_pending(0, &interrupts), Some(PEND_SV));
+    }
+}
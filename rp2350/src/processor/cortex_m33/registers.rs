@@ -0,0 +1,202 @@
+/**
+ * @file processor/cortex_m33/registers.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Core and special purpose registers of the Cortex-M33
+ */
+pub type Register = u8;
+
+pub const SP: Register = 13;
+pub const LR: Register = 14;
+pub const PC: Register = 15;
+
+pub const CONTROL_NPRIV: u32 = 1 << 0;
+pub const CONTROL_SPSEL: u32 = 1 << 1;
+
+/// Flags of the APSR
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Flags {
+    pub n: bool,
+    pub z: bool,
+    pub c: bool,
+    pub v: bool,
+    pub q: bool,
+    pub ge: u8,
+}
+
+/// R0-R14 with the banked stack pointers, the PC is kept by the core itself.
+/// Only the Secure state is modelled, the Non-secure banks do not exist.
+#[derive(Debug, Default, Clone)]
+pub struct Registers {
+    r: [u32; 13],
+    pub lr: u32,
+    pub msp: u32,
+    pub psp: u32,
+    pub msplim: u32,
+    pub psplim: u32,
+
+    pub flags: Flags,
+    /// Number of the exception being handled, 0 in Thread mode
+    pub ipsr: u16,
+    /// IT state of the EPSR, the T bit is always set
+    pub it_state: u8,
+
+    pub primask: bool,
+    pub faultmask: bool,
+    pub basepri: u8,
+    pub control: u32,
+}
+
+impl Registers {
+    pub fn read(&self, reg: Register) -> u32 {
+        match reg {
+            0..=12 => self.r[reg as usize],
+            SP => *self.sp(),
+            LR => self.lr,
+            _ => panic!("Invalid register r{reg}"),
+        }
+    }
+
+    pub fn write(&mut self, reg: Register, value: u32) {
+        match reg {
+            0..=12 => self.r[reg as usize] = value,
+            SP => *self.sp_mut() = value & !0b11,
+            LR => self.lr = value,
+            _ => panic!("Invalid register r{reg}"),
+        }
+    }
+
+    pub fn is_handler_mode(&self) -> bool {
+        self.ipsr != 0
+    }
+
+    pub fn is_privileged(&self) -> bool {
+        self.is_handler_mode() || self.control & CONTROL_NPRIV == 0
+    }
+
+    /// The process stack is only used in Thread mode with SPSEL set
+    pub fn is_using_psp(&self) -> bool {
+        !self.is_handler_mode() && self.control & CONTROL_SPSEL != 0
+    }
+
+    pub fn sp(&self) -> &u32 {
+        if self.is_using_psp() {
+            &self.psp
+        } else {
+            &self.msp
+        }
+    }
+
+    pub fn sp_mut(&mut self) -> &mut u32 {
+        if self.is_using_psp() {
+            &mut self.psp
+        } else {
+            &mut self.msp
+        }
+    }
+
+    pub fn in_it_block(&self) -> bool {
+        self.it_state & 0xF != 0
+    }
+
+    /// Advance the IT state after an instruction inside an IT block
+    pub fn advance_it(&mut self) {
+        if self.it_state & 0b111 == 0 {
+            self.it_state = 0;
+        } else {
+            self.it_state = (self.it_state & 0xE0) | ((self.it_state << 1) & 0x1F);
+        }
+    }
+
+    pub fn apsr(&self) -> u32 {
+        let Flags { n, z, c, v, q, ge } = self.flags;
+        (n as u32) << 31
+            | (z as u32) << 30
+            | (c as u32) << 29
+            | (v as u32) << 28
+            | (q as u32) << 27
+            | (ge as u32 & 0xF) << 16
+    }
+
+    pub fn set_apsr(&mut self, value: u32, nzcvq: bool, ge: bool) {
+        if nzcvq {
+            self.flags.n = value & (1 << 31) != 0;
+            self.flags.z = value & (1 << 30) != 0;
+            self.flags.c = value & (1 << 29) != 0;
+            self.flags.v = value & (1 << 28) != 0;
+            self.flags.q = value & (1 << 27) != 0;
+        }
+
+        if ge {
+            self.flags.ge = (value >> 16) as u8 & 0xF;
+        }
+    }
+
+    pub fn epsr(&self) -> u32 {
+        let it = self.it_state as u32;
+        1 << 24 | (it & 0b11) << 25 | (it >> 2) << 10
+    }
+
+    pub fn xpsr(&self) -> u32 {
+        self.apsr() | self.epsr() | self.ipsr as u32
+    }
+
+    pub fn set_xpsr(&mut self, value: u32) {
+        self.set_apsr(value, true, true);
+        self.it_state = (((value >> 25) & 0b11) | ((value >> 10) & 0x3F) << 2) as u8;
+        self.ipsr = (value & 0x1FF) as u16;
+    }
+
+    pub fn condition_passed(&self, cond: u32) -> bool {
+        let Flags { n, z, c, v, .. } = self.flags;
+
+        let result = match cond >> 1 {
+            0b000 => z,
+            0b001 => c,
+            0b010 => n,
+            0b011 => v,
+            0b100 => c && !z,
+            0b101 => n == v,
+            0b110 => n == v && !z,
+            _ => true,
+        };
+
+        if cond & 1 == 1 && cond != 0b1111 {
+            !result
+        } else {
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banked_stack_pointer() {
+        let mut registers = Registers::default();
+        registers.write(SP, 0x2000_1000);
+        registers.control = CONTROL_SPSEL;
+        registers.write(SP, 0x2000_0803);
+        assert_eq!(registers.msp, 0x2000_1000);
+        assert_eq!(registers.psp, 0x2000_0800);
+
+        // Handler mode always uses the main stack
+        registers.ipsr = 16;
+        assert_eq!(registers.read(SP), 0x2000_1000);
+    }
+
+    #[test]
+    fn test_xpsr() {
+        let mut registers = Registers::default();
+        registers.set_xpsr(0xF800_0000 | 1 << 24 | 0x7 << 25 | 0x3F << 10 | 0x13);
+        assert!(registers.flags.n && registers.flags.z && registers.flags.c);
+        assert_eq!(registers.it_state, 0xFF);
+        assert_eq!(registers.ipsr, 0x13);
+        assert_eq!(
+            registers.xpsr(),
+            0xF800_0000 | 1 << 24 | 0x3 << 25 | 0x3F << 10 | 0x13
+        );
+    }
+}
@@ -0,0 +1,152 @@
+/**
+ * @file processor/cortex_m33/systick.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief SysTick timer of the Cortex-M33, counting down on the processor clock
+ */
+// Offsets from the start of the System Control Space (0xE000_E000)
+pub const SYST_CSR: u32 = 0x010; // SysTick Control and Status Register
+pub const SYST_RVR: u32 = 0x014; // SysTick Reload Value Register
+pub const SYST_CVR: u32 = 0x018; // SysTick Current Value Register
+pub const SYST_CALIB: u32 = 0x01C; // SysTick Calibration Value Register
+
+pub const CSR_ENABLE: u32 = 1 << 0;
+pub const CSR_TICKINT: u32 = 1 << 1;
+pub const CSR_CLKSOURCE: u32 = 1 << 2;
+pub const CSR_COUNTFLAG: u32 = 1 << 16;
+
+/// The counter is 24 bits wide
+const COUNTER_MASK: u32 = 0x00FF_FFFF;
+
+/// No reference clock, TENMS unknown
+const CALIB_NOREF: u32 = 1 << 31;
+
+/// The external reference clock from the tick generators is not modelled, the counter
+/// only runs with CLKSOURCE set and CALIB tells the software so with NOREF.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SysTick {
+    pub csr: u32,
+    pub rvr: u32,
+    pub cvr: u32,
+}
+
+impl SysTick {
+    pub fn is_counting(&self) -> bool {
+        self.csr & (CSR_ENABLE | CSR_CLKSOURCE) == CSR_ENABLE | CSR_CLKSOURCE
+    }
+
+    /// Counting with TICKINT set, the core has to keep ticking to take the exception
+    pub fn is_interrupting(&self) -> bool {
+        self.is_counting() && self.csr & CSR_TICKINT != 0
+    }
+
+    /// Count down for a number of processor cycles, returns whether the SysTick
+    /// exception is raised
+    pub fn advance(&mut self, cycles: u64) -> bool {
+        if !self.is_counting() || cycles == 0 {
+            return false;
+        }
+
+        let current = self.cvr as u64;
+        let reload = self.rvr as u64;
+
+        // down to 0, where it wraps if it was counting
+        if current >= cycles {
+            self.cvr = (current - cycles) as u32;
+            return self.wrap(current > 0 && self.cvr == 0);
+        }
+
+        // then loads RVR on the next cycle and counts down again
+        let after_zero = cycles - current;
+        let periods = after_zero / (reload + 1);
+
+        self.cvr = match after_zero % (reload + 1) {
+            0 => 0,
+            phase => (reload + 1 - phase) as u32,
+        };
+
+        self.wrap(current > 0 || (reload > 0 && periods > 0))
+    }
+
+    fn wrap(&mut self, wrapped: bool) -> bool {
+        if wrapped {
+            self.csr |= CSR_COUNTFLAG;
+        }
+
+        wrapped && self.csr & CSR_TICKINT != 0
+    }
+
+    /// Reading SYST_CSR clears COUNTFLAG
+    pub fn read(&mut self, offset: u32) -> u32 {
+        match offset {
+            SYST_CSR => {
+                let value = self.csr;
+                self.csr &= !CSR_COUNTFLAG;
+                value
+            }
+            SYST_RVR => self.rvr,
+            SYST_CVR => self.cvr,
+            SYST_CALIB => CALIB_NOREF,
+            _ => 0,
+        }
+    }
+
+    pub fn write(&mut self, offset: u32, value: u32) {
+        match offset {
+            SYST_CSR => {
+                if value & CSR_ENABLE != 0 && value & CSR_CLKSOURCE == 0 {
+                    log::warn!("SysTick on the reference clock, which is not modelled");
+                }
+
+                let control = CSR_ENABLE | CSR_TICKINT | CSR_CLKSOURCE;
+                self.csr = self.csr & CSR_COUNTFLAG | value & control;
+            }
+            SYST_RVR => self.rvr = value & COUNTER_MASK,
+            // any write clears the counter and COUNTFLAG
+            SYST_CVR => {
+                self.cvr = 0;
+                self.csr &= !CSR_COUNTFLAG;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_down() {
+        let mut systick = SysTick::default();
+        systick.write(SYST_RVR, 3);
+        systick.write(SYST_CSR, CSR_ENABLE | CSR_TICKINT | CSR_CLKSOURCE);
+
+        // loads RVR from 0, then 3 cycles to wrap
+        assert!(!systick.advance(1));
+        assert_eq!(systick.read(SYST_CVR), 3);
+        assert!(!systick.advance(2));
+        assert!(systick.advance(1));
+        assert_eq!(systick.read(SYST_CSR) & CSR_COUNTFLAG, CSR_COUNTFLAG);
+        assert_eq!(systick.read(SYST_CSR) & CSR_COUNTFLAG, 0);
+
+        // 2 periods of 4 cycles, then 1 cycle into the next
+        assert!(systick.advance(9));
+        assert_eq!(systick.cvr, 3);
+        assert!(!systick.advance(2));
+        assert_eq!(systick.cvr, 1);
+
+        systick.write(SYST_CVR, 0x1234);
+        assert_eq!(systick.cvr, 0);
+    }
+
+    #[test]
+    fn test_reference_clock() {
+        let mut systick = SysTick::default();
+        systick.write(SYST_RVR, 1);
+        systick.write(SYST_CSR, CSR_ENABLE | CSR_TICKINT);
+
+        assert!(!systick.advance(100));
+        assert_eq!(systick.read(SYST_CALIB), CALIB_NOREF);
+    }
+}
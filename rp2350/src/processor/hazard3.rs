@@ -5,31 +5,46 @@
  * @brief Hazard3 processor implementation.
  */
 pub mod branch_predictor;
+pub mod busy_wait;
+pub mod call_stack;
 pub mod csrs;
+pub mod disasm;
 mod exec;
 pub(crate) mod instruction_format;
+pub mod pipeline;
 pub mod registers;
 pub mod trap;
+pub mod trigger;
 
 use super::{CpuArchitecture, ProcessorContext};
 use crate::bus::{BusAccessContext, LoadStatus, StoreStatus};
+use crate::interrupts::Interrupt;
 use crate::{common::*, InspectionEvent};
 use branch_predictor::BranchPredictor;
+use call_stack::CallStack;
 use core::mem;
 use csrs::Csrs;
 pub use csrs::PrivilegeMode;
 use exec::*;
+use pipeline::{Pipeline, PipelineState, StageInstruction, StallReason};
 pub use registers::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 use trap::*;
+pub use trigger::{DebugCause, Trigger, TriggerAccess, TriggerAction};
 
 type RegisterWrite = (Register, u32);
 
+/// Extra cycle of the return of CM.POPRET and CM.POPRETZ, the fetch restarts at ra
+const ZCMP_RETURN_PENALTY: u8 = 1;
+
 #[derive(Default)]
 pub enum State {
     Wfi,
     Stall(u8, RegisterWrite),
+    /// The pipeline is busy for the given cycles without anything to write back,
+    /// like on the entry and the exit of an interrupt
+    Busy(u8),
     BusWaitLoad(Register, Rc<RefCell<LoadStatus>>),
     BusWaitStore(Rc<RefCell<StoreStatus>>),
     Sleep(Box<State>),
@@ -53,6 +68,7 @@ impl PartialEq for State {
         match (self, other) {
             (State::Wfi, State::Wfi) => true,
             (State::Stall(_, _), State::Stall(_, _)) => true,
+            (State::Busy(_), State::Busy(_)) => true,
             (State::BusWaitLoad(_, _), State::BusWaitLoad(_, _)) => true,
             (State::BusWaitStore(_), State::BusWaitStore(_)) => true,
             (State::Atomic { .. }, State::Atomic { .. }) => true,
@@ -65,6 +81,16 @@ impl PartialEq for State {
 
 impl Eq for State {}
 
+/// Cycles the interrupts cost on top of the instructions, none by default.
+/// Set them to profile firmware sensitive to the interrupt latency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterruptLatency {
+    /// Between taking an interrupt and the first instruction of its handler
+    pub entry: u8,
+    /// Between an MRET and the first instruction it returns to
+    pub exit: u8,
+}
+
 pub struct Hazard3 {
     pub pc: u32,
     pub state: State,
@@ -72,6 +98,21 @@ pub struct Hazard3 {
     pub csrs: Csrs,
     pub xx_bypass: Option<RegisterWrite>,
     pub branch_predictor: BranchPredictor,
+    pub interrupt_latency: InterruptLatency,
+    /// Instructions executed since the core was created, unlike MINSTRET it cannot be
+    /// inhibited or written by the firmware
+    pub retired: u64,
+    /// Kept from starting the next instruction, the one in flight still completes
+    pub hold: bool,
+    /// Calls and traps since the core started, for the backtrace
+    pub call_stack: CallStack,
+    /// What each stage holds, cycle by cycle
+    pub pipeline: Pipeline,
+    /// The last instruction which entered X
+    last_instruction: Option<StageInstruction>,
+    /// Address of the instruction still in flight, a load, a store or a Zcmp sequence,
+    /// and of its last access. The bus reports an error after the core moved past it
+    in_flight: (u32, u32),
 
     // for atomic instructions
     // should be clear after any atomic instruction, or SC.W or getting a trap
@@ -82,6 +123,17 @@ pub struct Hazard3 {
     pub(self) inst_seq: InstructionSequence,
 }
 
+/// Architectural state of the core, pipeline state is not included
+#[derive(Clone)]
+pub struct Hazard3Snapshot {
+    pub pc: u32,
+    pub registers: Registers,
+    pub csrs: Csrs,
+    pub sleeping: bool,
+    pub wfi: bool,
+    pub retired: u64,
+}
+
 impl Hazard3 {
     pub fn new() -> Self {
         Self {
@@ -92,6 +144,13 @@ impl Hazard3 {
             xx_bypass: None,
             local_monitor_bit: false,
             branch_predictor: BranchPredictor::default(),
+            interrupt_latency: InterruptLatency::default(),
+            retired: 0,
+            hold: false,
+            call_stack: CallStack::default(),
+            pipeline: Pipeline::default(),
+            last_instruction: None,
+            in_flight: (0, 0),
             inst_seq: InstructionSequence::default(),
         }
     }
@@ -110,15 +169,52 @@ impl CpuArchitecture for Hazard3 {
         self.pc = value;
     }
 
+    fn get_sp(&self) -> u32 {
+        self.registers.read(2)
+    }
+
     fn set_sp(&mut self, value: u32) {
         self.registers.write(2, value);
     }
 
     fn tick(&mut self, ctx: &mut ProcessorContext) {
+        let retired = self.retired;
+        let sequence = !self.inst_seq.is_empty();
+
+        self.cycle(ctx);
+        self.record_pipeline(retired != self.retired, sequence);
+    }
+
+    fn sleep(&mut self) {
+        let last_state = mem::take(&mut self.state);
+        self.state = State::Sleep(Box::new(last_state));
+    }
+
+    fn wake(&mut self) {
+        if let State::Sleep(state) = mem::take(&mut self.state) {
+            self.state = *state;
+        }
+    }
+}
+
+impl Hazard3 {
+    fn cycle(&mut self, ctx: &mut ProcessorContext) {
         if let State::Sleep(_) = self.state {
             return;
         }
 
+        // halted until the debugger resumes it
+        if self.csrs.is_in_debug_mode() {
+            return;
+        }
+
+        // The bus checks the accesses against its copy of the PMP regions, updated
+        // once a write to their CSRs landed
+        if self.csrs.pmp_changed {
+            ctx.bus.set_pmp(self.csrs.core_id as usize, self.csrs.pmp);
+            self.csrs.pmp_changed = false;
+        }
+
         // Value which was in X-X bypass is now written to register
         // since that instruction has done the M (memory) stage
         // which was 1 cycle behind the current instruction
@@ -128,6 +224,12 @@ impl CpuArchitecture for Hazard3 {
 
         self.update_state(ctx);
 
+        // a load hit a data trigger
+        if self.csrs.is_in_debug_mode() {
+            self.csrs.tick();
+            return;
+        }
+
         if self.state != State::Normal {
             // The processor is in a state where it is waiting for something
             self.csrs.tick();
@@ -135,23 +237,48 @@ impl CpuArchitecture for Hazard3 {
         }
 
         if !self.inst_seq.is_empty() {
+            // An interrupt abandons a push or a pop while it still moves the registers,
+            // the stack pointer is untouched so the instruction runs again after MRET
+            if self.is_sequence_restartable() {
+                let pc = self.in_flight.0;
+
+                if let Some((new_pc, irq)) = self.csrs.interrupt_check(pc, ctx.interrupts.clone()) {
+                    self.pc = pc;
+                    self.inst_seq = InstructionSequence::default();
+                    self.interrupt_taken(new_pc, irq, ctx);
+                    self.csrs.tick();
+                    return;
+                }
+            }
+
             // Execute the next instruction in the sequence
             self.exec_next_instruction_sequence(ctx);
             self.csrs.tick();
             return;
         }
 
+        if self.hold {
+            self.csrs.tick();
+            return;
+        }
+
         // IRQ check before executing the next instruction
-        if let Some(new_pc) = self.csrs.interrupt_check(self.pc, ctx.interrupts.clone()) {
-            self.pc = new_pc;
-            self.state = State::Normal;
+        if let Some((new_pc, irq)) = self.csrs.interrupt_check(self.pc, ctx.interrupts.clone()) {
+            self.interrupt_taken(new_pc, irq, ctx);
+            self.csrs.tick();
+            return;
+        }
+
+        // Hardware breakpoints fire before the instruction is executed
+        if let Some(action) = self.csrs.address_trigger(TriggerAccess::Execute, self.pc) {
+            self.trigger_fired(action, ctx);
             self.csrs.tick();
             return;
         }
 
         // Fetch the next instruction
-        let Ok(inst_code) = ctx.bus.fetch(self.pc) else {
-            self.trap_handle(Exception::InstructionFetchFault);
+        let Ok(inst_code) = ctx.bus.fetch(self.pc, self.fetch_context()) else {
+            self.trap_handle(Exception::InstructionFetchFault, self.pc);
             return;
         };
 
@@ -161,6 +288,7 @@ impl CpuArchitecture for Hazard3 {
 
         let ExecContext {
             exception,
+            access_address,
             register_write,
             memory_access,
             next_pc,
@@ -168,9 +296,22 @@ impl CpuArchitecture for Hazard3 {
             zcmp_actions,
             instruction_name,
             wake_opposite_core,
+            trigger,
+            trap_return,
+            branch,
+            xx_bypassed,
             ..
         } = exec_ctx;
 
+        self.csrs.rearm_triggers();
+
+        // A watchpoint fired before the access, the instruction did not execute
+        if let Some(action) = trigger {
+            self.trigger_fired(action, ctx);
+            self.csrs.tick();
+            return;
+        }
+
         ctx.inspector.emit(InspectionEvent::ExecutedInstruction {
             core: self.csrs.core_id,
             instruction: inst_code,
@@ -179,24 +320,73 @@ impl CpuArchitecture for Hazard3 {
             operands: Vec::new(), // TODO
         });
 
+        self.last_instruction = Some(StageInstruction {
+            index: self.retired,
+            address: self.pc,
+            code: inst_code,
+            name: instruction_name,
+            bypassed: xx_bypassed,
+        });
+
+        if let Some((taken, mispredicted)) = branch {
+            ctx.inspector.emit(InspectionEvent::BranchPredicted {
+                core: self.csrs.core_id,
+                address: self.pc,
+                taken,
+                mispredicted,
+            });
+        }
+
+        self.retired += 1;
         self.csrs.tick();
         self.csrs.count_instret();
 
+        if !matches!(memory_access, MemoryAccess::None) || !zcmp_actions.is_empty() {
+            self.in_flight = (self.pc, access_address);
+        }
+
         if let Some(exception) = exception {
             ctx.inspector.emit(InspectionEvent::Exception {
                 core: self.csrs.core_id,
                 exception: exception as u32,
             });
 
-            return self.trap_handle(exception);
+            if exception == Exception::BreakPoint && self.csrs.ebreak_enters_debug_mode() {
+                return self.enter_debug_mode(DebugCause::Ebreak, ctx);
+            }
+
+            let value = match exception {
+                Exception::IllegalInstruction if inst_code & 0b11 == 0b11 => inst_code,
+                Exception::IllegalInstruction => inst_code & 0xffff,
+                Exception::BreakPoint => self.pc,
+                Exception::LoadAlignment
+                | Exception::LoadFault
+                | Exception::StoreAlignment
+                | Exception::StoreFault => access_address,
+                _ => 0,
+            };
+
+            return self.trap_handle(exception, value);
         } else {
+            self.call_stack.execute(inst_code, self.pc, next_pc);
             self.pc = next_pc;
         }
 
+        if self.csrs.is_single_stepping() {
+            self.enter_debug_mode(DebugCause::Step, ctx);
+        }
+
         ctx.wake_opposite_core = wake_opposite_core;
 
         if !zcmp_actions.is_empty() {
+            // the first action of the sequence is done in the cycle of the instruction
             self.inst_seq = zcmp_actions;
+            self.exec_next_instruction_sequence(ctx);
+        }
+
+        if trap_return {
+            self.call_stack.trap_return();
+            self.state = State::Busy(self.interrupt_latency.exit);
         }
 
         if let Some(write) = register_write {
@@ -228,27 +418,223 @@ impl CpuArchitecture for Hazard3 {
             MemoryAccess::None => (),
         }
     }
+}
 
-    fn sleep(&mut self) {
-        let last_state = mem::take(&mut self.state);
-        self.state = State::Sleep(Box::new(last_state));
+impl Hazard3 {
+    pub fn snapshot(&self) -> Hazard3Snapshot {
+        let inner_state = match &self.state {
+            State::Sleep(state) => state.as_ref(),
+            state => state,
+        };
+
+        Hazard3Snapshot {
+            pc: self.pc,
+            registers: self.registers.clone(),
+            csrs: self.csrs.clone(),
+            sleeping: matches!(self.state, State::Sleep(_)),
+            wfi: *inner_state == State::Wfi,
+            retired: self.retired,
+        }
     }
 
-    fn wake(&mut self) {
-        if let State::Sleep(state) = mem::take(&mut self.state) {
-            self.state = *state;
+    /// Restore the state from a snapshot.
+    /// Any in-flight bus transaction or instruction sequence is dropped,
+    /// the core continues at the instruction boundary of the snapshot
+    pub fn restore(&mut self, snapshot: &Hazard3Snapshot) {
+        self.pc = snapshot.pc;
+        self.registers = snapshot.registers.clone();
+        self.csrs = snapshot.csrs.clone();
+        self.csrs.pmp_changed = true;
+        self.retired = snapshot.retired;
+        self.xx_bypass = None;
+        self.local_monitor_bit = false;
+        self.inst_seq = InstructionSequence::default();
+        self.call_stack.clear();
+        self.pipeline.clear();
+        self.last_instruction = None;
+        self.state = if snapshot.wfi { State::Wfi } else { State::Normal };
+
+        if snapshot.sleeping {
+            self.sleep();
         }
     }
-}
 
-impl Hazard3 {
-    fn trap_handle(&mut self, trap: impl Into<Trap>) {
-        self.csrs.trap_handle(trap, self.pc);
+    fn fetch_context(&self) -> BusAccessContext {
+        BusAccessContext {
+            size: DataSize::Word,
+            exclusive: false,
+            signed: false,
+            secure: self.csrs.privilege_mode() == PrivilegeMode::Machine,
+            privileged: self.csrs.privilege_mode() == PrivilegeMode::Machine,
+            architecture: ArchitectureType::Hazard3,
+            requestor: match self.csrs.core_id {
+                0 => Requestor::Proc0,
+                1 => Requestor::Proc1,
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.csrs.is_in_debug_mode()
+    }
+
+    /// Write the result held in the X-X bypass to the register file, as the next tick
+    /// would before anything reads it
+    pub fn flush_bypass(&mut self) {
+        if let Some((rd, value)) = self.xx_bypass.take() {
+            self.registers.write(rd, value);
+        }
+    }
+
+    /// Still working on the last instruction, stalled, waiting on the bus or going
+    /// through the sequence of a Zcmp instruction
+    pub fn is_busy(&self) -> bool {
+        let busy = matches!(
+            self.state,
+            State::Stall(..)
+                | State::Busy(_)
+                | State::BusWaitLoad(..)
+                | State::BusWaitStore(_)
+                | State::Atomic { .. }
+        );
+
+        busy || !self.inst_seq.is_empty()
+    }
+
+    /// Why no instruction entered X this cycle, from what the core is waiting on
+    fn stall_reason(&self, sequence: bool) -> StallReason {
+        if self.is_halted() {
+            return StallReason::Halted;
+        }
+
+        match self.state {
+            State::Sleep(_) => StallReason::Sleep,
+            State::Wfi => StallReason::Wfi,
+            State::Stall(..) => StallReason::MultiCycle,
+            State::BusWaitLoad(..) => StallReason::BusLoad,
+            State::BusWaitStore(_) => StallReason::BusStore,
+            State::Atomic { .. } => StallReason::Atomic,
+            State::Busy(_) => StallReason::Refill,
+            State::Normal if sequence || !self.inst_seq.is_empty() => StallReason::Sequence,
+            State::Normal if self.hold => StallReason::Hold,
+            State::Normal => StallReason::Refill,
+        }
+    }
+
+    /// The stages at the end of the cycle, `executed` when an instruction entered X.
+    /// An instruction moves to M once it leaves X, and stays there while the bus is busy
+    fn record_pipeline(&mut self, executed: bool, sequence: bool) {
+        let last = *self.pipeline.state();
+        let stall = (!executed).then(|| self.stall_reason(sequence));
+
+        let execute = match stall {
+            None | Some(StallReason::MultiCycle | StallReason::Sequence) => self.last_instruction,
+            _ => None,
+        };
+
+        let left = last
+            .execute
+            .filter(|previous| execute.is_none_or(|current| current.index != previous.index));
+
+        let memory = match (left, last.stall) {
+            (Some(instruction), _) => Some(instruction),
+            (None, Some(reason)) if reason.is_bus_wait() => last.memory,
+            _ => None,
+        };
+
+        self.pipeline.record(PipelineState {
+            cycle: last.cycle + 1,
+            fetch: matches!(stall, None | Some(StallReason::Refill)).then_some(self.pc),
+            execute,
+            memory,
+            stall,
+            bypass: self.xx_bypass,
+        });
+    }
+
+    /// The core was asleep in WFI for `cycles` without being ticked
+    pub fn slept(&mut self, cycles: u64) {
+        self.csrs.count_sleep(cycles);
+    }
+
+    /// Halt request of the debugger, the core stops before its next instruction
+    pub fn halt(&mut self) {
+        if !self.is_halted() {
+            self.csrs.enter_debug_mode(DebugCause::HaltRequest, self.pc);
+        }
+    }
+
+    /// Leave Debug Mode like DRET, at the address in DPC
+    pub fn resume(&mut self) {
+        if self.is_halted() {
+            self.pc = self.csrs.exit_debug_mode();
+        }
+    }
+
+    fn enter_debug_mode(&mut self, cause: DebugCause, ctx: &ProcessorContext) {
+        self.csrs.enter_debug_mode(cause, self.pc);
+
+        ctx.inspector.emit(InspectionEvent::DebugHalt {
+            core: self.csrs.core_id,
+            address: self.pc,
+            cause: cause as u8,
+        });
+    }
+
+    fn trigger_fired(&mut self, action: TriggerAction, ctx: &ProcessorContext) {
+        match action {
+            TriggerAction::Breakpoint => self.trap_handle(Exception::BreakPoint, self.pc),
+            TriggerAction::DebugMode => self.enter_debug_mode(DebugCause::Trigger, ctx),
+        }
+    }
+
+    /// The loaded value is only known once the load is done, the core stops after it
+    fn load_data_trigger(&mut self, value: u32, ctx: &ProcessorContext) {
+        if let Some(action) = self.csrs.data_trigger(TriggerAccess::Load, value) {
+            self.trigger_fired(action, ctx);
+        }
+    }
+
+    /// Take a trap on the current instruction, `value` is the MTVAL of an exception
+    fn trap_handle(&mut self, trap: impl Into<Trap>, value: u32) {
+        // the rest of a Zcmp sequence is dropped, a fault in it restarts the instruction
+        self.inst_seq = InstructionSequence::default();
+
+        let trap = trap.into();
+        let handler = self.csrs.trap_handle(trap, self.pc, value);
+
+        self.call_stack.trap(trap.to_xcause(), self.pc, handler);
+        self.pc = handler;
+    }
+
+    /// The bus failed the access of an instruction the core already moved past,
+    /// the trap is taken on that instruction with the faulting address
+    fn bus_fault(&mut self, exception: Exception) {
+        let (pc, address) = self.in_flight;
+        self.pc = pc;
+        self.trap_handle(exception, address);
+    }
+
+    /// Jump to the handler of an interrupt, after the entry latency
+    fn interrupt_taken(&mut self, handler: u32, irq: Interrupt, ctx: &ProcessorContext) {
+        ctx.inspector.emit(InspectionEvent::InterruptTaken {
+            core: self.csrs.core_id,
+            number: irq as u32,
+        });
+
+        // MCAUSE was written when the interrupt was checked
+        self.call_stack.trap(self.csrs.mcause(), self.pc, handler);
+        self.pc = handler;
+        self.state = State::Busy(self.interrupt_latency.entry);
     }
 
     fn update_state(&mut self, ctx: &mut ProcessorContext) {
         match mem::take(&mut self.state) {
             State::Sleep(_state) => {}
+            // done with once it runs out, the last cycle is spent here
+            State::Busy(cycles) if cycles > 0 => self.state = State::Busy(cycles - 1),
+            State::Busy(_) => {}
             State::Stall(cycles, reg_write) => {
                 if cycles == 1 {
                     self.xx_bypass = Some(reg_write);
@@ -260,16 +646,18 @@ impl Hazard3 {
                 LoadStatus::Waiting => self.state = State::BusWaitLoad(rd, load_status),
                 LoadStatus::Done(value) => {
                     self.registers.write(rd, value);
+                    self.load_data_trigger(value, ctx);
                 }
 
                 LoadStatus::ExclusiveDone(value) => {
                     // successful claim the exclusive access to the address
                     self.registers.write(rd, value);
                     self.local_monitor_bit = true;
+                    self.load_data_trigger(value, ctx);
                 }
 
                 LoadStatus::Error(_e) => {
-                    self.trap_handle(Exception::LoadFault);
+                    self.bus_fault(Exception::LoadFault);
                     return;
                 }
             },
@@ -281,20 +669,24 @@ impl Hazard3 {
                     self.local_monitor_bit = false;
                 }
                 StoreStatus::Error(_e) => {
-                    self.trap_handle(Exception::StoreFault);
+                    self.bus_fault(Exception::StoreFault);
                     return;
                 }
             },
             State::Wfi => {
                 match self.csrs.interrupt_check(self.pc, ctx.interrupts.clone()) {
-                    Some(new_pc) => {
-                        self.pc = new_pc;
-                        self.state = State::Normal;
-                    }
+                    Some((new_pc, irq)) => self.interrupt_taken(new_pc, irq, ctx),
+
+                    // A pending and enabled interrupt wakes the core even when
+                    // MSTATUS.MIE masks it, the execution continues after the WFI
+                    None if self.csrs.has_wake_up_interrupt() => {}
 
                     None => {
-                        // No interrupt, just return to WFI state
+                        // No interrupt, sleep until one of the lines rises
                         self.state = State::Wfi;
+                        ctx.interrupts
+                            .borrow_mut()
+                            .sleep_until_interrupt(self.csrs.core_id);
                     }
                 }
             }
@@ -328,6 +720,7 @@ impl Hazard3 {
                         AtomicOp::MaxU => read_value.max(value),
                     };
 
+                    self.in_flight.1 = address;
                     let store_status = ctx.bus.store(
                         address,
                         new_value,
@@ -336,6 +729,7 @@ impl Hazard3 {
                             exclusive: true,
                             signed: false,
                             secure: self.csrs.privilege_mode() == PrivilegeMode::Machine,
+                            privileged: self.csrs.privilege_mode() == PrivilegeMode::Machine,
                             architecture: ArchitectureType::Hazard3,
                             requestor: match self.csrs.core_id {
                                 0 => Requestor::Proc0,
@@ -353,13 +747,13 @@ impl Hazard3 {
                             self.state = State::BusWaitStore(status);
                         }
                         Err(_e) => {
-                            self.trap_handle(Exception::StoreFault);
+                            self.bus_fault(Exception::StoreFault);
                             return;
                         }
                     }
                 }
                 LoadStatus::Error(_e) => {
-                    self.trap_handle(Exception::LoadFault);
+                    self.bus_fault(Exception::LoadFault);
                     return;
                 }
                 LoadStatus::Done(_) => unreachable!(),
@@ -368,6 +762,15 @@ impl Hazard3 {
         };
     }
 
+    /// Only the loads and the stores of a Zcmp sequence are left, nothing it did so far
+    /// keeps it from running again
+    fn is_sequence_restartable(&self) -> bool {
+        matches!(
+            self.inst_seq.iter().next(),
+            Some(ZcmpAction::Load { .. } | ZcmpAction::Store { .. })
+        )
+    }
+
     fn exec_next_instruction_sequence(&mut self, ctx: &mut ProcessorContext) {
         // TODO: Is the timing of these actions correct?
         // The sequence is guaranteed to be non-empty
@@ -384,6 +787,7 @@ impl Hazard3 {
                 from_register,
             } => {
                 let value = self.registers.read(from_register);
+                self.in_flight.1 = address;
                 let store_status = ctx.bus.store(
                     address,
                     value,
@@ -392,6 +796,7 @@ impl Hazard3 {
                         exclusive: false,
                         signed: false,
                         secure: self.csrs.privilege_mode() == PrivilegeMode::Machine,
+                        privileged: self.csrs.privilege_mode() == PrivilegeMode::Machine,
                         architecture: ArchitectureType::Hazard3,
                         requestor: match self.csrs.core_id {
                             0 => Requestor::Proc0,
@@ -406,7 +811,7 @@ impl Hazard3 {
                         self.state = State::BusWaitStore(status);
                     }
                     Err(_e) => {
-                        self.trap_handle(Exception::StoreFault);
+                        self.bus_fault(Exception::StoreFault);
                         return;
                     }
                 }
@@ -416,6 +821,7 @@ impl Hazard3 {
                 address,
                 to_register,
             } => {
+                self.in_flight.1 = address;
                 let load_status = ctx.bus.load(
                     address,
                     BusAccessContext {
@@ -423,6 +829,7 @@ impl Hazard3 {
                         exclusive: false,
                         signed: false,
                         secure: self.csrs.privilege_mode() == PrivilegeMode::Machine,
+                        privileged: self.csrs.privilege_mode() == PrivilegeMode::Machine,
                         architecture: ArchitectureType::Hazard3,
                         requestor: match self.csrs.core_id {
                             0 => Requestor::Proc0,
@@ -437,7 +844,7 @@ impl Hazard3 {
                         self.state = State::BusWaitLoad(to_register, status);
                     }
                     Err(_e) => {
-                        self.trap_handle(Exception::LoadFault);
+                        self.bus_fault(Exception::LoadFault);
                         return;
                     }
                 }
@@ -446,7 +853,9 @@ impl Hazard3 {
             ZcmpAction::Return => {
                 // RET = JALR x0, 0(x1)
                 let return_address = self.registers.read(1);
+                self.call_stack.ret(return_address);
                 self.pc = return_address;
+                self.state = State::Busy(ZCMP_RETURN_PENALTY);
             }
         }
     }
@@ -508,4 +917,493 @@ mod tests {
         assert!(cpu.xx_bypass.is_none());
         assert_eq!(cpu.registers.x[0], 0);
     }
+
+    #[test]
+    fn test_machine_timer_interrupt() {
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00000013).unwrap(); // nop
+        ctx.interrupts.borrow_mut().set_machine_timer(0, true);
+
+        // masked by MIE.MTIE
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 4);
+
+        cpu.set_pc(SRAM);
+        cpu.csrs.mie = csrs::MIE_MTIE;
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, 0x2000_0324);
+        assert_eq!(cpu.csrs.read(0x342), Ok(0x8000_0007));
+        assert_ne!(cpu.csrs.mip & csrs::MIP_MTIP, 0);
+    }
+
+    #[test]
+    fn test_interrupt_latency() {
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00000013).unwrap(); // nop
+        ctx.bus.sram.write_u32(0x324, 0x30200073).unwrap(); // mret
+        cpu.interrupt_latency = InterruptLatency { entry: 2, exit: 3 };
+        cpu.csrs.mie = csrs::MIE_MTIE;
+
+        ctx.interrupts.borrow_mut().set_machine_timer(0, true);
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, 0x2000_0324);
+        ctx.interrupts.borrow_mut().set_machine_timer(0, false);
+
+        for _ in 0..2 {
+            cpu.tick(&mut ctx);
+            assert!(cpu.state == State::Busy(0));
+        }
+
+        // MRET, then the exit latency before the interrupted code resumes
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM);
+
+        for _ in 0..3 {
+            cpu.tick(&mut ctx);
+            assert_eq!(cpu.pc, SRAM);
+        }
+
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 4);
+    }
+
+    #[test]
+    fn test_user_mode_preempted() {
+        setup!(cpu, ctx);
+        cpu.csrs.mie = csrs::MIE_MTIE;
+        cpu.csrs.mstatus &= !csrs::MSTATUS_MIE;
+        ctx.interrupts.borrow_mut().set_machine_timer(0, true);
+
+        // MSTATUS.MIE does not mask the interrupts of U-mode
+        cpu.csrs.privilege_mode = PrivilegeMode::User;
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, 0x2000_0324);
+        assert_eq!(cpu.csrs.privilege_mode(), PrivilegeMode::Machine);
+    }
+
+    #[test]
+    fn test_wfi_sleep() {
+        const MCOUNTINHIBIT: u16 = 0x320;
+        const MSLEEP: u16 = 0xBF0;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x10500073).unwrap(); // wfi
+        cpu.csrs.mie = csrs::MIE_MTIE;
+
+        cpu.tick(&mut ctx);
+        assert!(cpu.state == State::Wfi);
+        cpu.tick(&mut ctx);
+        assert!(ctx.interrupts.borrow().is_asleep(0));
+
+        // the rising timer line wakes the core, which takes the interrupt
+        ctx.interrupts.borrow_mut().set_machine_timer(0, true);
+        assert!(!ctx.interrupts.borrow().is_asleep(0));
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, 0x2000_0324);
+
+        // the skipped cycles are counted, unless the clock is gated in deep sleep
+        cpu.csrs.write(MCOUNTINHIBIT, 0).unwrap();
+        cpu.csrs.tick();
+        let cycles = cpu.csrs.mcycles;
+        cpu.slept(10);
+        assert_eq!(cpu.csrs.mcycles, cycles + 10);
+
+        cpu.csrs.write(MSLEEP, csrs::MSLEEP_DEEPSLEEP).unwrap();
+        cpu.csrs.tick();
+        let cycles = cpu.csrs.mcycles;
+        cpu.slept(10);
+        assert_eq!(cpu.csrs.mcycles, cycles);
+    }
+
+    #[test]
+    fn test_machine_software_interrupt() {
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00000013).unwrap(); // nop
+        ctx.interrupts.borrow_mut().set_machine_software(1, true);
+        cpu.csrs.mie = csrs::MIE_MSIE | csrs::MIE_MTIE;
+
+        // raised on the other core
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 4);
+
+        // taken before the timer
+        cpu.set_pc(SRAM);
+        ctx.interrupts.borrow_mut().set_machine_software(0, true);
+        ctx.interrupts.borrow_mut().set_machine_timer(0, true);
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, 0x2000_0324);
+        assert_eq!(cpu.csrs.read(0x342), Ok(0x8000_0003));
+        assert_ne!(cpu.csrs.mip & csrs::MIP_MSIP, 0);
+    }
+
+    #[test]
+    fn test_external_interrupt_routing() {
+        use crate::interrupts::Interrupts;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0xbe02a073).unwrap(); // csrrs x0, meiea, x5
+        cpu.registers.x[5] = 2 | 1 << 17; // window 2, IRQ 33
+        cpu.csrs.mie = csrs::MIE_MEIE;
+        ctx.interrupts
+            .borrow_mut()
+            .set_irq(Interrupts::UART0_IRQ, true);
+
+        // not enabled in MEIEA yet
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 4);
+
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, 0x2000_0324);
+
+        let route = ctx.interrupts.borrow().route(Interrupts::UART0_IRQ);
+        assert_eq!(route.enabled, [true, false]);
+        assert!(route.is_delivered(0));
+        assert!(!route.is_delivered(1));
+    }
+
+    #[test]
+    fn test_external_interrupt_priorities() {
+        const MTVEC: u16 = 0x305;
+        const MCAUSE: u16 = 0x342;
+        const MEIEA: u16 = 0xBE0;
+        const MEIPRA: u16 = 0xBE3;
+        const MEINEXT: u16 = 0xBE4;
+        const MEICONTEXT: u16 = 0xBE5;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00000013).unwrap(); // nop
+
+        fn write_csr(cpu: &mut Hazard3, csr: u16, value: u32) {
+            cpu.csrs.select_window(csr, value);
+            cpu.csrs.write(csr, value).unwrap();
+            cpu.csrs.tick();
+        }
+
+        write_csr(&mut cpu, MTVEC, (SRAM + 0x100) | 1);
+        write_csr(&mut cpu, MEIEA, (1 << 2 | 1 << 5 | 1 << 7) << 16);
+        write_csr(&mut cpu, MEIPRA, 1 << 24); // IRQ 2 at priority 1
+        write_csr(&mut cpu, MEIPRA, 1 | 3 << 20 | 3 << 28); // IRQ 5 and 7 at priority 3
+        cpu.csrs.mie = csrs::MIE_MEIE;
+
+        for irq in [2, 5, 7] {
+            ctx.interrupts.borrow_mut().set_irq(irq, true);
+        }
+
+        // every external IRQ goes through the same vector
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 0x100 + 4 * 11);
+        assert_eq!(cpu.csrs.read(MCAUSE), Ok(0x8000_000B));
+
+        let context = cpu.csrs.read(MEICONTEXT).unwrap();
+        assert_eq!(context & csrs::MEICONTEXT_PREEMPT, 4 << 16);
+        assert_ne!(context & csrs::MEICONTEXT_MRETEIRQ, 0);
+
+        // the lowest number wins between the same priorities
+        assert_eq!(cpu.csrs.read(MEINEXT), Ok(5 << 2));
+        write_csr(&mut cpu, MEINEXT, csrs::MEINEXT_UPDATE);
+        let context = cpu.csrs.read(MEICONTEXT).unwrap();
+        assert_eq!(context & csrs::MEICONTEXT_IRQ, 5 << 4);
+        assert_eq!(context & csrs::MEICONTEXT_NOIRQ, 0);
+
+        for irq in [5, 7] {
+            ctx.interrupts.borrow_mut().set_irq(irq, false);
+        }
+
+        cpu.csrs.interrupt_check(cpu.pc, ctx.interrupts.clone());
+        assert_eq!(cpu.csrs.read(MEINEXT), Ok(2 << 2));
+
+        ctx.interrupts.borrow_mut().set_irq(2, false);
+        cpu.csrs.interrupt_check(cpu.pc, ctx.interrupts.clone());
+        assert_eq!(cpu.csrs.read(MEINEXT), Ok(csrs::MEINEXT_NOIRQ));
+
+        // MRET restores the priority of the preempted code
+        cpu.csrs.trap_mret();
+        let context = cpu.csrs.read(MEICONTEXT).unwrap();
+        assert_eq!(context & csrs::MEICONTEXT_PREEMPT, 0);
+        assert_eq!(context & csrs::MEICONTEXT_MRETEIRQ, 0);
+    }
+
+    #[test]
+    fn test_meicontext_clearts() {
+        const MEICONTEXT: u16 = 0xBE5;
+
+        let mut cpu = Hazard3::new();
+        cpu.csrs.mie = csrs::MIE_MEIE | csrs::MIE_MTIE | csrs::MIE_MSIE;
+
+        // csrrsi a0, meicontext, CLEARTS
+        cpu.csrs.select_window(MEICONTEXT, csrs::MEICONTEXT_CLEARTS);
+        let saved = cpu.csrs.read(MEICONTEXT).unwrap();
+        cpu.csrs.write(MEICONTEXT, saved | csrs::MEICONTEXT_CLEARTS).unwrap();
+        cpu.csrs.tick();
+
+        assert_ne!(saved & csrs::MEICONTEXT_MTIESAVE, 0);
+        assert_ne!(saved & csrs::MEICONTEXT_MSIESAVE, 0);
+        assert_eq!(cpu.csrs.mie, csrs::MIE_MEIE);
+
+        cpu.csrs.select_window(MEICONTEXT, 0);
+        let context = cpu.csrs.read(MEICONTEXT).unwrap();
+        assert_eq!(context & (csrs::MEICONTEXT_MTIESAVE | csrs::MEICONTEXT_CLEARTS), 0);
+    }
+
+    #[test]
+    fn test_pmp_fetch_fault() {
+        const MCAUSE: u16 = 0x342;
+        const PMPCFG0: u16 = 0x3A0;
+        const PMPCFG2: u16 = 0x3A2;
+        const PMPADDR0: u16 = 0x3B0;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00000013).unwrap(); // nop
+
+        // no region allows U-mode to execute from the SRAM
+        cpu.csrs.privilege_mode = PrivilegeMode::User;
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.csrs.read(MCAUSE), Ok(Exception::InstructionFetchFault as u32));
+        assert_eq!(cpu.csrs.privilege_mode(), PrivilegeMode::Machine);
+        assert_ne!(cpu.pc, SRAM + 4);
+
+        // 8 bytes NAPOT region at the start of the SRAM, execute only
+        cpu.csrs.write(PMPADDR0, SRAM >> 2).unwrap();
+        cpu.csrs.tick();
+        cpu.csrs.write(PMPCFG0, 0b11 << 3 | 1 << 2).unwrap();
+        cpu.csrs.tick();
+
+        cpu.csrs.privilege_mode = PrivilegeMode::User;
+        cpu.set_pc(SRAM);
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 4);
+        // the bus keeps its copy until the next write
+        assert!(!cpu.csrs.pmp_changed);
+
+        // the regions over the ROM, the peripherals and the SIO are hardwired
+        cpu.csrs.privilege_mode = PrivilegeMode::Machine;
+        cpu.csrs.write(PMPCFG2, 0).unwrap();
+        cpu.csrs.tick();
+        assert_eq!(cpu.csrs.read(PMPCFG2), Ok(0x001f_1f1f));
+        assert!(!cpu.csrs.pmp_changed);
+    }
+
+    #[test]
+    fn test_user_counter_access() {
+        const MCOUNTEREN: u16 = 0x306;
+        const CYCLE: u16 = 0xC00;
+        const INSTRETH: u16 = 0xC82;
+
+        let mut csrs = Csrs::default();
+        csrs.mcycles = 42;
+        csrs.privilege_mode = PrivilegeMode::User;
+        assert_eq!(csrs.read(CYCLE), Err(Exception::IllegalInstruction));
+        assert_eq!(csrs.read(INSTRETH), Err(Exception::IllegalInstruction));
+
+        csrs.strict = false;
+        assert_eq!(csrs.read(CYCLE), Ok(0));
+
+        // MCOUNTEREN.CY only
+        csrs.privilege_mode = PrivilegeMode::Machine;
+        csrs.write(MCOUNTEREN, 0b1).unwrap();
+        csrs.tick();
+        csrs.strict = true;
+        csrs.privilege_mode = PrivilegeMode::User;
+        assert_eq!(csrs.read(CYCLE), Ok(42));
+        assert_eq!(csrs.read(INSTRETH), Err(Exception::IllegalInstruction));
+    }
+
+    #[test]
+    fn test_hardware_breakpoint() {
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00000013).unwrap(); // nop
+        ctx.bus.sram.write_u32(4, 0x00000013).unwrap(); // nop
+        cpu.csrs.triggers[0] = Trigger::breakpoint(SRAM + 4);
+
+        cpu.tick(&mut ctx);
+        cpu.tick(&mut ctx);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.pc, SRAM + 4);
+        assert!(cpu.csrs.triggers[0].has_hit());
+
+        // nothing happens until the debugger resumes the core
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 4);
+
+        // the breakpoint does not fire again on the instruction it halted on
+        cpu.resume();
+        cpu.tick(&mut ctx);
+        assert!(!cpu.is_halted());
+        assert_eq!(cpu.pc, SRAM + 8);
+    }
+
+    #[test]
+    fn test_store_watchpoint() {
+        const DCSR: u16 = 0x7B0;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x00112023).unwrap(); // sw x1, 0(x2)
+        cpu.registers.write(1, 0xdead);
+        cpu.registers.write(2, SRAM + 0x100);
+        cpu.csrs.triggers[1] = Trigger::watchpoint(SRAM + 0x100, false, true);
+
+        cpu.tick(&mut ctx);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.pc, SRAM);
+        assert_eq!(ctx.bus.sram.read_u32(0x100).unwrap(), 0);
+
+        let dcsr = cpu.csrs.read(DCSR).unwrap();
+        assert_eq!((dcsr & csrs::DCSR_CAUSE) >> 6, DebugCause::Trigger as u32);
+    }
+
+    #[test]
+    fn test_call_stack() {
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x008000ef).unwrap(); // jal ra, 8
+        ctx.bus.sram.write_u32(4, 0x00000000).unwrap(); // illegal
+        ctx.bus.sram.write_u32(8, 0x00008067).unwrap(); // ret
+        ctx.bus.sram.write_u32(0x324, 0x0000006f).unwrap(); // j . at MTVEC
+
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.pc, SRAM + 8);
+        assert_eq!(cpu.call_stack.frames()[0].return_address, SRAM + 4);
+
+        while cpu.pc != SRAM + 4 {
+            cpu.tick(&mut ctx);
+        }
+        assert!(cpu.call_stack.frames().is_empty());
+
+        for _ in 0..4 {
+            cpu.tick(&mut ctx);
+        }
+
+        let fault = cpu.call_stack.fault().unwrap();
+        assert_eq!(fault.pc, SRAM + 4);
+        assert_eq!(fault.cause, Exception::IllegalInstruction as u32);
+        assert_eq!(cpu.call_stack.frames().len(), 1);
+    }
+
+    #[test]
+    fn test_trap_value() {
+        const MEPC: u16 = 0x341;
+        const MTVAL: u16 = 0x343;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x0005a503).unwrap(); // lw a0, 0(a1)
+        ctx.bus.sram.write_u32(4, 0x3ff02573).unwrap(); // csrr a0, 0x3ff
+
+        cpu.registers.write(11, SRAM + 2);
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.csrs.mcause(), Exception::LoadAlignment as u32);
+        assert_eq!(cpu.csrs.read(MEPC), Ok(SRAM));
+        assert_eq!(cpu.csrs.read(MTVAL), Ok(SRAM + 2));
+
+        // the bits of an illegal instruction
+        cpu.set_pc(SRAM + 4);
+        cpu.tick(&mut ctx);
+        assert_eq!(cpu.csrs.mcause(), Exception::IllegalInstruction as u32);
+        assert_eq!(cpu.csrs.read(MTVAL), Ok(0x3ff02573));
+
+        cpu.csrs.write(MTVAL, 0x1234).unwrap();
+        cpu.csrs.tick();
+        assert_eq!(cpu.csrs.mtval(), 0x1234);
+    }
+
+    #[test]
+    fn test_zcmp_interrupted() {
+        fn run(cpu: &mut Hazard3, ctx: &mut ProcessorContext, ticks: usize) {
+            for _ in 0..ticks {
+                ctx.bus.tick();
+                cpu.tick(ctx);
+            }
+        }
+
+        setup!(cpu, ctx);
+        // cm.push {ra, s0-s1}, -16; cm.popret {ra, s0-s1}, 16
+        ctx.bus.sram.write_u32(0, 0xbe62_b862).unwrap();
+        ctx.bus.sram.write_u32(0x40, 0x00000013).unwrap(); // nop
+        ctx.bus.sram.write_u32(0x324, 0x30200073).unwrap(); // mret
+        cpu.registers.write(1, SRAM + 0x40);
+        cpu.registers.write(2, SRAM + 0x100);
+        cpu.registers.write(8, 0x22);
+        cpu.registers.write(9, 0x33);
+        cpu.csrs.mie = csrs::MIE_MTIE;
+
+        // the first store goes with the instruction, the timer fires before the second
+        run(&mut cpu, &mut ctx, 1);
+        ctx.interrupts.borrow_mut().set_machine_timer(0, true);
+        run(&mut cpu, &mut ctx, 1);
+        assert_eq!(cpu.pc, 0x2000_0324);
+        assert_eq!(cpu.csrs.read(0x341), Ok(SRAM));
+        assert_eq!(cpu.registers.read(2), SRAM + 0x100);
+        assert!(cpu.inst_seq.is_empty());
+
+        ctx.interrupts.borrow_mut().set_machine_timer(0, false);
+        run(&mut cpu, &mut ctx, 1);
+        assert_eq!(cpu.pc, SRAM);
+
+        // restarted from the start, 1 + n cycles
+        run(&mut cpu, &mut ctx, 4);
+        assert!(!cpu.is_busy());
+        assert_eq!(cpu.registers.read(2), SRAM + 0xf0);
+        assert_eq!(ctx.bus.sram.read_u32(0xfc).unwrap(), 0x33);
+        assert_eq!(ctx.bus.sram.read_u32(0xf8).unwrap(), 0x22);
+        assert_eq!(ctx.bus.sram.read_u32(0xf4).unwrap(), SRAM + 0x40);
+
+        cpu.registers.write(1, 0);
+        cpu.registers.write(8, 0);
+        cpu.registers.write(9, 0);
+
+        // 3 + n cycles, the return costs one more
+        run(&mut cpu, &mut ctx, 5);
+        assert_eq!(cpu.pc, SRAM + 0x40);
+        assert_eq!(cpu.registers.read(2), SRAM + 0x100);
+        assert_eq!(cpu.registers.read(9), 0x33);
+
+        run(&mut cpu, &mut ctx, 1);
+        assert_eq!(cpu.pc, SRAM + 0x40);
+        run(&mut cpu, &mut ctx, 1);
+        assert_eq!(cpu.pc, SRAM + 0x44);
+    }
+
+    #[test]
+    fn test_pipeline() {
+        use pipeline::PIPELINE_HISTORY;
+
+        setup!(cpu, ctx);
+        ctx.bus.sram.write_u32(0, 0x0005a503).unwrap(); // lw a0, 0(a1)
+        ctx.bus.sram.write_u32(4, 0x00150613).unwrap(); // addi a2, a0, 1
+        ctx.bus.sram.write_u32(8, 0x02c646b3).unwrap(); // div a3, a2, a2
+        ctx.bus.sram.write_u32(12, 0x00000013).unwrap(); // nop
+        ctx.bus.sram.write_u32(0x100, 41).unwrap();
+        cpu.registers.write(11, SRAM + 0x100);
+        cpu.pipeline.recording = true;
+
+        for _ in 0..PIPELINE_HISTORY {
+            ctx.bus.tick();
+            cpu.tick(&mut ctx);
+        }
+
+        let cycles = cpu.pipeline.history().copied().collect::<Vec<_>>();
+        let address = |instruction: Option<StageInstruction>| instruction.map(|i| i.address);
+
+        assert_eq!(address(cycles[0].execute), Some(SRAM));
+        assert_eq!(cycles[0].fetch, Some(SRAM + 4));
+
+        // the load is done in M while the next instruction executes
+        assert_eq!(address(cycles[1].execute), Some(SRAM + 4));
+        assert_eq!(address(cycles[1].memory), Some(SRAM));
+
+        // the sum of the addi is forwarded to the division
+        assert_eq!(address(cycles[2].execute), Some(SRAM + 8));
+        assert!(cycles[2].execute.unwrap().bypassed);
+        assert_eq!(cycles[2].bypass, Some((12, 42)));
+
+        assert_eq!(cycles[3].stall, Some(StallReason::MultiCycle));
+        assert_eq!(address(cycles[3].execute), Some(SRAM + 8));
+        assert_eq!(cycles[3].memory, None);
+        assert_eq!(cycles[3].fetch, None);
+
+        let nop = cycles
+            .iter()
+            .position(|cycle| address(cycle.execute) == Some(SRAM + 12))
+            .unwrap();
+        assert_eq!(address(cycles[nop].memory), Some(SRAM + 8));
+        assert_eq!(cycles[nop].stall, None);
+    }
 }
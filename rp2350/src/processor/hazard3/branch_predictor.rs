@@ -2,36 +2,169 @@
  * @file /processor/hazard/branch_predictor.rs
  * @author Nguyen Le Duy
  * @date 31/03/2025
- * @brief A simple branch predictor that uses a last branch taken strategy.
+ * @brief Branch predictors of the Hazard3, the last branch taken strategy of the core and
+ *        other models to compare the timing with
  */
+use std::collections::BTreeMap;
+
+/// Largest history of the gshare model, its table has 2^bits counters
+pub const MAX_GSHARE_BITS: u8 = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PredictorModel {
+    /// Only the last branch taken is predicted taken again, as the Hazard3 does
+    #[default]
+    LastTaken,
+    /// Every branch is predicted taken
+    AlwaysTaken,
+    /// 2 bit counters indexed by the address XOR the global history of `bits` branches
+    Gshare { bits: u8 },
+}
+
+impl PredictorModel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PredictorModel::LastTaken => "Last taken",
+            PredictorModel::AlwaysTaken => "Always taken",
+            PredictorModel::Gshare { .. } => "Gshare",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BranchStats {
+    pub executed: u64,
+    pub mispredicted: u64,
+}
 
 #[derive(Default)]
 pub struct BranchPredictor {
     pub last_branch_taken: Option<u32>,
+    model: PredictorModel,
+    /// Outcomes of the last branches for gshare, the latest in the LSB
+    history: u32,
+    counters: Vec<u8>,
+    hits: u64,
+    misses: u64,
+    branches: BTreeMap<u32, BranchStats>,
 }
 
 impl BranchPredictor {
-    pub fn miss_predicted(&mut self, pc: u32, taken: bool) -> bool {
-        if taken {
-            if self.last_branch_taken == Some(pc) {
-                // correctly predicted
-                false
-            } else {
-                // mispredicted
-                self.last_branch_taken = Some(pc);
-                true
+    pub fn new(model: PredictorModel) -> Self {
+        let mut predictor = Self::default();
+        predictor.set_model(model);
+        predictor
+    }
+
+    pub fn model(&self) -> PredictorModel {
+        self.model
+    }
+
+    /// Switch to another model, which starts without anything learned and no statistics
+    pub fn set_model(&mut self, model: PredictorModel) {
+        let model = match model {
+            PredictorModel::Gshare { bits } => PredictorModel::Gshare {
+                bits: bits.clamp(1, MAX_GSHARE_BITS),
+            },
+            model => model,
+        };
+
+        *self = Self {
+            model,
+            // weakly not taken, like a branch never seen by the other models
+            counters: match model {
+                PredictorModel::Gshare { bits } => vec![1; 1 << bits],
+                _ => Vec::new(),
+            },
+            ..Self::default()
+        };
+    }
+
+    /// Branches predicted right
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Statistics of every branch executed, by its address
+    pub fn branches(&self) -> &BTreeMap<u32, BranchStats> {
+        &self.branches
+    }
+
+    /// The branches mispredicted at least once, the most mispredicted first
+    pub fn mispredictions(&self) -> Vec<(u32, BranchStats)> {
+        let mut branches = self
+            .branches
+            .iter()
+            .filter(|(_, stats)| stats.mispredicted > 0)
+            .map(|(&address, &stats)| (address, stats))
+            .collect::<Vec<_>>();
+
+        branches.sort_by(|a, b| b.1.mispredicted.cmp(&a.1.mispredicted).then(a.0.cmp(&b.0)));
+        branches
+    }
+
+    pub fn clear_stats(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+        self.branches.clear();
+    }
+
+    fn gshare_index(&self, pc: u32, bits: u8) -> usize {
+        (((pc >> 1) ^ self.history) & ((1 << bits) - 1)) as usize
+    }
+
+    fn predict(&self, pc: u32) -> bool {
+        match self.model {
+            PredictorModel::LastTaken => self.last_branch_taken == Some(pc),
+            PredictorModel::AlwaysTaken => true,
+            PredictorModel::Gshare { bits } => self.counters[self.gshare_index(pc, bits)] >= 2,
+        }
+    }
+
+    fn update(&mut self, pc: u32, taken: bool) {
+        match self.model {
+            PredictorModel::LastTaken => {
+                if taken {
+                    self.last_branch_taken = Some(pc);
+                } else if self.last_branch_taken == Some(pc) {
+                    self.last_branch_taken = None;
+                }
             }
-        } else {
-            if self.last_branch_taken == Some(pc) {
-                // mispredicted
-                self.last_branch_taken = None;
-                true
-            } else {
-                // correctly predicted
-                false
+            PredictorModel::AlwaysTaken => {}
+            PredictorModel::Gshare { bits } => {
+                let index = self.gshare_index(pc, bits);
+                let counter = &mut self.counters[index];
+
+                *counter = match taken {
+                    true => (*counter + 1).min(3),
+                    false => counter.saturating_sub(1),
+                };
+
+                self.history = ((self.history << 1) | taken as u32) & ((1 << bits) - 1);
             }
         }
     }
+
+    pub fn miss_predicted(&mut self, pc: u32, taken: bool) -> bool {
+        let missed = self.predict(pc) != taken;
+        self.update(pc, taken);
+
+        let stats = self.branches.entry(pc).or_default();
+        stats.executed += 1;
+
+        if missed {
+            stats.mispredicted += 1;
+            self.misses += 1;
+        } else {
+            self.hits += 1;
+        }
+
+        missed
+    }
 }
 
 #[cfg(test)]
@@ -65,4 +198,51 @@ mod test {
         assert_eq!(predictor.miss_predicted(0x2000, true), false);
         assert_eq!(predictor.miss_predicted(0x3000, true), true);
     }
+
+    #[test]
+    fn test_statistics() {
+        let mut predictor = BranchPredictor::new(PredictorModel::AlwaysTaken);
+
+        for taken in [true, false, true, false] {
+            predictor.miss_predicted(0x1000, taken);
+        }
+        predictor.miss_predicted(0x2000, false);
+
+        assert_eq!((predictor.hits(), predictor.misses()), (2, 3));
+        let stats = |executed, mispredicted| BranchStats {
+            executed,
+            mispredicted,
+        };
+        assert_eq!(
+            predictor.mispredictions(),
+            vec![(0x1000, stats(4, 2)), (0x2000, stats(1, 1))]
+        );
+
+        predictor.set_model(PredictorModel::LastTaken);
+        assert!(predictor.branches().is_empty());
+    }
+
+    #[test]
+    fn test_gshare() {
+        let mut predictor = BranchPredictor::new(PredictorModel::Gshare { bits: 4 });
+
+        // a loop branch taken 3 times then falling through, learned from the history
+        let mut misses = Vec::new();
+        for _ in 0..8 {
+            for taken in [true, true, true, false] {
+                misses.push(predictor.miss_predicted(0x1000, taken));
+            }
+        }
+
+        assert!(misses[..4].iter().any(|&missed| missed));
+        assert!(misses[24..].iter().all(|&missed| !missed));
+
+        predictor.set_model(PredictorModel::Gshare { bits: 40 });
+        assert_eq!(
+            predictor.model(),
+            PredictorModel::Gshare {
+                bits: MAX_GSHARE_BITS
+            }
+        );
+    }
 }
@@ -0,0 +1,671 @@
+/**
+ * @file processor/hazard3/busy_wait.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Recognition of the busy-wait delay loops, so they can be fast-forwarded
+ *
+ * Only short straight-line loops closed by a single backward branch are considered.
+ * The body is interpreted on a copy of the state, a counter loop is one where every
+ * iteration adds the same amount to the registers and words it touches, a timer loop
+ * is one polling the lower half of a system timer without changing anything else.
+ */
+use super::instruction_format::*;
+use super::{Hazard3, Register, State};
+use crate::bus::Bus;
+use crate::utils::{extract_bit, extract_bits};
+use std::collections::BTreeMap;
+
+/// Longest loop body considered as a delay loop
+pub const MAX_INSTRUCTIONS: usize = 8;
+
+const TIMER_BASES: [u32; 2] = [0x400b_0000, 0x400b_8000];
+const TIMELR: u32 = 0x0c;
+const TIMERAWL: u32 = 0x28;
+
+/// Iterations run on the copy to make sure the progression of a counter is regular
+const CHECKED_ITERATIONS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayKind {
+    /// Counting a register or a variable in the SRAM up or down
+    Counter,
+    /// Polling the lower 32 bits of TIMER0 or TIMER1
+    Timer(usize),
+}
+
+/// A loop the core is spinning in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelayLoop {
+    /// First instruction of the loop, the target of its backward branch
+    pub start: u32,
+    /// Address of the backward branch
+    pub end: u32,
+    pub kind: DelayKind,
+    /// Instructions executed by one iteration
+    pub instructions: u32,
+}
+
+impl DelayLoop {
+    pub fn contains(&self, pc: u32) -> bool {
+        (self.start..=self.end).contains(&pc)
+    }
+}
+
+/// What the remaining iterations of a loop do, worked out at the start of the loop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Plan {
+    /// Iterations which can be skipped before the one leaving the loop, and what each
+    /// of them adds to the registers and to the words of the SRAM
+    Counter {
+        iterations: u64,
+        registers: Vec<(Register, u32)>,
+        memory: Vec<(u32, u32)>,
+    },
+    /// The loop is left once the timer is `delta` ticks further
+    Timer { timer: usize, delta: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Lui(Register, u32),
+    Addi(Register, Register, u32),
+    Add(Register, Register, Register),
+    Sub(Register, Register, Register),
+    /// Load word into rd from rs1 + offset
+    Load(Register, Register, u32),
+    /// Store word of rs2 at rs1 + offset
+    Store(Register, Register, u32),
+    Branch(Condition, Register, Register, u32),
+}
+
+fn decode(pc: u32, code: u32) -> Option<(Op, u32)> {
+    if code & 0b11 != 0b11 {
+        return decode_compressed(pc, code as u16).map(|op| (op, 2));
+    }
+
+    let funct3 = extract_bits(code, 12..=14);
+    let funct7 = extract_bits(code, 25..=31);
+
+    let op = match code & 0x7f {
+        0b0110111 => {
+            let u = UType::from(code);
+            Op::Lui(u.rd, u.imm)
+        }
+        0b0010011 if funct3 == 0 => {
+            let i = IType::from(code);
+            Op::Addi(i.rd, i.rs1, i.imm)
+        }
+        0b0110011 if funct3 == 0 => {
+            let r = RType::from(code);
+            match funct7 {
+                0b0000000 => Op::Add(r.rd, r.rs1, r.rs2),
+                0b0100000 => Op::Sub(r.rd, r.rs1, r.rs2),
+                _ => return None,
+            }
+        }
+        0b0000011 if funct3 == 0b010 => {
+            let i = IType::from(code);
+            Op::Load(i.rd, i.rs1, i.imm)
+        }
+        0b0100011 if funct3 == 0b010 => {
+            let s = SType::from(code);
+            Op::Store(s.rs1, s.rs2, s.imm)
+        }
+        0b1100011 => {
+            let b = BType::from(code);
+            let condition = match funct3 {
+                0b000 => Condition::Eq,
+                0b001 => Condition::Ne,
+                0b100 => Condition::Lt,
+                0b101 => Condition::Ge,
+                0b110 => Condition::Ltu,
+                0b111 => Condition::Geu,
+                _ => return None,
+            };
+            Op::Branch(condition, b.rs1, b.rs2, pc.wrapping_add(b.imm))
+        }
+        _ => return None,
+    };
+
+    Some((op, 4))
+}
+
+fn decode_compressed(pc: u32, code: u16) -> Option<Op> {
+    let funct3 = extract_bits(code, 13..=15);
+
+    let op = match (code & 0b11, funct3) {
+        // c.lw
+        (0b00, 0b010) => {
+            let offset = (extract_bits(code, 10..=12) << 3)
+                | (extract_bit(code, 6) << 2)
+                | (extract_bit(code, 5) << 6);
+            Op::Load(crs2_(code), crs1_(code), offset as u32)
+        }
+        // c.sw
+        (0b00, 0b110) => {
+            let offset = (extract_bits(code, 10..=12) << 3)
+                | (extract_bit(code, 6) << 2)
+                | (extract_bit(code, 5) << 6);
+            Op::Store(crs1_(code), crs2_(code), offset as u32)
+        }
+        // c.addi, c.nop
+        (0b01, 0b000) => Op::Addi(crs1(code), crs1(code), imm_ci(code)),
+        // c.li
+        (0b01, 0b010) => Op::Addi(crs1(code), 0, imm_ci(code)),
+        // c.lui, the same encoding with sp is c.addi16sp
+        (0b01, 0b011) if crs1(code) != 2 => Op::Lui(crs1(code), imm_ci(code) << 12),
+        // c.sub
+        (0b01, 0b100) if extract_bits(code, 10..=12) == 0b011 && extract_bits(code, 5..=6) == 0 => {
+            Op::Sub(crs1_(code), crs1_(code), crs2_(code))
+        }
+        (0b01, 0b110) => Op::Branch(Condition::Eq, crs1_(code), 0, pc.wrapping_add(imm_cb(code))),
+        (0b01, 0b111) => Op::Branch(Condition::Ne, crs1_(code), 0, pc.wrapping_add(imm_cb(code))),
+        // c.lwsp
+        (0b10, 0b010) if crs1(code) != 0 => {
+            let offset = (extract_bit(code, 12) << 5)
+                | (extract_bits(code, 4..=6) << 2)
+                | (extract_bits(code, 2..=3) << 6);
+            Op::Load(crs1(code), 2, offset as u32)
+        }
+        // c.mv and c.add
+        (0b10, 0b100) if crs2(code) != 0 => match extract_bit(code, 12) {
+            0 => Op::Add(crs1(code), 0, crs2(code)),
+            _ => Op::Add(crs1(code), crs1(code), crs2(code)),
+        },
+        // c.swsp
+        (0b10, 0b110) => {
+            let offset = (extract_bits(code, 9..=12) << 2) | (extract_bits(code, 7..=8) << 6);
+            Op::Store(2, crs2(code), offset as u32)
+        }
+        _ => return None,
+    };
+
+    Some(op)
+}
+
+/// Decode the loop around `pc`, the instructions up to the first branch have to be
+/// supported and the branch has to jump back at or before `pc`
+fn decode_loop(pc: u32, bus: &Bus) -> Option<(u32, u32, Vec<Op>)> {
+    let mut address = pc;
+    let mut branch = None;
+
+    for _ in 0..MAX_INSTRUCTIONS {
+        let code = u32::from_le_bytes(bus.peek_slice(address, 4)?.try_into().ok()?);
+        let (op, size) = decode(address, code)?;
+
+        if let Op::Branch(_, _, _, target) = op {
+            branch = Some((address, target));
+            break;
+        }
+
+        address += size;
+    }
+
+    let (end, start) = branch.filter(|&(end, target)| target <= pc && end > target)?;
+
+    // the body before `pc` is decoded again from the start of the loop, its
+    // instructions may not line up with the ones scanned above
+    let mut body = Vec::new();
+    let mut address = start;
+
+    loop {
+        let code = u32::from_le_bytes(bus.peek_slice(address, 4)?.try_into().ok()?);
+        let (op, size) = decode(address, code)?;
+        body.push(op);
+
+        if address == end {
+            return Some((start, end, body));
+        }
+
+        if matches!(op, Op::Branch(..)) || body.len() == MAX_INSTRUCTIONS {
+            return None;
+        }
+
+        address += size;
+    }
+}
+
+/// Copy of the state the loop body runs on
+#[derive(Clone)]
+struct Machine<'a> {
+    registers: [u32; 32],
+    /// Words stored by the loop, on top of the SRAM
+    memory: BTreeMap<u32, u32>,
+    bus: &'a Bus,
+}
+
+impl Machine<'_> {
+    fn write(&mut self, rd: Register, value: u32) {
+        if rd != 0 {
+            self.registers[rd as usize & 0x1f] = value;
+        }
+    }
+
+    fn read(&self, rs: Register) -> u32 {
+        self.registers[rs as usize & 0x1f]
+    }
+
+    fn load(&self, address: u32, timers: &[u32; 2], timer_read: &mut Option<usize>) -> Option<u32> {
+        if let Some(timer) = TIMER_BASES
+            .iter()
+            .position(|&base| address == base + TIMERAWL || address == base + TIMELR)
+        {
+            *timer_read = Some(timer);
+            return Some(timers[timer]);
+        }
+
+        if address & 0b11 != 0 || address & 0xF000_0000 != Bus::SRAM {
+            return None;
+        }
+
+        if let Some(&value) = self.memory.get(&address) {
+            return Some(value);
+        }
+
+        let bytes = self.bus.peek_slice(address, 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+
+    /// Run one iteration, return whether the backward branch was taken
+    fn run(
+        &mut self,
+        body: &[Op],
+        timers: &[u32; 2],
+        timer_read: &mut Option<usize>,
+    ) -> Option<bool> {
+        for op in body.iter() {
+            match *op {
+                Op::Lui(rd, imm) => self.write(rd, imm),
+                Op::Addi(rd, rs1, imm) => self.write(rd, self.read(rs1).wrapping_add(imm)),
+                Op::Add(rd, rs1, rs2) => {
+                    self.write(rd, self.read(rs1).wrapping_add(self.read(rs2)))
+                }
+                Op::Sub(rd, rs1, rs2) => {
+                    self.write(rd, self.read(rs1).wrapping_sub(self.read(rs2)))
+                }
+                Op::Load(rd, rs1, offset) => {
+                    let value =
+                        self.load(self.read(rs1).wrapping_add(offset), timers, timer_read)?;
+                    self.write(rd, value);
+                }
+                Op::Store(rs1, rs2, offset) => {
+                    let address = self.read(rs1).wrapping_add(offset);
+
+                    if address & 0b11 != 0
+                        || address & 0xF000_0000 != Bus::SRAM
+                        || self.bus.peek_slice(address, 4).is_none()
+                    {
+                        return None;
+                    }
+
+                    self.memory.insert(address, self.read(rs2));
+                }
+                Op::Branch(condition, rs1, rs2, _) => {
+                    let (a, b) = (self.read(rs1), self.read(rs2));
+
+                    return Some(match condition {
+                        Condition::Eq => a == b,
+                        Condition::Ne => a != b,
+                        Condition::Lt => (a as i32) < (b as i32),
+                        Condition::Ge => (a as i32) >= (b as i32),
+                        Condition::Ltu => a < b,
+                        Condition::Geu => a >= b,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    fn same_state(&self, other: &Machine) -> bool {
+        self.registers == other.registers && self.memory == other.memory
+    }
+
+    fn word(&self, address: u32) -> u32 {
+        self.memory.get(&address).copied().unwrap_or_else(|| {
+            self.bus
+                .peek_slice(address, 4)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(u32::from_le_bytes)
+                .unwrap_or_default()
+        })
+    }
+}
+
+fn registers_of(core: &Hazard3) -> [u32; 32] {
+    let mut registers: [u32; 32] = core::array::from_fn(|i| core.registers.read(i as Register));
+
+    // the write still in the X-X bypass is already visible to the next instruction
+    if let Some((rd, value)) = core.xx_bypass.filter(|&(rd, _)| rd != 0) {
+        registers[rd as usize & 0x1f] = value;
+    }
+
+    registers
+}
+
+/// Find the delay loop the core is spinning in, without touching the state of the chip
+pub fn detect(core: &Hazard3, bus: &Bus, timers: [u32; 2]) -> Option<DelayLoop> {
+    if matches!(core.state, State::Sleep(_) | State::Wfi) {
+        return None;
+    }
+
+    let (start, end, body) = decode_loop(core.pc, bus)?;
+
+    let mut machine = Machine {
+        registers: registers_of(core),
+        memory: BTreeMap::new(),
+        bus,
+    };
+
+    // the registers may be halfway through an iteration, only the shape of the loop
+    // is checked here
+    let mut timer_read = None;
+    machine.run(&body, &timers, &mut timer_read)?;
+
+    let kind = match timer_read {
+        Some(timer) => DelayKind::Timer(timer),
+        None => DelayKind::Counter,
+    };
+
+    Some(DelayLoop {
+        start,
+        end,
+        kind,
+        instructions: body.len() as u32,
+    })
+}
+
+/// Work out the remaining iterations of the loop, the core has to be at its start
+pub(crate) fn plan(core: &Hazard3, bus: &Bus, delay: &DelayLoop, timers: [u32; 2]) -> Option<Plan> {
+    if core.pc != delay.start || core.state != State::Normal || !core.inst_seq.is_empty() {
+        return None;
+    }
+
+    let (start, end, body) = decode_loop(core.pc, bus)?;
+
+    if start != delay.start || end != delay.end {
+        return None;
+    }
+
+    let initial = Machine {
+        registers: registers_of(core),
+        memory: BTreeMap::new(),
+        bus,
+    };
+
+    match delay.kind {
+        DelayKind::Counter => plan_counter(initial, &body, timers),
+        DelayKind::Timer(timer) => plan_timer(initial, &body, timers, timer),
+    }
+}
+
+fn plan_counter(initial: Machine, body: &[Op], timers: [u32; 2]) -> Option<Plan> {
+    let mut states = vec![initial];
+    let mut timer_read = None;
+
+    for _ in 0..CHECKED_ITERATIONS {
+        let mut next = states.last()?.clone();
+
+        // leaving the loop within the checked iterations is not worth a skip
+        if !next.run(body, &timers, &mut timer_read)? || timer_read.is_some() {
+            return None;
+        }
+
+        states.push(next);
+    }
+
+    let words: Vec<u32> = states.last()?.memory.keys().copied().collect();
+    let delta_of = |value: &dyn Fn(&Machine) -> u32| -> Option<u32> {
+        let delta = value(&states[1]).wrapping_sub(value(&states[0]));
+
+        states
+            .windows(2)
+            .all(|pair| value(&pair[1]).wrapping_sub(value(&pair[0])) == delta)
+            .then_some(delta)
+    };
+
+    let registers = (1..32u8)
+        .map(|rd| Some((rd, delta_of(&|state: &Machine| state.read(rd))?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let memory = words
+        .iter()
+        .map(|&address| Some((address, delta_of(&|state: &Machine| state.word(address))?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let registers: Vec<_> = registers
+        .into_iter()
+        .filter(|&(_, delta)| delta != 0)
+        .collect();
+    let memory: Vec<_> = memory
+        .into_iter()
+        .filter(|&(_, delta)| delta != 0)
+        .collect();
+
+    let Some(&Op::Branch(condition, rs1, rs2, _)) = body.last() else {
+        return None;
+    };
+
+    // the operands of the branch are the registers at the end of an iteration
+    let delta = |rs: Register| {
+        registers
+            .iter()
+            .find(|&&(rd, _)| rd == rs)
+            .map(|&(_, delta)| delta)
+            .unwrap_or_default()
+    };
+
+    let a = (states[1].read(rs1), delta(rs1));
+    let b = (states[1].read(rs2), delta(rs2));
+
+    // the iterations run on the copy were all taken, the current one included
+    let exit = exit_iteration(condition, a, b).filter(|&exit| exit >= CHECKED_ITERATIONS as u64)?;
+
+    Some(Plan::Counter {
+        iterations: exit,
+        registers,
+        memory,
+    })
+}
+
+/// First iteration after the current one where the branch is not taken, given the
+/// value of the operands at the end of the current iteration and their progression
+fn exit_iteration(condition: Condition, a: (u32, u32), b: (u32, u32)) -> Option<u64> {
+    match condition {
+        Condition::Ne => {
+            // solve diff + k * step == 0 modulo 2^32
+            let diff = a.0.wrapping_sub(b.0);
+            let step = a.1.wrapping_sub(b.1);
+
+            if step == 0 {
+                return None;
+            }
+
+            let shift = step.trailing_zeros();
+
+            if diff.trailing_zeros() < shift {
+                return None;
+            }
+
+            let modulus_mask = u32::MAX >> shift;
+            let k = ((diff.wrapping_neg() >> shift).wrapping_mul(inverse(step >> shift)))
+                & modulus_mask;
+            Some(k as u64)
+        }
+        // taken while equal, a loop still running after a few iterations never leaves
+        Condition::Eq => None,
+        Condition::Lt | Condition::Ge => {
+            let a = (a.0 as i32 as i64, a.1 as i32 as i64);
+            let b = (b.0 as i32 as i64, b.1 as i32 as i64);
+            exit_ordered(
+                condition == Condition::Lt,
+                a,
+                b,
+                i32::MIN as i64..=i32::MAX as i64,
+            )
+        }
+        Condition::Ltu | Condition::Geu => {
+            let a = (a.0 as i64, a.1 as i32 as i64);
+            let b = (b.0 as i64, b.1 as i32 as i64);
+            exit_ordered(condition == Condition::Ltu, a, b, 0..=u32::MAX as i64)
+        }
+    }
+}
+
+fn exit_ordered(
+    less: bool,
+    a: (i64, i64),
+    b: (i64, i64),
+    range: core::ops::RangeInclusive<i64>,
+) -> Option<u64> {
+    // taken while `gap > 0` for `a < b`, or while `gap >= 0` for `a >= b`
+    let (gap, step) = match less {
+        true => (b.0 - a.0, b.1 - a.1),
+        false => (a.0 - b.0, a.1 - b.1),
+    };
+
+    if step >= 0 {
+        return None;
+    }
+
+    let k = match less {
+        true => (gap + (-step) - 1) / -step,
+        false => gap / -step + 1,
+    };
+
+    // the progression has to stay in range, a wrap around changes the comparison
+    let last = |value: (i64, i64)| value.0 + k * value.1;
+
+    (range.contains(&last(a)) && range.contains(&last(b))).then_some(k as u64)
+}
+
+/// Inverse of an odd number modulo 2^32
+fn inverse(value: u32) -> u32 {
+    let mut inverse = value;
+
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2u32.wrapping_sub(value.wrapping_mul(inverse)));
+    }
+
+    inverse
+}
+
+fn plan_timer(initial: Machine, body: &[Op], timers: [u32; 2], timer: usize) -> Option<Plan> {
+    let mut timer_read = None;
+    let mut settled = initial;
+
+    if !settled.run(body, &timers, &mut timer_read)? || timer_read != Some(timer) {
+        return None;
+    }
+
+    // nothing but the value read from the timer may decide when the loop is left
+    let mut again = settled.clone();
+
+    if !again.run(body, &timers, &mut timer_read)? || !again.same_state(&settled) {
+        return None;
+    }
+
+    let exits = |delta: u32| {
+        let mut timers = timers;
+        timers[timer] = timers[timer].wrapping_add(delta);
+
+        let mut machine = settled.clone();
+        machine.run(body, &timers, &mut None).map(|taken| !taken)
+    };
+
+    let mut high = 1u32;
+
+    while !exits(high)? {
+        high = high.checked_mul(2)?;
+    }
+
+    let mut low = high / 2;
+
+    // the first tick where the loop is left, assuming the comparison is monotonic
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+
+        match exits(middle)? {
+            true => high = middle,
+            false => low = middle,
+        }
+    }
+
+    Some(Plan::Timer { timer, delta: high })
+}
+
+/// Fast-forward the core over `iterations` of a counter loop, the cycles and
+/// instructions are accounted by the caller
+pub(crate) fn apply(core: &mut Hazard3, bus: &mut Bus, plan: &Plan, iterations: u64) {
+    let Plan::Counter {
+        registers, memory, ..
+    } = plan
+    else {
+        return;
+    };
+
+    let times = iterations as u32;
+    let mut values = registers_of(core);
+    core.xx_bypass = None;
+
+    for &(rd, delta) in registers.iter() {
+        values[rd as usize] = values[rd as usize].wrapping_add(delta.wrapping_mul(times));
+    }
+
+    for (rd, value) in values.iter().enumerate().skip(1) {
+        core.registers.write(rd as Register, *value);
+    }
+
+    for &(address, delta) in memory.iter() {
+        let offset = address - Bus::SRAM;
+
+        if let Ok(value) = bus.sram.read_u32(offset) {
+            bus.sram
+                .write_u32(offset, value.wrapping_add(delta.wrapping_mul(times)))
+                .ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_iteration() {
+        // counting down to zero with `bnez`
+        assert_eq!(
+            exit_iteration(Condition::Ne, (9, u32::MAX), (0, 0)),
+            Some(9)
+        );
+        // counting up by 2 to 10 with `bne`
+        assert_eq!(exit_iteration(Condition::Ne, (2, 2), (10, 0)), Some(4));
+        // an odd distance is never reached with an even step
+        assert_eq!(exit_iteration(Condition::Ne, (1, 2), (10, 0)), None);
+        // `for (i = 0; i < 100; i++)`, the first iteration left i at 1
+        assert_eq!(exit_iteration(Condition::Lt, (1, 1), (100, 0)), Some(99));
+        // `while (i >= 0) i -= 3`
+        assert_eq!(
+            exit_iteration(Condition::Ge, (7, -3i32 as u32), (0, 0)),
+            Some(3)
+        );
+        assert_eq!(
+            exit_iteration(Condition::Ltu, (1, 1), (0x8000_0000, 0)),
+            Some(0x7fff_ffff)
+        );
+        // a counter running away from its bound never leaves
+        assert_eq!(exit_iteration(Condition::Lt, (1, u32::MAX), (100, 0)), None);
+        assert_eq!(inverse(3).wrapping_mul(3), 1);
+    }
+}
@@ -0,0 +1,281 @@
+/**
+ * @file processor/hazard3/call_stack.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Shadow call stack of the Hazard3, rebuilt from the jumps and the traps
+ */
+/// Deeper calls drop the outermost frames, so a runaway recursion stays bounded
+pub const MAX_CALL_DEPTH: usize = 256;
+
+const OPCODE_JAL: u32 = 0b1101111;
+const OPCODE_JALR: u32 = 0b1100111;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// A jump which linked its return address in ra or t0
+    Call,
+    /// Entry of a trap handler with its MCAUSE, left with MRET
+    Trap(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    /// Address of the call, or of the instruction the trap was taken on
+    pub site: u32,
+    /// Entry of the function or of the handler
+    pub target: u32,
+    /// Where the frame returns to
+    pub return_address: u32,
+}
+
+/// The stack as it was when an exception was taken
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    pub cause: u32,
+    pub pc: u32,
+    pub frames: Vec<Frame>,
+}
+
+enum Flow {
+    Call,
+    Return,
+    Other,
+}
+
+/// x1 (ra) and x5 (t0) hold the return addresses by the calling convention
+fn is_link(register: u32) -> bool {
+    register == 1 || register == 5
+}
+
+fn flow(code: u32) -> Flow {
+    if code & 0b11 == 0b11 {
+        let rd = (code >> 7) & 0x1f;
+        let rs1 = (code >> 15) & 0x1f;
+
+        return match code & 0x7f {
+            OPCODE_JAL if is_link(rd) => Flow::Call,
+            OPCODE_JALR if is_link(rd) => Flow::Call,
+            OPCODE_JALR if rd == 0 && is_link(rs1) => Flow::Return,
+            _ => Flow::Other,
+        };
+    }
+
+    let code = code & 0xffff;
+    let rs1 = (code >> 7) & 0x1f;
+    let rs2 = (code >> 2) & 0x1f;
+
+    match (code & 0xe003, code & 0xf003) {
+        // C.JAL
+        (0x2001, _) => Flow::Call,
+        // C.JALR
+        (_, 0x9002) if rs1 != 0 && rs2 == 0 => Flow::Call,
+        // C.JR
+        (_, 0x8002) if rs2 == 0 && is_link(rs1) => Flow::Return,
+        _ => Flow::Other,
+    }
+}
+
+/// Name of an MCAUSE value
+pub fn cause_name(cause: u32) -> String {
+    if cause & (1 << 31) != 0 {
+        return format!("interrupt {}", cause & !(1 << 31));
+    }
+
+    let name = match cause {
+        0x0 => "instruction misaligned",
+        0x1 => "instruction fetch fault",
+        0x2 => "illegal instruction",
+        0x3 => "breakpoint",
+        0x4 => "load misaligned",
+        0x5 => "load fault",
+        0x6 => "store misaligned",
+        0x7 => "store fault",
+        0x8 => "ecall from U-mode",
+        0x9 => "ecall from M-mode",
+        _ => return format!("exception {cause}"),
+    };
+
+    name.to_string()
+}
+
+/// Calls and traps seen by the core, the program keeps its own stack in memory.
+///
+/// A call is a jump linking its return address in ra or t0, it returns when the core
+/// jumps back to that address through a link register. A tail call keeps the frame of
+/// its caller, and a jump to anywhere else, like a longjmp, leaves the stack as it is.
+#[derive(Debug, Clone, Default)]
+pub struct CallStack {
+    /// The outermost first
+    frames: Vec<Frame>,
+    /// Frames dropped by going deeper than [`MAX_CALL_DEPTH`]
+    truncated: bool,
+    fault: Option<Fault>,
+}
+
+impl CallStack {
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The path to the last exception, kept after its handler returns
+    pub fn fault(&self) -> Option<&Fault> {
+        self.fault.as_ref()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.truncated = false;
+        self.fault = None;
+    }
+
+    fn push(&mut self, frame: Frame) {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            self.frames.remove(0);
+            self.truncated = true;
+        }
+
+        self.frames.push(frame);
+    }
+
+    /// An instruction at `pc` executed and the core continues at `next_pc`
+    pub(super) fn execute(&mut self, code: u32, pc: u32, next_pc: u32) {
+        let length = if code & 0b11 == 0b11 { 4 } else { 2 };
+
+        match flow(code) {
+            Flow::Call => self.push(Frame {
+                kind: FrameKind::Call,
+                site: pc,
+                target: next_pc,
+                return_address: pc.wrapping_add(length),
+            }),
+            Flow::Return => self.ret(next_pc),
+            Flow::Other => {}
+        }
+    }
+
+    /// Back to `address`, the innermost call returning there and the calls it made
+    /// are left. A return never leaves a trap handler
+    pub(super) fn ret(&mut self, address: u32) {
+        for index in (0..self.frames.len()).rev() {
+            match self.frames[index] {
+                Frame {
+                    kind: FrameKind::Trap(_),
+                    ..
+                } => return,
+                frame if frame.return_address == address => {
+                    self.frames.truncate(index);
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// A trap was taken on the instruction at `pc`, an exception keeps the path to it
+    pub(super) fn trap(&mut self, cause: u32, pc: u32, handler: u32) {
+        if cause & (1 << 31) == 0 {
+            self.fault = Some(Fault {
+                cause,
+                pc,
+                frames: self.frames.clone(),
+            });
+        }
+
+        self.push(Frame {
+            kind: FrameKind::Trap(cause),
+            site: pc,
+            target: handler,
+            return_address: pc,
+        });
+    }
+
+    /// MRET leaves the innermost trap handler and whatever it called
+    pub(super) fn trap_return(&mut self) {
+        let trap = self
+            .frames
+            .iter()
+            .rposition(|frame| matches!(frame.kind, FrameKind::Trap(_)));
+
+        if let Some(index) = trap {
+            self.frames.truncate(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAL_RA: u32 = 0x008000ef; // jal ra, 8
+    const JAL_ZERO: u32 = 0x0080006f; // j 8
+    const RET: u32 = 0x00008067; // jalr x0, 0(ra)
+    const C_JAL: u32 = 0x2011; // c.jal 4
+    const C_JALR: u32 = 0x9502; // c.jalr a0
+    const C_JR_RA: u32 = 0x8082; // c.jr ra
+
+    #[test]
+    fn test_calls_and_returns() {
+        let mut stack = CallStack::default();
+
+        stack.execute(JAL_RA, 0x100, 0x108);
+        stack.execute(C_JAL, 0x108, 0x10c);
+        stack.execute(JAL_ZERO, 0x10c, 0x114);
+        stack.execute(C_JALR, 0x114, 0x200);
+        assert_eq!(stack.frames().len(), 3);
+        assert_eq!(stack.frames()[2].return_address, 0x116);
+
+        // returning past the innermost call leaves the calls it skipped
+        stack.execute(C_JR_RA, 0x200, 0x10a);
+        assert_eq!(stack.frames().len(), 1);
+        assert_eq!(stack.frames()[0].target, 0x108);
+
+        // nothing returns to there
+        stack.execute(RET, 0x120, 0x300);
+        assert_eq!(stack.frames().len(), 1);
+
+        stack.execute(RET, 0x120, 0x104);
+        assert!(stack.frames().is_empty());
+    }
+
+    #[test]
+    fn test_traps() {
+        let mut stack = CallStack::default();
+
+        stack.execute(JAL_RA, 0x100, 0x108);
+        stack.trap(0x8000_000b, 0x108, 0x40);
+        stack.execute(JAL_RA, 0x40, 0x48);
+
+        // the interrupted function does not return from inside the handler
+        stack.execute(RET, 0x48, 0x104);
+        assert_eq!(stack.frames().len(), 3);
+        assert!(stack.fault().is_none());
+
+        stack.trap(0x2, 0x4c, 0x40);
+        let fault = stack.fault().unwrap();
+        assert_eq!(fault.pc, 0x4c);
+        assert_eq!(fault.frames.len(), 3);
+        assert_eq!(cause_name(fault.cause), "illegal instruction");
+
+        stack.trap_return();
+        stack.trap_return();
+        assert_eq!(stack.frames().len(), 1);
+        assert!(stack.fault().is_some());
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut stack = CallStack::default();
+
+        for _ in 0..MAX_CALL_DEPTH + 2 {
+            stack.execute(JAL_RA, 0x100, 0x100);
+        }
+
+        assert_eq!(stack.frames().len(), MAX_CALL_DEPTH);
+        assert!(stack.is_truncated());
+    }
+}
@@ -1,7 +1,8 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::interrupts::Interrupts;
+use crate::bus::Pmp;
+use crate::interrupts::{CoreRouting, Interrupt, Interrupts, NUM_IRQS};
 /**
  * @file /processor/hazard/csrs.rs
  * @author Nguyen Le Duy
@@ -13,6 +14,7 @@ use crate::utils::extract_bits;
 /// All CSRs are 32-bit, and MXLEN is fixed at 32 bits. CSR addresses not listed in this section are unimplemented.
 /// Accessing an unimplemented CSR raises an illegal instruction exception (mcause = 2). This includes all S-mode CSRs.
 use super::trap::{Exception, Trap};
+use super::trigger::*;
 
 pub const MSTATUS_UIE: u32 = 0x00000001;
 pub const MSTATUS_SIE: u32 = 0x00000002;
@@ -38,13 +40,43 @@ pub const MSTATUS32_SD: u32 = 0x80000000;
 // const MSTATUS_SXL: u32 = 0x0000000C00000000;
 // const MSTATUS64_SD: u32 = 0x8000000000000000;
 
-pub const MIP_MEIP: u16 = 1 << 11; // TODO correctly handle this
+pub const MIP_MEIP: u16 = 1 << 11;
 pub const MIE_MEIE: u32 = 1 << 11;
 pub const MIE_MTIE: u32 = 1 << 7;
 pub const MIE_MSIE: u32 = 1 << 3;
 pub const MIP_MTIP: u16 = 1 << 7;
 pub const MIP_MSIP: u16 = 1 << 3;
 
+/// Cause of the standard machine timer interrupt in MCAUSE
+pub const MACHINE_TIMER_IRQ: Interrupt = 7;
+pub const MACHINE_SOFTWARE_IRQ: Interrupt = 3;
+/// Cause of every external interrupt, the handler finds out which IRQ through MEINEXT
+pub const MACHINE_EXTERNAL_IRQ: Interrupt = 11;
+
+pub const MEINEXT_UPDATE: u32 = 1 << 0;
+pub const MEINEXT_NOIRQ: u32 = 1 << 31;
+
+pub const MEICONTEXT_MRETEIRQ: u32 = 1 << 0;
+pub const MEICONTEXT_CLEARTS: u32 = 1 << 1;
+pub const MEICONTEXT_MSIESAVE: u32 = 1 << 2;
+pub const MEICONTEXT_MTIESAVE: u32 = 1 << 3;
+pub const MEICONTEXT_IRQ: u32 = 0x1FF << 4;
+pub const MEICONTEXT_NOIRQ: u32 = 1 << 15;
+pub const MEICONTEXT_PREEMPT: u32 = 0x1F << 16;
+pub const MEICONTEXT_PPREEMPT: u32 = 0xF << 24;
+pub const MEICONTEXT_PPPREEMPT: u32 = 0xF << 28;
+
+pub const MSLEEP_DEEPSLEEP: u32 = 1 << 0;
+pub const MSLEEP_POWERDOWN: u32 = 1 << 1;
+pub const MSLEEP_SLEEPONBLOCK: u32 = 1 << 2;
+
+// DCSR fields
+pub const DCSR_PRV: u32 = 0b11;
+pub const DCSR_STEP: u32 = 1 << 2;
+pub const DCSR_CAUSE: u32 = 0b111 << 6;
+pub const DCSR_EBREAKU: u32 = 1 << 12;
+pub const DCSR_EBREAKM: u32 = 1 << 15;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrivilegeMode {
     Machine = 3,
@@ -62,6 +94,7 @@ impl From<u32> for PrivilegeMode {
     }
 }
 
+#[derive(Clone)]
 pub struct Csrs {
     pub mcycles: u64,
     medeleg: u32,
@@ -75,19 +108,25 @@ pub struct Csrs {
     mscratch: u32,
     mepc: u32,
     mcause: u32,
+    /// Faulting address or instruction of the last exception
+    mtval: u32,
     pub mip: u16,
-    pmpcfg: [u32; 4],
-    pmpaddr: [u32; 8],
+    pub pmp: Pmp,
+    /// The PMP was written since the bus got a copy of it
+    pub(crate) pmp_changed: bool,
     tselect: u8,
-    tdata: [u32; 2],
+    pub triggers: [Trigger; NUM_TRIGGERS],
     dcsr: u32,
     dpc: u32,
-    pmpcfgm0: u32,
-    meiea: u32,
-    meipa: u32,
-    meifa: u32,
-    meipra: u32,
-    meinext: u32,
+    // Xh3irq arrays, accessed through 16 bit windows
+    meiea: u64,
+    meipa: u64, // lines seen by the last interrupt check
+    meifa: u64,
+    meipra: [u8; NUM_IRQS],
+    /// Window of the arrays selected by the write data of the current CSR instruction
+    window: u8,
+    /// MEICONTEXT.CLEARTS is set by the write data of the current CSR instruction
+    clearts: bool,
     meicontext: u32,
     msleep: u32,
     dmdata0: u32,
@@ -95,6 +134,13 @@ pub struct Csrs {
     pending_write: Option<(u16, u32)>, // write happend only at the end of a step in Hazard3
     pub(super) core_id: u8,
     pub(super) privilege_mode: PrivilegeMode,
+    debug_mode: bool,
+    /// The triggers stay quiet for the first instruction after Debug Mode,
+    /// the one the core halted on
+    triggers_suppressed: bool,
+    /// Raise the exceptions the spec mandates, instead of reading 0 like older
+    /// versions of the simulator
+    pub strict: bool,
 }
 
 impl Default for Csrs {
@@ -113,20 +159,24 @@ impl Default for Csrs {
             mepc: 0,
             mscratch: 0,
             mcause: 0,
+            mtval: 0,
             mip: 0,
-            pmpcfg: [0; 4],
-            pmpaddr: [0; 8],
+            pmp: Pmp::default(),
+            pmp_changed: true,
             tselect: 0,
-            tdata: [0; 2],
+            triggers: [Trigger::default(); NUM_TRIGGERS],
             dcsr: 0,
             dpc: 0,
-            pmpcfgm0: 0,
+            debug_mode: false,
+            triggers_suppressed: false,
+            strict: true,
             meiea: 0,
             meipa: 0,
             meifa: 0,
-            meipra: 0,
-            meinext: 0,
-            meicontext: 0,
+            meipra: [0; NUM_IRQS],
+            window: 0,
+            clearts: false,
+            meicontext: MEICONTEXT_NOIRQ,
             dmdata0: 0,
             core_id: 0,
             msleep: 0,
@@ -169,7 +219,7 @@ impl Csrs {
     const _PMPADDR6: u16 = 0x3B6;
     const PMPADDR7: u16 = 0x3B7;
     const PMPADDR8: u16 = 0x3B8;
-    const PMPADDR9: u16 = 0x3B9;
+    const _PMPADDR9: u16 = 0x3B9;
     const PMPADDR10: u16 = 0x3BA;
     const PMPADDR11: u16 = 0x3BB;
     const PMPADDR15: u16 = 0x3BF;
@@ -210,8 +260,208 @@ impl Csrs {
     const ARCHID: u32 = 0x0000001b;
     const IMPID: u32 = 0x86fc4e3f;
 
-    pub(super) fn is_in_debug_mode(&self) -> bool {
-        (self.dcsr & 0b1) != 0
+    /// The Xh3irq arrays take the index of their window from the write data of the CSR
+    /// instruction, also for the read of the old value. So does MEICONTEXT, which shows
+    /// the saved timer and software enables only when the same write sets CLEARTS
+    pub(super) fn is_windowed(csr: u16) -> bool {
+        matches!(
+            csr,
+            Self::MEIEA | Self::MEIPA | Self::MEIFA | Self::MEIPRA | Self::MEICONTEXT
+        )
+    }
+
+    pub(super) fn select_window(&mut self, csr: u16, write_data: u32) {
+        match csr {
+            Self::MEIPRA => self.window = (write_data & 0x7F) as u8,
+            Self::MEICONTEXT => self.clearts = write_data & MEICONTEXT_CLEARTS != 0,
+            _ => self.window = (write_data & 0x1F) as u8,
+        }
+    }
+
+    fn bit_window(&self, array: u64) -> u32 {
+        // the windows past the implemented IRQs read as zero
+        let window = array.checked_shr(self.window as u32 * 16).unwrap_or(0);
+        (window as u32 & 0xFFFF) << 16
+    }
+
+    fn write_bit_window(&self, array: u64, value: u32) -> u64 {
+        let shift = self.window as u32 * 16;
+        if shift >= u64::BITS {
+            return array;
+        }
+
+        let valid = (1u64 << NUM_IRQS) - 1;
+        let window = 0xFFFFu64 << shift;
+        let bits = ((value >> 16) as u64) << shift;
+        (array & !window) | (bits & window & valid)
+    }
+
+    fn priority_window(&self) -> u32 {
+        let first = self.window as usize * 4;
+        (0..4)
+            .filter_map(|i| self.meipra.get(first + i).map(|p| (*p as u32) << (16 + i * 4)))
+            .fold(0, |acc, v| acc | v)
+    }
+
+    fn preempt(&self) -> u8 {
+        extract_bits(self.meicontext, 16..=20) as u8
+    }
+
+    fn ppreempt(&self) -> u8 {
+        extract_bits(self.meicontext, 24..=27) as u8
+    }
+
+    /// The highest priority IRQ both pending and enabled, ignoring the priorities below `level`.
+    /// The lowest numbered IRQ wins between equal priorities
+    fn next_irq(&self, level: u8) -> Option<Interrupt> {
+        let active = (self.meipa | self.meifa) & self.meiea;
+
+        (0..NUM_IRQS)
+            .filter(|&irq| active >> irq & 1 == 1 && self.meipra[irq] >= level)
+            .max_by_key(|&irq| (self.meipra[irq], std::cmp::Reverse(irq)))
+            .map(|irq| irq as Interrupt)
+    }
+
+    fn meinext(&self) -> u32 {
+        // the IRQs the preempted handler is serving are not visible
+        match self.next_irq(self.ppreempt()) {
+            Some(irq) => (irq as u32) << 2,
+            None => MEINEXT_NOIRQ,
+        }
+    }
+
+    fn meicontext(&self) -> u32 {
+        let mut value = self.meicontext;
+
+        if self.clearts {
+            if self.mie & MIE_MTIE != 0 {
+                value |= MEICONTEXT_MTIESAVE;
+            }
+
+            if self.mie & MIE_MSIE != 0 {
+                value |= MEICONTEXT_MSIESAVE;
+            }
+        }
+
+        value
+    }
+
+    /// MEINEXT.UPDATE, the IRQ shown in MEINEXT becomes the current one
+    fn update_context(&mut self) {
+        let (irq, noirq, preempt) = match self.next_irq(self.ppreempt()) {
+            Some(irq) => (irq as u32, 0, self.meipra[irq as usize] as u32 + 1),
+            None => (0, MEICONTEXT_NOIRQ, 0),
+        };
+
+        self.meicontext &= !(MEICONTEXT_IRQ | MEICONTEXT_NOIRQ | MEICONTEXT_PREEMPT);
+        self.meicontext |= irq << 4 | noirq | preempt << 16;
+    }
+
+    /// Priority save on entering the external interrupt vector, only the IRQs above the
+    /// taken one can preempt its handler
+    fn save_priority(&mut self, irq: Interrupt) {
+        let priority = self.meipra[irq as usize] as u32;
+        let ppreempt = self.ppreempt() as u32;
+        let preempt = (self.preempt() as u32).min(0xF);
+
+        self.meicontext &= !(MEICONTEXT_PREEMPT | MEICONTEXT_PPREEMPT | MEICONTEXT_PPPREEMPT);
+        self.meicontext |= ppreempt << 28 | preempt << 24 | (priority + 1) << 16;
+        self.meicontext |= MEICONTEXT_MRETEIRQ;
+    }
+
+    /// Priority restore on MRET from the external interrupt vector
+    fn restore_priority(&mut self) {
+        if self.meicontext & MEICONTEXT_MRETEIRQ == 0 {
+            return;
+        }
+
+        let ppreempt = self.ppreempt() as u32;
+        let pppreempt = extract_bits(self.meicontext, 28..=31);
+
+        self.meicontext &= !(MEICONTEXT_PREEMPT | MEICONTEXT_PPREEMPT | MEICONTEXT_PPPREEMPT);
+        self.meicontext |= ppreempt << 16 | pppreempt << 24;
+        self.meicontext &= !MEICONTEXT_MRETEIRQ;
+    }
+
+    /// What the Xh3irq arrays let through, for the routing of the interrupts
+    pub fn routing(&self) -> CoreRouting {
+        CoreRouting {
+            enabled: self.meiea,
+            forced: self.meifa,
+            priority: self.meipra,
+        }
+    }
+
+    pub fn is_in_debug_mode(&self) -> bool {
+        self.debug_mode
+    }
+
+    /// Halt in Debug Mode, the core stays there until [`Csrs::exit_debug_mode`]
+    pub(super) fn enter_debug_mode(&mut self, cause: DebugCause, pc: u32) {
+        self.dpc = pc;
+        self.dcsr &= !(DCSR_CAUSE | DCSR_PRV);
+        self.dcsr |= (cause as u32) << 6 | self.privilege_mode as u32;
+        self.privilege_mode = PrivilegeMode::Machine;
+        self.debug_mode = true;
+    }
+
+    /// Same as DRET, returns the address to continue at
+    pub(super) fn exit_debug_mode(&mut self) -> u32 {
+        self.privilege_mode = PrivilegeMode::from(self.dcsr & DCSR_PRV);
+        self.debug_mode = false;
+        self.triggers_suppressed = true;
+        self.dpc
+    }
+
+    pub(super) fn is_single_stepping(&self) -> bool {
+        self.dcsr & DCSR_STEP != 0
+    }
+
+    /// Called once an instruction is done with, the triggers can fire again
+    pub(super) fn rearm_triggers(&mut self) {
+        self.triggers_suppressed = false;
+    }
+
+    /// Whether an EBREAK enters Debug Mode instead of raising a breakpoint exception
+    pub(super) fn ebreak_enters_debug_mode(&self) -> bool {
+        match self.privilege_mode {
+            PrivilegeMode::Machine => self.dcsr & DCSR_EBREAKM != 0,
+            PrivilegeMode::User => self.dcsr & DCSR_EBREAKU != 0,
+        }
+    }
+
+    /// First trigger matching the address of an access
+    pub(super) fn address_trigger(
+        &mut self,
+        access: TriggerAccess,
+        address: u32,
+    ) -> Option<TriggerAction> {
+        self.fire_trigger(access, address, false)
+    }
+
+    /// First trigger matching the data of a load or a store
+    pub(super) fn data_trigger(&mut self, access: TriggerAccess, data: u32) -> Option<TriggerAction> {
+        self.fire_trigger(access, data, true)
+    }
+
+    fn fire_trigger(
+        &mut self,
+        access: TriggerAccess,
+        value: u32,
+        data: bool,
+    ) -> Option<TriggerAction> {
+        // the triggers never fire in Debug Mode
+        if self.debug_mode || self.triggers_suppressed {
+            return None;
+        }
+
+        let privilege = self.privilege_mode;
+        let trigger = self.triggers.iter_mut().find(|trigger| {
+            trigger.matches_data() == data && trigger.matches(access, value, privilege)
+        })?;
+
+        trigger.tdata1 |= TDATA1_HIT;
+        Some(trigger.action())
     }
 
     fn is_u_mode_cycle_enabled(&self) -> bool {
@@ -222,18 +472,47 @@ impl Csrs {
         (self.mcounteren & 0b100) != 0
     }
 
+    /// Reads a counter through its unprivileged alias. U-mode needs the counter
+    /// enabled in MCOUNTEREN, otherwise it is an illegal instruction, or reads 0
+    /// when the CSRs are not strict
+    fn user_counter(&self, enabled: bool, value: u64) -> Result<u32, Exception> {
+        if self.privilege_mode == PrivilegeMode::Machine || enabled {
+            Ok(value as u32)
+        } else if self.strict {
+            Err(Exception::IllegalInstruction)
+        } else {
+            Ok(0)
+        }
+    }
+
     pub fn privilege_mode(&self) -> PrivilegeMode {
         self.privilege_mode
     }
 
+    /// Cause of the last trap
+    pub fn mcause(&self) -> u32 {
+        self.mcause
+    }
+
+    /// Faulting address or instruction of the last exception, 0 for the other traps
+    pub fn mtval(&self) -> u32 {
+        self.mtval
+    }
+
     // Trap handle as described in the RP2350 in section 3.8.4
-    pub(super) fn trap_handle(&mut self, trap: impl Into<Trap>, pc: u32) -> u32 {
+    pub(super) fn trap_handle(&mut self, trap: impl Into<Trap>, pc: u32, value: u32) -> u32 {
+        // Only the external interrupt vector restores the priorities on MRET, it sets this
+        // again right after
+        self.meicontext &= !MEICONTEXT_MRETEIRQ;
+
         // 1. Save the address of the interrupted or excepting instruction to MEPC
         self.mepc = pc;
         // 2. Set the MSB of MCAUSE to indicate the cause is an interrupt, or clear it to indicate an exception
         let xcause = trap.into().to_xcause();
         // 3. Write the detailed trap cause to the LSBs of the MCAUSE register
         self.mcause = xcause;
+        // The faulting address or instruction goes to MTVAL, the interrupts clear it
+        self.mtval = value;
         // 4. Save the current privilege level to MSTATUS.MPP
         self.mstatus = (self.mstatus & !MSTATUS_MPP) | (self.privilege_mode() as u32) << 11;
         // 5. Set the privilege to M-mode (note Hazard3 does not implement S-mode)
@@ -258,6 +537,8 @@ impl Csrs {
     }
 
     pub(super) fn trap_mret(&mut self) -> u32 {
+        self.restore_priority();
+
         // 1. Restore core privilege level to the value of MSTATUS.MP
         self.privilege_mode = PrivilegeMode::from((self.mstatus >> 11) & 0b11);
 
@@ -311,21 +592,17 @@ impl Csrs {
             Self::MSCRATCH => self.mscratch,
             Self::MEPC => self.mepc,
             Self::MCAUSE => self.mcause,
+            Self::MTVAL => self.mtval,
             Self::MIP => self.mip as u32,
-            Self::PMPCFG0 => self.pmpcfg[0],
-            Self::PMPCFG1 => self.pmpcfg[1],
-            Self::PMPCFG2 => self.pmpcfg[2],
-            Self::PMPCFG3 => self.pmpcfg[3],
-            Self::PMPADDR0..=Self::PMPADDR7 => {
+            Self::PMPCFG0..=Self::PMPCFG3 => self.pmp.cfg[(offset - Self::PMPCFG0) as usize],
+            // 8 to 10 are hardwired
+            Self::PMPADDR0..=Self::PMPADDR10 => {
                 let idx = (offset - Self::PMPADDR0) as usize;
-                self.pmpaddr[idx]
+                self.pmp.addr[idx]
             }
-            Self::PMPADDR8 => 0x01ffffff,
-            Self::PMPADDR9 => 0x13ffffff,
-            Self::PMPADDR10 => 0x35ffffff,
             Self::TSELECT => self.tselect as u32,
-            Self::TDATA1 => self.tdata[0],
-            Self::TDATA2 => self.tdata[1],
+            Self::TDATA1 => self.triggers[self.tselect as usize].tdata1,
+            Self::TDATA2 => self.triggers[self.tselect as usize].tdata2,
             Self::DCSR => self.dcsr,
             Self::DPC => {
                 if !self.is_in_debug_mode() {
@@ -337,13 +614,13 @@ impl Csrs {
             Self::MINSTRET => self.minstret as u32,
             Self::MCYCLEH => (self.mcycles >> 32) as u32,
             Self::MINSTRETH => (self.minstret >> 32) as u32,
-            Self::PMPCFGM0 => self.pmpcfgm0,
-            Self::MEIEA => self.meiea & !0b11111,
-            Self::MEIPA => self.meipa & !0b11111,
-            Self::MEIFA => self.meifa & !0b11111,
-            Self::MEIPRA => self.meipra & !0b11111,
-            Self::MEINEXT => self.meinext,
-            Self::MEICONTEXT => self.meicontext,
+            Self::PMPCFGM0 => self.pmp.m0,
+            Self::MEIEA => self.bit_window(self.meiea),
+            Self::MEIPA => self.bit_window(self.meipa | self.meifa),
+            Self::MEIFA => self.bit_window(self.meifa),
+            Self::MEIPRA => self.priority_window(),
+            Self::MEINEXT => self.meinext(),
+            Self::MEICONTEXT => self.meicontext(),
             Self::MSLEEP => self.msleep,
             Self::DMDATA0 => {
                 if !self.is_in_debug_mode() {
@@ -353,38 +630,13 @@ impl Csrs {
                 self.dmdata0
             }
 
-            Self::CYCLE => {
-                if self.privilege_mode == PrivilegeMode::Machine || self.is_u_mode_cycle_enabled() {
-                    self.mcycles as u32
-                } else {
-                    0 // don't know if this is correct or should raise an exception
-                }
-            }
-
-            Self::INSTRET => {
-                if self.privilege_mode == PrivilegeMode::Machine || self.is_u_mode_instret_enabled()
-                {
-                    self.minstret as u32
-                } else {
-                    0 // don't know if this is correct or should raise an exception
-                }
-            }
-
+            Self::CYCLE => self.user_counter(self.is_u_mode_cycle_enabled(), self.mcycles)?,
+            Self::INSTRET => self.user_counter(self.is_u_mode_instret_enabled(), self.minstret)?,
             Self::CYCLEH => {
-                if self.privilege_mode == PrivilegeMode::Machine || self.is_u_mode_cycle_enabled() {
-                    (self.mcycles >> 32) as u32
-                } else {
-                    0 // don't know if this is correct or should raise an exception
-                }
+                self.user_counter(self.is_u_mode_cycle_enabled(), self.mcycles >> 32)?
             }
-
             Self::INSTRETH => {
-                if self.privilege_mode == PrivilegeMode::Machine || self.is_u_mode_instret_enabled()
-                {
-                    (self.minstret >> 32) as u32
-                } else {
-                    0 // don't know if this is correct or should raise an exception
-                }
+                self.user_counter(self.is_u_mode_instret_enabled(), self.minstret >> 32)?
             }
 
             Self::MENVCFG
@@ -393,7 +645,6 @@ impl Csrs {
             | Self::MHPMEVENT3..=Self::MHPMEVENT31
             | Self::MHPMCOUNTER3..=Self::MHPMCOUNTER31
             | Self::MHPMCOUNTER3H..=Self::MHPMCOUNTER31H
-            | Self::MTVAL
             | Self::PMPADDR11..=Self::PMPADDR15
             | Self::MCONFIGPTR => 0, // hardwired to 0
 
@@ -494,28 +745,48 @@ impl Csrs {
             Self::MSCRATCH => self.mscratch = value,
             Self::MEPC => self.mepc = value,
             Self::MCAUSE => self.mcause = value,
+            Self::MTVAL => self.mtval = value,
             // 11th bit of MIP is read-only
             Self::MIP => self.mip = (value as u16 & 0xFF00) | (self.mip & 0b0000_1000_0000_0000),
-            Self::PMPCFG0 => self.pmpcfg[0] = value,
-            Self::PMPCFG1 => self.pmpcfg[1] = value,
+            Self::PMPCFG0 => {
+                self.pmp.cfg[0] = value;
+                self.pmp_changed = true;
+            }
+            Self::PMPCFG1 => {
+                self.pmp.cfg[1] = value;
+                self.pmp_changed = true;
+            }
             Self::PMPADDR0..=Self::PMPADDR7 => {
                 let idx = (csr - Self::PMPADDR0) as usize;
-                self.pmpaddr[idx] = value & 0x3fffffff;
+                self.pmp.addr[idx] = value & 0x3fffffff;
+                self.pmp_changed = true;
             }
-            Self::TSELECT => self.tselect = value as u8,
-            Self::TDATA1 => {
-                // 27th bit is only writable from Debug Mode
-                let mask = if self.is_in_debug_mode() {
-                    0b0000_1000_0000_0000_1111_0000_0111_1100
+            // an unsupported index keeps the current trigger selected
+            Self::TSELECT if (value as usize) < NUM_TRIGGERS => self.tselect = value as u8,
+            Self::TSELECT => {}
+            Self::TDATA1 | Self::TDATA2 => {
+                let debug_mode = self.is_in_debug_mode();
+                let trigger = &mut self.triggers[self.tselect as usize];
+
+                // the triggers of the debugger are only writable from Debug Mode
+                if trigger.tdata1 & TDATA1_DMODE != 0 && !debug_mode {
+                    return;
+                }
+
+                if csr == Self::TDATA2 {
+                    trigger.tdata2 = value;
+                    return;
+                }
+
+                let mask = if debug_mode {
+                    TDATA1_WRITABLE | TDATA1_DMODE
                 } else {
-                    0b0000_0000_0000_0000_1111_0000_0111_1100
+                    TDATA1_WRITABLE
                 };
-                let value = value & mask;
 
                 // 31:28 is hardwired to 2
-                self.tdata[0] = value | 2 << 28;
+                trigger.tdata1 = value & mask | TDATA1_TYPE;
             }
-            Self::TDATA2 => self.tdata[1] = value,
             Self::DCSR => {
                 let value = value & 0b0000_1111_1111_1111_1111_0000_0000_0111;
                 // 31:28 is hardwired to 4
@@ -538,48 +809,48 @@ impl Csrs {
                 self.minstret &= 0x0000_0000_FFFF_FFFF; // clear upper 32 bits
                 self.minstret |= (value as u64) << 32; // set upper 32 bits
             }
-            Self::PMPCFGM0 => self.pmpcfgm0 = value,
+            Self::PMPCFGM0 => {
+                self.pmp.m0 = value;
+                self.pmp_changed = true;
+            }
 
             // ------ Interrupt handler CSRs ----------
-            Self::MEIEA => {
-                // TODO
-                self.meiea = value;
-            }
-            Self::MEIPA => {
-                // TODO
-                self.meipa = value;
-            }
-            Self::MEIFA => {
-                // TODO
-                self.meifa = value;
-            }
+            Self::MEIEA => self.meiea = self.write_bit_window(self.meiea, value),
+            Self::MEIPA => { /* Read-only, the pending state follows the lines */ }
+            Self::MEIFA => self.meifa = self.write_bit_window(self.meifa, value),
             Self::MEIPRA => {
-                // TODO
-                self.meipra = value;
+                let first = self.window as usize * 4;
+                for i in 0..4 {
+                    if let Some(priority) = self.meipra.get_mut(first + i) {
+                        *priority = (value >> (16 + i * 4)) as u8 & 0xF;
+                    }
+                }
             }
             Self::MEINEXT => {
-                if value & 1 != 0 {
-                    // TODO
-                    // update meicontext according to the IRQ number and preemption priority of the
-                    // interrupt indicated in noirq/irp
+                // the rest is read-only, UPDATE is self-clearing
+                if value & MEINEXT_UPDATE != 0 {
+                    self.update_context();
                 }
-                self.meinext = value & !0b1; // last bit is self clearing bit
             }
 
             Self::MEICONTEXT => {
-                if value & 0b10 != 0 {
-                    // TODO
-                    // Write-1 self-clearing field. Writing 1 will clear mie.mtie and mie.msie,
-                    // and present their prior values in the mtiesave and msiesave of this register. 
-                    // This makes it safe to re-enable IRQs (via mstatus.mie) without the possibility 
-                    // of being preempted by the standard timer and soft interrupt handlers, 
-                    // which may not be aware of Hazard3’s interrupt hardware.
+                // Write-1 self-clearing field. Writing 1 will clear mie.mtie and mie.msie,
+                // and present their prior values in the mtiesave and msiesave of this register.
+                // This makes it safe to re-enable IRQs (via mstatus.mie) without the possibility
+                // of being preempted by the standard timer and soft interrupt handlers,
+                // which may not be aware of Hazard3’s interrupt hardware.
+                if value & MEICONTEXT_CLEARTS != 0 {
+                    self.mie &= !(MIE_MTIE | MIE_MSIE);
                 }
 
-                let updated_value = value & 0b10;// second bit is self clearing bit
+                let writable = MEICONTEXT_MRETEIRQ
+                    | MEICONTEXT_IRQ
+                    | MEICONTEXT_NOIRQ
+                    | MEICONTEXT_PREEMPT
+                    | MEICONTEXT_PPREEMPT
+                    | MEICONTEXT_PPPREEMPT;
 
-                self.meicontext &= 0b1100; // 2nd and 3rd bits are read only
-                self.meicontext |= updated_value;
+                self.meicontext = value & writable;
             }
 
             // -- End of Interrupt handler CSRs -- 
@@ -592,7 +863,6 @@ impl Csrs {
             | Self::MENVCFGH
             | Self::MSTATUSH
             | Self::MHPMEVENT3..=Self::MHPMEVENT31
-            | Self::MTVAL
             | Self::PMPCFG2 | Self::PMPCFG3 // read only according to the spec
             | Self::PMPADDR8..=Self::PMPADDR15
             | Self::MHPMCOUNTER3..=Self::MHPMCOUNTER31
@@ -611,6 +881,25 @@ impl Csrs {
         }
     }
 
+    /// Account for cycles and instructions which were fast-forwarded
+    pub(crate) fn skip(&mut self, cycles: u64, instructions: u64) {
+        if self.mcountinhibit & 1 == 0 {
+            self.mcycles = self.mcycles.wrapping_add(cycles);
+        }
+
+        if self.mcountinhibit & 0b100 == 0 {
+            self.minstret = self.minstret.wrapping_add(instructions);
+        }
+    }
+
+    /// Cycles spent asleep without being ticked. MSLEEP.DEEPSLEEP gates the clock of
+    /// the core while it sleeps, MCYCLE stops along with it
+    pub(super) fn count_sleep(&mut self, cycles: u64) {
+        if self.mcountinhibit & 1 == 0 && self.msleep & MSLEEP_DEEPSLEEP == 0 {
+            self.mcycles = self.mcycles.wrapping_add(cycles);
+        }
+    }
+
     pub(super) fn count_instret(&mut self) {
         if self.mcountinhibit & 0b100 == 0 {
             self.minstret = self.minstret.wrapping_add(1);
@@ -631,41 +920,72 @@ impl Csrs {
         (self.mie & MIE_MTIE) != 0
     }
 
-    fn _software_irq_enabled(&self) -> bool {
+    fn software_irq_enabled(&self) -> bool {
         // mie.msie, mip.msie
         (self.mie & MIE_MSIE) != 0
     }
 
-    // Check for interrupt and return the handling address if needed
-    pub(super) fn interrupt_check(&mut self, pc: u32, irq: Rc<RefCell<Interrupts>>) -> Option<u32> {
-        if !self.irq_enabled() || self.privilege_mode != PrivilegeMode::Machine {
-            return None;
-        }
+    /// Any interrupt both pending in MIP and enabled in MIE, regardless of MSTATUS.MIE
+    pub(super) fn has_wake_up_interrupt(&self) -> bool {
+        (self.mip as u32) & self.mie != 0
+    }
 
-        // Transfering interrupts to the core
+    // Check for interrupt and return the handling address and the interrupt taken if needed
+    pub(super) fn interrupt_check(
+        &mut self,
+        pc: u32,
+        irq: Rc<RefCell<Interrupts>>,
+    ) -> Option<(u32, Interrupt)> {
+        // Publish the arrays before looking at what is delivered to this core
+        irq.borrow_mut().set_routing(self.core_id, self.routing());
         let irq = irq.borrow();
-        let mut irq = irq.iter(self.core_id);
+        self.meipa = irq.pending(self.core_id);
 
-        let Some(next_irq) = irq.next() else {
-            // No interrupt pending
-            self.mip &= !MIP_MEIP; // clear external interrupt
-            self.mip &= !MIP_MTIP; // clear timer interrupt
-            self.mip &= !MIP_MSIP; // clear software interrupt
-            return None;
-        };
+        // The timer line comes straight from the core local comparator of the SIO
+        if irq.machine_timer(self.core_id) {
+            self.mip |= MIP_MTIP;
+        } else {
+            self.mip &= !MIP_MTIP;
+        }
 
-        if next_irq == Interrupts::SIO_IRQ_MTIMECMP {
-            if self.timer_irq_enabled() {
-                self.mip |= MIP_MTIP;
-            }
+        // So is the software interrupt, from the RISCV_SOFTIRQ register of the SIO
+        if irq.machine_software(self.core_id) {
+            self.mip |= MIP_MSIP;
         } else {
-            if self.external_irq_enabled() {
-                self.mip |= MIP_MEIP;
-            }
+            self.mip &= !MIP_MSIP;
+        }
+
+        // only the IRQs above the preemption priority of the running handler are taken
+        let next_irq = self.next_irq(self.preempt());
+
+        if next_irq.is_some() {
+            self.mip |= MIP_MEIP;
+        } else {
+            self.mip &= !MIP_MEIP;
+        }
+
+        // MSTATUS.MIE only masks the interrupts in M-mode, U-mode is always preempted
+        if self.privilege_mode == PrivilegeMode::Machine && !self.irq_enabled() {
+            return None;
+        }
+
+        // Standard priority order: external, software then timer
+        if let Some(next_irq) = next_irq.filter(|_| self.external_irq_enabled()) {
+            let handler = self.trap_handle(Trap::Interrupt(MACHINE_EXTERNAL_IRQ), pc, 0);
+            self.save_priority(next_irq);
+            return Some((handler, next_irq));
+        }
+
+        if self.mip & MIP_MSIP != 0 && self.software_irq_enabled() {
+            let handler = self.trap_handle(Trap::Interrupt(MACHINE_SOFTWARE_IRQ), pc, 0);
+            return Some((handler, MACHINE_SOFTWARE_IRQ));
+        }
+
+        if self.mip & MIP_MTIP != 0 && self.timer_irq_enabled() {
+            let handler = self.trap_handle(Trap::Interrupt(MACHINE_TIMER_IRQ), pc, 0);
+            return Some((handler, Interrupts::SIO_IRQ_MTIMECMP));
         }
 
-        // Actually handling interrupt if needed
-        Some(self.trap_handle(Trap::Interrupt(next_irq), pc))
-        // TODO xh3 interrupt routine, tried it but it does not work
+        None
     }
 }
@@ -0,0 +1,573 @@
+/**
+ * @file /processor/hazard/disasm.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Disassembler for the instructions supported by the Hazard3 core
+ */
+use super::instruction_format::*;
+use super::Register;
+use crate::utils::{extract_bit, extract_bits};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    /// Size in bytes, 2 for compressed instructions
+    pub size: u8,
+    pub text: String,
+}
+
+fn reg(register: Register) -> &'static str {
+    ABI_NAMES[register as usize & 0x1f]
+}
+
+/// Disassemble the instruction at `pc`,
+/// only the lower half of `code` is used for compressed instructions
+pub fn disassemble(pc: u32, code: u32) -> DecodedInstruction {
+    if code & 0b11 != 0b11 {
+        let text = disassemble_compressed(pc, code as u16).unwrap_or_else(|| "unknown".into());
+        return DecodedInstruction { size: 2, text };
+    }
+
+    let text = disassemble_full(pc, code).unwrap_or_else(|| "unknown".into());
+    DecodedInstruction { size: 4, text }
+}
+
+fn funct3(code: u32) -> u32 {
+    extract_bits(code, 12..=14)
+}
+
+fn funct7(code: u32) -> u32 {
+    extract_bits(code, 25..=31)
+}
+
+fn disassemble_full(pc: u32, code: u32) -> Option<String> {
+    let text = match code & 0x7f {
+        0b0110111 => {
+            let u = UType::from(code);
+            format!("lui {}, {:#x}", reg(u.rd), u.imm >> 12)
+        }
+
+        0b0010111 => {
+            let u = UType::from(code);
+            format!("auipc {}, {:#x}", reg(u.rd), u.imm >> 12)
+        }
+
+        0b1101111 => {
+            let j = JType::from(code);
+            let target = pc.wrapping_add(j.imm);
+            match j.rd {
+                0 => format!("j {:#x}", target),
+                _ => format!("jal {}, {:#x}", reg(j.rd), target),
+            }
+        }
+
+        0b1100111 => {
+            let i = IType::from(code);
+            match (i.rd, i.imm) {
+                (0, 0) if i.rs1 == 1 => "ret".into(),
+                (0, 0) => format!("jr {}", reg(i.rs1)),
+                _ => format!("jalr {}, {}({})", reg(i.rd), i.imm as i32, reg(i.rs1)),
+            }
+        }
+
+        0b1100011 => {
+            let b = BType::from(code);
+            let name = match funct3(code) {
+                0b000 => "beq",
+                0b001 => "bne",
+                0b100 => "blt",
+                0b101 => "bge",
+                0b110 => "bltu",
+                0b111 => "bgeu",
+                _ => return None,
+            };
+
+            let target = pc.wrapping_add(b.imm);
+            format!("{} {}, {}, {:#x}", name, reg(b.rs1), reg(b.rs2), target)
+        }
+
+        0b0000011 => {
+            let i = IType::from(code);
+            let name = match funct3(code) {
+                0b000 => "lb",
+                0b001 => "lh",
+                0b010 => "lw",
+                0b100 => "lbu",
+                0b101 => "lhu",
+                _ => return None,
+            };
+
+            format!("{} {}, {}({})", name, reg(i.rd), i.imm as i32, reg(i.rs1))
+        }
+
+        0b0100011 => {
+            let s = SType::from(code);
+            let name = match funct3(code) {
+                0b000 => "sb",
+                0b001 => "sh",
+                0b010 => "sw",
+                _ => return None,
+            };
+
+            format!("{} {}, {}({})", name, reg(s.rs2), s.imm as i32, reg(s.rs1))
+        }
+
+        0b0010011 => disassemble_arithmetic_imm(code)?,
+        0b0110011 => disassemble_arithmetic_reg(code)?,
+
+        0b0001111 => match funct3(code) {
+            0b000 => "fence".into(),
+            0b001 => "fence.i".into(),
+            _ => return None,
+        },
+
+        0b0101111 if funct3(code) == 0b010 => {
+            let r = RType::from(code);
+            let name = match extract_bits(code, 27..=31) {
+                0b00010 => return Some(format!("lr.w {}, ({})", reg(r.rd), reg(r.rs1))),
+                0b00011 => "sc.w",
+                0b00001 => "amoswap.w",
+                0b00000 => "amoadd.w",
+                0b00100 => "amoxor.w",
+                0b01100 => "amoand.w",
+                0b01000 => "amoor.w",
+                0b10000 => "amomin.w",
+                0b10100 => "amomax.w",
+                0b11000 => "amominu.w",
+                0b11100 => "amomaxu.w",
+                _ => return None,
+            };
+
+            format!("{} {}, {}, ({})", name, reg(r.rd), reg(r.rs2), reg(r.rs1))
+        }
+
+        0b0001011 => {
+            // Hazard3 custom bit extract multiple
+            let i = IType::from(code);
+            let size = extract_bits(code, 26..=28) + 1;
+            match funct3(code) {
+                _ if code & 0xe200_0000 != 0 => return None,
+                0b000 => {
+                    let rs2 = extract_bits(code, 20..=24);
+                    format!("h3.bextm {}, {}, {}, {}", reg(i.rd), reg(i.rs1), reg(rs2 as u8), size)
+                }
+                0b100 => {
+                    let shamt = extract_bits(code, 20..=24);
+                    format!("h3.bextmi {}, {}, {}, {}", reg(i.rd), reg(i.rs1), shamt, size)
+                }
+                _ => return None,
+            }
+        }
+
+        0b1110011 => disassemble_system(code)?,
+
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn disassemble_arithmetic_imm(code: u32) -> Option<String> {
+    let i = IType::from(code);
+    let shamt = extract_bits(code, 20..=24);
+    let (rd, rs1) = (reg(i.rd), reg(i.rs1));
+
+    let name = match (funct3(code), funct7(code)) {
+        (0b000, _) if code == 0x0000_0013 => return Some("nop".into()),
+        (0b000, _) if i.rs1 == 0 => return Some(format!("li {}, {}", rd, i.imm as i32)),
+        (0b000, _) if i.imm == 0 => return Some(format!("mv {}, {}", rd, rs1)),
+        (0b000, _) => "addi",
+        (0b010, _) => "slti",
+        (0b011, _) => "sltiu",
+        (0b100, _) if i.imm == u32::MAX => return Some(format!("not {}, {}", rd, rs1)),
+        (0b100, _) => "xori",
+        (0b110, _) => "ori",
+        (0b111, _) => "andi",
+
+        (0b001, 0b0000000) => return Some(format!("slli {}, {}, {}", rd, rs1, shamt)),
+        (0b001, 0b0100100) => return Some(format!("bclri {}, {}, {}", rd, rs1, shamt)),
+        (0b001, 0b0110100) => return Some(format!("binvi {}, {}, {}", rd, rs1, shamt)),
+        (0b001, 0b0010100) => return Some(format!("bseti {}, {}, {}", rd, rs1, shamt)),
+        (0b001, 0b0110000) => {
+            let name = match shamt {
+                0b00000 => "clz",
+                0b00001 => "ctz",
+                0b00010 => "cpop",
+                0b00100 => "sext.b",
+                0b00101 => "sext.h",
+                _ => return None,
+            };
+            return Some(format!("{} {}, {}", name, rd, rs1));
+        }
+        (0b001, 0b0000100) if shamt == 0b01111 => return Some(format!("zip {}, {}", rd, rs1)),
+
+        (0b101, 0b0000000) => return Some(format!("srli {}, {}, {}", rd, rs1, shamt)),
+        (0b101, 0b0100000) => return Some(format!("srai {}, {}, {}", rd, rs1, shamt)),
+        (0b101, 0b0110000) => return Some(format!("rori {}, {}, {}", rd, rs1, shamt)),
+        (0b101, 0b0100100) => return Some(format!("bexti {}, {}, {}", rd, rs1, shamt)),
+        (0b101, 0b0010100) if shamt == 0b00111 => return Some(format!("orc.b {}, {}", rd, rs1)),
+        (0b101, 0b0110100) if shamt == 0b11000 => return Some(format!("rev8 {}, {}", rd, rs1)),
+        (0b101, 0b0110100) if shamt == 0b00111 => return Some(format!("brev8 {}, {}", rd, rs1)),
+        (0b101, 0b0000100) if shamt == 0b01111 => return Some(format!("unzip {}, {}", rd, rs1)),
+        _ => return None,
+    };
+
+    Some(format!("{} {}, {}, {}", name, rd, rs1, i.imm as i32))
+}
+
+fn disassemble_arithmetic_reg(code: u32) -> Option<String> {
+    // Hazard3 hints encoded as slt to x0
+    match code {
+        0x0000_2033 => return Some("h3.block".into()),
+        0x0010_2033 => return Some("h3.unblock".into()),
+        _ => {}
+    }
+
+    let r = RType::from(code);
+    let (rd, rs1, rs2) = (reg(r.rd), reg(r.rs1), reg(r.rs2));
+
+    let name = match (funct7(code), funct3(code)) {
+        (0b0000000, 0b000) => "add",
+        (0b0000000, 0b001) => "sll",
+        (0b0000000, 0b010) => "slt",
+        (0b0000000, 0b011) => "sltu",
+        (0b0000000, 0b100) => "xor",
+        (0b0000000, 0b101) => "srl",
+        (0b0000000, 0b110) => "or",
+        (0b0000000, 0b111) => "and",
+
+        (0b0100000, 0b000) if r.rs1 == 0 => return Some(format!("neg {}, {}", rd, rs2)),
+        (0b0100000, 0b000) => "sub",
+        (0b0100000, 0b101) => "sra",
+        (0b0100000, 0b100) => "xnor",
+        (0b0100000, 0b110) => "orn",
+        (0b0100000, 0b111) => "andn",
+
+        (0b0000001, 0b000) => "mul",
+        (0b0000001, 0b001) => "mulh",
+        (0b0000001, 0b010) => "mulhsu",
+        (0b0000001, 0b011) => "mulhu",
+        (0b0000001, 0b100) => "div",
+        (0b0000001, 0b101) => "divu",
+        (0b0000001, 0b110) => "rem",
+        (0b0000001, 0b111) => "remu",
+
+        (0b0010000, 0b010) => "sh1add",
+        (0b0010000, 0b100) => "sh2add",
+        (0b0010000, 0b110) => "sh3add",
+
+        (0b0000101, 0b001) => "clmul",
+        (0b0000101, 0b010) => "clmulr",
+        (0b0000101, 0b011) => "clmulh",
+        (0b0000101, 0b100) => "min",
+        (0b0000101, 0b101) => "minu",
+        (0b0000101, 0b110) => "max",
+        (0b0000101, 0b111) => "maxu",
+
+        (0b0110000, 0b001) => "rol",
+        (0b0110000, 0b101) => "ror",
+        (0b0100100, 0b001) => "bclr",
+        (0b0100100, 0b101) => "bext",
+        (0b0110100, 0b001) => "binv",
+        (0b0010100, 0b001) => "bset",
+
+        (0b0000100, 0b100) if r.rs2 == 0 => return Some(format!("zext.h {}, {}", rd, rs1)),
+        (0b0000100, 0b100) => "pack",
+        (0b0000100, 0b111) => "packh",
+        _ => return None,
+    };
+
+    Some(format!("{} {}, {}, {}", name, rd, rs1, rs2))
+}
+
+fn disassemble_system(code: u32) -> Option<String> {
+    let text = match code {
+        0x0000_0073 => "ecall".into(),
+        0x0010_0073 => "ebreak".into(),
+        0x3020_0073 => "mret".into(),
+        0x1050_0073 => "wfi".into(),
+        _ => {
+            let i = IType::from(code);
+            let csr = code >> 20;
+            let name = match funct3(code) {
+                0b001 => "csrrw",
+                0b010 => "csrrs",
+                0b011 => "csrrc",
+                0b101 => "csrrwi",
+                0b110 => "csrrsi",
+                0b111 => "csrrci",
+                _ => return None,
+            };
+
+            if funct3(code) & 0b100 != 0 {
+                format!("{} {}, {:#x}, {}", name, reg(i.rd), csr, i.rs1)
+            } else {
+                format!("{} {}, {:#x}, {}", name, reg(i.rd), csr, reg(i.rs1))
+            }
+        }
+    };
+
+    Some(text)
+}
+
+fn disassemble_compressed(pc: u32, code: u16) -> Option<String> {
+    let quadrant = code & 0b11;
+    let f3 = extract_bits(code, 13..=15);
+
+    let text = match (quadrant, f3) {
+        (0b00, 0b000) => {
+            let imm = (extract_bits(code, 11..=12) << 4)
+                | (extract_bits(code, 7..=10) << 6)
+                | (extract_bit(code, 6) << 2)
+                | (extract_bit(code, 5) << 3);
+
+            if imm == 0 {
+                return None;
+            }
+
+            format!("c.addi4spn {}, sp, {}", reg(crs2_(code)), imm)
+        }
+
+        (0b00, 0b010) | (0b00, 0b110) => {
+            let imm = (extract_bits(code, 10..=12) << 3)
+                | (extract_bit(code, 6) << 2)
+                | (extract_bit(code, 5) << 6);
+            let name = if f3 == 0b010 { "c.lw" } else { "c.sw" };
+            format!("{} {}, {}({})", name, reg(crs2_(code)), imm, reg(crs1_(code)))
+        }
+
+        (0b00, 0b100) => {
+            // Zcb loads and stores
+            let (rs2, rs1) = (reg(crs2_(code)), reg(crs1_(code)));
+            let byte_offset = (extract_bit(code, 5) << 1) | extract_bit(code, 6);
+            let half_offset = extract_bit(code, 5) << 1;
+
+            match (extract_bits(code, 10..=12), extract_bit(code, 6)) {
+                (0b000, _) => format!("c.lbu {}, {}({})", rs2, byte_offset, rs1),
+                (0b001, 0) => format!("c.lhu {}, {}({})", rs2, half_offset, rs1),
+                (0b001, _) => format!("c.lh {}, {}({})", rs2, half_offset, rs1),
+                (0b010, _) => format!("c.sb {}, {}({})", rs2, byte_offset, rs1),
+                (0b011, 0) => format!("c.sh {}, {}({})", rs2, half_offset, rs1),
+                _ => return None,
+            }
+        }
+
+        (0b01, 0b000) => match crs1(code) {
+            0 => "c.nop".into(),
+            rd => format!("c.addi {}, {}", reg(rd), imm_ci(code) as i32),
+        },
+
+        (0b01, 0b001) | (0b01, 0b101) => {
+            let target = pc.wrapping_add(imm_cj(code));
+            let name = if f3 == 0b001 { "c.jal" } else { "c.j" };
+            format!("{} {:#x}", name, target)
+        }
+
+        (0b01, 0b010) => format!("c.li {}, {}", reg(crs1(code)), imm_ci(code) as i32),
+
+        (0b01, 0b011) => match crs1(code) {
+            2 => {
+                let raw = (extract_bit(code, 12) << 9)
+                    | (extract_bit(code, 6) << 4)
+                    | (extract_bit(code, 5) << 6)
+                    | (extract_bits(code, 3..=4) << 7)
+                    | (extract_bit(code, 2) << 5);
+                let imm = crate::utils::sign_extend(raw as u32, 9) as i32;
+                format!("c.addi16sp sp, {}", imm)
+            }
+            rd => format!("c.lui {}, {:#x}", reg(rd), imm_ci(code) & 0xfffff),
+        },
+
+        (0b01, 0b100) => {
+            let rd = reg(crs1_(code));
+            let rs2 = reg(crs2_(code));
+            let shamt = extract_bits(code, 2..=6);
+
+            match (extract_bits(code, 10..=11), extract_bit(code, 12)) {
+                (0b00, _) => format!("c.srli {}, {}", rd, shamt),
+                (0b01, _) => format!("c.srai {}, {}", rd, shamt),
+                (0b10, _) => format!("c.andi {}, {}", rd, imm_ci(code) as i32),
+                (0b11, 0) => {
+                    let name = match extract_bits(code, 5..=6) {
+                        0b00 => "c.sub",
+                        0b01 => "c.xor",
+                        0b10 => "c.or",
+                        _ => "c.and",
+                    };
+                    format!("{} {}, {}", name, rd, rs2)
+                }
+                (0b11, _) => match (extract_bits(code, 5..=6), extract_bits(code, 2..=4)) {
+                    (0b10, _) => format!("c.mul {}, {}", rd, rs2),
+                    (0b11, 0b000) => format!("c.zext.b {}", rd),
+                    (0b11, 0b001) => format!("c.sext.b {}", rd),
+                    (0b11, 0b010) => format!("c.zext.h {}", rd),
+                    (0b11, 0b011) => format!("c.sext.h {}", rd),
+                    (0b11, 0b101) => format!("c.not {}", rd),
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+
+        (0b01, 0b110) | (0b01, 0b111) => {
+            let target = pc.wrapping_add(imm_cb(code));
+            let name = if f3 == 0b110 { "c.beqz" } else { "c.bnez" };
+            format!("{} {}, {:#x}", name, reg(crs1_(code)), target)
+        }
+
+        (0b10, 0b000) => {
+            let shamt = (extract_bit(code, 12) << 5) | extract_bits(code, 2..=6);
+            format!("c.slli {}, {}", reg(crs1(code)), shamt)
+        }
+
+        (0b10, 0b010) => {
+            let imm = (extract_bit(code, 12) << 5)
+                | (extract_bits(code, 4..=6) << 2)
+                | (extract_bits(code, 2..=3) << 6);
+            format!("c.lwsp {}, {}(sp)", reg(crs1(code)), imm)
+        }
+
+        (0b10, 0b100) => match (extract_bit(code, 12), crs1(code), crs2(code)) {
+            (0, rs1, 0) => match rs1 {
+                1 => "c.ret".into(),
+                _ => format!("c.jr {}", reg(rs1)),
+            },
+            (0, rd, rs2) => format!("c.mv {}, {}", reg(rd), reg(rs2)),
+            (_, 0, 0) => "c.ebreak".into(),
+            (_, rs1, 0) => format!("c.jalr {}", reg(rs1)),
+            (_, rd, rs2) => format!("c.add {}, {}", reg(rd), reg(rs2)),
+        },
+
+        (0b10, 0b101) => disassemble_zcmp(code)?,
+
+        (0b10, 0b110) => {
+            let imm = (extract_bits(code, 9..=12) << 2) | (extract_bits(code, 7..=8) << 6);
+            format!("c.swsp {}, {}(sp)", reg(crs2(code)), imm)
+        }
+
+        _ => return None,
+    };
+
+    Some(text)
+}
+
+fn disassemble_zcmp(code: u16) -> Option<String> {
+    let rlist = extract_bits(code, 4..=7);
+    let registers = match rlist {
+        4 => "{ra}".to_string(),
+        5 => "{ra, s0}".to_string(),
+        6..=14 => format!("{{ra, s0-s{}}}", rlist - 5),
+        15 => "{ra, s0-s11}".to_string(),
+        _ => return None,
+    };
+
+    let adj = zcmp_stack_adj(code);
+
+    let text = match extract_bits(code, 8..=12) {
+        0b11000 => format!("cm.push {}, -{}", registers, adj),
+        0b11010 => format!("cm.pop {}, {}", registers, adj),
+        0b11100 => format!("cm.popretz {}, {}", registers, adj),
+        0b11110 => format!("cm.popret {}, {}", registers, adj),
+        _ => {
+            let r1s = zcmp_s_mapping(extract_bits(code, 7..=9) as Register);
+            let r2s = zcmp_s_mapping(extract_bits(code, 2..=4) as Register);
+
+            match (extract_bits(code, 10..=12), extract_bits(code, 5..=6)) {
+                (0b011, 0b01) => format!("cm.mvsa01 {}, {}", reg(r1s), reg(r2s)),
+                (0b011, 0b11) => format!("cm.mva01s {}, {}", reg(r1s), reg(r2s)),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(text)
+}
+
+/// Produce an annotated listing of `data` loaded at `base`,
+/// every line contains the address, the raw bytes and the disassembled instruction.
+/// `labels` are printed in front of the address they point to
+pub fn listing(data: &[u8], base: u32, labels: &BTreeMap<u32, String>) -> String {
+    let mut output = String::new();
+    let mut offset = 0usize;
+
+    while offset + 2 <= data.len() {
+        let address = base.wrapping_add(offset as u32);
+        let mut bytes = [0u8; 4];
+        let available = (data.len() - offset).min(4);
+        bytes[..available].copy_from_slice(&data[offset..offset + available]);
+
+        let code = u32::from_le_bytes(bytes);
+        let mut decoded = disassemble(address, code);
+
+        if decoded.size as usize > available {
+            decoded = DecodedInstruction {
+                size: 2,
+                text: "unknown".into(),
+            };
+        }
+
+        if let Some(label) = labels.get(&address) {
+            let _ = writeln!(output, "\n{:08x} <{}>:", address, label);
+        }
+
+        let raw = match decoded.size {
+            2 => format!("{:04x}    ", code as u16),
+            _ => format!("{:08x}", code),
+        };
+
+        let _ = writeln!(output, "{:08x}:  {}  {}", address, raw, decoded.text);
+        offset += decoded.size as usize;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(pc: u32, code: u32) -> String {
+        disassemble(pc, code).text
+    }
+
+    #[test]
+    fn base_instructions() {
+        assert_eq!(text(0, 0x0000_0013), "nop");
+        assert_eq!(text(0, 0x0050_0513), "li a0, 5");
+        assert_eq!(text(0, 0x00b5_0633), "add a2, a0, a1");
+        assert_eq!(text(0, 0xffc1_2083), "lw ra, -4(sp)");
+        assert_eq!(text(0, 0x0011_2223), "sw ra, 4(sp)");
+        assert_eq!(text(0x100, 0x00b5_0463), "beq a0, a1, 0x108");
+        assert_eq!(text(0, 0x0000_8067), "ret");
+        assert_eq!(text(0, 0x3420_2573), "csrrs a0, 0x342, zero");
+        assert_eq!(text(0, 0x02b5_0533), "mul a0, a0, a1");
+    }
+
+    #[test]
+    fn compressed_instructions() {
+        let decoded = disassemble(0, 0x4501);
+        assert_eq!(decoded.size, 2);
+        assert_eq!(decoded.text, "c.li a0, 0");
+        assert_eq!(text(0, 0x8082), "c.ret");
+        assert_eq!(text(0, 0x852e), "c.mv a0, a1");
+        assert_eq!(text(0, 0x0000), "unknown");
+    }
+
+    #[test]
+    fn listing_with_labels() {
+        let data = [0x01, 0x45, 0x82, 0x80];
+        let labels = BTreeMap::from([(0x1000_0000, "main".to_string())]);
+        let output = listing(&data, 0x1000_0000, &labels);
+
+        assert!(output.contains("10000000 <main>:"));
+        assert!(output.contains("10000000:  4501      c.li a0, 0"));
+        assert!(output.contains("10000002:  8082      c.ret"));
+    }
+}
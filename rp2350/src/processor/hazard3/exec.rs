@@ -87,17 +87,25 @@ pub(super) enum ZcmpAction {
 }
 
 pub(super) struct ExecContext<'a> {
-    xx_bypassed: bool,
+    pub(super) xx_bypassed: bool,
     pub(super) cycles: u8,
     pub(super) next_pc: u32,
     pub(super) register_write: Option<(Register, u32)>,
     pub(super) exception: Option<Exception>,
+    /// Address of the access put on the bus, or of the one which faulted
+    pub(super) access_address: u32,
     pub(super) memory_access: MemoryAccess,
     pub(super) bus: &'a mut Bus,
     pub(super) core: &'a mut Hazard3,
     pub(super) instruction_name: &'static str,
     pub(super) wake_opposite_core: bool,
     pub(super) zcmp_actions: InstructionSequence,
+    /// A load or store trigger fired, the access is not issued
+    pub(super) trigger: Option<TriggerAction>,
+    /// An MRET returned from a trap handler
+    pub(super) trap_return: bool,
+    /// A conditional branch was resolved, whether it was taken and mispredicted
+    pub(super) branch: Option<(bool, bool)>,
 }
 
 impl ExecContext<'_> {
@@ -107,11 +115,15 @@ impl ExecContext<'_> {
             xx_bypassed: false,
             register_write: None,
             exception: None,
+            access_address: 0,
             instruction_name: "Unknown",
             next_pc: 0,
             memory_access: MemoryAccess::None,
             zcmp_actions: Fifo::default(),
             wake_opposite_core: false,
+            trigger: None,
+            trap_return: false,
+            branch: None,
             core,
             bus,
         }
@@ -127,6 +139,17 @@ impl ExecContext<'_> {
         self.core.csrs.privilege_mode
     }
 
+    /// Checks the triggers before an access, true when one fired
+    fn access_trigger(&mut self, access: TriggerAccess, address: u32, data: Option<u32>) -> bool {
+        let csrs = &mut self.core.csrs;
+        let action = csrs
+            .address_trigger(access, address)
+            .or_else(|| data.and_then(|data| csrs.data_trigger(access, data)));
+
+        self.trigger = action;
+        action.is_some()
+    }
+
     fn read_register(&mut self, reg: u8) -> u32 {
         if let Some(bypassing_value) = self.core.xx_bypass {
             if bypassing_value.0 == reg {
@@ -162,16 +185,26 @@ impl ExecContext<'_> {
         op: AtomicOp,
     ) {
         let address: u32 = address.as_();
+        self.access_address = address;
+
         if !is_address_aligned(address, DataSize::Word) {
             self.raise_exception(Exception::LoadAlignment);
             return;
         }
 
+        // the value written back is not known yet, only the addresses are compared
+        if self.access_trigger(TriggerAccess::Load, address, None)
+            || self.access_trigger(TriggerAccess::Store, address, None)
+        {
+            return;
+        }
+
         let bus_ctx = BusAccessContext {
             size: DataSize::Word,
             signed: false,
             exclusive: true,
             secure: self.privilege_mode() == PrivilegeMode::Machine,
+            privileged: self.privilege_mode() == PrivilegeMode::Machine,
             architecture: ArchitectureType::Hazard3,
             requestor: match self.core.csrs.core_id {
                 0 => Requestor::Proc0,
@@ -204,16 +237,24 @@ impl ExecContext<'_> {
         signed: bool,
         exclusive: bool,
     ) {
+        self.access_address = address.as_();
+
         if !is_address_aligned(address.as_(), size) {
             self.raise_exception(Exception::LoadAlignment);
             return;
         }
 
+        // the data triggers of the loads fire once the value is loaded
+        if self.access_trigger(TriggerAccess::Load, address.as_(), None) {
+            return;
+        }
+
         let bus_ctx = BusAccessContext {
             size,
             signed,
             exclusive,
             secure: self.privilege_mode() == PrivilegeMode::Machine,
+            privileged: self.privilege_mode() == PrivilegeMode::Machine,
             architecture: ArchitectureType::Hazard3,
             requestor: match self.core.csrs.core_id {
                 0 => Requestor::Proc0,
@@ -239,16 +280,23 @@ impl ExecContext<'_> {
     }
 
     fn _store(&mut self, address: u32, value: u32, size: DataSize, exclusive: bool) {
+        self.access_address = address;
+
         if !is_address_aligned(address, size) {
             self.raise_exception(Exception::StoreAlignment);
             return;
         }
 
+        if self.access_trigger(TriggerAccess::Store, address, Some(value)) {
+            return;
+        }
+
         let bus_ctx = BusAccessContext {
             size,
             signed: false,
             exclusive,
             secure: self.privilege_mode() == PrivilegeMode::Machine,
+            privileged: self.privilege_mode() == PrivilegeMode::Machine,
             architecture: ArchitectureType::Hazard3,
             requestor: match self.core.csrs.core_id {
                 0 => Requestor::Proc0,
@@ -292,15 +340,18 @@ impl ExecContext<'_> {
     }
 
     fn branch(&mut self, taken: bool, label: impl AsPrimitive<i32>) {
-        if self
+        let mispredicted = self
             .core
             .branch_predictor
-            .miss_predicted(self.core.pc, taken)
-        {
+            .miss_predicted(self.core.pc, taken);
+
+        if mispredicted {
             // cost of misprediction
             self.cycles += 1;
         }
 
+        self.branch = Some((taken, mispredicted));
+
         if taken {
             self.set_next_pc_offset(label);
         }
@@ -369,12 +420,12 @@ impl ExecContext<'_> {
             self.add_zcmp_action(ZcmpAction::RegisterUpdate(10, 0));
         }
 
+        // update stack pointer, before the return so the sequence ends on the jump
+        self.add_zcmp_action(ZcmpAction::RegisterUpdate(2, sp));
+
         if ret {
             self.add_zcmp_action(ZcmpAction::Return);
         }
-
-        // update stack pointer
-        self.add_zcmp_action(ZcmpAction::RegisterUpdate(2, sp));
     }
 }
 
@@ -414,6 +465,7 @@ fn exec_system_instruction(code: u32, ctx: &mut ExecContext) {
             let next_pc = ctx.core.csrs.trap_mret();
             ctx.set_absolute_pc_value(next_pc);
             ctx.set_cycles(2);
+            ctx.trap_return = true;
         }
         0b00010000010100000000000001110011 => {
             ctx.inst_name("WFI");
@@ -439,6 +491,15 @@ fn exec_system_instruction(code: u32, ctx: &mut ExecContext) {
             let IType { rd, rs1, imm } = code.into();
             let imm = imm & 0xfff; // unsigned
 
+            // The Xh3irq arrays are windowed, the index comes from the write data
+            if super::csrs::Csrs::is_windowed(imm as u16) {
+                let write_data = match func3(code) {
+                    0b001..=0b011 => ctx.read_register(rs1),
+                    _ => rs1 as u32,
+                };
+                ctx.core.csrs.select_window(imm as u16, write_data);
+            }
+
             macro_rules! read_csr {
                 ($csr:expr) => {
                     match ctx.read_csr($csr) {
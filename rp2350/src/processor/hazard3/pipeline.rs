@@ -0,0 +1,150 @@
+/**
+ * @file processor/hazard3/pipeline.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Contents of the stages of the Hazard3 pipeline cycle by cycle, for the pipeline diagram
+ */
+use super::Register;
+use std::collections::VecDeque;
+
+/// Cycles kept by the history while it records
+pub const PIPELINE_HISTORY: usize = 64;
+
+/// An instruction going through the pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageInstruction {
+    /// Instructions retired before it, tells apart two runs of the same code
+    pub index: u64,
+    pub address: u32,
+    pub code: u32,
+    pub name: &'static str,
+    /// Read one of its operands from the X-X bypass instead of the register file
+    pub bypassed: bool,
+}
+
+/// Why no new instruction entered X in a cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallReason {
+    /// The instruction in X takes several cycles, like a division
+    MultiCycle,
+    /// A load waits on the bus in M
+    BusLoad,
+    /// A store waits on the bus in M
+    BusStore,
+    /// The read-modify-write of an AMO waits on the bus
+    Atomic,
+    /// The pipeline refills after a trap, an MRET or the return of a Zcmp pop
+    Refill,
+    /// A Zcmp instruction still moving its registers
+    Sequence,
+    Wfi,
+    Sleep,
+    /// Halted in Debug Mode
+    Halted,
+    /// Held by the simulator, the instruction in flight completes
+    Hold,
+}
+
+impl StallReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StallReason::MultiCycle => "multi-cycle",
+            StallReason::BusLoad => "load wait",
+            StallReason::BusStore => "store wait",
+            StallReason::Atomic => "atomic wait",
+            StallReason::Refill => "refill",
+            StallReason::Sequence => "Zcmp sequence",
+            StallReason::Wfi => "WFI",
+            StallReason::Sleep => "sleep",
+            StallReason::Halted => "halted",
+            StallReason::Hold => "hold",
+        }
+    }
+
+    /// The instruction in M holds the pipeline until the bus answers
+    pub fn is_bus_wait(&self) -> bool {
+        matches!(
+            self,
+            StallReason::BusLoad | StallReason::BusStore | StallReason::Atomic
+        )
+    }
+}
+
+/// The stages at the end of a cycle. The Hazard3 has three: F fetches, X decodes and
+/// executes, and M does the loads and stores and writes the results back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineState {
+    /// Cycles of the core since it was created
+    pub cycle: u64,
+    /// Address the next instruction is fetched from, none while the front is stalled
+    pub fetch: Option<u32>,
+    pub execute: Option<StageInstruction>,
+    pub memory: Option<StageInstruction>,
+    pub stall: Option<StallReason>,
+    /// Result held in M and forwarded to X before it is written to the register file
+    pub bypass: Option<(Register, u32)>,
+}
+
+/// The stages of the last cycle, and of the ones before it while recording
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    pub recording: bool,
+    current: PipelineState,
+    history: VecDeque<PipelineState>,
+}
+
+impl Pipeline {
+    pub fn state(&self) -> &PipelineState {
+        &self.current
+    }
+
+    /// The oldest cycle first, the last one included
+    pub fn history(&self) -> impl Iterator<Item = &PipelineState> {
+        self.history.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    pub(super) fn record(&mut self, state: PipelineState) {
+        if self.recording {
+            if self.history.len() >= PIPELINE_HISTORY {
+                self.history.pop_front();
+            }
+
+            self.history.push_back(state);
+        }
+
+        self.current = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let mut pipeline = Pipeline::default();
+
+        pipeline.record(PipelineState {
+            cycle: 1,
+            ..Default::default()
+        });
+        assert_eq!(pipeline.state().cycle, 1);
+        assert_eq!(pipeline.history().count(), 0);
+
+        pipeline.recording = true;
+        for cycle in 2..PIPELINE_HISTORY as u64 + 10 {
+            pipeline.record(PipelineState {
+                cycle,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(pipeline.history().count(), PIPELINE_HISTORY);
+        assert_eq!(pipeline.history().next().unwrap().cycle, 10);
+        assert_eq!(pipeline.state().cycle, PIPELINE_HISTORY as u64 + 9);
+    }
+}
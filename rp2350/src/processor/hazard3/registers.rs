@@ -41,7 +41,7 @@ impl RegisterValue for u16 {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Registers {
     pub(super) x: [u32; 32],
 }
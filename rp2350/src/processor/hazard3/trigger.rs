@@ -0,0 +1,186 @@
+/**
+ * @file /processor/hazard/trigger.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Trigger module of the Hazard3, hardware breakpoints and watchpoints
+ */
+use super::PrivilegeMode;
+
+pub const NUM_TRIGGERS: usize = 4;
+
+// TDATA1 as an address/data match trigger (mcontrol, type 2)
+pub const TDATA1_LOAD: u32 = 1 << 0;
+pub const TDATA1_STORE: u32 = 1 << 1;
+pub const TDATA1_EXECUTE: u32 = 1 << 2;
+pub const TDATA1_U: u32 = 1 << 3;
+pub const TDATA1_M: u32 = 1 << 6;
+pub const TDATA1_MATCH: u32 = 0b1111 << 7;
+pub const TDATA1_ACTION: u32 = 0b1111 << 12;
+pub const TDATA1_SELECT: u32 = 1 << 19;
+pub const TDATA1_HIT: u32 = 1 << 20;
+pub const TDATA1_DMODE: u32 = 1 << 27;
+pub const TDATA1_TYPE: u32 = 2 << 28;
+
+/// Bits writable by M-mode, DMODE only from Debug Mode
+pub(super) const TDATA1_WRITABLE: u32 = TDATA1_LOAD
+    | TDATA1_STORE
+    | TDATA1_EXECUTE
+    | TDATA1_U
+    | TDATA1_M
+    | TDATA1_MATCH
+    | TDATA1_ACTION
+    | TDATA1_SELECT
+    | TDATA1_HIT;
+
+const ACTION_DEBUG_MODE: u32 = 1 << 12;
+
+/// Trigger owned by the debugger, halting the core in both modes
+const DEBUGGER_TRIGGER: u32 = TDATA1_TYPE | TDATA1_DMODE | ACTION_DEBUG_MODE | TDATA1_M | TDATA1_U;
+
+const MATCH_EQUAL: u32 = 0;
+const MATCH_GREATER_OR_EQUAL: u32 = 2;
+const MATCH_LESS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerAccess {
+    Execute,
+    Load,
+    Store,
+}
+
+/// What happens when a trigger fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerAction {
+    /// Breakpoint exception, handled by the firmware
+    Breakpoint,
+    /// The core halts in Debug Mode until it is resumed
+    DebugMode,
+}
+
+/// Why the core entered Debug Mode, the value of DCSR.CAUSE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCause {
+    Ebreak = 1,
+    Trigger = 2,
+    HaltRequest = 3,
+    Step = 4,
+}
+
+/// TDATA1 and TDATA2 of a trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trigger {
+    pub tdata1: u32,
+    pub tdata2: u32,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Self {
+            tdata1: TDATA1_TYPE,
+            tdata2: 0,
+        }
+    }
+}
+
+impl Trigger {
+    /// Halts the core before it executes the instruction at `address`
+    pub fn breakpoint(address: u32) -> Self {
+        Self {
+            tdata1: DEBUGGER_TRIGGER | TDATA1_EXECUTE,
+            tdata2: address,
+        }
+    }
+
+    /// Halts the core before a load or a store to `address`
+    pub fn watchpoint(address: u32, load: bool, store: bool) -> Self {
+        let mut tdata1 = DEBUGGER_TRIGGER;
+
+        if load {
+            tdata1 |= TDATA1_LOAD;
+        }
+
+        if store {
+            tdata1 |= TDATA1_STORE;
+        }
+
+        Self {
+            tdata1,
+            tdata2: address,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tdata1 & (TDATA1_LOAD | TDATA1_STORE | TDATA1_EXECUTE) != 0
+    }
+
+    pub fn has_hit(&self) -> bool {
+        self.tdata1 & TDATA1_HIT != 0
+    }
+
+    /// Compares against the data instead of the address
+    pub fn matches_data(&self) -> bool {
+        self.tdata1 & TDATA1_SELECT != 0
+    }
+
+    pub fn action(&self) -> TriggerAction {
+        // entering Debug Mode is reserved to the triggers owned by the debugger
+        match self.tdata1 & TDATA1_ACTION {
+            ACTION_DEBUG_MODE if self.tdata1 & TDATA1_DMODE != 0 => TriggerAction::DebugMode,
+            _ => TriggerAction::Breakpoint,
+        }
+    }
+
+    /// Whether the trigger fires on an access of `value`, the address or the data
+    /// depending on SELECT, in the given privilege mode
+    pub fn matches(&self, access: TriggerAccess, value: u32, privilege: PrivilegeMode) -> bool {
+        let access = match access {
+            TriggerAccess::Execute => TDATA1_EXECUTE,
+            TriggerAccess::Load => TDATA1_LOAD,
+            TriggerAccess::Store => TDATA1_STORE,
+        };
+
+        let mode = match privilege {
+            PrivilegeMode::Machine => TDATA1_M,
+            PrivilegeMode::User => TDATA1_U,
+        };
+
+        if self.tdata1 & access == 0 || self.tdata1 & mode == 0 {
+            return false;
+        }
+
+        match self.tdata1 >> 7 & 0b1111 {
+            MATCH_EQUAL => value == self.tdata2,
+            MATCH_GREATER_OR_EQUAL => value >= self.tdata2,
+            MATCH_LESS => value < self.tdata2,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match() {
+        let machine = PrivilegeMode::Machine;
+
+        let breakpoint = Trigger::breakpoint(0x2000_0010);
+        assert!(breakpoint.matches(TriggerAccess::Execute, 0x2000_0010, machine));
+        assert!(!breakpoint.matches(TriggerAccess::Execute, 0x2000_0014, machine));
+        assert!(!breakpoint.matches(TriggerAccess::Load, 0x2000_0010, machine));
+        assert_eq!(breakpoint.action(), TriggerAction::DebugMode);
+
+        // stores from U-mode at or above 0x20040000, handled by the firmware
+        let watchpoint = Trigger {
+            tdata1: TDATA1_TYPE | TDATA1_U | TDATA1_STORE | MATCH_GREATER_OR_EQUAL << 7,
+            tdata2: 0x2004_0000,
+        };
+        assert!(watchpoint.matches(TriggerAccess::Store, 0x2004_0100, PrivilegeMode::User));
+        assert!(!watchpoint.matches(TriggerAccess::Store, 0x2003_fffc, PrivilegeMode::User));
+        assert!(!watchpoint.matches(TriggerAccess::Store, 0x2004_0100, machine));
+        assert_eq!(watchpoint.action(), TriggerAction::Breakpoint);
+
+        assert!(!Trigger::default().is_enabled());
+    }
+}
@@ -0,0 +1,434 @@
+/**
+ * @file profiler.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Cycles spent on every instruction of the cores, attributed to the functions and their callers
+ */
+use crate::elf::{Elf, SymbolKind};
+use crate::inspector::{InspectionEvent, Inspector};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Deeper call stacks lose their outermost functions
+pub const MAX_STACK_DEPTH: usize = 64;
+
+/// Frame of the code outside of every function, like the bootrom without its symbols
+const UNKNOWN: usize = usize::MAX;
+const UNKNOWN_NAME: &str = "[unknown]";
+
+/// A function the cycles are attributed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub start: u32,
+    /// Address right after the function
+    pub end: u32,
+}
+
+impl Function {
+    pub fn new(name: impl Into<String>, start: u32, end: u32) -> Self {
+        Self {
+            name: name.into(),
+            start,
+            end,
+        }
+    }
+
+    /// The functions of the symbol table, the ones without a size are left out
+    pub fn from_elf(elf: &Elf) -> Vec<Function> {
+        elf.symbols
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Function && symbol.size > 0)
+            .map(|symbol| {
+                // the lowest bit marks the Thumb functions
+                let start = symbol.address & !1;
+                Self::new(&symbol.name, start, start + symbol.size)
+            })
+            .collect()
+    }
+}
+
+/// Cycles of a function over the whole profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    pub name: String,
+    /// Spent in the function itself
+    pub self_cycles: u64,
+    /// Spent in the function and everything it called
+    pub total_cycles: u64,
+}
+
+/// A frame of the flame graph, its children are the functions it called
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FlameNode {
+    pub name: String,
+    pub cycles: u64,
+    pub children: Vec<FlameNode>,
+}
+
+impl FlameNode {
+    fn insert(&mut self, names: &[&str], cycles: u64) {
+        self.cycles += cycles;
+
+        let Some((name, rest)) = names.split_first() else {
+            return;
+        };
+
+        let index = match self.children.iter().position(|child| child.name == *name) {
+            Some(index) => index,
+            None => {
+                self.children.push(FlameNode {
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                self.children.len() - 1
+            }
+        };
+
+        self.children[index].insert(rest, cycles);
+    }
+}
+
+#[derive(Default)]
+struct CoreState {
+    /// Address of the instruction being executed, the cycles until the next one go to it
+    pc: Option<u32>,
+    cycles: u64,
+    /// Indices of the functions, the outermost first
+    stack: Vec<usize>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Sorted by address
+    functions: Vec<Function>,
+    cores: [CoreState; 2],
+    addresses: HashMap<u32, u64>,
+    stacks: HashMap<Vec<usize>, u64>,
+    total: u64,
+}
+
+impl State {
+    fn function_at(&self, address: u32) -> usize {
+        let index = self
+            .functions
+            .partition_point(|function| function.start <= address);
+
+        match index.checked_sub(1) {
+            Some(index) if address < self.functions[index].end => index,
+            _ => UNKNOWN,
+        }
+    }
+
+    fn name(&self, index: usize) -> &str {
+        match self.functions.get(index) {
+            Some(function) => &function.name,
+            None => UNKNOWN_NAME,
+        }
+    }
+
+    /// Hand the cycles counted so far to the last instruction of the core
+    fn flush(&mut self, core: usize) {
+        let state = &mut self.cores[core];
+        let cycles = core::mem::take(&mut state.cycles);
+
+        let Some(pc) = state.pc.filter(|_| cycles != 0) else {
+            return;
+        };
+
+        *self.addresses.entry(pc).or_insert(0) += cycles;
+        self.total += cycles;
+
+        match self.stacks.get_mut(state.stack.as_slice()) {
+            Some(count) => *count += cycles,
+            None => {
+                self.stacks.insert(state.stack.clone(), cycles);
+            }
+        }
+    }
+
+    /// Follow the calls and the returns from where the instructions are. Jumping to the
+    /// start of a function calls it, landing in a function down the stack returns to it
+    fn enter(&mut self, core: usize, address: u32) {
+        let function = self.function_at(address);
+        let is_entry = self
+            .functions
+            .get(function)
+            .is_some_and(|function| function.start == address);
+        let stack = &mut self.cores[core].stack;
+
+        if stack.last() == Some(&function) && !is_entry {
+            return;
+        }
+
+        match stack.iter().rposition(|&frame| frame == function) {
+            Some(position) if !is_entry => stack.truncate(position + 1),
+            _ => {
+                if stack.len() >= MAX_STACK_DEPTH {
+                    stack.remove(0);
+                }
+
+                stack.push(function);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.cores = Default::default();
+        self.addresses.clear();
+        self.stacks.clear();
+        self.total = 0;
+    }
+}
+
+/// Inspector accounting every cycle of the cores to the instruction they were executing,
+/// the stalls and the bus waits included, and to the call stack of the functions it
+/// belongs to.
+///
+/// The call stacks are followed from the addresses of the instructions alone, so an
+/// interrupt handler shows as called by the function it interrupted and a tail call as
+/// called by the function it replaced. A sleeping core counts its cycles on its WFI.
+#[derive(Default)]
+pub struct Profiler(RefCell<State>);
+
+impl Profiler {
+    pub fn new(functions: Vec<Function>) -> Self {
+        let profiler = Self::default();
+        profiler.set_functions(functions);
+        profiler
+    }
+
+    /// Replace the functions, to do when another program is loaded. The profile starts over
+    pub fn set_functions(&self, mut functions: Vec<Function>) {
+        functions.sort_by_key(|function| function.start);
+
+        let mut state = self.0.borrow_mut();
+        state.functions = functions;
+        state.clear();
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    /// Cycles of both cores, the ones of the instructions still executing left out
+    pub fn total_cycles(&self) -> u64 {
+        self.0.borrow().total
+    }
+
+    /// Cycles by address of the instruction, the hottest first
+    pub fn hot_addresses(&self) -> Vec<(u32, u64)> {
+        let state = self.0.borrow();
+        let mut addresses: Vec<(u32, u64)> = state
+            .addresses
+            .iter()
+            .map(|(&address, &cycles)| (address, cycles))
+            .collect();
+
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        addresses
+    }
+
+    /// Cycles by function, the ones spent in the function itself the highest first
+    pub fn hot_functions(&self) -> Vec<FunctionProfile> {
+        let state = self.0.borrow();
+        let mut functions: HashMap<usize, FunctionProfile> = HashMap::new();
+
+        for (stack, &cycles) in state.stacks.iter() {
+            let mut counted = Vec::with_capacity(stack.len());
+
+            for (depth, &frame) in stack.iter().enumerate().rev() {
+                let profile = functions.entry(frame).or_insert_with(|| FunctionProfile {
+                    name: state.name(frame).to_string(),
+                    self_cycles: 0,
+                    total_cycles: 0,
+                });
+
+                if depth == stack.len() - 1 {
+                    profile.self_cycles += cycles;
+                }
+
+                // a recursive function is only counted once
+                if !counted.contains(&frame) {
+                    counted.push(frame);
+                    profile.total_cycles += cycles;
+                }
+            }
+        }
+
+        let mut functions: Vec<FunctionProfile> = functions.into_values().collect();
+        functions.sort_by(|a, b| {
+            (b.self_cycles, b.total_cycles)
+                .cmp(&(a.self_cycles, a.total_cycles))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        functions
+    }
+
+    fn stack_names<'a>(state: &'a State, stack: &[usize]) -> Vec<&'a str> {
+        match stack {
+            [] => vec![UNKNOWN_NAME],
+            stack => stack.iter().map(|&frame| state.name(frame)).collect(),
+        }
+    }
+
+    /// The call stacks and their cycles, the root named `all`
+    pub fn flame_graph(&self) -> FlameNode {
+        let state = self.0.borrow();
+        let mut root = FlameNode {
+            name: "all".to_string(),
+            ..Default::default()
+        };
+
+        for (stack, &cycles) in state.stacks.iter() {
+            root.insert(&Self::stack_names(&state, stack), cycles);
+        }
+
+        fn sort(node: &mut FlameNode) {
+            node.children.sort_by(|a, b| a.name.cmp(&b.name));
+            node.children.iter_mut().for_each(sort);
+        }
+
+        sort(&mut root);
+        root
+    }
+
+    /// The call stacks in the folded format of the flame graph tools, `main;foo;bar 42`
+    pub fn to_folded(&self) -> String {
+        let state = self.0.borrow();
+        let mut lines: Vec<String> = state
+            .stacks
+            .iter()
+            .map(|(stack, cycles)| {
+                format!("{} {cycles}", Self::stack_names(&state, stack).join(";"))
+            })
+            .collect();
+
+        lines.sort();
+
+        let mut folded = String::new();
+        for line in lines {
+            let _ = writeln!(folded, "{line}");
+        }
+
+        folded
+    }
+}
+
+impl Inspector for Profiler {
+    fn handle_event(&self, event: InspectionEvent) {
+        let mut state = self.0.borrow_mut();
+
+        match event {
+            InspectionEvent::TickCore(core) => {
+                state.cores[core as usize & 1].cycles += 1;
+            }
+            InspectionEvent::BusyWaitSkipped { core, cycles, .. } => {
+                state.cores[core as usize & 1].cycles += cycles;
+            }
+            InspectionEvent::IdleSkipped { cycles } => {
+                for core in state.cores.iter_mut() {
+                    core.cycles += cycles;
+                }
+            }
+            InspectionEvent::ExecutedInstruction { core, address, .. } => {
+                let core = core as usize & 1;
+                state.flush(core);
+                state.cores[core].pc = Some(address);
+                state.enter(core, address);
+            }
+            InspectionEvent::FlashedBinary => state.clear(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute(profiler: &Profiler, core: u8, address: u32, cycles: u64) {
+        profiler.handle_event(InspectionEvent::ExecutedInstruction {
+            core,
+            instruction: 0,
+            address,
+            name: "",
+            operands: Vec::new(),
+        });
+
+        for _ in 0..cycles {
+            profiler.handle_event(InspectionEvent::TickCore(core));
+        }
+    }
+
+    fn profiler() -> Profiler {
+        Profiler::new(vec![
+            Function::new("leaf", 0x300, 0x310),
+            Function::new("main", 0x100, 0x120),
+            Function::new("work", 0x200, 0x220),
+        ])
+    }
+
+    #[test]
+    fn test_call_stacks() {
+        let profiler = profiler();
+
+        execute(&profiler, 0, 0x100, 1);
+        execute(&profiler, 0, 0x104, 2); // call work
+        execute(&profiler, 0, 0x200, 3);
+        execute(&profiler, 0, 0x204, 1); // call leaf
+        execute(&profiler, 0, 0x300, 4);
+        execute(&profiler, 0, 0x208, 5); // back in work
+        execute(&profiler, 0, 0x108, 6); // back in main
+        execute(&profiler, 0, 0x400, 7); // nothing known there
+
+        // the cycles of the last instruction are not known yet
+        assert_eq!(profiler.total_cycles(), 22);
+        assert_eq!(
+            profiler.to_folded(),
+            "main 9\nmain;work 9\nmain;work;leaf 4\n"
+        );
+
+        let functions = profiler.hot_functions();
+        assert_eq!(functions[0].name, "main");
+        assert_eq!(functions[0].total_cycles, 22);
+        assert_eq!(functions[1].name, "work");
+        assert_eq!(
+            (functions[1].self_cycles, functions[1].total_cycles),
+            (9, 13)
+        );
+
+        assert_eq!(profiler.hot_addresses()[0], (0x108, 6));
+
+        let root = profiler.flame_graph();
+        assert_eq!(root.cycles, 22);
+        assert_eq!(root.children[0].children[0].name, "work");
+        assert_eq!(root.children[0].children[0].cycles, 13);
+    }
+
+    #[test]
+    fn test_recursion_and_cores() {
+        let profiler = profiler();
+
+        execute(&profiler, 0, 0x200, 1);
+        execute(&profiler, 1, 0x100, 10);
+        execute(&profiler, 0, 0x200, 2); // calls itself
+        execute(&profiler, 0, 0x204, 3);
+        execute(&profiler, 1, 0x104, 1);
+        execute(&profiler, 0, 0x208, 0);
+
+        let functions = profiler.hot_functions();
+        assert_eq!(functions[0].name, "main");
+        assert_eq!(functions[0].self_cycles, 10);
+        assert_eq!(functions[1].name, "work");
+        assert_eq!(
+            (functions[1].self_cycles, functions[1].total_cycles),
+            (6, 6)
+        );
+
+        profiler.handle_event(InspectionEvent::FlashedBinary);
+        assert_eq!(profiler.total_cycles(), 0);
+        assert!(profiler.hot_functions().is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+/**
+ * @file rewind.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Stepping back in time, by restoring a checkpoint and executing again up to the instruction asked for
+ */
+use crate::inspector::{InspectionEvent, Inspector};
+use crate::processor::Rp2350Core;
+use crate::rp2350::Rp2350;
+use crate::snapshot::ProgramSnapshot;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// Ticks between two checkpoints
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 1_000_000;
+
+/// Each checkpoint holds a copy of the SRAM
+pub const DEFAULT_MAX_CHECKPOINTS: usize = 8;
+
+/// Checkpoints taken along a run, to go back a number of instructions of a core.
+///
+/// Going back restores the latest checkpoint before the instruction, then steps the
+/// core forward until it is there again. The snapshots leave out the peripherals and
+/// the clock, so a checkpoint is only restored when no access reached the peripherals
+/// since it was taken. The instructions executed again are hidden from the inspectors,
+/// they already saw them the first time.
+pub struct Rewind {
+    interval: u64,
+    max_checkpoints: usize,
+    checkpoints: VecDeque<ProgramSnapshot>,
+    next: u64,
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHECKPOINT_INTERVAL, DEFAULT_MAX_CHECKPOINTS)
+    }
+}
+
+impl Rewind {
+    pub fn new(interval: u64, max_checkpoints: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            max_checkpoints: max_checkpoints.max(1),
+            checkpoints: VecDeque::new(),
+            next: 0,
+        }
+    }
+
+    pub fn checkpoints(&self) -> impl Iterator<Item = &ProgramSnapshot> {
+        self.checkpoints.iter()
+    }
+
+    /// Drop the checkpoints, to do when another program is loaded or the chip is reset
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+        self.next = 0;
+    }
+
+    /// Instructions of `core` which can be stepped back at most
+    pub fn depth(&self, mcu: &Rp2350, core: usize) -> u64 {
+        self.checkpoints
+            .iter()
+            .find(|checkpoint| mcu.can_restore(checkpoint))
+            .map_or(0, |oldest| {
+                let retired = mcu.processor[core].retired();
+                retired.saturating_sub(oldest.cores[core].retired())
+            })
+    }
+
+    /// Take a checkpoint once one is due, to call after every tick. It waits for both
+    /// cores to be between two instructions, a snapshot restores at an instruction boundary
+    pub fn record(&mut self, mcu: &mut Rp2350) {
+        if *mcu.clock.ticks.borrow() < self.next || mcu.processor.iter().any(Rp2350Core::is_busy) {
+            return;
+        }
+
+        self.checkpoint(mcu);
+    }
+
+    pub fn checkpoint(&mut self, mcu: &mut Rp2350) {
+        // the result of the last instruction is part of the state to capture
+        for core in &mut mcu.processor {
+            core.flush_bypass();
+        }
+
+        if self.checkpoints.len() >= self.max_checkpoints {
+            self.checkpoints.pop_front();
+        }
+
+        self.checkpoints.push_back(mcu.snapshot());
+        self.next = *mcu.clock.ticks.borrow() + self.interval;
+    }
+
+    /// Go back `count` instructions of `core`. The checkpoints past the one restored
+    /// are dropped, the run from there is a new history. The replay stops early if it
+    /// reaches a peripheral anyway, like through an interrupt raised while it runs.
+    /// Returns the instructions stepped back, `None` when the checkpoints do not go
+    /// back that far, or a peripheral was accessed since, and nothing changed
+    pub fn step_back(&mut self, mcu: &mut Rp2350, core: usize, count: u64) -> Option<u64> {
+        let retired = mcu.processor[core].retired();
+        let target = retired.saturating_sub(count);
+
+        let index = self
+            .checkpoints
+            .iter()
+            .rposition(|checkpoint| checkpoint.cores[core].retired() <= target)?;
+
+        // executing again from the checkpoint only gives the same results when none of
+        // the instructions since then read or changed a peripheral
+        mcu.restore(&self.checkpoints[index]).ok()?;
+        self.checkpoints.truncate(index + 1);
+
+        let inspector = mcu.inspector();
+        mcu.set_inspector(Rc::new(Muted));
+
+        let accesses = mcu.bus.peripheral_accesses();
+        while mcu.processor[core].retired() < target {
+            if !mcu.step_instruction(core) || mcu.bus.peripheral_accesses() != accesses {
+                break;
+            }
+        }
+
+        mcu.set_inspector(inspector);
+
+        self.next = *mcu.clock.ticks.borrow() + self.interval;
+        Some(retired - mcu.processor[core].retired())
+    }
+}
+
+/// Drops the events of the instructions executed again
+struct Muted;
+
+impl Inspector for Muted {
+    fn handle_event(&self, _event: InspectionEvent) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::setup;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+
+    fn state(mcu: &Rp2350) -> (u32, u32) {
+        match &mcu.processor[0] {
+            Rp2350Core::RiscV(core) => (core.pc, core.registers.read(10)),
+            Rp2350Core::Arm(_) => unreachable!(),
+        }
+    }
+
+    #[derive(Default)]
+    struct Counter(Cell<u64>);
+
+    impl Inspector for Counter {
+        fn handle_event(&self, _event: InspectionEvent) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut mcu = setup(&[
+            0x0015_0513, // addi a0, a0, 1
+            0x0000_0597, // auipc a1, 0
+            0x0005_a583, // lw a1, 0(a1)
+            0xff5f_f06f, // j -12
+        ]);
+
+        let mut rewind = Rewind::new(20, 4);
+        let mut history = HashMap::new();
+
+        for _ in 0..200 {
+            assert!(mcu.step_instruction(0));
+            rewind.record(&mut mcu);
+            history.insert(mcu.processor[0].retired(), state(&mcu));
+        }
+
+        let retired = mcu.processor[0].retired();
+        assert!(rewind.depth(&mcu, 0) >= 7);
+        assert_eq!(rewind.step_back(&mut mcu, 0, 7), Some(7));
+        assert_eq!(mcu.processor[0].retired(), retired - 7);
+        assert_eq!(state(&mcu), history[&(retired - 7)]);
+
+        // the oldest checkpoint is not that far back
+        assert_eq!(rewind.step_back(&mut mcu, 0, 190), None);
+        assert_eq!(mcu.processor[0].retired(), retired - 7);
+    }
+
+    #[test]
+    fn test_step_back_over_timer_read() {
+        let mut mcu = setup(&[
+            0x400b_05b7, // lui a1, 0x400b0
+            0x0285_a603, // lw a2, 0x28(a1), TIMER0 TIMERAWL
+            0x0015_0513, // addi a0, a0, 1
+            0xffdf_f06f, // j -4
+        ]);
+
+        let counter = Rc::new(Counter::default());
+        mcu.set_inspector(counter.clone());
+
+        let mut rewind = Rewind::new(20, 8);
+        rewind.checkpoint(&mut mcu);
+
+        for _ in 0..100 {
+            assert!(mcu.step_instruction(0));
+            rewind.record(&mut mcu);
+        }
+
+        // only the checkpoints after the timer read can be restored
+        let retired = mcu.processor[0].retired();
+        let depth = rewind.depth(&mcu, 0);
+        assert!(depth > 0 && depth < retired - 2);
+        assert_eq!(rewind.step_back(&mut mcu, 0, retired - 1), None);
+        assert_eq!(mcu.processor[0].retired(), retired);
+
+        // executing the loop again does not reach the inspectors twice
+        let events = counter.0.get();
+        assert_eq!(rewind.step_back(&mut mcu, 0, 3), Some(3));
+        assert_eq!(mcu.processor[0].retired(), retired - 3);
+        assert_eq!(counter.0.get(), events);
+    }
+}
@@ -4,17 +4,36 @@
  * @date 02/01/2025
  * @brief Entry point for the Rp2350 simulator.
  */
-use crate::bus::{self, Bus};
+use crate::bus::{self, Bus, BusStatistics};
 use crate::clock::Clock;
-use crate::common::MB;
+use crate::common::{ArchitectureType, Requestor, MB};
+use crate::device::VirtualDevice;
+use crate::environment::{Environment, ThermalScenario};
+use crate::error::{ConfigError, Error, LoadImageError};
 use crate::gpio::GpioController;
 use crate::inspector::{InspectionEvent, InspectorRef};
 use crate::interrupts::Interrupts;
-use crate::processor::{ProcessorContext, Rp2350Core};
+use crate::peripherals::{
+    powman, uart, I2cDeviceRef, Otp, Plugin, PluginDevice, PluginError, PluginInfo, PluginModule,
+    SpiDeviceRef, UartLink, WatchDog,
+};
+use crate::processor::{InterruptLatency, ProcessorContext, Rp2350Core};
+use crate::snapshot::ProgramSnapshot;
 use crate::Result;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+mod builder;
+mod busy_wait;
+mod idle;
+
+pub use builder::{Image, Rp2350Builder};
+pub use busy_wait::SkippedWait;
+pub use idle::MAX_IDLE_TICKS;
+
+/// Ticks [`Rp2350::step_instruction`] waits for an instruction, a sleeping core gives up there
+pub const STEP_TIMEOUT: u64 = 1_000_000;
+
 pub struct Rp2350 {
     pub clock: Rc<Clock>,
     pub bus: Bus,
@@ -23,6 +42,34 @@ pub struct Rp2350 {
     pub gpio: Rc<RefCell<GpioController>>,
     pub interrupts: Rc<RefCell<Interrupts>>,
     inspector: InspectorRef,
+    /// RUN pin, the chip is held in reset while it is low
+    run: bool,
+    devices: Vec<Box<dyn VirtualDevice>>,
+    /// Fast-forward the delay loops of the cores as soon as they are detected
+    skip_busy_waits: bool,
+    /// Fast-forward the time both cores sleep through, see [`Rp2350::skip_idle`]
+    skip_idle: bool,
+    /// Kept over the resets, which recreate the cores
+    strict_csrs: bool,
+    interrupt_latency: InterruptLatency,
+    /// Ticks each core was skipped while asleep, handed over to it when it wakes
+    slept: [u64; 2],
+}
+
+/// Tick a core, unless it sleeps until an interrupt. A sleeping core is skipped
+/// entirely, it is only told how long it slept once something wakes it up
+fn tick_core(core_id: u8, core: &mut Rp2350Core, slept: &mut u64, ctx: &mut ProcessorContext) {
+    if ctx.interrupts.borrow().is_asleep(core_id) {
+        *slept += 1;
+        return;
+    }
+
+    if *slept != 0 {
+        core.slept(std::mem::take(slept));
+    }
+
+    ctx.bus.set_core_pc(core_id as usize, core.get_pc());
+    core.tick(ctx);
 }
 
 impl Default for Rp2350 {
@@ -33,6 +80,15 @@ impl Default for Rp2350 {
 
 impl Rp2350 {
     pub fn new() -> Self {
+        Self::with_inspector(InspectorRef::default())
+    }
+
+    /// Start configuring a machine, see [`Rp2350Builder`]
+    pub fn builder() -> Rp2350Builder {
+        Rp2350Builder::new()
+    }
+
+    fn with_inspector(inspector: InspectorRef) -> Self {
         let interrupts = Rc::new(RefCell::new(Interrupts::default()));
         let gpio = Rc::new(RefCell::new(GpioController::new(interrupts.clone())));
         let clock = Rc::new(Clock::new());
@@ -41,7 +97,6 @@ impl Rp2350 {
         processor[0].set_core_id(0);
         processor[1].set_core_id(1);
 
-        let inspector = InspectorRef::default();
         let bus = Bus::new(
             Rc::clone(&gpio),
             Rc::clone(&interrupts),
@@ -58,58 +113,223 @@ impl Rp2350 {
             clock,
             interrupts,
             gpio,
+            run: true,
+            devices: Vec::new(),
+            skip_busy_waits: false,
+            skip_idle: false,
+            strict_csrs: true,
+            interrupt_latency: InterruptLatency::default(),
+            slept: [0; 2],
         }
     }
 
+    /// Wire a virtual device to the chip, it is ticked along with the machine
+    pub fn attach_device(&mut self, mut device: Box<dyn VirtualDevice>) {
+        device.attach(self);
+        self.devices.push(device);
+    }
+
+    /// Map the registers of a plugin into the peripherals, it is ticked along with the
+    /// devices
+    pub fn load_plugin(
+        &mut self,
+        info: PluginInfo,
+        module: Box<dyn PluginModule>,
+    ) -> core::result::Result<Rc<RefCell<Plugin>>, PluginError> {
+        let plugin = self.bus.peripherals.add_plugin(info, module)?;
+        self.attach_device(Box::new(PluginDevice(Rc::clone(&plugin))));
+        Ok(plugin)
+    }
+
+    fn tick_devices(&mut self) {
+        // the devices need the whole machine, take them out while they run
+        let mut devices = std::mem::take(&mut self.devices);
+
+        for device in devices.iter_mut() {
+            device.tick(self);
+        }
+
+        // keep the devices attached by the ones above
+        devices.append(&mut self.devices);
+        self.devices = devices;
+    }
+
     pub fn reset(&mut self) {
         self.bus.reset();
-        self.processor[0] = Rp2350Core::new();
-        self.processor[1] = Rp2350Core::new();
-        self.processor[0].set_core_id(0);
-        self.processor[1].set_core_id(1);
+        self.reset_cores();
         self.gpio.borrow_mut().reset();
         self.interrupts.borrow_mut().reset();
     }
 
+    /// External reset from the RUN pin. The cores and peripherals are reset,
+    /// while the SRAMs, the watchdog scratch registers and the flash keep their content
+    pub fn external_reset(&mut self) {
+        let bootram = self.bus.peripherals.bootram.data.clone();
+        self.bus.reset_retain_sram();
+        self.bus.peripherals.bootram.data = bootram;
+        self.reset_cores();
+        self.gpio.borrow_mut().reset();
+        self.interrupts.borrow_mut().reset();
+        self.run = true;
+    }
+
+    /// Power cycle the chip, only the flash survives
+    pub fn power_cycle(&mut self) {
+        self.bus.peripherals.otp.archsel = Otp::default().archsel;
+        self.reset();
+        self.bus.peripherals.watch_dog.scratch = WatchDog::default().scratch;
+        self.bus.peripherals.glitch_detector.trig_status = 0;
+        self.run = true;
+    }
+
+    /// Reset from a glitch detector, like the external reset with POWMAN recording
+    /// it in CHIP_RESET
+    fn glitch_reset(&mut self) {
+        self.external_reset();
+
+        let mut powman = self.bus.peripherals.powman.borrow_mut();
+        powman.chip_reset = powman.chip_reset & powman::CHIP_RESET_DOUBLE_TAP
+            | powman::CHIP_RESET_HAD_GLITCH_DETECT;
+    }
+
+    fn reset_cores(&mut self) {
+        let otp = &mut self.bus.peripherals.otp;
+        otp.archsel_status = otp.archsel;
+        self.slept = [0; 2];
+
+        for (core_id, core) in self.processor.iter_mut().enumerate() {
+            let architecture = if otp.is_riscv(core_id as u8) {
+                ArchitectureType::Hazard3
+            } else {
+                ArchitectureType::CortexM33
+            };
+
+            *core = Rp2350Core::with_architecture(architecture);
+            core.set_core_id(core_id as u8);
+            core.set_strict_csrs(self.strict_csrs);
+            core.set_interrupt_latency(self.interrupt_latency);
+        }
+    }
+
+    /// Raise an illegal instruction on the CSR accesses the spec forbids, like reading
+    /// a counter from U-mode without MCOUNTEREN, instead of reading 0. On by default
+    pub fn set_strict_csrs(&mut self, strict: bool) {
+        self.strict_csrs = strict;
+
+        for core in self.processor.iter_mut() {
+            core.set_strict_csrs(strict);
+        }
+    }
+
+    /// Extra cycles the cores spend entering and leaving the interrupt handlers
+    pub fn set_interrupt_latency(&mut self, latency: InterruptLatency) {
+        self.interrupt_latency = latency;
+
+        for core in self.processor.iter_mut() {
+            core.set_interrupt_latency(latency);
+        }
+    }
+
+    /// Select the architecture of both cores through ARCHSEL, it takes effect on the next reset
+    pub fn select_architecture(&mut self, architecture: ArchitectureType) {
+        self.bus.peripherals.otp.archsel = match architecture {
+            ArchitectureType::Hazard3 => 0b11,
+            ArchitectureType::CortexM33 => 0b00,
+        };
+    }
+
+    /// Architecture the cores will boot with after the next reset
+    pub fn selected_architecture(&self) -> ArchitectureType {
+        if self.bus.peripherals.otp.is_riscv(0) {
+            ArchitectureType::Hazard3
+        } else {
+            ArchitectureType::CortexM33
+        }
+    }
+
+    /// UF2 family accepted for the architecture core 0 is running
+    fn uf2_target(&self) -> uf2::FamilyId {
+        match self.processor[0].architecture() {
+            ArchitectureType::Hazard3 => uf2::FamilyId::Rp2350RiscV,
+            ArchitectureType::CortexM33 => uf2::FamilyId::Rp2350ArmS,
+        }
+    }
+
     pub fn set_inspector(&mut self, inspector: Rc<dyn crate::inspector::Inspector>) {
         self.inspector.set_inspector(inspector);
         self.bus.peripherals.inspector = self.inspector.clone();
     }
 
+    pub fn inspector(&self) -> Rc<dyn crate::inspector::Inspector> {
+        self.inspector.inspector()
+    }
+
     pub fn flash_bin(&mut self, bin: &[u8]) -> Result<()> {
         if bin.len() > 4 * MB {
-            return Err(crate::SimulatorError::FileTooLarge);
+            let error = LoadImageError::TooLarge {
+                size: bin.len(),
+                capacity: 4 * MB,
+            };
+
+            return Err(error.into());
         }
 
         self.bus.flash.write_slice(0, bin).ok();
         Ok(())
     }
 
+    /// Write raw data into the flash, the SRAM or the boot RAM at an absolute address
+    pub fn load_into_memory(&mut self, address: u32, data: &[u8]) -> Result<()> {
+        let result = match address & 0xF000_0000 {
+            Bus::XIP => {
+                let offset = address & bus::XIP_ADDRESS_MASK;
+                self.bus.flash.write_slice(offset, data)
+            }
+            Bus::SRAM => self.bus.sram.write_slice(address - Bus::SRAM, data),
+            _ if (Bus::BOOTRAM..Bus::BOOTRAM + 0x400).contains(&address) => {
+                let offset = address - Bus::BOOTRAM;
+                self.bus.peripherals.bootram.data.write_slice(offset, data)
+            }
+            _ => return Err(LoadImageError::InvalidAddress(address).into()),
+        };
+
+        result.map_err(|_| {
+            let size = data.len();
+            LoadImageError::OutOfBounds { address, size }.into()
+        })
+    }
+
     pub fn flash_uf2(&mut self, uf2: &[u8]) -> Result<()> {
-        for block in uf2::read_uf2(uf2)? {
-            let Some(family_id) = block.family_id else {
-                log::warn!("No family ID found in UF2 block");
-                continue;
-            };
+        let target = self.uf2_target();
+        let diagnostics = uf2::validate_for_target(uf2, target)?;
 
-            if crate::common::is_supported_uf2_family_id(family_id) {
-                log::debug!(
-                    "Flashing block: {:#X} -> {:#X}",
-                    block.target_addr,
-                    block.data.len()
-                );
-            } else {
-                log::warn!("Unsupported UF2 family ID: {:#X}", family_id);
+        for diagnostic in diagnostics.iter() {
+            log::warn!("{}", diagnostic);
+        }
+
+        if let Some(fatal) = diagnostics.iter().find(|v| v.is_fatal()) {
+            return Err(LoadImageError::Uf2Rejected(*fatal).into());
+        }
+
+        for block in uf2::read_uf2_ref(uf2)? {
+            if !target.accepts(block.family_id) {
+                continue;
             }
 
+            log::debug!(
+                "Flashing block: {:#X} -> {:#X}",
+                block.target_addr,
+                block.data.len()
+            );
+
             let address = block.target_addr;
 
             let result = match address & 0xF000_0000 {
                 Bus::XIP => {
                     let address = address & bus::XIP_ADDRESS_MASK;
-                    self.bus.flash.write_slice(address, &block.data)
+                    self.bus.flash.write_slice(address, block.data)
                 }
-                Bus::SRAM => self.bus.sram.write_slice(address - Bus::SRAM, &block.data),
+                Bus::SRAM => self.bus.sram.write_slice(address - Bus::SRAM, block.data),
                 _ => {
                     log::warn!("Unsupported target address: {:#X}", block.target_addr);
                     continue;
@@ -133,6 +353,68 @@ impl Rp2350 {
     }
 
     pub fn tick(&mut self) {
+        self.tick_once();
+
+        let ticks = *self.clock.ticks.borrow();
+        if self.skip_busy_waits && ticks & (busy_wait::CHECK_INTERVAL - 1) == 0 {
+            self.auto_skip_busy_waits();
+        }
+
+        if self.skip_idle {
+            self.skip_idle(MAX_IDLE_TICKS);
+        }
+    }
+
+    /// Tick the whole chip until `core` has executed one instruction and is done with it,
+    /// the stall and bus wait cycles included, so its registers hold the result. The
+    /// other core and the peripherals run alongside. Returns false when the core did
+    /// not get to an instruction within [`STEP_TIMEOUT`] ticks, like while it sleeps,
+    /// or when it is halted by its debug module
+    pub fn step_instruction(&mut self, core: usize) -> bool {
+        if self.processor[core].is_halted() {
+            return false;
+        }
+
+        let retired = self.processor[core].retired();
+
+        for _ in 0..STEP_TIMEOUT {
+            self.tick();
+
+            if self.processor[core].retired() != retired {
+                self.finish_instruction(core);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Tick until `core` completes the instruction in flight, without letting it start
+    /// the next one. A core ends its stall in the same cycle it executes what follows
+    pub fn finish_instruction(&mut self, core: usize) {
+        self.processor[core].set_hold(true);
+
+        for _ in 0..STEP_TIMEOUT {
+            if !self.processor[core].is_busy() {
+                break;
+            }
+
+            self.tick();
+        }
+
+        self.processor[core].set_hold(false);
+        self.processor[core].flush_bypass();
+    }
+
+    fn tick_once(&mut self) {
+        if !self.devices.is_empty() {
+            self.tick_devices();
+        }
+
+        if !self.run {
+            return;
+        }
+
         self.clock.tick();
         self.bus.tick();
 
@@ -144,30 +426,155 @@ impl Rp2350 {
         };
 
         self.inspector.emit(InspectionEvent::TickCore(0));
-        self.processor[0].tick(&mut ctx);
+        tick_core(0, &mut self.processor[0], &mut self.slept[0], &mut ctx);
 
         let wake_core_1 = ctx.wake_opposite_core;
         ctx.wake_opposite_core = false;
 
         self.inspector.emit(InspectionEvent::TickCore(1));
-        self.processor[1].tick(&mut ctx);
+        tick_core(1, &mut self.processor[1], &mut self.slept[1], &mut ctx);
         let wake_core_0 = ctx.wake_opposite_core;
 
         self.dma.borrow_mut().tick(&mut self.bus);
+        self.bus.peripherals.tick();
+
+        if self.bus.peripherals.glitch_detector.take_reset() {
+            self.glitch_reset();
+            return;
+        }
 
         // only wake after both cores have ticked
         if wake_core_1 {
             self.inspector.emit(InspectionEvent::WakeCore(1));
+            self.interrupts.borrow_mut().wake(1);
             self.processor[1].wake();
         }
 
         if wake_core_0 {
             self.inspector.emit(InspectionEvent::WakeCore(0));
+            self.interrupts.borrow_mut().wake(0);
             self.processor[0].wake();
         }
     }
 
+    pub fn snapshot(&self) -> ProgramSnapshot {
+        ProgramSnapshot {
+            ticks: *self.clock.ticks.borrow(),
+            peripheral_accesses: self.bus.peripheral_accesses(),
+            cores: [self.processor[0].snapshot(), self.processor[1].snapshot()],
+            sram: self.bus.sram.clone(),
+            bootram: self.bus.peripherals.bootram.data.clone(),
+            watchdog_scratch: self.bus.peripherals.watch_dog.scratch,
+        }
+    }
+
+    /// Nothing accessed the peripherals since the snapshot, they are still in the state
+    /// the program left them
+    pub fn can_restore(&self, snapshot: &ProgramSnapshot) -> bool {
+        self.bus.peripheral_accesses() == snapshot.peripheral_accesses
+    }
+
+    /// Restore the memories and cores from a snapshot, refused once a peripheral was
+    /// accessed since, see [`Rp2350::can_restore`].
+    /// The clock keeps counting from the current tick, so scheduled peripheral events are not disturbed
+    pub fn restore(&mut self, snapshot: &ProgramSnapshot) -> Result<()> {
+        if !self.can_restore(snapshot) {
+            return Err(Error::StaleSnapshot);
+        }
+
+        self.bus.sram = snapshot.sram.clone();
+        self.bus.peripherals.bootram.data = snapshot.bootram.clone();
+        self.bus.peripherals.watch_dog.scratch = snapshot.watchdog_scratch;
+        self.bus.cancel_core_transactions();
+        self.processor[0].restore(&snapshot.cores[0]);
+        self.processor[1].restore(&snapshot.cores[1]);
+
+        // the cores restored in WFI go back to sleep on their own
+        let mut interrupts = self.interrupts.borrow_mut();
+        interrupts.wake(0);
+        interrupts.wake(1);
+        self.slept = [0; 2];
+        Ok(())
+    }
+
+    /// Shared conditions around the chip, what the temperature sensor and the ROSC depend on
+    pub fn environment(&self) -> Rc<RefCell<Environment>> {
+        Rc::clone(&self.bus.peripherals.environment)
+    }
+
+    pub fn set_ambient_temperature(&mut self, temperature: f64) {
+        let mut environment = self.bus.peripherals.environment.borrow_mut();
+        environment.ambient_temperature = temperature;
+    }
+
+    /// Start a temperature scenario from the current simulated time
+    pub fn run_thermal_scenario(&mut self, scenario: ThermalScenario) {
+        let environment = &self.bus.peripherals.environment;
+        environment.borrow_mut().run_scenario(scenario, &self.clock);
+    }
+
+    /// Die temperature at the current simulated time
+    pub fn temperature(&self) -> f64 {
+        let environment = self.bus.peripherals.environment.borrow();
+        environment.temperature(&self.clock)
+    }
+
+    /// Drive one of the ADC pins (GPIO26 to GPIO29) with a voltage
+    pub fn set_adc_input(&mut self, channel: usize, voltage: f64) -> Result<()> {
+        let mut adc = self.bus.peripherals.adc.borrow_mut();
+        let input = adc
+            .inputs
+            .get_mut(channel)
+            .ok_or(ConfigError::InvalidAdcChannel(channel))?;
+
+        *input = voltage;
+        Ok(())
+    }
+
+    /// Write a peripheral register, or a word of the memory, right away from outside of
+    /// the firmware, to try a peripheral out before writing any code for it
+    pub fn write_register(&mut self, address: u32, value: u32) -> Result<()> {
+        self.bus
+            .debug_write(address, value)
+            .map_err(|error| Error::from_bus(address, Requestor::Proc0, error))
+    }
+
+    /// Read a peripheral register, or a word of the memory, like a debugger does.
+    /// The reads of a peripheral keep their side effects, e.g. popping a FIFO
+    pub fn read_register(&mut self, address: u32) -> Result<u32> {
+        self.bus
+            .debug_read(address)
+            .map_err(|error| Error::from_bus(address, Requestor::Proc0, error))
+    }
+
+    /// Apply the writes of a register preset in order, stopping at the first one failing
+    pub fn apply_register_writes(&mut self, writes: &[(u32, u32)]) -> Result<()> {
+        writes
+            .iter()
+            .try_for_each(|&(address, value)| self.write_register(address, value))
+    }
+
+    pub fn bus_statistics(&self) -> &BusStatistics {
+        &self.bus.statistics
+    }
+
+    pub fn reset_bus_statistics(&mut self) {
+        self.bus.reset_statistics();
+    }
+
+    /// DMA throughput in bytes per millisecond of simulated time since the last reset of the statistics
+    pub fn dma_throughput(&self) -> f64 {
+        self.bus.statistics.dma_bytes_per_ms(self.clock.clk_sys())
+    }
+
     pub fn skip_bootrom(&mut self) {
+        if let Rp2350Core::Arm(core) = &mut self.processor[0] {
+            // Arm images start with their vector table at the beginning of the flash
+            core.boot_from(Bus::XIP);
+            self.processor[1].sleep();
+            return;
+        }
+
         self.processor[0].set_pc(0x1000_0086);
         self.processor[1].set_pc(0x1000_0086);
         self.bus.sram.write_u32(0x0002c44, 0x20002c54).ok();
@@ -197,27 +604,215 @@ impl Rp2350 {
         self.processor[1].sleep();
     }
 
-    pub fn set_gpio_pin_input(&self, pin_index: u8, value: bool) {
-        assert!(pin_index < 30, "Invalid GPIO pin index: {}", pin_index);
-        let mut gpio = self.gpio.borrow_mut();
+    /// Drive the RUN pin. Pulling it low holds the chip in reset,
+    /// releasing it starts the chip from the bootrom again.
+    pub fn set_run_pin(&mut self, high: bool) {
+        if high && !self.run {
+            self.external_reset();
+        }
 
-        if let Some(pin) = gpio.get_pin_mut(pin_index) {
-            let irq_check = pin.set_input(value);
-            if irq_check {
-                gpio.update_interrupt();
-
-                // update for PWM
-                drop(gpio); // avoid deadlock
-                let clock = self.clock.clone();
-                let gpio = self.gpio.clone();
-                let pwm = self.bus.peripherals.pwm.clone();
-                let interrupts = self.interrupts.clone();
-                let inspector = self.inspector.clone();
-
-                crate::gpio::update_pwm_b_pin(
-                    pin_index, value, pwm, clock, gpio, interrupts, inspector,
-                );
-            }
+        self.run = high;
+    }
+
+    pub fn is_held_in_reset(&self) -> bool {
+        !self.run
+    }
+
+    /// Press or release the BOOTSEL button, it is sampled by the bootrom on boot
+    pub fn set_bootsel(&mut self, pressed: bool) {
+        self.gpio.borrow_mut().set_bootsel(pressed);
+    }
+
+    pub fn is_bootsel_pressed(&self) -> bool {
+        self.gpio.borrow().is_bootsel_pressed()
+    }
+
+    /// Cross-connect or loop back the UARTs, so they can talk without an external device
+    pub fn set_uart_link(&mut self, link: UartLink) {
+        self.gpio.borrow_mut().set_uart_link(link);
+    }
+
+    /// A character arriving on the RX line of a UART, stored in its receive FIFO right away.
+    /// Returns false when there is no such UART or its receiver is disabled, the character
+    /// is lost then. [`crate::device::SerialTerminal`] paces them at the baud rate
+    pub fn uart_receive(&self, uart: u8, data: u8) -> bool {
+        let peripherals = &self.bus.peripherals;
+        let (interrupts, inspector) = (&self.interrupts, &self.inspector);
+
+        match uart {
+            0 => uart::receive_character(&peripherals.uart0, data, interrupts, inspector),
+            1 => uart::receive_character(&peripherals.uart1, data, interrupts, inspector),
+            _ => false,
         }
     }
+
+    /// Put a device on the bus of I2C0 or I2C1 at its 7 bit address, it answers the
+    /// transfers of the firmware and stays on the bus through the resets of the chip
+    pub fn attach_i2c_device(&mut self, i2c: u8, address: u8, device: I2cDeviceRef) -> Result<()> {
+        let (i2c0, i2c1) = (&self.bus.peripherals.i2c0, &self.bus.peripherals.i2c1);
+
+        match i2c {
+            0 => i2c0.borrow_mut().attach_device(address, device)?,
+            1 => i2c1.borrow_mut().attach_device(address, device)?,
+            _ => return Err(ConfigError::InvalidI2c(i2c).into()),
+        }
+
+        Ok(())
+    }
+
+    pub fn detach_i2c_device(&mut self, i2c: u8, address: u8) -> Option<I2cDeviceRef> {
+        let (i2c0, i2c1) = (&self.bus.peripherals.i2c0, &self.bus.peripherals.i2c1);
+
+        match i2c {
+            0 => i2c0.borrow_mut().detach_device(address),
+            1 => i2c1.borrow_mut().detach_device(address),
+            _ => None,
+        }
+    }
+
+    /// Put a device on the bus of SPI0 or SPI1, selected while the firmware drives the
+    /// GPIO of its chip select low, by the SIO or by the CSn function of the block
+    pub fn attach_spi_device(&mut self, spi: u8, cs: u8, device: SpiDeviceRef) -> Result<()> {
+        self.gpio.borrow_mut().attach_spi_device(spi, cs, device)?;
+        Ok(())
+    }
+
+    pub fn detach_spi_device(&mut self, spi: u8, cs: u8) -> Option<SpiDeviceRef> {
+        self.gpio.borrow_mut().detach_spi_device(spi, cs)
+    }
+
+    pub fn set_gpio_pin_input(&self, pin_index: u8, value: bool) -> Result<()> {
+        let mut gpio = self.gpio.borrow_mut();
+        let pin = gpio
+            .get_pin_mut(pin_index)
+            .ok_or(ConfigError::InvalidGpioPin(pin_index))?;
+
+        if pin.set_input(value) {
+            gpio.update_interrupt();
+
+            // update for PWM
+            drop(gpio); // avoid deadlock
+            let clock = self.clock.clone();
+            let gpio = self.gpio.clone();
+            let pwm = self.bus.peripherals.pwm.clone();
+            let interrupts = self.interrupts.clone();
+            let inspector = self.inspector.clone();
+
+            crate::gpio::update_pwm_b_pin(pin_index, value, pwm, clock, gpio, interrupts, inspector);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{register, setup};
+
+    const START: u32 = 0x2000_0000;
+
+    #[test]
+    fn test_step_instruction() {
+        let program = [
+            0x0000_0617u32, // auipc a2, 0
+            0x3e80_0513,    // li a0, 1000
+            0x0006_2583,    // lw a1, 0(a2)
+            0x0015_0513,    // addi a0, a0, 1
+            0x0000_006f,    // j .
+        ];
+        let mut mcu = setup(&program);
+
+        assert!(mcu.step_instruction(0));
+        assert_eq!(mcu.processor[0].get_pc(), START + 4);
+        assert_eq!(register(&mcu, 12), START);
+
+        assert!(mcu.step_instruction(0));
+        assert_eq!(register(&mcu, 10), 1000);
+
+        // the load is done once the step returns, and the addi after it has not run
+        let retired = mcu.processor[0].retired();
+        assert!(mcu.step_instruction(0));
+        assert!(!mcu.processor[0].is_busy());
+        assert_eq!(register(&mcu, 11), program[0]);
+        assert_eq!(register(&mcu, 10), 1000);
+        assert_eq!(mcu.processor[0].retired(), retired + 1);
+        assert_eq!(mcu.processor[0].get_pc(), START + 12);
+
+        assert!(mcu.step_instruction(0));
+        assert_eq!(register(&mcu, 10), 1001);
+        assert!(mcu.step_instruction(0));
+        assert_eq!(mcu.processor[0].get_pc(), START + 16);
+    }
+
+    #[test]
+    fn test_glitch_reset() {
+        let mut mcu = Rp2350::builder().build().unwrap();
+        mcu.bus.peripherals.glitch_detector.arm = 0;
+        mcu.bus.peripherals.glitch_detector.trigger(0b10);
+        mcu.tick();
+
+        let detector = &mcu.bus.peripherals.glitch_detector;
+        assert!(!detector.is_armed());
+        assert_eq!(detector.trig_status, 0b10);
+
+        let chip_reset = mcu.bus.peripherals.powman.borrow().chip_reset;
+        assert_eq!(chip_reset, powman::CHIP_RESET_HAD_GLITCH_DETECT);
+
+        mcu.power_cycle();
+        assert_eq!(mcu.bus.peripherals.glitch_detector.trig_status, 0);
+    }
+
+    #[test]
+    fn test_clock_divider() {
+        use crate::peripherals::clocks::CLK_SYS_DIV;
+
+        let mut mcu = Rp2350::builder().build().unwrap();
+        let clk_sys_div = 0x4001_0000 + CLK_SYS_DIV as u32;
+
+        // clk_peri runs from clk_sys, clk_usb from PLL_USB
+        mcu.write_register(clk_sys_div, 2 << 16).unwrap();
+        assert_eq!(mcu.clock.clk_sys(), 75_000_000);
+        assert_eq!(mcu.clock.clk_peri(), 75_000_000);
+        assert_eq!(mcu.clock.clk_usb(), 48_000_000);
+
+        mcu.power_cycle();
+        assert_eq!(mcu.clock.clk_sys(), 150_000_000);
+    }
+
+    #[test]
+    fn test_register_errors() {
+        use crate::peripherals::PeripheralError;
+
+        let mut mcu = Rp2350::builder().build().unwrap();
+        let reserved = 0x4016_0004; // TBMAN only has PLATFORM
+        let error = Error::Peripheral {
+            address: reserved,
+            requestor: Requestor::Proc0,
+            source: PeripheralError::OutOfBounds,
+        };
+
+        assert_eq!(mcu.write_register(reserved, 0), Err(error));
+        assert_eq!(mcu.read_register(reserved), Err(error));
+        assert!(matches!(
+            mcu.read_register(0x3000_0000),
+            Err(Error::Bus { .. })
+        ));
+    }
+
+    #[test]
+    fn test_restore_needs_untouched_peripherals() {
+        let mut mcu = Rp2350::builder().build().unwrap();
+        let snapshot = mcu.snapshot();
+
+        mcu.write_register(0x2000_0000, 0xDEAD_BEEF).unwrap();
+        assert_eq!(mcu.restore(&snapshot), Ok(()));
+        assert_eq!(mcu.read_register(0x2000_0000), Ok(0));
+
+        // the snapshot cannot undo the change of the clocks
+        let clk_sys_div = 0x4001_0000 + crate::peripherals::clocks::CLK_SYS_DIV as u32;
+        mcu.write_register(clk_sys_div, 2 << 16).unwrap();
+        assert!(!mcu.can_restore(&snapshot));
+        assert_eq!(mcu.restore(&snapshot), Err(Error::StaleSnapshot));
+    }
 }
@@ -0,0 +1,360 @@
+/**
+ * @file rp2350/builder.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Builder to configure and wire a ready to run Rp2350
+ */
+use super::Rp2350;
+use crate::common::ArchitectureType;
+use crate::device::VirtualDevice;
+use crate::ihex::IntelHex;
+use crate::inspector::{Inspector, InspectorGroup, InspectorRef};
+use crate::memory::InitPattern;
+use crate::peripherals::{I2cDeviceRef, OtpData, SpiDeviceRef, SysInfo, UartLink};
+use crate::processor::InterruptLatency;
+use crate::simulator::Pico2;
+use crate::Result;
+use std::rc::Rc;
+
+/// Program image loaded into the memory while building the machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Image {
+    /// Raw binary written at the beginning of the flash
+    Bin(Vec<u8>),
+    Uf2(Vec<u8>),
+    IntelHex(String),
+    /// Raw data at an absolute address in the flash, the SRAM or the boot RAM
+    Raw {
+        address: u32,
+        data: Vec<u8>,
+    },
+}
+
+/// Configure a machine step by step, then build it ready to run
+///
+/// Images are loaded in the order they were added, after the configuration is applied.
+#[derive(Default)]
+pub struct Rp2350Builder {
+    architecture: Option<ArchitectureType>,
+    sram_init: Option<InitPattern>,
+    track_uninitialized_reads: bool,
+    ambient_temperature: Option<f64>,
+    clk_sys: Option<u64>,
+    bootsel: bool,
+    uart_link: UartLink,
+    skip_bootrom: bool,
+    skip_busy_waits: bool,
+    skip_idle: bool,
+    sysinfo: Option<SysInfo>,
+    otp: Option<OtpData>,
+    report_simulation: Option<bool>,
+    strict_csrs: Option<bool>,
+    interrupt_latency: Option<InterruptLatency>,
+    inspectors: Vec<Rc<dyn Inspector>>,
+    images: Vec<Image>,
+    devices: Vec<Box<dyn VirtualDevice>>,
+    i2c_devices: Vec<(u8, u8, I2cDeviceRef)>,
+    spi_devices: Vec<(u8, u8, SpiDeviceRef)>,
+}
+
+impl Rp2350Builder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Architecture of both cores, selected through ARCHSEL
+    pub fn architecture(mut self, architecture: ArchitectureType) -> Self {
+        self.architecture = Some(architecture);
+        self
+    }
+
+    /// Content of the SRAM on power up and after every reset
+    pub fn sram_init(mut self, pattern: InitPattern) -> Self {
+        self.sram_init = Some(pattern);
+        self
+    }
+
+    pub fn track_uninitialized_reads(mut self, enable: bool) -> Self {
+        self.track_uninitialized_reads = enable;
+        self
+    }
+
+    pub fn ambient_temperature(mut self, temperature: f64) -> Self {
+        self.ambient_temperature = Some(temperature);
+        self
+    }
+
+    /// Frequency of the system clock in Hz, 150MHz by default
+    pub fn clk_sys(mut self, frequency: u64) -> Self {
+        self.clk_sys = Some(frequency);
+        self
+    }
+
+    /// Hold the BOOTSEL button while the chip boots
+    pub fn bootsel(mut self, pressed: bool) -> Self {
+        self.bootsel = pressed;
+        self
+    }
+
+    /// Wire the UARTs to each other, see [`UartLink`]
+    pub fn uart_link(mut self, link: UartLink) -> Self {
+        self.uart_link = link;
+        self
+    }
+
+    /// Jump straight into the flashed program instead of running the bootrom
+    pub fn skip_bootrom(mut self, skip: bool) -> Self {
+        self.skip_bootrom = skip;
+        self
+    }
+
+    /// Fast-forward the delay loops of the program, see [`Rp2350::skip_busy_wait`]
+    pub fn skip_busy_waits(mut self, skip: bool) -> Self {
+        self.skip_busy_waits = skip;
+        self
+    }
+
+    /// Fast-forward the time both cores sleep through, see [`Rp2350::skip_idle`]
+    pub fn skip_idle(mut self, skip: bool) -> Self {
+        self.skip_idle = skip;
+        self
+    }
+
+    /// Chip revision, package and platform reported by SYSINFO
+    pub fn sysinfo(mut self, sysinfo: SysInfo) -> Self {
+        self.sysinfo = Some(sysinfo);
+        self
+    }
+
+    /// Rows programmed in the OTP, like the chip ID, the boot flags or a white label
+    pub fn otp(mut self, otp: OtpData) -> Self {
+        self.otp = Some(otp);
+        self
+    }
+
+    /// Whether TBMAN tells the firmware it runs in a simulation, see [`crate::peripherals::Tbman`]
+    pub fn report_simulation(mut self, report: bool) -> Self {
+        self.report_simulation = Some(report);
+        self
+    }
+
+    /// Strict or lax CSR accesses of the Hazard3, see [`Rp2350::set_strict_csrs`]
+    pub fn strict_csrs(mut self, strict: bool) -> Self {
+        self.strict_csrs = Some(strict);
+        self
+    }
+
+    /// Extra cycles of the interrupt entry and exit, see [`Rp2350::set_interrupt_latency`]
+    pub fn interrupt_latency(mut self, latency: InterruptLatency) -> Self {
+        self.interrupt_latency = Some(latency);
+        self
+    }
+
+    /// Add a sink for the inspection events, every sink receives all of the events
+    pub fn inspector(mut self, inspector: Rc<dyn Inspector>) -> Self {
+        self.inspectors.push(inspector);
+        self
+    }
+
+    pub fn image(mut self, image: Image) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    pub fn uf2(self, uf2: impl Into<Vec<u8>>) -> Self {
+        self.image(Image::Uf2(uf2.into()))
+    }
+
+    pub fn bin(self, bin: impl Into<Vec<u8>>) -> Self {
+        self.image(Image::Bin(bin.into()))
+    }
+
+    pub fn hex(self, hex: impl Into<String>) -> Self {
+        self.image(Image::IntelHex(hex.into()))
+    }
+
+    pub fn load_at(self, address: u32, data: impl Into<Vec<u8>>) -> Self {
+        self.image(Image::Raw {
+            address,
+            data: data.into(),
+        })
+    }
+
+    /// Wire a virtual device to the chip
+    pub fn device(mut self, device: impl VirtualDevice + 'static) -> Self {
+        self.devices.push(Box::new(device));
+        self
+    }
+
+    /// Put a device on the bus of I2C0 or I2C1 at its 7 bit address
+    pub fn i2c_device(mut self, i2c: u8, address: u8, device: I2cDeviceRef) -> Self {
+        self.i2c_devices.push((i2c, address, device));
+        self
+    }
+
+    /// Put a device on the bus of SPI0 or SPI1 with the GPIO of its chip select
+    pub fn spi_device(mut self, spi: u8, cs: u8, device: SpiDeviceRef) -> Self {
+        self.spi_devices.push((spi, cs, device));
+        self
+    }
+
+    fn inspector_ref(&mut self) -> InspectorRef {
+        let mut inspector = InspectorRef::default();
+        let mut sinks = core::mem::take(&mut self.inspectors);
+
+        match sinks.len() {
+            0 => {}
+            // no need to go through a group for a single sink
+            1 => inspector.set_inspector(sinks.remove(0)),
+            _ => inspector.set_inspector(Rc::new(sinks.into_iter().collect::<InspectorGroup>())),
+        }
+
+        inspector
+    }
+
+    pub fn build(mut self) -> Result<Rp2350> {
+        let mut mcu = Rp2350::with_inspector(self.inspector_ref());
+
+        if let Some(architecture) = self.architecture {
+            mcu.select_architecture(architecture);
+            mcu.reset();
+        }
+
+        if let Some(pattern) = self.sram_init {
+            mcu.bus.set_sram_init(pattern);
+        }
+
+        mcu.bus
+            .track_uninitialized_reads(self.track_uninitialized_reads);
+
+        if let Some(temperature) = self.ambient_temperature {
+            mcu.set_ambient_temperature(temperature);
+        }
+
+        if let Some(frequency) = self.clk_sys {
+            mcu.clock.set_clk_sys(frequency);
+        }
+
+        mcu.set_bootsel(self.bootsel);
+        mcu.set_uart_link(self.uart_link);
+        mcu.set_skip_busy_waits(self.skip_busy_waits);
+        mcu.set_skip_idle(self.skip_idle);
+
+        if let Some(sysinfo) = self.sysinfo {
+            mcu.bus.peripherals.sysinfo = sysinfo;
+        }
+
+        if let Some(otp) = self.otp {
+            mcu.bus.peripherals.otp_data = otp;
+            mcu.bus.peripherals.latch_otp();
+            // the critical flags may disable the architecture the cores started with
+            mcu.reset_cores();
+        }
+
+        if let Some(report) = self.report_simulation {
+            mcu.bus.peripherals.tbman.report_simulation = report;
+        }
+
+        if let Some(strict) = self.strict_csrs {
+            mcu.set_strict_csrs(strict);
+        }
+
+        if let Some(latency) = self.interrupt_latency {
+            mcu.set_interrupt_latency(latency);
+        }
+
+        for image in self.images.iter() {
+            match image {
+                Image::Bin(bin) => mcu.flash_bin(bin)?,
+                Image::Uf2(uf2) => mcu.flash_uf2(uf2)?,
+                Image::IntelHex(hex) => {
+                    for segment in IntelHex::parse(hex)?.segments.iter() {
+                        mcu.load_into_memory(segment.address, &segment.data)?;
+                    }
+                }
+                Image::Raw { address, data } => mcu.load_into_memory(*address, data)?,
+            }
+        }
+
+        if self.skip_bootrom {
+            mcu.skip_bootrom();
+        }
+
+        for device in self.devices {
+            mcu.attach_device(device);
+        }
+
+        for (i2c, address, device) in self.i2c_devices {
+            mcu.attach_i2c_device(i2c, address, device)?;
+        }
+
+        for (spi, cs, device) in self.spi_devices {
+            mcu.attach_spi_device(spi, cs, device)?;
+        }
+
+        Ok(mcu)
+    }
+
+    /// Build the machine wrapped in a Pico 2 board
+    pub fn build_pico2(self) -> Result<Pico2> {
+        let is_flashed = !self.images.is_empty();
+
+        Ok(Pico2 {
+            mcu: self.build()?,
+            is_flashed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct Counter(Rc<Cell<u32>>);
+
+    impl VirtualDevice for Counter {
+        fn tick(&mut self, _mcu: &mut Rp2350) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let ticks = Rc::new(Cell::new(0));
+        let mut mcu = Rp2350::builder()
+            .architecture(ArchitectureType::CortexM33)
+            .load_at(0x2000_0000, [0xde, 0xad, 0xbe, 0xef])
+            .device(Counter(Rc::clone(&ticks)))
+            .build()
+            .unwrap();
+
+        assert_eq!(mcu.processor[0].architecture(), ArchitectureType::CortexM33);
+        assert_eq!(mcu.bus.sram.read_u32(0), Ok(0xefbeadde));
+
+        mcu.tick();
+        mcu.tick();
+        assert_eq!(ticks.get(), 2);
+    }
+
+    #[test]
+    fn test_build_otp() {
+        let mut otp = OtpData::default();
+        otp.set_critical(crate::peripherals::otp::CRITICAL_RISCV_DISABLE);
+
+        let mcu = Rp2350::builder()
+            .architecture(ArchitectureType::Hazard3)
+            .otp(otp)
+            .build()
+            .unwrap();
+
+        assert_eq!(mcu.processor[0].architecture(), ArchitectureType::CortexM33);
+        assert_eq!(mcu.selected_architecture(), ArchitectureType::CortexM33);
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_image() {
+        let result = Rp2350::builder().load_at(0x4000_0000, [0; 4]).build();
+        assert!(result.is_err());
+    }
+}
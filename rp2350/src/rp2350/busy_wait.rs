@@ -0,0 +1,261 @@
+/**
+ * @file rp2350/busy_wait.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Fast-forwarding the delay loops the cores are spinning in
+ */
+use super::Rp2350;
+use crate::inspector::InspectionEvent;
+use crate::processor::hazard3::busy_wait::{self, DelayLoop, Plan};
+use crate::processor::hazard3::State as Hazard3State;
+use crate::processor::Rp2350Core;
+use std::rc::Rc;
+
+/// Ticks between two looks for a delay loop when they are skipped automatically,
+/// a power of two
+pub const CHECK_INTERVAL: u64 = 1024;
+
+/// Ticks allowed to reach the start of the loop, then to run one iteration of it
+const MAX_ITERATION_TICKS: usize = 256;
+
+/// A delay loop which was fast-forwarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkippedWait {
+    pub core: usize,
+    pub delay: DelayLoop,
+    pub iterations: u64,
+    pub cycles: u64,
+}
+
+impl Rp2350 {
+    /// The delay loop the core is spinning in, only the loops of the Hazard3 are recognised
+    pub fn busy_wait(&self, core: usize) -> Option<DelayLoop> {
+        let Rp2350Core::RiscV(hazard3) = self.processor.get(core)? else {
+            return None;
+        };
+
+        busy_wait::detect(hazard3, &self.bus, self.timer_values())
+    }
+
+    pub fn set_skip_busy_waits(&mut self, enable: bool) {
+        self.skip_busy_waits = enable;
+    }
+
+    pub fn skips_busy_waits(&self) -> bool {
+        self.skip_busy_waits
+    }
+
+    /// Fast-forward the delay loop the core is spinning in, its last iteration is left
+    /// to run normally.
+    ///
+    /// Time is skipped for the whole chip, which is refused while anything else is
+    /// running: the other core awake, a busy DMA channel, an enabled PIO, an attached
+    /// device... The skip stops early once an interrupt becomes pending.
+    pub fn skip_busy_wait(&mut self, core: usize) -> Option<SkippedWait> {
+        let delay = self.busy_wait(core)?;
+
+        if !self.can_skip_time(core) {
+            return None;
+        }
+
+        // one iteration is run for real to know its cost in cycles, the stalls of the
+        // pipeline and the branch predictor make it hard to guess
+        self.run_to_loop_start(core, &delay)?;
+        let start = *self.clock.ticks.borrow();
+        self.run_to_loop_start(core, &delay)?;
+        let period = *self.clock.ticks.borrow() - start;
+
+        let Rp2350Core::RiscV(hazard3) = &self.processor[core] else {
+            return None;
+        };
+
+        let plan = busy_wait::plan(hazard3, &self.bus, &delay, self.timer_values())?;
+
+        let interrupts = Rc::clone(&self.interrupts);
+        let pending = [
+            interrupts.borrow().pending(0),
+            interrupts.borrow().pending(1),
+        ];
+        let interrupted = || {
+            let interrupts = interrupts.borrow();
+            interrupts.pending(0) != pending[0] || interrupts.pending(1) != pending[1]
+        };
+
+        let (iterations, cycles) = match plan {
+            Plan::Counter { iterations, .. } => {
+                let cycles = self
+                    .clock
+                    .advance(iterations.saturating_mul(period), interrupted);
+
+                // an interrupted iteration is not applied, the loop runs it again
+                (cycles / period, cycles)
+            }
+            Plan::Timer { timer, delta } => {
+                let from = self.timer_values()[timer];
+
+                if self.timer_paused(timer) {
+                    return None;
+                }
+
+                let cycles = self.clock.advance(u64::MAX, || {
+                    interrupted() || self.timer_values()[timer].wrapping_sub(from) >= delta
+                });
+
+                (cycles / period, cycles)
+            }
+        };
+
+        let other = &mut self.processor[1 - core];
+
+        if let Rp2350Core::RiscV(other) = other {
+            // a core waiting for an interrupt keeps counting its cycles
+            if other.state == Hazard3State::Wfi {
+                other.csrs.skip(cycles, 0);
+            }
+        }
+
+        let instructions = iterations * delay.instructions as u64;
+
+        if let Rp2350Core::RiscV(hazard3) = &mut self.processor[core] {
+            busy_wait::apply(hazard3, &mut self.bus, &plan, iterations);
+            hazard3.csrs.skip(cycles, instructions);
+        }
+
+        self.inspector.emit(InspectionEvent::BusyWaitSkipped {
+            core: core as u8,
+            address: delay.start,
+            iterations,
+            instructions,
+            cycles,
+        });
+
+        Some(SkippedWait {
+            core,
+            delay,
+            iterations,
+            cycles,
+        })
+    }
+
+    pub(super) fn auto_skip_busy_waits(&mut self) {
+        for core in 0..self.processor.len() {
+            if self.busy_wait(core).is_some() {
+                self.skip_busy_wait(core);
+            }
+        }
+    }
+
+    fn can_skip_time(&self, core: usize) -> bool {
+        let other_waiting = match &self.processor[1 - core] {
+            Rp2350Core::RiscV(other) => {
+                matches!(other.state, Hazard3State::Sleep(_) | Hazard3State::Wfi)
+            }
+            Rp2350Core::Arm(_) => false,
+        };
+
+        other_waiting && self.nothing_else_runs()
+    }
+
+    /// Tick until the core arrives at the start of the loop, it has to stay in the
+    /// loop meanwhile
+    fn run_to_loop_start(&mut self, core: usize, delay: &DelayLoop) -> Option<()> {
+        let mut last_pc = self.processor[core].get_pc();
+
+        for _ in 0..MAX_ITERATION_TICKS {
+            self.tick_once();
+
+            let pc = self.processor[core].get_pc();
+
+            if !delay.contains(pc) {
+                return None;
+            }
+
+            if pc == delay.start && last_pc != delay.start {
+                return Some(());
+            }
+
+            last_pc = pc;
+        }
+
+        None
+    }
+
+    fn timer_values(&self) -> [u32; 2] {
+        let peripherals = &self.bus.peripherals;
+
+        [
+            peripherals.timer0.borrow().counter as u32,
+            peripherals.timer1.borrow().counter as u32,
+        ]
+    }
+
+    fn timer_paused(&self, timer: usize) -> bool {
+        let peripherals = &self.bus.peripherals;
+
+        match timer {
+            0 => peripherals.timer0.borrow().is_paused,
+            _ => peripherals.timer1.borrow().is_paused,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::processor::hazard3::busy_wait::DelayKind;
+    use crate::testing::{register, setup};
+
+    const START: u32 = 0x2000_0000;
+
+    #[test]
+    fn test_skip_counter_loop() {
+        let mut mcu = setup(&[
+            0x3e80_0513, // li a0, 1000
+            0xfff5_0513, // addi a0, a0, -1
+            0xfe05_1ee3, // bnez a0, -4
+            0x0010_0593, // li a1, 1
+            0x0000_006f, // j .
+        ]);
+
+        while mcu.processor[0].get_pc() != START + 8 {
+            mcu.tick();
+        }
+
+        let delay = mcu.busy_wait(0).unwrap();
+        assert_eq!(delay.start, START + 4);
+        assert_eq!(delay.end, START + 8);
+        assert_eq!(delay.kind, DelayKind::Counter);
+
+        let skipped = mcu.skip_busy_wait(0).unwrap();
+        assert!(skipped.iterations > 990);
+        assert!(skipped.cycles >= skipped.iterations);
+
+        let mut ticks = 0;
+
+        while register(&mcu, 11) != 1 {
+            mcu.tick();
+            ticks += 1;
+            assert!(ticks < 100);
+        }
+
+        assert_eq!(register(&mcu, 10), 0);
+    }
+
+    #[test]
+    fn test_busy_core_is_not_skipped() {
+        let mut mcu = setup(&[
+            0x3e80_0513, // li a0, 1000
+            0xfff5_0513, // addi a0, a0, -1
+            0xfe05_1ee3, // bnez a0, -4
+            0x0000_006f, // j .
+        ]);
+
+        mcu.processor[1].wake();
+
+        while mcu.processor[0].get_pc() != START + 8 {
+            mcu.tick();
+        }
+
+        assert!(mcu.busy_wait(0).is_some());
+        assert_eq!(mcu.skip_busy_wait(0), None);
+    }
+}
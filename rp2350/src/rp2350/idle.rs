@@ -0,0 +1,144 @@
+/**
+ * @file rp2350/idle.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Fast-forwarding the time both cores sleep through, event by event
+ */
+use super::Rp2350;
+use crate::inspector::InspectionEvent;
+use crate::processor::cortex_m33::State as CortexState;
+use crate::processor::hazard3::State as Hazard3State;
+use crate::processor::Rp2350Core;
+use std::rc::Rc;
+
+/// Ticks skipped at most at once, a sleep with nothing scheduled to end it still lets
+/// the host see the time pass
+pub const MAX_IDLE_TICKS: u64 = 1_000_000;
+
+/// Held asleep until the other core or the PSM wakes it, it does nothing meanwhile
+fn is_held(core: &Rp2350Core) -> bool {
+    match core {
+        Rp2350Core::RiscV(core) => matches!(core.state, Hazard3State::Sleep(_)),
+        Rp2350Core::Arm(core) => matches!(core.state, CortexState::Sleep(_)),
+    }
+}
+
+impl Rp2350 {
+    pub fn set_skip_idle(&mut self, enable: bool) {
+        self.skip_idle = enable;
+    }
+
+    pub fn skips_idle(&self) -> bool {
+        self.skip_idle
+    }
+
+    /// Both cores wait for an interrupt, or are held asleep, and nothing else runs on
+    /// every tick, so only the events of the clock can change anything
+    pub fn is_idle(&self) -> bool {
+        let interrupts = self.interrupts.borrow();
+        let waiting = (0..self.processor.len())
+            .all(|core| interrupts.is_asleep(core as u8) || is_held(&self.processor[core]));
+
+        waiting && self.nothing_else_runs()
+    }
+
+    /// Jump the clock to its next events while the chip is idle, until one of them wakes
+    /// a core waiting for an interrupt or `max_ticks` passed. The cores are told how long
+    /// they slept once they wake up, like when they are skipped tick by tick.
+    /// Returns the ticks skipped, none when the chip is not idle
+    pub fn skip_idle(&mut self, max_ticks: u64) -> Option<u64> {
+        if !self.is_idle() {
+            return None;
+        }
+
+        let interrupts = Rc::clone(&self.interrupts);
+        let asleep = [
+            interrupts.borrow().is_asleep(0),
+            interrupts.borrow().is_asleep(1),
+        ];
+        let woken = || {
+            let interrupts = interrupts.borrow();
+            (asleep[0] && !interrupts.is_asleep(0)) || (asleep[1] && !interrupts.is_asleep(1))
+        };
+
+        let cycles = self.clock.advance(max_ticks, woken);
+
+        for (core, slept) in self.slept.iter_mut().enumerate() {
+            if asleep[core] {
+                *slept += cycles;
+            }
+        }
+
+        self.bus.statistics.ticks += cycles;
+        self.inspector.emit(InspectionEvent::IdleSkipped { cycles });
+
+        Some(cycles)
+    }
+
+    /// Neither the devices, the DMA nor the peripherals ticked on every cycle have work
+    /// to do, and the chip is out of reset
+    pub(super) fn nothing_else_runs(&self) -> bool {
+        let dma_busy = self
+            .dma
+            .borrow()
+            .channels
+            .iter()
+            .any(|channel| channel.busy());
+
+        self.run && self.devices.is_empty() && !dma_busy && self.bus.peripherals.is_idle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::setup;
+
+    #[test]
+    fn test_skip_until_alarm() {
+        let mut mcu = setup(&[
+            0x1050_0073, // wfi
+            0x0000_006f, // j .
+        ]);
+
+        {
+            let mut timer = mcu.bus.peripherals.timer0.borrow_mut();
+            timer.alarm[0].time = timer.counter as u32 + 1000;
+            timer.alarm[0].armed = true;
+        }
+
+        for _ in 0..16 {
+            mcu.tick();
+        }
+
+        assert!(mcu.is_idle());
+        let start = *mcu.clock.ticks.borrow();
+        let skipped = mcu.skip_idle(u64::MAX).unwrap();
+
+        // woken by the alarm, 1000us of the timer later
+        assert_eq!(*mcu.clock.ticks.borrow() - start, skipped);
+        assert!(skipped.abs_diff(1000 * mcu.clock.clk_sys() / 1_000_000) < 200);
+        assert!(mcu.bus.peripherals.timer0.borrow().alarm[0].interrupting);
+        assert!(!mcu.is_idle());
+
+        // the core is told it slept through the skipped ticks
+        assert!(mcu.slept[0] >= skipped);
+    }
+
+    #[test]
+    fn test_skip_is_bounded() {
+        let mut mcu = setup(&[
+            0x1050_0073, // wfi
+            0x0000_006f, // j .
+        ]);
+
+        for _ in 0..16 {
+            mcu.tick();
+        }
+
+        assert_eq!(mcu.skip_idle(5000), Some(5000));
+        assert!(mcu.is_idle());
+
+        mcu.processor[1].wake();
+        assert_eq!(mcu.skip_idle(5000), None);
+    }
+}
@@ -0,0 +1,232 @@
+/**
+ * @file script.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Test harness driving the board from a script, the way a hardware-in-the-loop rig would
+ */
+#[cfg(feature = "scripting")]
+pub mod engine;
+
+use crate::common::MHZ;
+use crate::device::drive_pin;
+use crate::gpio::NUM_GPIO;
+use crate::inspector::{InspectionEvent, Inspector};
+use crate::rp2350::Rp2350;
+use std::cell::RefCell;
+use std::rc::Rc;
+use thiserror::Error;
+
+#[cfg(feature = "scripting")]
+pub use engine::run_script;
+
+/// Ticks run between two searches of the output while waiting for a text
+const EXPECT_INTERVAL: u64 = 1000;
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ScriptError {
+    #[error("There is no GPIO{0}")]
+    InvalidPin(i64),
+
+    #[error("GPIO{pin} is {}, expected {}", level_name(!expected), level_name(*expected))]
+    PinLevel { pin: u8, expected: bool },
+
+    #[error("`{text}` was not printed within {timeout_us} us")]
+    UartTimeout { text: String, timeout_us: u64 },
+
+    #[error(transparent)]
+    Simulator(#[from] crate::SimulatorError),
+
+    /// A failed assertion or an error of the script itself, with its position
+    #[error("{0}")]
+    Script(String),
+}
+
+fn level_name(high: bool) -> &'static str {
+    match high {
+        true => "high",
+        false => "low",
+    }
+}
+
+/// What the firmware prints on the UARTs and the USB serial, to give to the builder
+/// of the chip of a [`Harness`]
+#[derive(Default)]
+pub struct UartCapture(RefCell<Vec<u8>>);
+
+impl Inspector for UartCapture {
+    fn handle_event(&self, event: InspectionEvent) {
+        match event {
+            InspectionEvent::UartTx { value, .. } => self.0.borrow_mut().push(value),
+            InspectionEvent::UsbCdcTx { data } => self.0.borrow_mut().extend(data),
+            _ => {}
+        }
+    }
+}
+
+/// The board as a test script sees it: pins to drive and read, the serial output to
+/// wait for and the time to advance. The time counts from the creation of the harness.
+pub struct Harness {
+    pub mcu: Rp2350,
+    output: Rc<UartCapture>,
+    /// Output already matched by an expectation, the next one searches after it
+    consumed: usize,
+    start: u64,
+}
+
+impl Harness {
+    /// `output` is one of the inspectors the chip was built with
+    pub fn new(mcu: Rp2350, output: Rc<UartCapture>) -> Self {
+        let start = *mcu.clock.ticks.borrow();
+
+        Self {
+            mcu,
+            output,
+            consumed: 0,
+            start,
+        }
+    }
+
+    fn pin(pin: i64) -> Result<u8, ScriptError> {
+        u8::try_from(pin)
+            .ok()
+            .filter(|pin| (*pin as usize) < NUM_GPIO)
+            .ok_or(ScriptError::InvalidPin(pin))
+    }
+
+    /// Drive a GPIO from the outside, like a button or a signal generator
+    pub fn set_pin(&mut self, pin: i64, level: bool) -> Result<(), ScriptError> {
+        drive_pin(&self.mcu, Self::pin(pin)?, level);
+        Ok(())
+    }
+
+    /// Level on the pad, driven by the chip or by the outside
+    pub fn read_pin(&self, pin: i64) -> Result<bool, ScriptError> {
+        Ok(self.mcu.gpio.borrow().pad_level(Self::pin(pin)?))
+    }
+
+    pub fn expect_pin(&self, pin: i64, expected: bool) -> Result<(), ScriptError> {
+        match self.read_pin(pin)? == expected {
+            true => Ok(()),
+            false => Err(ScriptError::PinLevel {
+                pin: pin as u8,
+                expected,
+            }),
+        }
+    }
+
+    fn ticks(&self) -> u64 {
+        *self.mcu.clock.ticks.borrow() - self.start
+    }
+
+    fn ticks_per_us(&self) -> u64 {
+        (self.mcu.clock.clk_sys() / MHZ).max(1)
+    }
+
+    pub fn time_us(&self) -> u64 {
+        self.ticks() / self.ticks_per_us()
+    }
+
+    pub fn advance_cycles(&mut self, cycles: u64) {
+        let end = self.ticks() + cycles;
+
+        // a skipped delay loop moves the clock further than a single tick
+        while self.ticks() < end {
+            self.mcu.tick();
+        }
+    }
+
+    pub fn advance_us(&mut self, us: u64) {
+        self.advance_cycles(us * self.ticks_per_us());
+    }
+
+    /// Everything printed since the start
+    pub fn uart_output(&self) -> String {
+        String::from_utf8_lossy(&self.output.0.borrow()).into_owned()
+    }
+
+    fn find(&self, text: &str) -> Option<usize> {
+        if text.is_empty() {
+            return Some(self.consumed);
+        }
+
+        let output = self.output.0.borrow();
+        let position = output
+            .get(self.consumed..)?
+            .windows(text.len())
+            .position(|window| window == text.as_bytes())?;
+
+        Some(self.consumed + position + text.len())
+    }
+
+    /// Run until the firmware prints `text` after the last text expected, the time
+    /// stops right after it
+    pub fn expect_uart(&mut self, text: &str, timeout_us: u64) -> Result<(), ScriptError> {
+        let end = self.ticks() + timeout_us * self.ticks_per_us();
+
+        loop {
+            if let Some(consumed) = self.find(text) {
+                self.consumed = consumed;
+                return Ok(());
+            }
+
+            if self.ticks() >= end {
+                return Err(ScriptError::UartTimeout {
+                    text: text.to_string(),
+                    timeout_us,
+                });
+            }
+
+            let next = (self.ticks() + EXPECT_INTERVAL).min(end);
+            while self.ticks() < next && self.find(text).is_none() {
+                self.mcu.tick();
+            }
+        }
+    }
+
+    pub fn read_register(&mut self, address: u32) -> Result<u32, ScriptError> {
+        Ok(self.mcu.read_register(address)?)
+    }
+
+    pub fn write_register(&mut self, address: u32, value: u32) -> Result<(), ScriptError> {
+        Ok(self.mcu.write_register(address, value)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn harness() -> Harness {
+        let output = Rc::new(UartCapture::default());
+        let mcu = Rp2350::builder()
+            .inspector(Rc::clone(&output) as Rc<dyn Inspector>)
+            .build()
+            .unwrap();
+
+        Harness::new(mcu, output)
+    }
+
+    #[test]
+    fn test_harness() {
+        let mut harness = harness();
+
+        harness.set_pin(3, true).unwrap();
+        assert_eq!(harness.read_pin(3), Ok(true));
+        assert!(harness.expect_pin(3, false).is_err());
+        assert_eq!(harness.set_pin(48, true), Err(ScriptError::InvalidPin(48)));
+        assert_eq!(harness.read_pin(-1), Err(ScriptError::InvalidPin(-1)));
+
+        harness.advance_us(20);
+        assert_eq!(harness.time_us(), 20);
+
+        // nothing runs which could print it
+        let error = harness.expect_uart("PASS", 10).unwrap_err();
+        assert_eq!(error.to_string(), "`PASS` was not printed within 10 us");
+        assert_eq!(harness.time_us(), 30);
+
+        harness.output.0.borrow_mut().extend(b"one two one");
+        assert!(harness.expect_uart("one", 0).is_ok());
+        assert!(harness.expect_uart("one", 0).is_ok());
+        assert!(harness.expect_uart("two", 0).is_err());
+    }
+}
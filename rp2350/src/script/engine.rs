@@ -0,0 +1,174 @@
+/**
+ * @file script/engine.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Rhai bindings of the test harness
+ */
+use super::{Harness, ScriptError};
+use rhai::{Engine, EvalAltResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type RhaiResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Time given to `expect_uart` when the script does not say
+pub const DEFAULT_UART_TIMEOUT_MS: i64 = 1000;
+
+fn rhai_error(error: ScriptError) -> Box<EvalAltResult> {
+    error.to_string().into()
+}
+
+fn duration(value: i64) -> RhaiResult<u64> {
+    u64::try_from(value).map_err(|_| format!("Negative duration {value}").into())
+}
+
+fn address(value: i64) -> RhaiResult<u32> {
+    u32::try_from(value).map_err(|_| format!("Invalid address {value:#x}").into())
+}
+
+/// Run a test script against the harness, it fails on the first broken expectation
+/// or `throw`. The functions given to the script:
+///
+/// ```text
+/// set_pin(pin, level)              drive a GPIO from the outside, level is true for high
+/// read_pin(pin) -> bool
+/// expect_pin(pin, level)
+/// advance_us(us), advance_ms(ms), advance_cycles(cycles)
+/// time_us() -> int                 simulated time since the start of the script
+/// expect_uart(text)                run until the text is printed, 1s at most
+/// expect_uart(text, timeout_ms)
+/// uart_output() -> string          everything printed so far
+/// read_register(address) -> int
+/// write_register(address, value)
+/// ```
+pub fn run_script(harness: &Rc<RefCell<Harness>>, script: &str) -> Result<(), ScriptError> {
+    let mut engine = Engine::new();
+
+    let h = Rc::clone(harness);
+    engine.register_fn("set_pin", move |pin: i64, level: bool| -> RhaiResult<()> {
+        h.borrow_mut().set_pin(pin, level).map_err(rhai_error)
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn("read_pin", move |pin: i64| -> RhaiResult<bool> {
+        h.borrow().read_pin(pin).map_err(rhai_error)
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn(
+        "expect_pin",
+        move |pin: i64, level: bool| -> RhaiResult<()> {
+            h.borrow().expect_pin(pin, level).map_err(rhai_error)
+        },
+    );
+
+    let h = Rc::clone(harness);
+    engine.register_fn("advance_us", move |us: i64| -> RhaiResult<()> {
+        h.borrow_mut().advance_us(duration(us)?);
+        Ok(())
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn("advance_ms", move |ms: i64| -> RhaiResult<()> {
+        h.borrow_mut().advance_us(duration(ms)? * 1000);
+        Ok(())
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn("advance_cycles", move |cycles: i64| -> RhaiResult<()> {
+        h.borrow_mut().advance_cycles(duration(cycles)?);
+        Ok(())
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn("time_us", move || h.borrow().time_us() as i64);
+
+    let h = Rc::clone(harness);
+    engine.register_fn("uart_output", move || h.borrow().uart_output());
+
+    let h = Rc::clone(harness);
+    engine.register_fn("expect_uart", move |text: &str| -> RhaiResult<()> {
+        let timeout = DEFAULT_UART_TIMEOUT_MS as u64 * 1000;
+        h.borrow_mut()
+            .expect_uart(text, timeout)
+            .map_err(rhai_error)
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn(
+        "expect_uart",
+        move |text: &str, timeout_ms: i64| -> RhaiResult<()> {
+            let timeout = duration(timeout_ms)? * 1000;
+            h.borrow_mut()
+                .expect_uart(text, timeout)
+                .map_err(rhai_error)
+        },
+    );
+
+    let h = Rc::clone(harness);
+    engine.register_fn("read_register", move |address: i64| -> RhaiResult<i64> {
+        let value = h.borrow_mut().read_register(self::address(address)?);
+        value.map(i64::from).map_err(rhai_error)
+    });
+
+    let h = Rc::clone(harness);
+    engine.register_fn(
+        "write_register",
+        move |address: i64, value: i64| -> RhaiResult<()> {
+            // the upper bits of a negative value are dropped, -1 writes all ones
+            let address = self::address(address)?;
+            h.borrow_mut()
+                .write_register(address, value as u32)
+                .map_err(rhai_error)
+        },
+    );
+
+    engine
+        .run(script)
+        .map_err(|error| ScriptError::Script(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::UartCapture;
+    use crate::{Inspector, Rp2350};
+
+    fn harness() -> Rc<RefCell<Harness>> {
+        let output = Rc::new(UartCapture::default());
+        let mcu = Rp2350::builder()
+            .inspector(Rc::clone(&output) as Rc<dyn Inspector>)
+            .build()
+            .unwrap();
+
+        Rc::new(RefCell::new(Harness::new(mcu, output)))
+    }
+
+    #[test]
+    fn test_run_script() {
+        let harness = harness();
+
+        let script = r#"
+            set_pin(3, true);
+            expect_pin(3, true);
+            advance_ms(1);
+
+            if time_us() != 1000 {
+                throw "the time did not advance";
+            }
+
+            write_register(0x20000000, 0x1234);
+            if read_register(0x20000000) != 0x1234 {
+                throw "the write did not land";
+            }
+        "#;
+        assert_eq!(run_script(&harness, script), Ok(()));
+
+        let error = run_script(&harness, "expect_uart(\"PASS\", 1);").unwrap_err();
+        assert!(error.to_string().contains("was not printed within 1000 us"));
+
+        let error = run_script(&harness, "set_pin(3, false);\nexpect_pin(3, true);").unwrap_err();
+        assert!(error.to_string().contains("GPIO3 is low, expected high"));
+        assert!(error.to_string().contains("line 2"));
+    }
+}
@@ -5,7 +5,9 @@
  * @brief Wrapper for the RP2350 MCU that represents the Raspberry Pi Pico 2 board.
  */
 use super::Rp2350;
+use crate::bus::Bus;
 use crate::common::*;
+use crate::ihex::IntelHex;
 use core::ops::{Deref, DerefMut};
 
 /// A wrapper of the RP2350 MCU that represents the Raspberry Pi Pico 2 board.
@@ -39,6 +41,33 @@ impl Pico2 {
         }
     }
 
+    /// Load an Intel HEX image, every segment is written at its absolute address.
+    /// Returns the entry point of the image, if specified
+    pub fn load_hex(&mut self, hex: &str) -> crate::Result<Option<u32>> {
+        let image = IntelHex::parse(hex)?;
+
+        for segment in image.segments.iter() {
+            self.mcu.load_into_memory(segment.address, &segment.data)?;
+
+            if segment.address & 0xF000_0000 == Bus::XIP {
+                self.is_flashed = true;
+            }
+        }
+
+        Ok(image.entry)
+    }
+
+    /// Load a raw memory dump at an absolute address
+    pub fn load_bin_at(&mut self, address: u32, data: &[u8]) -> crate::Result<()> {
+        self.mcu.load_into_memory(address, data)?;
+
+        if address & 0xF000_0000 == Bus::XIP {
+            self.is_flashed = true;
+        }
+
+        Ok(())
+    }
+
     pub fn step(&mut self) {
         self.mcu.tick();
     }
@@ -0,0 +1,49 @@
+/**
+ * @file snapshot.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Snapshot of the program to jump back to a previous point of the simulation
+ */
+use crate::common::KB;
+use crate::memory::GenericMemory;
+use crate::processor::CoreSnapshot;
+
+/// State of the program running on the RP2350 at a given tick.
+/// Memories and the architectural state of both cores are captured,
+/// the flash is not since it is not writable by the firmware.
+/// The peripherals and the clock are not either, so it can only be restored
+/// while nothing accessed the peripherals since it was taken.
+#[derive(Clone)]
+pub struct ProgramSnapshot {
+    pub ticks: u64,
+    /// Accesses to the peripherals the bus had counted when the snapshot was taken
+    pub peripheral_accesses: u64,
+    pub cores: [CoreSnapshot; 2],
+    pub(crate) sram: GenericMemory<{ 520 * KB }>,
+    pub(crate) bootram: GenericMemory<{ 256 * 4 }>,
+    pub(crate) watchdog_scratch: [u32; 8],
+}
+
+impl ProgramSnapshot {
+    pub fn pc(&self, core: usize) -> Option<u32> {
+        self.cores.get(core).and_then(CoreSnapshot::pc)
+    }
+
+    pub fn registers(&self, core: usize) -> Option<[u32; 32]> {
+        self.cores.get(core).and_then(CoreSnapshot::registers)
+    }
+
+    pub fn sram(&self) -> &[u8] {
+        &self.sram
+    }
+
+    /// Addresses of the SRAM words which differ between the two snapshots
+    pub fn sram_diff<'a>(&'a self, other: &'a ProgramSnapshot) -> impl Iterator<Item = u32> + 'a {
+        self.sram
+            .chunks_exact(4)
+            .zip(other.sram.chunks_exact(4))
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, _)| crate::bus::Bus::SRAM + (i as u32) * 4)
+    }
+}
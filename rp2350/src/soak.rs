@@ -0,0 +1,467 @@
+/**
+ * @file soak.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Invariants checked over long runs of a firmware, for the bugs which take hours to show
+ */
+use crate::common::{Requestor, MHZ};
+use crate::inspector::{InspectionEvent, Inspector};
+use crate::processor::cortex_m33::nvic::{IRQ_BASE, PEND_SV, SV_CALL, SYS_TICK};
+use crate::rp2350::Rp2350;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Instructions kept in the trace window by default
+pub const DEFAULT_TRACE_WINDOW: usize = 256;
+
+// MCAUSE of the system calls of the Hazard3
+const ECALL_U_MODE: u32 = 0x8;
+const ECALL_M_MODE: u32 = 0x9;
+
+/// LOAD of the watchdog, in any of the atomic aliases
+const WATCHDOG_LOAD: u32 = 0x400D_8004;
+const ALIASES: u32 = 0x3000;
+
+/// The stack pointer of a core stays within `bottom..=top`, once it got there. The
+/// bootrom and a core waiting to be launched run on stacks of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackBounds {
+    pub bottom: u32,
+    pub top: u32,
+}
+
+impl StackBounds {
+    pub fn contains(&self, sp: u32) -> bool {
+        (self.bottom..=self.top).contains(&sp)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoakConfig {
+    /// Traps the firmware takes on purpose, by MCAUSE on the Hazard3 or by exception
+    /// number on the Cortex-M33. The interrupts are always expected
+    pub allowed_traps: Vec<u32>,
+    pub stacks: [Option<StackBounds>; 2],
+    /// Regions nothing may store into, like a red zone past the heap limit
+    pub guards: Vec<Range<u32>>,
+    /// Fail once the enabled watchdog runs out before it is reloaded
+    pub watchdog: bool,
+    pub trace_window: usize,
+}
+
+impl Default for SoakConfig {
+    /// Only the system calls are allowed. The causes of the two architectures do not
+    /// overlap, the same list serves both
+    fn default() -> Self {
+        Self {
+            allowed_traps: vec![
+                ECALL_U_MODE,
+                ECALL_M_MODE,
+                SV_CALL as u32,
+                PEND_SV as u32,
+                SYS_TICK as u32,
+            ],
+            stacks: [None; 2],
+            guards: Vec::new(),
+            watchdog: true,
+            trace_window: DEFAULT_TRACE_WINDOW,
+        }
+    }
+}
+
+/// An invariant which no longer holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    UnexpectedTrap {
+        core: u8,
+        exception: u32,
+    },
+    StackOutOfBounds {
+        core: u8,
+        sp: u32,
+        bounds: StackBounds,
+    },
+    GuardWrite {
+        requestor: Requestor,
+        address: u32,
+        value: u32,
+    },
+    /// The watchdog would have reset the chip, `load` is its timeout in microseconds
+    WatchdogStarved {
+        last_fed: u64,
+        load: u32,
+    },
+}
+
+impl core::fmt::Display for Violation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedTrap { core, exception } => {
+                write!(f, "Core {core} took the unexpected trap {exception:#x}")
+            }
+            Self::StackOutOfBounds { core, sp, bounds } => write!(
+                f,
+                "Core {core} has its stack pointer at {sp:#010x}, out of {:#010x}..={:#010x}",
+                bounds.bottom, bounds.top
+            ),
+            Self::GuardWrite {
+                requestor,
+                address,
+                value,
+            } => write!(
+                f,
+                "{requestor:?} stored {value:#010x} to the guarded address {address:#010x}"
+            ),
+            Self::WatchdogStarved { last_fed, load } => write!(
+                f,
+                "The watchdog was last fed at cycle {last_fed} and ran out after {load} us"
+            ),
+        }
+    }
+}
+
+/// An instruction of the trace window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub core: u8,
+    pub address: u32,
+    pub instruction: u32,
+    pub name: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct SoakFailure {
+    pub violation: Violation,
+    /// The last instructions of both cores before the violation, the oldest first
+    pub trace: Vec<TraceEntry>,
+}
+
+#[derive(Default)]
+struct State {
+    window: VecDeque<TraceEntry>,
+    /// The stack pointer of the core entered its bounds
+    on_stack: [bool; 2],
+    /// The watchdog was reloaded since the last check
+    fed: bool,
+    last_fed: Option<u64>,
+    failure: Option<SoakFailure>,
+}
+
+/// Inspector checking the invariants of a firmware over a long run.
+///
+/// The traps and the stores into the guarded regions are caught on the event, the
+/// stack pointers and the watchdog are sampled by [`SoakMonitor::check`], so the
+/// watchdog is only timed to the interval of the checks. Only the first violation is
+/// kept, with the trace window as it was when it happened. The driver of the run stops
+/// on [`SoakMonitor::has_failed`] and takes the snapshot of the chip from there.
+pub struct SoakMonitor {
+    config: SoakConfig,
+    failed: Cell<bool>,
+    state: RefCell<State>,
+}
+
+impl SoakMonitor {
+    pub fn new(config: SoakConfig) -> Self {
+        Self {
+            config,
+            failed: Cell::new(false),
+            state: RefCell::new(State::default()),
+        }
+    }
+
+    pub fn has_failed(&self) -> bool {
+        self.failed.get()
+    }
+
+    pub fn failure(&self) -> Option<SoakFailure> {
+        self.state.borrow().failure.clone()
+    }
+
+    /// Check the stack pointers and the watchdog, every so often
+    pub fn check(&self, mcu: &Rp2350) {
+        if self.has_failed() {
+            return;
+        }
+
+        for (core, bounds) in self.config.stacks.iter().enumerate() {
+            let Some(bounds) = bounds else {
+                continue;
+            };
+
+            let sp = mcu.processor[core].get_sp();
+            let on_stack = self.state.borrow().on_stack[core];
+
+            if !on_stack {
+                self.state.borrow_mut().on_stack[core] = bounds.contains(sp);
+                continue;
+            }
+
+            if !bounds.contains(sp) {
+                return self.fail(Violation::StackOutOfBounds {
+                    core: core as u8,
+                    sp,
+                    bounds: *bounds,
+                });
+            }
+        }
+
+        if self.config.watchdog {
+            self.check_watchdog(mcu);
+        }
+    }
+
+    fn check_watchdog(&self, mcu: &Rp2350) {
+        let watchdog = &mcu.bus.peripherals.watch_dog;
+        let now = *mcu.clock.ticks.borrow();
+        let mut state = self.state.borrow_mut();
+        let fed = core::mem::take(&mut state.fed);
+
+        if !watchdog.enable {
+            state.last_fed = None;
+            return;
+        }
+
+        let last_fed = match state.last_fed {
+            Some(last_fed) if !fed => last_fed,
+            _ => now,
+        };
+        state.last_fed = Some(last_fed);
+
+        // the watchdog counts down once per microsecond of the tick generator
+        let timeout = watchdog.timer as u64 * (mcu.clock.clk_sys() / MHZ).max(1);
+
+        if now - last_fed > timeout {
+            drop(state);
+            self.fail(Violation::WatchdogStarved {
+                last_fed,
+                load: watchdog.timer,
+            });
+        }
+    }
+
+    fn is_expected(&self, exception: u32) -> bool {
+        // the interrupts of the Cortex-M33, no cause of the Hazard3 goes this high
+        exception >= IRQ_BASE as u32 || self.config.allowed_traps.contains(&exception)
+    }
+
+    fn fail(&self, violation: Violation) {
+        log::warn!("Soak: {violation}");
+
+        let mut state = self.state.borrow_mut();
+        let trace = state.window.iter().cloned().collect();
+        state.failure = Some(SoakFailure { violation, trace });
+        self.failed.set(true);
+    }
+}
+
+impl Inspector for SoakMonitor {
+    fn handle_event(&self, event: InspectionEvent) {
+        if self.has_failed() {
+            return;
+        }
+
+        match event {
+            InspectionEvent::ExecutedInstruction {
+                core,
+                instruction,
+                address,
+                name,
+                ..
+            } if self.config.trace_window > 0 => {
+                let mut state = self.state.borrow_mut();
+
+                if state.window.len() >= self.config.trace_window {
+                    state.window.pop_front();
+                }
+
+                state.window.push_back(TraceEntry {
+                    core,
+                    address,
+                    instruction,
+                    name,
+                });
+            }
+
+            InspectionEvent::Exception { core, exception } if !self.is_expected(exception) => {
+                self.fail(Violation::UnexpectedTrap { core, exception });
+            }
+
+            InspectionEvent::BusStore {
+                requestor,
+                address,
+                value,
+                ..
+            } => {
+                let guards = &self.config.guards;
+
+                if guards.iter().any(|guard| guard.contains(&address)) {
+                    return self.fail(Violation::GuardWrite {
+                        requestor,
+                        address,
+                        value,
+                    });
+                }
+
+                if address & !ALIASES == WATCHDOG_LOAD {
+                    self.state.borrow_mut().fed = true;
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::DataSize;
+
+    fn executed(core: u8, address: u32) -> InspectionEvent {
+        InspectionEvent::ExecutedInstruction {
+            core,
+            instruction: 0x0000_0013,
+            address,
+            name: "addi",
+            operands: Vec::new(),
+        }
+    }
+
+    fn store(address: u32, value: u32) -> InspectionEvent {
+        InspectionEvent::BusStore {
+            requestor: Requestor::Proc0,
+            size: DataSize::Word,
+            address,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_unexpected_trap() {
+        let config = SoakConfig {
+            trace_window: 2,
+            ..Default::default()
+        };
+        let monitor = SoakMonitor::new(config);
+
+        for address in [0x100, 0x104, 0x108] {
+            monitor.handle_event(executed(0, address));
+        }
+
+        monitor.handle_event(InspectionEvent::Exception {
+            core: 0,
+            exception: ECALL_M_MODE,
+        });
+        assert!(!monitor.has_failed());
+
+        monitor.handle_event(InspectionEvent::Exception {
+            core: 1,
+            exception: 0x2,
+        });
+        monitor.handle_event(executed(0, 0x10c));
+
+        let failure = monitor.failure().unwrap();
+        assert_eq!(
+            failure.violation,
+            Violation::UnexpectedTrap {
+                core: 1,
+                exception: 0x2
+            }
+        );
+
+        // the window stops at the violation
+        let addresses: Vec<_> = failure.trace.iter().map(|entry| entry.address).collect();
+        assert_eq!(addresses, [0x104, 0x108]);
+    }
+
+    #[test]
+    fn test_guard_write() {
+        let mut config = SoakConfig::default();
+        config.guards.push(0x2007_0000..0x2007_0100);
+        let monitor = SoakMonitor::new(config);
+
+        // interrupts are exceptions too on the Cortex-M33
+        monitor.handle_event(InspectionEvent::Exception {
+            core: 0,
+            exception: IRQ_BASE as u32 + 3,
+        });
+        monitor.handle_event(store(0x2006_fffc, 1));
+        assert!(!monitor.has_failed());
+
+        monitor.handle_event(store(0x2007_0010, 0xdead_beef));
+        assert_eq!(
+            monitor.failure().unwrap().violation,
+            Violation::GuardWrite {
+                requestor: Requestor::Proc0,
+                address: 0x2007_0010,
+                value: 0xdead_beef,
+            }
+        );
+    }
+
+    #[test]
+    fn test_stack_bounds() {
+        let mut mcu = Rp2350::new();
+        let mut config = SoakConfig::default();
+        config.stacks[0] = Some(StackBounds {
+            bottom: 0x2008_0000,
+            top: 0x2008_1000,
+        });
+        let monitor = SoakMonitor::new(config);
+
+        // still on the stack of the bootrom
+        mcu.processor[0].set_sp(0x2008_2000);
+        monitor.check(&mcu);
+        mcu.processor[0].set_sp(0x2008_1000);
+        monitor.check(&mcu);
+        assert!(!monitor.has_failed());
+
+        mcu.processor[0].set_sp(0x2007_fff0);
+        monitor.check(&mcu);
+        assert!(matches!(
+            monitor.failure().unwrap().violation,
+            Violation::StackOutOfBounds {
+                core: 0,
+                sp: 0x2007_fff0,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_watchdog_starved() {
+        let mut mcu = Rp2350::new();
+        let monitor = SoakMonitor::new(SoakConfig::default());
+
+        // 1 us at the default 150 MHz
+        mcu.bus.peripherals.watch_dog.enable = true;
+        mcu.bus.peripherals.watch_dog.timer = 1;
+        monitor.check(&mcu);
+
+        for _ in 0..150 {
+            mcu.clock.tick();
+        }
+
+        // reloaded through the SET alias
+        monitor.handle_event(store(WATCHDOG_LOAD | 0x2000, 1));
+        monitor.check(&mcu);
+
+        for _ in 0..150 {
+            mcu.clock.tick();
+        }
+
+        monitor.check(&mcu);
+        assert!(!monitor.has_failed());
+
+        mcu.clock.tick();
+        monitor.check(&mcu);
+        assert_eq!(
+            monitor.failure().unwrap().violation,
+            Violation::WatchdogStarved {
+                last_fed: 150,
+                load: 1
+            }
+        );
+    }
+}
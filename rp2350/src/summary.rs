@@ -0,0 +1,184 @@
+/**
+ * @file summary.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Counters of a whole run gathered from the events of the chip, for the reports at its end
+ */
+use crate::inspector::{InspectionEvent, Inspector};
+use std::cell::RefCell;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CoreSummary {
+    /// Instructions retired, the fast-forwarded delay loops included
+    pub instret: u64,
+    /// Interrupts entered, the system exceptions pended on Arm included
+    pub interrupts: u64,
+    /// Conditional branches of the Hazard3, and how many its predictor got wrong
+    pub branches: u64,
+    pub mispredicted_branches: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RunSummary {
+    pub cores: [CoreSummary; 2],
+    /// Bytes sent by each UART
+    pub uart_tx: [u64; 2],
+    /// Bytes sent through the USB serial
+    pub usb_tx: u64,
+    /// Accesses to the peripherals the simulator does not model
+    pub unimplemented_accesses: u64,
+    pub bus_errors: u64,
+}
+
+/// Inspector counting what a run did, starting over every time a binary is flashed
+#[derive(Default)]
+pub struct SummaryCollector(RefCell<RunSummary>);
+
+impl SummaryCollector {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        *self.0.borrow()
+    }
+}
+
+impl Inspector for SummaryCollector {
+    fn handle_event(&self, event: InspectionEvent) {
+        let mut summary = self.0.borrow_mut();
+
+        match event {
+            InspectionEvent::ExecutedInstruction { core, .. } => {
+                summary.cores[core as usize].instret += 1;
+            }
+            InspectionEvent::BusyWaitSkipped {
+                core, instructions, ..
+            } => {
+                summary.cores[core as usize].instret += instructions;
+            }
+            InspectionEvent::InterruptTaken { core, .. } => {
+                summary.cores[core as usize].interrupts += 1;
+            }
+            InspectionEvent::BranchPredicted {
+                core, mispredicted, ..
+            } => {
+                summary.cores[core as usize].branches += 1;
+                summary.cores[core as usize].mispredicted_branches += mispredicted as u64;
+            }
+            InspectionEvent::UartTx { uart_index, .. } => {
+                summary.uart_tx[uart_index as usize] += 1;
+            }
+            InspectionEvent::UsbCdcTx { data } => {
+                summary.usb_tx += data.len() as u64;
+            }
+            InspectionEvent::UnimplementedAccess { .. } => {
+                summary.unimplemented_accesses += 1;
+            }
+            InspectionEvent::BusError { .. } => {
+                summary.bus_errors += 1;
+            }
+            InspectionEvent::FlashedBinary => {
+                *summary = RunSummary::default();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bus, BusAccessContext};
+    use crate::clock::Clock;
+    use crate::gpio::GpioController;
+    use crate::interrupts::Interrupts;
+    use crate::InspectorRef;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_summary_counts() {
+        let collector = SummaryCollector::new();
+
+        collector.handle_event(InspectionEvent::FlashedBinary);
+
+        for _ in 0..3 {
+            collector.handle_event(InspectionEvent::ExecutedInstruction {
+                core: 1,
+                instruction: 0x0000_0013,
+                address: 0x2000_0000,
+                name: "addi",
+                operands: Vec::new(),
+            });
+        }
+
+        collector.handle_event(InspectionEvent::BusyWaitSkipped {
+            core: 1,
+            address: 0x2000_0000,
+            iterations: 10,
+            instructions: 30,
+            cycles: 40,
+        });
+        collector.handle_event(InspectionEvent::InterruptTaken { core: 0, number: 7 });
+        for mispredicted in [true, false] {
+            collector.handle_event(InspectionEvent::BranchPredicted {
+                core: 0,
+                address: 0x2000_0010,
+                taken: true,
+                mispredicted,
+            });
+        }
+        collector.handle_event(InspectionEvent::UartTx {
+            uart_index: 1,
+            value: b'A',
+        });
+        collector.handle_event(InspectionEvent::UsbCdcTx {
+            data: b"OK\n".to_vec(),
+        });
+
+        let summary = collector.summary();
+        assert_eq!(summary.cores[0].instret, 0);
+        assert_eq!(summary.cores[0].interrupts, 1);
+        assert_eq!(summary.cores[0].branches, 2);
+        assert_eq!(summary.cores[0].mispredicted_branches, 1);
+        assert_eq!(summary.cores[1].instret, 33);
+        assert_eq!(summary.uart_tx, [0, 1]);
+        assert_eq!(summary.usb_tx, 3);
+
+        collector.handle_event(InspectionEvent::FlashedBinary);
+        assert_eq!(collector.summary(), RunSummary::default());
+    }
+
+    #[test]
+    fn test_summary_unimplemented_access() {
+        let collector = Rc::new(SummaryCollector::new());
+        let mut inspector = InspectorRef::default();
+        inspector.set_inspector(collector.clone());
+
+        let mut bus = Bus::new(
+            Rc::new(RefCell::new(GpioController::default())),
+            Rc::new(RefCell::new(Interrupts::default())),
+            Rc::new(Clock::new()),
+            inspector,
+        );
+
+        // SYSCFG is not modeled, ACCESSCTRL only lets the secure privileged code reach it
+        // and the APB takes a few cycles to complete each access
+        let address = 0x4000_8000;
+        let ctx = BusAccessContext {
+            secure: true,
+            privileged: true,
+            ..Default::default()
+        };
+        bus.load(address, ctx).unwrap();
+        for _ in 0..4 {
+            bus.tick();
+        }
+        bus.store(address, 1, ctx).unwrap();
+        for _ in 0..4 {
+            bus.tick();
+        }
+
+        assert_eq!(collector.summary().unimplemented_accesses, 2);
+    }
+}
@@ -0,0 +1,28 @@
+/**
+ * @file testing.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Fixtures shared by the tests of the simulator and of the crates built on it
+ */
+use crate::bus::Bus;
+use crate::processor::Rp2350Core;
+use crate::rp2350::Rp2350;
+
+/// Chip running `program`, RISC-V instructions put at the start of the SRAM,
+/// from core 0 while core 1 sleeps
+pub fn setup(program: &[u32]) -> Rp2350 {
+    let bytes: Vec<u8> = program.iter().flat_map(|code| code.to_le_bytes()).collect();
+    let mut mcu = Rp2350::builder().load_at(Bus::SRAM, bytes).build().unwrap();
+
+    mcu.processor[0].set_pc(Bus::SRAM);
+    mcu.processor[1].sleep();
+    mcu
+}
+
+/// Integer register of the Hazard3 core 0
+pub fn register(mcu: &Rp2350, reg: u8) -> u32 {
+    match &mcu.processor[0] {
+        Rp2350Core::RiscV(core) => core.registers.read(reg),
+        Rp2350Core::Arm(_) => unreachable!(),
+    }
+}
@@ -0,0 +1,165 @@
+/**
+ * @file trace.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Trace of the cores through the CoreSight components, stamped by the timestamp generator
+ */
+pub mod instructions;
+pub mod vcd;
+
+use crate::clock::Clock;
+use crate::inspector::{InspectionEvent, Inspector};
+use crate::peripherals::{AtbFunnel, TimestampGenerator, Tpiu};
+use crate::rp2350::Rp2350;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Records kept until they are taken, the events past it are dropped
+pub const MAX_TRACE_RECORDS: usize = 1 << 16;
+
+/// An event of a core as it left the TPIU
+#[derive(Debug, Clone)]
+pub struct TraceRecord {
+    /// Value of the timestamp generator when the event happened
+    pub timestamp: u64,
+    /// Port of the funnel the event came through, the index of the core
+    pub port: u8,
+    pub event: InspectionEvent,
+}
+
+/// The trace source of an event, the core it comes from
+fn source(event: &InspectionEvent) -> Option<u8> {
+    match event {
+        InspectionEvent::ExecutedInstruction { core, .. }
+        | InspectionEvent::Exception { core, .. }
+        | InspectionEvent::DebugHalt { core, .. }
+        | InspectionEvent::InterruptTaken { core, .. }
+        | InspectionEvent::BusyWaitSkipped { core, .. } => Some(*core),
+        _ => None,
+    }
+}
+
+/// Records the events of the cores the way the trace port of the chip would see them.
+/// An event goes through only while the timestamp generator counts, the port of its
+/// core is enabled in the ATB funnel and the formatter of the TPIU runs, so the
+/// firmware or a debugger configures the trace like on the real chip. Every event of
+/// the same cycle carries the same timestamp.
+///
+/// The records keep the inspection events, encoding them into an Arm trace format is
+/// left to the exporters.
+pub struct TraceRecorder {
+    clock: Rc<Clock>,
+    timestamps: Rc<RefCell<TimestampGenerator>>,
+    funnel: Rc<RefCell<AtbFunnel>>,
+    tpiu: Rc<RefCell<Tpiu>>,
+    records: RefCell<Vec<TraceRecord>>,
+    dropped: Cell<u64>,
+}
+
+impl TraceRecorder {
+    pub fn new(mcu: &Rp2350) -> Self {
+        let peripherals = &mcu.bus.peripherals;
+
+        Self {
+            clock: Rc::clone(&mcu.clock),
+            timestamps: Rc::clone(&peripherals.coresight_timestamp_gen),
+            funnel: Rc::clone(&peripherals.coresight_atb_funnel),
+            tpiu: Rc::clone(&peripherals.coresight_tpiu),
+            records: RefCell::new(Vec::new()),
+            dropped: Cell::new(0),
+        }
+    }
+
+    /// Take the records out, the oldest first
+    pub fn take(&self) -> Vec<TraceRecord> {
+        core::mem::take(&mut *self.records.borrow_mut())
+    }
+
+    /// Events lost while the records were full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.get()
+    }
+}
+
+impl Inspector for TraceRecorder {
+    fn handle_event(&self, event: InspectionEvent) {
+        let Some(port) = source(&event) else {
+            return;
+        };
+
+        let timestamps = self.timestamps.borrow();
+
+        if !timestamps.is_enabled()
+            || !self.funnel.borrow().is_port_enabled(port)
+            || !self.tpiu.borrow().is_formatting()
+        {
+            return;
+        }
+
+        let mut records = self.records.borrow_mut();
+
+        if records.len() >= MAX_TRACE_RECORDS {
+            self.dropped.set(self.dropped.get() + 1);
+            return;
+        }
+
+        records.push(TraceRecord {
+            timestamp: timestamps.value(*self.clock.ticks.borrow()),
+            port,
+            event,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::coresight::{funnel, timestamp, tpiu};
+    use crate::peripherals::{Peripheral, PeripheralAccessContext};
+
+    fn executed(core: u8) -> InspectionEvent {
+        InspectionEvent::ExecutedInstruction {
+            core,
+            instruction: 0,
+            address: 0,
+            name: "",
+            operands: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_trace_path() {
+        let mut mcu = Rp2350::new();
+        let recorder = TraceRecorder::new(&mcu);
+
+        recorder.handle_event(executed(0));
+        assert!(recorder.take().is_empty());
+
+        // timestamps on, port of core 1 only, continuous formatting
+        let ctx = PeripheralAccessContext::default();
+        let peripherals = &mut mcu.bus.peripherals;
+        peripherals
+            .coresight_timestamp_gen
+            .write(timestamp::CNTCR, timestamp::CNTCR_EN, &ctx)
+            .unwrap();
+        peripherals
+            .coresight_atb_funnel
+            .write(funnel::CTRL, 0b10, &ctx)
+            .unwrap();
+        peripherals
+            .coresight_tpiu
+            .write(tpiu::FFCR, tpiu::FFCR_EN_FCONT, &ctx)
+            .unwrap();
+
+        mcu.clock.tick();
+        mcu.clock.tick();
+        recorder.handle_event(executed(0));
+        recorder.handle_event(executed(1));
+        recorder.handle_event(InspectionEvent::FlashedBinary);
+
+        let records = recorder.take();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].port, 1);
+        assert_eq!(records[0].timestamp, 2);
+    }
+}
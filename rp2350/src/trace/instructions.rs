@@ -0,0 +1,311 @@
+/**
+ * @file trace/instructions.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Bounded trace of the instructions executed by the cores, exported to CSV or JSON
+ */
+use crate::clock::Clock;
+use crate::common::ArchitectureType;
+use crate::inspector::{InspectionEvent, Inspector};
+use crate::rp2350::Rp2350;
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::rc::Rc;
+
+/// About 64 bytes an instruction, a million of them fit in a browser tab
+pub const DEFAULT_TRACE_CAPACITY: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedInstruction {
+    pub core: u8,
+    /// Ticks of the system clock since the trace started
+    pub cycle: u64,
+    pub pc: u32,
+    pub opcode: u32,
+    pub mnemonic: &'static str,
+    /// Registers written and their new value, the loads included once they complete
+    pub writes: Vec<(u8, u32)>,
+}
+
+impl TracedInstruction {
+    /// Whether the mnemonic contains `query`, or `query` is the address of the instruction
+    /// in hexadecimal
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim();
+        let address = query.strip_prefix("0x").unwrap_or(query);
+
+        u32::from_str_radix(address, 16).is_ok_and(|address| address == self.pc)
+            || self
+                .mnemonic
+                .to_ascii_lowercase()
+                .contains(&query.to_ascii_lowercase())
+    }
+}
+
+fn register_name(architecture: ArchitectureType, register: u8) -> String {
+    match (architecture, register) {
+        (ArchitectureType::CortexM33, 13) => "sp".to_string(),
+        (ArchitectureType::CortexM33, 14) => "lr".to_string(),
+        (ArchitectureType::CortexM33, _) => format!("r{register}"),
+        (ArchitectureType::Hazard3, _) => format!("x{register}"),
+    }
+}
+
+/// Records the last instructions of both cores, the oldest ones dropped once it is full,
+/// to look at what led to a crash after the fact.
+///
+/// The instructions come as inspection events, so the trace must be one of the
+/// inspectors of the chip. The registers are compared on [`InstructionTrace::sample`],
+/// to call after every tick, and what changed is put on the last instruction of the
+/// core: a load gets its result when the bus delivers it, and an exception entry shows
+/// on the instruction it interrupted.
+pub struct InstructionTrace {
+    clock: Rc<Clock>,
+    start: Cell<u64>,
+    capacity: usize,
+    entries: RefCell<VecDeque<TracedInstruction>>,
+    /// Instructions recorded since the start, the dropped ones included
+    recorded: Cell<u64>,
+    /// Sequence number of the last instruction of each core
+    last: [Cell<Option<u64>>; 2],
+    registers: RefCell<[[u32; 32]; 2]>,
+    architectures: Cell<[ArchitectureType; 2]>,
+}
+
+impl InstructionTrace {
+    pub fn new(mcu: &Rp2350, capacity: usize) -> Self {
+        let trace = Self {
+            clock: Rc::clone(&mcu.clock),
+            start: Cell::new(*mcu.clock.ticks.borrow()),
+            capacity: capacity.max(1),
+            entries: RefCell::new(VecDeque::new()),
+            recorded: Cell::new(0),
+            last: Default::default(),
+            registers: RefCell::new([[0; 32]; 2]),
+            architectures: Cell::new(Default::default()),
+        };
+
+        trace.sample(mcu);
+        trace
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The instructions kept, the oldest first
+    pub fn entries(&self) -> Ref<'_, VecDeque<TracedInstruction>> {
+        self.entries.borrow()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    /// Instructions lost since the start as the trace was full
+    pub fn dropped(&self) -> u64 {
+        self.recorded.get() - self.len() as u64
+    }
+
+    /// Forget the instructions, the cycles count from now on
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+        self.recorded.set(0);
+        self.last.iter().for_each(|last| last.set(None));
+        self.start.set(*self.clock.ticks.borrow());
+    }
+
+    /// Compare the registers of the cores with the last sample, to call after every tick
+    pub fn sample(&self, mcu: &Rp2350) {
+        let mut registers = self.registers.borrow_mut();
+        let mut entries = self.entries.borrow_mut();
+        let oldest = self.recorded.get() - entries.len() as u64;
+
+        for (core, processor) in mcu.processor.iter().enumerate() {
+            let now = processor.registers();
+            let last = core::mem::replace(&mut registers[core], now);
+
+            let Some(entry) = self.last[core]
+                .get()
+                .and_then(|sequence| sequence.checked_sub(oldest))
+                .and_then(|index| entries.get_mut(index as usize))
+            else {
+                continue;
+            };
+
+            for (register, (&old, &new)) in last.iter().zip(now.iter()).enumerate() {
+                if old != new {
+                    entry
+                        .writes
+                        .retain(|(written, _)| *written != register as u8);
+                    entry.writes.push((register as u8, new));
+                }
+            }
+        }
+
+        self.architectures.set([
+            mcu.processor[0].architecture(),
+            mcu.processor[1].architecture(),
+        ]);
+    }
+
+    fn push(&self, entry: TracedInstruction) {
+        let mut entries = self.entries.borrow_mut();
+
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+
+        self.last[entry.core as usize & 1].set(Some(self.recorded.get()));
+        self.recorded.set(self.recorded.get() + 1);
+        entries.push_back(entry);
+    }
+
+    fn writes(
+        &self,
+        entry: &TracedInstruction,
+        separator: &str,
+        format: fn(&str, u32) -> String,
+    ) -> String {
+        let architecture = self.architectures.get()[entry.core as usize & 1];
+
+        entry
+            .writes
+            .iter()
+            .map(|&(register, value)| format(&register_name(architecture, register), value))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Registers written by an instruction as `name=value`, separated by spaces
+    pub fn describe_writes(&self, entry: &TracedInstruction) -> String {
+        self.writes(entry, " ", |name, value| format!("{name}={value:#010x}"))
+    }
+
+    /// One line per instruction, the registers written as in [`Self::describe_writes`]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("core,cycle,pc,opcode,mnemonic,writes\n");
+
+        for entry in self.entries.borrow().iter() {
+            let writes = self.describe_writes(entry);
+            let _ = writeln!(
+                csv,
+                "{},{},{:#010x},{:#010x},{},{writes}",
+                entry.core, entry.cycle, entry.pc, entry.opcode, entry.mnemonic
+            );
+        }
+
+        csv
+    }
+
+    /// An array of objects, the registers written as an object of their values
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+
+        for (index, entry) in self.entries.borrow().iter().enumerate() {
+            let separator = if index == 0 { "\n" } else { ",\n" };
+            let writes = self.writes(entry, ",", |name, value| format!("\"{name}\":{value}"));
+            let _ = write!(
+                json,
+                "{separator}{{\"core\":{},\"cycle\":{},\"pc\":{},\"opcode\":{},\"mnemonic\":\"{}\",\"writes\":{{{writes}}}}}",
+                entry.core, entry.cycle, entry.pc, entry.opcode, entry.mnemonic
+            );
+        }
+
+        json.push_str("\n]\n");
+        json
+    }
+}
+
+impl Inspector for InstructionTrace {
+    fn handle_event(&self, event: InspectionEvent) {
+        let InspectionEvent::ExecutedInstruction {
+            core,
+            instruction,
+            address,
+            name,
+            ..
+        } = event
+        else {
+            return;
+        };
+
+        self.push(TracedInstruction {
+            core,
+            cycle: *self.clock.ticks.borrow() - self.start.get(),
+            pc: address,
+            opcode: instruction,
+            mnemonic: name,
+            writes: Vec::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn executed(core: u8, address: u32, name: &'static str) -> InspectionEvent {
+        InspectionEvent::ExecutedInstruction {
+            core,
+            instruction: 0x0050_0093,
+            address,
+            name,
+            operands: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_register_writes() {
+        let mut mcu = Rp2350::new();
+        let trace = InstructionTrace::new(&mcu, 2);
+
+        mcu.clock.tick();
+        trace.handle_event(executed(0, 0x2000_0000, "addi"));
+        mcu.processor[0].set_register(1, 5);
+        trace.sample(&mcu);
+
+        // the load completes a tick later, still on the same instruction
+        trace.handle_event(executed(1, 0x2000_0100, "lw"));
+        trace.sample(&mcu);
+        mcu.processor[1].set_register(10, 0x1234);
+        trace.sample(&mcu);
+
+        let entries = trace.entries();
+        assert_eq!(entries[0].cycle, 1);
+        assert_eq!(entries[0].writes, [(1, 5)]);
+        assert_eq!(entries[1].writes, [(10, 0x1234)]);
+        drop(entries);
+
+        trace.handle_event(executed(0, 0x2000_0004, "add"));
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace.dropped(), 1);
+        assert!(trace.entries()[0].matches("0x20000100"));
+        assert!(trace.entries()[1].matches("AD"));
+        assert!(!trace.entries()[1].matches("lw"));
+    }
+
+    #[test]
+    fn test_export() {
+        let mcu = Rp2350::new();
+        let trace = InstructionTrace::new(&mcu, DEFAULT_TRACE_CAPACITY);
+
+        trace.handle_event(executed(0, 0x2000_0000, "addi"));
+        trace.entries.borrow_mut()[0].writes.push((1, 5));
+
+        assert_eq!(
+            trace.to_csv(),
+            "core,cycle,pc,opcode,mnemonic,writes\n\
+             0,0,0x20000000,0x00500093,addi,x1=0x00000005\n"
+        );
+        assert_eq!(
+            trace.to_json(),
+            "[\n{\"core\":0,\"cycle\":0,\"pc\":536870912,\"opcode\":5243027,\"mnemonic\":\"addi\",\"writes\":{\"x1\":5}}\n]\n"
+        );
+    }
+}
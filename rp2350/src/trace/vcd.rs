@@ -0,0 +1,416 @@
+/**
+ * @file trace/vcd.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Waveform of the pins, the interrupt lines and the bus in the Value Change Dump format
+ */
+use crate::clock::Clock;
+use crate::gpio::NUM_GPIO;
+use crate::inspector::{InspectionEvent, Inspector};
+use crate::rp2350::Rp2350;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::rc::Rc;
+
+/// Value changes kept, the ones past it are dropped
+pub const MAX_VCD_CHANGES: usize = 1 << 20;
+
+/// Picoseconds, a cycle of the system clock is a few nanoseconds
+const PS_PER_SECOND: u128 = 1_000_000_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VcdConfig {
+    pub pins: Vec<u8>,
+    /// Interrupt lines, only the ones raised at least once end up in the file
+    pub irqs: bool,
+    /// Address and data of every access of the bus, makes the dump grow fast
+    pub bus: bool,
+}
+
+impl Default for VcdConfig {
+    fn default() -> Self {
+        Self {
+            // the GPIOs on the header of the Pico 2
+            pins: (0..30).collect(),
+            irqs: true,
+            bus: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Signal {
+    Pin(u8),
+    Irq(u8),
+    BusAddress,
+    BusData,
+    BusRead,
+    BusWrite,
+}
+
+impl Signal {
+    fn scope(&self) -> &'static str {
+        match self {
+            Signal::Pin(_) => "gpio",
+            Signal::Irq(_) => "irq",
+            _ => "bus",
+        }
+    }
+
+    fn width(&self) -> u8 {
+        match self {
+            Signal::BusAddress | Signal::BusData => 32,
+            _ => 1,
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Signal::Pin(pin) => format!("gpio{pin}"),
+            Signal::Irq(irq) => format!("irq{irq}"),
+            Signal::BusAddress => "address".to_string(),
+            Signal::BusData => "data".to_string(),
+            Signal::BusRead => "read".to_string(),
+            Signal::BusWrite => "write".to_string(),
+        }
+    }
+
+    /// Value before anything is recorded, `None` is unknown
+    fn initial(&self) -> Option<u32> {
+        match self {
+            Signal::BusAddress | Signal::BusData => None,
+            _ => Some(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Change {
+    tick: u64,
+    signal: Signal,
+    /// `None` is an unknown value, the data of a load
+    value: Option<u32>,
+}
+
+/// Records the level changes of the pins and the interrupt lines, and optionally the
+/// accesses of the bus, to look at the timing in GTKWave or any logic analyzer viewer.
+///
+/// The levels are compared on [`VcdRecorder::sample`], to call after every tick, while
+/// the bus accesses come as inspection events, so the recorder must also be one of the
+/// inspectors of the chip when the bus is recorded. An interrupt line is high while it
+/// is pending on either core.
+pub struct VcdRecorder {
+    clock: Rc<Clock>,
+    config: VcdConfig,
+    start: u64,
+    pins: Cell<u64>,
+    irqs: Cell<u64>,
+    /// Tick of the last load and store, their strobe falls on the tick after it
+    strobes: [Cell<Option<u64>>; 2],
+    changes: RefCell<Vec<Change>>,
+    dropped: Cell<u64>,
+}
+
+impl VcdRecorder {
+    pub fn new(mcu: &Rp2350, mut config: VcdConfig) -> Self {
+        config.pins.retain(|pin| (*pin as usize) < NUM_GPIO);
+        config.pins.sort_unstable();
+        config.pins.dedup();
+
+        let recorder = Self {
+            clock: Rc::clone(&mcu.clock),
+            config,
+            start: *mcu.clock.ticks.borrow(),
+            pins: Cell::new(0),
+            irqs: Cell::new(0),
+            strobes: Default::default(),
+            changes: RefCell::new(Vec::new()),
+            dropped: Cell::new(0),
+        };
+
+        // every pin gets its level at the start, the first sample only sees the changes
+        let pins = recorder.pin_levels(mcu);
+        for &pin in &recorder.config.pins {
+            recorder.push(
+                recorder.start,
+                Signal::Pin(pin),
+                Some((pins >> pin & 1) as u32),
+            );
+        }
+
+        recorder.pins.set(pins);
+        recorder.sample(mcu);
+        recorder
+    }
+
+    pub fn config(&self) -> &VcdConfig {
+        &self.config
+    }
+
+    /// Changes lost while the recorder was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.get()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.borrow().is_empty()
+    }
+
+    fn pin_levels(&self, mcu: &Rp2350) -> u64 {
+        let gpio = mcu.gpio.borrow();
+
+        self.config.pins.iter().fold(0, |levels, &pin| {
+            levels | (gpio.pad_level(pin) as u64) << pin
+        })
+    }
+
+    fn push(&self, tick: u64, signal: Signal, value: Option<u32>) {
+        let mut changes = self.changes.borrow_mut();
+
+        if changes.len() >= MAX_VCD_CHANGES {
+            self.dropped.set(self.dropped.get() + 1);
+            return;
+        }
+
+        changes.push(Change {
+            tick,
+            signal,
+            value,
+        });
+    }
+
+    fn push_mask_changes(&self, tick: u64, last: &Cell<u64>, now: u64, signal: fn(u8) -> Signal) {
+        let mut changed = last.replace(now) ^ now;
+
+        while changed != 0 {
+            let bit = changed.trailing_zeros() as u8;
+            self.push(tick, signal(bit), Some((now >> bit & 1) as u32));
+            changed &= changed - 1;
+        }
+    }
+
+    /// Compare the levels with the last sample, to call after every tick
+    pub fn sample(&self, mcu: &Rp2350) {
+        let tick = *self.clock.ticks.borrow();
+
+        self.push_mask_changes(tick, &self.pins, self.pin_levels(mcu), Signal::Pin);
+
+        if self.config.irqs {
+            let interrupts = mcu.interrupts.borrow();
+            let lines = interrupts.pending(0) | interrupts.pending(1);
+            self.push_mask_changes(tick, &self.irqs, lines, Signal::Irq);
+        }
+
+        for (strobe, signal) in self.strobes.iter().zip([Signal::BusRead, Signal::BusWrite]) {
+            if let Some(last) = strobe.get().filter(|last| *last < tick) {
+                self.push(last + 1, signal, Some(0));
+                strobe.set(None);
+            }
+        }
+    }
+
+    fn access(&self, strobe: usize, address: u32, value: Option<u32>) {
+        let tick = *self.clock.ticks.borrow();
+        let signal = [Signal::BusRead, Signal::BusWrite][strobe];
+
+        // back to back accesses keep the strobe high
+        if self.strobes[strobe].replace(Some(tick)).is_none() {
+            self.push(tick, signal, Some(1));
+        }
+
+        self.push(tick, Signal::BusAddress, Some(address));
+        self.push(tick, Signal::BusData, value);
+    }
+
+    fn time_ps(&self, tick: u64) -> u128 {
+        (tick - self.start) as u128 * PS_PER_SECOND / self.clock.clk_sys() as u128
+    }
+
+    /// The recording as a VCD file, the ticks are turned into time with the current
+    /// frequency of the system clock
+    pub fn to_vcd(&self) -> String {
+        let mut changes = self.changes.borrow().clone();
+        // a strobe falls after the accesses of the following ticks are recorded
+        changes.sort_by_key(|change| change.tick);
+
+        let mut signals: BTreeSet<Signal> = changes.iter().map(|change| change.signal).collect();
+        signals.extend(self.config.pins.iter().map(|&pin| Signal::Pin(pin)));
+
+        if self.config.bus {
+            signals.extend([
+                Signal::BusAddress,
+                Signal::BusData,
+                Signal::BusRead,
+                Signal::BusWrite,
+            ]);
+        }
+
+        let signals: Vec<Signal> = signals.into_iter().collect();
+        let id = |signal: &Signal| identifier(signals.binary_search(signal).unwrap_or(0));
+
+        let mut vcd = String::new();
+        let _ = writeln!(vcd, "$version pico2 simulator $end");
+        let _ = writeln!(vcd, "$timescale 1ps $end");
+        let _ = writeln!(vcd, "$scope module rp2350 $end");
+
+        let mut scope = None;
+        for signal in &signals {
+            if scope != Some(signal.scope()) {
+                if scope.is_some() {
+                    let _ = writeln!(vcd, "$upscope $end");
+                }
+
+                scope = Some(signal.scope());
+                let _ = writeln!(vcd, "$scope module {} $end", signal.scope());
+            }
+
+            let (width, name) = (signal.width(), signal.name());
+            let _ = writeln!(vcd, "$var wire {width} {} {name} $end", id(signal));
+        }
+
+        if scope.is_some() {
+            let _ = writeln!(vcd, "$upscope $end");
+        }
+
+        let _ = writeln!(vcd, "$upscope $end");
+        let _ = writeln!(vcd, "$enddefinitions $end");
+
+        // the values at the start, the changes of the first tick included
+        let mut initial: Vec<Option<u32>> = signals.iter().map(Signal::initial).collect();
+        let first = changes.partition_point(|change| change.tick <= self.start);
+
+        for change in &changes[..first] {
+            if let Ok(index) = signals.binary_search(&change.signal) {
+                initial[index] = change.value;
+            }
+        }
+
+        let _ = writeln!(vcd, "#0");
+        let _ = writeln!(vcd, "$dumpvars");
+        for (signal, value) in signals.iter().zip(initial) {
+            write_value(&mut vcd, signal, value, &id(signal));
+        }
+        let _ = writeln!(vcd, "$end");
+
+        let mut time = 0;
+        for change in &changes[first..] {
+            let change_time = self.time_ps(change.tick);
+
+            if change_time != time {
+                time = change_time;
+                let _ = writeln!(vcd, "#{time}");
+            }
+
+            write_value(&mut vcd, &change.signal, change.value, &id(&change.signal));
+        }
+
+        vcd
+    }
+}
+
+impl Inspector for VcdRecorder {
+    fn handle_event(&self, event: InspectionEvent) {
+        if !self.config.bus {
+            return;
+        }
+
+        match event {
+            InspectionEvent::BusLoad { address, .. } => self.access(0, address, None),
+            InspectionEvent::BusStore { address, value, .. } => {
+                self.access(1, address, Some(value))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Short name of a signal in the dump, made of the printable ASCII characters
+fn identifier(mut index: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!' + 1) as usize;
+
+    let mut id = String::new();
+    loop {
+        id.push((FIRST + (index % COUNT) as u8) as char);
+        index /= COUNT;
+
+        if index == 0 {
+            return id;
+        }
+
+        index -= 1;
+    }
+}
+
+fn write_value(vcd: &mut String, signal: &Signal, value: Option<u32>, id: &str) {
+    let _ = match (signal.width(), value) {
+        (1, Some(value)) => writeln!(vcd, "{}{id}", value & 1),
+        (1, None) => writeln!(vcd, "x{id}"),
+        (_, Some(value)) => writeln!(vcd, "b{value:b} {id}"),
+        (_, None) => writeln!(vcd, "bx {id}"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{DataSize, Requestor};
+    use crate::device::drive_pin;
+    use crate::interrupts::Interrupts;
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(identifier(95), "\"!");
+    }
+
+    #[test]
+    fn test_vcd_changes() {
+        let mcu = Rp2350::new();
+        let config = VcdConfig {
+            pins: vec![3, 60],
+            irqs: true,
+            bus: true,
+        };
+        let recorder = VcdRecorder::new(&mcu, config);
+        assert_eq!(recorder.config().pins, vec![3]);
+
+        mcu.clock.tick();
+        drive_pin(&mcu, 3, true);
+        mcu.interrupts
+            .borrow_mut()
+            .set_irq(Interrupts::TIMER0_IRQ_0, true);
+        recorder.handle_event(InspectionEvent::BusStore {
+            requestor: Requestor::Proc0,
+            size: DataSize::Word,
+            address: 0x2000_0000,
+            value: 5,
+        });
+        recorder.sample(&mcu);
+
+        mcu.clock.tick();
+        recorder.sample(&mcu);
+
+        let vcd = recorder.to_vcd();
+        assert!(vcd.contains("$timescale 1ps $end"));
+        assert!(vcd.contains("$var wire 1 ! gpio3 $end"));
+        assert!(vcd.contains("$var wire 1 \" irq0 $end"));
+        assert!(vcd.contains("$var wire 32 # address $end"));
+
+        // one cycle at 150 MHz is 6666 ps
+        let changes = vcd.split("$enddefinitions $end\n").nth(1).unwrap();
+        assert_eq!(
+            changes,
+            "#0\n$dumpvars\n0!\n0\"\nbx #\nbx $\n0%\n0&\n$end\n\
+             #6666\n1&\nb100000000000000000000000000000 #\nb101 $\n1!\n1\"\n\
+             #13333\n0&\n"
+        );
+    }
+}
@@ -0,0 +1,312 @@
+/**
+ * @file access.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Rate limiting by IP and API keys in front of the compile and save endpoints.
+ */
+use api_types::CompilationResponse;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use thiserror::Error;
+use warp::http::StatusCode;
+use warp::reject::Reject;
+use warp::{Filter, Rejection, Reply};
+
+use crate::config::AccessConfig;
+
+/// Clients remembered at most, the ones with a full bucket are forgotten past it
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Error, Debug)]
+pub enum AccessError {
+    #[error("Missing or invalid API key")]
+    Unauthorized,
+    #[error("Too many requests, try again in {0} seconds")]
+    RateLimited(u64),
+}
+
+impl Reject for AccessError {}
+
+/// Requests left to a client, refilled over time
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A token bucket for each client IP, and the keys accepted by the server
+pub struct AccessControl {
+    config: AccessConfig,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl AccessControl {
+    pub fn new(config: &AccessConfig) -> Self {
+        Self {
+            config: config.clone(),
+            buckets: Default::default(),
+        }
+    }
+
+    /// Filter rejecting the requests over the limit of their IP, then the ones without
+    /// a valid key when the server has some
+    pub fn guard(self: Arc<Self>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+        warp::addr::remote()
+            .and(warp::header::optional::<String>("x-forwarded-for"))
+            .and(warp::header::optional::<String>("x-api-key"))
+            .and_then(
+                move |remote: Option<SocketAddr>,
+                      forwarded: Option<String>,
+                      key: Option<String>| {
+                    let access = self.clone();
+                    async move {
+                        let ip = access.client_ip(remote, forwarded.as_deref());
+                        access
+                            .check(ip, key.as_deref())
+                            .map_err(warp::reject::custom)
+                    }
+                },
+            )
+            .untuple_one()
+    }
+
+    fn client_ip(&self, remote: Option<SocketAddr>, forwarded: Option<&str>) -> Option<IpAddr> {
+        // the proxy in front of the server appends the address it was reached from, the
+        // ones before it are sent by the client and can be anything
+        let forwarded = forwarded
+            .filter(|_| self.config.trust_forwarded)
+            .and_then(|list| list.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+
+        forwarded.or(remote.map(|addr| addr.ip()))
+    }
+
+    fn check(&self, ip: Option<IpAddr>, key: Option<&str>) -> Result<(), AccessError> {
+        // counted before the key, so the keys cannot be guessed faster than the limit
+        if let Some(ip) = ip {
+            self.take_token(ip, Instant::now())?;
+        }
+
+        let keys = &self.config.api_keys;
+        let authorized = keys.is_empty()
+            || key.is_some_and(|key| keys.iter().any(|valid| constant_time_eq(valid, key)));
+
+        match authorized {
+            true => Ok(()),
+            false => Err(AccessError::Unauthorized),
+        }
+    }
+
+    fn take_token(&self, ip: IpAddr, now: Instant) -> Result<(), AccessError> {
+        if self.config.requests_per_minute == 0 {
+            return Ok(());
+        }
+
+        let rate = self.config.requests_per_minute as f64 / 60.0;
+        let capacity = self.config.burst.max(1) as f64;
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| {
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate < capacity
+            });
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            let wait = ((1.0 - bucket.tokens) / rate).ceil() as u64;
+            return Err(AccessError::RateLimited(wait));
+        }
+
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+}
+
+/// The rejections of the guard as a `CompilationResponse::Error`, with their status.
+/// A `ProjectResponse::Error` has the same shape
+pub async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    let Some(e) = rejection.find::<AccessError>() else {
+        return Err(rejection);
+    };
+
+    let status = match e {
+        AccessError::Unauthorized => StatusCode::UNAUTHORIZED,
+        AccessError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+    };
+
+    let response = CompilationResponse::Error {
+        message: e.to_string(),
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        status,
+    ))
+}
+
+/// Compare the keys in a time which does not tell how much of them matches
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const CLIENT: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+
+    fn limited(requests_per_minute: u32, burst: u32) -> AccessControl {
+        AccessControl::new(&AccessConfig {
+            requests_per_minute,
+            burst,
+            ..Default::default()
+        })
+    }
+
+    fn wait(result: Result<(), AccessError>) -> Option<u64> {
+        match result {
+            Err(AccessError::RateLimited(wait)) => Some(wait),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_burst_and_refill() {
+        // a request per second, 3 in a row
+        let access = limited(60, 3);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(access.take_token(CLIENT, start).is_ok());
+        }
+        assert_eq!(wait(access.take_token(CLIENT, start)), Some(1));
+
+        // another client has its own bucket
+        let other = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 2));
+        assert!(access.take_token(other, start).is_ok());
+
+        let later = start + Duration::from_secs(1);
+        assert!(access.take_token(CLIENT, later).is_ok());
+        assert!(access.take_token(CLIENT, later).is_err());
+
+        // the bucket does not fill past the burst
+        let much_later = later + Duration::from_secs(3600);
+        for _ in 0..3 {
+            assert!(access.take_token(CLIENT, much_later).is_ok());
+        }
+        assert!(access.take_token(CLIENT, much_later).is_err());
+    }
+
+    #[test]
+    fn test_rate_limited_wait() {
+        // a request every 10 seconds
+        let access = limited(6, 1);
+        let start = Instant::now();
+
+        assert!(access.take_token(CLIENT, start).is_ok());
+        assert_eq!(wait(access.take_token(CLIENT, start)), Some(10));
+
+        // 8.5 seconds left are rounded up
+        let later = start + Duration::from_millis(1500);
+        assert_eq!(wait(access.take_token(CLIENT, later)), Some(9));
+
+        assert_eq!(
+            AccessError::RateLimited(9).to_string(),
+            "Too many requests, try again in 9 seconds"
+        );
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let access = limited(0, 1);
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert!(access.take_token(CLIENT, now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_forwarded_ip() {
+        let remote = Some(SocketAddr::new(CLIENT, 4000));
+        let forwarded = Some("203.0.113.7, 10.0.0.1");
+
+        let access = limited(10, 5);
+        assert_eq!(access.client_ip(remote, forwarded), Some(CLIENT));
+        assert_eq!(access.client_ip(None, forwarded), None);
+
+        let access = AccessControl::new(&AccessConfig {
+            trust_forwarded: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            access.client_ip(remote, forwarded),
+            Some("10.0.0.1".parse().unwrap())
+        );
+
+        // the entries of the client do not change its bucket
+        for spoofed in [
+            "198.51.100.1, 10.0.0.1",
+            "198.51.100.2,10.0.0.1",
+            "10.0.0.1",
+        ] {
+            assert_eq!(
+                access.client_ip(remote, Some(spoofed)),
+                Some("10.0.0.1".parse().unwrap())
+            );
+        }
+
+        assert_eq!(access.client_ip(remote, Some("garbage")), Some(CLIENT));
+        assert_eq!(
+            access.client_ip(remote, Some("10.0.0.1, garbage")),
+            Some(CLIENT)
+        );
+        assert_eq!(access.client_ip(remote, None), Some(CLIENT));
+    }
+
+    #[test]
+    fn test_api_keys() {
+        let access = AccessControl::new(&AccessConfig {
+            api_keys: vec!["secret".to_string()],
+            requests_per_minute: 0,
+            ..Default::default()
+        });
+
+        assert!(access.check(Some(CLIENT), Some("secret")).is_ok());
+        assert!(access.check(None, Some("secret")).is_ok());
+
+        for key in [None, Some(""), Some("sec"), Some("secret2"), Some("Secret")] {
+            assert!(matches!(
+                access.check(Some(CLIENT), key),
+                Err(AccessError::Unauthorized)
+            ));
+        }
+
+        // without keys the server is open
+        assert!(limited(0, 1).check(Some(CLIENT), None).is_ok());
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(!constant_time_eq("secret", "secre"));
+        assert!(!constant_time_eq("secre", "secret"));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(constant_time_eq("", ""));
+    }
+}
@@ -6,19 +6,28 @@
  */
 use api_types::*;
 use std::cmp::Ordering;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsString;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
-use tokio::sync::oneshot;
-use tokio::sync::Mutex;
-use tokio::time::sleep;
+use tokio::sync::watch;
+use tokio::sync::{Mutex, Notify};
+use tokio::time::{sleep, timeout};
+use uf2::FamilyId;
 use warp::reject::Reject;
 
-use crate::config::ServerConfig;
+use crate::config::{
+    JobConfig, MicroPythonToolchain, RustToolchain, ServerConfig, ToolchainConfig,
+};
+use crate::libraries;
+use crate::metrics::{Health, Metrics};
 
 #[derive(Error, Debug)]
 pub enum CompileError {
@@ -26,10 +35,22 @@ pub enum CompileError {
     CompilationError(String),
     #[error("No code provided")]
     NoCode,
-    #[error("Unsupported multiple files")]
-    UnsupportedMultipleFiles,
-    #[error("Unsupported file name. Currently only main.c is allowed")]
-    UnsupportedFileName,
+    #[error("Invalid file name: {0}")]
+    InvalidFileName(String),
+    #[error("Missing {0}, the entry point of the program")]
+    MissingMainFile(&'static str),
+    #[error("{} is not set up on this server", .0.name())]
+    UnsupportedLanguage(Language),
+    #[error("Library {0} is not available on this server")]
+    UnknownLibrary(String),
+    #[error("Libraries are linked to the pico-sdk projects only, not to {}", .0.name())]
+    UnsupportedLibraries(Language),
+    #[error("A custom {0} can run any command, this server only builds it in a sandbox")]
+    UnsandboxedBuildFile(&'static str),
+    #[error("The build took longer than {0} seconds")]
+    Timeout(u64),
+    #[error("The server is busy, try again later")]
+    QueueFull,
     #[error("File system error: {0}")]
     FileSystemError(#[from] std::io::Error),
 }
@@ -37,8 +58,19 @@ pub enum CompileError {
 impl Reject for CompileError {}
 
 const MAX_RESULT_STORAGE_LEN: usize = 500;
+/// Start of the flash, where the images converted from an ELF file are flashed
+const FLASH_BASE: u32 = 0x1000_0000;
+const FLASH_SIZE: usize = 0x100_0000;
+
+const CMAKE_FILE: &[u8] = include_bytes!("../assets/CMakeLists.txt");
+const TOOLCHAIN_FILE: &[u8] = include_bytes!("../assets/pico_sdk_import.cmake");
+const DUMMY_FILE: &[u8] = include_bytes!("../assets/dummy_main.c");
+/// Included by the CMakeLists, see `libraries::cmake_script`
+const LIBRARIES_FILE: &str = "libraries.cmake";
 
 type Id = String;
+/// Lines printed by the build of a request, followed by its event streams
+type BuildLog = watch::Sender<Vec<String>>;
 
 #[derive(Debug)]
 enum CompilationStatus {
@@ -64,15 +96,88 @@ struct CompilationResult {
     status: CompilationStatus,
     updated_on: Instant,
     served: bool,
+    log: watch::Receiver<Vec<String>>,
+}
+
+/// A request in the queue, then built by one of the workers
+struct Job {
+    id: Id,
+    /// Key of the request in the cache, see `request_key`
+    key: u64,
+    request: CompilationRequest,
+    log: BuildLog,
+}
+
+/// What the steps of a build share: the log they print to and the limits of their
+/// processes
+struct BuildContext {
+    log: BuildLog,
+    limits: JobConfig,
 }
 
+impl BuildContext {
+    /// The command confined by the sandbox and the resource limits of the config, none
+    /// if there is nothing to confine it with
+    fn confine(&self, cmd: &Command) -> Option<Command> {
+        let mut wrapper: Vec<OsString> = self.limits.sandbox.iter().map(OsString::from).collect();
+        let mut rlimits = Vec::new();
+
+        if let Some(memory) = self.limits.memory_mb {
+            rlimits.push(OsString::from(format!("--as={}", memory * 1024 * 1024)));
+        }
+
+        if let Some(cpu) = self.limits.cpu_secs {
+            rlimits.push(OsString::from(format!("--cpu={cpu}")));
+        }
+
+        if !rlimits.is_empty() {
+            wrapper.push(OsString::from("prlimit"));
+            wrapper.extend(rlimits);
+            wrapper.push(OsString::from("--"));
+        }
+
+        if wrapper.is_empty() {
+            return None;
+        }
+
+        let std_cmd = cmd.as_std();
+        let mut confined = Command::new(&wrapper[0]);
+        confined
+            .args(&wrapper[1..])
+            .arg(std_cmd.get_program())
+            .args(std_cmd.get_args());
+
+        if let Some(dir) = std_cmd.get_current_dir() {
+            confined.current_dir(dir);
+        }
+
+        for (key, value) in std_cmd.get_envs() {
+            match value {
+                Some(value) => confined.env(key, value),
+                None => confined.env_remove(key),
+            };
+        }
+
+        Some(confined)
+    }
+}
+
+/// The requests are queued and built by a few workers side by side, each in its own
+/// workspace under the build directory so the builds of the pico-sdk stay incremental
+/// without two jobs sharing their files.
 pub struct Compiler {
     results: Arc<Mutex<HashMap<Id, CompilationResult>>>,
-    queue: Arc<Mutex<VecDeque<(Id, CompilationRequest)>>>,
-    notifier: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+    /// Successful build of each request, the same request gets its result
+    cache: Arc<Mutex<HashMap<u64, Id>>>,
+    metrics: Arc<Metrics>,
+    queue: Arc<Mutex<VecDeque<Job>>>,
+    notifier: Arc<Notify>,
     build_dir: PathBuf,
     data_dir: PathBuf,
     result_dir: PathBuf,
+    pico_sdk: Option<String>,
+    toolchains: ToolchainConfig,
+    jobs: JobConfig,
 }
 
 impl Compiler {
@@ -88,15 +193,20 @@ impl Compiler {
 
         let res = Self {
             results: Default::default(),
+            cache: Default::default(),
+            metrics: Default::default(),
             queue: Default::default(),
             notifier: Default::default(),
             build_dir,
             result_dir,
             data_dir,
+            pico_sdk: config.pico_sdk.clone(),
+            toolchains: config.toolchains.clone(),
+            jobs: config.jobs.clone(),
         };
 
         res.prepare_build_env(config).await?;
-        res.spawn_compilation_handler();
+        res.spawn_workers();
         res.spawm_clean_up_task();
 
         Ok(res)
@@ -104,11 +214,13 @@ impl Compiler {
 
     fn spawm_clean_up_task(&self) {
         let results_lock = self.results.clone();
-        let result_dir = self.build_dir.clone();
+        let cache = self.cache.clone();
+        let result_dir = self.result_dir.clone();
 
         tokio::spawn(async move {
             loop {
                 let mut results = results_lock.lock().await;
+                let mut removed = HashSet::new();
 
                 // TODO should check by length or by total size???
                 if results.len() > MAX_RESULT_STORAGE_LEN {
@@ -138,28 +250,50 @@ impl Compiler {
 
                     for key in keys.iter().take(results.len() - MAX_RESULT_STORAGE_LEN) {
                         results.remove(key);
+                        removed.insert(key.clone());
                         let _ = fs::remove_file(result_dir.join(format!("{key}.uf2"))).await;
                         let _ = fs::remove_file(result_dir.join(format!("{key}.dis"))).await;
+                        let _ = fs::remove_file(result_dir.join(format!("{key}.elf"))).await;
+                        let _ = fs::remove_file(result_dir.join(format!("{key}.map"))).await;
+                        let _ = fs::remove_file(result_dir.join(format!("{key}.sym"))).await;
                     }
                 }
 
                 drop(results);
+
+                // `cached` locks the cache before the results, never hold both here
+                if !removed.is_empty() {
+                    cache.lock().await.retain(|_, id| !removed.contains(id));
+                }
+
                 sleep(Duration::from_secs(60)).await; // do it one per minute
             }
         });
     }
 
-    fn spawn_compilation_handler(&self) {
-        let notifier = self.notifier.clone();
-        let queue = self.queue.clone();
-        let results = self.results.clone();
-        let build_dir = self.build_dir.clone();
-        let result_dir = self.result_dir.clone();
+    fn spawn_workers(&self) {
+        for worker in 0..self.jobs.workers.max(1) {
+            let notifier = self.notifier.clone();
+            let queue = self.queue.clone();
+            let results = self.results.clone();
+            let cache = self.cache.clone();
+            let metrics = self.metrics.clone();
+            let workspace = self.build_dir.join(workspace_dir(worker));
+            let result_dir = self.result_dir.clone();
+            let pico_sdk = self.pico_sdk.clone();
+            let toolchains = self.toolchains.clone();
+            let limits = self.jobs.clone();
+
+            tokio::spawn(async move {
+                loop {
+                    let Some(job) = queue.lock().await.pop_front() else {
+                        // Queue is empty, wait for it to has at least one
+                        notifier.notified().await;
+                        continue;
+                    };
 
-        tokio::spawn(async move {
-            loop {
-                while let Some((id, req)) = queue.lock().await.pop_front() {
-                    log::info!("Compiling request {id}");
+                    let id = job.id;
+                    log::info!("Compiling request {id} on worker {worker}");
 
                     // Add one again here to avoid data race
                     results.lock().await.insert(
@@ -168,14 +302,40 @@ impl Compiler {
                             status: CompilationStatus::InProgress,
                             updated_on: Instant::now(),
                             served: false,
+                            log: job.log.subscribe(),
                         },
                     );
 
-                    let res = match req.lang {
-                        Language::C => compile_c_code(&id, &req, &build_dir, &result_dir).await,
+                    let ctx = BuildContext {
+                        log: job.log,
+                        limits: limits.clone(),
                     };
 
+                    let started = Instant::now();
+                    metrics.build_started();
+
+                    let res = timeout(
+                        Duration::from_secs(limits.timeout_secs),
+                        compile_code(
+                            &id,
+                            &job.request,
+                            &toolchains,
+                            pico_sdk.as_deref(),
+                            &workspace,
+                            &result_dir,
+                            &ctx,
+                        ),
+                    )
+                    .await
+                    .unwrap_or(Err(CompileError::Timeout(limits.timeout_secs)));
+
                     log::info!("Request {id} done");
+                    metrics.build_finished(started.elapsed(), &res);
+
+                    if res.is_ok() {
+                        cache.lock().await.insert(job.key, id.clone());
+                    }
+
                     results.lock().await.insert(
                         id,
                         CompilationResult {
@@ -185,59 +345,134 @@ impl Compiler {
                             },
                             updated_on: Instant::now(),
                             served: false,
+                            log: ctx.log.subscribe(),
                         },
                     );
-                }
 
-                // Queue is empty, wait for it to has at least one
-                let (tx, rx) = oneshot::channel();
-                notifier.lock().await.replace(tx);
-                let _ = rx.await;
-            }
-        });
+                    // the streams see the end of the log once the result is stored
+                    drop(ctx);
+                }
+            });
+        }
     }
 
-    pub async fn compile(&mut self, req: CompilationRequest) -> CompilationResponse {
+    pub async fn compile(&self, req: CompilationRequest) -> CompilationResponse {
+        self.metrics.request();
+        let key = request_key(&req);
+
+        if let Some(id) = self.cached(key).await {
+            log::info!("Request served by the result of {id}");
+            self.metrics.cache_hit();
+            return CompilationResponse::InProgress { id, position: 0 };
+        }
+
         let id = generate_id();
+        let (log, log_receiver) = watch::channel(Vec::new());
+
+        let mut queue = self.queue.lock().await;
+        if queue.len() >= self.jobs.queue_capacity {
+            self.metrics.rejected();
+            return CompilationResponse::Error {
+                message: CompileError::QueueFull.to_string(),
+            };
+        }
 
-        // TODO caching to avoid compile the same code multiple times
-        // TOOD clean up
+        queue.push_back(Job {
+            id: id.clone(),
+            key,
+            request: req,
+            log,
+        });
+        let position = queue.len();
+        drop(queue);
 
-        self.queue.lock().await.push_back((id.clone(), req));
         self.results.lock().await.insert(
             id.clone(),
             CompilationResult {
                 status: CompilationStatus::InProgress,
                 updated_on: Instant::now(),
                 served: false,
+                log: log_receiver,
             },
         );
 
         log::info!("Added request {id} to the queue");
 
-        // Notify the compilation handler to continue its work
-        if let Some(notifier) = self.notifier.lock().await.take() {
-            let _ = notifier.send(());
+        // Notify a worker to continue its work
+        self.notifier.notify_one();
+
+        CompilationResponse::InProgress { id, position }
+    }
+
+    /// A successful build of the same request, as long as its result is kept
+    async fn cached(&self, key: u64) -> Option<Id> {
+        let mut cache = self.cache.lock().await;
+        let id = cache.get(&key)?;
+
+        let results = self.results.lock().await;
+        match results.get(id) {
+            Some(result) if result.status == CompilationStatus::Success => Some(id.clone()),
+            _ => {
+                drop(results);
+                cache.remove(&key);
+                None
+            }
         }
+    }
+
+    pub async fn health(&self) -> Health {
+        let queue_depth = self.queue.lock().await.len();
+        self.metrics.health(queue_depth, self.jobs.workers.max(1))
+    }
+
+    /// Counters of the compiler for Prometheus
+    pub async fn metrics(&self) -> String {
+        let queue_depth = self.queue.lock().await.len();
+        self.metrics.render(queue_depth, self.jobs.workers.max(1))
+    }
+
+    /// Place of a request in the queue from 1, 0 once a worker builds it
+    async fn queue_position(&self, id: &str) -> usize {
+        let queue = self.queue.lock().await;
+        queue
+            .iter()
+            .position(|job| job.id == id)
+            .map_or(0, |index| index + 1)
+    }
 
-        CompilationResponse::InProgress { id }
+    /// Libraries the requests may ask for
+    pub fn libraries(&self) -> Vec<String> {
+        libraries::available(&self.toolchains)
     }
 
-    pub async fn get_uf2(&mut self, id: &str) -> Result<Vec<u8>, CompileError> {
+    /// Log of the build of a request, it changes until the result is stored
+    pub async fn log(&self, id: &str) -> Option<watch::Receiver<Vec<String>>> {
+        let results = self.results.lock().await;
+        results.get(id).map(|result| result.log.clone())
+    }
+
+    pub async fn get_uf2(&self, id: &str) -> Result<Vec<u8>, CompileError> {
         let uf2_path = self.result_dir.join(format!("{}.uf2", id));
         fs::read(uf2_path)
             .await
             .map_err(CompileError::FileSystemError)
     }
 
-    pub async fn get_dis(&mut self, id: &str) -> Result<String, CompileError> {
+    pub async fn get_dis(&self, id: &str) -> Result<String, CompileError> {
         let dis_path = self.result_dir.join(format!("{}.dis", id));
         fs::read_to_string(dis_path)
             .await
             .map_err(CompileError::FileSystemError)
     }
 
-    pub async fn get_result(&mut self, id: &str) -> CompilationResponse {
+    pub async fn get_elf(&self, id: &str) -> Result<Vec<u8>, CompileError> {
+        let elf_path = self.result_dir.join(format!("{}.elf", id));
+        fs::read(elf_path)
+            .await
+            .map_err(CompileError::FileSystemError)
+    }
+
+    pub async fn get_result(&self, id: &str) -> CompilationResponse {
         let mut lock = self.results.lock().await;
         let Some(result) = lock.get_mut(id) else {
             return CompilationResponse::Error {
@@ -246,7 +481,14 @@ impl Compiler {
         };
 
         match &result.status {
-            CompilationStatus::InProgress => CompilationResponse::InProgress { id: id.to_string() },
+            CompilationStatus::InProgress => {
+                drop(lock);
+
+                CompilationResponse::InProgress {
+                    id: id.to_string(),
+                    position: self.queue_position(id).await,
+                }
+            }
             CompilationStatus::Success => {
                 result.served = true;
                 drop(lock);
@@ -269,9 +511,30 @@ impl Compiler {
                     }
                 };
 
+                let elf = match self.get_elf(id).await {
+                    Ok(elf) => elf,
+                    Err(e) => {
+                        return CompilationResponse::Error {
+                            message: e.to_string(),
+                        }
+                    }
+                };
+
+                // the optional artifacts are there only if asked for
+                let map = fs::read_to_string(self.result_dir.join(format!("{}.map", id)))
+                    .await
+                    .unwrap_or_default();
+                let symbols = fs::read_to_string(self.result_dir.join(format!("{}.sym", id)))
+                    .await
+                    .map(|nm| parse_symbols(&nm))
+                    .unwrap_or_default();
+
                 CompilationResponse::Done {
                     uf2,
                     disassembler: dis,
+                    elf,
+                    map,
+                    symbols,
                 }
             }
             CompilationStatus::Failure(e) => {
@@ -284,51 +547,106 @@ impl Compiler {
     }
 
     pub async fn prepare_build_env(&self, config: &ServerConfig) -> Result<(), CompileError> {
-        log::info!("Preparing build environment");
-
-        const CMAKE_FILE: &'static [u8] = include_bytes!("../assets/CMakeLists.txt");
-        const TOOLCHAIN_FILE: &'static [u8] = include_bytes!("../assets/pico_sdk_import.cmake");
-        const DUMMY_FILE: &'static [u8] = include_bytes!("../assets/dummy_main.c");
-
-        let sdk_path = config.pico_sdk.as_deref();
-
         if !has_dir(&self.data_dir).await? {
             fs::create_dir(&self.data_dir).await?;
         }
 
         ensure_new_dir(&self.build_dir).await?;
-        ensure_new_dir(&self.build_dir.join("build")).await?;
-        fs::write(self.build_dir.join("CMakeLists.txt"), CMAKE_FILE).await?;
-        fs::write(self.build_dir.join("pico_sdk_import.cmake"), TOOLCHAIN_FILE).await?;
-        fs::write(self.build_dir.join("main.c"), DUMMY_FILE).await?;
-
-        // Run cmake
-        let mut cmd = Command::new("cmake");
 
-        cmd.current_dir(self.build_dir.join("build"))
-            .arg(&self.build_dir)
-            .arg("-DPICO_BOARD=pico2")
-            .arg("-DPICO_PLATFORM=rp2350-riscv");
+        for worker in 0..self.jobs.workers.max(1) {
+            log::info!("Preparing build environment of worker {worker}");
 
-        if let Some(path) = sdk_path {
-            cmd.arg(format!("-DPICO_SDK_PATH={}", path));
+            let workspace = self.build_dir.join(workspace_dir(worker));
+            self.prepare_workspace(&workspace, config.pico_sdk.as_deref())
+                .await?;
         }
 
-        let cmake_build_result = cmd.output().await?;
+        Ok(())
+    }
 
-        if !cmake_build_result.status.success() {
-            return Err(CompileError::CompilationError(format!(
-                "Failed to run cmake: {}",
-                String::from_utf8_lossy(&cmake_build_result.stderr),
-            )));
+    /// The projects of every language in a workspace, built once so that the first
+    /// compilations are quick
+    async fn prepare_workspace(
+        &self,
+        workspace: &Path,
+        sdk_path: Option<&str>,
+    ) -> Result<(), CompileError> {
+        // nobody follows the initial builds, they are not confined either
+        let ctx = BuildContext {
+            log: watch::channel(Vec::new()).0,
+            limits: JobConfig {
+                memory_mb: None,
+                cpu_secs: None,
+                sandbox: Vec::new(),
+                ..self.jobs.clone()
+            },
+        };
+
+        ensure_new_dir(workspace).await?;
+        ensure_new_dir(&workspace.join("src")).await?;
+        fs::write(workspace.join("CMakeLists.txt"), CMAKE_FILE).await?;
+        fs::write(workspace.join("pico_sdk_import.cmake"), TOOLCHAIN_FILE).await?;
+        fs::write(workspace.join("src").join("main.c"), DUMMY_FILE).await?;
+        fs::write(
+            workspace.join(LIBRARIES_FILE),
+            libraries::cmake_script(&[], &self.toolchains)?,
+        )
+        .await?;
+
+        for target in Target::ALL {
+            let build_path = workspace.join(pico_sdk_build_dir(target));
+            ensure_new_dir(&build_path).await?;
+            cmake(&build_path, workspace, target, sdk_path, &ctx).await?;
+
+            // Initial build to speed up the first compilation
+            Command::new("make")
+                .current_dir(&build_path)
+                .output()
+                .await?;
         }
 
-        // Initial build to speed up the first compilation
-        Command::new("make")
-            .current_dir(self.build_dir.join("build"))
-            .output()
+        if let Some(rust) = &self.toolchains.rust {
+            let project = workspace.join("rust");
+
+            run(
+                Command::new("cp")
+                    .arg("-r")
+                    .arg(&rust.project)
+                    .arg(&project),
+                &ctx,
+                true,
+            )
             .await?;
 
+            // Initial build of the dependencies, the code of the template is fine
+            for target in Target::ALL {
+                Command::new(&rust.cargo)
+                    .current_dir(&project)
+                    .args(["build", "--release", "--target", rust.triple(target)])
+                    .output()
+                    .await?;
+            }
+        }
+
+        if let Some(micropython) = &self.toolchains.micropython {
+            let dir = workspace.join("micropython");
+            ensure_new_dir(&dir.join("src")).await?;
+            fs::write(dir.join("src").join("main.py"), "").await?;
+
+            // The scripts are frozen, MicroPython runs main.py on boot like the one of the
+            // filesystem and the others are imported as modules
+            let manifest = format!(
+                "include(\"$(PORT_DIR)/boards/manifest.py\")\nfreeze(\"{}\")\n",
+                dir.join("src").display()
+            );
+            fs::write(dir.join("manifest.py"), manifest).await?;
+
+            // Initial build of the firmwares, the longest by far
+            for target in Target::ALL {
+                micropython_make(micropython, &dir, target).output().await?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -354,52 +672,550 @@ fn generate_id() -> String {
     nanoid::nanoid!(21, &nanoid::alphabet::SAFE)
 }
 
-async fn compile_c_code(
+/// Key of a request in the cache, from all its fields
+fn request_key(req: &CompilationRequest) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(req)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Workspace of a worker in the build directory
+fn workspace_dir(worker: usize) -> String {
+    format!("worker{worker}")
+}
+
+/// Build directory of the pico-sdk project, one per target
+fn pico_sdk_build_dir(target: Target) -> &'static str {
+    match target {
+        Target::RiscV => "build",
+        Target::Arm => "build-arm",
+    }
+}
+
+fn pico_platform(target: Target) -> &'static str {
+    match target {
+        Target::RiscV => "rp2350-riscv",
+        Target::Arm => "rp2350-arm-s",
+    }
+}
+
+/// Configure a pico-sdk project in its build directory
+async fn cmake(
+    build_path: &Path,
+    project: &Path,
+    target: Target,
+    sdk_path: Option<&str>,
+    ctx: &BuildContext,
+) -> Result<(), CompileError> {
+    let mut cmd = Command::new("cmake");
+
+    cmd.current_dir(build_path)
+        .arg(project)
+        .arg("-DPICO_BOARD=pico2")
+        .arg(format!("-DPICO_PLATFORM={}", pico_platform(target)));
+
+    if let Some(path) = sdk_path {
+        cmd.arg(format!("-DPICO_SDK_PATH={}", path));
+    }
+
+    match run(&mut cmd, ctx, true).await {
+        Err(CompileError::CompilationError(stderr)) => Err(CompileError::CompilationError(
+            format!("Failed to run cmake: {stderr}"),
+        )),
+        res => res.map(|_| ()),
+    }
+}
+
+/// Write the files of a project in a fresh directory
+async fn write_sources<'a>(
+    dir: &Path,
+    files: impl IntoIterator<Item = &'a SourceCode>,
+) -> Result<(), CompileError> {
+    ensure_new_dir(dir).await?;
+
+    for file in files {
+        let path = dir.join(&file.filename);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        fs::write(path, &file.code).await?;
+    }
+
+    Ok(())
+}
+
+/// Write a file only if its content differs, make reruns cmake when it is newer.
+/// The build directories keep the libraries built until the set changes.
+async fn write_if_changed(path: &Path, content: &str) -> Result<(), CompileError> {
+    if fs::read_to_string(path).await.ok().as_deref() != Some(content) {
+        fs::write(path, content).await?;
+    }
+
+    Ok(())
+}
+
+/// Run a step of a build, its error output is the message of a failure. The command
+/// and its output are streamed to the log line by line, the stdout only if asked as
+/// some tools print JSON or a whole disassembly there. The process is confined by the
+/// limits of the jobs, and killed if the job is dropped on its timeout.
+async fn run(
+    cmd: &mut Command,
+    ctx: &BuildContext,
+    log_stdout: bool,
+) -> Result<Output, CompileError> {
+    let log = &ctx.log;
+    let std_cmd = cmd.as_std();
+    let step = std::iter::once(std_cmd.get_program())
+        .chain(std_cmd.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    log.send_modify(|lines| lines.push(format!("$ {step}")));
+
+    let mut confined = ctx.confine(cmd);
+    let cmd = confined.as_mut().unwrap_or(cmd);
+
+    let Ok(mut process) = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+    else {
+        let comp_err =
+            CompileError::CompilationError("Failed to start the compilation process".to_string());
+
+        return Err(comp_err);
+    };
+
+    let (stdout, stderr, status) = tokio::join!(
+        read_lines(process.stdout.take(), log_stdout.then_some(log)),
+        read_lines(process.stderr.take(), Some(log)),
+        process.wait(),
+    );
+    let status = status?;
+
+    if !status.success() {
+        let comp_err = CompileError::CompilationError(String::from_utf8_lossy(&stderr).to_string());
+
+        return Err(comp_err);
+    }
+
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Whole output of a pipe, its lines pushed to the log as they come if given
+async fn read_lines(pipe: Option<impl AsyncRead + Unpin>, log: Option<&BuildLog>) -> Vec<u8> {
+    let mut output = Vec::new();
+    let Some(pipe) = pipe else {
+        return output;
+    };
+
+    // split rather than lines, a tool may print something else than UTF-8
+    let mut lines = BufReader::new(pipe).split(b'\n');
+    while let Ok(Some(line)) = lines.next_segment().await {
+        if let Some(log) = log {
+            let text = String::from_utf8_lossy(&line).trim_end().to_string();
+            log.send_modify(|lines| lines.push(text));
+        }
+
+        output.extend(line);
+        output.push(b'\n');
+    }
+
+    output
+}
+
+async fn compile_code(
     id: &str,
     req: &CompilationRequest,
+    toolchains: &ToolchainConfig,
+    sdk_path: Option<&str>,
     build_dir: impl AsRef<Path>,
     result_dir: impl AsRef<Path>,
+    ctx: &BuildContext,
 ) -> Result<(), CompileError> {
-    if req.source.len() > 1 {
-        return Err(CompileError::UnsupportedMultipleFiles);
+    if req.source.is_empty() {
+        return Err(CompileError::NoCode);
     }
 
-    let Some(code) = req.source.iter().next() else {
-        return Err(CompileError::NoCode);
-    };
+    if let Some(file) = req.source.iter().find(|file| !file.is_valid_path()) {
+        return Err(CompileError::InvalidFileName(file.filename.clone()));
+    }
 
-    if code.filename != "main.c" {
-        return Err(CompileError::UnsupportedFileName);
+    let has_file = |name: &str| req.source.iter().any(|file| file.filename == name);
+    let pico_sdk = matches!(req.lang, Language::C | Language::Cpp | Language::Assembly);
+    let custom_cmake = pico_sdk && has_file("CMakeLists.txt");
+
+    if !custom_cmake && !has_file(req.lang.filename()) {
+        return Err(CompileError::MissingMainFile(req.lang.filename()));
+    }
+
+    if !pico_sdk && !req.libraries.is_empty() {
+        return Err(CompileError::UnsupportedLibraries(req.lang));
+    }
+
+    // `execute_process` and `build.rs` run on the server, confined by the sandbox only
+    if ctx.limits.sandbox.is_empty() {
+        if custom_cmake {
+            return Err(CompileError::UnsandboxedBuildFile("CMakeLists.txt"));
+        }
+
+        if req.lang == Language::Rust && has_file("Cargo.toml") {
+            return Err(CompileError::UnsandboxedBuildFile("Cargo.toml"));
+        }
     }
 
+    let libraries = libraries::cmake_script(&req.libraries, toolchains)?;
     let build_dir = build_dir.as_ref();
     let result_dir = result_dir.as_ref();
 
-    let path = build_dir.join(&code.filename);
-    let build_path = build_dir.join("build");
-    let uf2_path = result_dir.join(format!("{}.uf2", id));
-    let dis_path = result_dir.join(format!("{}.dis", id));
-    fs::write(path, &code.code).await?;
+    let prefix = toolchains.prefix(req.target);
 
-    let mut cmd = Command::new("make");
-    cmd.current_dir(&build_path);
+    // the map of the linker, for the builds which make one
+    let map = match req.lang {
+        _ if custom_cmake => {
+            let project = build_dir.join("project");
+            write_sources(&project, &req.source).await?;
 
-    let Ok(process) = cmd.output().await else {
-        let comp_err =
-            CompileError::CompilationError("Failed to start the compilation process".to_string());
+            if !has_file("pico_sdk_import.cmake") {
+                fs::write(project.join("pico_sdk_import.cmake"), TOOLCHAIN_FILE).await?;
+            }
 
-        return Err(comp_err);
+            // for the CMakeLists to include, like the one of the server
+            fs::write(project.join(LIBRARIES_FILE), libraries).await?;
+
+            // configured from scratch, the project may differ from the last one
+            let build_path = project.join("build");
+            fs::create_dir(&build_path).await?;
+            cmake(&build_path, &project, req.target, sdk_path, ctx).await?;
+
+            compile_pico_sdk(id, &build_path, result_dir, ctx).await?;
+            Some(build_path.join("main.elf.map"))
+        }
+        Language::C | Language::Cpp | Language::Assembly => {
+            write_sources(&build_dir.join("src"), &req.source).await?;
+            write_if_changed(&build_dir.join(LIBRARIES_FILE), &libraries).await?;
+            let build_path = build_dir.join(pico_sdk_build_dir(req.target));
+            compile_pico_sdk(id, &build_path, result_dir, ctx).await?;
+            Some(build_path.join("main.elf.map"))
+        }
+        Language::Rust => {
+            let Some(rust) = &toolchains.rust else {
+                return Err(CompileError::UnsupportedLanguage(req.lang));
+            };
+
+            let elf = compile_rust(&req.source, rust, req.target, build_dir, ctx).await?;
+            let uf2 = elf_to_uf2(prefix, req.target, &elf, ctx).await?;
+            fs::write(result_dir.join(format!("{}.uf2", id)), uf2).await?;
+            save_elf(id, prefix, &elf, result_dir, ctx).await?;
+            None
+        }
+        Language::MicroPython => {
+            let Some(micropython) = &toolchains.micropython else {
+                return Err(CompileError::UnsupportedLanguage(req.lang));
+            };
+
+            // the port makes its own uf2 of the firmware
+            let dir = build_dir.join("micropython");
+            write_sources(&dir.join("src"), &req.source).await?;
+            run(
+                &mut micropython_make(micropython, &dir, req.target),
+                ctx,
+                true,
+            )
+            .await?;
+
+            let build = dir.join(micropython_build_dir(req.target));
+            fs::copy(
+                build.join("firmware.uf2"),
+                result_dir.join(format!("{}.uf2", id)),
+            )
+            .await?;
+            save_elf(id, prefix, &build.join("firmware.elf"), result_dir, ctx).await?;
+            Some(build.join("firmware.elf.map"))
+        }
     };
 
-    if !process.status.success() {
-        let comp_err =
-            CompileError::CompilationError(String::from_utf8_lossy(&process.stderr).to_string());
+    save_artifacts(id, req.artifacts, prefix, map.as_deref(), result_dir, ctx).await
+}
 
-        return Err(comp_err);
+/// Keep the optional artifacts asked for, next to the ELF file of the result
+async fn save_artifacts(
+    id: &str,
+    artifacts: Artifacts,
+    prefix: &str,
+    map: Option<&Path>,
+    result_dir: &Path,
+    ctx: &BuildContext,
+) -> Result<(), CompileError> {
+    if let (true, Some(map)) = (artifacts.map, map) {
+        // a custom CMakeLists may not make one
+        if fs::try_exists(map).await? {
+            fs::copy(map, result_dir.join(format!("{}.map", id))).await?;
+        }
+    }
+
+    if artifacts.symbols {
+        let elf = result_dir.join(format!("{}.elf", id));
+        let nm = run(
+            Command::new(format!("{prefix}nm"))
+                .args(["--defined-only", "--numeric-sort", "--print-size"])
+                .arg(elf),
+            ctx,
+            false,
+        )
+        .await?;
+
+        fs::write(result_dir.join(format!("{}.sym", id)), nm.stdout).await?;
     }
 
+    Ok(())
+}
+
+/// Symbols listed by nm with their size, the lines of the symbols without one have no
+/// size column
+fn parse_symbols(nm: &str) -> Vec<ElfSymbol> {
+    nm.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (address, size, kind, name) = match fields[..] {
+                [address, size, kind, name] => (address, size, kind, name),
+                [address, kind, name] => (address, "0", kind, name),
+                _ => return None,
+            };
+
+            let kind = match kind {
+                "T" | "t" => ElfSymbolKind::Function,
+                "D" | "d" | "B" | "b" | "R" | "r" | "G" | "g" | "S" | "s" => ElfSymbolKind::Object,
+                _ => ElfSymbolKind::Other,
+            };
+
+            let address = u32::from_str_radix(address, 16).ok()?;
+            Some(ElfSymbol {
+                name: name.to_string(),
+                // the Arm functions have the Thumb bit set
+                address: match kind {
+                    ElfSymbolKind::Function => address & !1,
+                    _ => address,
+                },
+                size: u32::from_str_radix(size, 16).ok()?,
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Build a configured pico-sdk project, its `main` executable is the program
+async fn compile_pico_sdk(
+    id: &str,
+    build_path: &Path,
+    result_dir: &Path,
+    ctx: &BuildContext,
+) -> Result<(), CompileError> {
+    let uf2_path = result_dir.join(format!("{}.uf2", id));
+    let dis_path = result_dir.join(format!("{}.dis", id));
+    let elf_path = result_dir.join(format!("{}.elf", id));
+
+    run(Command::new("make").current_dir(build_path), ctx, true).await?;
+
     log::info!("Compilation successful");
     fs::rename(build_path.join("main.uf2"), uf2_path.clone()).await?;
     fs::rename(build_path.join("main.dis"), dis_path.clone()).await?;
+    fs::rename(build_path.join("main.elf"), elf_path.clone()).await?;
     Ok(())
 }
+
+/// Build the code in the cargo project, returns the path of the executable. The files
+/// replace its `src`, and a Cargo.toml of the project the one of the template.
+async fn compile_rust(
+    files: &[SourceCode],
+    rust: &RustToolchain,
+    target: Target,
+    build_dir: &Path,
+    ctx: &BuildContext,
+) -> Result<PathBuf, CompileError> {
+    let project = build_dir.join("rust");
+    let (manifest, sources): (Vec<_>, Vec<_>) =
+        files.iter().partition(|file| file.filename == "Cargo.toml");
+
+    write_sources(&project.join("src"), sources).await?;
+
+    match manifest.first() {
+        Some(manifest) => fs::write(project.join("Cargo.toml"), &manifest.code).await?,
+        None => {
+            fs::copy(
+                Path::new(&rust.project).join("Cargo.toml"),
+                project.join("Cargo.toml"),
+            )
+            .await?;
+        }
+    }
+
+    // the diagnostics are rendered on stderr, stdout has the JSON messages
+    let process = run(
+        Command::new(&rust.cargo).current_dir(&project).args([
+            "build",
+            "--release",
+            "--message-format=json-render-diagnostics",
+            "--target",
+            rust.triple(target),
+        ]),
+        ctx,
+        false,
+    )
+    .await?;
+
+    log::info!("Compilation successful");
+
+    // the messages of cargo, the executable is the last artifact built
+    String::from_utf8_lossy(&process.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|message| message.get("executable")?.as_str().map(PathBuf::from))
+        .next_back()
+        .ok_or_else(|| CompileError::CompilationError("No executable was built".to_string()))
+}
+
+fn micropython_build_dir(target: Target) -> &'static str {
+    match target {
+        Target::RiscV => "build",
+        Target::Arm => "build-arm",
+    }
+}
+
+/// make of the rp2 port of MicroPython, with main.py of the directory frozen in
+fn micropython_make(micropython: &MicroPythonToolchain, dir: &Path, target: Target) -> Command {
+    let mut cmd = Command::new("make");
+    let build = dir.join(micropython_build_dir(target));
+
+    cmd.current_dir(Path::new(&micropython.path).join("ports").join("rp2"))
+        .arg(format!("BOARD={}", micropython.board))
+        .arg(format!(
+            "BOARD_VARIANT={}",
+            micropython.board_variant(target)
+        ))
+        .arg(format!("BUILD={}", build.display()))
+        .arg(format!(
+            "FROZEN_MANIFEST={}",
+            dir.join("manifest.py").display()
+        ));
+
+    cmd
+}
+
+/// Flat image of the ELF file from the start of the flash, as an uf2 file
+async fn elf_to_uf2(
+    prefix: &str,
+    target: Target,
+    elf: &Path,
+    ctx: &BuildContext,
+) -> Result<Vec<u8>, CompileError> {
+    let bin = elf.with_extension("bin");
+
+    run(
+        Command::new(format!("{prefix}objcopy"))
+            .args(["-O", "binary"])
+            .arg(elf)
+            .arg(&bin),
+        ctx,
+        true,
+    )
+    .await?;
+
+    let image = fs::read(&bin).await?;
+    if image.len() > FLASH_SIZE {
+        return Err(CompileError::CompilationError(
+            "The program does not fit in the flash".to_string(),
+        ));
+    }
+
+    let family = match target {
+        Target::RiscV => FamilyId::Rp2350RiscV,
+        Target::Arm => FamilyId::Rp2350ArmS,
+    };
+
+    Ok(uf2::write_uf2(&image, FLASH_BASE, family))
+}
+
+/// Keep the ELF file of a build and its disassembly, like the pico-sdk does
+async fn save_elf(
+    id: &str,
+    prefix: &str,
+    elf: &Path,
+    result_dir: &Path,
+    ctx: &BuildContext,
+) -> Result<(), CompileError> {
+    let objdump = format!("{prefix}objdump");
+    let headers = run(Command::new(&objdump).arg("-h").arg(elf), ctx, false).await?;
+    let code = run(Command::new(&objdump).arg("-d").arg(elf), ctx, false).await?;
+
+    let mut dis = headers.stdout;
+    dis.extend(code.stdout);
+
+    fs::write(result_dir.join(format!("{}.dis", id)), dis).await?;
+    fs::copy(elf, result_dir.join(format!("{}.elf", id))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(lang: Language, files: &[&str]) -> CompilationRequest {
+        CompilationRequest {
+            lang,
+            source: files
+                .iter()
+                .map(|filename| SourceCode {
+                    filename: filename.to_string(),
+                    code: String::new(),
+                })
+                .collect(),
+            target: Target::RiscV,
+            compiler_options: None,
+            libraries: Vec::new(),
+            artifacts: Default::default(),
+        }
+    }
+
+    async fn build(req: &CompilationRequest) -> Result<(), CompileError> {
+        let ctx = BuildContext {
+            log: watch::channel(Vec::new()).0,
+            limits: JobConfig::default(),
+        };
+
+        let dir = std::env::temp_dir().join("pico2-compile-missing");
+        let toolchains = ToolchainConfig::default();
+        compile_code("test", req, &toolchains, None, &dir, &dir, &ctx).await
+    }
+
+    #[tokio::test]
+    async fn test_build_files_need_a_sandbox() {
+        let cmake = request(Language::C, &["main.c", "CMakeLists.txt"]);
+        assert!(matches!(
+            build(&cmake).await,
+            Err(CompileError::UnsandboxedBuildFile("CMakeLists.txt"))
+        ));
+
+        let cargo = request(Language::Rust, &["main.rs", "Cargo.toml"]);
+        assert!(matches!(
+            build(&cargo).await,
+            Err(CompileError::UnsandboxedBuildFile("Cargo.toml"))
+        ));
+
+        // the files of the server build the others, without Rust set up here
+        let rust = request(Language::Rust, &["main.rs"]);
+        assert!(matches!(
+            build(&rust).await,
+            Err(CompileError::UnsupportedLanguage(Language::Rust))
+        ));
+    }
+}
@@ -4,6 +4,7 @@
  * @date 09/04/2025
  * @brief Configuration handling for the server.
  */
+use api_types::Target;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -13,6 +14,203 @@ pub struct ServerConfig {
     pub static_dir: String,
     pub data_dir: String,
     pub pico_sdk: Option<String>,
+    pub toolchains: ToolchainConfig,
+    pub jobs: JobConfig,
+    pub projects: ProjectConfig,
+    pub access: AccessConfig,
+}
+
+/// Workers building the requests side by side, and the limits of their builds
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JobConfig {
+    pub workers: usize,
+    /// Requests waiting at most, the next ones are turned down
+    pub queue_capacity: usize,
+    pub timeout_secs: u64,
+    /// Address space of each process of a build in MiB, set with prlimit
+    pub memory_mb: Option<u64>,
+    /// CPU time of each process of a build in seconds, set with prlimit
+    pub cpu_secs: Option<u64>,
+    /// Command the steps of a build run in, e.g. `["firejail", "--quiet", "--net=none"]`.
+    /// The projects with their own CMakeLists.txt or Cargo.toml are turned down without it
+    pub sandbox: Vec<String>,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            queue_capacity: 64,
+            timeout_secs: 300,
+            memory_mb: None,
+            cpu_secs: None,
+            sandbox: Vec::new(),
+        }
+    }
+}
+
+/// Who may compile and how often, the public servers are open to anyone
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AccessConfig {
+    /// Keys accepted in the `X-Api-Key` header of the compile requests, none asked if empty
+    pub api_keys: Vec<String>,
+    /// Compile requests of an IP per minute on average, unlimited if 0
+    pub requests_per_minute: u32,
+    /// Requests an IP may send in a row before the rate applies
+    pub burst: u32,
+    /// Take the IP of the client from the last entry of `X-Forwarded-For`, the one added
+    /// by the reverse proxy, behind a single reverse proxy only
+    pub trust_forwarded: bool,
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            api_keys: Vec::new(),
+            requests_per_minute: 10,
+            burst: 5,
+            trust_forwarded: false,
+        }
+    }
+}
+
+/// Projects shared by a link, stored apart from the data directory which is wiped on start
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    pub dir: String,
+    /// Largest project accepted, its files and its breadboard
+    pub max_size_kb: usize,
+    /// Projects stored at most, the next saves are turned down
+    pub max_projects: usize,
+    /// Size of all the projects stored at most
+    pub max_total_mb: usize,
+    /// Days a project is kept after it is saved, forever if unset
+    pub expiry_days: Option<u64>,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            dir: String::from("./projects"),
+            max_size_kb: 512,
+            max_projects: 10_000,
+            max_total_mb: 1024,
+            expiry_days: None,
+        }
+    }
+}
+
+/// Toolchains of the languages, C, C++ and assembly only need the pico-sdk
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ToolchainConfig {
+    /// Prefix of the binutils of each target, for the languages not built by the pico-sdk
+    pub riscv_prefix: String,
+    pub arm_prefix: String,
+    pub rust: Option<RustToolchain>,
+    pub micropython: Option<MicroPythonToolchain>,
+    /// A FreeRTOS-Kernel checkout with the RP2350 ports, offered as the `FreeRTOS` library
+    pub freertos_kernel: Option<String>,
+    /// Third-party libraries which the pico-sdk projects may link
+    pub libraries: Vec<LibraryConfig>,
+}
+
+impl Default for ToolchainConfig {
+    fn default() -> Self {
+        Self {
+            riscv_prefix: String::from("riscv32-corev-elf-"),
+            arm_prefix: String::from("arm-none-eabi-"),
+            rust: None,
+            micropython: None,
+            freertos_kernel: None,
+            libraries: Vec::new(),
+        }
+    }
+}
+
+impl ToolchainConfig {
+    pub fn prefix(&self, target: Target) -> &str {
+        match target {
+            Target::RiscV => &self.riscv_prefix,
+            Target::Arm => &self.arm_prefix,
+        }
+    }
+}
+
+/// A cargo project for the RP2350 RISC-V cores, with embassy-rp or rp235x-hal. It is
+/// copied to the build directory and its `src/main.rs` replaced by the code.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RustToolchain {
+    pub project: String,
+    pub cargo: String,
+    pub target: String,
+    pub arm_target: String,
+}
+
+impl Default for RustToolchain {
+    fn default() -> Self {
+        Self {
+            project: String::from("./rust-template"),
+            cargo: String::from("cargo"),
+            target: String::from("riscv32imac-unknown-none-elf"),
+            arm_target: String::from("thumbv8m.main-none-eabihf"),
+        }
+    }
+}
+
+impl RustToolchain {
+    /// Target triple of cargo for a core
+    pub fn triple(&self, target: Target) -> &str {
+        match target {
+            Target::RiscV => &self.target,
+            Target::Arm => &self.arm_target,
+        }
+    }
+}
+
+/// A MicroPython checkout, the script is frozen into the firmware of its rp2 port
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MicroPythonToolchain {
+    pub path: String,
+    pub board: String,
+    pub variant: String,
+    /// Variant of the board for the Arm cores, its default one
+    pub arm_variant: String,
+}
+
+impl Default for MicroPythonToolchain {
+    fn default() -> Self {
+        Self {
+            path: String::from("./micropython"),
+            board: String::from("RPI_PICO2"),
+            variant: String::from("RISCV"),
+            arm_variant: String::new(),
+        }
+    }
+}
+
+impl MicroPythonToolchain {
+    pub fn board_variant(&self, target: Target) -> &str {
+        match target {
+            Target::RiscV => &self.variant,
+            Target::Arm => &self.arm_variant,
+        }
+    }
+}
+
+/// A CMake library, added as a subdirectory of the projects which ask for it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LibraryConfig {
+    pub name: String,
+    pub path: String,
+    /// CMake targets linked to the program, the name of the library if empty
+    #[serde(default)]
+    pub targets: Vec<String>,
 }
 
 impl Default for ServerConfig {
@@ -23,6 +221,10 @@ impl Default for ServerConfig {
             static_dir: String::from("./static"),
             data_dir: String::from("./data"),
             pico_sdk: None,
+            toolchains: ToolchainConfig::default(),
+            jobs: JobConfig::default(),
+            projects: ProjectConfig::default(),
+            access: AccessConfig::default(),
         }
     }
 }
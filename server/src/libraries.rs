@@ -0,0 +1,99 @@
+/**
+ * @file libraries.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Libraries linked to the pico-sdk projects on request, from an allowlist.
+ */
+use crate::compile::CompileError;
+use crate::config::ToolchainConfig;
+
+/// Name of the FreeRTOS kernel in the requests, with the heap_4 allocator
+pub const FREERTOS: &str = "FreeRTOS";
+
+/// Components of the pico-sdk which can be asked for, the CMakeLists links a few already
+pub const PICO_SDK_LIBRARIES: &[&str] = &[
+    "hardware_adc",
+    "hardware_clocks",
+    "hardware_dma",
+    "hardware_exception",
+    "hardware_flash",
+    "hardware_i2c",
+    "hardware_interp",
+    "hardware_irq",
+    "hardware_pio",
+    "hardware_powman",
+    "hardware_pwm",
+    "hardware_sha256",
+    "hardware_spi",
+    "hardware_sync",
+    "hardware_timer",
+    "hardware_uart",
+    "hardware_watchdog",
+    "hardware_xosc",
+    "pico_aon_timer",
+    "pico_async_context_poll",
+    "pico_multicore",
+    "pico_rand",
+    "pico_sha256",
+    "pico_stdlib",
+    "pico_unique_id",
+    "pico_util",
+    "tinyusb_board",
+    "tinyusb_device",
+];
+
+/// Names of the libraries this server lets the projects link
+pub fn available(toolchains: &ToolchainConfig) -> Vec<String> {
+    let mut names: Vec<String> = PICO_SDK_LIBRARIES.iter().map(|s| s.to_string()).collect();
+
+    if toolchains.freertos_kernel.is_some() {
+        names.push(FREERTOS.to_string());
+    }
+
+    names.extend(toolchains.libraries.iter().map(|lib| lib.name.clone()));
+    names
+}
+
+/// CMake script of the libraries of a request, included by the CMakeLists after
+/// `pico_sdk_init`. It sets `PROJECT_LIBRARIES` for `target_link_libraries` and
+/// `FREERTOS_KERNEL_PATH` when FreeRTOS is asked for. The names are sorted, the same
+/// set gives the same script and the build is not configured again.
+pub fn cmake_script(
+    names: &[String],
+    toolchains: &ToolchainConfig,
+) -> Result<String, CompileError> {
+    let mut names = names.to_vec();
+    names.sort();
+    names.dedup();
+
+    let mut linked = Vec::new();
+    let mut script = String::from("# Libraries of the project, written by the server\n");
+
+    for name in &names {
+        if PICO_SDK_LIBRARIES.contains(&name.as_str()) {
+            linked.push(name.clone());
+        } else if name == FREERTOS {
+            let Some(path) = &toolchains.freertos_kernel else {
+                return Err(CompileError::UnknownLibrary(name.clone()));
+            };
+
+            script.push_str(&format!("set(FREERTOS_KERNEL_PATH {path})\n"));
+        } else if let Some(lib) = toolchains.libraries.iter().find(|lib| &lib.name == name) {
+            // built in the build directory of the project, kept between the requests
+            script.push_str(&format!(
+                "add_subdirectory({} ${{CMAKE_BINARY_DIR}}/libraries/{})\n",
+                lib.path, lib.name
+            ));
+
+            match lib.targets.is_empty() {
+                true => linked.push(lib.name.clone()),
+                false => linked.extend(lib.targets.iter().cloned()),
+            }
+        } else {
+            return Err(CompileError::UnknownLibrary(name.clone()));
+        }
+    }
+
+    script.push_str(&format!("set(PROJECT_LIBRARIES {})\n", linked.join(" ")));
+    Ok(script)
+}
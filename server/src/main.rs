@@ -4,17 +4,25 @@
  * @date 09/04/2025
  * @brief Main entry point for the server.
  */
-use api_types::{CompilationRequest, CompilationStatusRequest};
+use api_types::{
+    CompilationEvent, CompilationRequest, CompilationStatusRequest, ProjectResponse, SharedProject,
+};
+use futures::StreamExt;
 use std::net;
 use std::sync::Arc;
 use tokio::fs;
-use tokio::sync::Mutex;
 use warp::Filter;
 
+mod access;
 mod compile;
 mod config;
+mod libraries;
+mod metrics;
+mod projects;
 
+use access::AccessControl;
 use compile::*;
+use projects::ProjectStore;
 
 const CONFIG_PATH: &str = "config.toml";
 
@@ -72,15 +80,27 @@ async fn main() -> anyhow::Result<()> {
     let ip_address: net::IpAddr = config.ip.parse().expect("Invalid IP address");
 
     let compiler = Compiler::new(&config).await?;
-    let compiler = Arc::new(Mutex::new(compiler));
+    // shared as is, the requests are queued for the workers of the compiler
+    let compiler = Arc::new(compiler);
     let compiler_clone = compiler.clone();
+    let libraries_compiler = compiler.clone();
+    let events_compiler = compiler.clone();
+    let health_compiler = compiler.clone();
+    let metrics_compiler = compiler.clone();
 
-    // Compile endpoint
+    let projects = Arc::new(ProjectStore::new(&config.projects).await?);
+    let projects_clone = projects.clone();
+
+    let access = Arc::new(AccessControl::new(&config.access));
+
+    // Compile endpoint, behind the rate limit and the API keys
     let compile_route = warp::path("compile")
         .and(warp::post())
+        .and(access.clone().guard())
         .and(warp::body::json())
         .and(warp::any().map(move || compiler_clone.clone()))
-        .and_then(compile_handler);
+        .and_then(compile_handler)
+        .recover(access::handle_rejection);
 
     // Result endpoint
     let result_route = warp::path("result")
@@ -89,6 +109,46 @@ async fn main() -> anyhow::Result<()> {
         .and(warp::any().map(move || compiler.clone()))
         .and_then(result_handler);
 
+    // Events endpoint, the progress of a compilation
+    let events_route = warp::path!("events" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || events_compiler.clone()))
+        .and_then(events_handler);
+
+    // Libraries endpoint
+    let libraries_route = warp::path("libraries")
+        .and(warp::get())
+        .and(warp::any().map(move || libraries_compiler.clone()))
+        .and_then(libraries_handler);
+
+    // Health endpoint, for the load balancers and the uptime checks
+    let health_route = warp::path!("health")
+        .and(warp::get())
+        .and(warp::any().map(move || health_compiler.clone()))
+        .and_then(health_handler);
+
+    // Prometheus endpoint, outside of the API like the usual exporters
+    let metrics_route = warp::path!("metrics")
+        .and(warp::get())
+        .and(warp::any().map(move || metrics_compiler.clone()))
+        .and_then(metrics_handler);
+
+    // Project endpoints, to save a workspace and load it back by its ID. The saves
+    // take from the same limit as the compile requests
+    let save_project_route = warp::path!("project")
+        .and(warp::post())
+        .and(access.guard())
+        .and(warp::body::content_length_limit(projects.max_size() as u64))
+        .and(warp::body::json())
+        .and(warp::any().map(move || projects_clone.clone()))
+        .and_then(save_project_handler)
+        .recover(access::handle_rejection);
+
+    let load_project_route = warp::path!("project" / String)
+        .and(warp::get())
+        .and(warp::any().map(move || projects.clone()))
+        .and_then(load_project_handler);
+
     // Logger middleware
     let logger = warp::any().map(warp::reply).with(warp::log("server"));
 
@@ -97,10 +157,18 @@ async fn main() -> anyhow::Result<()> {
     let index = warp::path::end().and(index_file);
 
     // Combine API routes
-    let api = warp::path("api").and(compile_route.or(result_route));
+    let api = warp::path("api").and(
+        compile_route
+            .or(result_route)
+            .or(events_route)
+            .or(libraries_route)
+            .or(health_route)
+            .or(save_project_route)
+            .or(load_project_route),
+    );
 
     // Combine all routes
-    let routes = index.or(static_files).or(api).or(logger);
+    let routes = index.or(metrics_route).or(static_files).or(api).or(logger);
 
     // Start the server
     warp::serve(routes).run((ip_address, config.port)).await;
@@ -109,20 +177,101 @@ async fn main() -> anyhow::Result<()> {
 
 async fn compile_handler(
     request: CompilationRequest,
-    compiler: Arc<Mutex<Compiler>>,
+    compiler: Arc<Compiler>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut compiler = compiler.lock().await;
     let result = compiler.compile(request).await;
-    drop(compiler); // Release the lock before sending the response
     Ok(warp::reply::json(&result))
 }
 
+/// Server-sent events of a request, the lines of its build then its result
+async fn events_handler(
+    id: String,
+    compiler: Arc<Compiler>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let Some(mut log) = compiler.log(&id).await else {
+        return Err(warp::reject::not_found());
+    };
+
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+
+    tokio::spawn(async move {
+        let mut sent = 0;
+
+        loop {
+            let lines = log.borrow_and_update()[sent..].to_vec();
+            sent += lines.len();
+
+            for line in lines {
+                let _ = tx.unbounded_send(CompilationEvent::Log { line });
+            }
+
+            // the build is over, its last lines were sent above
+            if log.changed().await.is_err() && log.borrow()[sent..].is_empty() {
+                break;
+            }
+        }
+
+        let result = compiler.get_result(&id).await;
+        let _ = tx.unbounded_send(CompilationEvent::Finished(result));
+    });
+
+    let events = rx.map(|event| warp::sse::Event::default().json_data(event));
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(events)))
+}
+
+async fn libraries_handler(compiler: Arc<Compiler>) -> Result<impl warp::Reply, warp::Rejection> {
+    let libraries = compiler.libraries();
+    Ok(warp::reply::json(&libraries))
+}
+
+async fn health_handler(compiler: Arc<Compiler>) -> Result<impl warp::Reply, warp::Rejection> {
+    let health = compiler.health().await;
+    Ok(warp::reply::json(&health))
+}
+
+async fn metrics_handler(compiler: Arc<Compiler>) -> Result<impl warp::Reply, warp::Rejection> {
+    let metrics = compiler.metrics().await;
+    Ok(warp::reply::with_header(
+        metrics,
+        "Content-Type",
+        "text/plain; version=0.0.4",
+    ))
+}
+
 async fn result_handler(
     request: CompilationStatusRequest,
-    compiler: Arc<Mutex<Compiler>>,
+    compiler: Arc<Compiler>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let mut compiler = compiler.lock().await;
     let result = compiler.get_result(&request.id).await;
-    drop(compiler); // Release the lock before sending the response
     Ok(warp::reply::json(&result))
 }
+
+async fn save_project_handler(
+    project: SharedProject,
+    projects: Arc<ProjectStore>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let response = match projects.save(&project).await {
+        Ok(id) => ProjectResponse::Saved { id },
+        Err(e) => {
+            log::error!("Failed to save a project: {}", e);
+            ProjectResponse::Error {
+                message: e.to_string(),
+            }
+        }
+    };
+
+    Ok(warp::reply::json(&response))
+}
+
+async fn load_project_handler(
+    id: String,
+    projects: Arc<ProjectStore>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match projects.load(&id).await {
+        Ok(project) => Ok(warp::reply::json(&project)),
+        Err(e) => {
+            log::warn!("Failed to load the project {}: {}", id, e);
+            Err(warp::reject::not_found())
+        }
+    }
+}
@@ -0,0 +1,237 @@
+/**
+ * @file metrics.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Counters of the compiler, for the health check and Prometheus.
+ */
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::compile::CompileError;
+
+/// Upper bounds of the buckets of the build durations, in seconds
+const DURATION_BUCKETS: [f64; 8] = [1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Kinds of the failed builds, the label of their counter
+const ERROR_KINDS: [&str; 4] = ["compilation", "timeout", "request", "internal"];
+
+/// State of the server for `/api/health`
+#[derive(Debug, Serialize)]
+pub struct Health {
+    pub status: &'static str,
+    pub uptime_secs: u64,
+    pub queue_depth: usize,
+    pub workers: usize,
+    pub busy_workers: usize,
+}
+
+#[derive(Default)]
+struct DurationHistogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+/// Counters updated by the compiler and its workers
+pub struct Metrics {
+    started: Instant,
+    requests: AtomicU64,
+    cache_hits: AtomicU64,
+    rejected: AtomicU64,
+    successes: AtomicU64,
+    errors: [AtomicU64; ERROR_KINDS.len()],
+    busy_workers: AtomicUsize,
+    durations: Mutex<DurationHistogram>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: Default::default(),
+            cache_hits: Default::default(),
+            rejected: Default::default(),
+            successes: Default::default(),
+            errors: Default::default(),
+            busy_workers: Default::default(),
+            durations: Default::default(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A request answered by the result of the same one
+    pub fn cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A request turned down by a full queue
+    pub fn rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn build_started(&self) {
+        self.busy_workers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn build_finished(&self, duration: Duration, result: &Result<(), CompileError>) {
+        self.busy_workers.fetch_sub(1, Ordering::Relaxed);
+
+        match result {
+            Ok(()) => self.successes.fetch_add(1, Ordering::Relaxed),
+            Err(e) => self.errors[error_kind(e)].fetch_add(1, Ordering::Relaxed),
+        };
+
+        let secs = duration.as_secs_f64();
+        let mut durations = self.durations.lock().unwrap();
+        durations.sum += secs;
+        durations.count += 1;
+
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| secs <= *bound) {
+            durations.buckets[bucket] += 1;
+        }
+    }
+
+    pub fn health(&self, queue_depth: usize, workers: usize) -> Health {
+        Health {
+            status: "ok",
+            uptime_secs: self.started.elapsed().as_secs(),
+            queue_depth,
+            workers,
+            busy_workers: self.busy_workers.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The counters in the text format of Prometheus
+    pub fn render(&self, queue_depth: usize, workers: usize) -> String {
+        let mut out = String::new();
+        let requests = self.requests.load(Ordering::Relaxed);
+        let cache_hits = self.cache_hits.load(Ordering::Relaxed);
+        let hit_ratio = match requests {
+            0 => 0.0,
+            _ => cache_hits as f64 / requests as f64,
+        };
+
+        let gauges: [(&str, &str, f64); 5] = [
+            (
+                "pico2_uptime_seconds",
+                "Seconds since the server started.",
+                self.started.elapsed().as_secs() as f64,
+            ),
+            (
+                "pico2_queue_depth",
+                "Requests waiting for a worker.",
+                queue_depth as f64,
+            ),
+            (
+                "pico2_workers",
+                "Workers building the requests.",
+                workers as f64,
+            ),
+            (
+                "pico2_busy_workers",
+                "Workers building a request right now.",
+                self.busy_workers.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "pico2_cache_hit_ratio",
+                "Share of the compile requests answered from the cache.",
+                hit_ratio,
+            ),
+        ];
+
+        let counters: [(&str, &str, u64); 4] = [
+            (
+                "pico2_compile_requests_total",
+                "Compile requests received.",
+                requests,
+            ),
+            (
+                "pico2_cache_hits_total",
+                "Compile requests answered by the result of the same request.",
+                cache_hits,
+            ),
+            (
+                "pico2_compile_rejected_total",
+                "Compile requests turned down by a full queue.",
+                self.rejected.load(Ordering::Relaxed),
+            ),
+            (
+                "pico2_builds_succeeded_total",
+                "Builds which made a program.",
+                self.successes.load(Ordering::Relaxed),
+            ),
+        ];
+
+        for (name, help, value) in gauges {
+            header(&mut out, name, "gauge", help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        for (name, help, value) in counters {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        let name = "pico2_builds_failed_total";
+        header(
+            &mut out,
+            name,
+            "counter",
+            "Builds which failed, by the kind of their error.",
+        );
+        for (kind, count) in ERROR_KINDS.iter().zip(&self.errors) {
+            let count = count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}{{kind=\"{kind}\"}} {count}");
+        }
+
+        let name = "pico2_build_duration_seconds";
+        let durations = self.durations.lock().unwrap();
+        let mut cumulative = 0;
+        header(
+            &mut out,
+            name,
+            "histogram",
+            "Time taken by the builds, from a worker taking them to their result.",
+        );
+
+        for (bound, count) in DURATION_BUCKETS.iter().zip(durations.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", durations.count);
+        let _ = writeln!(out, "{name}_sum {}", durations.sum);
+        let _ = writeln!(out, "{name}_count {}", durations.count);
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Index of the kind of an error in `ERROR_KINDS`
+fn error_kind(e: &CompileError) -> usize {
+    match e {
+        CompileError::CompilationError(_) => 0,
+        CompileError::Timeout(_) => 1,
+        CompileError::NoCode
+        | CompileError::InvalidFileName(_)
+        | CompileError::MissingMainFile(_)
+        | CompileError::UnsupportedLanguage(_)
+        | CompileError::UnknownLibrary(_)
+        | CompileError::UnsupportedLibraries(_)
+        | CompileError::UnsandboxedBuildFile(_) => 2,
+        CompileError::QueueFull | CompileError::FileSystemError(_) => 3,
+    }
+}
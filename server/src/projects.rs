@@ -0,0 +1,436 @@
+/**
+ * @file projects.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Projects saved under a short ID, to share a workspace by a link.
+ */
+use api_types::SharedProject;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use warp::reject::Reject;
+
+use crate::config::ProjectConfig;
+
+const ID_LEN: usize = 8;
+/// Attempts at a free ID, a collision of two is already unlikely
+const ID_ATTEMPTS: usize = 8;
+
+#[derive(Error, Debug)]
+pub enum ProjectError {
+    #[error("Project not found")]
+    NotFound,
+    #[error("The project is larger than {0} KiB")]
+    TooLarge(usize),
+    #[error("The server cannot store more projects")]
+    Full,
+    #[error("Invalid project ID")]
+    InvalidId,
+    #[error("Invalid project: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("File system error: {0}")]
+    FileSystemError(#[from] std::io::Error),
+}
+
+impl Reject for ProjectError {}
+
+/// Projects stored and their size, counted against the quota of the store
+#[derive(Debug, Default)]
+struct Usage {
+    projects: usize,
+    bytes: usize,
+}
+
+impl Usage {
+    fn remove(&mut self, size: usize) {
+        self.projects = self.projects.saturating_sub(1);
+        self.bytes = self.bytes.saturating_sub(size);
+    }
+}
+
+/// One JSON file per project in its own directory, kept across the restarts unlike
+/// the data directory. They expire by their modification time when the config says so.
+pub struct ProjectStore {
+    dir: PathBuf,
+    max_size: usize,
+    max_projects: usize,
+    max_bytes: usize,
+    expiry: Option<Duration>,
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl ProjectStore {
+    pub async fn new(config: &ProjectConfig) -> Result<Self, ProjectError> {
+        fs::create_dir_all(&config.dir).await?;
+        let dir = fs::canonicalize(&config.dir).await?;
+        let usage = usage(&dir).await?;
+
+        let res = Self {
+            dir,
+            max_size: config.max_size_kb * 1024,
+            max_projects: config.max_projects,
+            max_bytes: config.max_total_mb * 1024 * 1024,
+            expiry: config
+                .expiry_days
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+            usage: Arc::new(Mutex::new(usage)),
+        };
+
+        res.spawn_clean_up_task();
+        Ok(res)
+    }
+
+    /// Largest body of a project, in bytes
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Store a project, its ID is returned
+    pub async fn save(&self, project: &SharedProject) -> Result<String, ProjectError> {
+        let data = serde_json::to_vec(project)?;
+
+        if data.len() > self.max_size {
+            return Err(ProjectError::TooLarge(self.max_size / 1024));
+        }
+
+        self.reserve(data.len())?;
+
+        let result = self.store(&data).await;
+        if result.is_err() {
+            self.usage.lock().unwrap().remove(data.len());
+        }
+
+        result
+    }
+
+    /// Count a project of `size` bytes in the usage, unless it goes over the quota
+    fn reserve(&self, size: usize) -> Result<(), ProjectError> {
+        let mut usage = self.usage.lock().unwrap();
+
+        if usage.projects >= self.max_projects || usage.bytes + size > self.max_bytes {
+            return Err(ProjectError::Full);
+        }
+
+        usage.projects += 1;
+        usage.bytes += size;
+        Ok(())
+    }
+
+    /// Write a project under a free ID
+    async fn store(&self, data: &[u8]) -> Result<String, ProjectError> {
+        for _ in 0..ID_ATTEMPTS {
+            let id = nanoid::nanoid!(ID_LEN, &nanoid::alphabet::SAFE);
+            // reserves the ID, the content is moved over it once completely written
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.path(&id))
+                .await;
+
+            match file {
+                Ok(_) => {
+                    if let Err(e) = self.write(&id, data).await {
+                        let _ = fs::remove_file(self.path(&id)).await;
+                        return Err(e.into());
+                    }
+
+                    return Ok(id);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(std::io::Error::new(ErrorKind::AlreadyExists, "No free project ID").into())
+    }
+
+    /// Write the content of a project next to it then rename it, a failure never
+    /// leaves a truncated project behind
+    async fn write(&self, id: &str, data: &[u8]) -> std::io::Result<()> {
+        let temp = self.dir.join(format!("{id}.tmp"));
+
+        let result = async {
+            let mut file = fs::File::create(&temp).await?;
+            file.write_all(data).await?;
+            file.sync_all().await?;
+            fs::rename(&temp, self.path(id)).await
+        }
+        .await;
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp).await;
+        }
+
+        result
+    }
+
+    pub async fn load(&self, id: &str) -> Result<SharedProject, ProjectError> {
+        if !is_valid_id(id) {
+            return Err(ProjectError::InvalidId);
+        }
+
+        let path = self.path(id);
+        let metadata = match fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Err(ProjectError::NotFound),
+            Err(e) => return Err(e.into()),
+        };
+
+        // until the next clean up
+        if self.is_expired(metadata.modified()?) {
+            if fs::remove_file(&path).await.is_ok() {
+                self.usage.lock().unwrap().remove(metadata.len() as usize);
+            }
+            return Err(ProjectError::NotFound);
+        }
+
+        let data = fs::read(&path).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    fn is_expired(&self, modified: SystemTime) -> bool {
+        self.expiry
+            .is_some_and(|expiry| has_expired(modified, expiry))
+    }
+
+    fn spawn_clean_up_task(&self) {
+        let Some(expiry) = self.expiry else {
+            return;
+        };
+
+        let dir = self.dir.clone();
+        let usage = self.usage.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Ok(mut entries) = fs::read_dir(&dir).await {
+                    while let Ok(Some(entry)) = entries.next_entry().await {
+                        let Ok(metadata) = entry.metadata().await else {
+                            continue;
+                        };
+
+                        let expired = metadata
+                            .modified()
+                            .is_ok_and(|modified| has_expired(modified, expiry));
+
+                        if expired
+                            && fs::remove_file(entry.path()).await.is_ok()
+                            && is_project(&entry.path())
+                        {
+                            usage.lock().unwrap().remove(metadata.len() as usize);
+                        }
+                    }
+                }
+
+                tokio::time::sleep(Duration::from_secs(60 * 60)).await; // once an hour
+            }
+        });
+    }
+}
+
+/// The projects already in the directory, the temporary files of the saves aside
+async fn usage(dir: &Path) -> std::io::Result<Usage> {
+    let mut usage = Usage::default();
+    let mut entries = fs::read_dir(dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        if is_project(&entry.path()) {
+            usage.projects += 1;
+            usage.bytes += entry.metadata().await?.len() as usize;
+        }
+    }
+
+    Ok(usage)
+}
+
+fn is_project(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+fn has_expired(modified: SystemTime, expiry: Duration) -> bool {
+    modified.elapsed().is_ok_and(|age| age > expiry)
+}
+
+/// IDs are made of the nanoid alphabet only, they never leave the directory
+fn is_valid_id(id: &str) -> bool {
+    id.len() == ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api_types::{Language, SourceCode, Target};
+
+    fn project(code: &str) -> SharedProject {
+        SharedProject {
+            language: Language::C,
+            target: Target::RiscV,
+            files: vec![SourceCode {
+                filename: "main.c".to_string(),
+                code: code.to_string(),
+            }],
+            libraries: Vec::new(),
+            board: String::new(),
+        }
+    }
+
+    /// A store in its own directory, without the clean up task
+    fn store(name: &str, max_size: usize, expiry: Option<Duration>) -> ProjectStore {
+        let dir =
+            std::env::temp_dir().join(format!("pico2-projects-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        ProjectStore {
+            dir,
+            max_size,
+            max_projects: usize::MAX,
+            max_bytes: usize::MAX,
+            expiry,
+            usage: Default::default(),
+        }
+    }
+
+    fn files(store: &ProjectStore) -> Vec<String> {
+        std::fs::read_dir(&store.dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_id() {
+        assert!(is_valid_id("abcD_-09"));
+        assert!(!is_valid_id("abcD_-0"));
+        assert!(!is_valid_id("abcD_-090"));
+        assert!(!is_valid_id("../a.jso"));
+        assert!(!is_valid_id("ab/cd/ef"));
+        assert!(!is_valid_id("abc.json"));
+        assert!(!is_valid_id(""));
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let store = store("save", 1024, None);
+
+        let id = store.save(&project("int main() {}")).await.unwrap();
+        assert!(is_valid_id(&id));
+        assert_eq!(files(&store), [format!("{id}.json")]);
+
+        let loaded = store.load(&id).await.unwrap();
+        assert_eq!(loaded.files[0].code, "int main() {}");
+
+        assert!(matches!(
+            store.load("AAAAAAAA").await,
+            Err(ProjectError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal() {
+        let store = store("traversal", 1024, None);
+
+        // a project next to the directory of the store
+        let outside = store.dir.with_extension("json");
+        std::fs::write(&outside, serde_json::to_vec(&project("")).unwrap()).unwrap();
+        let name = outside.file_stem().unwrap().to_str().unwrap().to_string();
+
+        // the length alone does not stop them
+        for id in ["../../ab", "..\\..\\ab", "/etc/pwd"] {
+            assert_eq!(id.len(), ID_LEN);
+            assert!(matches!(store.load(id).await, Err(ProjectError::InvalidId)));
+        }
+
+        let id = format!("../{name}");
+        assert!(matches!(
+            store.load(&id).await,
+            Err(ProjectError::InvalidId)
+        ));
+
+        std::fs::remove_file(outside).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_too_large() {
+        let store = store("large", 2048, None);
+
+        let result = store.save(&project(&"x".repeat(4096))).await;
+        assert!(matches!(result, Err(ProjectError::TooLarge(2))));
+        assert!(files(&store).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quota() {
+        let mut store = store("quota", 1024, None);
+        store.max_projects = 2;
+
+        let first = store.save(&project("")).await.unwrap();
+        store.save(&project("")).await.unwrap();
+        assert!(matches!(
+            store.save(&project("")).await,
+            Err(ProjectError::Full)
+        ));
+        assert_eq!(files(&store).len(), 2);
+
+        // the projects found on start count too
+        let usage = usage(&store.dir).await.unwrap();
+        assert_eq!(usage.projects, 2);
+        assert_eq!(usage.bytes, store.usage.lock().unwrap().bytes);
+
+        // freed by an expired project
+        std::fs::remove_file(store.path(&first)).unwrap();
+        store.usage.lock().unwrap().remove(usage.bytes / 2);
+        store.save(&project("")).await.unwrap();
+
+        // the total size is bounded as well
+        let size = serde_json::to_vec(&project("")).unwrap().len();
+        store.max_projects = usize::MAX;
+        store.max_bytes = 4 * size;
+        store.save(&project("")).await.unwrap();
+        assert!(matches!(
+            store.save(&project("x")).await,
+            Err(ProjectError::Full)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_save() {
+        let store = store("failed", 1024, None);
+        let id = "AAAAAAAA";
+        std::fs::File::create(store.path(id)).unwrap();
+
+        // the temporary file cannot be created where a directory is
+        std::fs::create_dir(store.dir.join(format!("{id}.tmp"))).unwrap();
+        assert!(store.write(id, b"{}").await.is_err());
+        assert_eq!(std::fs::read(store.path(id)).unwrap(), b"");
+    }
+
+    #[tokio::test]
+    async fn test_expiry() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(has_expired(SystemTime::now() - 2 * day, day));
+        assert!(!has_expired(SystemTime::now(), day));
+
+        let store = store("expiry", 1024, Some(Duration::ZERO));
+        let id = store.save(&project("")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(matches!(store.load(&id).await, Err(ProjectError::NotFound)));
+        // removed on the way, and from the usage
+        assert!(files(&store).is_empty());
+        assert_eq!(store.usage.lock().unwrap().projects, 0);
+        assert_eq!(store.usage.lock().unwrap().bytes, 0);
+    }
+}
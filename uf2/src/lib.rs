@@ -4,8 +4,16 @@
  * @date: 08/04/2025
  * @brief: Library for UF2 parser
  */
+use std::io::Read;
 use thiserror::Error;
 
+pub const BLOCK_SIZE: usize = 512;
+
+const MAGIC_START0: u32 = 0x0A32_4655;
+const MAGIC_START1: u32 = 0x9E5D_5157;
+const MAGIC_END: u32 = 0x0AB1_6F30;
+const MAX_PAYLOAD_SIZE: usize = 476;
+
 #[derive(Debug, Clone)]
 pub struct Uf2Block {
     pub flags: u32,
@@ -23,8 +31,37 @@ impl Uf2Block {
     }
 }
 
+/// Same as `Uf2Block`, but the payload borrows the input
+#[derive(Debug, Clone, Copy)]
+pub struct Uf2BlockRef<'a> {
+    pub flags: u32,
+    pub target_addr: u32,
+    pub block_no: u32,
+    pub num_blocks: u32,
+    pub data: &'a [u8],
+    pub family_id: Option<u32>,
+}
+
+impl Uf2BlockRef<'_> {
+    /// Check if the block can be flashed to the target device
+    pub fn is_flashable(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    pub fn to_owned(&self) -> Uf2Block {
+        Uf2Block {
+            flags: self.flags,
+            target_addr: self.target_addr,
+            block_no: self.block_no,
+            num_blocks: self.num_blocks,
+            data: self.data.to_vec(),
+            family_id: self.family_id,
+        }
+    }
+}
+
 fn read_u32(data: &[u8], offset: usize) -> u32 {
-    // Since we use `chunks_exact(512)` and the max offset is 508
+    // Blocks are always 512 bytes long and the max offset is 508
     // we can safely assume that the data is at least 4 bytes long
 
     let mut value = 0u32;
@@ -37,51 +74,485 @@ fn read_u32(data: &[u8], offset: usize) -> u32 {
     value
 }
 
-#[derive(Debug, Error, Clone, Copy)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
     #[error("Invalid UF2 file")]
     InvalidUF2File,
+    #[error("Failed to read the UF2 file: {0}")]
+    Io(std::io::ErrorKind),
 }
 
-pub fn read_uf2(data: &[u8]) -> Result<impl Iterator<Item = Uf2Block>, Error> {
-    if data.len() % 512 != 0 {
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.kind())
+    }
+}
+
+/// Parse a single 512 bytes block, `None` if it is not a valid UF2 block
+pub fn parse_block(v: &[u8]) -> Option<Uf2BlockRef<'_>> {
+    if v.len() != BLOCK_SIZE {
+        return None;
+    }
+
+    let magic_start0 = read_u32(v, 0);
+    let magic_start1 = read_u32(v, 4);
+    let magic_end = read_u32(v, 508);
+
+    if (magic_start0, magic_start1, magic_end) != (MAGIC_START0, MAGIC_START1, MAGIC_END) {
+        return None;
+    }
+
+    let flags = read_u32(v, 8);
+    let target_addr = read_u32(v, 12);
+    let payload_size = read_u32(v, 16);
+    let block_no = read_u32(v, 20);
+    let num_blocks = read_u32(v, 24);
+
+    let family_id = if flags & 0x2000 != 0 {
+        Some(read_u32(v, 28))
+    } else {
+        None
+    };
+
+    let payload_size = (payload_size as usize).min(MAX_PAYLOAD_SIZE);
+
+    Some(Uf2BlockRef {
+        flags,
+        target_addr,
+        block_no,
+        num_blocks,
+        data: &v[32..32 + payload_size],
+        family_id,
+    })
+}
+
+/// Iterate over the blocks of an UF2 file without copying the payloads
+pub fn read_uf2_ref(data: &[u8]) -> Result<impl Iterator<Item = Uf2BlockRef<'_>>, Error> {
+    if data.len() % BLOCK_SIZE != 0 {
         return Err(Error::InvalidUF2File);
     }
 
-    Ok(data.chunks_exact(512).filter_map(|v| {
-        let magic_start0 = read_u32(v, 0);
-        let magic_start1 = read_u32(v, 4);
-        let magic_end = read_u32(v, 508);
+    Ok(data.chunks_exact(BLOCK_SIZE).filter_map(parse_block))
+}
 
-        if (magic_start0, magic_start1, magic_end) != (0x0A32_4655, 0x9E5D_5157, 0x0AB16F30) {
-            return None;
+pub fn read_uf2(data: &[u8]) -> Result<impl Iterator<Item = Uf2Block>, Error> {
+    Ok(read_uf2_ref(data)?.map(|block| block.to_owned()))
+}
+
+/// Streaming UF2 parser, only a single block is kept in memory at a time
+pub struct Uf2Reader<R: Read> {
+    reader: R,
+    buffer: [u8; BLOCK_SIZE],
+}
+
+impl<R: Read> Uf2Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: [0; BLOCK_SIZE],
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read the next valid block, the payload borrows the internal buffer.
+    /// Returns `Ok(None)` at the end of the input
+    pub fn next_block(&mut self) -> Result<Option<Uf2BlockRef<'_>>, Error> {
+        loop {
+            if !self.fill_buffer()? {
+                return Ok(None);
+            }
+
+            if parse_block(&self.buffer).is_some() {
+                break;
+            }
+        }
+
+        Ok(parse_block(&self.buffer))
+    }
+
+    /// Returns false if the input ended right at a block boundary
+    fn fill_buffer(&mut self) -> Result<bool, Error> {
+        let mut filled = 0;
+
+        while filled < BLOCK_SIZE {
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(Error::InvalidUF2File),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Iterator for Uf2Reader<R> {
+    type Item = Result<Uf2Block, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block()
+            .map(|block| block.map(|block| block.to_owned()))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FamilyId {
+    Rp2040,
+    /// Absolute blocks, flashed regardless of the running family
+    Rp2xxxAbsolute,
+    Rp2xxxData,
+    Rp2350ArmS,
+    Rp2350RiscV,
+    Rp2350ArmNs,
+}
+
+impl FamilyId {
+    pub const fn id(self) -> u32 {
+        match self {
+            Self::Rp2040 => 0xe48b_ff56,
+            Self::Rp2xxxAbsolute => 0xe48b_ff57,
+            Self::Rp2xxxData => 0xe48b_ff58,
+            Self::Rp2350ArmS => 0xe48b_ff59,
+            Self::Rp2350RiscV => 0xe48b_ff5a,
+            Self::Rp2350ArmNs => 0xe48b_ff5b,
         }
+    }
+
+    pub fn from_id(id: u32) -> Option<Self> {
+        [
+            Self::Rp2040,
+            Self::Rp2xxxAbsolute,
+            Self::Rp2xxxData,
+            Self::Rp2350ArmS,
+            Self::Rp2350RiscV,
+            Self::Rp2350ArmNs,
+        ]
+        .into_iter()
+        .find(|family| family.id() == id)
+    }
 
-        let flags = read_u32(v, 8);
-        let target_addr = read_u32(v, 12);
-        let payload_size = read_u32(v, 16);
-        let block_no = read_u32(v, 20);
-        let num_blocks = read_u32(v, 24);
+    fn is_rp2350(self) -> bool {
+        matches!(
+            self,
+            Self::Rp2350ArmS | Self::Rp2350RiscV | Self::Rp2350ArmNs
+        )
+    }
 
-        let family_id = if flags & 0x2000 != 0 {
-            Some(read_u32(v, 28))
-        } else {
-            None
+    /// Check if a block with the given family ID is meant for this target
+    pub fn accepts(self, family_id: Option<u32>) -> bool {
+        let Some(family) = family_id.and_then(Self::from_id) else {
+            return false;
         };
 
-        let data = v[32..508]
-            .into_iter()
-            .take(payload_size as usize)
-            .cloned()
-            .collect::<Vec<u8>>();
+        family == self
+            || (self.is_rp2350() && matches!(family, Self::Rp2xxxAbsolute | Self::Rp2xxxData))
+    }
+
+    /// Address ranges of the target which can be written by an UF2 block
+    fn writable_ranges(self) -> &'static [(u32, u32)] {
+        const RP2040: &[(u32, u32)] = &[
+            (0x1000_0000, 0x1100_0000), // XIP
+            (0x2000_0000, 0x2004_2000), // SRAM
+        ];
+
+        const RP2350: &[(u32, u32)] = &[
+            (0x1000_0000, 0x1100_0000), // XIP, first chip select
+            (0x2000_0000, 0x2008_2000), // SRAM
+        ];
+
+        match self {
+            Self::Rp2040 => RP2040,
+            _ => RP2350,
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnostic {
+    #[error("Block {index}: family ID {family_id:#x?} does not match the target")]
+    FamilyMismatch {
+        index: usize,
+        family_id: Option<u32>,
+    },
+
+    #[error("Block {index}: {len} bytes at {target_addr:#010x} are outside of the flash and SRAM")]
+    OutOfRange {
+        index: usize,
+        target_addr: u32,
+        len: usize,
+    },
 
-        Some(Uf2Block {
-            flags,
-            target_addr,
-            block_no,
+    #[error("Block {index}: {target_addr:#010x} overlaps with block {other}")]
+    Overlap {
+        index: usize,
+        other: usize,
+        target_addr: u32,
+    },
+
+    #[error("No block of the file is meant for the target")]
+    NoMatchingBlocks,
+}
+
+impl Diagnostic {
+    /// Blocks of another family are simply skipped,
+    /// anything else means the image cannot be flashed as is
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, Self::FamilyMismatch { .. })
+    }
+}
+
+/// Check every block of the file against the target family and its memory map.
+/// Blocks are indexed by their position in the file.
+pub fn validate_for_target(data: &[u8], target: FamilyId) -> Result<Vec<Diagnostic>, Error> {
+    if data.len() % BLOCK_SIZE != 0 {
+        return Err(Error::InvalidUF2File);
+    }
+
+    let mut diagnostics = Vec::new();
+    // (start, end, index) of the accepted blocks
+    let mut regions: Vec<(u64, u64, usize)> = Vec::new();
+
+    let blocks = data
+        .chunks_exact(BLOCK_SIZE)
+        .enumerate()
+        .filter_map(|(index, chunk)| Some((index, parse_block(chunk)?)));
+
+    for (index, block) in blocks {
+        if !target.accepts(block.family_id) {
+            diagnostics.push(Diagnostic::FamilyMismatch {
+                index,
+                family_id: block.family_id,
+            });
+            continue;
+        }
+
+        let start = block.target_addr as u64;
+        let end = start + block.data.len() as u64;
+
+        let in_range = target
+            .writable_ranges()
+            .iter()
+            .any(|&(low, high)| start >= low as u64 && end <= high as u64);
+
+        if !in_range {
+            diagnostics.push(Diagnostic::OutOfRange {
+                index,
+                target_addr: block.target_addr,
+                len: block.data.len(),
+            });
+            continue;
+        }
+
+        regions.push((start, end, index));
+    }
+
+    if regions.is_empty() {
+        diagnostics.push(Diagnostic::NoMatchingBlocks);
+    }
+
+    regions.sort_unstable();
+
+    for pair in regions.windows(2) {
+        let (_, prev_end, prev_index) = pair[0];
+        let (start, _, index) = pair[1];
+
+        if start < prev_end {
+            diagnostics.push(Diagnostic::Overlap {
+                index,
+                other: prev_index,
+                target_addr: start as u32,
+            });
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Payload of the blocks written, the size used by the pico-sdk tools
+const WRITE_PAYLOAD_SIZE: usize = 256;
+
+/// Build an UF2 file flashing a binary image at the given address
+pub fn write_uf2(data: &[u8], base_addr: u32, family: FamilyId) -> Vec<u8> {
+    let num_blocks = data.len().div_ceil(WRITE_PAYLOAD_SIZE) as u32;
+    let mut uf2 = Vec::with_capacity(num_blocks as usize * BLOCK_SIZE);
+
+    for (block_no, payload) in data.chunks(WRITE_PAYLOAD_SIZE).enumerate() {
+        let header = [
+            MAGIC_START0,
+            MAGIC_START1,
+            0x2000, // family ID present
+            base_addr + (block_no * WRITE_PAYLOAD_SIZE) as u32,
+            WRITE_PAYLOAD_SIZE as u32,
+            block_no as u32,
             num_blocks,
-            data,
-            family_id,
-        })
-    }))
+            family.id(),
+        ];
+
+        let mut block = [0u8; BLOCK_SIZE];
+        for (i, word) in header.iter().enumerate() {
+            block[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        block[32..32 + payload.len()].copy_from_slice(payload);
+        block[508..].copy_from_slice(&MAGIC_END.to_le_bytes());
+        uf2.extend_from_slice(&block);
+    }
+
+    uf2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLASH: u32 = 0x1000_0000;
+
+    fn image(len: usize) -> Vec<u8> {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        write_uf2(&data, FLASH, FamilyId::Rp2350RiscV)
+    }
+
+    #[test]
+    fn test_multi_block() {
+        let uf2 = image(600);
+
+        let blocks: Vec<_> = read_uf2_ref(&uf2).unwrap().collect();
+        assert_eq!(blocks.len(), 3);
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(block.block_no, i as u32);
+            assert_eq!(block.num_blocks, 3);
+            assert_eq!(block.target_addr, FLASH + 256 * i as u32);
+            assert_eq!(block.family_id, Some(FamilyId::Rp2350RiscV.id()));
+            assert_eq!(block.data[0], (256 * i) as u8);
+        }
+
+        let streamed: Vec<_> = Uf2Reader::new(uf2.as_slice())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(streamed.len(), 3);
+        assert_eq!(streamed[2].target_addr, blocks[2].target_addr);
+        assert_eq!(streamed[2].data, blocks[2].data);
+    }
+
+    #[test]
+    fn test_truncated_block() {
+        let mut uf2 = image(600);
+        uf2.truncate(uf2.len() - 100);
+
+        assert!(matches!(read_uf2_ref(&uf2), Err(Error::InvalidUF2File)));
+
+        // the complete blocks are still read before the error
+        let mut reader = Uf2Reader::new(uf2.as_slice());
+        assert_eq!(reader.next_block().unwrap().unwrap().block_no, 0);
+        assert_eq!(reader.next_block().unwrap().unwrap().block_no, 1);
+        assert_eq!(reader.next_block().unwrap_err(), Error::InvalidUF2File);
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let mut uf2 = image(600);
+        // second block
+        uf2[BLOCK_SIZE] ^= 0xff;
+
+        let blocks: Vec<_> = read_uf2_ref(&uf2).unwrap().map(|b| b.block_no).collect();
+        assert_eq!(blocks, [0, 2]);
+
+        let streamed: Vec<_> = Uf2Reader::new(uf2.as_slice())
+            .map(|block| block.unwrap().block_no)
+            .collect();
+        assert_eq!(streamed, [0, 2]);
+
+        assert!(parse_block(&uf2[BLOCK_SIZE..2 * BLOCK_SIZE]).is_none());
+    }
+
+    #[test]
+    fn test_out_of_order_blocks() {
+        let mut uf2 = image(512);
+        let (first, second) = uf2.split_at_mut(BLOCK_SIZE);
+        first.swap_with_slice(second);
+
+        // the blocks come in the order of the file, their address places the payload
+        let streamed: Vec<_> = Uf2Reader::new(uf2.as_slice())
+            .map(|block| block.unwrap())
+            .collect();
+        assert_eq!(streamed[0].block_no, 1);
+        assert_eq!(streamed[0].target_addr, FLASH + 256);
+        assert_eq!(streamed[1].block_no, 0);
+        assert_eq!(streamed[1].target_addr, FLASH);
+
+        let blocks: Vec<_> = read_uf2_ref(&uf2).unwrap().map(|b| b.block_no).collect();
+        assert_eq!(blocks, [1, 0]);
+    }
+
+    #[test]
+    fn test_family_accepts() {
+        let target = FamilyId::Rp2350RiscV;
+
+        assert!(target.accepts(Some(FamilyId::Rp2350RiscV.id())));
+        assert!(target.accepts(Some(FamilyId::Rp2xxxAbsolute.id())));
+        assert!(target.accepts(Some(FamilyId::Rp2xxxData.id())));
+        assert!(!target.accepts(Some(FamilyId::Rp2040.id())));
+        assert!(!target.accepts(Some(FamilyId::Rp2350ArmS.id())));
+        assert!(!target.accepts(Some(0x1234_5678)));
+        assert!(!target.accepts(None));
+
+        // the absolute blocks are for the RP2350 family only
+        assert!(!FamilyId::Rp2040.accepts(Some(FamilyId::Rp2xxxAbsolute.id())));
+    }
+
+    #[test]
+    fn test_validate_family() {
+        let data = [0u8; 16];
+
+        let uf2 = write_uf2(&data, FLASH, FamilyId::Rp2040);
+        assert_eq!(
+            validate_for_target(&uf2, FamilyId::Rp2350RiscV),
+            Ok(vec![
+                Diagnostic::FamilyMismatch {
+                    index: 0,
+                    family_id: Some(FamilyId::Rp2040.id()),
+                },
+                Diagnostic::NoMatchingBlocks,
+            ])
+        );
+
+        for family in [FamilyId::Rp2xxxAbsolute, FamilyId::Rp2350RiscV] {
+            let uf2 = write_uf2(&data, FLASH, family);
+            assert_eq!(validate_for_target(&uf2, FamilyId::Rp2350RiscV), Ok(vec![]));
+        }
+    }
+
+    #[test]
+    fn test_validate_range() {
+        let data = [0u8; 16];
+
+        // the last bytes of the SRAM
+        let uf2 = write_uf2(&data, 0x2008_2000 - 256, FamilyId::Rp2350RiscV);
+        assert_eq!(validate_for_target(&uf2, FamilyId::Rp2350RiscV), Ok(vec![]));
+
+        let uf2 = write_uf2(&data, 0x4000_0000, FamilyId::Rp2350RiscV);
+        let diagnostics = validate_for_target(&uf2, FamilyId::Rp2350RiscV).unwrap();
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic::OutOfRange {
+                index: 0,
+                target_addr: 0x4000_0000,
+                len: 256,
+            }
+        );
+        assert!(diagnostics[0].is_fatal());
+
+        // runs past the end of the SRAM
+        let uf2 = write_uf2(&data, 0x2008_2000 - 16, FamilyId::Rp2350RiscV);
+        assert!(matches!(
+            validate_for_target(&uf2, FamilyId::Rp2350RiscV).unwrap()[0],
+            Diagnostic::OutOfRange { .. }
+        ));
+    }
 }
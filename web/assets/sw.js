@@ -19,7 +19,17 @@ self.addEventListener('install', function (e) {
 self.addEventListener('fetch', function (e) {
   e.respondWith(
     caches.match(e.request).then(function (response) {
-      return response || fetch(e.request);
+      return response || fetch(e.request).then(function (fetched) {
+        /* The compiler of the browser is kept once loaded, to build offline */
+        if (fetched.ok && new URL(e.request.url).pathname.includes('/clang/')) {
+          var copy = fetched.clone();
+          caches.open(cacheName).then(function (cache) {
+            cache.put(e.request, copy);
+          });
+        }
+
+        return fetched;
+      });
     })
   );
 });
@@ -5,23 +5,40 @@
  * @brief API module to communicate with the server
  */
 use api_types::*;
+use futures::StreamExt;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{EventSource, MessageEvent};
 
-/// Represents the result of a compilation process.
-pub async fn compile(lang: Language, code: &str) -> Result<CompilationResponse, String> {
-    let compilation_request = CompilationRequest {
-        lang,
-        source: vec![SourceCode {
-            filename: "main.c".to_string(),
-            code: code.to_string(),
-        }],
-        target: Target::RiscV,
-        compiler_options: None,
-    };
+thread_local! {
+    /// Key sent with the compile requests and the project saves, for the servers which
+    /// ask for one
+    static API_KEY: RefCell<String> = RefCell::new(String::new());
+}
+
+pub fn set_api_key(key: &str) {
+    API_KEY.with_borrow_mut(|current| key.clone_into(current));
+}
+
+fn with_api_key(mut request: ehttp::Request) -> ehttp::Request {
+    let key = API_KEY.with_borrow(|key| key.clone());
+    if !key.is_empty() {
+        request.headers.insert("X-Api-Key", key);
+    }
 
+    request
+}
+
+/// Represents the result of a compilation process.
+pub async fn compile(
+    compilation_request: &CompilationRequest,
+) -> Result<CompilationResponse, String> {
     let request =
-        ehttp::Request::json("/api/compile", &compilation_request).map_err(|e| e.to_string())?;
+        ehttp::Request::json("/api/compile", compilation_request).map_err(|e| e.to_string())?;
 
-    ehttp::fetch_async(request)
+    ehttp::fetch_async(with_api_key(request))
         .await
         .map_err(|e| e.to_string())
         .and_then(|response| {
@@ -29,28 +46,112 @@ pub async fn compile(lang: Language, code: &str) -> Result<CompilationResponse,
                 response
                     .json::<CompilationResponse>()
                     .map_err(|e| e.to_string())
+            } else if let Ok(CompilationResponse::Error { message }) = response.json() {
+                // turned down for the rate limit or the API key
+                Err(message)
             } else {
                 Err(format!("Error: {}", response.status))
             }
         })
 }
 
-pub async fn compilation_result(id: &str) -> Result<CompilationResponse, String> {
-    let compilation_status_request = CompilationStatusRequest { id: id.to_string() };
+/// Libraries the server lets the projects link
+pub async fn libraries() -> Result<Vec<String>, String> {
+    ehttp::fetch_async(ehttp::Request::get("/api/libraries"))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|response| {
+            if response.ok {
+                response.json::<Vec<String>>().map_err(|e| e.to_string())
+            } else {
+                Err(format!("Error: {}", response.status))
+            }
+        })
+}
 
-    let request = ehttp::Request::json("/api/result", &compilation_status_request)
-        .map_err(|e| e.to_string())?;
+/// Save a project on the server, its ID is returned
+pub async fn save_project(project: &SharedProject) -> Result<String, String> {
+    let request = ehttp::Request::json("/api/project", project).map_err(|e| e.to_string())?;
 
-    ehttp::fetch_async(request)
+    let response = ehttp::fetch_async(with_api_key(request))
         .await
         .map_err(|e| e.to_string())
         .and_then(|response| {
             if response.ok {
                 response
-                    .json::<CompilationResponse>()
+                    .json::<ProjectResponse>()
                     .map_err(|e| e.to_string())
+            } else if let Ok(ProjectResponse::Error { message }) = response.json() {
+                // turned down for the rate limit or the API key
+                Err(message)
             } else {
                 Err(format!("Error: {}", response.status))
             }
+        })?;
+
+    match response {
+        ProjectResponse::Saved { id } => Ok(id),
+        ProjectResponse::Error { message } => Err(message),
+    }
+}
+
+/// A project shared by its ID
+pub async fn load_project(id: &str) -> Result<SharedProject, String> {
+    ehttp::fetch_async(ehttp::Request::get(format!("/api/project/{id}")))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|response| {
+            if response.ok {
+                response.json::<SharedProject>().map_err(|e| e.to_string())
+            } else if response.status == 404 {
+                Err(String::from("The project does not exist or has expired"))
+            } else {
+                Err(format!("Error: {}", response.status))
+            }
+        })
+}
+
+/// Follow a compilation until its end, the lines printed by the build are pushed to
+/// the log as they come
+pub async fn compilation_events(
+    id: &str,
+    log: &Rc<RefCell<Vec<String>>>,
+) -> Result<CompilationResponse, String> {
+    let source = EventSource::new(&format!("/api/events/{id}")).map_err(|e| format!("{e:?}"))?;
+    let (tx, mut rx) = futures::channel::mpsc::unbounded();
+
+    let on_message = {
+        let tx = tx.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if let Some(data) = event.data().as_string() {
+                let _ = tx.unbounded_send(Some(data));
+            }
         })
+    };
+
+    // also raised when the server closes the stream
+    let on_error = Closure::<dyn FnMut()>::new(move || {
+        let _ = tx.unbounded_send(None);
+    });
+
+    source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    let result = loop {
+        let Some(Some(data)) = rx.next().await else {
+            break Err(String::from("Lost the connection to the server"));
+        };
+
+        match serde_json::from_str::<CompilationEvent>(&data) {
+            Ok(CompilationEvent::Log { line }) => log.borrow_mut().push(line),
+            Ok(CompilationEvent::Finished(response)) => break Ok(response),
+            Err(e) => break Err(e.to_string()),
+        }
+    };
+
+    // the browser would reconnect otherwise
+    source.close();
+    source.set_onmessage(None);
+    source.set_onerror(None);
+    result
 }
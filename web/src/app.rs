@@ -4,27 +4,45 @@
  * @date 04/05/2025
  * @brief Main application for the simulator
  */
+mod accessctrl;
+mod board_health;
 mod boot_ram;
 mod boot_rom;
+mod breadboard;
 mod bus;
+mod coverage;
 pub(crate) mod disassembler;
+mod dma;
 mod editor;
 mod field;
 mod flash;
 mod i2c;
+mod instruction_trace;
+mod led_matrix;
+mod memory_import;
+mod otp;
+mod plugins;
+mod presets;
 mod processor_core;
+mod profiler;
 mod pwm;
 mod sha256;
 mod sio;
+pub(crate) mod snapshots;
 mod spi;
 mod sram;
+mod test_spec;
 mod timer;
 mod trng;
 mod uart;
+mod usb;
+mod variables;
 mod watchdog;
+pub(crate) mod waveform;
 
-use crate::simulator::TaskCommand;
+use crate::simulator::{SimulationSpeed, TaskCommand};
 use crate::Tracker;
+use api_types::SharedProject;
 use egui::collapsing_header::CollapsingState;
 use egui::{ComboBox, ImageSource, Layout, Margin, ScrollArea, Ui, UiBuilder, Widget};
 use egui_dock::{
@@ -32,6 +50,7 @@ use egui_dock::{
 };
 use egui_extras::install_image_loaders;
 use futures::channel::mpsc::Sender;
+use rp2350::device::ComponentFactory;
 use rp2350::simulator::Pico2;
 use rp2350::Rp2350;
 use std::cell::RefCell;
@@ -59,6 +78,17 @@ pub enum Window {
     Field,
     Disassembler,
     Bus,
+    AccessCtrl,
+    Snapshots,
+    Variables,
+    TestSpec,
+    RegisterPresets,
+    BoardHealth,
+    Waveform,
+    LogicAnalyzer,
+    InstructionTrace,
+    Profiler,
+    Coverage,
 
     // Processor Cores
     Core0,
@@ -68,7 +98,9 @@ pub enum Window {
     BootRom,
     Sram,
     BootRam,
+    Otp,
     Flash,
+    MemoryImport,
 
     // Peripherals
     WatchDog,
@@ -80,11 +112,16 @@ pub enum Window {
     Spi1,
     Uart0,
     Uart1,
+    Usb,
     I2c0,
     I2c1,
     Pwm,
     Dma,
     Sio,
+
+    // Virtual devices
+    LedMatrix,
+    Plugins,
 }
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
@@ -93,6 +130,7 @@ pub struct App {
     open_windows: HashSet<Window>,
     #[serde(skip)]
     is_running: Rc<RefCell<bool>>,
+    speed: Rc<RefCell<SimulationSpeed>>,
     #[serde(skip)]
     pico2: Rc<RefCell<Pico2>>,
     #[serde(skip)]
@@ -102,18 +140,37 @@ pub struct App {
 
     #[serde(skip)]
     example: usize,
+    /// A project shared by a link, applied once it is fetched
+    #[serde(skip)]
+    shared_project: Rc<RefCell<Option<SharedProject>>>,
+    /// Origin of the page, the links of the shared projects start with it
+    #[serde(skip)]
+    origin: String,
 
     editor: editor::CodeEditor,
     bus: bus::Bus,
     disassembler: Rc<RefCell<disassembler::Disassembler>>,
+    accessctrl: accessctrl::AccessCtrl,
+    snapshots: snapshots::Snapshots,
+    variables: variables::Variables,
+    test_spec: test_spec::TestSpecView,
+    presets: presets::RegisterPresets,
+    board_health: board_health::BoardHealth,
+    waveform: waveform::Waveform,
+    logic_analyzer: crate::widgets::LogicAnalyzer,
+    instruction_trace: instruction_trace::InstructionTraceView,
+    profiler: profiler::ProfilerView,
+    coverage: coverage::CoverageView,
     // components
     core0: processor_core::ProcessorCore<0>,
     core1: processor_core::ProcessorCore<1>,
     boot_rom: boot_rom::Bootroom,
     sram: sram::Sram,
     boot_ram: boot_ram::BootRam,
+    otp: otp::Otp,
     field: field::Field,
     flash: flash::Flash,
+    memory_import: memory_import::MemoryImport,
 
     // peripherals
     watchdog: watchdog::WatchDog,
@@ -121,6 +178,7 @@ pub struct App {
     trng: trng::Trng,
     uart0: uart::Uart<0>,
     uart1: uart::Uart<1>,
+    usb: usb::Usb,
     spi0: spi::Spi<0>,
     spi1: spi::Spi<1>,
     i2c0: i2c::I2c<0>,
@@ -128,7 +186,12 @@ pub struct App {
     timer0: timer::Timer<0>,
     timer1: timer::Timer<1>,
     pwm: pwm::Pwm,
+    dma: dma::Dma,
     sio: sio::Sio,
+
+    // virtual devices
+    led_matrix: led_matrix::LedMatrix,
+    plugins: plugins::Plugins,
 }
 
 impl TabViewer for App {
@@ -142,16 +205,30 @@ impl TabViewer for App {
             Window::Core0 => "Processor Core 0",
             Window::Core1 => "Processor Core 1",
             Window::Bus => "Bus",
+            Window::AccessCtrl => "Access Control",
+            Window::Snapshots => "Snapshots",
+            Window::Variables => "Variables",
+            Window::TestSpec => "Test Spec",
+            Window::RegisterPresets => "Register Presets",
+            Window::BoardHealth => "Board Health",
+            Window::Waveform => "Waveform",
+            Window::LogicAnalyzer => "Logic Analyzer",
+            Window::InstructionTrace => "Instruction Trace",
+            Window::Profiler => "Profiler",
+            Window::Coverage => "Coverage",
             Window::BootRom => "Boot ROM",
             Window::Sram => "SRAM",
             Window::BootRam => "Boot RAM",
+            Window::Otp => "OTP",
             Window::Flash => "Flash",
+            Window::MemoryImport => "Import Memory",
             Window::WatchDog => "Watch Dog",
             Window::Sha256 => "SHA-256",
             Window::Spi0 => "SPI 0",
             Window::Spi1 => "SPI 1",
             Window::Uart0 => "UART 0",
             Window::Uart1 => "UART 1",
+            Window::Usb => "USB",
             Window::I2c0 => "I2C 0",
             Window::I2c1 => "I2C 1",
             Window::TRNG => "TRNG",
@@ -160,6 +237,8 @@ impl TabViewer for App {
             Window::Pwm => "PWM",
             Window::Dma => "DMA",
             Window::Sio => "SIO",
+            Window::LedMatrix => "LED Matrix",
+            Window::Plugins => "Plugins",
         };
 
         title.into()
@@ -197,7 +276,13 @@ impl TabViewer for App {
                 match tab {
                     Window::Editor => {
                         drop(pico2); // avoid borrow checker issues
-                        self.editor.ui(ui, self.send_task.as_mut().unwrap());
+                        let heatmap = self.editor.open_filename().and_then(|filename| {
+                            let debug_info = self.variables.debug_info();
+                            self.coverage.heatmap(&self.tracker, &debug_info, filename)
+                        });
+
+                        self.editor
+                            .ui(ui, self.send_task.as_mut().unwrap(), heatmap.as_ref());
                     }
                     Window::Disassembler => {
                         if let Ok(mut disassembler) = self.disassembler.try_borrow_mut() {
@@ -205,18 +290,69 @@ impl TabViewer for App {
                         }
                     }
                     Window::Bus => self.bus.ui_with_tracker(ui, rp2350, self.tracker.clone()),
+                    Window::AccessCtrl => self.accessctrl.ui(ui, rp2350),
+                    Window::Snapshots => self.snapshots.ui(ui, rp2350),
+                    Window::Variables => {
+                        let paused = !*self.is_running.borrow();
+                        self.variables.ui(ui, rp2350, self.tracker.clone(), paused);
+                    }
+                    Window::TestSpec => self.test_spec.ui(
+                        ui,
+                        &self.disassembler,
+                        &mut self.variables,
+                        &mut self.presets,
+                    ),
+                    Window::RegisterPresets => self.presets.ui(ui, rp2350),
+                    Window::BoardHealth => self.board_health.ui(ui, rp2350),
+                    Window::Waveform => {
+                        self.waveform
+                            .ui_with_tracker(ui, rp2350, self.tracker.clone())
+                    }
+                    Window::LogicAnalyzer => {
+                        ui.heading("Logic Analyzer");
+                        let mut tracker = self.tracker.borrow_mut();
+                        self.logic_analyzer.ui(ui, &mut tracker.logic);
+                    }
+                    Window::InstructionTrace => {
+                        self.instruction_trace
+                            .ui_with_tracker(ui, rp2350, self.tracker.clone())
+                    }
+                    Window::Profiler => {
+                        self.profiler
+                            .ui(ui, self.tracker.clone(), &self.disassembler)
+                    }
+                    Window::Coverage => {
+                        let debug_info = self.variables.debug_info();
+                        self.coverage.ui(ui, self.tracker.clone(), &debug_info);
+                    }
                     Window::Field => self.field.ui(ui, rp2350),
-                    Window::Core0 => self.core0.ui_with_tracker(ui, rp2350, self.tracker.clone()),
-                    Window::Core1 => self.core1.ui_with_tracker(ui, rp2350, self.tracker.clone()),
+                    Window::Core0 => self.core0.ui_with_symbols(
+                        ui,
+                        rp2350,
+                        self.tracker.clone(),
+                        Some(&self.disassembler),
+                    ),
+                    Window::Core1 => self.core1.ui_with_symbols(
+                        ui,
+                        rp2350,
+                        self.tracker.clone(),
+                        Some(&self.disassembler),
+                    ),
                     Window::BootRom => self.boot_rom.ui(ui, rp2350),
                     Window::Sram => self.sram.ui(ui, rp2350),
                     Window::BootRam => self.boot_ram.ui(ui, rp2350),
+                    Window::Otp => self.otp.ui(ui, rp2350),
                     Window::Flash => self.flash.ui(ui, rp2350),
+                    Window::MemoryImport => {
+                        drop(pico2); // the file is loaded asynchronously
+                        self.memory_import.ui(ui, self.pico2.clone());
+                    }
                     Window::WatchDog => self.watchdog.ui(ui, rp2350),
                     Window::Sha256 => self.sha256.ui(ui, rp2350),
                     Window::TRNG => self.trng.ui_with_tracker(ui, rp2350, self.tracker.clone()),
                     Window::Uart0 => self.uart0.ui_with_tracker(ui, rp2350, self.tracker.clone()),
                     Window::Uart1 => self.uart1.ui_with_tracker(ui, rp2350, self.tracker.clone()),
+                    Window::Usb => self.usb.ui_with_tracker(ui, rp2350, self.tracker.clone()),
                     Window::Spi0 => self.spi0.ui_with_tracker(ui, rp2350, self.tracker.clone()),
                     Window::Spi1 => self.spi1.ui_with_tracker(ui, rp2350, self.tracker.clone()),
                     Window::Timer0 => self.timer0.ui(ui, rp2350),
@@ -225,9 +361,11 @@ impl TabViewer for App {
                     Window::Sio => self.sio.ui(ui, rp2350),
                     Window::I2c0 => self.i2c0.ui_with_tracker(ui, rp2350, self.tracker.clone()),
                     Window::I2c1 => self.i2c1.ui_with_tracker(ui, rp2350, self.tracker.clone()),
-                    Window::Dma => {
-                        ui.heading("DMA");
-                        ui.label("todo");
+                    Window::Dma => self.dma.ui(ui, rp2350),
+                    Window::LedMatrix => self.led_matrix.ui(ui, rp2350),
+                    Window::Plugins => {
+                        drop(pico2); // the plugins are loaded asynchronously
+                        self.plugins.ui(ui, self.pico2.clone());
                     }
                 }
             });
@@ -243,16 +381,30 @@ impl Window {
             Window::Core0 => "Core 0",
             Window::Core1 => "Core 1",
             Window::Bus => "Bus",
+            Window::AccessCtrl => "Access Control",
+            Window::Snapshots => "Snapshots",
+            Window::Variables => "Variables",
+            Window::TestSpec => "Test Spec",
+            Window::RegisterPresets => "Register Presets",
+            Window::BoardHealth => "Board Health",
+            Window::Waveform => "Waveform",
+            Window::LogicAnalyzer => "Logic Analyzer",
+            Window::InstructionTrace => "Instruction Trace",
+            Window::Profiler => "Profiler",
+            Window::Coverage => "Coverage",
             Window::BootRom => "Boot ROM",
             Window::Sram => "SRAM",
             Window::BootRam => "Boot RAM",
+            Window::Otp => "OTP",
             Window::Flash => "Flash",
+            Window::MemoryImport => "Import Memory",
             Window::WatchDog => "Watch Dog",
             Window::Sha256 => "SHA-256",
             Window::Spi0 => "SPI 0",
             Window::Spi1 => "SPI 1",
             Window::Uart0 => "UART 0",
             Window::Uart1 => "UART 1",
+            Window::Usb => "USB",
             Window::I2c0 => "I2C 0",
             Window::I2c1 => "I2C 1",
             Window::TRNG => "TRNG",
@@ -261,6 +413,8 @@ impl Window {
             Window::Pwm => "PWM",
             Window::Dma => "DMA",
             Window::Sio => "SIO",
+            Window::LedMatrix => "LED Matrix",
+            Window::Plugins => "Plugins",
         }
     }
 }
@@ -328,13 +482,86 @@ impl SimulatorApp {
             cc.egui_ctx.clone(),
             pico2,
             is_running,
+            app.app.speed.clone(),
             app.app.disassembler.clone(),
+            app.app.snapshots.library(),
+            app.app.waveform.capture(),
+            app.app.tracker.clone(),
+            app.app.variables.debug_info(),
         );
         app.app.send_task = Some(sender);
 
+        #[cfg(target_arch = "wasm32")]
+        {
+            let location = &cc.integration_info.web_info.location;
+            app.app.origin = location.origin.clone();
+
+            if let Some(id) = location
+                .query_map
+                .get("project")
+                .and_then(|ids| ids.first())
+            {
+                app.fetch_project(cc.egui_ctx.clone(), id.clone());
+            }
+        }
+
         return app;
     }
 
+    /// Add a custom component to the palette of the breadboard. The layouts saved with it
+    /// are placed again once it is registered, before the first frame.
+    pub fn register_component(&mut self, name: &'static str, factory: ComponentFactory) {
+        self.app.field.registry_mut().register(name, factory);
+    }
+
+    /// Load a shared project, it replaces the code and the breadboard on the next frame
+    fn fetch_project(&self, ctx: egui::Context, id: String) {
+        let shared = self.app.shared_project.clone();
+
+        wasm_bindgen_futures::spawn_local(async move {
+            match crate::api::load_project(&id).await {
+                Ok(project) => {
+                    *shared.borrow_mut() = Some(project);
+                    ctx.request_repaint();
+                }
+                Err(why) => crate::notify::error(format!("Failed to load the project: {why}")),
+            }
+        });
+    }
+
+    fn apply_shared_project(&mut self) {
+        let Some(project) = self.app.shared_project.borrow_mut().take() else {
+            return;
+        };
+
+        if !project.board.is_empty() {
+            if let Err(why) = self.app.field.breadboard_mut().load_layout(&project.board) {
+                crate::notify::warning(format!("Failed to load the breadboard: {why}"));
+            }
+        }
+
+        self.app.editor.load_project(project);
+        crate::notify::info("Loaded the shared project");
+    }
+
+    /// Save the project on the server and copy its link
+    fn share_project(&self, ctx: egui::Context) {
+        let board = self.app.field.breadboard().layout_json();
+        let project = self.app.editor.project(board);
+        let origin = self.app.origin.clone();
+
+        wasm_bindgen_futures::spawn_local(async move {
+            match crate::api::save_project(&project).await {
+                Ok(id) => {
+                    let link = format!("{origin}/?project={id}");
+                    ctx.copy_text(link.clone());
+                    crate::notify::success(format!("Link copied: {link}"));
+                }
+                Err(why) => crate::notify::error(format!("Failed to share the project: {why}")),
+            }
+        });
+    }
+
     fn step(&mut self) {
         if let Some(ref mut send_task) = self.app.send_task {
             let _ = send_task.try_send(TaskCommand::Step);
@@ -359,6 +586,12 @@ impl SimulatorApp {
         }
     }
 
+    fn reset_run(&mut self) {
+        if let Some(ref mut send_task) = self.app.send_task {
+            let _ = send_task.try_send(TaskCommand::ResetRun);
+        }
+    }
+
     fn top_panel(&mut self, ui: &mut egui::Ui) {
         // The top panel is often a good place for a menu bar:
 
@@ -388,12 +621,20 @@ impl SimulatorApp {
 
             if ui.button("Load Example").clicked() {
                 let example = &editor::EXAMPLES[self.app.example];
-                self.app.editor.code = String::from(example.code);
+                self.app.editor.load_example(example);
                 if let Some(tab) = self.dock_state.find_tab(&Window::Editor) {
                     self.dock_state.set_active_tab(tab);
                 }
             }
 
+            if ui
+                .button("Share")
+                .on_hover_text("Save the code and the breadboard on the server, and copy its link")
+                .clicked()
+            {
+                self.share_project(ui.ctx().clone());
+            }
+
             ui.add_space(50.0);
 
             if ui
@@ -459,9 +700,54 @@ impl SimulatorApp {
                     self.run();
                 }
             }
+
+            ui.add_space(100.0);
+
+            if self
+                .top_panel_button(egui::include_image!("../assets/reset.svg"), "Reset (RUN)")
+                .ui(ui)
+                .on_hover_text("Reset through the RUN pin, the SRAM and watchdog scratch are kept")
+                .clicked()
+            {
+                self.reset_run();
+            }
+
+            ui.add_space(50.0);
+            self.speed_selector(ui);
         });
     }
 
+    fn speed_selector(&mut self, ui: &mut egui::Ui) {
+        let mut speed = self.app.speed.borrow_mut();
+        let scaled = match *speed {
+            SimulationSpeed::Scaled(ratio) => ratio,
+            _ => 0.1,
+        };
+
+        ComboBox::from_id_salt("SimulationSpeed")
+            .selected_text(speed.name())
+            .show_ui(ui, |ui| {
+                for option in [
+                    SimulationSpeed::Unlimited,
+                    SimulationSpeed::RealTime,
+                    SimulationSpeed::Scaled(scaled),
+                ] {
+                    ui.selectable_value(&mut *speed, option, option.name());
+                }
+            })
+            .response
+            .on_hover_text("How fast the simulated clk_sys runs against the clock of the host");
+
+        if let SimulationSpeed::Scaled(ratio) = &mut *speed {
+            ui.add(
+                egui::DragValue::new(ratio)
+                    .range(0.001..=1000.0)
+                    .speed(0.01)
+                    .prefix("x"),
+            );
+        }
+    }
+
     fn side_panel(&mut self, ui: &mut egui::Ui) {
         // The side panel is often a good place for tools and options.
 
@@ -480,6 +766,17 @@ impl SimulatorApp {
                         Window::Core1,
                         Window::Disassembler,
                         Window::Bus,
+                        Window::AccessCtrl,
+                        Window::Snapshots,
+                        Window::Variables,
+                        Window::TestSpec,
+                        Window::RegisterPresets,
+                        Window::BoardHealth,
+                        Window::Waveform,
+                        Window::LogicAnalyzer,
+                        Window::InstructionTrace,
+                        Window::Profiler,
+                        Window::Coverage,
                     ],
                 );
 
@@ -491,7 +788,9 @@ impl SimulatorApp {
                         Window::BootRom,
                         Window::Sram,
                         Window::BootRam,
+                        Window::Otp,
                         Window::Flash,
+                        Window::MemoryImport,
                     ],
                 );
 
@@ -503,6 +802,7 @@ impl SimulatorApp {
                         Window::Pwm,
                         Window::Uart0,
                         Window::Uart1,
+                        Window::Usb,
                         Window::I2c0,
                         Window::I2c1,
                         Window::Spi0,
@@ -516,6 +816,13 @@ impl SimulatorApp {
                         Window::Sha256,
                     ],
                 );
+
+                self.side_panel_collapsing(
+                    ui,
+                    egui::include_image!("../assets/peripherals.svg"),
+                    "Devices",
+                    &[Window::LedMatrix, Window::Plugins],
+                );
             });
         });
     }
@@ -595,6 +902,8 @@ impl eframe::App for SimulatorApp {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.apply_shared_project();
+
         egui::TopBottomPanel::top("top_panel")
             .frame(egui::Frame::side_top_panel(&ctx.style()).inner_margin(10.0))
             .show(ctx, |ui| self.top_panel(ui));
@@ -0,0 +1,123 @@
+/**
+ * @file app/accessctrl.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief View window for the ACCESSCTRL permission matrix
+ */
+use super::Rp2350Component;
+use egui::{Color32, RichText};
+use rp2350::peripherals::accessctrl::{AccessMaster, ACCESS_CONTROLLED};
+use rp2350::Rp2350;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct AccessCtrl {
+    only_blocked: bool,
+}
+
+impl Rp2350Component for AccessCtrl {
+    const NAME: &'static str = "Access Control";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading("Access Control");
+
+        let accessctrl = &mut rp2350.bus.peripherals.accessctrl;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.only_blocked, "Only show blocked peripherals");
+            if ui.button("Clear blocked accesses").clicked() {
+                accessctrl.clear_blocked();
+            }
+        });
+
+        ui.label(
+            RichText::new(
+                "Permissions of the privileged accesses, red cells mark a master which had an access blocked",
+            )
+                .small()
+                .weak(),
+        );
+
+        if let Some(violation) = accessctrl.last_violation {
+            let privilege = match violation.privileged {
+                true => "privileged",
+                false => "unprivileged",
+            };
+
+            ui.label(format!(
+                "Last blocked: {} {privilege} access at {:#010x}",
+                master_name(violation.master),
+                violation.address
+            ));
+        }
+
+        ui.add_space(12.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("ACCESSCTRL matrix")
+                .num_columns(1 + AccessMaster::ALL.len())
+                .spacing([20.0, 6.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Peripheral");
+                    for master in AccessMaster::ALL {
+                        ui.strong(master_name(master));
+                    }
+                    ui.end_row();
+
+                    for (index, (name, _)) in ACCESS_CONTROLLED.iter().enumerate() {
+                        let any_blocked = AccessMaster::ALL
+                            .iter()
+                            .any(|master| accessctrl.was_blocked(index, *master));
+
+                        if self.only_blocked && !any_blocked {
+                            continue;
+                        }
+
+                        ui.monospace(*name);
+
+                        for master in AccessMaster::ALL {
+                            let secure = !matches!(
+                                master,
+                                AccessMaster::Core0NonSecure | AccessMaster::Core1NonSecure
+                            );
+
+                            let allowed = accessctrl.is_allowed(index, master, secure, true);
+                            let blocked = accessctrl.was_blocked(index, master);
+                            ui.add(permission_cell(allowed, blocked));
+                        }
+
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+fn master_name(master: AccessMaster) -> &'static str {
+    match master {
+        AccessMaster::Core0Secure => "Core 0 S",
+        AccessMaster::Core0NonSecure => "Core 0 NS",
+        AccessMaster::Core1Secure => "Core 1 S",
+        AccessMaster::Core1NonSecure => "Core 1 NS",
+        AccessMaster::Dma => "DMA",
+    }
+}
+
+fn permission_cell(allowed: bool, blocked: bool) -> impl egui::Widget + 'static {
+    move |ui: &mut egui::Ui| {
+        let text = if allowed { "allow" } else { "deny" };
+        let background = match (allowed, blocked) {
+            (_, true) => Color32::from_rgb(0xc0, 0x20, 0x20),
+            (true, false) => egui::hex_color!("#2e7d32"),
+            (false, false) => egui::hex_color!("#151313"),
+        };
+
+        egui::Frame::new()
+            .inner_margin(egui::Margin::symmetric(6, 2))
+            .fill(background)
+            .show(ui, |ui| {
+                ui.monospace(RichText::new(text).color(Color32::WHITE));
+            })
+            .response
+    }
+}
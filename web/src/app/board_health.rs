@@ -0,0 +1,34 @@
+/**
+ * @file app/board_health.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Live list of the conflicting or misconfigured pins
+ */
+use super::Rp2350Component;
+use egui::RichText;
+use rp2350::Rp2350;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct BoardHealth {
+    // None
+}
+
+impl Rp2350Component for BoardHealth {
+    const NAME: &'static str = "Board Health";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading("Board Health");
+        let issues = rp2350.gpio.borrow().lint();
+
+        if issues.is_empty() {
+            ui.label(
+                RichText::new("No issue with the pin configuration").color(egui::Color32::GREEN),
+            );
+            return;
+        }
+
+        for issue in issues {
+            ui.label(RichText::new(format!("⚠ {issue}")).color(egui::Color32::ORANGE));
+        }
+    }
+}
@@ -0,0 +1,689 @@
+/**
+ * @file app/breadboard.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Breadboard editor of the Field view, components dragged around and wired to the GPIOs
+ */
+use egui::{Color32, ComboBox, DragValue, Pos2, Rect, Sense, Stroke, Vec2};
+use rp2350::Rp2350;
+use rp2350::device::{
+    Breadboard, Button, Buzzer, ComponentRegistry, DcMotor, EncoderKnob, Fault, KEYPAD_COLUMNS,
+    KEYPAD_LABELS, KEYPAD_ROWS, Keypad, Led, PlacedComponent, Resistor, Servo, SevenSegment,
+    StepperDriver, Tm1637, Ws2812,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const COLUMNS: i32 = 30;
+const ROWS: i32 = 10;
+/// Distance between two holes, in points
+const PITCH: f32 = 18.0;
+/// The header with the GPIOs is the row above the holes
+const HEADER_HEIGHT: f32 = 2.0 * PITCH;
+
+const BOARD_COLOR: Color32 = Color32::from_rgb(236, 230, 214);
+const HOLE_COLOR: Color32 = Color32::from_rgb(90, 90, 90);
+const HIGH_COLOR: Color32 = Color32::from_rgb(220, 60, 60);
+const LOW_COLOR: Color32 = Color32::from_rgb(60, 90, 200);
+const FAULT_COLOR: Color32 = Color32::from_rgb(230, 30, 30);
+/// Horizontal drag turning the knob of an encoder by one detent
+const POINTS_PER_DETENT: f32 = 12.0;
+const SEGMENT_COLOR: Color32 = Color32::from_rgb(255, 40, 30);
+
+/// A placed component as saved with the app, by the name it is registered with
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+struct SavedComponent {
+    name: String,
+    position: (i32, i32),
+    wires: Vec<Option<u8>>,
+    state: String,
+}
+
+impl From<&PlacedComponent> for SavedComponent {
+    fn from(placed: &PlacedComponent) -> Self {
+        Self {
+            name: placed.component.name().to_string(),
+            position: placed.position,
+            wires: placed.wires.clone(),
+            state: placed.component.save(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct BreadboardEditor {
+    layout: Vec<SavedComponent>,
+    #[serde(skip)]
+    registry: ComponentRegistry,
+    #[serde(skip)]
+    board: Option<Rc<RefCell<Breadboard>>>,
+    #[serde(skip)]
+    selected: Option<usize>,
+    /// GPIOs with a fault already notified, so it is not repeated every frame
+    #[serde(skip)]
+    notified: Vec<u8>,
+    /// Drag of the knob of an encoder since its last detent
+    #[serde(skip)]
+    knob_rest: f32,
+    /// A layout was loaded, its components replace the placed ones on the next frame
+    #[serde(skip)]
+    reload: bool,
+}
+
+impl BreadboardEditor {
+    /// Components of the palette, custom ones are registered here to be placed and saved
+    pub fn registry_mut(&mut self) -> &mut ComponentRegistry {
+        &mut self.registry
+    }
+
+    /// The layout as JSON, to share it with a project
+    pub fn layout_json(&self) -> String {
+        serde_json::to_string(&self.layout).unwrap_or_default()
+    }
+
+    /// Replace the components by the ones of a layout from `layout_json`
+    pub fn load_layout(&mut self, json: &str) -> Result<(), String> {
+        self.layout = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        self.selected = None;
+        self.reload = true;
+        Ok(())
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        let restore = self.board.is_none() && !self.layout.is_empty();
+        if restore || std::mem::take(&mut self.reload) {
+            self.restore(rp2350);
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Breadboard");
+
+            let names: Vec<_> = self.registry.names().collect();
+            for name in names {
+                if ui.button(format!("+ {name}")).clicked() {
+                    self.place(rp2350, name);
+                }
+            }
+        });
+
+        let Some(board) = self.board.clone() else {
+            return;
+        };
+
+        let Ok(mut board) = board.try_borrow_mut() else {
+            return;
+        };
+
+        self.board_ui(ui, &mut board);
+        self.faults_ui(ui, board.faults());
+
+        if let Some(index) = self.selected {
+            self.properties_ui(ui, &mut board, index);
+        }
+
+        self.layout = board.components.iter().map(SavedComponent::from).collect();
+    }
+
+    fn attached_board(&mut self, rp2350: &mut Rp2350) -> Rc<RefCell<Breadboard>> {
+        let board = self.board.get_or_insert_with(|| {
+            let board = Rc::new(RefCell::new(Breadboard::new()));
+            rp2350.attach_device(Box::new(Rc::clone(&board)));
+            board
+        });
+
+        Rc::clone(board)
+    }
+
+    /// Place the saved components again, the ones which are not registered are dropped
+    fn restore(&mut self, rp2350: &mut Rp2350) {
+        let board = self.attached_board(rp2350);
+        let mut board = board.borrow_mut();
+        board.components.clear();
+
+        for saved in self.layout.iter() {
+            let Some(mut component) = self.registry.create(&saved.name) else {
+                crate::notify::warning(format!("Unknown breadboard component {}", saved.name));
+                continue;
+            };
+
+            component.load(&saved.state);
+            let index = board.place(component, saved.position);
+
+            for (wire, saved) in board.components[index].wires.iter_mut().zip(&saved.wires) {
+                *wire = *saved;
+            }
+        }
+    }
+
+    /// The board is attached to the chip with its first component
+    fn place(&mut self, rp2350: &mut Rp2350, name: &str) {
+        let Some(component) = self.registry.create(name) else {
+            return;
+        };
+
+        let board = self.attached_board(rp2350);
+        let Ok(mut board) = board.try_borrow_mut() else {
+            return;
+        };
+
+        // next to the last one, the left of the board once the row is full
+        let width = component.pins().len() as i32;
+        let position = match board.components.last() {
+            Some(last) => {
+                let column = last.position.0 + last.wires.len() as i32 + 2;
+                match column + width <= COLUMNS {
+                    true => (column, last.position.1),
+                    false => (1, (last.position.1 + 3) % ROWS),
+                }
+            }
+            None => (1, 2),
+        };
+
+        self.selected = Some(board.place(component, position));
+    }
+
+    fn board_ui(&mut self, ui: &mut egui::Ui, board: &mut Breadboard) {
+        let size = Vec2::new(COLUMNS as f32 * PITCH, HEADER_HEIGHT + ROWS as f32 * PITCH);
+        let (response, painter) = ui.allocate_painter(size, Sense::click());
+        let origin = response.rect.min;
+
+        painter.rect_filled(response.rect, 4.0, BOARD_COLOR);
+
+        // the header, one hole per GPIO
+        for pin in 0..COLUMNS {
+            let center = header_hole(origin, pin as u8);
+            painter.circle_filled(center, 3.0, Color32::from_rgb(200, 170, 60));
+
+            if board.faults().iter().any(|fault| fault.gpio() == pin as u8) {
+                painter.circle_stroke(center, 6.0, Stroke::new(2.0, FAULT_COLOR));
+            }
+            painter.text(
+                center - Vec2::new(0.0, 10.0),
+                egui::Align2::CENTER_CENTER,
+                pin.to_string(),
+                egui::FontId::monospace(8.0),
+                Color32::DARK_GRAY,
+            );
+        }
+
+        for row in 0..ROWS {
+            for column in 0..COLUMNS {
+                let center = hole(origin, (column, row));
+                painter.rect_filled(
+                    Rect::from_center_size(center, Vec2::splat(4.0)),
+                    0.0,
+                    HOLE_COLOR,
+                );
+            }
+        }
+
+        if response.clicked() {
+            self.selected = None;
+        }
+
+        for (index, placed) in board.components.iter_mut().enumerate() {
+            let width = placed.wires.len().max(1) as i32;
+            let handle = ui.interact(
+                outline(origin, placed.position, width),
+                response.id.with(index),
+                Sense::click_and_drag(),
+            );
+
+            if handle.clicked() || handle.drag_started() {
+                self.selected = Some(index);
+            }
+
+            // dragged from hole to hole, by its first pin
+            if let Some(pointer) = handle.interact_pointer_pos().filter(|_| handle.dragged()) {
+                let column = ((pointer.x - origin.x) / PITCH - 0.5).round() as i32;
+                let row = ((pointer.y - origin.y - HEADER_HEIGHT) / PITCH - 0.5).round() as i32;
+                placed.position = (column.clamp(0, COLUMNS - width), row.clamp(0, ROWS - 1));
+            }
+
+            let pressed = handle.is_pointer_button_down_on() && !handle.dragged();
+            if let Some(button) = placed.downcast_mut::<Button>() {
+                button.pressed = pressed;
+            }
+
+            let first_pin = hole(origin, placed.position);
+
+            // the knob turns by dragging it sideways and is pushed by holding it down
+            if let Some(knob) = placed.downcast_mut::<EncoderKnob>() {
+                let area = ui.interact(
+                    Rect::from_center_size(knob_center(first_pin), Vec2::splat(PITCH * 1.6)),
+                    response.id.with((index, "knob")),
+                    Sense::click_and_drag(),
+                );
+
+                if area.dragged() {
+                    self.knob_rest += area.drag_delta().x;
+                    let detents = (self.knob_rest / POINTS_PER_DETENT).trunc();
+                    self.knob_rest -= detents * POINTS_PER_DETENT;
+                    knob.rotate(detents as i32);
+                }
+
+                knob.pressed = area.is_pointer_button_down_on() && !area.dragged();
+            }
+
+            if let Some(keypad) = placed.downcast_mut::<Keypad>() {
+                for row in 0..KEYPAD_ROWS {
+                    for column in 0..KEYPAD_COLUMNS {
+                        let key = ui.interact(
+                            key_rect(first_pin, row, column),
+                            response.id.with((index, row, column)),
+                            Sense::click(),
+                        );
+                        keypad.pressed[row][column] = key.is_pointer_button_down_on();
+                    }
+                }
+            }
+
+            for (pin, wire) in placed.wires.iter().enumerate() {
+                let Some(gpio) = wire else {
+                    continue;
+                };
+
+                let color = if placed.level(pin) {
+                    HIGH_COLOR
+                } else {
+                    LOW_COLOR
+                };
+                let end = hole(origin, (placed.position.0 + pin as i32, placed.position.1));
+                painter.line_segment([header_hole(origin, *gpio), end], Stroke::new(2.0, color));
+            }
+
+            draw_component(&painter, placed, hole(origin, placed.position));
+
+            if self.selected == Some(index) {
+                painter.rect_stroke(
+                    outline(origin, placed.position, width),
+                    3.0,
+                    Stroke::new(1.5, Color32::BLACK),
+                    egui::StrokeKind::Outside,
+                );
+            }
+        }
+    }
+
+    /// Wiring that would damage a real board, notified once it shows up
+    fn faults_ui(&mut self, ui: &mut egui::Ui, faults: &[Fault]) {
+        for fault in faults {
+            ui.colored_label(FAULT_COLOR, format!("⚠ {fault}"));
+
+            if !self.notified.contains(&fault.gpio()) {
+                crate::notify::warning(fault.to_string());
+            }
+        }
+
+        self.notified = faults.iter().map(Fault::gpio).collect();
+    }
+
+    fn properties_ui(&mut self, ui: &mut egui::Ui, board: &mut Breadboard, index: usize) {
+        let Some(placed) = board.components.get_mut(index) else {
+            self.selected = None;
+            return;
+        };
+
+        let mut removed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(placed.component.name());
+
+            let wire = |pin: Option<u8>| match pin {
+                Some(pin) => format!("GPIO{pin}"),
+                None => "Not wired".to_string(),
+            };
+
+            let labels = placed.component.pins();
+            for (pin, wired) in placed.wires.iter_mut().enumerate() {
+                if labels.len() > 1 {
+                    ui.label(labels[pin]);
+                }
+
+                ComboBox::from_id_salt(("breadboard wire", pin))
+                    .selected_text(wire(*wired))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(wired, None, wire(None));
+                        for gpio in 0..30 {
+                            ui.selectable_value(wired, Some(gpio), wire(Some(gpio)));
+                        }
+                    });
+            }
+
+            for (pin, label) in labels.iter().enumerate() {
+                if let Some(volts) = placed.volts(pin) {
+                    ui.label(format!(
+                        "{label} {volts:.2}V {:.1}mA",
+                        placed.amps(pin) * 1000.0
+                    ));
+                }
+            }
+
+            settings_ui(ui, placed);
+
+            if let Some(status) = placed.component.status() {
+                ui.label(status);
+            }
+
+            removed = ui.button("Remove").clicked();
+        });
+
+        if removed {
+            board.remove(index);
+            self.selected = None;
+        }
+    }
+}
+
+/// Settings of the built-in components, the custom ones only show their status
+fn settings_ui(ui: &mut egui::Ui, placed: &mut PlacedComponent) {
+    if let Some(led) = placed.downcast_mut::<Led>() {
+        ui.color_edit_button_srgb(&mut led.color);
+    } else if let Some(button) = placed.downcast_mut::<Button>() {
+        ui.checkbox(&mut button.to_vcc, "To 3V3");
+    } else if let Some(resistor) = placed.downcast_mut::<Resistor>() {
+        ui.add(
+            DragValue::new(&mut resistor.ohms)
+                .range(100..=1_000_000)
+                .suffix(" Ω"),
+        );
+        ui.checkbox(&mut resistor.to_vcc, "To 3V3");
+    } else if let Some(buzzer) = placed.downcast_ref::<Buzzer>() {
+        ui.label(match buzzer.frequency() {
+            frequency if frequency > 0.0 => format!("{frequency:.0} Hz"),
+            _ if buzzer.is_buzzing() => "Buzzing".to_string(),
+            _ => "Silent".to_string(),
+        });
+    } else if let Some(driver) = placed.downcast_mut::<StepperDriver>() {
+        ui.add(
+            DragValue::new(&mut driver.steps_per_revolution)
+                .range(1..=2048)
+                .suffix(" steps/rev"),
+        );
+    } else if let Some(motor) = placed.downcast_mut::<DcMotor>() {
+        ui.add(
+            DragValue::new(&mut motor.max_rpm)
+                .range(1.0..=20_000.0)
+                .suffix(" RPM max"),
+        );
+    } else if let Some(strip) = placed.downcast_mut::<Ws2812>() {
+        let mut length = strip.len();
+        ui.add(DragValue::new(&mut length).range(1..=144).suffix(" LEDs"));
+        strip.set_len(length);
+    } else if let Some(display) = placed.downcast_mut::<SevenSegment>() {
+        ui.add(
+            DragValue::new(&mut display.digits)
+                .range(1..=4)
+                .suffix(" digits"),
+        );
+        ui.checkbox(&mut display.common_anode, "Common anode");
+    } else if let Some(tm) = placed.downcast_mut::<Tm1637>() {
+        ui.add(
+            DragValue::new(&mut tm.digits)
+                .range(1..=6)
+                .suffix(" digits"),
+        );
+    }
+}
+
+fn hole(origin: Pos2, (column, row): (i32, i32)) -> Pos2 {
+    origin
+        + Vec2::new(
+            (column as f32 + 0.5) * PITCH,
+            HEADER_HEIGHT + (row as f32 + 0.5) * PITCH,
+        )
+}
+
+fn header_hole(origin: Pos2, pin: u8) -> Pos2 {
+    origin + Vec2::new((pin as f32 + 0.5) * PITCH, HEADER_HEIGHT - PITCH * 0.5)
+}
+
+/// Area of a component over the holes of its pins
+fn outline(origin: Pos2, position: (i32, i32), width: i32) -> Rect {
+    let first = hole(origin, position);
+    let last = hole(origin, (position.0 + width - 1, position.1));
+    Rect::from_two_pos(first, last).expand(PITCH * 0.8)
+}
+
+fn draw_component(painter: &egui::Painter, placed: &PlacedComponent, center: Pos2) {
+    let radius = PITCH * 0.6;
+
+    if let Some(led) = placed.downcast_ref::<Led>() {
+        let [r, g, b] = led.color;
+        let color = Color32::from_rgb(r, g, b);
+
+        if led.is_lit() {
+            painter.circle_filled(center, radius * 1.4, color.gamma_multiply(0.3));
+            painter.circle_filled(center, radius, color);
+        } else {
+            painter.circle_filled(center, radius, color.gamma_multiply(0.35));
+        }
+    } else if let Some(button) = placed.downcast_ref::<Button>() {
+        let body = Rect::from_center_size(center, Vec2::splat(radius * 2.0));
+        painter.rect_filled(body, 2.0, Color32::from_gray(50));
+
+        let cap = match button.pressed {
+            true => Color32::from_gray(110),
+            false => Color32::from_gray(170),
+        };
+        painter.circle_filled(center, radius * 0.6, cap);
+    } else if placed.downcast_ref::<Resistor>().is_some() {
+        let body = Rect::from_center_size(center, Vec2::new(radius * 2.2, radius));
+        painter.rect_filled(body, 3.0, Color32::from_rgb(210, 180, 130));
+
+        for (offset, color) in [
+            (-0.6, Color32::BROWN),
+            (0.0, Color32::BLACK),
+            (0.6, Color32::ORANGE),
+        ] {
+            let band = Rect::from_center_size(
+                center + Vec2::new(offset * radius, 0.0),
+                Vec2::new(2.0, radius),
+            );
+            painter.rect_filled(band, 0.0, color);
+        }
+    } else if let Some(buzzer) = placed.downcast_ref::<Buzzer>() {
+        painter.circle_filled(center, radius, Color32::from_gray(20));
+        painter.circle_filled(center, radius * 0.2, Color32::from_gray(80));
+
+        if buzzer.is_buzzing() {
+            for ring in [1.3, 1.6] {
+                painter.circle_stroke(
+                    center,
+                    radius * ring,
+                    Stroke::new(1.0, Color32::from_gray(60)),
+                );
+            }
+        }
+    } else if let Some(knob) = placed.downcast_ref::<EncoderKnob>() {
+        draw_chip(painter, placed, center);
+
+        let knob_center = knob_center(center);
+        painter.circle_filled(knob_center, PITCH * 0.8, Color32::from_gray(70));
+
+        let mark = Vec2::angled((knob.angle() as f32 - 90.0).to_radians()) * PITCH * 0.6;
+        painter.line_segment(
+            [knob_center, knob_center + mark],
+            Stroke::new(2.0, Color32::WHITE),
+        );
+
+        if knob.pressed {
+            painter.circle_stroke(knob_center, PITCH * 0.9, Stroke::new(1.5, Color32::BLACK));
+        }
+    } else if let Some(keypad) = placed.downcast_ref::<Keypad>() {
+        draw_chip(painter, placed, center);
+
+        for (row, labels) in KEYPAD_LABELS.iter().enumerate() {
+            for (column, label) in labels.iter().enumerate() {
+                let key = key_rect(center, row, column);
+                let color = match keypad.pressed[row][column] {
+                    true => Color32::from_rgb(90, 110, 160),
+                    false => Color32::from_rgb(150, 170, 220),
+                };
+
+                painter.rect_filled(key, 2.0, color);
+                painter.text(
+                    key.center(),
+                    egui::Align2::CENTER_CENTER,
+                    label,
+                    egui::FontId::monospace(10.0),
+                    Color32::WHITE,
+                );
+            }
+        }
+    } else if let Some(servo) = placed.downcast_ref::<Servo>() {
+        let body = Rect::from_center_size(center, Vec2::new(radius * 2.6, radius * 1.6));
+        painter.rect_filled(body, 2.0, Color32::from_rgb(40, 90, 200));
+
+        // the horn, 0° on the right and 180° on the left
+        let angle = servo.angle.to_radians() as f32;
+        let tip = center + Vec2::angled(-angle) * radius * 1.8;
+        painter.line_segment([center, tip], Stroke::new(3.0, Color32::WHITE));
+        painter.circle_filled(center, radius * 0.3, Color32::WHITE);
+    } else if let Some(motor) = placed.downcast_ref::<DcMotor>() {
+        let center = center + Vec2::new(PITCH * 0.5, 0.0);
+        painter.circle_filled(center, radius * 1.4, Color32::from_rgb(230, 200, 40));
+
+        let spoke = Vec2::angled(motor.angle.to_radians() as f32) * radius * 1.2;
+        painter.line_segment(
+            [center - spoke, center + spoke],
+            Stroke::new(2.0, Color32::BLACK),
+        );
+        painter.circle_filled(center, radius * 0.3, Color32::from_gray(60));
+    } else if let Some(strip) = placed.downcast_ref::<Ws2812>() {
+        draw_strip(painter, strip, placed.position, center);
+    } else if let Some(display) = placed.downcast_ref::<SevenSegment>() {
+        draw_chip(painter, placed, center);
+        draw_digits(painter, center, &display.segments(), display.digits, 1.0);
+    } else if let Some(tm) = placed.downcast_ref::<Tm1637>() {
+        draw_chip(painter, placed, center);
+
+        // the 8 steps of the pulse width, the lowest one still shows
+        let brightness = (tm.brightness + 1) as f32 / 8.0;
+        draw_digits(painter, center, tm.shown(), tm.digits, brightness);
+    } else {
+        draw_chip(painter, placed, center);
+    }
+}
+
+/// Any other component, a chip with its pins labelled
+fn draw_chip(painter: &egui::Painter, placed: &PlacedComponent, first_pin: Pos2) {
+    let labels = placed.component.pins();
+    let width = labels.len().max(1) as f32;
+    let body = Rect::from_min_size(
+        first_pin - Vec2::splat(PITCH * 0.5),
+        Vec2::new(width * PITCH, PITCH),
+    );
+
+    painter.rect_filled(body, 2.0, Color32::from_gray(35));
+
+    for (pin, label) in labels.iter().enumerate() {
+        painter.text(
+            first_pin + Vec2::new(pin as f32 * PITCH, 0.0),
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::monospace(6.0),
+            Color32::WHITE,
+        );
+    }
+
+    painter.text(
+        body.center_bottom() + Vec2::new(0.0, 2.0),
+        egui::Align2::CENTER_TOP,
+        placed.component.status().unwrap_or_default(),
+        egui::FontId::proportional(9.0),
+        Color32::DARK_GRAY,
+    );
+}
+
+/// The LEDs of a strip in a row from its data pin, packed to stay on the board
+fn draw_strip(painter: &egui::Painter, strip: &Ws2812, position: (i32, i32), din: Pos2) {
+    let room = (COLUMNS - position.0) as f32 * PITCH;
+    let spacing = (room / strip.len().max(1) as f32).min(PITCH);
+    let size = Vec2::splat(spacing * 0.8);
+
+    let body = Rect::from_min_size(
+        din - Vec2::new(PITCH * 0.5, PITCH * 0.5),
+        Vec2::new(spacing * strip.len() as f32, PITCH),
+    );
+    painter.rect_filled(body, 2.0, Color32::from_gray(235));
+
+    for (index, [r, g, b]) in strip.pixels.iter().enumerate() {
+        let center = din + Vec2::new((index as f32 - 0.5) * spacing + PITCH * 0.5, 0.0);
+        let color = Color32::from_rgb(*r, *g, *b);
+        painter.rect_filled(
+            Rect::from_center_size(center, size),
+            1.0,
+            Color32::from_gray(30),
+        );
+        painter.circle_filled(center, size.x * 0.35, color);
+    }
+}
+
+/// Digits of a display above its pins, a bitmap of the segments A to G then the dot
+fn draw_digits(
+    painter: &egui::Painter,
+    first_pin: Pos2,
+    segments: &[u8],
+    digits: usize,
+    brightness: f32,
+) {
+    let size = Vec2::new(PITCH * 1.2, PITCH * 2.0);
+    let top_left = first_pin - Vec2::new(PITCH * 0.5, size.y + PITCH * 0.7);
+    let body = Rect::from_min_size(top_left, Vec2::new(size.x * digits as f32, size.y));
+    painter.rect_filled(body, 2.0, Color32::from_gray(25));
+
+    let (w, h) = (size.x * 0.5, size.y * 0.35);
+    // from and to of the bars, around the middle of the digit
+    let bars = [
+        ((-0.5, -1.0), (0.5, -1.0)),
+        ((0.5, -1.0), (0.5, 0.0)),
+        ((0.5, 0.0), (0.5, 1.0)),
+        ((-0.5, 1.0), (0.5, 1.0)),
+        ((-0.5, 0.0), (-0.5, 1.0)),
+        ((-0.5, -1.0), (-0.5, 0.0)),
+        ((-0.5, 0.0), (0.5, 0.0)),
+    ];
+
+    for digit in 0..digits {
+        let lit = segments.get(digit).copied().unwrap_or_default();
+        let center = top_left + Vec2::new((digit as f32 + 0.45) * size.x, size.y * 0.5);
+
+        for (segment, ((x0, y0), (x1, y1))) in bars.iter().enumerate() {
+            let color = match lit >> segment & 1 {
+                1 => SEGMENT_COLOR.gamma_multiply(brightness),
+                _ => Color32::from_gray(50),
+            };
+            painter.line_segment(
+                [
+                    center + Vec2::new(x0 * w, y0 * h),
+                    center + Vec2::new(x1 * w, y1 * h),
+                ],
+                Stroke::new(2.0, color),
+            );
+        }
+
+        let dot = match lit >> 7 & 1 {
+            1 => SEGMENT_COLOR.gamma_multiply(brightness),
+            _ => Color32::from_gray(50),
+        };
+        painter.circle_filled(center + Vec2::new(w * 0.8, h), 1.5, dot);
+    }
+}
+
+/// Knob of an encoder, above the middle of its pins
+fn knob_center(first_pin: Pos2) -> Pos2 {
+    first_pin + Vec2::new(PITCH, -PITCH * 1.4)
+}
+
+/// Key of a keypad, the keys are stacked above its pins
+fn key_rect(first_pin: Pos2, row: usize, column: usize) -> Rect {
+    let top_left = first_pin - Vec2::new(PITCH * 0.5, PITCH * (KEYPAD_ROWS as f32 + 0.6));
+    let size = Vec2::new(2.0 * PITCH, PITCH);
+
+    Rect::from_min_size(
+        top_left + Vec2::new(column as f32 * size.x, row as f32 * size.y),
+        size - Vec2::splat(2.0),
+    )
+}
@@ -7,15 +7,281 @@ use crate::tracker::BusEvent;
  * @brief View window for the Bus system
  */
 use super::Rp2350Component;
+use egui::collapsing_header::CollapsingState;
 use egui::RichText;
 use egui_extras::{Column, TableBuilder};
+use rp2350::bus::{
+    BreakOn, MemoryBreakpoint, RequestorStatistics, LATENCY_HISTOGRAM_SIZE, SRAM_BANKS,
+};
 use rp2350::common::{DataSize, Requestor};
 use rp2350::Rp2350;
+use std::collections::HashMap;
 use std::rc::Rc;
 
+const BREAK_ON: [(BreakOn, &str); 3] = [
+    (BreakOn::Read, "Read"),
+    (BreakOn::Write, "Write"),
+    (BreakOn::Access, "Access"),
+];
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct Breakpoint {
+    start: u32,
+    end: u32,
+    /// Index in `BREAK_ON`
+    on: usize,
+    id: u32,
+}
+
 #[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Bus {
-    // None
+    breakpoints: Vec<Breakpoint>,
+    next_id: u32,
+    #[serde(skip)]
+    new_address: String,
+    #[serde(skip)]
+    new_length: String,
+    #[serde(skip)]
+    new_on: usize,
+}
+
+const REQUESTORS: [(Requestor, &str); 4] = [
+    (Requestor::Proc0, "Core 0"),
+    (Requestor::Proc1, "Core 1"),
+    (Requestor::DmaR, "DMA Read"),
+    (Requestor::DmaW, "DMA Write"),
+];
+
+fn parse_number(value: &str) -> Option<u32> {
+    let value = value.trim();
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+impl Bus {
+    fn add_breakpoint(&mut self) {
+        let start = parse_number(&self.new_address);
+        let length = parse_number(&self.new_length).filter(|&length| length > 0);
+
+        let Some((start, end)) = start.zip(length).and_then(|(start, length)| {
+            let end = start.checked_add(length)?;
+            Some((start, end))
+        }) else {
+            crate::notify::error("Invalid breakpoint address or length");
+            return;
+        };
+
+        self.breakpoints.push(Breakpoint {
+            start,
+            end,
+            on: self.new_on,
+            id: self.next_id,
+        });
+        self.next_id = self.next_id.wrapping_add(1);
+    }
+
+    /// Keep the breakpoints of the bus in line with the list, the bus forgets them when the
+    /// chip is rebuilt
+    fn sync_breakpoints(&self, rp2350: &mut Rp2350) {
+        let desired: Vec<MemoryBreakpoint> = self
+            .breakpoints
+            .iter()
+            .map(|breakpoint| MemoryBreakpoint {
+                range: breakpoint.start..breakpoint.end,
+                on: BREAK_ON[breakpoint.on].0,
+                id: breakpoint.id,
+            })
+            .collect();
+
+        if rp2350.bus.memory_breakpoints() == desired.as_slice() {
+            return;
+        }
+
+        let current: Vec<u32> = rp2350
+            .bus
+            .memory_breakpoints()
+            .iter()
+            .map(|breakpoint| breakpoint.id)
+            .collect();
+
+        for id in current {
+            rp2350.bus.remove_memory_breakpoint(id);
+        }
+
+        for breakpoint in desired {
+            rp2350
+                .bus
+                .add_memory_breakpoint(breakpoint.range, breakpoint.on, breakpoint.id);
+        }
+    }
+
+    fn breakpoints_ui(&mut self, ui: &mut egui::Ui, hits: &HashMap<u32, u64>) {
+        ui.label(
+            RichText::new("The simulation pauses on the accesses of the cores and the DMA")
+                .small()
+                .weak(),
+        );
+
+        ui.horizontal(|ui| {
+            ui.label("Address");
+            ui.add(egui::TextEdit::singleline(&mut self.new_address).desired_width(90.0));
+            ui.label("Length");
+            ui.add(egui::TextEdit::singleline(&mut self.new_length).desired_width(50.0));
+
+            egui::ComboBox::from_id_salt("bus_break_on")
+                .selected_text(BREAK_ON[self.new_on].1)
+                .show_ui(ui, |ui| {
+                    for (index, (_, name)) in BREAK_ON.iter().enumerate() {
+                        ui.selectable_value(&mut self.new_on, index, *name);
+                    }
+                });
+
+            if ui.button("Add").clicked() {
+                self.add_breakpoint();
+            }
+        });
+
+        let mut removed = None;
+
+        egui::Grid::new("Bus Breakpoints")
+            .num_columns(4)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for breakpoint in self.breakpoints.iter() {
+                    let range = breakpoint.start..breakpoint.end;
+                    ui.monospace(format!("{:#010x}..{:#010x}", range.start, range.end));
+                    ui.label(BREAK_ON[breakpoint.on].1);
+                    let count = hits.get(&breakpoint.id).copied().unwrap_or(0);
+                    ui.label(format!("{count} hits"));
+
+                    if ui.button("Remove").clicked() {
+                        removed = Some(breakpoint.id);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(id) = removed {
+            self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+        }
+    }
+
+    fn statistics_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        let stats = *rp2350.bus_statistics();
+        let elapsed_ms = stats.ticks as f64 * 1000.0 / rp2350.clock.clk_sys() as f64;
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Collected over {:.3} ms", elapsed_ms));
+            if ui.button("Reset").clicked() {
+                rp2350.reset_bus_statistics();
+            }
+        });
+
+        ui.label(format!(
+            "DMA throughput: {:.1} bytes/ms ({} bytes)",
+            rp2350.dma_throughput(),
+            stats.dma_bytes()
+        ));
+
+        ui.add_space(8.0);
+
+        egui::Grid::new("Bus Statistics")
+            .num_columns(1 + REQUESTORS.len())
+            .spacing([40.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("");
+                for (_, name) in REQUESTORS {
+                    ui.strong(name);
+                }
+                ui.end_row();
+
+                let rows: [(&str, fn(&RequestorStatistics) -> String); 7] = [
+                    ("Loads", |s| s.loads.to_string()),
+                    ("Stores", |s| s.stores.to_string()),
+                    ("Errors", |s| s.errors.to_string()),
+                    ("Bytes", |s| s.bytes.to_string()),
+                    ("Wait cycles", |s| s.wait_cycles.to_string()),
+                    ("Contention stalls", |s| s.contention_stalls.to_string()),
+                    ("Avg. latency", |s| format!("{:.2}", s.average_latency())),
+                ];
+
+                for (label, value) in rows {
+                    ui.label(label);
+                    for (requestor, _) in REQUESTORS {
+                        ui.monospace(value(stats.requestor(requestor)));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.label("Latency histogram (cycles)");
+
+        egui::Grid::new("Bus Latency Histogram")
+            .num_columns(1 + REQUESTORS.len())
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Cycles");
+                for (_, name) in REQUESTORS {
+                    ui.strong(name);
+                }
+                ui.end_row();
+
+                for bucket in 0..LATENCY_HISTOGRAM_SIZE {
+                    if REQUESTORS
+                        .iter()
+                        .all(|(r, _)| stats.requestor(*r).latency_histogram[bucket] == 0)
+                    {
+                        continue;
+                    }
+
+                    if bucket == LATENCY_HISTOGRAM_SIZE - 1 {
+                        ui.label(format!("{}+", bucket + 1));
+                    } else {
+                        ui.label(format!("{}", bucket + 1));
+                    }
+
+                    for (requestor, _) in REQUESTORS {
+                        let count = stats.requestor(requestor).latency_histogram[bucket];
+                        ui.monospace(count.to_string());
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.label("SRAM banks, SRAM0-7 striped and the SRAM8-9 scratch");
+
+        egui::Grid::new("Bus SRAM Banks")
+            .num_columns(1 + SRAM_BANKS)
+            .spacing([20.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("");
+                for bank in 0..SRAM_BANKS {
+                    ui.strong(format!("SRAM{bank}"));
+                }
+                ui.end_row();
+
+                ui.label("Accesses");
+                for bank in stats.sram_banks.iter() {
+                    ui.monospace(bank.accesses.to_string());
+                }
+                ui.end_row();
+
+                ui.label("Contention stalls");
+                for bank in stats.sram_banks.iter() {
+                    ui.monospace(bank.contention_stalls.to_string());
+                }
+                ui.end_row();
+            });
+    }
 }
 
 impl Rp2350Component for Bus {
@@ -24,24 +290,50 @@ impl Rp2350Component for Bus {
     fn ui_with_tracker(
         &mut self,
         ui: &mut egui::Ui,
-        _rp2350: &mut Rp2350,
+        rp2350: &mut Rp2350,
         tracker: Rc<crate::Tracker>,
     ) {
-        ui.heading("Bus Events");
+        CollapsingState::load_with_default_open(ui.ctx(), ui.make_persistent_id("bus_stats"), true)
+            .show_header(ui, |ui| {
+                ui.heading("Statistics");
+            })
+            .body(|ui| {
+                self.statistics_ui(ui, rp2350);
+            });
+
+        ui.add_space(12.0);
+
         let tracker = tracker.borrow();
-        let ref bus = tracker.bus;
 
-        egui::Grid::new("Bus")
-            .num_columns(2)
-            .spacing([40.0, 6.0])
-            .striped(false)
-            .show(ui, |ui| {
-                // TODO
-                ui.end_row();
-            });
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            ui.make_persistent_id("bus_breakpoints"),
+            true,
+        )
+        .show_header(ui, |ui| {
+            ui.heading("Memory Breakpoints");
+        })
+        .body(|ui| {
+            self.breakpoints_ui(ui, &tracker.breakpoint_hits);
+        });
+        self.sync_breakpoints(rp2350);
 
         ui.add_space(12.0);
 
+        ui.heading("Bus Events");
+        let ref bus = tracker.bus;
+
+        if ui.button("Copy events").clicked() {
+            let text = bus
+                .events
+                .iter()
+                .map(format_event)
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            crate::widgets::clipboard::copy(ui.ctx(), text);
+        }
+
         TableBuilder::new(ui)
             .striped(true)
             .resizable(true)
@@ -140,3 +432,28 @@ impl Rp2350Component for Bus {
             });
     }
 }
+
+fn format_event(event: &BusEvent) -> String {
+    let requestor = |requestor: &Requestor| REQUESTORS[*requestor as usize].1;
+    let size = |size: &DataSize| *size as u32 * 8;
+
+    match event {
+        BusEvent::Read {
+            requestor: r,
+            address,
+            size: s,
+        } => format!("Read  {:<9} {:#010x} {:>2} bits", requestor(r), address, size(s)),
+        BusEvent::Write {
+            requestor: r,
+            address,
+            value,
+            size: s,
+        } => format!(
+            "Write {:<9} {:#010x} {:>2} bits {:#010x}",
+            requestor(r),
+            address,
+            size(s),
+            value
+        ),
+    }
+}
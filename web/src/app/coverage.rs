@@ -0,0 +1,144 @@
+/**
+ * @file app/coverage.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Source lines executed by the firmware, exported as lcov and shown in the editor
+ */
+use crate::Tracker;
+use egui::RichText;
+use rp2350::coverage::{Coverage, LineHits};
+use rp2350::elf::DebugInfo;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn percent(hit: usize, total: usize) -> String {
+    match total {
+        0 => String::from("-"),
+        total => format!("{:.1}%", hit as f64 * 100.0 / total as f64),
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CoverageView {
+    /// Colour the lines of the editor by their coverage
+    heatmap: bool,
+}
+
+impl CoverageView {
+    /// Executions of the lines of a file of the editor, while it is shown there
+    pub fn heatmap(
+        &self,
+        tracker: &Tracker,
+        debug_info: &RefCell<Option<DebugInfo>>,
+        filename: &str,
+    ) -> Option<LineHits> {
+        if !self.heatmap {
+            return None;
+        }
+
+        let tracker = tracker.borrow();
+        let debug_info = debug_info.borrow();
+        let (coverage, debug_info) = tracker.coverage.as_ref().zip(debug_info.as_ref())?;
+
+        Some(coverage.file_lines(&debug_info.lines, filename))
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        tracker: Rc<Tracker>,
+        debug_info: &RefCell<Option<DebugInfo>>,
+    ) {
+        ui.heading("Coverage");
+        ui.label(
+            RichText::new("The lines come from the debug info of the program, built with -g")
+                .small()
+                .weak(),
+        );
+
+        let mut tracker = tracker.borrow_mut();
+        let recording = tracker.coverage.is_some();
+
+        ui.horizontal(|ui| {
+            if recording {
+                if ui.button("Stop").clicked() {
+                    tracker.coverage = None;
+                }
+            } else if ui.button("Record").clicked() {
+                tracker.coverage = Some(Coverage::new());
+            }
+
+            ui.checkbox(&mut self.heatmap, "Show in the editor");
+        });
+
+        let Some(coverage) = tracker.coverage.as_ref() else {
+            return;
+        };
+
+        let debug_info = debug_info.borrow();
+        let lines = debug_info.as_ref().map(|info| &info.lines);
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} instructions executed", coverage.executed()));
+
+            if ui.button("Clear").clicked() {
+                coverage.clear();
+            }
+
+            if let Some(lines) = lines.filter(|lines| !lines.is_empty()) {
+                if ui
+                    .button("Export lcov")
+                    .on_hover_text("The tracefile of lcov, for genhtml or the CI")
+                    .clicked()
+                {
+                    crate::simulator::export_text_file("coverage.info", coverage.to_lcov(lines));
+                }
+            }
+        });
+
+        let Some(lines) = lines.filter(|lines| !lines.is_empty()) else {
+            ui.add_space(8.0);
+            ui.label("The program has no line table, build it with debug info to map the lines");
+            return;
+        };
+
+        let files = coverage.lines(lines);
+        let (hit, total) = files.values().fold((0, 0), |(hit, total), lines| {
+            let covered = lines.values().filter(|&&hits| hits > 0).count();
+            (hit + covered, total + lines.len())
+        });
+
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new(format!(
+                "{hit} of {total} lines covered ({})",
+                percent(hit, total)
+            ))
+            .strong(),
+        );
+
+        ui.add_space(8.0);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("CoverageFiles")
+                .num_columns(3)
+                .spacing([20.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label(RichText::new("File").strong());
+                    ui.label(RichText::new("Lines").strong());
+                    ui.label(RichText::new("Covered").strong());
+                    ui.end_row();
+
+                    for (path, lines) in files.iter() {
+                        let hit = lines.values().filter(|&&hits| hits > 0).count();
+
+                        ui.monospace(path);
+                        ui.label(format!("{hit} / {}", lines.len()));
+                        ui.label(percent(hit, lines.len()));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
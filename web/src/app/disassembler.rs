@@ -9,10 +9,14 @@ const ARM_BOOTROM_DISASSEMBLY: &str = include_str!("../../assets/arm-bootrom.dis
 const RISCV_BOOTROM_DISASSEMBLY: &str = include_str!("../../assets/riscv-bootrom.dis");
 
 use super::Rp2350Component;
+use api_types::{ElfSymbol, ElfSymbolKind};
 use egui::RichText;
 use egui_extras::{Column, TableBuilder};
+use rp2350::bus::Bus;
+use rp2350::processor::hazard3::disasm;
+use rp2350::profiler::Function;
 use rp2350::Rp2350;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 
 const COLOR_CORE0: egui::Color32 = egui::Color32::BLUE;
 const COLOR_CORE1: egui::Color32 = egui::Color32::GREEN;
@@ -25,27 +29,68 @@ enum StickOption {
     None,
 }
 
+/// What happen when the execution reaches a breakpoint
+#[derive(serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointAction {
+    #[default]
+    Pause,
+    Snapshot,
+    SnapshotAndPause,
+}
+
+impl BreakpointAction {
+    pub fn should_pause(&self) -> bool {
+        matches!(self, Self::Pause | Self::SnapshotAndPause)
+    }
+
+    pub fn should_snapshot(&self) -> bool {
+        matches!(self, Self::Snapshot | Self::SnapshotAndPause)
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+enum ExportRegion {
+    #[default]
+    Flash,
+    Sram,
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Disassembler {
     codes: Vec<String>,
-    breakpoints: HashSet<u32>,
+    breakpoints: HashMap<u32, BreakpointAction>,
     pc_to_line_map: HashMap<u32, usize>,
     last_pc_core0: u32,
     last_pc_core1: u32,
     search_buffer: String,
     stick: StickOption,
+    export_region: ExportRegion,
+    export_start: String,
+    export_length: String,
+    /// Symbols and linker map of the last build, from the server
+    symbols: Vec<ElfSymbol>,
+    map: String,
+    /// Names of the addresses in the operands of a line, by its index
+    annotations: HashMap<usize, String>,
 }
 
 impl Default for Disassembler {
     fn default() -> Self {
         let mut res = Self {
             codes: Vec::new(),
-            breakpoints: HashSet::new(),
+            breakpoints: HashMap::new(),
             pc_to_line_map: HashMap::new(),
             last_pc_core0: 0,
             last_pc_core1: 0,
             search_buffer: String::new(),
             stick: StickOption::Core0,
+            export_region: ExportRegion::Flash,
+            export_start: String::from("20000000"),
+            export_length: String::from("1000"),
+            symbols: Vec::new(),
+            map: String::new(),
+            annotations: HashMap::new(),
         };
 
         res.codes
@@ -62,10 +107,84 @@ impl Disassembler {
         self.codes = codes;
         self.codes.extend(file.lines().map(String::from));
         self.update_pc_to_line_map();
+        self.update_annotations();
+    }
+
+    pub fn update_artifacts(&mut self, symbols: Vec<ElfSymbol>, map: String) {
+        self.symbols = symbols;
+        self.map = map;
+        self.update_annotations();
+    }
+
+    /// Functions of the last build, for the profiler
+    pub fn functions(&self) -> Vec<Function> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.kind == ElfSymbolKind::Function && symbol.size > 0)
+            .map(|symbol| Function::new(&symbol.name, symbol.address, symbol.address + symbol.size))
+            .collect()
+    }
+
+    /// Function or variable at an address, the smallest one if they overlap
+    fn symbol_at(&self, address: u32) -> Option<&ElfSymbol> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.kind != ElfSymbolKind::Other && symbol.contains(address))
+            .min_by_key(|symbol| symbol.size)
+    }
+
+    pub(crate) fn symbol_name(&self, address: u32) -> Option<String> {
+        let symbol = self.symbol_at(address)?;
+
+        Some(match address - symbol.address {
+            0 => format!("<{}>", symbol.name),
+            offset => format!("<{}+0x{:x}>", symbol.name, offset),
+        })
+    }
+
+    /// Names of the `0x` operands which are addresses of a symbol, e.g. the words of
+    /// the literal pools. objdump already names the targets of the jumps, and the
+    /// upper part loaded by `lui` is not an address on its own.
+    fn update_annotations(&mut self) {
+        let mut annotations = HashMap::new();
+
+        for (index, line) in self.codes.iter().enumerate() {
+            if self.parse_addr(line).is_none() || line.contains('<') || line.contains("lui") {
+                continue;
+            }
+
+            let Some((_, instruction)) = line.split_once(':') else {
+                continue;
+            };
+
+            let names: Vec<String> = instruction
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter_map(|token| u32::from_str_radix(token.strip_prefix("0x")?, 16).ok())
+                .filter_map(|address| self.symbol_name(address))
+                .collect();
+
+            if !names.is_empty() {
+                annotations.insert(index, names.join(" "));
+            }
+        }
+
+        self.annotations = annotations;
     }
 
     pub fn add_breakpoint(&mut self, addr: u32) {
-        self.breakpoints.insert(addr);
+        self.breakpoints.entry(addr).or_default();
+    }
+
+    pub fn set_breakpoint_action(&mut self, addr: u32, action: BreakpointAction) {
+        self.breakpoints.insert(addr, action);
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (u32, BreakpointAction)> + '_ {
+        self.breakpoints.iter().map(|(addr, action)| (*addr, *action))
+    }
+
+    pub fn breakpoint(&self, addr: &u32) -> Option<BreakpointAction> {
+        self.breakpoints.get(addr).copied()
     }
 
     pub fn remove_breakpoint(&mut self, addr: &u32) {
@@ -73,7 +192,7 @@ impl Disassembler {
     }
 
     pub fn has_breakpoint(&self, addr: &u32) -> bool {
-        self.breakpoints.contains(&addr)
+        self.breakpoints.contains_key(addr)
     }
 
     pub fn clear_breakpoints(&mut self) {
@@ -89,6 +208,79 @@ impl Disassembler {
         u32::from_str_radix(num, 16).ok()
     }
 
+    /// Labels of the loaded listing, from the `10000000 <main>:` lines, and the
+    /// functions of the symbol table
+    pub fn symbols(&self) -> BTreeMap<u32, String> {
+        let mut labels: BTreeMap<u32, String> = self
+            .codes
+            .iter()
+            .filter_map(|line| {
+                let (address, rest) = line.trim().split_once(' ')?;
+                let name = rest.strip_prefix('<')?.strip_suffix(">:")?;
+                let address = u32::from_str_radix(address, 16).ok()?;
+                Some((address, name.to_string()))
+            })
+            .collect();
+
+        for symbol in &self.symbols {
+            if symbol.kind == ElfSymbolKind::Function {
+                labels
+                    .entry(symbol.address)
+                    .or_insert_with(|| symbol.name.clone());
+            }
+        }
+
+        labels
+    }
+
+    fn export_ui(&mut self, ui: &mut egui::Ui, rp2350: &Rp2350) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.export_region, ExportRegion::Flash, "Flash");
+            ui.radio_value(&mut self.export_region, ExportRegion::Sram, "SRAM");
+
+            if self.export_region == ExportRegion::Sram {
+                ui.label("from");
+                ui.add(egui::TextEdit::singleline(&mut self.export_start).desired_width(80.0));
+                ui.label("length");
+                ui.add(egui::TextEdit::singleline(&mut self.export_length).desired_width(60.0));
+            }
+
+            if !ui.button("Export").clicked() {
+                return;
+            }
+
+            let (base, data) = match self.export_region {
+                // Only the part of the flash which was programmed
+                ExportRegion::Flash => {
+                    let flash: &[u8] = &rp2350.bus.flash;
+                    let end = flash.iter().rposition(|v| *v != 0).map_or(0, |v| v + 1);
+                    (Bus::XIP, &flash[..end.next_multiple_of(2)])
+                }
+                ExportRegion::Sram => {
+                    let parse = |v: &str| u32::from_str_radix(v.trim().trim_start_matches("0x"), 16);
+                    let (Ok(start), Ok(length)) = (parse(&self.export_start), parse(&self.export_length))
+                    else {
+                        crate::notify::error("Invalid SRAM range");
+                        return;
+                    };
+
+                    let sram: &[u8] = &rp2350.bus.sram;
+                    let offset = start.saturating_sub(Bus::SRAM) as usize;
+                    if start < Bus::SRAM || offset >= sram.len() {
+                        crate::notify::error("The range is outside of the SRAM");
+                        return;
+                    }
+
+                    let end = (offset + length as usize).min(sram.len());
+                    (start, &sram[offset..end])
+                }
+            };
+
+            let listing = disasm::listing(data, base, &self.symbols());
+            crate::simulator::export_text_file("disassembly.txt", listing);
+        });
+    }
+
     fn update_pc_to_line_map(&mut self) {
         self.pc_to_line_map.clear();
         for (i, line) in self.codes.iter().enumerate() {
@@ -186,6 +378,19 @@ impl Rp2350Component for Disassembler {
                 if ui.button("Clear Breakpoints").clicked() {
                     self.clear_breakpoints();
                 }
+                ui.end_row();
+
+                ui.label("Export disassembly");
+                self.export_ui(ui, rp2350);
+                ui.end_row();
+
+                if !self.map.is_empty() {
+                    ui.label("Linker map");
+                    if ui.button("Export").clicked() {
+                        crate::simulator::export_text_file("main.map", self.map.clone());
+                    }
+                    ui.end_row();
+                }
 
             });
 
@@ -250,7 +455,8 @@ impl Rp2350Component for Disassembler {
                         return;
                     };
 
-                    let has_breakpoint = self.breakpoints.contains(&addr);
+                    let breakpoint = self.breakpoint(&addr);
+                    let has_breakpoint = breakpoint.is_some();
                     let center = ui.available_rect_before_wrap().center();
                     let radius = 6.0;
 
@@ -262,14 +468,34 @@ impl Rp2350Component for Disassembler {
                     let response = ui
                         .allocate_rect(rect, egui::Sense::HOVER | egui::Sense::CLICK)
                         .on_hover_ui(|ui| {
-                            ui.label("Toggle Breakpoint");
+                            ui.label("Toggle Breakpoint (right click for more options)");
                         });
                     let mut color = None;
 
-                    // show tooltip
+                    response.context_menu(|ui| {
+                        for (action, text) in [
+                            (BreakpointAction::Pause, "Pause"),
+                            (BreakpointAction::Snapshot, "Take snapshot"),
+                            (BreakpointAction::SnapshotAndPause, "Take snapshot and pause"),
+                        ] {
+                            if ui.radio(breakpoint == Some(action), text).clicked() {
+                                self.set_breakpoint_action(addr, action);
+                                ui.close_menu();
+                            }
+                        }
+
+                        if has_breakpoint && ui.button("Remove").clicked() {
+                            self.remove_breakpoint(&addr);
+                            ui.close_menu();
+                        }
+                    });
 
-                    if has_breakpoint {
-                        color = Some(egui::Color32::RED);
+                    if let Some(action) = breakpoint {
+                        color = Some(match action {
+                            BreakpointAction::Pause => egui::Color32::RED,
+                            BreakpointAction::Snapshot => egui::Color32::ORANGE,
+                            BreakpointAction::SnapshotAndPause => egui::Color32::GOLD,
+                        });
                     } else if response.hovered() {
                         color = Some(faded_color(egui::Color32::LIGHT_RED));
                     }
@@ -299,7 +525,19 @@ impl Rp2350Component for Disassembler {
                     }
 
                     let line = &self.codes[line_index];
-                    ui.monospace(line);
+                    let response = ui.monospace(line);
+
+                    if let Some(annotation) = self.annotations.get(&line_index) {
+                        ui.label(RichText::new(annotation).monospace().weak());
+                    }
+
+                    // the function the line is part of, from the symbol table
+                    if let Some(name) = self
+                        .parse_addr(line)
+                        .and_then(|addr| self.symbol_name(addr))
+                    {
+                        response.on_hover_text(name);
+                    }
                 });
             });
         });
@@ -0,0 +1,111 @@
+/**
+ * @file app/dma.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief View window for the DMA peripheral
+ */
+use super::Rp2350Component;
+use egui::collapsing_header::CollapsingState;
+use rp2350::Rp2350;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Dma {
+    // None
+}
+
+impl Rp2350Component for Dma {
+    const NAME: &'static str = "DMA";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading("DMA");
+
+        let Ok(dma) = rp2350.dma.try_borrow() else {
+            ui.label("DMA peripheral is not available");
+            return;
+        };
+
+        let sniffer = dma.sniffer;
+
+        ui.label("Sniffer");
+        egui::Grid::new("dma_sniffer")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Enabled");
+                ui.label(if sniffer.is_enabled() { "Yes" } else { "No" });
+                ui.end_row();
+
+                ui.label("Channel");
+                ui.label(format!("{}", sniffer.channel()));
+                ui.end_row();
+
+                ui.label("Calculation");
+                ui.label(format!("{:?}", sniffer.calculation()));
+                ui.end_row();
+
+                ui.label("Running value");
+                ui.label(format!("{:#010X}", sniffer.data));
+                ui.end_row();
+
+                ui.label("Result");
+                ui.label(format!("{:#010X}", sniffer.result()));
+                ui.end_row();
+            });
+
+        ui.separator();
+
+        for (i, channel) in dma.channels.iter().enumerate() {
+            CollapsingState::load_with_default_open(
+                ui.ctx(),
+                ui.make_persistent_id(format!("dma_channel_{}", i)),
+                false,
+            )
+            .show_header(ui, |ui| {
+                let state = match (channel.is_enabled(), channel.busy()) {
+                    _ if channel.read_err() => "read error",
+                    _ if channel.write_err() => "write error",
+                    (_, true) => "busy",
+                    (true, false) => "idle",
+                    (false, false) => "disabled",
+                };
+                ui.label(format!("Channel {} ({})", i, state));
+            })
+            .body(|ui| {
+                egui::Grid::new(format!("dma_channel_{}", i))
+                    .num_columns(2)
+                    .spacing([40.0, 6.0])
+                    .striped(false)
+                    .show(ui, |ui| {
+                        ui.label("Read address");
+                        ui.label(format!("{:#010X}", channel.read_addr));
+                        ui.end_row();
+
+                        ui.label("Write address");
+                        ui.label(format!("{:#010X}", channel.write_addr));
+                        ui.end_row();
+
+                        ui.label("Transfer count");
+                        ui.label(format!(
+                            "{} / {}",
+                            channel.transfer_count & 0x0fff_ffff,
+                            channel.transfer_counter_reload & 0x0fff_ffff
+                        ));
+                        ui.end_row();
+
+                        ui.label("Data size");
+                        ui.label(format!("{} bytes", channel.datasize() as u32));
+                        ui.end_row();
+
+                        ui.label("Chain to");
+                        ui.label(format!("{}", channel.chain_to()));
+                        ui.end_row();
+
+                        ui.label("Sniffed");
+                        ui.label(if channel.sniff_en() { "Yes" } else { "No" });
+                        ui.end_row();
+                    });
+            });
+        }
+    }
+}
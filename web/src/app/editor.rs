@@ -2,9 +2,11 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::simulator::TaskCommand;
-use api_types::Language;
-use egui::ComboBox;
+use api_types::{Artifacts, CompilationRequest, Language, SharedProject, SourceCode, Target};
+use egui::text::{LayoutJob, LayoutSection};
+use egui::{Color32, ComboBox};
 use futures::channel::mpsc::Sender;
+use rp2350::coverage::LineHits;
 
 pub struct Example {
     pub name: &'static str,
@@ -78,64 +80,420 @@ pub const EXAMPLES: &[Example] = &[
 #[serde(default)]
 pub struct CodeEditor {
     pub language: Language,
-    pub code: String,
+    pub target: Target,
+    /// Files of the project, sent together to the server
+    pub files: Vec<SourceCode>,
+    /// Index of the file shown in the editor
+    pub open: usize,
+    /// Libraries linked to the pico-sdk projects
+    pub libraries: Vec<String>,
     pub skip_bootrom: bool,
+    /// Key of the servers which only compile for their users
+    pub api_key: String,
     pub is_flashing: Rc<RefCell<bool>>,
+    /// Libraries offered by the server, fetched once
+    #[serde(skip)]
+    available_libraries: Rc<RefCell<Option<Vec<String>>>>,
+    /// Output of the last build, streamed by the server
+    #[serde(skip)]
+    build_log: Rc<RefCell<Vec<String>>>,
+    /// New name of the open file while it is being renamed
+    #[serde(skip)]
+    renaming: Option<String>,
 }
 
 impl Default for CodeEditor {
     fn default() -> Self {
         Self {
             language: Language::C,
-            code: String::from(EXAMPLES[0].code),
+            target: Target::RiscV,
+            files: vec![SourceCode {
+                filename: Language::C.filename().to_string(),
+                code: String::from(EXAMPLES[0].code),
+            }],
+            open: 0,
+            libraries: Vec::new(),
             skip_bootrom: true,
+            api_key: String::new(),
             is_flashing: Rc::new(RefCell::new(false)),
+            available_libraries: Rc::new(RefCell::new(None)),
+            build_log: Rc::new(RefCell::new(Vec::new())),
+            renaming: None,
         }
     }
 }
 
+/// Background of the lines by their coverage, green once executed and red if never
+fn paint_coverage(job: &mut LayoutJob, heatmap: &LineHits) {
+    let text = job.text.clone();
+    let mut sections = Vec::with_capacity(job.sections.len());
+    let mut line = 1;
+
+    // the sections follow each other, split at the end of the lines
+    for section in job.sections.drain(..) {
+        let mut start = section.byte_range.start;
+
+        while start < section.byte_range.end {
+            let end = text[start..section.byte_range.end]
+                .find('\n')
+                .map_or(section.byte_range.end, |index| start + index + 1);
+
+            let mut format = section.format.clone();
+            match heatmap.get(&line) {
+                Some(0) => format.background = Color32::from_rgba_unmultiplied(200, 0, 0, 40),
+                Some(_) => format.background = Color32::from_rgba_unmultiplied(0, 160, 0, 40),
+                None => {}
+            }
+
+            sections.push(LayoutSection {
+                leading_space: match start == section.byte_range.start {
+                    true => section.leading_space,
+                    false => 0.0,
+                },
+                byte_range: start..end,
+                format,
+            });
+
+            if text[start..end].ends_with('\n') {
+                line += 1;
+            }
+
+            start = end;
+        }
+    }
+
+    job.sections = sections;
+}
+
 impl CodeEditor {
-    pub fn ui(&mut self, ui: &mut egui::Ui, tx: &mut Sender<TaskCommand>) {
-        let Self {
-            language,
-            code,
-            skip_bootrom,
-            is_flashing,
-        } = self;
+    /// Name of the file shown in the editor
+    pub fn open_filename(&self) -> Option<&str> {
+        self.files.get(self.open).map(|file| file.filename.as_str())
+    }
+
+    /// Replace the project by an example, they are all written in C
+    pub fn load_example(&mut self, example: &Example) {
+        self.language = Language::C;
+        self.files = vec![SourceCode {
+            filename: Language::C.filename().to_string(),
+            code: String::from(example.code),
+        }];
+        self.open = 0;
+        self.renaming = None;
+    }
+
+    /// The project to share, with the layout of the breadboard
+    pub fn project(&self, board: String) -> SharedProject {
+        SharedProject {
+            language: self.language,
+            target: self.target,
+            files: self.files.clone(),
+            libraries: self.libraries.clone(),
+            board,
+        }
+    }
+
+    /// Replace the project by a shared one
+    pub fn load_project(&mut self, project: SharedProject) {
+        self.language = project.language;
+        self.target = project.target;
+        self.files = project.files;
+        self.libraries = project.libraries;
+        self.open = 0;
+        self.renaming = None;
+    }
+
+    /// First free name like `file1.c`, with the extension given
+    fn new_file(&mut self, extension: &str) {
+        let filename = (1..)
+            .map(|n| format!("file{n}.{extension}"))
+            .find(|name| !self.files.iter().any(|file| &file.filename == name))
+            .unwrap_or_default();
+
+        self.files.push(SourceCode {
+            filename,
+            code: String::new(),
+        });
+        self.open = self.files.len() - 1;
+    }
+
+    fn rename_open(&mut self, filename: String) {
+        let renamed = SourceCode {
+            filename,
+            code: String::new(),
+        };
+
+        if !renamed.is_valid_path() {
+            crate::notify::warning(format!("Invalid file name {}", renamed.filename));
+        } else if self
+            .files
+            .iter()
+            .any(|file| file.filename == renamed.filename)
+        {
+            crate::notify::warning(format!("{} already exists", renamed.filename));
+        } else {
+            self.files[self.open].filename = renamed.filename;
+        }
+    }
+
+    fn libraries_ui(&mut self, ui: &mut egui::Ui) {
+        if self.available_libraries.borrow().is_none() {
+            let available = self.available_libraries.clone();
+            *available.borrow_mut() = Some(Vec::new());
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match crate::api::libraries().await {
+                    Ok(libraries) => *available.borrow_mut() = Some(libraries),
+                    Err(why) => log::warn!("Failed to get the libraries: {}", why),
+                }
+            });
+        }
+
+        let available = self.available_libraries.borrow();
+        let available = available.as_deref().unwrap_or_default();
+
+        ui.menu_button(format!("Libraries ({})", self.libraries.len()), |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for name in available {
+                        let mut linked = self.libraries.contains(name);
+
+                        if ui.checkbox(&mut linked, name).changed() {
+                            match linked {
+                                true => self.libraries.push(name.clone()),
+                                false => self.libraries.retain(|lib| lib != name),
+                            }
+                        }
+                    }
+                });
+        })
+        .response
+        .on_hover_text("Linked on top of pico_stdlib and the usual hardware libraries");
+    }
+
+    fn build_log_ui(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Build log");
+
+            if ui.small_button("Clear").clicked() {
+                self.build_log.borrow_mut().clear();
+            }
+        });
+
+        egui::ScrollArea::both()
+            .id_salt("BuildLog")
+            .stick_to_bottom(true)
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                for line in self.build_log.borrow().iter() {
+                    ui.label(egui::RichText::new(line).monospace());
+                }
+            });
+    }
+
+    /// Files of a directory, the subdirectories folded under their name
+    fn file_tree(&mut self, ui: &mut egui::Ui, dir: &str) {
+        let mut subdirs: Vec<&str> = Vec::new();
+        let mut entries = Vec::new();
+
+        for (index, file) in self.files.iter().enumerate() {
+            let Some(path) = file.filename.strip_prefix(dir) else {
+                continue;
+            };
+
+            match path.split_once('/') {
+                Some((subdir, _)) if !subdirs.contains(&subdir) => subdirs.push(subdir),
+                Some(_) => {}
+                None => entries.push((index, path.to_string())),
+            }
+        }
+
+        let mut subdirs: Vec<String> = subdirs.into_iter().map(String::from).collect();
+        subdirs.sort();
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+        for subdir in subdirs {
+            egui::CollapsingHeader::new(&subdir)
+                .id_salt(format!("{dir}{subdir}"))
+                .default_open(true)
+                .show(ui, |ui| self.file_tree(ui, &format!("{dir}{subdir}/")));
+        }
+
+        for (index, name) in entries {
+            if ui.selectable_label(self.open == index, name).clicked() {
+                self.open = index;
+                self.renaming = None;
+            }
+        }
+    }
+
+    fn files_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let extension = self
+                .language
+                .filename()
+                .rsplit('.')
+                .next()
+                .unwrap_or_default();
+            if ui.button("+ File").clicked() {
+                self.new_file(extension);
+            }
+
+            let headers = matches!(
+                self.language,
+                Language::C | Language::Cpp | Language::Assembly
+            );
+            if headers && ui.button("+ Header").clicked() {
+                self.new_file("h");
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Rename").clicked() {
+                self.renaming = Some(self.files[self.open].filename.clone());
+            }
+
+            if ui
+                .add_enabled(self.files.len() > 1, egui::Button::new("Delete"))
+                .clicked()
+            {
+                self.files.remove(self.open);
+                self.open = self.open.min(self.files.len() - 1);
+                self.renaming = None;
+            }
+        });
+
+        if let Some(name) = self.renaming.as_mut() {
+            let response = ui.text_edit_singleline(name);
+            response.request_focus();
+
+            if response.lost_focus() {
+                let name = self.renaming.take().unwrap_or_default();
+                if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.rename_open(name);
+                }
+            }
+        }
+
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("ProjectFiles")
+            .show(ui, |ui| self.file_tree(ui, ""));
+    }
+
+    /// `heatmap` colours the lines of the open file by their coverage
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        tx: &mut Sender<TaskCommand>,
+        heatmap: Option<&LineHits>,
+    ) {
+        // an empty project from an older save
+        if self.files.is_empty() {
+            *self = Self::default();
+        }
+
+        self.open = self.open.min(self.files.len() - 1);
 
         ui.horizontal(|ui| {
             ui.label("Language");
             ComboBox::from_label("")
-                .selected_text(format!("{:?}", language))
+                .selected_text(self.language.name())
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(language, Language::C, "C");
-                    // Add more languages here if needed
+                    for lang in Language::ALL {
+                        ui.selectable_value(&mut self.language, lang, lang.name());
+                    }
                 });
 
+            ui.label("Target");
+            ComboBox::from_id_salt("CodeTarget")
+                .selected_text(self.target.name())
+                .show_ui(ui, |ui| {
+                    for option in Target::ALL {
+                        ui.selectable_value(&mut self.target, option, option.name());
+                    }
+                })
+                .response
+                .on_hover_text("Core the code is built for, ARCHSEL follows it when flashing");
+
+            let pico_sdk = matches!(
+                self.language,
+                Language::C | Language::Cpp | Language::Assembly
+            );
+            if pico_sdk {
+                self.libraries_ui(ui);
+            }
+
             ui.add_space(30.0);
 
-            if *is_flashing.borrow() {
+            if *self.is_flashing.borrow() {
                 ui.spinner();
+                // the lines of the build arrive without any input
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_millis(100));
             } else {
                 if ui
                     .button("Flash")
                     .on_hover_text("Flash the code to the Pico2")
                     .clicked()
                 {
+                    let request = CompilationRequest {
+                        lang: self.language,
+                        source: self.files.clone(),
+                        target: self.target,
+                        compiler_options: None,
+                        libraries: match pico_sdk {
+                            true => self.libraries.clone(),
+                            false => Vec::new(),
+                        },
+                        // the symbols name the addresses in the disassembler
+                        artifacts: Artifacts {
+                            map: true,
+                            symbols: true,
+                        },
+                    };
+
+                    crate::api::set_api_key(&self.api_key);
                     let _ = tx.try_send(TaskCommand::FlashCode(
-                        language.clone(),
-                        code.clone(),
-                        *skip_bootrom,
-                        is_flashing.clone(),
+                        request,
+                        self.build_log.clone(),
+                        self.skip_bootrom,
+                        self.is_flashing.clone(),
                     ));
                 }
             }
 
-            ui.checkbox(skip_bootrom, "Skip Bootrom")
+            ui.checkbox(&mut self.skip_bootrom, "Skip Bootrom")
                 .on_hover_text("Skip the bootrom code");
+
+            ui.menu_button("API key", |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.api_key)
+                        .password(true)
+                        .hint_text("None"),
+                );
+            })
+            .response
+            .on_hover_text("Sent with the compile requests, for the servers which ask for one");
         });
 
+        egui::SidePanel::left("ProjectFiles")
+            .resizable(true)
+            .default_width(140.0)
+            .show_inside(ui, |ui| self.files_ui(ui));
+
+        if !self.build_log.borrow().is_empty() {
+            egui::TopBottomPanel::bottom("BuildLog")
+                .resizable(true)
+                .default_height(120.0)
+                .show_inside(ui, |ui| self.build_log_ui(ui));
+        }
+
         let theme = egui_extras::syntax_highlighting::CodeTheme::from_memory(ui.ctx(), ui.style());
+        let file = &mut self.files[self.open];
+        // highlighted by the extension of the file
+        let extension = file.extension().to_string();
 
         let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
             let mut layout_job = egui_extras::syntax_highlighting::highlight(
@@ -143,8 +501,12 @@ impl CodeEditor {
                 ui.style(),
                 &theme,
                 string,
-                &format!("{:?}", language),
+                &extension,
             );
+            if let Some(heatmap) = heatmap {
+                paint_coverage(&mut layout_job, heatmap);
+            }
+
             layout_job.wrap.max_width = wrap_width;
             ui.fonts(|f| f.layout_job(layout_job))
         };
@@ -154,7 +516,7 @@ impl CodeEditor {
 
             ui.add_sized(
                 size,
-                egui::TextEdit::multiline(code)
+                egui::TextEdit::multiline(&mut file.code)
                     .font(egui::TextStyle::Monospace) // for cursor height
                     .code_editor()
                     .lock_focus(true)
@@ -5,16 +5,59 @@
  * @brief View schematic and field of Raspberry Pi Pico 2
  */
 use super::Rp2350Component;
+use super::breadboard::BreadboardEditor;
 use egui::Margin;
 use egui::RichText;
+use rp2350::common::ArchitectureType;
+use rp2350::device::{
+    Bounce, ComponentRegistry, Dht, DhtKind, HcSr04, IrReceiver, IrRemote, NecCode, NecEvent,
+    RotaryEncoder,
+};
 use rp2350::gpio::*;
 use rp2350::Rp2350;
+use std::cell::RefCell;
+use std::f32::consts::TAU;
+use std::rc::Rc;
+
+/// Detents of a common EC11 style encoder
+const DETENTS_PER_REVOLUTION: f32 = 20.0;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Field {
     show_schematic: bool,
     scene_rect: egui::Rect,
     schematic_rect: egui::Rect,
+    bounce_glitches: u8,
+    bounce_duration: u64,
+    /// Distance to the obstacle in front of the ultrasonic sensor, `None` if there is nothing
+    distance_cm: Option<f64>,
+    dht_pin: u8,
+    dht22: bool,
+    temperature: f64,
+    humidity: f64,
+    ir_address: u8,
+    ir_remote_pin: u8,
+    ir_receiver_pin: u8,
+
+    #[serde(skip)]
+    ir_remote: Option<Rc<RefCell<IrRemote>>>,
+    #[serde(skip)]
+    ir_receiver: Option<Rc<RefCell<IrReceiver>>>,
+    #[serde(skip)]
+    dht: Option<Rc<RefCell<Dht>>>,
+    #[serde(skip)]
+    ultrasonic: Option<Rc<RefCell<HcSr04>>>,
+    #[serde(skip)]
+    encoder: Option<Rc<RefCell<RotaryEncoder>>>,
+    /// Angle of the knob, in radians
+    #[serde(skip)]
+    knob_angle: f32,
+    /// Rotation of the knob since the last detent
+    #[serde(skip)]
+    knob_rest: f32,
+    /// Layout of the breadboard, its components are placed again on start
+    #[serde(default)]
+    breadboard: BreadboardEditor,
 }
 
 impl Default for Field {
@@ -23,6 +66,24 @@ impl Default for Field {
             show_schematic: false,
             scene_rect: egui::Rect::ZERO,
             schematic_rect: egui::Rect::ZERO,
+            bounce_glitches: 3,
+            bounce_duration: 1_500,
+            distance_cm: Some(100.0),
+            dht_pin: 22,
+            dht22: true,
+            temperature: 25.0,
+            humidity: 50.0,
+            ir_address: 0x00,
+            ir_remote_pin: 16,
+            ir_receiver_pin: 17,
+            ir_remote: None,
+            ir_receiver: None,
+            dht: None,
+            ultrasonic: None,
+            encoder: None,
+            knob_angle: 0.0,
+            knob_rest: 0.0,
+            breadboard: BreadboardEditor::default(),
         }
     }
 }
@@ -37,17 +98,43 @@ impl Rp2350Component for Field {
             ui.radio_value(&mut self.show_schematic, true, "Schematic");
         });
 
+        ui.add_space(6.0);
+        chip_inputs_ui(ui, _rp2350);
+        ui.add_space(6.0);
+        self.rotary_encoder_ui(ui, _rp2350);
+        ui.add_space(6.0);
+        self.ultrasonic_ui(ui, _rp2350);
+        ui.add_space(6.0);
+        self.dht_ui(ui, _rp2350);
+        ui.add_space(6.0);
+        self.ir_remote_ui(ui, _rp2350);
+        ui.add_space(6.0);
+        self.ir_receiver_ui(ui, _rp2350);
         ui.add_space(12.0);
 
         if self.show_schematic {
             self.schematic_ui(ui);
         } else {
+            self.breadboard.ui(ui, _rp2350);
+            ui.add_space(12.0);
             self.field_ui(ui, _rp2350);
         }
     }
 }
 
 impl Field {
+    pub fn registry_mut(&mut self) -> &mut ComponentRegistry {
+        self.breadboard.registry_mut()
+    }
+
+    pub fn breadboard(&self) -> &BreadboardEditor {
+        &self.breadboard
+    }
+
+    pub fn breadboard_mut(&mut self) -> &mut BreadboardEditor {
+        &mut self.breadboard
+    }
+
     fn schematic_ui(&mut self, ui: &mut egui::Ui) {
         egui::Scene::new()
             .zoom_range(0.1..=3.0)
@@ -62,6 +149,289 @@ impl Field {
             });
     }
 
+    fn rotary_encoder_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            ui.label("Rotary encoder");
+
+            ui.add(egui::DragValue::new(&mut self.bounce_glitches).range(0..=8))
+                .on_hover_text("Glitches of the contacts after each edge");
+            ui.add(
+                egui::DragValue::new(&mut self.bounce_duration)
+                    .range(1..=150_000)
+                    .suffix(" cycles"),
+            )
+            .on_hover_text("Longest glitch of the contacts");
+
+            let Some(encoder) = self.encoder.as_ref() else {
+                let pins = rp2350::device::RotaryEncoderPins::default();
+                let connect = ui.button("Connect").on_hover_text(format!(
+                    "A on GPIO{}, B on GPIO{} and the push switch on GPIO{}",
+                    pins.a,
+                    pins.b,
+                    pins.button.unwrap_or_default(),
+                ));
+
+                if connect.clicked() {
+                    let encoder = Rc::new(RefCell::new(RotaryEncoder::new(pins)));
+                    rp2350.attach_device(Box::new(Rc::clone(&encoder)));
+                    self.encoder = Some(encoder);
+                }
+
+                return;
+            };
+
+            let Ok(mut encoder) = encoder.try_borrow_mut() else {
+                return;
+            };
+
+            encoder.bounce = Bounce {
+                glitches: self.bounce_glitches,
+                max_duration: self.bounce_duration,
+            };
+
+            let size = 48.0;
+            let (response, painter) =
+                ui.allocate_painter(egui::Vec2::splat(size), egui::Sense::click_and_drag());
+            let center = response.rect.center();
+
+            if let Some(pointer) = response.interact_pointer_pos() {
+                if response.dragged() {
+                    let previous = pointer - response.drag_delta() - center;
+                    let current = pointer - center;
+                    let mut delta = current.angle() - previous.angle();
+
+                    // keep the shortest way around when crossing the negative x axis
+                    if delta > TAU / 2.0 {
+                        delta -= TAU;
+                    } else if delta < -TAU / 2.0 {
+                        delta += TAU;
+                    }
+
+                    self.knob_angle += delta;
+                    self.knob_rest += delta;
+                }
+            }
+
+            let detent = TAU / DETENTS_PER_REVOLUTION;
+            let detents = (self.knob_rest / detent).trunc();
+            if detents != 0.0 {
+                encoder.rotate(detents as i32);
+                self.knob_rest -= detents * detent;
+            }
+
+            // the knob is pushed while the primary button is held without dragging
+            encoder.set_pressed(response.is_pointer_button_down_on() && !response.dragged());
+
+            let radius = size / 2.0 - 2.0;
+            let knob_color = match encoder.is_pressed() {
+                true => egui::Color32::DARK_GRAY,
+                false => egui::Color32::GRAY,
+            };
+
+            painter.circle_filled(center, radius, knob_color);
+            painter.line_segment(
+                [
+                    center,
+                    center + egui::Vec2::angled(self.knob_angle) * radius,
+                ],
+                egui::Stroke::new(3.0, egui::Color32::WHITE),
+            );
+
+            ui.label(format!("Position: {}", encoder.position()));
+        });
+    }
+
+    fn ultrasonic_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            ui.label("Ultrasonic sensor");
+
+            let mut obstacle = self.distance_cm.is_some();
+            ui.checkbox(&mut obstacle, "Obstacle");
+
+            let mut distance = self.distance_cm.unwrap_or(100.0);
+            ui.add_enabled(
+                obstacle,
+                egui::Slider::new(&mut distance, 2.0..=400.0).suffix(" cm"),
+            );
+            self.distance_cm = obstacle.then_some(distance);
+
+            let Some(sensor) = self.ultrasonic.as_ref() else {
+                let pins = rp2350::device::HcSr04Pins::default();
+                let connect = ui.button("Connect").on_hover_text(format!(
+                    "TRIG on GPIO{} and ECHO on GPIO{}",
+                    pins.trigger, pins.echo,
+                ));
+
+                if connect.clicked() {
+                    let sensor = Rc::new(RefCell::new(HcSr04::new(pins)));
+                    rp2350.attach_device(Box::new(Rc::clone(&sensor)));
+                    self.ultrasonic = Some(sensor);
+                }
+
+                return;
+            };
+
+            let Ok(mut sensor) = sensor.try_borrow_mut() else {
+                return;
+            };
+
+            sensor.distance_cm = self.distance_cm;
+
+            match sensor.last_echo_us() {
+                Some(echo) => ui.label(format!("Last echo: {echo:.0}µs")),
+                None => ui.label("No measurement yet"),
+            };
+        });
+    }
+
+    fn dht_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            ui.label("DHT sensor");
+
+            ui.add(
+                egui::DragValue::new(&mut self.temperature)
+                    .range(-40.0..=80.0)
+                    .speed(0.1)
+                    .suffix(" °C"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.humidity)
+                    .range(0.0..=100.0)
+                    .speed(0.1)
+                    .suffix(" %"),
+            );
+
+            let Some(dht) = self.dht.as_ref() else {
+                ui.radio_value(&mut self.dht22, false, "DHT11");
+                ui.radio_value(&mut self.dht22, true, "DHT22");
+                ui.add(
+                    egui::DragValue::new(&mut self.dht_pin)
+                        .range(0..=29)
+                        .prefix("GPIO"),
+                );
+
+                if ui.button("Connect").clicked() {
+                    let kind = match self.dht22 {
+                        true => DhtKind::Dht22,
+                        false => DhtKind::Dht11,
+                    };
+
+                    let dht = Rc::new(RefCell::new(Dht::new(self.dht_pin, kind)));
+                    rp2350.attach_device(Box::new(Rc::clone(&dht)));
+                    self.dht = Some(dht);
+                }
+
+                return;
+            };
+
+            let Ok(mut dht) = dht.try_borrow_mut() else {
+                return;
+            };
+
+            dht.temperature = self.temperature;
+            dht.humidity = self.humidity;
+
+            ui.label(format!("Readings: {}", dht.readings()));
+        });
+    }
+
+    fn ir_remote_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            ui.label("IR remote");
+
+            ui.add(
+                egui::DragValue::new(&mut self.ir_address)
+                    .hexadecimal(2, false, true)
+                    .prefix("Address 0x"),
+            );
+
+            let Some(remote) = self.ir_remote.as_ref() else {
+                ui.add(
+                    egui::DragValue::new(&mut self.ir_remote_pin)
+                        .range(0..=29)
+                        .prefix("GPIO"),
+                );
+
+                if ui.button("Connect").clicked() {
+                    let remote = Rc::new(RefCell::new(IrRemote::new(self.ir_remote_pin)));
+                    rp2350.attach_device(Box::new(Rc::clone(&remote)));
+                    self.ir_remote = Some(remote);
+                }
+
+                return;
+            };
+
+            let Ok(mut remote) = remote.try_borrow_mut() else {
+                return;
+            };
+
+            let mut held = false;
+
+            for command in 0..10 {
+                let button = ui.button(format!("{command}"));
+
+                if button.clicked() {
+                    remote.press(NecCode::new(self.ir_address, command));
+                }
+
+                // repeat codes are sent while the button is held
+                held |= button.is_pointer_button_down_on();
+            }
+
+            remote.held = held;
+
+            if let Some(code) = remote.last_code() {
+                ui.label(format!("Sent: 0x{:02x}", code.command));
+            }
+        });
+    }
+
+    fn ir_receiver_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            ui.label("IR receiver");
+
+            let Some(receiver) = self.ir_receiver.as_ref() else {
+                ui.add(
+                    egui::DragValue::new(&mut self.ir_receiver_pin)
+                        .range(0..=29)
+                        .prefix("GPIO"),
+                );
+
+                let connect = ui
+                    .button("Connect")
+                    .on_hover_text("Decode the NEC frames sent by the firmware to an IR LED");
+
+                if connect.clicked() {
+                    let receiver = Rc::new(RefCell::new(IrReceiver::new(self.ir_receiver_pin)));
+                    rp2350.attach_device(Box::new(Rc::clone(&receiver)));
+                    self.ir_receiver = Some(receiver);
+                }
+
+                return;
+            };
+
+            let Ok(mut receiver) = receiver.try_borrow_mut() else {
+                return;
+            };
+
+            match receiver.events().last() {
+                Some(NecEvent::Code(code)) => ui.label(format!(
+                    "Received: address 0x{:02x}, command 0x{:02x}",
+                    code.address, code.command
+                )),
+                Some(NecEvent::Repeat) => ui.label("Received: repeat"),
+                Some(NecEvent::Invalid(bits)) => ui.label(format!("Invalid frame: 0x{bits:08x}")),
+                None => ui.label("Nothing received yet"),
+            };
+
+            ui.label(format!("Timing errors: {}", receiver.errors()));
+
+            if ui.button("Clear").clicked() {
+                receiver.clear();
+            }
+        });
+    }
+
     fn field_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
         egui::Scene::new()
             .zoom_range(0.1..=3.0)
@@ -76,6 +446,65 @@ impl Field {
     }
 }
 
+/// Board level inputs of the chip which are not ordinary GPIOs
+fn chip_inputs_ui(ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+    ui.horizontal(|ui| {
+        let mut bootsel = rp2350.is_bootsel_pressed();
+        if ui
+            .toggle_value(&mut bootsel, "BOOTSEL")
+            .on_hover_text("Hold the BOOTSEL button, the bootrom samples it on the next reset")
+            .changed()
+        {
+            rp2350.set_bootsel(bootsel);
+        }
+
+        let run = ui
+            .button("RUN")
+            .on_hover_text("Hold to pull the RUN pin low, the chip restarts once released");
+
+        let held = run.is_pointer_button_down_on();
+        if held != rp2350.is_held_in_reset() {
+            rp2350.set_run_pin(!held);
+            if !held {
+                crate::notify::info("Chip restarted by the RUN pin");
+            }
+        }
+
+        if rp2350.is_held_in_reset() {
+            ui.label(RichText::new("Held in reset").color(egui::Color32::RED));
+        }
+
+        ui.separator();
+
+        // ARCHSEL is only sampled by a reset
+        let mut architecture = rp2350.selected_architecture();
+        egui::ComboBox::from_id_salt("ArchitectureSelect")
+            .selected_text(architecture_name(architecture))
+            .show_ui(ui, |ui| {
+                for option in [ArchitectureType::Hazard3, ArchitectureType::CortexM33] {
+                    ui.selectable_value(&mut architecture, option, architecture_name(option));
+                }
+            })
+            .response
+            .on_hover_text("Architecture of the cores (ARCHSEL), applied on the next reset");
+
+        if architecture != rp2350.selected_architecture() {
+            rp2350.select_architecture(architecture);
+            crate::notify::info(format!(
+                "{} selected, reset the chip to apply it",
+                architecture_name(architecture)
+            ));
+        }
+    });
+}
+
+fn architecture_name(architecture: ArchitectureType) -> &'static str {
+    match architecture {
+        ArchitectureType::Hazard3 => "RISC-V (Hazard3)",
+        ArchitectureType::CortexM33 => "Arm (Cortex-M33)",
+    }
+}
+
 #[rustfmt::skip]
 fn draw_gpio_state(ui: &mut egui::Ui, gpio: &GpioController, is_left: bool) {
     ui.vertical(|ui| {
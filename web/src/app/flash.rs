@@ -17,6 +17,6 @@ impl Rp2350Component for Flash {
 
     fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
         ui.heading("Flash");
-        self.view.ui(ui, &rp2350.bus.flash);
+        self.view.ui_mut(ui, &mut rp2350.bus.flash);
     }
 }
@@ -2,17 +2,95 @@
  * @file app/i2c.rs
  * @author Nguyen Le Duy
  * @date 11/05/2025
- * @brief View window for the I2C peripheral
+ * @brief View window for the I2C peripheral and the devices on its bus
  */
 use super::Rp2350Component;
 use crate::tracker::I2cTracker;
+use egui::{Color32, ComboBox, DragValue, RichText, Sense, Vec2};
 use rp2350::Rp2350;
+use rp2350::device::{Eeprom, Mpu6050, Ssd1306, eeprom, mpu6050, ssd1306};
+use rp2350::peripherals::I2cDeviceRef;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+enum DeviceKind {
+    Eeprom,
+    Mpu6050,
+    Ssd1306,
+}
+
+impl DeviceKind {
+    const ALL: [Self; 3] = [Self::Eeprom, Self::Mpu6050, Self::Ssd1306];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Eeprom => "24LC256 EEPROM",
+            Self::Mpu6050 => "MPU6050 IMU",
+            Self::Ssd1306 => "SSD1306 OLED",
+        }
+    }
+
+    fn address(self) -> u8 {
+        match self {
+            Self::Eeprom => eeprom::EEPROM_ADDRESS,
+            Self::Mpu6050 => mpu6050::MPU6050_ADDRESS,
+            Self::Ssd1306 => ssd1306::SSD1306_ADDRESS,
+        }
+    }
+}
+
+enum Device {
+    Eeprom(Rc<RefCell<Eeprom>>),
+    Mpu6050(Rc<RefCell<Mpu6050>>),
+    Ssd1306(Rc<RefCell<Ssd1306>>),
+}
+
+impl Device {
+    fn new(kind: DeviceKind, oled_height: usize) -> Self {
+        match kind {
+            DeviceKind::Eeprom => Self::Eeprom(Rc::new(RefCell::new(Eeprom::new_24lc256()))),
+            DeviceKind::Mpu6050 => Self::Mpu6050(Rc::new(RefCell::new(Mpu6050::new()))),
+            DeviceKind::Ssd1306 => Self::Ssd1306(Rc::new(RefCell::new(Ssd1306::new(oled_height)))),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Eeprom(_) => DeviceKind::Eeprom.name(),
+            Self::Mpu6050(_) => DeviceKind::Mpu6050.name(),
+            Self::Ssd1306(_) => DeviceKind::Ssd1306.name(),
+        }
+    }
+
+    fn bus_ref(&self) -> I2cDeviceRef {
+        match self {
+            Self::Eeprom(device) => device.clone(),
+            Self::Mpu6050(device) => device.clone(),
+            Self::Ssd1306(device) => device.clone(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct I2c<const IDX: usize> {
-    // None
+    kind: DeviceKind,
+    address: u8,
+    oled_height: usize,
+    #[serde(skip)]
+    devices: Vec<(u8, Device)>,
+}
+
+impl<const IDX: usize> Default for I2c<IDX> {
+    fn default() -> Self {
+        Self {
+            kind: DeviceKind::Ssd1306,
+            address: DeviceKind::Ssd1306.address(),
+            oled_height: 64,
+            devices: Vec::new(),
+        }
+    }
 }
 
 impl<const IDX: usize> Rp2350Component for I2c<IDX> {
@@ -26,119 +104,260 @@ impl<const IDX: usize> Rp2350Component for I2c<IDX> {
     ) {
         ui.heading(format!("I2C {IDX}"));
 
-        let tracker = tracker.borrow();
-        match IDX {
-            0 => view_i2c(ui, &rp2350.bus.peripherals.i2c0, &tracker.i2c[0]),
-            1 => view_i2c(ui, &rp2350.bus.peripherals.i2c1, &tracker.i2c[1]),
-            _ => unreachable!(),
+        {
+            let tracker = tracker.borrow();
+            match IDX {
+                0 => view_i2c(ui, rp2350, &rp2350.bus.peripherals.i2c0, &tracker.i2c[0]),
+                1 => view_i2c(ui, rp2350, &rp2350.bus.peripherals.i2c1, &tracker.i2c[1]),
+                _ => unreachable!(),
+            }
+        }
+
+        ui.add_space(8.0);
+        ui.separator();
+        self.attach_ui(ui, rp2350);
+        ui.add_space(6.0);
+        self.devices_ui(ui, rp2350);
+    }
+}
+
+impl<const IDX: usize> I2c<IDX> {
+    fn attach_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            let kind = self.kind;
+            ComboBox::from_id_salt(ui.id().with("device kind"))
+                .selected_text(self.kind.name())
+                .show_ui(ui, |ui| {
+                    for kind in DeviceKind::ALL {
+                        ui.selectable_value(&mut self.kind, kind, kind.name());
+                    }
+                });
+
+            if self.kind != kind {
+                self.address = self.kind.address();
+            }
+
+            ui.label("at");
+            ui.add(
+                DragValue::new(&mut self.address)
+                    .range(0x08..=0x77)
+                    .hexadecimal(2, false, true)
+                    .prefix("0x"),
+            );
+
+            if self.kind == DeviceKind::Ssd1306 {
+                ComboBox::from_id_salt(ui.id().with("oled height"))
+                    .selected_text(format!("128x{}", self.oled_height))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.oled_height, 64, "128x64");
+                        ui.selectable_value(&mut self.oled_height, 32, "128x32");
+                    });
+            }
+
+            if ui.button("Attach").clicked() {
+                let device = Device::new(self.kind, self.oled_height);
+
+                match rp2350.attach_i2c_device(IDX as u8, self.address, device.bus_ref()) {
+                    Ok(()) => {
+                        self.devices.push((self.address, device));
+                        self.devices.sort_by_key(|(address, _)| *address);
+                    }
+                    Err(why) => crate::notify::error(why.to_string()),
+                }
+            }
+        });
+    }
+
+    fn devices_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        if self.devices.is_empty() {
+            ui.label(RichText::new("No device on the bus").weak());
+            return;
+        }
+
+        let mut detached = None;
+
+        for (address, device) in self.devices.iter() {
+            let title = format!("{} at {address:#04X}", device.name());
+
+            egui::CollapsingHeader::new(title)
+                .id_salt((IDX, *address))
+                .default_open(true)
+                .show(ui, |ui| {
+                    match device {
+                        Device::Eeprom(eeprom) => eeprom_ui(ui, &mut eeprom.borrow_mut()),
+                        Device::Mpu6050(mpu) => mpu6050_ui(ui, &mut mpu.borrow_mut()),
+                        Device::Ssd1306(oled) => ssd1306_ui(ui, &oled.borrow()),
+                    }
+
+                    if ui.button("Detach").clicked() {
+                        detached = Some(*address);
+                    }
+                });
+        }
+
+        if let Some(address) = detached {
+            rp2350.detach_i2c_device(IDX as u8, address);
+            self.devices.retain(|(device, _)| *device != address);
         }
     }
 }
 
 fn view_i2c<const IDX: usize>(
     ui: &mut egui::Ui,
-    _i2c: &Rc<RefCell<rp2350::peripherals::I2c<IDX>>>,
+    rp2350: &Rp2350,
+    i2c: &Rc<RefCell<rp2350::peripherals::I2c<IDX>>>,
     _tracker: &I2cTracker,
 ) {
-    ui.label("I2C peripheral is not implemented yet");
-    /*
     let i2c = i2c.borrow();
-    egui::Grid::new(format!("Uart {IDX}"))
+
+    egui::Grid::new(format!("I2c {IDX}"))
         .num_columns(2)
         .spacing([40.0, 6.0])
         .striped(false)
         .show(ui, |ui| {
-            // is enabled
             ui.label("Enabled");
-            if uart.is_enabled() {
-                ui.label("Yes");
-            } else {
-                ui.label("No");
-            }
+            ui.label(if i2c.is_enabled() { "Yes" } else { "No" });
             ui.end_row();
 
-            // is TX enabled
-            ui.label("TX Enabled");
-            if uart.is_transmit_enabled() {
-                ui.label("Yes");
+            ui.label("Mode");
+            ui.label(if i2c.is_master_mode() {
+                "Master"
             } else {
-                ui.label("No");
-            }
+                "Slave"
+            });
             ui.end_row();
 
-            // is RX enabled
-            ui.label("RX Enabled");
-            if uart.is_receive_enabled() {
-                ui.label("Yes");
-            } else {
-                ui.label("No");
-            }
+            ui.label("Target address");
+            ui.label(format!("{:#04X}", i2c.target_address & 0x7F));
             ui.end_row();
 
-            // Baud rate
-            ui.label("Baud Rate");
-            ui.label(format!("{}", uart.get_baudrate()));
+            // IC_CON.SPEED selects the standard or the fast mode counts
+            let (high, low) = match (i2c.ctrl >> 1) & 0b11 {
+                1 => (i2c.ssclk_hcnt, i2c.ssclk_lcnt),
+                _ => (i2c.fsclk_hcnt, i2c.fsclk_lcnt),
+            };
+            let period = (high as u64 + low as u64).max(1);
+
+            ui.label("SCL");
+            ui.label(format!("{} kHz", rp2350.clock.clk_sys() / period / 1000));
             ui.end_row();
 
-            // Data bits
-            ui.label("Data bits");
-            ui.label(format!("{} bits", uart.word_len()));
+            ui.label("RX FIFO");
+            ui.label(format!("{} / 16", i2c.rx_fifo.len()));
             ui.end_row();
 
-            // Stop bits
-            ui.label("Stop bits");
-            ui.label(if uart.two_stop_bits() {
-                "2 bits"
-            } else {
-                "1 bit"
+            ui.label("Last abort");
+            ui.label(match i2c.tx_abort_source {
+                0 => "-".to_string(),
+                source => format!("{source:#010X}"),
             });
             ui.end_row();
+        });
+}
 
-            // Parity Odd/Even/None
-            ui.label("Parity");
-            if uart.is_parity_enabled() {
-                ui.label(if uart.is_parity_even() { "Even" } else { "Odd" });
-            } else {
-                ui.label("None");
+fn eeprom_ui(ui: &mut egui::Ui, eeprom: &mut Eeprom) {
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} bytes, pages of {}, address {:#06X}",
+            eeprom.memory.len(),
+            eeprom.page_size(),
+            eeprom.address()
+        ));
+
+        if ui.button("Erase").clicked() {
+            eeprom.memory.fill(0xFF);
+        }
+    });
+
+    let rows = eeprom.memory.len().div_ceil(16);
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+
+    egui::ScrollArea::vertical()
+        .id_salt("eeprom memory")
+        .max_height(200.0)
+        .show_rows(ui, row_height, rows, |ui, range| {
+            for row in range {
+                let start = row * 16;
+                let bytes = &eeprom.memory[start..(start + 16).min(eeprom.memory.len())];
+                let hex: Vec<String> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+                ui.label(RichText::new(format!("{:04X}: {}", row * 16, hex.join(" "))).monospace());
             }
-            ui.end_row();
         });
+}
 
-    // FIFO
-    // Transmitting FIFO
+fn mpu6050_ui(ui: &mut egui::Ui, mpu: &mut Mpu6050) {
+    if mpu.is_sleeping() {
+        ui.label(RichText::new("Asleep, waiting for PWR_MGMT_1").weak());
+    }
 
-    // Receiving FIFO
+    egui::Grid::new("mpu6050")
+        .num_columns(4)
+        .spacing([12.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("Acceleration");
+            for value in mpu.accel.iter_mut() {
+                ui.add(
+                    DragValue::new(value)
+                        .speed(0.01)
+                        .range(-16.0..=16.0)
+                        .suffix(" g"),
+                );
+            }
+            ui.end_row();
 
-    ui.collapsing("Transmitted value", |ui| {
-        ScrollArea::vertical()
-            .max_width(f32::INFINITY)
-            .max_height(200.0) // TODO
-            .stick_to_bottom(true)
-            .show(ui, |ui| {
-                let mut str = String::with_capacity(uart_tracker.tx.len());
+            ui.label("Rotation");
+            for value in mpu.gyro.iter_mut() {
+                ui.add(
+                    DragValue::new(value)
+                        .speed(1.0)
+                        .range(-2000.0..=2000.0)
+                        .suffix(" °/s"),
+                );
+            }
+            ui.end_row();
 
-                for ch in &uart_tracker.tx {
-                    str.push(char::from(*ch));
-                }
+            ui.label("Temperature");
+            ui.add(
+                DragValue::new(&mut mpu.temperature)
+                    .speed(0.1)
+                    .range(-40.0..=85.0)
+                    .suffix(" °C"),
+            );
+            ui.end_row();
+        });
+}
 
-                ui.label(RichText::new(str).monospace());
-            });
-    });
+fn ssd1306_ui(ui: &mut egui::Ui, oled: &Ssd1306) {
+    let size = 3.0;
+    let (response, painter) = ui.allocate_painter(
+        Vec2::new(oled.width() as f32 * size, oled.height() as f32 * size),
+        Sense::hover(),
+    );
 
-    ui.collapsing("Received value", |ui| {
-        ScrollArea::vertical()
-            .max_width(f32::INFINITY)
-            .max_height(200.0) // TODO
-            .stick_to_bottom(true)
-            .show(ui, |ui| {
-                let mut str = String::with_capacity(uart_tracker.rx.len());
+    painter.rect_filled(response.rect, 0.0, Color32::BLACK);
 
-                for ch in &uart_tracker.rx {
-                    str.push(char::from(*ch as u8));
-                }
+    // the contrast dims the lit pixels
+    let lit = Color32::from_rgb(120, 200, 255).gamma_multiply(0.4 + oled.contrast as f32 / 425.0);
 
-                ui.label(RichText::new(str).monospace());
-            });
-    });
-    */
+    for y in 0..oled.height() {
+        for x in 0..oled.width() {
+            if !oled.pixel(x, y) {
+                continue;
+            }
+
+            let min = response.rect.min + Vec2::new(x as f32, y as f32) * size;
+            let rect = egui::Rect::from_min_size(min, Vec2::splat(size - 0.5));
+            painter.rect_filled(rect, 0.0, lit);
+        }
+    }
+
+    if !oled.display_on {
+        painter.text(
+            response.rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "Display off",
+            egui::FontId::proportional(14.0),
+            Color32::DARK_GRAY,
+        );
+    }
 }
@@ -0,0 +1,163 @@
+/**
+ * @file app/instruction_trace.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Recording of the last instructions of the cores, searched and exported for a postmortem
+ */
+use super::Rp2350Component;
+use crate::Tracker;
+use egui::RichText;
+use egui_extras::{Column, TableBuilder};
+use rp2350::trace::instructions::{InstructionTrace, DEFAULT_TRACE_CAPACITY};
+use rp2350::Rp2350;
+use std::rc::Rc;
+
+/// Searching stops there, from the newest instruction back
+const MAX_MATCHES: usize = 10_000;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct InstructionTraceView {
+    capacity: usize,
+    #[serde(skip)]
+    query: String,
+}
+
+impl Default for InstructionTraceView {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_TRACE_CAPACITY,
+            query: String::new(),
+        }
+    }
+}
+
+impl InstructionTraceView {
+    fn table_ui(&self, ui: &mut egui::Ui, trace: &InstructionTrace) {
+        let entries = trace.entries();
+
+        // the newest matches, in the order they were executed
+        let rows: Vec<usize> = match self.query.trim() {
+            "" => (0..entries.len()).collect(),
+            query => {
+                let mut rows: Vec<usize> = (0..entries.len())
+                    .rev()
+                    .filter(|&index| entries[index].matches(query))
+                    .take(MAX_MATCHES)
+                    .collect();
+                rows.reverse();
+                rows
+            }
+        };
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .stick_to_bottom(true)
+            .column(Column::exact(40.0))
+            .column(Column::exact(90.0))
+            .column(Column::exact(90.0))
+            .column(Column::exact(90.0))
+            .column(Column::exact(80.0))
+            .column(Column::remainder())
+            .header(20.0, |mut header| {
+                for title in ["Core", "Cycle", "PC", "Opcode", "Mnemonic", "Writes"] {
+                    header.col(|ui| {
+                        ui.label(RichText::new(title).strong());
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, rows.len(), |mut row| {
+                    let entry = &entries[rows[row.index()]];
+
+                    row.col(|ui| {
+                        ui.label(entry.core.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.monospace(entry.cycle.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.monospace(format!("{:08x}", entry.pc));
+                    });
+                    row.col(|ui| {
+                        ui.monospace(format!("{:08x}", entry.opcode));
+                    });
+                    row.col(|ui| {
+                        ui.label(entry.mnemonic);
+                    });
+                    row.col(|ui| {
+                        ui.monospace(trace.describe_writes(entry));
+                    });
+                });
+            });
+    }
+}
+
+impl Rp2350Component for InstructionTraceView {
+    const NAME: &'static str = "Instruction Trace";
+
+    fn ui_with_tracker(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350, tracker: Rc<Tracker>) {
+        ui.heading("Instruction Trace");
+        ui.label(
+            RichText::new("The oldest instructions are dropped once the trace is full")
+                .small()
+                .weak(),
+        );
+
+        let mut tracker = tracker.borrow_mut();
+        let recording = tracker.instruction_trace.is_some();
+
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!recording, |ui| {
+                ui.label("Capacity");
+                ui.add(
+                    egui::DragValue::new(&mut self.capacity)
+                        .range(1_000..=10_000_000)
+                        .speed(10_000),
+                );
+            });
+
+            if recording {
+                if ui.button("Stop").clicked() {
+                    tracker.instruction_trace = None;
+                }
+            } else if ui.button("Record").clicked() {
+                tracker.instruction_trace = Some(InstructionTrace::new(rp2350, self.capacity));
+            }
+        });
+
+        let Some(trace) = tracker.instruction_trace.as_ref() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} instructions, {} dropped",
+                trace.len(),
+                trace.dropped()
+            ));
+
+            if ui.button("Clear").clicked() {
+                trace.clear();
+            }
+
+            if ui.button("Export CSV").clicked() {
+                crate::simulator::export_trace_file("csv", trace.to_csv());
+            }
+
+            if ui.button("Export JSON").clicked() {
+                crate::simulator::export_trace_file("json", trace.to_json());
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Search");
+            ui.text_edit_singleline(&mut self.query)
+                .on_hover_text("A mnemonic, or the address of an instruction in hexadecimal");
+        });
+
+        ui.add_space(8.0);
+        self.table_ui(ui, trace);
+    }
+}
@@ -0,0 +1,238 @@
+/**
+ * @file app/led_matrix.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Virtual MAX7219 and HUB75 LED matrices wired to the GPIOs
+ */
+use super::Rp2350Component;
+use egui::{Color32, ComboBox, DragValue, Sense, Vec2};
+use rp2350::device::{Hub75, Hub75Pins, Max7219, Max7219Pins};
+use rp2350::Rp2350;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+enum MatrixKind {
+    Max7219,
+    Hub75,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct LedMatrix {
+    kind: MatrixKind,
+    nof_modules: usize,
+    max7219_pins: [u8; 3],
+    /// The MAX7219 on the bus of SPI0 or SPI1 instead of sampling its pins
+    max7219_spi: Option<u8>,
+    panel_size: [usize; 2],
+
+    #[serde(skip)]
+    max7219: Option<Rc<RefCell<Max7219>>>,
+    #[serde(skip)]
+    hub75: Option<Rc<RefCell<Hub75>>>,
+}
+
+impl Default for LedMatrix {
+    fn default() -> Self {
+        let pins = Max7219Pins::default();
+
+        Self {
+            kind: MatrixKind::Max7219,
+            nof_modules: 4,
+            max7219_pins: [pins.din, pins.clk, pins.cs],
+            max7219_spi: None,
+            panel_size: [64, 32],
+            max7219: None,
+            hub75: None,
+        }
+    }
+}
+
+impl Rp2350Component for LedMatrix {
+    const NAME: &'static str = "LED Matrix";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading(Self::NAME);
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.kind, MatrixKind::Max7219, "MAX7219");
+            ui.radio_value(&mut self.kind, MatrixKind::Hub75, "HUB75");
+        });
+
+        ui.add_space(6.0);
+
+        match self.kind {
+            MatrixKind::Max7219 => self.max7219_ui(ui, rp2350),
+            MatrixKind::Hub75 => self.hub75_ui(ui, rp2350),
+        }
+    }
+}
+
+impl LedMatrix {
+    fn max7219_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        let [din, clk, cs] = &mut self.max7219_pins;
+        let connected = self.max7219.is_some();
+
+        egui::Grid::new("max7219_pins")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("Bus");
+                ui.add_enabled_ui(!connected, |ui| {
+                    let bus = |spi: Option<u8>| match spi {
+                        Some(spi) => format!("SPI{spi}"),
+                        None => "GPIO pins".to_string(),
+                    };
+
+                    ComboBox::from_id_salt("max7219_bus")
+                        .selected_text(bus(self.max7219_spi))
+                        .show_ui(ui, |ui| {
+                            for spi in [None, Some(0), Some(1)] {
+                                ui.selectable_value(&mut self.max7219_spi, spi, bus(spi));
+                            }
+                        });
+                });
+                ui.end_row();
+
+                // on an SPI bus only the chip select is a GPIO of its own
+                let pins = match self.max7219_spi {
+                    Some(_) => vec![("CS", cs)],
+                    None => vec![("DIN", din), ("CLK", clk), ("CS", cs)],
+                };
+
+                for (name, pin) in pins {
+                    ui.label(name);
+                    ui.add_enabled(
+                        !connected || self.max7219_spi.is_none(),
+                        DragValue::new(pin).range(0..=29).prefix("GPIO"),
+                    );
+                    ui.end_row();
+                }
+
+                ui.label("Modules");
+                ui.add_enabled(
+                    self.max7219.is_none(),
+                    DragValue::new(&mut self.nof_modules).range(1..=16),
+                );
+                ui.end_row();
+            });
+
+        let [din, clk, cs] = self.max7219_pins;
+        let pins = Max7219Pins { din, clk, cs };
+
+        let Some(device) = self.max7219.as_ref() else {
+            if ui.button("Connect").clicked() {
+                let device = Rc::new(RefCell::new(Max7219::new(pins, self.nof_modules)));
+
+                match self.max7219_spi {
+                    Some(spi) => match rp2350.attach_spi_device(spi, cs, device.clone()) {
+                        Ok(()) => self.max7219 = Some(device),
+                        Err(why) => crate::notify::error(why.to_string()),
+                    },
+                    None => {
+                        rp2350.attach_device(Box::new(Rc::clone(&device)));
+                        self.max7219 = Some(device);
+                    }
+                }
+            }
+
+            return;
+        };
+
+        let Ok(mut device) = device.try_borrow_mut() else {
+            return;
+        };
+
+        // the pins can be rewired while the device is attached
+        device.pins = pins;
+
+        ui.add_space(12.0);
+
+        let size = 12.0;
+        let nof_modules = device.modules.len();
+        let (response, painter) = ui.allocate_painter(
+            Vec2::new(nof_modules as f32 * (8.0 * size + 1.0), 8.0 * size),
+            Sense::hover(),
+        );
+
+        for (index, module) in device.modules.iter().enumerate() {
+            // the module wired to the chip is drawn on the right, the text scrolls in from there
+            let offset = (nof_modules - 1 - index) as f32 * (8.0 * size + 1.0);
+            let left = response.rect.left() + offset;
+            let lit = Color32::RED.gamma_multiply(module.brightness().max(0.2));
+
+            for row in 0..8 {
+                for column in 0..8 {
+                    let center = egui::pos2(
+                        left + (column as f32 + 0.5) * size,
+                        response.rect.top() + (row as f32 + 0.5) * size,
+                    );
+
+                    let color = match module.is_lit(row, column) {
+                        true => lit,
+                        false => Color32::from_gray(40),
+                    };
+
+                    painter.circle_filled(center, size * 0.4, color);
+                }
+            }
+        }
+    }
+
+    fn hub75_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        let Some(panel) = self.hub75.as_ref() else {
+            egui::Grid::new("hub75_size")
+                .num_columns(2)
+                .spacing([40.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label("Width");
+                    ui.add(DragValue::new(&mut self.panel_size[0]).range(8..=128));
+                    ui.end_row();
+
+                    ui.label("Height");
+                    ui.add(DragValue::new(&mut self.panel_size[1]).range(8..=64));
+                    ui.end_row();
+                });
+
+            let pins = Hub75Pins::default();
+            ui.label(format!(
+                "RGB on GPIO{}-{}, address from GPIO{}, CLK, LAT and OE on GPIO{}-{}",
+                pins.rgb[0], pins.rgb[5], pins.address[0], pins.clk, pins.oe,
+            ));
+
+            if ui.button("Connect").clicked() {
+                let [width, height] = self.panel_size;
+                let panel = Rc::new(RefCell::new(Hub75::new(pins, width, height)));
+                rp2350.attach_device(Box::new(Rc::clone(&panel)));
+                self.hub75 = Some(panel);
+            }
+
+            return;
+        };
+
+        let Ok(panel) = panel.try_borrow() else {
+            return;
+        };
+
+        let size = 6.0;
+        let (response, painter) = ui.allocate_painter(
+            Vec2::new(panel.width() as f32 * size, panel.height() as f32 * size),
+            Sense::hover(),
+        );
+
+        painter.rect_filled(response.rect, 0.0, Color32::BLACK);
+
+        for y in 0..panel.height() {
+            for x in 0..panel.width() {
+                let [r, g, b] = panel.pixel(x, y);
+                if r == 0 && g == 0 && b == 0 {
+                    continue;
+                }
+
+                let min = response.rect.min + Vec2::new(x as f32, y as f32) * size;
+                let rect = egui::Rect::from_min_size(min, Vec2::splat(size - 1.0));
+                painter.rect_filled(rect, 1.0, Color32::from_rgb(r, g, b));
+            }
+        }
+    }
+}
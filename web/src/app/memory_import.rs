@@ -0,0 +1,84 @@
+/**
+ * @file app/memory_import.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Dialog to load Intel HEX images and raw dumps into the memories
+ */
+use rp2350::simulator::Pico2;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Default, serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+enum ImportKind {
+    #[default]
+    IntelHex,
+    Raw,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MemoryImport {
+    kind: ImportKind,
+    address: String,
+}
+
+impl Default for MemoryImport {
+    fn default() -> Self {
+        Self {
+            kind: ImportKind::IntelHex,
+            address: String::from("20000000"),
+        }
+    }
+}
+
+impl MemoryImport {
+    pub fn ui(&mut self, ui: &mut egui::Ui, pico2: Rc<RefCell<Pico2>>) {
+        ui.heading("Import Memory");
+
+        egui::Grid::new("Memory Import")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Format");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.kind, ImportKind::IntelHex, "Intel HEX");
+                    ui.radio_value(&mut self.kind, ImportKind::Raw, "Raw binary");
+                });
+                ui.end_row();
+
+                if self.kind == ImportKind::Raw {
+                    ui.label("Load address (hex)");
+                    ui.text_edit_singleline(&mut self.address)
+                        .on_hover_text("Flash (0x10000000), SRAM (0x20000000) or Boot RAM (0x400e0000)");
+                    ui.end_row();
+                }
+
+                ui.label("");
+                if ui.button("Choose file...").clicked() {
+                    let target = match self.kind {
+                        ImportKind::IntelHex => Some(None),
+                        ImportKind::Raw => {
+                            let address = self.address.trim().trim_start_matches("0x");
+                            u32::from_str_radix(address, 16).ok().map(Some)
+                        }
+                    };
+
+                    match target {
+                        Some(address) => {
+                            crate::simulator::pick_file_into_memory(ui.ctx().clone(), pico2, address)
+                        }
+                        None => crate::notify::error("Invalid load address"),
+                    }
+                }
+                ui.end_row();
+            });
+
+        ui.add_space(8.0);
+        ui.label(
+            egui::RichText::new("Intel HEX records are loaded at the addresses they contain")
+                .small()
+                .weak(),
+        );
+    }
+}
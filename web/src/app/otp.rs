@@ -0,0 +1,216 @@
+/**
+ * @file app/otp.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Editor of the OTP rows, the chip ID and the white label
+ */
+use super::Rp2350Component;
+use rp2350::peripherals::glitch_detector::DETECTORS;
+use rp2350::peripherals::otp::data::{WhiteLabel, ROWS};
+use rp2350::peripherals::otp::{
+    CRITICAL_ARM_DISABLE, CRITICAL_DEBUG_DISABLE, CRITICAL_GLITCH_DETECTOR_ENABLE,
+    CRITICAL_RISCV_DISABLE, CRITICAL_SECURE_BOOT_ENABLE, CRITICAL_SECURE_DEBUG_DISABLE,
+};
+use rp2350::Rp2350;
+
+const ROWS_PER_PAGE: usize = 64;
+
+const CRITICAL_FLAGS: [(&str, u32); 6] = [
+    ("Secure boot", CRITICAL_SECURE_BOOT_ENABLE),
+    ("Glitch detector", CRITICAL_GLITCH_DETECTOR_ENABLE),
+    ("No secure debug", CRITICAL_SECURE_DEBUG_DISABLE),
+    ("No debug", CRITICAL_DEBUG_DISABLE),
+    ("No Arm", CRITICAL_ARM_DISABLE),
+    ("No RISC-V", CRITICAL_RISCV_DISABLE),
+];
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Otp {
+    page: usize,
+    chip_id: String,
+    row: usize,
+    value: String,
+    ecc: bool,
+    vendor_id: String,
+    product_id: String,
+    product: String,
+}
+
+impl Default for Otp {
+    fn default() -> Self {
+        Self {
+            page: 0,
+            chip_id: String::new(),
+            row: 0,
+            value: String::from("0"),
+            ecc: true,
+            vendor_id: String::new(),
+            product_id: String::new(),
+            product: String::new(),
+        }
+    }
+}
+
+fn parse_hex(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+}
+
+impl Otp {
+    fn program_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        let otp = &mut rp2350.bus.peripherals.otp_data;
+
+        egui::Grid::new("OTP Program")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("Chip ID (hex)");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.chip_id)
+                        .on_hover_text(format!("Currently {:016x}", otp.chip_id()));
+
+                    if ui.button("Apply").clicked() {
+                        match parse_hex(&self.chip_id) {
+                            Some(id) => otp.set_chip_id(id),
+                            None => crate::notify::error("Invalid chip ID"),
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Row");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(&mut self.row)
+                            .range(0..=ROWS - 1)
+                            .hexadecimal(3, false, true),
+                    );
+                    ui.text_edit_singleline(&mut self.value);
+                    ui.checkbox(&mut self.ecc, "ECC");
+
+                    if ui.button("Program").clicked() {
+                        match (parse_hex(&self.value), self.ecc) {
+                            (Some(value), true) if value <= 0xffff => {
+                                otp.program_ecc(self.row, value as u16)
+                            }
+                            (Some(value), false) if value <= 0xff_ffff => {
+                                otp.program_raw(self.row, value as u32)
+                            }
+                            _ => crate::notify::error("Invalid row value"),
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Critical flags");
+                ui.horizontal_wrapped(|ui| {
+                    let mut critical = otp.critical();
+
+                    for (name, flag) in CRITICAL_FLAGS {
+                        let mut set = critical & flag != 0;
+                        if ui.checkbox(&mut set, name).changed() {
+                            critical ^= flag;
+                        }
+                    }
+
+                    if critical != otp.critical() {
+                        otp.set_critical(critical);
+                    }
+                })
+                .response
+                .on_hover_text("Latched by the chip on the next reset");
+                ui.end_row();
+
+                ui.label("USB white label");
+                ui.horizontal(|ui| {
+                    ui.label("VID");
+                    ui.add(egui::TextEdit::singleline(&mut self.vendor_id).desired_width(50.0));
+                    ui.label("PID");
+                    ui.add(egui::TextEdit::singleline(&mut self.product_id).desired_width(50.0));
+                    ui.label("Product");
+                    ui.text_edit_singleline(&mut self.product);
+
+                    if ui.button("Program").clicked() {
+                        otp.set_white_label(&WhiteLabel {
+                            vendor_id: parse_hex(&self.vendor_id).map(|id| id as u16),
+                            product_id: parse_hex(&self.product_id).map(|id| id as u16),
+                            product: Some(self.product.clone()).filter(|name| !name.is_empty()),
+                            ..Default::default()
+                        });
+                    }
+                });
+                ui.end_row();
+            });
+    }
+}
+
+impl Rp2350Component for Otp {
+    const NAME: &'static str = "OTP";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading("OTP");
+        ui.label(
+            egui::RichText::new(
+                "The rows are kept through the resets, the bootrom reads them on boot",
+            )
+            .small()
+            .weak(),
+        );
+
+        ui.add_space(8.0);
+        self.program_ui(ui, rp2350);
+        ui.add_space(8.0);
+
+        let detector = &mut rp2350.bus.peripherals.glitch_detector;
+        let state = match detector.is_armed() {
+            true => "armed",
+            false => "disarmed",
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Glitch detectors {state}, last triggered {:04b}",
+                detector.trig_status
+            ));
+
+            if ui.button("Glitch").clicked() {
+                detector.trigger(DETECTORS);
+            }
+        });
+        ui.add_space(12.0);
+
+        ui.horizontal(|ui| {
+            ui.label("Page");
+            ui.add(egui::DragValue::new(&mut self.page).range(0..=ROWS / ROWS_PER_PAGE - 1));
+        });
+
+        let otp = &rp2350.bus.peripherals.otp_data;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("OTP Rows")
+                .num_columns(3)
+                .spacing([40.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Row");
+                    ui.strong("Value");
+                    ui.strong("");
+                    ui.end_row();
+
+                    let start = self.page * ROWS_PER_PAGE;
+                    for row in start..start + ROWS_PER_PAGE {
+                        let value = otp.row(row).unwrap_or(0);
+
+                        ui.monospace(format!("{row:#05x}"));
+                        ui.monospace(format!("{value:06x}"));
+                        ui.label(match (otp.is_ecc(row), value) {
+                            (true, _) => "ECC",
+                            (false, 0) => "",
+                            (false, _) => "raw",
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
@@ -0,0 +1,83 @@
+/**
+ * @file app/plugins.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Loader of the peripheral plugins and the list of the mapped ones
+ */
+use egui::RichText;
+use rp2350::peripherals::plugin::ABI_VERSION;
+use rp2350::simulator::Pico2;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Plugins;
+
+impl Plugins {
+    pub fn ui(&mut self, ui: &mut egui::Ui, pico2: Rc<RefCell<Pico2>>) {
+        ui.heading("Plugins");
+
+        ui.horizontal(|ui| {
+            if ui.button("Load plugin...").clicked() {
+                crate::simulator::pick_plugin_file(ui.ctx().clone(), Rc::clone(&pico2));
+            }
+
+            ui.weak(format!(
+                "WebAssembly modules of the interface v{}",
+                ABI_VERSION
+            ));
+        });
+
+        ui.separator();
+
+        let Ok(pico2) = pico2.try_borrow() else {
+            return;
+        };
+
+        let plugins = &pico2.mcu.bus.peripherals.plugins;
+
+        if plugins.is_empty() {
+            ui.label("No plugin loaded, they stay mapped until the page is reloaded");
+            return;
+        }
+
+        egui::Grid::new("Plugins")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Name").strong());
+                ui.label(RichText::new("Registers").strong());
+                ui.label(RichText::new("IRQ").strong());
+                ui.label(RichText::new("Status").strong());
+                ui.end_row();
+
+                for plugin in plugins {
+                    let Ok(plugin) = plugin.try_borrow() else {
+                        continue;
+                    };
+
+                    let info = &plugin.info;
+
+                    ui.label(&info.name);
+                    ui.monospace(format!(
+                        "{:#010x}..{:#010x}",
+                        info.base,
+                        info.base + info.size
+                    ));
+
+                    match info.irq {
+                        Some(irq) => ui.label(irq.to_string()),
+                        None => ui.weak("-"),
+                    };
+
+                    match &plugin.error {
+                        Some(why) => ui.colored_label(ui.visuals().error_fg_color, why.to_string()),
+                        None => ui.label("Running"),
+                    };
+
+                    ui.end_row();
+                }
+            });
+    }
+}
@@ -0,0 +1,184 @@
+/**
+ * @file app/presets.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Named lists of register writes, applied at any time to try the peripherals out
+ */
+use super::Rp2350Component;
+use api_types::{InvalidRegisterWrite, RegisterPreset, RegisterWrite};
+use egui::RichText;
+use rp2350::Rp2350;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RegisterPresets {
+    presets: Vec<RegisterPreset>,
+    /// Preset being edited, the writes as one `ADDRESS=VALUE` per line
+    name: String,
+    writes: String,
+    #[serde(skip)]
+    status: Option<Result<String, String>>,
+}
+
+impl RegisterPresets {
+    pub fn presets(&self) -> &[RegisterPreset] {
+        &self.presets
+    }
+
+    /// The imported presets replace the ones of the same name
+    pub fn import(&mut self, presets: Vec<RegisterPreset>) {
+        for preset in presets {
+            match self.presets.iter_mut().find(|p| p.name == preset.name) {
+                Some(existing) => *existing = preset,
+                None => self.presets.push(preset),
+            }
+        }
+    }
+
+    fn parse_writes(&self) -> Result<Vec<RegisterWrite>, InvalidRegisterWrite> {
+        self.writes
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    fn save(&mut self) {
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            self.status = Some(Err("The preset needs a name".to_string()));
+            return;
+        }
+
+        let writes = match self.parse_writes() {
+            Ok(writes) => writes,
+            Err(why) => {
+                self.status = Some(Err(why.to_string()));
+                return;
+            }
+        };
+
+        self.status = Some(Ok(format!("Saved {name}")));
+        self.import(vec![RegisterPreset { name, writes }]);
+    }
+
+    fn edit(&mut self, preset: &RegisterPreset) {
+        self.name = preset.name.clone();
+        self.writes = preset
+            .writes
+            .iter()
+            .map(|write| format!("{write}\n"))
+            .collect();
+    }
+
+    fn apply(&mut self, rp2350: &mut Rp2350, preset: &RegisterPreset) {
+        let writes: Vec<_> = preset
+            .writes
+            .iter()
+            .map(|write| (write.address, write.value))
+            .collect();
+
+        self.status = Some(match rp2350.apply_register_writes(&writes) {
+            Ok(()) => Ok(format!("Applied {}", preset.name)),
+            Err(why) => Err(format!("{}: {why}", preset.name)),
+        });
+    }
+}
+
+impl Rp2350Component for RegisterPresets {
+    const NAME: &'static str = "Register Presets";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading("Register Presets");
+
+        let mut apply = None;
+        let mut edit = None;
+        let mut remove = None;
+
+        egui::Grid::new("Register presets")
+            .num_columns(3)
+            .spacing([20.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, preset) in self.presets.iter().enumerate() {
+                    ui.label(&preset.name);
+                    ui.label(format!("{} writes", preset.writes.len()));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            apply = Some(i);
+                        }
+
+                        if ui.button("Edit").clicked() {
+                            edit = Some(i);
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        if let Some(preset) = apply.and_then(|i| self.presets.get(i).cloned()) {
+            self.apply(rp2350, &preset);
+        }
+
+        if let Some(preset) = edit.and_then(|i| self.presets.get(i).cloned()) {
+            self.edit(&preset);
+        }
+
+        if let Some(i) = remove {
+            self.presets.remove(i);
+        }
+
+        ui.add_space(12.0);
+
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut self.name);
+        });
+
+        ui.add(
+            egui::TextEdit::multiline(&mut self.writes)
+                .code_editor()
+                .desired_rows(6)
+                .hint_text("0x400a8010=999  # TOP of the PWM slice 0"),
+        );
+
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                self.save();
+            }
+
+            if ui.button("Apply once").clicked() {
+                match self.parse_writes() {
+                    Ok(writes) => {
+                        let name = "the writes".to_string();
+                        self.apply(rp2350, &RegisterPreset { name, writes });
+                    }
+                    Err(why) => self.status = Some(Err(why.to_string())),
+                }
+            }
+        });
+
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(why)) => {
+                ui.label(RichText::new(why).color(egui::Color32::RED));
+            }
+            None => {}
+        }
+
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new("One ADDRESS=VALUE per line, hex with 0x or decimal. The presets are exported with the test spec")
+                .small()
+                .weak(),
+        );
+    }
+}
@@ -4,19 +4,29 @@
  * @date 04/05/2025
  * @brief View window for the processor core
  */
+use super::disassembler::Disassembler;
 use super::Rp2350Component;
 use crate::tracker::ProcessorTracker;
-use crate::widgets::DisplayMode;
+use crate::widgets::{pipeline_diagram, DisplayMode};
 use egui::collapsing_header::CollapsingState;
 use egui::Margin;
 use egui::RichText;
 use egui_extras::Column;
 use egui_extras::TableBuilder;
-use rp2350::processor::cortex_m33::CortexM33;
+use rp2350::processor::cortex_m33::{CortexM33, Registers as CortexM33Registers};
+use rp2350::processor::cortex_m33::{State as CortexM33State, LR, SP};
+use rp2350::processor::hazard3::branch_predictor::{
+    BranchPredictor, PredictorModel, MAX_GSHARE_BITS,
+};
+use rp2350::processor::hazard3::busy_wait::DelayKind;
+use rp2350::processor::hazard3::call_stack::{cause_name, Frame, FrameKind};
+use rp2350::processor::hazard3::pipeline::{StageInstruction, PIPELINE_HISTORY};
 use rp2350::processor::hazard3::Registers as Hazard3Registers;
 use rp2350::processor::hazard3::{Hazard3, State as Hazard3State};
 use rp2350::processor::Rp2350Core;
 use rp2350::Rp2350;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 struct RegisterOption {
@@ -37,17 +47,61 @@ impl<const T: usize> Rp2350Component for ProcessorCore<T> {
         &mut self,
         ui: &mut egui::Ui,
         rp2350: &mut Rp2350,
-        tracker: std::rc::Rc<crate::Tracker>,
+        tracker: Rc<crate::Tracker>,
+    ) {
+        self.ui_with_symbols(ui, rp2350, tracker, None);
+    }
+}
+
+impl<const T: usize> ProcessorCore<T> {
+    /// The window, with the backtrace named by the symbols of the build when there are some
+    pub fn ui_with_symbols(
+        &mut self,
+        ui: &mut egui::Ui,
+        rp2350: &mut Rp2350,
+        tracker: Rc<crate::Tracker>,
+        disassembler: Option<&RefCell<Disassembler>>,
     ) {
         ui.heading(format!("Processor Core {}", T));
 
+        if ui
+            .button("Step instruction")
+            .on_hover_text("Run until this core executes its next instruction")
+            .clicked()
+        {
+            rp2350.processor[T].resume();
+
+            if !rp2350.step_instruction(T) {
+                crate::notify::warning(format!("Core {T} did not execute any instruction"));
+            }
+        }
+
+        // the tracker is borrowed again by the event of a skip
+        show_busy_wait::<T>(ui, rp2350, &tracker);
+
         let track = tracker.borrow();
         let ref processor_tracker = track.processor[T];
 
         // Show processor details
         match rp2350.processor[T] {
-            Rp2350Core::Arm(ref processor) => self.ui_arm(ui, processor),
-            Rp2350Core::RiscV(ref processor) => self.ui_riscv(ui, processor, processor_tracker),
+            Rp2350Core::Arm(ref processor) => self.ui_arm(ui, processor, processor_tracker),
+            Rp2350Core::RiscV(ref mut processor) => {
+                self.ui_riscv(ui, processor, processor_tracker);
+
+                let disassembler = disassembler.and_then(|d| d.try_borrow().ok());
+                ui.add_space(12.0);
+                show_backtrace::<T>(ui, processor, disassembler.as_deref());
+
+                ui.add_space(12.0);
+                show_pipeline::<T>(ui, processor);
+
+                ui.add_space(12.0);
+                show_branch_predictor::<T>(
+                    ui,
+                    &mut processor.branch_predictor,
+                    disassembler.as_deref(),
+                );
+            }
         }
 
         // Show processor tracker
@@ -65,9 +119,108 @@ const fn name<const T: usize>() -> &'static str {
 }
 
 impl<const T: usize> ProcessorCore<T> {
-    fn ui_arm(&mut self, ui: &mut egui::Ui, _cortex_m33: &CortexM33) {
-        ui.heading("ARM Cortex-M33");
-        // TODO
+    fn ui_arm(&mut self, ui: &mut egui::Ui, cortex_m33: &CortexM33, tracker: &ProcessorTracker) {
+        egui::Grid::new("ProcessorInfo")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Type");
+
+                egui::Frame::new()
+                    .corner_radius(10)
+                    .inner_margin(Margin::symmetric(6, 4))
+                    .fill(egui::Color32::from_rgb(0x00, 0x5f, 0x9f))
+                    .show(ui, |ui| {
+                        ui.monospace(RichText::new("ARM").strong().color(egui::Color32::WHITE));
+                    });
+
+                ui.end_row();
+
+                ui.label("State");
+                ui.label(match cortex_m33.state {
+                    CortexM33State::Reset => "Reset".to_owned(),
+                    CortexM33State::Wfi => "WFI".to_owned(),
+                    CortexM33State::Wfe => "WFE".to_owned(),
+                    CortexM33State::Stall(cycles) => format!("Stall for ({cycles} cycles)"),
+                    CortexM33State::Normal => "Running".to_owned(),
+                    CortexM33State::Sleep(_) => "Sleep".to_owned(),
+                    CortexM33State::Lockup => "Lockup".to_owned(),
+                    CortexM33State::BusWaitStore(..) => "Bus Wait Store".to_owned(),
+                    CortexM33State::BusWaitLoad(..) => "Bus Wait Load".to_owned(),
+                });
+                ui.end_row();
+
+                ui.label("Executed");
+                ui.label(format!("{}", tracker.inst_count));
+                ui.end_row();
+
+                ui.label("IPC");
+                ui.label(format!(
+                    "{}",
+                    (tracker.inst_count as f64) / (tracker.ticks as f64)
+                ));
+                ui.end_row();
+
+                ui.label("PC");
+                ui.label(format!("0x{:08x}", cortex_m33.pc));
+                ui.end_row();
+
+                ui.label("Mode");
+                ui.label(match cortex_m33.registers.ipsr {
+                    0 => "Thread".to_owned(),
+                    exception => format!("Handler (exception {exception})"),
+                });
+                ui.end_row();
+            });
+
+        ui.add_space(12.0);
+
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            ui.make_persistent_id(register_name::<T>()),
+            true,
+        )
+        .show_header(ui, |ui| {
+            ui.heading("Registers");
+        })
+        .body(|ui| {
+            self.cortex_m33_registers_ui(ui, &cortex_m33.registers);
+        });
+    }
+
+    fn cortex_m33_registers_ui(&mut self, ui: &mut egui::Ui, registers: &CortexM33Registers) {
+        let special = [
+            ("xpsr", registers.xpsr()),
+            ("ctrl", registers.control),
+            ("pmsk", registers.primask as u32),
+            ("bpri", registers.basepri as u32),
+            ("fmsk", registers.faultmask as u32),
+        ];
+
+        if ui.button("Copy").clicked() {
+            let text = (0..=LR)
+                .map(|index| (arm_register_name(index), registers.read(index)))
+                .chain(special.map(|(name, value)| (name.to_string(), value)))
+                .map(|(name, value)| format!("{} = 0x{:08x}", name, value))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            crate::widgets::clipboard::copy(ui.ctx(), text);
+        }
+
+        let (core_registers, special_registers) = self.registers.split_at_mut(LR as usize + 1);
+
+        for (reg_opt, index) in core_registers.iter_mut().zip(0..) {
+            let value = registers.read(index);
+            let name = arm_register_name(index);
+            ui.add(register_ui(name, value, &mut reg_opt.display_mode));
+        }
+
+        for (reg_opt, (name, value)) in special_registers.iter_mut().zip(special) {
+            let name = name.to_string();
+            ui.add(register_ui(name, value, &mut reg_opt.display_mode));
+        }
     }
 
     fn ui_riscv(&mut self, ui: &mut egui::Ui, hazard3: &Hazard3, tracker: &ProcessorTracker) {
@@ -92,6 +245,7 @@ impl<const T: usize> ProcessorCore<T> {
                 ui.label(match hazard3.state {
                     Hazard3State::Wfi => "WFI".to_owned(),
                     Hazard3State::Stall(cycles, _) => format!("Stall for ({cycles} cycles)"),
+                    Hazard3State::Busy(cycles) => format!("Busy for ({cycles} cycles)"),
                     Hazard3State::Normal => "Running".to_owned(),
                     Hazard3State::Sleep(_) => "Sleep".to_owned(),
                     Hazard3State::BusWaitStore(_) => "Bus Wait Store".to_owned(),
@@ -135,11 +289,25 @@ impl<const T: usize> ProcessorCore<T> {
     }
 
     fn hazard3_registers_ui(&mut self, ui: &mut egui::Ui, registers: &Hazard3Registers) {
-        // option to show with naming convention
-        ui.checkbox(
-            &mut self.show_with_naming_convention,
-            "Show with naming convention",
-        );
+        ui.horizontal(|ui| {
+            // option to show with naming convention
+            ui.checkbox(
+                &mut self.show_with_naming_convention,
+                "Show with naming convention",
+            );
+
+            if ui.button("Copy").clicked() {
+                let text = (0..32)
+                    .map(|index| {
+                        let name = riscv_register_name(index, self.show_with_naming_convention);
+                        format!("{} = 0x{:08x}", name, registers.read(index))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                crate::widgets::clipboard::copy(ui.ctx(), text);
+            }
+        });
 
         for (reg_opt, index) in self.registers.iter_mut().zip(0..) {
             let name = riscv_register_name(index, self.show_with_naming_convention);
@@ -179,6 +347,14 @@ fn register_ui(
     }
 }
 
+fn arm_register_name(register: u8) -> String {
+    match register {
+        SP => "sp".to_string(),
+        LR => "lr".to_string(),
+        _ => format!("r{register}"),
+    }
+}
+
 fn riscv_register_name(register: u8, with_convention: bool) -> String {
     if !with_convention {
         return format!("x{register}");
@@ -230,6 +406,33 @@ const fn tracker_name<const T: usize>() -> &'static str {
     }
 }
 
+// Helper to get the ID for the collapsing header
+const fn backtrace_name<const T: usize>() -> &'static str {
+    if T == 0 {
+        "ProcessorCore0Backtrace"
+    } else {
+        "ProcessorCore1Backtrace"
+    }
+}
+
+// Helper to get the ID for the collapsing header
+const fn pipeline_name<const T: usize>() -> &'static str {
+    if T == 0 {
+        "ProcessorCore0Pipeline"
+    } else {
+        "ProcessorCore1Pipeline"
+    }
+}
+
+// Helper to get the ID for the collapsing header
+const fn branch_predictor_name<const T: usize>() -> &'static str {
+    if T == 0 {
+        "ProcessorCore0BranchPredictor"
+    } else {
+        "ProcessorCore1BranchPredictor"
+    }
+}
+
 // Helper to get the ID for the collapsing header
 const fn log_name<const T: usize>() -> &'static str {
     if T == 0 {
@@ -239,6 +442,84 @@ const fn log_name<const T: usize>() -> &'static str {
     }
 }
 
+fn show_busy_wait<const T: usize>(
+    ui: &mut egui::Ui,
+    rp2350: &mut Rp2350,
+    tracker: &crate::Tracker,
+) {
+    ui.horizontal(|ui| {
+        let mut skip = rp2350.skips_busy_waits();
+
+        if ui
+            .checkbox(&mut skip, "Skip delay loops")
+            .on_hover_text(
+                "Fast-forward the busy-wait loops of both cores as soon as they are detected",
+            )
+            .changed()
+        {
+            rp2350.set_skip_busy_waits(skip);
+        }
+
+        let (waits, cycles) = {
+            let track = tracker.borrow();
+            let processor = &track.processor[T];
+            (processor.skipped_waits, processor.skipped_cycles)
+        };
+
+        if waits > 0 {
+            ui.weak(format!("{} skipped, {} cycles saved", waits, cycles));
+        }
+    });
+
+    ui.horizontal(|ui| {
+        let mut skip = rp2350.skips_idle();
+
+        if ui
+            .checkbox(&mut skip, "Skip idle time")
+            .on_hover_text(
+                "Jump to the next event of the clock while both cores sleep and nothing else runs",
+            )
+            .changed()
+        {
+            rp2350.set_skip_idle(skip);
+        }
+
+        let cycles = tracker.borrow().processor[T].idle_cycles;
+
+        if cycles > 0 {
+            ui.weak(format!("{} cycles slept through", cycles));
+        }
+    });
+
+    let Some(delay) = rp2350.busy_wait(T) else {
+        return;
+    };
+
+    ui.horizontal(|ui| {
+        let kind = match delay.kind {
+            DelayKind::Counter => String::from("counting"),
+            DelayKind::Timer(timer) => format!("polling TIMER{}", timer),
+        };
+
+        ui.label(format!(
+            "In a delay loop at {:#010x} ({})",
+            delay.start, kind
+        ));
+
+        if ui.button("Skip").clicked() {
+            match rp2350.skip_busy_wait(T) {
+                Some(skipped) => crate::notify::info(format!(
+                    "Skipped {} iterations of the loop, {} cycles",
+                    skipped.iterations, skipped.cycles
+                )),
+                None => crate::notify::warning(
+                    "The loop cannot be skipped while the rest of the chip is busy",
+                ),
+            }
+        }
+    });
+}
+
 fn show_processor_tracker<const T: usize>(ui: &mut egui::Ui, tracker: &ProcessorTracker) {
     CollapsingState::load_with_default_open(
         ui.ctx(),
@@ -326,3 +607,272 @@ fn show_processor_tracker<const T: usize>(ui: &mut egui::Ui, tracker: &Processor
             });
     });
 }
+
+/// The innermost frame first, each line is where the core is in that function
+fn frames_ui(ui: &mut egui::Ui, pc: u32, frames: &[Frame], disassembler: Option<&Disassembler>) {
+    let name = |address: u32| {
+        disassembler
+            .and_then(|disassembler| disassembler.symbol_name(address))
+            .unwrap_or_default()
+    };
+
+    egui::Grid::new("Backtrace")
+        .num_columns(3)
+        .spacing([12.0, 4.0])
+        .striped(true)
+        .show(ui, |ui| {
+            ui.monospace("#0");
+            ui.monospace(format!("{pc:#010x}"));
+            ui.monospace(name(pc));
+            ui.end_row();
+
+            for (depth, frame) in frames.iter().rev().enumerate() {
+                if let FrameKind::Trap(cause) = frame.kind {
+                    ui.label("");
+                    ui.label(RichText::new(format!("--- {} ---", cause_name(cause))).weak());
+                    ui.label("");
+                    ui.end_row();
+                }
+
+                ui.monospace(format!("#{}", depth + 1));
+                ui.monospace(format!("{:#010x}", frame.site));
+                ui.monospace(name(frame.site));
+                ui.end_row();
+            }
+        });
+}
+
+fn show_backtrace<const T: usize>(
+    ui: &mut egui::Ui,
+    hazard3: &Hazard3,
+    disassembler: Option<&Disassembler>,
+) {
+    CollapsingState::load_with_default_open(
+        ui.ctx(),
+        ui.make_persistent_id(backtrace_name::<T>()),
+        true,
+    )
+    .show_header(ui, |ui| {
+        ui.heading("Backtrace");
+    })
+    .body(|ui| {
+        let call_stack = &hazard3.call_stack;
+
+        if call_stack.is_truncated() {
+            ui.weak("The outermost calls were dropped, the stack went too deep");
+        }
+
+        frames_ui(ui, hazard3.pc, call_stack.frames(), disassembler);
+
+        if let Some(fault) = call_stack.fault() {
+            ui.add_space(8.0);
+            ui.label(
+                RichText::new(format!("Last exception: {}", cause_name(fault.cause))).strong(),
+            );
+            ui.push_id("Fault", |ui| {
+                frames_ui(ui, fault.pc, &fault.frames, disassembler);
+            });
+        }
+    });
+}
+
+fn show_pipeline<const T: usize>(ui: &mut egui::Ui, hazard3: &mut Hazard3) {
+    CollapsingState::load_with_default_open(
+        ui.ctx(),
+        ui.make_persistent_id(pipeline_name::<T>()),
+        false,
+    )
+    .show_header(ui, |ui| {
+        ui.heading("Pipeline");
+    })
+    .body(|ui| {
+        let pipeline = &mut hazard3.pipeline;
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut pipeline.recording, "Record")
+                .on_hover_text(format!(
+                    "Keep the stages of the last {PIPELINE_HISTORY} cycles"
+                ));
+
+            if ui.button("Clear").clicked() {
+                pipeline.clear();
+            }
+        });
+
+        let stage = |instruction: Option<StageInstruction>| match instruction {
+            Some(instruction) => format!("{:#010x} {}", instruction.address, instruction.name),
+            None => String::from("-"),
+        };
+
+        let state = *pipeline.state();
+        egui::Grid::new("PipelineStages")
+            .num_columns(2)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Fetch");
+                ui.monospace(match state.fetch {
+                    Some(address) => format!("{address:#010x}"),
+                    None => String::from("-"),
+                });
+                ui.end_row();
+
+                ui.label("Execute");
+                ui.monospace(stage(state.execute));
+                ui.end_row();
+
+                ui.label("Memory");
+                ui.monospace(stage(state.memory));
+                ui.end_row();
+
+                ui.label("Stall");
+                ui.monospace(state.stall.map_or("-", |reason| reason.name()));
+                ui.end_row();
+
+                ui.label("Bypass");
+                ui.monospace(match state.bypass {
+                    Some((rd, value)) => {
+                        format!("{} = {value:#010x}", riscv_register_name(rd, true))
+                    }
+                    None => String::from("-"),
+                });
+                ui.end_row();
+            });
+
+        let cycles = pipeline.history().copied().collect::<Vec<_>>();
+        if cycles.is_empty() {
+            return;
+        }
+
+        ui.add_space(8.0);
+        egui::ScrollArea::horizontal()
+            .id_salt("pipeline_diagram")
+            .show(ui, |ui| {
+                pipeline_diagram(ui, &cycles);
+            });
+    });
+}
+
+/// History of the gshare model when it is picked
+const GSHARE_BITS: u8 = 10;
+
+fn show_branch_predictor<const T: usize>(
+    ui: &mut egui::Ui,
+    predictor: &mut BranchPredictor,
+    disassembler: Option<&Disassembler>,
+) {
+    CollapsingState::load_with_default_open(
+        ui.ctx(),
+        ui.make_persistent_id(branch_predictor_name::<T>()),
+        false,
+    )
+    .show_header(ui, |ui| {
+        ui.heading("Branch predictor");
+    })
+    .body(|ui| {
+        ui.label(
+            RichText::new("The Hazard3 predicts the last branch taken, others change the timing")
+                .small()
+                .weak(),
+        );
+
+        ui.horizontal(|ui| {
+            let mut model = predictor.model();
+
+            egui::ComboBox::from_id_salt("branch_predictor_model")
+                .selected_text(model.name())
+                .show_ui(ui, |ui| {
+                    let models = [
+                        PredictorModel::LastTaken,
+                        PredictorModel::AlwaysTaken,
+                        PredictorModel::Gshare { bits: GSHARE_BITS },
+                    ];
+
+                    for option in models {
+                        if ui
+                            .selectable_label(option.name() == model.name(), option.name())
+                            .clicked()
+                        {
+                            model = option;
+                        }
+                    }
+                });
+
+            if let PredictorModel::Gshare { bits } = &mut model {
+                ui.add(
+                    egui::DragValue::new(bits)
+                        .range(1..=MAX_GSHARE_BITS)
+                        .suffix(" bits"),
+                );
+            }
+
+            // switching forgets what the predictor learned
+            if model != predictor.model() {
+                predictor.set_model(model);
+            }
+
+            if ui.button("Clear").clicked() {
+                predictor.clear_stats();
+            }
+        });
+
+        let total = predictor.hits() + predictor.misses();
+        let rate = match total {
+            0 => 0.0,
+            total => predictor.misses() as f64 * 100.0 / total as f64,
+        };
+
+        ui.label(format!(
+            "{} branches, {} mispredicted ({rate:.1}%)",
+            total,
+            predictor.misses()
+        ));
+
+        let mispredictions = predictor.mispredictions();
+        if mispredictions.is_empty() {
+            return;
+        }
+
+        let name = |address: u32| {
+            disassembler
+                .and_then(|disassembler| disassembler.symbol_name(address))
+                .unwrap_or_default()
+        };
+
+        ui.add_space(8.0);
+        TableBuilder::new(ui)
+            .id_salt("mispredictions")
+            .striped(true)
+            .resizable(true)
+            .column(Column::exact(100.0))
+            .column(Column::remainder().at_least(120.0))
+            .column(Column::exact(80.0))
+            .column(Column::exact(90.0))
+            .min_scrolled_height(200.0)
+            .max_scroll_height(200.0)
+            .header(20.0, |mut header| {
+                for title in ["Address", "Function", "Executed", "Mispredicted"] {
+                    header.col(|ui| {
+                        ui.label(RichText::new(title).strong());
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(20.0, mispredictions.len(), |mut row| {
+                    let (address, stats) = mispredictions[row.index()];
+
+                    row.col(|ui| {
+                        ui.monospace(format!("{address:#010x}"));
+                    });
+                    row.col(|ui| {
+                        ui.monospace(name(address));
+                    });
+                    row.col(|ui| {
+                        ui.label(stats.executed.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(stats.mispredicted.to_string());
+                    });
+                });
+            });
+    });
+}
@@ -0,0 +1,242 @@
+/**
+ * @file app/profiler.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Cycles spent in each function of the program, as a table and a flame graph
+ */
+use super::disassembler::Disassembler;
+use crate::Tracker;
+use egui::{Color32, RichText, Sense, Stroke, StrokeKind};
+use egui_extras::{Column, TableBuilder};
+use rp2350::profiler::{FlameNode, Profiler};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Height of a frame of the flame graph
+const FRAME_HEIGHT: f32 = 18.0;
+
+/// Narrower frames are not drawn
+const MIN_FRAME_WIDTH: f32 = 1.0;
+
+/// Warm colour from the name, so a function keeps its colour between frames
+fn frame_color(name: &str) -> Color32 {
+    let hash = name.bytes().fold(0x811c_9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+
+    Color32::from_rgb(
+        200 + (hash % 55) as u8,
+        80 + ((hash >> 8) % 120) as u8,
+        ((hash >> 16) % 60) as u8,
+    )
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ProfilerView {
+    /// Number of rows of the hot function table
+    rows: usize,
+}
+
+impl Default for ProfilerView {
+    fn default() -> Self {
+        Self { rows: 20 }
+    }
+}
+
+impl ProfilerView {
+    fn table_ui(&self, ui: &mut egui::Ui, profiler: &Profiler) {
+        let total = profiler.total_cycles().max(1) as f64;
+        let functions = profiler.hot_functions();
+        let rows = functions.len().min(self.rows);
+
+        TableBuilder::new(ui)
+            .id_salt("hot_functions")
+            .striped(true)
+            .resizable(true)
+            .column(Column::remainder().at_least(160.0))
+            .column(Column::exact(100.0))
+            .column(Column::exact(70.0))
+            .column(Column::exact(70.0))
+            .header(20.0, |mut header| {
+                for title in ["Function", "Self cycles", "Self", "Total"] {
+                    header.col(|ui| {
+                        ui.label(RichText::new(title).strong());
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, rows, |mut row| {
+                    let function = &functions[row.index()];
+
+                    row.col(|ui| {
+                        ui.monospace(&function.name);
+                    });
+                    row.col(|ui| {
+                        ui.monospace(function.self_cycles.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format!(
+                            "{:.1}%",
+                            function.self_cycles as f64 * 100.0 / total
+                        ));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!(
+                            "{:.1}%",
+                            function.total_cycles as f64 * 100.0 / total
+                        ));
+                    });
+                });
+            });
+    }
+
+    fn flame_graph_ui(&self, ui: &mut egui::Ui, root: &FlameNode) {
+        fn depth(node: &FlameNode) -> usize {
+            1 + node.children.iter().map(depth).max().unwrap_or(0)
+        }
+
+        let width = ui.available_width();
+        let height = depth(root) as f32 * FRAME_HEIGHT;
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(width, height), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let hover = response.hover_pos();
+        let mut hovered = None;
+
+        // the root at the bottom, the callees on top of their caller
+        let mut frames = vec![(root, rect.left(), 0usize)];
+        while let Some((node, left, level)) = frames.pop() {
+            let frame_width = node.cycles as f32 / root.cycles.max(1) as f32 * width;
+            if frame_width < MIN_FRAME_WIDTH {
+                continue;
+            }
+
+            let bottom = rect.bottom() - level as f32 * FRAME_HEIGHT;
+            let frame = egui::Rect::from_min_max(
+                egui::pos2(left, bottom - FRAME_HEIGHT),
+                egui::pos2(left + frame_width, bottom),
+            );
+
+            painter.rect_filled(frame, 0.0, frame_color(&node.name));
+            painter.rect_stroke(
+                frame,
+                0.0,
+                Stroke::new(1.0, ui.visuals().extreme_bg_color),
+                StrokeKind::Inside,
+            );
+
+            let label = egui::FontId::monospace(11.0);
+            let chars = (frame_width / 7.0) as usize;
+            if chars >= 3 {
+                let name = match node.name.chars().count() > chars {
+                    true => format!(
+                        "{}..",
+                        node.name.chars().take(chars - 2).collect::<String>()
+                    ),
+                    false => node.name.clone(),
+                };
+
+                painter.text(
+                    frame.left_center() + egui::vec2(3.0, 0.0),
+                    egui::Align2::LEFT_CENTER,
+                    name,
+                    label,
+                    Color32::BLACK,
+                );
+            }
+
+            if hover.is_some_and(|pos| frame.contains(pos)) {
+                hovered = Some(node);
+            }
+
+            let mut child_left = left;
+            for child in node.children.iter() {
+                frames.push((child, child_left, level + 1));
+                child_left += child.cycles as f32 / root.cycles.max(1) as f32 * width;
+            }
+        }
+
+        if let Some(node) = hovered {
+            let percent = node.cycles as f64 * 100.0 / root.cycles.max(1) as f64;
+            response.on_hover_text(format!(
+                "{}\n{} cycles ({percent:.1}%)",
+                node.name, node.cycles
+            ));
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        tracker: Rc<Tracker>,
+        disassembler: &RefCell<Disassembler>,
+    ) {
+        ui.heading("Profiler");
+        ui.label(
+            RichText::new("The functions come from the symbols of the last build, record again after flashing")
+                .small()
+                .weak(),
+        );
+
+        let mut tracker = tracker.borrow_mut();
+        let recording = tracker.profiler.is_some();
+
+        ui.horizontal(|ui| {
+            if recording {
+                if ui.button("Stop").clicked() {
+                    tracker.profiler = None;
+                }
+            } else if ui.button("Record").clicked() {
+                let functions = disassembler
+                    .try_borrow()
+                    .map(|disassembler| disassembler.functions())
+                    .unwrap_or_default();
+
+                tracker.profiler = Some(Profiler::new(functions));
+            }
+        });
+
+        let Some(profiler) = tracker.profiler.as_ref() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} cycles", profiler.total_cycles()));
+
+            if ui.button("Clear").clicked() {
+                profiler.clear();
+            }
+
+            if ui
+                .button("Export")
+                .on_hover_text("The call stacks in the folded format of the flame graph tools")
+                .clicked()
+            {
+                crate::simulator::export_text_file("profile.txt", profiler.to_folded());
+            }
+        });
+
+        ui.add_space(8.0);
+        egui::ScrollArea::vertical()
+            .id_salt("flame_graph")
+            .max_height(240.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                self.flame_graph_ui(ui, &profiler.flame_graph());
+            });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Hot functions").strong());
+            ui.add(egui::DragValue::new(&mut self.rows).range(5..=500));
+        });
+
+        if let Some((address, cycles)) = profiler.hot_addresses().first() {
+            ui.label(format!(
+                "Hottest instruction at {address:#010x}, {cycles} cycles"
+            ));
+        }
+
+        self.table_ui(ui, profiler);
+    }
+}
@@ -90,9 +90,11 @@ impl Sio {
                 ui.label(format!("{}", timer.counter));
                 ui.end_row();
 
-                ui.label("Compare value");
-                ui.label(format!("{}", timer.cmp));
-                ui.end_row();
+                for (core, cmp) in timer.cmp.iter().enumerate() {
+                    ui.label(format!("Compare value (core {core})"));
+                    ui.label(format!("{}", cmp));
+                    ui.end_row();
+                }
 
                 ui.label("Counting speed");
                 ui.label(if (timer.ctrl & 0b10) != 0 {
@@ -0,0 +1,292 @@
+/**
+ * @file app/snapshots.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief View window for the library of program snapshots
+ */
+use super::Rp2350Component;
+use egui::{Button, DragValue, RichText};
+use rp2350::rewind::Rewind;
+use rp2350::{ProgramSnapshot, Rp2350};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// Each snapshot holds a copy of the SRAM, so keep the library small
+const MAX_CHECKPOINTS: usize = 32;
+
+pub struct Checkpoint {
+    pub name: String,
+    pub snapshot: ProgramSnapshot,
+}
+
+#[derive(Default)]
+pub struct SnapshotLibrary {
+    pub checkpoints: VecDeque<Checkpoint>,
+    /// Taken by the simulation loop on its own, for the steps back
+    pub rewind: Rewind,
+}
+
+impl SnapshotLibrary {
+    pub fn push(&mut self, name: String, snapshot: ProgramSnapshot) {
+        if self.checkpoints.len() >= MAX_CHECKPOINTS {
+            self.checkpoints.pop_front();
+        }
+
+        self.checkpoints.push_back(Checkpoint { name, snapshot });
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Snapshots {
+    #[serde(skip)]
+    library: Rc<RefCell<SnapshotLibrary>>,
+    #[serde(skip)]
+    compare: [Option<usize>; 2],
+    core: usize,
+    #[serde(default)]
+    rewind_core: usize,
+    #[serde(default)]
+    rewind_count: u64,
+}
+
+impl Snapshots {
+    pub fn library(&self) -> Rc<RefCell<SnapshotLibrary>> {
+        Rc::clone(&self.library)
+    }
+
+    fn rewind_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350, rewind: &mut Rewind) {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.rewind_core, 0, "Core 0");
+            ui.radio_value(&mut self.rewind_core, 1, "Core 1");
+            ui.add(
+                DragValue::new(&mut self.rewind_count)
+                    .range(1..=1_000_000)
+                    .suffix(" instructions"),
+            );
+
+            if ui.button("Step back").clicked() {
+                let core = self.rewind_core;
+
+                match rewind.step_back(rp2350, core, self.rewind_count.max(1)) {
+                    Some(count) => crate::notify::info(format!(
+                        "Core {core} stepped back {count} instructions"
+                    )),
+                    None => crate::notify::warning(format!(
+                        "Core {core} cannot go back that far, or past a peripheral access"
+                    )),
+                }
+            }
+        });
+
+        ui.label(
+            RichText::new(format!(
+                "Up to {} instructions back, from the checkpoints taken while running. The peripherals are not rewound, so neither is an access to them",
+                rewind.depth(rp2350, self.rewind_core)
+            ))
+            .small()
+            .weak(),
+        );
+    }
+
+    fn compare_ui(&mut self, ui: &mut egui::Ui, library: &SnapshotLibrary) {
+        let [Some(a), Some(b)] = self.compare else {
+            ui.label("Select two snapshots (A and B) to compare them");
+            return;
+        };
+
+        let (Some(a), Some(b)) = (library.checkpoints.get(a), library.checkpoints.get(b)) else {
+            self.compare = [None, None];
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.core, 0, "Core 0");
+            ui.radio_value(&mut self.core, 1, "Core 1");
+        });
+
+        let (Some(regs_a), Some(regs_b)) = (
+            a.snapshot.registers(self.core),
+            b.snapshot.registers(self.core),
+        ) else {
+            ui.label("Registers are not available for this core");
+            return;
+        };
+
+        egui::Grid::new("Snapshot compare")
+            .num_columns(3)
+            .spacing([40.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("");
+                ui.strong(format!("A: {}", a.name));
+                ui.strong(format!("B: {}", b.name));
+                ui.end_row();
+
+                ui.label("Tick");
+                ui.label(format!("{}", a.snapshot.ticks));
+                ui.label(format!("{}", b.snapshot.ticks));
+                ui.end_row();
+
+                for (i, (value_a, value_b)) in regs_a.iter().zip(regs_b.iter()).enumerate() {
+                    let text_a = RichText::new(format!("{:#010x}", value_a)).monospace();
+                    let text_b = RichText::new(format!("{:#010x}", value_b)).monospace();
+
+                    ui.label(format!("x{}", i));
+                    if value_a != value_b {
+                        ui.label(text_a.color(egui::Color32::RED));
+                        ui.label(text_b.color(egui::Color32::RED));
+                    } else {
+                        ui.label(text_a);
+                        ui.label(text_b);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        let sram_diff = a.snapshot.sram_diff(&b.snapshot).collect::<Vec<_>>();
+        ui.add_space(8.0);
+        ui.label(format!("{} SRAM words differ", sram_diff.len()));
+
+        egui::ScrollArea::vertical()
+            .id_salt("snapshot sram diff")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for address in sram_diff.iter().take(256) {
+                    let offset = (address - rp2350::bus::Bus::SRAM) as usize;
+                    let word = |sram: &[u8]| {
+                        u32::from_le_bytes(sram[offset..offset + 4].try_into().unwrap())
+                    };
+
+                    ui.monospace(format!(
+                        "{:#010x}: {:#010x} -> {:#010x}",
+                        address,
+                        word(a.snapshot.sram()),
+                        word(b.snapshot.sram())
+                    ));
+                }
+            });
+    }
+}
+
+impl Rp2350Component for Snapshots {
+    const NAME: &'static str = "Snapshots";
+
+    fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.heading("Snapshots");
+
+        let library = Rc::clone(&self.library);
+        let Ok(mut library) = library.try_borrow_mut() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("Take snapshot").clicked() {
+                let name = format!("Manual #{}", library.checkpoints.len());
+                library.push(name, rp2350.snapshot());
+            }
+
+            if ui.button("Clear").clicked() {
+                library.checkpoints.clear();
+                self.compare = [None, None];
+            }
+        });
+
+        ui.label(
+            RichText::new(
+                "Breakpoints can take a snapshot when hit, right click on them in the Disassembler",
+            )
+            .small()
+            .weak(),
+        );
+        ui.label(
+            RichText::new(
+                "⚠ A snapshot holds the memories and the cores only, it cannot be restored once the peripherals were accessed since",
+            )
+            .small()
+            .color(egui::Color32::ORANGE),
+        );
+
+        ui.add_space(12.0);
+
+        let mut restore = None;
+        let mut remove = None;
+
+        egui::Grid::new("Snapshot list")
+            .num_columns(5)
+            .spacing([20.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("Tick");
+                ui.strong("PC (Core 0 / Core 1)");
+                ui.strong("Compare");
+                ui.strong("");
+                ui.end_row();
+
+                for (i, checkpoint) in library.checkpoints.iter().enumerate() {
+                    let pc = |core| match checkpoint.snapshot.pc(core) {
+                        Some(pc) => format!("{:#010x}", pc),
+                        None => "-".to_string(),
+                    };
+
+                    ui.label(&checkpoint.name);
+                    ui.label(format!("{}", checkpoint.snapshot.ticks));
+                    ui.monospace(format!("{} / {}", pc(0), pc(1)));
+
+                    ui.horizontal(|ui| {
+                        for (slot, text) in ["A", "B"].into_iter().enumerate() {
+                            let selected = self.compare[slot] == Some(i);
+                            if ui.selectable_label(selected, text).clicked() {
+                                self.compare[slot] = if selected { None } else { Some(i) };
+                            }
+                        }
+                    });
+
+                    let restorable = rp2350.can_restore(&checkpoint.snapshot);
+
+                    ui.horizontal(|ui| {
+                        let button = ui
+                            .add_enabled(restorable, Button::new("Restore"))
+                            .on_disabled_hover_text(
+                                "The peripherals were accessed since the snapshot, it cannot undo that",
+                            );
+
+                        if button.clicked() {
+                            restore = Some(i);
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        if let Some(index) = restore {
+            if let Some(checkpoint) = library.checkpoints.get(index) {
+                match rp2350.restore(&checkpoint.snapshot) {
+                    Ok(()) => crate::notify::info(format!("Restored snapshot {}", checkpoint.name)),
+                    Err(why) => crate::notify::warning(format!(
+                        "Cannot restore snapshot {}: {why}",
+                        checkpoint.name
+                    )),
+                }
+            }
+        }
+
+        if let Some(index) = remove {
+            library.checkpoints.remove(index);
+            self.compare = [None, None];
+        }
+
+        ui.add_space(12.0);
+        ui.heading("Rewind");
+        self.rewind_ui(ui, rp2350, &mut library.rewind);
+
+        ui.add_space(12.0);
+        ui.heading("Compare");
+        self.compare_ui(ui, &library);
+    }
+}
@@ -2,15 +2,79 @@
  * @file app/spi.rs
  * @author Nguyen Le Duy
  * @date 11/05/2025
- * @brief View window for the SPI peripheral
+ * @brief View window for the SPI peripheral and the devices on its bus
  */
 use super::Rp2350Component;
+use egui::{ComboBox, DragValue, RichText};
 use rp2350::Rp2350;
+use rp2350::device::{SdCard, SpiFlash};
+use rp2350::peripherals::SpiDeviceRef;
+use std::cell::RefCell;
 use std::rc::Rc;
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
+const MB: usize = 1024 * 1024;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+enum DeviceKind {
+    SdCard,
+    Flash,
+}
+
+impl DeviceKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::SdCard => "SD card",
+            Self::Flash => "SPI NOR flash",
+        }
+    }
+}
+
+enum Device {
+    SdCard(Rc<RefCell<SdCard>>),
+    Flash(Rc<RefCell<SpiFlash>>),
+}
+
+impl Device {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SdCard(_) => DeviceKind::SdCard.name(),
+            Self::Flash(_) => DeviceKind::Flash.name(),
+        }
+    }
+
+    fn bus_ref(&self) -> SpiDeviceRef {
+        match self {
+            Self::SdCard(device) => device.clone(),
+            Self::Flash(device) => device.clone(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Spi<const IDX: usize> {
-    // None
+    kind: DeviceKind,
+    cs: u8,
+    /// Size of a blank SD card or of the flash, in MiB
+    size: usize,
+    #[serde(skip)]
+    devices: Vec<(u8, Device)>,
+    /// Disk image picked from the host, inserted on the next frame
+    #[serde(skip)]
+    picked_image: Rc<RefCell<Option<Vec<u8>>>>,
+}
+
+impl<const IDX: usize> Default for Spi<IDX> {
+    fn default() -> Self {
+        Self {
+            kind: DeviceKind::SdCard,
+            // SPI0 CSn of the Pico SDK, the second block on the ones next to it
+            cs: if IDX == 0 { 17 } else { 13 },
+            size: 8,
+            devices: Vec::new(),
+            picked_image: Rc::default(),
+        }
+    }
 }
 
 impl<const IDX: usize> Rp2350Component for Spi<IDX> {
@@ -19,125 +83,221 @@ impl<const IDX: usize> Rp2350Component for Spi<IDX> {
     fn ui_with_tracker(
         &mut self,
         ui: &mut egui::Ui,
-        _rp2350: &mut Rp2350,
+        rp2350: &mut Rp2350,
         _tracker: Rc<crate::Tracker>,
     ) {
         ui.heading(format!("SPI {IDX}"));
 
-        ui.label("SPI peripheral is not implemented yet");
+        match IDX {
+            0 => view_spi(ui, rp2350, &rp2350.bus.peripherals.spi0),
+            1 => view_spi(ui, rp2350, &rp2350.bus.peripherals.spi1),
+            _ => unreachable!(),
+        }
 
-        // let tracker = tracker.borrow();
-        // match IDX {
-        //     0 => view_spi(ui, &rp2350.bus.peripherals.spi0, &tracker.spi[0]),
-        //     1 => view_spi(ui, &rp2350.bus.peripherals.spi1, &tracker.spi[1]),
-        //     _ => unreachable!(),
-        // }
+        let picked = self.picked_image.borrow_mut().take();
+        if let Some(image) = picked {
+            self.attach(
+                rp2350,
+                Device::SdCard(Rc::new(RefCell::new(SdCard::new(image)))),
+            );
+        }
+
+        ui.add_space(8.0);
+        ui.separator();
+        self.attach_ui(ui, rp2350);
+        ui.add_space(6.0);
+        self.devices_ui(ui, rp2350);
+    }
+}
+
+impl<const IDX: usize> Spi<IDX> {
+    fn attach(&mut self, rp2350: &mut Rp2350, device: Device) {
+        match rp2350.attach_spi_device(IDX as u8, self.cs, device.bus_ref()) {
+            Ok(()) => {
+                self.devices.push((self.cs, device));
+                self.devices.sort_by_key(|(cs, _)| *cs);
+            }
+            Err(why) => crate::notify::error(why.to_string()),
+        }
+    }
+
+    fn attach_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt(ui.id().with("device kind"))
+                .selected_text(self.kind.name())
+                .show_ui(ui, |ui| {
+                    for kind in [DeviceKind::SdCard, DeviceKind::Flash] {
+                        ui.selectable_value(&mut self.kind, kind, kind.name());
+                    }
+                });
+
+            ui.label("CS on");
+            ui.add(DragValue::new(&mut self.cs).range(0..=29).prefix("GPIO"));
+
+            match self.kind {
+                DeviceKind::SdCard => {
+                    ui.add(DragValue::new(&mut self.size).range(1..=64).suffix(" MiB"))
+                }
+                DeviceKind::Flash => {
+                    ui.add(DragValue::new(&mut self.size).range(1..=16).suffix(" MiB"))
+                }
+            };
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Attach").clicked() {
+                let device = match self.kind {
+                    DeviceKind::SdCard => {
+                        Device::SdCard(Rc::new(RefCell::new(SdCard::blank(self.size * MB))))
+                    }
+                    DeviceKind::Flash => {
+                        Device::Flash(Rc::new(RefCell::new(SpiFlash::new(self.size * MB))))
+                    }
+                };
+
+                self.attach(rp2350, device);
+            }
+
+            if self.kind == DeviceKind::SdCard && ui.button("Insert image…").clicked() {
+                crate::simulator::pick_sd_image(ui.ctx().clone(), self.picked_image.clone());
+            }
+        });
+    }
+
+    fn devices_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        if self.devices.is_empty() {
+            ui.label(RichText::new("No device on the bus").weak());
+            return;
+        }
+
+        let mut detached = None;
+
+        for (cs, device) in self.devices.iter() {
+            let selected = rp2350
+                .gpio
+                .borrow()
+                .spi_devices()
+                .iter()
+                .any(|d| d.spi == IDX as u8 && d.cs == *cs && d.is_selected());
+
+            let title = match selected {
+                true => format!("{} on GPIO{cs}, selected", device.name()),
+                false => format!("{} on GPIO{cs}", device.name()),
+            };
+
+            egui::CollapsingHeader::new(title)
+                .id_salt((IDX, *cs))
+                .default_open(true)
+                .show(ui, |ui| {
+                    match device {
+                        Device::SdCard(card) => sd_card_ui(ui, &card.borrow()),
+                        Device::Flash(flash) => flash_ui(ui, &mut flash.borrow_mut()),
+                    }
+
+                    if ui.button("Detach").clicked() {
+                        detached = Some(*cs);
+                    }
+                });
+        }
+
+        if let Some(cs) = detached {
+            rp2350.detach_spi_device(IDX as u8, cs);
+            self.devices.retain(|(device, _)| *device != cs);
+        }
     }
 }
 
-/*
 fn view_spi<const IDX: usize>(
     ui: &mut egui::Ui,
+    rp2350: &Rp2350,
     spi: &Rc<RefCell<rp2350::peripherals::Spi<IDX>>>,
-    _tracker: &SpiTracker,
 ) {
     let spi = spi.borrow();
-    egui::Grid::new(format!("Uart {IDX}"))
+
+    egui::Grid::new(format!("Spi {IDX}"))
         .num_columns(2)
         .spacing([40.0, 6.0])
         .striped(false)
         .show(ui, |ui| {
-            // is enabled
             ui.label("Enabled");
-            if uart.is_enabled() {
-                ui.label("Yes");
-            } else {
-                ui.label("No");
-            }
+            ui.label(if spi.is_enabled() { "Yes" } else { "No" });
             ui.end_row();
 
-            // is TX enabled
-            ui.label("TX Enabled");
-            if uart.is_transmit_enabled() {
-                ui.label("Yes");
-            } else {
-                ui.label("No");
-            }
+            ui.label("Mode");
+            ui.label(match (spi.is_master(), spi.is_loopback()) {
+                (_, true) => "Loopback",
+                (true, false) => "Master",
+                (false, false) => "Slave",
+            });
             ui.end_row();
 
-            // is RX enabled
-            ui.label("RX Enabled");
-            if uart.is_receive_enabled() {
-                ui.label("Yes");
-            } else {
-                ui.label("No");
-            }
+            ui.label("Frame format");
+            ui.label(match spi.frame_format() {
+                0 => format!(
+                    "Motorola SPI, mode {}",
+                    ((spi.clock_polarity() as u8) << 1) | spi.clock_phase() as u8
+                ),
+                1 => "TI synchronous serial".to_string(),
+                _ => "National Microwire".to_string(),
+            });
             ui.end_row();
 
-            // Baud rate
-            ui.label("Baud Rate");
-            ui.label(format!("{}", uart.get_baudrate()));
+            ui.label("Data bits");
+            ui.label(format!("{} bits", spi.data_bits()));
             ui.end_row();
 
-            // Data bits
-            ui.label("Data bits");
-            ui.label(format!("{} bits", uart.word_len()));
+            ui.label("SCK");
+            ui.label(format!(
+                "{} kHz",
+                spi.baudrate(rp2350.clock.clk_peri()) / 1000
+            ));
             ui.end_row();
 
-            // Stop bits
-            ui.label("Stop bits");
-            ui.label(if uart.two_stop_bits() {
-                "2 bits"
-            } else {
-                "1 bit"
-            });
+            ui.label("Frames");
+            ui.label(spi.frames.to_string());
             ui.end_row();
 
-            // Parity Odd/Even/None
-            ui.label("Parity");
-            if uart.is_parity_enabled() {
-                ui.label(if uart.is_parity_even() { "Even" } else { "Odd" });
-            } else {
-                ui.label("None");
-            }
+            ui.label("RX FIFO");
+            ui.label(format!("{} / 8", spi.rx_fifo.len()));
             ui.end_row();
         });
+}
 
-    // FIFO
-    // Transmitting FIFO
-
-    // Receiving FIFO
-
-    ui.collapsing("Transmitted value", |ui| {
-        ScrollArea::vertical()
-            .max_width(f32::INFINITY)
-            .max_height(200.0) // TODO
-            .stick_to_bottom(true)
-            .show(ui, |ui| {
-                let mut str = String::with_capacity(uart_tracker.tx.len());
+fn sd_card_ui(ui: &mut egui::Ui, card: &SdCard) {
+    ui.label(format!(
+        "{} MiB, {}",
+        card.image.len() / MB,
+        if card.is_initialized() {
+            "initialized"
+        } else {
+            "idle"
+        }
+    ));
+    ui.label(format!(
+        "{} blocks read, {} written",
+        card.reads, card.writes
+    ));
 
-                for ch in &uart_tracker.tx {
-                    str.push(char::from(*ch));
-                }
-
-                ui.label(RichText::new(str).monospace());
-            });
-    });
+    if ui.button("Download image").clicked() {
+        crate::simulator::export_disk_image("sdcard.img", card.image.clone());
+    }
+}
 
-    ui.collapsing("Received value", |ui| {
-        ScrollArea::vertical()
-            .max_width(f32::INFINITY)
-            .max_height(200.0) // TODO
-            .stick_to_bottom(true)
-            .show(ui, |ui| {
-                let mut str = String::with_capacity(uart_tracker.rx.len());
+fn flash_ui(ui: &mut egui::Ui, flash: &mut SpiFlash) {
+    ui.label(format!(
+        "{} KiB, {} page programs, {} erases",
+        flash.memory.len() / 1024,
+        flash.flash.programs(),
+        flash.flash.erases()
+    ));
 
-                for ch in &uart_tracker.rx {
-                    str.push(char::from(*ch as u8));
-                }
+    ui.horizontal(|ui| {
+        if ui.button("Erase").clicked() {
+            flash.memory.fill(0xFF);
+        }
 
-                ui.label(RichText::new(str).monospace());
-            });
+        if ui.button("Download").clicked() {
+            crate::simulator::export_disk_image("flash.bin", flash.memory.clone());
+        }
     });
 }
-*/
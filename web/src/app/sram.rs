@@ -5,11 +5,217 @@
  * @brief View window for the SRAM
  */
 use super::Rp2350Component;
+use egui::collapsing_header::CollapsingState;
+use rp2350::bus::{StoreRecord, StoreSource};
+use rp2350::common::Requestor;
+use rp2350::memory::InitPattern;
 use rp2350::Rp2350;
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[derive(Default, serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+enum PatternKind {
+    #[default]
+    Zero,
+    Byte,
+    Random,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct Sram {
     view: crate::widgets::MemoryView<0x2000_0000>,
+    pattern: PatternKind,
+    fill_byte: String,
+    seed: String,
+    /// Address asked from the memory view and its last writer
+    #[serde(skip)]
+    last_writer: Option<(u32, Option<StoreRecord>)>,
+}
+
+impl Default for Sram {
+    fn default() -> Self {
+        Self {
+            view: Default::default(),
+            pattern: PatternKind::Zero,
+            fill_byte: String::from("CC"),
+            seed: String::from("0"),
+            last_writer: None,
+        }
+    }
+}
+
+impl Sram {
+    fn init_pattern(&self) -> Option<InitPattern> {
+        let pattern = match self.pattern {
+            PatternKind::Zero => InitPattern::Zero,
+            PatternKind::Byte => {
+                let value = self.fill_byte.trim().trim_start_matches("0x");
+                InitPattern::Byte(u8::from_str_radix(value, 16).ok()?)
+            }
+            PatternKind::Random => InitPattern::Random(self.seed.trim().parse().ok()?),
+        };
+
+        Some(pattern)
+    }
+
+    fn init_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        egui::Grid::new("SRAM Init")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Fill pattern");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.pattern, PatternKind::Zero, "Zero");
+                    ui.radio_value(&mut self.pattern, PatternKind::Byte, "Byte");
+                    ui.radio_value(&mut self.pattern, PatternKind::Random, "Random");
+                });
+                ui.end_row();
+
+                match self.pattern {
+                    PatternKind::Zero => {}
+                    PatternKind::Byte => {
+                        ui.label("Byte (hex)");
+                        ui.text_edit_singleline(&mut self.fill_byte);
+                        ui.end_row();
+                    }
+                    PatternKind::Random => {
+                        ui.label("Seed");
+                        ui.text_edit_singleline(&mut self.seed);
+                        ui.end_row();
+                    }
+                }
+
+                ui.label("");
+                if ui
+                    .button("Apply")
+                    .on_hover_text("Fill the SRAM now and after every reset")
+                    .clicked()
+                {
+                    match self.init_pattern() {
+                        Some(pattern) => rp2350.bus.set_sram_init(pattern),
+                        None => crate::notify::error("Invalid fill pattern"),
+                    }
+                }
+                ui.end_row();
+
+                ui.label("Detect uninitialized reads");
+                let mut tracking = rp2350.bus.is_tracking_uninitialized_reads();
+                if ui.checkbox(&mut tracking, "").changed() {
+                    rp2350.bus.track_uninitialized_reads(tracking);
+                }
+                ui.end_row();
+            });
+
+        if !rp2350.bus.is_tracking_uninitialized_reads() {
+            return;
+        }
+
+        ui.add_space(8.0);
+        ui.label(format!(
+            "{} uninitialized read(s)",
+            rp2350.bus.uninitialized_reads.len()
+        ));
+
+        egui::ScrollArea::vertical()
+            .id_salt("SRAM uninitialized reads")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                egui::Grid::new("SRAM uninitialized reads")
+                    .num_columns(3)
+                    .spacing([40.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Requestor");
+                        ui.strong("PC");
+                        ui.strong("Address");
+                        ui.end_row();
+
+                        for read in rp2350.bus.uninitialized_reads.iter() {
+                            ui.label(match read.requestor {
+                                Requestor::Proc0 => "Core 0",
+                                Requestor::Proc1 => "Core 1",
+                                Requestor::DmaR => "DMA Read",
+                                Requestor::DmaW => "DMA Write",
+                            });
+                            ui.monospace(match read.pc {
+                                Some(pc) => format!("{:#010x}", pc),
+                                None => "-".to_string(),
+                            });
+                            ui.monospace(format!("{:#010x}", read.address));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    /// Look up who wrote the address asked from the memory view, up to the current time
+    fn query_writer(&mut self, rp2350: &Rp2350) -> bool {
+        let Some(address) = self.view.take_writer_query() else {
+            return false;
+        };
+
+        if !rp2350.bus.is_recording_stores() {
+            crate::notify::warning("Enable \"Record stores\" to find who wrote an address");
+        }
+
+        let now = *rp2350.clock.ticks.borrow();
+        let record = rp2350.bus.last_writer(address, now).copied();
+        self.last_writer = Some((address, record));
+        true
+    }
+
+    fn writer_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
+        let mut recording = rp2350.bus.is_recording_stores();
+        if ui
+            .checkbox(&mut recording, "Record stores")
+            .on_hover_text("Remember who wrote each address, right click a row to ask")
+            .changed()
+        {
+            rp2350.bus.record_stores(recording);
+            self.last_writer = None;
+        }
+
+        let Some((address, record)) = self.last_writer else {
+            return;
+        };
+
+        ui.add_space(6.0);
+
+        let Some(record) = record else {
+            ui.label(format!("No recorded store to {:#010x}", address));
+            return;
+        };
+
+        egui::Grid::new("SRAM last writer")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Address");
+                ui.monospace(format!("{:#010x}", address));
+                ui.end_row();
+
+                ui.label("Written by");
+                match record.source {
+                    StoreSource::Core { core, pc } => {
+                        ui.monospace(format!("Core {} at PC {:#010x}", core, pc))
+                    }
+                    StoreSource::Dma { channel } => ui.label(format!("DMA channel {}", channel)),
+                };
+                ui.end_row();
+
+                ui.label("Store");
+                ui.monospace(format!(
+                    "{:#x} ({:?}) to {:#010x}",
+                    record.value, record.size, record.address
+                ));
+                ui.end_row();
+
+                ui.label("Tick");
+                ui.label(format!("{}", record.tick));
+                ui.end_row();
+            });
+    }
 }
 
 impl Rp2350Component for Sram {
@@ -17,6 +223,32 @@ impl Rp2350Component for Sram {
 
     fn ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350) {
         ui.heading("SRAM");
-        self.view.ui(ui, &rp2350.bus.sram);
+
+        CollapsingState::load_with_default_open(ui.ctx(), ui.make_persistent_id("sram_init"), false)
+            .show_header(ui, |ui| {
+                ui.label("Initialization");
+            })
+            .body(|ui| {
+                self.init_ui(ui, rp2350);
+            });
+
+        let mut writer_state =
+            CollapsingState::load_with_default_open(ui.ctx(), ui.make_persistent_id("sram_writer"), false);
+
+        if self.query_writer(rp2350) {
+            writer_state.set_open(true);
+        }
+
+        writer_state
+            .show_header(ui, |ui| {
+                ui.label("Last writer");
+            })
+            .body(|ui| {
+                self.writer_ui(ui, rp2350);
+            });
+
+        ui.add_space(12.0);
+
+        self.view.ui_mut(ui, &mut rp2350.bus.sram);
     }
 }
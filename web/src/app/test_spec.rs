@@ -0,0 +1,179 @@
+/**
+ * @file app/test_spec.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Import and export of the breakpoints, watches, register presets and acceptance checks of a project
+ */
+use super::disassembler::{BreakpointAction, Disassembler};
+use super::presets::RegisterPresets;
+use super::variables::Variables;
+use api_types::{CycleBudgetSpec, GpioAssertion, TestSpec, UartAssertions};
+use egui::RichText;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+impl From<BreakpointAction> for api_types::BreakpointAction {
+    fn from(action: BreakpointAction) -> Self {
+        match action {
+            BreakpointAction::Pause => Self::Pause,
+            BreakpointAction::Snapshot => Self::Snapshot,
+            BreakpointAction::SnapshotAndPause => Self::SnapshotAndPause,
+        }
+    }
+}
+
+impl From<api_types::BreakpointAction> for BreakpointAction {
+    fn from(action: api_types::BreakpointAction) -> Self {
+        match action {
+            api_types::BreakpointAction::Pause => Self::Pause,
+            api_types::BreakpointAction::Snapshot => Self::Snapshot,
+            api_types::BreakpointAction::SnapshotAndPause => Self::SnapshotAndPause,
+        }
+    }
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TestSpecView {
+    /// The checks are run by the CLI, they are kept here to be exported again
+    uart: UartAssertions,
+    gpio: Vec<GpioAssertion>,
+    budgets: Vec<CycleBudgetSpec>,
+    #[serde(skip)]
+    imported: Rc<RefCell<Option<TestSpec>>>,
+}
+
+impl TestSpecView {
+    fn export(
+        &self,
+        disassembler: &Disassembler,
+        variables: &Variables,
+        presets: &RegisterPresets,
+    ) -> TestSpec {
+        let mut breakpoints: Vec<_> = disassembler
+            .breakpoints()
+            .map(|(address, action)| api_types::Breakpoint {
+                address,
+                action: action.into(),
+            })
+            .collect();
+        breakpoints.sort_by_key(|breakpoint| breakpoint.address);
+
+        TestSpec {
+            breakpoints,
+            watches: variables.watched().map(String::from).collect(),
+            uart: self.uart.clone(),
+            gpio: self.gpio.clone(),
+            budgets: self.budgets.clone(),
+            presets: presets.presets().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// The breakpoints, watches and presets are added to the current ones, the checks are replaced
+    fn import(
+        &mut self,
+        spec: TestSpec,
+        disassembler: &mut Disassembler,
+        variables: &mut Variables,
+        presets: &mut RegisterPresets,
+    ) {
+        for breakpoint in spec.breakpoints {
+            disassembler.set_breakpoint_action(breakpoint.address, breakpoint.action.into());
+        }
+
+        for name in &spec.watches {
+            variables.watch(name);
+        }
+
+        presets.import(spec.presets);
+        self.uart = spec.uart;
+        self.gpio = spec.gpio;
+        self.budgets = spec.budgets;
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        disassembler: &RefCell<Disassembler>,
+        variables: &mut Variables,
+        presets: &mut RegisterPresets,
+    ) {
+        ui.heading("Test Spec");
+
+        let Ok(mut disassembler) = disassembler.try_borrow_mut() else {
+            return;
+        };
+
+        let imported = self.imported.borrow_mut().take();
+        if let Some(spec) = imported {
+            self.import(spec, &mut disassembler, variables, presets);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Import...").clicked() {
+                crate::simulator::pick_test_spec(ui.ctx().clone(), Rc::clone(&self.imported));
+            }
+
+            if ui.button("Export...").clicked() {
+                let spec = self.export(&disassembler, variables, presets);
+                crate::simulator::export_test_spec(&spec);
+            }
+        });
+
+        ui.add_space(8.0);
+
+        egui::Grid::new("Test Spec")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Breakpoints");
+                ui.label(disassembler.breakpoints().count().to_string());
+                ui.end_row();
+
+                ui.label("Watched variables");
+                ui.label(variables.watched().count().to_string());
+                ui.end_row();
+
+                ui.label("Register presets");
+                ui.label(presets.presets().len().to_string());
+                ui.end_row();
+
+                for pattern in &self.uart.expect {
+                    ui.label("UART expects");
+                    ui.monospace(pattern);
+                    ui.end_row();
+                }
+
+                for pattern in &self.uart.fail_on {
+                    ui.label("UART fails on");
+                    ui.monospace(pattern);
+                    ui.end_row();
+                }
+
+                for assertion in &self.gpio {
+                    ui.label(format!("GPIO{}", assertion.pin));
+                    let level = if assertion.high { "high" } else { "low" };
+                    ui.label(match assertion.at_us {
+                        Some(at_us) => format!("{level} at {at_us} us"),
+                        None => format!("{level} at the end"),
+                    });
+                    ui.end_row();
+                }
+
+                for budget in &self.budgets {
+                    ui.label("Cycle budget");
+                    ui.label(format!("{} within {} cycles", budget.region, budget.max_cycles));
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.label(
+            RichText::new("The UART, GPIO and cycle budget checks run with `pico2-cli run --spec`")
+                .small()
+                .weak(),
+        );
+    }
+}
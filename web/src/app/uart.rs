@@ -6,14 +6,58 @@
  */
 use super::Rp2350Component;
 use crate::tracker::UartTracker;
+use crate::widgets::Terminal;
 use egui::{RichText, ScrollArea};
 use rp2350::Rp2350;
+use rp2350::device::SerialTerminal;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct Uart<const IDX: usize> {
-    // None
+    #[serde(default)]
+    terminal: Terminal,
+    /// Wired to the chip on the first line typed
+    #[serde(skip)]
+    serial: Option<Rc<RefCell<SerialTerminal>>>,
+}
+
+impl<const IDX: usize> Uart<IDX> {
+    fn terminal_ui(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350, uart_tracker: &UartTracker) {
+        ui.add_space(8.0);
+        ui.strong("Terminal");
+
+        if let Some(bytes) = self.terminal.ui(ui, uart_tracker) {
+            let serial = self.serial.get_or_insert_with(|| {
+                let serial = Rc::new(RefCell::new(SerialTerminal::new(IDX as u8)));
+                rp2350.attach_device(Box::new(Rc::clone(&serial)));
+                serial
+            });
+
+            serial.borrow_mut().send(&bytes);
+        }
+
+        let Some(serial) = self.serial.as_ref() else {
+            return;
+        };
+
+        let pending = serial.borrow().pending();
+        if pending > 0 {
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(format!(
+                        "{pending} characters waiting for the receiver, at the baud rate of the UART"
+                    ))
+                    .small()
+                    .weak(),
+                );
+
+                if ui.small_button("Drop").clicked() {
+                    serial.borrow_mut().clear();
+                }
+            });
+        }
+    }
 }
 
 impl<const IDX: usize> Rp2350Component for Uart<IDX> {
@@ -27,11 +71,15 @@ impl<const IDX: usize> Rp2350Component for Uart<IDX> {
     ) {
         ui.heading(format!("UART {IDX}"));
         let tracker = tracker.borrow();
+        let clk_peri = rp2350.clock.clk_peri();
+        let peripherals = &rp2350.bus.peripherals;
         match IDX {
-            0 => view_uart(ui, &rp2350.bus.peripherals.uart0, &tracker.uart[0]),
-            1 => view_uart(ui, &rp2350.bus.peripherals.uart1, &tracker.uart[1]),
+            0 => view_uart(ui, &peripherals.uart0, &tracker.uart[0], clk_peri),
+            1 => view_uart(ui, &peripherals.uart1, &tracker.uart[1], clk_peri),
             _ => unreachable!(),
         }
+
+        self.terminal_ui(ui, rp2350, &tracker.uart[IDX]);
     }
 }
 
@@ -39,6 +87,7 @@ fn view_uart<const IDX: usize>(
     ui: &mut egui::Ui,
     uart: &Rc<RefCell<rp2350::peripherals::Uart<IDX>>>,
     uart_tracker: &UartTracker,
+    clk_peri: u64,
 ) {
     let uart = uart.borrow();
     egui::Grid::new(format!("Uart {IDX}"))
@@ -75,7 +124,7 @@ fn view_uart<const IDX: usize>(
 
             // Baud rate
             ui.label("Baud Rate");
-            ui.label(format!("{}", uart.get_baudrate()));
+            ui.label(format!("{}", uart.get_baudrate(clk_peri)));
             ui.end_row();
 
             // Data bits
@@ -0,0 +1,132 @@
+/**
+ * @file app/usb.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief View window for the USB controller and the serial port of its CDC ACM interface
+ */
+use super::Rp2350Component;
+use egui::{RichText, ScrollArea};
+use rp2350::peripherals::usb::HostState;
+use rp2350::Rp2350;
+use std::rc::Rc;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Usb {
+    #[serde(skip)]
+    input: String,
+    /// Send a line ending with each input, like a terminal does on enter
+    append_newline: bool,
+}
+
+impl Rp2350Component for Usb {
+    const NAME: &'static str = "USB";
+
+    fn ui_with_tracker(
+        &mut self,
+        ui: &mut egui::Ui,
+        rp2350: &mut Rp2350,
+        tracker: Rc<crate::Tracker>,
+    ) {
+        ui.heading(Self::NAME);
+
+        let mut usb = rp2350.bus.peripherals.usbctrl.borrow_mut();
+
+        egui::Grid::new("usb")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Enabled");
+                ui.label(if usb.is_enabled() { "Yes" } else { "No" });
+                ui.end_row();
+
+                ui.label("Attached");
+                ui.label(if usb.is_attached() { "Yes" } else { "No" });
+                ui.end_row();
+
+                ui.label("Address");
+                ui.label(format!("{}", usb.device_address()));
+                ui.end_row();
+
+                ui.label("Host");
+                ui.label(match usb.host.state {
+                    HostState::Detached => "Detached",
+                    HostState::Connecting => "Connecting",
+                    HostState::Resetting => "Resetting",
+                    HostState::Enumerating => "Enumerating",
+                    HostState::Configured => "Configured",
+                });
+                ui.end_row();
+
+                ui.label("CDC ACM");
+                ui.label(match usb.host.cdc {
+                    Some(cdc) => format!(
+                        "Interface {}, IN EP{}, OUT EP{}",
+                        cdc.interface, cdc.data_in, cdc.data_out
+                    ),
+                    None => String::from("None"),
+                });
+                ui.end_row();
+
+                let line_coding = usb.host.line_coding;
+                ui.label("Line coding");
+                ui.label(format!(
+                    "{} baud, {} data bits",
+                    line_coding.baudrate, line_coding.data_bits
+                ));
+                ui.end_row();
+            });
+
+        ui.add_space(6.0);
+
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(&mut self.input);
+            let entered =
+                response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+
+            if ui.button("Send").clicked() || entered {
+                let mut data = core::mem::take(&mut self.input).into_bytes();
+                if self.append_newline {
+                    data.push(b'\n');
+                }
+
+                usb.cdc_send(&data);
+            }
+
+            ui.checkbox(&mut self.append_newline, "Newline");
+        });
+
+        if usb.host.pending_tx() > 0 {
+            ui.label(format!(
+                "{} bytes waiting for the device",
+                usb.host.pending_tx()
+            ));
+        }
+
+        let tracker = tracker.borrow();
+
+        ui.collapsing("Transmitted value", |ui| {
+            ScrollArea::vertical()
+                .id_salt("usb_tx")
+                .max_width(f32::INFINITY)
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    let bytes: Vec<u8> = tracker.usb.tx.iter().copied().collect();
+                    ui.label(RichText::new(String::from_utf8_lossy(&bytes)).monospace());
+                });
+        });
+
+        ui.collapsing("Received value", |ui| {
+            ScrollArea::vertical()
+                .id_salt("usb_rx")
+                .max_width(f32::INFINITY)
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    let bytes: Vec<u8> = tracker.usb.rx.iter().copied().collect();
+                    ui.label(RichText::new(String::from_utf8_lossy(&bytes)).monospace());
+                });
+        });
+    }
+}
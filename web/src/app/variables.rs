@@ -0,0 +1,364 @@
+/**
+ * @file app/variables.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Watch window of the global variables, decoded with the debug info of the program
+ */
+use crate::Tracker;
+use egui::collapsing_header::CollapsingState;
+use egui::{RichText, Sense};
+use rp2350::bus::WatchRegion;
+use rp2350::elf::{DebugInfo, Type, TypeRef};
+use rp2350::Rp2350;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The watch ids of the bus are shared with other tools, the variables use their own range
+const WATCH_ID_BASE: u32 = 0x5641_0000;
+const WATCH_ID_MASK: u32 = 0xffff_0000;
+
+const MAX_SUGGESTIONS: usize = 8;
+const MAX_NESTING: usize = 8;
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct Watched {
+    name: String,
+    id: u32,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Variables {
+    #[serde(skip)]
+    debug_info: Rc<RefCell<Option<DebugInfo>>>,
+    /// Kept by name, so the list survives a new build of the program
+    watched: Vec<Watched>,
+    next_id: u32,
+    #[serde(skip)]
+    new_name: String,
+    /// Path of the value being edited and its text
+    #[serde(skip)]
+    editing: Option<(String, String)>,
+}
+
+impl Variables {
+    pub fn debug_info(&self) -> Rc<RefCell<Option<DebugInfo>>> {
+        Rc::clone(&self.debug_info)
+    }
+
+    /// Names of the watched variables, in the order they were added
+    pub fn watched(&self) -> impl Iterator<Item = &str> {
+        self.watched.iter().map(|watched| watched.name.as_str())
+    }
+
+    pub fn watch(&mut self, name: &str) {
+        if name.is_empty() || self.watched.iter().any(|watched| watched.name == name) {
+            return;
+        }
+
+        self.watched.push(Watched {
+            name: name.to_string(),
+            id: WATCH_ID_BASE | (self.next_id & !WATCH_ID_MASK),
+        });
+
+        self.next_id = self.next_id.wrapping_add(1);
+        self.new_name.clear();
+    }
+
+    /// Keep the watch regions of the bus in line with the watched variables, the bus
+    /// forgets them when the chip is rebuilt
+    fn sync_watches(&self, rp2350: &mut Rp2350, debug_info: Option<&DebugInfo>) {
+        let desired: Vec<WatchRegion> = self
+            .watched
+            .iter()
+            .filter_map(|watched| {
+                let debug_info = debug_info?;
+                let variable = debug_info.variable(&watched.name)?;
+                let size = debug_info.size_of(variable.ty).filter(|&size| size > 0)?;
+
+                Some(WatchRegion {
+                    range: variable.address..variable.address + size,
+                    id: watched.id,
+                })
+            })
+            .collect();
+
+        let current: Vec<WatchRegion> = rp2350
+            .bus
+            .watched_regions()
+            .iter()
+            .filter(|watch| watch.id & WATCH_ID_MASK == WATCH_ID_BASE)
+            .cloned()
+            .collect();
+
+        if current == desired {
+            return;
+        }
+
+        for watch in current {
+            rp2350.bus.unwatch_region(watch.id);
+        }
+
+        for watch in desired {
+            rp2350.bus.watch_region(watch.range, watch.id);
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        rp2350: &mut Rp2350,
+        tracker: Rc<Tracker>,
+        paused: bool,
+    ) {
+        ui.heading("Variables");
+
+        let shared = Rc::clone(&self.debug_info);
+        let debug_info = shared.borrow();
+
+        ui.horizontal(|ui| {
+            match debug_info.as_ref() {
+                Some(debug_info) => ui.label(format!(
+                    "{} variables in the debug info",
+                    debug_info.variables.len()
+                )),
+                None => ui.label("No debug info, compile the program or load its ELF file"),
+            };
+
+            if ui.button("Load ELF...").clicked() {
+                crate::simulator::pick_elf_file(ui.ctx().clone(), Rc::clone(&self.debug_info));
+            }
+        });
+
+        let mut add = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Variable");
+            let response = ui.text_edit_singleline(&mut self.new_name);
+            let entered =
+                response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+
+            if ui.button("Watch").clicked() || entered {
+                add = Some(self.new_name.trim().to_string());
+            }
+        });
+
+        if let (Some(debug_info), false) = (debug_info.as_ref(), self.new_name.trim().is_empty()) {
+            let pattern = self.new_name.trim();
+
+            ui.horizontal_wrapped(|ui| {
+                for variable in debug_info
+                    .variables
+                    .iter()
+                    .filter(|variable| variable.name.contains(pattern))
+                    .take(MAX_SUGGESTIONS)
+                {
+                    if ui.small_button(&variable.name).clicked() {
+                        add = Some(variable.name.clone());
+                    }
+                }
+            });
+        }
+
+        if let Some(name) = add {
+            self.watch(&name);
+        }
+
+        self.sync_watches(rp2350, debug_info.as_ref());
+
+        if !paused {
+            ui.weak("Pause the simulation to edit the values");
+        }
+
+        ui.separator();
+
+        let mut removed = None;
+        let tracker = tracker.borrow();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, watched) in self.watched.iter().enumerate() {
+                let mut controls = |ui: &mut egui::Ui| {
+                    if ui
+                        .small_button("✖")
+                        .on_hover_text("Stop watching")
+                        .clicked()
+                    {
+                        removed = Some(index);
+                    }
+                };
+
+                let Some((debug_info, variable)) = debug_info
+                    .as_ref()
+                    .and_then(|debug_info| Some((debug_info, debug_info.variable(&watched.name)?)))
+                else {
+                    ui.horizontal(|ui| {
+                        controls(ui);
+                        ui.label(&watched.name);
+                        ui.weak("not found in the debug info");
+                    });
+                    continue;
+                };
+
+                let writes = tracker
+                    .watched_writes
+                    .get(&watched.id)
+                    .copied()
+                    .unwrap_or_default();
+
+                let mut controls = |ui: &mut egui::Ui| {
+                    controls(ui);
+                    ui.weak(format!("{} writes", writes))
+                        .on_hover_text("Stores seen by the bus since the program was flashed");
+                };
+
+                let mut view = ValueView {
+                    debug_info,
+                    rp2350: &mut *rp2350,
+                    editing: &mut self.editing,
+                    paused,
+                };
+
+                view.show(
+                    ui,
+                    &watched.name,
+                    &watched.name,
+                    variable.address,
+                    variable.ty,
+                    &mut controls,
+                    0,
+                );
+            }
+        });
+
+        if let Some(index) = removed {
+            self.watched.remove(index);
+        }
+    }
+}
+
+struct ValueView<'a> {
+    debug_info: &'a DebugInfo,
+    rp2350: &'a mut Rp2350,
+    editing: &'a mut Option<(String, String)>,
+    paused: bool,
+}
+
+impl ValueView<'_> {
+    #[allow(clippy::too_many_arguments)]
+    fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        path: &str,
+        name: &str,
+        address: u32,
+        ty: Option<TypeRef>,
+        controls: &mut dyn FnMut(&mut egui::Ui),
+        depth: usize,
+    ) {
+        let fields = match depth < MAX_NESTING {
+            true => self.debug_info.fields(ty),
+            false => Vec::new(),
+        };
+
+        let mut header = |ui: &mut egui::Ui, view: &mut Self| {
+            controls(ui);
+            ui.label(RichText::new(name).strong());
+            ui.weak(view.debug_info.type_name(ty));
+            ui.weak(format!("{:#010x}", address));
+            view.value(ui, path, address, ty);
+        };
+
+        if fields.is_empty() {
+            ui.horizontal(|ui| header(ui, self));
+            return;
+        }
+
+        CollapsingState::load_with_default_open(ui.ctx(), ui.make_persistent_id(path), false)
+            .show_header(ui, |ui| header(ui, self))
+            .body(|ui| {
+                for field in fields {
+                    let path = format!("{}.{}", path, field.name);
+                    let address = address + field.offset;
+
+                    self.show(
+                        ui,
+                        &path,
+                        &field.name,
+                        address,
+                        field.ty,
+                        &mut |_| {},
+                        depth + 1,
+                    );
+                }
+            });
+    }
+
+    fn value(&mut self, ui: &mut egui::Ui, path: &str, address: u32, ty: Option<TypeRef>) {
+        let debug_info = self.debug_info;
+
+        if self
+            .editing
+            .as_ref()
+            .is_some_and(|(editing, _)| editing == path)
+        {
+            let Some((_, text)) = self.editing.as_mut() else {
+                return;
+            };
+
+            let response = ui.add(egui::TextEdit::singleline(text).desired_width(160.0));
+
+            if !response.has_focus() && !response.lost_focus() {
+                response.request_focus();
+            }
+
+            if response.lost_focus() {
+                if ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    match debug_info.encode(ty, text) {
+                        Some(bytes) => {
+                            if let Err(why) = self.rp2350.load_into_memory(address, &bytes) {
+                                crate::notify::error(format!("Failed to write the value: {}", why));
+                            }
+                        }
+                        None => crate::notify::error(format!(
+                            "`{}` is not a valid {}",
+                            text.trim(),
+                            debug_info.type_name(ty)
+                        )),
+                    }
+                }
+
+                *self.editing = None;
+            }
+
+            return;
+        }
+
+        let size = debug_info.size_of(ty).unwrap_or_default() as usize;
+        let value = match self.rp2350.bus.peek_slice(address, size) {
+            Some(bytes) if size > 0 => debug_info.format(ty, bytes),
+            _ => String::from("?"),
+        };
+
+        let editable = self.paused && debug_info.is_editable(ty);
+        let sense = match editable {
+            true => Sense::click(),
+            false => Sense::hover(),
+        };
+
+        let response = ui.add(
+            egui::Label::new(RichText::new(&value).monospace())
+                .sense(sense)
+                .truncate(),
+        );
+
+        if editable && response.on_hover_text("Click to edit").clicked() {
+            // drop the character shown next to the numeric value of a char
+            let text = match debug_info.resolve(ty) {
+                Some(Type::Base { .. }) => value.split(' ').next().unwrap_or_default().to_string(),
+                _ => value,
+            };
+
+            *self.editing = Some((path.to_string(), text));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+/**
+ * @file app/waveform.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Recording of the pins, the interrupt lines and the bus into a VCD file
+ */
+use super::Rp2350Component;
+use crate::Tracker;
+use egui::RichText;
+use rp2350::trace::vcd::{VcdConfig, VcdRecorder};
+use rp2350::{Inspector, InspectorGroup, Rp2350};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The recording in progress, sampled by the simulation loop after every step
+#[derive(Default)]
+pub struct WaveformCapture {
+    /// The last recording, kept once stopped to be downloaded
+    pub recorder: Option<Rc<VcdRecorder>>,
+    pub recording: bool,
+}
+
+impl WaveformCapture {
+    pub fn sample(&self, rp2350: &Rp2350) {
+        if let Some(recorder) = self.recorder.as_ref().filter(|_| self.recording) {
+            recorder.sample(rp2350);
+        }
+    }
+}
+
+/// Pins as a list of numbers and ranges, like `0-3, 25`
+fn parse_pins(text: &str) -> Result<Vec<u8>, String> {
+    let number = |text: &str| {
+        let text = text.trim();
+        text.parse::<u8>()
+            .ok()
+            .filter(|pin| (*pin as usize) < rp2350::gpio::NUM_GPIO)
+            .ok_or_else(|| format!("`{text}` is not a GPIO"))
+    };
+
+    let mut pins = Vec::new();
+
+    for item in text.split(',').filter(|item| !item.trim().is_empty()) {
+        match item.split_once('-') {
+            Some((first, last)) => pins.extend(number(first)?..=number(last)?),
+            None => pins.push(number(item)?),
+        }
+    }
+
+    Ok(pins)
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Waveform {
+    #[serde(skip)]
+    capture: Rc<RefCell<WaveformCapture>>,
+    pins: String,
+    irqs: bool,
+    bus: bool,
+}
+
+impl Default for Waveform {
+    fn default() -> Self {
+        Self {
+            capture: Default::default(),
+            pins: "0-29".to_string(),
+            irqs: true,
+            bus: false,
+        }
+    }
+}
+
+impl Waveform {
+    pub fn capture(&self) -> Rc<RefCell<WaveformCapture>> {
+        Rc::clone(&self.capture)
+    }
+
+    fn start(&mut self, rp2350: &mut Rp2350, tracker: Rc<Tracker>) {
+        let pins = match parse_pins(&self.pins) {
+            Ok(pins) => pins,
+            Err(why) => {
+                crate::notify::error(why);
+                return;
+            }
+        };
+
+        let config = VcdConfig {
+            pins,
+            irqs: self.irqs,
+            bus: self.bus,
+        };
+        let recorder = Rc::new(VcdRecorder::new(rp2350, config));
+
+        // the bus accesses reach the recorder as inspection events
+        let inspectors = [
+            tracker as Rc<dyn Inspector>,
+            Rc::clone(&recorder) as Rc<dyn Inspector>,
+        ];
+        rp2350.set_inspector(Rc::new(inspectors.into_iter().collect::<InspectorGroup>()));
+
+        let mut capture = self.capture.borrow_mut();
+        capture.recorder = Some(recorder);
+        capture.recording = true;
+    }
+
+    fn stop(&mut self, rp2350: &mut Rp2350, tracker: Rc<Tracker>) {
+        rp2350.set_inspector(tracker);
+        self.capture.borrow_mut().recording = false;
+    }
+}
+
+impl Rp2350Component for Waveform {
+    const NAME: &'static str = "Waveform";
+
+    fn ui_with_tracker(&mut self, ui: &mut egui::Ui, rp2350: &mut Rp2350, tracker: Rc<Tracker>) {
+        ui.heading("Waveform");
+
+        let (recorder, recording) = {
+            let capture = self.capture.borrow();
+            (capture.recorder.clone(), capture.recording)
+        };
+
+        ui.add_enabled_ui(!recording, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("GPIO");
+                ui.text_edit_singleline(&mut self.pins);
+            });
+
+            ui.checkbox(&mut self.irqs, "Interrupt lines");
+            ui.checkbox(&mut self.bus, "Bus accesses");
+        });
+
+        ui.horizontal(|ui| {
+            if recording {
+                if ui.button("Stop").clicked() {
+                    self.stop(rp2350, tracker);
+                }
+            } else if ui.button("Record").clicked() {
+                self.start(rp2350, tracker);
+            }
+
+            if let Some(recorder) = recorder.as_ref() {
+                if ui.button("Download VCD").clicked() {
+                    crate::simulator::export_vcd_file(recorder.to_vcd());
+                }
+            }
+        });
+
+        if let Some(recorder) = recorder {
+            ui.label(format!("{} value changes recorded", recorder.len()));
+
+            if recorder.dropped() > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!(
+                        "The recording is full, {} changes dropped",
+                        recorder.dropped()
+                    ),
+                );
+            }
+        }
+
+        ui.label(
+            RichText::new("Open the file in GTKWave or any logic analyzer viewer. The bus fills the recording fast")
+                .small()
+                .weak(),
+        );
+    }
+}
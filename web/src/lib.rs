@@ -6,7 +6,9 @@
  */
 mod api;
 mod app;
+mod local_compiler;
 mod notify;
+mod plugin;
 mod simulator;
 mod tracker;
 mod widgets;
@@ -0,0 +1,193 @@
+/**
+ * @file local_compiler.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Compilation in the browser with a clang built for WebAssembly, for the offline use
+ */
+use api_types::{CompilationRequest, ElfSymbol, ElfSymbolKind, Language, Target};
+use js_sys::{Function, JSON, Promise, Reflect, Uint8Array};
+use rp2350::bus::Bus;
+use rp2350::elf::{Elf, SymbolKind};
+use rp2350::processor::hazard3::disasm;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use uf2::FamilyId;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+/// The toolchain, next to the page. It is not part of the app and only used if deployed.
+const COMPILER_MODULE: &str = "clang/compiler.js";
+const FLASH_SIZE: usize = 0x100_0000;
+/// Headers of the pico-sdk, the toolchain only has the ones of its sysroot
+const SDK_HEADERS: [&str; 4] = ["pico.h", "pico/", "hardware/", "boards/"];
+
+#[wasm_bindgen(inline_js = "
+export function import_module(url) {
+    return import(new URL(url, document.baseURI).href);
+}
+")]
+extern "C" {
+    fn import_module(url: &str) -> Promise;
+}
+
+thread_local! {
+    /// `compile` of the module once it is imported, `None` inside if it is missing
+    static COMPILER: RefCell<Option<Option<Function>>> = const { RefCell::new(None) };
+}
+
+/// A program built in the browser, like the `Done` response of the server
+pub struct LocalBuild {
+    pub uf2: Vec<u8>,
+    pub disassembler: String,
+    pub elf: Vec<u8>,
+    pub symbols: Vec<ElfSymbol>,
+}
+
+/// The toolchain builds bare-metal C and assembly for rv32imac, the rest goes to the
+/// server, like the programs of the pico-sdk
+pub fn supports(request: &CompilationRequest) -> bool {
+    matches!(request.lang, Language::C | Language::Assembly)
+        && request.target == Target::RiscV
+        && request.libraries.is_empty()
+        && !request
+            .source
+            .iter()
+            .any(|file| file.filename == "CMakeLists.txt" || includes_sdk(&file.code))
+}
+
+fn includes_sdk(code: &str) -> bool {
+    code.lines()
+        .filter_map(|line| line.trim_start().strip_prefix('#'))
+        .filter_map(|line| line.trim_start().strip_prefix("include"))
+        .map(|path| path.trim_start().trim_start_matches(['<', '"']))
+        .any(|path| SDK_HEADERS.iter().any(|header| path.starts_with(header)))
+}
+
+/// `compile` exported by the module of the toolchain, imported on the first build
+async fn compiler() -> Option<Function> {
+    if let Some(compiler) = COMPILER.with_borrow(Clone::clone) {
+        return compiler;
+    }
+
+    let compiler = match JsFuture::from(import_module(COMPILER_MODULE)).await {
+        Ok(module) => Reflect::get(&module, &"compile".into())
+            .ok()
+            .and_then(|compile| compile.dyn_into::<Function>().ok()),
+        Err(why) => {
+            log::info!("No compiler in the browser, building on the server: {why:?}");
+            None
+        }
+    };
+
+    COMPILER.set(Some(compiler.clone()));
+    compiler
+}
+
+/// Build a request in the browser, `None` if the toolchain is not deployed. The
+/// module gets the request as JSON and resolves to `{ elf, bin, log }`, the ELF file,
+/// its flat image from the start of the flash and the lines printed by the build;
+/// it rejects with the output of the build when it fails.
+pub async fn compile(
+    request: &CompilationRequest,
+    build_log: &Rc<RefCell<Vec<String>>>,
+) -> Option<Result<LocalBuild, String>> {
+    let compile = compiler().await?;
+    build_log
+        .borrow_mut()
+        .push(String::from("Compiling in the browser"));
+
+    Some(build(&compile, request, build_log).await)
+}
+
+async fn build(
+    compile: &Function,
+    request: &CompilationRequest,
+    build_log: &Rc<RefCell<Vec<String>>>,
+) -> Result<LocalBuild, String> {
+    let request = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    let request = JSON::parse(&request).map_err(js_error)?;
+
+    let promise: Promise = compile
+        .call1(&JsValue::NULL, &request)
+        .and_then(|promise| promise.dyn_into())
+        .map_err(js_error)?;
+
+    let output = JsFuture::from(promise).await.map_err(js_error)?;
+
+    let log = Reflect::get(&output, &"log".into())
+        .ok()
+        .and_then(|log| log.as_string())
+        .unwrap_or_default();
+    build_log.borrow_mut().extend(log.lines().map(String::from));
+
+    let bytes = |name: &str| {
+        Reflect::get(&output, &name.into())
+            .ok()
+            .and_then(|bytes| bytes.dyn_into::<Uint8Array>().ok())
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| format!("The compiler did not give the {name} file"))
+    };
+
+    let elf = bytes("elf")?;
+    let image = bytes("bin")?;
+
+    if image.len() > FLASH_SIZE {
+        return Err(String::from("The program does not fit in the flash"));
+    }
+
+    let parsed = Elf::parse(&elf).map_err(|e| e.to_string())?;
+
+    Ok(LocalBuild {
+        uf2: uf2::write_uf2(&image, Bus::XIP, FamilyId::Rp2350RiscV),
+        disassembler: listing(&parsed),
+        symbols: symbols(&parsed),
+        elf,
+    })
+}
+
+/// Disassembly of the code sections, in the format of objdump
+fn listing(elf: &Elf) -> String {
+    let labels: BTreeMap<u32, String> = elf
+        .symbols
+        .iter()
+        .filter(|symbol| symbol.kind == SymbolKind::Function)
+        .map(|symbol| (symbol.address, symbol.name.clone()))
+        .collect();
+
+    elf.sections
+        .iter()
+        .filter(|section| section.name.starts_with(".text") || section.name.starts_with(".init"))
+        .filter_map(|section| {
+            let data = elf.section_data(&section.name)?;
+            Some(disasm::listing(data, section.address, &labels))
+        })
+        .collect()
+}
+
+fn symbols(elf: &Elf) -> Vec<ElfSymbol> {
+    let mut symbols: Vec<ElfSymbol> = elf
+        .symbols
+        .iter()
+        .map(|symbol| ElfSymbol {
+            name: symbol.name.clone(),
+            address: symbol.address,
+            size: symbol.size,
+            kind: match symbol.kind {
+                SymbolKind::Function => ElfSymbolKind::Function,
+                SymbolKind::Object => ElfSymbolKind::Object,
+                SymbolKind::Other => ElfSymbolKind::Other,
+            },
+        })
+        .collect();
+
+    symbols.sort_by_key(|symbol| symbol.address);
+    symbols
+}
+
+fn js_error(why: JsValue) -> String {
+    match why.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => why.as_string().unwrap_or_else(|| format!("{why:?}")),
+    }
+}
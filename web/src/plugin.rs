@@ -0,0 +1,199 @@
+/**
+ * @file plugin.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Hosting the peripheral plugins in the WebAssembly runtime of the browser
+ */
+use js_sys::{Array, Function, Object, Reflect, Uint8Array, WebAssembly};
+use rp2350::peripherals::plugin::{export, import, PluginIo};
+use rp2350::peripherals::{PluginError, PluginInfo, PluginModule};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+pub struct WasmPlugin {
+    read: Function,
+    write: Function,
+    tick: Option<Function>,
+    reset: Option<Function>,
+    /// The chip as seen by the imports, only valid during a call
+    io: Rc<RefCell<PluginIo>>,
+}
+
+/// Compile and instantiate a module, then ask it where its registers are
+pub async fn instantiate(
+    name: &str,
+    bytes: &[u8],
+) -> Result<(PluginInfo, WasmPlugin), PluginError> {
+    let io = Rc::new(RefCell::new(PluginIo::default()));
+    let memory = Rc::new(RefCell::new(None));
+    let imports = imports(name, &io, &memory).map_err(runtime_error)?;
+
+    let result = JsFuture::from(WebAssembly::instantiate_buffer(bytes, &imports))
+        .await
+        .map_err(runtime_error)?;
+
+    let instance: WebAssembly::Instance = Reflect::get(&result, &"instance".into())
+        .and_then(|instance| instance.dyn_into())
+        .map_err(runtime_error)?;
+
+    let exports = instance.exports();
+
+    *memory.borrow_mut() = Reflect::get(&exports, &export::MEMORY.into())
+        .ok()
+        .and_then(|memory| memory.dyn_into::<WebAssembly::Memory>().ok());
+
+    let function = |name: &str| {
+        Reflect::get(&exports, &name.into())
+            .ok()
+            .and_then(|function| function.dyn_into::<Function>().ok())
+    };
+
+    let required =
+        |name: &str| function(name).ok_or_else(|| PluginError::MissingExport(name.to_string()));
+
+    let number = |name: &str| {
+        let value = required(name)?
+            .call0(&JsValue::NULL)
+            .map_err(runtime_error)?;
+
+        Ok::<_, PluginError>(value.as_f64().unwrap_or_default() as i32)
+    };
+
+    let irq = match function(export::IRQ) {
+        // a negative number means no interrupt
+        Some(_) => u8::try_from(number(export::IRQ)?).ok(),
+        None => None,
+    };
+
+    let info = PluginInfo {
+        name: name.to_string(),
+        version: number(export::ABI_VERSION)? as u32,
+        base: number(export::BASE)? as u32,
+        size: number(export::SIZE)? as u32,
+        irq,
+    };
+
+    let plugin = WasmPlugin {
+        read: required(export::READ)?,
+        write: required(export::WRITE)?,
+        tick: function(export::TICK),
+        reset: function(export::RESET),
+        io,
+    };
+
+    Ok((info, plugin))
+}
+
+/// The `pico2` module imported by the plugins
+fn imports(
+    name: &str,
+    io: &Rc<RefCell<PluginIo>>,
+    memory: &Rc<RefCell<Option<WebAssembly::Memory>>>,
+) -> Result<Object, JsValue> {
+    let pico2 = Object::new();
+
+    let set_irq = {
+        let io = Rc::clone(io);
+        Closure::<dyn FnMut(i32)>::new(move |level: i32| io.borrow_mut().set_irq(level != 0))
+    };
+
+    let gpio_get = {
+        let io = Rc::clone(io);
+        Closure::<dyn FnMut(i32) -> i32>::new(move |pin: i32| {
+            u8::try_from(pin).is_ok_and(|pin| io.borrow().gpio_get(pin)) as i32
+        })
+    };
+
+    let gpio_set = {
+        let io = Rc::clone(io);
+        Closure::<dyn FnMut(i32, i32)>::new(move |pin: i32, level: i32| {
+            if let Ok(pin) = u8::try_from(pin) {
+                io.borrow_mut().gpio_set(pin, level != 0);
+            }
+        })
+    };
+
+    let ticks = {
+        let io = Rc::clone(io);
+        Closure::<dyn FnMut() -> i64>::new(move || io.borrow().ticks as i64)
+    };
+
+    let log = {
+        let name = name.to_string();
+        let memory = Rc::clone(memory);
+        Closure::<dyn FnMut(u32, u32)>::new(move |ptr: u32, len: u32| {
+            let Some(memory) = memory.borrow().clone() else {
+                return;
+            };
+
+            let bytes = Uint8Array::new(&memory.buffer())
+                .subarray(ptr, ptr.saturating_add(len))
+                .to_vec();
+
+            log::info!("[{}] {}", name, String::from_utf8_lossy(&bytes));
+        })
+    };
+
+    Reflect::set(&pico2, &import::SET_IRQ.into(), &set_irq.into_js_value())?;
+    Reflect::set(&pico2, &import::GPIO_GET.into(), &gpio_get.into_js_value())?;
+    Reflect::set(&pico2, &import::GPIO_SET.into(), &gpio_set.into_js_value())?;
+    Reflect::set(&pico2, &import::TICKS.into(), &ticks.into_js_value())?;
+    Reflect::set(&pico2, &import::LOG.into(), &log.into_js_value())?;
+
+    let imports = Object::new();
+    Reflect::set(&imports, &import::MODULE.into(), &pico2)?;
+    Ok(imports)
+}
+
+fn runtime_error(why: JsValue) -> PluginError {
+    let message = match why.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => format!("{:?}", why),
+    };
+
+    PluginError::Runtime(message)
+}
+
+impl WasmPlugin {
+    fn call(
+        &self,
+        function: &Function,
+        args: &[JsValue],
+        io: &mut PluginIo,
+    ) -> Result<JsValue, PluginError> {
+        *self.io.borrow_mut() = std::mem::take(io);
+        let result = function.apply(&JsValue::NULL, &args.iter().collect::<Array>());
+        *io = std::mem::take(&mut *self.io.borrow_mut());
+
+        result.map_err(runtime_error)
+    }
+}
+
+impl PluginModule for WasmPlugin {
+    fn read(&mut self, offset: u16, io: &mut PluginIo) -> Result<u32, PluginError> {
+        let value = self.call(&self.read, &[offset.into()], io)?;
+        Ok(value.as_f64().unwrap_or_default() as i32 as u32)
+    }
+
+    fn write(&mut self, offset: u16, value: u32, io: &mut PluginIo) -> Result<(), PluginError> {
+        self.call(&self.write, &[offset.into(), (value as i32).into()], io)
+            .map(|_| ())
+    }
+
+    fn tick(&mut self, cycles: u32, io: &mut PluginIo) -> Result<(), PluginError> {
+        match &self.tick {
+            Some(tick) => self.call(tick, &[cycles.into()], io).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        match &self.reset {
+            Some(reset) => self.call(reset, &[], &mut PluginIo::default()).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
@@ -4,11 +4,16 @@
  * @date 04/05/2025
  * @brief Handling of simulator tasks
  */
+use crate::Tracker;
 use crate::app::disassembler::Disassembler;
-use api_types::{CompilationResponse, Language};
+use crate::app::snapshots::SnapshotLibrary;
+use crate::app::waveform::WaveformCapture;
+use api_types::{CompilationRequest, CompilationResponse, ElfSymbol, Target, TestSpec};
 use egui::Context;
 use futures::channel::mpsc::{channel, Receiver, Sender};
 use futures::stream::StreamExt;
+use rp2350::common::ArchitectureType;
+use rp2350::elf::{DebugInfo, Elf, ElfError};
 use rp2350::simulator::Pico2;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -23,7 +28,102 @@ pub enum TaskCommand {
     Pause,
     Step,
     Stop,
-    FlashCode(Language, String, ShoulSkipBootrom, Rc<RefCell<bool>>),
+    /// Pulse the RUN pin, unlike `Stop` the SRAM and watchdog scratch survive
+    ResetRun,
+    /// The request, then where the lines of the build go while it is followed
+    FlashCode(
+        CompilationRequest,
+        Rc<RefCell<Vec<String>>>,
+        ShoulSkipBootrom,
+        Rc<RefCell<bool>>,
+    ),
+}
+
+/// Simulated cycles between two checks of the host clock
+const PACE_TICKS: u64 = 1000;
+/// Seconds the simulation may fall behind the host before it stops catching up
+const MAX_LAG: f64 = 0.1;
+
+/// How fast the simulated time goes compared to the time of the host
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum SimulationSpeed {
+    /// As many cycles as the host can run
+    #[default]
+    Unlimited,
+    /// A simulated second of clk_sys in a second of the host, a blinky blinks at its real rate
+    RealTime,
+    /// The simulated time goes this many times as fast as the host
+    Scaled(f64),
+}
+
+impl SimulationSpeed {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimulationSpeed::Unlimited => "Unlimited",
+            SimulationSpeed::RealTime => "Real time",
+            SimulationSpeed::Scaled(_) => "Scaled",
+        }
+    }
+
+    /// Simulated seconds in a second of the host, none when unlimited
+    fn ratio(&self) -> Option<f64> {
+        match self {
+            SimulationSpeed::Unlimited => None,
+            SimulationSpeed::RealTime => Some(1.0),
+            SimulationSpeed::Scaled(ratio) => Some(ratio.max(1e-6)),
+        }
+    }
+}
+
+/// Keeps the simulated time in step with the host clock for a speed
+#[derive(Default)]
+struct Pacer {
+    speed: SimulationSpeed,
+    /// Time of the host in ms when the pace was set
+    started: f64,
+    /// Simulated seconds since then, up to the last check
+    simulated: f64,
+    /// Ticks of the clock at the last check, a skipped sleep moves it by more than one
+    checked: u64,
+}
+
+impl Pacer {
+    fn restart(&mut self, speed: SimulationSpeed, ticks: u64) {
+        *self = Self {
+            speed,
+            started: js_sys::Date::now(),
+            simulated: 0.0,
+            checked: ticks,
+        };
+    }
+
+    /// Count the ticks of clk_sys up to `ticks`, gives the ms to wait for while the
+    /// simulation is ahead
+    fn step(&mut self, speed: SimulationSpeed, ticks: u64, clk_sys: u64) -> Option<u32> {
+        let ratio = speed.ratio()?;
+
+        // a power cycle starts the clock again
+        if speed != self.speed || ticks < self.checked {
+            self.restart(speed, ticks);
+        }
+
+        if ticks - self.checked < PACE_TICKS {
+            return None;
+        }
+
+        self.simulated += (ticks - self.checked) as f64 / clk_sys.max(1) as f64;
+        self.checked = ticks;
+        let host = (js_sys::Date::now() - self.started) / 1000.0;
+        let ahead = self.simulated / ratio - host;
+
+        // too slow for the speed, runs as fast as it can without a burst to catch up
+        if ahead < -MAX_LAG {
+            self.restart(speed, ticks);
+            return None;
+        }
+
+        (ahead >= 0.001).then(|| (ahead * 1000.0) as u32)
+    }
 }
 
 pub fn pick_file_into_pico2(
@@ -51,6 +151,16 @@ pub fn pick_file_into_pico2(
             } else {
                 crate::notify::success("Flashed bin file successfully");
             }
+        } else if file_name.ends_with(".hex") || file_name.ends_with(".ihex") {
+            let file = file.read().await;
+            let result = String::from_utf8(file)
+                .map_err(|_| "the file is not a text file".to_string())
+                .and_then(|hex| pico2.load_hex(&hex).map_err(|why| why.to_string()));
+
+            match result {
+                Ok(_) => crate::notify::success("Loaded hex file successfully"),
+                Err(why) => crate::notify::error(format!("Failed to load hex file: {}", why)),
+            }
         } else if file_name.ends_with(".uf2") {
             let file = file.read().await;
             if let Err(why) = pico2.flash_uf2(&file) {
@@ -81,6 +191,76 @@ pub fn pick_file_into_pico2(
 struct CompilationResult {
     uf2: Vec<u8>,
     disassembler: String,
+    elf: Vec<u8>,
+    map: String,
+    symbols: Vec<ElfSymbol>,
+}
+
+/// Load a file into the memories, an Intel HEX image if `address` is `None`,
+/// otherwise a raw dump written at `address`
+pub fn pick_file_into_memory(ctx: Context, pico2: Rc<RefCell<Pico2>>, address: Option<u32>) {
+    let file_picker = match address {
+        None => rfd::AsyncFileDialog::new().add_filter("Intel HEX", &["hex", "ihex"]),
+        Some(_) => rfd::AsyncFileDialog::new(),
+    };
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.pick_file().await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        let data = file.read().await;
+        let mut pico2 = pico2.borrow_mut();
+
+        let result = match address {
+            None => String::from_utf8(data)
+                .map_err(|_| "the file is not a text file".to_string())
+                .and_then(|hex| pico2.load_hex(&hex).map(|_| ()).map_err(|why| why.to_string())),
+            Some(address) => pico2
+                .load_bin_at(address, &data)
+                .map_err(|why| why.to_string()),
+        };
+
+        match result {
+            Ok(_) => crate::notify::success(format!("Loaded {}", file.file_name())),
+            Err(why) => crate::notify::error(format!("Failed to load {}: {}", file.file_name(), why)),
+        }
+
+        drop(pico2);
+        ctx.request_repaint();
+    })
+}
+
+/// Instantiate a peripheral plugin and map it into the chip
+pub fn pick_plugin_file(ctx: Context, pico2: Rc<RefCell<Pico2>>) {
+    let file_picker = rfd::AsyncFileDialog::new().add_filter("WebAssembly", &["wasm"]);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.pick_file().await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        let file_name = file.file_name();
+        let name = file_name.trim_end_matches(".wasm");
+
+        let result = match crate::plugin::instantiate(name, &file.read().await).await {
+            Ok((info, module)) => pico2.borrow_mut().mcu.load_plugin(info, Box::new(module)),
+            Err(why) => Err(why),
+        };
+
+        match result {
+            Ok(plugin) => crate::notify::success(format!(
+                "Mapped {} at {:#010x}",
+                file_name,
+                plugin.borrow().info.base
+            )),
+            Err(why) => crate::notify::error(format!("Failed to load {}: {}", file_name, why)),
+        }
+
+        ctx.request_repaint();
+    })
 }
 
 pub fn export_file() {
@@ -107,46 +287,275 @@ pub fn export_file() {
     });
 }
 
-async fn compile_source_code(lang: Language, code: &str) -> Result<CompilationResult, String> {
-    // The code maybe in a cache, so it may complete immediately
-    let id = match crate::api::compile(lang, code).await? {
-        CompilationResponse::InProgress { id } => id,
-        CompilationResponse::Done { uf2, disassembler } => {
-            return Ok(CompilationResult { uf2, disassembler })
+/// Save a generated text file on the host
+pub fn export_text_file(file_name: &str, text: String) {
+    let file_picker = rfd::AsyncFileDialog::new()
+        .set_file_name(file_name)
+        .add_filter("Text", &["txt", "dis", "lst"])
+        .save_file();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        if let Err(why) = file.write(text.as_bytes()).await {
+            crate::notify::error(format!("Failed to write to file: {}", why));
+        } else {
+            crate::notify::success(format!("Exported {}", file.file_name()));
         }
-        CompilationResponse::Error { message } => return Err(message),
-    };
+    });
+}
+
+/// Save a waveform recording on the host
+pub fn export_vcd_file(vcd: String) {
+    let file_picker = rfd::AsyncFileDialog::new()
+        .set_file_name("waveform.vcd")
+        .add_filter("Value Change Dump", &["vcd"])
+        .save_file();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        if let Err(why) = file.write(vcd.as_bytes()).await {
+            crate::notify::error(format!("Failed to write to file: {}", why));
+        } else {
+            crate::notify::success(format!("Exported {}", file.file_name()));
+        }
+    });
+}
+
+/// Save an instruction trace on the host, `extension` is `csv` or `json`
+pub fn export_trace_file(extension: &str, text: String) {
+    let file_picker = rfd::AsyncFileDialog::new()
+        .set_file_name(format!("trace.{extension}"))
+        .add_filter("Instruction trace", &[extension])
+        .save_file();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        if let Err(why) = file.write(text.as_bytes()).await {
+            crate::notify::error(format!("Failed to write to file: {}", why));
+        } else {
+            crate::notify::success(format!("Exported {}", file.file_name()));
+        }
+    });
+}
+
+/// Save the memory of a virtual SD card or flash on the host
+pub fn export_disk_image(file_name: &str, image: Vec<u8>) {
+    let file_picker = rfd::AsyncFileDialog::new()
+        .set_file_name(file_name)
+        .add_filter("Disk image", &["img", "bin"])
+        .save_file();
 
-    loop {
-        // Check the status of the compilation
-        let status_request = crate::api::compilation_result(&id).await?;
-        match status_request {
-            CompilationResponse::Done { uf2, disassembler } => {
-                log::info!("Compilation done");
-                return Ok(CompilationResult { uf2, disassembler });
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        if let Err(why) = file.write(&image).await {
+            crate::notify::error(format!("Failed to write to file: {}", why));
+        } else {
+            crate::notify::success(format!("Exported {}", file.file_name()));
+        }
+    });
+}
+
+/// Save the test spec of the project as JSON on the host
+pub fn export_test_spec(spec: &TestSpec) {
+    let text = spec.to_json();
+    let file_picker = rfd::AsyncFileDialog::new()
+        .set_file_name("test_spec.json")
+        .add_filter("Test spec", &["json"])
+        .save_file();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        if let Err(why) = file.write(text.as_bytes()).await {
+            crate::notify::error(format!("Failed to write to file: {}", why));
+        } else {
+            crate::notify::success(format!("Exported {}", file.file_name()));
+        }
+    });
+}
+
+/// Load a test spec, it is applied by its window on the next frame
+pub fn pick_test_spec(ctx: Context, imported: Rc<RefCell<Option<TestSpec>>>) {
+    let file_picker = rfd::AsyncFileDialog::new().add_filter("Test spec", &["json"]);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.pick_file().await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        let result = String::from_utf8(file.read().await)
+            .map_err(|_| "the file is not a text file".to_string())
+            .and_then(|text| TestSpec::from_json(&text).map_err(|why| why.to_string()));
+
+        match result {
+            Ok(spec) => {
+                *imported.borrow_mut() = Some(spec);
+                crate::notify::success(format!("Loaded {}", file.file_name()));
             }
-            CompilationResponse::Error { message } => {
-                log::error!("Compilation error: {}", message);
-                return Err(message);
+            Err(why) => crate::notify::error(format!("Failed to load {}: {}", file.file_name(), why)),
+        }
+
+        ctx.request_repaint();
+    })
+}
+
+/// Load a disk image for a virtual SD card, it is inserted by its window on the next frame
+pub fn pick_sd_image(ctx: Context, picked: Rc<RefCell<Option<Vec<u8>>>>) {
+    let file_picker = rfd::AsyncFileDialog::new().add_filter("Disk image", &["img", "bin"]);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.pick_file().await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        *picked.borrow_mut() = Some(file.read().await);
+        crate::notify::success(format!("Loaded {}", file.file_name()));
+        ctx.request_repaint();
+    })
+}
+
+/// Replace the debug info of the variables window, it is cleared if the file has none
+fn load_debug_info(debug_info: &RefCell<Option<DebugInfo>>, elf: &[u8]) -> Result<usize, ElfError> {
+    let result = Elf::parse(elf).and_then(|elf| elf.debug_info());
+    let count = result.as_ref().map(|info| info.variables.len());
+    *debug_info.borrow_mut() = result.ok();
+    count
+}
+
+/// Load the debug info of a program flashed from a file
+pub fn pick_elf_file(ctx: Context, debug_info: Rc<RefCell<Option<DebugInfo>>>) {
+    let file_picker = rfd::AsyncFileDialog::new().add_filter("ELF", &["elf"]);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = file_picker.pick_file().await else {
+            crate::notify::warning("No file selected");
+            return;
+        };
+
+        match load_debug_info(&debug_info, &file.read().await) {
+            Ok(count) => crate::notify::success(format!(
+                "Loaded {} variables from {}",
+                count,
+                file.file_name()
+            )),
+            Err(why) => {
+                crate::notify::error(format!("Failed to load {}: {}", file.file_name(), why))
+            }
+        }
+
+        ctx.request_repaint();
+    })
+}
+
+async fn compile_source_code(
+    request: &CompilationRequest,
+    build_log: &Rc<RefCell<Vec<String>>>,
+) -> Result<CompilationResult, String> {
+    // the server is only asked when the browser cannot build the code itself
+    let mut local_error = None;
+
+    if crate::local_compiler::supports(request) {
+        match crate::local_compiler::compile(request, build_log).await {
+            Some(Ok(build)) => {
+                return Ok(CompilationResult {
+                    uf2: build.uf2,
+                    disassembler: build.disassembler,
+                    elf: build.elf,
+                    map: String::new(),
+                    symbols: build.symbols,
+                });
+            }
+            Some(Err(why)) => {
+                let mut log = build_log.borrow_mut();
+                log.extend(why.lines().map(String::from));
+                log.push(String::from("Building on the server instead"));
+                local_error = Some(why);
             }
-            CompilationResponse::InProgress { id } => {
-                log::info!("Compilation in progress: {}", id);
-                // Wait for a while before checking again
-                gloo::timers::future::sleep(std::time::Duration::from_secs(1)).await;
+            None => {}
+        }
+    }
+
+    // offline, the error of the build in the browser tells more than the request
+    let response = match crate::api::compile(request).await {
+        Ok(response) => response,
+        Err(why) => return Err(local_error.unwrap_or(why)),
+    };
+
+    // The code maybe in a cache, so it may complete immediately
+    let response = match response {
+        CompilationResponse::InProgress { id, position } => {
+            log::info!("Compilation in progress: {}", id);
+
+            if position > 0 {
+                let line = format!("Queued at position {position} on the server");
+                build_log.borrow_mut().push(line);
             }
+
+            crate::api::compilation_events(&id, build_log).await?
+        }
+        response => response,
+    };
+
+    match response {
+        CompilationResponse::Done {
+            uf2,
+            disassembler,
+            elf,
+            map,
+            symbols,
+        } => {
+            log::info!("Compilation done");
+            Ok(CompilationResult {
+                uf2,
+                disassembler,
+                elf,
+                map,
+                symbols,
+            })
+        }
+        CompilationResponse::Error { message } => {
+            log::error!("Compilation error: {}", message);
+            Err(message)
+        }
+        CompilationResponse::InProgress { id, .. } => {
+            Err(format!("Compilation {id} did not finish"))
         }
     }
 }
 
 async fn flash_code(
     pico2: Rc<RefCell<Pico2>>,
-    lang: Language,
-    code: &str,
+    request: &CompilationRequest,
+    build_log: &Rc<RefCell<Vec<String>>>,
     skip_bootrom: bool,
     disassembler: &Rc<RefCell<Disassembler>>,
+    debug_info: &Rc<RefCell<Option<DebugInfo>>>,
 ) {
     // TODO add a loading spinner
-    let res = match compile_source_code(lang, code).await {
+    build_log.borrow_mut().clear();
+
+    let res = match compile_source_code(request, build_log).await {
         Ok(res) => res,
         Err(err) => {
             crate::notify::error(format!("Failed to compile code: {}", err));
@@ -155,6 +564,8 @@ async fn flash_code(
     };
 
     let mut mcu = pico2.borrow_mut();
+    boot_target(&mut mcu, request.target);
+
     if let Err(why) = mcu.flash_uf2(&res.uf2) {
         crate::notify::error(format!("Failed to flash uf2 file: {}", why));
         return;
@@ -167,6 +578,13 @@ async fn flash_code(
     {
         let mut disassembler = disassembler.borrow_mut();
         disassembler.update_file(&res.disassembler);
+        // empty from an older server, the listing has the labels anyway
+        disassembler.update_artifacts(res.symbols, res.map);
+    }
+
+    // an older server does not send the ELF file
+    if let Err(why) = load_debug_info(debug_info, &res.elf) {
+        log::warn!("No debug info for the variables: {}", why);
     }
 
     crate::notify::success("Code flashed successfully");
@@ -176,29 +594,80 @@ pub fn run_pico2_sim(
     ctx: Context,
     pico2: Rc<RefCell<Pico2>>,
     is_running: Rc<RefCell<bool>>,
+    speed: Rc<RefCell<SimulationSpeed>>,
     disassembler: Rc<RefCell<Disassembler>>,
+    snapshots: Rc<RefCell<SnapshotLibrary>>,
+    waveform: Rc<RefCell<WaveformCapture>>,
+    tracker: Rc<Tracker>,
+    debug_info: Rc<RefCell<Option<DebugInfo>>>,
 ) -> Sender<TaskCommand> {
     let (tx, mut rx): (Sender<TaskCommand>, Receiver<TaskCommand>) = channel(4);
 
     wasm_bindgen_futures::spawn_local(async move {
         let mut request_repaint = 5;
         let mut skipped_bootrom = false;
+        let mut flashed_target = Target::RiscV;
+        let mut last_pcs = [0u32; 2];
+        let mut pacer = Pacer::default();
 
         loop {
             if *is_running.borrow() {
                 request_repaint -= 1;
 
-                {
+                let wait = {
                     let mut pico2 = pico2.borrow_mut();
                     pico2.step();
-                    let pc0 = pico2.processor[0].get_pc();
-                    let pc1 = pico2.processor[1].get_pc();
-                    drop(pico2);
+                    snapshots.borrow_mut().rewind.record(&mut pico2);
+                    waveform.borrow().sample(&pico2);
+                    tracker.borrow_mut().sample(&pico2);
+                    let pcs = [pico2.processor[0].get_pc(), pico2.processor[1].get_pc()];
                     let disassembler = disassembler.borrow();
-                    if disassembler.has_breakpoint(&pc0) || disassembler.has_breakpoint(&pc1) {
-                        drop(disassembler);
+
+                    for (core, pc) in pcs.iter().enumerate() {
+                        // Only trigger once when the core arrives at the breakpoint,
+                        // not on every cycle it is stalled there
+                        if *pc == last_pcs[core] {
+                            continue;
+                        }
+
+                        let Some(action) = disassembler.breakpoint(pc) else {
+                            continue;
+                        };
+
+                        if action.should_snapshot() {
+                            let name = format!("Core {} at {:#010x}", core, pc);
+                            snapshots.borrow_mut().push(name, pico2.snapshot());
+                        }
+
+                        if action.should_pause() {
+                            *is_running.borrow_mut() = false;
+                        }
+                    }
+
+                    last_pcs = pcs;
+
+                    // a hardware breakpoint or watchpoint halted a core
+                    if pico2.processor.iter().any(|core| core.is_halted()) {
                         *is_running.borrow_mut() = false;
                     }
+
+                    // a load or a store of the cores or the DMA hit a memory breakpoint
+                    if let Some(hit) = pico2.bus.take_breakpoint_hit() {
+                        *is_running.borrow_mut() = false;
+                        let access = if hit.write { "Write" } else { "Read" };
+                        crate::notify::info(format!(
+                            "{access} of {:?} at {:#010x} hit breakpoint {}",
+                            hit.requestor, hit.address, hit.id
+                        ));
+                    }
+
+                    let ticks = *pico2.clock.ticks.borrow();
+                    pacer.step(*speed.borrow(), ticks, pico2.clock.clk_sys())
+                };
+
+                if let Some(wait) = wait {
+                    ctx.request_repaint();
+                    gloo::timers::future::TimeoutFuture::new(wait).await;
                 }
 
                 if request_repaint == 0 {
@@ -212,37 +681,77 @@ pub fn run_pico2_sim(
                 match rx.try_next() {
                     Ok(Some(TaskCommand::Stop)) => {
                         *is_running.borrow_mut() = false;
-                        pico2.borrow_mut().reset();
+                        snapshots.borrow_mut().rewind.clear();
+                        pico2.borrow_mut().power_cycle();
+                        boot_target(&mut pico2.borrow_mut(), flashed_target);
                         if skipped_bootrom {
                             pico2.borrow_mut().skip_bootrom();
                         }
                     }
+                    Ok(Some(TaskCommand::ResetRun)) => pico2.borrow_mut().external_reset(),
                     Ok(Some(TaskCommand::Pause)) => *is_running.borrow_mut() = false,
-                    Ok(Some(TaskCommand::FlashCode(language, code, skip_bootrom, is_flashing))) => {
+                    Ok(Some(TaskCommand::FlashCode(
+                        request,
+                        build_log,
+                        skip_bootrom,
+                        is_flashing,
+                    ))) => {
                         *is_running.borrow_mut() = false;
                         *is_flashing.borrow_mut() = true;
+                        snapshots.borrow_mut().rewind.clear();
                         skipped_bootrom = skip_bootrom;
-                        flash_code(pico2.clone(), language, &code, skip_bootrom, &disassembler)
-                            .await;
+                        flashed_target = request.target;
+                        flash_code(
+                            pico2.clone(),
+                            &request,
+                            &build_log,
+                            skip_bootrom,
+                            &disassembler,
+                            &debug_info,
+                        )
+                        .await;
                         *is_flashing.borrow_mut() = false;
                     }
                     _ => {}
                 }
             } else {
                 match rx.next().await {
-                    Some(TaskCommand::Run) => *is_running.borrow_mut() = true,
-                    Some(TaskCommand::Step) => pico2.borrow_mut().step(),
+                    Some(TaskCommand::Run) => {
+                        resume_halted_cores(&mut pico2.borrow_mut());
+                        pacer.restart(*speed.borrow(), *pico2.borrow().clock.ticks.borrow());
+                        *is_running.borrow_mut() = true;
+                    }
+                    Some(TaskCommand::Step) => {
+                        let mut pico2 = pico2.borrow_mut();
+                        resume_halted_cores(&mut pico2);
+                        pico2.step();
+                        snapshots.borrow_mut().rewind.record(&mut pico2);
+                        waveform.borrow().sample(&pico2);
+                        tracker.borrow_mut().sample(&pico2);
+                    }
                     Some(TaskCommand::Stop) => {
-                        pico2.borrow_mut().reset();
+                        snapshots.borrow_mut().rewind.clear();
+                        pico2.borrow_mut().power_cycle();
+                        boot_target(&mut pico2.borrow_mut(), flashed_target);
                         if skipped_bootrom {
                             pico2.borrow_mut().skip_bootrom();
                         }
                     }
+                    Some(TaskCommand::ResetRun) => pico2.borrow_mut().external_reset(),
                     Some(TaskCommand::Pause) => *is_running.borrow_mut() = false,
-                    Some(TaskCommand::FlashCode(language, code, skip_bootrom, is_flashing)) => {
+                    Some(TaskCommand::FlashCode(request, build_log, skip_bootrom, is_flashing)) => {
                         *is_flashing.borrow_mut() = true;
-                        flash_code(pico2.clone(), language, &code, skip_bootrom, &disassembler)
-                            .await;
+                        snapshots.borrow_mut().rewind.clear();
+                        flashed_target = request.target;
+                        flash_code(
+                            pico2.clone(),
+                            &request,
+                            &build_log,
+                            skip_bootrom,
+                            &disassembler,
+                            &debug_info,
+                        )
+                        .await;
                         *is_flashing.borrow_mut() = false;
                     }
                     None => {}
@@ -254,6 +763,25 @@ pub fn run_pico2_sim(
     tx
 }
 
+/// Boot the cores of the target the code is built for, ARCHSEL is only sampled by a reset
+fn boot_target(pico2: &mut Pico2, target: Target) {
+    let architecture = match target {
+        Target::RiscV => ArchitectureType::Hazard3,
+        Target::Arm => ArchitectureType::CortexM33,
+    };
+
+    if pico2.processor[0].architecture() != architecture {
+        pico2.select_architecture(architecture);
+        pico2.reset();
+    }
+}
+
+fn resume_halted_cores(pico2: &mut Pico2) {
+    pico2.processor.iter_mut().for_each(|core| core.resume());
+    // a hit while stepping is not a reason to pause right after resuming
+    pico2.bus.take_breakpoint_hit();
+}
+
 fn yield_now() -> impl Future<Output = ()> {
     gloo::timers::future::TimeoutFuture::new(0)
 }
@@ -5,7 +5,13 @@ use rp2350::common::{DataSize, Requestor};
  * @date 04/05/2025
  * @brief Tracker module for the simulator
  */
+use rp2350::Rp2350;
+use rp2350::common::MHZ;
+use rp2350::coverage::Coverage;
+use rp2350::gpio::NUM_GPIO;
 use rp2350::inspector::*;
+use rp2350::profiler::Profiler;
+use rp2350::trace::instructions::InstructionTrace;
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
 use std::ops::Deref;
@@ -33,12 +39,25 @@ pub struct ProcessorTracker {
     pub instruction_count: HashMap<&'static str, u64>,
     pub instruction_log: VecDeque<Instruction>,
     pub ticks: u64,
+    /// Delay loops fast-forwarded and the cycles they would have taken
+    pub skipped_waits: u64,
+    pub skipped_cycles: u64,
+    /// Cycles fast-forwarded while both cores slept
+    pub idle_cycles: u64,
 }
 
 pub struct UartTracker {
     pub tx: VecDeque<u8>,
     pub rx: VecDeque<u16>,
     pub max_buffer_size: usize,
+    /// Bytes transmitted since the start, the buffer only keeps the last ones
+    pub tx_count: u64,
+}
+
+pub struct UsbTracker {
+    pub tx: VecDeque<u8>,
+    pub rx: VecDeque<u8>,
+    pub max_buffer_size: usize,
 }
 
 pub struct SpiTracker {
@@ -54,6 +73,17 @@ pub struct I2cTracker {
 }
 
 impl Default for UartTracker {
+    fn default() -> Self {
+        Self {
+            tx: VecDeque::new(),
+            rx: VecDeque::new(),
+            max_buffer_size: 4096, // Default size to 4096 bytes
+            tx_count: 0,
+        }
+    }
+}
+
+impl Default for UsbTracker {
     fn default() -> Self {
         Self {
             tx: VecDeque::new(),
@@ -111,14 +141,107 @@ impl Default for BusTracker {
     }
 }
 
+#[derive(Clone, Copy)]
+pub struct LogicSample {
+    pub time_us: u64,
+    /// Level of each GPIO, a bit per pin
+    pub levels: u64,
+}
+
+/// Levels of the GPIOs sampled every simulated microsecond, for the logic analyzer.
+/// A sample is kept only when it differs from the previous one
+pub struct LogicTracker {
+    /// Pins sampled, a bit per GPIO
+    pub pins: u64,
+    pub samples: VecDeque<LogicSample>,
+    /// Time of the last sample, the end of the capture
+    pub now_us: u64,
+    pub max_buffer_size: usize,
+}
+
+impl Default for LogicTracker {
+    fn default() -> Self {
+        Self {
+            // the LED of the Pico 2 and the UART0 pins of the pico-sdk
+            pins: 1 << 25 | 0b11,
+            samples: VecDeque::new(),
+            now_us: 0,
+            max_buffer_size: 100_000,
+        }
+    }
+}
+
+impl LogicTracker {
+    /// Take a sample once a microsecond passed since the last one, to call after every step
+    pub fn sample(&mut self, rp2350: &Rp2350) {
+        let ticks_per_us = (rp2350.clock.clk_sys() / MHZ).max(1);
+        let now_us = *rp2350.clock.ticks.borrow() / ticks_per_us;
+
+        if now_us == self.now_us && !self.samples.is_empty() {
+            return;
+        }
+
+        self.now_us = now_us;
+
+        let gpio = rp2350.gpio.borrow();
+        let levels = (0..NUM_GPIO as u8)
+            .filter(|pin| self.pins >> pin & 1 == 1)
+            .fold(0, |levels, pin| {
+                levels | (gpio.pad_level(pin) as u64) << pin
+            });
+
+        if self.samples.back().is_none_or(|last| last.levels != levels) {
+            let sample = LogicSample {
+                time_us: now_us,
+                levels,
+            };
+            push_to_buffer(&mut self.samples, sample, self.max_buffer_size);
+        }
+    }
+
+    /// Level of a pin at a time, `None` before the first sample
+    pub fn level_at(&self, pin: u8, time_us: u64) -> Option<bool> {
+        let index = self
+            .samples
+            .partition_point(|sample| sample.time_us <= time_us);
+        let sample = self.samples.get(index.checked_sub(1)?)?;
+        Some(sample.levels >> pin & 1 == 1)
+    }
+
+    /// Changes of a pin, as their time and whether it rose
+    pub fn edges(&self, pin: u8) -> impl Iterator<Item = (u64, bool)> + '_ {
+        self.samples
+            .iter()
+            .map(move |sample| (sample.time_us, sample.levels >> pin & 1 == 1))
+            .scan(None, |last, (time_us, level)| {
+                let edge = last.filter(|last| *last != level).map(|_| (time_us, level));
+                *last = Some(level);
+                Some(edge)
+            })
+            .flatten()
+    }
+}
+
 pub struct TrackerInner {
     pub processor: [ProcessorTracker; 2],
     pub uart: [UartTracker; 2],
+    pub usb: UsbTracker,
     pub spi: [SpiTracker; 2],
     pub i2c: [I2cTracker; 2],
     pub last_generated_trng: Option<u32>,
     pub nof_instruction_log: usize,
     pub bus: BusTracker,
+    pub logic: LogicTracker,
+    /// Stores seen in each watched region, by the id of the region
+    pub watched_writes: HashMap<u32, u64>,
+    /// Accesses which hit each memory breakpoint, by the id of the breakpoint
+    pub breakpoint_hits: HashMap<u32, u64>,
+    /// Recording of the Instruction Trace window, sampled with the logic analyzer
+    pub instruction_trace: Option<InstructionTrace>,
+    /// Recording of the Profiler window, dropped with a new program as its functions change
+    pub profiler: Option<Profiler>,
+    /// Recording of the Coverage window, kept with a new program to measure its tests
+    pub coverage: Option<Coverage>,
 }
 
 impl Default for TrackerInner {
@@ -126,21 +249,51 @@ impl Default for TrackerInner {
         Self {
             processor: Default::default(),
             uart: Default::default(),
+            usb: Default::default(),
             spi: Default::default(),
             i2c: Default::default(),
             bus: Default::default(),
+            logic: Default::default(),
+            watched_writes: Default::default(),
+            breakpoint_hits: Default::default(),
+            instruction_trace: None,
+            profiler: None,
+            coverage: None,
             last_generated_trng: None,
             nof_instruction_log: 50,
         }
     }
 }
 
+impl TrackerInner {
+    /// Sample what is compared from one tick to the next, to call after every step
+    pub fn sample(&mut self, rp2350: &Rp2350) {
+        self.logic.sample(rp2350);
+
+        if let Some(trace) = self.instruction_trace.as_ref() {
+            trace.sample(rp2350);
+        }
+    }
+}
+
 impl Inspector for Tracker {
     fn handle_event(&self, event: InspectionEvent) {
         // LoggerInspector.handle_event(event.clone());
 
         let mut inner = self.0.borrow_mut();
 
+        if let Some(trace) = inner.instruction_trace.as_ref() {
+            trace.handle_event(event.clone());
+        }
+
+        if let Some(profiler) = inner.profiler.as_ref() {
+            profiler.handle_event(event.clone());
+        }
+
+        if let Some(coverage) = inner.coverage.as_ref() {
+            coverage.handle_event(event.clone());
+        }
+
         // Handle the event
         match event {
             InspectionEvent::TrngGenerated(value) => {
@@ -166,9 +319,23 @@ impl Inspector for Tracker {
                 processor.inst_count += 1;
             }
 
+            InspectionEvent::BusyWaitSkipped { core, cycles, .. } => {
+                let processor = &mut inner.processor[core as usize];
+                processor.skipped_waits += 1;
+                processor.skipped_cycles += cycles;
+            }
+
+            InspectionEvent::IdleSkipped { cycles } => {
+                for processor in inner.processor.iter_mut() {
+                    processor.ticks += cycles;
+                    processor.idle_cycles += cycles;
+                }
+            }
+
             InspectionEvent::UartTx { uart_index, value } => {
                 let uart = &mut inner.uart[uart_index as usize];
                 push_to_buffer(&mut uart.tx, value, uart.max_buffer_size);
+                uart.tx_count += 1;
             }
 
             InspectionEvent::UartRx { uart_index, value } => {
@@ -176,9 +343,33 @@ impl Inspector for Tracker {
                 push_to_buffer(&mut uart.rx, value, uart.max_buffer_size);
             }
 
-            // reset the tracker
+            InspectionEvent::UsbCdcTx { data } => {
+                let usb = &mut inner.usb;
+                for byte in data {
+                    push_to_buffer(&mut usb.tx, byte, usb.max_buffer_size);
+                }
+            }
+
+            InspectionEvent::UsbCdcRx { data } => {
+                let usb = &mut inner.usb;
+                for byte in data {
+                    push_to_buffer(&mut usb.rx, byte, usb.max_buffer_size);
+                }
+            }
+
+            // reset the tracker, a running instruction trace starts over with the program
             InspectionEvent::FlashedBinary => {
+                let trace = inner.instruction_trace.take();
+                let coverage = inner.coverage.take();
                 core::mem::take(&mut *inner);
+
+                if let Some(trace) = trace {
+                    trace.clear();
+                    inner.instruction_trace = Some(trace);
+                }
+
+                // already cleared by the event
+                inner.coverage = coverage;
             }
 
             InspectionEvent::BusLoad {
@@ -211,6 +402,14 @@ impl Inspector for Tracker {
                 push_to_buffer(&mut bus.events, event, bus.max_buffer_size);
             }
 
+            InspectionEvent::WatchedWrite { id, .. } => {
+                *inner.watched_writes.entry(id).or_insert(0) += 1;
+            }
+
+            InspectionEvent::MemoryBreakpoint { id, .. } => {
+                *inner.breakpoint_hits.entry(id).or_insert(0) += 1;
+            }
+
             InspectionEvent::TickCore(idx) => {
                 let processor = &mut inner.processor[idx as usize];
                 processor.ticks += 1;
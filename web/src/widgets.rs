@@ -4,8 +4,15 @@
  * @date 31/03/2025
  * @brief Custom widgets for the simulator
  */
+pub mod clipboard;
 pub mod display_mode;
+pub mod logic_analyzer;
 pub mod memory_view;
+pub mod pipeline_diagram;
+pub mod terminal;
 
 pub use display_mode::*;
+pub use logic_analyzer::*;
 pub use memory_view::*;
+pub use pipeline_diagram::*;
+pub use terminal::*;
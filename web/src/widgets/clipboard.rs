@@ -0,0 +1,110 @@
+/**
+ * @file widgets/clipboard.rs
+ * @author Nguyen Le Duy
+ * @date 14/10/2026
+ * @brief Formatting and parsing of data moved through the host clipboard
+ */
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum CopyFormat {
+    #[default]
+    HexDump,
+    CArray,
+    RustArray,
+}
+
+impl CopyFormat {
+    pub const ALL: [Self; 3] = [Self::HexDump, Self::CArray, Self::RustArray];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::HexDump => "Hex dump",
+            Self::CArray => "C array",
+            Self::RustArray => "Rust array",
+        }
+    }
+
+    /// Format the bytes, `address` is only used for the offsets of the hex dump
+    pub fn format(&self, data: &[u8], address: u32) -> String {
+        match self {
+            Self::HexDump => data
+                .chunks(16)
+                .enumerate()
+                .map(|(i, row)| {
+                    let bytes = row
+                        .iter()
+                        .map(|v| format!("{:02X}", v))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+
+                    format!("{:08X}: {}", address as usize + i * 16, bytes)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+
+            Self::CArray => format!(
+                "const uint8_t data[{}] = {{\n{}\n}};",
+                data.len(),
+                array_body(data)
+            ),
+
+            Self::RustArray => format!(
+                "const DATA: [u8; {}] = [\n{}\n];",
+                data.len(),
+                array_body(data)
+            ),
+        }
+    }
+}
+
+fn array_body(data: &[u8]) -> String {
+    data.chunks(16)
+        .map(|row| {
+            let bytes = row
+                .iter()
+                .map(|v| format!("0x{:02x},", v))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            format!("    {}", bytes)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse bytes from any of the `CopyFormat` or from plain hex bytes separated by spaces
+pub fn parse_bytes(text: &str) -> Option<Vec<u8>> {
+    // Only the content of the array matters for C and Rust arrays
+    let text = match text.rfind('=') {
+        Some(index) => &text[index + 1..],
+        None => text,
+    };
+
+    let mut bytes = Vec::new();
+
+    for line in text.lines() {
+        // the offset of the hex dump
+        let line = match line.find(':') {
+            Some(index) => &line[index + 1..],
+            None => line,
+        };
+
+        let tokens = line
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | '{' | '}' | '[' | ']' | ';'))
+            .filter(|v| !v.is_empty());
+
+        for token in tokens {
+            let token = token.trim_start_matches("0x").trim_start_matches("0X");
+            let token = token.trim_end_matches("u8");
+            bytes.push(u8::from_str_radix(token, 16).ok()?);
+        }
+    }
+
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+/// Copy the text to the host clipboard, works on both web and native
+pub fn copy(ctx: &egui::Context, text: String) {
+    ctx.copy_text(text);
+    crate::notify::success("Copied to clipboard");
+}
@@ -0,0 +1,361 @@
+/**
+ * @file widgets/logic_analyzer.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Timing view of the GPIOs sampled by the tracker, with zoom, cursors and edge measurements
+ */
+use crate::tracker::LogicTracker;
+use egui::{Align2, Color32, FontId, Pos2, RichText, Sense, Shape, Stroke, Vec2};
+use rp2350::gpio::NUM_GPIO;
+
+const ROW_HEIGHT: f32 = 24.0;
+const LABEL_WIDTH: f32 = 56.0;
+const RULER_HEIGHT: f32 = 16.0;
+/// Pixels per microsecond
+const MIN_ZOOM: f32 = 0.001;
+const MAX_ZOOM: f32 = 200.0;
+
+const CURSOR_COLORS: [Color32; 2] = [Color32::LIGHT_BLUE, Color32::ORANGE];
+
+/// Microseconds in the unit that fits them
+fn format_time(us: f64) -> String {
+    match us.abs() {
+        us if us >= 1_000_000.0 => format!("{:.3} s", us / 1_000_000.0),
+        us if us >= 1000.0 => format!("{:.3} ms", us / 1000.0),
+        _ => format!("{us:.0} us"),
+    }
+}
+
+fn format_frequency(period_us: f64) -> String {
+    match 1_000_000.0 / period_us {
+        hz if hz >= 1_000_000.0 => format!("{:.3} MHz", hz / 1_000_000.0),
+        hz if hz >= 1000.0 => format!("{:.3} kHz", hz / 1000.0),
+        hz => format!("{hz:.1} Hz"),
+    }
+}
+
+/// Timing of the last full cycle of a pin, between its last two rising edges
+struct Measurement {
+    edges: usize,
+    period_us: Option<u64>,
+    high_us: Option<u64>,
+}
+
+impl Measurement {
+    fn new(logic: &LogicTracker, pin: u8, start_us: u64, end_us: u64) -> Self {
+        let edges: Vec<(u64, bool)> = logic
+            .edges(pin)
+            .filter(|(time, _)| (start_us..=end_us).contains(time))
+            .collect();
+
+        let mut rising = edges.iter().rev().filter(|(_, rose)| *rose);
+        let last_rise = rising.next().map(|(time, _)| *time);
+        let period_us = last_rise
+            .zip(rising.next())
+            .map(|(last, (first, _))| last - first);
+
+        // the fall of the pulse which started the last full period
+        let high_us = period_us.zip(last_rise).and_then(|(period, last)| {
+            let rise = last - period;
+            edges
+                .iter()
+                .find(|(time, rose)| !rose && *time > rise && *time <= last)
+                .map(|(time, _)| time - rise)
+        });
+
+        Self {
+            edges: edges.len(),
+            period_us,
+            high_us,
+        }
+    }
+}
+
+/// Waveforms of the sampled GPIOs. Dragging pans, the wheel with Ctrl zooms, a click
+/// places the cursor A and a right click the cursor B
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LogicAnalyzer {
+    zoom: f32,
+    /// Keep the end of the capture in view
+    follow: bool,
+    /// Time at the right edge of the view when not following
+    view_end_us: f64,
+    cursors: [Option<u64>; 2],
+}
+
+impl Default for LogicAnalyzer {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            follow: true,
+            view_end_us: 0.0,
+            cursors: [None; 2],
+        }
+    }
+}
+
+impl LogicAnalyzer {
+    pub fn ui(&mut self, ui: &mut egui::Ui, logic: &mut LogicTracker) {
+        self.controls_ui(ui, logic);
+        ui.add_space(6.0);
+
+        let pins: Vec<u8> = (0..NUM_GPIO as u8)
+            .filter(|pin| logic.pins >> pin & 1 == 1)
+            .collect();
+
+        if pins.is_empty() {
+            ui.label("Select the pins to sample");
+            return;
+        }
+
+        let (start_us, end_us) = self.waveforms_ui(ui, logic, &pins);
+        ui.add_space(8.0);
+        self.measurements_ui(ui, logic, &pins, start_us, end_us);
+    }
+
+    fn controls_ui(&mut self, ui: &mut egui::Ui, logic: &mut LogicTracker) {
+        ui.horizontal(|ui| {
+            ui.menu_button("Pins", |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for pin in 0..NUM_GPIO as u8 {
+                            let mut selected = logic.pins >> pin & 1 == 1;
+
+                            if ui.checkbox(&mut selected, format!("GPIO{pin}")).changed() {
+                                logic.pins ^= 1 << pin;
+                                // the samples kept only hold the changes of the old selection
+                                logic.samples.clear();
+                            }
+                        }
+                    });
+            });
+
+            ui.label("Zoom");
+            ui.add(
+                egui::Slider::new(&mut self.zoom, MIN_ZOOM..=MAX_ZOOM)
+                    .logarithmic(true)
+                    .suffix(" px/us"),
+            );
+
+            if ui.toggle_value(&mut self.follow, "Follow").changed() && !self.follow {
+                self.view_end_us = logic.now_us as f64;
+            }
+
+            if ui.button("Clear").clicked() {
+                logic.samples.clear();
+                self.cursors = [None; 2];
+            }
+        });
+    }
+
+    /// Draw the rows of the pins, returns the time span in view
+    fn waveforms_ui(&mut self, ui: &mut egui::Ui, logic: &LogicTracker, pins: &[u8]) -> (u64, u64) {
+        let height = RULER_HEIGHT + pins.len() as f32 * ROW_HEIGHT;
+        let (response, painter) = ui.allocate_painter(
+            Vec2::new(ui.available_width(), height),
+            Sense::click_and_drag(),
+        );
+        let rect = response.rect;
+        let plot_left = rect.left() + LABEL_WIDTH;
+        let width = (rect.right() - plot_left).max(1.0);
+
+        if response.hovered() {
+            let (scroll, zoom) =
+                ui.input(|input| (input.smooth_scroll_delta.y, input.modifiers.command));
+            if zoom && scroll != 0.0 {
+                self.zoom = (self.zoom * (scroll * 0.01).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+            }
+        }
+
+        if response.dragged() {
+            if self.follow {
+                self.follow = false;
+                self.view_end_us = logic.now_us as f64;
+            }
+
+            self.view_end_us -= (response.drag_delta().x / self.zoom) as f64;
+        }
+
+        let end_us = match self.follow {
+            true => logic.now_us as f64,
+            false => self.view_end_us,
+        };
+        let start_us = end_us - (width / self.zoom) as f64;
+        let x_of = |time_us: f64| plot_left + ((time_us - start_us) as f32 * self.zoom);
+        let time_at = |x: f32| (start_us + ((x - plot_left) / self.zoom) as f64).max(0.0) as u64;
+
+        if let Some(position) = response
+            .interact_pointer_pos()
+            .filter(|pos| pos.x >= plot_left)
+        {
+            if response.clicked() {
+                self.cursors[0] = Some(time_at(position.x));
+            } else if response.secondary_clicked() {
+                self.cursors[1] = Some(time_at(position.x));
+            }
+        }
+
+        let text_color = ui.visuals().text_color();
+        let weak_color = ui.visuals().weak_text_color();
+        let font = FontId::monospace(11.0);
+
+        painter.text(
+            Pos2::new(plot_left + 2.0, rect.top()),
+            Align2::LEFT_TOP,
+            format_time(start_us.max(0.0)),
+            font.clone(),
+            weak_color,
+        );
+        painter.text(
+            Pos2::new(rect.right() - 2.0, rect.top()),
+            Align2::RIGHT_TOP,
+            format_time(end_us),
+            font.clone(),
+            weak_color,
+        );
+
+        let view_start = start_us.max(0.0) as u64;
+        let view_end = end_us.max(0.0) as u64;
+
+        for (row, &pin) in pins.iter().enumerate() {
+            let top = rect.top() + RULER_HEIGHT + row as f32 * ROW_HEIGHT;
+            let (high, low) = (top + 4.0, top + ROW_HEIGHT - 4.0);
+            let y_of = |level: bool| if level { high } else { low };
+
+            painter.text(
+                Pos2::new(rect.left(), top + ROW_HEIGHT / 2.0),
+                Align2::LEFT_CENTER,
+                format!("GPIO{pin}"),
+                font.clone(),
+                text_color,
+            );
+
+            // nothing to draw before the first sample
+            let first_us = logic
+                .samples
+                .front()
+                .map_or(u64::MAX, |sample| sample.time_us);
+            let from = view_start.max(first_us);
+            let Some(mut level) = logic.level_at(pin, from).filter(|_| from <= view_end) else {
+                continue;
+            };
+
+            let mut points = vec![Pos2::new(x_of(from as f64).max(plot_left), y_of(level))];
+
+            for (time_us, rose) in logic
+                .edges(pin)
+                .filter(|(time, _)| *time > from && *time <= view_end)
+            {
+                let x = x_of(time_us as f64);
+                points.push(Pos2::new(x, y_of(level)));
+                points.push(Pos2::new(x, y_of(rose)));
+                level = rose;
+            }
+
+            let end = x_of(logic.now_us.min(view_end) as f64).min(rect.right());
+            points.push(Pos2::new(end, y_of(level)));
+            painter.add(Shape::line(points, Stroke::new(1.5, Color32::LIGHT_GREEN)));
+        }
+
+        for (name, (cursor, color)) in ["A", "B"]
+            .iter()
+            .zip(self.cursors.iter().zip(CURSOR_COLORS))
+        {
+            let Some(x) = cursor
+                .map(|time| x_of(time as f64))
+                .filter(|x| (plot_left..=rect.right()).contains(x))
+            else {
+                continue;
+            };
+
+            painter.line_segment(
+                [
+                    Pos2::new(x, rect.top() + RULER_HEIGHT),
+                    Pos2::new(x, rect.bottom()),
+                ],
+                Stroke::new(1.0, color),
+            );
+            painter.text(
+                Pos2::new(x + 2.0, rect.top() + RULER_HEIGHT),
+                Align2::LEFT_BOTTOM,
+                *name,
+                font.clone(),
+                color,
+            );
+        }
+
+        (view_start, view_end)
+    }
+
+    fn measurements_ui(
+        &self,
+        ui: &mut egui::Ui,
+        logic: &LogicTracker,
+        pins: &[u8],
+        start_us: u64,
+        end_us: u64,
+    ) {
+        if let [Some(a), Some(b)] = self.cursors {
+            let delta = a.abs_diff(b) as f64;
+            let frequency = match delta > 0.0 {
+                true => format_frequency(delta),
+                false => "-".to_string(),
+            };
+
+            ui.label(format!(
+                "A: {}   B: {}   B - A: {}   1 / (B - A): {}",
+                format_time(a as f64),
+                format_time(b as f64),
+                format_time(delta),
+                frequency
+            ));
+        } else {
+            ui.label(
+                RichText::new("Click to place the cursor A, right click for the cursor B. Drag to pan, Ctrl and the wheel to zoom")
+                    .small()
+                    .weak(),
+            );
+        }
+
+        egui::Grid::new("Logic analyzer measurements")
+            .num_columns(5)
+            .spacing([24.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Pin");
+                ui.strong("Edges in view");
+                ui.strong("Period");
+                ui.strong("Frequency");
+                ui.strong("Duty cycle");
+                ui.end_row();
+
+                for &pin in pins {
+                    let measurement = Measurement::new(logic, pin, start_us, end_us);
+
+                    ui.label(format!("GPIO{pin}"));
+                    ui.label(measurement.edges.to_string());
+
+                    match measurement.period_us.filter(|period| *period > 0) {
+                        Some(period) => {
+                            ui.label(format_time(period as f64));
+                            ui.label(format_frequency(period as f64));
+                        }
+                        None => {
+                            ui.label("-");
+                            ui.label("-");
+                        }
+                    }
+
+                    match measurement.period_us.zip(measurement.high_us) {
+                        Some((period, high)) if period > 0 => {
+                            ui.label(format!("{:.1} %", high as f64 * 100.0 / period as f64))
+                        }
+                        _ => ui.label("-"),
+                    };
+                    ui.end_row();
+                }
+            });
+    }
+}
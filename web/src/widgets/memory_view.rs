@@ -4,16 +4,27 @@
  * @brief: Memory view widget for the RP2350 emulator
  * TODO Search functionality
  */
+use egui::collapsing_header::CollapsingState;
 use egui_extras::{Column, TableBuilder};
 use rp2350::memory::GenericMemory;
 
+use super::clipboard::{self, CopyFormat};
 use super::DisplayMode;
 
 #[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct MemoryView<const OFFSET: usize> {
     bytes_per_row: usize,
     address_buffer: String,
     display_mode: DisplayMode,
+    copy_format: CopyFormat,
+    copy_address: String,
+    copy_length: String,
+    #[serde(skip)]
+    paste_buffer: String,
+    /// Address picked with "Who wrote this?" in the context menu of a row
+    #[serde(skip)]
+    writer_query: Option<u32>,
 }
 
 impl<const OFFSET: usize> Default for MemoryView<OFFSET> {
@@ -22,6 +33,11 @@ impl<const OFFSET: usize> Default for MemoryView<OFFSET> {
             bytes_per_row: 16,
             address_buffer: String::new(),
             display_mode: DisplayMode::default(),
+            copy_format: CopyFormat::default(),
+            copy_address: format!("{:08X}", OFFSET),
+            copy_length: String::from("64"),
+            paste_buffer: String::new(),
+            writer_query: None,
         }
     }
 }
@@ -36,6 +52,20 @@ impl<const OFFSET: usize> MemoryView<OFFSET> {
             .striped(false)
             .show(ui, |ui| self.show_info_grid::<N>(ui, &mut address));
 
+        ui.add_space(6.0);
+
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            ui.make_persistent_id("memory_view_clipboard"),
+            false,
+        )
+        .show_header(ui, |ui| {
+            ui.label("Clipboard");
+        })
+        .body(|ui| {
+            self.show_copy_ui(ui, mem);
+        });
+
         ui.add_space(12.0);
 
         egui::ScrollArea::horizontal().show(ui, |ui| {
@@ -43,6 +73,104 @@ impl<const OFFSET: usize> MemoryView<OFFSET> {
         });
     }
 
+    /// Same as `ui`, but the content can also be edited by pasting data into it
+    pub fn ui_mut<const N: usize>(&mut self, ui: &mut egui::Ui, mem: &mut GenericMemory<N>) {
+        CollapsingState::load_with_default_open(
+            ui.ctx(),
+            ui.make_persistent_id("memory_view_paste"),
+            false,
+        )
+        .show_header(ui, |ui| {
+            ui.label("Paste");
+        })
+        .body(|ui| {
+            self.show_paste_ui(ui, mem);
+        });
+
+        self.ui(ui, mem);
+    }
+
+    /// Take the address the user asked the last writer of
+    pub fn take_writer_query(&mut self) -> Option<u32> {
+        self.writer_query.take()
+    }
+
+    /// Address relative to the start of the memory
+    fn parse_copy_address(&self) -> Option<usize> {
+        let address = self.copy_address.trim().trim_start_matches("0x");
+        let address = usize::from_str_radix(address, 16).ok()?;
+        address.checked_sub(OFFSET)
+    }
+
+    fn show_copy_ui(&mut self, ui: &mut egui::Ui, mem: &[u8]) {
+        egui::Grid::new("MemoryViewCopy")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label("Start address:");
+                ui.text_edit_singleline(&mut self.copy_address);
+                ui.end_row();
+
+                ui.label("Length (bytes):");
+                ui.text_edit_singleline(&mut self.copy_length);
+                ui.end_row();
+
+                ui.label("Format:");
+                ui.horizontal(|ui| {
+                    for format in CopyFormat::ALL {
+                        ui.radio_value(&mut self.copy_format, format, format.label());
+                    }
+                });
+                ui.end_row();
+
+                ui.label("");
+                if ui.button("Copy").clicked() {
+                    let start = self.parse_copy_address();
+                    let length = self.copy_length.trim().parse::<usize>().ok();
+
+                    match (start, length) {
+                        (Some(start), Some(length)) if start < mem.len() => {
+                            let end = (start + length).min(mem.len());
+                            let text = self
+                                .copy_format
+                                .format(&mem[start..end], (OFFSET + start) as u32);
+                            clipboard::copy(ui.ctx(), text);
+                        }
+                        _ => crate::notify::error("Invalid address range"),
+                    }
+                }
+                ui.end_row();
+            });
+    }
+
+    fn show_paste_ui<const N: usize>(&mut self, ui: &mut egui::Ui, mem: &mut GenericMemory<N>) {
+        ui.label("Bytes are written starting from the address of the Clipboard section");
+        ui.add(
+            egui::TextEdit::multiline(&mut self.paste_buffer)
+                .code_editor()
+                .desired_rows(4)
+                .hint_text("Hex dump, C array, Rust array or hex bytes"),
+        );
+
+        if ui.button("Write").clicked() {
+            let Some(bytes) = clipboard::parse_bytes(&self.paste_buffer) else {
+                crate::notify::error("Cannot parse the pasted data");
+                return;
+            };
+
+            let Some(start) = self.parse_copy_address() else {
+                crate::notify::error("Invalid start address");
+                return;
+            };
+
+            match mem.write_slice(start as u32, &bytes) {
+                Ok(_) => crate::notify::success(format!("Wrote {} bytes", bytes.len())),
+                Err(_) => crate::notify::error("The data does not fit into the memory"),
+            }
+        }
+    }
+
     fn show_info_grid<const N: usize>(&mut self, ui: &mut egui::Ui, address: &mut Option<u32>) {
         ui.label("Size:");
         ui.label(format_memory_length(N));
@@ -143,7 +271,17 @@ impl<const OFFSET: usize> MemoryView<OFFSET> {
                             }
                         }
 
-                        ui.monospace(string);
+                        ui.monospace(string).context_menu(|ui| {
+                            let row_start = OFFSET + row_index * self.bytes_per_row;
+
+                            for word in (0..self.bytes_per_row).step_by(4) {
+                                let address = (row_start + word) as u32;
+                                if ui.button(format!("Who wrote {:#010x}?", address)).clicked() {
+                                    self.writer_query = Some(address);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
                     });
 
                     // ASCII
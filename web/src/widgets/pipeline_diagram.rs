@@ -0,0 +1,192 @@
+/**
+ * @file widgets/pipeline_diagram.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Diagram of the instructions going through the stages of a Hazard3, cycle by cycle
+ */
+use egui::{Align2, Color32, FontId, Rect, Sense, Stroke, StrokeKind, pos2, vec2};
+use rp2350::processor::hazard3::pipeline::{PipelineState, StageInstruction};
+use std::collections::BTreeMap;
+
+const CELL_WIDTH: f32 = 24.0;
+const ROW_HEIGHT: f32 = 18.0;
+const LABEL_WIDTH: f32 = 180.0;
+
+const FETCH_COLOR: Color32 = Color32::from_rgb(100, 150, 210);
+const EXECUTE_COLOR: Color32 = Color32::from_rgb(90, 180, 100);
+const MEMORY_COLOR: Color32 = Color32::from_rgb(220, 160, 70);
+const STALL_COLOR: Color32 = Color32::from_rgb(210, 90, 90);
+
+fn stage_text(instruction: Option<StageInstruction>) -> String {
+    match instruction {
+        Some(instruction) => format!("{} at {:#010x}", instruction.name, instruction.address),
+        None => String::from("-"),
+    }
+}
+
+fn describe(cycle: &PipelineState) -> String {
+    let mut text = format!(
+        "Cycle {}\nF: {}\nX: {}\nM: {}",
+        cycle.cycle,
+        cycle
+            .fetch
+            .map(|address| format!("{address:#010x}"))
+            .unwrap_or_else(|| String::from("-")),
+        stage_text(cycle.execute),
+        stage_text(cycle.memory),
+    );
+
+    if let Some(reason) = cycle.stall {
+        text += &format!("\nStalled: {}", reason.name());
+    }
+
+    if let Some((rd, value)) = cycle.bypass {
+        text += &format!("\nBypass: x{rd} = {value:#010x}");
+    }
+
+    text
+}
+
+/// A row per instruction and a column per cycle, the oldest on the left. F, X and M are the
+/// stage the instruction is in, red when it is stalled there, and an outlined X read one of
+/// its operands from the bypass. The last row gives why no instruction entered X
+pub fn pipeline_diagram(ui: &mut egui::Ui, cycles: &[PipelineState]) {
+    let instructions = cycles
+        .iter()
+        .flat_map(|cycle| [cycle.execute, cycle.memory])
+        .flatten()
+        .map(|instruction| (instruction.index, instruction))
+        .collect::<BTreeMap<_, _>>();
+    let rows = instructions.into_values().collect::<Vec<_>>();
+
+    let size = vec2(
+        LABEL_WIDTH + cycles.len() as f32 * CELL_WIDTH,
+        (rows.len() + 2) as f32 * ROW_HEIGHT,
+    );
+    let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+    let painter = ui.painter_at(rect);
+    let font = FontId::monospace(11.0);
+    let text_color = ui.visuals().text_color();
+
+    // row 0 is the cycle numbers, the stalls are below the instructions
+    let cell = |column: usize, row: usize| {
+        Rect::from_min_size(
+            pos2(
+                rect.left() + LABEL_WIDTH + column as f32 * CELL_WIDTH,
+                rect.top() + row as f32 * ROW_HEIGHT,
+            ),
+            vec2(CELL_WIDTH, ROW_HEIGHT),
+        )
+    };
+    let row_of = |instruction: Option<StageInstruction>| {
+        instruction
+            .and_then(|instruction| rows.iter().position(|row| row.index == instruction.index))
+    };
+
+    for (row, instruction) in rows.iter().enumerate() {
+        painter.text(
+            pos2(rect.left(), cell(0, row + 1).center().y),
+            Align2::LEFT_CENTER,
+            format!("{:08x} {}", instruction.address, instruction.name),
+            font.clone(),
+            text_color,
+        );
+    }
+
+    painter.text(
+        pos2(rect.left(), cell(0, rows.len() + 1).center().y),
+        Align2::LEFT_CENTER,
+        "stall",
+        font.clone(),
+        text_color,
+    );
+
+    let mut hovered = None;
+
+    for (column, cycle) in cycles.iter().enumerate() {
+        if cycle.cycle % 5 == 0 {
+            painter.text(
+                cell(column, 0).center(),
+                Align2::CENTER_CENTER,
+                (cycle.cycle % 1000).to_string(),
+                FontId::monospace(9.0),
+                ui.visuals().weak_text_color(),
+            );
+        }
+
+        let mut stages = Vec::with_capacity(3);
+
+        // fetched in the cycle before it entered X
+        let fetched = cycles
+            .get(column + 1)
+            .and_then(|next| next.execute)
+            .filter(|next| cycle.fetch == Some(next.address))
+            .filter(|next| {
+                cycle
+                    .execute
+                    .is_none_or(|current| current.index != next.index)
+            });
+
+        stages.push((row_of(fetched), "F", FETCH_COLOR));
+
+        let execute_color = match cycle.stall {
+            Some(_) => STALL_COLOR,
+            None => EXECUTE_COLOR,
+        };
+        stages.push((row_of(cycle.execute), "X", execute_color));
+
+        let memory_color = match cycle.stall {
+            Some(reason) if reason.is_bus_wait() => STALL_COLOR,
+            _ => MEMORY_COLOR,
+        };
+        stages.push((row_of(cycle.memory), "M", memory_color));
+
+        for (row, label, color) in stages {
+            let Some(row) = row else {
+                continue;
+            };
+
+            let stage = cell(column, row + 1).shrink(1.0);
+            painter.rect_filled(stage, 2.0, color);
+            painter.text(
+                stage.center(),
+                Align2::CENTER_CENTER,
+                label,
+                font.clone(),
+                Color32::BLACK,
+            );
+
+            if label == "X"
+                && cycle
+                    .execute
+                    .is_some_and(|instruction| instruction.bypassed)
+            {
+                painter.rect_stroke(stage, 2.0, Stroke::new(2.0, text_color), StrokeKind::Inside);
+            }
+        }
+
+        if let Some(reason) = cycle.stall {
+            let stall = cell(column, rows.len() + 1).shrink(1.0);
+            painter.rect_filled(stall, 2.0, STALL_COLOR.gamma_multiply(0.4));
+            painter.text(
+                stall.center(),
+                Align2::CENTER_CENTER,
+                &reason.name()[..1],
+                font.clone(),
+                text_color,
+            );
+        }
+
+        let column_rect = cell(column, 0).with_max_y(rect.bottom());
+        if response
+            .hover_pos()
+            .is_some_and(|pos| column_rect.contains(pos))
+        {
+            hovered = Some(cycle);
+        }
+    }
+
+    if let Some(cycle) = hovered {
+        response.on_hover_text(describe(cycle));
+    }
+}
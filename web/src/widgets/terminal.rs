@@ -0,0 +1,206 @@
+/**
+ * @file widgets/terminal.rs
+ * @author Nguyen Le Duy
+ * @date 15/10/2026
+ * @brief Serial console showing what a UART transmits and typing into it
+ */
+use crate::tracker::UartTracker;
+use egui::{ComboBox, Key, RichText, ScrollArea, TextEdit};
+
+/// Text kept on the screen, the oldest is dropped past it
+const MAX_TRANSCRIPT: usize = 64 * 1024;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    None,
+    Cr,
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    const ALL: [Self; 4] = [Self::None, Self::Cr, Self::Lf, Self::CrLf];
+
+    fn bytes(self) -> &'static [u8] {
+        match self {
+            Self::None => b"",
+            Self::Cr => b"\r",
+            Self::Lf => b"\n",
+            Self::CrLf => b"\r\n",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::None => "No line ending",
+            Self::Cr => "CR",
+            Self::Lf => "LF",
+            Self::CrLf => "CR LF",
+        }
+    }
+}
+
+/// Console of a UART. The output comes from the tracker, the lines typed are returned to
+/// the caller which queues them on the line
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Terminal {
+    local_echo: bool,
+    line_ending: LineEnding,
+    /// A lone CR starts a new line, as most firmware ends its lines with CR LF
+    cr_as_newline: bool,
+    #[serde(skip)]
+    input: String,
+    #[serde(skip)]
+    transcript: String,
+    /// Bytes of the tracker already on the screen
+    #[serde(skip)]
+    seen: u64,
+    #[serde(skip)]
+    last_cr: bool,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self {
+            local_echo: false,
+            line_ending: LineEnding::default(),
+            cr_as_newline: true,
+            input: String::new(),
+            transcript: String::new(),
+            seen: 0,
+            last_cr: false,
+        }
+    }
+}
+
+impl Terminal {
+    fn push_byte(&mut self, byte: u8) {
+        let last_cr = std::mem::replace(&mut self.last_cr, byte == b'\r');
+
+        match byte {
+            b'\r' if self.cr_as_newline => self.transcript.push('\n'),
+            b'\r' => {}
+            // the new line already started on the CR
+            b'\n' if last_cr && self.cr_as_newline => {}
+            // backspace
+            0x08 => {
+                self.transcript.pop();
+            }
+            byte => self.transcript.push(char::from(byte)),
+        }
+    }
+
+    fn trim(&mut self) {
+        if self.transcript.len() <= MAX_TRANSCRIPT {
+            return;
+        }
+
+        let mut start = self.transcript.len() - MAX_TRANSCRIPT;
+        while !self.transcript.is_char_boundary(start) {
+            start += 1;
+        }
+
+        self.transcript.drain(..start);
+    }
+
+    /// Take the bytes transmitted since the last frame
+    fn update(&mut self, uart: &UartTracker) {
+        if uart.tx_count < self.seen {
+            // the tracker started over
+            self.seen = 0;
+        }
+
+        let new = (uart.tx_count - self.seen) as usize;
+        let lost = new.saturating_sub(uart.tx.len());
+
+        if lost > 0 {
+            self.transcript
+                .push_str(&format!("\n[{lost} bytes lost]\n"));
+        }
+
+        for &byte in uart.tx.iter().skip(uart.tx.len() - (new - lost)) {
+            self.push_byte(byte);
+        }
+
+        self.seen = uart.tx_count;
+        self.trim();
+    }
+
+    /// Show the console, returns the bytes typed to send on the RX line
+    pub fn ui(&mut self, ui: &mut egui::Ui, uart: &UartTracker) -> Option<Vec<u8>> {
+        self.update(uart);
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.local_echo, "Local echo");
+            ui.checkbox(&mut self.cr_as_newline, "CR starts a new line");
+
+            ComboBox::from_id_salt(ui.id().with("line ending"))
+                .selected_text(self.line_ending.name())
+                .show_ui(ui, |ui| {
+                    for ending in LineEnding::ALL {
+                        ui.selectable_value(&mut self.line_ending, ending, ending.name());
+                    }
+                });
+
+            if ui.button("Clear").clicked() {
+                self.transcript.clear();
+            }
+        });
+
+        egui::Frame::default()
+            .fill(egui::Color32::BLACK)
+            .inner_margin(6.0)
+            .show(ui, |ui| {
+                ScrollArea::vertical()
+                    .id_salt("terminal output")
+                    .max_width(f32::INFINITY)
+                    .max_height(300.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        ui.set_min_width(ui.available_width());
+                        ui.label(
+                            RichText::new(&self.transcript)
+                                .monospace()
+                                .color(egui::Color32::LIGHT_GRAY),
+                        );
+                    });
+            });
+
+        let mut send = false;
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut self.input)
+                    .hint_text("Type and press Enter")
+                    .desired_width(ui.available_width() - 60.0),
+            );
+
+            if response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+                send = true;
+                response.request_focus();
+            }
+
+            send |= ui.button("Send").clicked();
+        });
+
+        if !send {
+            return None;
+        }
+
+        let text = std::mem::take(&mut self.input);
+        let ending = self.line_ending.bytes();
+
+        if self.local_echo {
+            self.transcript.push_str(&text);
+            self.last_cr = false;
+            ending.iter().for_each(|byte| self.push_byte(*byte));
+            self.trim();
+        }
+
+        let mut bytes = text.into_bytes();
+        bytes.extend_from_slice(ending);
+        Some(bytes)
+    }
+}